@@ -1,4 +1,7 @@
+mod build_support;
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    build_support::emit_build_info();
     tonic_build::configure()
         .build_server(true)
         .build_client(true)
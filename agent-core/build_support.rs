@@ -0,0 +1,64 @@
+//! Build-script support shared by every service crate
+//!
+//! Each crate's build.rs includes this file with `#[path]`, as each compiles
+//! the protos from agent-core/proto.
+
+/// Build facts reported by GetBuildInfo (see agent-core/src/build_info.rs),
+/// and the proto package GetApiVersion takes its major version from
+pub fn emit_build_info() {
+    let commit = std::env::var("AIOS_GIT_COMMIT")
+        .ok()
+        .filter(|c| !c.is_empty())
+        .or_else(|| {
+            std::process::Command::new("git")
+                .args(["rev-parse", "--short=12", "HEAD"])
+                .output()
+                .ok()
+                .filter(|out| out.status.success())
+                .map(|out| String::from_utf8_lossy(&out.stdout).trim().to_string())
+        })
+        .unwrap_or_else(|| "unknown".to_string());
+    // SOURCE_DATE_EPOCH keeps reproducible builds reproducible
+    let timestamp = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse::<i64>().ok())
+        .unwrap_or_else(|| {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs() as i64)
+        });
+    let mut features: Vec<String> = std::env::vars()
+        .filter_map(|(key, _)| key.strip_prefix("CARGO_FEATURE_").map(str::to_lowercase))
+        .collect();
+    features.sort();
+
+    println!("cargo:rustc-env=AIOS_GIT_COMMIT={commit}");
+    println!("cargo:rustc-env=AIOS_BUILD_TIMESTAMP={timestamp}");
+    println!("cargo:rustc-env=AIOS_CARGO_FEATURES={}", features.join(","));
+    println!(
+        "cargo:rustc-env=AIOS_BUILD_PROFILE={}",
+        std::env::var("PROFILE").unwrap_or_default()
+    );
+    // The API major version is the one common.proto's package carries
+    let common = "../agent-core/proto/common.proto";
+    let package = std::fs::read_to_string(common)
+        .ok()
+        .and_then(|proto| {
+            proto.lines().find_map(|line| {
+                line.trim()
+                    .strip_prefix("package ")
+                    .map(|p| p.trim_end_matches(';').trim().to_string())
+            })
+        })
+        .unwrap_or_default();
+    println!("cargo:rustc-env=AIOS_API_PACKAGE={package}");
+    println!("cargo:rerun-if-changed={common}");
+    println!("cargo:rerun-if-env-changed=AIOS_GIT_COMMIT");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    // Missing paths would rerun the script on every build
+    for path in ["../.git/HEAD", "../.git/refs/heads", "../.git/packed-refs"] {
+        if std::path::Path::new(path).exists() {
+            println!("cargo:rerun-if-changed={path}");
+        }
+    }
+}
@@ -7,6 +7,7 @@ Capabilities:
   - File integrity checking
   - Audit log monitoring for suspicious activity
   - Threat analysis
+  - Canary tokens (decoy files and fake credentials) as intrusion tripwires
 """
 
 from __future__ import annotations
@@ -23,8 +24,18 @@ logger = logging.getLogger("aios.agent.security")
 
 IDS_CHECK_INTERVAL_S = 45.0
 AUDIT_CHECK_INTERVAL_S = 120.0
+CANARY_CHECK_INTERVAL_S = 5.0
 SEVERITY_WEIGHTS = {"critical": 10, "high": 7, "medium": 4, "low": 1, "info": 0}
 
+# Decoys planted by deploy_canaries when none are given. Paths that already
+# exist are skipped by sec.canary_plant, so real files are never replaced.
+DEFAULT_CANARIES = [
+    {"kind": "aws_credentials", "path": "/root/.aws/credentials"},
+    {"kind": "ssh_key", "path": "/root/.ssh/id_rsa_backup"},
+    {"kind": "env_file", "path": "/etc/aios/secrets/.env.production"},
+    {"kind": "password_file", "path": "/var/backups/db-passwords.txt"},
+]
+
 
 class SecurityAgent(BaseAgent):
     """Agent responsible for system security: IDS, scanning, and monitoring."""
@@ -39,6 +50,10 @@ class SecurityAgent(BaseAgent):
             "sec.file_integrity",
             "sec.scan_rootkits",
             "sec.audit",
+            "sec.canary_plant",
+            "sec.canary_list",
+            "sec.canary_remove",
+            "sec.canary_check",
             "monitor.logs",
             "net.port_scan",
             "process.list",
@@ -52,6 +67,8 @@ class SecurityAgent(BaseAgent):
         description = task.get("description", "").lower()
         input_data = task.get("input_json", {}) if isinstance(task.get("input_json"), dict) else {}
 
+        if "canary" in description or "honeypot" in description or "tripwire" in description:
+            return await self._deploy_canaries(input_data)
         if "vulnerabilit" in description or "scan" in description or "cve" in description:
             return await self._scan_vulnerabilities(input_data)
         if "integrity" in description or "checksum" in description or "hash" in description:
@@ -68,7 +85,8 @@ class SecurityAgent(BaseAgent):
         decision = await self.think(
             f"Security task: '{task.get('description', '')}'. "
             f"Options: scan_vulnerabilities, check_integrity, "
-            f"audit_logs, intrusion_check, threat_analysis, check_permissions. "
+            f"audit_logs, intrusion_check, threat_analysis, check_permissions, "
+            f"deploy_canaries. "
             f"Which action? Reply with ONLY the action name.",
             level=IntelligenceLevel.OPERATIONAL,
         )
@@ -84,6 +102,8 @@ class SecurityAgent(BaseAgent):
             return await self._threat_analysis(input_data)
         if "perm" in action:
             return await self._check_permissions(input_data)
+        if "canar" in action:
+            return await self._deploy_canaries(input_data)
         return await self._intrusion_check(input_data)
 
     # ------------------------------------------------------------------
@@ -541,6 +561,71 @@ class SecurityAgent(BaseAgent):
             },
         }
 
+    # ------------------------------------------------------------------
+    # Canary tokens
+    # ------------------------------------------------------------------
+
+    async def _deploy_canaries(self, params: dict[str, Any]) -> dict[str, Any]:
+        """Plant canary files, skipping paths that are taken or already canaries."""
+        wanted = params.get("canaries") or DEFAULT_CANARIES
+        existing = await self.call_tool("sec.canary_list", {}, reason="List planted canaries")
+        planted_paths = {
+            c.get("path") for c in existing.get("output", {}).get("canaries", [])
+        }
+
+        planted: list[dict[str, Any]] = []
+        skipped: list[dict[str, Any]] = []
+        for spec in wanted:
+            path = spec.get("path", "")
+            if path in planted_paths:
+                skipped.append({"path": path, "reason": "already a canary"})
+                continue
+            result = await self.call_tool(
+                "sec.canary_plant",
+                {
+                    "path": path,
+                    "kind": spec.get("kind", "file"),
+                    "description": spec.get("description", f"planted by {self.agent_id}"),
+                },
+                reason=f"Plant canary at {path}",
+            )
+            if result.get("success"):
+                planted.append(result.get("output", {}).get("canary", {"path": path}))
+            else:
+                skipped.append({"path": path, "reason": result.get("error", "")})
+
+        await self.store_memory("canaries_deployed", {
+            "timestamp": int(time.time()),
+            "planted": [c.get("path") for c in planted],
+        })
+        return {
+            "success": True,
+            "planted": planted,
+            "skipped": skipped,
+            "total_canaries": len(planted_paths) + len(planted),
+        }
+
+    async def _check_canaries(self) -> list[dict[str, Any]]:
+        """Collect new canary trips and raise an incident for each one."""
+        result = await self.call_tool(
+            "sec.canary_check",
+            {"acknowledge": True},
+            reason="Canary tripwire sweep",
+        )
+        trips = result.get("output", {}).get("trips", [])
+        for trip in trips:
+            logger.critical(
+                "CANARY TRIPPED: %s (%s) %s",
+                trip.get("path"), trip.get("kind"), trip.get("event"),
+            )
+            await self.push_event("security.canary_tripped", trip, critical=True)
+            await self.store_incident(
+                f"Canary {trip.get('event')}: {trip.get('path')}",
+                trip,
+                root_cause="Possible intrusion: a decoy no legitimate process uses was touched",
+            )
+        return trips
+
     # ------------------------------------------------------------------
     # Background loops
     # ------------------------------------------------------------------
@@ -577,6 +662,21 @@ class SecurityAgent(BaseAgent):
             except asyncio.TimeoutError:
                 pass
 
+    async def _canary_loop(self) -> None:
+        """Fast tripwire loop: report canary trips within seconds."""
+        while not self._shutdown_event.is_set():
+            try:
+                await self._check_canaries()
+            except Exception as exc:
+                logger.error("Canary loop error: %s", exc)
+            try:
+                await asyncio.wait_for(
+                    self._shutdown_event.wait(),
+                    timeout=CANARY_CHECK_INTERVAL_S,
+                )
+            except asyncio.TimeoutError:
+                pass
+
     async def run(self) -> None:
         self._running = True
         try:
@@ -586,6 +686,7 @@ class SecurityAgent(BaseAgent):
                 self.task_poll_loop(),
                 self._ids_loop(),
                 self._audit_loop(),
+                self._canary_loop(),
                 self._shutdown_event.wait(),
             )
         finally:
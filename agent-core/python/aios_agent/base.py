@@ -36,6 +36,48 @@ from aios_agent.proto import memory_pb2_grpc
 
 logger = logging.getLogger("aios.agent")
 
+# Header naming the identity a call acts as
+IDENTITY_HEADER = "x-aios-identity"
+
+# Header carrying the identity's token
+TOKEN_HEADER = "x-aios-identity-token"
+
+# Where the orchestrator writes agents' tokens
+DEFAULT_AGENT_TOKEN_DIR = "/var/lib/aios/identity/agents"
+
+
+class _ActAsAgent(grpc.aio.UnaryUnaryClientInterceptor, grpc.aio.UnaryStreamClientInterceptor):
+    """Names the agent's identity (``agent:<agent_id>``) on every call, with
+    the token the orchestrator issued it once it has one."""
+
+    def __init__(self, agent_id: str) -> None:
+        self._identity = f"agent:{agent_id}"
+        token_dir = os.environ.get("AIOS_AGENT_TOKEN_DIR", DEFAULT_AGENT_TOKEN_DIR)
+        self._token_path = os.path.join(token_dir, f"{agent_id}.token")
+
+    def _token(self) -> str | None:
+        # Re-read on every call: the token is replaced when the agent
+        # registers again
+        try:
+            with open(self._token_path, encoding="utf-8") as f:
+                return f.read().strip() or None
+        except OSError:
+            return None
+
+    def _with_identity(self, details: grpc.aio.ClientCallDetails) -> grpc.aio.ClientCallDetails:
+        metadata = grpc.aio.Metadata(*(details.metadata or ()))
+        metadata[IDENTITY_HEADER] = self._identity
+        token = self._token()
+        if token:
+            metadata[TOKEN_HEADER] = token
+        return details._replace(metadata=metadata)
+
+    async def intercept_unary_unary(self, continuation: Any, client_call_details: Any, request: Any) -> Any:
+        return await continuation(self._with_identity(client_call_details), request)
+
+    async def intercept_unary_stream(self, continuation: Any, client_call_details: Any, request: Any) -> Any:
+        return await continuation(self._with_identity(client_call_details), request)
+
 
 class IntelligenceLevel(str, Enum):
     """Intelligence levels for the think() dispatcher.
@@ -127,6 +169,9 @@ class BaseAgent(ABC):
             intelligence_level, required_tools, depends_on,
             input_json (already deserialised), ...
         The returned dict is serialised into ``TaskResult.output_json``.
+        It may include a ``spawn_goals`` list (strings or
+        ``{"description": ..., "priority": ...}`` dicts) to have the
+        orchestrator create follow-up subgoals linked to this task's goal.
         """
         ...
 
@@ -144,24 +189,27 @@ class BaseAgent(ABC):
     # gRPC channel helpers
     # ------------------------------------------------------------------
 
+    def _channel(self, addr: str) -> grpc.aio.Channel:
+        return grpc.aio.insecure_channel(addr, interceptors=[_ActAsAgent(self.agent_id)])
+
     def _get_orchestrator_channel(self) -> grpc.aio.Channel:
         if self._orchestrator_channel is None:
-            self._orchestrator_channel = grpc.aio.insecure_channel(self.config.orchestrator_addr)
+            self._orchestrator_channel = self._channel(self.config.orchestrator_addr)
         return self._orchestrator_channel
 
     def _get_tools_channel(self) -> grpc.aio.Channel:
         if self._tools_channel is None:
-            self._tools_channel = grpc.aio.insecure_channel(self.config.tools_addr)
+            self._tools_channel = self._channel(self.config.tools_addr)
         return self._tools_channel
 
     def _get_memory_channel(self) -> grpc.aio.Channel:
         if self._memory_channel is None:
-            self._memory_channel = grpc.aio.insecure_channel(self.config.memory_addr)
+            self._memory_channel = self._channel(self.config.memory_addr)
         return self._memory_channel
 
     def _get_runtime_channel(self) -> grpc.aio.Channel:
         if self._runtime_channel is None:
-            self._runtime_channel = grpc.aio.insecure_channel(self.config.runtime_addr)
+            self._runtime_channel = self._channel(self.config.runtime_addr)
         return self._runtime_channel
 
     # ------------------------------------------------------------------
@@ -470,6 +518,29 @@ class BaseAgent(ABC):
         await stub.StorePattern(request, timeout=self.config.grpc_timeout_s)
         return pattern_id
 
+    async def store_incident(
+        self,
+        description: str,
+        symptoms: dict[str, Any],
+        *,
+        root_cause: str = "",
+        resolution: str = "",
+    ) -> str:
+        """Record an incident in long-term memory."""
+        incident_id = uuid.uuid4().hex
+        request = memory_pb2.Incident(
+            id=incident_id,
+            description=description,
+            symptoms_json=json.dumps(symptoms, default=str).encode("utf-8"),
+            root_cause=root_cause,
+            resolution=resolution,
+            resolved_by=self.agent_id if resolution else "",
+            timestamp=int(time.time()),
+        )
+        stub = self._get_memory_stub()
+        await stub.StoreIncident(request, timeout=self.config.grpc_timeout_s)
+        return incident_id
+
     async def find_pattern(self, trigger: str, min_success_rate: float = 0.5) -> dict[str, Any] | None:
         """Find a matching pattern for the given trigger."""
         request = memory_pb2.PatternQuery(
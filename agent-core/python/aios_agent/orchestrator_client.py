@@ -12,6 +12,7 @@ import json
 import logging
 import os
 import time
+from collections.abc import AsyncIterator
 from dataclasses import dataclass, field
 from typing import Any
 
@@ -196,17 +197,134 @@ class OrchestratorClient:
         status_filter: str = "",
         limit: int = 50,
         offset: int = 0,
+        labels: list[str] | None = None,
+        query: str = "",
     ) -> tuple[list[dict[str, Any]], int]:
         """List goals with optional filtering.
 
+        Goals must carry every label in *labels*; *query* is matched
+        word-by-word against goal descriptions.
+
         Returns (goals_list, total_count).
         """
         result = await self._call(
             "ListGoals",
-            {"status_filter": status_filter, "limit": limit, "offset": offset},
+            {
+                "status_filter": status_filter,
+                "limit": limit,
+                "offset": offset,
+                "labels": labels or [],
+                "query": query,
+            },
         )
         return result.get("goals", []), result.get("total", 0)
 
+    async def update_goal_labels(
+        self,
+        goal_id: str,
+        add: list[str] | None = None,
+        remove: list[str] | None = None,
+    ) -> list[str]:
+        """Add and remove labels on a goal. Returns the goal's resulting labels."""
+        result = await self._call(
+            "UpdateGoalLabels",
+            {"goal_id": goal_id, "add": add or [], "remove": remove or []},
+        )
+        return result.get("tags", [])
+
+    async def get_goal_timeline(self, goal_id: str) -> list[dict[str, Any]]:
+        """Get the recorded state transitions of a goal and its tasks, oldest first.
+
+        Each entry has keys: goal_id, entity_type, entity_id, from_status,
+        to_status, cause, actor, timestamp.
+        """
+        result = await self._call("GetGoalTimeline", {"id": goal_id})
+        return result.get("transitions", [])
+
+    async def stream_task_events(
+        self, goal_id: str = "", replay: bool = False
+    ) -> AsyncIterator[dict[str, Any]]:
+        """Yield task lifecycle events as they happen.
+
+        Each event has keys: goal_id, task_id, kind (queued, in_progress,
+        dispatched, tool_call, completed, failed, ...), entity_type,
+        from_status, to_status, tool_name, success, error, cause, actor,
+        timestamp. For a single goal the stream ends once the goal completes,
+        fails or is cancelled; *replay* first yields its recorded transitions.
+        """
+        if self._channel is None:
+            self.connect()
+        assert self._channel is not None
+
+        call = self._channel.unary_stream(
+            "/aios.orchestrator.Orchestrator/StreamTaskEvents",
+            request_serializer=lambda x: x,
+            response_deserializer=lambda x: x,
+        )
+        async for raw in call(self._encode({"goal_id": goal_id, "replay": replay})):
+            yield self._decode(raw)
+
+    # ------------------------------------------------------------------
+    # Local timers
+    # ------------------------------------------------------------------
+
+    async def schedule_timer(
+        self,
+        tool_name: str,
+        tool_input: dict[str, Any] | None = None,
+        delay_s: float = 0.0,
+        owner: str = "python-client",
+        repeat_interval_s: float = 0.0,
+        repeat_count: int = 0,
+        max_retries: int = 0,
+        initial_backoff_s: float = 0.0,
+        backoff_multiplier: float = 0.0,
+        max_backoff_s: float = 0.0,
+    ) -> str:
+        """Run a tool call after *delay_s* without creating a goal.
+
+        Failed calls are retried up to *max_retries* times with exponential
+        backoff; *repeat_count* additional runs follow every
+        *repeat_interval_s*. Zero backoff settings use the orchestrator
+        defaults (1s initial, x2, 60s cap). Returns the timer ID.
+
+        Raises ``ValueError`` if the orchestrator rejects the timer.
+        """
+        result = await self._call(
+            "ScheduleTimer",
+            {
+                "owner": owner,
+                "tool_name": tool_name,
+                "input_json": json.dumps(tool_input or {}, default=str),
+                "delay_ms": int(delay_s * 1000),
+                "repeat_interval_ms": int(repeat_interval_s * 1000),
+                "repeat_count": repeat_count,
+                "max_retries": max_retries,
+                "initial_backoff_ms": int(initial_backoff_s * 1000),
+                "backoff_multiplier": backoff_multiplier,
+                "max_backoff_ms": int(max_backoff_s * 1000),
+            },
+        )
+        if not result.get("success", False):
+            raise ValueError(f"Timer rejected: {result.get('error', 'unknown error')}")
+        return result.get("timer_id", "")
+
+    async def cancel_timer(self, timer_id: str) -> bool:
+        """Cancel a pending timer. Returns True if it existed."""
+        result = await self._call("CancelTimer", {"timer_id": timer_id})
+        return result.get("success", False)
+
+    async def list_timers(self) -> dict[str, list[dict[str, Any]]]:
+        """List pending timers and recent firings.
+
+        Returns a dict with keys: timers (soonest first), recent_firings.
+        """
+        result = await self._call("ListTimers", {})
+        return {
+            "timers": result.get("timers", []),
+            "recent_firings": result.get("recent_firings", []),
+        }
+
     # ------------------------------------------------------------------
     # Agent registration
     # ------------------------------------------------------------------
@@ -598,3 +598,20 @@ class TestIntelligenceLevel:
     def test_string_conversion(self):
         level = IntelligenceLevel("tactical")
         assert level == IntelligenceLevel.TACTICAL
+
+
+# ---------------------------------------------------------------------------
+# Identity tests
+# ---------------------------------------------------------------------------
+
+
+class TestIdentity:
+    def test_token_read_from_agent_token_file(self, tmp_path, monkeypatch):
+        from aios_agent.base import _ActAsAgent
+
+        monkeypatch.setenv("AIOS_AGENT_TOKEN_DIR", str(tmp_path))
+        act_as = _ActAsAgent("security-agent")
+        assert act_as._token() is None
+
+        (tmp_path / "security-agent.token").write_text("secret\n")
+        assert act_as._token() == "secret"
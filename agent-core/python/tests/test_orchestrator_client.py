@@ -239,6 +239,30 @@ class TestGoalManagement:
         assert total == 2
         assert len(goals) == 2
 
+    @pytest.mark.asyncio
+    async def test_list_goals_with_labels_and_query(self, client: OrchestratorClient):
+        channel = _mock_channel({"goals": [{"id": "g1"}], "total": 1})
+        client._channel = channel
+
+        await client.list_goals(labels=["infra"], query="disk cleanup")
+
+        call_fn = channel.unary_unary.return_value
+        payload = json.loads(call_fn.call_args[0][0])
+        assert payload["labels"] == ["infra"]
+        assert payload["query"] == "disk cleanup"
+
+    @pytest.mark.asyncio
+    async def test_update_goal_labels(self, client: OrchestratorClient):
+        channel = _mock_channel({"id": "g1", "tags": ["infra", "urgent"]})
+        client._channel = channel
+
+        labels = await client.update_goal_labels("g1", add=["urgent"], remove=["stale"])
+
+        call_fn = channel.unary_unary.return_value
+        payload = json.loads(call_fn.call_args[0][0])
+        assert payload == {"goal_id": "g1", "add": ["urgent"], "remove": ["stale"]}
+        assert labels == ["infra", "urgent"]
+
 
 # ---------------------------------------------------------------------------
 # Agent registration
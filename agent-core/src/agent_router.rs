@@ -4,11 +4,42 @@
 //! load, and health status.
 
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Instant;
 use tracing::info;
 
+use crate::capabilities::CapabilityEngine;
 use crate::proto::common::{AgentRegistration, Task};
 
+/// Fields agents can be sorted by
+pub const AGENT_SORT_FIELDS: &[&str] = &["agent_id", "agent_type", "status"];
+
+/// Agents with the given status and type (empty matches any), ordered by
+/// `sort` or by id
+pub fn filter_agents(
+    mut agents: Vec<AgentRegistration>,
+    status: &str,
+    agent_type: &str,
+    sort: &str,
+) -> Result<Vec<AgentRegistration>, String> {
+    agents.retain(|a| {
+        (status.is_empty() || a.status == status)
+            && (agent_type.is_empty() || a.agent_type == agent_type)
+    });
+    agents.sort_by(|a, b| a.agent_id.cmp(&b.agent_id));
+    crate::pagination::sort_by_field(
+        &mut agents,
+        sort,
+        AGENT_SORT_FIELDS,
+        |a, b, field| match field {
+            "agent_type" => a.agent_type.cmp(&b.agent_type),
+            "status" => a.status.cmp(&b.status),
+            _ => a.agent_id.cmp(&b.agent_id),
+        },
+    )?;
+    Ok(agents)
+}
+
 /// Agent state tracked by the router
 struct TrackedAgent {
     registration: AgentRegistration,
@@ -23,6 +54,9 @@ struct TrackedAgent {
 pub struct AgentRouter {
     agents: HashMap<String, TrackedAgent>,
     heartbeat_timeout_secs: u64,
+    /// Capabilities agents must hold to be routed a task; any agent may
+    /// take any task it matches when unset
+    capabilities: Option<Arc<CapabilityEngine>>,
 }
 
 impl AgentRouter {
@@ -30,9 +64,38 @@ impl AgentRouter {
         Self {
             agents: HashMap::new(),
             heartbeat_timeout_secs: 15,
+            capabilities: None,
         }
     }
 
+    /// Only route tasks to agents holding the capabilities they need
+    pub fn set_capabilities(&mut self, engine: Arc<CapabilityEngine>) {
+        self.capabilities = Some(engine);
+    }
+
+    /// The capability engine routing is enforced with
+    pub fn capabilities(&self) -> Option<Arc<CapabilityEngine>> {
+        self.capabilities.clone()
+    }
+
+    /// Type of a registered agent
+    pub fn agent_type(&self, agent_id: &str) -> Option<String> {
+        self.agents
+            .get(agent_id)
+            .map(|a| a.registration.agent_type.clone())
+    }
+
+    /// Whether the agent holds a capability for each tool the task needs
+    fn permitted(&self, agent: &TrackedAgent, required_tools: &[String]) -> bool {
+        let Some(ref engine) = self.capabilities else {
+            return true;
+        };
+        let reg = &agent.registration;
+        required_tools
+            .iter()
+            .all(|tool| engine.holds(&reg.agent_id, &reg.agent_type, tool))
+    }
+
     /// Register a new agent
     pub async fn register_agent(&mut self, registration: AgentRegistration) {
         let agent_id = registration.agent_id.clone();
@@ -107,6 +170,7 @@ impl AgentRouter {
                             })
                 })
             })
+            .filter(|(_, agent)| self.permitted(agent, required_tools))
             .collect();
 
         if candidates.is_empty() {
@@ -125,6 +189,7 @@ impl AgentRouter {
                         .iter()
                         .any(|tool| agent.registration.tool_namespaces.contains(tool))
                 })
+                .filter(|(_, agent)| self.permitted(agent, required_tools))
                 .collect();
         }
 
@@ -286,6 +351,8 @@ mod tests {
             started_at: 0,
             completed_at: 0,
             error: String::new(),
+            model_class: String::new(),
+            provider_hint: String::new(),
         };
 
         let agent = router.route_task(&task);
@@ -308,6 +375,8 @@ mod tests {
             started_at: 0,
             completed_at: 0,
             error: String::new(),
+            model_class: String::new(),
+            provider_hint: String::new(),
         }
     }
 
@@ -532,4 +601,40 @@ mod tests {
         // Should prefer the more experienced agent
         assert_eq!(selected, Some("agent-exp".to_string()));
     }
+
+    #[tokio::test]
+    async fn test_route_requires_capability() {
+        let policy = crate::capabilities::CapabilityPolicy::from_toml(
+            r#"
+            [[rules]]
+            agent_types = ["system"]
+            standing = ["fs.*"]
+
+            [[rules]]
+            agent_ids = ["sys-2"]
+            grantable = ["process.*"]
+            "#,
+        )
+        .unwrap();
+        let engine = Arc::new(CapabilityEngine::open(policy, ":memory:"));
+        let mut router = AgentRouter::new();
+        router.set_capabilities(engine.clone());
+        router
+            .register_agent(make_registration("sys-1", "system", vec!["fs", "process"]))
+            .await;
+        router
+            .register_agent(make_registration("sys-2", "system", vec!["fs", "process"]))
+            .await;
+
+        assert!(router.route_task(&make_task(vec!["fs"])).is_some());
+        assert_eq!(router.route_task(&make_task(vec!["process"])), None);
+
+        engine
+            .request("sys-2", "system", &["process.*".to_string()], "restart", 1)
+            .unwrap();
+        assert_eq!(
+            router.route_task(&make_task(vec!["process"])),
+            Some("sys-2".to_string())
+        );
+    }
 }
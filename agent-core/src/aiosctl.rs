@@ -0,0 +1,529 @@
+//! aiOS Control — drive the orchestrator from a shell
+//!
+//! A command-line client for hosts where the management console is not
+//! exposed: submits, lists and cancels goals, follows their task events,
+//! lists agents and nodes, runs schedules on demand, and shows the API
+//! budget. Goals and schedules go through the orchestrator's gRPC API; the
+//! budget comes from the API gateway and ad hoc tool calls go to the tools
+//! service, which enforces its usual permissions and audit log for them.
+//!
+//! Service addresses come from `AIOS_ORCHESTRATOR_ADDR`, `AIOS_GATEWAY_ADDR`
+//! and `AIOS_TOOLS_ADDR`, defaulting to the local services.
+
+use anyhow::{bail, Context, Result};
+
+pub mod proto {
+    pub mod common {
+        tonic::include_proto!("aios.v1.common");
+    }
+    pub mod orchestrator {
+        tonic::include_proto!("aios.v1.orchestrator");
+    }
+    pub mod tools {
+        tonic::include_proto!("aios.v1.tools");
+    }
+    pub mod api_gateway {
+        tonic::include_proto!("aios.v1.api_gateway");
+    }
+}
+
+use proto::api_gateway::api_gateway_client::ApiGatewayClient;
+use proto::common::{Empty, GoalId};
+use proto::orchestrator::orchestrator_client::OrchestratorClient;
+use proto::tools::tool_registry_client::ToolRegistryClient;
+use tonic::transport::Channel;
+
+const USAGE: &str = "Usage: aiosctl <command>
+
+Commands:
+  status                          System status
+  goals [status] [limit]          Goals, newest first
+  goal <goal_id>                  A goal's tasks and usage
+  submit [-p <priority>] <text>   Submit a goal
+  cancel <goal_id>                Cancel a goal
+  tail [goal_id]                  Follow task events (of every goal without an id)
+  agents                          Registered agents
+  nodes [all]                     Cluster nodes (all: include dead ones)
+  budget                          API budget and spend
+  schedules                       Scheduled goals
+  run-schedule <schedule_id>      Submit a schedule's goal now
+  exec <tool> [input_json]        Execute a tool
+  help                            This text";
+
+/// Identity of ad hoc tool calls in the audit log
+const AGENT_ID: &str = "aiosctl";
+
+/// Priority of goals submitted without `-p`, as in the console
+const DEFAULT_PRIORITY: i32 = 2;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|a| a == "-h" || a == "--help") {
+        println!("{USAGE}");
+        return Ok(());
+    }
+    run(parse(&args)?).await
+}
+
+fn addr(env: &str, default: &str) -> String {
+    std::env::var(env).unwrap_or_else(|_| default.to_string())
+}
+
+async fn orchestrator() -> Result<OrchestratorClient<Channel>> {
+    let addr = addr("AIOS_ORCHESTRATOR_ADDR", "http://127.0.0.1:50051");
+    OrchestratorClient::connect(addr.clone())
+        .await
+        .with_context(|| format!("Cannot connect to the orchestrator at {addr}"))
+}
+
+/// A parsed command line
+#[derive(Debug, PartialEq)]
+enum Command {
+    Status,
+    Goals { status: String, limit: i32 },
+    Goal { id: String },
+    Submit { priority: i32, description: String },
+    Cancel { id: String },
+    Tail { goal_id: String },
+    Agents,
+    Nodes { all: bool },
+    Budget,
+    Schedules,
+    RunSchedule { id: String },
+    Exec { tool: String, input: String },
+    Help,
+}
+
+/// Goals listed without a limit
+const DEFAULT_GOALS_LIMIT: i32 = 20;
+
+fn parse(args: &[String]) -> Result<Command> {
+    let arg = |i: usize| args.get(i).map(String::as_str);
+    let required = |i: usize, what: &str| match arg(i) {
+        Some(value) => Ok(value.to_string()),
+        None => bail!("{} needs {what}", args[0]),
+    };
+    Ok(match args[0].as_str() {
+        "status" => Command::Status,
+        "goals" => {
+            // A number is the limit, anything else the status, in either order
+            let (mut status, mut limit) = (None, None);
+            for value in &args[1..] {
+                match value.parse::<i32>() {
+                    Ok(n) if n <= 0 => bail!("The limit must be positive, not {n}"),
+                    Ok(n) if limit.is_none() => limit = Some(n),
+                    Err(_) if status.is_none() => status = Some(value.clone()),
+                    _ => bail!("Unexpected argument '{value}' (goals [status] [limit])"),
+                }
+            }
+            Command::Goals {
+                status: status.unwrap_or_default(),
+                limit: limit.unwrap_or(DEFAULT_GOALS_LIMIT),
+            }
+        }
+        "goal" => Command::Goal {
+            id: required(1, "a goal id")?,
+        },
+        "submit" => {
+            let mut words = &args[1..];
+            let mut priority = DEFAULT_PRIORITY;
+            if words.first().map(String::as_str) == Some("-p") {
+                let Some(value) = words.get(1) else {
+                    bail!("-p needs a priority");
+                };
+                priority = value
+                    .parse()
+                    .with_context(|| format!("Bad priority '{value}'"))?;
+                words = &words[2..];
+            }
+            if words.is_empty() {
+                bail!("submit needs a goal description");
+            }
+            Command::Submit {
+                priority,
+                description: words.join(" "),
+            }
+        }
+        "cancel" => Command::Cancel {
+            id: required(1, "a goal id")?,
+        },
+        "tail" => Command::Tail {
+            goal_id: arg(1).unwrap_or_default().to_string(),
+        },
+        "agents" => Command::Agents,
+        "nodes" => match arg(1) {
+            None => Command::Nodes { all: false },
+            Some("all") => Command::Nodes { all: true },
+            Some(other) => bail!("Unexpected argument '{other}' (nodes [all])"),
+        },
+        "budget" => Command::Budget,
+        "schedules" => Command::Schedules,
+        "run-schedule" => Command::RunSchedule {
+            id: required(1, "a schedule id")?,
+        },
+        "exec" => {
+            let tool = required(1, "a tool name")?;
+            let input = arg(2).unwrap_or("{}").to_string();
+            serde_json::from_str::<serde_json::Value>(&input).context("Input is not JSON")?;
+            Command::Exec { tool, input }
+        }
+        "help" => Command::Help,
+        other => bail!("Unknown command '{other}' (try help)"),
+    })
+}
+
+async fn run(command: Command) -> Result<()> {
+    match command {
+        Command::Status => {
+            let status = orchestrator()
+                .await?
+                .get_system_status(Empty {})
+                .await?
+                .into_inner();
+            println!("Autonomy:      {}", status.autonomy_level);
+            println!("Uptime:        {}s", status.uptime_seconds);
+            println!("Active goals:  {}", status.active_goals);
+            println!("Pending tasks: {}", status.pending_tasks);
+            println!("Agents:        {}", status.active_agents);
+            println!("Models:        {}", status.loaded_models.join(", "));
+            println!(
+                "CPU {:.1}%, memory {:.0}/{:.0} MB",
+                status.cpu_percent, status.memory_used_mb, status.memory_total_mb
+            );
+        }
+        Command::Goals { status, limit } => {
+            let list = orchestrator()
+                .await?
+                .list_goals(proto::orchestrator::ListGoalsRequest {
+                    status_filter: status,
+                    limit,
+                    sort: "-created_at".into(),
+                    ..Default::default()
+                })
+                .await?
+                .into_inner();
+            for goal in &list.goals {
+                print_goal(goal);
+            }
+            if list.total as usize > list.goals.len() {
+                println!("... {} of {} goals shown", list.goals.len(), list.total);
+            }
+        }
+        Command::Goal { id } => {
+            let status = orchestrator()
+                .await?
+                .get_goal_status(GoalId { id })
+                .await?
+                .into_inner();
+            if let Some(goal) = &status.goal {
+                print_goal(goal);
+            }
+            println!(
+                "Phase {}, {:.0}% done",
+                status.current_phase, status.progress_percent
+            );
+            for task in &status.tasks {
+                println!("  {}  [{}]  {}", task.id, task.status, task.description);
+                if !task.error.is_empty() {
+                    println!("      error: {}", task.error);
+                }
+            }
+            if let Some(usage) = &status.usage {
+                println!(
+                    "Usage: {} tokens, ${:.4}, {} tool calls, {} ms CPU, {} bytes written, {} ms",
+                    usage.tokens_used,
+                    usage.cost_usd,
+                    usage.tool_calls,
+                    usage.tool_cpu_ms,
+                    usage.bytes_written,
+                    usage.wall_clock_ms
+                );
+            }
+        }
+        Command::Submit {
+            priority,
+            description,
+        } => {
+            let goal_id = orchestrator()
+                .await?
+                .submit_goal(proto::orchestrator::SubmitGoalRequest {
+                    description,
+                    priority,
+                    source: "aiosctl".into(),
+                    ..Default::default()
+                })
+                .await?
+                .into_inner()
+                .id;
+            println!("{goal_id}");
+        }
+        Command::Cancel { id } => {
+            let status = orchestrator()
+                .await?
+                .cancel_goal(GoalId { id })
+                .await?
+                .into_inner();
+            if !status.success {
+                bail!("{}", status.message);
+            }
+            println!("{}", status.message);
+        }
+        Command::Tail { goal_id } => {
+            let mut events = orchestrator()
+                .await?
+                .stream_task_events(proto::orchestrator::StreamTaskEventsRequest {
+                    replay: !goal_id.is_empty(),
+                    goal_id,
+                })
+                .await?
+                .into_inner();
+            while let Some(event) = events.message().await? {
+                print_event(&event);
+            }
+        }
+        Command::Agents => {
+            let agents = orchestrator()
+                .await?
+                .list_agents(proto::orchestrator::ListAgentsRequest::default())
+                .await?
+                .into_inner()
+                .agents;
+            for agent in agents {
+                println!(
+                    "{}  {:<12} [{}]  {}",
+                    agent.agent_id,
+                    agent.agent_type,
+                    agent.status,
+                    agent.tool_namespaces.join(",")
+                );
+            }
+        }
+        Command::Nodes { all } => {
+            let nodes = orchestrator()
+                .await?
+                .list_nodes(proto::orchestrator::ListNodesRequest {
+                    include_dead: all,
+                    ..Default::default()
+                })
+                .await?
+                .into_inner()
+                .nodes;
+            for node in nodes {
+                println!(
+                    "{}  {}  {}  {}  cpu {:.1}%  mem {:.1}%  {} tasks  {} agents",
+                    node.node_id,
+                    node.hostname,
+                    node.address,
+                    if node.healthy { "healthy" } else { "dead" },
+                    node.cpu_usage,
+                    node.memory_usage,
+                    node.active_tasks,
+                    node.agents.len()
+                );
+            }
+        }
+        Command::Budget => {
+            let addr = addr("AIOS_GATEWAY_ADDR", "http://127.0.0.1:50054");
+            let budget = ApiGatewayClient::connect(addr.clone())
+                .await
+                .with_context(|| format!("Cannot connect to the API gateway at {addr}"))?
+                .get_budget(Empty {})
+                .await?
+                .into_inner();
+            println!(
+                "Claude: ${:.2} of ${:.2}",
+                budget.claude_used_usd, budget.claude_monthly_budget_usd
+            );
+            println!(
+                "OpenAI: ${:.2} of ${:.2}",
+                budget.openai_used_usd, budget.openai_monthly_budget_usd
+            );
+            println!(
+                "{:.1}% spent, ${:.2}/day, {} days left in the month",
+                budget.spent_percent, budget.daily_rate_usd, budget.days_remaining
+            );
+            if budget.budget_exceeded {
+                println!("Budget exceeded");
+            }
+            if !budget.degradation.is_empty() {
+                println!("Degraded: {}", budget.degradation);
+            }
+        }
+        Command::Schedules => {
+            let schedules = orchestrator()
+                .await?
+                .list_schedules(Empty {})
+                .await?
+                .into_inner()
+                .schedules;
+            for s in schedules {
+                let next = if s.enabled && s.next_run > 0 {
+                    timestamp(s.next_run)
+                } else {
+                    "-".to_string()
+                };
+                println!(
+                    "{}  {} ({})  next {next}  p{}  {}",
+                    s.id, s.cron_expr, s.timezone, s.priority, s.goal_template
+                );
+            }
+        }
+        Command::RunSchedule { id } => {
+            let goal_id = orchestrator()
+                .await?
+                .run_schedule(proto::orchestrator::RunScheduleRequest { schedule_id: id })
+                .await?
+                .into_inner()
+                .id;
+            println!("{goal_id}");
+        }
+        Command::Exec { tool, input } => {
+            let addr = addr("AIOS_TOOLS_ADDR", "http://127.0.0.1:50052");
+            let response = ToolRegistryClient::connect(addr.clone())
+                .await
+                .with_context(|| format!("Cannot connect to the tools service at {addr}"))?
+                .execute(proto::tools::ExecuteRequest {
+                    tool_name: tool,
+                    agent_id: AGENT_ID.into(),
+                    input_json: input.as_bytes().to_vec(),
+                    reason: "aiosctl exec".into(),
+                    workload: "interactive".into(),
+                    ..Default::default()
+                })
+                .await?
+                .into_inner();
+            if !response.success {
+                bail!("{} ({})", response.error, response.failure_class);
+            }
+            let output: serde_json::Value = serde_json::from_slice(&response.output_json)
+                .unwrap_or_else(|_| {
+                    String::from_utf8_lossy(&response.output_json)
+                        .into_owned()
+                        .into()
+                });
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        }
+        Command::Help => println!("{USAGE}"),
+    }
+    Ok(())
+}
+
+fn print_goal(goal: &proto::common::Goal) {
+    println!(
+        "{}  [{}] p{}  {}  {}",
+        goal.id,
+        goal.status,
+        goal.priority,
+        timestamp(goal.created_at),
+        goal.description
+    );
+}
+
+fn print_event(event: &proto::orchestrator::TaskEvent) {
+    let entity = if event.task_id.is_empty() {
+        event.goal_id.clone()
+    } else {
+        format!("{}/{}", event.goal_id, event.task_id)
+    };
+    let detail = if event.kind == "tool_call" {
+        let outcome = if event.success {
+            "ok".to_string()
+        } else {
+            format!("failed: {}", event.error)
+        };
+        format!("{} {outcome}", event.tool_name)
+    } else {
+        event.cause.clone()
+    };
+    println!(
+        "{}  {:<12} {entity}  {detail}",
+        timestamp(event.timestamp),
+        event.kind
+    );
+}
+
+fn timestamp(secs: i64) -> String {
+    chrono::DateTime::from_timestamp(secs, 0)
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| secs.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed(line: &str) -> Result<Command> {
+        let args: Vec<String> = line.split_whitespace().map(str::to_string).collect();
+        parse(&args)
+    }
+
+    #[test]
+    fn test_goals_takes_status_and_limit_in_either_order() {
+        let goals = |status: &str, limit| Command::Goals {
+            status: status.into(),
+            limit,
+        };
+        assert_eq!(parsed("goals").unwrap(), goals("", DEFAULT_GOALS_LIMIT));
+        assert_eq!(parsed("goals 5").unwrap(), goals("", 5));
+        assert_eq!(
+            parsed("goals pending").unwrap(),
+            goals("pending", DEFAULT_GOALS_LIMIT)
+        );
+        assert_eq!(parsed("goals pending 5").unwrap(), goals("pending", 5));
+        assert_eq!(parsed("goals 5 pending").unwrap(), goals("pending", 5));
+        assert!(parsed("goals 5 10").is_err());
+        assert!(parsed("goals pending failed").is_err());
+        assert!(parsed("goals 0").is_err());
+    }
+
+    #[test]
+    fn test_submit_takes_an_optional_priority() {
+        assert_eq!(
+            parsed("submit rotate the logs").unwrap(),
+            Command::Submit {
+                priority: DEFAULT_PRIORITY,
+                description: "rotate the logs".into(),
+            }
+        );
+        assert_eq!(
+            parsed("submit -p 1 restart nginx").unwrap(),
+            Command::Submit {
+                priority: 1,
+                description: "restart nginx".into(),
+            }
+        );
+        assert!(parsed("submit -p high restart nginx").is_err());
+        assert!(parsed("submit -p 1").is_err());
+        assert!(parsed("submit").is_err());
+    }
+
+    #[test]
+    fn test_commands_check_their_arguments() {
+        assert_eq!(
+            parsed("goal g-1").unwrap(),
+            Command::Goal { id: "g-1".into() }
+        );
+        assert!(parsed("goal").is_err());
+        assert!(parsed("cancel").is_err());
+        assert!(parsed("run-schedule").is_err());
+        assert_eq!(
+            parsed("tail").unwrap(),
+            Command::Tail {
+                goal_id: String::new()
+            }
+        );
+        assert_eq!(parsed("nodes all").unwrap(), Command::Nodes { all: true });
+        assert!(parsed("nodes dead").is_err());
+        assert_eq!(
+            parsed("exec monitor.cpu").unwrap(),
+            Command::Exec {
+                tool: "monitor.cpu".into(),
+                input: "{}".into(),
+            }
+        );
+        assert!(parsed("exec fs.read {path").is_err());
+        assert!(parsed("exec").is_err());
+        assert!(parsed("reboot").is_err());
+    }
+}
@@ -0,0 +1,148 @@
+//! API versioning shared by every service — GetApiVersion and the
+//! pre-versioned package shim
+//!
+//! Service packages carry their major version (aios.v1.tools). Clients built
+//! before versioning call /aios.tools.ToolRegistry/...; the shim layer
+//! rewrites those paths to their aios.v1 equivalents before routing, so old
+//! agents and peers keep working during a rolling update. Message encoding
+//! is identical between the two; only the path differs.
+//!
+//! The orchestrator uses this through api_version.rs, which adds version
+//! negotiation for outgoing calls; the other services include the file
+//! directly with `#[path]`, so every service reports the same version.
+
+use tonic::body::BoxBody;
+
+use crate::proto::common::ApiVersion;
+
+/// Major API version, taken from common.proto's package (aios.v1.common)
+/// by the build script
+pub const API_MAJOR: u32 = match package_major(env!("AIOS_API_PACKAGE")) {
+    Some(major) => major,
+    None => panic!("common.proto's package does not carry a major version"),
+};
+
+/// Minor API version; bump for backwards-compatible additions
+pub const API_MINOR: u32 = 45;
+
+/// Oldest minor version still served (0 = the pre-versioned aios.* packages)
+pub const MIN_COMPATIBLE_MINOR: u32 = 0;
+
+/// Version information for a fully qualified service name
+pub fn api_version(service: &str) -> ApiVersion {
+    ApiVersion {
+        service: service.to_string(),
+        major: API_MAJOR,
+        minor: API_MINOR,
+        min_compatible_minor: MIN_COMPATIBLE_MINOR,
+        legacy_packages: legacy_package(service).into_iter().collect(),
+        build_version: env!("CARGO_PKG_VERSION").to_string(),
+    }
+}
+
+/// `aios.v1.common` → 1
+const fn package_major(package: &str) -> Option<u32> {
+    let bytes = package.as_bytes();
+    let prefix = b"aios.v";
+    if bytes.len() <= prefix.len() {
+        return None;
+    }
+    let mut i = 0;
+    while i < prefix.len() {
+        if bytes[i] != prefix[i] {
+            return None;
+        }
+        i += 1;
+    }
+    let mut major = 0;
+    while i < bytes.len() && bytes[i] != b'.' {
+        if !bytes[i].is_ascii_digit() {
+            return None;
+        }
+        major = major * 10 + (bytes[i] - b'0') as u32;
+        i += 1;
+    }
+    if i == prefix.len() {
+        None
+    } else {
+        Some(major)
+    }
+}
+
+/// `aios.v1.tools.ToolRegistry` → `aios.tools`
+fn legacy_package(service: &str) -> Option<String> {
+    let rest = service.strip_prefix("aios.")?;
+    let (version, rest) = rest.split_once('.')?;
+    let (package, _) = rest.rsplit_once('.')?;
+    is_version_segment(version).then(|| format!("aios.{package}"))
+}
+
+type ShimFn = fn(http::Request<BoxBody>) -> http::Request<BoxBody>;
+
+/// Server layer that also accepts calls on the pre-versioned package paths
+pub fn legacy_shim() -> tower::util::MapRequestLayer<ShimFn> {
+    tower::util::MapRequestLayer::new(upgrade_legacy_path as ShimFn)
+}
+
+fn upgrade_legacy_path(mut req: http::Request<BoxBody>) -> http::Request<BoxBody> {
+    if let Some(path) = versioned_path(req.uri().path()) {
+        set_path(&mut req, &path);
+    }
+    req
+}
+
+pub(crate) fn set_path(req: &mut http::Request<BoxBody>, path: &str) {
+    let mut parts = req.uri().clone().into_parts();
+    if let Ok(path) = path.parse() {
+        parts.path_and_query = Some(path);
+        if let Ok(uri) = http::Uri::from_parts(parts) {
+            *req.uri_mut() = uri;
+        }
+    }
+}
+
+/// `/aios.tools.ToolRegistry/Execute` → `/aios.v1.tools.ToolRegistry/Execute`;
+/// None for paths that are already versioned or not aiOS services
+fn versioned_path(path: &str) -> Option<String> {
+    let rest = path.strip_prefix("/aios.")?;
+    let (package, _) = rest.split_once('.')?;
+    if is_version_segment(package) {
+        return None;
+    }
+    Some(format!("/aios.v{API_MAJOR}.{rest}"))
+}
+
+pub(crate) fn is_version_segment(segment: &str) -> bool {
+    segment
+        .strip_prefix('v')
+        .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legacy_paths_map_to_v1() {
+        assert_eq!(
+            versioned_path("/aios.tools.ToolRegistry/Execute").as_deref(),
+            Some("/aios.v1.tools.ToolRegistry/Execute")
+        );
+        assert!(versioned_path("/aios.v1.tools.ToolRegistry/Execute").is_none());
+        assert!(versioned_path("/grpc.health.v1.Health/Check").is_none());
+
+        let version = api_version("aios.v1.tools.ToolRegistry");
+        assert_eq!(version.legacy_packages, vec!["aios.tools".to_string()]);
+        assert!(version.min_compatible_minor < version.minor);
+    }
+
+    #[test]
+    fn test_major_version_comes_from_the_package() {
+        assert_eq!(API_MAJOR, 1);
+        assert_eq!(package_major("aios.v12.tools"), Some(12));
+        assert_eq!(package_major("aios.v1"), Some(1));
+        assert_eq!(package_major("aios.tools"), None);
+        assert_eq!(package_major("aios.v"), None);
+        assert_eq!(package_major("aios.vx.tools"), None);
+    }
+}
@@ -0,0 +1,200 @@
+//! API versioning — version negotiation for outgoing calls
+//!
+//! GetApiVersion and the server shim for the pre-versioned package paths
+//! live in api_compat.rs, which every service shares. Going the other way,
+//! [`VersionedChannel`] asks a peer for its API version on connect and falls
+//! back to the legacy paths when the peer predates GetApiVersion, so either
+//! side of a rolling update can be the newer one.
+
+use std::task::{Context, Poll};
+use tonic::body::BoxBody;
+use tonic::transport::Channel;
+use tracing::{debug, info, warn};
+
+use crate::proto::common::{ApiVersion, Empty};
+
+#[path = "api_compat.rs"]
+mod compat;
+
+pub use compat::{api_version, legacy_shim};
+use compat::{is_version_segment, set_path, API_MAJOR, API_MINOR};
+
+/// `/aios.v1.tools.ToolRegistry/Execute` → `/aios.tools.ToolRegistry/Execute`
+fn legacy_path(path: &str) -> Option<String> {
+    let rest = path.strip_prefix("/aios.")?;
+    let (version, rest) = rest.split_once('.')?;
+    is_version_segment(version).then(|| format!("/aios.{rest}"))
+}
+
+/// Client channel that speaks whichever package paths the peer serves
+#[derive(Clone)]
+pub struct VersionedChannel {
+    inner: Channel,
+    legacy: bool,
+}
+
+impl VersionedChannel {
+    /// Use the versioned paths without asking the peer
+    pub fn current(channel: Channel) -> Self {
+        Self {
+            inner: channel,
+            legacy: false,
+        }
+    }
+
+    /// Ask the peer which API version `service` speaks. Peers without
+    /// GetApiVersion predate the versioned packages and are called on the
+    /// legacy paths; peers that cannot be reached are assumed current.
+    pub async fn negotiate(channel: Channel, service: &str) -> Self {
+        let legacy = match get_api_version(channel.clone(), service).await {
+            Ok(version) => {
+                if version.major != API_MAJOR {
+                    warn!(
+                        "{service} speaks API v{}.{}, this node v{API_MAJOR}.{API_MINOR}",
+                        version.major, version.minor
+                    );
+                } else {
+                    debug!(
+                        "{service} speaks API v{}.{} ({})",
+                        version.major, version.minor, version.build_version
+                    );
+                }
+                false
+            }
+            Err(status) if status.code() == tonic::Code::Unimplemented => {
+                info!("{service} predates API versioning; using legacy package paths");
+                true
+            }
+            Err(status) => {
+                debug!("API version check for {service} failed: {status}");
+                false
+            }
+        };
+        Self {
+            inner: channel,
+            legacy,
+        }
+    }
+
+    /// Whether calls go out on the pre-versioned package paths
+    pub fn is_legacy(&self) -> bool {
+        self.legacy
+    }
+}
+
+impl tower::Service<http::Request<BoxBody>> for VersionedChannel {
+    type Response = <Channel as tower::Service<http::Request<BoxBody>>>::Response;
+    type Error = <Channel as tower::Service<http::Request<BoxBody>>>::Error;
+    type Future = <Channel as tower::Service<http::Request<BoxBody>>>::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: http::Request<BoxBody>) -> Self::Future {
+        if self.legacy {
+            if let Some(path) = legacy_path(req.uri().path()) {
+                set_path(&mut req, &path);
+            }
+        }
+        crate::telemetry::inject(req.headers_mut());
+        crate::identity::inject(req.headers_mut());
+        self.inner.call(req)
+    }
+}
+
+/// Call `<service>/GetApiVersion` without a service-specific client
+async fn get_api_version(channel: Channel, service: &str) -> Result<ApiVersion, tonic::Status> {
+    let mut grpc = tonic::client::Grpc::new(channel);
+    grpc.ready()
+        .await
+        .map_err(|e| tonic::Status::unavailable(e.to_string()))?;
+    let path = format!("/{service}/GetApiVersion")
+        .parse()
+        .map_err(|_| tonic::Status::invalid_argument(format!("bad service name {service}")))?;
+    let codec = tonic::codec::ProstCodec::<Empty, ApiVersion>::default();
+    grpc.unary(tonic::Request::new(Empty {}), path, codec)
+        .await
+        .map(|response| response.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paths_map_to_legacy() {
+        assert_eq!(
+            legacy_path("/aios.v1.tools.ToolRegistry/Execute").as_deref(),
+            Some("/aios.tools.ToolRegistry/Execute")
+        );
+        assert!(legacy_path("/aios.tools.ToolRegistry/Execute").is_none());
+        assert!(legacy_path("/grpc.health.v1.Health/Check").is_none());
+    }
+
+    /// A peer that only serves the pre-versioned tools package and records
+    /// the paths it is called on
+    #[derive(Clone, Default)]
+    struct LegacyToolsPeer {
+        calls: std::sync::Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    impl tonic::server::NamedService for LegacyToolsPeer {
+        const NAME: &'static str = "aios.tools.ToolRegistry";
+    }
+
+    impl tower::Service<http::Request<BoxBody>> for LegacyToolsPeer {
+        type Response = http::Response<BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = std::future::Ready<Result<Self::Response, Self::Error>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, req: http::Request<BoxBody>) -> Self::Future {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(req.uri().path().to_string());
+            std::future::ready(Ok(tonic::Status::unavailable("legacy peer").into_http()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_negotiate_falls_back_to_legacy_peer() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let peer = LegacyToolsPeer::default();
+        let calls = peer.calls.clone();
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(peer)
+                .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener)),
+        );
+
+        let channel = tonic::transport::Endpoint::from_shared(format!("http://{addr}"))
+            .unwrap()
+            .connect()
+            .await
+            .unwrap();
+        let channel = VersionedChannel::negotiate(
+            channel,
+            crate::proto::tools::tool_registry_server::SERVICE_NAME,
+        )
+        .await;
+        assert!(channel.is_legacy());
+
+        let mut client =
+            crate::proto::tools::tool_registry_client::ToolRegistryClient::new(channel);
+        let err = client
+            .list_tools(crate::proto::tools::ListToolsRequest::default())
+            .await
+            .unwrap_err();
+        assert_eq!(err.message(), "legacy peer");
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec!["/aios.tools.ToolRegistry/ListTools".to_string()]
+        );
+    }
+}
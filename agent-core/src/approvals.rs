@@ -0,0 +1,436 @@
+//! Approval chains — who approves a plan's tool calls, and what happens
+//! when nobody answers
+//!
+//! Rules in approvals.toml are matched against each tool call of a plan,
+//! first match wins, by tool namespace, tool name, risk level and the paths
+//! the call works on. A rule names the approver of the calls it matches:
+//! - `none`: they run without approval
+//! - `review`: a second model reviews the plan (see [`crate::peer_review`])
+//!   and escalates it to an operator when it objects
+//! - `operator`: the plan goes straight to an operator
+//!
+//! Agents listed in a rule's `auto_approve` ("security*" matches agent ids
+//! by prefix) run the calls it matches without approval. Calls no rule
+//! matches are reviewed when their risk level is listed in peer_review.toml.
+//!
+//! A plan waiting for an operator is escalated once `escalate_after_secs`
+//! pass unanswered: the `approval_escalated` notification goes to the
+//! rule's `escalate_to` notification channels, or along the usual routes
+//! when it names none. After `deny_after_secs` the plan is denied and its
+//! task fails. The earliest times among the rules of a plan's calls apply;
+//! the top-level settings cover calls no rule matched. 0 means never.
+//!
+//! Writes under /etc that run for the security agent, need an operator
+//! otherwise, go to email after 15 minutes and are denied after an hour:
+//!
+//! ```toml
+//! [[rules]]
+//! name = "etc-writes"
+//! namespace = "fs"
+//! tools = ["fs.write", "fs.delete", "fs.move"]
+//! paths = ["/etc"]
+//! auto_approve = ["security*"]
+//! approver = "operator"
+//! escalate_after_secs = 900
+//! escalate_to = ["oncall-email"]
+//! deny_after_secs = 3600
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::event_bus::EventSeverity;
+use crate::peer_review::PlannedCall;
+use crate::OrchestratorState;
+
+/// Default location of the approval policy
+pub const APPROVALS_CONFIG_PATH: &str = "/etc/aios/approvals.toml";
+
+/// Reviewer named on plans the policy sends straight to an operator
+pub const POLICY_REVIEWER: &str = "approval policy";
+
+/// How often escalated plans are checked against their deadlines
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Input fields naming the paths a tool call works on
+const PATH_FIELDS: [&str; 5] = ["path", "source", "destination", "target", "device"];
+
+/// Who approves the calls a rule matches
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Approver {
+    /// They run without approval
+    None,
+    /// A second model, then an operator if it objects
+    #[default]
+    Review,
+    /// An operator
+    Operator,
+}
+
+/// One rule of approvals.toml
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApprovalRule {
+    /// Shown in reviews and decision records
+    pub name: String,
+    /// Tool namespace, the part of the name before the first `.` ("" = any)
+    pub namespace: String,
+    /// Tool names; a trailing `*` matches a prefix (empty = any)
+    pub tools: Vec<String>,
+    /// Risk levels (empty = any)
+    pub risk_levels: Vec<String>,
+    /// Path prefixes one of the call's paths must fall under (empty = any)
+    pub paths: Vec<String>,
+    /// Agents that run the matched calls without approval; a trailing `*`
+    /// matches a prefix of the agent id
+    pub auto_approve: Vec<String>,
+    pub approver: Approver,
+    pub escalate_after_secs: i64,
+    /// Notification channels escalations go to
+    pub escalate_to: Vec<String>,
+    pub deny_after_secs: i64,
+}
+
+impl ApprovalRule {
+    fn matches(&self, tool: &str, risk: &str, input: &serde_json::Value) -> bool {
+        let namespace = tool.split('.').next().unwrap_or(tool);
+        (self.namespace.is_empty() || self.namespace == namespace)
+            && (self.tools.is_empty() || self.tools.iter().any(|p| pattern_matches(p, tool)))
+            && (self.risk_levels.is_empty() || self.risk_levels.iter().any(|r| r == risk))
+            && (self.paths.is_empty() || call_paths(input).any(|p| self.covers(p)))
+    }
+
+    fn covers(&self, path: &str) -> bool {
+        self.paths.iter().any(|prefix| {
+            let prefix = prefix.trim_end_matches('/');
+            prefix.is_empty()
+                || path == prefix
+                || path
+                    .strip_prefix(prefix)
+                    .is_some_and(|rest| rest.starts_with('/'))
+        })
+    }
+
+    /// Whether `agent` runs the matched calls without approval
+    pub fn exempts(&self, agent: &str) -> bool {
+        self.auto_approve.iter().any(|p| pattern_matches(p, agent))
+    }
+}
+
+/// Exact match, or prefix match for a pattern ending in `*`
+fn pattern_matches(pattern: &str, value: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => value.starts_with(prefix),
+        None => pattern == value,
+    }
+}
+
+fn call_paths(input: &serde_json::Value) -> impl Iterator<Item = &str> {
+    PATH_FIELDS
+        .iter()
+        .filter_map(|field| input.get(*field).and_then(|v| v.as_str()))
+}
+
+/// When an unanswered plan is escalated and denied, in seconds after it
+/// went to an operator (0 = never), and where escalations go
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Chain {
+    pub escalate_after_secs: i64,
+    pub escalate_to: Vec<String>,
+    pub deny_after_secs: i64,
+}
+
+impl Chain {
+    /// Add the steps of a rule, keeping the earliest times
+    fn merge(&mut self, escalate_after_secs: i64, escalate_to: &[String], deny_after_secs: i64) {
+        let earliest = |a: i64, b: i64| match (a > 0, b > 0) {
+            (true, true) => a.min(b),
+            (true, false) => a,
+            _ => b,
+        };
+        self.escalate_after_secs = earliest(self.escalate_after_secs, escalate_after_secs);
+        self.deny_after_secs = earliest(self.deny_after_secs, deny_after_secs);
+        for channel in escalate_to {
+            if !self.escalate_to.contains(channel) {
+                self.escalate_to.push(channel.clone());
+            }
+        }
+    }
+}
+
+/// approvals.toml layout
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApprovalPolicy {
+    /// Escalation of plans whose calls no rule matched
+    pub escalate_after_secs: i64,
+    pub escalate_to: Vec<String>,
+    pub deny_after_secs: i64,
+    pub rules: Vec<ApprovalRule>,
+}
+
+impl ApprovalPolicy {
+    /// Load the policy from `path`; without one, risky plans are only peer
+    /// reviewed and wait for an operator indefinitely
+    pub fn load(path: &str) -> Self {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => match toml::from_str::<ApprovalPolicy>(&contents) {
+                Ok(policy) => {
+                    info!("Loaded {} approval rules", policy.rules.len());
+                    policy
+                }
+                Err(e) => {
+                    warn!("Invalid approval policy {path}: {e}; using no rules");
+                    Self::default()
+                }
+            },
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// The first rule matching a call of `tool`, whose risk level is `risk`
+    pub fn rule_for(
+        &self,
+        tool: &str,
+        risk: &str,
+        input: &serde_json::Value,
+    ) -> Option<&ApprovalRule> {
+        self.rules.iter().find(|r| r.matches(tool, risk, input))
+    }
+
+    fn rule(&self, name: &str) -> Option<&ApprovalRule> {
+        self.rules.iter().find(|r| r.name == name)
+    }
+
+    /// Name of the first rule that sends one of `calls` straight to an
+    /// operator
+    pub fn operator_rule(&self, calls: &[PlannedCall]) -> Option<String> {
+        calls
+            .iter()
+            .filter(|c| !c.risk.is_empty())
+            .filter_map(|c| self.rule(&c.rule))
+            .find(|r| r.approver == Approver::Operator)
+            .map(|r| r.name.clone())
+    }
+
+    /// Escalation chain of a plan waiting for an operator
+    pub fn chain(&self, calls: &[PlannedCall]) -> Chain {
+        let mut chain = Chain::default();
+        let mut unruled = false;
+        for call in calls.iter().filter(|c| !c.risk.is_empty()) {
+            match self.rule(&call.rule) {
+                Some(rule) => chain.merge(
+                    rule.escalate_after_secs,
+                    &rule.escalate_to,
+                    rule.deny_after_secs,
+                ),
+                None => unruled = true,
+            }
+        }
+        if unruled {
+            chain.merge(
+                self.escalate_after_secs,
+                &self.escalate_to,
+                self.deny_after_secs,
+            );
+        }
+        chain
+    }
+}
+
+/// Escalate and deny plans that went unanswered, until `cancel` fires
+pub async fn run_approval_monitor(
+    state: Arc<RwLock<OrchestratorState>>,
+    cancel: CancellationToken,
+) {
+    info!("Approval monitor started");
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => break,
+            _ = tokio::time::sleep(CHECK_INTERVAL) => {}
+        }
+        check(&state).await;
+    }
+    info!("Approval monitor stopped");
+}
+
+async fn check(state: &RwLock<OrchestratorState>) {
+    let peer_review = state.read().await.peer_review.clone();
+    let (escalate, deny) = peer_review.overdue(chrono::Utc::now().timestamp());
+    if escalate.is_empty() && deny.is_empty() {
+        return;
+    }
+
+    let mut s = crate::liveness::write_state(state, "approvals.check").await;
+    for review in &escalate {
+        let language = crate::locale::for_goal(&s.goal_engine, &review.goal_id, s.language);
+        let title = language.text("approval_escalated", &[("goal", &review.goal_id)]);
+        let body = format!(
+            "No operator has ruled on this plan.\nRisky calls:\n{}",
+            review.risky_calls()
+        );
+        if review.escalate_to.is_empty() {
+            s.notifier.notify(
+                "approval_escalated",
+                EventSeverity::Critical,
+                &title,
+                &body,
+                &review.task_id,
+            );
+        } else {
+            s.notifier.notify_channels(
+                &review.escalate_to,
+                "approval_escalated",
+                EventSeverity::Critical,
+                &title,
+                &body,
+            );
+        }
+        s.goal_engine.add_message(
+            &review.goal_id,
+            "system",
+            "Nobody has approved or rejected the planned actions yet; they have been escalated.",
+        );
+        info!("Task {}: unanswered plan escalated", review.task_id);
+    }
+    for review in &deny {
+        s.decision_logger.log_decision(
+            "peer_review",
+            &review.opinions(),
+            "denied",
+            &format!(
+                "Task {}: plan denied after waiting {}s for an operator\n{}",
+                review.task_id,
+                review.deny_at - review.created_at,
+                review.risky_calls()
+            ),
+            "human",
+            POLICY_REVIEWER,
+        );
+        s.task_planner.fail_task(
+            &review.task_id,
+            "Plan denied: no operator approved it in time",
+        );
+        s.goal_engine.update_task_status(
+            &review.goal_id,
+            &review.task_id,
+            "failed",
+            "no operator approved the plan in time",
+            "autonomy",
+        );
+        s.goal_engine.add_message(
+            &review.goal_id,
+            "system",
+            "Nobody approved the planned actions in time; they were denied and will not run.",
+        );
+        let language = crate::locale::for_goal(&s.goal_engine, &review.goal_id, s.language);
+        s.notifier.notify(
+            "approval_denied",
+            EventSeverity::Warning,
+            &language.text("approval_denied", &[("goal", &review.goal_id)]),
+            &review.risky_calls(),
+            &review.task_id,
+        );
+        info!("Task {}: unanswered plan denied", review.task_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn etc_writes() -> ApprovalRule {
+        ApprovalRule {
+            name: "etc-writes".into(),
+            namespace: "fs".into(),
+            tools: vec!["fs.write".into(), "fs.delete*".into()],
+            paths: vec!["/etc/".into()],
+            auto_approve: vec!["security*".into()],
+            approver: Approver::Operator,
+            escalate_after_secs: 900,
+            escalate_to: vec!["oncall-email".into()],
+            deny_after_secs: 3600,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_rules_match_namespace_tool_risk_and_path() {
+        let policy = ApprovalPolicy {
+            rules: vec![
+                etc_writes(),
+                ApprovalRule {
+                    name: "critical".into(),
+                    risk_levels: vec!["critical".into()],
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let at = |path: &str| serde_json::json!({ "path": path });
+
+        let rule = policy.rule_for("fs.write", "medium", &at("/etc/hosts"));
+        assert_eq!(rule.map(|r| r.name.as_str()), Some("etc-writes"));
+        assert!(policy
+            .rule_for("fs.delete_tree", "high", &at("/etc"))
+            .is_some());
+        // Outside /etc, or a tool the rule does not name
+        assert!(policy
+            .rule_for("fs.write", "medium", &at("/etcetera"))
+            .is_none());
+        assert!(policy
+            .rule_for("fs.read", "low", &at("/etc/hosts"))
+            .is_none());
+        let moved = serde_json::json!({ "source": "/tmp/x", "destination": "/etc/x" });
+        assert!(policy.rule_for("fs.write", "medium", &moved).is_some());
+        // Falls through to the next rule by risk level
+        let rule = policy.rule_for("process.kill", "critical", &serde_json::json!({}));
+        assert_eq!(rule.map(|r| r.approver), Some(Approver::Review));
+
+        assert!(etc_writes().exempts("security-1a2b3c4d"));
+        assert!(!etc_writes().exempts("system-1a2b3c4d"));
+    }
+
+    #[test]
+    fn test_chain_takes_the_earliest_deadlines() {
+        let policy = ApprovalPolicy {
+            escalate_after_secs: 600,
+            escalate_to: vec!["slack".into()],
+            deny_after_secs: 0,
+            rules: vec![etc_writes()],
+        };
+        let call = |tool: &str, rule: &str| PlannedCall {
+            tool: tool.into(),
+            risk: "high".into(),
+            input: serde_json::Value::Null,
+            rule: rule.into(),
+            key: String::new(),
+        };
+
+        let chain = policy.chain(&[call("fs.write", "etc-writes")]);
+        assert_eq!(chain.escalate_after_secs, 900);
+        assert_eq!(chain.escalate_to, vec!["oncall-email"]);
+        assert_eq!(chain.deny_after_secs, 3600);
+        assert_eq!(
+            policy.operator_rule(&[call("fs.write", "etc-writes")]),
+            Some("etc-writes".to_string())
+        );
+
+        let chain = policy.chain(&[call("fs.write", "etc-writes"), call("pkg.remove", "")]);
+        assert_eq!(chain.escalate_after_secs, 600);
+        assert_eq!(chain.escalate_to, vec!["oncall-email", "slack"]);
+        assert_eq!(chain.deny_after_secs, 3600);
+
+        assert_eq!(policy.chain(&[call("pkg.remove", "")]).deny_after_secs, 0);
+        assert!(policy.operator_rule(&[call("pkg.remove", "")]).is_none());
+    }
+}
@@ -0,0 +1,171 @@
+//! Artifact store — what tasks produced and where it is
+//!
+//! Agents list the files, reports and packages a task produced in
+//! `TaskResult.artifacts`. Each one is recorded against its goal and task
+//! so operators and later tasks can find it without reading the task's
+//! output (`GET /api/goals/:goal_id/artifacts`). The store records where an
+//! artifact is and its checksum, not its contents.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::sync::Mutex;
+use tracing::warn;
+
+use crate::proto::common::Artifact;
+
+pub const ARTIFACTS_DB_PATH: &str = "/var/lib/aios/data/artifacts.db";
+
+/// An artifact as recorded
+#[derive(Debug, Clone, Serialize)]
+pub struct ArtifactRecord {
+    pub goal_id: String,
+    pub task_id: String,
+    /// Agent that reported it
+    pub producer: String,
+    pub name: String,
+    pub path: String,
+    pub kind: String,
+    pub sha256: String,
+    pub size_bytes: i64,
+    pub description: String,
+    pub created_at: i64,
+}
+
+/// SQLite-backed record of task artifacts
+#[derive(Default)]
+pub struct ArtifactStore {
+    conn: Option<Mutex<rusqlite::Connection>>,
+}
+
+impl ArtifactStore {
+    /// A store backed by the database at `path`; artifacts are not recorded
+    /// if it cannot be opened
+    pub fn open(path: &str) -> Self {
+        match Self::open_db(path) {
+            Ok(conn) => Self {
+                conn: Some(Mutex::new(conn)),
+            },
+            Err(e) => {
+                warn!("Artifact store disabled: {e}");
+                Self::default()
+            }
+        }
+    }
+
+    fn open_db(path: &str) -> Result<rusqlite::Connection> {
+        if let Some(parent) = std::path::Path::new(path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = rusqlite::Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS artifacts (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                goal_id TEXT NOT NULL,
+                task_id TEXT NOT NULL,
+                producer TEXT NOT NULL,
+                name TEXT NOT NULL,
+                path TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                sha256 TEXT NOT NULL,
+                size_bytes INTEGER NOT NULL,
+                description TEXT NOT NULL,
+                created_at INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_artifacts_goal ON artifacts(goal_id);",
+        )?;
+        Ok(conn)
+    }
+
+    /// Record an artifact a task produced
+    pub fn record(
+        &self,
+        goal_id: &str,
+        task_id: &str,
+        producer: &str,
+        artifact: &Artifact,
+    ) -> Result<()> {
+        let Some(ref conn) = self.conn else {
+            return Ok(());
+        };
+        let conn = conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        conn.execute(
+            "INSERT INTO artifacts (goal_id, task_id, producer, name, path, kind, sha256,
+                size_bytes, description, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            rusqlite::params![
+                goal_id,
+                task_id,
+                producer,
+                artifact.name,
+                artifact.path,
+                artifact.kind,
+                artifact.sha256,
+                artifact.size_bytes,
+                artifact.description,
+                chrono::Utc::now().timestamp(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Artifacts of a goal's tasks, oldest first
+    pub fn for_goal(&self, goal_id: &str) -> Result<Vec<ArtifactRecord>> {
+        let Some(ref conn) = self.conn else {
+            return Ok(Vec::new());
+        };
+        let conn = conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let mut stmt = conn.prepare(
+            "SELECT goal_id, task_id, producer, name, path, kind, sha256, size_bytes,
+                description, created_at
+             FROM artifacts WHERE goal_id = ?1 ORDER BY id",
+        )?;
+        let rows = stmt.query_map([goal_id], |row| {
+            Ok(ArtifactRecord {
+                goal_id: row.get(0)?,
+                task_id: row.get(1)?,
+                producer: row.get(2)?,
+                name: row.get(3)?,
+                path: row.get(4)?,
+                kind: row.get(5)?,
+                sha256: row.get(6)?,
+                size_bytes: row.get(7)?,
+                description: row.get(8)?,
+                created_at: row.get(9)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_list_by_goal() {
+        let store = ArtifactStore::open(":memory:");
+        let report = Artifact {
+            name: "disk report".into(),
+            path: "/var/lib/aios/reports/disk.md".into(),
+            kind: "report".into(),
+            size_bytes: 2048,
+            ..Default::default()
+        };
+        store
+            .record("goal-1", "task-1", "monitor-agent", &report)
+            .unwrap();
+        store
+            .record("goal-2", "task-9", "monitor-agent", &Artifact::default())
+            .unwrap();
+
+        let artifacts = store.for_goal("goal-1").unwrap();
+        assert_eq!(artifacts.len(), 1);
+        assert_eq!(artifacts[0].path, "/var/lib/aios/reports/disk.md");
+        assert_eq!(artifacts[0].producer, "monitor-agent");
+        assert!(store.for_goal("goal-3").unwrap().is_empty());
+    }
+}
@@ -6,31 +6,227 @@
 //! 3. Routes to appropriate agent or AI model
 //! 4. Records results and updates goal status
 //!
-//! Respects CancellationToken for graceful shutdown.
+//! The loop is event-driven: goal submissions, task results, and agent
+//! heartbeats wake it through an [`AutonomyWaker`], and it keeps ticking
+//! while ready tasks remain. A slow fallback tick covers housekeeping
+//! (dead agents, goal completion) when nothing else happens.
+//!
+//! Every ready task is dispatched in the same tick: to an agent, to a
+//! cluster node, or to one of up to `max_concurrent_tasks` workers (see
+//! [`crate::task_workers`]) that execute tasks concurrently without the
+//! state lock.
+//!
+//! Priority-1 goals preempt the rest ([`PreemptionPolicy`]): their tasks are
+//! dispatched first and, by default, lower-priority tasks on local workers
+//! are paused until no urgent goal is active.
+//!
+//! A failed task whose retry policy allows it ([`crate::task_planner::TaskRetryPolicy`])
+//! is re-queued once its backoff has passed; every retry is logged as a
+//! `task_retry` decision.
+//!
+//! Respects CancellationToken for graceful shutdown. The loop reports its
+//! phases to a heartbeat the watchdog in [`crate::liveness`] checks.
 
 use std::sync::Arc;
-use std::time::Duration;
-use tokio::sync::RwLock;
+use std::time::{Duration, Instant};
+use tokio::sync::{Notify, RwLock};
 use tokio_util::sync::CancellationToken;
-use tracing::{debug, error, info, warn};
+use tracing::{debug, error, info, warn, Instrument};
 
 use crate::context::ContextAssembler;
-use crate::task_planner::IntelligenceLevel;
+use crate::liveness::{write_state, LoopHeartbeat, LoopPhase};
+use crate::proto::api_gateway::PromptSection;
+use crate::task_planner::{FailureKind, IntelligenceLevel};
 use crate::OrchestratorState;
 
 /// Configuration for the autonomy loop
+#[derive(Clone)]
 pub struct AutonomyConfig {
-    /// Tick interval between autonomy loop iterations
+    /// Fallback tick interval used when no wake-up events arrive
     pub tick_interval: Duration,
-    /// Maximum concurrent tasks
+    /// Minimum delay between consecutive ticks (debounces bursts of events)
+    pub min_tick_interval: Duration,
+    /// Maximum tasks the orchestrator executes at once; fewer while the
+    /// CPU is loaded
     pub max_concurrent_tasks: usize,
+    /// What urgent goals do to lower-priority work
+    pub preemption: PreemptionPolicy,
 }
 
 impl Default for AutonomyConfig {
     fn default() -> Self {
         Self {
-            tick_interval: Duration::from_millis(500),
+            tick_interval: Duration::from_secs(5),
+            min_tick_interval: Duration::from_millis(50),
             max_concurrent_tasks: 10,
+            preemption: PreemptionPolicy::default(),
+        }
+    }
+}
+
+/// What happens to lower-priority tasks while an urgent goal is active
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreemptionMode {
+    /// Priority plays no part in dispatch
+    Off,
+    /// Urgent tasks are dispatched first; lower-priority tasks keep running
+    /// and take whatever workers are left
+    Deprioritize,
+    /// Lower-priority tasks are held back, and those running on local
+    /// workers are stopped and marked `preempted`, until no urgent goal is
+    /// active; they then resume from their checkpoints. Tasks already with
+    /// an agent or a cluster node run on.
+    Pause,
+}
+
+impl PreemptionMode {
+    pub fn parse(mode: &str) -> Option<Self> {
+        match mode {
+            "off" => Some(Self::Off),
+            "deprioritize" => Some(Self::Deprioritize),
+            "pause" => Some(Self::Pause),
+            _ => None,
+        }
+    }
+}
+
+/// Preemption policy: goals of priority `urgent_priority` or more urgent
+/// (1 is the most urgent) preempt the rest
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PreemptionPolicy {
+    pub mode: PreemptionMode,
+    pub urgent_priority: i32,
+}
+
+impl Default for PreemptionPolicy {
+    fn default() -> Self {
+        Self {
+            mode: PreemptionMode::Pause,
+            urgent_priority: 1,
+        }
+    }
+}
+
+impl PreemptionPolicy {
+    /// Policy from `AIOS_PREEMPTION` (off, deprioritize or pause) and
+    /// `AIOS_PREEMPT_PRIORITY`
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let mode = match std::env::var("AIOS_PREEMPTION") {
+            Ok(mode) => PreemptionMode::parse(&mode).unwrap_or_else(|| {
+                warn!("Ignoring unknown AIOS_PREEMPTION mode {mode}");
+                default.mode
+            }),
+            Err(_) => default.mode,
+        };
+        Self {
+            mode,
+            urgent_priority: std::env::var("AIOS_PREEMPT_PRIORITY")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.urgent_priority),
+        }
+    }
+
+    /// Whether a goal of `priority` preempts others
+    pub fn is_urgent(&self, priority: i32) -> bool {
+        self.mode != PreemptionMode::Off && (1..=self.urgent_priority).contains(&priority)
+    }
+}
+
+/// Wakes the autonomy loop when new work may be available
+#[derive(Default)]
+pub struct AutonomyWaker {
+    notify: Notify,
+    /// When the oldest wake-up not yet served by a tick was requested
+    pending_since: std::sync::Mutex<Option<Instant>>,
+}
+
+impl AutonomyWaker {
+    /// Request a tick. Wake-ups that arrive while a tick is running are
+    /// coalesced into a single follow-up tick.
+    pub fn wake(&self) {
+        self.pending_since
+            .lock()
+            .unwrap()
+            .get_or_insert_with(Instant::now);
+        self.notify.notify_one();
+    }
+
+    /// Time since the oldest outstanding wake-up, clearing it
+    fn take_pending(&self) -> Option<Duration> {
+        self.pending_since
+            .lock()
+            .unwrap()
+            .take()
+            .map(|t| t.elapsed())
+    }
+}
+
+/// What caused an autonomy tick to run
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum WakeReason {
+    /// An `AutonomyWaker::wake` call
+    Event,
+    /// The previous tick left ready tasks behind
+    Backlog,
+    /// The fallback interval elapsed without events
+    Fallback,
+}
+
+/// Weight of the newest sample in the moving averages
+const METRICS_EWMA_ALPHA: f64 = 0.2;
+
+/// Latency metrics for the autonomy loop
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct LoopMetrics {
+    pub ticks: u64,
+    pub event_ticks: u64,
+    pub backlog_ticks: u64,
+    pub fallback_ticks: u64,
+    /// Tick duration (milliseconds); averages are exponentially weighted
+    pub last_tick_ms: f64,
+    pub avg_tick_ms: f64,
+    pub max_tick_ms: f64,
+    /// Delay between a wake-up request and the start of the tick serving it
+    pub last_wake_latency_ms: f64,
+    pub avg_wake_latency_ms: f64,
+    pub max_wake_latency_ms: f64,
+}
+
+impl LoopMetrics {
+    fn record_tick(
+        &mut self,
+        reason: WakeReason,
+        duration: Duration,
+        wake_latency: Option<Duration>,
+    ) {
+        fn ewma(avg: f64, sample: f64, first: bool) -> f64 {
+            if first {
+                sample
+            } else {
+                avg + METRICS_EWMA_ALPHA * (sample - avg)
+            }
+        }
+
+        self.ticks += 1;
+        match reason {
+            WakeReason::Event => self.event_ticks += 1,
+            WakeReason::Backlog => self.backlog_ticks += 1,
+            WakeReason::Fallback => self.fallback_ticks += 1,
+        }
+
+        let tick_ms = duration.as_secs_f64() * 1000.0;
+        self.avg_tick_ms = ewma(self.avg_tick_ms, tick_ms, self.ticks == 1);
+        self.last_tick_ms = tick_ms;
+        self.max_tick_ms = self.max_tick_ms.max(tick_ms);
+
+        if let Some(latency) = wake_latency {
+            let latency_ms = latency.as_secs_f64() * 1000.0;
+            let first = self.last_wake_latency_ms == 0.0 && self.avg_wake_latency_ms == 0.0;
+            self.avg_wake_latency_ms = ewma(self.avg_wake_latency_ms, latency_ms, first);
+            self.last_wake_latency_ms = latency_ms;
+            self.max_wake_latency_ms = self.max_wake_latency_ms.max(latency_ms);
         }
     }
 }
@@ -40,23 +236,78 @@ pub async fn run_autonomy_loop(
     state: Arc<RwLock<OrchestratorState>>,
     cancel: CancellationToken,
     config: AutonomyConfig,
+    heartbeat: Arc<LoopHeartbeat>,
 ) {
+    heartbeat.enter(LoopPhase::Starting);
+    let (waker, metrics) = {
+        let s = state.read().await;
+        (s.autonomy_waker.clone(), s.autonomy_metrics.clone())
+    };
+
     info!(
-        "Autonomy loop started (tick={}ms)",
+        "Autonomy loop started (event-driven, fallback tick={}ms)",
         config.tick_interval.as_millis()
     );
 
+    let mut backlog = false;
     loop {
+        let reason = if backlog {
+            WakeReason::Backlog
+        } else {
+            tokio::select! {
+                _ = cancel.cancelled() => {
+                    info!("Autonomy loop shutting down gracefully");
+                    break;
+                }
+                _ = waker.notify.notified() => WakeReason::Event,
+                _ = tokio::time::sleep(config.tick_interval) => WakeReason::Fallback,
+            }
+        };
+
+        let wake_latency = waker.take_pending();
+        let started = Instant::now();
+        heartbeat.enter(LoopPhase::Tick);
+        if let Err(e) = autonomy_tick(&state, &config).await {
+            error!("Autonomy tick error: {e}");
+        }
+        metrics
+            .lock()
+            .unwrap()
+            .record_tick(reason, started.elapsed(), wake_latency);
+
+        // Keep going without waiting for an event while tasks the tick may
+        // dispatch are ready and a worker is free; a finishing worker wakes
+        // the loop otherwise
+        let (ready, busy) = {
+            let s = state.read().await;
+            let urgent_active = urgent_goal_active(&s.goal_engine, &config.preemption);
+            (
+                !dispatchable_tasks(
+                    &s.goal_engine,
+                    &s.task_planner,
+                    &config.preemption,
+                    urgent_active,
+                )
+                .is_empty(),
+                s.task_workers.active()
+                    >= crate::task_workers::capacity(
+                        config.max_concurrent_tasks,
+                        crate::read_cpu_percent(),
+                    ),
+            )
+        };
+        backlog = ready && !busy;
+        heartbeat.tick_done();
+        if busy {
+            heartbeat.enter(LoopPhase::Reasoning);
+        }
+
         tokio::select! {
             _ = cancel.cancelled() => {
                 info!("Autonomy loop shutting down gracefully");
                 break;
             }
-            _ = tokio::time::sleep(config.tick_interval) => {
-                if let Err(e) = autonomy_tick(&state, &config).await {
-                    error!("Autonomy tick error: {e}");
-                }
-            }
+            _ = tokio::time::sleep(config.min_tick_interval) => {}
         }
     }
 
@@ -72,6 +323,30 @@ struct AiWorkItem {
     preferred_provider: String,
     messages: Vec<crate::goal_engine::GoalMessage>,
     clients: Arc<crate::clients::ServiceClients>,
+    drain: Arc<crate::shutdown::Drain>,
+    tool_usage: Arc<std::sync::Mutex<crate::tool_usage::ToolUsage>>,
+    /// Whether the task's tool calls run in a staging sandbox
+    staging: crate::staging::StagingContext,
+    /// Workload the task's requests are tagged with
+    workload: &'static str,
+    /// Goal the task's resource usage is charged to
+    charge: Option<crate::usage::Charge>,
+    /// Span of the task's execution, in its goal's trace
+    span: tracing::Span,
+    /// Tool calls completed or under way, kept across restarts
+    checkpoints: Arc<crate::task_checkpoint::TaskCheckpoints>,
+    /// Second-model review of risky plans
+    peer_review: Arc<crate::peer_review::PeerReview>,
+    /// Tool calls are pushed to `StreamTaskEvents` subscribers
+    task_events: crate::task_events::TaskEvents,
+    /// Language the goal's messages are written in
+    language: crate::locale::Language,
+    /// Whether the goal may only call read-only tools
+    read_only: bool,
+    /// Whether the goal opted in to experimental tools
+    experimental_tools: bool,
+    /// Keeps shutdown waiting until the result has been recorded
+    _in_flight: crate::shutdown::InFlight,
 }
 
 /// Configuration for multi-turn reasoning loops.
@@ -81,39 +356,89 @@ struct ReasoningLoopConfig {
     max_rounds: u32,
     /// Token budget cap per task
     max_total_tokens: i32,
+    /// Ask the model to verify that successful tool calls achieved the task
+    /// before it is marked complete
+    reflect: bool,
 }
 
-/// A single round in a multi-turn reasoning conversation.
-#[allow(dead_code)]
-struct ConversationTurn {
-    round: u32,
-    ai_response: String,
-    tool_calls: Vec<ToolCallRequest>,
-    tool_results: Vec<serde_json::Value>,
+impl ReasoningLoopConfig {
+    /// Limits for a task's intelligence level. Levels listed in
+    /// `AIOS_REFLECT_LEVELS` (default "tactical,strategic") get a
+    /// verification pass after their tools run.
+    fn for_level(level: &IntelligenceLevel) -> Self {
+        let reflect_levels = std::env::var("AIOS_REFLECT_LEVELS")
+            .unwrap_or_else(|_| "tactical,strategic".to_string());
+        Self {
+            max_rounds: match level {
+                IntelligenceLevel::Reactive | IntelligenceLevel::Operational => 1,
+                IntelligenceLevel::Tactical => 3,
+                IntelligenceLevel::Strategic => 5,
+            },
+            max_total_tokens: match level {
+                IntelligenceLevel::Reactive | IntelligenceLevel::Operational => 2048,
+                IntelligenceLevel::Tactical => 8192,
+                IntelligenceLevel::Strategic => 16384,
+            },
+            reflect: reflect_levels
+                .split(',')
+                .any(|l| l.trim() == level.as_str()),
+        }
+    }
 }
 
+/// A single round in a multi-turn reasoning conversation.
+type ConversationTurn = crate::shutdown::CheckpointTurn;
+
 /// Run an iterative reasoning loop: observe→think→act.
 ///
 /// Instead of giving the AI ONE shot and marking the task done,
 /// this function lets the AI see tool results and decide whether
 /// to call more tools or signal completion.
+///
+/// A task checkpointed by a previous shutdown resumes at the round it
+/// reached. Returns None if shutdown interrupts an inference; the completed
+/// rounds are then checkpointed and the task is left in progress.
 async fn run_reasoning_loop(
     work: &AiWorkItem,
     config: &ReasoningLoopConfig,
-) -> (AiInferenceResult, ToolExecutionResult) {
+) -> Option<(AiInferenceResult, ToolExecutionResult)> {
     let mut conversation: Vec<ConversationTurn> = Vec::new();
     let mut total_tokens_used: i32 = 0;
     let mut final_result: Option<AiInferenceResult> = None;
+    let mut compressions = Vec::new();
+    // Gateway session of a multi-round loop; once open, later rounds send
+    // only the newest tool results instead of the whole prompt
+    let mut session: Option<String> = None;
     let mut final_tool_exec = ToolExecutionResult {
         tool_results: Vec::new(),
         all_succeeded: true,
     };
+    let mut start_round = 0;
+    let mut reviews = Vec::new();
+    // Set when a peer review sent the plan to an operator
+    let mut escalated = false;
+
+    if let Some(checkpoint) = work.drain.take_checkpoint(&work.task_id) {
+        info!(
+            "Resuming task {} at round {} from shutdown checkpoint",
+            work.task_id,
+            checkpoint.next_round + 1
+        );
+        start_round = checkpoint.next_round;
+        total_tokens_used = checkpoint.tokens_used;
+        final_tool_exec.all_succeeded = checkpoint.all_succeeded;
+        final_tool_exec.tool_results = checkpoint
+            .turns
+            .iter()
+            .flat_map(|turn| turn.tool_results.clone())
+            .collect();
+        conversation = checkpoint.turns;
+    }
 
-    for round in 0..config.max_rounds {
+    for round in start_round..config.max_rounds {
         // Build prompt for this round
         let prompt = build_round_prompt(work, round, &conversation);
 
-        let backend = AiBackend::ApiGateway;
         info!(
             "Reasoning round {}/{} for task {} (tokens so far: {})",
             round + 1,
@@ -122,17 +447,30 @@ async fn run_reasoning_loop(
             total_tokens_used
         );
 
-        let result = execute_ai_task(
-            &work.clients,
-            &prompt,
-            work.level.as_str(),
-            backend,
-            &work.preferred_provider,
-            &work.messages,
+        let Some(mut result) = unless_interrupted(
+            work,
+            infer_round(
+                work,
+                &prompt,
+                &mut session,
+                conversation.last(),
+                config.max_rounds > 1,
+            ),
         )
-        .await;
+        .await
+        else {
+            checkpoint_interrupted(
+                work,
+                round,
+                total_tokens_used,
+                final_tool_exec.all_succeeded,
+                conversation,
+            );
+            return None;
+        };
 
         total_tokens_used += result.tokens_used;
+        compressions.append(&mut result.compressions);
 
         // Check if we've exceeded the token budget
         if total_tokens_used > config.max_total_tokens {
@@ -156,13 +494,27 @@ async fn run_reasoning_loop(
         }
 
         // If parsing returned no tool calls but text is non-empty, try correction
-        let mut result = result;
         if result.tool_calls.is_empty() && !result.response_text.trim().is_empty() && result.success
         {
             // Try JSON correction: ask the model to fix its output
-            let corrected = try_json_correction(work, &result.response_text).await;
-            if let Some(corrected_result) = corrected {
+            let Some(corrected) = unless_interrupted(
+                work,
+                try_json_correction(work, &result.response_text, session.as_deref()),
+            )
+            .await
+            else {
+                checkpoint_interrupted(
+                    work,
+                    round,
+                    total_tokens_used,
+                    final_tool_exec.all_succeeded,
+                    conversation,
+                );
+                return None;
+            };
+            if let Some(mut corrected_result) = corrected {
                 total_tokens_used += corrected_result.tokens_used;
+                compressions.append(&mut corrected_result.compressions);
                 result = corrected_result;
             }
         }
@@ -173,23 +525,66 @@ async fn run_reasoning_loop(
             break;
         }
 
+        // A second model checks risky plans before they run
+        let Some(review) = unless_interrupted(work, review_plan(work, &result)).await else {
+            checkpoint_interrupted(
+                work,
+                round,
+                total_tokens_used,
+                final_tool_exec.all_succeeded,
+                conversation,
+            );
+            return None;
+        };
+        if let Some(review) = review {
+            total_tokens_used += review.tokens_used;
+            let agreed = review.agreed();
+            if !agreed {
+                work.peer_review.escalate(review.clone());
+            }
+            reviews.push(review);
+            if !agreed {
+                escalated = true;
+                final_result = Some(result);
+                break;
+            }
+        }
+
         // Execute tool calls
-        let tool_exec =
-            execute_tool_calls_unlocked(&work.clients, &work.task_id, &result).await;
+        let tool_exec = execute_tool_calls_unlocked(
+            &work.clients,
+            &work.task_id,
+            &work.staging,
+            &work.checkpoints,
+            work.read_only,
+            work.experimental_tools,
+            &result,
+        )
+        .await;
+        publish_tool_calls(
+            &work.task_events,
+            &work.goal_id,
+            &work.task_id,
+            &result,
+            &tool_exec,
+        );
+        record_tool_usage(
+            &work.tool_usage,
+            &work.task.description,
+            &result,
+            &tool_exec,
+        );
 
         // Accumulate tool results for the next round
         let turn = ConversationTurn {
             round,
             ai_response: result.response_text.clone(),
-            tool_calls: result.tool_calls.clone(),
             tool_results: tool_exec.tool_results.clone(),
         };
 
         // Merge tool execution results
         final_tool_exec.all_succeeded = final_tool_exec.all_succeeded && tool_exec.all_succeeded;
-        final_tool_exec
-            .tool_results
-            .extend(tool_exec.tool_results);
+        final_tool_exec.tool_results.extend(tool_exec.tool_results);
 
         conversation.push(turn);
 
@@ -212,26 +607,102 @@ async fn run_reasoning_loop(
     }
 
     // Use the last result, or create a failure if none
-    let result = final_result.unwrap_or(AiInferenceResult {
+    let mut result = final_result.unwrap_or(AiInferenceResult {
         success: false,
         response_text: "Reasoning loop completed without producing a result".to_string(),
         tool_calls: vec![],
         model_used: "none".to_string(),
         tokens_used: total_tokens_used,
+        compressions: Vec::new(),
+        verdict: None,
+        reviews: Vec::new(),
     });
+    result.compressions = compressions;
+    result.reviews = reviews;
+
+    // Verify the outcome unless the model already declared completion
+    // after seeing the results
+    if config.reflect
+        && !escalated
+        && final_tool_exec.all_succeeded
+        && !final_tool_exec.tool_results.is_empty()
+        && !is_completion_signal(&result.response_text)
+    {
+        // An interrupted verification is skipped: the tools have already run
+        if let Some(Some((verdict, tokens_used))) = unless_interrupted(
+            work,
+            reflect_on_results(work, session.as_deref(), &conversation),
+        )
+        .await
+        {
+            result.tokens_used += tokens_used;
+            result.verdict = Some(verdict);
+        }
+    }
+
+    if let Some(id) = &session {
+        end_gateway_session(&work.clients, id).await;
+    }
 
-    (result, final_tool_exec)
+    // Drop the checkpoint of a resumed task now that it has finished
+    if start_round > 0 {
+        work.drain.finish(&work.task_id);
+    }
+    // Failed calls keep their progress for the task's retry, and an
+    // escalated task's for when it is approved
+    if final_tool_exec.all_succeeded && !escalated {
+        work.checkpoints.finish(&work.clients, &work.task_id).await;
+    }
+
+    Some((result, final_tool_exec))
+}
+
+/// Await `fut` unless the shutdown drain deadline passes first
+async fn unless_interrupted<T>(
+    work: &AiWorkItem,
+    fut: impl std::future::Future<Output = T>,
+) -> Option<T> {
+    tokio::select! {
+        biased;
+        _ = work.drain.interrupted().cancelled() => None,
+        out = fut => Some(out),
+    }
+}
+
+/// Save the completed rounds of a reasoning loop interrupted by shutdown so
+/// the task resumes at `next_round` after restart
+fn checkpoint_interrupted(
+    work: &AiWorkItem,
+    next_round: u32,
+    tokens_used: i32,
+    all_succeeded: bool,
+    turns: Vec<ConversationTurn>,
+) {
+    info!(
+        "Task {} interrupted by shutdown before round {} completed",
+        work.task_id,
+        next_round + 1
+    );
+    if turns.is_empty() {
+        // Nothing done yet; the task simply runs again after restart
+        return;
+    }
+    work.drain.checkpoint(&crate::shutdown::TaskCheckpoint {
+        task_id: work.task_id.clone(),
+        goal_id: work.goal_id.clone(),
+        next_round,
+        tokens_used,
+        all_succeeded,
+        turns,
+        saved_at: chrono::Utc::now().timestamp(),
+    });
 }
 
 /// Build the prompt for a given reasoning round.
 ///
 /// - Round 0: Original task description (existing format)
 /// - Round 1+: Task description + previous tool results + continuation instructions
-fn build_round_prompt(
-    work: &AiWorkItem,
-    round: u32,
-    conversation: &[ConversationTurn],
-) -> String {
+fn build_round_prompt(work: &AiWorkItem, round: u32, conversation: &[ConversationTurn]) -> String {
     if round == 0 || conversation.is_empty() {
         // First round: just the task description (existing format)
         return work.task.description.clone();
@@ -240,21 +711,29 @@ fn build_round_prompt(
     // Subsequent rounds: include task + tool results from previous rounds
     let mut prompt = format!("Task: {}\n\n", work.task.description);
     prompt.push_str("Previous tool results:\n");
+    prompt.push_str(&render_tool_results(conversation));
+    prompt.push_str(NEXT_STEP_INSTRUCTIONS);
+    prompt
+}
+
+/// What the AI should do after seeing tool results
+const NEXT_STEP_INSTRUCTIONS: &str = "\nBased on the results above, decide what to do next:\n\
+     - If more information is needed, call additional tools.\n\
+     - If the task is complete, respond with: {\"done\": true, \"summary\": \"brief summary of what was accomplished\"}\n\
+     - Respond with ONLY valid JSON.\n";
 
+/// One line per tool result of the given rounds, outputs condensed
+fn render_tool_results(conversation: &[ConversationTurn]) -> String {
+    let mut prompt = String::new();
     for turn in conversation {
         for tr in &turn.tool_results {
             let tool_name = tr.get("tool").and_then(|v| v.as_str()).unwrap_or("unknown");
             let success = tr.get("success").and_then(|v| v.as_bool()).unwrap_or(false);
             if success {
                 let output = tr.get("output").unwrap_or(&serde_json::Value::Null);
-                // Truncate large outputs to avoid exceeding context
-                let output_str = serde_json::to_string(output).unwrap_or_default();
-                let truncated = if output_str.len() > 1000 {
-                    format!("{}...(truncated)", &output_str[..1000])
-                } else {
-                    output_str
-                };
-                prompt.push_str(&format!("- {tool_name}: {truncated}\n"));
+                // Condense large outputs to avoid exceeding context
+                let condensed = condense_tool_output(output, MAX_PROMPT_TOOL_OUTPUT_CHARS);
+                prompt.push_str(&format!("- {tool_name}: {condensed}\n"));
             } else {
                 let error = tr
                     .get("error")
@@ -264,15 +743,81 @@ fn build_round_prompt(
             }
         }
     }
+    prompt
+}
 
-    prompt.push_str(
-        "\nBased on the results above, decide what to do next:\n\
-         - If more information is needed, call additional tools.\n\
-         - If the task is complete, respond with: {\"done\": true, \"summary\": \"brief summary of what was accomplished\"}\n\
-         - Respond with ONLY valid JSON.\n",
-    );
+/// Maximum characters of a single tool output placed into a reasoning prompt.
+const MAX_PROMPT_TOOL_OUTPUT_CHARS: usize = 2000;
 
-    prompt
+/// Render a tool output for AI context within `max_chars`.
+///
+/// Small outputs are passed through verbatim. Larger ones keep their JSON
+/// shape but long strings are reduced to head and tail excerpts and long
+/// arrays to their first items, with a note pointing at `output.page` when
+/// the tool service retained the full output.
+fn condense_tool_output(output: &serde_json::Value, max_chars: usize) -> String {
+    let full = serde_json::to_string(output).unwrap_or_default();
+    let full_chars = full.chars().count();
+    if full_chars <= max_chars {
+        return full;
+    }
+
+    let mut condensed = output.clone();
+    condense_value(&mut condensed, (max_chars / 4).max(40));
+    let text = serde_json::to_string(&condensed).unwrap_or_default();
+    let mut text = if text.chars().count() > max_chars {
+        let head: String = text.chars().take(max_chars).collect();
+        format!("{head}...(truncated)")
+    } else {
+        text
+    };
+
+    text.push_str(&format!(" [condensed from {full_chars} chars"));
+    if let Some(handle) = output
+        .get("_truncated")
+        .and_then(|t| t.get("page_handle"))
+        .and_then(|h| h.as_str())
+    {
+        text.push_str(&format!(
+            "; full output available via output.page with handle \"{handle}\""
+        ));
+    }
+    text.push(']');
+    text
+}
+
+/// Shorten long strings to head/tail excerpts and long arrays to their first items.
+fn condense_value(value: &mut serde_json::Value, string_budget: usize) {
+    const MAX_ARRAY_ITEMS: usize = 10;
+    match value {
+        serde_json::Value::String(s) => {
+            let chars = s.chars().count();
+            if chars > string_budget {
+                let head_len = string_budget * 2 / 3;
+                let tail_len = string_budget - head_len;
+                let head: String = s.chars().take(head_len).collect();
+                let tail: String = s.chars().skip(chars - tail_len).collect();
+                let omitted = chars - head_len - tail_len;
+                *s = format!("{head}…[{omitted} chars omitted]…{tail}");
+            }
+        }
+        serde_json::Value::Array(items) => {
+            if items.len() > MAX_ARRAY_ITEMS {
+                let dropped = items.len() - MAX_ARRAY_ITEMS;
+                items.truncate(MAX_ARRAY_ITEMS);
+                items.push(serde_json::Value::String(format!("…{dropped} more items")));
+            }
+            for item in items.iter_mut() {
+                condense_value(item, string_budget);
+            }
+        }
+        serde_json::Value::Object(map) => {
+            for item in map.values_mut() {
+                condense_value(item, string_budget);
+            }
+        }
+        _ => {}
+    }
 }
 
 /// Check if the AI response signals task completion.
@@ -290,6 +835,7 @@ fn is_completion_signal(response_text: &str) -> bool {
 async fn try_json_correction(
     work: &AiWorkItem,
     original_response: &str,
+    session: Option<&str>,
 ) -> Option<AiInferenceResult> {
     let preview: String = original_response.chars().take(300).collect();
     let correction_prompt = format!(
@@ -308,33 +854,292 @@ async fn try_json_correction(
         work.task_id
     );
 
+    // Within a session the correction becomes part of the conversation
+    let in_session = match session {
+        Some(id) => {
+            try_api_gateway_infer_with_provider(
+                &work.clients,
+                &[pinned_section(correction_prompt.as_str())],
+                "",
+                &work.preferred_provider,
+                &work.task.model_class,
+                id,
+                "",
+                "",
+            )
+            .await
+        }
+        None => None,
+    };
+    let result = match in_session {
+        Some(result) => result,
+        None => {
+            execute_ai_task(
+                &work.clients,
+                &correction_prompt,
+                work.level.as_str(),
+                AiBackend::ApiGateway,
+                &work.preferred_provider,
+                &work.task.model_class,
+                &work.messages,
+                "",
+                &work.tool_usage,
+                work.language,
+                work.experimental_tools,
+            )
+            .await
+        }
+    };
+
+    if !result.tool_calls.is_empty() || is_completion_signal(&result.response_text) {
+        info!("JSON correction succeeded for task {}", work.task_id);
+        Some(result)
+    } else {
+        info!("JSON correction also failed for task {}", work.task_id);
+        None
+    }
+}
+
+/// Run one reasoning round. Within an open gateway session only the newest
+/// tool results are sent; otherwise, or when the gateway no longer has the
+/// session, the full prompt is sent, opening a new session if `use_session`.
+async fn infer_round(
+    work: &AiWorkItem,
+    prompt: &str,
+    session: &mut Option<String>,
+    last_turn: Option<&ConversationTurn>,
+    use_session: bool,
+) -> AiInferenceResult {
+    if let (Some(id), Some(turn)) = (session.as_deref(), last_turn) {
+        let results = render_tool_results(std::slice::from_ref(turn)) + NEXT_STEP_INSTRUCTIONS;
+        if let Some(result) = try_api_gateway_infer_with_provider(
+            &work.clients,
+            &[pinned_section(results)],
+            "",
+            &work.preferred_provider,
+            &work.task.model_class,
+            id,
+            "tool_results",
+            "",
+        )
+        .await
+        {
+            return result;
+        }
+        info!("Gateway session {id} unavailable, resending the full prompt");
+    }
+
+    *session = use_session.then(|| format!("{}-{}", work.task_id, uuid::Uuid::new_v4()));
     let result = execute_ai_task(
         &work.clients,
-        &correction_prompt,
+        prompt,
         work.level.as_str(),
         AiBackend::ApiGateway,
         &work.preferred_provider,
+        &work.task.model_class,
         &work.messages,
+        session.as_deref().unwrap_or(""),
+        &work.tool_usage,
+        work.language,
+        work.experimental_tools,
     )
     .await;
+    if !result.success {
+        *session = None;
+    }
+    result
+}
 
-    if !result.tool_calls.is_empty() || is_completion_signal(&result.response_text) {
-        info!("JSON correction succeeded for task {}", work.task_id);
-        Some(result)
+/// Ask the model whether the executed tool calls achieved the task.
+/// Within a gateway session only the newest tool results are sent.
+/// Returns the verdict and the tokens spent, or None if no usable verdict
+/// came back.
+async fn reflect_on_results(
+    work: &AiWorkItem,
+    session: Option<&str>,
+    conversation: &[ConversationTurn],
+) -> Option<(Verdict, i32)> {
+    const QUESTION: &str = "\nBefore this task is marked complete, verify the outcome: \
+         did these results actually achieve the task, not just run without errors? \
+         Respond with ONLY a JSON object: {\"achieved\": true or false, \
+         \"reason\": \"why\", \"next_steps\": [\"what remains to be done, if anything\"]}\n";
+
+    let (prompt, system_prompt, session_id, turn_kind) = match (session, conversation.last()) {
+        (Some(id), Some(turn)) => (
+            render_tool_results(std::slice::from_ref(turn)) + QUESTION,
+            String::new(),
+            id,
+            "tool_results",
+        ),
+        _ => (
+            format!(
+                "Task: {}\n\nTool results:\n{}{QUESTION}",
+                work.task.description,
+                render_tool_results(conversation)
+            ),
+            "You are aiOS, reviewing whether executed system actions achieved their task."
+                .to_string()
+                + &work.language.prompt_instruction(),
+            "",
+            "",
+        ),
+    };
+
+    let result = try_api_gateway_infer_with_provider(
+        &work.clients,
+        &[pinned_section(prompt)],
+        &system_prompt,
+        &work.preferred_provider,
+        &work.task.model_class,
+        session_id,
+        turn_kind,
+        VERDICT_SCHEMA,
+    )
+    .await?;
+    match parse_verdict(&result.response_text) {
+        Some(verdict) => Some((verdict, result.tokens_used)),
+        None => {
+            debug!("Task {}: unusable verification response", work.task_id);
+            None
+        }
+    }
+}
+
+/// Have a second model review the plan when it calls a risky tool, or
+/// object on the approval policy's behalf when a rule sends the plan to an
+/// operator. Returns None when the plan needs no review; a reviewer that
+/// cannot be reached or gives no usable answer counts as an objection.
+async fn review_plan(
+    work: &AiWorkItem,
+    result: &AiInferenceResult,
+) -> Option<crate::peer_review::Review> {
+    if !result.success || result.tool_calls.is_empty() {
+        return None;
+    }
+    let calls: Vec<(&str, &[u8])> = result
+        .tool_calls
+        .iter()
+        .map(|tc| (tc.tool_name.as_str(), tc.input_json.as_slice()))
+        .collect();
+    let agent = if work.task.assigned_agent.is_empty() {
+        "autonomy-loop"
     } else {
-        info!("JSON correction also failed for task {}", work.task_id);
-        None
+        work.task.assigned_agent.as_str()
+    };
+    let planned = work
+        .peer_review
+        .plan_to_review(&work.clients, &work.task_id, agent, &calls)
+        .await?;
+
+    let planner_reasoning = extract_json_from_text(&result.response_text)
+        .and_then(|v| {
+            v.get("reasoning")
+                .and_then(|r| r.as_str())
+                .map(String::from)
+        })
+        .unwrap_or_default();
+    let planner = crate::peer_review::Opinion {
+        model: result.model_used.clone(),
+        approve: true,
+        reasoning: planner_reasoning.clone(),
+        concerns: Vec::new(),
+    };
+    let review = |calls, reviewer, tokens_used| crate::peer_review::Review {
+        task_id: work.task_id.clone(),
+        goal_id: work.goal_id.clone(),
+        calls,
+        planner,
+        reviewer,
+        created_at: chrono::Utc::now().timestamp(),
+        tokens_used,
+        escalate_at: 0,
+        escalate_to: Vec::new(),
+        escalated: false,
+        deny_at: 0,
+    };
+
+    if let Some(rule) = work.peer_review.operator_rule(&planned) {
+        info!(
+            "Task {}: approval rule {rule} sends the plan to an operator",
+            work.task_id
+        );
+        let reviewer = crate::peer_review::Opinion {
+            model: crate::approvals::POLICY_REVIEWER.to_string(),
+            approve: false,
+            reasoning: format!(
+                "Approval rule '{rule}' requires an operator to approve these calls"
+            ),
+            concerns: Vec::new(),
+        };
+        return Some(review(planned, reviewer, 0));
+    }
+    let prompt = work
+        .peer_review
+        .prompt(&work.task.description, &planned, &planner_reasoning);
+    let provider = work.peer_review.reviewer_provider(&result.model_used);
+    info!(
+        "Task {}: peer review of a risky plan by {provider}",
+        work.task_id
+    );
+    let response = try_api_gateway_infer_with_provider(
+        &work.clients,
+        &[pinned_section(prompt)],
+        crate::peer_review::REVIEW_SYSTEM_PROMPT,
+        &provider,
+        work.peer_review
+            .reviewer_model_class(&work.task.model_class),
+        "",
+        "",
+        crate::peer_review::REVIEW_SCHEMA,
+    )
+    .await;
+    let tokens_used = response.as_ref().map_or(0, |r| r.tokens_used);
+    let reviewer = response
+        .as_ref()
+        .and_then(|r| {
+            let verdict: crate::peer_review::ReviewVerdict =
+                serde_json::from_value(extract_json_from_text(&r.response_text)?).ok()?;
+            Some(verdict.into_opinion(&r.model_used))
+        })
+        .unwrap_or_else(|| crate::peer_review::Opinion {
+            model: provider.clone(),
+            approve: false,
+            reasoning: "The reviewing model could not be reached or gave no usable answer"
+                .to_string(),
+            concerns: Vec::new(),
+        });
+
+    Some(review(planned, reviewer, tokens_used))
+}
+
+/// Release a gateway session once its reasoning loop is over
+async fn end_gateway_session(clients: &crate::clients::ServiceClients, session_id: &str) {
+    match clients.api_gateway().await {
+        Ok(mut client) => {
+            let request = tonic::Request::new(crate::proto::api_gateway::EndSessionRequest {
+                session_id: session_id.to_string(),
+            });
+            if let Err(e) = client.end_session(request).await {
+                debug!("Failed to end gateway session {session_id}: {e}");
+            }
+        }
+        Err(e) => debug!("Cannot connect to API gateway: {e}"),
     }
 }
 
 /// Single tick of the autonomy loop
 async fn autonomy_tick(
     state_arc: &Arc<RwLock<OrchestratorState>>,
-    _config: &AutonomyConfig,
+    config: &AutonomyConfig,
 ) -> anyhow::Result<()> {
     // ── Phase 1: Hold write lock for decomposition + task selection ──
-    let ai_work = {
-        let mut state = state_arc.write().await;
+    let local_work = {
+        let mut state = write_state(state_arc, "autonomy.tick").await;
+
+        // Shutting down: leave remaining tasks for after the restart
+        if state.drain.is_draining() {
+            return Ok(());
+        }
 
         // 1. Check goal engine for active goals
         let active_goals = state.goal_engine.active_goal_count();
@@ -351,15 +1156,31 @@ async fn autonomy_tick(
             let tasks = state.goal_engine.get_goal_tasks(&goal.id);
             if tasks.is_empty() {
                 info!("Decomposing pending goal {} into tasks", goal.id);
-                match state
-                    .task_planner
-                    .decompose_goal(&goal.id, &goal.description)
-                    .await
+                let workload = crate::workload::for_goal(&state.goal_engine, &goal.id);
+                let charge = crate::usage::Charge::new(&state.usage, &state.goal_engine, &goal.id);
+                let span = tracing::info_span!("decompose_goal", goal_id = %goal.id);
+                state.traces.attach(&state.goal_engine, &goal.id, &span);
+                match crate::workload::scope(
+                    workload,
+                    crate::usage::scope(
+                        charge,
+                        state
+                            .task_planner
+                            .decompose_goal(&goal.id, &goal.description)
+                            .instrument(span),
+                    ),
+                )
+                .await
                 {
                     Ok(new_tasks) => {
                         let task_count = new_tasks.len();
                         state.goal_engine.add_tasks(&goal.id, new_tasks);
-                        state.goal_engine.update_status(&goal.id, "in_progress");
+                        state.goal_engine.update_status(
+                            &goal.id,
+                            "in_progress",
+                            &format!("decomposed into {task_count} tasks"),
+                            "autonomy",
+                        );
                         info!("Goal {} decomposed into {task_count} tasks", goal.id);
                     }
                     Err(e) => {
@@ -368,18 +1189,37 @@ async fn autonomy_tick(
                 }
             } else {
                 // Tasks already exist (from submit_goal handler) — advance to in_progress
-                state.goal_engine.update_status(&goal.id, "in_progress");
+                state.goal_engine.update_status(
+                    &goal.id,
+                    "in_progress",
+                    "tasks already planned",
+                    "autonomy",
+                );
             }
         }
 
-        // 3. Get next unblocked tasks from task planner (batch for parallel dispatch)
-        let max_parallel = _config.max_concurrent_tasks.min(3); // cap parallel AI at 3
-        let next_tasks: Vec<_> = state
-            .task_planner
-            .next_tasks(max_parallel)
-            .into_iter()
-            .cloned()
-            .collect();
+        // 3. Get every unblocked task; agents take what they can, free
+        //    workers the rest
+        requeue_due_retries(&mut state);
+        let urgent_active = apply_preemption(&mut state, &config.preemption);
+        let policy = config.preemption;
+        let mut next_tasks: Vec<_> = dispatchable_tasks(
+            &state.goal_engine,
+            &state.task_planner,
+            &policy,
+            urgent_active,
+        )
+        .into_iter()
+        .cloned()
+        .collect();
+        // Tasks of urgent goals go first, then those a person submitted
+        next_tasks.sort_by_key(|t| {
+            (
+                !is_urgent_task(&state.goal_engine, &policy, t),
+                crate::workload::for_goal(&state.goal_engine, &t.goal_id)
+                    != crate::workload::INTERACTIVE,
+            )
+        });
         if next_tasks.is_empty() {
             // No pending tasks — drop lock and skip to Phase 4 (housekeeping)
             drop(state);
@@ -387,236 +1227,460 @@ async fn autonomy_tick(
             return Ok(());
         }
 
-        // Use first task for the existing single-task path; remaining tasks
-        // will be dispatched in parallel in Phase 2.
-        let task = next_tasks[0].clone();
-        let remaining_tasks: Vec<_> = next_tasks[1..].to_vec();
+        let capacity =
+            crate::task_workers::capacity(config.max_concurrent_tasks, crate::read_cpu_percent());
+        let mut free_workers = capacity.saturating_sub(state.task_workers.active());
+        let mut local_work = Vec::new();
+        for task in next_tasks {
+            if let Some(work) = dispatch_task(&mut state, task, free_workers > 0).await {
+                free_workers -= 1;
+                local_work.push(work);
+            }
+        }
+        if free_workers == 0 {
+            debug!("All {capacity} task workers busy");
+        }
+        local_work
+    }; // ── Write lock dropped here ──
 
-        let task_id = task.id.clone();
-        let goal_id = task.goal_id.clone();
-        let level = IntelligenceLevel::from_str(&task.intelligence_level);
+    // ── Phase 2: Start a worker per task, each running WITHOUT the write lock ──
+    for work in local_work {
+        start_worker(state_arc, work).await;
+    }
 
-        // Mark the first task as in-progress (remaining tasks are marked
-        // later if we reach the parallel AI dispatch path)
-        state.task_planner.mark_in_progress(&task_id);
-        state
-            .goal_engine
-            .update_task_status(&goal_id, &task_id, "in_progress");
+    // ── Phase 4: Housekeeping (dead agent recovery + goal completion) ──
+    run_housekeeping(state_arc).await;
 
-        // 4. Route task via agent router or handle directly
-        let agent_id = state.agent_router.route_task(&task);
+    Ok(())
+}
 
-        if let Some(ref agent_id) = agent_id {
-            info!("Dispatching task {task_id} to agent {agent_id}");
-            state.agent_router.assign_task(agent_id, &task_id);
+/// Whether `task` belongs to a goal that preempts others under `policy`
+fn is_urgent_task(
+    goals: &crate::goal_engine::GoalEngine,
+    policy: &PreemptionPolicy,
+    task: &crate::proto::common::Task,
+) -> bool {
+    goals
+        .goal_priority(&task.goal_id)
+        .is_some_and(|p| policy.is_urgent(p))
+}
 
-            state.decision_logger.log_decision(
-                "task_routing",
-                &[agent_id.clone()],
-                "agent_dispatch",
-                &format!(
-                    "Task {task_id} dispatched to agent {agent_id} (level: {})",
-                    level.as_str()
-                ),
-                level.as_str(),
-                "heuristic",
-            );
+/// Whether an urgent goal is active under a policy that preempts for it
+fn urgent_goal_active(goals: &crate::goal_engine::GoalEngine, policy: &PreemptionPolicy) -> bool {
+    policy.mode != PreemptionMode::Off && goals.urgent_goal_active(policy.urgent_priority)
+}
 
-            // Agent polls via GetAssignedTask, executes, reports via ReportTaskResult.
-            // Dead agent recovery at the end of this tick handles timeout.
-            return Ok(());
-        }
+/// Ready tasks a tick may dispatch: under `pause`, only those of urgent
+/// goals while one is active
+fn dispatchable_tasks<'a>(
+    goals: &crate::goal_engine::GoalEngine,
+    planner: &'a crate::task_planner::TaskPlanner,
+    policy: &PreemptionPolicy,
+    urgent_active: bool,
+) -> Vec<&'a crate::proto::common::Task> {
+    planner
+        .next_tasks(usize::MAX)
+        .into_iter()
+        .filter(|t| {
+            !urgent_active
+                || policy.mode != PreemptionMode::Pause
+                || is_urgent_task(goals, policy, t)
+        })
+        .collect()
+}
 
-        // No local agent matched — try cluster routing if enabled
-        if std::env::var("AIOS_CLUSTER_ENABLED").unwrap_or_default() == "true" {
-            let cluster_guard = state.cluster.read().await;
-            if let Some(remote_node_id) =
-                state.agent_router.route_task_to_node(&task, &cluster_guard)
-            {
-                drop(cluster_guard);
-                info!("Routing task {task_id} to remote node {remote_node_id}");
-
-                let mut remote = crate::remote_exec::RemoteExecutor::new();
-                match remote
-                    .submit_remote_goal(
-                        &remote_node_id,
-                        &task.description,
-                        5, // default priority
-                        &format!("cluster:{}", task_id),
-                    )
-                    .await
-                {
-                    Ok(remote_goal_id) => {
-                        info!(
-                            "Task {task_id} submitted to remote node {remote_node_id} as goal {remote_goal_id}"
-                        );
-                        state.task_planner.complete_task(&task_id, Vec::new());
-                        state
-                            .goal_engine
-                            .update_task_status(&goal_id, &task_id, "completed");
-                        state.decision_logger.log_decision(
-                            "task_routing",
-                            &[remote_node_id],
-                            "cluster_dispatch",
-                            &format!("Task {task_id} routed to remote cluster node"),
-                            level.as_str(),
-                            "cluster",
-                        );
-                        return Ok(());
-                    }
-                    Err(e) => {
-                        warn!("Remote dispatch failed for {task_id}: {e}, falling back to AI inference");
-                    }
-                }
-            }
+/// Apply the preemption policy: under `pause`, stop the lower-priority
+/// tasks running on local workers while an urgent goal is active; once none
+/// is, re-queue every preempted task. Returns whether an urgent goal is
+/// active.
+fn apply_preemption(state: &mut OrchestratorState, policy: &PreemptionPolicy) -> bool {
+    let urgent_active = urgent_goal_active(&state.goal_engine, policy);
+
+    if !urgent_active {
+        for task_id in state.task_planner.restore_preempted() {
+            let Some(goal_id) = state
+                .task_planner
+                .get_task(&task_id)
+                .map(|t| t.goal_id.clone())
+            else {
+                continue;
+            };
+            state.goal_engine.update_task_status(
+                &goal_id,
+                &task_id,
+                "pending",
+                "no urgent goal active",
+                "autonomy",
+            );
+            info!("Restored preempted task {task_id}");
         }
+        return false;
+    }
 
-        // ── Phase 1.5: Try heuristic execution (no AI needed) ──
-        // For well-structured tasks that map to a single tool with extractable
-        // parameters, execute directly without AI inference. This makes aiOS
-        // resilient to API outages and faster for simple tasks.
-        let clients_for_heuristic = state.clients.clone();
-        if let Some(heuristic_calls) = try_heuristic_execution(&task) {
+    if policy.mode == PreemptionMode::Pause {
+        let paused: Vec<crate::proto::common::Task> = state
+            .task_workers
+            .running()
+            .iter()
+            .filter_map(|id| state.task_planner.get_task(id))
+            .filter(|t| !is_urgent_task(&state.goal_engine, policy, t))
+            .cloned()
+            .collect();
+        for task in &paused {
+            state.task_planner.preempt_task(&task.id);
+            state.goal_engine.update_task_status(
+                &task.goal_id,
+                &task.id,
+                "preempted",
+                "paused for an urgent goal",
+                "autonomy",
+            );
+        }
+        if !paused.is_empty() {
+            let ids: Vec<String> = paused.into_iter().map(|t| t.id).collect();
+            state.task_workers.cancel(&ids);
             info!(
-                "Heuristic execution: {} tool calls for task {task_id} (bypassing AI)",
-                heuristic_calls.len()
+                "Paused {} lower-priority tasks for urgent goals: {ids:?}",
+                ids.len()
             );
+        }
+    }
+    true
+}
 
-            state.decision_logger.log_decision(
-                "task_routing",
-                &[task_id.clone()],
-                "heuristic_execution",
-                &format!(
-                    "Task '{}' executed via heuristic (no AI inference needed)",
-                    task.description
-                ),
-                level.as_str(),
-                "heuristic",
-            );
+/// A task the orchestrator executes itself
+struct LocalWork {
+    work: AiWorkItem,
+    /// Tool calls to make directly instead of reasoning, when the task
+    /// maps onto them without a model
+    heuristic: Option<AiInferenceResult>,
+}
 
-            // Build a synthetic AI result from the heuristic tool calls
-            let heuristic_result = AiInferenceResult {
-                success: true,
-                response_text: format!(
-                    "{{\"reasoning\": \"Heuristic execution\", \"tool_calls\": [{}], \"result\": \"Executing directly\"}}",
-                    heuristic_calls.iter().map(|c| format!("{{\"tool\": \"{}\"}}", c.tool_name)).collect::<Vec<_>>().join(", ")
-                ),
-                tool_calls: heuristic_calls,
-                model_used: "heuristic".to_string(),
-                tokens_used: 0,
-            };
+/// Hand a ready task to an agent or a cluster node, or, when `can_run_locally`,
+/// prepare it for a local worker. Tasks none of these take stay pending.
+async fn dispatch_task(
+    state: &mut OrchestratorState,
+    task: crate::proto::common::Task,
+    can_run_locally: bool,
+) -> Option<LocalWork> {
+    let task_id = task.id.clone();
+    let goal_id = task.goal_id.clone();
+    let level = IntelligenceLevel::from_str(&task.intelligence_level);
 
-            // Drop the lock, execute tools, reacquire for recording
-            let goal_id_h = goal_id.clone();
-            let task_id_h = task_id.clone();
-            let task_desc_h = task.description.clone();
-            let level_str_h = level.as_str().to_string();
-            drop(state);
+    // Read-only goals stay with the local worker, which enforces the restriction
+    let read_only = crate::onboarding::is_read_only(&state.goal_engine, &goal_id);
 
-            let tool_execution =
-                execute_tool_calls_unlocked(&clients_for_heuristic, &task_id_h, &heuristic_result)
-                    .await;
+    // 4. Route task via agent router or handle directly
+    let agent = if read_only {
+        None
+    } else {
+        state.agent_router.route_task(&task)
+    };
+    if let Some(agent_id) = agent {
+        mark_picked(state, &task, "picked for execution");
+        info!("Dispatching task {task_id} to agent {agent_id}");
+        state.agent_router.assign_task(&agent_id, &task_id);
+        state
+            .goal_engine
+            .task_events()
+            .dispatched(&goal_id, &task_id, &agent_id);
 
-            {
-                let mut state = state_arc.write().await;
-                record_ai_result(
-                    &mut state,
-                    &task_id_h,
-                    &goal_id_h,
-                    &task_desc_h,
-                    &level_str_h,
-                    heuristic_result,
-                    tool_execution,
+        state.decision_logger.log_decision(
+            "task_routing",
+            &[agent_id.clone()],
+            "agent_dispatch",
+            &format!(
+                "Task {task_id} dispatched to agent {agent_id} (level: {})",
+                level.as_str()
+            ),
+            level.as_str(),
+            "heuristic",
+        );
+
+        // Agent polls via GetAssignedTask, executes, reports via ReportTaskResult.
+        // Dead agent recovery in housekeeping handles timeout.
+        return None;
+    }
+
+    // No local agent matched — try cluster routing if enabled
+    if !read_only && state.cluster.read().await.is_enabled() {
+        let cluster_guard = state.cluster.read().await;
+        if let Some(remote_node_id) = state.agent_router.route_task_to_node(&task, &cluster_guard) {
+            drop(cluster_guard);
+            info!("Routing task {task_id} to remote node {remote_node_id}");
+
+            let mut remote = crate::remote_exec::RemoteExecutor::new();
+            match remote
+                .submit_remote_goal(
+                    &remote_node_id,
+                    &task.description,
+                    5, // default priority
+                    &format!("cluster:{}", task_id),
                 )
-                .await;
+                .await
+            {
+                Ok(remote_goal_id) => {
+                    info!(
+                        "Task {task_id} submitted to remote node {remote_node_id} as goal {remote_goal_id}"
+                    );
+                    state.task_planner.complete_task(&task_id, Vec::new());
+                    state.goal_engine.update_task_status(
+                        &goal_id,
+                        &task_id,
+                        "completed",
+                        &format!("submitted to cluster node {remote_node_id}"),
+                        "autonomy",
+                    );
+                    state.decision_logger.log_decision(
+                        "task_routing",
+                        &[remote_node_id],
+                        "cluster_dispatch",
+                        &format!("Task {task_id} routed to remote cluster node"),
+                        level.as_str(),
+                        "cluster",
+                    );
+                    return None;
+                }
+                Err(e) => {
+                    warn!("Remote dispatch failed for {task_id}: {e}, running it locally");
+                }
             }
-
-            // Run housekeeping so goal completion is detected immediately
-            run_housekeeping(state_arc).await;
-            return Ok(());
         }
+    }
 
-        // No agent matched — prepare AI work items and release the lock
-        let mut preferred_provider = get_preferred_provider(&state, &goal_id);
-        let messages = state.goal_engine.get_messages(&goal_id);
-        let clients = state.clients.clone(); // Arc clone — cheap
+    // Wait for a free worker
+    if !can_run_locally {
+        return None;
+    }
+    mark_picked(state, &task, "picked for execution");
 
-        if preferred_provider.is_empty() {
-            preferred_provider = "qwen3".to_string();
-        }
+    // ── Phase 1.5: Try heuristic execution (no AI needed) ──
+    // For well-structured tasks that map to a single tool with extractable
+    // parameters, execute directly without AI inference. This makes aiOS
+    // resilient to API outages and faster for simple tasks.
+    let heuristic = try_heuristic_execution(&task).map(|heuristic_calls| {
+        info!(
+            "Heuristic execution: {} tool calls for task {task_id} (bypassing AI)",
+            heuristic_calls.len()
+        );
 
-        let mut ai_work_items = vec![AiWorkItem {
-            task,
-            task_id,
-            goal_id,
-            level,
-            preferred_provider,
-            messages,
-            clients: clients.clone(),
-        }];
-
-        // Mark remaining tasks as in-progress now that we're on the AI path
-        for extra_task in &remaining_tasks {
-            state.task_planner.mark_in_progress(&extra_task.id);
-            state
-                .goal_engine
-                .update_task_status(&extra_task.goal_id, &extra_task.id, "in_progress");
-        }
-
-        // Prepare work items for remaining parallel tasks
-        for extra_task in remaining_tasks {
-            let extra_level =
-                IntelligenceLevel::from_str(&extra_task.intelligence_level);
-            let mut extra_provider =
-                get_preferred_provider(&state, &extra_task.goal_id);
-            let extra_messages = state.goal_engine.get_messages(&extra_task.goal_id);
-            if extra_provider.is_empty() {
-                extra_provider = "qwen3".to_string();
-            }
-            ai_work_items.push(AiWorkItem {
-                task_id: extra_task.id.clone(),
-                goal_id: extra_task.goal_id.clone(),
-                level: extra_level,
-                preferred_provider: extra_provider,
-                messages: extra_messages,
-                clients: clients.clone(),
-                task: extra_task,
-            });
+        state.decision_logger.log_decision(
+            "task_routing",
+            &[task_id.clone()],
+            "heuristic_execution",
+            &format!(
+                "Task '{}' executed via heuristic (no AI inference needed)",
+                task.description
+            ),
+            level.as_str(),
+            "heuristic",
+        );
+
+        // Build a synthetic AI result from the heuristic tool calls
+        AiInferenceResult {
+            success: true,
+            response_text: format!(
+                "{{\"reasoning\": \"Heuristic execution\", \"tool_calls\": [{}], \"result\": \"Executing directly\"}}",
+                heuristic_calls.iter().map(|c| format!("{{\"tool\": \"{}\"}}", c.tool_name)).collect::<Vec<_>>().join(", ")
+            ),
+            tool_calls: heuristic_calls,
+            model_used: "heuristic".to_string(),
+            tokens_used: 0,
+            compressions: Vec::new(),
+            verdict: None,
+            reviews: Vec::new(),
         }
+    });
 
-        Some(ai_work_items)
-    }; // ── Write lock dropped here ──
+    let span = tracing::info_span!("execute_task", goal_id = %goal_id, task_id = %task_id);
+    state.traces.attach(&state.goal_engine, &goal_id, &span);
+
+    // Everything the worker needs, so it can run without the lock
+    let work = AiWorkItem {
+        _in_flight: state.drain.track(&task_id),
+        drain: state.drain.clone(),
+        tool_usage: state.tool_usage.clone(),
+        staging: crate::staging::StagingContext::new(
+            state.staging.clone(),
+            &state.goal_engine,
+            &goal_id,
+        ),
+        workload: crate::workload::for_goal(&state.goal_engine, &goal_id),
+        charge: crate::usage::Charge::new(&state.usage, &state.goal_engine, &goal_id),
+        span,
+        checkpoints: state.task_checkpoints.clone(),
+        peer_review: state.peer_review.clone(),
+        task_events: state.goal_engine.task_events().clone(),
+        language: crate::locale::for_goal(&state.goal_engine, &goal_id, state.language),
+        read_only: crate::onboarding::is_read_only(&state.goal_engine, &goal_id),
+        experimental_tools: allows_experimental_tools(&state.goal_engine, &goal_id),
+        preferred_provider: task_provider(state, &task),
+        messages: state.goal_engine.get_messages(&goal_id),
+        clients: state.clients.clone(),
+        task,
+        task_id,
+        goal_id,
+        level,
+    };
+    Some(LocalWork { work, heuristic })
+}
 
-    // ── Phase 2: Multi-turn reasoning loops WITHOUT holding the write lock ──
-    // Dispatch up to 3 reasoning loops in parallel using a semaphore.
-    if let Some(work_items) = ai_work {
-        if work_items.len() == 1 {
-            // Single task — run inline (no spawn overhead)
-            let work = &work_items[0];
-            let loop_config = ReasoningLoopConfig {
-                max_rounds: match work.level {
-                    IntelligenceLevel::Reactive | IntelligenceLevel::Operational => 1,
-                    IntelligenceLevel::Tactical => 3,
-                    IntelligenceLevel::Strategic => 5,
-                },
-                max_total_tokens: match work.level {
-                    IntelligenceLevel::Reactive | IntelligenceLevel::Operational => 2048,
-                    IntelligenceLevel::Tactical => 8192,
-                    IntelligenceLevel::Strategic => 16384,
-                },
-            };
+/// Mark a task as taken off the ready list
+fn mark_picked(state: &mut OrchestratorState, task: &crate::proto::common::Task, cause: &str) {
+    state.task_planner.mark_in_progress(&task.id);
+    state
+        .goal_engine
+        .update_task_status(&task.goal_id, &task.id, "in_progress", cause, "autonomy");
+}
 
-            info!(
-                "Starting reasoning loop for {} task {} (max_rounds={}, provider={})",
-                work.level.as_str(),
-                work.task_id,
-                loop_config.max_rounds,
-                work.preferred_provider
+/// Record a failed attempt of a task: re-queued after a backoff if its
+/// retry policy covers `kind` and attempts remain, failed otherwise. Each
+/// attempt is logged as a `task_retry` decision.
+pub fn fail_or_retry(
+    state: &mut OrchestratorState,
+    goal_id: &str,
+    task_id: &str,
+    error: &str,
+    kind: FailureKind,
+    cause: &str,
+    actor: &str,
+) {
+    use crate::task_planner::FailureOutcome;
+
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let language = crate::locale::for_goal(&state.goal_engine, goal_id, state.language);
+    match state
+        .task_planner
+        .fail_or_retry(task_id, error, kind, now_ms)
+    {
+        FailureOutcome::Retry {
+            attempt,
+            max_attempts,
+            delay,
+        } => {
+            let wait = format!("{:.1}s", delay.as_secs_f64());
+            state.goal_engine.update_task_status(
+                goal_id,
+                task_id,
+                "retrying",
+                &format!("{cause}; attempt {attempt} of {max_attempts} in {wait}"),
+                actor,
             );
+            state.goal_engine.add_message(
+                goal_id,
+                "system",
+                &language.text(
+                    "task_retrying",
+                    &[
+                        ("error", error),
+                        ("wait", &wait),
+                        ("attempt", &attempt.to_string()),
+                        ("max", &max_attempts.to_string()),
+                    ],
+                ),
+            );
+            state.decision_logger.log_decision(
+                "task_retry",
+                &[task_id.to_string()],
+                "retry_scheduled",
+                &format!(
+                    "{} failure ({cause}); attempt {attempt} of {max_attempts} in {wait}",
+                    kind.as_str()
+                ),
+                "reactive",
+                "heuristic",
+            );
+            info!("Task {task_id} failed ({cause}); attempt {attempt} of {max_attempts} in {wait}");
+        }
+        FailureOutcome::Failed { attempts } => {
+            state
+                .goal_engine
+                .update_task_status(goal_id, task_id, "failed", cause, actor);
+            state.goal_engine.add_message(
+                goal_id,
+                "system",
+                &language.text("task_failed", &[("error", error)]),
+            );
+            if attempts > 1 {
+                state.decision_logger.log_decision(
+                    "task_retry",
+                    &[task_id.to_string()],
+                    "retries_exhausted",
+                    &format!(
+                        "{} failure ({cause}) after {attempts} attempts",
+                        kind.as_str()
+                    ),
+                    "reactive",
+                    "heuristic",
+                );
+            }
+        }
+    }
+}
 
-            let (result, tool_execution) = run_reasoning_loop(work, &loop_config).await;
+/// Put retrying tasks whose backoff has passed back on the ready list
+fn requeue_due_retries(state: &mut OrchestratorState) {
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    for (task_id, attempt) in state.task_planner.due_retries(now_ms) {
+        let Some(goal_id) = state
+            .task_planner
+            .get_task(&task_id)
+            .map(|t| t.goal_id.clone())
+        else {
+            continue;
+        };
+        state.goal_engine.update_task_status(
+            &goal_id,
+            &task_id,
+            "pending",
+            &format!("retry backoff elapsed; attempt {attempt}"),
+            "autonomy",
+        );
+        state.decision_logger.log_decision(
+            "task_retry",
+            std::slice::from_ref(&task_id),
+            "requeued",
+            &format!("Backoff elapsed, starting attempt {attempt}"),
+            "reactive",
+            "heuristic",
+        );
+    }
+}
 
-            let mut state = state_arc.write().await;
+/// Run a task on a worker of its own. Cancelling the worker stops its
+/// tool calls or reasoning; what finished is recorded, then housekeeping
+/// runs and the loop is woken to fill the freed worker.
+async fn start_worker(state_arc: &Arc<RwLock<OrchestratorState>>, local: LocalWork) {
+    let (slot, waker) = {
+        let state = state_arc.read().await;
+        (
+            state.task_workers.claim(&local.work.task_id),
+            state.autonomy_waker.clone(),
+        )
+    };
+    let state_arc = state_arc.clone();
+    tokio::spawn(async move {
+        let LocalWork { work, heuristic } = local;
+        let started = std::time::Instant::now();
+        let outcome = tokio::select! {
+            biased;
+            _ = slot.token().cancelled() => {
+                info!("Task {} cancelled while running", work.task_id);
+                None
+            }
+            outcome = crate::usage::scope(
+                work.charge.clone(),
+                execute_local_work(&work, heuristic).instrument(work.span.clone()),
+            ) => outcome,
+        };
+        if let Some(ref charge) = work.charge {
+            charge.record(&crate::usage::GoalUsage {
+                wall_clock_ms: started.elapsed().as_millis() as i64,
+                ..Default::default()
+            });
+        }
+        if let Some((result, tool_execution)) = outcome {
+            let mut state = write_state(&state_arc, "autonomy.record_result").await;
             record_ai_result(
                 &mut state,
                 &work.task_id,
@@ -627,73 +1691,69 @@ async fn autonomy_tick(
                 tool_execution,
             )
             .await;
-        } else {
-            // Multiple tasks — dispatch in parallel with semaphore
-            let semaphore = Arc::new(tokio::sync::Semaphore::new(3));
-            let mut handles = Vec::new();
-
-            for work in work_items {
-                let sem = semaphore.clone();
-                let state_ref = state_arc.clone();
-                handles.push(tokio::spawn(async move {
-                    let _permit = sem.acquire().await;
-                    let loop_config = ReasoningLoopConfig {
-                        max_rounds: match work.level {
-                            IntelligenceLevel::Reactive | IntelligenceLevel::Operational => 1,
-                            IntelligenceLevel::Tactical => 3,
-                            IntelligenceLevel::Strategic => 5,
-                        },
-                        max_total_tokens: match work.level {
-                            IntelligenceLevel::Reactive | IntelligenceLevel::Operational => 2048,
-                            IntelligenceLevel::Tactical => 8192,
-                            IntelligenceLevel::Strategic => 16384,
-                        },
-                    };
-
-                    info!(
-                        "Parallel reasoning loop for {} task {} (max_rounds={})",
-                        work.level.as_str(),
-                        work.task_id,
-                        loop_config.max_rounds,
-                    );
-
-                    let (result, tool_execution) =
-                        run_reasoning_loop(&work, &loop_config).await;
-
-                    // Reacquire write lock to record results
-                    let mut state = state_ref.write().await;
-                    record_ai_result(
-                        &mut state,
-                        &work.task_id,
-                        &work.goal_id,
-                        &work.task.description,
-                        work.level.as_str(),
-                        result,
-                        tool_execution,
-                    )
-                    .await;
-                }));
-            }
-
-            // Wait for all parallel tasks to complete
-            for handle in handles {
-                if let Err(e) = handle.await {
-                    error!("Parallel reasoning task panicked: {e}");
-                }
-            }
         }
-    }
+        drop(work);
+        drop(slot);
+        // Detect goal completion right away and pick up what is now unblocked
+        run_housekeeping(&state_arc).await;
+        waker.wake();
+    });
+}
 
-    // ── Phase 4: Housekeeping (dead agent recovery + goal completion) ──
-    run_housekeeping(state_arc).await;
+/// Execute a task's heuristic tool calls or reasoning loop. None if
+/// shutdown interrupted it.
+async fn execute_local_work(
+    work: &AiWorkItem,
+    heuristic: Option<AiInferenceResult>,
+) -> Option<(AiInferenceResult, ToolExecutionResult)> {
+    let Some(heuristic_result) = heuristic else {
+        let loop_config = ReasoningLoopConfig::for_level(&work.level);
+        info!(
+            "Starting reasoning loop for {} task {} (max_rounds={}, provider={})",
+            work.level.as_str(),
+            work.task_id,
+            loop_config.max_rounds,
+            work.preferred_provider
+        );
+        return crate::workload::scope(work.workload, run_reasoning_loop(work, &loop_config)).await;
+    };
 
-    Ok(())
+    let tool_execution = crate::workload::scope(
+        work.workload,
+        execute_tool_calls_unlocked(
+            &work.clients,
+            &work.task_id,
+            &work.staging,
+            &work.checkpoints,
+            work.read_only,
+            work.experimental_tools,
+            &heuristic_result,
+        ),
+    )
+    .await;
+    if tool_execution.all_succeeded {
+        work.checkpoints.finish(&work.clients, &work.task_id).await;
+    }
+    publish_tool_calls(
+        &work.task_events,
+        &work.goal_id,
+        &work.task_id,
+        &heuristic_result,
+        &tool_execution,
+    );
+    record_tool_usage(
+        &work.tool_usage,
+        &work.task.description,
+        &heuristic_result,
+        &tool_execution,
+    );
+    Some((heuristic_result, tool_execution))
 }
 
 /// Housekeeping: dead agent recovery + goal completion checks.
 /// Extracted so it can be called from multiple code paths (heuristic, AI, no-task).
 async fn run_housekeeping(state_arc: &Arc<RwLock<OrchestratorState>>) {
-    let mut state = state_arc.write().await;
+    let mut state = write_state(state_arc, "autonomy.housekeeping").await;
 
     // Check for stuck agent-assigned tasks (timeout recovery)
     let dead_agents = state.agent_router.dead_agents();
@@ -702,6 +1762,19 @@ async fn run_housekeeping(state_arc: &Arc<RwLock<OrchestratorState>>) {
             warn!("Agent {dead_id} is dead with task {stuck_task_id} assigned — re-queuing task");
             state.agent_router.task_completed(dead_id, false);
             state.task_planner.resume_task(&stuck_task_id);
+            if let Some(goal_id) = state
+                .task_planner
+                .get_task(&stuck_task_id)
+                .map(|t| t.goal_id.clone())
+            {
+                state.goal_engine.update_task_status(
+                    &goal_id,
+                    &stuck_task_id,
+                    "pending",
+                    &format!("assigned agent {dead_id} stopped responding"),
+                    "autonomy",
+                );
+            }
         }
     }
 
@@ -710,9 +1783,30 @@ async fn run_housekeeping(state_arc: &Arc<RwLock<OrchestratorState>>) {
     for goal in goals {
         if goal.status == "pending" || goal.status == "in_progress" {
             let progress = state.goal_engine.calculate_progress(&goal.id).await;
-            if progress >= 100.0 {
-                state.goal_engine.update_status(&goal.id, "completed");
+            // A goal stays open until every subgoal it spawned has finished
+            if progress >= 100.0 && !state.goal_engine.has_active_subgoals(&goal.id) {
+                state.goal_engine.update_status(
+                    &goal.id,
+                    "completed",
+                    "all tasks and subgoals finished",
+                    "autonomy",
+                );
                 info!("Goal {} completed", goal.id);
+                let language =
+                    crate::locale::for_goal(&state.goal_engine, &goal.id, state.language);
+                state.notifier.notify(
+                    "goal_completed",
+                    crate::event_bus::EventSeverity::Info,
+                    &language.text("goal_completed", &[("goal", &goal.description)]),
+                    &language.text("goal_completed_detail", &[]),
+                    &goal.id,
+                );
+                if state
+                    .goal_engine
+                    .has_label(&goal.id, crate::onboarding::ONBOARDING_LABEL)
+                {
+                    crate::onboarding::spawn_report(state_arc.clone(), goal.id.clone());
+                }
 
                 state.decision_logger.log_decision(
                     "goal_completion",
@@ -726,7 +1820,12 @@ async fn run_housekeeping(state_arc: &Arc<RwLock<OrchestratorState>>) {
                     "heuristic",
                 );
             } else if progress > 0.0 && goal.status == "pending" {
-                state.goal_engine.update_status(&goal.id, "in_progress");
+                state.goal_engine.update_status(
+                    &goal.id,
+                    "in_progress",
+                    "first task completed",
+                    "autonomy",
+                );
             }
         }
     }
@@ -738,11 +1837,24 @@ struct ToolExecutionResult {
     all_succeeded: bool,
 }
 
+/// Maximum number of independent tool calls executed concurrently.
+const MAX_PARALLEL_TOOL_CALLS: usize = 4;
+
 /// Execute tool calls from an AI response WITHOUT holding the state write lock.
 /// Returns the results so they can be recorded once the lock is reacquired.
+///
+/// Runs of independent calls (see [`plan_tool_batches`]) execute concurrently,
+/// bounded by `MAX_PARALLEL_TOOL_CALLS`; results keep the original call order.
+/// When `read_only`, a plan with any call that is not read-only is denied
+/// as a whole. Failed calls of experimental tools are reported but do not
+/// fail the task.
 async fn execute_tool_calls_unlocked(
-    clients: &crate::clients::ServiceClients,
+    clients: &Arc<crate::clients::ServiceClients>,
     task_id: &str,
+    staging: &crate::staging::StagingContext,
+    checkpoints: &Arc<crate::task_checkpoint::TaskCheckpoints>,
+    read_only: bool,
+    allow_experimental: bool,
     result: &AiInferenceResult,
 ) -> ToolExecutionResult {
     if result.tool_calls.is_empty() || !result.success {
@@ -752,16 +1864,147 @@ async fn execute_tool_calls_unlocked(
         };
     }
 
+    if let Some(denied) = deny_writes(read_only, &result.tool_calls) {
+        warn!("Denied the plan of read-only task {task_id}: it calls '{denied}'");
+        return ToolExecutionResult {
+            tool_results: result
+                .tool_calls
+                .iter()
+                .map(|tc| {
+                    serde_json::json!({
+                        "tool": tc.tool_name,
+                        "success": false,
+                        "error": format!(
+                            "Denied: this goal may only use read-only tools, and '{denied}' is not one"
+                        ),
+                        "failure_class": "denied",
+                    })
+                })
+                .collect(),
+            all_succeeded: false,
+        };
+    }
+
+    let tool_names: Vec<&str> = result
+        .tool_calls
+        .iter()
+        .map(|tc| tc.tool_name.as_str())
+        .collect();
+    match staging.route(clients, &tool_names).await {
+        Ok(None) => {}
+        Ok(Some(address)) => {
+            return execute_staged_tool_calls(staging, &address, task_id, result).await;
+        }
+        // Never fall back to the host for a goal that should be staged
+        Err(e) => {
+            warn!("Staging sandbox unavailable for task {task_id}: {e:#}");
+            return ToolExecutionResult {
+                tool_results: result
+                    .tool_calls
+                    .iter()
+                    .map(|tc| {
+                        serde_json::json!({
+                            "tool": tc.tool_name,
+                            "success": false,
+                            "error": format!("Staging sandbox unavailable: {e:#}"),
+                            "failure_class": "staging",
+                        })
+                    })
+                    .collect(),
+                all_succeeded: false,
+            };
+        }
+    }
+
+    let mut outcomes: Vec<Option<anyhow::Result<serde_json::Value>>> =
+        (0..result.tool_calls.len()).map(|_| None).collect();
+
+    for batch in plan_tool_batches(&result.tool_calls) {
+        if let [i] = batch[..] {
+            let tc = &result.tool_calls[i];
+            info!("Executing tool '{}' for task {task_id}", tc.tool_name);
+            outcomes[i] = Some(
+                execute_tool_call(
+                    clients,
+                    checkpoints,
+                    task_id,
+                    &tc.tool_name,
+                    &tc.input_json,
+                    allow_experimental,
+                )
+                .await,
+            );
+            continue;
+        }
+
+        info!(
+            "Executing {} independent tools concurrently for task {task_id}",
+            batch.len()
+        );
+        let semaphore = Arc::new(tokio::sync::Semaphore::new(MAX_PARALLEL_TOOL_CALLS));
+        let mut join_set = tokio::task::JoinSet::new();
+        let workload = crate::workload::current();
+        let charge = crate::usage::current();
+        for i in batch {
+            let tc = result.tool_calls[i].clone();
+            let clients = clients.clone();
+            let checkpoints = checkpoints.clone();
+            let task_id = task_id.to_string();
+            let sem = semaphore.clone();
+            let charge = charge.clone();
+            join_set.spawn(crate::workload::scope(
+                workload,
+                async move {
+                    let _permit = sem.acquire().await;
+                    info!("Executing tool '{}' for task {task_id}", tc.tool_name);
+                    let outcome = crate::usage::scope(
+                        charge,
+                        execute_tool_call(
+                            &clients,
+                            &checkpoints,
+                            &task_id,
+                            &tc.tool_name,
+                            &tc.input_json,
+                            allow_experimental,
+                        ),
+                    )
+                    .await;
+                    (i, outcome)
+                }
+                .instrument(tracing::Span::current()),
+            ));
+        }
+        while let Some(joined) = join_set.join_next().await {
+            match joined {
+                Ok((i, outcome)) => outcomes[i] = Some(outcome),
+                Err(e) => error!("Parallel tool call panicked for task {task_id}: {e}"),
+            }
+        }
+    }
+
     let mut tool_results = Vec::new();
     let mut all_succeeded = true;
 
-    for tc in &result.tool_calls {
-        info!("Executing tool '{}' for task {task_id}", tc.tool_name);
-        match execute_tool_call(clients, task_id, &tc.tool_name, &tc.input_json).await {
+    for (tc, outcome) in result.tool_calls.iter().zip(outcomes) {
+        let outcome = outcome.unwrap_or_else(|| Err(anyhow::anyhow!("tool call did not complete")));
+        match outcome {
             Ok(tool_result) => {
                 info!("Tool '{}' succeeded for task {task_id}", tc.tool_name);
                 tool_results.push(tool_result);
             }
+            Err(e) if is_experimental(&e) => {
+                warn!(
+                    "Experimental tool '{}' failed for task {task_id}, not failing the task: {e}",
+                    tc.tool_name
+                );
+                tool_results.push(serde_json::json!({
+                    "tool": tc.tool_name,
+                    "success": false,
+                    "error": e.to_string(),
+                    "failure_class": failure_class(&e),
+                    "experimental": true,
+                }));
+            }
             Err(e) => {
                 warn!("Tool '{}' failed for task {task_id}: {e}", tc.tool_name);
                 all_succeeded = false;
@@ -769,6 +2012,7 @@ async fn execute_tool_calls_unlocked(
                     "tool": tc.tool_name,
                     "success": false,
                     "error": e.to_string(),
+                    "failure_class": failure_class(&e),
                 }));
             }
         }
@@ -780,6 +2024,45 @@ async fn execute_tool_calls_unlocked(
     }
 }
 
+/// Execute tool calls in a staging sandbox, one at a time so the recorded
+/// sequence replays in the same order
+async fn execute_staged_tool_calls(
+    staging: &crate::staging::StagingContext,
+    address: &str,
+    task_id: &str,
+    result: &AiInferenceResult,
+) -> ToolExecutionResult {
+    let mut tool_results = Vec::new();
+    let mut all_succeeded = true;
+    for tc in &result.tool_calls {
+        info!(
+            "Executing tool '{}' in staging for task {task_id}",
+            tc.tool_name
+        );
+        match staging
+            .execute(address, task_id, &tc.tool_name, &tc.input_json)
+            .await
+        {
+            Ok(tool_result) => tool_results.push(tool_result),
+            Err(e) => {
+                warn!("{e:#}");
+                all_succeeded = false;
+                tool_results.push(serde_json::json!({
+                    "tool": tc.tool_name,
+                    "success": false,
+                    "error": e.to_string(),
+                    "failure_class": "error",
+                    "staged": true,
+                }));
+            }
+        }
+    }
+    ToolExecutionResult {
+        tool_results,
+        all_succeeded,
+    }
+}
+
 /// Which AI backend to use for inference
 enum AiBackend {
     /// Local runtime (llama.cpp / small models)
@@ -795,6 +2078,31 @@ struct AiInferenceResult {
     tool_calls: Vec<ToolCallRequest>,
     model_used: String,
     tokens_used: i32,
+    /// Prompt compression the API gateway applied, one report per inference
+    compressions: Vec<crate::proto::common::CompressionReport>,
+    /// The model's verdict on whether the tool calls achieved the task
+    verdict: Option<Verdict>,
+    /// Peer reviews of the risky plans the loop made; the last one may
+    /// have escalated its plan to an operator
+    reviews: Vec<crate::peer_review::Review>,
+}
+
+/// Outcome of the verification pass after tool execution
+#[derive(serde::Deserialize)]
+struct Verdict {
+    achieved: bool,
+    #[serde(default)]
+    reason: String,
+    #[serde(default)]
+    next_steps: Vec<String>,
+}
+
+/// Response schema of the verification pass
+const VERDICT_SCHEMA: &str = r#"{"type":"object","properties":{"achieved":{"type":"boolean"},"reason":{"type":"string"},"next_steps":{"type":"array","items":{"type":"string"}}},"required":["achieved","reason"]}"#;
+
+/// Parse the model's verdict from a verification response
+fn parse_verdict(text: &str) -> Option<Verdict> {
+    serde_json::from_value(extract_json_from_text(text)?).ok()
 }
 
 /// A tool call extracted from AI response
@@ -802,6 +2110,83 @@ struct AiInferenceResult {
 struct ToolCallRequest {
     tool_name: String,
     input_json: Vec<u8>,
+    /// Explicit hint from the AI that this call does not depend on (or affect)
+    /// its neighbours. `None` means "infer from the tool name".
+    independent: Option<bool>,
+}
+
+impl ToolCallRequest {
+    /// Whether this call may run concurrently with adjacent independent calls.
+    fn is_independent(&self) -> bool {
+        self.independent
+            .unwrap_or_else(|| is_read_only_tool(&self.tool_name))
+    }
+}
+
+/// Tool actions that only observe the system and are safe to run concurrently.
+const READ_ONLY_ACTIONS: &[&str] = &[
+    "audit_detail",
+    "audit_query",
+    "canary_list",
+    "check_perms",
+    "diff",
+    "disk_usage",
+    "dns",
+    "info",
+    "interfaces",
+    "jq",
+    "list",
+    "list_block_devices",
+    "list_installed",
+    "log",
+    "logs",
+    "page",
+    "ping",
+    "read",
+    "rules",
+    "search",
+    "snapshot_list",
+    "stat",
+    "status",
+];
+
+/// Infer whether a tool is read-only from its `namespace.action` name.
+/// Whole namespaces (`monitor`, `hw`, `hash`, `text`, `calc`) are observational; elsewhere only
+/// well-known query actions qualify. Plugins are never assumed safe.
+fn is_read_only_tool(tool_name: &str) -> bool {
+    let Some((namespace, action)) = tool_name.split_once('.') else {
+        return false;
+    };
+    match namespace {
+        "monitor" | "hw" | "hash" | "text" | "calc" => true,
+        "plugin" => false,
+        _ => READ_ONLY_ACTIONS.contains(&action),
+    }
+}
+
+/// First call a read-only goal may not make, if any
+fn deny_writes(read_only: bool, calls: &[ToolCallRequest]) -> Option<&str> {
+    calls
+        .iter()
+        .map(|tc| tc.tool_name.as_str())
+        .find(|name| read_only && !is_read_only_tool(name))
+}
+
+/// Split tool calls into ordered execution batches. Consecutive independent
+/// calls share a batch and run concurrently; every other call forms its own
+/// batch, acting as a barrier so dependent steps keep their original order.
+fn plan_tool_batches(calls: &[ToolCallRequest]) -> Vec<Vec<usize>> {
+    let mut batches: Vec<Vec<usize>> = Vec::new();
+    let mut open_parallel = false;
+    for (i, tc) in calls.iter().enumerate() {
+        let independent = tc.is_independent();
+        match batches.last_mut() {
+            Some(batch) if independent && open_parallel => batch.push(i),
+            _ => batches.push(vec![i]),
+        }
+        open_parallel = independent;
+    }
+    batches
 }
 
 /// Extract preferred provider from goal metadata JSON
@@ -818,15 +2203,40 @@ fn get_preferred_provider(state: &OrchestratorState, goal_id: &str) -> String {
         .unwrap_or_default()
 }
 
+/// Provider for a task's inference: the goal's preference, else the
+/// planner's hint. Tasks planned with a model class leave the choice to
+/// the gateway; older tasks keep the qwen3 default. A hint or the default
+/// whose circuit breaker is open at the gateway gives way to a healthy
+/// provider.
+fn task_provider(state: &OrchestratorState, task: &crate::proto::common::Task) -> String {
+    let preferred = get_preferred_provider(state, &task.goal_id);
+    let now = chrono::Utc::now().timestamp();
+    if !preferred.is_empty() {
+        preferred
+    } else if !task.provider_hint.is_empty() {
+        state.provider_health.pick(&task.provider_hint, now)
+    } else if !task.model_class.is_empty() {
+        String::new()
+    } else {
+        state.provider_health.pick("qwen3", now)
+    }
+}
+
 /// Execute a task through AI inference with fallback chain:
 /// local runtime -> api-gateway -> heuristic
+#[allow(clippy::too_many_arguments)]
 async fn execute_ai_task(
     clients: &crate::clients::ServiceClients,
     task_description: &str,
     intelligence_level: &str,
     preferred_backend: AiBackend,
     preferred_provider: &str,
+    model_class: &str,
     conversation_history: &[crate::goal_engine::GoalMessage],
+    session_id: &str,
+    tool_usage: &std::sync::Mutex<crate::tool_usage::ToolUsage>,
+    language: crate::locale::Language,
+    experimental_tools: bool,
 ) -> AiInferenceResult {
     // Assemble context for the AI call
     let assembler = ContextAssembler::new(4096);
@@ -843,88 +2253,113 @@ async fn execute_ai_task(
          Never output natural language, markdown, or explanations outside of JSON. \
          Your response must contain a \"tool_calls\" array with at least one tool to execute.",
     );
-
-    // Query memory service for relevant context chunks
-    match clients.memory().await {
-        Ok(mut mem_client) => {
-            let mem_request = tonic::Request::new(crate::proto::memory::ContextRequest {
-                task_description: task_description.to_string(),
-                max_tokens: 2048,
-                memory_tiers: vec![
-                    "operational".to_string(),
-                    "working".to_string(),
-                    "long_term".to_string(),
-                ],
-            });
-            match mem_client.assemble_context(mem_request).await {
-                Ok(response) => {
-                    let chunks = response.into_inner().chunks;
-                    if !chunks.is_empty() {
-                        let mut memory_context = String::from("\n\nRelevant memory context:\n");
-                        for chunk in &chunks {
-                            memory_context
-                                .push_str(&format!("- [{}] {}\n", chunk.source, chunk.content));
-                        }
-                        system_prompt.push_str(&memory_context);
-                        info!("Assembled {} memory chunks for task context", chunks.len());
-                    }
-                }
-                Err(e) => {
-                    debug!("Memory context assembly unavailable: {e}");
+    system_prompt.push_str(&language.prompt_instruction());
+
+    // The prompt is sent as sections so the gateway can compress it to the
+    // provider's context window: memory chunks may be dropped and history
+    // summarized, everything else is pinned
+    let mut sections = vec![pinned_section(format!("Task: {task_description}\n\n"))];
+
+    // Query memory service for relevant context chunks (cached briefly per task description)
+    match clients
+        .assemble_context(
+            task_description,
+            2048,
+            &["operational", "working", "long_term", "graph"],
+        )
+        .await
+    {
+        Ok(chunks) => {
+            if !chunks.is_empty() {
+                sections.push(pinned_section(
+                    "Relevant memory context (cite an entry by its bracketed reference \
+                     when your reasoning relies on it):\n",
+                ));
+                for chunk in &chunks {
+                    let reference = chunk
+                        .provenance
+                        .as_ref()
+                        .map_or(chunk.source.as_str(), |p| p.citation.as_str());
+                    sections.push(PromptSection {
+                        kind: "chunk".to_string(),
+                        text: format!("- [{reference}] {}\n", chunk.content),
+                        relevance: chunk.relevance,
+                        label: format!("memory [{reference}]"),
+                    });
                 }
+                sections.push(pinned_section("\n"));
+                info!("Assembled {} memory chunks for task context", chunks.len());
             }
         }
         Err(e) => {
-            debug!("Memory service unavailable for context: {e}");
+            debug!("Memory context assembly unavailable: {e}");
         }
     }
 
-    let mut prompt = format!("Task: {task_description}\n\n");
-
     // Include conversation history for context (e.g., after user replies to clarification)
     let relevant_messages: Vec<_> = conversation_history
         .iter()
         .filter(|m| m.sender == "user" || m.sender == "ai")
         .collect();
     if !relevant_messages.is_empty() {
-        prompt.push_str("Previous conversation:\n");
-        for msg in relevant_messages {
+        sections.push(pinned_section("Previous conversation:\n"));
+        for (i, msg) in relevant_messages.into_iter().enumerate() {
             let label = if msg.sender == "user" {
                 "[User]"
             } else {
                 "[AI]"
             };
-            prompt.push_str(&format!("{}: {}\n", label, msg.content));
+            sections.push(PromptSection {
+                kind: "history".to_string(),
+                text: format!("{}: {}\n", label, msg.content),
+                relevance: 0.0,
+                label: format!("{} message {}", msg.sender, i + 1),
+            });
         }
-        prompt.push_str("\nExecute the task using the provided context.\n\n");
+        sections.push(pinned_section(
+            "\nExecute the task using the provided context.\n\n",
+        ));
     }
 
-    // Tell the AI what tools are available — dynamically queried from the tool registry
-    let tool_catalog = query_tool_catalog(clients).await;
-    prompt.push_str(&tool_catalog);
+    // Tell the AI what tools are available — dynamically queried from the tool
+    // registry, ranked for this task by past usage
+    sections.push(pinned_section(
+        query_tool_catalog(clients, tool_usage, task_description, experimental_tools).await,
+    ));
 
-    prompt.push_str(
+    sections.push(pinned_section(
         "IMPORTANT — Self-Evolution:\n\
          If the task requires a tool you do NOT have, create one using plugin.create.\n\
-         The code must define: def main(input_data: dict) -> dict\n\n",
-    );
-
-    prompt.push_str(
+         The code must define: def main(input_data: dict) -> dict\n\
+         Give sample inputs as \"examples\": the plugin is only registered once main returns a dict for each of them.\n\
+         For something that must keep running (a watcher or poller), pass \"daemon\": {} and define run(config, emit) instead, \
+         calling emit(event_type, data) for each event; manage it with plugin.<name>.control.\n\
+         To react to events, pass \"triggers\": [{\"category\": \"service\", \"event_type\": \"unit_*\"}]; the plugin then runs with input_data[\"event\"] for each match.\n\n",
+    ));
+
+    sections.push(pinned_section(
         "You MUST respond with ONLY a valid JSON object. No prose, no markdown, no explanation outside JSON.\n\n\
          FORMAT — Execute tools:\n\
          {\"reasoning\": \"brief explanation\", \"tool_calls\": [{\"tool\": \"monitor.cpu\", \"input\": {}}, {\"tool\": \"monitor.memory\", \"input\": {}}], \"result\": \"summary of what will be done\"}\n\n\
+         FORMAT — Execute tools and queue follow-up work as subgoals:\n\
+         {\"reasoning\": \"brief explanation\", \"tool_calls\": [{\"tool\": \"fs.list\", \"input\": {\"path\": \"/srv\"}}], \"spawn_goals\": [{\"description\": \"Audit each site under /srv\", \"priority\": 2}]}\n\n\
          FORMAT — Need user input:\n\
          {\"needs_clarification\": true, \"questions\": [\"What specific thing?\"]}\n\n\
          FORMAT — Create new tool then use it:\n\
-         {\"reasoning\": \"Need custom tool\", \"tool_calls\": [{\"tool\": \"plugin.create\", \"input\": {\"name\": \"my_tool\", \"description\": \"Does X\", \"code\": \"def main(input_data):\\n    return {'result': 'done'}\", \"capabilities\": [], \"dependencies\": []}}, {\"tool\": \"plugin.my_tool\", \"input\": {}}], \"result\": \"Created and executed tool\"}\n\n\
+         {\"reasoning\": \"Need custom tool\", \"tool_calls\": [{\"tool\": \"plugin.create\", \"input\": {\"name\": \"my_tool\", \"description\": \"Does X\", \"code\": \"def main(input_data):\\n    return {'result': 'done'}\", \"capabilities\": [], \"dependencies\": [], \"examples\": [{}]}}, {\"tool\": \"plugin.my_tool\", \"input\": {}}], \"result\": \"Created and executed tool\"}\n\n\
          EXAMPLE — Check CPU usage:\n\
          {\"reasoning\": \"Using monitor.cpu to get CPU metrics\", \"tool_calls\": [{\"tool\": \"monitor.cpu\", \"input\": {}}], \"result\": \"Checking CPU usage\"}\n\n\
          RULES:\n\
          1. ALWAYS include tool_calls array with at least one tool call — never just describe a plan\n\
          2. Output ONLY valid JSON — no text before or after\n\
          3. Tool names use namespace.action format (e.g. monitor.cpu, fs.read, net.ping)\n\
-         4. If unsure which tool, use the closest match from the catalog above"
-    );
+         4. If unsure which tool, use the closest match from the catalog above\n\
+         5. Add \"independent\": true to a tool call that neither depends on nor affects the calls next to it \
+         (e.g. several read-only checks) so they run in parallel; add \"independent\": false to force ordering\n\
+         6. Before changing a database or model directory, first call disk.snapshot_create on it so the change \
+         can be undone with disk.snapshot_rollback",
+    ));
+    let prompt: String = sections.iter().map(|s| s.text.as_str()).collect();
 
     // Try preferred backend first
     let result = match preferred_backend {
@@ -932,9 +2367,13 @@ async fn execute_ai_task(
         AiBackend::ApiGateway => {
             try_api_gateway_infer_with_provider(
                 clients,
-                &prompt,
+                &sections,
                 &system_prompt,
                 preferred_provider,
+                model_class,
+                session_id,
+                "",
+                "",
             )
             .await
         }
@@ -950,9 +2389,13 @@ async fn execute_ai_task(
             info!("Local runtime unavailable, falling back to API gateway");
             try_api_gateway_infer_with_provider(
                 clients,
-                &prompt,
+                &sections,
                 &system_prompt,
                 preferred_provider,
+                model_class,
+                session_id,
+                "",
+                "",
             )
             .await
         }
@@ -979,17 +2422,38 @@ async fn execute_ai_task(
         tool_calls: vec![],
         model_used: "none".to_string(),
         tokens_used: 0,
+        compressions: Vec::new(),
+        verdict: None,
+        reviews: Vec::new(),
+    }
+}
+
+/// A prompt section the gateway must send unchanged
+fn pinned_section(text: impl Into<String>) -> PromptSection {
+    PromptSection {
+        kind: "pinned".to_string(),
+        text: text.into(),
+        relevance: 0.0,
+        label: String::new(),
     }
 }
 
 /// Query the live tool catalog from the tools gRPC service.
-/// Groups tools by namespace and formats them for the AI prompt.
+/// Tools relevant to the task come first, in full; the rest by name only
+/// (see [`crate::tool_usage`]). Experimental tools are listed only for
+/// goals that opted in to them.
 /// Falls back to a static list if the tools service is unreachable.
-async fn query_tool_catalog(clients: &crate::clients::ServiceClients) -> String {
+async fn query_tool_catalog(
+    clients: &crate::clients::ServiceClients,
+    tool_usage: &std::sync::Mutex<crate::tool_usage::ToolUsage>,
+    task_description: &str,
+    include_experimental: bool,
+) -> String {
     match clients.tools().await {
         Ok(mut client) => {
             let request = tonic::Request::new(crate::proto::tools::ListToolsRequest {
-                namespace: String::new(),
+                include_experimental,
+                ..Default::default()
             });
             match client.list_tools(request).await {
                 Ok(response) => {
@@ -997,30 +2461,14 @@ async fn query_tool_catalog(clients: &crate::clients::ServiceClients) -> String
                     if tools.is_empty() {
                         return static_tool_catalog();
                     }
-
-                    // Group by namespace
-                    let mut by_ns: std::collections::BTreeMap<String, Vec<String>> =
-                        std::collections::BTreeMap::new();
-                    for tool in &tools {
-                        let ns = if tool.namespace.is_empty() {
-                            "other".to_string()
-                        } else {
-                            tool.namespace.clone()
-                        };
-                        let desc = if tool.description.is_empty() {
-                            tool.name.clone()
-                        } else {
-                            format!("{} — {}", tool.name, tool.description)
-                        };
-                        by_ns.entry(ns).or_default().push(desc);
-                    }
-
-                    let mut catalog = format!("Available tools ({} total):\n", tools.len());
-                    for (ns, tool_list) in &by_ns {
-                        catalog.push_str(&format!("[{}] {}\n", ns, tool_list.join(", ")));
-                    }
-                    catalog.push('\n');
-                    catalog
+                    let recommend = std::env::var("AIOS_TOOL_CATALOG_RECOMMEND")
+                        .ok()
+                        .and_then(|v| v.parse().ok())
+                        .unwrap_or(12);
+                    tool_usage
+                        .lock()
+                        .unwrap()
+                        .catalog(task_description, &tools, recommend)
                 }
                 Err(e) => {
                     debug!("Failed to list tools via gRPC: {e}");
@@ -1035,10 +2483,48 @@ async fn query_tool_catalog(clients: &crate::clients::ServiceClients) -> String
     }
 }
 
+/// Feed executed tool calls into the usage analytics behind the catalog
+fn record_tool_usage(
+    tool_usage: &std::sync::Mutex<crate::tool_usage::ToolUsage>,
+    task_description: &str,
+    result: &AiInferenceResult,
+    execution: &ToolExecutionResult,
+) {
+    let mut usage = tool_usage.lock().unwrap();
+    for (call, outcome) in result.tool_calls.iter().zip(&execution.tool_results) {
+        let success = outcome.get("success").and_then(|v| v.as_bool()) == Some(true);
+        usage.record(&call.tool_name, task_description, &call.input_json, success);
+    }
+}
+
+/// Push each tool call's outcome to the task's event stream
+fn publish_tool_calls(
+    events: &crate::task_events::TaskEvents,
+    goal_id: &str,
+    task_id: &str,
+    result: &AiInferenceResult,
+    execution: &ToolExecutionResult,
+) {
+    for (call, outcome) in result.tool_calls.iter().zip(&execution.tool_results) {
+        events.tool_call(goal_id, task_id, &call.tool_name, outcome);
+    }
+}
+
+/// Goal label that opts a goal and its subgoals in to experimental tools
+pub const EXPERIMENTAL_TOOLS_LABEL: &str = "experimental-tools";
+
+/// Whether `goal_id` or one of its ancestors opted in to experimental tools
+fn allows_experimental_tools(goals: &crate::goal_engine::GoalEngine, goal_id: &str) -> bool {
+    goals
+        .ancestry(goal_id)
+        .iter()
+        .any(|id| goals.has_label(id, EXPERIMENTAL_TOOLS_LABEL))
+}
+
 /// Static fallback tool catalog when tools service is unreachable
 fn static_tool_catalog() -> String {
     "Available tools you can call:\n\
-     - fs.read, fs.write, fs.list, fs.delete, fs.mkdir, fs.copy, fs.move, fs.stat, fs.search\n\
+     - fs.read, fs.write, fs.list, fs.delete, fs.restore_deleted, fs.mkdir, fs.copy, fs.move, fs.stat, fs.search\n\
      - process.list, process.kill, process.spawn, process.info\n\
      - service.list, service.start, service.stop, service.restart, service.status\n\
      - net.ping, net.dns, net.interfaces, net.http_get, net.port_scan\n\
@@ -1046,6 +2532,8 @@ fn static_tool_catalog() -> String {
      - pkg.install, pkg.remove, pkg.list_installed, pkg.search, pkg.update\n\
      - sec.check_perms, sec.audit_query\n\
      - monitor.cpu, monitor.memory, monitor.disk, monitor.network, monitor.logs\n\
+     - disk.list_block_devices, disk.mount, disk.umount, disk.format, disk.resize_fs\n\
+     - disk.snapshot_create, disk.snapshot_list, disk.snapshot_rollback, disk.snapshot_delete\n\
      - web.http_request, web.scrape, web.webhook, web.download, web.api_call\n\
      - git.init, git.clone, git.add, git.commit, git.push, git.pull, git.branch, git.status, git.log, git.diff\n\
      - code.scaffold, code.generate\n\
@@ -1071,11 +2559,13 @@ async fn try_runtime_infer(
                 intelligence_level: "operational".to_string(),
                 requesting_agent: "autonomy-loop".to_string(),
                 task_id: String::new(),
+                workload: crate::workload::current().to_string(),
             });
 
             match client.infer(request).await {
                 Ok(response) => {
                     let resp = response.into_inner();
+                    crate::usage::record_inference(resp.tokens_used.into(), 0.0);
                     let tool_calls = parse_tool_calls(&resp.text);
                     Some(AiInferenceResult {
                         success: true,
@@ -1083,6 +2573,9 @@ async fn try_runtime_infer(
                         tool_calls,
                         model_used: resp.model_used,
                         tokens_used: resp.tokens_used,
+                        compressions: Vec::new(),
+                        verdict: None,
+                        reviews: Vec::new(),
                     })
                 }
                 Err(e) => {
@@ -1098,17 +2591,24 @@ async fn try_runtime_infer(
     }
 }
 
-/// Try to call the API gateway for inference with a specific provider
+/// Try to call the API gateway for inference with a specific provider.
+/// A non-empty `session_id` sends the prompt as the next turn of that
+/// gateway session.
+#[allow(clippy::too_many_arguments)]
 async fn try_api_gateway_infer_with_provider(
     clients: &crate::clients::ServiceClients,
-    prompt: &str,
+    sections: &[PromptSection],
     system_prompt: &str,
     preferred_provider: &str,
+    model_class: &str,
+    session_id: &str,
+    turn_kind: &str,
+    response_schema: &str,
 ) -> Option<AiInferenceResult> {
     match clients.api_gateway().await {
         Ok(mut client) => {
             let request = tonic::Request::new(crate::proto::api_gateway::ApiInferRequest {
-                prompt: prompt.to_string(),
+                prompt: String::new(),
                 system_prompt: system_prompt.to_string(),
                 max_tokens: 60000,
                 temperature: 0.3,
@@ -1116,11 +2616,21 @@ async fn try_api_gateway_infer_with_provider(
                 requesting_agent: "autonomy-loop".to_string(),
                 task_id: String::new(),
                 allow_fallback: true,
+                sections: sections.to_vec(),
+                session_id: session_id.to_string(),
+                turn_kind: turn_kind.to_string(),
+                response_schema: response_schema.to_string(),
+                model_class: model_class.to_string(),
+                workload: crate::workload::current().to_string(),
+                goal_id: crate::usage::current_goal_id(),
+                goal_budget: crate::usage::current_budget(),
+                bypass_cache: false,
             });
 
             match client.infer(request).await {
                 Ok(response) => {
                     let resp: crate::proto::common::InferenceResponse = response.into_inner();
+                    crate::usage::record_inference(resp.tokens_used.into(), resp.cost_usd);
                     let tool_calls = parse_tool_calls(&resp.text);
                     Some(AiInferenceResult {
                         success: true,
@@ -1128,6 +2638,9 @@ async fn try_api_gateway_infer_with_provider(
                         tool_calls,
                         model_used: resp.model_used,
                         tokens_used: resp.tokens_used,
+                        compressions: resp.compression.into_iter().collect(),
+                        verdict: None,
+                        reviews: Vec::new(),
                     })
                 }
                 Err(e) => {
@@ -1161,6 +2674,7 @@ fn try_heuristic_execution(task: &crate::proto::common::Task) -> Option<Vec<Tool
             return Some(vec![ToolCallRequest {
                 tool_name: "email.send".to_string(),
                 input_json: serde_json::to_vec(&email_input).ok()?,
+                independent: None,
             }]);
         }
     }
@@ -1172,6 +2686,7 @@ fn try_heuristic_execution(task: &crate::proto::common::Task) -> Option<Vec<Tool
         return Some(vec![ToolCallRequest {
             tool_name: "monitor.cpu".to_string(),
             input_json: b"{}".to_vec(),
+            independent: None,
         }]);
     }
     if desc_lower.contains("monitor.memory")
@@ -1180,6 +2695,7 @@ fn try_heuristic_execution(task: &crate::proto::common::Task) -> Option<Vec<Tool
         return Some(vec![ToolCallRequest {
             tool_name: "monitor.memory".to_string(),
             input_json: b"{}".to_vec(),
+            independent: None,
         }]);
     }
     if desc_lower.contains("monitor.disk")
@@ -1189,6 +2705,7 @@ fn try_heuristic_execution(task: &crate::proto::common::Task) -> Option<Vec<Tool
         return Some(vec![ToolCallRequest {
             tool_name: "monitor.disk".to_string(),
             input_json: b"{}".to_vec(),
+            independent: None,
         }]);
     }
 
@@ -1199,6 +2716,7 @@ fn try_heuristic_execution(task: &crate::proto::common::Task) -> Option<Vec<Tool
             return Some(vec![ToolCallRequest {
                 tool_name: "net.ping".to_string(),
                 input_json: serde_json::to_vec(&input).ok()?,
+                independent: None,
             }]);
         }
     }
@@ -1212,6 +2730,7 @@ fn try_heuristic_execution(task: &crate::proto::common::Task) -> Option<Vec<Tool
             return Some(vec![ToolCallRequest {
                 tool_name: "net.dns".to_string(),
                 input_json: serde_json::to_vec(&input).ok()?,
+                independent: None,
             }]);
         }
     }
@@ -1223,6 +2742,7 @@ fn try_heuristic_execution(task: &crate::proto::common::Task) -> Option<Vec<Tool
             return Some(vec![ToolCallRequest {
                 tool_name: "fs.read".to_string(),
                 input_json: serde_json::to_vec(&input).ok()?,
+                independent: None,
             }]);
         }
     }
@@ -1236,6 +2756,7 @@ fn try_heuristic_execution(task: &crate::proto::common::Task) -> Option<Vec<Tool
             return Some(vec![ToolCallRequest {
                 tool_name: "service.status".to_string(),
                 input_json: serde_json::to_vec(&input).ok()?,
+                independent: None,
             }]);
         }
     }
@@ -1493,6 +3014,7 @@ fn extract_explicit_tool_call(desc: &str) -> Option<ToolCallRequest> {
         "sec.",
         "monitor.",
         "hw.",
+        "disk.",
         "web.",
         "git.",
         "code.",
@@ -1528,6 +3050,7 @@ fn extract_explicit_tool_call(desc: &str) -> Option<ToolCallRequest> {
             return Some(ToolCallRequest {
                 tool_name: tool_name.to_string(),
                 input_json: serde_json::to_vec(&input).ok()?,
+                independent: None,
             });
         }
     }
@@ -1572,12 +3095,14 @@ fn parse_tool_calls(response_text: &str) -> Vec<ToolCallRequest> {
                 .get("input")
                 .cloned()
                 .unwrap_or(serde_json::Value::Object(serde_json::Map::new()));
+            let independent = tc.get("independent").and_then(|v| v.as_bool());
 
             if !tool_name.is_empty() {
                 if let Ok(input_bytes) = serde_json::to_vec(&input) {
                     calls.push(ToolCallRequest {
                         tool_name: tool_name.to_string(),
                         input_json: input_bytes,
+                        independent,
                     });
                 }
             }
@@ -1615,52 +3140,209 @@ fn parse_tool_calls(response_text: &str) -> Vec<ToolCallRequest> {
     calls
 }
 
-/// Execute a single tool call via the tools gRPC service
+/// A tool call the tools service ran and reported as failed
+#[derive(Debug)]
+struct ToolCallError {
+    /// "denied", "rate_limited", "error", "timeout", "cancelled" or
+    /// "invalid_input"
+    failure_class: String,
+    message: String,
+    /// The tool is experimental
+    experimental: bool,
+}
+
+impl std::fmt::Display for ToolCallError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ToolCallError {}
+
+/// Failure class of a failed tool call; "error" when the call never reached
+/// the tools service
+fn failure_class(e: &anyhow::Error) -> &str {
+    e.downcast_ref::<ToolCallError>()
+        .map_or("error", |e| e.failure_class.as_str())
+}
+
+/// Whether a failed tool call was of an experimental tool
+fn is_experimental(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<ToolCallError>()
+        .is_some_and(|e| e.experimental)
+}
+
+/// Execute a single tool call via the tools gRPC service. A read-only call
+/// that times out is retried once: timeouts are often transient, and
+/// repeating a read has no side effects.
 async fn execute_tool_call(
-    clients: &crate::clients::ServiceClients,
+    clients: &Arc<crate::clients::ServiceClients>,
+    checkpoints: &crate::task_checkpoint::TaskCheckpoints,
     task_id: &str,
     tool_name: &str,
     input_json: &[u8],
+    allow_experimental: bool,
 ) -> anyhow::Result<serde_json::Value> {
+    // Work done before a restart is not repeated
+    let resume_token = match checkpoints
+        .resume(clients, task_id, tool_name, input_json)
+        .await
+    {
+        crate::task_checkpoint::Resume::Completed(output) => {
+            info!("Tool '{tool_name}' for task {task_id} completed before the restart, reusing its output");
+            return Ok(serde_json::json!({
+                "tool": tool_name,
+                "success": true,
+                "output": output,
+                "resumed": true,
+            }));
+        }
+        crate::task_checkpoint::Resume::Continue(token) => {
+            info!("Resuming tool '{tool_name}' for task {task_id} from {token}");
+            token
+        }
+        crate::task_checkpoint::Resume::Fresh => String::new(),
+    };
+
     let mut client = clients
         .tools()
         .await
         .map_err(|e| anyhow::anyhow!("Cannot connect to tools service: {e}"))?;
 
-    let request = tonic::Request::new(crate::proto::tools::ExecuteRequest {
+    let mut request = crate::proto::tools::ExecuteRequest {
         tool_name: tool_name.to_string(),
         agent_id: "autonomy-loop".to_string(),
         task_id: task_id.to_string(),
         input_json: input_json.to_vec(),
-        reason: format!("Autonomy loop executing tool for task {task_id}"),
+        reason: format!("Autonomy loop executing tool for task {task_id}"),
+        workload: crate::workload::current().to_string(),
+        resume_token,
+        allow_experimental,
+    };
+
+    let mut attempts = 0;
+    let resp = loop {
+        attempts += 1;
+        let response = client
+            .execute(tonic::Request::new(request.clone()))
+            .await
+            .map_err(|e| anyhow::anyhow!("Tool execution gRPC failed: {e}"))?;
+        let resp = response.into_inner();
+        crate::usage::record_tool(&resp);
+        record_tool_call(clients, task_id, tool_name, input_json, &resp);
+        checkpoints
+            .record(clients, task_id, tool_name, input_json, &resp)
+            .await;
+        request.resume_token = resp.progress_token.clone();
+        if resp.failure_class == "timeout" && is_read_only_tool(tool_name) && attempts < 2 {
+            warn!("Tool '{tool_name}' timed out for task {task_id}, retrying once");
+            continue;
+        }
+        break resp;
+    };
+
+    if resp.success {
+        let mut output: serde_json::Value = serde_json::from_slice(&resp.output_json)
+            .unwrap_or_else(|_| {
+                serde_json::Value::String(String::from_utf8_lossy(&resp.output_json).to_string())
+            });
+        // The tools service only validates benchmark requests; the pack
+        // itself drives the other services from here
+        if tool_name == "self.benchmark" {
+            crate::benchmark::start_from_tool(clients, &mut output).await;
+        }
+        Ok(serde_json::json!({
+            "tool": tool_name,
+            "success": true,
+            "output": output,
+            "execution_id": resp.execution_id,
+            "duration_ms": resp.duration_ms,
+        }))
+    } else {
+        Err(ToolCallError {
+            failure_class: resp.failure_class,
+            message: format!("Tool '{}' failed: {}", tool_name, resp.error),
+            experimental: resp.experimental,
+        }
+        .into())
+    }
+}
+
+/// Stop the tool executions still running for cancelled tasks, killing
+/// their child processes. Best effort.
+pub async fn cancel_tool_executions(
+    clients: &Arc<crate::clients::ServiceClients>,
+    task_ids: Vec<String>,
+    reason: &str,
+) {
+    if task_ids.is_empty() {
+        return;
+    }
+    let request = crate::proto::tools::CancelRequest {
+        task_ids,
+        reason: reason.to_string(),
+    };
+    let cancelled = match clients.tools().await {
+        Ok(mut client) => client.cancel(request).await.map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    match cancelled {
+        Ok(response) => {
+            let response = response.into_inner();
+            if !response.execution_ids.is_empty() {
+                info!(
+                    "Cancelled {} running tool executions: {reason}",
+                    response.execution_ids.len()
+                );
+            }
+            if !response.deferred_execution_ids.is_empty() {
+                info!(
+                    "Cancellation of {} tool executions deferred until their critical sections end: {reason}",
+                    response.deferred_execution_ids.len()
+                );
+            }
+        }
+        Err(e) => warn!("Could not cancel tool executions ({reason}): {e}"),
+    }
+}
+
+/// Store a tool call in memory, which also feeds its knowledge graph
+/// (services, packages and their dependencies). Best effort, off the
+/// critical path.
+fn record_tool_call(
+    clients: &Arc<crate::clients::ServiceClients>,
+    task_id: &str,
+    tool_name: &str,
+    input_json: &[u8],
+    resp: &crate::proto::tools::ExecuteResponse,
+) {
+    let record = crate::proto::memory::ToolCallRecord {
+        id: if resp.execution_id.is_empty() {
+            uuid::Uuid::new_v4().to_string()
+        } else {
+            resp.execution_id.clone()
+        },
+        task_id: task_id.to_string(),
+        tool_name: tool_name.to_string(),
+        agent: "autonomy-loop".to_string(),
+        input_json: input_json.to_vec(),
+        output_json: resp.output_json.clone(),
+        success: resp.success,
+        duration_ms: resp.duration_ms,
+        reason: resp.error.clone(),
+        timestamp: chrono::Utc::now().timestamp(),
+    };
+    let clients = clients.clone();
+    tokio::spawn(async move {
+        match clients.memory().await {
+            Ok(mut client) => {
+                if let Err(e) = client.store_tool_call(record).await {
+                    debug!("Failed to record tool call in memory: {e}");
+                }
+            }
+            Err(e) => debug!("Memory service unavailable for tool call record: {e}"),
+        }
     });
-
-    let response = client
-        .execute(request)
-        .await
-        .map_err(|e| anyhow::anyhow!("Tool execution gRPC failed: {e}"))?;
-
-    let resp = response.into_inner();
-
-    if resp.success {
-        let output: serde_json::Value =
-            serde_json::from_slice(&resp.output_json).unwrap_or_else(|_| {
-                serde_json::Value::String(String::from_utf8_lossy(&resp.output_json).to_string())
-            });
-        Ok(serde_json::json!({
-            "tool": tool_name,
-            "success": true,
-            "output": output,
-            "execution_id": resp.execution_id,
-            "duration_ms": resp.duration_ms,
-        }))
-    } else {
-        Err(anyhow::anyhow!(
-            "Tool '{}' failed: {}",
-            tool_name,
-            resp.error
-        ))
-    }
 }
 
 /// Parse clarification request from AI response
@@ -1793,6 +3475,7 @@ fn extract_tools_from_json_fallback(parsed: &serde_json::Value) -> Vec<ToolCallR
                     calls.push(ToolCallRequest {
                         tool_name: tool.to_string(),
                         input_json: bytes,
+                        independent: None,
                     });
                 }
             } else {
@@ -1828,6 +3511,7 @@ fn extract_tools_from_json_fallback(parsed: &serde_json::Value) -> Vec<ToolCallR
                             calls.push(ToolCallRequest {
                                 tool_name: name.to_string(),
                                 input_json: bytes,
+                                independent: None,
                             });
                         }
                     }
@@ -1841,6 +3525,7 @@ fn extract_tools_from_json_fallback(parsed: &serde_json::Value) -> Vec<ToolCallR
                         calls.push(ToolCallRequest {
                             tool_name: name.to_string(),
                             input_json: bytes,
+                            independent: None,
                         });
                     }
                 }
@@ -1867,6 +3552,7 @@ fn extract_tools_from_json_fallback(parsed: &serde_json::Value) -> Vec<ToolCallR
                         calls.push(ToolCallRequest {
                             tool_name: tool.to_string(),
                             input_json: bytes,
+                            independent: None,
                         });
                     }
                 }
@@ -1992,6 +3678,7 @@ fn extract_tools_from_natural_language(text: &str) -> Vec<ToolCallRequest> {
                         calls.push(ToolCallRequest {
                             tool_name,
                             input_json: b"{}".to_vec(),
+                            independent: None,
                         });
                     }
                 }
@@ -2030,6 +3717,7 @@ fn extract_tools_from_natural_language(text: &str) -> Vec<ToolCallRequest> {
                         calls.push(ToolCallRequest {
                             tool_name,
                             input_json: b"{}".to_vec(),
+                            independent: None,
                         });
                     }
                 }
@@ -2324,7 +4012,10 @@ fn summarize_tool_output(tool_name: &str, output: Option<&serde_json::Value>) ->
 /// Build a human-readable summary from the AI response and tool execution results.
 /// This gets posted as an "ai" message so users can see what the AI reasoned and
 /// what tool outputs were produced, instead of just "Task completed".
-fn build_completion_summary(response_text: &str, tool_results: &[serde_json::Value]) -> String {
+pub(crate) fn build_completion_summary(
+    response_text: &str,
+    tool_results: &[serde_json::Value],
+) -> String {
     let mut parts = Vec::new();
 
     // Extract readable AI reasoning from the response (handles prose-wrapped JSON, fences, etc.)
@@ -2386,6 +4077,8 @@ async fn record_ai_result(
     result: AiInferenceResult,
     tool_exec: ToolExecutionResult,
 ) {
+    let language = crate::locale::for_goal(&state.goal_engine, goal_id, state.language);
+
     // Log what the AI returned for debugging
     let tool_count = result.tool_calls.len();
     let response_preview: String = result.response_text.chars().take(200).collect();
@@ -2394,6 +4087,107 @@ async fn record_ai_result(
         tool_count, result.tokens_used, result.model_used, response_preview
     );
 
+    // Record what the gateway cut from the prompt to fit a context window
+    for report in &result.compressions {
+        let options: Vec<String> = report
+            .dropped
+            .iter()
+            .chain(&report.summarized)
+            .cloned()
+            .collect();
+        let list = |labels: &[String]| {
+            if labels.is_empty() {
+                "none".to_string()
+            } else {
+                labels.join(", ")
+            }
+        };
+        state.decision_logger.log_decision(
+            "prompt_compression",
+            &options,
+            &report.provider,
+            &format!(
+                "Task {task_id}: prompt compressed from {} to {} tokens for the {}-token {} context window; dropped: {}; summarized: {}",
+                report.original_tokens,
+                report.final_tokens,
+                report.context_window,
+                report.provider,
+                list(&report.dropped),
+                list(&report.summarized)
+            ),
+            intelligence_level,
+            &result.model_used,
+        );
+    }
+
+    // Record both opinions of every peer review
+    for review in &result.reviews {
+        state.decision_logger.log_decision(
+            "peer_review",
+            &review.opinions(),
+            if review.agreed() {
+                "agreed"
+            } else {
+                "escalated"
+            },
+            &format!(
+                "Task {task_id}: plan with risky calls\n{}",
+                review.risky_calls()
+            ),
+            intelligence_level,
+            &review.reviewer.model,
+        );
+    }
+
+    // A plan the reviewer objected to waits for an operator
+    if let Some(review) = result.reviews.last().filter(|r| !r.agreed()) {
+        let concerns = if review.reviewer.concerns.is_empty() {
+            String::new()
+        } else {
+            format!(" Concerns: {}.", review.reviewer.concerns.join("; "))
+        };
+        let objection = if review.by_policy() {
+            format!("{}, so they have not run.", review.reviewer.reasoning)
+        } else {
+            format!(
+                "A second model ({}) objected to the planned actions, so they have not run: \
+                 {}{concerns}",
+                review.reviewer.model, review.reviewer.reasoning
+            )
+        };
+        state.goal_engine.add_message(
+            goal_id,
+            "system",
+            &format!(
+                "{objection}\nRisky calls:\n{}\nApprove or reject them on the console's Plan \
+                 Reviews panel, or reply to have the plan made again.",
+                review.risky_calls()
+            ),
+        );
+        state.task_planner.mark_awaiting_input(task_id);
+        state.goal_engine.update_task_status(
+            goal_id,
+            task_id,
+            "awaiting_input",
+            if review.by_policy() {
+                "the approval policy requires an operator"
+            } else {
+                "peer review objected to the plan"
+            },
+            "autonomy",
+        );
+        state.notifier.notify(
+            "approval",
+            crate::event_bus::EventSeverity::Warning,
+            &crate::locale::for_goal(&state.goal_engine, goal_id, state.language)
+                .text("plan_approval", &[("goal", goal_id)]),
+            &review.reviewer.reasoning,
+            task_id,
+        );
+        info!("Task {task_id}: peer review escalated the plan for approval");
+        return;
+    }
+
     // If the AI inference itself failed (all backends down), mark the task
     // as failed rather than silently succeeding or waiting for input.
     if !result.success && result.tool_calls.is_empty() {
@@ -2403,13 +4197,15 @@ async fn record_ai_result(
             result.response_text.clone()
         };
 
-        state.task_planner.fail_task(task_id, &error_msg);
-        state
-            .goal_engine
-            .update_task_status(goal_id, task_id, "failed");
-        state
-            .goal_engine
-            .add_message(goal_id, "system", &format!("Task failed: {error_msg}"));
+        fail_or_retry(
+            state,
+            goal_id,
+            task_id,
+            &error_msg,
+            FailureKind::Transient,
+            "AI inference failed",
+            "autonomy",
+        );
 
         state.result_aggregator.record_result(
             goal_id,
@@ -2421,6 +4217,7 @@ async fn record_ai_result(
                 duration_ms: 0,
                 tokens_used: result.tokens_used,
                 model_used: result.model_used.clone(),
+                ..Default::default()
             },
         );
 
@@ -2446,12 +4243,18 @@ async fn record_ai_result(
                 "AI was unable to produce executable tool calls after multiple attempts. \
                              The model may not support the required JSON output format.";
             state.task_planner.fail_task(task_id, error_msg);
-            state
-                .goal_engine
-                .update_task_status(goal_id, task_id, "failed");
-            state
-                .goal_engine
-                .add_message(goal_id, "system", &format!("Task failed: {error_msg}"));
+            state.goal_engine.update_task_status(
+                goal_id,
+                task_id,
+                "failed",
+                &format!("no tool calls after {ai_msg_count} attempts"),
+                "autonomy",
+            );
+            state.goal_engine.add_message(
+                goal_id,
+                "system",
+                &language.text("task_failed", &[("error", error_msg)]),
+            );
             warn!("Task {task_id}: Failed after {ai_msg_count} attempts without tool calls");
             return;
         }
@@ -2471,9 +4274,20 @@ async fn record_ai_result(
         }
 
         state.task_planner.mark_awaiting_input(task_id);
-        state
-            .goal_engine
-            .update_task_status(goal_id, task_id, "awaiting_input");
+        state.goal_engine.update_task_status(
+            goal_id,
+            task_id,
+            "awaiting_input",
+            "AI returned no tool calls",
+            "autonomy",
+        );
+        state.notifier.notify(
+            "approval",
+            crate::event_bus::EventSeverity::Warning,
+            &language.text("awaiting_input", &[("goal", goal_id)]),
+            task_description,
+            task_id,
+        );
 
         info!("Task {task_id}: No tools executed, awaiting user input (attempt {ai_msg_count})");
         return;
@@ -2493,13 +4307,25 @@ async fn record_ai_result(
             .collect::<Vec<_>>()
             .join("; ");
 
-        state.task_planner.fail_task(task_id, &error_msg);
-        state
-            .goal_engine
-            .update_task_status(goal_id, task_id, "failed");
-        state
-            .goal_engine
-            .add_message(goal_id, "system", &format!("Task failed: {error_msg}"));
+        let kind = crate::task_planner::classify_tool_failures(
+            tool_results
+                .iter()
+                .filter(|r| r.get("success").and_then(|v| v.as_bool()) == Some(false))
+                .map(|r| {
+                    r.get("failure_class")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("error")
+                }),
+        );
+        fail_or_retry(
+            state,
+            goal_id,
+            task_id,
+            &error_msg,
+            kind,
+            "tool execution failed",
+            "autonomy",
+        );
 
         state.result_aggregator.record_result(
             goal_id,
@@ -2511,6 +4337,7 @@ async fn record_ai_result(
                 duration_ms: 0,
                 tokens_used: result.tokens_used,
                 model_used: result.model_used.clone(),
+                ..Default::default()
             },
         );
 
@@ -2527,6 +4354,67 @@ async fn record_ai_result(
         return;
     }
 
+    // All tools succeeded, but the verification pass found the task undone
+    if let Some(verdict) = result.verdict.as_ref().filter(|v| !v.achieved) {
+        let mut error_msg = format!("Objective not met: {}", verdict.reason);
+        if !verdict.next_steps.is_empty() {
+            error_msg.push_str(&format!(" (next: {})", verdict.next_steps.join("; ")));
+        }
+
+        fail_or_retry(
+            state,
+            goal_id,
+            task_id,
+            &error_msg,
+            FailureKind::Unverified,
+            "verification found the objective unmet",
+            "autonomy",
+        );
+
+        state.result_aggregator.record_result(
+            goal_id,
+            crate::proto::common::TaskResult {
+                task_id: task_id.to_string(),
+                success: false,
+                output_json: serde_json::to_vec(&tool_results).unwrap_or_default(),
+                error: error_msg.clone(),
+                duration_ms: 0,
+                tokens_used: result.tokens_used,
+                model_used: result.model_used.clone(),
+                ..Default::default()
+            },
+        );
+
+        state.decision_logger.log_decision(
+            "task_verification",
+            &[task_id.to_string()],
+            "objective_unmet",
+            &format!("Task '{task_description}': {error_msg}"),
+            intelligence_level,
+            &result.model_used,
+        );
+
+        warn!("Task {task_id} failed verification: {}", verdict.reason);
+        return;
+    }
+    if let Some(verdict) = &result.verdict {
+        state.decision_logger.log_decision(
+            "task_verification",
+            &[task_id.to_string()],
+            "objective_met",
+            &format!("Task '{task_description}': {}", verdict.reason),
+            intelligence_level,
+            &result.model_used,
+        );
+        if !verdict.next_steps.is_empty() {
+            state.goal_engine.add_message(
+                goal_id,
+                "ai",
+                &language.text("next_steps", &[("steps", &verdict.next_steps.join("; "))]),
+            );
+        }
+    }
+
     // All tools succeeded — build combined output
     let output = serde_json::to_vec(&serde_json::json!({
         "ai_response": result.response_text,
@@ -2545,12 +4433,29 @@ async fn record_ai_result(
     state.goal_engine.add_message(
         goal_id,
         "system",
-        &format!("Task completed: {task_description}"),
+        &language.text("task_completed", &[("task", task_description)]),
     );
 
     // Mark task complete in both planners
     state.task_planner.complete_task(task_id, output.clone());
-    state.goal_engine.complete_task(goal_id, task_id);
+    state
+        .goal_engine
+        .complete_task(goal_id, task_id, "all tool calls succeeded", "autonomy");
+
+    // The AI may declare follow-up subgoals alongside its tool calls
+    let specs = extract_json_from_text(&result.response_text)
+        .map(|v| crate::goal_engine::parse_spawn_goals(&v))
+        .unwrap_or_default();
+    if !specs.is_empty() {
+        match state
+            .goal_engine
+            .spawn_subgoals(goal_id, task_id, specs)
+            .await
+        {
+            Ok(ids) => info!("Task {task_id} spawned {} subgoals", ids.len()),
+            Err(e) => warn!("Task {task_id} could not spawn subgoals: {e}"),
+        }
+    }
 
     // Record result
     state.result_aggregator.record_result(
@@ -2563,6 +4468,7 @@ async fn record_ai_result(
             duration_ms: 0,
             tokens_used: result.tokens_used,
             model_used: result.model_used,
+            ..Default::default()
         },
     );
 
@@ -2589,8 +4495,212 @@ mod tests {
     #[test]
     fn test_autonomy_config_default() {
         let config = AutonomyConfig::default();
-        assert_eq!(config.tick_interval, Duration::from_millis(500));
+        assert_eq!(config.tick_interval, Duration::from_secs(5));
+        assert_eq!(config.min_tick_interval, Duration::from_millis(50));
         assert_eq!(config.max_concurrent_tasks, 10);
+        assert_eq!(config.preemption.mode, PreemptionMode::Pause);
+        assert_eq!(config.preemption.urgent_priority, 1);
+    }
+
+    #[test]
+    fn test_preemption_policy_urgency() {
+        let policy = PreemptionPolicy::default();
+        assert!(policy.is_urgent(1));
+        assert!(!policy.is_urgent(2));
+        assert!(!policy.is_urgent(0));
+
+        let policy = PreemptionPolicy {
+            mode: PreemptionMode::Deprioritize,
+            urgent_priority: 2,
+        };
+        assert!(policy.is_urgent(2));
+        assert!(!policy.is_urgent(5));
+
+        let policy = PreemptionPolicy {
+            mode: PreemptionMode::Off,
+            urgent_priority: 1,
+        };
+        assert!(!policy.is_urgent(1));
+        assert_eq!(PreemptionMode::parse("pause"), Some(PreemptionMode::Pause));
+        assert_eq!(PreemptionMode::parse("later"), None);
+    }
+
+    #[test]
+    fn test_autonomy_waker_coalesces_wakeups() {
+        let waker = AutonomyWaker::default();
+        assert!(waker.take_pending().is_none());
+
+        waker.wake();
+        std::thread::sleep(Duration::from_millis(5));
+        waker.wake();
+        // Latency is measured from the first outstanding wake-up
+        assert!(waker.take_pending().unwrap() >= Duration::from_millis(5));
+        assert!(waker.take_pending().is_none());
+    }
+
+    #[test]
+    fn test_loop_metrics_record_tick() {
+        let mut metrics = LoopMetrics::default();
+        metrics.record_tick(
+            WakeReason::Event,
+            Duration::from_millis(10),
+            Some(Duration::from_millis(4)),
+        );
+        assert_eq!(metrics.ticks, 1);
+        assert_eq!(metrics.event_ticks, 1);
+        assert_eq!(metrics.avg_tick_ms, 10.0);
+        assert_eq!(metrics.avg_wake_latency_ms, 4.0);
+
+        metrics.record_tick(WakeReason::Fallback, Duration::from_millis(20), None);
+        assert_eq!(metrics.ticks, 2);
+        assert_eq!(metrics.fallback_ticks, 1);
+        assert_eq!(metrics.last_tick_ms, 20.0);
+        assert_eq!(metrics.max_tick_ms, 20.0);
+        assert!((metrics.avg_tick_ms - 12.0).abs() < 1e-9);
+        // Ticks without a wake-up leave latency stats untouched
+        assert_eq!(metrics.last_wake_latency_ms, 4.0);
+    }
+
+    fn idle_state() -> OrchestratorState {
+        OrchestratorState {
+            goal_engine: crate::goal_engine::GoalEngine::new(),
+            task_planner: crate::task_planner::TaskPlanner::new(),
+            agent_router: crate::agent_router::AgentRouter::new(),
+            result_aggregator: crate::result_aggregator::ResultAggregator::new(),
+            decision_logger: crate::decision_logger::DecisionLogger::new(),
+            started_at: std::time::Instant::now(),
+            cancel_token: CancellationToken::new(),
+            clients: Arc::new(crate::clients::ServiceClients::new()),
+            health_checker: Arc::new(RwLock::new(crate::health::HealthChecker::new())),
+            cluster: Arc::new(RwLock::new(crate::cluster::ClusterManager::new("test"))),
+            autonomy_waker: Arc::new(AutonomyWaker::default()),
+            autonomy_metrics: Arc::new(std::sync::Mutex::new(LoopMetrics::default())),
+            timers: Arc::new(crate::timers::LocalTimers::default()),
+            drain: Arc::new(crate::shutdown::Drain::default()),
+            budget_degradation: Default::default(),
+            provider_health: Default::default(),
+            tool_usage: Default::default(),
+            calendar: Default::default(),
+            notifier: Default::default(),
+            staging: Default::default(),
+            task_checkpoints: Default::default(),
+            peer_review: Default::default(),
+            artifacts: Default::default(),
+            task_workers: Default::default(),
+            language: Default::default(),
+            usage: Default::default(),
+            traces: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_autonomy_loop_wakes_on_event() {
+        let state = Arc::new(RwLock::new(idle_state()));
+        let (waker, metrics) = {
+            let s = state.read().await;
+            (s.autonomy_waker.clone(), s.autonomy_metrics.clone())
+        };
+
+        let cancel = CancellationToken::new();
+        let handle = tokio::spawn(run_autonomy_loop(
+            state,
+            cancel.clone(),
+            AutonomyConfig {
+                tick_interval: Duration::from_secs(60),
+                ..Default::default()
+            },
+            Default::default(),
+        ));
+
+        waker.wake();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        cancel.cancel();
+        handle.await.unwrap();
+
+        let metrics = metrics.lock().unwrap().clone();
+        assert_eq!(metrics.event_ticks, 1);
+        assert_eq!(metrics.fallback_ticks, 0);
+    }
+
+    /// An urgent goal with its task under way, and a ready task of a
+    /// routine goal that `pause` holds back meanwhile
+    async fn paused_state() -> OrchestratorState {
+        let mut state = idle_state();
+        let task = |id: &str, goal_id: &str, status: &str| crate::proto::common::Task {
+            id: id.into(),
+            goal_id: goal_id.into(),
+            description: "Rotate the logs".into(),
+            status: status.into(),
+            ..Default::default()
+        };
+        for (priority, task_id, status) in [
+            (1, "urgent-task", "in_progress"),
+            (5, "routine-task", "pending"),
+        ] {
+            let goal_id = state
+                .goal_engine
+                .submit_goal("Keep the host healthy".into(), priority, "console".into())
+                .await
+                .unwrap();
+            let task = task(task_id, &goal_id, status);
+            state.goal_engine.add_tasks(&goal_id, vec![task.clone()]);
+            state.task_planner.add_tasks(&[task]);
+        }
+        state
+    }
+
+    #[tokio::test]
+    async fn test_pause_holds_back_routine_tasks() {
+        let state = paused_state().await;
+        let policy = PreemptionPolicy::default();
+        let urgent_active = urgent_goal_active(&state.goal_engine, &policy);
+        assert!(urgent_active);
+        assert!(dispatchable_tasks(
+            &state.goal_engine,
+            &state.task_planner,
+            &policy,
+            urgent_active
+        )
+        .is_empty());
+
+        // Deprioritizing still dispatches them, after urgent work
+        let policy = PreemptionPolicy {
+            mode: PreemptionMode::Deprioritize,
+            ..policy
+        };
+        let ready = dispatchable_tasks(&state.goal_engine, &state.task_planner, &policy, true);
+        assert_eq!(ready.len(), 1);
+        assert_eq!(ready[0].id, "routine-task");
+    }
+
+    #[tokio::test]
+    async fn test_autonomy_loop_idles_while_paused() {
+        let state = Arc::new(RwLock::new(paused_state().await));
+        let (waker, metrics) = {
+            let s = state.read().await;
+            (s.autonomy_waker.clone(), s.autonomy_metrics.clone())
+        };
+
+        let cancel = CancellationToken::new();
+        let handle = tokio::spawn(run_autonomy_loop(
+            state,
+            cancel.clone(),
+            AutonomyConfig {
+                tick_interval: Duration::from_secs(60),
+                ..Default::default()
+            },
+            Default::default(),
+        ));
+
+        waker.wake();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        cancel.cancel();
+        handle.await.unwrap();
+
+        // The held-back task is no backlog: the loop waits for an event
+        let metrics = metrics.lock().unwrap().clone();
+        assert_eq!(metrics.event_ticks, 1);
+        assert_eq!(metrics.backlog_ticks, 0);
     }
 
     #[test]
@@ -2654,7 +4764,9 @@ mod tests {
 
     #[test]
     fn test_is_completion_signal() {
-        assert!(is_completion_signal(r#"{"done": true, "summary": "all done"}"#));
+        assert!(is_completion_signal(
+            r#"{"done": true, "summary": "all done"}"#
+        ));
         assert!(!is_completion_signal(r#"{"done": false}"#));
         assert!(!is_completion_signal(r#"{"tool_calls": []}"#));
         assert!(!is_completion_signal("not json"));
@@ -2675,6 +4787,134 @@ mod tests {
         assert_eq!(calls[1].tool_name, "net.ping");
     }
 
+    #[test]
+    fn test_parse_tool_calls_independent_hint() {
+        let response = r#"{"tool_calls": [{"tool": "fs.write", "input": {}, "independent": true}, {"tool": "fs.read", "input": {}, "independent": false}, {"tool": "fs.stat", "input": {}}]}"#;
+        let calls = parse_tool_calls(response);
+        assert_eq!(calls.len(), 3);
+        assert_eq!(calls[0].independent, Some(true));
+        assert!(calls[0].is_independent());
+        assert_eq!(calls[1].independent, Some(false));
+        assert!(!calls[1].is_independent());
+        assert_eq!(calls[2].independent, None);
+        assert!(calls[2].is_independent());
+    }
+
+    #[test]
+    fn test_condense_tool_output() {
+        let small = serde_json::json!({"cpu_percent": 12.5});
+        assert_eq!(
+            condense_tool_output(&small, 2000),
+            r#"{"cpu_percent":12.5}"#
+        );
+
+        let big = serde_json::json!({
+            "content": format!("HEAD{}TAIL", "é".repeat(10_000)),
+            "lines": (0..500).collect::<Vec<_>>(),
+            "_truncated": {"page_handle": "exec-42"},
+        });
+        let condensed = condense_tool_output(&big, 2000);
+        assert!(condensed.chars().count() < 2300);
+        assert!(condensed.contains("HEAD"));
+        assert!(condensed.contains("TAIL"));
+        assert!(condensed.contains("chars omitted"));
+        assert!(condensed.contains("490 more items"));
+        assert!(condensed.contains("output.page with handle \"exec-42\""));
+    }
+
+    #[tokio::test]
+    async fn test_experimental_tools_opt_in_and_failures() {
+        let mut goals = crate::goal_engine::GoalEngine::new();
+        let root = goals
+            .submit_goal("Try the new feed digest".into(), 5, "console".into())
+            .await
+            .unwrap();
+        let child = goals
+            .spawn_subgoals(
+                &root,
+                "task-1",
+                vec![crate::goal_engine::SubgoalSpec {
+                    description: "Digest the release feed".into(),
+                    priority: None,
+                }],
+            )
+            .await
+            .unwrap()
+            .remove(0);
+        assert!(!allows_experimental_tools(&goals, &child));
+        goals
+            .update_labels(&root, &[EXPERIMENTAL_TOOLS_LABEL.to_string()], &[])
+            .unwrap();
+        assert!(allows_experimental_tools(&goals, &child));
+
+        let failed = |experimental| -> anyhow::Error {
+            ToolCallError {
+                failure_class: "error".into(),
+                message: "Tool 'plugin.feed_digest' failed".into(),
+                experimental,
+            }
+            .into()
+        };
+        assert!(is_experimental(&failed(true)));
+        assert!(!is_experimental(&failed(false)));
+        assert!(!is_experimental(&anyhow::anyhow!("connection refused")));
+    }
+
+    #[test]
+    fn test_is_read_only_tool() {
+        assert!(is_read_only_tool("monitor.cpu"));
+        assert!(is_read_only_tool("hw.info"));
+        assert!(is_read_only_tool("fs.read"));
+        assert!(is_read_only_tool("disk.list_block_devices"));
+        assert!(!is_read_only_tool("disk.mount"));
+        assert!(is_read_only_tool("service.status"));
+        assert!(!is_read_only_tool("fs.write"));
+        assert!(!is_read_only_tool("service.restart"));
+        assert!(!is_read_only_tool("plugin.list"));
+        assert!(!is_read_only_tool("nonamespace"));
+    }
+
+    #[test]
+    fn test_read_only_goals_deny_writes() {
+        let call = |name: &str| ToolCallRequest {
+            tool_name: name.to_string(),
+            input_json: b"{}".to_vec(),
+            independent: None,
+        };
+        let calls = vec![call("hw.info"), call("service.restart")];
+        assert_eq!(deny_writes(true, &calls), Some("service.restart"));
+        assert_eq!(deny_writes(false, &calls), None);
+        assert_eq!(deny_writes(true, &calls[..1]), None);
+
+        // The onboarding pack only suggests tools it is allowed to call
+        for template in crate::onboarding::TEMPLATES {
+            assert!(template.tools.iter().all(|t| is_read_only_tool(t)));
+        }
+    }
+
+    #[test]
+    fn test_plan_tool_batches() {
+        let call = |name: &str| ToolCallRequest {
+            tool_name: name.to_string(),
+            input_json: b"{}".to_vec(),
+            independent: None,
+        };
+        let calls = vec![
+            call("monitor.cpu"),
+            call("monitor.memory"),
+            call("fs.write"),
+            call("fs.read"),
+            call("net.ping"),
+            call("net.dns"),
+            call("service.restart"),
+        ];
+        assert_eq!(
+            plan_tool_batches(&calls),
+            vec![vec![0, 1], vec![2], vec![3, 4, 5], vec![6]]
+        );
+        assert!(plan_tool_batches(&[]).is_empty());
+    }
+
     #[test]
     fn test_heuristic_email_with_quotes() {
         let task = crate::proto::common::Task {
@@ -2692,6 +4932,8 @@ mod tests {
             started_at: 0,
             completed_at: 0,
             error: String::new(),
+            model_class: String::new(),
+            provider_hint: String::new(),
         };
 
         let calls = try_heuristic_execution(&task);
@@ -2724,6 +4966,8 @@ mod tests {
             started_at: 0,
             completed_at: 0,
             error: String::new(),
+            model_class: String::new(),
+            provider_hint: String::new(),
         };
 
         let calls = try_heuristic_execution(&task);
@@ -2751,6 +4995,8 @@ mod tests {
             started_at: 0,
             completed_at: 0,
             error: String::new(),
+            model_class: String::new(),
+            provider_hint: String::new(),
         };
 
         let calls = try_heuristic_execution(&task);
@@ -2775,6 +5021,8 @@ mod tests {
             started_at: 0,
             completed_at: 0,
             error: String::new(),
+            model_class: String::new(),
+            provider_hint: String::new(),
         };
 
         let calls = try_heuristic_execution(&task);
@@ -2802,6 +5050,8 @@ mod tests {
             started_at: 0,
             completed_at: 0,
             error: String::new(),
+            model_class: String::new(),
+            provider_hint: String::new(),
         };
 
         let calls = try_heuristic_execution(&task);
@@ -2830,6 +5080,8 @@ mod tests {
             started_at: 0,
             completed_at: 0,
             error: String::new(),
+            model_class: String::new(),
+            provider_hint: String::new(),
         };
 
         let calls = try_heuristic_execution(&task);
@@ -2857,6 +5109,8 @@ mod tests {
             started_at: 0,
             completed_at: 0,
             error: String::new(),
+            model_class: String::new(),
+            provider_hint: String::new(),
         };
 
         let calls = try_heuristic_execution(&task);
@@ -2872,20 +5126,35 @@ mod tests {
         assert!(params["body"].as_str().unwrap().contains("aiOS"));
     }
 
+    #[test]
+    fn test_parse_verdict() {
+        let verdict = parse_verdict(
+            r#"{"achieved": false, "reason": "nginx is installed but not running", "next_steps": ["start nginx"]}"#,
+        )
+        .unwrap();
+        assert!(!verdict.achieved);
+        assert_eq!(verdict.reason, "nginx is installed but not running");
+        assert_eq!(verdict.next_steps, vec!["start nginx"]);
+
+        let verdict = parse_verdict("Sure:\n```json\n{\"achieved\": true}\n```").unwrap();
+        assert!(verdict.achieved);
+        assert!(verdict.next_steps.is_empty());
+
+        assert!(parse_verdict(r#"{"reason": "no verdict"}"#).is_none());
+        assert!(parse_verdict("looks good to me").is_none());
+    }
+
+    #[test]
+    fn test_reasoning_loop_config_reflects_by_level() {
+        assert!(!ReasoningLoopConfig::for_level(&IntelligenceLevel::Operational).reflect);
+        let strategic = ReasoningLoopConfig::for_level(&IntelligenceLevel::Strategic);
+        assert!(strategic.reflect);
+        assert_eq!(strategic.max_rounds, 5);
+    }
+
     #[tokio::test]
     async fn test_autonomy_loop_cancellation() {
-        let state = Arc::new(RwLock::new(OrchestratorState {
-            goal_engine: crate::goal_engine::GoalEngine::new(),
-            task_planner: crate::task_planner::TaskPlanner::new(),
-            agent_router: crate::agent_router::AgentRouter::new(),
-            result_aggregator: crate::result_aggregator::ResultAggregator::new(),
-            decision_logger: crate::decision_logger::DecisionLogger::new(),
-            started_at: std::time::Instant::now(),
-            cancel_token: CancellationToken::new(),
-            clients: Arc::new(crate::clients::ServiceClients::new()),
-            health_checker: Arc::new(RwLock::new(crate::health::HealthChecker::new())),
-            cluster: Arc::new(RwLock::new(crate::cluster::ClusterManager::new("test"))),
-        }));
+        let state = Arc::new(RwLock::new(idle_state()));
 
         let cancel = CancellationToken::new();
         let cancel_clone = cancel.clone();
@@ -2898,6 +5167,7 @@ mod tests {
                     tick_interval: Duration::from_millis(50),
                     ..Default::default()
                 },
+                Default::default(),
             )
             .await;
         });
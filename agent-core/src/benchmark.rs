@@ -0,0 +1,605 @@
+//! Built-in Benchmark Pack
+//!
+//! Measures tool latency, inference throughput, memory search latency, and
+//! end-to-end goal time by driving the live services through the same
+//! clients the autonomy loop uses. Runs are requested through the
+//! `self.benchmark` tool (manually or by the nightly schedule) or start on
+//! their own after a self-update, and every run is stored in working memory.
+//!
+//! Each run is compared with recent runs of the previous build; metrics that
+//! got markedly worse are recorded as regressions, and on the first run of a
+//! new build they are raised as incidents.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::clients::ServiceClients;
+
+/// Suites in the pack, in run order
+pub const SUITES: &[&str] = &["tool_latency", "inference", "memory_search", "goal_e2e"];
+
+/// Id of the nightly schedule registered at startup
+pub const NIGHTLY_SCHEDULE_ID: &str = "nightly-benchmark";
+
+/// Nightly schedule: 03:30 UTC, outside the usual working hours
+pub const NIGHTLY_CRON: &str = "30 3 * * *";
+
+/// Goal the nightly schedule submits
+pub const NIGHTLY_GOAL: &str =
+    "Run the nightly self-benchmark: call self.benchmark with {\"trigger\": \"nightly\"}";
+
+/// How long to let the system settle after a restart before the
+/// post-update run
+const POST_UPDATE_DELAY: Duration = Duration::from_secs(300);
+
+/// Most inference samples per run; each one is a full model call
+const MAX_INFERENCE_SAMPLES: u32 = 3;
+
+/// How long the end-to-end probe goal may take
+const GOAL_E2E_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Runs of the previous build the baseline is taken from
+const BASELINE_RUNS: usize = 5;
+
+/// A metric regressed when it is this much worse than the baseline
+const REGRESSION_THRESHOLD_PERCENT: f64 = 25.0;
+
+/// Only one run at a time; runs would skew each other's numbers
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Result of one suite
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct BenchmarkResult {
+    pub name: String,
+    pub unit: String,
+    pub value: f64,
+    pub samples: u32,
+    pub higher_is_better: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A metric that got worse compared with the previous build
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Regression {
+    pub name: String,
+    pub unit: String,
+    pub baseline: f64,
+    pub value: f64,
+    pub change_percent: f64,
+}
+
+/// Parameters of a requested run, as accepted by the `self.benchmark` tool
+#[derive(Debug, Clone, serde::Deserialize)]
+struct RunRequest {
+    #[serde(default)]
+    suites: Vec<String>,
+    #[serde(default = "default_trigger")]
+    trigger: String,
+    #[serde(default = "default_samples")]
+    samples: u32,
+}
+
+fn default_trigger() -> String {
+    "manual".to_string()
+}
+
+fn default_samples() -> u32 {
+    10
+}
+
+/// Identity of the running build; the executable's mtime changes with every
+/// self-update even when the package version does not
+pub fn build_id() -> String {
+    let mtime = std::env::current_exe()
+        .and_then(std::fs::metadata)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    format!("{}+{mtime}", env!("CARGO_PKG_VERSION"))
+}
+
+/// Start a run for a successful `self.benchmark` call and fold the run id
+/// and the previous run's results into the tool output
+pub async fn start_from_tool(clients: &Arc<ServiceClients>, output: &mut serde_json::Value) {
+    let request: RunRequest = match serde_json::from_value(output.clone()) {
+        Ok(r) => r,
+        Err(e) => {
+            warn!("self.benchmark returned unexpected output: {e}");
+            return;
+        }
+    };
+
+    let previous = recent_runs(clients, 1).await.into_iter().next();
+    let status = match start(clients.clone(), request) {
+        Some(run_id) => serde_json::json!({"status": "started", "run_id": run_id}),
+        None => serde_json::json!({"status": "already_running"}),
+    };
+    if let (Some(obj), serde_json::Value::Object(status)) = (output.as_object_mut(), status) {
+        obj.extend(status);
+        if let Some(prev) = previous {
+            obj.insert(
+                "previous_run".to_string(),
+                serde_json::json!({
+                    "id": prev.id,
+                    "started_at": prev.started_at,
+                    "build": prev.build,
+                    "results": serde_json::from_slice::<serde_json::Value>(&prev.results_json)
+                        .unwrap_or_default(),
+                    "regressions": serde_json::from_slice::<serde_json::Value>(&prev.regressions_json)
+                        .unwrap_or_default(),
+                }),
+            );
+        }
+    }
+}
+
+/// Start a run after a self-update: once the system has settled, run the
+/// pack if no run has been stored for the current build yet
+pub async fn run_after_update(clients: Arc<ServiceClients>, cancel: CancellationToken) {
+    tokio::select! {
+        _ = cancel.cancelled() => return,
+        _ = tokio::time::sleep(POST_UPDATE_DELAY) => {}
+    }
+
+    let build = build_id();
+    let latest = recent_runs(&clients, 1).await.into_iter().next();
+    // No history yet: the nightly run establishes the first baseline
+    if let Some(run) = latest.filter(|r| r.build != build) {
+        info!(
+            "Build changed ({} -> {build}); starting post-update benchmark",
+            run.build
+        );
+        start(
+            clients,
+            RunRequest {
+                suites: Vec::new(),
+                trigger: "post_update".to_string(),
+                samples: default_samples(),
+            },
+        );
+    }
+}
+
+/// Spawn a run unless one is already going; returns the new run's id
+fn start(clients: Arc<ServiceClients>, request: RunRequest) -> Option<String> {
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return None;
+    }
+    let run_id = uuid::Uuid::new_v4().to_string();
+    let id = run_id.clone();
+    tokio::spawn(async move {
+        run(&clients, &id, request).await;
+        RUNNING.store(false, Ordering::SeqCst);
+    });
+    Some(run_id)
+}
+
+async fn run(clients: &ServiceClients, run_id: &str, request: RunRequest) {
+    let started_at = chrono::Utc::now().timestamp();
+    let started = Instant::now();
+    let build = build_id();
+    info!(
+        "Benchmark run {run_id} started (trigger: {}, build: {build})",
+        request.trigger
+    );
+
+    let mut results = Vec::new();
+    for suite in SUITES {
+        if !request.suites.is_empty() && !request.suites.iter().any(|s| s == suite) {
+            continue;
+        }
+        let result = match *suite {
+            "tool_latency" => tool_latency(clients, request.samples).await,
+            "inference" => inference(clients, request.samples.min(MAX_INFERENCE_SAMPLES)).await,
+            "memory_search" => memory_search(clients, request.samples).await,
+            _ => goal_e2e(clients).await,
+        };
+        results.push(result);
+    }
+
+    let history = recent_runs(clients, 20).await;
+    let baseline = baseline_results(&history, &build);
+    let regressions = if baseline.is_empty() {
+        Vec::new()
+    } else {
+        find_regressions(&results, &baseline)
+    };
+    let first_of_build = !history.iter().any(|r| r.build == build);
+
+    let record = crate::proto::memory::BenchmarkRun {
+        id: run_id.to_string(),
+        started_at,
+        duration_ms: started.elapsed().as_millis() as i64,
+        trigger: request.trigger.clone(),
+        build: build.clone(),
+        results_json: serde_json::to_vec(&results).unwrap_or_default(),
+        regressions_json: serde_json::to_vec(&regressions).unwrap_or_default(),
+    };
+    match clients.memory().await {
+        Ok(mut client) => {
+            if let Err(e) = client.store_benchmark_run(record).await {
+                warn!("Failed to store benchmark run {run_id}: {e}");
+            }
+        }
+        Err(e) => warn!("Cannot store benchmark run {run_id}: {e}"),
+    }
+
+    if regressions.is_empty() {
+        info!("Benchmark run {run_id} finished with no regressions");
+        return;
+    }
+    for r in &regressions {
+        warn!(
+            "Benchmark regression in {}: {:.2} {} (baseline {:.2}, {:+.1}%)",
+            r.name, r.value, r.unit, r.baseline, r.change_percent
+        );
+    }
+    if first_of_build {
+        raise_incident(clients, run_id, &build, &regressions).await;
+    }
+}
+
+async fn raise_incident(
+    clients: &ServiceClients,
+    run_id: &str,
+    build: &str,
+    regressions: &[Regression],
+) {
+    let names: Vec<&str> = regressions.iter().map(|r| r.name.as_str()).collect();
+    let incident = crate::proto::memory::Incident {
+        id: format!("benchmark-{run_id}"),
+        description: format!(
+            "Performance regression after update to {build}: {}",
+            names.join(", ")
+        ),
+        symptoms_json: serde_json::to_vec(regressions).unwrap_or_default(),
+        root_cause: String::new(),
+        resolution: String::new(),
+        resolved_by: String::new(),
+        prevention: String::new(),
+        timestamp: chrono::Utc::now().timestamp(),
+    };
+    match clients.memory().await {
+        Ok(mut client) => {
+            if let Err(e) = client.store_incident(incident).await {
+                warn!("Failed to record benchmark regression incident: {e}");
+            }
+        }
+        Err(e) => warn!("Cannot record benchmark regression incident: {e}"),
+    }
+}
+
+async fn recent_runs(
+    clients: &ServiceClients,
+    limit: i32,
+) -> Vec<crate::proto::memory::BenchmarkRun> {
+    let Ok(mut client) = clients.memory().await else {
+        return Vec::new();
+    };
+    client
+        .get_benchmark_runs(crate::proto::memory::BenchmarkRunsRequest { limit })
+        .await
+        .map(|r| r.into_inner().runs)
+        .unwrap_or_default()
+}
+
+// ── Suites ──────────────────────────────────────────────
+
+/// Round-trip time of a cheap, read-only tool call
+async fn tool_latency(clients: &ServiceClients, samples: u32) -> BenchmarkResult {
+    let mut timings = Vec::new();
+    let mut error = None;
+    for _ in 0..samples {
+        let started = Instant::now();
+        let outcome = match clients.tools().await {
+            Ok(mut client) => client
+                .execute(crate::proto::tools::ExecuteRequest {
+                    tool_name: "monitor.cpu".to_string(),
+                    agent_id: "benchmark".to_string(),
+                    task_id: String::new(),
+                    input_json: b"{}".to_vec(),
+                    reason: "Benchmark: tool latency".to_string(),
+                    workload: String::new(),
+                    resume_token: String::new(),
+                    allow_experimental: false,
+                })
+                .await
+                .map_err(|e| e.to_string())
+                .and_then(|r| {
+                    let r = r.into_inner();
+                    if r.success {
+                        Ok(())
+                    } else {
+                        Err(r.error)
+                    }
+                }),
+            Err(e) => Err(e.to_string()),
+        };
+        match outcome {
+            Ok(()) => timings.push(started.elapsed().as_secs_f64() * 1000.0),
+            Err(e) => error = Some(e),
+        }
+    }
+    latency_result("tool_latency", timings, error)
+}
+
+/// Generation speed of a short operational-level completion
+async fn inference(clients: &ServiceClients, samples: u32) -> BenchmarkResult {
+    let mut rates = Vec::new();
+    let mut error = None;
+    for _ in 0..samples {
+        let started = Instant::now();
+        let outcome = match clients.runtime().await {
+            Ok(mut client) => client
+                .infer(crate::proto::runtime::InferRequest {
+                    model: String::new(),
+                    prompt: "List three uses of a Linux process monitor.".to_string(),
+                    system_prompt: String::new(),
+                    max_tokens: 64,
+                    temperature: 0.0,
+                    intelligence_level: "operational".to_string(),
+                    requesting_agent: "benchmark".to_string(),
+                    task_id: String::new(),
+                    workload: String::new(),
+                })
+                .await
+                .map(|r| r.into_inner().tokens_used)
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        let secs = started.elapsed().as_secs_f64();
+        match outcome {
+            Ok(tokens) if tokens > 0 && secs > 0.0 => rates.push(f64::from(tokens) / secs),
+            Ok(_) => error = Some("runtime reported no tokens".to_string()),
+            Err(e) => error = Some(e),
+        }
+    }
+    BenchmarkResult {
+        name: "inference".to_string(),
+        unit: "tokens/s".to_string(),
+        value: median(&mut rates).unwrap_or(0.0),
+        samples: rates.len() as u32,
+        higher_is_better: true,
+        error: if rates.is_empty() { error } else { None },
+    }
+}
+
+/// Latency of a semantic search over long-term memory
+async fn memory_search(clients: &ServiceClients, samples: u32) -> BenchmarkResult {
+    let mut timings = Vec::new();
+    let mut error = None;
+    for _ in 0..samples {
+        let started = Instant::now();
+        let outcome = match clients.memory().await {
+            Ok(mut client) => client
+                .semantic_search(crate::proto::memory::SemanticSearchRequest {
+                    query: "disk usage high on root filesystem".to_string(),
+                    collections: Vec::new(),
+                    n_results: 5,
+                    min_relevance: 0.0,
+                    requesting_agent: "orchestrator".to_string(),
+                })
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        match outcome {
+            Ok(()) => timings.push(started.elapsed().as_secs_f64() * 1000.0),
+            Err(e) => error = Some(e),
+        }
+    }
+    latency_result("memory_search", timings, error)
+}
+
+/// Time from submitting a one-step goal to its completion, through the
+/// orchestrator's public API
+async fn goal_e2e(clients: &ServiceClients) -> BenchmarkResult {
+    let started = Instant::now();
+    let outcome = async {
+        let mut client = clients.orchestrator().await.map_err(|e| e.to_string())?;
+        let goal_id = client
+            .submit_goal(crate::proto::orchestrator::SubmitGoalRequest {
+                description: "Benchmark probe: call monitor.cpu with {}".to_string(),
+                priority: 5,
+                source: "benchmark".to_string(),
+                tags: vec!["benchmark".to_string()],
+                metadata_json: Vec::new(),
+            })
+            .await
+            .map_err(|e| e.to_string())?
+            .into_inner();
+
+        while started.elapsed() < GOAL_E2E_TIMEOUT {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            let status = client
+                .get_goal_status(goal_id.clone())
+                .await
+                .map_err(|e| e.to_string())?
+                .into_inner();
+            match status.goal.map(|g| g.status).as_deref() {
+                Some("completed") => return Ok(started.elapsed().as_secs_f64() * 1000.0),
+                Some(s @ ("failed" | "cancelled")) => return Err(format!("probe goal {s}")),
+                _ => {}
+            }
+        }
+        Err("probe goal timed out".to_string())
+    }
+    .await;
+
+    match outcome {
+        Ok(ms) => latency_result("goal_e2e", vec![ms], None),
+        Err(e) => latency_result("goal_e2e", Vec::new(), Some(e)),
+    }
+}
+
+fn latency_result(name: &str, mut timings: Vec<f64>, error: Option<String>) -> BenchmarkResult {
+    BenchmarkResult {
+        name: name.to_string(),
+        unit: "ms".to_string(),
+        value: median(&mut timings).unwrap_or(0.0),
+        samples: timings.len() as u32,
+        higher_is_better: false,
+        error: if timings.is_empty() { error } else { None },
+    }
+}
+
+// ── Regression detection ────────────────────────────────
+
+fn median(values: &mut [f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+    let mid = values.len() / 2;
+    Some(if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    })
+}
+
+/// Baseline per metric: the median over the most recent runs of the build
+/// that preceded `build`. Empty when there is no earlier build on record.
+fn baseline_results(
+    history: &[crate::proto::memory::BenchmarkRun],
+    build: &str,
+) -> Vec<BenchmarkResult> {
+    let Some(previous_build) = history.iter().map(|r| &r.build).find(|b| *b != build) else {
+        return Vec::new();
+    };
+    let runs: Vec<Vec<BenchmarkResult>> = history
+        .iter()
+        .filter(|r| &r.build == previous_build)
+        .take(BASELINE_RUNS)
+        .filter_map(|r| serde_json::from_slice(&r.results_json).ok())
+        .collect();
+
+    let mut baseline = Vec::new();
+    for suite in SUITES {
+        let matching: Vec<&BenchmarkResult> = runs
+            .iter()
+            .flatten()
+            .filter(|r| r.name == *suite && r.error.is_none() && r.samples > 0)
+            .collect();
+        let Some(first) = matching.first() else {
+            continue;
+        };
+        let mut values: Vec<f64> = matching.iter().map(|r| r.value).collect();
+        baseline.push(BenchmarkResult {
+            value: median(&mut values).unwrap_or(0.0),
+            samples: matching.len() as u32,
+            error: None,
+            ..(*first).clone()
+        });
+    }
+    baseline
+}
+
+/// Smallest absolute change worth reporting, so that noise on tiny values
+/// (a 2ms tool call taking 3ms) is not flagged
+fn min_delta(unit: &str) -> f64 {
+    match unit {
+        "ms" => 5.0,
+        "tokens/s" => 1.0,
+        _ => 0.0,
+    }
+}
+
+fn find_regressions(results: &[BenchmarkResult], baseline: &[BenchmarkResult]) -> Vec<Regression> {
+    results
+        .iter()
+        .filter(|r| r.error.is_none() && r.samples > 0)
+        .filter_map(|r| {
+            let base = baseline.iter().find(|b| b.name == r.name)?;
+            if base.value <= 0.0 {
+                return None;
+            }
+            let worse_by = if r.higher_is_better {
+                base.value - r.value
+            } else {
+                r.value - base.value
+            };
+            let worse_percent = worse_by / base.value * 100.0;
+            (worse_percent >= REGRESSION_THRESHOLD_PERCENT && worse_by >= min_delta(&r.unit)).then(
+                || Regression {
+                    name: r.name.clone(),
+                    unit: r.unit.clone(),
+                    baseline: base.value,
+                    value: r.value,
+                    change_percent: (r.value - base.value) / base.value * 100.0,
+                },
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(name: &str, value: f64, higher_is_better: bool) -> BenchmarkResult {
+        BenchmarkResult {
+            name: name.to_string(),
+            unit: if higher_is_better { "tokens/s" } else { "ms" }.to_string(),
+            value,
+            samples: 10,
+            higher_is_better,
+            error: None,
+        }
+    }
+
+    fn run(build: &str, results: &[BenchmarkResult]) -> crate::proto::memory::BenchmarkRun {
+        crate::proto::memory::BenchmarkRun {
+            build: build.to_string(),
+            results_json: serde_json::to_vec(results).unwrap(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_regressions_respect_direction_and_noise_floor() {
+        let baseline = vec![
+            result("tool_latency", 20.0, false),
+            result("inference", 40.0, true),
+            result("memory_search", 2.0, false),
+        ];
+        let results = vec![
+            result("tool_latency", 30.0, false),
+            result("inference", 50.0, true),
+            // 100% slower but only 2ms: below the noise floor
+            result("memory_search", 4.0, false),
+        ];
+        let regressions = find_regressions(&results, &baseline);
+        assert_eq!(regressions.len(), 1);
+        assert_eq!(regressions[0].name, "tool_latency");
+        assert_eq!(regressions[0].change_percent, 50.0);
+
+        let slower_inference = vec![result("inference", 20.0, true)];
+        let regressions = find_regressions(&slower_inference, &baseline);
+        assert_eq!(regressions.len(), 1);
+        assert_eq!(regressions[0].change_percent, -50.0);
+    }
+
+    #[test]
+    fn test_baseline_uses_previous_build_median() {
+        // Newest first, as returned by memory
+        let history = vec![
+            run("1.1+200", &[result("tool_latency", 90.0, false)]),
+            run("1.0+100", &[result("tool_latency", 10.0, false)]),
+            run("1.0+100", &[result("tool_latency", 30.0, false)]),
+            run("1.0+100", &[result("tool_latency", 20.0, false)]),
+        ];
+        let baseline = baseline_results(&history, "1.1+200");
+        assert_eq!(baseline.len(), 1);
+        assert_eq!(baseline[0].value, 20.0);
+
+        assert!(baseline_results(&history[1..], "1.0+100").is_empty());
+    }
+}
@@ -0,0 +1,41 @@
+//! Build information — GetBuildInfo
+//!
+//! GetBuildInfo reports what a running service was built from: crate
+//! version, git commit, build time and cargo features, set by the build
+//! script (agent-core/build_support.rs). Every service includes this file
+//! with `#[path]`; only the orchestrator has runtime feature flags to add
+//! (see feature_flags.rs).
+
+use crate::proto::common::{BuildInfo, FeatureFlag};
+
+/// Build information for a fully qualified service name
+pub fn build_info(service: &str, flags: Vec<FeatureFlag>) -> BuildInfo {
+    BuildInfo {
+        service: service.to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        git_commit: env!("AIOS_GIT_COMMIT").to_string(),
+        build_timestamp: env!("AIOS_BUILD_TIMESTAMP").parse().unwrap_or(0),
+        cargo_features: env!("AIOS_CARGO_FEATURES")
+            .split(',')
+            .filter(|f| !f.is_empty())
+            .map(str::to_string)
+            .collect(),
+        flags,
+        profile: env!("AIOS_BUILD_PROFILE").to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_info_reports_the_build() {
+        let info = build_info("aios.v1.common.Example", Vec::new());
+        assert_eq!(info.service, "aios.v1.common.Example");
+        assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+        assert!(!info.git_commit.is_empty());
+        assert!(info.build_timestamp > 0);
+        assert!(info.flags.is_empty());
+    }
+}
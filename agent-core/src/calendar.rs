@@ -0,0 +1,746 @@
+//! Calendar Windows — maintenance, on-call and blackout periods
+//!
+//! Teams already keep maintenance windows, on-call rotations and change
+//! freezes in a shared calendar. The feeds listed in calendars.toml (ICS
+//! over HTTP or from a file, or a CalDAV collection) are fetched every
+//! `refresh_secs` and their events, recurring ones included, are turned
+//! into windows for the next `horizon_days`:
+//!
+//! - `blackout`: scheduled goals do not fire and proactive goals are not
+//!   generated. Schedules whose upcoming runs fall inside a blackout are
+//!   reported as conflicts when created and after every refresh.
+//! - `maintenance`: proactive goals are not generated, since alerts are
+//!   expected while the work is under way.
+//! - `on_call`: informational; shown in the console.
+//!
+//! A feed's `kind` applies to all of its events; an event whose CATEGORIES
+//! name a kind ("BLACKOUT", "MAINTENANCE", "ON-CALL") overrides it. A feed
+//! that cannot be fetched keeps its last windows.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock as StdRwLock};
+use tokio::sync::RwLock;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use crate::scheduler::{self, GoalScheduler};
+
+/// Default location of the calendar configuration
+pub const CALENDAR_CONFIG_PATH: &str = "/etc/aios/calendars.toml";
+
+/// Recurrences are expanded at most this many times per event
+const MAX_OCCURRENCES: usize = 5000;
+
+/// Scheduled runs checked for conflicts per schedule
+const MAX_CONFLICT_RUNS: usize = 2000;
+
+/// What a calendar window means for the orchestrator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WindowKind {
+    Maintenance,
+    OnCall,
+    Blackout,
+}
+
+impl WindowKind {
+    /// Kind named by an event category, if any
+    fn from_category(category: &str) -> Option<Self> {
+        match category
+            .trim()
+            .to_ascii_lowercase()
+            .replace(['-', ' '], "_")
+            .as_str()
+        {
+            "maintenance" => Some(Self::Maintenance),
+            "on_call" | "oncall" => Some(Self::OnCall),
+            "blackout" => Some(Self::Blackout),
+            _ => None,
+        }
+    }
+}
+
+/// One occurrence of a calendar event
+#[derive(Debug, Clone, Serialize)]
+pub struct CalendarWindow {
+    pub calendar: String,
+    pub kind: WindowKind,
+    pub summary: String,
+    /// Unix time the window opens
+    pub start: i64,
+    /// Unix time the window closes (exclusive)
+    pub end: i64,
+}
+
+impl CalendarWindow {
+    fn contains(&self, at: i64) -> bool {
+        self.start <= at && at < self.end
+    }
+}
+
+/// A scheduled run that falls inside a blackout window
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduleConflict {
+    pub schedule_id: String,
+    /// Unix time of the run
+    pub run_at: i64,
+    /// Local time of the run in the schedule's timezone
+    pub run_local: String,
+    pub window: CalendarWindow,
+}
+
+/// calendars.toml layout
+#[derive(Debug, Deserialize)]
+struct CalendarFile {
+    #[serde(default = "default_refresh_secs")]
+    refresh_secs: u64,
+    #[serde(default = "default_horizon_days")]
+    horizon_days: i64,
+    #[serde(default)]
+    calendar: Vec<CalendarSource>,
+}
+
+/// One calendar feed
+#[derive(Debug, Clone, Deserialize)]
+pub struct CalendarSource {
+    pub name: String,
+    /// http(s):// or webcal:// URL, or a local file path
+    pub url: String,
+    /// Kind of events that do not name one in their categories
+    #[serde(default)]
+    pub kind: Option<WindowKind>,
+    /// Query `url` as a CalDAV collection instead of an ICS feed
+    #[serde(default)]
+    pub caldav: bool,
+    #[serde(default)]
+    pub username: String,
+    /// Environment variable holding the password
+    #[serde(default)]
+    pub password_env: String,
+}
+
+fn default_refresh_secs() -> u64 {
+    900
+}
+
+fn default_horizon_days() -> i64 {
+    30
+}
+
+/// Calendar windows and the schedule conflicts they cause
+#[derive(Debug, Default)]
+pub struct Calendar {
+    windows: StdRwLock<Vec<CalendarWindow>>,
+    conflicts: StdRwLock<Vec<ScheduleConflict>>,
+}
+
+impl Calendar {
+    /// All known windows, ordered by start
+    pub fn windows(&self) -> Vec<CalendarWindow> {
+        self.windows.read().unwrap().clone()
+    }
+
+    /// Scheduled runs inside blackouts, as of the last refresh
+    pub fn conflicts(&self) -> Vec<ScheduleConflict> {
+        self.conflicts.read().unwrap().clone()
+    }
+
+    fn replace(&self, mut windows: Vec<CalendarWindow>) {
+        windows.sort_by_key(|w| w.start);
+        *self.windows.write().unwrap() = windows;
+    }
+
+    /// The first window of `kind` open at `at`
+    fn open_window(&self, kind: WindowKind, at: i64) -> Option<CalendarWindow> {
+        self.windows
+            .read()
+            .unwrap()
+            .iter()
+            .find(|w| w.kind == kind && w.contains(at))
+            .cloned()
+    }
+
+    /// The blackout open at `at`, if any
+    pub fn blackout_at(&self, at: i64) -> Option<CalendarWindow> {
+        self.open_window(WindowKind::Blackout, at)
+    }
+
+    /// Why proactive goals are held back at `at`, if they are
+    pub fn proactive_suppression(&self, at: i64) -> Option<String> {
+        let window = self
+            .blackout_at(at)
+            .or_else(|| self.open_window(WindowKind::Maintenance, at))?;
+        Some(format!(
+            "calendar {:?} window '{}' ({}) until {}",
+            window.kind,
+            window.summary,
+            window.calendar,
+            DateTime::from_timestamp(window.end, 0).unwrap_or_default()
+        ))
+    }
+
+    /// Runs of `cron_expr` in `tz` between `from` and `until` that fall
+    /// inside a blackout
+    pub fn schedule_conflicts(
+        &self,
+        schedule_id: &str,
+        cron_expr: &str,
+        tz: Tz,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Vec<ScheduleConflict> {
+        let windows = self.windows.read().unwrap();
+        if !windows.iter().any(|w| w.kind == WindowKind::Blackout) {
+            return Vec::new();
+        }
+        let mut conflicts = Vec::new();
+        let mut after = from;
+        for _ in 0..MAX_CONFLICT_RUNS {
+            let Some(run) = scheduler::next_run(cron_expr, tz, after).filter(|r| *r <= until)
+            else {
+                break;
+            };
+            let at = run.timestamp();
+            if let Some(window) = windows
+                .iter()
+                .find(|w| w.kind == WindowKind::Blackout && w.contains(at))
+            {
+                conflicts.push(ScheduleConflict {
+                    schedule_id: schedule_id.to_string(),
+                    run_at: at,
+                    run_local: run
+                        .with_timezone(&tz)
+                        .format("%a %Y-%m-%d %H:%M %Z")
+                        .to_string(),
+                    window: window.clone(),
+                });
+            }
+            after = run;
+        }
+        conflicts
+    }
+
+    /// Recompute the conflicts of every enabled schedule
+    fn check_schedules(&self, scheduler: &GoalScheduler, horizon: Duration) {
+        let now = Utc::now();
+        let mut conflicts = Vec::new();
+        for schedule in scheduler.list_schedules().into_iter().filter(|s| s.enabled) {
+            let Ok(tz) = scheduler::parse_timezone(&schedule.timezone) else {
+                continue;
+            };
+            conflicts.extend(self.schedule_conflicts(
+                &schedule.id,
+                &schedule.cron_expr,
+                tz,
+                now,
+                now + horizon,
+            ));
+        }
+        for conflict in &conflicts {
+            warn!(
+                "Schedule {} would fire at {} inside blackout '{}' ({}); the run will be skipped",
+                conflict.schedule_id,
+                conflict.run_local,
+                conflict.window.summary,
+                conflict.window.calendar
+            );
+        }
+        *self.conflicts.write().unwrap() = conflicts;
+    }
+}
+
+/// Fetch the configured calendars every `refresh_secs` until `cancel`
+/// fires. Returns at once when no calendars are configured.
+pub async fn run_calendar_sync(
+    calendar: Arc<Calendar>,
+    scheduler: Arc<RwLock<GoalScheduler>>,
+    config_path: &str,
+    cancel: CancellationToken,
+) {
+    let file: CalendarFile = match std::fs::read_to_string(config_path) {
+        Ok(contents) => match toml::from_str(&contents) {
+            Ok(file) => file,
+            Err(e) => {
+                warn!("Invalid calendar configuration {config_path}: {e}");
+                return;
+            }
+        },
+        Err(_) => {
+            debug!("No calendar configuration at {config_path}");
+            return;
+        }
+    };
+    if file.calendar.is_empty() {
+        return;
+    }
+    info!(
+        "Calendar sync started ({} calendars, every {}s)",
+        file.calendar.len(),
+        file.refresh_secs
+    );
+
+    let http = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(30))
+        .build()
+        .unwrap_or_default();
+    let horizon = Duration::days(file.horizon_days);
+    let mut by_source: HashMap<String, Vec<CalendarWindow>> = HashMap::new();
+    loop {
+        let now = Utc::now();
+        for source in &file.calendar {
+            match fetch(&http, source, now, now + horizon).await {
+                Ok(text) => {
+                    let windows = parse_ics(&text, source, now - Duration::days(1), now + horizon);
+                    debug!("Calendar '{}': {} windows", source.name, windows.len());
+                    by_source.insert(source.name.clone(), windows);
+                }
+                Err(e) => warn!("Failed to fetch calendar '{}': {e:#}", source.name),
+            }
+        }
+        calendar.replace(by_source.values().flatten().cloned().collect());
+        let schedules = scheduler.read().await;
+        calendar.check_schedules(&schedules, horizon);
+        drop(schedules);
+
+        tokio::select! {
+            _ = cancel.cancelled() => break,
+            _ = tokio::time::sleep(std::time::Duration::from_secs(file.refresh_secs.max(60))) => {}
+        }
+    }
+    info!("Calendar sync stopped");
+}
+
+/// Fetch the ICS text of `source`
+async fn fetch(
+    http: &reqwest::Client,
+    source: &CalendarSource,
+    from: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Result<String> {
+    let url = match source.url.strip_prefix("webcal://") {
+        Some(rest) => format!("https://{rest}"),
+        None => source.url.clone(),
+    };
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        let path = url.strip_prefix("file://").unwrap_or(&url);
+        return std::fs::read_to_string(path).with_context(|| format!("Cannot read {path}"));
+    }
+
+    let mut request = if source.caldav {
+        let range = |t: DateTime<Utc>| t.format("%Y%m%dT%H%M%SZ").to_string();
+        let body = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<C:calendar-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <D:prop><C:calendar-data/></D:prop>
+  <C:filter><C:comp-filter name="VCALENDAR"><C:comp-filter name="VEVENT">
+    <C:time-range start="{}" end="{}"/>
+  </C:comp-filter></C:comp-filter></C:filter>
+</C:calendar-query>"#,
+            range(from),
+            range(until)
+        );
+        http.request(reqwest::Method::from_bytes(b"REPORT")?, &url)
+            .header("Depth", "1")
+            .header("Content-Type", "application/xml; charset=utf-8")
+            .body(body)
+    } else {
+        http.get(&url)
+    };
+    if !source.username.is_empty() {
+        let password = std::env::var(&source.password_env).ok();
+        request = request.basic_auth(&source.username, password);
+    }
+    let response = request.send().await?.error_for_status()?;
+    let text = response.text().await?;
+    // CalDAV wraps each event's ICS in an XML multistatus response
+    Ok(if source.caldav {
+        unescape_xml(&text)
+    } else {
+        text
+    })
+}
+
+fn unescape_xml(text: &str) -> String {
+    text.replace("&#13;", "\r")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// One property line of an ICS event: parameters and value
+struct Property {
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// The windows of the events in `text` that overlap `from`..`until`
+pub fn parse_ics(
+    text: &str,
+    source: &CalendarSource,
+    from: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Vec<CalendarWindow> {
+    // Unfold continuation lines, which start with a space or tab
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        let line = line.trim_end_matches('\r');
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    let mut windows = Vec::new();
+    let mut event: Option<HashMap<String, Property>> = None;
+    for line in &lines {
+        if line.eq_ignore_ascii_case("BEGIN:VEVENT") {
+            event = Some(HashMap::new());
+        } else if line.eq_ignore_ascii_case("END:VEVENT") {
+            if let Some(props) = event.take() {
+                windows.extend(event_windows(&props, source, from, until));
+            }
+        } else if let (Some(props), Some((head, value))) = (event.as_mut(), line.split_once(':')) {
+            let mut parts = head.split(';');
+            let name = parts.next().unwrap_or_default().to_ascii_uppercase();
+            let params = parts
+                .filter_map(|p| p.split_once('='))
+                .map(|(k, v)| (k.to_string(), v.trim_matches('"').to_string()))
+                .collect();
+            // Keep the first of repeated properties, such as DTSTART
+            props.entry(name).or_insert(Property {
+                params,
+                value: value.to_string(),
+            });
+        }
+    }
+    windows
+}
+
+/// Windows of one event, expanding its recurrence rule
+fn event_windows(
+    props: &HashMap<String, Property>,
+    source: &CalendarSource,
+    from: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Vec<CalendarWindow> {
+    if props
+        .get("STATUS")
+        .is_some_and(|s| s.value.eq_ignore_ascii_case("CANCELLED"))
+    {
+        return Vec::new();
+    }
+    let category_kind = props
+        .get("CATEGORIES")
+        .and_then(|c| c.value.split(',').find_map(WindowKind::from_category));
+    let Some(kind) = category_kind.or(source.kind) else {
+        return Vec::new();
+    };
+    let Some((start, tz)) = props.get("DTSTART").and_then(parse_time) else {
+        return Vec::new();
+    };
+    let all_day = start.time() == chrono::NaiveTime::MIN
+        && props
+            .get("DTSTART")
+            .is_some_and(|p| p.param("VALUE") == Some("DATE") || p.value.len() == 8);
+    let length = match (
+        props.get("DTEND").and_then(parse_time),
+        props.get("DURATION"),
+    ) {
+        (Some((end, _)), _) => end - start,
+        (None, Some(duration)) => parse_duration(&duration.value).unwrap_or_default(),
+        (None, None) if all_day => Duration::days(1),
+        _ => Duration::zero(),
+    };
+    if length <= Duration::zero() {
+        return Vec::new();
+    }
+    let summary = props
+        .get("SUMMARY")
+        .map(|s| s.value.replace("\\,", ",").replace("\\;", ";"))
+        .unwrap_or_default();
+
+    let starts = match props.get("RRULE") {
+        Some(rule) => recurrences(start, &rule.value, tz, until),
+        None => vec![start],
+    };
+    starts
+        .into_iter()
+        .filter_map(|local| {
+            let begin = scheduler::resolve_local(tz, local)?;
+            let end = scheduler::resolve_local(tz, local + length)?;
+            (end > from && begin < until).then(|| CalendarWindow {
+                calendar: source.name.clone(),
+                kind,
+                summary: summary.clone(),
+                start: begin.timestamp(),
+                end: end.timestamp(),
+            })
+        })
+        .collect()
+}
+
+/// Wall-clock time of a DTSTART/DTEND and the timezone it is in. UTC
+/// times ("...Z") and floating times are taken as UTC.
+fn parse_time(prop: &Property) -> Option<(NaiveDateTime, Tz)> {
+    let value = prop.value.trim();
+    if let Some(utc) = value.strip_suffix('Z') {
+        let at = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some((at, Tz::UTC));
+    }
+    let tz = prop
+        .param("TZID")
+        .and_then(|name| name.parse::<Tz>().ok())
+        .unwrap_or(Tz::UTC);
+    let at = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y%m%d")
+                .ok()
+                .map(|d| d.and_time(chrono::NaiveTime::MIN))
+        })?;
+    Some((at, tz))
+}
+
+/// Parse an ICS duration such as "PT2H", "P1D" or "P1W"
+fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.strip_prefix('+').unwrap_or(value);
+    let mut rest = value.strip_prefix('P')?;
+    let mut total = Duration::zero();
+    let mut in_time = false;
+    while !rest.is_empty() {
+        if let Some(r) = rest.strip_prefix('T') {
+            in_time = true;
+            rest = r;
+            continue;
+        }
+        let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+        let n: i64 = rest[..digits].parse().ok()?;
+        total += match (&rest[digits..digits + 1], in_time) {
+            ("W", false) => Duration::weeks(n),
+            ("D", false) => Duration::days(n),
+            ("H", true) => Duration::hours(n),
+            ("M", true) => Duration::minutes(n),
+            ("S", true) => Duration::seconds(n),
+            _ => return None,
+        };
+        rest = &rest[digits + 1..];
+    }
+    Some(total)
+}
+
+/// Start times of a recurring event up to `until`, in its wall-clock time.
+/// Supports FREQ=DAILY/WEEKLY/MONTHLY/YEARLY with INTERVAL, COUNT, UNTIL
+/// and, for weekly rules, BYDAY.
+fn recurrences(
+    start: NaiveDateTime,
+    rule: &str,
+    tz: Tz,
+    until: DateTime<Utc>,
+) -> Vec<NaiveDateTime> {
+    let parts: HashMap<String, String> = rule
+        .split(';')
+        .filter_map(|p| p.split_once('='))
+        .map(|(k, v)| (k.to_ascii_uppercase(), v.to_ascii_uppercase()))
+        .collect();
+    let interval: i64 = parts
+        .get("INTERVAL")
+        .and_then(|v| v.parse().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(1);
+    let count: Option<usize> = parts.get("COUNT").and_then(|v| v.parse().ok());
+    let rule_until = parts.get("UNTIL").and_then(|v| {
+        parse_time(&Property {
+            params: Vec::new(),
+            value: v.clone(),
+        })
+        .and_then(|(at, tz)| scheduler::resolve_local(tz, at))
+    });
+    let end = rule_until.map_or(until, |u| u.min(until));
+    let by_day: Vec<Weekday> = parts
+        .get("BYDAY")
+        .map(|days| {
+            days.split(',')
+                .filter_map(|d| {
+                    match d.trim_start_matches(|c: char| c == '+' || c == '-' || c.is_ascii_digit())
+                    {
+                        "MO" => Some(Weekday::Mon),
+                        "TU" => Some(Weekday::Tue),
+                        "WE" => Some(Weekday::Wed),
+                        "TH" => Some(Weekday::Thu),
+                        "FR" => Some(Weekday::Fri),
+                        "SA" => Some(Weekday::Sat),
+                        "SU" => Some(Weekday::Sun),
+                        _ => None,
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let freq = parts.get("FREQ").map(String::as_str).unwrap_or_default();
+    let mut starts = Vec::new();
+    for step in 0..MAX_OCCURRENCES as i64 {
+        let candidates: Vec<NaiveDateTime> = match freq {
+            "DAILY" => vec![start + Duration::days(step * interval)],
+            "WEEKLY" if !by_day.is_empty() => {
+                let week = start.date()
+                    - Duration::days(start.weekday().num_days_from_monday() as i64)
+                    + Duration::weeks(step * interval);
+                let mut days: Vec<NaiveDateTime> = by_day
+                    .iter()
+                    .map(|d| {
+                        (week + Duration::days(d.num_days_from_monday() as i64))
+                            .and_time(start.time())
+                    })
+                    .filter(|at| *at >= start)
+                    .collect();
+                days.sort();
+                days
+            }
+            "WEEKLY" => vec![start + Duration::weeks(step * interval)],
+            "MONTHLY" => start
+                .checked_add_months(chrono::Months::new((step * interval) as u32))
+                .filter(|at| at.day() == start.day())
+                .into_iter()
+                .collect(),
+            "YEARLY" => start
+                .checked_add_months(chrono::Months::new((step * interval * 12) as u32))
+                .filter(|at| at.day() == start.day())
+                .into_iter()
+                .collect(),
+            _ => return vec![start],
+        };
+        for at in candidates {
+            let past_end = scheduler::resolve_local(tz, at).is_none_or(|t| t > end);
+            if past_end || count.is_some_and(|c| starts.len() >= c) {
+                return starts;
+            }
+            starts.push(at);
+        }
+    }
+    starts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(kind: Option<WindowKind>) -> CalendarSource {
+        CalendarSource {
+            name: "ops".into(),
+            url: String::new(),
+            kind,
+            caldav: false,
+            username: String::new(),
+            password_env: String::new(),
+        }
+    }
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    const FEED: &str = "BEGIN:VCALENDAR\r\n\
+BEGIN:VEVENT\r\n\
+SUMMARY:Release\r\n  freeze\r\n\
+CATEGORIES:BLACKOUT\r\n\
+DTSTART;TZID=Europe/Berlin:20260330T000000\r\n\
+DTEND;TZID=Europe/Berlin:20260401T000000\r\n\
+END:VEVENT\r\n\
+BEGIN:VEVENT\r\n\
+SUMMARY:Patch window\r\n\
+DTSTART;TZID=Europe/Berlin:20260301T020000\r\n\
+DURATION:PT2H\r\n\
+RRULE:FREQ=WEEKLY;BYDAY=SU;COUNT=6\r\n\
+END:VEVENT\r\n\
+BEGIN:VEVENT\r\n\
+SUMMARY:Cancelled\r\n\
+STATUS:CANCELLED\r\n\
+DTSTART:20260331T000000Z\r\n\
+DTEND:20260331T010000Z\r\n\
+END:VEVENT\r\n\
+END:VCALENDAR\r\n";
+
+    #[test]
+    fn test_parse_ics_windows_and_recurrences() {
+        let windows = parse_ics(
+            FEED,
+            &source(Some(WindowKind::Maintenance)),
+            utc("2026-03-01T00:00:00Z"),
+            utc("2026-05-01T00:00:00Z"),
+        );
+        let blackouts: Vec<_> = windows
+            .iter()
+            .filter(|w| w.kind == WindowKind::Blackout)
+            .collect();
+        assert_eq!(blackouts.len(), 1);
+        assert_eq!(blackouts[0].summary, "Release freeze");
+        assert_eq!(blackouts[0].start, utc("2026-03-29T22:00:00Z").timestamp());
+
+        // Six Sundays at 02:00 Berlin time, before and after the DST change
+        let patches: Vec<_> = windows
+            .iter()
+            .filter(|w| w.kind == WindowKind::Maintenance)
+            .collect();
+        assert_eq!(patches.len(), 6);
+        assert_eq!(patches[0].start, utc("2026-03-01T01:00:00Z").timestamp());
+        assert_eq!(patches[5].start, utc("2026-04-05T00:00:00Z").timestamp());
+        assert_eq!(patches[5].end - patches[5].start, 2 * 3600);
+
+        // Events with no kind are ignored
+        assert_eq!(
+            parse_ics(
+                FEED,
+                &source(None),
+                utc("2026-03-01T00:00:00Z"),
+                utc("2026-05-01T00:00:00Z")
+            )
+            .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_blackout_conflicts_and_suppression() {
+        let calendar = Calendar::default();
+        calendar.replace(parse_ics(
+            FEED,
+            &source(Some(WindowKind::Maintenance)),
+            utc("2026-03-01T00:00:00Z"),
+            utc("2026-05-01T00:00:00Z"),
+        ));
+        let berlin = scheduler::parse_timezone("Europe/Berlin").unwrap();
+        let conflicts = calendar.schedule_conflicts(
+            "nightly",
+            "0 2 * * *",
+            berlin,
+            utc("2026-03-28T00:00:00Z"),
+            utc("2026-04-05T00:00:00Z"),
+        );
+        let days: Vec<&str> = conflicts.iter().map(|c| &c.run_local[..14]).collect();
+        assert_eq!(days, vec!["Mon 2026-03-30", "Tue 2026-03-31"]);
+
+        let during_patch = utc("2026-03-08T01:30:00Z").timestamp();
+        assert!(calendar.blackout_at(during_patch).is_none());
+        assert!(calendar.proactive_suppression(during_patch).is_some());
+        assert!(calendar
+            .proactive_suppression(utc("2026-03-08T04:00:00Z").timestamp())
+            .is_none());
+        assert_eq!(parse_duration("P1DT2H30M"), Some(Duration::minutes(1590)));
+    }
+}
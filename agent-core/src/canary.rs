@@ -0,0 +1,459 @@
+//! Synthetic Canary Goals
+//!
+//! Per-service health checks only show that a port answers. Canaries are
+//! small, known-good goals ("read /etc/hostname and report it") submitted
+//! every few minutes through the full pipeline — planner, pattern or AI
+//! execution, tools, result aggregation — so a break anywhere along the way
+//! shows up before users notice it.
+//!
+//! Canaries are configured in canaries.toml. A canary that fails, times out,
+//! or gets markedly slower than its own recent history opens an incident in
+//! long-term memory; the incident is resolved when the canary passes again.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use crate::OrchestratorState;
+
+/// Default location of the canary configuration
+pub const CANARY_CONFIG_PATH: &str = "/etc/aios/canaries.toml";
+
+/// How often due canaries are looked for
+const TICK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Canaries wait this long after startup so services can come up
+const STARTUP_GRACE: Duration = Duration::from_secs(120);
+
+/// Successful latencies kept per canary for regression detection
+const LATENCY_WINDOW: usize = 20;
+
+/// Samples needed before latency regressions are judged
+const MIN_LATENCY_SAMPLES: usize = 5;
+
+/// A run is a latency regression when it takes this many times the median
+const LATENCY_REGRESSION_FACTOR: f64 = 3.0;
+
+/// Canary goals run below the default priority so they never delay real work
+const CANARY_PRIORITY: i32 = 4;
+
+/// canaries.toml layout
+#[derive(Debug, Deserialize)]
+struct CanaryFile {
+    #[serde(default = "default_true")]
+    enabled: bool,
+    /// Also run the built-in canaries alongside the configured ones
+    #[serde(default = "default_true")]
+    builtin: bool,
+    #[serde(default)]
+    canary: Vec<CanaryConfig>,
+}
+
+/// One canary goal
+#[derive(Debug, Clone, Deserialize)]
+pub struct CanaryConfig {
+    pub name: String,
+    /// Goal description submitted to the pipeline
+    pub goal: String,
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Text the goal's task output must contain
+    #[serde(default)]
+    pub expect: Option<String>,
+    /// Hard latency limit, on top of the comparison with recent runs
+    #[serde(default)]
+    pub max_latency_ms: Option<u64>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_interval_secs() -> u64 {
+    300
+}
+
+fn default_timeout_secs() -> u64 {
+    120
+}
+
+/// Canaries that run unless canaries.toml turns them off
+pub fn builtin_canaries() -> Vec<CanaryConfig> {
+    vec![CanaryConfig {
+        name: "hostname".to_string(),
+        goal: "Canary: read /etc/hostname and report it".to_string(),
+        interval_secs: default_interval_secs(),
+        timeout_secs: default_timeout_secs(),
+        expect: None,
+        max_latency_ms: None,
+    }]
+}
+
+/// Load the canary set from `path`. A missing file yields the built-ins; an
+/// invalid one is logged and the built-ins are used.
+pub fn load(path: &str) -> Vec<CanaryConfig> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => from_toml(&contents).unwrap_or_else(|e| {
+            warn!("Ignoring invalid canary config in {path}: {e}");
+            builtin_canaries()
+        }),
+        Err(_) => builtin_canaries(),
+    }
+}
+
+/// Parse a canaries.toml document; configured canaries replace built-ins of
+/// the same name
+pub fn from_toml(contents: &str) -> Result<Vec<CanaryConfig>> {
+    let file: CanaryFile = toml::from_str(contents).context("Failed to parse canary config")?;
+    if !file.enabled {
+        return Ok(Vec::new());
+    }
+    let mut canaries = if file.builtin {
+        builtin_canaries()
+    } else {
+        Vec::new()
+    };
+    for canary in file.canary {
+        if canary.goal.trim().is_empty() {
+            anyhow::bail!("canary '{}' has no goal", canary.name);
+        }
+        if canary.interval_secs == 0 || canary.timeout_secs == 0 {
+            anyhow::bail!(
+                "canary '{}' needs a non-zero interval and timeout",
+                canary.name
+            );
+        }
+        canaries.retain(|c| c.name != canary.name);
+        canaries.push(canary);
+    }
+    Ok(canaries)
+}
+
+/// How a single canary run ended
+#[derive(Debug, Clone, PartialEq)]
+enum Outcome {
+    Passed { latency_ms: u64 },
+    Failed(String),
+    Slow { latency_ms: u64, limit_ms: u64 },
+}
+
+/// Running state of one canary
+#[derive(Debug, Default)]
+struct CanaryState {
+    last_run: Option<Instant>,
+    latencies: VecDeque<u64>,
+    /// Incident opened for the current failure streak, if any
+    open_incident: Option<String>,
+}
+
+impl CanaryState {
+    /// Classify a completed run against the hard limit and recent history,
+    /// recording its latency when it passes
+    fn judge(&mut self, canary: &CanaryConfig, latency_ms: u64) -> Outcome {
+        let mut limit = canary.max_latency_ms;
+        if self.latencies.len() >= MIN_LATENCY_SAMPLES {
+            let mut sorted: Vec<u64> = self.latencies.iter().copied().collect();
+            sorted.sort_unstable();
+            let median = sorted[sorted.len() / 2];
+            let relative = (median as f64 * LATENCY_REGRESSION_FACTOR) as u64;
+            limit = Some(limit.map_or(relative, |l| l.min(relative)));
+        }
+        if let Some(limit_ms) = limit.filter(|l| latency_ms > *l) {
+            return Outcome::Slow {
+                latency_ms,
+                limit_ms,
+            };
+        }
+        self.latencies.push_back(latency_ms);
+        if self.latencies.len() > LATENCY_WINDOW {
+            self.latencies.pop_front();
+        }
+        Outcome::Passed { latency_ms }
+    }
+}
+
+/// Run configured canaries until cancelled
+pub async fn run_canaries(
+    state: Arc<RwLock<OrchestratorState>>,
+    canaries: Vec<CanaryConfig>,
+    cancel: CancellationToken,
+) {
+    if canaries.is_empty() {
+        info!("No canary goals configured");
+        return;
+    }
+    info!("Canary goals enabled: {}", canaries.len());
+
+    tokio::select! {
+        _ = cancel.cancelled() => return,
+        _ = tokio::time::sleep(STARTUP_GRACE) => {}
+    }
+
+    let mut states: HashMap<String, CanaryState> = HashMap::new();
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => {
+                debug!("Canary loop shutting down");
+                break;
+            }
+            _ = tokio::time::sleep(TICK_INTERVAL) => {}
+        }
+        if state.read().await.drain.is_draining() {
+            continue;
+        }
+
+        for canary in &canaries {
+            let canary_state = states.entry(canary.name.clone()).or_default();
+            let due = canary_state
+                .last_run
+                .is_none_or(|t| t.elapsed() >= Duration::from_secs(canary.interval_secs));
+            if !due {
+                continue;
+            }
+            canary_state.last_run = Some(Instant::now());
+
+            let outcome = match run_once(&state, canary, &cancel).await {
+                Ok(latency_ms) => canary_state.judge(canary, latency_ms),
+                Err(e) => Outcome::Failed(e.to_string()),
+            };
+            report(&state, canary, canary_state, outcome).await;
+        }
+    }
+}
+
+/// Submit the canary goal and wait for it to finish; returns its latency
+async fn run_once(
+    state: &Arc<RwLock<OrchestratorState>>,
+    canary: &CanaryConfig,
+    cancel: &CancellationToken,
+) -> Result<u64> {
+    let started = Instant::now();
+    let goal_id = {
+        let mut s = crate::liveness::write_state(state, "canary.run").await;
+        let goal_id = s
+            .goal_engine
+            .submit_goal(
+                canary.goal.clone(),
+                CANARY_PRIORITY,
+                format!("canary:{}", canary.name),
+            )
+            .await?;
+        s.goal_engine
+            .update_labels(&goal_id, &["canary".to_string()], &[])
+            .ok();
+        let tasks = s
+            .task_planner
+            .decompose_goal(&goal_id, &canary.goal)
+            .await?;
+        s.goal_engine.add_tasks(&goal_id, tasks);
+        s.autonomy_waker.wake();
+        goal_id
+    };
+
+    let timeout = Duration::from_secs(canary.timeout_secs);
+    while started.elapsed() < timeout {
+        tokio::select! {
+            _ = cancel.cancelled() => anyhow::bail!("shutting down"),
+            _ = tokio::time::sleep(Duration::from_secs(2)) => {}
+        }
+        let (goal, tasks) = state
+            .read()
+            .await
+            .goal_engine
+            .get_goal_with_tasks(&goal_id)
+            .await?;
+        match goal.status.as_str() {
+            "completed" => {
+                if let Some(expect) = &canary.expect {
+                    let found = tasks
+                        .iter()
+                        .any(|t| String::from_utf8_lossy(&t.output_json).contains(expect.as_str()));
+                    if !found {
+                        anyhow::bail!("goal {goal_id} completed without reporting '{expect}'");
+                    }
+                }
+                return Ok(started.elapsed().as_millis() as u64);
+            }
+            "failed" | "cancelled" => {
+                let error = tasks
+                    .iter()
+                    .find(|t| !t.error.is_empty())
+                    .map(|t| t.error.clone())
+                    .unwrap_or_default();
+                anyhow::bail!("goal {goal_id} {}: {error}", goal.status);
+            }
+            _ => {}
+        }
+    }
+
+    let mut s = crate::liveness::write_state(state, "canary.cancel").await;
+    if let Ok(task_ids) = s.goal_engine.cancel_goal(&goal_id, "canary").await {
+        s.task_workers.cancel(&task_ids);
+        let clients = s.clients.clone();
+        drop(s);
+        crate::autonomy::cancel_tool_executions(&clients, task_ids, "canary timed out").await;
+    }
+    anyhow::bail!(
+        "goal {goal_id} did not finish within {}s",
+        canary.timeout_secs
+    )
+}
+
+/// Log a run, opening an incident for a new failure streak and resolving it
+/// once the canary passes again
+async fn report(
+    state: &Arc<RwLock<OrchestratorState>>,
+    canary: &CanaryConfig,
+    canary_state: &mut CanaryState,
+    outcome: Outcome,
+) {
+    let problem = match &outcome {
+        Outcome::Passed { latency_ms } => {
+            debug!("Canary '{}' passed in {latency_ms}ms", canary.name);
+            None
+        }
+        Outcome::Failed(reason) => Some(format!("Canary '{}' failed: {reason}", canary.name)),
+        Outcome::Slow {
+            latency_ms,
+            limit_ms,
+        } => Some(format!(
+            "Canary '{}' took {latency_ms}ms (limit {limit_ms}ms)",
+            canary.name
+        )),
+    };
+
+    let (clients, notifier) = {
+        let s = state.read().await;
+        (s.clients.clone(), s.notifier.clone())
+    };
+    let now = chrono::Utc::now().timestamp();
+    match (problem, canary_state.open_incident.take()) {
+        (Some(description), None) => {
+            warn!("{description}");
+            notifier.notify(
+                "incident",
+                crate::event_bus::EventSeverity::Warning,
+                &description,
+                &format!("Canary goal: {}", canary.goal),
+                &format!("canary-{}", canary.name),
+            );
+            let id = format!("canary-{}-{now}", canary.name);
+            let incident = crate::proto::memory::Incident {
+                id: id.clone(),
+                description,
+                symptoms_json: serde_json::to_vec(&serde_json::json!({
+                    "canary": canary.name,
+                    "goal": canary.goal,
+                    "outcome": format!("{outcome:?}"),
+                }))
+                .unwrap_or_default(),
+                timestamp: now,
+                ..Default::default()
+            };
+            store_incident(&clients, incident).await;
+            canary_state.open_incident = Some(id);
+        }
+        (Some(description), Some(open)) => {
+            warn!("{description} (incident {open} still open)");
+            canary_state.open_incident = Some(open);
+        }
+        (None, Some(open)) => {
+            info!(
+                "Canary '{}' recovered; resolving incident {open}",
+                canary.name
+            );
+            let incident = crate::proto::memory::Incident {
+                id: open,
+                description: format!("Canary '{}' failing", canary.name),
+                resolution: format!("Canary passed again: {outcome:?}"),
+                resolved_by: "canary".to_string(),
+                timestamp: now,
+                ..Default::default()
+            };
+            store_incident(&clients, incident).await;
+        }
+        (None, None) => {}
+    }
+}
+
+async fn store_incident(
+    clients: &crate::clients::ServiceClients,
+    incident: crate::proto::memory::Incident,
+) {
+    match clients.memory().await {
+        Ok(mut client) => {
+            if let Err(e) = client.store_incident(incident).await {
+                warn!("Failed to record canary incident: {e}");
+            }
+        }
+        Err(e) => warn!("Cannot record canary incident: {e}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_overrides_builtins() {
+        let canaries = from_toml(
+            r#"
+            [[canary]]
+            name = "hostname"
+            goal = "Read /etc/hostname and report it"
+            expect = "aios"
+
+            [[canary]]
+            name = "disk"
+            goal = "Report disk usage of /"
+            interval_secs = 600
+            "#,
+        )
+        .unwrap();
+        assert_eq!(canaries.len(), 2);
+        assert_eq!(canaries[0].name, "hostname");
+        assert_eq!(canaries[0].expect.as_deref(), Some("aios"));
+        assert_eq!(canaries[1].interval_secs, 600);
+        assert_eq!(canaries[1].timeout_secs, 120);
+
+        assert!(from_toml("enabled = false").unwrap().is_empty());
+        assert!(from_toml("[[canary]]\nname = \"x\"\ngoal = \"\"").is_err());
+    }
+
+    #[test]
+    fn test_latency_judged_against_recent_runs() {
+        let canary = builtin_canaries().remove(0);
+        let mut state = CanaryState::default();
+        for latency in [1000, 1200, 900, 1100, 1000] {
+            assert!(matches!(
+                state.judge(&canary, latency),
+                Outcome::Passed { .. }
+            ));
+        }
+        assert_eq!(
+            state.judge(&canary, 5000),
+            Outcome::Slow {
+                latency_ms: 5000,
+                limit_ms: 3000
+            }
+        );
+        // Slow runs are not folded into the baseline
+        assert_eq!(state.latencies.len(), 5);
+
+        let strict = CanaryConfig {
+            max_latency_ms: Some(500),
+            ..canary
+        };
+        assert!(matches!(
+            CanaryState::default().judge(&strict, 800),
+            Outcome::Slow { limit_ms: 500, .. }
+        ));
+    }
+}
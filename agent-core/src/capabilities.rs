@@ -0,0 +1,546 @@
+//! Capability policy — what agents may hold, grants with expiry, revocation
+//!
+//! Capabilities are tool names ("firewall.add") or namespaces ("net",
+//! "net.*"). policies.toml has rules matching agents by type or id; a rule
+//! gives its agents `standing` capabilities they hold without asking,
+//! `grantable` ones they may be granted on request for at most
+//! `max_duration_hours`, and `deny` patterns no grant can cover.
+//!
+//! RequestCapability is granted only when every requested capability is
+//! standing or grantable for the agent and none is denied. Grants are kept
+//! in the capabilities database shared with `sec.grant`, so grants made
+//! either way count, expire, and reach the SIEM export. Task routing only
+//! hands an agent a task when it holds a capability in each namespace the
+//! task needs. Without a policy file nothing is standing or grantable.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Default location of the capability policy
+pub const POLICIES_PATH: &str = "/etc/aios/policies.toml";
+
+/// Capability grants, shared with the tools service's sec.grant
+pub const GRANTS_DB_PATH: &str = "/var/lib/aios/data/capabilities.db";
+
+/// Grant length when a request does not ask for one
+const DEFAULT_DURATION_HOURS: i64 = 24;
+
+/// How long grants read from the database are trusted before re-reading,
+/// to pick up sec.grant and sec.revoke from the tools service
+const GRANT_REFRESH: Duration = Duration::from_secs(30);
+
+/// A rule of policies.toml
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PolicyRule {
+    /// Agent types the rule applies to
+    #[serde(default)]
+    pub agent_types: Vec<String>,
+    /// Agent id patterns the rule applies to; a trailing `*` matches any suffix
+    #[serde(default)]
+    pub agent_ids: Vec<String>,
+    /// Held without asking
+    #[serde(default)]
+    pub standing: Vec<String>,
+    /// May be granted on request
+    #[serde(default)]
+    pub grantable: Vec<String>,
+    /// Never granted, even if another rule allows it
+    #[serde(default)]
+    pub deny: Vec<String>,
+    /// Longest grant of this rule's capabilities
+    #[serde(default)]
+    pub max_duration_hours: Option<i64>,
+}
+
+impl PolicyRule {
+    fn applies_to(&self, agent_id: &str, agent_type: &str) -> bool {
+        (!agent_type.is_empty() && self.agent_types.iter().any(|t| t == agent_type))
+            || self.agent_ids.iter().any(|p| matches_agent(p, agent_id))
+    }
+}
+
+/// policies.toml layout
+#[derive(Debug, Clone, Deserialize)]
+pub struct CapabilityPolicy {
+    /// Longest grant of any capability
+    #[serde(default = "default_max_duration_hours")]
+    pub max_duration_hours: i64,
+    #[serde(default)]
+    pub rules: Vec<PolicyRule>,
+}
+
+fn default_max_duration_hours() -> i64 {
+    168
+}
+
+impl Default for CapabilityPolicy {
+    fn default() -> Self {
+        Self {
+            max_duration_hours: default_max_duration_hours(),
+            rules: Vec::new(),
+        }
+    }
+}
+
+impl CapabilityPolicy {
+    /// The policy at `path`; a missing or invalid file is logged and
+    /// yields an empty policy that grants nothing
+    pub fn load(path: &str) -> Self {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => Self::from_toml(&contents).unwrap_or_else(|e| {
+                warn!("Ignoring invalid capability policy in {path}: {e}");
+                Self::default()
+            }),
+            Err(_) => {
+                warn!("No capability policy at {path}; no capabilities will be granted");
+                Self::default()
+            }
+        }
+    }
+
+    pub fn from_toml(contents: &str) -> Result<Self> {
+        toml::from_str(contents).context("Failed to parse capability policy")
+    }
+
+    fn rules_for<'a>(
+        &'a self,
+        agent_id: &'a str,
+        agent_type: &'a str,
+    ) -> impl Iterator<Item = &'a PolicyRule> + 'a {
+        self.rules
+            .iter()
+            .filter(move |r| r.applies_to(agent_id, agent_type))
+    }
+
+    fn denies(&self, agent_id: &str, agent_type: &str, capability: &str) -> bool {
+        self.rules_for(agent_id, agent_type)
+            .any(|r| r.deny.iter().any(|p| covers(p, capability)))
+    }
+
+    /// Whether the agent holds `capability` without a grant
+    pub fn is_standing(&self, agent_id: &str, agent_type: &str, capability: &str) -> bool {
+        !self.denies(agent_id, agent_type, capability)
+            && self
+                .rules_for(agent_id, agent_type)
+                .any(|r| r.standing.iter().any(|p| covers(p, capability)))
+    }
+
+    /// Decide a request: the grant length in hours, or why it is denied
+    pub fn evaluate(
+        &self,
+        agent_id: &str,
+        agent_type: &str,
+        capabilities: &[String],
+        duration_hours: i64,
+    ) -> std::result::Result<i64, String> {
+        if capabilities.is_empty() {
+            return Err("No capabilities requested".into());
+        }
+        let mut hours = if duration_hours > 0 {
+            duration_hours
+        } else {
+            DEFAULT_DURATION_HOURS
+        }
+        .min(self.max_duration_hours);
+        for capability in capabilities {
+            if self.denies(agent_id, agent_type, capability) {
+                return Err(format!("{capability} is denied by policy"));
+            }
+            let allowed: Vec<&PolicyRule> = self
+                .rules_for(agent_id, agent_type)
+                .filter(|r| {
+                    r.standing
+                        .iter()
+                        .chain(&r.grantable)
+                        .any(|p| covers(p, capability))
+                })
+                .collect();
+            if allowed.is_empty() {
+                return Err(format!("{capability} is not grantable to {agent_id}"));
+            }
+            // The most generous rule allowing this capability bounds it
+            let longest = allowed
+                .iter()
+                .map(|r| r.max_duration_hours.unwrap_or(self.max_duration_hours))
+                .max()
+                .unwrap_or(self.max_duration_hours);
+            hours = hours.min(longest);
+        }
+        Ok(hours)
+    }
+}
+
+/// An active grant
+#[derive(Debug, Clone)]
+pub struct Grant {
+    pub id: i64,
+    pub agent_id: String,
+    pub capability: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Outcome of a capability request
+#[derive(Debug, Clone, PartialEq)]
+pub enum Decision {
+    Granted { expires_at: DateTime<Utc> },
+    Denied { reason: String },
+}
+
+struct GrantStore {
+    conn: Option<rusqlite::Connection>,
+    active: Vec<Grant>,
+    loaded_at: Option<Instant>,
+}
+
+impl GrantStore {
+    fn refresh(&mut self) {
+        if self
+            .loaded_at
+            .is_some_and(|at| at.elapsed() < GRANT_REFRESH)
+        {
+            return;
+        }
+        let Some(ref conn) = self.conn else {
+            return;
+        };
+        match load_active(conn) {
+            Ok(active) => self.active = active,
+            Err(e) => warn!("Failed to read capability grants: {e}"),
+        }
+        self.loaded_at = Some(Instant::now());
+    }
+}
+
+/// Policy plus the grants made under it
+pub struct CapabilityEngine {
+    policy: CapabilityPolicy,
+    grants: Mutex<GrantStore>,
+}
+
+impl CapabilityEngine {
+    /// Engine for `policy` keeping grants in the database at `db_path`;
+    /// grants are only kept in memory if it cannot be opened
+    pub fn open(policy: CapabilityPolicy, db_path: &str) -> Self {
+        let conn = match open_db(db_path) {
+            Ok(conn) => Some(conn),
+            Err(e) => {
+                warn!("Capability grants will not persist: {e}");
+                None
+            }
+        };
+        Self {
+            policy,
+            grants: Mutex::new(GrantStore {
+                conn,
+                active: Vec::new(),
+                loaded_at: None,
+            }),
+        }
+    }
+
+    /// Evaluate a request and record the grants if it is allowed
+    pub fn request(
+        &self,
+        agent_id: &str,
+        agent_type: &str,
+        capabilities: &[String],
+        reason: &str,
+        duration_hours: i64,
+    ) -> Result<Decision> {
+        let hours = match self
+            .policy
+            .evaluate(agent_id, agent_type, capabilities, duration_hours)
+        {
+            Ok(hours) => hours,
+            Err(reason) => {
+                warn!("Capability request from {agent_id} denied: {reason}");
+                return Ok(Decision::Denied { reason });
+            }
+        };
+        let now = Utc::now();
+        let expires_at = now + chrono::Duration::hours(hours);
+
+        let mut store = self
+            .grants
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        store.refresh();
+        for capability in capabilities {
+            let id = match store.conn {
+                Some(ref conn) => {
+                    conn.execute(
+                        "INSERT INTO capability_grants (agent_id, capability, reason, granted_at, expires_at)
+                         VALUES (?1, ?2, ?3, ?4, ?5)",
+                        rusqlite::params![
+                            agent_id,
+                            capability,
+                            reason,
+                            now.to_rfc3339(),
+                            expires_at.to_rfc3339()
+                        ],
+                    )?;
+                    conn.last_insert_rowid()
+                }
+                None => 0,
+            };
+            store.active.push(Grant {
+                id,
+                agent_id: agent_id.to_string(),
+                capability: capability.clone(),
+                expires_at,
+            });
+        }
+        info!("Granted {capabilities:?} to {agent_id} for {hours}h");
+        Ok(Decision::Granted { expires_at })
+    }
+
+    /// Revoke an agent's grants of `capabilities`, or all of them; the
+    /// number of grants revoked
+    pub fn revoke(&self, agent_id: &str, capabilities: &[String], all: bool) -> Result<usize> {
+        let mut store = self
+            .grants
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        store.refresh();
+        let revoked: Vec<i64> = store
+            .active
+            .iter()
+            .filter(|g| g.agent_id == agent_id && (all || capabilities.contains(&g.capability)))
+            .map(|g| g.id)
+            .collect();
+        if let Some(ref conn) = store.conn {
+            if all {
+                conn.execute(
+                    "UPDATE capability_grants SET revoked = 1 WHERE agent_id = ?1 AND revoked = 0",
+                    [agent_id],
+                )?;
+            } else {
+                for capability in capabilities {
+                    conn.execute(
+                        "UPDATE capability_grants SET revoked = 1
+                         WHERE agent_id = ?1 AND capability = ?2 AND revoked = 0",
+                        [agent_id, capability.as_str()],
+                    )?;
+                }
+            }
+        }
+        store
+            .active
+            .retain(|g| !(g.agent_id == agent_id && (all || capabilities.contains(&g.capability))));
+        Ok(revoked.len())
+    }
+
+    /// Whether the agent holds a capability satisfying `required`, a tool
+    /// name or a namespace
+    pub fn holds(&self, agent_id: &str, agent_type: &str, required: &str) -> bool {
+        if self.policy.denies(agent_id, agent_type, required) {
+            return false;
+        }
+        let standing = self
+            .policy
+            .rules_for(agent_id, agent_type)
+            .flat_map(|r| &r.standing)
+            .any(|p| satisfies(p, required));
+        if standing {
+            return true;
+        }
+        let Ok(mut store) = self.grants.lock() else {
+            return false;
+        };
+        store.refresh();
+        let now = Utc::now();
+        store.active.iter().any(|g| {
+            g.agent_id == agent_id && g.expires_at > now && satisfies(&g.capability, required)
+        })
+    }
+}
+
+fn open_db(path: &str) -> Result<rusqlite::Connection> {
+    if let Some(parent) = std::path::Path::new(path).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let conn = rusqlite::Connection::open(path)?;
+    // Same table sec.grant creates
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS capability_grants (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            agent_id TEXT NOT NULL,
+            capability TEXT NOT NULL,
+            reason TEXT,
+            granted_at TEXT NOT NULL,
+            expires_at TEXT NOT NULL,
+            revoked INTEGER DEFAULT 0
+        )",
+    )?;
+    Ok(conn)
+}
+
+fn load_active(conn: &rusqlite::Connection) -> Result<Vec<Grant>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, agent_id, capability, expires_at FROM capability_grants WHERE revoked = 0",
+    )?;
+    let now = Utc::now();
+    let grants = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+            ))
+        })?
+        .filter_map(|r| r.ok())
+        .filter_map(|(id, agent_id, capability, expires_at)| {
+            let expires_at = DateTime::parse_from_rfc3339(&expires_at)
+                .ok()?
+                .with_timezone(&Utc);
+            (expires_at > now).then_some(Grant {
+                id,
+                agent_id,
+                capability,
+                expires_at,
+            })
+        })
+        .collect();
+    Ok(grants)
+}
+
+/// Whether capability pattern `pattern` covers `capability`: `*` covers
+/// everything, `ns.*` and `ns` cover the namespace and its tools
+pub fn covers(pattern: &str, capability: &str) -> bool {
+    if pattern == "*" || pattern == capability {
+        return true;
+    }
+    let namespace = pattern.strip_suffix(".*").unwrap_or(pattern);
+    !namespace.contains('.')
+        && (capability == namespace
+            || capability
+                .strip_prefix(namespace)
+                .is_some_and(|rest| rest.starts_with('.')))
+}
+
+/// Whether holding `held` satisfies a task's need for `required`: a tool
+/// needs a capability covering it, a namespace any capability within it
+fn satisfies(held: &str, required: &str) -> bool {
+    if required.contains('.') {
+        return covers(held, required);
+    }
+    held == "*" || held.split('.').next() == Some(required)
+}
+
+fn matches_agent(pattern: &str, agent_id: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => agent_id.starts_with(prefix),
+        None => pattern == agent_id,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLICY: &str = r#"
+        max_duration_hours = 48
+
+        [[rules]]
+        agent_types = ["network"]
+        standing = ["net.*", "firewall.list"]
+        grantable = ["firewall.*"]
+        deny = ["firewall.disable"]
+        max_duration_hours = 8
+
+        [[rules]]
+        agent_ids = ["ops-*"]
+        grantable = ["*"]
+    "#;
+
+    fn caps(list: &[&str]) -> Vec<String> {
+        list.iter().map(|c| c.to_string()).collect()
+    }
+
+    #[test]
+    fn test_policy_evaluation() {
+        let policy = CapabilityPolicy::from_toml(POLICY).unwrap();
+        assert_eq!(
+            policy.evaluate("net-1", "network", &caps(&["firewall.add"]), 24),
+            Ok(8)
+        );
+        assert_eq!(
+            policy.evaluate("net-1", "network", &caps(&["firewall.disable"]), 1),
+            Err("firewall.disable is denied by policy".into())
+        );
+        assert_eq!(
+            policy.evaluate("net-1", "network", &caps(&["pkg.install"]), 1),
+            Err("pkg.install is not grantable to net-1".into())
+        );
+        assert_eq!(
+            policy.evaluate("ops-7", "", &caps(&["pkg.install"]), 0),
+            Ok(DEFAULT_DURATION_HOURS)
+        );
+        assert_eq!(
+            policy.evaluate("ops-7", "", &caps(&["pkg.install"]), 500),
+            Ok(48)
+        );
+        assert!(policy.is_standing("net-1", "network", "net.ping"));
+        assert!(!policy.is_standing("net-1", "system", "net.ping"));
+        assert!(CapabilityPolicy::default()
+            .evaluate("net-1", "network", &caps(&["net.ping"]), 1)
+            .is_err());
+    }
+
+    #[test]
+    fn test_grants_route_expire_and_revoke() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("capabilities.db");
+        let policy = CapabilityPolicy::from_toml(POLICY).unwrap();
+        let engine = CapabilityEngine::open(policy.clone(), db.to_str().unwrap());
+
+        assert!(engine.holds("net-1", "network", "net"));
+        assert!(!engine.holds("net-1", "network", "firewall.add"));
+        assert!(engine.holds("net-1", "network", "firewall"));
+        assert!(!engine.holds("ops-7", "", "pkg"));
+
+        let decision = engine
+            .request("ops-7", "", &caps(&["pkg.install"]), "upgrade", 2)
+            .unwrap();
+        assert!(matches!(decision, Decision::Granted { .. }));
+        assert!(engine.holds("ops-7", "", "pkg"));
+        assert!(engine.holds("ops-7", "", "pkg.install"));
+        assert!(!engine.holds("ops-7", "", "pkg.remove"));
+
+        // Grants survive a restart
+        let reopened = CapabilityEngine::open(policy, db.to_str().unwrap());
+        assert!(reopened.holds("ops-7", "", "pkg.install"));
+
+        assert_eq!(reopened.revoke("ops-7", &[], true).unwrap(), 1);
+        assert!(!reopened.holds("ops-7", "", "pkg"));
+        assert_eq!(reopened.revoke("ops-7", &[], true).unwrap(), 0);
+
+        // Expired grants are not held
+        let conn = rusqlite::Connection::open(&db).unwrap();
+        conn.execute(
+            "INSERT INTO capability_grants (agent_id, capability, reason, granted_at, expires_at)
+             VALUES ('ops-8', 'pkg.*', '', '2026-01-01T00:00:00+00:00', '2026-01-02T00:00:00+00:00')",
+            [],
+        )
+        .unwrap();
+        let fresh = CapabilityEngine::open(CapabilityPolicy::default(), db.to_str().unwrap());
+        assert!(!fresh.holds("ops-8", "", "pkg"));
+    }
+
+    #[test]
+    fn test_covers() {
+        assert!(covers("*", "fs.read"));
+        assert!(covers("fs.*", "fs.read"));
+        assert!(covers("fs", "fs.read"));
+        assert!(covers("fs.*", "fs"));
+        assert!(!covers("fs.*", "fsx.read"));
+        assert!(!covers("fs.read", "fs.write"));
+        assert!(satisfies("fs.read", "fs"));
+        assert!(!satisfies("fs.read", "net"));
+    }
+}
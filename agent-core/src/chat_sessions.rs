@@ -0,0 +1,257 @@
+//! Chat sessions — multi-turn conversations in the management console
+//!
+//! Every `/api/chat` message belongs to a session kept in working memory,
+//! so a conversation keeps its context from one message to the next and
+//! can be listed and resumed from the dashboard. A message without a
+//! session starts a new one.
+//!
+//! The prompt carries the session's recent messages verbatim after a
+//! running summary of the ones before them. Once more than
+//! [`SUMMARIZE_AFTER`] messages are outside the summary, all but the last
+//! [`KEEP_RECENT`] are folded into it by the model, so long conversations
+//! stay within the context window.
+
+use tracing::{debug, warn};
+
+use crate::clients::ServiceClients;
+use crate::proto::memory::{
+    ChatMessage, ChatSession, ChatSessionListRequest, ChatSessionRequest, ChatSummary,
+};
+
+/// Messages outside the summary that trigger a new summary
+pub const SUMMARIZE_AFTER: usize = 16;
+
+/// Most recent messages always quoted verbatim
+pub const KEEP_RECENT: usize = 6;
+
+/// Characters of one message quoted in a prompt
+const MAX_MESSAGE_CHARS: usize = 4000;
+
+/// Role of the operator's messages
+pub const USER: &str = "user";
+
+/// Role of the model's replies
+pub const ASSISTANT: &str = "assistant";
+
+/// A session from working memory, if it exists
+pub async fn load(clients: &ServiceClients, session_id: &str) -> Option<ChatSession> {
+    let request = ChatSessionRequest {
+        session_id: session_id.to_string(),
+    };
+    match clients.memory().await {
+        Ok(mut client) => match client.get_chat_session(request).await {
+            Ok(response) => Some(response.into_inner()).filter(|s| !s.session_id.is_empty()),
+            Err(e) => {
+                debug!("Failed to load chat session {session_id}: {e}");
+                None
+            }
+        },
+        Err(e) => {
+            debug!("Memory service unavailable for chat session: {e}");
+            None
+        }
+    }
+}
+
+/// Sessions without their messages, most recently active first
+pub async fn list(clients: &ServiceClients, limit: i32) -> Vec<ChatSession> {
+    let Ok(mut client) = clients.memory().await else {
+        return Vec::new();
+    };
+    client
+        .list_chat_sessions(ChatSessionListRequest { limit })
+        .await
+        .map(|r| r.into_inner().sessions)
+        .unwrap_or_default()
+}
+
+/// Add a message to its session in working memory
+pub async fn append(clients: &ServiceClients, message: ChatMessage) {
+    let session_id = message.session_id.clone();
+    match clients.memory().await {
+        Ok(mut client) => {
+            if let Err(e) = client.append_chat_message(message).await {
+                warn!("Failed to store chat message of session {session_id}: {e}");
+            }
+        }
+        Err(e) => warn!("Memory service unavailable for chat session {session_id}: {e}"),
+    }
+}
+
+/// Prompt for `message`, preceded by the conversation so far
+pub fn prompt(session: Option<&ChatSession>, message: &str) -> String {
+    let Some(session) = session else {
+        return message.to_string();
+    };
+    let recent = unsummarized(session);
+    if session.summary.is_empty() && recent.is_empty() {
+        return message.to_string();
+    }
+
+    let mut prompt = String::from("## Conversation so far\n");
+    if !session.summary.is_empty() {
+        prompt.push_str(&format!(
+            "Summary of earlier messages: {}\n\n",
+            session.summary
+        ));
+    }
+    prompt.push_str(&render(recent));
+    prompt.push_str(&format!("\n## New message\n{message}"));
+    prompt
+}
+
+/// Fold older messages of the session into its summary when enough have
+/// piled up since the last one
+pub async fn summarize(clients: &ServiceClients, session_id: &str) {
+    let Some(session) = load(clients, session_id).await else {
+        return;
+    };
+    let Some((summarized_through, prompt)) = pending_summary(&session) else {
+        return;
+    };
+    let Ok(mut client) = clients.api_gateway().await else {
+        return;
+    };
+    let request = tonic::Request::new(crate::proto::api_gateway::ApiInferRequest {
+        prompt,
+        system_prompt: String::new(),
+        max_tokens: 1024,
+        temperature: 0.2,
+        preferred_provider: String::new(),
+        requesting_agent: "chat-console".to_string(),
+        task_id: String::new(),
+        allow_fallback: true,
+        response_schema: String::new(),
+        sections: Vec::new(),
+        session_id: String::new(),
+        turn_kind: String::new(),
+        model_class: String::new(),
+        workload: crate::workload::BACKGROUND.to_string(),
+        goal_id: String::new(),
+        goal_budget: None,
+        bypass_cache: false,
+    });
+    let summary = match client.infer(request).await {
+        Ok(response) => response.into_inner().text.trim().to_string(),
+        Err(e) => {
+            warn!("Could not summarize chat session {session_id}: {e}");
+            return;
+        }
+    };
+    if summary.is_empty() {
+        return;
+    }
+    let summary = ChatSummary {
+        session_id: session_id.to_string(),
+        summary,
+        summarized_through,
+    };
+    match clients.memory().await {
+        Ok(mut client) => {
+            if let Err(e) = client.store_chat_summary(summary).await {
+                warn!("Failed to store summary of chat session {session_id}: {e}");
+            }
+        }
+        Err(e) => warn!("Memory service unavailable for chat session {session_id}: {e}"),
+    }
+}
+
+/// Messages the summary does not cover yet
+fn unsummarized(session: &ChatSession) -> &[ChatMessage] {
+    let through = (session.summarized_through.max(0) as usize).min(session.messages.len());
+    &session.messages[through..]
+}
+
+/// How far a new summary would reach and the prompt asking for it, when
+/// the session needs one
+fn pending_summary(session: &ChatSession) -> Option<(i32, String)> {
+    let recent = unsummarized(session);
+    if recent.len() <= SUMMARIZE_AFTER {
+        return None;
+    }
+    let folded = &recent[..recent.len() - KEEP_RECENT];
+    let summarized_through = (session.messages.len() - KEEP_RECENT) as i32;
+
+    let mut prompt = String::from(
+        "Summarize this conversation between an operator and aiOS so it can be continued \
+         later. Keep facts, decisions, names, paths and open questions; leave out \
+         pleasantries. Reply with the summary only.\n\n",
+    );
+    if !session.summary.is_empty() {
+        prompt.push_str(&format!("Summary so far: {}\n\n", session.summary));
+    }
+    prompt.push_str(&render(folded));
+    Some((summarized_through, prompt))
+}
+
+fn render(messages: &[ChatMessage]) -> String {
+    let mut text = String::new();
+    for message in messages {
+        let speaker = if message.role == USER {
+            "Operator"
+        } else {
+            "aiOS"
+        };
+        let content: String = message.content.chars().take(MAX_MESSAGE_CHARS).collect();
+        text.push_str(&format!("{speaker}: {content}\n"));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(count: usize, summarized_through: i32) -> ChatSession {
+        let messages = (0..count)
+            .map(|i| ChatMessage {
+                session_id: "chat-1".into(),
+                role: if i % 2 == 0 { USER } else { ASSISTANT }.into(),
+                content: format!("message {i}"),
+                timestamp: i as i64,
+                ..Default::default()
+            })
+            .collect();
+        ChatSession {
+            session_id: "chat-1".into(),
+            summary: if summarized_through > 0 {
+                "earlier talk".into()
+            } else {
+                String::new()
+            },
+            summarized_through,
+            message_count: count as i32,
+            messages,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_prompt_quotes_unsummarized_messages() {
+        assert_eq!(prompt(None, "hi"), "hi");
+        assert_eq!(prompt(Some(&session(0, 0)), "hi"), "hi");
+
+        let text = prompt(Some(&session(4, 2)), "and now?");
+        assert!(text.contains("Summary of earlier messages: earlier talk"));
+        assert!(!text.contains("message 1\n"));
+        assert!(text.contains("Operator: message 2\naiOS: message 3\n"));
+        assert!(text.ends_with("## New message\nand now?"));
+    }
+
+    #[test]
+    fn test_pending_summary_keeps_recent_messages() {
+        assert!(pending_summary(&session(SUMMARIZE_AFTER, 0)).is_none());
+
+        let (through, text) = pending_summary(&session(SUMMARIZE_AFTER + 1, 0)).unwrap();
+        assert_eq!(through as usize, SUMMARIZE_AFTER + 1 - KEEP_RECENT);
+        assert!(text.contains("message 0\n"));
+        assert!(!text.contains(&format!("message {through}\n")));
+
+        // Later summaries start where the last one stopped
+        let (through, text) = pending_summary(&session(40, 20)).unwrap();
+        assert_eq!(through as usize, 40 - KEEP_RECENT);
+        assert!(text.contains("Summary so far: earlier talk"));
+        assert!(!text.contains("message 19\n"));
+        assert!(text.contains("message 20\n"));
+    }
+}
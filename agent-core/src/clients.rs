@@ -1,147 +1,281 @@
 //! Inter-Service gRPC Clients
 //!
-//! Provides lazy-connecting gRPC client stubs for all aiOS services:
-//! runtime, tools, memory, and api-gateway.
+//! Provides gRPC client stubs for all aiOS services: runtime, tools, memory,
+//! api-gateway, and the orchestrator's own public API. Stubs of a service
+//! share its channels, which are created on first use and retry calls and
+//! trip circuit breakers as described in [`crate::resilience`]. Calls on
+//! every stub are recorded for their method's latency SLO ([`crate::slo`]).
+//!
+//! Memory context lookups go through a short-lived read-through cache so
+//! repeated similar tasks don't re-query the memory service on every tick.
 
-use anyhow::{Context, Result};
-use std::sync::Arc;
-use std::time::Duration;
-use tokio::sync::{OnceCell, RwLock};
-use tonic::transport::{Channel, Endpoint};
-use tracing::{debug, info, warn};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use tracing::debug;
 
 use crate::discovery::ServiceRegistry;
 use crate::proto;
+use crate::resilience::{ResilientChannel, RetryPolicy, ServiceEndpoints};
+use crate::slo::{self, SloTracker};
 
-/// Holds gRPC client connections to all aiOS services
-pub struct ServiceClients {
-    runtime_channel: OnceCell<Channel>,
-    tools_channel: OnceCell<Channel>,
-    memory_channel: OnceCell<Channel>,
-    api_gateway_channel: OnceCell<Channel>,
-    runtime_addr: String,
-    tools_addr: String,
-    memory_addr: String,
-    api_gateway_addr: String,
-    /// Optional service discovery registry for dynamic address resolution
-    discovery: Option<Arc<RwLock<ServiceRegistry>>>,
+/// Default lifetime of a cached memory context lookup
+const DEFAULT_CONTEXT_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// Maximum number of cached memory context lookups
+const CONTEXT_CACHE_CAPACITY: usize = 128;
+
+/// Read-through cache for memory `AssembleContext` results, keyed by the
+/// normalized task description and request parameters
+pub struct ContextCache {
+    ttl: Duration,
+    capacity: usize,
+    entries: Mutex<HashMap<String, (Instant, Vec<proto::memory::ContextChunk>)>>,
 }
 
-impl ServiceClients {
-    pub fn new() -> Self {
+impl ContextCache {
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
         Self {
-            runtime_channel: OnceCell::new(),
-            tools_channel: OnceCell::new(),
-            memory_channel: OnceCell::new(),
-            api_gateway_channel: OnceCell::new(),
-            runtime_addr: std::env::var("AIOS_RUNTIME_ADDR")
-                .unwrap_or_else(|_| "http://127.0.0.1:50055".to_string()),
-            tools_addr: std::env::var("AIOS_TOOLS_ADDR")
-                .unwrap_or_else(|_| "http://127.0.0.1:50052".to_string()),
-            memory_addr: std::env::var("AIOS_MEMORY_ADDR")
-                .unwrap_or_else(|_| "http://127.0.0.1:50053".to_string()),
-            api_gateway_addr: std::env::var("AIOS_GATEWAY_ADDR")
-                .unwrap_or_else(|_| "http://127.0.0.1:50054".to_string()),
-            discovery: None,
+            ttl,
+            capacity,
+            entries: Mutex::new(HashMap::new()),
         }
     }
 
-    /// Create clients with service discovery support
-    pub fn with_discovery(discovery: Arc<RwLock<ServiceRegistry>>) -> Self {
-        let mut clients = Self::new();
-        clients.discovery = Some(discovery);
-        clients
+    /// Build a cache key; descriptions differing only in case or whitespace share an entry
+    pub fn key(task_description: &str, max_tokens: i32, tiers: &[&str]) -> String {
+        let normalized = task_description
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase();
+        format!("{max_tokens}|{}|{normalized}", tiers.join(","))
     }
 
-    /// Resolve a service address via discovery, falling back to the hardcoded default
-    async fn resolve_addr(&self, service_name: &str, default: &str) -> String {
-        if std::env::var("AIOS_USE_DISCOVERY").unwrap_or_default() != "true" {
-            return default.to_string();
-        }
-        if let Some(ref registry) = self.discovery {
-            let reg = registry.read().await;
-            if let Some(info) = reg.lookup(service_name) {
-                let addr = format!("http://{}", info.address);
-                debug!("Resolved {service_name} via discovery: {addr}");
-                return addr;
+    /// Get unexpired chunks for a key
+    pub fn get(&self, key: &str) -> Option<Vec<proto::memory::ContextChunk>> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(key) {
+            Some((stored_at, chunks)) if stored_at.elapsed() < self.ttl => Some(chunks.clone()),
+            Some(_) => {
+                entries.remove(key);
+                None
             }
+            None => None,
+        }
+    }
+
+    /// Store chunks for a key, evicting expired entries and then the oldest when full
+    pub fn insert(&self, key: String, chunks: Vec<proto::memory::ContextChunk>) {
+        if self.capacity == 0 || self.ttl.is_zero() {
+            return;
         }
-        default.to_string()
-    }
-
-    /// Create a channel with retry logic
-    async fn connect_with_retry(addr: &str) -> Result<Channel> {
-        let endpoint = Endpoint::from_shared(addr.to_string())
-            .context("Invalid endpoint address")?
-            .connect_timeout(Duration::from_secs(5))
-            .timeout(Duration::from_secs(300))
-            .tcp_keepalive(Some(Duration::from_secs(10)));
-
-        for attempt in 1..=3 {
-            match endpoint.connect().await {
-                Ok(channel) => {
-                    info!("Connected to {addr} (attempt {attempt})");
-                    return Ok(channel);
-                }
-                Err(e) => {
-                    warn!("Connection to {addr} failed (attempt {attempt}): {e}");
-                    if attempt < 3 {
-                        tokio::time::sleep(Duration::from_millis(500 * attempt as u64)).await;
-                    }
-                }
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, (stored_at, _)| stored_at.elapsed() < self.ttl);
+        if entries.len() >= self.capacity && !entries.contains_key(&key) {
+            if let Some(oldest) = entries
+                .iter()
+                .min_by_key(|(_, (stored_at, _))| *stored_at)
+                .map(|(k, _)| k.clone())
+            {
+                entries.remove(&oldest);
             }
         }
+        entries.insert(key, (Instant::now(), chunks));
+    }
+}
+
+/// Holds gRPC client connections to all aiOS services
+pub struct ServiceClients {
+    runtime: Arc<ServiceEndpoints>,
+    tools: Arc<ServiceEndpoints>,
+    memory: Arc<ServiceEndpoints>,
+    api_gateway: Arc<ServiceEndpoints>,
+    orchestrator: Arc<ServiceEndpoints>,
+    /// Cache for memory context lookups
+    context_cache: ContextCache,
+    /// Latency and error SLOs of every method called
+    slo: Arc<SloTracker>,
+}
+
+impl ServiceClients {
+    pub fn new() -> Self {
+        Self::build(None)
+    }
+
+    /// Create clients that prefer the addresses services register in
+    /// discovery, when `AIOS_USE_DISCOVERY` is "true"
+    pub fn with_discovery(discovery: Arc<RwLock<ServiceRegistry>>) -> Self {
+        if std::env::var("AIOS_USE_DISCOVERY").unwrap_or_default() == "true" {
+            Self::build(Some(discovery))
+        } else {
+            Self::new()
+        }
+    }
+
+    fn build(discovery: Option<Arc<RwLock<ServiceRegistry>>>) -> Self {
+        let slo = Arc::new(SloTracker::new(slo::load(slo::SLO_CONFIG_PATH)));
+        let endpoints = |service: &str, discovery_name: &str, env: &str, default: &str| {
+            Arc::new(
+                ServiceEndpoints::new(
+                    service,
+                    discovery_name,
+                    std::env::var(env).unwrap_or_else(|_| default.to_string()),
+                    discovery.clone(),
+                    RetryPolicy::default(),
+                )
+                .with_slo(slo.clone()),
+            )
+        };
+        Self {
+            runtime: endpoints(
+                proto::runtime::ai_runtime_server::SERVICE_NAME,
+                "runtime",
+                "AIOS_RUNTIME_ADDR",
+                "http://127.0.0.1:50055",
+            ),
+            tools: endpoints(
+                proto::tools::tool_registry_server::SERVICE_NAME,
+                "tools",
+                "AIOS_TOOLS_ADDR",
+                "http://127.0.0.1:50052",
+            ),
+            memory: endpoints(
+                proto::memory::memory_service_server::SERVICE_NAME,
+                "memory",
+                "AIOS_MEMORY_ADDR",
+                "http://127.0.0.1:50053",
+            ),
+            api_gateway: endpoints(
+                proto::api_gateway::api_gateway_server::SERVICE_NAME,
+                "api-gateway",
+                "AIOS_GATEWAY_ADDR",
+                "http://127.0.0.1:50054",
+            ),
+            orchestrator: endpoints(
+                proto::orchestrator::orchestrator_server::SERVICE_NAME,
+                "orchestrator",
+                "AIOS_ORCHESTRATOR_ADDR",
+                "http://127.0.0.1:50051",
+            ),
+            context_cache: ContextCache::new(
+                std::env::var("AIOS_CONTEXT_CACHE_TTL_SECS")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .map(Duration::from_secs)
+                    .unwrap_or(DEFAULT_CONTEXT_CACHE_TTL),
+                CONTEXT_CACHE_CAPACITY,
+            ),
+            slo,
+        }
+    }
 
-        // Return a lazy channel that will connect on first use
-        Ok(endpoint.connect_lazy())
+    /// Latency and error SLO tracking shared by every stub
+    pub fn slo(&self) -> Arc<SloTracker> {
+        self.slo.clone()
     }
 
-    /// Get or create the runtime gRPC client
+    /// Circuit breaker state of every endpoint called so far, as
+    /// (service, address, state)
+    pub fn circuit_states(&self) -> Vec<(&'static str, String, &'static str)> {
+        [
+            ("runtime", &self.runtime),
+            ("tools", &self.tools),
+            ("memory", &self.memory),
+            ("api-gateway", &self.api_gateway),
+            ("orchestrator", &self.orchestrator),
+        ]
+        .into_iter()
+        .flat_map(|(service, endpoints)| {
+            endpoints
+                .circuit_states()
+                .into_iter()
+                .map(move |(addr, state)| (service, addr, state))
+        })
+        .collect()
+    }
+
+    /// Get the runtime gRPC client
     pub async fn runtime(
         &self,
-    ) -> Result<proto::runtime::ai_runtime_client::AiRuntimeClient<Channel>> {
-        let channel = self
-            .runtime_channel
-            .get_or_try_init(|| Self::connect_with_retry(&self.runtime_addr))
-            .await?;
+    ) -> Result<proto::runtime::ai_runtime_client::AiRuntimeClient<ResilientChannel>> {
         Ok(proto::runtime::ai_runtime_client::AiRuntimeClient::new(
-            channel.clone(),
+            ResilientChannel::new(self.runtime.clone()),
         ))
     }
 
-    /// Get or create the tools gRPC client
+    /// Get the tools gRPC client
     pub async fn tools(
         &self,
-    ) -> Result<proto::tools::tool_registry_client::ToolRegistryClient<Channel>> {
-        let channel = self
-            .tools_channel
-            .get_or_try_init(|| Self::connect_with_retry(&self.tools_addr))
-            .await?;
+    ) -> Result<proto::tools::tool_registry_client::ToolRegistryClient<ResilientChannel>> {
         Ok(proto::tools::tool_registry_client::ToolRegistryClient::new(
-            channel.clone(),
+            ResilientChannel::new(self.tools.clone()),
         ))
     }
 
-    /// Get or create the memory gRPC client
+    /// Get the memory gRPC client
     pub async fn memory(
         &self,
-    ) -> Result<proto::memory::memory_service_client::MemoryServiceClient<Channel>> {
-        let channel = self
-            .memory_channel
-            .get_or_try_init(|| Self::connect_with_retry(&self.memory_addr))
+    ) -> Result<proto::memory::memory_service_client::MemoryServiceClient<ResilientChannel>> {
+        Ok(
+            proto::memory::memory_service_client::MemoryServiceClient::new(ResilientChannel::new(
+                self.memory.clone(),
+            )),
+        )
+    }
+
+    /// Assemble memory context for a task, serving repeated lookups from the cache.
+    /// Failed lookups are not cached.
+    pub async fn assemble_context(
+        &self,
+        task_description: &str,
+        max_tokens: i32,
+        tiers: &[&str],
+    ) -> Result<Vec<proto::memory::ContextChunk>> {
+        let key = ContextCache::key(task_description, max_tokens, tiers);
+        if let Some(chunks) = self.context_cache.get(&key) {
+            debug!("Memory context cache hit ({} chunks)", chunks.len());
+            return Ok(chunks);
+        }
+
+        let mut client = self.memory().await?;
+        let response = client
+            .assemble_context(tonic::Request::new(proto::memory::ContextRequest {
+                task_description: task_description.to_string(),
+                max_tokens,
+                memory_tiers: tiers.iter().map(|t| t.to_string()).collect(),
+                requesting_agent: "orchestrator".to_string(),
+            }))
             .await?;
-        Ok(proto::memory::memory_service_client::MemoryServiceClient::new(channel.clone()))
+        let chunks = response.into_inner().chunks;
+        self.context_cache.insert(key, chunks.clone());
+        Ok(chunks)
     }
 
-    /// Get or create the api-gateway gRPC client
+    /// Get the api-gateway gRPC client
     pub async fn api_gateway(
         &self,
-    ) -> Result<proto::api_gateway::api_gateway_client::ApiGatewayClient<Channel>> {
-        let channel = self
-            .api_gateway_channel
-            .get_or_try_init(|| Self::connect_with_retry(&self.api_gateway_addr))
-            .await?;
-        Ok(proto::api_gateway::api_gateway_client::ApiGatewayClient::new(channel.clone()))
+    ) -> Result<proto::api_gateway::api_gateway_client::ApiGatewayClient<ResilientChannel>> {
+        Ok(
+            proto::api_gateway::api_gateway_client::ApiGatewayClient::new(ResilientChannel::new(
+                self.api_gateway.clone(),
+            )),
+        )
+    }
+
+    /// Get a client for this node's own orchestrator API, used to drive
+    /// work through the same path external clients take
+    pub async fn orchestrator(
+        &self,
+    ) -> Result<proto::orchestrator::orchestrator_client::OrchestratorClient<ResilientChannel>>
+    {
+        Ok(
+            proto::orchestrator::orchestrator_client::OrchestratorClient::new(
+                ResilientChannel::new(self.orchestrator.clone()),
+            ),
+        )
     }
 }
 
@@ -152,9 +286,66 @@ mod tests {
     #[test]
     fn test_service_clients_new() {
         let clients = ServiceClients::new();
-        assert_eq!(clients.runtime_addr, "http://127.0.0.1:50055");
-        assert_eq!(clients.tools_addr, "http://127.0.0.1:50052");
-        assert_eq!(clients.memory_addr, "http://127.0.0.1:50053");
-        assert_eq!(clients.api_gateway_addr, "http://127.0.0.1:50054");
+        assert_eq!(clients.runtime.default_addr(), "http://127.0.0.1:50055");
+        assert_eq!(clients.tools.default_addr(), "http://127.0.0.1:50052");
+        assert_eq!(clients.memory.default_addr(), "http://127.0.0.1:50053");
+        assert_eq!(clients.api_gateway.default_addr(), "http://127.0.0.1:50054");
+    }
+
+    fn chunk(content: &str) -> proto::memory::ContextChunk {
+        proto::memory::ContextChunk {
+            source: "test".into(),
+            content: content.into(),
+            relevance: 1.0,
+            tokens: 1,
+            provenance: None,
+        }
+    }
+
+    #[test]
+    fn test_context_cache_key_normalizes_description() {
+        let tiers = ["operational", "working"];
+        assert_eq!(
+            ContextCache::key("Check  disk\tusage", 2048, &tiers),
+            ContextCache::key("check disk usage", 2048, &tiers)
+        );
+        assert_ne!(
+            ContextCache::key("check disk usage", 2048, &tiers),
+            ContextCache::key("check disk usage", 1024, &tiers)
+        );
+    }
+
+    #[test]
+    fn test_context_cache_hit_and_expiry() {
+        let cache = ContextCache::new(Duration::from_secs(60), 8);
+        assert!(cache.get("k").is_none());
+        cache.insert("k".into(), vec![chunk("a")]);
+        assert_eq!(cache.get("k").unwrap()[0].content, "a");
+
+        let expired = ContextCache::new(Duration::from_millis(1), 8);
+        expired.insert("k".into(), vec![chunk("a")]);
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(expired.get("k").is_none());
+        assert_eq!(expired.entries.lock().unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_context_cache_evicts_oldest_when_full() {
+        let cache = ContextCache::new(Duration::from_secs(60), 2);
+        cache.insert("a".into(), vec![]);
+        std::thread::sleep(Duration::from_millis(2));
+        cache.insert("b".into(), vec![]);
+        cache.insert("c".into(), vec![]);
+        assert_eq!(cache.entries.lock().unwrap().len(), 2);
+        assert!(cache.get("a").is_none());
+        assert!(cache.get("b").is_some());
+        assert!(cache.get("c").is_some());
+    }
+
+    #[test]
+    fn test_context_cache_disabled_with_zero_ttl() {
+        let cache = ContextCache::new(Duration::ZERO, 8);
+        cache.insert("k".into(), vec![chunk("a")]);
+        assert!(cache.get("k").is_none());
     }
 }
@@ -10,6 +10,15 @@ use tokio::sync::RwLock;
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, info, warn};
 
+/// Fields cluster nodes can be sorted by
+pub const NODE_SORT_FIELDS: &[&str] = &[
+    "node_id",
+    "hostname",
+    "cpu_usage",
+    "memory_usage",
+    "active_tasks",
+];
+
 /// Information about a cluster node
 #[derive(Debug, Clone)]
 pub struct ClusterNode {
@@ -40,7 +49,8 @@ impl ClusterManager {
             nodes: HashMap::new(),
             local_node_id: local_node_id.to_string(),
             heartbeat_timeout_secs: 30,
-            enabled: std::env::var("AIOS_CLUSTER_ENABLED").unwrap_or_default() == "true",
+            enabled: std::env::var("AIOS_CLUSTER_ENABLED").unwrap_or_default() == "true"
+                || crate::feature_flags::FeatureFlags::global().enabled("cluster"),
         }
     }
 
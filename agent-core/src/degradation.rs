@@ -0,0 +1,249 @@
+//! Budget Degradation — follows the API gateway's degradation ladder
+//!
+//! The gateway degrades requests as the API budget fills up: cheaper models
+//! from 50% spent, capped max_tokens from 80%, the local model only from
+//! 95%. This monitor follows the step changes the gateway publishes on the
+//! event bus (`gateway.budget_degradation`), records every step change as a
+//! decision, and keeps the current step in the orchestrator state, where
+//! the management console shows it and the proactive generator stops
+//! creating goals from the "reduced_tokens" step on. The gateway's budget
+//! is read on every (re)subscription, and polled while the event bus cannot
+//! be reached.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::sync::RwLock;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use crate::OrchestratorState;
+
+/// How often the gateway's budget is polled while the event bus is down
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Step changes kept for the console
+const MAX_CHANGES: usize = 20;
+
+/// Ladder steps, least restrictive first ("" is no degradation)
+const STEPS: [&str; 4] = ["", "cheaper_providers", "reduced_tokens", "local_only"];
+
+/// A move between two ladder steps
+#[derive(Debug, Clone, Serialize)]
+pub struct StepChange {
+    pub from: String,
+    pub to: String,
+    pub spent_percent: f64,
+    pub reason: String,
+    pub timestamp: i64,
+}
+
+/// Degradation step currently in force at the gateway
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BudgetDegradation {
+    pub step: String,
+    pub spent_percent: f64,
+    /// Most recent step changes, newest last
+    pub changes: VecDeque<StepChange>,
+}
+
+impl BudgetDegradation {
+    /// Record the gateway's latest step. Returns the change, if the step moved.
+    pub fn update(&mut self, step: &str, spent_percent: f64, now: i64) -> Option<StepChange> {
+        self.spent_percent = spent_percent;
+        if step == self.step {
+            return None;
+        }
+        let change = StepChange {
+            from: self.step.clone(),
+            to: step.to_string(),
+            spent_percent,
+            reason: describe(&self.step, step, spent_percent),
+            timestamp: now,
+        };
+        self.step = step.to_string();
+        self.changes.push_back(change.clone());
+        while self.changes.len() > MAX_CHANGES {
+            self.changes.pop_front();
+        }
+        Some(change)
+    }
+
+    /// Why proactive goals may not be created at the current step, if so
+    pub fn proactive_suppression(&self) -> Option<String> {
+        matches!(self.step.as_str(), "reduced_tokens" | "local_only").then(|| {
+            format!(
+                "API budget degradation step '{}' ({:.0}% spent)",
+                self.step, self.spent_percent
+            )
+        })
+    }
+}
+
+fn step_rank(step: &str) -> usize {
+    STEPS.iter().position(|s| *s == step).unwrap_or(0)
+}
+
+/// What a step change means for requests
+fn describe(from: &str, to: &str, spent_percent: f64) -> String {
+    let effect = match to {
+        "cheaper_providers" => "requests use cheaper models",
+        "reduced_tokens" => "cheaper models, capped max_tokens, proactive goals paused",
+        "local_only" => "only the local model serves requests",
+        _ => "full service restored",
+    };
+    let direction = if step_rank(to) > step_rank(from) {
+        "degraded"
+    } else {
+        "relaxed"
+    };
+    format!("API budget {spent_percent:.0}% spent: {direction} to {effect}")
+}
+
+/// Event bus topic of the gateway's step changes
+const DEGRADATION_TOPIC: &str = "gateway.budget_degradation";
+
+/// Follow the gateway's degradation steps until cancelled
+pub async fn run_degradation_monitor(
+    state: Arc<RwLock<OrchestratorState>>,
+    cancel: CancellationToken,
+) {
+    info!("Budget degradation monitor started");
+    let clients = state.read().await.clients.clone();
+    loop {
+        check(&state).await;
+        let subscription = match clients.memory().await {
+            Ok(mut memory) => memory
+                .subscribe(crate::proto::memory::SubscribeRequest {
+                    subscriber: String::new(),
+                    topics: vec![DEGRADATION_TOPIC.to_string()],
+                    from_latest: true,
+                })
+                .await
+                .map(|r| r.into_inner())
+                .map_err(anyhow::Error::from),
+            Err(e) => Err(e),
+        };
+        let mut deliveries = match subscription {
+            Ok(deliveries) => deliveries,
+            Err(e) => {
+                debug!("Budget degradation monitor polling; event bus unavailable: {e}");
+                tokio::select! {
+                    _ = cancel.cancelled() => break,
+                    _ = tokio::time::sleep(CHECK_INTERVAL) => continue,
+                }
+            }
+        };
+        loop {
+            tokio::select! {
+                _ = cancel.cancelled() => {
+                    info!("Budget degradation monitor stopped");
+                    return;
+                }
+                delivery = deliveries.message() => match delivery {
+                    Ok(Some(delivery)) => {
+                        let payload = delivery
+                            .message
+                            .and_then(|m| serde_json::from_slice::<serde_json::Value>(&m.payload_json).ok())
+                            .unwrap_or_default();
+                        apply(
+                            &state,
+                            payload["degradation"].as_str().unwrap_or_default(),
+                            payload["spent_percent"].as_f64().unwrap_or_default(),
+                        )
+                        .await;
+                    }
+                    Ok(None) => break,
+                    Err(e) => {
+                        debug!("Budget degradation monitor lost the event bus: {e}");
+                        break;
+                    }
+                },
+            }
+        }
+    }
+    info!("Budget degradation monitor stopped");
+}
+
+/// Read the gateway's budget
+async fn check(state: &Arc<RwLock<OrchestratorState>>) {
+    let clients = state.read().await.clients.clone();
+    let budget = match clients.api_gateway().await {
+        Ok(mut client) => client.get_budget(crate::proto::common::Empty {}).await,
+        Err(e) => {
+            debug!("Budget degradation check skipped: {e}");
+            return;
+        }
+    };
+    let budget = match budget {
+        Ok(b) => b.into_inner(),
+        Err(e) => {
+            debug!("Budget degradation check failed: {e}");
+            return;
+        }
+    };
+
+    apply(state, &budget.degradation, budget.spent_percent).await;
+}
+
+/// Record the gateway's current step
+async fn apply(state: &Arc<RwLock<OrchestratorState>>, step: &str, spent_percent: f64) {
+    let mut s = crate::liveness::write_state(state, "degradation.check").await;
+    let now = chrono::Utc::now().timestamp();
+    let Some(change) = s.budget_degradation.update(step, spent_percent, now) else {
+        return;
+    };
+    warn!(
+        "Budget degradation '{}' -> '{}': {}",
+        change.from, change.to, change.reason
+    );
+    let options: Vec<String> = STEPS.iter().map(|s| step_name(s).to_string()).collect();
+    s.decision_logger.log_decision(
+        "budget_degradation",
+        &options,
+        step_name(&change.to),
+        &change.reason,
+        "operational",
+        "budget-monitor",
+    );
+}
+
+/// Display name of a ladder step
+fn step_name(step: &str) -> &str {
+    if step.is_empty() {
+        "none"
+    } else {
+        step
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_records_step_changes() {
+        let mut degradation = BudgetDegradation::default();
+        assert!(degradation.update("", 20.0, 1).is_none());
+        assert!(degradation.proactive_suppression().is_none());
+
+        let change = degradation.update("cheaper_providers", 55.0, 2).unwrap();
+        assert_eq!(change.from, "");
+        assert!(change.reason.contains("degraded"));
+        assert!(degradation.proactive_suppression().is_none());
+
+        // Unchanged steps only refresh the spend
+        assert!(degradation.update("cheaper_providers", 60.0, 3).is_none());
+        assert_eq!(degradation.spent_percent, 60.0);
+
+        degradation.update("reduced_tokens", 85.0, 4).unwrap();
+        assert!(degradation.proactive_suppression().is_some());
+
+        // A new billing month relaxes the ladder
+        let change = degradation.update("", 0.0, 5).unwrap();
+        assert!(change.reason.contains("relaxed"));
+        assert_eq!(degradation.changes.len(), 3);
+    }
+}
@@ -3,7 +3,12 @@
 //! Publishers emit events (e.g., from proactive.rs, health.rs, plugins).
 //! Consumers subscribe with patterns and goal templates.
 //! When events match subscriptions, goals are created automatically.
+//!
+//! This bus lives inside the orchestrator. Every event it carries is also
+//! published on the memory service's shared event bus, as
+//! `orchestrator.<event_type>`, for the other services to subscribe to.
 
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::{mpsc, RwLock};
@@ -20,8 +25,9 @@ pub struct SystemEvent {
     pub severity: EventSeverity,
 }
 
-/// Event severity levels
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// Event severity levels, in increasing order
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum EventSeverity {
     Info,
     Warning,
@@ -129,6 +135,10 @@ impl EventBus {
             }
         };
 
+        let (notifier, clients) = {
+            let s = state.read().await;
+            (s.notifier.clone(), s.clients.clone())
+        };
         info!("Event bus started");
 
         loop {
@@ -162,6 +172,7 @@ impl EventBus {
                                             if let Ok(tasks) = state_w.task_planner.decompose_goal(&goal_id, &goal_desc).await {
                                                 state_w.goal_engine.add_tasks(&goal_id, tasks);
                                             }
+                                            state_w.autonomy_waker.wake();
                                         }
                                         Err(e) => {
                                             warn!("Failed to create event-triggered goal: {e}");
@@ -170,6 +181,15 @@ impl EventBus {
                                 }
                             }
 
+                            notifier.notify(
+                                &event.event_type,
+                                event.severity.clone(),
+                                &format!("{} from {}", event.event_type, event.source),
+                                &event.data.to_string(),
+                                "",
+                            );
+                            tokio::spawn(share(clients.clone(), event.clone()));
+
                             // Store in recent events
                             drop(bus_r);
                             let mut bus_w = bus.write().await;
@@ -186,6 +206,37 @@ impl EventBus {
     }
 }
 
+/// Publish an event on the shared event bus
+async fn share(clients: Arc<crate::clients::ServiceClients>, event: SystemEvent) {
+    let message = crate::proto::memory::BusMessage {
+        topic: format!("orchestrator.{}", event.event_type),
+        source: event.source.clone(),
+        payload_json: serde_json::json!({
+            "id": event.id,
+            "severity": event.severity,
+            "data": event.data,
+        })
+        .to_string()
+        .into_bytes(),
+        timestamp: event.timestamp,
+        event: None,
+    };
+    let result = match clients.memory().await {
+        Ok(mut memory) => memory
+            .publish(message)
+            .await
+            .map(|_| ())
+            .map_err(Into::into),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        debug!(
+            "Failed to share event {} on the event bus: {e}",
+            event.event_type
+        );
+    }
+}
+
 /// Helper to publish an event
 pub async fn publish_event(
     sender: &mpsc::Sender<SystemEvent>,
@@ -0,0 +1,199 @@
+//! Runtime feature flags
+//!
+//! Feature flags switch the orchestrator's risky subsystems on or off without
+//! recompiling; GetBuildInfo reports them. They are read once at startup
+//! from features.toml; `AIOS_FEATURE_<NAME>` (`true`/`false`) overrides a
+//! single flag, e.g. AIOS_FEATURE_CLUSTER=true:
+//!
+//! ```toml
+//! [flags]
+//! cluster = true
+//! approvals = false
+//! ```
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+use tracing::{info, warn};
+
+use crate::proto::common::FeatureFlag;
+
+/// Default location of the feature flag configuration
+pub const FEATURES_CONFIG_PATH: &str = "/etc/aios/features.toml";
+
+/// Known flags: name, built-in default, description
+pub const KNOWN_FLAGS: &[(&str, bool, &str)] = &[
+    (
+        "approvals",
+        true,
+        "Approval chains from approvals.toml and escalation of unanswered plans",
+    ),
+    (
+        "cluster",
+        false,
+        "Multi-node clustering: node registration, heartbeats and the cluster monitor",
+    ),
+];
+
+const ENV_PREFIX: &str = "AIOS_FEATURE_";
+
+/// features.toml layout
+#[derive(Debug, Default, Deserialize)]
+struct FeaturesFile {
+    #[serde(default)]
+    flags: BTreeMap<String, bool>,
+}
+
+/// A flag's value and where it came from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Flag {
+    pub enabled: bool,
+    /// "default", "config" or "env"
+    pub source: &'static str,
+}
+
+/// Feature flags in effect for this process
+#[derive(Debug, Clone)]
+pub struct FeatureFlags {
+    flags: BTreeMap<String, Flag>,
+}
+
+impl FeatureFlags {
+    /// Load flags from `path` and the environment; a missing or invalid
+    /// file leaves the built-in defaults in place
+    pub fn load(path: &str) -> Self {
+        let mut flags = Self::defaults();
+        if let Ok(contents) = std::fs::read_to_string(path) {
+            match toml::from_str::<FeaturesFile>(&contents) {
+                Ok(file) => flags.apply_file(file),
+                Err(e) => warn!("Invalid feature flags {path}: {e}; using defaults"),
+            }
+        }
+        flags.apply_env(std::env::vars());
+
+        let enabled: Vec<&str> = flags
+            .flags
+            .iter()
+            .filter(|(_, flag)| flag.enabled)
+            .map(|(name, _)| name.as_str())
+            .collect();
+        info!("Feature flags enabled: [{}]", enabled.join(", "));
+        flags
+    }
+
+    /// Flags loaded from FEATURES_CONFIG_PATH on first use
+    pub fn global() -> &'static FeatureFlags {
+        static FLAGS: OnceLock<FeatureFlags> = OnceLock::new();
+        FLAGS.get_or_init(|| Self::load(FEATURES_CONFIG_PATH))
+    }
+
+    fn defaults() -> Self {
+        let flags = KNOWN_FLAGS
+            .iter()
+            .map(|(name, enabled, _)| {
+                let flag = Flag {
+                    enabled: *enabled,
+                    source: "default",
+                };
+                (name.to_string(), flag)
+            })
+            .collect();
+        Self { flags }
+    }
+
+    fn apply_file(&mut self, file: FeaturesFile) {
+        for (name, enabled) in file.flags {
+            // Kept anyway: a newer peer's config may name flags this build lacks
+            if !self.flags.contains_key(&name) {
+                warn!("Unknown feature flag '{name}' in features.toml");
+            }
+            let flag = Flag {
+                enabled,
+                source: "config",
+            };
+            self.flags.insert(name, flag);
+        }
+    }
+
+    fn apply_env(&mut self, vars: impl IntoIterator<Item = (String, String)>) {
+        for (key, value) in vars {
+            let Some(name) = key.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+            let enabled = match value.trim().to_ascii_lowercase().as_str() {
+                "true" | "1" | "on" => true,
+                "false" | "0" | "off" => false,
+                _ => {
+                    warn!("Ignoring {key}={value}: expected true or false");
+                    continue;
+                }
+            };
+            let flag = Flag {
+                enabled,
+                source: "env",
+            };
+            self.flags.insert(name.to_ascii_lowercase(), flag);
+        }
+    }
+
+    /// Whether a flag is on; flags nobody set are off
+    pub fn enabled(&self, name: &str) -> bool {
+        self.flags.get(name).is_some_and(|flag| flag.enabled)
+    }
+
+    /// The flags as GetBuildInfo reports them
+    pub fn to_proto(&self) -> Vec<FeatureFlag> {
+        self.flags
+            .iter()
+            .map(|(name, flag)| FeatureFlag {
+                name: name.clone(),
+                enabled: flag.enabled,
+                source: flag.source.to_string(),
+                description: KNOWN_FLAGS
+                    .iter()
+                    .find(|(known, _, _)| known == name)
+                    .map(|(_, _, description)| description.to_string())
+                    .unwrap_or_default(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_env_overrides_config_overrides_defaults() {
+        let mut flags = FeatureFlags::defaults();
+        assert!(!flags.enabled("cluster"));
+
+        let file: FeaturesFile =
+            toml::from_str("[flags]\ncluster = true\napprovals = false\nexperimental = true\n")
+                .unwrap();
+        flags.apply_file(file);
+        flags.apply_env([
+            ("AIOS_FEATURE_APPROVALS".to_string(), "on".to_string()),
+            ("AIOS_FEATURE_CLUSTER".to_string(), "maybe".to_string()),
+            ("AIOS_CLUSTER_ENABLED".to_string(), "false".to_string()),
+        ]);
+
+        // Unparseable values leave the flag alone
+        let cluster = flags.flags.get("cluster").unwrap();
+        assert!(cluster.enabled);
+        assert_eq!(cluster.source, "config");
+        let approvals = flags.flags.get("approvals").unwrap();
+        assert!(approvals.enabled);
+        assert_eq!(approvals.source, "env");
+        assert!(flags.enabled("experimental"));
+        assert!(!flags.enabled("missing"));
+    }
+
+    #[test]
+    fn test_reported_flags_carry_descriptions() {
+        let reported = FeatureFlags::defaults().to_proto();
+        let names: Vec<&str> = reported.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["approvals", "cluster"]);
+        assert!(reported.iter().all(|f| !f.description.is_empty()));
+    }
+}
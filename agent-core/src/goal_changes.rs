@@ -0,0 +1,155 @@
+//! Goal change feed — goal engine mutations pushed to the console
+//!
+//! The goal engine publishes a typed change whenever a goal is created or
+//! changes status, a task is planned or changes status, a message is added
+//! to a goal's thread, or a goal's labels change. The management console's
+//! `/ws` forwards them as they happen, so the dashboard updates in real
+//! time instead of on its periodic full status update.
+//!
+//! As with task events, publishing never blocks: subscribers that fall
+//! behind lose the oldest changes and resync from a full update.
+
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use crate::goal_engine::{GoalMessage, StateTransition};
+
+/// Changes buffered per subscriber before the oldest are dropped
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// A change to a goal, serialized with its kind as `type`
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GoalChange {
+    GoalCreated {
+        goal_id: String,
+        description: String,
+        status: String,
+        priority: i32,
+        source: String,
+        parent_goal_id: String,
+        labels: Vec<String>,
+        created_at: i64,
+    },
+    GoalStatusChanged(StateTransition),
+    /// Includes tasks being planned, as a change from no status
+    TaskStatusChanged {
+        description: String,
+        #[serde(flatten)]
+        transition: StateTransition,
+    },
+    MessageAdded {
+        goal_id: String,
+        #[serde(flatten)]
+        message: GoalMessage,
+    },
+    LabelsChanged {
+        goal_id: String,
+        labels: Vec<String>,
+    },
+}
+
+impl GoalChange {
+    pub fn goal_id(&self) -> &str {
+        match self {
+            Self::GoalCreated { goal_id, .. }
+            | Self::MessageAdded { goal_id, .. }
+            | Self::LabelsChanged { goal_id, .. } => goal_id,
+            Self::GoalStatusChanged(transition) | Self::TaskStatusChanged { transition, .. } => {
+                &transition.goal_id
+            }
+        }
+    }
+}
+
+/// Publisher of goal changes; clones share subscribers
+#[derive(Clone)]
+pub struct GoalChanges {
+    sender: broadcast::Sender<GoalChange>,
+}
+
+impl Default for GoalChanges {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GoalChanges {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self { sender }
+    }
+
+    /// Receive every change published from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<GoalChange> {
+        self.sender.subscribe()
+    }
+
+    /// Having no subscribers is not an error
+    pub fn publish(&self, change: GoalChange) {
+        let _ = self.sender.send(change);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::goal_engine::GoalEngine;
+
+    #[tokio::test]
+    async fn test_engine_publishes_typed_changes() {
+        let mut engine = GoalEngine::new();
+        let mut changes = engine.changes().subscribe();
+
+        let goal_id = engine
+            .submit_goal("Rotate logs".into(), 2, "user".into())
+            .await
+            .unwrap();
+        let mut task = crate::proto::common::Task {
+            id: "t1".into(),
+            goal_id: goal_id.clone(),
+            description: "Compress old logs".into(),
+            status: "pending".into(),
+            ..Default::default()
+        };
+        engine.add_tasks(&goal_id, vec![task.clone()]);
+        engine.update_task_status(&goal_id, "t1", "in_progress", "picked up", "worker");
+        engine.add_message(&goal_id, "ai", "Compressing");
+        engine
+            .update_labels(&goal_id, &["ops".into()], &[])
+            .unwrap();
+        engine.update_status(&goal_id, "completed", "all tasks done", "autonomy");
+
+        let mut received = Vec::new();
+        while let Ok(change) = changes.try_recv() {
+            assert_eq!(change.goal_id(), goal_id);
+            received.push(serde_json::to_value(change).unwrap());
+        }
+        let types: Vec<&str> = received
+            .iter()
+            .map(|c| c["type"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            types,
+            [
+                "goal_created",
+                "task_status_changed",
+                "task_status_changed",
+                "message_added",
+                "labels_changed",
+                "goal_status_changed",
+            ]
+        );
+        assert_eq!(received[0]["description"], "Rotate logs");
+        assert_eq!(received[1]["from_status"], "");
+        assert_eq!(received[2]["description"], "Compress old logs");
+        assert_eq!(received[2]["to_status"], "in_progress");
+        assert_eq!(received[3]["content"], "Compressing");
+        assert_eq!(received[4]["labels"][0], "ops");
+        assert_eq!(received[5]["to_status"], "completed");
+
+        // Replicas share nothing with the engine's subscribers
+        task.id = "t2".into();
+        engine.read_replica().add_tasks(&goal_id, vec![task]);
+        assert!(changes.try_recv().is_err());
+    }
+}
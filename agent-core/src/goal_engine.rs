@@ -5,13 +5,19 @@
 //! Storage: HashMap in-memory cache + optional SQLite persistence.
 //! When a db_path is provided, all mutations are written to SQLite so
 //! goals, tasks, and messages survive service restarts.
+//!
+//! Every status change of a goal or task is also appended to a
+//! `state_transitions` table, which backs the goal timeline view. Every
+//! mutation is published as a typed change to the console's live feed.
 
 use anyhow::Result;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Mutex;
 use uuid::Uuid;
 
+use crate::goal_changes::{GoalChange, GoalChanges};
 use crate::proto::common::{Goal, Task};
+use crate::task_events::TaskEvents;
 
 /// A message in a goal's conversation thread
 #[derive(Clone, Debug, serde::Serialize)]
@@ -22,11 +28,154 @@ pub struct GoalMessage {
     pub timestamp: i64,
 }
 
+/// A single status change of a goal or one of its tasks
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct StateTransition {
+    pub goal_id: String,
+    pub entity_type: String, // "goal" | "task"
+    pub entity_id: String,
+    pub from_status: String, // empty when the entity was just created
+    pub to_status: String,
+    pub cause: String,
+    pub actor: String,
+    pub timestamp: i64,
+}
+
+/// Search criteria for listing goals. Empty fields match everything.
+#[derive(Debug, Clone, Default)]
+pub struct GoalQuery {
+    /// Exact status to match
+    pub status: String,
+    /// Labels a goal must all carry
+    pub labels: Vec<String>,
+    /// Free text; every word must prefix-match a word of the description
+    pub text: String,
+    /// One of [`GOAL_SORT_FIELDS`], `-` prefixed for descending; empty
+    /// sorts by priority, then newest first
+    pub sort: String,
+}
+
+/// Fields goals can be sorted by
+pub const GOAL_SORT_FIELDS: &[&str] = &["priority", "created_at", "updated_at", "status"];
+
+/// Normalize labels: trimmed, lowercased, deduplicated, and sorted
+pub fn normalize_labels(labels: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = labels
+        .iter()
+        .map(|l| l.trim().to_lowercase())
+        .filter(|l| !l.is_empty())
+        .collect();
+    normalized.sort();
+    normalized.dedup();
+    normalized
+}
+
+/// Split text into lowercase alphanumeric words for the search index
+fn index_words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect()
+}
+
+/// Limits applied when task results spawn follow-up subgoals
+#[derive(Debug, Clone)]
+pub struct SubgoalLimits {
+    /// Maximum nesting depth below a root goal (root goals are depth 0)
+    pub max_depth: usize,
+    /// Maximum number of subgoals a single task result may spawn
+    pub max_fanout: usize,
+}
+
+impl Default for SubgoalLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: 3,
+            max_fanout: 5,
+        }
+    }
+}
+
+impl SubgoalLimits {
+    /// `AIOS_SUBGOAL_MAX_DEPTH` and `AIOS_SUBGOAL_MAX_FANOUT`, or the defaults
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let var = |name: &str, default: usize| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        };
+        Self {
+            max_depth: var("AIOS_SUBGOAL_MAX_DEPTH", default.max_depth),
+            max_fanout: var("AIOS_SUBGOAL_MAX_FANOUT", default.max_fanout),
+        }
+    }
+}
+
+/// A follow-up subgoal declared by a task result via `spawn_goals`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubgoalSpec {
+    pub description: String,
+    /// Priority for the subgoal; inherits the parent's priority when absent
+    pub priority: Option<i32>,
+}
+
+/// Parse the `spawn_goals` array from a task result JSON object.
+///
+/// Entries may be plain strings or `{"description": ..., "priority": ...}`
+/// objects. Entries without a description are ignored.
+pub fn parse_spawn_goals(result: &serde_json::Value) -> Vec<SubgoalSpec> {
+    let Some(entries) = result.get("spawn_goals").and_then(|v| v.as_array()) else {
+        return Vec::new();
+    };
+
+    entries
+        .iter()
+        .filter_map(|entry| {
+            let (description, priority) = match entry {
+                serde_json::Value::String(s) => (s.trim().to_string(), None),
+                serde_json::Value::Object(obj) => (
+                    obj.get("description")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("")
+                        .trim()
+                        .to_string(),
+                    obj.get("priority")
+                        .and_then(|v| v.as_i64())
+                        .map(|p| p as i32),
+                ),
+                _ => return None,
+            };
+            if description.is_empty() {
+                None
+            } else {
+                Some(SubgoalSpec {
+                    description,
+                    priority,
+                })
+            }
+        })
+        .collect()
+}
+
 /// Manages goals and their lifecycle
 pub struct GoalEngine {
     goals: HashMap<String, Goal>,
     goal_tasks: HashMap<String, Vec<Task>>,
     goal_messages: HashMap<String, Vec<GoalMessage>>,
+    /// Append-only log of status changes, keyed by goal ID
+    transitions: HashMap<String, Vec<StateTransition>>,
+    /// Label → goal IDs carrying it
+    label_index: HashMap<String, HashSet<String>>,
+    /// Description word → goal IDs (ordered for prefix lookups)
+    word_index: BTreeMap<String, HashSet<String>>,
+    /// Depth and fan-out limits for task-spawned subgoals
+    subgoal_limits: SubgoalLimits,
+    /// Recorded transitions are pushed to `StreamTaskEvents` subscribers
+    task_events: TaskEvents,
+    /// Mutations are pushed to the console's WebSocket clients
+    changes: GoalChanges,
     /// Optional SQLite connection for persistence (Mutex because Connection is !Send)
     db: Option<Mutex<rusqlite::Connection>>,
 }
@@ -38,6 +187,30 @@ impl GoalEngine {
             goals: HashMap::new(),
             goal_tasks: HashMap::new(),
             goal_messages: HashMap::new(),
+            transitions: HashMap::new(),
+            label_index: HashMap::new(),
+            word_index: BTreeMap::new(),
+            subgoal_limits: SubgoalLimits::from_env(),
+            task_events: TaskEvents::new(),
+            changes: GoalChanges::new(),
+            db: None,
+        }
+    }
+
+    /// An in-memory copy of every goal, task, message and transition,
+    /// without persistence, for serving queries off the state lock
+    pub fn read_replica(&self) -> Self {
+        Self {
+            goals: self.goals.clone(),
+            goal_tasks: self.goal_tasks.clone(),
+            goal_messages: self.goal_messages.clone(),
+            transitions: self.transitions.clone(),
+            label_index: self.label_index.clone(),
+            word_index: self.word_index.clone(),
+            subgoal_limits: self.subgoal_limits.clone(),
+            // Replicas never record transitions, so have nothing to publish
+            task_events: TaskEvents::new(),
+            changes: GoalChanges::new(),
             db: None,
         }
     }
@@ -65,7 +238,8 @@ impl GoalEngine {
                 created_at INTEGER NOT NULL,
                 updated_at INTEGER NOT NULL,
                 tags TEXT NOT NULL DEFAULT '[]',
-                metadata_json BLOB NOT NULL DEFAULT X''
+                metadata_json BLOB NOT NULL DEFAULT X'',
+                parent_goal_id TEXT NOT NULL DEFAULT ''
             );
             CREATE TABLE IF NOT EXISTS tasks (
                 id TEXT PRIMARY KEY,
@@ -82,6 +256,8 @@ impl GoalEngine {
                 started_at INTEGER NOT NULL DEFAULT 0,
                 completed_at INTEGER NOT NULL DEFAULT 0,
                 error TEXT NOT NULL DEFAULT '',
+                model_class TEXT NOT NULL DEFAULT '',
+                provider_hint TEXT NOT NULL DEFAULT '',
                 FOREIGN KEY(goal_id) REFERENCES goals(id)
             );
             CREATE TABLE IF NOT EXISTS messages (
@@ -92,10 +268,39 @@ impl GoalEngine {
                 timestamp INTEGER NOT NULL,
                 FOREIGN KEY(goal_id) REFERENCES goals(id)
             );
+            CREATE TABLE IF NOT EXISTS state_transitions (
+                seq INTEGER PRIMARY KEY AUTOINCREMENT,
+                goal_id TEXT NOT NULL,
+                entity_type TEXT NOT NULL,
+                entity_id TEXT NOT NULL,
+                from_status TEXT NOT NULL,
+                to_status TEXT NOT NULL,
+                cause TEXT NOT NULL DEFAULT '',
+                actor TEXT NOT NULL DEFAULT '',
+                timestamp INTEGER NOT NULL
+            );
             CREATE INDEX IF NOT EXISTS idx_tasks_goal ON tasks(goal_id);
-            CREATE INDEX IF NOT EXISTS idx_messages_goal ON messages(goal_id);",
+            CREATE INDEX IF NOT EXISTS idx_messages_goal ON messages(goal_id);
+            CREATE INDEX IF NOT EXISTS idx_transitions_goal ON state_transitions(goal_id);",
         )?;
 
+        // Databases created before subgoal support lack the parent link column;
+        // the ALTER fails harmlessly when the column already exists.
+        let _ = db.execute(
+            "ALTER TABLE goals ADD COLUMN parent_goal_id TEXT NOT NULL DEFAULT ''",
+            [],
+        );
+        db.execute_batch("CREATE INDEX IF NOT EXISTS idx_goals_parent ON goals(parent_goal_id);")?;
+        // Likewise for the model selection hints on tasks
+        let _ = db.execute(
+            "ALTER TABLE tasks ADD COLUMN model_class TEXT NOT NULL DEFAULT ''",
+            [],
+        );
+        let _ = db.execute(
+            "ALTER TABLE tasks ADD COLUMN provider_hint TEXT NOT NULL DEFAULT ''",
+            [],
+        );
+
         // Load existing data into cache
         let mut goals = HashMap::new();
         let mut goal_tasks: HashMap<String, Vec<Task>> = HashMap::new();
@@ -104,7 +309,8 @@ impl GoalEngine {
         // Load goals
         {
             let mut stmt = db.prepare(
-                "SELECT id, description, priority, source, status, created_at, updated_at, tags, metadata_json FROM goals"
+                "SELECT id, description, priority, source, status, created_at, updated_at, tags, metadata_json, \
+                 parent_goal_id FROM goals",
             )?;
             let rows = stmt.query_map([], |row| {
                 let tags_json: String = row.get(7)?;
@@ -119,6 +325,7 @@ impl GoalEngine {
                     updated_at: row.get(6)?,
                     tags,
                     metadata_json: row.get(8)?,
+                    parent_goal_id: row.get(9)?,
                 })
             })?;
             for row in rows {
@@ -134,7 +341,7 @@ impl GoalEngine {
             let mut stmt = db.prepare(
                 "SELECT id, goal_id, description, assigned_agent, status, intelligence_level, \
                  required_tools, depends_on, input_json, output_json, created_at, started_at, \
-                 completed_at, error FROM tasks ORDER BY created_at ASC",
+                 completed_at, error, model_class, provider_hint FROM tasks ORDER BY created_at ASC",
             )?;
             let rows = stmt.query_map([], |row| {
                 let tools_json: String = row.get(6)?;
@@ -154,6 +361,8 @@ impl GoalEngine {
                     started_at: row.get(11)?,
                     completed_at: row.get(12)?,
                     error: row.get(13)?,
+                    model_class: row.get(14)?,
+                    provider_hint: row.get(15)?,
                 })
             })?;
             for row in rows {
@@ -187,15 +396,54 @@ impl GoalEngine {
             }
         }
 
+        // Load state transitions
+        let mut transitions: HashMap<String, Vec<StateTransition>> = HashMap::new();
+        {
+            let mut stmt = db.prepare(
+                "SELECT goal_id, entity_type, entity_id, from_status, to_status, cause, actor, \
+                 timestamp FROM state_transitions ORDER BY seq ASC",
+            )?;
+            let rows = stmt.query_map([], |row| {
+                Ok(StateTransition {
+                    goal_id: row.get(0)?,
+                    entity_type: row.get(1)?,
+                    entity_id: row.get(2)?,
+                    from_status: row.get(3)?,
+                    to_status: row.get(4)?,
+                    cause: row.get(5)?,
+                    actor: row.get(6)?,
+                    timestamp: row.get(7)?,
+                })
+            })?;
+            for row in rows {
+                let transition = row?;
+                transitions
+                    .entry(transition.goal_id.clone())
+                    .or_default()
+                    .push(transition);
+            }
+        }
+
         let goal_count = goals.len();
         tracing::info!("GoalEngine loaded from {db_path}: {goal_count} goals restored");
 
-        Ok(Self {
+        let mut engine = Self {
             goals,
             goal_tasks,
             goal_messages,
+            transitions,
+            label_index: HashMap::new(),
+            word_index: BTreeMap::new(),
+            subgoal_limits: SubgoalLimits::from_env(),
+            task_events: TaskEvents::new(),
+            changes: GoalChanges::new(),
             db: Some(Mutex::new(db)),
-        })
+        };
+        let loaded: Vec<Goal> = engine.goals.values().cloned().collect();
+        for goal in &loaded {
+            engine.index_goal(goal);
+        }
+        Ok(engine)
     }
 
     /// Submit a new goal
@@ -204,6 +452,147 @@ impl GoalEngine {
         description: String,
         priority: i32,
         source: String,
+    ) -> Result<String> {
+        let cause = "goal submitted".to_string();
+        self.insert_goal(description, priority, source, String::new(), cause)
+    }
+
+    /// Spawn follow-up subgoals declared by a task result.
+    ///
+    /// Each subgoal is created as a pending goal linked to `parent_goal_id`,
+    /// so the autonomy loop decomposes it like any other goal. Fails if the
+    /// new goals would exceed the depth limit; specs beyond the fan-out limit
+    /// are dropped. Returns the IDs of the created subgoals.
+    pub async fn spawn_subgoals(
+        &mut self,
+        parent_goal_id: &str,
+        parent_task_id: &str,
+        specs: Vec<SubgoalSpec>,
+    ) -> Result<Vec<String>> {
+        let parent_priority = self
+            .goals
+            .get(parent_goal_id)
+            .ok_or_else(|| anyhow::anyhow!("Goal not found: {parent_goal_id}"))?
+            .priority;
+
+        let depth = self.goal_depth(parent_goal_id) + 1;
+        if depth > self.subgoal_limits.max_depth {
+            anyhow::bail!(
+                "Subgoal depth limit reached ({} > {}) for goal {parent_goal_id}",
+                depth,
+                self.subgoal_limits.max_depth
+            );
+        }
+
+        let requested = specs.len();
+        if requested > self.subgoal_limits.max_fanout {
+            tracing::warn!(
+                "Task {parent_task_id} requested {requested} subgoals, limiting to {}",
+                self.subgoal_limits.max_fanout
+            );
+        }
+
+        let mut ids = Vec::new();
+        for spec in specs.into_iter().take(self.subgoal_limits.max_fanout) {
+            let id = self.insert_goal(
+                spec.description.clone(),
+                spec.priority.unwrap_or(parent_priority),
+                format!("subgoal:{parent_task_id}"),
+                parent_goal_id.to_string(),
+                format!("spawned by task {parent_task_id} of goal {parent_goal_id}"),
+            )?;
+            self.add_message(
+                parent_goal_id,
+                "system",
+                &format!(
+                    "Task {parent_task_id} spawned subgoal {id}: {}",
+                    spec.description
+                ),
+            );
+            ids.push(id);
+        }
+
+        Ok(ids)
+    }
+
+    /// Depth of a goal in its subgoal tree (root goals are depth 0)
+    pub fn goal_depth(&self, goal_id: &str) -> usize {
+        let mut depth = 0;
+        let mut current = goal_id;
+        while let Some(goal) = self.goals.get(current) {
+            if goal.parent_goal_id.is_empty() || depth > self.goals.len() {
+                break;
+            }
+            depth += 1;
+            current = &goal.parent_goal_id;
+        }
+        depth
+    }
+
+    /// A goal followed by its ancestors, nearest first
+    pub fn ancestry(&self, goal_id: &str) -> Vec<String> {
+        let mut ids = vec![goal_id.to_string()];
+        let mut current = goal_id;
+        while let Some(goal) = self.goals.get(current) {
+            if goal.parent_goal_id.is_empty() || ids.len() > self.goals.len() {
+                break;
+            }
+            ids.push(goal.parent_goal_id.clone());
+            current = &goal.parent_goal_id;
+        }
+        ids
+    }
+
+    /// Whether a goal carries `label`
+    pub fn has_label(&self, goal_id: &str, label: &str) -> bool {
+        self.label_index
+            .get(label)
+            .is_some_and(|ids| ids.contains(goal_id))
+    }
+
+    /// Current status of a goal
+    pub fn goal_status(&self, goal_id: &str) -> Option<&str> {
+        self.goals.get(goal_id).map(|g| g.status.as_str())
+    }
+
+    /// Priority of a goal; 1 is the most urgent
+    pub fn goal_priority(&self, goal_id: &str) -> Option<i32> {
+        self.goals.get(goal_id).map(|g| g.priority)
+    }
+
+    /// Whether an active goal has priority `priority` or a more urgent one
+    pub fn urgent_goal_active(&self, priority: i32) -> bool {
+        self.goals.values().any(|g| {
+            (1..=priority).contains(&g.priority)
+                && g.status != "completed"
+                && g.status != "failed"
+                && g.status != "cancelled"
+        })
+    }
+
+    /// Who submitted a goal
+    pub fn goal_source(&self, goal_id: &str) -> Option<&str> {
+        self.goals.get(goal_id).map(|g| g.source.as_str())
+    }
+
+    /// Whether a goal has subgoals that have not yet reached a terminal state
+    pub fn has_active_subgoals(&self, goal_id: &str) -> bool {
+        self.goals.values().any(|g| {
+            g.parent_goal_id == goal_id
+                && g.status != "completed"
+                && g.status != "failed"
+                && g.status != "cancelled"
+        })
+    }
+
+    /// Create a goal (optionally linked to a parent) and persist it
+    fn insert_goal(
+        &mut self,
+        description: String,
+        priority: i32,
+        source: String,
+        parent_goal_id: String,
+        cause: String,
     ) -> Result<String> {
         let id = Uuid::new_v4().to_string();
         let now = chrono::Utc::now().timestamp();
@@ -218,6 +607,7 @@ impl GoalEngine {
             updated_at: now,
             tags: vec![],
             metadata_json: vec![],
+            parent_goal_id,
         };
 
         // Initialize conversation with a system message
@@ -232,12 +622,12 @@ impl GoalEngine {
         if let Some(ref db_mutex) = self.db {
             let db = db_mutex.lock().unwrap();
             db.execute(
-                "INSERT INTO goals (id, description, priority, source, status, created_at, updated_at, tags, metadata_json) \
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                "INSERT INTO goals (id, description, priority, source, status, created_at, updated_at, tags, metadata_json, parent_goal_id) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
                 rusqlite::params![
                     goal.id, goal.description, goal.priority, goal.source,
                     goal.status, goal.created_at, goal.updated_at,
-                    "[]", goal.metadata_json,
+                    "[]", goal.metadata_json, goal.parent_goal_id,
                 ],
             )?;
             db.execute(
@@ -247,9 +637,12 @@ impl GoalEngine {
         }
 
         // Update in-memory cache
+        self.index_goal(&goal);
         self.goals.insert(id.clone(), goal.clone());
         self.goal_tasks.insert(id.clone(), vec![]);
         self.goal_messages.insert(id.clone(), vec![system_msg]);
+        let actor = goal.source.clone();
+        self.record_transition(&id, "goal", &id, "", "pending", &cause, &actor);
 
         tracing::info!("Goal submitted: {id}");
         Ok(id)
@@ -285,14 +678,48 @@ impl GoalEngine {
         (completed / total) * 100.0
     }
 
-    /// Cancel a goal
-    pub async fn cancel_goal(&mut self, goal_id: &str) -> Result<()> {
-        let goal = self
-            .goals
-            .get_mut(goal_id)
-            .ok_or_else(|| anyhow::anyhow!("Goal not found: {goal_id}"))?;
+    /// Cancel a goal along with any subgoals it spawned. Returns the ids of
+    /// the tasks cancelled, whose running tool executions should be stopped.
+    pub async fn cancel_goal(&mut self, goal_id: &str, actor: &str) -> Result<Vec<String>> {
+        if !self.goals.contains_key(goal_id) {
+            anyhow::bail!("Goal not found: {goal_id}");
+        }
+
+        // Collect the goal and all of its descendants
+        let mut to_cancel = vec![goal_id.to_string()];
+        let mut i = 0;
+        while i < to_cancel.len() {
+            let parent = to_cancel[i].clone();
+            to_cancel.extend(
+                self.goals
+                    .values()
+                    .filter(|g| g.parent_goal_id == parent && !to_cancel.contains(&g.id))
+                    .map(|g| g.id.clone())
+                    .collect::<Vec<_>>(),
+            );
+            i += 1;
+        }
+
+        let mut task_ids = Vec::new();
+        for id in &to_cancel {
+            let cause = if id == goal_id {
+                "goal cancelled".to_string()
+            } else {
+                format!("ancestor goal {goal_id} cancelled")
+            };
+            task_ids.extend(self.cancel_single_goal(id, &cause, actor));
+        }
 
-        goal.status = "cancelled".to_string();
+        Ok(task_ids)
+    }
+
+    /// Cancel one goal and its non-completed tasks, returning the task ids
+    fn cancel_single_goal(&mut self, goal_id: &str, cause: &str, actor: &str) -> Vec<String> {
+        let Some(goal) = self.goals.get_mut(goal_id) else {
+            return Vec::new();
+        };
+
+        let old_status = std::mem::replace(&mut goal.status, "cancelled".to_string());
         goal.updated_at = chrono::Utc::now().timestamp();
 
         // Persist
@@ -305,10 +732,12 @@ impl GoalEngine {
         }
 
         // Cancel all associated tasks
+        let mut cancelled_tasks = Vec::new();
         if let Some(tasks) = self.goal_tasks.get_mut(goal_id) {
             for task in tasks.iter_mut() {
-                if task.status != "completed" {
-                    task.status = "cancelled".to_string();
+                if task.status != "completed" && task.status != "cancelled" {
+                    let old = std::mem::replace(&mut task.status, "cancelled".to_string());
+                    cancelled_tasks.push((task.id.clone(), old));
                     if let Some(ref db_mutex) = self.db {
                         let db = db_mutex.lock().unwrap();
                         let _ = db.execute(
@@ -320,8 +749,23 @@ impl GoalEngine {
             }
         }
 
+        self.record_transition(
+            goal_id,
+            "goal",
+            goal_id,
+            &old_status,
+            "cancelled",
+            cause,
+            actor,
+        );
+        let mut task_ids = Vec::new();
+        for (task_id, old) in cancelled_tasks {
+            self.record_transition(goal_id, "task", &task_id, &old, "cancelled", cause, actor);
+            task_ids.push(task_id);
+        }
+
         tracing::info!("Goal cancelled: {goal_id}");
-        Ok(())
+        task_ids
     }
 
     /// List goals with filtering
@@ -331,21 +775,75 @@ impl GoalEngine {
         limit: i32,
         offset: i32,
     ) -> (Vec<Goal>, i32) {
-        let mut goals: Vec<&Goal> = if status_filter.is_empty() {
-            self.goals.values().collect()
-        } else {
-            self.goals
-                .values()
-                .filter(|g| g.status == status_filter)
-                .collect()
+        let query = GoalQuery {
+            status: status_filter.to_string(),
+            ..Default::default()
+        };
+        self.search_goals(&query, limit, offset).await
+    }
+
+    /// Search goals by status, labels, and description text.
+    ///
+    /// Label and text criteria are resolved through the in-memory indexes,
+    /// so only matching goals are visited.
+    pub async fn search_goals(
+        &self,
+        query: &GoalQuery,
+        limit: i32,
+        offset: i32,
+    ) -> (Vec<Goal>, i32) {
+        // One ID set per label and per search word; a goal must be in all of them
+        let mut constraints: Vec<HashSet<&str>> = Vec::new();
+        for label in normalize_labels(&query.labels) {
+            constraints.push(
+                self.label_index
+                    .get(&label)
+                    .map(|ids| ids.iter().map(String::as_str).collect())
+                    .unwrap_or_default(),
+            );
+        }
+        for word in index_words(&query.text) {
+            constraints.push(
+                self.word_index
+                    .range(word.clone()..)
+                    .take_while(|(indexed, _)| indexed.starts_with(&word))
+                    .flat_map(|(_, ids)| ids.iter().map(String::as_str))
+                    .collect(),
+            );
+        }
+        let candidates = constraints
+            .into_iter()
+            .reduce(|acc, ids| acc.intersection(&ids).copied().collect());
+
+        let mut goals: Vec<&Goal> = match candidates {
+            Some(ids) => ids
+                .into_iter()
+                .filter_map(|id| self.goals.get(id))
+                .collect(),
+            None => self.goals.values().collect(),
         };
+        if !query.status.is_empty() {
+            goals.retain(|g| g.status == query.status);
+        }
 
-        // Sort by priority (lower = higher priority) then by creation time
+        // Sort by priority (lower = higher priority) then by creation time,
+        // unless the query asks for another order
         goals.sort_by(|a, b| {
             a.priority
                 .cmp(&b.priority)
                 .then(b.created_at.cmp(&a.created_at))
         });
+        let _ = crate::pagination::sort_by_field(
+            &mut goals,
+            &query.sort,
+            GOAL_SORT_FIELDS,
+            |a, b, field| match field {
+                "priority" => a.priority.cmp(&b.priority),
+                "created_at" => a.created_at.cmp(&b.created_at),
+                "updated_at" => a.updated_at.cmp(&b.updated_at),
+                _ => a.status.cmp(&b.status),
+            },
+        );
 
         let total = goals.len() as i32;
         let offset = offset as usize;
@@ -369,6 +867,15 @@ impl GoalEngine {
             .count()
     }
 
+    /// Get count of active goals submitted by `source`
+    pub fn active_goal_count_from(&self, source: &str) -> usize {
+        self.goals
+            .values()
+            .filter(|g| g.source == source)
+            .filter(|g| g.status != "completed" && g.status != "failed" && g.status != "cancelled")
+            .count()
+    }
+
     /// Get tasks for a goal
     pub fn get_goal_tasks(&self, goal_id: &str) -> Vec<Task> {
         self.goal_tasks.get(goal_id).cloned().unwrap_or_default()
@@ -388,26 +895,43 @@ impl GoalEngine {
                     let _ = db.execute(
                         "INSERT OR REPLACE INTO tasks (id, goal_id, description, assigned_agent, status, \
                          intelligence_level, required_tools, depends_on, input_json, output_json, \
-                         created_at, started_at, completed_at, error) \
-                         VALUES (?1,?2,?3,?4,?5,?6,?7,?8,?9,?10,?11,?12,?13,?14)",
+                         created_at, started_at, completed_at, error, model_class, provider_hint) \
+                         VALUES (?1,?2,?3,?4,?5,?6,?7,?8,?9,?10,?11,?12,?13,?14,?15,?16)",
                         rusqlite::params![
                             t.id, t.goal_id, t.description, t.assigned_agent, t.status,
                             t.intelligence_level, tools_json, deps_json, t.input_json, t.output_json,
                             t.created_at, t.started_at, t.completed_at, t.error,
+                            t.model_class, t.provider_hint,
                         ],
                     );
                 }
             }
+            let created: Vec<(String, String)> = tasks
+                .iter()
+                .map(|t| (t.id.clone(), t.status.clone()))
+                .collect();
             existing.extend(tasks);
+            for (task_id, status) in created {
+                self.record_transition(
+                    goal_id,
+                    "task",
+                    &task_id,
+                    "",
+                    &status,
+                    "task planned",
+                    "task_planner",
+                );
+            }
         }
     }
 
     /// Mark a task within a goal as completed
-    pub fn complete_task(&mut self, goal_id: &str, task_id: &str) {
+    pub fn complete_task(&mut self, goal_id: &str, task_id: &str, cause: &str, actor: &str) {
+        let mut old_status = None;
         if let Some(tasks) = self.goal_tasks.get_mut(goal_id) {
             for task in tasks.iter_mut() {
                 if task.id == task_id {
-                    task.status = "completed".to_string();
+                    old_status = Some(std::mem::replace(&mut task.status, "completed".to_string()));
                     task.completed_at = chrono::Utc::now().timestamp();
                     if let Some(ref db_mutex) = self.db {
                         let db = db_mutex.lock().unwrap();
@@ -420,21 +944,29 @@ impl GoalEngine {
                 }
             }
         }
+        if let Some(old) = old_status {
+            self.record_transition(goal_id, "task", task_id, &old, "completed", cause, actor);
+        }
     }
 
-    /// Update goal status
-    pub fn update_status(&mut self, goal_id: &str, status: &str) {
-        if let Some(goal) = self.goals.get_mut(goal_id) {
-            goal.status = status.to_string();
-            goal.updated_at = chrono::Utc::now().timestamp();
-            if let Some(ref db_mutex) = self.db {
-                let db = db_mutex.lock().unwrap();
-                let _ = db.execute(
-                    "UPDATE goals SET status = ?1, updated_at = ?2 WHERE id = ?3",
-                    rusqlite::params![status, goal.updated_at, goal_id],
-                );
-            }
+    /// Update goal status, recording the transition when the status changes
+    pub fn update_status(&mut self, goal_id: &str, status: &str, cause: &str, actor: &str) {
+        let Some(goal) = self.goals.get_mut(goal_id) else {
+            return;
+        };
+        if goal.status == status {
+            return;
+        }
+        let old_status = std::mem::replace(&mut goal.status, status.to_string());
+        goal.updated_at = chrono::Utc::now().timestamp();
+        if let Some(ref db_mutex) = self.db {
+            let db = db_mutex.lock().unwrap();
+            let _ = db.execute(
+                "UPDATE goals SET status = ?1, updated_at = ?2 WHERE id = ?3",
+                rusqlite::params![status, goal.updated_at, goal_id],
+            );
         }
+        self.record_transition(goal_id, "goal", goal_id, &old_status, status, cause, actor);
     }
 
     /// Set metadata on a goal (used to store preferred provider, etc.)
@@ -456,6 +988,83 @@ impl GoalEngine {
         self.goals.get(goal_id).map(|g| g.metadata_json.as_slice())
     }
 
+    /// Add and remove labels on a goal, returning its resulting label set
+    pub fn update_labels(
+        &mut self,
+        goal_id: &str,
+        add: &[String],
+        remove: &[String],
+    ) -> Result<Vec<String>> {
+        let goal = self
+            .goals
+            .get_mut(goal_id)
+            .ok_or_else(|| anyhow::anyhow!("Goal not found: {goal_id}"))?;
+
+        let remove = normalize_labels(remove);
+        let mut labels = goal.tags.clone();
+        labels.extend(normalize_labels(add));
+        labels.retain(|l| !remove.contains(l));
+        let labels = normalize_labels(&labels);
+        let previous = std::mem::replace(&mut goal.tags, labels.clone());
+
+        if let Some(ref db_mutex) = self.db {
+            let db = db_mutex.lock().unwrap();
+            let tags_json = serde_json::to_string(&labels).unwrap_or_else(|_| "[]".to_string());
+            let _ = db.execute(
+                "UPDATE goals SET tags = ?1 WHERE id = ?2",
+                rusqlite::params![tags_json, goal_id],
+            );
+        }
+
+        for label in previous.iter().filter(|l| !labels.contains(l)) {
+            if let Some(ids) = self.label_index.get_mut(label) {
+                ids.remove(goal_id);
+                if ids.is_empty() {
+                    self.label_index.remove(label);
+                }
+            }
+        }
+        for label in &labels {
+            self.label_index
+                .entry(label.clone())
+                .or_default()
+                .insert(goal_id.to_string());
+        }
+
+        self.changes.publish(GoalChange::LabelsChanged {
+            goal_id: goal_id.to_string(),
+            labels: labels.clone(),
+        });
+        Ok(labels)
+    }
+
+    /// All labels in use, with the number of goals carrying each
+    pub fn label_counts(&self) -> Vec<(String, usize)> {
+        let mut counts: Vec<(String, usize)> = self
+            .label_index
+            .iter()
+            .map(|(label, ids)| (label.clone(), ids.len()))
+            .collect();
+        counts.sort();
+        counts
+    }
+
+    /// Add a goal's labels and description words to the search indexes
+    fn index_goal(&mut self, goal: &Goal) {
+        for label in &goal.tags {
+            self.label_index
+                .entry(label.clone())
+                .or_default()
+                .insert(goal.id.clone());
+        }
+        for word in index_words(&goal.description) {
+            self.word_index
+                .entry(word)
+                .or_default()
+                .insert(goal.id.clone());
+        }
+    }
+
     /// Add a message to a goal's conversation thread
     pub fn add_message(&mut self, goal_id: &str, sender: &str, content: &str) -> String {
         let msg_id = Uuid::new_v4().to_string();
@@ -475,6 +1084,10 @@ impl GoalEngine {
             );
         }
 
+        self.changes.publish(GoalChange::MessageAdded {
+            goal_id: goal_id.to_string(),
+            message: msg.clone(),
+        });
         self.goal_messages
             .entry(goal_id.to_string())
             .or_default()
@@ -489,18 +1102,20 @@ impl GoalEngine {
 
     /// Get all non-terminal tasks across all goals.
     /// Used on startup to reload tasks into the TaskPlanner.
-    /// Tasks that were `in_progress` at shutdown are reset to `pending`.
+    /// Tasks that were `in_progress` or `preempted` at shutdown are reset
+    /// to `pending`.
     pub fn get_all_resumable_tasks(&mut self) -> Vec<Task> {
         let mut tasks = Vec::new();
+        let mut reset = Vec::new();
         for task_list in self.goal_tasks.values_mut() {
             for task in task_list.iter_mut() {
                 match task.status.as_str() {
                     "pending" | "awaiting_input" => {
                         tasks.push(task.clone());
                     }
-                    "in_progress" => {
+                    "in_progress" | "preempted" | "retrying" => {
                         // Was interrupted by restart — reset to pending
-                        task.status = "pending".to_string();
+                        let old = std::mem::replace(&mut task.status, "pending".to_string());
                         if let Some(ref db_mutex) = self.db {
                             let db = db_mutex.lock().unwrap();
                             let _ = db.execute(
@@ -508,21 +1123,41 @@ impl GoalEngine {
                                 rusqlite::params![task.id],
                             );
                         }
+                        reset.push((task.goal_id.clone(), task.id.clone(), old));
                         tasks.push(task.clone());
                     }
                     _ => {} // completed, failed, cancelled — skip
                 }
             }
         }
+        for (goal_id, task_id, old) in reset {
+            let cause = match old.as_str() {
+                "preempted" => "orchestrator restarted while task was preempted",
+                "retrying" => "orchestrator restarted while task waited to retry",
+                _ => "orchestrator restarted while task was in progress",
+            };
+            self.record_transition(&goal_id, "task", &task_id, &old, "pending", cause, "system");
+        }
         tasks
     }
 
     /// Update task status within a goal (mirrors task_planner updates)
-    pub fn update_task_status(&mut self, goal_id: &str, task_id: &str, status: &str) {
+    pub fn update_task_status(
+        &mut self,
+        goal_id: &str,
+        task_id: &str,
+        status: &str,
+        cause: &str,
+        actor: &str,
+    ) {
+        let mut old_status = None;
         if let Some(tasks) = self.goal_tasks.get_mut(goal_id) {
             for task in tasks.iter_mut() {
                 if task.id == task_id {
-                    task.status = status.to_string();
+                    if task.status == status {
+                        break;
+                    }
+                    old_status = Some(std::mem::replace(&mut task.status, status.to_string()));
                     if let Some(ref db_mutex) = self.db {
                         let db = db_mutex.lock().unwrap();
                         let _ = db.execute(
@@ -534,6 +1169,106 @@ impl GoalEngine {
                 }
             }
         }
+        if let Some(old) = old_status {
+            self.record_transition(goal_id, "task", task_id, &old, status, cause, actor);
+        }
+    }
+
+    /// Publisher of this engine's task lifecycle events
+    pub fn task_events(&self) -> &TaskEvents {
+        &self.task_events
+    }
+
+    /// Publisher of this engine's goal changes
+    pub fn changes(&self) -> &GoalChanges {
+        &self.changes
+    }
+
+    /// Get the recorded status transitions for a goal and its tasks, oldest first
+    pub fn get_timeline(&self, goal_id: &str) -> Vec<StateTransition> {
+        self.transitions.get(goal_id).cloned().unwrap_or_default()
+    }
+
+    /// Append a status transition to the log (never updated or deleted)
+    #[allow(clippy::too_many_arguments)]
+    fn record_transition(
+        &mut self,
+        goal_id: &str,
+        entity_type: &str,
+        entity_id: &str,
+        from_status: &str,
+        to_status: &str,
+        cause: &str,
+        actor: &str,
+    ) {
+        let transition = StateTransition {
+            goal_id: goal_id.to_string(),
+            entity_type: entity_type.to_string(),
+            entity_id: entity_id.to_string(),
+            from_status: from_status.to_string(),
+            to_status: to_status.to_string(),
+            cause: cause.to_string(),
+            actor: actor.to_string(),
+            timestamp: chrono::Utc::now().timestamp(),
+        };
+
+        if let Some(ref db_mutex) = self.db {
+            let db = db_mutex.lock().unwrap();
+            let _ = db.execute(
+                "INSERT INTO state_transitions (goal_id, entity_type, entity_id, from_status, \
+                 to_status, cause, actor, timestamp) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                rusqlite::params![
+                    transition.goal_id,
+                    transition.entity_type,
+                    transition.entity_id,
+                    transition.from_status,
+                    transition.to_status,
+                    transition.cause,
+                    transition.actor,
+                    transition.timestamp,
+                ],
+            );
+        }
+
+        self.task_events.transition(&transition);
+        self.publish_change(&transition);
+        self.transitions
+            .entry(goal_id.to_string())
+            .or_default()
+            .push(transition);
+    }
+
+    /// Publish a recorded status change to the console: a goal that was
+    /// just created as `goal_created`, any other change by entity type
+    fn publish_change(&self, transition: &StateTransition) {
+        let change = match transition.entity_type.as_str() {
+            "goal" if transition.from_status.is_empty() => {
+                let Some(goal) = self.goals.get(&transition.goal_id) else {
+                    return;
+                };
+                GoalChange::GoalCreated {
+                    goal_id: goal.id.clone(),
+                    description: goal.description.clone(),
+                    status: goal.status.clone(),
+                    priority: goal.priority,
+                    source: goal.source.clone(),
+                    parent_goal_id: goal.parent_goal_id.clone(),
+                    labels: goal.tags.clone(),
+                    created_at: goal.created_at,
+                }
+            }
+            "goal" => GoalChange::GoalStatusChanged(transition.clone()),
+            _ => GoalChange::TaskStatusChanged {
+                description: self
+                    .goal_tasks
+                    .get(&transition.goal_id)
+                    .and_then(|tasks| tasks.iter().find(|t| t.id == transition.entity_id))
+                    .map(|t| t.description.clone())
+                    .unwrap_or_default(),
+                transition: transition.clone(),
+            },
+        };
+        self.changes.publish(change);
     }
 }
 
@@ -561,7 +1296,7 @@ mod tests {
             .await
             .unwrap();
 
-        engine.cancel_goal(&id).await.unwrap();
+        engine.cancel_goal(&id, "test").await.unwrap();
         assert_eq!(engine.active_goal_count(), 0);
     }
 
@@ -600,6 +1335,33 @@ mod tests {
         assert!(tasks.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_read_replica_answers_queries() {
+        let mut engine = GoalEngine::new();
+        let id = engine
+            .submit_goal("Rotate nginx logs".into(), 1, "test".into())
+            .await
+            .unwrap();
+        engine
+            .update_labels(&id, &["ops".to_string()], &[])
+            .unwrap();
+        engine.add_message(&id, "user", "only on weekdays");
+
+        let replica = engine.read_replica();
+        let query = GoalQuery {
+            labels: vec!["ops".into()],
+            text: "ngin".into(),
+            ..Default::default()
+        };
+        assert_eq!(replica.search_goals(&query, 10, 0).await.1, 1);
+        assert_eq!(replica.get_messages(&id).len(), 2);
+        assert_eq!(replica.get_timeline(&id).len(), 1);
+
+        // Later changes do not reach the replica
+        engine.update_status(&id, "in_progress", "started", "test");
+        assert_eq!(replica.list_goals("in_progress", 10, 0).await.1, 0);
+    }
+
     #[tokio::test]
     async fn test_get_goal_not_found() {
         let engine = GoalEngine::new();
@@ -642,6 +1404,8 @@ mod tests {
             started_at: 0,
             completed_at: 0,
             error: String::new(),
+            model_class: String::new(),
+            provider_hint: String::new(),
         };
         let task2 = Task {
             id: "t2".into(),
@@ -658,6 +1422,8 @@ mod tests {
             started_at: 0,
             completed_at: 0,
             error: String::new(),
+            model_class: String::new(),
+            provider_hint: String::new(),
         };
 
         engine.add_tasks(&id, vec![task1, task2]);
@@ -689,6 +1455,8 @@ mod tests {
                 started_at: 0,
                 completed_at: 0,
                 error: String::new(),
+                model_class: String::new(),
+                provider_hint: String::new(),
             })
             .collect();
 
@@ -720,6 +1488,8 @@ mod tests {
             started_at: 0,
             completed_at: 0,
             error: String::new(),
+            model_class: String::new(),
+            provider_hint: String::new(),
         };
         let task_pending = Task {
             id: "t2".into(),
@@ -736,10 +1506,13 @@ mod tests {
             started_at: 0,
             completed_at: 0,
             error: String::new(),
+            model_class: String::new(),
+            provider_hint: String::new(),
         };
 
         engine.add_tasks(&id, vec![task_completed, task_pending]);
-        engine.cancel_goal(&id).await.unwrap();
+        let cancelled = engine.cancel_goal(&id, "test").await.unwrap();
+        assert_eq!(cancelled, vec!["t2"]);
 
         let (goal, tasks) = engine.get_goal_with_tasks(&id).await.unwrap();
         assert_eq!(goal.status, "cancelled");
@@ -751,7 +1524,7 @@ mod tests {
     #[tokio::test]
     async fn test_cancel_nonexistent_goal() {
         let mut engine = GoalEngine::new();
-        let result = engine.cancel_goal("nonexistent").await;
+        let result = engine.cancel_goal("nonexistent", "test").await;
         assert!(result.is_err());
     }
 
@@ -767,7 +1540,7 @@ mod tests {
             .await
             .unwrap();
 
-        engine.update_status(&id1, "completed");
+        engine.update_status(&id1, "completed", "test", "test");
 
         let (pending, total_pending) = engine.list_goals("pending", 50, 0).await;
         assert_eq!(total_pending, 1);
@@ -833,6 +1606,7 @@ mod tests {
                     updated_at: 0,
                     tags: vec![],
                     metadata_json: vec![],
+                    parent_goal_id: String::new(),
                 },
             );
         }
@@ -856,10 +1630,11 @@ mod tests {
                 updated_at: 100,
                 tags: vec![],
                 metadata_json: vec![],
+                parent_goal_id: String::new(),
             },
         );
 
-        engine.update_status(&id, "in_progress");
+        engine.update_status(&id, "in_progress", "test", "test");
         let goal = engine.goals.get(&id).unwrap();
         assert_eq!(goal.status, "in_progress");
         assert!(goal.updated_at >= 100);
@@ -879,6 +1654,122 @@ mod tests {
         assert_eq!(progress, 0.0);
     }
 
+    #[test]
+    fn test_parse_spawn_goals() {
+        let value = serde_json::json!({
+            "tool_calls": [],
+            "spawn_goals": [
+                "Check nginx config",
+                {"description": "Rotate logs", "priority": 4},
+                {"priority": 1},
+                42
+            ]
+        });
+        let specs = parse_spawn_goals(&value);
+        assert_eq!(specs.len(), 2);
+        assert_eq!(specs[0].description, "Check nginx config");
+        assert_eq!(specs[0].priority, None);
+        assert_eq!(specs[1].description, "Rotate logs");
+        assert_eq!(specs[1].priority, Some(4));
+
+        assert!(parse_spawn_goals(&serde_json::json!({"done": true})).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_spawn_subgoals_links_parent() {
+        let mut engine = GoalEngine::new();
+        let parent = engine
+            .submit_goal("Parent".into(), 3, "test".into())
+            .await
+            .unwrap();
+
+        let ids = engine
+            .spawn_subgoals(
+                &parent,
+                "t1",
+                vec![SubgoalSpec {
+                    description: "Child".into(),
+                    priority: None,
+                }],
+            )
+            .await
+            .unwrap();
+        assert_eq!(ids.len(), 1);
+
+        let (child, _) = engine.get_goal_with_tasks(&ids[0]).await.unwrap();
+        assert_eq!(child.parent_goal_id, parent);
+        assert_eq!(child.priority, 3);
+        assert_eq!(child.status, "pending");
+        assert_eq!(engine.goal_depth(&ids[0]), 1);
+        assert_eq!(
+            engine.ancestry(&ids[0]),
+            vec![ids[0].clone(), parent.clone()]
+        );
+        assert!(engine.has_active_subgoals(&parent));
+
+        engine.update_status(&ids[0], "completed", "test", "test");
+        assert!(!engine.has_active_subgoals(&parent));
+    }
+
+    #[tokio::test]
+    async fn test_spawn_subgoals_enforces_limits() {
+        let mut engine = GoalEngine::new();
+        engine.subgoal_limits = SubgoalLimits {
+            max_depth: 1,
+            max_fanout: 2,
+        };
+        let root = engine
+            .submit_goal("Root".into(), 2, "test".into())
+            .await
+            .unwrap();
+
+        let specs: Vec<SubgoalSpec> = (0..4)
+            .map(|i| SubgoalSpec {
+                description: format!("Child {i}"),
+                priority: None,
+            })
+            .collect();
+        let ids = engine.spawn_subgoals(&root, "t1", specs).await.unwrap();
+        assert_eq!(ids.len(), 2, "fan-out should be capped");
+
+        let nested = engine
+            .spawn_subgoals(
+                &ids[0],
+                "t2",
+                vec![SubgoalSpec {
+                    description: "Grandchild".into(),
+                    priority: None,
+                }],
+            )
+            .await;
+        assert!(nested.is_err(), "depth limit should reject grandchildren");
+    }
+
+    #[tokio::test]
+    async fn test_cancel_goal_cascades_to_subgoals() {
+        let mut engine = GoalEngine::new();
+        let root = engine
+            .submit_goal("Root".into(), 2, "test".into())
+            .await
+            .unwrap();
+        let ids = engine
+            .spawn_subgoals(
+                &root,
+                "t1",
+                vec![SubgoalSpec {
+                    description: "Child".into(),
+                    priority: None,
+                }],
+            )
+            .await
+            .unwrap();
+
+        engine.cancel_goal(&root, "test").await.unwrap();
+        let (child, _) = engine.get_goal_with_tasks(&ids[0]).await.unwrap();
+        assert_eq!(child.status, "cancelled");
+        assert_eq!(engine.active_goal_count(), 0);
+    }
+
     #[tokio::test]
     async fn test_sqlite_persistence() {
         let dir = tempfile::tempdir().unwrap();
@@ -894,7 +1785,10 @@ mod tests {
                 .await
                 .unwrap();
             engine.add_message(&goal_id, "user", "Hello from test");
-            engine.update_status(&goal_id, "in_progress");
+            engine
+                .update_labels(&goal_id, &["ops".into()], &[])
+                .unwrap();
+            engine.update_status(&goal_id, "in_progress", "test", "test");
         }
 
         // Reopen — data should still be there
@@ -904,10 +1798,217 @@ mod tests {
             let (goal, _tasks) = engine.get_goal_with_tasks(&goal_id).await.unwrap();
             assert_eq!(goal.description, "Persistent goal");
             assert_eq!(goal.status, "in_progress");
+            assert_eq!(goal.tags, vec!["ops"]);
+            let query = GoalQuery {
+                labels: vec!["ops".into()],
+                text: "persistent".into(),
+                ..Default::default()
+            };
+            let (found, _) = engine.search_goals(&query, 50, 0).await;
+            assert_eq!(found.len(), 1);
             let msgs = engine.get_messages(&goal_id);
             assert_eq!(msgs.len(), 2); // system + user
             assert_eq!(msgs[1].sender, "user");
             assert_eq!(msgs[1].content, "Hello from test");
+            let timeline = engine.get_timeline(&goal_id);
+            assert_eq!(timeline.len(), 2);
+            assert_eq!(timeline[1].from_status, "pending");
+            assert_eq!(timeline[1].to_status, "in_progress");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_subgoals_persist() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("test_goals.db");
+        let db_str = db_path.to_str().unwrap();
+
+        let goal_id;
+        let child_id;
+        {
+            let mut engine = GoalEngine::with_db(db_str).unwrap();
+            goal_id = engine
+                .submit_goal("Persistent goal".into(), 1, "test".into())
+                .await
+                .unwrap();
+            child_id = engine
+                .spawn_subgoals(
+                    &goal_id,
+                    "t1",
+                    vec![SubgoalSpec {
+                        description: "Persistent child".into(),
+                        priority: None,
+                    }],
+                )
+                .await
+                .unwrap()
+                .remove(0);
         }
+
+        let engine = GoalEngine::with_db(db_str).unwrap();
+        assert_eq!(engine.active_goal_count(), 2);
+        let (child, _) = engine.get_goal_with_tasks(&child_id).await.unwrap();
+        assert_eq!(child.parent_goal_id, goal_id);
+        assert_eq!(child.priority, 1);
+        assert!(engine.has_active_subgoals(&goal_id));
+        // The parent's conversation notes the spawned subgoal
+        assert_eq!(engine.get_messages(&goal_id).len(), 2);
+    }
+
+    #[test]
+    fn test_normalize_labels() {
+        let labels =
+            normalize_labels(&[" Infra ".into(), "urgent".into(), "".into(), "infra".into()]);
+        assert_eq!(labels, vec!["infra", "urgent"]);
+    }
+
+    #[tokio::test]
+    async fn test_update_labels() {
+        let mut engine = GoalEngine::new();
+        let id = engine
+            .submit_goal("Rotate logs".into(), 1, "test".into())
+            .await
+            .unwrap();
+
+        let labels = engine
+            .update_labels(&id, &["Ops".into(), "stale".into()], &[])
+            .unwrap();
+        assert_eq!(labels, vec!["ops", "stale"]);
+
+        let labels = engine
+            .update_labels(&id, &["urgent".into()], &["STALE".into()])
+            .unwrap();
+        assert_eq!(labels, vec!["ops", "urgent"]);
+        assert!(engine.has_label(&id, "urgent"));
+        assert!(!engine.has_label(&id, "stale"));
+        assert_eq!(
+            engine.label_counts(),
+            vec![("ops".to_string(), 1), ("urgent".to_string(), 1)]
+        );
+
+        assert!(engine.update_labels("nonexistent", &[], &[]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_search_goals() {
+        let mut engine = GoalEngine::new();
+        let disk = engine
+            .submit_goal("Clean up disk space on /var".into(), 1, "test".into())
+            .await
+            .unwrap();
+        let nginx = engine
+            .submit_goal("Install nginx and configure TLS".into(), 2, "test".into())
+            .await
+            .unwrap();
+        let logs = engine
+            .submit_goal("Rotate nginx logs".into(), 3, "test".into())
+            .await
+            .unwrap();
+        engine.update_labels(&disk, &["ops".into()], &[]).unwrap();
+        engine
+            .update_labels(&nginx, &["ops".into(), "web".into()], &[])
+            .unwrap();
+        engine.update_status(&logs, "completed", "test", "test");
+
+        let search = |status: &str, labels: &[&str], text: &str| GoalQuery {
+            status: status.to_string(),
+            labels: labels.iter().map(|l| l.to_string()).collect(),
+            text: text.to_string(),
+            sort: String::new(),
+        };
+        let ids = |goals: Vec<Goal>| goals.into_iter().map(|g| g.id).collect::<Vec<_>>();
+
+        let (goals, total) = engine.search_goals(&search("", &["ops"], ""), 50, 0).await;
+        assert_eq!(total, 2);
+        assert_eq!(ids(goals), vec![disk.clone(), nginx.clone()]);
+
+        let (goals, _) = engine
+            .search_goals(&search("", &["ops", "web"], ""), 50, 0)
+            .await;
+        assert_eq!(ids(goals), vec![nginx.clone()]);
+
+        // Words prefix-match, case-insensitively, and must all be present
+        let (goals, _) = engine.search_goals(&search("", &[], "NGINX"), 50, 0).await;
+        assert_eq!(ids(goals), vec![nginx.clone(), logs.clone()]);
+        let (goals, _) = engine
+            .search_goals(&search("", &[], "ngi conf"), 50, 0)
+            .await;
+        assert_eq!(ids(goals), vec![nginx.clone()]);
+
+        let (goals, _) = engine
+            .search_goals(&search("completed", &[], "nginx"), 50, 0)
+            .await;
+        assert_eq!(ids(goals), vec![logs.clone()]);
+
+        let (goals, total) = engine
+            .search_goals(&search("", &["missing"], ""), 50, 0)
+            .await;
+        assert!(goals.is_empty());
+        assert_eq!(total, 0);
+    }
+
+    #[tokio::test]
+    async fn test_timeline_records_transitions() {
+        let mut engine = GoalEngine::new();
+        let id = engine
+            .submit_goal("Timeline goal".into(), 1, "user".into())
+            .await
+            .unwrap();
+
+        let task = Task {
+            id: "t1".into(),
+            goal_id: id.clone(),
+            description: "Ask for details".into(),
+            assigned_agent: String::new(),
+            status: "pending".into(),
+            intelligence_level: "tactical".into(),
+            required_tools: vec![],
+            depends_on: vec![],
+            input_json: vec![],
+            output_json: vec![],
+            created_at: 0,
+            started_at: 0,
+            completed_at: 0,
+            error: String::new(),
+            model_class: String::new(),
+            provider_hint: String::new(),
+        };
+        engine.add_tasks(&id, vec![task]);
+        engine.update_status(&id, "in_progress", "goal decomposed", "autonomy");
+        engine.update_task_status(&id, "t1", "in_progress", "dispatched", "autonomy");
+        engine.update_task_status(&id, "t1", "awaiting_input", "no tool calls", "autonomy");
+        engine.update_task_status(&id, "t1", "pending", "user replied", "user");
+        // No-op updates are not recorded
+        engine.update_task_status(&id, "t1", "pending", "user replied", "user");
+        engine.update_status(&id, "in_progress", "tick", "autonomy");
+        engine.complete_task(&id, "t1", "task succeeded", "agent-1");
+
+        let timeline = engine.get_timeline(&id);
+        let steps: Vec<(&str, &str, &str)> = timeline
+            .iter()
+            .map(|t| {
+                (
+                    t.entity_type.as_str(),
+                    t.from_status.as_str(),
+                    t.to_status.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            steps,
+            vec![
+                ("goal", "", "pending"),
+                ("task", "", "pending"),
+                ("goal", "pending", "in_progress"),
+                ("task", "pending", "in_progress"),
+                ("task", "in_progress", "awaiting_input"),
+                ("task", "awaiting_input", "pending"),
+                ("task", "pending", "completed"),
+            ]
+        );
+        assert_eq!(timeline[0].actor, "user");
+        assert_eq!(timeline[5].cause, "user replied");
+        assert_eq!(timeline[6].actor, "agent-1");
+        assert!(engine.get_timeline("nonexistent").is_empty());
     }
 }
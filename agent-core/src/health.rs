@@ -1,10 +1,14 @@
 //! Inter-Service Health Checker
 //!
 //! Periodically pings all aiOS services (runtime, tools, memory, api-gateway)
-//! and reports their health status.
+//! and probes the host resources they rely on (disk, network). Services and
+//! resources form a dependency graph under the orchestrator, so a failing
+//! leaf is reported as the root cause of everything above it.
 
-use std::collections::HashMap;
+use crate::proto::orchestrator::{HealthNode, HealthReport};
+use std::collections::{HashMap, HashSet};
 use std::net::SocketAddr;
+use std::path::Path;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::net::TcpStream;
@@ -12,6 +16,18 @@ use tokio::sync::RwLock;
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, warn};
 
+/// Directory whose filesystem the disk probe watches
+const DATA_DIR: &str = "/var/lib/aios";
+
+/// Disk usage at or above this percentage fails the disk probe
+const DISK_MAX_PERCENT: f64 = 95.0;
+
+/// Host resolved by the network probe, overridable with `AIOS_HEALTH_DNS_PROBE`
+const DEFAULT_DNS_PROBE: &str = "api.anthropic.com:443";
+
+/// Recorded as `last_error` until a node has been checked once
+const NOT_CHECKED: &str = "not checked yet";
+
 /// Health status for a single service
 #[derive(Debug, Clone)]
 pub struct ServiceHealthStatus {
@@ -21,11 +37,37 @@ pub struct ServiceHealthStatus {
     pub last_check_ms: u64,
     pub last_checked_at: i64,
     pub consecutive_failures: u32,
+    /// Most recent check failure, kept after the service recovers
+    pub last_error: String,
+    /// Resources this service needs to be useful
+    pub depends_on: Vec<String>,
+}
+
+/// How a host resource is probed
+#[derive(Debug, Clone)]
+pub enum ResourceProbe {
+    /// The filesystem holding `path` must be less than `max_percent` full
+    Disk { path: String, max_percent: f64 },
+    /// `host` (as `host:port`) must resolve
+    Dns { host: String },
+}
+
+/// Health status for a host resource services depend on
+#[derive(Debug, Clone)]
+pub struct ResourceHealthStatus {
+    pub name: String,
+    pub probe: ResourceProbe,
+    pub healthy: bool,
+    pub last_check_ms: u64,
+    pub last_checked_at: i64,
+    pub consecutive_failures: u32,
+    pub last_error: String,
 }
 
 /// Tracks health of all inter-service dependencies
 pub struct HealthChecker {
     services: HashMap<String, ServiceHealthStatus>,
+    resources: HashMap<String, ResourceHealthStatus>,
     check_interval: Duration,
     timeout: Duration,
 }
@@ -34,13 +76,13 @@ impl HealthChecker {
     pub fn new() -> Self {
         let mut services = HashMap::new();
         let default_services = [
-            ("runtime", "127.0.0.1:50055"),
-            ("tools", "127.0.0.1:50052"),
-            ("memory", "127.0.0.1:50053"),
-            ("api-gateway", "127.0.0.1:50054"),
+            ("runtime", "127.0.0.1:50055", "disk"),
+            ("tools", "127.0.0.1:50052", "disk"),
+            ("memory", "127.0.0.1:50053", "disk"),
+            ("api-gateway", "127.0.0.1:50054", "network"),
         ];
 
-        for (name, addr) in &default_services {
+        for (name, addr, dependency) in &default_services {
             let address: SocketAddr = addr.parse().expect("valid socket addr");
             services.insert(
                 name.to_string(),
@@ -51,32 +93,79 @@ impl HealthChecker {
                     last_check_ms: 0,
                     last_checked_at: 0,
                     consecutive_failures: 0,
+                    last_error: NOT_CHECKED.to_string(),
+                    depends_on: vec![dependency.to_string()],
                 },
             );
         }
 
+        let dns_host = std::env::var("AIOS_HEALTH_DNS_PROBE")
+            .ok()
+            .filter(|h| !h.is_empty())
+            .unwrap_or_else(|| DEFAULT_DNS_PROBE.to_string());
+        let resources = [
+            (
+                "disk",
+                ResourceProbe::Disk {
+                    path: DATA_DIR.to_string(),
+                    max_percent: DISK_MAX_PERCENT,
+                },
+            ),
+            ("network", ResourceProbe::Dns { host: dns_host }),
+        ]
+        .into_iter()
+        .map(|(name, probe)| {
+            (
+                name.to_string(),
+                ResourceHealthStatus {
+                    name: name.to_string(),
+                    probe,
+                    healthy: false,
+                    last_check_ms: 0,
+                    last_checked_at: 0,
+                    consecutive_failures: 0,
+                    last_error: NOT_CHECKED.to_string(),
+                },
+            )
+        })
+        .collect();
+
         Self {
             services,
+            resources,
             check_interval: Duration::from_secs(10),
             timeout: Duration::from_secs(2),
         }
     }
 
-    /// Check health of all services via TCP connect
+    /// Check health of all services via TCP connect, then probe resources
     pub async fn check_all(&mut self) {
         let now = chrono::Utc::now().timestamp();
 
         for status in self.services.values_mut() {
             let start = std::time::Instant::now();
-            let healthy = tokio::time::timeout(self.timeout, TcpStream::connect(status.address))
-                .await
-                .map(|r| r.is_ok())
-                .unwrap_or(false);
+            let result = match tokio::time::timeout(
+                self.timeout,
+                TcpStream::connect(status.address),
+            )
+            .await
+            {
+                Ok(Ok(_)) => Ok(()),
+                Ok(Err(e)) => Err(format!("connect to {} failed: {e}", status.address)),
+                Err(_) => Err(format!(
+                    "connect to {} timed out after {}ms",
+                    status.address,
+                    self.timeout.as_millis()
+                )),
+            };
 
             status.last_check_ms = start.elapsed().as_millis() as u64;
             status.last_checked_at = now;
 
-            if healthy {
+            if let Err(e) = &result {
+                status.last_error = e.clone();
+            }
+            if result.is_ok() {
                 if !status.healthy {
                     debug!("Service {} is now healthy", status.name);
                 }
@@ -93,6 +182,31 @@ impl HealthChecker {
                 }
             }
         }
+
+        for status in self.resources.values_mut() {
+            let start = std::time::Instant::now();
+            let result = probe_resource(&status.probe, self.timeout).await;
+            status.last_check_ms = start.elapsed().as_millis() as u64;
+            status.last_checked_at = now;
+
+            match result {
+                Ok(()) => {
+                    if !status.healthy {
+                        debug!("Resource {} is now healthy", status.name);
+                    }
+                    status.healthy = true;
+                    status.consecutive_failures = 0;
+                }
+                Err(e) => {
+                    status.consecutive_failures += 1;
+                    status.healthy = false;
+                    if status.consecutive_failures <= 3 {
+                        warn!("Resource {} probe failed: {e}", status.name);
+                    }
+                    status.last_error = e;
+                }
+            }
+        }
     }
 
     /// Get current health status of all services
@@ -105,6 +219,157 @@ impl HealthChecker {
         self.services.values().all(|s| s.healthy)
     }
 
+    /// Dependencies of a node; the orchestrator depends on every service
+    fn depends_on(&self, name: &str) -> Vec<String> {
+        if name == "orchestrator" {
+            let mut services: Vec<String> = self.services.keys().cloned().collect();
+            services.sort();
+            return services;
+        }
+        self.services
+            .get(name)
+            .map(|s| s.depends_on.clone())
+            .unwrap_or_default()
+    }
+
+    /// Whether a node's own check passes, ignoring its dependencies
+    fn own_healthy(&self, name: &str) -> bool {
+        if name == "orchestrator" {
+            return true;
+        }
+        match self.services.get(name) {
+            Some(s) => s.healthy,
+            None => self.resources.get(name).is_some_and(|r| r.healthy),
+        }
+    }
+
+    fn last_error(&self, name: &str) -> &str {
+        match self.services.get(name) {
+            Some(s) => &s.last_error,
+            None => self
+                .resources
+                .get(name)
+                .map(|r| r.last_error.as_str())
+                .unwrap_or(""),
+        }
+    }
+
+    /// "unhealthy" if the node's own check fails, "degraded" if it passes
+    /// but something it depends on does not, "healthy" otherwise
+    pub fn node_status(&self, name: &str) -> &'static str {
+        if !self.own_healthy(name) {
+            "unhealthy"
+        } else if self
+            .depends_on(name)
+            .iter()
+            .any(|d| self.node_status(d) != "healthy")
+        {
+            "degraded"
+        } else {
+            "healthy"
+        }
+    }
+
+    /// The unhealthy nodes furthest down the graph that explain `name`'s
+    /// status. A node is only its own root cause when its dependencies are fine.
+    pub fn root_causes(&self, name: &str) -> Vec<String> {
+        let mut seen = HashSet::new();
+        let mut causes = Vec::new();
+        self.collect_root_causes(name, &mut seen, &mut causes);
+        causes
+    }
+
+    fn collect_root_causes(
+        &self,
+        name: &str,
+        seen: &mut HashSet<String>,
+        causes: &mut Vec<String>,
+    ) {
+        if !seen.insert(name.to_string()) {
+            return;
+        }
+        let before = causes.len();
+        for dep in self.depends_on(name) {
+            if self.node_status(&dep) != "healthy" {
+                self.collect_root_causes(&dep, seen, causes);
+            }
+        }
+        if causes.len() == before && !self.own_healthy(name) && !causes.iter().any(|c| c == name) {
+            causes.push(name.to_string());
+        }
+    }
+
+    /// Human-readable explanation of a node's status, empty when healthy
+    fn cause(&self, name: &str, status: &str, root_causes: &[String]) -> String {
+        if status == "healthy" {
+            return String::new();
+        }
+        if root_causes == [name] {
+            return format!("{name} unhealthy: {}", self.last_error(name));
+        }
+        let reasons: Vec<String> = root_causes
+            .iter()
+            .map(|r| format!("{r} fails: {}", self.last_error(r)))
+            .collect();
+        format!("{name} {status} because {}", reasons.join("; "))
+    }
+
+    /// The full dependency graph with statuses, root causes and last errors
+    pub fn report(&self) -> HealthReport {
+        let mut service_names: Vec<&String> = self.services.keys().collect();
+        service_names.sort();
+        let mut resource_names: Vec<&String> = self.resources.keys().collect();
+        resource_names.sort();
+
+        let mut nodes = Vec::new();
+        for name in std::iter::once("orchestrator")
+            .chain(service_names.into_iter().map(String::as_str))
+            .chain(resource_names.into_iter().map(String::as_str))
+        {
+            let status = self.node_status(name);
+            let root_causes = if status == "healthy" {
+                Vec::new()
+            } else {
+                self.root_causes(name)
+            };
+            let (kind, latency_ms, last_checked_at, consecutive_failures) =
+                if let Some(s) = self.services.get(name) {
+                    (
+                        "service",
+                        s.last_check_ms,
+                        s.last_checked_at,
+                        s.consecutive_failures,
+                    )
+                } else if let Some(r) = self.resources.get(name) {
+                    (
+                        "resource",
+                        r.last_check_ms,
+                        r.last_checked_at,
+                        r.consecutive_failures,
+                    )
+                } else {
+                    ("service", 0, chrono::Utc::now().timestamp(), 0)
+                };
+            nodes.push(HealthNode {
+                name: name.to_string(),
+                kind: kind.to_string(),
+                status: status.to_string(),
+                depends_on: self.depends_on(name),
+                latency_ms,
+                last_checked_at,
+                consecutive_failures,
+                last_error: self.last_error(name).to_string(),
+                cause: self.cause(name, status, &root_causes),
+                root_causes,
+            });
+        }
+
+        HealthReport {
+            healthy: nodes.iter().all(|n| n.status == "healthy"),
+            nodes,
+        }
+    }
+
     /// Start the health check background loop
     pub async fn run(checker: Arc<RwLock<Self>>, cancel: CancellationToken) {
         // Wait for services to initialize (model loading can take 60+ seconds)
@@ -127,6 +392,61 @@ impl HealthChecker {
     }
 }
 
+/// Run one resource probe, returning why it failed
+async fn probe_resource(probe: &ResourceProbe, timeout: Duration) -> Result<(), String> {
+    match probe {
+        ResourceProbe::Disk { path, max_percent } => {
+            // df needs an existing path; fall back to the nearest ancestor
+            let target = Path::new(path)
+                .ancestors()
+                .find(|p| p.exists())
+                .unwrap_or(Path::new("/"));
+            let output = tokio::time::timeout(
+                timeout,
+                tokio::process::Command::new("df")
+                    .arg("-P")
+                    .arg(target)
+                    .output(),
+            )
+            .await
+            .map_err(|_| format!("df {} timed out", target.display()))?
+            .map_err(|e| format!("df {} failed: {e}", target.display()))?;
+            let text = String::from_utf8_lossy(&output.stdout);
+            let percent = parse_df_percent(&text)
+                .ok_or_else(|| format!("could not parse df output for {}", target.display()))?;
+            if percent >= *max_percent {
+                return Err(format!(
+                    "{} is {percent:.0}% full (limit {max_percent:.0}%)",
+                    target.display()
+                ));
+            }
+            Ok(())
+        }
+        ResourceProbe::Dns { host } => {
+            match tokio::time::timeout(timeout, tokio::net::lookup_host(host.as_str())).await {
+                Ok(Ok(mut addrs)) => match addrs.next() {
+                    Some(_) => Ok(()),
+                    None => Err(format!("DNS lookup of {host} returned no addresses")),
+                },
+                Ok(Err(e)) => Err(format!("DNS lookup of {host} failed: {e}")),
+                Err(_) => Err(format!(
+                    "DNS lookup of {host} timed out after {}ms",
+                    timeout.as_millis()
+                )),
+            }
+        }
+    }
+}
+
+/// Use% column from `df -P` output
+fn parse_df_percent(text: &str) -> Option<f64> {
+    text.lines().nth(1).and_then(|l| {
+        l.split_whitespace()
+            .find(|w| w.ends_with('%'))
+            .and_then(|w| w.trim_end_matches('%').parse::<f64>().ok())
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -161,4 +481,97 @@ mod tests {
             assert_eq!(status.consecutive_failures, 1);
         }
     }
+
+    fn mark(checker: &mut HealthChecker, name: &str, healthy: bool, error: &str) {
+        if let Some(s) = checker.services.get_mut(name) {
+            s.healthy = healthy;
+            s.last_error = error.to_string();
+        } else if let Some(r) = checker.resources.get_mut(name) {
+            r.healthy = healthy;
+            r.last_error = error.to_string();
+        }
+    }
+
+    fn node<'a>(report: &'a HealthReport, name: &str) -> &'a HealthNode {
+        report.nodes.iter().find(|n| n.name == name).unwrap()
+    }
+
+    #[test]
+    fn test_dependency_graph_root_causes() {
+        let mut checker = HealthChecker::new();
+        for name in [
+            "runtime",
+            "tools",
+            "memory",
+            "api-gateway",
+            "disk",
+            "network",
+        ] {
+            mark(&mut checker, name, true, "");
+        }
+        let report = checker.report();
+        assert!(report.healthy);
+        assert_eq!(report.nodes[0].name, "orchestrator");
+        assert_eq!(report.nodes.len(), 7);
+        assert!(report.nodes.iter().all(|n| n.cause.is_empty()));
+
+        // DNS failure cascades to the gateway and the orchestrator
+        mark(
+            &mut checker,
+            "network",
+            false,
+            "DNS lookup of example.com:443 failed",
+        );
+        let report = checker.report();
+        assert!(!report.healthy);
+        let gateway = node(&report, "api-gateway");
+        assert_eq!(gateway.status, "degraded");
+        assert_eq!(gateway.root_causes, vec!["network"]);
+        assert_eq!(
+            gateway.cause,
+            "api-gateway degraded because network fails: DNS lookup of example.com:443 failed"
+        );
+        assert_eq!(node(&report, "network").status, "unhealthy");
+        assert_eq!(node(&report, "tools").status, "healthy");
+        let orchestrator = node(&report, "orchestrator");
+        assert_eq!(orchestrator.status, "degraded");
+        assert_eq!(orchestrator.root_causes, vec!["network"]);
+
+        // A service down on its own is its own root cause
+        mark(&mut checker, "tools", false, "connection refused");
+        let report = checker.report();
+        let tools = node(&report, "tools");
+        assert_eq!(tools.status, "unhealthy");
+        assert_eq!(tools.root_causes, vec!["tools"]);
+        assert_eq!(tools.cause, "tools unhealthy: connection refused");
+        assert_eq!(
+            node(&report, "orchestrator").root_causes,
+            vec!["network", "tools"]
+        );
+
+        // A failing dependency explains a service that is down too
+        mark(&mut checker, "disk", false, "/ is 99% full (limit 95%)");
+        let report = checker.report();
+        let tools = node(&report, "tools");
+        assert_eq!(tools.status, "unhealthy");
+        assert_eq!(tools.root_causes, vec!["disk"]);
+        assert_eq!(node(&report, "runtime").status, "degraded");
+    }
+
+    #[test]
+    fn test_last_error_before_first_check() {
+        let report = HealthChecker::new().report();
+        let memory = node(&report, "memory");
+        assert_eq!(memory.status, "unhealthy");
+        assert_eq!(memory.last_error, NOT_CHECKED);
+        assert_eq!(memory.depends_on, vec!["disk"]);
+    }
+
+    #[test]
+    fn test_parse_df_percent() {
+        let text = "Filesystem 1024-blocks Used Available Capacity Mounted on\n\
+                    /dev/sda1 100 42 58 42% /\n";
+        assert_eq!(parse_df_percent(text), Some(42.0));
+        assert_eq!(parse_df_percent(""), None);
+    }
 }
@@ -0,0 +1,442 @@
+//! Identity — who the orchestrator's calls act as
+//!
+//! Every actor has one identity, `<kind>:<name>`, kept in the memory
+//! service's identity registry: agents (`agent:<agent id>`, registered when
+//! they register here), console users (`user:<name>`), cluster nodes
+//! (`node:<AIOS_NODE_ID>`, this orchestrator), tools and services.
+//!
+//! Every gRPC call the orchestrator makes names the identity it acts as in
+//! `x-aios-identity`, with the identity's token in `x-aios-identity-token`
+//! when it has one, so audit, policy and budgets downstream key off the
+//! same principal. Calls act as this node unless made while handling a
+//! call that named another identity, or a console request, which acts as
+//! `user:<x-aios-user>` (`user:console` without the header) with the
+//! token in `x-aios-user-token`, if any; those identities are passed on as
+//! they came. Deciding a tool approval or an escalated plan review needs
+//! the user's token, which the console issues when an operator signs in
+//! with an account from operators.toml; only this node registers users.
+//!
+//! The node registers itself with a token at startup and keeps the token in
+//! `AIOS_IDENTITY_TOKEN_PATH`, so a restarted node acts as the same
+//! identity. Nodes are reserved identities: the first registration, or one
+//! after the node's token was lost, presents the memory service's bootstrap
+//! token (`AIOS_IDENTITY_BOOTSTRAP_TOKEN_PATH`). Agents that register here
+//! are given a token, written to `<AIOS_AGENT_TOKEN_DIR>/<agent id>.token`
+//! for the agent to present. The memory service checks tokens and grants
+//! reads only to identities that presented theirs; the identities other
+//! services read from calls are otherwise taken at their word.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use crate::clients::ServiceClients;
+use crate::proto::memory::{Identity, IdentityCredentials, IdentityRegistration};
+
+/// Header naming the identity a call acts as
+pub const IDENTITY_HEADER: &str = "x-aios-identity";
+
+/// Header carrying the acting identity's token
+pub const TOKEN_HEADER: &str = "x-aios-identity-token";
+
+/// Header naming the console user a request is made by
+pub const USER_HEADER: &str = "x-aios-user";
+
+/// Header carrying the console user's token
+pub const USER_TOKEN_HEADER: &str = "x-aios-user-token";
+
+/// Kinds of identity
+const KINDS: [&str; 5] = ["agent", "tool", "user", "node", "service"];
+
+/// Where the node's token is kept
+const DEFAULT_TOKEN_PATH: &str = "/var/lib/aios/identity/node.token";
+
+/// Where the memory service keeps the token that registers reserved identities
+const DEFAULT_BOOTSTRAP_TOKEN_PATH: &str = "/var/lib/aios/identity/bootstrap.token";
+
+/// Where agents' tokens are written
+const DEFAULT_AGENT_TOKEN_DIR: &str = "/var/lib/aios/identity/agents";
+
+/// How often registration is retried while the memory service is down
+const REGISTER_INTERVAL: Duration = Duration::from_secs(30);
+
+/// An identity calls act as
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Acting {
+    pub id: String,
+    pub token: Option<String>,
+}
+
+impl Acting {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            token: None,
+        }
+    }
+}
+
+/// The node's own identity, with its token once registered
+static NODE: RwLock<Option<Acting>> = RwLock::new(None);
+
+tokio::task_local! {
+    static ACTING: Acting;
+}
+
+/// `node:<AIOS_NODE_ID>`
+pub fn node_id() -> String {
+    format!(
+        "node:{}",
+        std::env::var("AIOS_NODE_ID").unwrap_or_else(|_| "local".to_string())
+    )
+}
+
+fn node() -> Acting {
+    NODE.read()
+        .ok()
+        .and_then(|node| node.clone())
+        .unwrap_or_else(|| Acting::new(node_id()))
+}
+
+fn set_node(acting: Acting) {
+    if let Ok(mut node) = NODE.write() {
+        *node = Some(acting);
+    }
+}
+
+/// Run `fut` acting as `acting`
+pub async fn scope<F: Future>(acting: Acting, fut: F) -> F::Output {
+    ACTING.scope(acting, fut).await
+}
+
+/// Identity of the current scope; the node outside any
+pub fn current() -> Acting {
+    ACTING.try_with(|a| a.clone()).unwrap_or_else(|_| node())
+}
+
+/// Name the current identity on an outgoing call
+pub fn inject(headers: &mut http::HeaderMap) {
+    let acting = current();
+    if let Ok(id) = acting.id.parse() {
+        headers.insert(IDENTITY_HEADER, id);
+    }
+    if let Some(token) = acting.token.and_then(|t| t.parse().ok()) {
+        headers.insert(TOKEN_HEADER, token);
+    }
+}
+
+/// Whether `id` is `<kind>:<name>` for a known kind
+pub fn is_valid(id: &str) -> bool {
+    id.split_once(':')
+        .is_some_and(|(kind, name)| KINDS.contains(&kind) && !name.is_empty())
+}
+
+/// The identity an incoming call names, if it names a valid one
+pub fn from_headers(headers: &http::HeaderMap) -> Option<Acting> {
+    let id = headers.get(IDENTITY_HEADER)?.to_str().ok()?;
+    is_valid(id).then(|| Acting {
+        id: id.to_string(),
+        token: headers
+            .get(TOKEN_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string),
+    })
+}
+
+/// Server layer running each call's handler as the identity the call names
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ActAsCallerLayer;
+
+impl<S> tower::Layer<S> for ActAsCallerLayer {
+    type Service = ActAsCaller<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ActAsCaller { inner }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ActAsCaller<S> {
+    inner: S,
+}
+
+impl<S, B> tower::Service<http::Request<B>> for ActAsCaller<S>
+where
+    S: tower::Service<http::Request<B>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let acting = from_headers(req.headers());
+        let fut = self.inner.call(req);
+        match acting {
+            Some(acting) => Box::pin(scope(acting, fut)),
+            None => Box::pin(fut),
+        }
+    }
+}
+
+/// Console middleware: requests act as the user they name
+pub async fn console_user(
+    req: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    let user = req
+        .headers()
+        .get(USER_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|u| !u.is_empty() && !u.contains(char::is_whitespace))
+        .unwrap_or("console");
+    let acting = Acting {
+        id: format!("user:{user}"),
+        token: req
+            .headers()
+            .get(USER_TOKEN_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(str::to_string),
+    };
+    scope(acting, next.run(req)).await
+}
+
+fn token_path() -> String {
+    std::env::var("AIOS_IDENTITY_TOKEN_PATH").unwrap_or_else(|_| DEFAULT_TOKEN_PATH.to_string())
+}
+
+fn bootstrap_token() -> Option<String> {
+    let path = std::env::var("AIOS_IDENTITY_BOOTSTRAP_TOKEN_PATH")
+        .unwrap_or_else(|_| DEFAULT_BOOTSTRAP_TOKEN_PATH.to_string());
+    std::fs::read_to_string(path)
+        .ok()
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+}
+
+/// Register the node, retrying until the memory service answers. Without
+/// a saved token, or once the saved one is refused, the registration
+/// presents the bootstrap token.
+pub async fn run_node_registration(clients: Arc<ServiceClients>, cancel: CancellationToken) {
+    let id = node_id();
+    let path = token_path();
+    let saved = std::fs::read_to_string(&path)
+        .ok()
+        .map(|t| t.trim().to_string());
+    set_node(Acting {
+        id: id.clone(),
+        token: saved.or_else(bootstrap_token),
+    });
+    let identity = Identity {
+        kind: "node".to_string(),
+        name: id.trim_start_matches("node:").to_string(),
+        roles: vec!["orchestrator".to_string()],
+        metadata: [("version".to_string(), env!("CARGO_PKG_VERSION").to_string())].into(),
+        ..Default::default()
+    };
+    loop {
+        match register(&clients, identity.clone(), true).await {
+            Ok(token) => {
+                if let Err(e) = save_token(&path, &token) {
+                    warn!("Cannot keep the node token in {path}: {e}");
+                }
+                set_node(Acting {
+                    id: id.clone(),
+                    token: Some(token),
+                });
+                info!("Registered as {id}");
+                return;
+            }
+            Err(e) => {
+                debug!("Node registration failed, retrying: {e}");
+                if let Some(token) = bootstrap_token() {
+                    set_node(Acting {
+                        id: id.clone(),
+                        token: Some(token),
+                    });
+                }
+            }
+        }
+        tokio::select! {
+            _ = cancel.cancelled() => return,
+            _ = tokio::time::sleep(REGISTER_INTERVAL) => {}
+        }
+    }
+}
+
+fn save_token(path: &str, token: &str) -> std::io::Result<()> {
+    if let Some(parent) = std::path::Path::new(path).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, token)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(())
+}
+
+/// Where the token of `agent_id` is written; None for ids that are not
+/// plain file names
+fn agent_token_path(agent_id: &str) -> Option<std::path::PathBuf> {
+    let plain = !agent_id.is_empty()
+        && !agent_id.starts_with('.')
+        && agent_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    let dir = std::env::var("AIOS_AGENT_TOKEN_DIR")
+        .unwrap_or_else(|_| DEFAULT_AGENT_TOKEN_DIR.to_string());
+    plain.then(|| std::path::Path::new(&dir).join(format!("{agent_id}.token")))
+}
+
+/// Register an agent that registered with the orchestrator, issuing it a
+/// token it presents from its token file
+pub fn register_agent(
+    clients: Arc<ServiceClients>,
+    registration: &crate::proto::common::AgentRegistration,
+) {
+    let identity = Identity {
+        kind: "agent".to_string(),
+        name: registration.agent_id.clone(),
+        roles: vec![registration.agent_type.clone()],
+        metadata: [
+            (
+                "capabilities".to_string(),
+                registration.capabilities.join(","),
+            ),
+            (
+                "tool_namespaces".to_string(),
+                registration.tool_namespaces.join(","),
+            ),
+        ]
+        .into(),
+        ..Default::default()
+    };
+    let Some(path) = agent_token_path(&registration.agent_id) else {
+        warn!(
+            "Not registering identity agent:{}: not a plain name",
+            registration.agent_id
+        );
+        return;
+    };
+    tokio::spawn(async move {
+        let name = identity.name.clone();
+        let saved = match register(&clients, identity, true).await {
+            Ok(token) => save_token(&path.to_string_lossy(), &token),
+            Err(e) => {
+                warn!("Failed to register identity agent:{name}: {e}");
+                return;
+            }
+        };
+        if let Err(e) = saved {
+            warn!(
+                "Cannot keep the token of agent:{name} in {}: {e}",
+                path.display()
+            );
+        }
+    });
+}
+
+/// The user the current console request acts as, once the memory service
+/// has checked the token the request carries
+pub async fn verified_user(clients: &ServiceClients) -> Result<String, tonic::Status> {
+    let acting = current();
+    if !acting.id.starts_with("user:") {
+        return Err(tonic::Status::permission_denied("Only a user can decide"));
+    }
+    let token = acting.token.clone().ok_or_else(|| {
+        tonic::Status::unauthenticated(format!("{} did not present its token", acting.id))
+    })?;
+    let mut memory = clients
+        .memory()
+        .await
+        .map_err(|e| tonic::Status::unavailable(e.to_string()))?;
+    memory
+        .verify_identity(IdentityCredentials {
+            id: acting.id.clone(),
+            token,
+        })
+        .await
+        .map_err(|e| tonic::Status::unauthenticated(e.message().to_string()))?;
+    Ok(acting.id)
+}
+
+/// Issue operator `name`, signed in on the console, a new token for
+/// `user:<name>`. Users are registered by this node only, so the node
+/// registers it whatever the request acts as.
+pub async fn sign_in_user(clients: &ServiceClients, name: &str) -> anyhow::Result<String> {
+    let identity = Identity {
+        kind: "user".to_string(),
+        name: name.to_string(),
+        roles: vec!["operator".to_string()],
+        ..Default::default()
+    };
+    scope(node(), register(clients, identity, true)).await
+}
+
+/// Register `identity`; returns its new token, "" unless `issue_token`
+async fn register(
+    clients: &ServiceClients,
+    identity: Identity,
+    issue_token: bool,
+) -> anyhow::Result<String> {
+    let mut memory = clients.memory().await?;
+    let registered = memory
+        .register_identity(IdentityRegistration {
+            identity: Some(identity),
+            issue_token,
+        })
+        .await?
+        .into_inner();
+    Ok(registered.token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_calls_act_as_the_scope_identity() {
+        let mut headers = http::HeaderMap::new();
+        inject(&mut headers);
+        assert_eq!(headers[IDENTITY_HEADER], node_id().as_str());
+        assert!(headers.get(TOKEN_HEADER).is_none());
+
+        let alice = Acting {
+            id: "user:alice".to_string(),
+            token: Some("secret".to_string()),
+        };
+        let mut headers = http::HeaderMap::new();
+        scope(alice.clone(), async { inject(&mut headers) }).await;
+        assert_eq!(from_headers(&headers), Some(alice));
+
+        // Malformed identities are not passed on
+        let mut headers = http::HeaderMap::new();
+        headers.insert(IDENTITY_HEADER, "alice".parse().unwrap());
+        assert!(from_headers(&headers).is_none());
+        assert!(!is_valid("robot:r2"));
+        assert!(!is_valid("agent:"));
+    }
+
+    #[test]
+    fn test_agent_tokens_are_kept_under_plain_names() {
+        let path = agent_token_path("security-agent").unwrap();
+        assert!(path.ends_with("security-agent.token"));
+        assert!(agent_token_path("../node").is_none());
+        assert!(agent_token_path("a/b").is_none());
+        assert!(agent_token_path("").is_none());
+    }
+}
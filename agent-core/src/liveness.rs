@@ -0,0 +1,492 @@
+//! Liveness — heartbeat and watchdog for the autonomy loop
+//!
+//! The autonomy loop reports every phase it enters to a [`LoopHeartbeat`]:
+//! idle between ticks, inside a tick, or reasoning (model calls and tool
+//! calls made without the state lock). A phase that lasts longer than its
+//! limit means the loop is stuck — typically waiting for the orchestrator
+//! state lock that something else never releases.
+//!
+//! The watchdog runs the loop as its own task and checks the heartbeat
+//! every few seconds. When the loop stalls, the recovery path is:
+//!
+//! 1. Dump the lock holders: every writer of the state lock that holds or
+//!    waits for it, and for how long, is logged.
+//! 2. Restart the loop: its task is aborted, dropping whatever it held or
+//!    waited for, and a new one is started. A loop task that exits on its
+//!    own before shutdown is restarted too.
+//! 3. Raise an incident in long-term memory with the dump as symptoms.
+//!
+//! The heartbeat, the restarts and the last stall are reported by
+//! /api/health, which reads them without the state lock.
+//!
+//! Limits come from `AIOS_AUTONOMY_STALL_SECS` (120) for ticks and
+//! `AIOS_AUTONOMY_REASONING_STALL_SECS` (1800) for reasoning; idle waits
+//! may last the fallback tick interval on top of the tick limit.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tokio::sync::{RwLock, RwLockWriteGuard};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+use crate::autonomy::AutonomyConfig;
+use crate::clients::ServiceClients;
+use crate::OrchestratorState;
+
+/// How often the watchdog checks the heartbeat
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// What the autonomy loop is doing
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoopPhase {
+    Starting,
+    Idle,
+    Tick,
+    Reasoning,
+}
+
+/// How long each phase may last before the loop counts as stalled
+#[derive(Debug, Clone)]
+pub struct StallLimits {
+    pub tick: Duration,
+    pub reasoning: Duration,
+    pub idle: Duration,
+}
+
+impl StallLimits {
+    /// Limits from the environment for a loop with the given fallback tick
+    pub fn from_env(tick_interval: Duration) -> Self {
+        let secs = |var: &str, default: u64| {
+            Duration::from_secs(
+                std::env::var(var)
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(default),
+            )
+        };
+        let tick = secs("AIOS_AUTONOMY_STALL_SECS", 120);
+        Self {
+            tick,
+            reasoning: secs("AIOS_AUTONOMY_REASONING_STALL_SECS", 1800),
+            idle: tick_interval + tick,
+        }
+    }
+
+    fn for_phase(&self, phase: LoopPhase) -> Duration {
+        match phase {
+            LoopPhase::Starting | LoopPhase::Tick => self.tick,
+            LoopPhase::Reasoning => self.reasoning,
+            LoopPhase::Idle => self.idle,
+        }
+    }
+}
+
+impl Default for StallLimits {
+    fn default() -> Self {
+        Self::from_env(AutonomyConfig::default().tick_interval)
+    }
+}
+
+/// A stall the watchdog recovered from
+#[derive(Debug, Clone, Serialize)]
+pub struct Stall {
+    pub at: i64,
+    pub phase: LoopPhase,
+    pub stalled_for_ms: u64,
+    /// Whether the state lock was held when the stall was detected
+    pub state_lock_held: bool,
+    pub lock_holders: Vec<LockHolder>,
+}
+
+/// Liveness of the autonomy loop, as reported by /api/health
+#[derive(Debug, Clone, Serialize)]
+pub struct LoopLiveness {
+    pub phase: LoopPhase,
+    pub phase_age_ms: u64,
+    pub ticks: u64,
+    /// Unix time the last tick finished; 0 before the first
+    pub last_tick_at: i64,
+    pub stalled: bool,
+    pub restarts: u32,
+    pub last_stall: Option<Stall>,
+}
+
+#[derive(Debug)]
+struct Beat {
+    phase: LoopPhase,
+    phase_since: Instant,
+    ticks: u64,
+    last_tick_at: i64,
+    restarts: u32,
+    last_stall: Option<Stall>,
+}
+
+/// Phase timestamps of the autonomy loop, shared with the watchdog
+#[derive(Debug)]
+pub struct LoopHeartbeat {
+    beat: Mutex<Beat>,
+    limits: StallLimits,
+}
+
+impl LoopHeartbeat {
+    pub fn new(limits: StallLimits) -> Self {
+        Self {
+            beat: Mutex::new(Beat {
+                phase: LoopPhase::Starting,
+                phase_since: Instant::now(),
+                ticks: 0,
+                last_tick_at: 0,
+                restarts: 0,
+                last_stall: None,
+            }),
+            limits,
+        }
+    }
+
+    /// Record that the loop entered `phase`
+    pub fn enter(&self, phase: LoopPhase) {
+        let mut beat = self.beat.lock().unwrap();
+        beat.phase = phase;
+        beat.phase_since = Instant::now();
+    }
+
+    /// Record a finished tick; the loop is idle until the next one
+    pub fn tick_done(&self) {
+        let mut beat = self.beat.lock().unwrap();
+        beat.ticks += 1;
+        beat.last_tick_at = chrono::Utc::now().timestamp();
+        beat.phase = LoopPhase::Idle;
+        beat.phase_since = Instant::now();
+    }
+
+    /// The current phase and how long it has lasted, when beyond its limit
+    fn stalled(&self) -> Option<(LoopPhase, Duration)> {
+        let beat = self.beat.lock().unwrap();
+        let age = beat.phase_since.elapsed();
+        (age > self.limits.for_phase(beat.phase)).then_some((beat.phase, age))
+    }
+
+    fn record_restart(&self, stall: Option<Stall>) {
+        let mut beat = self.beat.lock().unwrap();
+        beat.restarts += 1;
+        if stall.is_some() {
+            beat.last_stall = stall;
+        }
+        beat.phase = LoopPhase::Starting;
+        beat.phase_since = Instant::now();
+    }
+
+    pub fn liveness(&self) -> LoopLiveness {
+        let stalled = self.stalled().is_some();
+        let beat = self.beat.lock().unwrap();
+        LoopLiveness {
+            phase: beat.phase,
+            phase_age_ms: beat.phase_since.elapsed().as_millis() as u64,
+            ticks: beat.ticks,
+            last_tick_at: beat.last_tick_at,
+            stalled,
+            restarts: beat.restarts,
+            last_stall: beat.last_stall.clone(),
+        }
+    }
+}
+
+impl Default for LoopHeartbeat {
+    fn default() -> Self {
+        Self::new(StallLimits::default())
+    }
+}
+
+// ── State lock holders ──────────────────────────────────
+
+/// A writer of the state lock, as dumped on a stall
+#[derive(Debug, Clone, Serialize)]
+pub struct LockHolder {
+    pub holder: &'static str,
+    /// Holding the lock, or still waiting for it
+    pub holding: bool,
+    pub for_ms: u64,
+}
+
+#[derive(Debug)]
+struct Entry {
+    id: u64,
+    holder: &'static str,
+    holding: bool,
+    since: Instant,
+}
+
+/// Writers that hold or wait for the orchestrator state lock
+static STATE_LOCK_WRITERS: Mutex<Vec<Entry>> = Mutex::new(Vec::new());
+static NEXT_WRITER: AtomicU64 = AtomicU64::new(0);
+
+/// A write guard of the state lock, tracked until dropped
+pub struct Held<G> {
+    guard: G,
+    id: u64,
+}
+
+impl<G: std::ops::Deref> std::ops::Deref for Held<G> {
+    type Target = G::Target;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<G: std::ops::DerefMut> std::ops::DerefMut for Held<G> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.guard
+    }
+}
+
+impl<G> Drop for Held<G> {
+    fn drop(&mut self) {
+        untrack(self.id);
+    }
+}
+
+/// Removes a writer that stopped waiting without taking the lock
+struct Waiting(u64);
+
+impl Drop for Waiting {
+    fn drop(&mut self) {
+        untrack(self.0);
+    }
+}
+
+fn untrack(id: u64) {
+    if let Ok(mut writers) = STATE_LOCK_WRITERS.lock() {
+        writers.retain(|e| e.id != id);
+    }
+}
+
+/// Take the write lock of the orchestrator state as `holder`, which the
+/// watchdog names in its lock dump while it waits for and holds the lock
+pub async fn write_state<'a, T>(
+    lock: &'a RwLock<T>,
+    holder: &'static str,
+) -> Held<RwLockWriteGuard<'a, T>> {
+    let id = NEXT_WRITER.fetch_add(1, Ordering::Relaxed);
+    if let Ok(mut writers) = STATE_LOCK_WRITERS.lock() {
+        writers.push(Entry {
+            id,
+            holder,
+            holding: false,
+            since: Instant::now(),
+        });
+    }
+    let waiting = Waiting(id);
+    let guard = lock.write().await;
+    std::mem::forget(waiting);
+    if let Ok(mut writers) = STATE_LOCK_WRITERS.lock() {
+        if let Some(entry) = writers.iter_mut().find(|e| e.id == id) {
+            entry.holding = true;
+            entry.since = Instant::now();
+        }
+    }
+    Held { guard, id }
+}
+
+/// Every writer holding or waiting for the state lock, longest first
+pub fn lock_holders() -> Vec<LockHolder> {
+    let Ok(writers) = STATE_LOCK_WRITERS.lock() else {
+        return Vec::new();
+    };
+    let mut holders: Vec<LockHolder> = writers
+        .iter()
+        .map(|e| LockHolder {
+            holder: e.holder,
+            holding: e.holding,
+            for_ms: e.since.elapsed().as_millis() as u64,
+        })
+        .collect();
+    holders.sort_by(|a, b| b.holding.cmp(&a.holding).then(b.for_ms.cmp(&a.for_ms)));
+    holders
+}
+
+// ── Watchdog ────────────────────────────────────────────
+
+fn spawn_loop(
+    state: &Arc<RwLock<OrchestratorState>>,
+    cancel: &CancellationToken,
+    config: &AutonomyConfig,
+    heartbeat: &Arc<LoopHeartbeat>,
+) -> JoinHandle<()> {
+    tokio::spawn(crate::autonomy::run_autonomy_loop(
+        state.clone(),
+        cancel.clone(),
+        config.clone(),
+        heartbeat.clone(),
+    ))
+}
+
+/// Run the autonomy loop and restart it when it stalls or exits before
+/// shutdown, until `cancel` fires
+pub async fn run_autonomy_watchdog(
+    state: Arc<RwLock<OrchestratorState>>,
+    cancel: CancellationToken,
+    config: AutonomyConfig,
+    heartbeat: Arc<LoopHeartbeat>,
+) {
+    let (clients, notifier) = {
+        let s = state.read().await;
+        (s.clients.clone(), s.notifier.clone())
+    };
+    let mut task = spawn_loop(&state, &cancel, &config, &heartbeat);
+    info!(
+        "Autonomy watchdog started (stall after {}s in a tick, {}s reasoning)",
+        heartbeat.limits.tick.as_secs(),
+        heartbeat.limits.reasoning.as_secs()
+    );
+
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => {
+                let _ = task.await;
+                break;
+            }
+            _ = tokio::time::sleep(CHECK_INTERVAL) => {}
+        }
+
+        if task.is_finished() {
+            if cancel.is_cancelled() {
+                break;
+            }
+            error!("Autonomy loop exited unexpectedly; restarting it");
+            heartbeat.record_restart(None);
+            task = spawn_loop(&state, &cancel, &config, &heartbeat);
+            continue;
+        }
+
+        let Some((phase, stalled_for)) = heartbeat.stalled() else {
+            continue;
+        };
+        let stall = Stall {
+            at: chrono::Utc::now().timestamp(),
+            phase,
+            stalled_for_ms: stalled_for.as_millis() as u64,
+            state_lock_held: state.try_write().is_err(),
+            lock_holders: lock_holders(),
+        };
+        error!(
+            "Autonomy loop stalled: {:?} for {}s (state lock {}); restarting it",
+            phase,
+            stalled_for.as_secs(),
+            if stall.state_lock_held {
+                "held"
+            } else {
+                "free"
+            }
+        );
+        for h in &stall.lock_holders {
+            error!(
+                "  state lock {} by {} for {}ms",
+                if h.holding { "held" } else { "awaited" },
+                h.holder,
+                h.for_ms
+            );
+        }
+
+        task.abort();
+        let _ = (&mut task).await;
+        heartbeat.record_restart(Some(stall.clone()));
+        task = spawn_loop(&state, &cancel, &config, &heartbeat);
+        notifier.notify(
+            "incident",
+            crate::event_bus::EventSeverity::Critical,
+            &format!(
+                "Autonomy loop stalled in {:?} and was restarted",
+                stall.phase
+            ),
+            &format!("No progress for {}s", stall.stalled_for_ms / 1000),
+            "autonomy-stall",
+        );
+        raise_incident(&clients, &stall).await;
+    }
+
+    info!("Autonomy watchdog stopped");
+}
+
+async fn raise_incident(clients: &ServiceClients, stall: &Stall) {
+    let incident = crate::proto::memory::Incident {
+        id: format!("autonomy-stall-{}", stall.at),
+        description: format!(
+            "Autonomy loop stalled in {:?} for {}s and was restarted",
+            stall.phase,
+            stall.stalled_for_ms / 1000
+        ),
+        symptoms_json: serde_json::to_vec(stall).unwrap_or_default(),
+        resolution: "Autonomy loop task restarted by the watchdog".to_string(),
+        resolved_by: "watchdog".to_string(),
+        timestamp: stall.at,
+        ..Default::default()
+    };
+    match clients.memory().await {
+        Ok(mut client) => {
+            if let Err(e) = client.store_incident(incident).await {
+                warn!("Failed to record autonomy stall incident: {e}");
+            }
+        }
+        Err(e) => warn!("Cannot record autonomy stall incident: {e}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heartbeat_detects_stalled_phase() {
+        let heartbeat = LoopHeartbeat::new(StallLimits {
+            tick: Duration::from_millis(20),
+            reasoning: Duration::from_secs(60),
+            idle: Duration::from_secs(60),
+        });
+        heartbeat.enter(LoopPhase::Tick);
+        assert!(heartbeat.stalled().is_none());
+        std::thread::sleep(Duration::from_millis(30));
+        assert_eq!(heartbeat.stalled().map(|s| s.0), Some(LoopPhase::Tick));
+        assert!(heartbeat.liveness().stalled);
+
+        // Reasoning may take longer than a tick
+        heartbeat.enter(LoopPhase::Reasoning);
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(heartbeat.stalled().is_none());
+
+        heartbeat.tick_done();
+        let liveness = heartbeat.liveness();
+        assert_eq!(liveness.ticks, 1);
+        assert_eq!(liveness.phase, LoopPhase::Idle);
+        assert!(liveness.last_tick_at > 0);
+    }
+
+    #[tokio::test]
+    async fn test_lock_holders_track_writers() {
+        let lock = RwLock::new(0u32);
+        let guard = write_state(&lock, "test.holder").await;
+        let waiter = tokio::spawn(async move {
+            let lock = RwLock::new(0u32);
+            let _held = lock.write().await;
+            write_state(&lock, "test.waiter").await;
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        let mine = |h: &LockHolder| h.holder.starts_with("test.");
+        let holders: Vec<LockHolder> = lock_holders().into_iter().filter(mine).collect();
+        assert_eq!(holders.len(), 2);
+        assert!(holders[0].holding && holders[0].holder == "test.holder");
+        assert!(!holders[1].holding && holders[1].holder == "test.waiter");
+
+        // Writers are forgotten once they release or stop waiting
+        waiter.abort();
+        let _ = waiter.await;
+        drop(guard);
+        assert!(!lock_holders().iter().any(mine));
+    }
+}
@@ -0,0 +1,580 @@
+//! Locale — the language aiOS speaks to people in
+//!
+//! locale.toml sets the system language. A goal can override it with
+//! `language` in its metadata, and subgoals inherit their root goal's.
+//! What people read comes out in that language: the goal messages and
+//! notifications the orchestrator writes come from the catalog below, and
+//! model output meant for people (chat replies, task reasoning behind
+//! completion summaries) is asked for in it through
+//! [`Language::prompt_instruction`]. The dashboard fetches its strings from
+//! `/api/locale`. Tool calls, JSON keys and logs stay in English.
+
+use serde::Deserialize;
+use tracing::warn;
+
+use crate::goal_engine::GoalEngine;
+
+/// Default location of the locale configuration
+pub const LOCALE_CONFIG_PATH: &str = "/etc/aios/locale.toml";
+
+/// Languages aiOS has translations for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Language {
+    #[default]
+    English,
+    German,
+    French,
+    Spanish,
+}
+
+impl Language {
+    pub const ALL: [Language; 4] = [
+        Language::English,
+        Language::German,
+        Language::French,
+        Language::Spanish,
+    ];
+
+    /// Language of a tag such as `de`, `de-AT` or `de_DE.UTF-8`
+    pub fn parse(tag: &str) -> Option<Self> {
+        let primary = tag
+            .split(['-', '_', '.'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        Self::ALL.into_iter().find(|l| l.code() == primary)
+    }
+
+    /// ISO 639-1 code
+    pub fn code(self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::German => "de",
+            Language::French => "fr",
+            Language::Spanish => "es",
+        }
+    }
+
+    /// Name of the language, in the language itself
+    pub fn native_name(self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::German => "Deutsch",
+            Language::French => "Français",
+            Language::Spanish => "Español",
+        }
+    }
+
+    /// Instruction appended to system prompts whose output people read;
+    /// empty for English
+    pub fn prompt_instruction(self) -> String {
+        match self {
+            Language::English => String::new(),
+            _ => format!(
+                "\n\nWrite everything meant for people (replies, reasoning, results, \
+                 summaries) in {} (language code \"{}\"). Keep tool names, JSON keys, \
+                 commands and file paths exactly as they are.",
+                self.english_name(),
+                self.code()
+            ),
+        }
+    }
+
+    fn english_name(self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::German => "German",
+            Language::French => "French",
+            Language::Spanish => "Spanish",
+        }
+    }
+
+    /// System language from the configuration at `path`.
+    /// A missing file yields English; an invalid one is logged and ignored.
+    pub fn load(path: &str) -> Self {
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct LocaleConfig {
+            language: String,
+        }
+
+        let Ok(contents) = std::fs::read_to_string(path) else {
+            return Self::default();
+        };
+        match toml::from_str::<LocaleConfig>(&contents) {
+            Ok(config) => Self::parse(&config.language).unwrap_or_else(|| {
+                warn!(
+                    "Unsupported language {} in {path}; using English",
+                    config.language
+                );
+                Self::default()
+            }),
+            Err(e) => {
+                warn!("Ignoring invalid locale configuration in {path}: {e}");
+                Self::default()
+            }
+        }
+    }
+
+    /// Catalog entry `key`, with `{name}` placeholders filled from `args`.
+    /// Falls back to English, then to the key itself.
+    pub fn text(self, key: &str, args: &[(&str, &str)]) -> String {
+        let mut text = lookup(self, key).to_string();
+        for (name, value) in args {
+            text = text.replace(&format!("{{{name}}}"), value);
+        }
+        text
+    }
+
+    /// Every dashboard string, by key without the `ui.` prefix
+    pub fn ui_strings(self) -> Vec<(&'static str, &'static str)> {
+        CATALOG
+            .iter()
+            .filter_map(|(key, _)| {
+                let ui_key = key.strip_prefix("ui.")?;
+                Some((ui_key, lookup(self, key)))
+            })
+            .collect()
+    }
+}
+
+/// Language of `goal_id`: its own `language` metadata, else the nearest
+/// ancestor's, else `system`
+pub fn for_goal(goals: &GoalEngine, goal_id: &str, system: Language) -> Language {
+    goals
+        .ancestry(goal_id)
+        .iter()
+        .find_map(|id| {
+            let metadata = goals.get_metadata(id)?;
+            let value: serde_json::Value = serde_json::from_slice(metadata).ok()?;
+            Language::parse(value.get("language")?.as_str()?)
+        })
+        .unwrap_or(system)
+}
+
+fn lookup(language: Language, key: &str) -> &'static str {
+    let Some((_, texts)) = CATALOG.iter().find(|(k, _)| *k == key) else {
+        return "";
+    };
+    let index = Language::ALL
+        .iter()
+        .position(|l| *l == language)
+        .unwrap_or(0);
+    match texts[index] {
+        "" => texts[0],
+        text => text,
+    }
+}
+
+/// Translations, in the order of [`Language::ALL`]
+const CATALOG: &[(&str, [&str; 4])] = &[
+    (
+        "task_completed",
+        [
+            "Task completed: {task}",
+            "Aufgabe abgeschlossen: {task}",
+            "Tâche terminée : {task}",
+            "Tarea completada: {task}",
+        ],
+    ),
+    (
+        "task_failed",
+        [
+            "Task failed: {error}",
+            "Aufgabe fehlgeschlagen: {error}",
+            "Échec de la tâche : {error}",
+            "La tarea falló: {error}",
+        ],
+    ),
+    (
+        "task_retrying",
+        [
+            "Task attempt failed: {error}. Retrying in {wait} (attempt {attempt} of {max}).",
+            "Versuch fehlgeschlagen: {error}. Neuer Versuch in {wait} (Versuch {attempt} von {max}).",
+            "Échec de la tentative : {error}. Nouvel essai dans {wait} (tentative {attempt} sur {max}).",
+            "El intento falló: {error}. Reintentando en {wait} (intento {attempt} de {max}).",
+        ],
+    ),
+    (
+        "next_steps",
+        [
+            "Suggested next steps: {steps}",
+            "Empfohlene nächste Schritte: {steps}",
+            "Prochaines étapes suggérées : {steps}",
+            "Próximos pasos sugeridos: {steps}",
+        ],
+    ),
+    (
+        "goal_completed",
+        [
+            "Goal completed: {goal}",
+            "Ziel erreicht: {goal}",
+            "Objectif atteint : {goal}",
+            "Objetivo completado: {goal}",
+        ],
+    ),
+    (
+        "goal_completed_detail",
+        [
+            "All tasks and subgoals finished",
+            "Alle Aufgaben und Teilziele sind abgeschlossen",
+            "Toutes les tâches et sous-objectifs sont terminés",
+            "Todas las tareas y subobjetivos han terminado",
+        ],
+    ),
+    (
+        "awaiting_input",
+        [
+            "Goal {goal} is waiting for your input",
+            "Ziel {goal} wartet auf Ihre Eingabe",
+            "L'objectif {goal} attend votre réponse",
+            "El objetivo {goal} espera su respuesta",
+        ],
+    ),
+    (
+        "plan_approval",
+        [
+            "Goal {goal}: a risky plan needs your approval",
+            "Ziel {goal}: Ein riskanter Plan braucht Ihre Freigabe",
+            "Objectif {goal} : un plan risqué attend votre approbation",
+            "Objetivo {goal}: un plan arriesgado necesita su aprobación",
+        ],
+    ),
+    (
+        "approval_escalated",
+        [
+            "Goal {goal}: a risky plan is still waiting for approval",
+            "Ziel {goal}: Ein riskanter Plan wartet noch auf Freigabe",
+            "Objectif {goal} : un plan risqué attend toujours une approbation",
+            "Objetivo {goal}: un plan arriesgado sigue esperando aprobación",
+        ],
+    ),
+    (
+        "approval_denied",
+        [
+            "Goal {goal}: an unanswered plan was denied",
+            "Ziel {goal}: Ein unbeantworteter Plan wurde abgelehnt",
+            "Objectif {goal} : un plan sans réponse a été refusé",
+            "Objetivo {goal}: un plan sin respuesta fue denegado",
+        ],
+    ),
+    (
+        "ui.subtitle",
+        [
+            "Autonomous AI Operating System",
+            "Autonomes KI-Betriebssystem",
+            "Système d'exploitation IA autonome",
+            "Sistema operativo de IA autónomo",
+        ],
+    ),
+    (
+        "ui.active_goals",
+        ["Active Goals", "Aktive Ziele", "Objectifs actifs", "Objetivos activos"],
+    ),
+    (
+        "ui.pending_tasks",
+        [
+            "Pending Tasks",
+            "Offene Aufgaben",
+            "Tâches en attente",
+            "Tareas pendientes",
+        ],
+    ),
+    (
+        "ui.active_agents",
+        ["Active Agents", "Aktive Agenten", "Agents actifs", "Agentes activos"],
+    ),
+    (
+        "ui.uptime",
+        ["Uptime", "Laufzeit", "Disponibilité", "Tiempo activo"],
+    ),
+    (
+        "ui.system_status",
+        [
+            "System Status",
+            "Systemstatus",
+            "État du système",
+            "Estado del sistema",
+        ],
+    ),
+    (
+        "ui.budget_spent",
+        [
+            "API Budget Spent",
+            "API-Budget verbraucht",
+            "Budget API consommé",
+            "Presupuesto de API usado",
+        ],
+    ),
+    ("ui.tab_chat", ["Chat", "Chat", "Discussion", "Chat"]),
+    (
+        "ui.tab_goals",
+        [
+            "Goals & Tasks",
+            "Ziele & Aufgaben",
+            "Objectifs et tâches",
+            "Objetivos y tareas",
+        ],
+    ),
+    ("ui.tab_system", ["System", "System", "Système", "Sistema"]),
+    ("ui.model", ["Model:", "Modell:", "Modèle :", "Modelo:"]),
+    (
+        "ui.language",
+        ["Language:", "Sprache:", "Langue :", "Idioma:"],
+    ),
+    (
+        "ui.auto_model",
+        [
+            "Auto (best available)",
+            "Automatisch (bestes verfügbares)",
+            "Auto (meilleur disponible)",
+            "Automático (el mejor disponible)",
+        ],
+    ),
+    (
+        "ui.system_language",
+        [
+            "System language",
+            "Systemsprache",
+            "Langue du système",
+            "Idioma del sistema",
+        ],
+    ),
+    (
+        "ui.chat_welcome",
+        [
+            "Hello! I'm aiOS, your AI operating system. Select a model above and ask me anything.",
+            "Hallo! Ich bin aiOS, Ihr KI-Betriebssystem. Wählen Sie oben ein Modell und fragen Sie mich, was Sie möchten.",
+            "Bonjour ! Je suis aiOS, votre système d'exploitation IA. Choisissez un modèle ci-dessus et posez-moi vos questions.",
+            "¡Hola! Soy aiOS, su sistema operativo de IA. Elija un modelo arriba y pregúnteme lo que quiera.",
+        ],
+    ),
+    (
+        "ui.chat_placeholder",
+        [
+            "Ask aiOS anything...",
+            "Fragen Sie aiOS...",
+            "Posez une question à aiOS...",
+            "Pregunte a aiOS...",
+        ],
+    ),
+    ("ui.send", ["Send", "Senden", "Envoyer", "Enviar"]),
+    (
+        "ui.conversation",
+        [
+            "Conversation:",
+            "Unterhaltung:",
+            "Conversation :",
+            "Conversación:",
+        ],
+    ),
+    (
+        "ui.new_chat",
+        [
+            "New chat",
+            "Neuer Chat",
+            "Nouvelle discussion",
+            "Nuevo chat",
+        ],
+    ),
+    (
+        "ui.submit_goal",
+        [
+            "Submit Goal",
+            "Ziel einreichen",
+            "Soumettre l'objectif",
+            "Enviar objetivo",
+        ],
+    ),
+    (
+        "ui.goal_placeholder",
+        [
+            "Describe what you want the system to do...",
+            "Beschreiben Sie, was das System tun soll...",
+            "Décrivez ce que le système doit faire...",
+            "Describa lo que quiere que haga el sistema...",
+        ],
+    ),
+    (
+        "ui.labels_placeholder",
+        [
+            "Labels (comma-separated, optional)",
+            "Labels (kommagetrennt, optional)",
+            "Étiquettes (séparées par des virgules, facultatif)",
+            "Etiquetas (separadas por comas, opcional)",
+        ],
+    ),
+    (
+        "ui.goal_chat",
+        ["Goal Chat", "Ziel-Chat", "Discussion de l'objectif", "Chat del objetivo"],
+    ),
+    (
+        "ui.goal_chat_empty",
+        [
+            "Click on a goal to see its progress and chat...",
+            "Klicken Sie auf ein Ziel, um Fortschritt und Chat zu sehen...",
+            "Cliquez sur un objectif pour voir sa progression et sa discussion...",
+            "Haga clic en un objetivo para ver su progreso y su chat...",
+        ],
+    ),
+    (
+        "ui.awaiting_input",
+        [
+            "AI is awaiting your input",
+            "Die KI wartet auf Ihre Eingabe",
+            "L'IA attend votre réponse",
+            "La IA espera su respuesta",
+        ],
+    ),
+    (
+        "ui.reply_placeholder",
+        [
+            "Reply to AI...",
+            "Der KI antworten...",
+            "Répondre à l'IA...",
+            "Responder a la IA...",
+        ],
+    ),
+    ("ui.reply", ["Reply", "Antworten", "Répondre", "Responder"]),
+    (
+        "ui.explain_system",
+        [
+            "Explain this system",
+            "Dieses System erklären",
+            "Expliquer ce système",
+            "Explicar este sistema",
+        ],
+    ),
+    ("ui.apply", ["Apply", "Übernehmen", "Appliquer", "Aplicar"]),
+    ("ui.timeline", ["Timeline", "Verlauf", "Chronologie", "Cronología"]),
+    (
+        "ui.usage",
+        [
+            "Resource usage",
+            "Ressourcenverbrauch",
+            "Consommation de ressources",
+            "Consumo de recursos",
+        ],
+    ),
+    ("ui.goals", ["Goals", "Ziele", "Objectifs", "Objetivos"]),
+    ("ui.filter", ["Filter", "Filtern", "Filtrer", "Filtrar"]),
+    (
+        "ui.service_health",
+        [
+            "Service Health",
+            "Dienststatus",
+            "État des services",
+            "Estado de los servicios",
+        ],
+    ),
+    ("ui.agents", ["Agents", "Agenten", "Agents", "Agentes"]),
+    (
+        "ui.autonomy_loop",
+        [
+            "Autonomy Loop",
+            "Autonomieschleife",
+            "Boucle d'autonomie",
+            "Bucle de autonomía",
+        ],
+    ),
+    (
+        "ui.latency_slos",
+        [
+            "Latency SLOs",
+            "Latenz-SLOs",
+            "SLO de latence",
+            "SLO de latencia",
+        ],
+    ),
+    (
+        "ui.expensive_goals",
+        [
+            "Most Expensive Goals",
+            "Teuerste Ziele",
+            "Objectifs les plus coûteux",
+            "Objetivos más costosos",
+        ],
+    ),
+    (
+        "ui.pending_approvals",
+        [
+            "Pending Tool Approvals",
+            "Ausstehende Werkzeugfreigaben",
+            "Approbations d'outils en attente",
+            "Aprobaciones de herramientas pendientes",
+        ],
+    ),
+    (
+        "ui.notifications",
+        [
+            "Notifications",
+            "Benachrichtigungen",
+            "Notifications",
+            "Notificaciones",
+        ],
+    ),
+    (
+        "ui.plan_reviews",
+        [
+            "Plan Reviews",
+            "Planprüfungen",
+            "Revues de plans",
+            "Revisiones de planes",
+        ],
+    ),
+    (
+        "ui.setup",
+        ["Setup", "Einrichtung", "Configuration initiale", "Configuración inicial"],
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_catalog() {
+        assert_eq!(Language::parse("de_DE.UTF-8"), Some(Language::German));
+        assert_eq!(Language::parse("FR"), Some(Language::French));
+        assert_eq!(Language::parse("xx"), None);
+
+        assert_eq!(
+            Language::German.text("task_completed", &[("task", "Backup")]),
+            "Aufgabe abgeschlossen: Backup"
+        );
+        assert_eq!(Language::English.text("no_such_key", &[]), "");
+        assert!(Language::English.prompt_instruction().is_empty());
+        assert!(Language::Spanish.prompt_instruction().contains("Spanish"));
+        for language in Language::ALL {
+            assert!(language
+                .ui_strings()
+                .iter()
+                .all(|(_, text)| !text.is_empty()));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_goal_language_overrides_and_inherits() {
+        let mut goals = GoalEngine::new();
+        let root = goals
+            .submit_goal("Audit disks".into(), 5, "console".into())
+            .await
+            .unwrap();
+        assert_eq!(for_goal(&goals, &root, Language::French), Language::French);
+
+        goals.set_metadata(&root, br#"{"language":"de"}"#.to_vec());
+        let child = goals
+            .spawn_subgoals(
+                &root,
+                "task-1",
+                vec![crate::goal_engine::SubgoalSpec {
+                    description: "Check /var".into(),
+                    priority: None,
+                }],
+            )
+            .await
+            .unwrap()
+            .remove(0);
+        assert_eq!(for_goal(&goals, &child, Language::French), Language::German);
+    }
+}
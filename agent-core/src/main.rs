@@ -10,48 +10,85 @@ use std::time::Instant;
 use tokio::sync::RwLock;
 use tokio_util::sync::CancellationToken;
 use tonic::transport::Server;
-use tracing::{debug, error, info, warn};
+use tracing::{debug, error, info, warn, Instrument};
 
 mod agent_router;
 mod agent_spawner;
+mod api_version;
+mod approvals;
+mod artifacts;
 mod autonomy;
+mod benchmark;
+mod build_info;
+mod calendar;
+mod canary;
+mod capabilities;
+mod chat_sessions;
 mod clients;
 mod cluster;
 mod context;
 mod decision_logger;
+mod degradation;
 mod discovery;
 mod event_bus;
+mod feature_flags;
+mod goal_changes;
 mod goal_engine;
 mod health;
+mod identity;
+mod liveness;
+mod locale;
 mod management;
+mod notifications;
+mod onboarding;
+mod operators;
+mod pagination;
+mod peer_review;
 mod proactive;
+mod provider_health;
+mod read_model;
+mod reconcile;
 mod remote_exec;
+mod report;
+mod resilience;
 mod result_aggregator;
 mod scheduler;
+mod setup_status;
+mod shutdown;
+mod slo;
+mod staging;
+mod task_checkpoint;
+mod task_events;
 mod task_planner;
+mod task_workers;
+mod telemetry;
+mod timers;
 mod tls;
+mod tool_usage;
+mod usage;
+mod workload;
 
 pub mod proto {
     pub mod common {
-        tonic::include_proto!("aios.common");
+        tonic::include_proto!("aios.v1.common");
     }
     pub mod orchestrator {
-        tonic::include_proto!("aios.orchestrator");
+        tonic::include_proto!("aios.v1.orchestrator");
     }
     pub mod agent {
-        tonic::include_proto!("aios.agent");
+        tonic::include_proto!("aios.v1.agent");
     }
     pub mod runtime {
-        tonic::include_proto!("aios.runtime");
+        tonic::include_proto!("aios.v1.runtime");
     }
     pub mod tools {
-        tonic::include_proto!("aios.tools");
+        tonic::include_proto!("aios.v1.tools");
     }
     pub mod memory {
-        tonic::include_proto!("aios.memory");
+        tonic::include_proto!("aios.v1.memory");
     }
     pub mod api_gateway {
-        tonic::include_proto!("aios.api_gateway");
+        tonic::include_proto!("aios.v1.api_gateway");
     }
 }
 
@@ -69,6 +106,40 @@ pub struct OrchestratorState {
     pub clients: Arc<clients::ServiceClients>,
     pub health_checker: Arc<RwLock<health::HealthChecker>>,
     pub cluster: Arc<RwLock<cluster::ClusterManager>>,
+    /// Wakes the autonomy loop when new work arrives
+    pub autonomy_waker: Arc<autonomy::AutonomyWaker>,
+    /// Autonomy loop tick and wake-up latency metrics
+    pub autonomy_metrics: Arc<std::sync::Mutex<autonomy::LoopMetrics>>,
+    /// Short-delay tool call timers
+    pub timers: Arc<timers::LocalTimers>,
+    /// In-flight task tracking, shutdown drain and task checkpoints
+    pub drain: Arc<shutdown::Drain>,
+    /// API gateway budget degradation step in force
+    pub budget_degradation: degradation::BudgetDegradation,
+    /// Circuit breaker states of the API gateway's providers
+    pub provider_health: provider_health::ProviderHealth,
+    /// Per-tool usage analytics that rank the prompt's tool catalog
+    pub tool_usage: Arc<std::sync::Mutex<tool_usage::ToolUsage>>,
+    /// Maintenance, on-call and blackout windows from team calendars
+    pub calendar: Arc<calendar::Calendar>,
+    /// Routes incidents, approvals and other events to people
+    pub notifier: Arc<notifications::Notifier>,
+    /// Sandbox runs of risky goals, replayed on the host once verified
+    pub staging: Arc<staging::Staging>,
+    /// Progress of long-running tasks, kept across restarts
+    pub task_checkpoints: Arc<task_checkpoint::TaskCheckpoints>,
+    /// Second-model review of risky plans, and plans awaiting an operator
+    pub peer_review: Arc<peer_review::PeerReview>,
+    /// Where task artifacts are recorded
+    pub artifacts: Arc<artifacts::ArtifactStore>,
+    /// Tasks the orchestrator is executing itself
+    pub task_workers: Arc<task_workers::TaskWorkers>,
+    /// System language; goals may override it in their metadata
+    pub language: locale::Language,
+    /// Resources each goal has used
+    pub usage: Arc<usage::UsageLedger>,
+    /// The trace of each goal's work
+    pub traces: Arc<telemetry::GoalTraces>,
 }
 
 /// Read CPU usage from /proc/stat (Linux) or return 0.0 on other platforms
@@ -135,6 +206,7 @@ fn read_memory_mb() -> (f64, f64) {
 /// gRPC service implementation
 pub struct OrchestratorService {
     state: Arc<RwLock<OrchestratorState>>,
+    scheduler: Arc<RwLock<scheduler::GoalScheduler>>,
 }
 
 #[tonic::async_trait]
@@ -146,7 +218,12 @@ impl proto::orchestrator::orchestrator_server::Orchestrator for OrchestratorServ
         let req = request.into_inner();
         info!("Received goal: {}", req.description);
 
-        let mut state = self.state.write().await;
+        let mut state = crate::liveness::write_state(&self.state, "grpc.submit_goal").await;
+        if state.drain.is_draining() {
+            return Err(tonic::Status::unavailable(
+                "Orchestrator is shutting down; resubmit after restart",
+            ));
+        }
 
         // Decompose goal into tasks
         let goal_id = state
@@ -154,12 +231,31 @@ impl proto::orchestrator::orchestrator_server::Orchestrator for OrchestratorServ
             .submit_goal(req.description.clone(), req.priority, req.source)
             .await
             .map_err(|e| tonic::Status::internal(format!("Failed to submit goal: {e}")))?;
+        if !req.tags.is_empty() {
+            let _ = state.goal_engine.update_labels(&goal_id, &req.tags, &[]);
+        }
+        // Per-goal settings such as `preferred_provider` and `language`
+        if !req.metadata_json.is_empty() {
+            state.goal_engine.set_metadata(&goal_id, req.metadata_json);
+        }
+
+        // The goal's trace continues the caller's
+        state.traces.record(&goal_id, &tracing::Span::current());
 
         // Decompose into tasks using the task planner
-        match state
-            .task_planner
-            .decompose_goal(&goal_id, &req.description)
-            .await
+        let workload = workload::for_goal(&state.goal_engine, &goal_id);
+        let charge = usage::Charge::new(&state.usage, &state.goal_engine, &goal_id);
+        match workload::scope(
+            workload,
+            usage::scope(
+                charge,
+                state
+                    .task_planner
+                    .decompose_goal(&goal_id, &req.description)
+                    .instrument(tracing::info_span!("decompose_goal", goal_id = %goal_id)),
+            ),
+        )
+        .await
         {
             Ok(tasks) => {
                 let task_count = tasks.len();
@@ -170,6 +266,7 @@ impl proto::orchestrator::orchestrator_server::Orchestrator for OrchestratorServ
                 warn!("Failed to decompose goal {goal_id}: {e}");
             }
         }
+        state.autonomy_waker.wake();
 
         Ok(tonic::Response::new(proto::common::GoalId { id: goal_id }))
     }
@@ -188,13 +285,16 @@ impl proto::orchestrator::orchestrator_server::Orchestrator for OrchestratorServ
             .map_err(|e| tonic::Status::not_found(format!("Goal not found: {e}")))?;
 
         let progress = state.goal_engine.calculate_progress(&goal_id).await;
+        let usage = state.usage.total(&state.goal_engine, &goal_id);
 
         Ok(tonic::Response::new(
             proto::orchestrator::GoalStatusResponse {
                 goal: Some(goal),
+                dependencies: task_planner::dependency_graph(&tasks),
                 tasks,
                 current_phase: "executing".to_string(),
                 progress_percent: progress,
+                usage: Some(usage.into()),
             },
         ))
     }
@@ -204,14 +304,22 @@ impl proto::orchestrator::orchestrator_server::Orchestrator for OrchestratorServ
         request: tonic::Request<proto::common::GoalId>,
     ) -> Result<tonic::Response<proto::common::Status>, tonic::Status> {
         let goal_id = request.into_inner().id;
-        let mut state = self.state.write().await;
+        let mut state = crate::liveness::write_state(&self.state, "grpc.cancel_goal").await;
 
-        state
+        let task_ids = state
             .goal_engine
-            .cancel_goal(&goal_id)
+            .cancel_goal(&goal_id, "grpc")
             .await
             .map_err(|e| tonic::Status::internal(format!("Failed to cancel goal: {e}")))?;
 
+        // Stop the workers and tools still running for the cancelled tasks
+        state.task_workers.cancel(&task_ids);
+        let clients = state.clients.clone();
+        let reason = format!("goal {goal_id} cancelled");
+        tokio::spawn(async move {
+            autonomy::cancel_tool_executions(&clients, task_ids, &reason).await;
+        });
+
         Ok(tonic::Response::new(proto::common::Status {
             success: true,
             message: format!("Goal {goal_id} cancelled"),
@@ -223,11 +331,19 @@ impl proto::orchestrator::orchestrator_server::Orchestrator for OrchestratorServ
         request: tonic::Request<proto::orchestrator::ListGoalsRequest>,
     ) -> Result<tonic::Response<proto::orchestrator::GoalListResponse>, tonic::Status> {
         let req = request.into_inner();
+        pagination::check_sort(&req.sort, goal_engine::GOAL_SORT_FIELDS)
+            .map_err(tonic::Status::invalid_argument)?;
         let state = self.state.read().await;
 
+        let query = goal_engine::GoalQuery {
+            status: req.status_filter,
+            labels: req.labels,
+            text: req.query,
+            sort: req.sort,
+        };
         let (goals, total) = state
             .goal_engine
-            .list_goals(&req.status_filter, req.limit, req.offset)
+            .search_goals(&query, req.limit, req.offset)
             .await;
 
         Ok(tonic::Response::new(
@@ -235,6 +351,131 @@ impl proto::orchestrator::orchestrator_server::Orchestrator for OrchestratorServ
         ))
     }
 
+    async fn get_goal_timeline(
+        &self,
+        request: tonic::Request<proto::common::GoalId>,
+    ) -> Result<tonic::Response<proto::orchestrator::GoalTimelineResponse>, tonic::Status> {
+        let goal_id = request.into_inner().id;
+        let state = self.state.read().await;
+
+        state
+            .goal_engine
+            .get_goal_with_tasks(&goal_id)
+            .await
+            .map_err(|e| tonic::Status::not_found(format!("Goal not found: {e}")))?;
+
+        let transitions = state
+            .goal_engine
+            .get_timeline(&goal_id)
+            .into_iter()
+            .map(|t| proto::orchestrator::StateTransition {
+                goal_id: t.goal_id,
+                entity_type: t.entity_type,
+                entity_id: t.entity_id,
+                from_status: t.from_status,
+                to_status: t.to_status,
+                cause: t.cause,
+                actor: t.actor,
+                timestamp: t.timestamp,
+            })
+            .collect();
+
+        Ok(tonic::Response::new(
+            proto::orchestrator::GoalTimelineResponse {
+                goal_id,
+                transitions,
+            },
+        ))
+    }
+
+    type StreamTaskEventsStream = tokio_stream::wrappers::ReceiverStream<
+        Result<proto::orchestrator::TaskEvent, tonic::Status>,
+    >;
+
+    async fn stream_task_events(
+        &self,
+        request: tonic::Request<proto::orchestrator::StreamTaskEventsRequest>,
+    ) -> Result<tonic::Response<Self::StreamTaskEventsStream>, tonic::Status> {
+        let req = request.into_inner();
+        let goal_id = req.goal_id;
+        let state = self.state.read().await;
+
+        // Subscribe under the read lock: transitions need the write lock, so
+        // none fall between the replayed timeline and the live events
+        let mut events = state.goal_engine.task_events().subscribe();
+        let mut replay = Vec::new();
+        let mut finished = false;
+        if !goal_id.is_empty() {
+            let Some(status) = state.goal_engine.goal_status(&goal_id) else {
+                return Err(tonic::Status::not_found(format!(
+                    "Goal not found: {goal_id}"
+                )));
+            };
+            finished = task_events::TERMINAL_GOAL_STATUSES.contains(&status);
+            if req.replay {
+                replay = state
+                    .goal_engine
+                    .get_timeline(&goal_id)
+                    .into_iter()
+                    .map(task_events::from_transition)
+                    .collect();
+            }
+        }
+        drop(state);
+
+        let (tx, rx) = tokio::sync::mpsc::channel(64);
+        tokio::spawn(async move {
+            for event in replay {
+                if tx.send(Ok(event)).await.is_err() {
+                    return;
+                }
+            }
+            if finished {
+                return;
+            }
+            loop {
+                let event = match events.recv().await {
+                    Ok(event) => event,
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(missed)) => {
+                        warn!("Task event subscriber fell behind; {missed} events dropped");
+                        continue;
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => return,
+                };
+                if !goal_id.is_empty() && event.goal_id != goal_id {
+                    continue;
+                }
+                let last = !goal_id.is_empty() && task_events::ends_goal(&event);
+                if tx.send(Ok(event)).await.is_err() || last {
+                    return;
+                }
+            }
+        });
+        Ok(tonic::Response::new(
+            tokio_stream::wrappers::ReceiverStream::new(rx),
+        ))
+    }
+
+    async fn update_goal_labels(
+        &self,
+        request: tonic::Request<proto::orchestrator::UpdateGoalLabelsRequest>,
+    ) -> Result<tonic::Response<proto::common::Goal>, tonic::Status> {
+        let req = request.into_inner();
+        let mut state = crate::liveness::write_state(&self.state, "grpc.update_goal_labels").await;
+
+        state
+            .goal_engine
+            .update_labels(&req.goal_id, &req.add, &req.remove)
+            .map_err(|e| tonic::Status::not_found(e.to_string()))?;
+        let (goal, _) = state
+            .goal_engine
+            .get_goal_with_tasks(&req.goal_id)
+            .await
+            .map_err(|e| tonic::Status::not_found(e.to_string()))?;
+
+        Ok(tonic::Response::new(goal))
+    }
+
     async fn register_agent(
         &self,
         request: tonic::Request<proto::common::AgentRegistration>,
@@ -245,7 +486,8 @@ impl proto::orchestrator::orchestrator_server::Orchestrator for OrchestratorServ
             registration.agent_id, registration.agent_type
         );
 
-        let mut state = self.state.write().await;
+        let mut state = crate::liveness::write_state(&self.state, "grpc.register_agent").await;
+        identity::register_agent(state.clients.clone(), &registration);
         state.agent_router.register_agent(registration).await;
 
         Ok(tonic::Response::new(proto::common::Status {
@@ -259,7 +501,7 @@ impl proto::orchestrator::orchestrator_server::Orchestrator for OrchestratorServ
         request: tonic::Request<proto::common::AgentId>,
     ) -> Result<tonic::Response<proto::common::Status>, tonic::Status> {
         let agent_id = request.into_inner().id;
-        let mut state = self.state.write().await;
+        let mut state = crate::liveness::write_state(&self.state, "grpc.unregister_agent").await;
         state.agent_router.unregister_agent(&agent_id).await;
 
         Ok(tonic::Response::new(proto::common::Status {
@@ -273,10 +515,12 @@ impl proto::orchestrator::orchestrator_server::Orchestrator for OrchestratorServ
         request: tonic::Request<proto::orchestrator::HeartbeatRequest>,
     ) -> Result<tonic::Response<proto::common::Status>, tonic::Status> {
         let hb = request.into_inner();
-        let mut state = self.state.write().await;
+        let mut state = crate::liveness::write_state(&self.state, "grpc.heartbeat").await;
         state
             .agent_router
             .update_heartbeat(&hb.agent_id, &hb.status);
+        // An agent reporting in may be free to take the next task
+        state.autonomy_waker.wake();
 
         Ok(tonic::Response::new(proto::common::Status {
             success: true,
@@ -286,13 +530,21 @@ impl proto::orchestrator::orchestrator_server::Orchestrator for OrchestratorServ
 
     async fn list_agents(
         &self,
-        _request: tonic::Request<proto::common::Empty>,
+        request: tonic::Request<proto::orchestrator::ListAgentsRequest>,
     ) -> Result<tonic::Response<proto::orchestrator::AgentListResponse>, tonic::Status> {
+        let req = request.into_inner();
         let state = self.state.read().await;
-        let agents = state.agent_router.list_agents().await;
+        let agents = agent_router::filter_agents(
+            state.agent_router.list_agents().await,
+            &req.status_filter,
+            &req.agent_type,
+            &req.sort,
+        )
+        .map_err(tonic::Status::invalid_argument)?;
+        let (agents, total) = pagination::page(agents, req.limit, req.offset);
 
         Ok(tonic::Response::new(
-            proto::orchestrator::AgentListResponse { agents },
+            proto::orchestrator::AgentListResponse { agents, total },
         ))
     }
 
@@ -303,6 +555,12 @@ impl proto::orchestrator::orchestrator_server::Orchestrator for OrchestratorServ
         let agent_id = request.into_inner().id;
         let state = self.state.read().await;
 
+        // Hand out no new work while shutting down; the task stays assigned
+        // and is picked up again after restart
+        if state.drain.is_draining() {
+            return Ok(tonic::Response::new(proto::common::Task::default()));
+        }
+
         // Look up whether this agent has a task assigned
         if let Some(ref task_id) = state.agent_router.get_assigned_task_id(&agent_id) {
             if let Some(task) = state.task_planner.get_task(task_id) {
@@ -320,9 +578,10 @@ impl proto::orchestrator::orchestrator_server::Orchestrator for OrchestratorServ
         &self,
         request: tonic::Request<proto::common::TaskResult>,
     ) -> Result<tonic::Response<proto::common::Status>, tonic::Status> {
-        let result = request.into_inner();
+        let mut result = request.into_inner();
         let task_id = result.task_id.clone();
-        let mut state = self.state.write().await;
+        let sections = result_aggregator::RoutedSections::take(&mut result);
+        let mut state = crate::liveness::write_state(&self.state, "grpc.report_task_result").await;
 
         // Find which goal this task belongs to
         let goal_id = state
@@ -332,6 +591,7 @@ impl proto::orchestrator::orchestrator_server::Orchestrator for OrchestratorServ
 
         if let Some(ref goal_id) = goal_id {
             // Find the agent that completed this task and release it
+            let mut reporter = "agent".to_string();
             for agent in state.agent_router.list_agents().await {
                 if let Some(ref assigned) = state.agent_router.get_assigned_task_id(&agent.agent_id)
                 {
@@ -339,6 +599,7 @@ impl proto::orchestrator::orchestrator_server::Orchestrator for OrchestratorServ
                         state
                             .agent_router
                             .task_completed(&agent.agent_id, result.success);
+                        reporter = agent.agent_id;
                         break;
                     }
                 }
@@ -348,25 +609,73 @@ impl proto::orchestrator::orchestrator_server::Orchestrator for OrchestratorServ
                 state
                     .task_planner
                     .complete_task(&task_id, result.output_json.clone());
-                state.goal_engine.complete_task(goal_id, &task_id);
+                state.goal_engine.complete_task(
+                    goal_id,
+                    &task_id,
+                    "agent reported success",
+                    &reporter,
+                );
                 state.goal_engine.add_message(
                     goal_id,
                     "system",
                     &format!("Task {task_id} completed by agent"),
                 );
+
+                // Agents may declare follow-up work via `spawn_goals` or
+                // the result's follow-ups
+                let mut specs = serde_json::from_slice::<serde_json::Value>(&result.output_json)
+                    .map(|v| goal_engine::parse_spawn_goals(&v))
+                    .unwrap_or_default();
+                specs.extend(result_aggregator::follow_up_specs(&result.follow_ups));
+                if !specs.is_empty() {
+                    if let Err(e) = state
+                        .goal_engine
+                        .spawn_subgoals(goal_id, &task_id, specs)
+                        .await
+                    {
+                        warn!("Task {task_id} could not spawn subgoals: {e}");
+                    }
+                }
             } else {
-                state.task_planner.fail_task(&task_id, &result.error);
-                state
-                    .goal_engine
-                    .update_task_status(goal_id, &task_id, "failed");
-                state.goal_engine.add_message(
+                autonomy::fail_or_retry(
+                    &mut state,
                     goal_id,
-                    "system",
-                    &format!("Task {task_id} failed: {}", result.error),
+                    &task_id,
+                    &result.error,
+                    task_planner::FailureKind::Agent,
+                    &format!("agent reported failure: {}", result.error),
+                    &reporter,
                 );
             }
 
+            // Agents run their own inference, outside the orchestrator's scopes
+            if let Err(e) = state.usage.record(
+                goal_id,
+                &usage::GoalUsage {
+                    tokens_used: result.tokens_used.into(),
+                    inference_calls: (result.tokens_used > 0).into(),
+                    wall_clock_ms: result.duration_ms,
+                    ..Default::default()
+                },
+            ) {
+                warn!("Failed to record usage of goal {goal_id}: {e}");
+            }
             state.result_aggregator.record_result(goal_id, result);
+            state.autonomy_waker.wake();
+
+            // Artifacts, metrics and memory entries are routed off the lock
+            if !sections.is_empty() {
+                let clients = state.clients.clone();
+                let artifacts = state.artifacts.clone();
+                let goal_id = goal_id.clone();
+                let task_id = task_id.clone();
+                tokio::spawn(async move {
+                    result_aggregator::route_sections(
+                        &clients, &artifacts, &goal_id, &task_id, &reporter, sections,
+                    )
+                    .await;
+                });
+            }
 
             info!("Agent reported result for task {task_id}");
             Ok(tonic::Response::new(proto::common::Status {
@@ -392,22 +701,44 @@ impl proto::orchestrator::orchestrator_server::Orchestrator for OrchestratorServ
             req.agent_id, req.capabilities
         );
 
-        // For now, auto-grant capabilities (a real implementation would check policies)
-        let expires = chrono::Utc::now()
-            + chrono::Duration::hours(if req.duration_hours > 0 {
-                req.duration_hours
-            } else {
-                24
-            });
-
-        Ok(tonic::Response::new(
-            proto::orchestrator::CapabilityResponse {
-                granted: true,
-                capabilities: req.capabilities,
-                expires_at: expires.to_rfc3339(),
-                denial_reason: String::new(),
+        let (engine, agent_type) = {
+            let state = self.state.read().await;
+            (
+                state.agent_router.capabilities(),
+                state.agent_router.agent_type(&req.agent_id),
+            )
+        };
+        let engine =
+            engine.ok_or_else(|| tonic::Status::unavailable("Capability policy not loaded"))?;
+        let agent_type =
+            agent_type.ok_or_else(|| tonic::Status::not_found("Agent not registered"))?;
+
+        let decision = engine
+            .request(
+                &req.agent_id,
+                &agent_type,
+                &req.capabilities,
+                &req.reason,
+                req.duration_hours,
+            )
+            .map_err(|e| tonic::Status::internal(e.to_string()))?;
+        let response = match decision {
+            capabilities::Decision::Granted { expires_at } => {
+                proto::orchestrator::CapabilityResponse {
+                    granted: true,
+                    capabilities: req.capabilities,
+                    expires_at: expires_at.to_rfc3339(),
+                    denial_reason: String::new(),
+                }
+            }
+            capabilities::Decision::Denied { reason } => proto::orchestrator::CapabilityResponse {
+                granted: false,
+                capabilities: Vec::new(),
+                expires_at: String::new(),
+                denial_reason: reason,
             },
-        ))
+        };
+        Ok(tonic::Response::new(response))
     }
 
     async fn revoke_capability(
@@ -417,9 +748,24 @@ impl proto::orchestrator::orchestrator_server::Orchestrator for OrchestratorServ
         let req = request.into_inner();
         info!("Revoking capabilities from {}", req.agent_id);
 
+        let engine = self
+            .state
+            .read()
+            .await
+            .agent_router
+            .capabilities()
+            .ok_or_else(|| tonic::Status::unavailable("Capability policy not loaded"))?;
+        let revoked = engine
+            .revoke(&req.agent_id, &req.capabilities, req.revoke_all)
+            .map_err(|e| tonic::Status::internal(e.to_string()))?;
+
         Ok(tonic::Response::new(proto::common::Status {
-            success: true,
-            message: format!("Capabilities revoked for {}", req.agent_id),
+            success: revoked > 0,
+            message: if revoked > 0 {
+                format!("Revoked {revoked} grants from {}", req.agent_id)
+            } else {
+                format!("{} holds no matching grants", req.agent_id)
+            },
         }))
     }
 
@@ -430,17 +776,65 @@ impl proto::orchestrator::orchestrator_server::Orchestrator for OrchestratorServ
         let req = request.into_inner();
         let schedule_id = uuid::Uuid::new_v4().to_string();
 
+        scheduler::check_cron(&req.cron_expr)
+            .map_err(|e| tonic::Status::invalid_argument(e.to_string()))?;
+        let tz = scheduler::parse_timezone(&req.timezone)
+            .map_err(|e| tonic::Status::invalid_argument(e.to_string()))?;
+
         info!(
-            "Creating schedule {}: {} → {}",
+            "Creating schedule {}: {} ({}) → {}",
             schedule_id,
             req.cron_expr,
+            tz,
             &req.goal_template[..60.min(req.goal_template.len())]
         );
 
+        let now = chrono::Utc::now();
+        let next_runs = scheduler::preview(&req.cron_expr, tz, now, 5);
+        let conflicts: Vec<String> = self
+            .state
+            .read()
+            .await
+            .calendar
+            .schedule_conflicts(
+                &schedule_id,
+                &req.cron_expr,
+                tz,
+                now,
+                now + chrono::Duration::days(30),
+            )
+            .into_iter()
+            .map(|c| {
+                format!(
+                    "{} falls in blackout '{}' ({})",
+                    c.run_local, c.window.summary, c.window.calendar
+                )
+            })
+            .collect();
+        for conflict in &conflicts {
+            warn!("Schedule {schedule_id}: {conflict}; the run will be skipped");
+        }
+        self.scheduler
+            .write()
+            .await
+            .add_schedule(scheduler::ScheduledGoal {
+                id: schedule_id.clone(),
+                cron_expr: req.cron_expr,
+                goal_template: req.goal_template,
+                priority: req.priority,
+                enabled: true,
+                last_run: None,
+                timezone: tz.name().to_string(),
+                next_run: None,
+            })
+            .map_err(|e| tonic::Status::internal(e.to_string()))?;
+
         Ok(tonic::Response::new(
             proto::orchestrator::ScheduleResponse {
                 schedule_id,
                 success: true,
+                next_runs,
+                conflicts,
             },
         ))
     }
@@ -449,8 +843,23 @@ impl proto::orchestrator::orchestrator_server::Orchestrator for OrchestratorServ
         &self,
         _request: tonic::Request<proto::common::Empty>,
     ) -> Result<tonic::Response<proto::orchestrator::ScheduleListResponse>, tonic::Status> {
+        let scheduler = self.scheduler.read().await;
+        let schedules = scheduler
+            .list_schedules()
+            .into_iter()
+            .map(|s| proto::orchestrator::ScheduleEntry {
+                id: s.id.clone(),
+                cron_expr: s.cron_expr.clone(),
+                goal_template: s.goal_template.clone(),
+                priority: s.priority,
+                enabled: s.enabled,
+                last_run: s.last_run.unwrap_or(0),
+                timezone: s.timezone.clone(),
+                next_run: s.next_run.unwrap_or(0),
+            })
+            .collect();
         Ok(tonic::Response::new(
-            proto::orchestrator::ScheduleListResponse { schedules: vec![] },
+            proto::orchestrator::ScheduleListResponse { schedules },
         ))
     }
 
@@ -460,6 +869,18 @@ impl proto::orchestrator::orchestrator_server::Orchestrator for OrchestratorServ
     ) -> Result<tonic::Response<proto::common::Status>, tonic::Status> {
         let req = request.into_inner();
         info!("Deleting schedule: {}", req.schedule_id);
+        let removed = self
+            .scheduler
+            .write()
+            .await
+            .remove_schedule(&req.schedule_id)
+            .map_err(|e| tonic::Status::internal(e.to_string()))?;
+        if !removed {
+            return Err(tonic::Status::not_found(format!(
+                "Schedule {} not found",
+                req.schedule_id
+            )));
+        }
 
         Ok(tonic::Response::new(proto::common::Status {
             success: true,
@@ -467,11 +888,160 @@ impl proto::orchestrator::orchestrator_server::Orchestrator for OrchestratorServ
         }))
     }
 
+    async fn run_schedule(
+        &self,
+        request: tonic::Request<proto::orchestrator::RunScheduleRequest>,
+    ) -> Result<tonic::Response<proto::common::GoalId>, tonic::Status> {
+        let req = request.into_inner();
+        let (goal_template, priority) = self
+            .scheduler
+            .read()
+            .await
+            .list_schedules()
+            .into_iter()
+            .find(|s| s.id == req.schedule_id)
+            .map(|s| (s.goal_template.clone(), s.priority))
+            .ok_or_else(|| {
+                tonic::Status::not_found(format!("Schedule {} not found", req.schedule_id))
+            })?;
+        info!("Running schedule {} on demand", req.schedule_id);
+
+        let mut state = crate::liveness::write_state(&self.state, "grpc.run_schedule").await;
+        if state.drain.is_draining() {
+            return Err(tonic::Status::unavailable(
+                "Orchestrator is shutting down; resubmit after restart",
+            ));
+        }
+        let goal_id = scheduler::submit_scheduled_goal(
+            &mut state,
+            &req.schedule_id,
+            &goal_template,
+            priority,
+        )
+        .await
+        .map_err(|e| tonic::Status::internal(format!("Failed to submit goal: {e}")))?;
+        Ok(tonic::Response::new(proto::common::GoalId { id: goal_id }))
+    }
+
+    async fn schedule_timer(
+        &self,
+        request: tonic::Request<proto::orchestrator::ScheduleTimerRequest>,
+    ) -> Result<tonic::Response<proto::orchestrator::TimerResponse>, tonic::Status> {
+        let req = request.into_inner();
+        let retry = (req.max_retries > 0).then(|| {
+            let defaults = timers::RetryPolicy::default();
+            timers::RetryPolicy {
+                max_retries: req.max_retries,
+                initial_backoff: match req.initial_backoff_ms {
+                    0 => defaults.initial_backoff,
+                    ms => std::time::Duration::from_millis(ms),
+                },
+                multiplier: if req.backoff_multiplier > 0.0 {
+                    req.backoff_multiplier
+                } else {
+                    defaults.multiplier
+                },
+                max_backoff: match req.max_backoff_ms {
+                    0 => defaults.max_backoff,
+                    ms => std::time::Duration::from_millis(ms),
+                },
+            }
+        });
+        let spec = timers::TimerSpec {
+            owner: req.owner,
+            tool_name: req.tool_name,
+            input_json: req.input_json,
+            delay: std::time::Duration::from_millis(req.delay_ms),
+            repeat_interval: (req.repeat_interval_ms > 0)
+                .then(|| std::time::Duration::from_millis(req.repeat_interval_ms)),
+            repeat_count: req.repeat_count,
+            retry,
+        };
+
+        let state = self.state.read().await;
+        let response = match state.timers.schedule(spec) {
+            Ok(timer_id) => proto::orchestrator::TimerResponse {
+                timer_id,
+                success: true,
+                error: String::new(),
+            },
+            Err(e) => proto::orchestrator::TimerResponse {
+                timer_id: String::new(),
+                success: false,
+                error: e.to_string(),
+            },
+        };
+        Ok(tonic::Response::new(response))
+    }
+
+    async fn cancel_timer(
+        &self,
+        request: tonic::Request<proto::orchestrator::CancelTimerRequest>,
+    ) -> Result<tonic::Response<proto::common::Status>, tonic::Status> {
+        let timer_id = request.into_inner().timer_id;
+        let state = self.state.read().await;
+        let success = state.timers.cancel(&timer_id);
+        Ok(tonic::Response::new(proto::common::Status {
+            success,
+            message: if success {
+                format!("Timer {timer_id} cancelled")
+            } else {
+                format!("Timer {timer_id} not found")
+            },
+        }))
+    }
+
+    async fn list_timers(
+        &self,
+        _request: tonic::Request<proto::common::Empty>,
+    ) -> Result<tonic::Response<proto::orchestrator::TimerListResponse>, tonic::Status> {
+        let (pending, recent) = self.state.read().await.timers.snapshot();
+        let now = std::time::Instant::now();
+
+        let timers = pending
+            .into_iter()
+            .map(|t| proto::orchestrator::TimerEntry {
+                due_in_ms: t.due.saturating_duration_since(now).as_millis() as u64,
+                id: t.id,
+                owner: t.spec.owner,
+                tool_name: t.spec.tool_name,
+                attempt: t.attempt,
+                runs: t.runs,
+                repeat_count: t.spec.repeat_count,
+                in_flight: t.in_flight,
+            })
+            .collect();
+        let recent_firings = recent
+            .into_iter()
+            .map(|f| proto::orchestrator::TimerFiring {
+                timer_id: f.timer_id,
+                owner: f.owner,
+                tool_name: f.tool_name,
+                success: f.success,
+                output: f.output,
+                attempt: f.attempt,
+                fired_at: f.fired_at,
+            })
+            .collect();
+
+        Ok(tonic::Response::new(
+            proto::orchestrator::TimerListResponse {
+                timers,
+                recent_firings,
+            },
+        ))
+    }
+
     async fn register_node(
         &self,
         request: tonic::Request<proto::orchestrator::NodeRegistration>,
     ) -> Result<tonic::Response<proto::common::Status>, tonic::Status> {
         let req = request.into_inner();
+        if !self.state.read().await.cluster.read().await.is_enabled() {
+            return Err(tonic::Status::failed_precondition(
+                "cluster mode is off; enable the 'cluster' feature flag",
+            ));
+        }
         info!(
             "Cluster node registering: {} ({}) with agents: {:?}",
             req.node_id, req.hostname, req.agents
@@ -533,7 +1103,7 @@ impl proto::orchestrator::orchestrator_server::Orchestrator for OrchestratorServ
             cm.list_healthy_nodes()
         };
 
-        let node_infos: Vec<proto::orchestrator::NodeInfo> = nodes
+        let mut node_infos: Vec<proto::orchestrator::NodeInfo> = nodes
             .iter()
             .map(|n| proto::orchestrator::NodeInfo {
                 node_id: n.node_id.clone(),
@@ -546,9 +1116,27 @@ impl proto::orchestrator::orchestrator_server::Orchestrator for OrchestratorServ
                 healthy: n.last_heartbeat.elapsed().as_secs() < 30,
             })
             .collect();
+        node_infos.sort_by(|a, b| a.node_id.cmp(&b.node_id));
+        pagination::sort_by_field(
+            &mut node_infos,
+            &req.sort,
+            cluster::NODE_SORT_FIELDS,
+            |a, b, field| match field {
+                "hostname" => a.hostname.cmp(&b.hostname),
+                "cpu_usage" => a.cpu_usage.total_cmp(&b.cpu_usage),
+                "memory_usage" => a.memory_usage.total_cmp(&b.memory_usage),
+                "active_tasks" => a.active_tasks.cmp(&b.active_tasks),
+                _ => a.node_id.cmp(&b.node_id),
+            },
+        )
+        .map_err(tonic::Status::invalid_argument)?;
+        let (node_infos, total) = pagination::page(node_infos, req.limit, req.offset);
 
         Ok(tonic::Response::new(
-            proto::orchestrator::NodeListResponse { nodes: node_infos },
+            proto::orchestrator::NodeListResponse {
+                nodes: node_infos,
+                total,
+            },
         ))
     }
 
@@ -584,16 +1172,49 @@ impl proto::orchestrator::orchestrator_server::Orchestrator for OrchestratorServ
 
         Ok(tonic::Response::new(status))
     }
+
+    async fn get_setup_status(
+        &self,
+        _request: tonic::Request<proto::common::Empty>,
+    ) -> Result<tonic::Response<proto::orchestrator::SetupStatus>, tonic::Status> {
+        Ok(tonic::Response::new(setup_status::load(
+            setup_status::SETUP_STATE_PATH,
+        )))
+    }
+
+    async fn get_health(
+        &self,
+        _request: tonic::Request<proto::common::Empty>,
+    ) -> Result<tonic::Response<proto::orchestrator::HealthReport>, tonic::Status> {
+        let checker = self.state.read().await.health_checker.clone();
+        let report = checker.read().await.report();
+        Ok(tonic::Response::new(report))
+    }
+
+    async fn get_api_version(
+        &self,
+        _request: tonic::Request<proto::common::Empty>,
+    ) -> Result<tonic::Response<proto::common::ApiVersion>, tonic::Status> {
+        Ok(tonic::Response::new(api_version::api_version(
+            proto::orchestrator::orchestrator_server::SERVICE_NAME,
+        )))
+    }
+
+    async fn get_build_info(
+        &self,
+        _request: tonic::Request<proto::common::Empty>,
+    ) -> Result<tonic::Response<proto::common::BuildInfo>, tonic::Status> {
+        Ok(tonic::Response::new(build_info::build_info(
+            proto::orchestrator::orchestrator_server::SERVICE_NAME,
+            feature_flags::FeatureFlags::global().to_proto(),
+        )))
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     // Initialize tracing
-    tracing_subscriber::fmt()
-        .with_target(false)
-        .with_level(true)
-        .compact()
-        .init();
+    let telemetry = telemetry::init("aios-orchestrator");
 
     info!("aiOS Orchestrator starting...");
 
@@ -619,20 +1240,34 @@ async fn main() -> Result<()> {
         }
     };
     // Create shared service clients (used by both task planner and orchestrator state)
-    let shared_clients = Arc::new(clients::ServiceClients::new());
+    let shared_clients = Arc::new(clients::ServiceClients::with_discovery(
+        service_registry.clone(),
+    ));
 
     // Create task planner with AI decomposition support via shared clients
     let mut task_plan = task_planner::TaskPlanner::with_clients(shared_clients.clone());
     let resumable = goal_eng.get_all_resumable_tasks();
+    let drain = Arc::new(shutdown::Drain::open(shutdown::CHECKPOINT_DB_PATH));
+    drain.retain_tasks(&resumable.iter().map(|t| t.id.clone()).collect());
     if !resumable.is_empty() {
-        info!("Restoring {} tasks from previous session", resumable.len());
+        info!(
+            "Restoring {} tasks from previous session ({} with checkpoints)",
+            resumable.len(),
+            drain.resumable_count()
+        );
         task_plan.load_persisted_tasks(resumable);
     }
 
+    let mut router = agent_router::AgentRouter::new();
+    router.set_capabilities(Arc::new(capabilities::CapabilityEngine::open(
+        capabilities::CapabilityPolicy::load(capabilities::POLICIES_PATH),
+        capabilities::GRANTS_DB_PATH,
+    )));
+
     let state = Arc::new(RwLock::new(OrchestratorState {
         goal_engine: goal_eng,
         task_planner: task_plan,
-        agent_router: agent_router::AgentRouter::new(),
+        agent_router: router,
         result_aggregator: result_aggregator::ResultAggregator::new(),
         decision_logger: decision_logger::DecisionLogger::new(),
         started_at: Instant::now(),
@@ -642,17 +1277,83 @@ async fn main() -> Result<()> {
         cluster: Arc::new(RwLock::new(cluster::ClusterManager::new(
             &std::env::var("AIOS_NODE_ID").unwrap_or_else(|_| "local".to_string()),
         ))),
+        autonomy_waker: Arc::new(autonomy::AutonomyWaker::default()),
+        autonomy_metrics: Arc::new(std::sync::Mutex::new(autonomy::LoopMetrics::default())),
+        timers: Arc::new(timers::LocalTimers::default()),
+        drain: drain.clone(),
+        budget_degradation: degradation::BudgetDegradation::default(),
+        provider_health: provider_health::ProviderHealth::default(),
+        tool_usage: Arc::new(std::sync::Mutex::new(tool_usage::ToolUsage::default())),
+        calendar: Arc::new(calendar::Calendar::default()),
+        notifier: Arc::new(notifications::Notifier::load(
+            notifications::NOTIFICATIONS_CONFIG_PATH,
+        )),
+        staging: Arc::new(staging::Staging::load(staging::STAGING_CONFIG_PATH)),
+        task_checkpoints: Arc::new(task_checkpoint::TaskCheckpoints::new()),
+        peer_review: Arc::new(
+            peer_review::PeerReview::load(peer_review::PEER_REVIEW_CONFIG_PATH).with_approvals(
+                if feature_flags::FeatureFlags::global().enabled("approvals") {
+                    approvals::ApprovalPolicy::load(approvals::APPROVALS_CONFIG_PATH)
+                } else {
+                    approvals::ApprovalPolicy::default()
+                },
+            ),
+        ),
+        artifacts: Arc::new(artifacts::ArtifactStore::open(artifacts::ARTIFACTS_DB_PATH)),
+        task_workers: Default::default(),
+        language: locale::Language::load(locale::LOCALE_CONFIG_PATH),
+        usage: Arc::new(usage::UsageLedger::open(usage::USAGE_DB_PATH)),
+        traces: Default::default(),
     }));
 
+    // Load scheduled goals; the scheduler loop starts below
+    let scheduler_db = "/var/lib/aios/data/scheduler.db";
+    let mut goal_scheduler = scheduler::GoalScheduler::new(scheduler_db);
+    if let Err(e) = goal_scheduler.load() {
+        warn!("Failed to load scheduled goals: {e}");
+    }
+    if let Err(e) = goal_scheduler.ensure_schedule(scheduler::ScheduledGoal {
+        id: benchmark::NIGHTLY_SCHEDULE_ID.to_string(),
+        cron_expr: benchmark::NIGHTLY_CRON.to_string(),
+        goal_template: benchmark::NIGHTLY_GOAL.to_string(),
+        priority: 3,
+        enabled: true,
+        last_run: None,
+        timezone: scheduler::DEFAULT_TIMEZONE.to_string(),
+        next_run: None,
+    }) {
+        warn!("Failed to register nightly benchmark: {e}");
+    }
+    let scheduler_arc = Arc::new(RwLock::new(goal_scheduler));
+
     let service = OrchestratorService {
         state: state.clone(),
+        scheduler: scheduler_arc.clone(),
+    };
+
+    // Autonomy loop heartbeat, read by the watchdog and /api/health
+    let autonomy_config = autonomy::AutonomyConfig {
+        preemption: autonomy::PreemptionPolicy::from_env(),
+        ..Default::default()
     };
+    let autonomy_heartbeat = Arc::new(liveness::LoopHeartbeat::new(
+        liveness::StallLimits::from_env(autonomy_config.tick_interval),
+    ));
 
     // Start management console (HTTP) in background
     let mgmt_state = state.clone();
     let mgmt_health = health_checker.clone();
+    let mgmt_heartbeat = autonomy_heartbeat.clone();
+    let mgmt_read_model = read_model::start(state.clone(), cancel_token.clone()).await;
     tokio::spawn(async move {
-        if let Err(e) = management::start_management_server(mgmt_state, mgmt_health).await {
+        if let Err(e) = management::start_management_server(
+            mgmt_state,
+            mgmt_health,
+            mgmt_heartbeat,
+            mgmt_read_model,
+        )
+        .await
+        {
             error!("Management server failed: {e}");
         }
     });
@@ -689,14 +1390,15 @@ async fn main() -> Result<()> {
         agent_spawner::AgentSpawner::run_monitor(spawner, spawner_cancel).await;
     });
 
-    // Start autonomy loop
+    // Start autonomy loop under its watchdog
     let autonomy_state = state.clone();
     let autonomy_cancel = cancel_token.clone();
     tokio::spawn(async move {
-        autonomy::run_autonomy_loop(
+        liveness::run_autonomy_watchdog(
             autonomy_state,
             autonomy_cancel,
-            autonomy::AutonomyConfig::default(),
+            autonomy_config,
+            autonomy_heartbeat,
         )
         .await;
     });
@@ -708,48 +1410,162 @@ async fn main() -> Result<()> {
         proactive::run_proactive_loop(
             proactive_state,
             proactive_cancel,
-            proactive::ProactiveConfig::default(),
+            proactive::ProactiveConfig {
+                quotas: proactive::ProactiveQuotas::load(proactive::PROACTIVE_QUOTAS_PATH),
+                ..Default::default()
+            },
+        )
+        .await;
+    });
+
+    // Start desired-state reconciliation
+    let reconcile_state = state.clone();
+    let reconcile_cancel = cancel_token.clone();
+    tokio::spawn(async move {
+        reconcile::run_reconcile_loop(
+            reconcile_state,
+            reconcile::DesiredState::load(reconcile::DESIRED_STATE_PATH),
+            reconcile_cancel,
         )
         .await;
     });
 
+    // Start budget degradation monitor
+    let degradation_state = state.clone();
+    let degradation_cancel = cancel_token.clone();
+    tokio::spawn(async move {
+        degradation::run_degradation_monitor(degradation_state, degradation_cancel).await;
+    });
+
+    // Escalate and deny plans operators leave unanswered
+    if feature_flags::FeatureFlags::global().enabled("approvals") {
+        let approval_state = state.clone();
+        let approval_cancel = cancel_token.clone();
+        tokio::spawn(async move {
+            approvals::run_approval_monitor(approval_state, approval_cancel).await;
+        });
+    }
+
+    // Register this node's identity
+    let identity_clients = state.read().await.clients.clone();
+    let identity_cancel = cancel_token.clone();
+    tokio::spawn(async move {
+        identity::run_node_registration(identity_clients, identity_cancel).await;
+    });
+
+    // Start provider health monitor
+    let provider_health_state = state.clone();
+    let provider_health_cancel = cancel_token.clone();
+    tokio::spawn(async move {
+        provider_health::run_provider_health_monitor(provider_health_state, provider_health_cancel)
+            .await;
+    });
+
     // Start service discovery background loop
     let discovery_cancel = cancel_token.clone();
     tokio::spawn(async move {
         discovery::ServiceRegistry::run(service_registry, discovery_cancel).await;
     });
 
+    // Start calendar sync; blackouts hold back the scheduler and proactive goals
+    let calendar = state.read().await.calendar.clone();
+    let calendar_scheduler = scheduler_arc.clone();
+    let calendar_cancel = cancel_token.clone();
+    tokio::spawn(async move {
+        calendar::run_calendar_sync(
+            calendar,
+            calendar_scheduler,
+            calendar::CALENDAR_CONFIG_PATH,
+            calendar_cancel,
+        )
+        .await;
+    });
+
     // Start goal scheduler
-    let scheduler_db = "/var/lib/aios/data/scheduler.db";
-    let mut goal_scheduler = scheduler::GoalScheduler::new(scheduler_db);
-    if let Err(e) = goal_scheduler.load() {
-        warn!("Failed to load scheduled goals: {e}");
-    }
-    let scheduler_arc = Arc::new(RwLock::new(goal_scheduler));
     let scheduler_state = state.clone();
     let scheduler_cancel = cancel_token.clone();
     tokio::spawn(async move {
         scheduler::GoalScheduler::run(scheduler_arc, scheduler_state, scheduler_cancel).await;
     });
 
+    // Start canary goals (end-to-end checks through the full pipeline)
+    let canary_state = state.clone();
+    let canary_cancel = cancel_token.clone();
+    let canaries = canary::load(canary::CANARY_CONFIG_PATH);
+    tokio::spawn(async move {
+        canary::run_canaries(canary_state, canaries, canary_cancel).await;
+    });
+
+    // Start notification dispatch
+    let (notifier, notifier_clients) = {
+        let s = state.read().await;
+        (s.notifier.clone(), s.clients.clone())
+    };
+    let notifier_cancel = cancel_token.clone();
+    tokio::spawn(async move {
+        notifications::run_notifier(notifier, notifier_clients, notifier_cancel).await;
+    });
+
+    // Settle staged goals and keep tool risk levels current
+    let staging_state = state.clone();
+    let staging_cancel = cancel_token.clone();
+    tokio::spawn(async move {
+        staging::run_staging(staging_state, staging_cancel).await;
+    });
+
+    // Start the weekly operations report
+    let report_state = state.clone();
+    let report_cancel = cancel_token.clone();
+    tokio::spawn(async move {
+        report::run_weekly_reports(report_state, report_cancel).await;
+    });
+
     // Start event bus
     let event_bus = Arc::new(RwLock::new(event_bus::EventBus::new()));
     let event_bus_state = state.clone();
     let event_bus_cancel = cancel_token.clone();
+    let event_sender = event_bus.read().await.sender();
     tokio::spawn(async move {
         event_bus::EventBus::run(event_bus, event_bus_state, event_bus_cancel).await;
     });
 
-    // Start cluster monitor (only does work if AIOS_CLUSTER_ENABLED=true)
-    let cluster_ref = {
+    // Check per-method latency SLOs and publish burn-rate alerts
+    let slo_tracker = state.read().await.clients.slo();
+    let slo_events = event_sender.clone();
+    let slo_cancel = cancel_token.clone();
+    tokio::spawn(async move {
+        slo::run(slo_tracker, slo_events, slo_cancel).await;
+    });
+
+    // Start local timers
+    let (timers_ref, timers_clients) = {
         let s = state.read().await;
-        s.cluster.clone()
+        (s.timers.clone(), s.clients.clone())
     };
-    let cluster_cancel = cancel_token.clone();
+    let timers_cancel = cancel_token.clone();
+    tokio::spawn(async move {
+        timers::LocalTimers::run(timers_ref, timers_clients, event_sender, timers_cancel).await;
+    });
+
+    // Benchmark the new build once it has settled after a self-update
+    let benchmark_clients = state.read().await.clients.clone();
+    let benchmark_cancel = cancel_token.clone();
     tokio::spawn(async move {
-        cluster::ClusterManager::run_monitor(cluster_ref, cluster_cancel).await;
+        benchmark::run_after_update(benchmark_clients, benchmark_cancel).await;
     });
 
+    // Start cluster monitor (feature flag 'cluster' or AIOS_CLUSTER_ENABLED=true)
+    let cluster_ref = {
+        let s = state.read().await;
+        s.cluster.clone()
+    };
+    if cluster_ref.read().await.is_enabled() {
+        let cluster_cancel = cancel_token.clone();
+        tokio::spawn(async move {
+            cluster::ClusterManager::run_monitor(cluster_ref, cluster_cancel).await;
+        });
+    }
+
     // Set up signal handlers for graceful shutdown
     let shutdown_token = cancel_token.clone();
     tokio::spawn(async move {
@@ -778,12 +1594,19 @@ async fn main() -> Result<()> {
             info!("Received SIGINT, initiating graceful shutdown...");
         }
 
+        // Stop taking work and let in-flight tasks finish; anything still
+        // running at the deadline is checkpointed for the next start
+        let interrupted = drain.drain(shutdown::drain_timeout()).await;
+        if !interrupted.is_empty() {
+            info!(
+                "Interrupted {} tasks; they resume from their last completed round after restart",
+                interrupted.len()
+            );
+        }
+
         // Signal all background tasks to stop
         shutdown_token.cancel();
 
-        // Give background tasks time to drain
-        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
-
         info!("Graceful shutdown complete");
     });
 
@@ -792,10 +1615,14 @@ async fn main() -> Result<()> {
     info!("Orchestrator gRPC server listening on {addr}");
 
     Server::builder()
+        .trace_fn(telemetry::server_span)
+        .layer(api_version::legacy_shim())
+        .layer(identity::ActAsCallerLayer)
         .add_service(OrchestratorServer::new(service))
         .serve_with_shutdown(addr, cancel_token.cancelled_owned())
         .await
         .context("gRPC server failed")?;
 
+    telemetry::shutdown(telemetry);
     Ok(())
 }
@@ -2,12 +2,21 @@
 //!
 //! Provides HTTP endpoints for monitoring and controlling aiOS.
 //! Includes WebSocket endpoint for real-time updates.
-//! Chat endpoint for direct AI interaction.
+//! Chat endpoint for direct AI interaction, in sessions kept in working
+//! memory (see [`crate::chat_sessions`]).
 //! Runs on port 9090 alongside the gRPC server.
+//!
+//! Reads are served from the snapshot kept by [`crate::read_model`], so
+//! the console never holds the state lock while answering them; only
+//! writes take it.
+//!
+//! List endpoints take `limit`, `offset` and `sort` (see
+//! [`crate::pagination`]) and return one page, with the unpaged count in
+//! the `X-Total-Count` header.
 
 use axum::{
     extract::ws::{Message, WebSocket, WebSocketUpgrade},
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     response::Json,
     routing::{get, post},
@@ -16,42 +25,138 @@ use axum::{
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tracing::{info, warn};
+use tracing::{info, warn, Instrument};
 
+use crate::goal_engine::GoalQuery;
 use crate::health::HealthChecker;
+use crate::liveness::{LoopHeartbeat, LoopLiveness};
+use crate::pagination::{self, PageParams};
+use crate::read_model::ReadModel;
 use crate::OrchestratorState;
 
 type SharedState = Arc<RwLock<OrchestratorState>>;
 
+/// One page of a list endpoint, with the unpaged count in `X-Total-Count`
+type Page<T> = ([(&'static str, String); 1], Json<Vec<T>>);
+
+/// Sort `items` as `params` asks (400 on an unknown field) and cut the page
+fn paged<T>(
+    mut items: Vec<T>,
+    params: &PageParams,
+    fields: &[&str],
+    compare: impl Fn(&T, &T, &str) -> std::cmp::Ordering,
+) -> Result<Page<T>, StatusCode> {
+    pagination::sort_by_field(&mut items, &params.sort, fields, compare)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    let (items, total) = pagination::page(items, params.rest_limit(), params.offset);
+    Ok(([("x-total-count", total.to_string())], Json(items)))
+}
+
 /// Combined state for management server
 #[derive(Clone)]
 struct MgmtState {
     orchestrator: SharedState,
     health_checker: Arc<RwLock<HealthChecker>>,
+    heartbeat: Arc<LoopHeartbeat>,
+    read_model: ReadModel,
+    notifier: Arc<crate::notifications::Notifier>,
+    clients: Arc<crate::clients::ServiceClients>,
+    language: crate::locale::Language,
+    /// Live goal changes, forwarded to WebSocket clients
+    changes: crate::goal_changes::GoalChanges,
 }
 
 /// Start the management HTTP server on port 9090
 pub async fn start_management_server(
     state: SharedState,
     health_checker: Arc<RwLock<HealthChecker>>,
+    heartbeat: Arc<LoopHeartbeat>,
+    read_model: ReadModel,
 ) -> anyhow::Result<()> {
+    let (notifier, clients, language, changes) = {
+        let state = state.read().await;
+        (
+            state.notifier.clone(),
+            state.clients.clone(),
+            state.language,
+            state.goal_engine.changes().clone(),
+        )
+    };
     let mgmt_state = MgmtState {
         orchestrator: state,
         health_checker,
+        heartbeat,
+        read_model,
+        notifier,
+        clients,
+        language,
+        changes,
     };
 
     let app = Router::new()
         .route("/api/status", get(get_status))
+        .route("/api/setup", get(get_setup_status))
+        .route("/api/login", post(sign_in))
         .route("/api/goals", get(list_goals))
         .route("/api/goals", post(submit_goal))
         .route("/api/goals/:goal_id/tasks", get(get_goal_tasks))
         .route("/api/goals/:goal_id/messages", get(get_goal_messages))
         .route("/api/goals/:goal_id/messages", post(post_goal_message))
+        .route("/api/goals/:goal_id/timeline", get(get_goal_timeline))
+        .route("/api/goals/:goal_id/artifacts", get(get_goal_artifacts))
+        .route("/api/goals/:goal_id/usage", get(get_goal_usage))
+        .route("/api/goals/:goal_id/cost", get(get_goal_cost))
+        .route("/api/goals/:goal_id/labels", post(update_goal_labels))
+        .route(
+            "/api/goals/:goal_id/staging/replay",
+            post(replay_staged_goal),
+        )
+        .route("/api/staging", get(list_staging_runs))
+        .route("/api/reviews", get(list_escalated_reviews))
+        .route(
+            "/api/goals/:goal_id/review/approve",
+            post(approve_reviewed_plan),
+        )
+        .route(
+            "/api/goals/:goal_id/review/reject",
+            post(reject_reviewed_plan),
+        )
+        .route("/api/tool-approvals", get(list_tool_approvals))
+        .route(
+            "/api/tool-approvals/:approval_id/approve",
+            post(approve_tool_execution),
+        )
+        .route(
+            "/api/tool-approvals/:approval_id/deny",
+            post(deny_tool_execution),
+        )
+        .route("/api/labels", get(list_labels))
         .route("/api/chat", post(chat_handler))
+        .route("/api/chat/sessions", get(list_chat_sessions))
+        .route("/api/chat/sessions/:session_id", get(get_chat_session))
+        .route("/api/locale", get(get_locale))
+        .route(
+            "/api/onboarding",
+            get(list_onboarding_templates).post(start_onboarding),
+        )
         .route("/api/agents", get(list_agents))
         .route("/api/health", get(health_check))
+        .route("/api/autonomy", get(autonomy_metrics))
+        .route("/api/slo", get(slo_report))
+        .route("/api/tools/usage", get(tool_usage))
+        .route("/api/usage", get(list_goal_usage))
+        .route("/api/calendar", get(calendar_windows))
+        .route("/api/notifications", get(list_notifications))
+        .route("/api/reports", get(list_reports))
+        .route("/api/reports/weekly", post(generate_weekly_report))
+        .route("/api/reports/:week", get(get_report))
+        .route(
+            "/api/notifications/config",
+            get(get_notification_config).put(put_notification_config),
+        )
         .route("/ws", get(ws_handler))
         .route("/", get(dashboard))
+        .layer(axum::middleware::from_fn(crate::identity::console_user))
         .with_state(mgmt_state);
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:9090").await?;
@@ -72,6 +177,11 @@ struct StatusResponse {
     active_agents: usize,
     uptime_seconds: u64,
     autonomy_level: String,
+    /// API budget degradation step in force ("" when none)
+    budget_degradation: String,
+    budget_spent_percent: f64,
+    /// Unix time of the snapshot the console read
+    snapshot_at: i64,
 }
 
 #[derive(Serialize)]
@@ -80,9 +190,56 @@ struct GoalResponse {
     description: String,
     status: String,
     priority: i32,
+    labels: Vec<String>,
     created_at: i64,
 }
 
+/// Goal list filter, shared by `GET /api/goals` and the WebSocket feed
+#[derive(Deserialize, Default, Clone)]
+struct GoalFilterParams {
+    #[serde(default)]
+    status: String,
+    /// Comma-separated labels
+    #[serde(default)]
+    labels: String,
+    #[serde(default)]
+    q: String,
+}
+
+impl GoalFilterParams {
+    fn to_query(&self) -> GoalQuery {
+        GoalQuery {
+            status: self.status.clone(),
+            labels: self.labels.split(',').map(str::to_string).collect(),
+            text: self.q.clone(),
+            sort: String::new(),
+        }
+    }
+}
+
+/// Agent list filter for `GET /api/agents`
+#[derive(Deserialize, Default)]
+struct AgentFilterParams {
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    agent_type: String,
+}
+
+#[derive(Deserialize)]
+struct UpdateLabelsRequest {
+    #[serde(default)]
+    add: Vec<String>,
+    #[serde(default)]
+    remove: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct LabelResponse {
+    label: String,
+    goal_count: usize,
+}
+
 #[derive(Serialize)]
 struct GoalTaskResponse {
     task_id: String,
@@ -94,6 +251,12 @@ struct GoalTaskResponse {
     error: String,
     created_at: i64,
     completed_at: i64,
+    /// Tasks this one waits for
+    depends_on: Vec<String>,
+    /// Of those, the ones not completed yet
+    blocked_by: Vec<String>,
+    /// Stage in the goal's task graph; tasks of equal depth run in parallel
+    depth: i32,
 }
 
 #[derive(Deserialize)]
@@ -103,22 +266,63 @@ struct SubmitGoalRequest {
     priority: i32,
     #[serde(default)]
     provider: String,
+    /// Language of the goal's messages, overriding the system language
+    #[serde(default)]
+    language: String,
+    #[serde(default)]
+    labels: Vec<String>,
+    /// Budget of the goal and its subgoals, in tokens and dollars
+    #[serde(default)]
+    budget_tokens: Option<i64>,
+    #[serde(default)]
+    budget_usd: Option<f64>,
+    /// What happens past the budget: "downgrade" (the default) or "fail"
+    #[serde(default)]
+    budget_policy: String,
 }
 
 fn default_priority() -> i32 {
     2
 }
 
+#[derive(Serialize)]
+struct LocaleResponse {
+    language: &'static str,
+    language_name: &'static str,
+    languages: Vec<LanguageOption>,
+    /// Dashboard strings in the system language
+    strings: std::collections::BTreeMap<&'static str, &'static str>,
+}
+
+#[derive(Serialize)]
+struct LanguageOption {
+    code: &'static str,
+    name: &'static str,
+}
+
 #[derive(Serialize)]
 struct SubmitGoalResponse {
     goal_id: String,
 }
 
+#[derive(Deserialize)]
+struct StartOnboardingRequest {
+    /// Templates to run; all of them when empty
+    #[serde(default)]
+    templates: Vec<String>,
+    /// Language of the goal's messages and report, overriding the system language
+    #[serde(default)]
+    language: String,
+}
+
 #[derive(Deserialize)]
 struct ChatRequest {
     message: String,
     #[serde(default)]
     provider: String,
+    /// Session to continue; a new session is started when empty
+    #[serde(default)]
+    session_id: String,
 }
 
 #[derive(Serialize)]
@@ -127,6 +331,53 @@ struct ChatResponse {
     model: String,
     tokens: i32,
     latency_ms: i64,
+    session_id: String,
+}
+
+#[derive(Serialize)]
+struct ChatSessionResponse {
+    session_id: String,
+    title: String,
+    summary: String,
+    message_count: i32,
+    created_at: i64,
+    updated_at: i64,
+    /// Only filled in when a single session is fetched
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    messages: Vec<ChatMessageResponse>,
+}
+
+#[derive(Serialize)]
+struct ChatMessageResponse {
+    role: String,
+    content: String,
+    model: String,
+    tokens: i32,
+    timestamp: i64,
+}
+
+impl From<crate::proto::memory::ChatSession> for ChatSessionResponse {
+    fn from(session: crate::proto::memory::ChatSession) -> Self {
+        Self {
+            session_id: session.session_id,
+            title: session.title,
+            summary: session.summary,
+            message_count: session.message_count,
+            created_at: session.created_at,
+            updated_at: session.updated_at,
+            messages: session
+                .messages
+                .into_iter()
+                .map(|m| ChatMessageResponse {
+                    role: m.role,
+                    content: m.content,
+                    model: m.model,
+                    tokens: m.tokens,
+                    timestamp: m.timestamp,
+                })
+                .collect(),
+        }
+    }
 }
 
 #[derive(Serialize)]
@@ -150,38 +401,85 @@ struct GoalMessageResponse {
     timestamp: i64,
 }
 
+#[derive(Serialize)]
+struct TimelineEntryResponse {
+    entity_type: String,
+    entity_id: String,
+    from_status: String,
+    to_status: String,
+    cause: String,
+    actor: String,
+    timestamp: i64,
+}
+
 #[derive(Serialize)]
 struct HealthResponse {
     healthy: bool,
     services: Vec<ServiceHealth>,
+    autonomy: LoopLiveness,
+    /// Circuit breakers of inter-service endpoints that have been called
+    circuits: Vec<CircuitHealth>,
+}
+
+#[derive(Serialize)]
+struct CircuitHealth {
+    service: String,
+    endpoint: String,
+    state: String,
 }
 
 #[derive(Serialize)]
 struct ServiceHealth {
     name: String,
+    /// "service" or "resource"
+    kind: String,
+    /// "healthy", "degraded" (a dependency is not) or "unhealthy"
     status: String,
     latency_ms: u64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    depends_on: Vec<String>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    last_error: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    root_causes: Vec<String>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    cause: String,
 }
 
 // --- Handlers ---
 
 async fn get_status(State(state): State<MgmtState>) -> Json<StatusResponse> {
-    let s = state.orchestrator.read().await;
-    let uptime = s.started_at.elapsed().as_secs();
+    let s = state.read_model.current();
     Json(StatusResponse {
         status: "running".into(),
         version: env!("CARGO_PKG_VERSION").into(),
-        active_goals: s.goal_engine.active_goal_count(),
-        pending_tasks: s.task_planner.pending_task_count(),
-        active_agents: s.agent_router.active_agent_count(),
-        uptime_seconds: uptime,
+        active_goals: s.goals.active_goal_count(),
+        pending_tasks: s.pending_tasks,
+        active_agents: s.active_agents,
+        uptime_seconds: s.uptime_seconds,
         autonomy_level: "full".into(),
+        budget_degradation: s.budget.step.clone(),
+        budget_spent_percent: s.budget.spent_percent,
+        snapshot_at: s.taken_at,
     })
 }
 
-async fn list_goals(State(state): State<MgmtState>) -> Json<Vec<GoalResponse>> {
-    let s = state.orchestrator.read().await;
-    let (goals, _) = s.goal_engine.list_goals("", 50, 0).await;
+async fn list_goals(
+    State(state): State<MgmtState>,
+    Query(filter): Query<GoalFilterParams>,
+    Query(page): Query<PageParams>,
+) -> Result<Page<GoalResponse>, StatusCode> {
+    pagination::check_sort(&page.sort, crate::goal_engine::GOAL_SORT_FIELDS)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    let query = GoalQuery {
+        sort: page.sort.clone(),
+        ..filter.to_query()
+    };
+    let s = state.read_model.current();
+    let (goals, total) = s
+        .goals
+        .search_goals(&query, page.rest_limit(), page.offset)
+        .await;
     let response: Vec<GoalResponse> = goals
         .into_iter()
         .map(|g| GoalResponse {
@@ -189,23 +487,67 @@ async fn list_goals(State(state): State<MgmtState>) -> Json<Vec<GoalResponse>> {
             description: g.description,
             status: g.status,
             priority: g.priority,
+            labels: g.tags,
             created_at: g.created_at,
         })
         .collect();
-    Json(response)
+    Ok(([("x-total-count", total.to_string())], Json(response)))
+}
+
+/// Add or remove labels on a goal
+async fn update_goal_labels(
+    State(state): State<MgmtState>,
+    Path(goal_id): Path<String>,
+    Json(req): Json<UpdateLabelsRequest>,
+) -> Result<Json<Vec<String>>, StatusCode> {
+    let mut s =
+        crate::liveness::write_state(&state.orchestrator, "console.update_goal_labels").await;
+    s.goal_engine
+        .update_labels(&goal_id, &req.add, &req.remove)
+        .map(|labels| {
+            state.read_model.invalidate();
+            Json(labels)
+        })
+        .map_err(|_| StatusCode::NOT_FOUND)
+}
+
+/// List all labels in use with their goal counts
+async fn list_labels(
+    State(state): State<MgmtState>,
+    Query(page): Query<PageParams>,
+) -> Result<Page<LabelResponse>, StatusCode> {
+    let s = state.read_model.current();
+    let response = s
+        .goals
+        .label_counts()
+        .into_iter()
+        .map(|(label, goal_count)| LabelResponse { label, goal_count })
+        .collect();
+    paged(
+        response,
+        &page,
+        &["label", "goal_count"],
+        |a, b, field| match field {
+            "goal_count" => a.goal_count.cmp(&b.goal_count),
+            _ => a.label.cmp(&b.label),
+        },
+    )
 }
 
 /// Get tasks and their outputs for a specific goal
 async fn get_goal_tasks(
     State(state): State<MgmtState>,
     Path(goal_id): Path<String>,
-) -> Result<Json<Vec<GoalTaskResponse>>, StatusCode> {
-    let s = state.orchestrator.read().await;
-    match s.goal_engine.get_goal_with_tasks(&goal_id).await {
+    Query(page): Query<PageParams>,
+) -> Result<Page<GoalTaskResponse>, StatusCode> {
+    let s = state.read_model.current();
+    match s.goals.get_goal_with_tasks(&goal_id).await {
         Ok((_goal, tasks)) => {
+            let graph = crate::task_planner::dependency_graph(&tasks);
             let response: Vec<GoalTaskResponse> = tasks
                 .into_iter()
-                .map(|t| {
+                .zip(graph)
+                .map(|(t, dep)| {
                     let output_text = String::from_utf8_lossy(&t.output_json).to_string();
                     // Try to extract the actual AI response text from JSON
                     let display_output = extract_ai_response(&output_text);
@@ -219,10 +561,21 @@ async fn get_goal_tasks(
                         error: t.error,
                         created_at: t.created_at,
                         completed_at: t.completed_at,
+                        depends_on: dep.depends_on,
+                        blocked_by: dep.blocked_by,
+                        depth: dep.depth,
                     }
                 })
                 .collect();
-            Ok(Json(response))
+            paged(
+                response,
+                &page,
+                &["created_at", "status"],
+                |a, b, field| match field {
+                    "status" => a.status.cmp(&b.status),
+                    _ => a.created_at.cmp(&b.created_at),
+                },
+            )
         }
         Err(_) => Err(StatusCode::NOT_FOUND),
     }
@@ -232,9 +585,10 @@ async fn get_goal_tasks(
 async fn get_goal_messages(
     State(state): State<MgmtState>,
     Path(goal_id): Path<String>,
-) -> Json<Vec<GoalMessageResponse>> {
-    let s = state.orchestrator.read().await;
-    let messages = s.goal_engine.get_messages(&goal_id);
+    Query(page): Query<PageParams>,
+) -> Result<Page<GoalMessageResponse>, StatusCode> {
+    let s = state.read_model.current();
+    let messages = s.goals.get_messages(&goal_id);
     let response: Vec<GoalMessageResponse> = messages
         .into_iter()
         .map(|m| GoalMessageResponse {
@@ -244,7 +598,210 @@ async fn get_goal_messages(
             timestamp: m.timestamp,
         })
         .collect();
-    Json(response)
+    paged(response, &page, &["timestamp"], |a, b, _| {
+        a.timestamp.cmp(&b.timestamp)
+    })
+}
+
+/// Get the state transition history of a goal and its tasks
+async fn get_goal_timeline(
+    State(state): State<MgmtState>,
+    Path(goal_id): Path<String>,
+    Query(page): Query<PageParams>,
+) -> Result<Page<TimelineEntryResponse>, StatusCode> {
+    let s = state.read_model.current();
+    if s.goals.get_goal_with_tasks(&goal_id).await.is_err() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    let response: Vec<TimelineEntryResponse> = s
+        .goals
+        .get_timeline(&goal_id)
+        .into_iter()
+        .map(|t| TimelineEntryResponse {
+            entity_type: t.entity_type,
+            entity_id: t.entity_id,
+            from_status: t.from_status,
+            to_status: t.to_status,
+            cause: t.cause,
+            actor: t.actor,
+            timestamp: t.timestamp,
+        })
+        .collect();
+    paged(response, &page, &["timestamp"], |a, b, _| {
+        a.timestamp.cmp(&b.timestamp)
+    })
+}
+
+/// Artifacts the goal's tasks produced, oldest first
+async fn get_goal_artifacts(
+    State(state): State<MgmtState>,
+    Path(goal_id): Path<String>,
+) -> Result<Json<Vec<crate::artifacts::ArtifactRecord>>, StatusCode> {
+    let artifacts = state.orchestrator.read().await.artifacts.clone();
+    artifacts
+        .for_goal(&goal_id)
+        .map(Json)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+#[derive(Serialize)]
+struct GoalUsageResponse {
+    /// What the goal's own tasks used
+    own: crate::usage::GoalUsage,
+    /// Including its subgoals
+    total: crate::usage::GoalUsage,
+}
+
+/// Resources the goal has used, on its own and with its subgoals
+async fn get_goal_usage(
+    State(state): State<MgmtState>,
+    Path(goal_id): Path<String>,
+) -> Result<Json<GoalUsageResponse>, StatusCode> {
+    let s = state.read_model.current();
+    if s.goals.goal_status(&goal_id).is_none() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    Ok(Json(GoalUsageResponse {
+        own: s.usage.own(&goal_id),
+        total: s.usage.total(&s.goals, &goal_id),
+    }))
+}
+
+#[derive(Serialize)]
+struct GoalCostResponse {
+    /// What the goal and its subgoals used
+    total: crate::usage::GoalUsage,
+    /// Budget the goal's calls are held to: its own or its nearest ancestor's
+    budget: Option<GoalBudgetInfo>,
+    /// The goal's own gateway calls by provider; empty when the gateway
+    /// cannot be reached
+    providers: Vec<ProviderCostInfo>,
+}
+
+#[derive(Serialize)]
+struct GoalBudgetInfo {
+    goal_id: String,
+    max_tokens: i64,
+    max_usd: f64,
+    on_exceeded: String,
+    spent_tokens: i64,
+    spent_usd: f64,
+    exceeded: bool,
+}
+
+#[derive(Serialize)]
+struct ProviderCostInfo {
+    provider: String,
+    tokens_used: i64,
+    cost_usd: f64,
+    requests: i32,
+}
+
+/// What the goal cost, against its budget, with the gateway's attribution
+/// of its calls to providers
+async fn get_goal_cost(
+    State(state): State<MgmtState>,
+    Path(goal_id): Path<String>,
+) -> Result<Json<GoalCostResponse>, StatusCode> {
+    let (total, budget) = {
+        let s = state.read_model.current();
+        if s.goals.goal_status(&goal_id).is_none() {
+            return Err(StatusCode::NOT_FOUND);
+        }
+        (
+            s.usage.total(&s.goals, &goal_id),
+            crate::usage::goal_budget(&s.usage, &s.goals, &goal_id),
+        )
+    };
+
+    let gateway = match state.clients.api_gateway().await {
+        Ok(mut client) => client
+            .get_goal_cost(crate::proto::api_gateway::GoalCostRequest {
+                goal_id: goal_id.clone(),
+            })
+            .await
+            .map(|r| r.into_inner())
+            .map_err(|e| warn!("Cannot get cost of goal {goal_id} from the API gateway: {e}"))
+            .ok(),
+        Err(e) => {
+            warn!("Cannot connect to API gateway for goal cost: {e}");
+            None
+        }
+    }
+    .unwrap_or_default();
+
+    let budget = budget.map(|b| GoalBudgetInfo {
+        exceeded: gateway.budget_exceeded
+            || (b.max_tokens > 0 && b.spent_tokens >= b.max_tokens)
+            || (b.max_usd > 0.0 && b.spent_usd >= b.max_usd),
+        goal_id: b.goal_id,
+        max_tokens: b.max_tokens,
+        max_usd: b.max_usd,
+        on_exceeded: b.on_exceeded,
+        spent_tokens: b.spent_tokens,
+        spent_usd: b.spent_usd,
+    });
+    Ok(Json(GoalCostResponse {
+        total,
+        budget,
+        providers: gateway
+            .providers
+            .into_iter()
+            .map(|p| ProviderCostInfo {
+                provider: p.provider,
+                tokens_used: p.tokens_used,
+                cost_usd: p.cost_usd,
+                requests: p.requests,
+            })
+            .collect(),
+    }))
+}
+
+#[derive(Serialize)]
+struct GoalUsageEntry {
+    goal_id: String,
+    description: String,
+    status: String,
+    /// Including subgoals
+    usage: crate::usage::GoalUsage,
+}
+
+/// Root goals of the snapshot with their usage, most expensive first
+async fn goal_usage_entries(s: &crate::read_model::ConsoleSnapshot) -> Vec<GoalUsageEntry> {
+    let mut entries = Vec::new();
+    for (goal_id, usage) in s.usage.ranking(&s.goals) {
+        let (description, status) = match s.goals.get_goal_with_tasks(&goal_id).await {
+            Ok((goal, _)) => (goal.description, goal.status),
+            Err(_) => (String::new(), "unknown".to_string()),
+        };
+        entries.push(GoalUsageEntry {
+            goal_id,
+            description,
+            status,
+            usage,
+        });
+    }
+    entries
+}
+
+/// Root goals with the resources they and their subgoals used, most
+/// expensive first
+async fn list_goal_usage(
+    State(state): State<MgmtState>,
+    Query(page): Query<PageParams>,
+) -> Result<Page<GoalUsageEntry>, StatusCode> {
+    let entries = goal_usage_entries(&state.read_model.current()).await;
+    paged(
+        entries,
+        &page,
+        &["cost_usd", "tokens_used", "tool_cpu_ms", "wall_clock_ms"],
+        |a, b, field| match field {
+            "tokens_used" => a.usage.tokens_used.cmp(&b.usage.tokens_used),
+            "tool_cpu_ms" => a.usage.tool_cpu_ms.cmp(&b.usage.tool_cpu_ms),
+            "wall_clock_ms" => a.usage.wall_clock_ms.cmp(&b.usage.wall_clock_ms),
+            _ => a.usage.cost_usd.total_cmp(&b.usage.cost_usd),
+        },
+    )
 }
 
 /// Post a user message to a goal and resume awaiting tasks
@@ -253,7 +810,8 @@ async fn post_goal_message(
     Path(goal_id): Path<String>,
     Json(req): Json<PostMessageRequest>,
 ) -> Result<Json<GoalMessageResponse>, StatusCode> {
-    let mut s = state.orchestrator.write().await;
+    let mut s =
+        crate::liveness::write_state(&state.orchestrator, "console.post_goal_message").await;
 
     let msg_id = s.goal_engine.add_message(&goal_id, "user", &req.content);
     let timestamp = chrono::Utc::now().timestamp();
@@ -269,8 +827,13 @@ async fn post_goal_message(
 
     for task_id in &awaiting_tasks {
         s.task_planner.resume_task(task_id);
-        s.goal_engine
-            .update_task_status(&goal_id, task_id, "pending");
+        s.goal_engine.update_task_status(
+            &goal_id,
+            task_id,
+            "pending",
+            "user replied to the goal",
+            "user",
+        );
     }
 
     if !awaiting_tasks.is_empty() {
@@ -278,7 +841,9 @@ async fn post_goal_message(
             "Resumed {} awaiting tasks for goal {goal_id}",
             awaiting_tasks.len()
         );
+        s.autonomy_waker.wake();
     }
+    state.read_model.invalidate();
 
     Ok(Json(GoalMessageResponse {
         id: msg_id,
@@ -288,12 +853,354 @@ async fn post_goal_message(
     }))
 }
 
+/// Staging runs of risky goals, most recent first
+async fn list_staging_runs(
+    State(state): State<MgmtState>,
+) -> Json<Vec<crate::staging::StagingRun>> {
+    let staging = state.orchestrator.read().await.staging.clone();
+    Json(staging.runs())
+}
+
+/// Replay a goal's verified staging run on this system
+async fn replay_staged_goal(
+    State(state): State<MgmtState>,
+    Path(goal_id): Path<String>,
+) -> Result<Json<crate::staging::StagingRun>, (StatusCode, String)> {
+    let (staging, clients) = {
+        let s = state.orchestrator.read().await;
+        (s.staging.clone(), s.clients.clone())
+    };
+    let run = staging
+        .replay(&clients, &goal_id)
+        .await
+        .map_err(|e| (StatusCode::CONFLICT, e.to_string()))?;
+
+    let replayed = run.calls.iter().filter(|c| c.replayed).count();
+    let message = match run.calls.iter().find(|c| !c.replay_error.is_empty()) {
+        Some(failed) => format!(
+            "Replay on this system stopped at call {replayed} ({}): {}",
+            failed.tool_name, failed.replay_error
+        ),
+        None => format!("Replayed {replayed} verified tool calls on this system."),
+    };
+    let mut s =
+        crate::liveness::write_state(&state.orchestrator, "console.replay_staged_goal").await;
+    s.goal_engine.add_message(&goal_id, "system", &message);
+    drop(s);
+    state.read_model.invalidate();
+    Ok(Json(run))
+}
+
+/// What the first-boot setup wizard recorded (GetSetupStatus)
+#[derive(Serialize)]
+struct SetupResponse {
+    completed: bool,
+    completed_at: i64,
+    steps: Vec<SetupStepResponse>,
+}
+
+#[derive(Serialize)]
+struct SetupStepResponse {
+    name: String,
+    done: bool,
+    detail: String,
+}
+
+async fn get_setup_status() -> Json<SetupResponse> {
+    let status = crate::setup_status::load(crate::setup_status::SETUP_STATE_PATH);
+    Json(SetupResponse {
+        completed: status.completed,
+        completed_at: status.completed_at,
+        steps: status
+            .steps
+            .into_iter()
+            .map(|s| SetupStepResponse {
+                name: s.name,
+                done: s.done,
+                detail: s.detail,
+            })
+            .collect(),
+    })
+}
+
+#[derive(Deserialize)]
+struct SignInRequest {
+    user: String,
+    password: String,
+}
+
+#[derive(Serialize)]
+struct SignInResponse {
+    user: String,
+    /// Token of `user:<user>`, sent as X-Aios-User-Token from then on
+    token: String,
+}
+
+/// Sign an operator in with an account from operators.toml, issuing its
+/// user identity a new token
+async fn sign_in(
+    State(state): State<MgmtState>,
+    Json(req): Json<SignInRequest>,
+) -> Result<Json<SignInResponse>, (StatusCode, String)> {
+    let user = req.user.trim().to_string();
+    let name = user.clone();
+    // Argon2 is deliberately slow
+    let valid = tokio::task::spawn_blocking(move || {
+        crate::operators::verify(crate::operators::OPERATORS_PATH, &name, &req.password)
+    })
+    .await
+    .unwrap_or(false);
+    if !valid {
+        warn!("Console sign-in refused for operator '{user}'");
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            "Unknown operator or wrong password".to_string(),
+        ));
+    }
+    let token = crate::identity::sign_in_user(&state.clients, &user)
+        .await
+        .map_err(|e| (StatusCode::BAD_GATEWAY, e.to_string()))?;
+    info!("Operator {user} signed in to the console");
+    Ok(Json(SignInResponse { user, token }))
+}
+
+/// Risky plans a peer reviewer escalated, waiting for an operator
+async fn list_escalated_reviews(
+    State(state): State<MgmtState>,
+) -> Json<Vec<crate::peer_review::Review>> {
+    let peer_review = state.orchestrator.read().await.peer_review.clone();
+    Json(peer_review.escalations())
+}
+
+/// Status of a refused decision: 403 for callers that are not users, 401
+/// for users that did not prove who they are
+fn decision_error(e: tonic::Status) -> (StatusCode, String) {
+    let status = match e.code() {
+        tonic::Code::PermissionDenied => StatusCode::FORBIDDEN,
+        tonic::Code::Unauthenticated => StatusCode::UNAUTHORIZED,
+        _ => StatusCode::BAD_GATEWAY,
+    };
+    (status, e.message().to_string())
+}
+
+fn no_escalated_review() -> (StatusCode, String) {
+    (
+        StatusCode::NOT_FOUND,
+        "No plan of that goal is waiting for an operator".to_string(),
+    )
+}
+
+/// Approve a goal's escalated plans; their tasks run them when they plan
+/// them again. Only a verified user decides.
+async fn approve_reviewed_plan(
+    State(state): State<MgmtState>,
+    Path(goal_id): Path<String>,
+) -> Result<Json<Vec<crate::peer_review::Review>>, (StatusCode, String)> {
+    let user = crate::identity::verified_user(&state.clients)
+        .await
+        .map_err(decision_error)?;
+    let mut s =
+        crate::liveness::write_state(&state.orchestrator, "console.approve_reviewed_plan").await;
+    let reviews = s.peer_review.approve(&goal_id);
+    if reviews.is_empty() {
+        return Err(no_escalated_review());
+    }
+    for review in &reviews {
+        s.decision_logger.log_decision(
+            "peer_review",
+            &review.opinions(),
+            "approved",
+            &format!(
+                "Task {}: {user} approved the plan over the reviewer's objection\n{}",
+                review.task_id,
+                review.risky_calls()
+            ),
+            "human",
+            &user,
+        );
+        s.task_planner.resume_task(&review.task_id);
+        s.goal_engine.update_task_status(
+            &goal_id,
+            &review.task_id,
+            "pending",
+            "operator approved the reviewed plan",
+            &user,
+        );
+    }
+    s.goal_engine.add_message(
+        &goal_id,
+        "system",
+        &format!("{user} approved the planned actions; they run when the task plans them again."),
+    );
+    s.autonomy_waker.wake();
+    drop(s);
+    state.read_model.invalidate();
+    Ok(Json(reviews))
+}
+
+/// Reject a goal's escalated plans, failing their tasks. Only a verified
+/// user decides.
+async fn reject_reviewed_plan(
+    State(state): State<MgmtState>,
+    Path(goal_id): Path<String>,
+) -> Result<Json<Vec<crate::peer_review::Review>>, (StatusCode, String)> {
+    let user = crate::identity::verified_user(&state.clients)
+        .await
+        .map_err(decision_error)?;
+    let mut s =
+        crate::liveness::write_state(&state.orchestrator, "console.reject_reviewed_plan").await;
+    let reviews = s.peer_review.reject(&goal_id);
+    if reviews.is_empty() {
+        return Err(no_escalated_review());
+    }
+    for review in &reviews {
+        s.decision_logger.log_decision(
+            "peer_review",
+            &review.opinions(),
+            "rejected",
+            &format!(
+                "Task {}: {user} rejected the plan\n{}",
+                review.task_id,
+                review.risky_calls()
+            ),
+            "human",
+            &user,
+        );
+        s.task_planner.fail_task(
+            &review.task_id,
+            &format!("Plan rejected by {user} after peer review"),
+        );
+        s.goal_engine.update_task_status(
+            &goal_id,
+            &review.task_id,
+            "failed",
+            "operator rejected the reviewed plan",
+            &user,
+        );
+    }
+    s.goal_engine.add_message(
+        &goal_id,
+        "system",
+        &format!("{user} rejected the planned actions; they will not run."),
+    );
+    drop(s);
+    state.read_model.invalidate();
+    Ok(Json(reviews))
+}
+
+/// A critical-risk tool execution parked until an operator decides
+#[derive(Serialize)]
+struct PendingToolApproval {
+    approval_id: String,
+    tool_name: String,
+    agent_id: String,
+    task_id: String,
+    reason: String,
+    input_json: String,
+    risk_level: String,
+    requested_at: i64,
+    /// 0 = never
+    expires_at: i64,
+}
+
+impl From<crate::proto::tools::PendingApproval> for PendingToolApproval {
+    fn from(a: crate::proto::tools::PendingApproval) -> Self {
+        Self {
+            approval_id: a.approval_id,
+            tool_name: a.tool_name,
+            agent_id: a.agent_id,
+            task_id: a.task_id,
+            reason: a.reason,
+            input_json: a.input_json,
+            risk_level: a.risk_level,
+            requested_at: a.requested_at,
+            expires_at: a.expires_at,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ToolApprovalDecision {
+    #[serde(default)]
+    reason: String,
+}
+
+/// Tool executions waiting for approval in the tools service
+async fn list_tool_approvals(
+    State(state): State<MgmtState>,
+) -> Result<Json<Vec<PendingToolApproval>>, (StatusCode, String)> {
+    let mut client = state
+        .clients
+        .tools()
+        .await
+        .map_err(|e| (StatusCode::BAD_GATEWAY, e.to_string()))?;
+    let list = client
+        .list_pending_approvals(crate::proto::common::Empty {})
+        .await
+        .map_err(|e| (StatusCode::BAD_GATEWAY, e.message().to_string()))?
+        .into_inner();
+    Ok(Json(list.approvals.into_iter().map(Into::into).collect()))
+}
+
+/// Let a parked tool execution run
+async fn approve_tool_execution(
+    State(state): State<MgmtState>,
+    Path(approval_id): Path<String>,
+) -> Result<Json<PendingToolApproval>, (StatusCode, String)> {
+    decide_tool_execution(&state, approval_id, String::new(), true).await
+}
+
+/// Fail a parked tool execution without running it
+async fn deny_tool_execution(
+    State(state): State<MgmtState>,
+    Path(approval_id): Path<String>,
+    body: Option<Json<ToolApprovalDecision>>,
+) -> Result<Json<PendingToolApproval>, (StatusCode, String)> {
+    let reason = body.map(|Json(b)| b.reason).unwrap_or_default();
+    decide_tool_execution(&state, approval_id, reason, false).await
+}
+
+/// The decider is the console user, carried to the tools service with the
+/// call along with the user's token; the tools service refuses deciders it
+/// cannot verify
+async fn decide_tool_execution(
+    state: &MgmtState,
+    approval_id: String,
+    reason: String,
+    approve: bool,
+) -> Result<Json<PendingToolApproval>, (StatusCode, String)> {
+    let mut client = state
+        .clients
+        .tools()
+        .await
+        .map_err(|e| (StatusCode::BAD_GATEWAY, e.to_string()))?;
+    let request = crate::proto::tools::ApprovalDecisionRequest {
+        approval_id,
+        decided_by: String::new(),
+        reason,
+    };
+    let response = if approve {
+        client.approve(request).await
+    } else {
+        client.deny(request).await
+    }
+    .map_err(decision_error)?
+    .into_inner();
+    match response.approval.filter(|_| response.found) {
+        Some(approval) => Ok(Json(approval.into())),
+        None => Err((
+            StatusCode::NOT_FOUND,
+            "No execution is waiting under that approval id".to_string(),
+        )),
+    }
+}
+
 /// Build a system context string with real state for the AI chat
 async fn build_system_context(state: &MgmtState) -> String {
-    let s = state.orchestrator.read().await;
+    let s = state.read_model.current();
     let health = state.health_checker.read().await;
     let health_status = health.get_all_status();
-    let uptime_secs = s.started_at.elapsed().as_secs();
+    let uptime_secs = s.uptime_seconds;
     let uptime_str = if uptime_secs >= 3600 {
         format!("{}h {}m", uptime_secs / 3600, (uptime_secs % 3600) / 60)
     } else {
@@ -301,11 +1208,11 @@ async fn build_system_context(state: &MgmtState) -> String {
     };
 
     // Gather all goals with their tasks
-    let (all_goals, total_goals) = s.goal_engine.list_goals("", 100, 0).await;
-    let active_goals = s.goal_engine.active_goal_count();
-    let pending_tasks = s.task_planner.pending_task_count();
-    let active_agents = s.agent_router.active_agent_count();
-    let agents = s.agent_router.list_agents().await;
+    let (all_goals, total_goals) = s.goals.list_goals("", 100, 0).await;
+    let active_goals = s.goals.active_goal_count();
+    let pending_tasks = s.pending_tasks;
+    let active_agents = s.active_agents;
+    let agents = &s.agents;
 
     let mut context = format!(
         r#"You are aiOS, an AI-native operating system where AI agents replace traditional system services. You ARE the operating system — you control init, scheduling, services, and all system operations autonomously.
@@ -332,7 +1239,7 @@ async fn build_system_context(state: &MgmtState) -> String {
     // Add registered agents
     if !agents.is_empty() {
         context.push_str("\n## Registered Agents\n");
-        for a in &agents {
+        for a in agents {
             context.push_str(&format!(
                 "- {} (type: {}, status: {}, capabilities: {})\n",
                 a.agent_id,
@@ -360,7 +1267,7 @@ async fn build_system_context(state: &MgmtState) -> String {
             ));
 
             // Get tasks for this goal
-            if let Ok((_g, tasks)) = s.goal_engine.get_goal_with_tasks(&goal.id).await {
+            if let Ok((_g, tasks)) = s.goals.get_goal_with_tasks(&goal.id).await {
                 if !tasks.is_empty() {
                     context.push_str("Tasks:\n");
                     for task in &tasks {
@@ -411,6 +1318,7 @@ You can manage this system through goals and tasks. When the user submits a goal
 - Be specific and factual — you have real data, use it
 - If the user asks you to do something, suggest they submit it as a goal in the Goals tab
 "#);
+    context.push_str(&state.language.prompt_instruction());
 
     context
 }
@@ -423,30 +1331,77 @@ async fn chat_handler(
     // Build rich system context with real state
     let system_prompt = build_system_context(&state).await;
 
-    let s = state.orchestrator.read().await;
+    let clients = state.orchestrator.read().await.clients.clone();
+
+    let (session_id, session) = if req.session_id.is_empty() {
+        (uuid::Uuid::new_v4().to_string(), None)
+    } else {
+        let session = crate::chat_sessions::load(&clients, &req.session_id).await;
+        (req.session_id.clone(), session)
+    };
+    let prompt = crate::chat_sessions::prompt(session.as_ref(), &req.message);
 
     // Try API gateway (Qwen3)
-    match s.clients.api_gateway().await {
+    match clients.api_gateway().await {
         Ok(mut client) => {
+            let asked_at = chrono::Utc::now().timestamp();
             let request = tonic::Request::new(crate::proto::api_gateway::ApiInferRequest {
-                prompt: req.message.clone(),
+                prompt,
                 system_prompt,
                 max_tokens: 4096,
                 temperature: 0.7,
                 preferred_provider: req.provider.clone(),
                 requesting_agent: "chat-console".to_string(),
+                workload: crate::workload::INTERACTIVE.to_string(),
                 task_id: String::new(),
                 allow_fallback: true,
+                response_schema: String::new(),
+                sections: Vec::new(),
+                session_id: String::new(),
+                turn_kind: String::new(),
+                model_class: String::new(),
+                goal_id: String::new(),
+                goal_budget: None,
+                bypass_cache: false,
             });
 
             match client.infer(request).await {
                 Ok(response) => {
                     let resp: crate::proto::common::InferenceResponse = response.into_inner();
+                    // Only answered messages join the session
+                    let exchange = [
+                        crate::proto::memory::ChatMessage {
+                            session_id: session_id.clone(),
+                            role: crate::chat_sessions::USER.to_string(),
+                            content: req.message.clone(),
+                            model: String::new(),
+                            tokens: 0,
+                            timestamp: asked_at,
+                        },
+                        crate::proto::memory::ChatMessage {
+                            session_id: session_id.clone(),
+                            role: crate::chat_sessions::ASSISTANT.to_string(),
+                            content: resp.text.clone(),
+                            model: resp.model_used.clone(),
+                            tokens: resp.tokens_used,
+                            timestamp: chrono::Utc::now().timestamp(),
+                        },
+                    ];
+                    for message in exchange {
+                        crate::chat_sessions::append(&clients, message).await;
+                    }
+                    let summary_clients = clients.clone();
+                    let summary_session = session_id.clone();
+                    tokio::spawn(async move {
+                        crate::chat_sessions::summarize(&summary_clients, &summary_session).await;
+                    });
+
                     Ok(Json(ChatResponse {
                         reply: resp.text,
                         model: resp.model_used,
                         tokens: resp.tokens_used,
                         latency_ms: resp.latency_ms,
+                        session_id,
                     }))
                 }
                 Err(e) => {
@@ -456,6 +1411,7 @@ async fn chat_handler(
                         model: "error".into(),
                         tokens: 0,
                         latency_ms: 0,
+                        session_id,
                     }))
                 }
             }
@@ -467,37 +1423,116 @@ async fn chat_handler(
                 model: "error".into(),
                 tokens: 0,
                 latency_ms: 0,
+                session_id,
             }))
         }
     }
 }
 
+/// Chat sessions, most recently active first
+async fn list_chat_sessions(
+    State(state): State<MgmtState>,
+    Query(page): Query<PageParams>,
+) -> Result<Page<ChatSessionResponse>, StatusCode> {
+    let sessions = crate::chat_sessions::list(&state.clients, pagination::REST_MAX_LIMIT).await;
+    let response = sessions
+        .into_iter()
+        .map(ChatSessionResponse::from)
+        .collect();
+    paged(
+        response,
+        &page,
+        &["updated_at", "created_at", "title"],
+        |a, b, field| match field {
+            "created_at" => a.created_at.cmp(&b.created_at),
+            "title" => a.title.cmp(&b.title),
+            _ => a.updated_at.cmp(&b.updated_at),
+        },
+    )
+}
+
+/// One chat session with its messages, to resume it
+async fn get_chat_session(
+    State(state): State<MgmtState>,
+    Path(session_id): Path<String>,
+) -> Result<Json<ChatSessionResponse>, StatusCode> {
+    crate::chat_sessions::load(&state.clients, &session_id)
+        .await
+        .map(|session| Json(session.into()))
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
 async fn submit_goal(
     State(state): State<MgmtState>,
     Json(req): Json<SubmitGoalRequest>,
 ) -> Result<Json<SubmitGoalResponse>, StatusCode> {
-    let mut s = state.orchestrator.write().await;
+    let mut s = crate::liveness::write_state(&state.orchestrator, "console.submit_goal").await;
     let description = req.description.clone();
     let provider = req.provider.clone();
+    let language = if req.language.is_empty() {
+        None
+    } else {
+        Some(crate::locale::Language::parse(&req.language).ok_or(StatusCode::BAD_REQUEST)?)
+    };
+    if !["", "downgrade", "fail"].contains(&req.budget_policy.as_str()) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
     match s
         .goal_engine
         .submit_goal(req.description, req.priority, "management-console".into())
         .await
     {
         Ok(id) => {
-            // Store preferred provider in goal metadata
+            // Store preferred provider and language in goal metadata
+            let mut metadata = serde_json::Map::new();
             if !provider.is_empty() {
-                let metadata = format!("{{\"preferred_provider\":\"{provider}\"}}");
+                metadata.insert("preferred_provider".into(), provider.clone().into());
+            }
+            if let Some(language) = language {
+                metadata.insert("language".into(), language.code().into());
+            }
+            if let Some(tokens) = req.budget_tokens {
+                metadata.insert("budget_tokens".into(), tokens.into());
+            }
+            if let Some(usd) = req.budget_usd {
+                metadata.insert("budget_usd".into(), usd.into());
+            }
+            if !req.budget_policy.is_empty() {
+                metadata.insert("budget_policy".into(), req.budget_policy.into());
+            }
+            if !metadata.is_empty() {
+                let metadata = serde_json::Value::Object(metadata).to_string();
                 s.goal_engine.set_metadata(&id, metadata.into_bytes());
             }
+            if !req.labels.is_empty() {
+                let _ = s.goal_engine.update_labels(&id, &req.labels, &[]);
+            }
 
             // Decompose goal into executable tasks so the autonomy loop can process them
-            match s.task_planner.decompose_goal(&id, &description).await {
+            let charge = crate::usage::Charge::new(&s.usage, &s.goal_engine, &id);
+            let span = tracing::info_span!("decompose_goal", goal_id = %id);
+            s.traces.attach(&s.goal_engine, &id, &span);
+            match crate::workload::scope(
+                crate::workload::INTERACTIVE,
+                crate::usage::scope(
+                    charge,
+                    s.task_planner
+                        .decompose_goal(&id, &description)
+                        .instrument(span),
+                ),
+            )
+            .await
+            {
                 Ok(tasks) => {
                     let task_count = tasks.len();
                     s.goal_engine.add_tasks(&id, tasks);
                     if task_count > 0 {
-                        s.goal_engine.update_status(&id, "in_progress");
+                        s.goal_engine.update_status(
+                            &id,
+                            "in_progress",
+                            &format!("decomposed into {task_count} tasks"),
+                            "management-console",
+                        );
                     }
                     info!("Goal {id} decomposed into {task_count} tasks (provider: {provider})");
                 }
@@ -505,15 +1540,56 @@ async fn submit_goal(
                     warn!("Failed to decompose goal {id}: {e}");
                 }
             }
+            s.autonomy_waker.wake();
+            state.read_model.invalidate();
             Ok(Json(SubmitGoalResponse { goal_id: id }))
         }
         Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
     }
 }
 
-async fn list_agents(State(state): State<MgmtState>) -> Json<Vec<AgentResponse>> {
-    let s = state.orchestrator.read().await;
-    let agents = s.agent_router.list_agents().await;
+/// Templates of the read-only "explain this system to me" goal pack
+async fn list_onboarding_templates() -> Json<&'static [crate::onboarding::Template]> {
+    Json(crate::onboarding::TEMPLATES)
+}
+
+/// Start the onboarding goal pack as a read-only goal
+async fn start_onboarding(
+    State(state): State<MgmtState>,
+    Json(req): Json<StartOnboardingRequest>,
+) -> Result<Json<SubmitGoalResponse>, (StatusCode, String)> {
+    let language = if req.language.is_empty() {
+        None
+    } else {
+        let language = crate::locale::Language::parse(&req.language).ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("Unsupported language: {}", req.language),
+            )
+        })?;
+        Some(language)
+    };
+    let mut s = crate::liveness::write_state(&state.orchestrator, "console.onboarding").await;
+    let goal_id = crate::onboarding::submit(&mut s, &req.templates, language, "management-console")
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    state.read_model.invalidate();
+    Ok(Json(SubmitGoalResponse { goal_id }))
+}
+
+async fn list_agents(
+    State(state): State<MgmtState>,
+    Query(filter): Query<AgentFilterParams>,
+    Query(page): Query<PageParams>,
+) -> Result<Page<AgentResponse>, StatusCode> {
+    let s = state.read_model.current();
+    let agents = crate::agent_router::filter_agents(
+        s.agents.clone(),
+        &filter.status,
+        &filter.agent_type,
+        &page.sort,
+    )
+    .map_err(|_| StatusCode::BAD_REQUEST)?;
     let response: Vec<AgentResponse> = agents
         .into_iter()
         .map(|a| AgentResponse {
@@ -523,34 +1599,175 @@ async fn list_agents(State(state): State<MgmtState>) -> Json<Vec<AgentResponse>>
             capabilities: a.capabilities,
         })
         .collect();
-    Json(response)
+    let (response, total) = pagination::page(response, page.rest_limit(), page.offset);
+    Ok(([("x-total-count", total.to_string())], Json(response)))
 }
 
 async fn health_check(State(state): State<MgmtState>) -> Json<HealthResponse> {
-    let checker = state.health_checker.read().await;
-    let statuses = checker.get_all_status();
-
-    let mut services = vec![ServiceHealth {
-        name: "orchestrator".into(),
-        status: "healthy".into(),
-        latency_ms: 0,
-    }];
-
-    for svc in &statuses {
-        services.push(ServiceHealth {
-            name: svc.name.clone(),
-            status: if svc.healthy {
-                "healthy".into()
-            } else {
-                "unhealthy".into()
-            },
-            latency_ms: svc.last_check_ms,
-        });
-    }
+    let report = state.health_checker.read().await.report();
+    let services = report
+        .nodes
+        .into_iter()
+        .map(|n| ServiceHealth {
+            name: n.name,
+            kind: n.kind,
+            status: n.status,
+            latency_ms: n.latency_ms,
+            depends_on: n.depends_on,
+            last_error: n.last_error,
+            root_causes: n.root_causes,
+            cause: n.cause,
+        })
+        .collect();
 
-    let healthy = statuses.iter().all(|s| s.healthy);
+    // Read without the state lock, so a stalled loop still reports
+    let autonomy = state.heartbeat.liveness();
+    let healthy = report.healthy && !autonomy.stalled;
+    let circuits = state
+        .clients
+        .circuit_states()
+        .into_iter()
+        .map(|(service, endpoint, state)| CircuitHealth {
+            service: service.into(),
+            endpoint,
+            state: state.into(),
+        })
+        .collect();
+
+    Json(HealthResponse {
+        healthy,
+        services,
+        autonomy,
+        circuits,
+    })
+}
 
-    Json(HealthResponse { healthy, services })
+/// Rolling latency percentiles, error counts and SLO burn rates per gRPC
+/// method the orchestrator calls
+async fn slo_report(State(state): State<MgmtState>) -> Json<Vec<crate::slo::MethodSlo>> {
+    Json(state.clients.slo().report())
+}
+
+/// Autonomy loop tick and wake-up latency metrics
+async fn autonomy_metrics(State(state): State<MgmtState>) -> Json<crate::autonomy::LoopMetrics> {
+    Json(state.read_model.current().autonomy.clone())
+}
+
+/// Per-tool invocation counts, success rates and typical inputs
+async fn tool_usage(
+    State(state): State<MgmtState>,
+    Query(page): Query<PageParams>,
+) -> Result<Page<crate::tool_usage::ToolUsageSummary>, StatusCode> {
+    let usage = state.read_model.current().tool_usage.clone();
+    paged(
+        usage,
+        &page,
+        &["tool", "invocations", "success_rate"],
+        |a, b, field| match field {
+            "invocations" => a.invocations.cmp(&b.invocations),
+            "success_rate" => a.success_rate.total_cmp(&b.success_rate),
+            _ => a.tool.cmp(&b.tool),
+        },
+    )
+}
+
+#[derive(Serialize)]
+struct CalendarResponse {
+    /// Open and upcoming windows
+    windows: Vec<crate::calendar::CalendarWindow>,
+    /// Scheduled runs that fall inside blackouts
+    conflicts: Vec<crate::calendar::ScheduleConflict>,
+}
+
+/// Calendar windows that have not ended yet and the schedule conflicts
+async fn calendar_windows(State(state): State<MgmtState>) -> Json<CalendarResponse> {
+    let s = state.read_model.current();
+    let now = chrono::Utc::now().timestamp();
+    Json(CalendarResponse {
+        windows: s
+            .calendar_windows
+            .iter()
+            .filter(|w| w.end > now)
+            .cloned()
+            .collect(),
+        conflicts: s.schedule_conflicts.clone(),
+    })
+}
+
+#[derive(Serialize)]
+struct NotificationsResponse {
+    /// Console notifications, newest first
+    notifications: Vec<crate::notifications::Notification>,
+    /// Deliveries to outside channels, newest first
+    deliveries: Vec<crate::notifications::DeliveryRecord>,
+}
+
+/// System language, the languages goals can choose, and the dashboard's
+/// strings in the system language
+async fn get_locale(State(state): State<MgmtState>) -> Json<LocaleResponse> {
+    use crate::locale::Language;
+    Json(LocaleResponse {
+        language: state.language.code(),
+        language_name: state.language.native_name(),
+        languages: Language::ALL
+            .iter()
+            .map(|l| LanguageOption {
+                code: l.code(),
+                name: l.native_name(),
+            })
+            .collect(),
+        strings: state.language.ui_strings().into_iter().collect(),
+    })
+}
+
+/// Recent console notifications and outside deliveries
+async fn list_notifications(State(state): State<MgmtState>) -> Json<NotificationsResponse> {
+    Json(NotificationsResponse {
+        notifications: state.notifier.feed(),
+        deliveries: state.notifier.deliveries(),
+    })
+}
+
+/// Notification channels and routing rules
+async fn get_notification_config(
+    State(state): State<MgmtState>,
+) -> Json<crate::notifications::NotificationConfig> {
+    Json(state.notifier.config())
+}
+
+/// Replace the notification channels and routing rules
+async fn put_notification_config(
+    State(state): State<MgmtState>,
+    Json(config): Json<crate::notifications::NotificationConfig>,
+) -> Result<Json<crate::notifications::NotificationConfig>, (StatusCode, String)> {
+    state
+        .notifier
+        .set_config(config)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    Ok(Json(state.notifier.config()))
+}
+
+/// Weekly reports on disk, newest first
+async fn list_reports() -> Json<Vec<crate::report::ReportFile>> {
+    Json(crate::report::list_reports(crate::report::REPORTS_DIR))
+}
+
+/// One weekly report as HTML
+async fn get_report(Path(week): Path<String>) -> Result<axum::response::Html<String>, StatusCode> {
+    let path = crate::report::report_html_path(crate::report::REPORTS_DIR, &week)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let html = std::fs::read_to_string(path).map_err(|_| StatusCode::NOT_FOUND)?;
+    Ok(axum::response::Html(html))
+}
+
+/// Write and send the weekly report for the week ending now
+async fn generate_weekly_report(
+    State(state): State<MgmtState>,
+) -> Result<Json<crate::report::WeeklyReport>, (StatusCode, String)> {
+    crate::report::generate(&state.orchestrator, crate::report::REPORTS_DIR)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))
 }
 
 /// WebSocket handler for real-time updates
@@ -561,22 +1778,45 @@ async fn ws_handler(
     ws.on_upgrade(move |socket| handle_ws(socket, state))
 }
 
-/// Handle a WebSocket connection — push full state every 2 seconds, accept subscription commands
+/// Most expensive goals pushed to the System tab
+const EXPENSIVE_GOALS_SHOWN: usize = 10;
+
+/// Interval between full status updates
+const STATUS_UPDATE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Whether a goal change goes to a client watching `subscribed_goal`: goal
+/// level changes go to every client, task and message changes only to the
+/// clients watching their goal
+fn forward_change(change: &crate::goal_changes::GoalChange, subscribed_goal: Option<&str>) -> bool {
+    use crate::goal_changes::GoalChange;
+    match change {
+        GoalChange::TaskStatusChanged { .. } | GoalChange::MessageAdded { .. } => {
+            subscribed_goal == Some(change.goal_id())
+        }
+        _ => true,
+    }
+}
+
+/// Handle a WebSocket connection — forward goal changes as they happen,
+/// push full state every 2 seconds, accept subscription commands
 async fn handle_ws(mut socket: WebSocket, state: MgmtState) {
     info!("WebSocket client connected");
 
-    // Track which goal the client is watching
+    // Track which goal the client is watching and how it filters the goal list
     let mut subscribed_goal: Option<String> = None;
+    let mut goal_filter = GoalFilterParams::default();
+    let mut changes = state.changes.subscribe();
+    let mut changes_open = true;
 
-    loop {
+    'connection: loop {
         // Gather current status + goals + subscribed goal chat
         let update = {
-            let s = state.orchestrator.read().await;
+            let s = state.read_model.current();
             let health = state.health_checker.read().await;
             let health_status = health.get_all_status();
 
             // Build goals list
-            let (goals, _) = s.goal_engine.list_goals("", 50, 0).await;
+            let (goals, goals_total) = s.goals.search_goals(&goal_filter.to_query(), 50, 0).await;
             let goals_json: Vec<serde_json::Value> = goals
                 .iter()
                 .map(|g| {
@@ -585,14 +1825,15 @@ async fn handle_ws(mut socket: WebSocket, state: MgmtState) {
                         "description": g.description,
                         "status": g.status,
                         "priority": g.priority,
+                        "labels": g.tags,
                         "created_at": g.created_at,
                     })
                 })
                 .collect();
 
             // Build agents list
-            let agents = s.agent_router.list_agents().await;
-            let agents_json: Vec<serde_json::Value> = agents
+            let agents_json: Vec<serde_json::Value> = s
+                .agents
                 .iter()
                 .map(|a| {
                     serde_json::json!({
@@ -606,7 +1847,7 @@ async fn handle_ws(mut socket: WebSocket, state: MgmtState) {
 
             // Build goal chat if subscribed
             let goal_chat = if let Some(ref gid) = subscribed_goal {
-                let messages = s.goal_engine.get_messages(gid);
+                let messages = s.goals.get_messages(gid);
                 let messages_json: Vec<serde_json::Value> = messages
                     .iter()
                     .map(|m| {
@@ -619,7 +1860,7 @@ async fn handle_ws(mut socket: WebSocket, state: MgmtState) {
                     })
                     .collect();
 
-                let tasks_json = match s.goal_engine.get_goal_with_tasks(gid).await {
+                let tasks_json = match s.goals.get_goal_with_tasks(gid).await {
                     Ok((_goal, tasks)) => tasks
                         .iter()
                         .map(|t| {
@@ -641,10 +1882,29 @@ async fn handle_ws(mut socket: WebSocket, state: MgmtState) {
                     Err(_) => Vec::new(),
                 };
 
+                let timeline_json: Vec<serde_json::Value> = s
+                    .goals
+                    .get_timeline(gid)
+                    .iter()
+                    .map(|t| {
+                        serde_json::json!({
+                            "entity_type": t.entity_type,
+                            "entity_id": t.entity_id,
+                            "from_status": t.from_status,
+                            "to_status": t.to_status,
+                            "cause": t.cause,
+                            "actor": t.actor,
+                            "timestamp": t.timestamp,
+                        })
+                    })
+                    .collect();
+
                 Some(serde_json::json!({
                     "goal_id": gid,
                     "messages": messages_json,
                     "tasks": tasks_json,
+                    "timeline": timeline_json,
+                    "usage": s.usage.total(&s.goals, gid),
                 }))
             } else {
                 None
@@ -652,10 +1912,11 @@ async fn handle_ws(mut socket: WebSocket, state: MgmtState) {
 
             let mut update = serde_json::json!({
                 "type": "status_update",
-                "active_goals": s.goal_engine.active_goal_count(),
-                "pending_tasks": s.task_planner.pending_task_count(),
-                "active_agents": s.agent_router.active_agent_count(),
-                "uptime_seconds": s.started_at.elapsed().as_secs(),
+                "active_goals": s.goals.active_goal_count(),
+                "pending_tasks": s.pending_tasks,
+                "active_agents": s.active_agents,
+                "uptime_seconds": s.uptime_seconds,
+                "snapshot_at": s.taken_at,
                 "services": health_status.iter().map(|h| {
                     serde_json::json!({
                         "name": h.name,
@@ -664,7 +1925,16 @@ async fn handle_ws(mut socket: WebSocket, state: MgmtState) {
                     })
                 }).collect::<Vec<_>>(),
                 "goals": goals_json,
+                "goals_total": goals_total,
                 "agents": agents_json,
+                "autonomy": serde_json::to_value(&s.autonomy).unwrap_or_default(),
+                "slo": serde_json::to_value(state.clients.slo().report()).unwrap_or_default(),
+                "budget": serde_json::to_value(&s.budget).unwrap_or_default(),
+                "expensive_goals": goal_usage_entries(&s)
+                    .await
+                    .into_iter()
+                    .take(EXPENSIVE_GOALS_SHOWN)
+                    .collect::<Vec<_>>(),
             });
 
             if let Some(chat) = goal_chat {
@@ -685,27 +1955,53 @@ async fn handle_ws(mut socket: WebSocket, state: MgmtState) {
             break;
         }
 
-        // Check for client messages (subscribe, ping, close) with 2s timeout
-        match tokio::time::timeout(std::time::Duration::from_secs(2), socket.recv()).await {
-            Ok(Some(Ok(Message::Text(text)))) => {
-                // Handle subscription commands from the client
-                if let Ok(msg) = serde_json::from_str::<serde_json::Value>(&text) {
-                    match msg.get("type").and_then(|v| v.as_str()) {
-                        Some("subscribe_goal") => {
-                            if let Some(gid) = msg.get("goal_id").and_then(|v| v.as_str()) {
-                                subscribed_goal = Some(gid.to_string());
-                            }
+        // Until the next full update: forward goal changes, and handle
+        // client messages (subscribe, ping, close); a command is answered
+        // with a full update at once
+        let next_update = tokio::time::Instant::now() + STATUS_UPDATE_INTERVAL;
+        loop {
+            tokio::select! {
+                change = changes.recv(), if changes_open => match change {
+                    Ok(change) => {
+                        if !forward_change(&change, subscribed_goal.as_deref()) {
+                            continue;
                         }
-                        Some("unsubscribe_goal") => {
-                            subscribed_goal = None;
+                        let json = serde_json::to_string(&change).unwrap_or_default();
+                        if socket.send(Message::Text(json)).await.is_err() {
+                            break 'connection;
                         }
-                        _ => {}
                     }
-                }
+                    // Missed changes are caught up by a full update
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => break,
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => changes_open = false,
+                },
+                msg = socket.recv() => match msg {
+                    Some(Ok(Message::Text(text))) => {
+                        // Handle subscription commands from the client
+                        if let Ok(msg) = serde_json::from_str::<serde_json::Value>(&text) {
+                            match msg.get("type").and_then(|v| v.as_str()) {
+                                Some("subscribe_goal") => {
+                                    if let Some(gid) = msg.get("goal_id").and_then(|v| v.as_str()) {
+                                        subscribed_goal = Some(gid.to_string());
+                                    }
+                                }
+                                Some("unsubscribe_goal") => {
+                                    subscribed_goal = None;
+                                }
+                                Some("set_goal_filter") => {
+                                    goal_filter =
+                                        serde_json::from_value(msg.clone()).unwrap_or_default();
+                                }
+                                _ => {}
+                            }
+                        }
+                        break;
+                    }
+                    Some(Ok(Message::Close(_))) | None | Some(Err(_)) => break 'connection,
+                    Some(Ok(_)) => {}
+                },
+                _ = tokio::time::sleep_until(next_update) => break,
             }
-            Ok(Some(Ok(Message::Close(_)))) | Ok(None) => break,
-            Ok(Some(Err(_))) => break,
-            _ => {} // Timeout — continue pushing
         }
     }
 
@@ -783,6 +2079,7 @@ const DASHBOARD_HTML: &str = r##"<!DOCTYPE html>
         textarea:focus, input:focus { outline: none; border-color: #00d4ff; }
         .ws-status { font-size: 0.7em; color: #6b7280; vertical-align: middle; }
         .ws-connected { color: #00ff88; }
+        .budget-banner { display: none; border-color: #f59e0b; color: #fbbf24; }
         .svc-healthy { color: #00ff88; }
         .svc-unhealthy { color: #ff4444; }
         .status-completed { color: #00ff88; font-weight: bold; }
@@ -817,50 +2114,61 @@ const DASHBOARD_HTML: &str = r##"<!DOCTYPE html>
         .tab.active { background: #111827; color: #00d4ff; border-color: #1e3a5f; }
         .tab-content { display: none; }
         .tab-content.active { display: block; }
+        .label-chip { display: inline-block; background: #1e3a5f; color: #00d4ff; padding: 1px 8px; margin: 1px 2px; border-radius: 10px; font-size: 0.8em; cursor: pointer; }
+        .label-chip:hover { background: #00d4ff; color: #0a0e1a; }
+        .filter-bar { display: flex; gap: 8px; align-items: center; margin-bottom: 8px; flex-wrap: wrap; }
+        .filter-bar input { width: auto; flex: 1; min-width: 140px; padding: 8px 10px; }
+        .timeline-row td { font-size: 0.8em; padding: 4px 8px; }
+        .timeline-arrow { color: #4b5563; }
         .grid-2 { display: grid; grid-template-columns: 1fr 1fr; gap: 10px; }
         @media (max-width: 900px) { .grid-2 { grid-template-columns: 1fr; } }
     </style>
 </head>
 <body>
     <h1>aiOS Management Console <span class="ws-status" id="ws-status">connecting...</span></h1>
-    <p style="color:#4b5563;margin-top:0">Autonomous AI Operating System</p>
+    <p style="color:#4b5563;margin-top:0" data-i18n="subtitle">Autonomous AI Operating System</p>
 
     <div class="card">
-        <div class="metric"><div class="metric-value" id="goals">-</div><div class="metric-label">Active Goals</div></div>
-        <div class="metric"><div class="metric-value" id="tasks">-</div><div class="metric-label">Pending Tasks</div></div>
-        <div class="metric"><div class="metric-value" id="agents">-</div><div class="metric-label">Active Agents</div></div>
-        <div class="metric"><div class="metric-value" id="uptime">-</div><div class="metric-label">Uptime</div></div>
-        <div class="metric"><div class="metric-value" style="color:#00ff88" id="sys-status">-</div><div class="metric-label">System Status</div></div>
+        <div class="metric"><div class="metric-value" id="goals">-</div><div class="metric-label" data-i18n="active_goals">Active Goals</div></div>
+        <div class="metric"><div class="metric-value" id="tasks">-</div><div class="metric-label" data-i18n="pending_tasks">Pending Tasks</div></div>
+        <div class="metric"><div class="metric-value" id="agents">-</div><div class="metric-label" data-i18n="active_agents">Active Agents</div></div>
+        <div class="metric"><div class="metric-value" id="uptime">-</div><div class="metric-label" data-i18n="uptime">Uptime</div></div>
+        <div class="metric"><div class="metric-value" style="color:#00ff88" id="sys-status">-</div><div class="metric-label" data-i18n="system_status">System Status</div></div>
+        <div class="metric"><div class="metric-value" id="budget">-</div><div class="metric-label" data-i18n="budget_spent">API Budget Spent</div></div>
     </div>
+    <div class="card budget-banner" id="budget-banner"></div>
 
     <div class="tabs">
-        <div class="tab active" onclick="switchTab('chat')">Chat</div>
-        <div class="tab" onclick="switchTab('goals-tab')">Goals & Tasks</div>
-        <div class="tab" onclick="switchTab('system')">System</div>
+        <div class="tab active" onclick="switchTab('chat')" data-i18n="tab_chat">Chat</div>
+        <div class="tab" onclick="switchTab('goals-tab')" data-i18n="tab_goals">Goals & Tasks</div>
+        <div class="tab" onclick="switchTab('system')" data-i18n="tab_system">System</div>
     </div>
 
     <!-- CHAT TAB -->
     <div class="card tab-content active" id="chat" style="border-radius: 0 8px 8px 8px">
         <div class="chat-container">
             <div class="provider-bar">
-                <label>Model:</label>
+                <label data-i18n="model">Model:</label>
                 <select id="provider-select">
-                    <option value="">Auto (best available)</option>
+                    <option value="" data-i18n="auto_model">Auto (best available)</option>
                     <option value="claude">Claude Sonnet 4</option>
                     <option value="openai">ChatGPT 5</option>
                     <option value="qwen3">Qwen3 30B</option>
                 </select>
                 <span id="provider-status" style="color:#6b7280;font-size:0.85em"></span>
+                <label data-i18n="conversation">Conversation:</label>
+                <select id="chat-session-select" onchange="resumeChat(this.value)"></select>
+                <button onclick="newChat()" data-i18n="new_chat">New chat</button>
             </div>
             <div class="chat-messages" id="chat-messages">
                 <div class="msg msg-ai">
                     <div class="msg-label">aiOS</div>
-                    <div class="msg-content">Hello! I'm aiOS, your AI operating system. Select a model above and ask me anything.</div>
+                    <div class="msg-content" data-i18n="chat_welcome">Hello! I'm aiOS, your AI operating system. Select a model above and ask me anything.</div>
                 </div>
             </div>
             <div class="chat-input-row">
-                <textarea id="chat-input" rows="2" placeholder="Ask aiOS anything..." onkeydown="if(event.key==='Enter'&&!event.shiftKey){event.preventDefault();sendChat()}"></textarea>
-                <button id="chat-send-btn" onclick="sendChat()">Send</button>
+                <textarea id="chat-input" rows="2" placeholder="Ask aiOS anything..." data-i18n-placeholder="chat_placeholder" onkeydown="if(event.key==='Enter'&&!event.shiftKey){event.preventDefault();sendChat()}"></textarea>
+                <button id="chat-send-btn" onclick="sendChat()" data-i18n="send">Send</button>
             </div>
         </div>
     </div>
@@ -869,36 +2177,68 @@ const DASHBOARD_HTML: &str = r##"<!DOCTYPE html>
     <div class="card tab-content" id="goals-tab" style="border-radius: 0 8px 8px 8px">
         <div class="grid-2">
             <div>
-                <h2>Submit Goal</h2>
-                <textarea id="goal-input" rows="2" placeholder="Describe what you want the system to do..."></textarea>
+                <h2 data-i18n="submit_goal">Submit Goal</h2>
+                <textarea id="goal-input" rows="2" placeholder="Describe what you want the system to do..." data-i18n-placeholder="goal_placeholder"></textarea>
+                <input id="goal-labels-input" style="margin-top:8px" placeholder="Labels (comma-separated, optional)" data-i18n-placeholder="labels_placeholder">
                 <div class="provider-bar" style="margin-top:8px">
-                    <label>Model:</label>
+                    <label data-i18n="model">Model:</label>
                     <select id="goal-provider-select">
-                        <option value="">Auto (best available)</option>
+                        <option value="" data-i18n="auto_model">Auto (best available)</option>
                         <option value="claude">Claude Sonnet 4</option>
                         <option value="openai">ChatGPT 5</option>
                         <option value="qwen3">Qwen3 30B</option>
                     </select>
+                    <label data-i18n="language">Language:</label>
+                    <select id="goal-language-select">
+                        <option value="" data-i18n="system_language">System language</option>
+                    </select>
                 </div>
-                <button onclick="submitGoal()" id="goal-submit-btn">Submit Goal</button>
+                <button onclick="submitGoal()" id="goal-submit-btn" data-i18n="submit_goal">Submit Goal</button>
+                <button onclick="startOnboarding()" id="onboarding-btn" title="Read-only: inventory, running services and risky findings, written up as a report" data-i18n="explain_system">Explain this system</button>
                 <span id="goal-result" style="margin-left:10px;color:#6b7280"></span>
             </div>
             <div>
-                <h2>Goal Chat</h2>
+                <h2 data-i18n="goal_chat">Goal Chat</h2>
                 <div id="goal-chat-area" style="min-height:300px;max-height:500px;overflow-y:auto;background:#0d1117;border:1px solid #1e3a5f;border-radius:6px;padding:10px">
-                    <div style="color:#6b7280;text-align:center;padding:40px 0">Click on a goal to see its progress and chat...</div>
+                    <div style="color:#6b7280;text-align:center;padding:40px 0" data-i18n="goal_chat_empty">Click on a goal to see its progress and chat...</div>
+                </div>
+                <div class="filter-bar" style="margin-top:8px">
+                    <input id="goal-label-edit" placeholder="Edit labels of selected goal: urgent, -stale" onkeydown="if(event.key==='Enter'){event.preventDefault();editGoalLabels()}">
+                    <button onclick="editGoalLabels()" data-i18n="apply">Apply</button>
                 </div>
                 <div id="goal-reply-area" style="display:none;margin-top:8px">
-                    <div style="background:#332200;border:1px solid #ffa500;border-radius:4px;padding:8px;margin-bottom:8px;font-size:0.85em;color:#ffa500">AI is awaiting your input</div>
+                    <div style="background:#332200;border:1px solid #ffa500;border-radius:4px;padding:8px;margin-bottom:8px;font-size:0.85em;color:#ffa500" data-i18n="awaiting_input">AI is awaiting your input</div>
                     <div class="chat-input-row">
-                        <textarea id="goal-reply-input" rows="2" placeholder="Reply to AI..." onkeydown="if(event.key==='Enter'&&!event.shiftKey){event.preventDefault();sendGoalMessage()}"></textarea>
-                        <button onclick="sendGoalMessage()">Reply</button>
+                        <textarea id="goal-reply-input" rows="2" placeholder="Reply to AI..." data-i18n-placeholder="reply_placeholder" onkeydown="if(event.key==='Enter'&&!event.shiftKey){event.preventDefault();sendGoalMessage()}"></textarea>
+                        <button onclick="sendGoalMessage()" data-i18n="reply">Reply</button>
                     </div>
                 </div>
             </div>
         </div>
-        <h2 style="margin-top:16px">Goals</h2>
-        <table><thead><tr><th>ID</th><th>Description</th><th>Status</th><th>Priority</th></tr></thead>
+        <h2 style="margin-top:16px" data-i18n="usage">Resource usage</h2>
+        <div id="goal-usage" style="color:#6b7280;padding:4px 0">Click on a goal to see what it has used...</div>
+        <h2 style="margin-top:16px" data-i18n="timeline">Timeline</h2>
+        <div id="goal-timeline" style="max-height:300px;overflow-y:auto">
+            <div style="color:#6b7280;padding:10px 0">Click on a goal to see its state transitions...</div>
+        </div>
+        <h2 style="margin-top:16px"><span data-i18n="goals">Goals</span> <span id="goals-total" style="color:#6b7280;font-size:0.8em"></span></h2>
+        <div class="filter-bar">
+            <select id="filter-status" onchange="applyGoalFilter()">
+                <option value="">Any status</option>
+                <option value="pending">pending</option>
+                <option value="in_progress">in_progress</option>
+                <option value="completed">completed</option>
+                <option value="failed">failed</option>
+                <option value="cancelled">cancelled</option>
+            </select>
+            <input id="filter-labels" placeholder="Labels (comma-separated)" onkeydown="if(event.key==='Enter')applyGoalFilter()">
+            <input id="filter-query" placeholder="Search descriptions..." onkeydown="if(event.key==='Enter')applyGoalFilter()">
+            <button onclick="applyGoalFilter()" data-i18n="filter">Filter</button>
+            <button onclick="saveGoalFilter()">Save</button>
+            <select id="saved-filters" onchange="useSavedFilter(this.value)"></select>
+            <button onclick="deleteSavedFilter()">Delete</button>
+        </div>
+        <table><thead><tr><th>ID</th><th>Description</th><th>Labels</th><th>Status</th><th>Priority</th></tr></thead>
         <tbody id="goals-table"></tbody></table>
     </div>
 
@@ -906,16 +2246,46 @@ const DASHBOARD_HTML: &str = r##"<!DOCTYPE html>
     <div class="card tab-content" id="system" style="border-radius: 0 8px 8px 8px">
         <div class="grid-2">
             <div>
-                <h2>Service Health</h2>
+                <h2 data-i18n="service_health">Service Health</h2>
                 <table><thead><tr><th>Service</th><th>Status</th><th>Latency</th></tr></thead>
                 <tbody id="health-table"></tbody></table>
             </div>
             <div>
-                <h2>Agents</h2>
+                <h2 data-i18n="agents">Agents</h2>
                 <table><thead><tr><th>ID</th><th>Type</th><th>Status</th><th>Capabilities</th></tr></thead>
                 <tbody id="agents-table"></tbody></table>
             </div>
         </div>
+        <h2 style="margin-top:16px" data-i18n="autonomy_loop">Autonomy Loop</h2>
+        <table><thead><tr><th>Ticks (event / backlog / fallback)</th><th>Tick time (last / avg / max)</th><th>Wake latency (last / avg / max)</th></tr></thead>
+        <tbody id="autonomy-table"></tbody></table>
+        <h2 style="margin-top:16px" data-i18n="latency_slos">Latency SLOs</h2>
+        <table><thead><tr><th>Method</th><th>Calls</th><th>Errors</th><th>p50 / p95 / p99</th><th>Objective</th><th>Burn rate (latency / errors)</th></tr></thead>
+        <tbody id="slo-table"></tbody></table>
+        <h2 style="margin-top:16px" data-i18n="expensive_goals">Most Expensive Goals</h2>
+        <table><thead><tr><th>ID</th><th>Description</th><th>Status</th><th>Cost</th><th>Tokens</th><th>Tool CPU</th><th>Written</th><th>Wall clock</th></tr></thead>
+        <tbody id="expensive-goals-table"></tbody></table>
+        <h2 style="margin-top:16px" data-i18n="setup">Setup</h2>
+        <table><thead><tr><th>Step</th><th>Status</th><th>Detail</th></tr></thead>
+        <tbody id="setup-table"></tbody></table>
+        <h2 style="margin-top:16px" data-i18n="plan_reviews">Plan Reviews</h2>
+        <table><thead><tr><th>Goal</th><th>Task</th><th>Risky calls</th><th>Reviewer</th><th>Waiting</th><th></th></tr></thead>
+        <tbody id="plan-reviews-table"></tbody></table>
+        <h2 style="margin-top:16px" data-i18n="pending_approvals">Pending Tool Approvals</h2>
+        <table><thead><tr><th>Tool</th><th>Agent</th><th>Task</th><th>Reason</th><th>Input</th><th>Waiting</th><th>Expires</th><th></th></tr></thead>
+        <tbody id="tool-approvals-table"></tbody></table>
+        <h2 style="margin-top:16px" data-i18n="notifications">Notifications</h2>
+        <p style="color:#6b7280;margin:0 0 8px">Routes are tried in order; the first whose event pattern (<code>incident.*</code>, <code>*</code>) and minimum severity match sends to its channels. Quiet hours look like <code>22:00-07:00</code>.</p>
+        <table><thead><tr><th>Channel</th><th>Kind</th><th>Destination</th><th></th></tr></thead>
+        <tbody id="notification-channels-table"></tbody></table>
+        <table style="margin-top:8px"><thead><tr><th>Event</th><th>Channels</th><th>Min severity</th><th>Quiet hours</th><th>Timezone</th><th>Critical during quiet hours</th><th>Dedup (s)</th><th>Batch (s)</th><th></th></tr></thead>
+        <tbody id="notification-routes-table"></tbody></table>
+        <div style="margin-top:8px">
+            <button onclick="addNotificationChannel()">Add channel</button>
+            <button onclick="addNotificationRoute()">Add route</button>
+            <button onclick="saveNotificationConfig()">Save</button>
+            <span id="notification-config-status" style="color:#6b7280;margin-left:8px"></span>
+        </div>
     </div>
 
     <script>
@@ -925,12 +2295,16 @@ const DASHBOARD_HTML: &str = r##"<!DOCTYPE html>
             document.querySelectorAll('.tab').forEach(el => el.classList.remove('active'));
             document.getElementById(tabId).classList.add('active');
             event.target.classList.add('active');
+            if (tabId === 'system') { loadToolApprovals(true); loadPlanReviews(); loadSetupStatus(); loadNotificationConfig(); }
         }
 
         // --- State ---
         let ws;
-        let currentGoalId = null;
+        // Goal links in reports open the console at /#goal=<id>
+        let currentGoalId = location.hash.startsWith('#goal=') ? decodeURIComponent(location.hash.slice(6)) : null;
         let lastGoalChatCount = 0; // Track item count to avoid unnecessary DOM updates
+        let lastGoals = []; // Goals table rows, updated in place by goal changes
+        let lastGoalChat = null; // Chat of the selected goal, updated in place by goal changes
 
         // --- WebSocket (single source of truth for ALL data) ---
         function connectWS() {
@@ -943,6 +2317,7 @@ const DASHBOARD_HTML: &str = r##"<!DOCTYPE html>
                 if (currentGoalId) {
                     ws.send(JSON.stringify({ type: 'subscribe_goal', goal_id: currentGoalId }));
                 }
+                ws.send(JSON.stringify({ type: 'set_goal_filter', ...goalFilter }));
             };
             ws.onmessage = (event) => {
                 const data = JSON.parse(event.data);
@@ -956,6 +2331,19 @@ const DASHBOARD_HTML: &str = r##"<!DOCTYPE html>
                     const hrs = Math.floor(mins / 60);
                     document.getElementById('uptime').textContent = hrs > 0 ? `${hrs}h ${mins%60}m` : `${mins}m`;
 
+                    // Budget degradation ladder
+                    if (data.budget) {
+                        document.getElementById('budget').textContent = `${Math.round(data.budget.spent_percent)}%`;
+                        const banner = document.getElementById('budget-banner');
+                        const last = data.budget.changes[data.budget.changes.length - 1];
+                        if (data.budget.step && last) {
+                            banner.textContent = `Budget degradation: ${data.budget.step.replace('_', ' ')} — ${last.reason}`;
+                            banner.style.display = 'block';
+                        } else {
+                            banner.style.display = 'none';
+                        }
+                    }
+
                     // Update health table
                     if (data.services) {
                         document.getElementById('health-table').innerHTML = data.services.map(s =>
@@ -965,7 +2353,9 @@ const DASHBOARD_HTML: &str = r##"<!DOCTYPE html>
 
                     // Update goals table
                     if (data.goals) {
+                        lastGoals = data.goals;
                         updateGoalsTable(data.goals);
+                        document.getElementById('goals-total').textContent = `(${data.goals.length} of ${data.goals_total})`;
                     }
 
                     // Update agents table
@@ -973,10 +2363,68 @@ const DASHBOARD_HTML: &str = r##"<!DOCTYPE html>
                         updateAgentsTable(data.agents);
                     }
 
+                    // Update autonomy loop metrics
+                    if (data.autonomy) {
+                        const a = data.autonomy;
+                        const ms = v => `${v.toFixed(1)}ms`;
+                        document.getElementById('autonomy-table').innerHTML =
+                            `<tr><td>${a.ticks} (${a.event_ticks} / ${a.backlog_ticks} / ${a.fallback_ticks})</td><td>${ms(a.last_tick_ms)} / ${ms(a.avg_tick_ms)} / ${ms(a.max_tick_ms)}</td><td>${ms(a.last_wake_latency_ms)} / ${ms(a.avg_wake_latency_ms)} / ${ms(a.max_wake_latency_ms)}</td></tr>`;
+                    }
+
+                    // Update per-method latency SLOs
+                    if (data.slo) {
+                        const ms = v => `${v.toFixed(0)}ms`;
+                        const burn = v => `${v.toFixed(1)}x`;
+                        document.getElementById('slo-table').innerHTML = data.slo.map(m =>
+                            `<tr><td>${escapeHtml(m.method)}</td><td>${m.calls}</td><td>${m.errors}</td><td>${ms(m.p50_ms)} / ${ms(m.p95_ms)} / ${ms(m.p99_ms)}</td><td>${m.objective ? `${(m.objective.latency_objective * 100).toFixed(1)}% &lt; ${m.objective.latency_ms}ms` : '-'}</td><td class="${m.alerting ? 'svc-unhealthy' : ''}">${m.objective ? `${burn(m.latency_burn_rate)} / ${burn(m.error_burn_rate)}` : '-'}</td></tr>`
+                        ).join('') || '<tr><td colspan="6" style="color:#6b7280">No calls recorded yet</td></tr>';
+                    }
+
+                    // Update the most expensive goals
+                    if (data.expensive_goals) {
+                        document.getElementById('expensive-goals-table').innerHTML = data.expensive_goals.map(g =>
+                            `<tr class="goal-row" onclick="selectGoal('${g.goal_id}')"><td>${g.goal_id.slice(0,8)}</td><td>${escapeHtml(g.description.slice(0,60))}</td><td class="status-${g.status}">${g.status}</td><td>${formatUsage(g.usage).join('</td><td>')}</td></tr>`
+                        ).join('') || '<tr><td colspan="8" style="color:#6b7280">No usage recorded yet</td></tr>';
+                    }
+
+                    // Critical tool executions waiting for an operator
+                    if (document.getElementById('system').classList.contains('active')) {
+                        loadToolApprovals();
+                    }
+
                     // Update goal chat (only if content changed)
                     if (data.goal_chat && data.goal_chat.goal_id === currentGoalId) {
+                        if (data.goal_chat.usage) {
+                            const u = data.goal_chat.usage;
+                            const [cost, tokens, cpu, written, wall] = formatUsage(u);
+                            document.getElementById('goal-usage').textContent =
+                                `${cost} · ${tokens} tokens in ${u.inference_calls} inference calls · ${u.tool_calls} tool calls using ${cpu} CPU, ${written} written · ${wall} wall clock (including subgoals)`;
+                        }
+                        lastGoalChat = data.goal_chat;
                         renderGoalChat(data.goal_chat);
                     }
+                } else if (data.type === 'goal_created' || data.type === 'labels_changed') {
+                    // Re-sending the filter asks for a full update; the server filters the list
+                    ws.send(JSON.stringify({ type: 'set_goal_filter', ...goalFilter }));
+                } else if (data.type === 'goal_status_changed') {
+                    const goal = lastGoals.find(g => g.id === data.goal_id);
+                    if (goal) {
+                        goal.status = data.to_status;
+                        updateGoalsTable(lastGoals);
+                    }
+                    applyToGoalChat(data, chat => chat.timeline.push(data));
+                } else if (data.type === 'task_status_changed') {
+                    applyToGoalChat(data, chat => {
+                        let task = chat.tasks.find(t => t.task_id === data.entity_id);
+                        if (!task) {
+                            task = { task_id: data.entity_id, description: data.description, output: '', error: '', created_at: data.timestamp, completed_at: 0 };
+                            chat.tasks.push(task);
+                        }
+                        task.status = data.to_status;
+                        chat.timeline.push(data);
+                    });
+                } else if (data.type === 'message_added') {
+                    applyToGoalChat(data, chat => chat.messages.push({ id: data.id, sender: data.sender, content: data.content, timestamp: data.timestamp }));
                 }
             };
             ws.onclose = () => {
@@ -990,8 +2438,97 @@ const DASHBOARD_HTML: &str = r##"<!DOCTYPE html>
         // --- Render goals table from WS data ---
         function updateGoalsTable(goals) {
             document.getElementById('goals-table').innerHTML = goals.map(g =>
-                `<tr class="goal-row${currentGoalId === g.id ? ' style="background:#1e293b"' : ''}" onclick="selectGoal('${g.id}')"><td>${g.id.slice(0,8)}</td><td>${escapeHtml(g.description.slice(0,80))}${g.description.length>80?'...':''}</td><td class="status-${g.status}">${g.status}</td><td>${g.priority}</td></tr>`
-            ).join('');
+                `<tr class="goal-row${currentGoalId === g.id ? ' style="background:#1e293b"' : ''}" onclick="selectGoal('${g.id}')"><td>${g.id.slice(0,8)}</td><td>${escapeHtml(g.description.slice(0,80))}${g.description.length>80?'...':''}</td><td>${(g.labels || []).map(l => `<span class="label-chip" data-label="${escapeHtml(l)}">${escapeHtml(l)}</span>`).join('')}</td><td class="status-${g.status}">${g.status}</td><td>${g.priority}</td></tr>`
+            ).join('') || '<tr><td colspan="5" style="color:#6b7280">No matching goals</td></tr>';
+        }
+
+        // Label chips filter by their label instead of opening the goal (the
+        // capturing listener runs before the row's); the label stays data,
+        // never script
+        document.getElementById('goals-table').addEventListener('click', e => {
+            const chip = e.target.closest('.label-chip');
+            if (!chip) return;
+            e.stopPropagation();
+            filterByLabel(chip.dataset.label);
+        }, true);
+
+        // --- Goal filters (sent to the server over WS; saved filters live in localStorage) ---
+        let goalFilter = { status: '', labels: '', q: '' };
+        const SAVED_FILTERS_KEY = 'aios.savedGoalFilters';
+
+        function applyGoalFilter() {
+            goalFilter = {
+                status: document.getElementById('filter-status').value,
+                labels: document.getElementById('filter-labels').value.trim(),
+                q: document.getElementById('filter-query').value.trim(),
+            };
+            if (ws && ws.readyState === WebSocket.OPEN) {
+                ws.send(JSON.stringify({ type: 'set_goal_filter', ...goalFilter }));
+            }
+        }
+
+        function filterByLabel(label) {
+            const input = document.getElementById('filter-labels');
+            const labels = input.value.split(',').map(l => l.trim()).filter(l => l);
+            if (!labels.includes(label)) labels.push(label);
+            input.value = labels.join(', ');
+            applyGoalFilter();
+        }
+
+        function loadSavedFilters() {
+            try { return JSON.parse(localStorage.getItem(SAVED_FILTERS_KEY) || '[]'); } catch (e) { return []; }
+        }
+
+        function renderSavedFilters() {
+            document.getElementById('saved-filters').innerHTML = '<option value="">Saved filters...</option>' +
+                loadSavedFilters().map((f, i) => `<option value="${i}">${escapeHtml(f.name)}</option>`).join('');
+        }
+
+        function saveGoalFilter() {
+            applyGoalFilter();
+            const name = prompt('Name for this filter:');
+            if (!name) return;
+            const filters = loadSavedFilters().filter(f => f.name !== name);
+            filters.push({ name, ...goalFilter });
+            localStorage.setItem(SAVED_FILTERS_KEY, JSON.stringify(filters));
+            renderSavedFilters();
+        }
+
+        function useSavedFilter(index) {
+            const f = loadSavedFilters()[index];
+            if (!f) return;
+            document.getElementById('filter-status').value = f.status || '';
+            document.getElementById('filter-labels').value = f.labels || '';
+            document.getElementById('filter-query').value = f.q || '';
+            applyGoalFilter();
+        }
+
+        function deleteSavedFilter() {
+            const index = document.getElementById('saved-filters').value;
+            if (index === '') return;
+            const filters = loadSavedFilters();
+            filters.splice(Number(index), 1);
+            localStorage.setItem(SAVED_FILTERS_KEY, JSON.stringify(filters));
+            renderSavedFilters();
+        }
+        renderSavedFilters();
+
+        // --- Edit labels of the selected goal ("name" adds, "-name" removes) ---
+        async function editGoalLabels() {
+            if (!currentGoalId) return;
+            const input = document.getElementById('goal-label-edit');
+            const entries = input.value.split(',').map(l => l.trim()).filter(l => l);
+            if (!entries.length) return;
+            const add = entries.filter(l => !l.startsWith('-'));
+            const remove = entries.filter(l => l.startsWith('-')).map(l => l.slice(1));
+            try {
+                await fetch(`/api/goals/${currentGoalId}/labels`, {
+                    method: 'POST',
+                    headers: {'Content-Type': 'application/json'},
+                    body: JSON.stringify({ add, remove })
+                });
+                input.value = '';
+            } catch(e) { console.error('Failed to update labels:', e); }
         }
 
         // --- Render agents table from WS data ---
@@ -1001,11 +2538,27 @@ const DASHBOARD_HTML: &str = r##"<!DOCTYPE html>
             ).join('') || '<tr><td colspan="4" style="color:#6b7280">No agents registered</td></tr>';
         }
 
+        // --- Cost, tokens, tool CPU, bytes written and wall clock of a usage ---
+        function formatUsage(u) {
+            const secs = ms => ms >= 60000 ? `${(ms/60000).toFixed(1)}m` : `${(ms/1000).toFixed(1)}s`;
+            const bytes = b => b >= 1048576 ? `${(b/1048576).toFixed(1)} MB` : `${(b/1024).toFixed(1)} KB`;
+            return [`$${u.cost_usd.toFixed(4)}`, u.tokens_used.toLocaleString(), secs(u.tool_cpu_ms), bytes(u.bytes_written), secs(u.wall_clock_ms)];
+        }
+
+        // --- Apply a goal change to the selected goal's chat and re-render it ---
+        function applyToGoalChat(change, apply) {
+            if (!lastGoalChat || change.goal_id !== currentGoalId || lastGoalChat.goal_id !== currentGoalId) return;
+            apply(lastGoalChat);
+            lastGoalChatCount = -1;
+            renderGoalChat(lastGoalChat);
+        }
+
         // --- Render goal chat from WS-pushed data (no fetch!) ---
         function renderGoalChat(chatData) {
             const messages = chatData.messages || [];
             const tasks = chatData.tasks || [];
-            const totalItems = messages.length + tasks.length;
+            const timeline = chatData.timeline || [];
+            const totalItems = messages.length + tasks.length + timeline.length;
 
             // Skip DOM update if item count unchanged (avoids flicker)
             if (totalItems === lastGoalChatCount) return;
@@ -1046,11 +2599,27 @@ const DASHBOARD_HTML: &str = r##"<!DOCTYPE html>
 
             const hasAwaiting = tasks.some(t => t.status === 'awaiting_input');
             document.getElementById('goal-reply-area').style.display = hasAwaiting ? 'block' : 'none';
+
+            renderTimeline(timeline);
+        }
+
+        // --- Render state transition timeline (newest first) ---
+        function renderTimeline(timeline) {
+            const rows = timeline.slice().reverse().map(t => {
+                const when = new Date(t.timestamp * 1000).toLocaleString();
+                const entity = t.entity_type === 'goal' ? 'goal' : `task ${t.entity_id.slice(0,8)}`;
+                const from = t.from_status ? `<span class="status-${t.from_status}">${t.from_status}</span>` : '<span style="color:#4b5563">created</span>';
+                return `<tr class="timeline-row"><td>${when}</td><td>${escapeHtml(entity)}</td><td>${from} <span class="timeline-arrow">&rarr;</span> <span class="status-${t.to_status}">${t.to_status}</span></td><td>${escapeHtml(t.cause)}</td><td>${escapeHtml(t.actor)}</td></tr>`;
+            }).join('');
+            document.getElementById('goal-timeline').innerHTML = rows
+                ? `<table><thead><tr><th>Time</th><th>Entity</th><th>Transition</th><th>Cause</th><th>Actor</th></tr></thead><tbody>${rows}</tbody></table>`
+                : '<div style="color:#6b7280;padding:10px 0">No transitions recorded yet...</div>';
         }
 
         // --- Select a goal (subscribe via WS) ---
         function selectGoal(goalId) {
             currentGoalId = goalId;
+            lastGoalChat = null;
             lastGoalChatCount = -1; // Force re-render on next push
             document.getElementById('goal-chat-area').innerHTML = '<div style="color:#6b7280;text-align:center;padding:20px"><span class="spinner"></span> Loading...</div>';
             if (ws && ws.readyState === WebSocket.OPEN) {
@@ -1058,7 +2627,41 @@ const DASHBOARD_HTML: &str = r##"<!DOCTYPE html>
             }
         }
 
-        // --- Chat ---
+        // --- Chat (sessions live in working memory; resume any of them) ---
+        let chatSessionId = null;
+        const chatWelcome = document.getElementById('chat-messages').innerHTML;
+
+        async function loadChatSessions() {
+            try {
+                const sessions = await (await fetch('/api/chat/sessions?limit=30')).json();
+                document.getElementById('chat-session-select').innerHTML =
+                    `<option value="">${escapeHtml(t('new_chat', 'New chat'))}</option>` +
+                    sessions.map(s => `<option value="${escapeHtml(s.session_id)}"${s.session_id === chatSessionId ? ' selected' : ''}>${escapeHtml(s.title || s.session_id.slice(0,8))} (${s.message_count})</option>`).join('');
+            } catch(e) { console.warn('Chat sessions unavailable', e); }
+        }
+
+        function newChat() {
+            chatSessionId = null;
+            document.getElementById('chat-session-select').value = '';
+            document.getElementById('chat-messages').innerHTML = chatWelcome;
+        }
+
+        async function resumeChat(sessionId) {
+            if (!sessionId) { newChat(); return; }
+            try {
+                const res = await fetch(`/api/chat/sessions/${encodeURIComponent(sessionId)}`);
+                if (!res.ok) throw new Error(`HTTP ${res.status}`);
+                const session = await res.json();
+                chatSessionId = session.session_id;
+                const msgBox = document.getElementById('chat-messages');
+                msgBox.innerHTML = (session.messages || []).map(m => m.role === 'user'
+                    ? `<div class="msg msg-user"><div class="msg-label">You</div><div class="msg-content">${escapeHtml(m.content)}</div></div>`
+                    : `<div class="msg msg-ai"><div class="msg-label">aiOS <span style="color:#6b7280;font-size:0.85em">(${escapeHtml(m.model)})</span></div><div class="msg-content">${formatResponse(m.content)}</div></div>`
+                ).join('');
+                msgBox.scrollTop = msgBox.scrollHeight;
+            } catch(e) { console.error('Failed to resume chat:', e); }
+        }
+
         async function sendChat() {
             const input = document.getElementById('chat-input');
             const msg = input.value.trim();
@@ -1080,9 +2683,13 @@ const DASHBOARD_HTML: &str = r##"<!DOCTYPE html>
                 const res = await fetch('/api/chat', {
                     method: 'POST',
                     headers: {'Content-Type': 'application/json'},
-                    body: JSON.stringify({message: msg, provider: provider})
+                    body: JSON.stringify({message: msg, provider: provider, session_id: chatSessionId || ''})
                 });
                 const data = await res.json();
+                if (data.session_id && data.session_id !== chatSessionId) {
+                    chatSessionId = data.session_id;
+                    loadChatSessions();
+                }
                 const thinkEl = document.getElementById('thinking');
                 if (thinkEl) thinkEl.remove();
                 msgBox.innerHTML += `<div class="msg msg-ai"><div class="msg-label">aiOS <span style="color:#6b7280;font-size:0.85em">(${escapeHtml(data.model)})</span></div><div class="msg-content">${formatResponse(data.reply)}</div><div class="msg-meta">${data.model} | ${data.tokens} tokens | ${(data.latency_ms/1000).toFixed(1)}s</div></div>`;
@@ -1096,10 +2703,11 @@ const DASHBOARD_HTML: &str = r##"<!DOCTYPE html>
             msgBox.scrollTop = msgBox.scrollHeight;
         }
 
+        // Safe in text and in quoted attribute values
         function escapeHtml(text) {
             const div = document.createElement('div');
             div.textContent = text;
-            return div.innerHTML;
+            return div.innerHTML.replace(/"/g, '&quot;').replace(/'/g, '&#39;');
         }
 
         function formatResponse(text) {
@@ -1133,6 +2741,8 @@ const DASHBOARD_HTML: &str = r##"<!DOCTYPE html>
             const desc = document.getElementById('goal-input').value;
             if (!desc) return;
             const provider = document.getElementById('goal-provider-select').value;
+            const language = document.getElementById('goal-language-select').value;
+            const labels = document.getElementById('goal-labels-input').value.split(',').map(l => l.trim()).filter(l => l);
             const btn = document.getElementById('goal-submit-btn');
             btn.disabled = true;
             btn.textContent = 'Submitting...';
@@ -1140,17 +2750,237 @@ const DASHBOARD_HTML: &str = r##"<!DOCTYPE html>
                 const res = await fetch('/api/goals', {
                     method: 'POST',
                     headers: {'Content-Type': 'application/json'},
-                    body: JSON.stringify({description: desc, priority: 2, provider: provider})
+                    body: JSON.stringify({description: desc, priority: 2, provider: provider, language: language, labels: labels})
                 });
                 const data = await res.json();
                 document.getElementById('goal-result').textContent = `Created: ${data.goal_id.slice(0,8)}`;
                 document.getElementById('goal-input').value = '';
+                document.getElementById('goal-labels-input').value = '';
                 // Select the new goal — WS will push goals table + chat
                 selectGoal(data.goal_id);
             } catch(e) { document.getElementById('goal-result').textContent = `Error: ${e}`; }
             btn.disabled = false;
-            btn.textContent = 'Submit Goal';
+            btn.textContent = t('submit_goal', 'Submit Goal');
+        }
+
+        async function startOnboarding() {
+            const btn = document.getElementById('onboarding-btn');
+            btn.disabled = true;
+            try {
+                const res = await fetch('/api/onboarding', {
+                    method: 'POST',
+                    headers: {'Content-Type': 'application/json'},
+                    body: JSON.stringify({language: document.getElementById('goal-language-select').value})
+                });
+                const data = await res.json();
+                document.getElementById('goal-result').textContent = `Created: ${data.goal_id.slice(0,8)}`;
+                selectGoal(data.goal_id);
+            } catch(e) { document.getElementById('goal-result').textContent = `Error: ${e}`; }
+            btn.disabled = false;
+        }
+
+        // --- Tool approvals (critical-risk executions parked in the tools service) ---
+        let toolApprovalsLoadedAt = 0;
+        async function loadToolApprovals(force) {
+            if (!force && Date.now() - toolApprovalsLoadedAt < 5000) return;
+            toolApprovalsLoadedAt = Date.now();
+            try {
+                const res = await fetch('/api/tool-approvals');
+                if (!res.ok) throw new Error(`HTTP ${res.status}`);
+                const approvals = await res.json();
+                const now = Date.now() / 1000;
+                const ago = s => s < 60 ? `${Math.floor(s)}s` : `${Math.floor(s / 60)}m`;
+                document.getElementById('tool-approvals-table').innerHTML = approvals.map(a =>
+                    `<tr><td>${escapeHtml(a.tool_name)}</td><td>${escapeHtml(a.agent_id)}</td><td>${escapeHtml(a.task_id.slice(0,8))}</td><td>${escapeHtml(a.reason)}</td><td><code>${escapeHtml(a.input_json.slice(0,200))}</code></td><td>${ago(now - a.requested_at)}</td><td>${a.expires_at ? `in ${ago(Math.max(0, a.expires_at - now))}` : 'never'}</td><td><button onclick="decideToolApproval('${a.approval_id}', true)">Approve</button> <button onclick="decideToolApproval('${a.approval_id}', false)">Deny</button></td></tr>`
+                ).join('') || '<tr><td colspan="8" style="color:#6b7280">Nothing is waiting for approval</td></tr>';
+            } catch(e) { console.warn('Tool approvals unavailable', e); }
+        }
+
+        async function decideToolApproval(approvalId, approve) {
+            let body = '{}';
+            if (!approve) {
+                const reason = prompt('Reason for denying (optional):');
+                if (reason === null) return;
+                body = JSON.stringify({ reason });
+            }
+            const headers = await operatorHeaders();
+            if (!headers) return;
+            try {
+                const res = await fetch(`/api/tool-approvals/${encodeURIComponent(approvalId)}/${approve ? 'approve' : 'deny'}`, {
+                    method: 'POST', headers, body
+                });
+                if (res.status === 401) signOut();
+                if (!res.ok) alert(await res.text());
+            } catch(e) { alert('Decision failed: ' + e.message); }
+            loadToolApprovals(true);
+        }
+
+        // --- Plan reviews: risky plans a reviewer model objected to, escalated to an operator ---
+        async function loadPlanReviews() {
+            try {
+                const res = await fetch('/api/reviews');
+                if (!res.ok) throw new Error(`HTTP ${res.status}`);
+                const reviews = await res.json();
+                const now = Date.now() / 1000;
+                const ago = s => s < 60 ? `${Math.floor(s)}s` : `${Math.floor(s / 60)}m`;
+                document.getElementById('plan-reviews-table').innerHTML = reviews.map(r =>
+                    `<tr><td>${escapeHtml(r.goal_id.slice(0,8))}</td><td>${escapeHtml(r.task_id.slice(0,8))}</td><td>${r.calls.filter(c => c.risk).map(c => `<code>${escapeHtml(c.tool)}</code> (${escapeHtml(c.risk)}) <code>${escapeHtml(JSON.stringify(c.input).slice(0,200))}</code>`).join('<br>')}</td><td>${escapeHtml(r.reviewer.model)}: ${escapeHtml(r.reviewer.reasoning)}</td><td>${ago(now - r.created_at)}</td><td><button data-goal="${escapeHtml(r.goal_id)}" data-decision="approve">Approve</button> <button data-goal="${escapeHtml(r.goal_id)}" data-decision="reject">Reject</button></td></tr>`
+                ).join('') || '<tr><td colspan="6" style="color:#6b7280">No plan is waiting for an operator</td></tr>';
+            } catch(e) { console.warn('Plan reviews unavailable', e); }
+        }
+
+        document.getElementById('plan-reviews-table').addEventListener('click', async e => {
+            const button = e.target.closest('button[data-goal]');
+            if (!button) return;
+            const headers = await operatorHeaders();
+            if (!headers) return;
+            try {
+                const res = await fetch(`/api/goals/${encodeURIComponent(button.dataset.goal)}/review/${button.dataset.decision}`, { method: 'POST', headers });
+                if (res.status === 401) signOut();
+                if (!res.ok) alert(await res.text());
+            } catch(e) { alert('Decision failed: ' + e.message); }
+            loadPlanReviews();
+        });
+
+        // --- Notification channels and routing rules (/api/notifications/config) ---
+        // Destination fields of each channel kind, as notifications.toml names them
+        const CHANNEL_FIELDS = {
+            console: [], email: ['to'], slack: ['url'], telegram: ['chat_id', 'bot_token_env'],
+            pagerduty: ['routing_key_env'], webhook: ['url', 'secret']
+        };
+
+        async function loadNotificationConfig() {
+            try {
+                const res = await fetch('/api/notifications/config');
+                if (!res.ok) throw new Error(`HTTP ${res.status}`);
+                renderNotificationConfig(await res.json());
+            } catch(e) { console.warn('Notification settings unavailable', e); }
+        }
+
+        function renderNotificationConfig(config) {
+            document.getElementById('notification-channels-table').innerHTML = '';
+            document.getElementById('notification-routes-table').innerHTML = '';
+            Object.entries(config.channels).forEach(([name, channel]) => addNotificationChannel(name, channel));
+            config.route.forEach(addNotificationRoute);
+        }
+
+        function addNotificationChannel(name = '', channel = { kind: 'console' }) {
+            const row = document.createElement('tr');
+            row.innerHTML = `<td><input data-field="name" value="${escapeHtml(name)}"></td><td><select data-field="kind">${Object.keys(CHANNEL_FIELDS).map(k => `<option${k === channel.kind ? ' selected' : ''}>${k}</option>`).join('')}</select></td><td data-field="destination"></td><td><button onclick="this.closest('tr').remove()">Remove</button></td>`;
+            const showFields = values => {
+                row.querySelector('[data-field="destination"]').innerHTML = CHANNEL_FIELDS[row.querySelector('[data-field="kind"]').value]
+                    .map(f => `<input data-channel-field="${f}" placeholder="${f}" value="${escapeHtml(values[f] || '')}">`).join(' ');
+            };
+            row.querySelector('[data-field="kind"]').addEventListener('change', () => showFields({}));
+            showFields(channel);
+            document.getElementById('notification-channels-table').appendChild(row);
+        }
+
+        function addNotificationRoute(route = {}) {
+            const r = { event: '*', channels: ['console'], min_severity: 'info', quiet_hours: '', timezone: '', critical_bypasses_quiet: true, dedup_secs: 300, batch_secs: 0, ...route };
+            const row = document.createElement('tr');
+            row.innerHTML = `<td><input data-field="event" value="${escapeHtml(r.event)}"></td>`
+                + `<td><input data-field="channels" value="${escapeHtml(r.channels.join(', '))}"></td>`
+                + `<td><select data-field="min_severity">${['info', 'warning', 'critical'].map(s => `<option${s === r.min_severity ? ' selected' : ''}>${s}</option>`).join('')}</select></td>`
+                + `<td><input data-field="quiet_hours" value="${escapeHtml(r.quiet_hours)}" placeholder="22:00-07:00"></td>`
+                + `<td><input data-field="timezone" value="${escapeHtml(r.timezone)}" placeholder="UTC"></td>`
+                + `<td><input type="checkbox" data-field="critical_bypasses_quiet"${r.critical_bypasses_quiet ? ' checked' : ''}></td>`
+                + `<td><input type="number" min="0" data-field="dedup_secs" value="${r.dedup_secs}"></td>`
+                + `<td><input type="number" min="0" data-field="batch_secs" value="${r.batch_secs}"></td>`
+                + `<td><button onclick="this.closest('tr').remove()">Remove</button></td>`;
+            document.getElementById('notification-routes-table').appendChild(row);
+        }
+
+        async function saveNotificationConfig() {
+            const field = (row, name) => row.querySelector(`[data-field="${name}"]`);
+            const channels = {};
+            document.querySelectorAll('#notification-channels-table tr').forEach(row => {
+                const channel = { kind: field(row, 'kind').value };
+                // Left empty, optional fields (bot_token_env, secret) keep their defaults
+                row.querySelectorAll('[data-channel-field]').forEach((input, i) => {
+                    if (i === 0 || input.value.trim()) channel[input.dataset.channelField] = input.value.trim();
+                });
+                channels[field(row, 'name').value.trim()] = channel;
+            });
+            const route = [...document.querySelectorAll('#notification-routes-table tr')].map(row => ({
+                event: field(row, 'event').value.trim(),
+                channels: field(row, 'channels').value.split(',').map(c => c.trim()).filter(c => c),
+                min_severity: field(row, 'min_severity').value,
+                quiet_hours: field(row, 'quiet_hours').value.trim(),
+                timezone: field(row, 'timezone').value.trim(),
+                critical_bypasses_quiet: field(row, 'critical_bypasses_quiet').checked,
+                dedup_secs: Number(field(row, 'dedup_secs').value) || 0,
+                batch_secs: Number(field(row, 'batch_secs').value) || 0
+            }));
+            const status = document.getElementById('notification-config-status');
+            try {
+                const res = await fetch('/api/notifications/config', {
+                    method: 'PUT',
+                    headers: { 'Content-Type': 'application/json' },
+                    body: JSON.stringify({ channels, route })
+                });
+                if (!res.ok) { status.textContent = await res.text(); status.style.color = '#ff4444'; return; }
+                renderNotificationConfig(await res.json());
+                status.textContent = 'Saved';
+                status.style.color = '#00ff88';
+            } catch(e) { status.textContent = 'Save failed: ' + e.message; status.style.color = '#ff4444'; }
+        }
+
+        // --- Operator sign-in: decisions are made as a user, proven by the token sign-in issues ---
+        async function operatorHeaders() {
+            let user = sessionStorage.getItem('aiosUser');
+            let token = sessionStorage.getItem('aiosUserToken');
+            if (!user || !token) {
+                user = prompt('Operator:');
+                if (!user) return null;
+                const password = prompt(`Password of ${user}:`);
+                if (!password) return null;
+                const res = await fetch('/api/login', {
+                    method: 'POST',
+                    headers: { 'Content-Type': 'application/json' },
+                    body: JSON.stringify({ user, password })
+                });
+                if (!res.ok) { alert(await res.text()); return null; }
+                ({ user, token } = await res.json());
+                sessionStorage.setItem('aiosUser', user);
+                sessionStorage.setItem('aiosUserToken', token);
+            }
+            return { 'Content-Type': 'application/json', 'X-Aios-User': user, 'X-Aios-User-Token': token };
+        }
+
+        function signOut() {
+            sessionStorage.removeItem('aiosUser');
+            sessionStorage.removeItem('aiosUserToken');
+        }
+
+        // --- Setup: what the first-boot wizard recorded ---
+        async function loadSetupStatus() {
+            try {
+                const res = await fetch('/api/setup');
+                if (!res.ok) throw new Error(`HTTP ${res.status}`);
+                const setup = await res.json();
+                document.getElementById('setup-table').innerHTML = setup.steps.map(s =>
+                    `<tr><td>${escapeHtml(s.name)}</td><td>${s.done ? '<span style="color:#10b981">done</span>' : '<span style="color:#f59e0b">pending</span>'}</td><td>${escapeHtml(s.detail)}</td></tr>`
+                ).join('');
+            } catch(e) { console.warn('Setup status unavailable', e); }
+        }
+
+        // --- Locale: strings in the system language; goals may pick their own ---
+        let uiStrings = {};
+        function t(key, fallback) { return uiStrings[key] || fallback; }
+        async function applyLocale() {
+            try {
+                const data = await (await fetch('/api/locale')).json();
+                uiStrings = data.strings;
+                document.documentElement.lang = data.language;
+                document.querySelectorAll('[data-i18n]').forEach(el => { el.textContent = t(el.dataset.i18n, el.textContent); });
+                document.querySelectorAll('[data-i18n-placeholder]').forEach(el => { el.placeholder = t(el.dataset.i18nPlaceholder, el.placeholder); });
+                document.getElementById('goal-language-select').innerHTML =
+                    `<option value="">${escapeHtml(t('system_language', 'System language'))} (${escapeHtml(data.language_name)})</option>` +
+                    data.languages.map(l => `<option value="${l.code}">${escapeHtml(l.name)}</option>`).join('');
+            } catch(e) { console.warn('Locale unavailable', e); }
         }
+        applyLocale().then(loadChatSessions);
 
         // No polling! Everything arrives via WebSocket.
     </script>
@@ -0,0 +1,813 @@
+//! Notifications — routing events to people over the channels they prefer
+//!
+//! Incidents, approvals, goal completions and event bus events are handed to
+//! the [`Notifier`], which picks the first route in notifications.toml whose
+//! event pattern and minimum severity match and sends to that route's
+//! channels: the console feed, email, Slack, Telegram, PagerDuty or a plain
+//! webhook. Outside deliveries go through the tools service (`email.send`,
+//! `web.webhook`), so they are audited like any other tool call.
+//!
+//! Per route:
+//! - `dedup_secs`: the same event (type and key) is sent once per window.
+//! - `batch_secs`: events are collected and sent as one digest.
+//! - `quiet_hours` ("22:00-07:00" in `timezone`): events are held and sent
+//!   as a digest when quiet hours end; critical ones still go out at once
+//!   unless `critical_bypasses_quiet` is off.
+//!
+//! Without a configuration everything goes to the console feed. The System
+//! tab's Notifications form edits the configuration through
+//! `/api/notifications/config`.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use crate::clients::ServiceClients;
+use crate::event_bus::EventSeverity;
+use crate::scheduler;
+
+/// Default location of the notification routing configuration
+pub const NOTIFICATIONS_CONFIG_PATH: &str = "/var/lib/aios/config/notifications.toml";
+
+/// Notifications and delivery records kept for the console
+const CONSOLE_FEED_LEN: usize = 200;
+
+/// How often held and batched notifications are checked
+const DISPATCH_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Something worth telling a person about
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    pub id: String,
+    pub event_type: String,
+    pub severity: EventSeverity,
+    pub title: String,
+    pub body: String,
+    pub timestamp: i64,
+    /// Identifies repeats of the same event for deduplication
+    #[serde(skip)]
+    pub dedup_key: String,
+}
+
+/// Where notifications can be sent
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Channel {
+    /// The management console's notification feed
+    Console,
+    Email {
+        to: String,
+    },
+    /// Slack incoming webhook
+    Slack {
+        url: String,
+    },
+    Telegram {
+        chat_id: String,
+        /// Environment variable holding the bot token
+        #[serde(default = "default_telegram_token_env")]
+        bot_token_env: String,
+    },
+    /// PagerDuty Events API v2
+    Pagerduty {
+        /// Environment variable holding the integration's routing key
+        routing_key_env: String,
+    },
+    /// JSON POST of the notifications to any URL
+    Webhook {
+        url: String,
+        #[serde(default)]
+        secret: String,
+    },
+}
+
+fn default_telegram_token_env() -> String {
+    "AIOS_TELEGRAM_BOT_TOKEN".to_string()
+}
+
+/// Which channels an event type goes to, and when
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Route {
+    /// Event type; a trailing `*` matches a prefix and `*` alone matches all
+    pub event: String,
+    pub channels: Vec<String>,
+    #[serde(default = "default_min_severity")]
+    pub min_severity: EventSeverity,
+    /// Local "HH:MM-HH:MM" window in which only critical events go out
+    #[serde(default)]
+    pub quiet_hours: String,
+    /// IANA timezone of `quiet_hours`; empty means UTC
+    #[serde(default)]
+    pub timezone: String,
+    #[serde(default = "default_true")]
+    pub critical_bypasses_quiet: bool,
+    #[serde(default = "default_dedup_secs")]
+    pub dedup_secs: i64,
+    /// Collect events for this long and send them as one digest (0 = at once)
+    #[serde(default)]
+    pub batch_secs: i64,
+}
+
+fn default_min_severity() -> EventSeverity {
+    EventSeverity::Info
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_dedup_secs() -> i64 {
+    300
+}
+
+impl Route {
+    fn matches(&self, event_type: &str, severity: &EventSeverity) -> bool {
+        let event_match = match self.event.strip_suffix('*') {
+            Some(prefix) => event_type.starts_with(prefix),
+            None => self.event == event_type,
+        };
+        event_match && *severity >= self.min_severity
+    }
+
+    /// End of the quiet hours `now` falls in, as Unix time
+    fn quiet_until(&self, now: DateTime<Utc>) -> Option<i64> {
+        let (start, end) = parse_quiet_hours(&self.quiet_hours).ok()??;
+        let tz = scheduler::parse_timezone(&self.timezone).ok()?;
+        let local = now.with_timezone(&tz).naive_local();
+        let time = local.time();
+        let inside = if start <= end {
+            start <= time && time < end
+        } else {
+            time >= start || time < end
+        };
+        if !inside {
+            return None;
+        }
+        let mut end_day = local.date();
+        if time >= end {
+            end_day = end_day.succ_opt()?;
+        }
+        scheduler::resolve_local(tz, end_day.and_time(end)).map(|at| at.timestamp())
+    }
+}
+
+/// Parse "HH:MM-HH:MM"; empty means no quiet hours
+fn parse_quiet_hours(value: &str) -> Result<Option<(NaiveTime, NaiveTime)>> {
+    if value.trim().is_empty() {
+        return Ok(None);
+    }
+    let (start, end) = value
+        .split_once('-')
+        .with_context(|| format!("Quiet hours '{value}' must look like 22:00-07:00"))?;
+    let parse = |t: &str| {
+        NaiveTime::parse_from_str(t.trim(), "%H:%M")
+            .with_context(|| format!("Invalid time '{t}' in quiet hours '{value}'"))
+    };
+    Ok(Some((parse(start)?, parse(end)?)))
+}
+
+/// notifications.toml layout
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationConfig {
+    #[serde(default)]
+    pub channels: BTreeMap<String, Channel>,
+    #[serde(default, rename = "route")]
+    pub routes: Vec<Route>,
+}
+
+impl Default for NotificationConfig {
+    /// Everything to the console feed
+    fn default() -> Self {
+        Self {
+            channels: BTreeMap::from([("console".to_string(), Channel::Console)]),
+            routes: vec![Route {
+                event: "*".to_string(),
+                channels: vec!["console".to_string()],
+                min_severity: EventSeverity::Info,
+                quiet_hours: String::new(),
+                timezone: String::new(),
+                critical_bypasses_quiet: true,
+                dedup_secs: default_dedup_secs(),
+                batch_secs: 0,
+            }],
+        }
+    }
+}
+
+impl NotificationConfig {
+    /// Check that routes name known channels and valid quiet hours
+    pub fn validate(&self) -> Result<()> {
+        for route in &self.routes {
+            for channel in &route.channels {
+                if !self.channels.contains_key(channel) {
+                    anyhow::bail!("Route '{}' uses unknown channel '{channel}'", route.event);
+                }
+            }
+            parse_quiet_hours(&route.quiet_hours)?;
+            scheduler::parse_timezone(&route.timezone)?;
+        }
+        for (name, channel) in &self.channels {
+            let missing = match channel {
+                Channel::Console => false,
+                Channel::Email { to } => to.is_empty(),
+                Channel::Slack { url } | Channel::Webhook { url, .. } => url.is_empty(),
+                Channel::Telegram { chat_id, .. } => chat_id.is_empty(),
+                Channel::Pagerduty { routing_key_env } => routing_key_env.is_empty(),
+            };
+            if missing {
+                anyhow::bail!("Channel '{name}' is missing its destination");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Notifications ready to send to one channel
+#[derive(Debug)]
+struct Delivery {
+    channel: String,
+    items: Vec<Notification>,
+}
+
+/// Notifications held for a batch or for the end of quiet hours
+struct Pending {
+    due: i64,
+    items: Vec<Notification>,
+}
+
+/// Dedup windows and held notifications
+#[derive(Default)]
+struct RouterState {
+    last_sent: HashMap<String, i64>,
+    pending: BTreeMap<(String, usize), Pending>,
+}
+
+impl RouterState {
+    /// Route `notification`, returning what must be sent now
+    fn route(
+        &mut self,
+        config: &NotificationConfig,
+        notification: Notification,
+        now: DateTime<Utc>,
+    ) -> Vec<Delivery> {
+        let Some((index, route)) = config
+            .routes
+            .iter()
+            .enumerate()
+            .find(|(_, r)| r.matches(&notification.event_type, &notification.severity))
+        else {
+            debug!("No notification route for {}", notification.event_type);
+            return Vec::new();
+        };
+
+        let ts = now.timestamp();
+        let key = format!(
+            "{index}:{}:{}",
+            notification.event_type,
+            if notification.dedup_key.is_empty() {
+                &notification.title
+            } else {
+                &notification.dedup_key
+            }
+        );
+        if self
+            .last_sent
+            .get(&key)
+            .is_some_and(|last| ts - last < route.dedup_secs)
+        {
+            debug!("Dropping duplicate notification {key}");
+            return Vec::new();
+        }
+        self.last_sent.insert(key, ts);
+        self.last_sent
+            .retain(|_, last| ts - *last < route.dedup_secs.max(3600));
+
+        let bypass =
+            route.critical_bypasses_quiet && notification.severity == EventSeverity::Critical;
+        let quiet = if bypass { None } else { route.quiet_until(now) };
+        let batch = (route.batch_secs > 0).then(|| ts + route.batch_secs);
+        let hold = quiet.max(batch);
+
+        let mut deliveries = Vec::new();
+        for channel in &route.channels {
+            match hold {
+                None => deliveries.push(Delivery {
+                    channel: channel.clone(),
+                    items: vec![notification.clone()],
+                }),
+                Some(due) => {
+                    let pending = self
+                        .pending
+                        .entry((channel.clone(), index))
+                        .or_insert(Pending {
+                            due,
+                            items: Vec::new(),
+                        });
+                    // Quiet hours can push an open batch back, never forward
+                    pending.due = pending.due.max(quiet.unwrap_or(0));
+                    pending.items.push(notification.clone());
+                }
+            }
+        }
+        deliveries
+    }
+
+    /// Held notifications whose time has come
+    fn take_due(&mut self, now: DateTime<Utc>) -> Vec<Delivery> {
+        let ts = now.timestamp();
+        let due: Vec<(String, usize)> = self
+            .pending
+            .iter()
+            .filter(|(_, p)| p.due <= ts)
+            .map(|(k, _)| k.clone())
+            .collect();
+        due.into_iter()
+            .filter_map(|key| {
+                let pending = self.pending.remove(&key)?;
+                Some(Delivery {
+                    channel: key.0,
+                    items: pending.items,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Outcome of one delivery, shown in the console
+#[derive(Debug, Clone, Serialize)]
+pub struct DeliveryRecord {
+    pub channel: String,
+    pub subject: String,
+    pub notifications: usize,
+    pub success: bool,
+    pub error: String,
+    pub timestamp: i64,
+}
+
+/// Routes notifications and queues them for delivery
+pub struct Notifier {
+    config: Mutex<NotificationConfig>,
+    router: Mutex<RouterState>,
+    outbox: Mutex<Vec<Delivery>>,
+    feed: Mutex<VecDeque<Notification>>,
+    deliveries: Mutex<VecDeque<DeliveryRecord>>,
+    wake: Notify,
+    /// Where console changes to the configuration are saved; empty = nowhere
+    config_path: String,
+}
+
+impl Default for Notifier {
+    fn default() -> Self {
+        Self::with_config(NotificationConfig::default(), String::new())
+    }
+}
+
+impl Notifier {
+    fn with_config(config: NotificationConfig, config_path: String) -> Self {
+        Self {
+            config: Mutex::new(config),
+            router: Mutex::new(RouterState::default()),
+            outbox: Mutex::new(Vec::new()),
+            feed: Mutex::new(VecDeque::new()),
+            deliveries: Mutex::new(VecDeque::new()),
+            wake: Notify::new(),
+            config_path,
+        }
+    }
+
+    /// Load the routing configuration from `path`, falling back to the
+    /// console-only default when it is missing or invalid
+    pub fn load(path: &str) -> Self {
+        let config = match std::fs::read_to_string(path) {
+            Ok(contents) => match toml::from_str::<NotificationConfig>(&contents)
+                .map_err(anyhow::Error::from)
+                .and_then(|c| c.validate().map(|_| c))
+            {
+                Ok(config) => {
+                    info!(
+                        "Loaded {} notification routes over {} channels",
+                        config.routes.len(),
+                        config.channels.len()
+                    );
+                    config
+                }
+                Err(e) => {
+                    warn!("Invalid notification configuration {path}: {e}; using the console only");
+                    NotificationConfig::default()
+                }
+            },
+            Err(_) => NotificationConfig::default(),
+        };
+        Self::with_config(config, path.to_string())
+    }
+
+    /// Route an event to its channels. `dedup_key` identifies repeats of
+    /// the same event (the title is used when empty).
+    pub fn notify(
+        &self,
+        event_type: &str,
+        severity: EventSeverity,
+        title: &str,
+        body: &str,
+        dedup_key: &str,
+    ) {
+        let notification = Notification {
+            id: uuid::Uuid::new_v4().to_string(),
+            event_type: event_type.to_string(),
+            severity,
+            title: title.to_string(),
+            body: body.to_string(),
+            timestamp: Utc::now().timestamp(),
+            dedup_key: dedup_key.to_string(),
+        };
+        let config = self.config.lock().unwrap().clone();
+        let deliveries = self
+            .router
+            .lock()
+            .unwrap()
+            .route(&config, notification, Utc::now());
+        if !deliveries.is_empty() {
+            self.outbox.lock().unwrap().extend(deliveries);
+            self.wake.notify_one();
+        }
+    }
+
+    /// Send an event straight to the named channels, bypassing routes,
+    /// deduplication and quiet hours. Unknown channels are skipped.
+    pub fn notify_channels(
+        &self,
+        channels: &[String],
+        event_type: &str,
+        severity: EventSeverity,
+        title: &str,
+        body: &str,
+    ) {
+        let notification = Notification {
+            id: uuid::Uuid::new_v4().to_string(),
+            event_type: event_type.to_string(),
+            severity,
+            title: title.to_string(),
+            body: body.to_string(),
+            timestamp: Utc::now().timestamp(),
+            dedup_key: String::new(),
+        };
+        let config = self.config.lock().unwrap().clone();
+        let deliveries: Vec<Delivery> = channels
+            .iter()
+            .filter(|channel| {
+                let known = config.channels.contains_key(*channel);
+                if !known {
+                    warn!("Cannot send {event_type} to unknown channel '{channel}'");
+                }
+                known
+            })
+            .map(|channel| Delivery {
+                channel: channel.clone(),
+                items: vec![notification.clone()],
+            })
+            .collect();
+        if !deliveries.is_empty() {
+            self.outbox.lock().unwrap().extend(deliveries);
+            self.wake.notify_one();
+        }
+    }
+
+    /// The routing configuration in force
+    pub fn config(&self) -> NotificationConfig {
+        self.config.lock().unwrap().clone()
+    }
+
+    /// Replace the routing configuration and save it
+    pub fn set_config(&self, config: NotificationConfig) -> Result<()> {
+        config.validate()?;
+        if !self.config_path.is_empty() {
+            if let Some(dir) = std::path::Path::new(&self.config_path).parent() {
+                std::fs::create_dir_all(dir).ok();
+            }
+            std::fs::write(&self.config_path, toml::to_string(&config)?)
+                .with_context(|| format!("Cannot save {}", self.config_path))?;
+        }
+        *self.config.lock().unwrap() = config;
+        Ok(())
+    }
+
+    /// Recent console notifications, newest first
+    pub fn feed(&self) -> Vec<Notification> {
+        self.feed.lock().unwrap().iter().rev().cloned().collect()
+    }
+
+    /// Recent deliveries to outside channels, newest first
+    pub fn deliveries(&self) -> Vec<DeliveryRecord> {
+        self.deliveries
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .cloned()
+            .collect()
+    }
+
+    fn take_ready(&self) -> Vec<Delivery> {
+        let mut ready = std::mem::take(&mut *self.outbox.lock().unwrap());
+        ready.extend(self.router.lock().unwrap().take_due(Utc::now()));
+        ready
+    }
+
+    fn record(&self, record: DeliveryRecord) {
+        let mut deliveries = self.deliveries.lock().unwrap();
+        deliveries.push_back(record);
+        while deliveries.len() > CONSOLE_FEED_LEN {
+            deliveries.pop_front();
+        }
+    }
+}
+
+/// Send queued, batched and held notifications until `cancel` fires
+pub async fn run_notifier(
+    notifier: Arc<Notifier>,
+    clients: Arc<ServiceClients>,
+    cancel: CancellationToken,
+) {
+    info!("Notification dispatcher started");
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => break,
+            _ = notifier.wake.notified() => {}
+            _ = tokio::time::sleep(DISPATCH_INTERVAL) => {}
+        }
+
+        let channels = notifier.config().channels;
+        for delivery in notifier.take_ready() {
+            let Some(channel) = channels.get(&delivery.channel) else {
+                warn!(
+                    "Notification channel '{}' no longer exists",
+                    delivery.channel
+                );
+                continue;
+            };
+            if *channel == Channel::Console {
+                let mut feed = notifier.feed.lock().unwrap();
+                feed.extend(delivery.items);
+                while feed.len() > CONSOLE_FEED_LEN {
+                    feed.pop_front();
+                }
+                continue;
+            }
+
+            let (subject, text) = render(&delivery.items);
+            let result = send(&clients, channel, &subject, &text, &delivery.items).await;
+            if let Err(e) = &result {
+                warn!("Failed to notify '{}': {e:#}", delivery.channel);
+            }
+            notifier.record(DeliveryRecord {
+                channel: delivery.channel,
+                subject,
+                notifications: delivery.items.len(),
+                success: result.is_ok(),
+                error: result.err().map(|e| format!("{e:#}")).unwrap_or_default(),
+                timestamp: Utc::now().timestamp(),
+            });
+        }
+    }
+    info!("Notification dispatcher stopped");
+}
+
+/// Subject and text of one notification or a digest of several
+fn render(items: &[Notification]) -> (String, String) {
+    match items {
+        [one] => (
+            format!("[aiOS] {}", one.title),
+            format!("{}\n\n{}", one.title, one.body),
+        ),
+        many => {
+            let lines: Vec<String> = many
+                .iter()
+                .map(|n| format!("- [{:?}] {}", n.severity, n.title))
+                .collect();
+            (
+                format!("[aiOS] {} notifications", many.len()),
+                lines.join("\n"),
+            )
+        }
+    }
+}
+
+/// Send to an outside channel through the tools service
+async fn send(
+    clients: &ServiceClients,
+    channel: &Channel,
+    subject: &str,
+    text: &str,
+    items: &[Notification],
+) -> Result<()> {
+    let secret = |var: &str| {
+        std::env::var(var).with_context(|| format!("Environment variable {var} is not set"))
+    };
+    let (tool, input) = match channel {
+        Channel::Console => return Ok(()),
+        Channel::Email { to } => (
+            "email.send",
+            serde_json::json!({ "to": to, "subject": subject, "body": text }),
+        ),
+        Channel::Slack { url } => (
+            "web.webhook",
+            serde_json::json!({ "url": url, "payload": { "text": text } }),
+        ),
+        Channel::Telegram {
+            chat_id,
+            bot_token_env,
+        } => (
+            "web.webhook",
+            serde_json::json!({
+                "url": format!("https://api.telegram.org/bot{}/sendMessage", secret(bot_token_env)?),
+                "payload": { "chat_id": chat_id, "text": text },
+            }),
+        ),
+        Channel::Pagerduty { routing_key_env } => {
+            let critical = items.iter().any(|n| n.severity == EventSeverity::Critical);
+            (
+                "web.webhook",
+                serde_json::json!({
+                    "url": "https://events.pagerduty.com/v2/enqueue",
+                    "payload": {
+                        "routing_key": secret(routing_key_env)?,
+                        "event_action": "trigger",
+                        "payload": {
+                            "summary": subject,
+                            "source": "aios",
+                            "severity": if critical { "critical" } else { "warning" },
+                        },
+                    },
+                }),
+            )
+        }
+        Channel::Webhook { url, secret } => (
+            "web.webhook",
+            serde_json::json!({
+                "url": url,
+                "secret": secret,
+                "payload": { "subject": subject, "text": text, "notifications": items },
+            }),
+        ),
+    };
+
+    let mut client = clients
+        .tools()
+        .await
+        .map_err(|e| anyhow::anyhow!("Cannot connect to tools service: {e}"))?;
+    let response = client
+        .execute(tonic::Request::new(crate::proto::tools::ExecuteRequest {
+            tool_name: tool.to_string(),
+            agent_id: "notifications".to_string(),
+            task_id: String::new(),
+            input_json: serde_json::to_vec(&input)?,
+            reason: format!("Notification: {subject}"),
+            workload: String::new(),
+            resume_token: String::new(),
+            allow_experimental: false,
+        }))
+        .await
+        .map_err(|e| anyhow::anyhow!("Tool execution gRPC failed: {e}"))?
+        .into_inner();
+    if response.success {
+        Ok(())
+    } else {
+        anyhow::bail!("{tool} failed: {}", response.error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notification(event_type: &str, severity: EventSeverity, title: &str) -> Notification {
+        Notification {
+            id: uuid::Uuid::new_v4().to_string(),
+            event_type: event_type.to_string(),
+            severity,
+            title: title.to_string(),
+            body: String::new(),
+            timestamp: 0,
+            dedup_key: String::new(),
+        }
+    }
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    const CONFIG: &str = r#"
+[channels.pager]
+kind = "pagerduty"
+routing_key_env = "PD_KEY"
+
+[channels.mail]
+kind = "email"
+to = "ops@example.com"
+
+[[route]]
+event = "incident"
+channels = ["pager"]
+min_severity = "warning"
+quiet_hours = "22:00-07:00"
+timezone = "Europe/Berlin"
+
+[[route]]
+event = "weekly_*"
+channels = ["mail"]
+batch_secs = 3600
+"#;
+
+    #[test]
+    fn test_routing_dedup_quiet_hours_and_batching() {
+        let config: NotificationConfig = toml::from_str(CONFIG).unwrap();
+        config.validate().unwrap();
+        let mut router = RouterState::default();
+
+        // Daytime in Berlin: sent at once, then deduplicated
+        let noon = utc("2026-07-01T10:00:00Z");
+        let sent = router.route(
+            &config,
+            notification("incident", EventSeverity::Warning, "Disk"),
+            noon,
+        );
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].channel, "pager");
+        assert!(router
+            .route(
+                &config,
+                notification("incident", EventSeverity::Warning, "Disk"),
+                noon
+            )
+            .is_empty());
+        // Below the route's minimum severity, and no other route matches
+        assert!(router
+            .route(
+                &config,
+                notification("incident", EventSeverity::Info, "Note"),
+                noon
+            )
+            .is_empty());
+
+        // 23:30 Berlin: held until 07:00 Berlin (05:00 UTC), critical goes out
+        let night = utc("2026-07-01T21:30:00Z");
+        assert!(router
+            .route(
+                &config,
+                notification("incident", EventSeverity::Warning, "CPU"),
+                night
+            )
+            .is_empty());
+        assert_eq!(
+            router
+                .route(
+                    &config,
+                    notification("incident", EventSeverity::Critical, "Down"),
+                    night
+                )
+                .len(),
+            1
+        );
+        assert!(router.take_due(utc("2026-07-02T04:59:00Z")).is_empty());
+        let morning = router.take_due(utc("2026-07-02T05:00:00Z"));
+        assert_eq!(morning.len(), 1);
+        assert_eq!(morning[0].items[0].title, "CPU");
+
+        // Batched route: one digest after an hour
+        for title in ["a", "b"] {
+            assert!(router
+                .route(
+                    &config,
+                    notification("weekly_summary", EventSeverity::Info, title),
+                    noon
+                )
+                .is_empty());
+        }
+        let digest = router.take_due(noon + chrono::Duration::hours(1));
+        assert_eq!(digest[0].items.len(), 2);
+        assert_eq!(render(&digest[0].items).0, "[aiOS] 2 notifications");
+    }
+
+    #[test]
+    fn test_config_validation() {
+        let mut config: NotificationConfig = toml::from_str(CONFIG).unwrap();
+        config.routes[0].channels.push("slack".into());
+        assert!(config.validate().is_err());
+
+        let mut config: NotificationConfig = toml::from_str(CONFIG).unwrap();
+        config.routes[0].quiet_hours = "late".into();
+        assert!(config.validate().is_err());
+
+        // Round-trips through the saved form
+        let config: NotificationConfig = toml::from_str(CONFIG).unwrap();
+        let saved: NotificationConfig = toml::from_str(&toml::to_string(&config).unwrap()).unwrap();
+        assert_eq!(saved.routes.len(), 2);
+        assert_eq!(saved.channels["mail"], config.channels["mail"]);
+    }
+}
@@ -0,0 +1,424 @@
+//! Onboarding — "explain this system to me"
+//!
+//! A built-in goal pack that lets a new operator see what aiOS can do
+//! before giving it write access. Each template becomes one task of a goal
+//! labeled `onboarding` and `read-only`: inventory the system, summarize
+//! what is running, list risky findings. Tasks of a `read-only` goal (and
+//! of its subgoals) may only call read-only tools; any other call is denied
+//! before it reaches the tools service, and the tasks are never handed to
+//! agents or cluster nodes, which would not enforce that.
+//!
+//! When the goal completes, the AI writes up the findings as a narrative
+//! report in the goal's language. It is posted to the goal chat, written to
+//! /var/lib/aios/reports as `onboarding-<goal id>.md`, recorded as an
+//! artifact of the goal and sent as an `onboarding_report` notification.
+//! When inference is unavailable the report is rendered from the findings
+//! directly.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+use crate::clients::ServiceClients;
+use crate::goal_engine::GoalEngine;
+use crate::locale::Language;
+use crate::proto::common::{Artifact, Task};
+use crate::OrchestratorState;
+
+/// Label of goals started from the onboarding pack
+pub const ONBOARDING_LABEL: &str = "onboarding";
+
+/// Label that restricts a goal (and its subgoals) to read-only tools
+pub const READ_ONLY_LABEL: &str = "read-only";
+
+/// Event of the notification that carries the report
+pub const REPORT_EVENT: &str = "onboarding_report";
+
+/// Description of goals started from the pack
+pub const GOAL_DESCRIPTION: &str = "Explain this system to me";
+
+/// One introspection step of the pack
+#[derive(Debug, Clone, Serialize)]
+pub struct Template {
+    pub id: &'static str,
+    pub title: &'static str,
+    /// What the task asks the AI to do
+    pub description: &'static str,
+    /// Read-only tools suggested for the task
+    pub tools: &'static [&'static str],
+}
+
+/// The pack, in the order its findings are reported
+pub const TEMPLATES: &[Template] = &[
+    Template {
+        id: "inventory",
+        title: "Inventory",
+        description: "Inventory this system: hardware (CPU, memory, disks), operating system \
+            and kernel, network interfaces and installed packages. Only observe; do not change \
+            anything. Describe what you found.",
+        tools: &[
+            "hw.info",
+            "monitor.memory",
+            "monitor.disk",
+            "net.interfaces",
+            "pkg.list_installed",
+        ],
+    },
+    Template {
+        id: "services",
+        title: "Running services",
+        description: "Summarize what is running on this system: services and their state, the \
+            busiest processes and network activity. Only observe; do not change anything. \
+            Describe what each notable service is for.",
+        tools: &[
+            "service.list",
+            "process.list",
+            "monitor.cpu",
+            "monitor.network",
+        ],
+    },
+    Template {
+        id: "risks",
+        title: "Risky findings",
+        description: "List risky findings on this system: loose permissions on sensitive \
+            files, firewall gaps, failed services, disks running out of space, recurring errors \
+            in the logs and notable security audit events. Only observe; do not change \
+            anything. Give each finding its evidence and how serious it is.",
+        tools: &[
+            "sec.check_perms",
+            "firewall.rules",
+            "service.status",
+            "monitor.disk",
+            "monitor.logs",
+            "sec.audit_query",
+        ],
+    },
+];
+
+/// Whether `goal_id` or one of its ancestors is restricted to read-only tools
+pub fn is_read_only(goals: &GoalEngine, goal_id: &str) -> bool {
+    goals
+        .ancestry(goal_id)
+        .iter()
+        .any(|id| goals.has_label(id, READ_ONLY_LABEL))
+}
+
+/// Tasks of the templates named in `ids` (all of them when empty) for
+/// `goal_id`. Errors on an unknown template.
+pub fn plan(goal_id: &str, ids: &[String]) -> Result<Vec<Task>> {
+    if let Some(unknown) = ids.iter().find(|id| !TEMPLATES.iter().any(|t| t.id == *id)) {
+        anyhow::bail!("Unknown onboarding template: {unknown}");
+    }
+    let now = chrono::Utc::now().timestamp();
+    Ok(TEMPLATES
+        .iter()
+        .filter(|t| ids.is_empty() || ids.iter().any(|id| id == t.id))
+        .map(|template| Task {
+            id: uuid::Uuid::new_v4().to_string(),
+            goal_id: goal_id.to_string(),
+            description: format!(
+                "{} Use only read-only tools, such as {}.",
+                template.description,
+                template.tools.join(", ")
+            ),
+            status: "pending".to_string(),
+            intelligence_level: "tactical".to_string(),
+            required_tools: template.tools.iter().map(|t| t.to_string()).collect(),
+            input_json: serde_json::json!({ "onboarding": template.id })
+                .to_string()
+                .into_bytes(),
+            created_at: now,
+            ..Default::default()
+        })
+        .collect())
+}
+
+/// Start the pack as a read-only goal, with the templates named in `ids`
+/// (all of them when empty). `language` overrides the system language of
+/// its messages and report.
+pub async fn submit(
+    state: &mut OrchestratorState,
+    ids: &[String],
+    language: Option<Language>,
+    source: &str,
+) -> Result<String> {
+    let mut tasks = plan("", ids)?;
+    let goal_id = state
+        .goal_engine
+        .submit_goal(GOAL_DESCRIPTION.to_string(), 2, source.to_string())
+        .await?;
+    for task in &mut tasks {
+        task.goal_id = goal_id.clone();
+    }
+    state.goal_engine.update_labels(
+        &goal_id,
+        &[ONBOARDING_LABEL.to_string(), READ_ONLY_LABEL.to_string()],
+        &[],
+    )?;
+    if let Some(language) = language {
+        let metadata = serde_json::json!({ "language": language.code() }).to_string();
+        state
+            .goal_engine
+            .set_metadata(&goal_id, metadata.into_bytes());
+    }
+    state.task_planner.add_tasks(&tasks);
+    let task_count = tasks.len();
+    state.goal_engine.add_tasks(&goal_id, tasks);
+    state.goal_engine.update_status(
+        &goal_id,
+        "in_progress",
+        &format!("onboarding pack of {task_count} read-only tasks"),
+        source,
+    );
+    state.autonomy_waker.wake();
+    info!("Onboarding goal {goal_id} started with {task_count} tasks");
+    Ok(goal_id)
+}
+
+/// What one template's task found
+#[derive(Debug, Serialize)]
+struct Finding {
+    title: &'static str,
+    status: String,
+    /// The AI's notes and the outputs of the tools it ran
+    summary: String,
+}
+
+/// Findings of the onboarding tasks of `goal_id`, in template order
+fn findings(state: &OrchestratorState, goal_id: &str) -> Vec<Finding> {
+    let tasks = state.task_planner.get_tasks_for_goal(goal_id);
+    TEMPLATES
+        .iter()
+        .filter_map(|template| {
+            let task = tasks.iter().find(|task| {
+                serde_json::from_slice::<serde_json::Value>(&task.input_json)
+                    .ok()
+                    .and_then(|v| v.get("onboarding")?.as_str().map(|id| id == template.id))
+                    .unwrap_or(false)
+            })?;
+            let output: serde_json::Value =
+                serde_json::from_slice(&task.output_json).unwrap_or_default();
+            let tool_results = output
+                .get("tool_results")
+                .and_then(|v| v.as_array())
+                .cloned()
+                .unwrap_or_default();
+            let summary = crate::autonomy::build_completion_summary(
+                output
+                    .get("ai_response")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default(),
+                &tool_results,
+            );
+            Some(Finding {
+                title: template.title,
+                status: task.status.clone(),
+                summary: if summary.is_empty() {
+                    task.error.clone()
+                } else {
+                    summary
+                },
+            })
+        })
+        .collect()
+}
+
+/// The report rendered from the findings, without inference
+fn render_markdown(findings: &[Finding]) -> String {
+    let mut markdown = format!("# {GOAL_DESCRIPTION}\n");
+    for finding in findings {
+        markdown.push_str(&format!("\n## {}\n\n", finding.title));
+        if finding.status != "completed" {
+            markdown.push_str(&format!("_This step {}._\n\n", finding.status));
+        }
+        if finding.summary.is_empty() {
+            markdown.push_str("Nothing was found.\n");
+        } else {
+            markdown.push_str(&finding.summary);
+            markdown.push('\n');
+        }
+    }
+    markdown
+}
+
+/// Have the AI write the findings up as a narrative for a new operator
+async fn write_with_ai(
+    clients: &ServiceClients,
+    findings: &[Finding],
+    language: Language,
+) -> Option<String> {
+    let system_prompt = format!(
+        "You explain a computer system to an operator who has just started using aiOS, an \
+         AI-run operating system. Write a narrative report from the findings you are given: \
+         what this machine is, what runs on it and why, and what looks risky, most serious \
+         first, with what the operator could do about each risk. Use only the facts in the \
+         findings; do not invent hardware, services or problems. Start with a level-1 heading \
+         and respond with the markdown report only.{}",
+        language.prompt_instruction()
+    );
+    let prompt = format!(
+        "Findings:\n{}",
+        serde_json::to_string_pretty(findings).ok()?
+    );
+    let mut client = clients.api_gateway().await.ok()?;
+    let request = tonic::Request::new(crate::proto::api_gateway::ApiInferRequest {
+        prompt,
+        system_prompt,
+        max_tokens: 4096,
+        temperature: 0.3,
+        preferred_provider: String::new(),
+        requesting_agent: "onboarding-report".to_string(),
+        task_id: String::new(),
+        allow_fallback: true,
+        response_schema: String::new(),
+        sections: Vec::new(),
+        session_id: String::new(),
+        turn_kind: String::new(),
+        model_class: String::new(),
+        workload: crate::workload::INTERACTIVE.to_string(),
+        goal_id: crate::usage::current_goal_id(),
+        goal_budget: crate::usage::current_budget(),
+        bypass_cache: false,
+    });
+    match client.infer(request).await {
+        Ok(response) => {
+            let response = response.into_inner();
+            crate::usage::record_inference(response.tokens_used.into(), response.cost_usd);
+            let text = response.text.trim();
+            (!text.is_empty()).then(|| text.to_string())
+        }
+        Err(e) => {
+            warn!("AI could not write the onboarding report: {e}");
+            None
+        }
+    }
+}
+
+/// Write the report of the completed onboarding goal `goal_id` to `dir`,
+/// returning its path
+pub async fn generate_report(
+    state: &RwLock<OrchestratorState>,
+    goal_id: &str,
+    dir: &str,
+) -> Result<String> {
+    let (findings, language, clients, charge) = {
+        let s = state.read().await;
+        (
+            findings(&s, goal_id),
+            crate::locale::for_goal(&s.goal_engine, goal_id, s.language),
+            s.clients.clone(),
+            crate::usage::Charge::new(&s.usage, &s.goal_engine, goal_id),
+        )
+    };
+    let written = crate::usage::scope(charge, write_with_ai(&clients, &findings, language));
+    let markdown = match written.await {
+        Some(text) => text,
+        None => render_markdown(&findings),
+    };
+
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {dir}"))?;
+    let path = format!("{dir}/onboarding-{goal_id}.md");
+    std::fs::write(&path, &markdown).with_context(|| format!("Failed to write {path}"))?;
+
+    let mut s = crate::liveness::write_state(state, "onboarding.report").await;
+    s.goal_engine.add_message(goal_id, "ai", &markdown);
+    let artifact = Artifact {
+        name: "System overview".to_string(),
+        path: path.clone(),
+        kind: "report".to_string(),
+        size_bytes: markdown.len() as i64,
+        description: "Narrative report of the onboarding goal pack".to_string(),
+        ..Default::default()
+    };
+    if let Err(e) = s.artifacts.record(goal_id, "", "orchestrator", &artifact) {
+        warn!("Failed to record the onboarding report of goal {goal_id}: {e}");
+    }
+    s.notifier.notify(
+        REPORT_EVENT,
+        crate::event_bus::EventSeverity::Info,
+        GOAL_DESCRIPTION,
+        &markdown,
+        goal_id,
+    );
+    info!("Onboarding report of goal {goal_id} written to {path}");
+    Ok(path)
+}
+
+/// Write the report of a completed onboarding goal in the background
+pub fn spawn_report(state: Arc<RwLock<OrchestratorState>>, goal_id: String) {
+    tokio::spawn(async move {
+        if let Err(e) = generate_report(&state, &goal_id, crate::report::REPORTS_DIR).await {
+            warn!("Onboarding report of goal {goal_id} failed: {e:#}");
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_selects_templates() {
+        let tasks = plan("goal-1", &[]).unwrap();
+        assert_eq!(tasks.len(), TEMPLATES.len());
+        assert!(tasks
+            .iter()
+            .all(|t| t.goal_id == "goal-1" && t.status == "pending"));
+
+        let tasks = plan("goal-1", &["risks".to_string()]).unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(
+            String::from_utf8_lossy(&tasks[0].input_json),
+            r#"{"onboarding":"risks"}"#
+        );
+        assert!(plan("goal-1", &["reboot".to_string()]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_only_is_inherited() {
+        let mut goals = GoalEngine::new();
+        let root = goals
+            .submit_goal(GOAL_DESCRIPTION.into(), 2, "console".into())
+            .await
+            .unwrap();
+        let child = goals
+            .spawn_subgoals(
+                &root,
+                "task-1",
+                vec![crate::goal_engine::SubgoalSpec {
+                    description: "Look closer at /var".into(),
+                    priority: None,
+                }],
+            )
+            .await
+            .unwrap()
+            .remove(0);
+        assert!(!is_read_only(&goals, &child));
+
+        goals
+            .update_labels(&root, &[READ_ONLY_LABEL.to_string()], &[])
+            .unwrap();
+        assert!(is_read_only(&goals, &child));
+    }
+
+    #[test]
+    fn test_render_markdown_without_inference() {
+        let markdown = render_markdown(&[
+            Finding {
+                title: "Inventory",
+                status: "completed".into(),
+                summary: "**hw.info**: 4 CPUs".into(),
+            },
+            Finding {
+                title: "Risky findings",
+                status: "failed".into(),
+                summary: String::new(),
+            },
+        ]);
+        assert!(markdown.starts_with("# Explain this system to me"));
+        assert!(markdown.contains("## Inventory\n\n**hw.info**: 4 CPUs"));
+        assert!(markdown.contains("_This step failed._"));
+    }
+}
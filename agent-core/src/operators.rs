@@ -0,0 +1,86 @@
+//! Operator accounts — who may sign in to the management console
+//!
+//! aios-init's setup wizard writes operator accounts to
+//! /etc/aios/operators.toml, each password Argon2-hashed. The console signs
+//! an operator in against that file; the orchestrator then issues the
+//! operator's `user:<name>` identity a token in the memory service, which
+//! console requests present from then on.
+//!
+//! ```toml
+//! [[operator]]
+//! name = "ops"
+//! password_hash = "$argon2id$v=19$..."
+//! created_at = 1700000000
+//! ```
+
+use argon2::password_hash::{PasswordHash, PasswordVerifier};
+use argon2::Argon2;
+use serde::Deserialize;
+
+/// Where aios-init writes operator accounts
+pub const OPERATORS_PATH: &str = "/etc/aios/operators.toml";
+
+/// operators.toml layout, as aios-init writes it
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct OperatorsFile {
+    operator: Vec<Operator>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Operator {
+    name: String,
+    password_hash: String,
+}
+
+/// Whether `password` is the password of operator `name` in the accounts
+/// file at `path`. A missing or unreadable file has no operators.
+pub fn verify(path: &str, name: &str, password: &str) -> bool {
+    let file: OperatorsFile = match std::fs::read_to_string(path) {
+        Ok(contents) => match toml::from_str(&contents) {
+            Ok(file) => file,
+            Err(e) => {
+                tracing::warn!("Cannot read operator accounts from {path}: {e}");
+                return false;
+            }
+        },
+        Err(_) => return false,
+    };
+    file.operator
+        .iter()
+        .filter(|op| op.name == name)
+        .filter_map(|op| PasswordHash::new(&op.password_hash).ok())
+        .any(|hash| {
+            Argon2::default()
+                .verify_password(password.as_bytes(), &hash)
+                .is_ok()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use argon2::password_hash::{rand_core::OsRng, PasswordHasher, SaltString};
+
+    #[test]
+    fn test_operators_sign_in_with_their_password() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("operators.toml");
+        let path = path.to_str().unwrap();
+        assert!(!verify(path, "ops", "longenough"));
+
+        let hash = Argon2::default()
+            .hash_password(b"longenough", &SaltString::generate(&mut OsRng))
+            .unwrap()
+            .to_string();
+        std::fs::write(
+            path,
+            format!("[[operator]]\nname = \"ops\"\npassword_hash = \"{hash}\"\ncreated_at = 1700000000\n"),
+        )
+        .unwrap();
+        assert!(verify(path, "ops", "longenough"));
+        assert!(!verify(path, "ops", "wrong password"));
+        assert!(!verify(path, "admin", "longenough"));
+    }
+}
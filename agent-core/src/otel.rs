@@ -0,0 +1,112 @@
+//! OpenTelemetry setup shared by every traced service
+//!
+//! Logs go to stdout as before. When `OTEL_EXPORTER_OTLP_ENDPOINT` (or
+//! `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT`) is set, spans are also exported
+//! over OTLP/gRPC, as service `OTEL_SERVICE_NAME` (default: the name passed
+//! to [`init`]). Calls carrying a W3C `traceparent` header, as the
+//! orchestrator's do, continue the caller's trace, so the work they cause
+//! lands in the trace of the goal it was made for.
+//!
+//! The orchestrator uses this through telemetry.rs; the tools service and
+//! the gateway include the file directly with `#[path]`.
+
+use opentelemetry::propagation::Extractor;
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::TracerProvider;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Layer;
+
+/// Set up logging, and span export when an OTLP endpoint is configured.
+/// The returned provider flushes pending spans on [`shutdown`].
+pub fn init(service: &str) -> Option<TracerProvider> {
+    let provider = otlp_endpoint_configured()
+        .then(|| build_provider(service))
+        .and_then(|result| match result {
+            Ok(provider) => Some(provider),
+            Err(e) => {
+                eprintln!("OpenTelemetry export disabled: {e}");
+                None
+            }
+        });
+    let otel = provider.as_ref().map(|provider| {
+        tracing_opentelemetry::layer().with_tracer(provider.tracer(service.to_string()))
+    });
+    opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_target(false)
+                .with_level(true)
+                .compact()
+                .with_filter(LevelFilter::INFO),
+        )
+        .with(otel.with_filter(LevelFilter::INFO))
+        .init();
+    provider
+}
+
+fn otlp_endpoint_configured() -> bool {
+    [
+        "OTEL_EXPORTER_OTLP_ENDPOINT",
+        "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+    ]
+    .iter()
+    .any(|name| std::env::var(name).is_ok_and(|v| !v.is_empty()))
+}
+
+fn build_provider(service: &str) -> anyhow::Result<TracerProvider> {
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_tonic()
+        .build()?;
+    let name = std::env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| service.to_string());
+    let provider = TracerProvider::builder()
+        .with_batch_exporter(exporter, opentelemetry_sdk::runtime::Tokio)
+        .with_resource(opentelemetry_sdk::Resource::new_with_defaults([
+            opentelemetry::KeyValue::new("service.name", name),
+        ]))
+        .build();
+    opentelemetry::global::set_tracer_provider(provider.clone());
+    Ok(provider)
+}
+
+/// Export the spans still buffered
+pub fn shutdown(provider: Option<TracerProvider>) {
+    if let Some(provider) = provider {
+        if let Err(e) = provider.shutdown() {
+            tracing::warn!("OpenTelemetry shutdown failed: {e}");
+        }
+    }
+}
+
+struct HeaderReader<'a>(&'a http::HeaderMap);
+
+impl Extractor for HeaderReader<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|v| v.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|k| k.as_str()).collect()
+    }
+}
+
+/// Span of an incoming gRPC call, continuing the caller's trace
+/// (`Server::trace_fn`)
+pub fn server_span(req: &http::Request<()>) -> tracing::Span {
+    let span = tracing::info_span!(
+        "grpc.request",
+        otel.name = %req.uri().path(),
+        otel.kind = "server",
+        rpc.system = "grpc",
+    );
+    let parent = opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.extract(&HeaderReader(req.headers()))
+    });
+    span.set_parent(parent);
+    span
+}
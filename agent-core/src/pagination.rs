@@ -0,0 +1,124 @@
+//! Pagination — paging and sorting shared by the list endpoints
+//!
+//! Every list RPC and console endpoint takes the same parameters: `limit`
+//! and `offset` select a page, and `sort` names a field to order by,
+//! prefixed with `-` for descending order. gRPC lists return everything
+//! when `limit` is 0, as they did before paging; console lists default to
+//! [`REST_DEFAULT_LIMIT`] items and never return more than
+//! [`REST_MAX_LIMIT`], reporting the unpaged count in `X-Total-Count`.
+
+use std::cmp::Ordering;
+
+use serde::Deserialize;
+
+/// Console page size when none is given
+pub const REST_DEFAULT_LIMIT: i32 = 50;
+
+/// Largest page the console serves
+pub const REST_MAX_LIMIT: i32 = 500;
+
+/// Paging and sort parameters of a console list endpoint
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PageParams {
+    #[serde(default)]
+    pub limit: i32,
+    #[serde(default)]
+    pub offset: i32,
+    #[serde(default)]
+    pub sort: String,
+}
+
+impl PageParams {
+    /// Page size for the console: the default when unset, capped
+    pub fn rest_limit(&self) -> i32 {
+        if self.limit <= 0 {
+            REST_DEFAULT_LIMIT
+        } else {
+            self.limit.min(REST_MAX_LIMIT)
+        }
+    }
+}
+
+/// Check that `sort` is empty or one of `fields`, optionally prefixed
+/// with `-`
+pub fn check_sort(sort: &str, fields: &[&str]) -> Result<(), String> {
+    let field = sort.strip_prefix('-').unwrap_or(sort);
+    if sort.is_empty() || fields.contains(&field) {
+        Ok(())
+    } else {
+        Err(format!(
+            "Cannot sort by '{field}'; expected one of: {}",
+            fields.join(", ")
+        ))
+    }
+}
+
+/// Order `items` by `sort`, one of `fields` optionally prefixed with `-`
+/// for descending order. An empty `sort` keeps the current order.
+pub fn sort_by_field<T>(
+    items: &mut [T],
+    sort: &str,
+    fields: &[&str],
+    compare: impl Fn(&T, &T, &str) -> Ordering,
+) -> Result<(), String> {
+    check_sort(sort, fields)?;
+    if sort.is_empty() {
+        return Ok(());
+    }
+    let (field, descending) = match sort.strip_prefix('-') {
+        Some(field) => (field, true),
+        None => (sort, false),
+    };
+    items.sort_by(|a, b| {
+        let ordering = compare(a, b, field);
+        if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+    Ok(())
+}
+
+/// The page of `items` starting at `offset`, at most `limit` long (all
+/// remaining items when `limit` is 0 or less), and the unpaged count
+pub fn page<T>(items: Vec<T>, limit: i32, offset: i32) -> (Vec<T>, i32) {
+    let total = items.len() as i32;
+    let items = items.into_iter().skip(offset.max(0) as usize);
+    let items = if limit > 0 {
+        items.take(limit as usize).collect()
+    } else {
+        items.collect()
+    };
+    (items, total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sort_and_page() {
+        let mut items = vec![("b", 2), ("a", 3), ("c", 1)];
+        let compare = |x: &(&str, i32), y: &(&str, i32), field: &str| match field {
+            "name" => x.0.cmp(y.0),
+            _ => x.1.cmp(&y.1),
+        };
+        sort_by_field(&mut items, "-count", &["name", "count"], compare).unwrap();
+        assert_eq!(items, vec![("a", 3), ("b", 2), ("c", 1)]);
+        assert!(sort_by_field(&mut items, "size", &["name", "count"], compare).is_err());
+
+        let (first, total) = page(items.clone(), 2, 0);
+        assert_eq!((first.len(), total), (2, 3));
+        let (rest, _) = page(items.clone(), 2, 2);
+        assert_eq!(rest, vec![("c", 1)]);
+        assert_eq!(page(items, 0, 1).0.len(), 2);
+
+        let params = PageParams {
+            limit: 10_000,
+            ..Default::default()
+        };
+        assert_eq!(params.rest_limit(), REST_MAX_LIMIT);
+        assert_eq!(PageParams::default().rest_limit(), REST_DEFAULT_LIMIT);
+    }
+}
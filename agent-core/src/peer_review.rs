@@ -0,0 +1,639 @@
+//! Peer review — a second model checks risky plans before they run
+//!
+//! When a plan from the reasoning loop calls a tool whose risk level is
+//! listed in peer_review.toml, the plan is shown to a second model —
+//! another provider than the one that planned it, unless one is configured
+//! — together with the task and the operating policy, and it is asked
+//! whether the calls serve the task and whether any of them deletes user
+//! data or is otherwise destructive. The plan runs only if the reviewer
+//! approves. A reviewer that objects, or gives no usable answer, escalates
+//! the plan to a person: the task waits for input, the `approval`
+//! notification goes out, and a signed-in operator approves the exact
+//! calls (`POST /api/goals/:goal_id/review/approve`, with a verified user
+//! token) or rejects them (`.../review/reject`), on the console's Plan
+//! Reviews panel or through the API. Replying to the goal instead lets the model plan
+//! again with the reply in view.
+//!
+//! Rules in approvals.toml (see [`crate::approvals`]) can exempt calls from
+//! review, send plans straight to an operator, and escalate or deny plans
+//! an operator leaves unanswered.
+//!
+//! Both opinions of every review, and the operator's ruling, are recorded
+//! in the decision ledger.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+use crate::approvals::ApprovalPolicy;
+use crate::clients::ServiceClients;
+
+/// Default location of the peer review configuration
+pub const PEER_REVIEW_CONFIG_PATH: &str = "/etc/aios/peer_review.toml";
+
+/// How often tool risk levels are refreshed from the tools service
+const RISK_REFRESH_INTERVAL: Duration = Duration::from_secs(300);
+
+/// System prompt of the reviewing model
+pub const REVIEW_SYSTEM_PROMPT: &str = "You are aiOS's safety reviewer. Another model planned \
+     the tool calls below for an operating system task; nothing has run yet. Judge them \
+     strictly: approve only calls that the task needs and that the policy allows.";
+
+/// Response schema of the reviewing model
+pub const REVIEW_SCHEMA: &str = r#"{"type":"object","properties":{"approve":{"type":"boolean"},"destructive":{"type":"boolean"},"concerns":{"type":"array","items":{"type":"string"}},"reasoning":{"type":"string"}},"required":["approve","reasoning"]}"#;
+
+/// peer_review.toml layout
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct PeerReviewConfig {
+    pub enabled: bool,
+    /// Tool risk levels that send a plan to review
+    pub risk_levels: Vec<String>,
+    /// Provider of the reviewing model; by default the other of claude
+    /// and openai from the planner's
+    pub reviewer_provider: String,
+    /// Model class asked for the review ("" keeps the task's)
+    pub reviewer_model_class: String,
+    /// What the reviewer holds plans to
+    pub policy: String,
+}
+
+impl Default for PeerReviewConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            risk_levels: vec!["high".to_string(), "critical".to_string()],
+            reviewer_provider: String::new(),
+            reviewer_model_class: String::new(),
+            policy: "Never delete, overwrite or expose user data. Never disable security \
+                     controls, backups or audit logging. Make no change the task does not \
+                     ask for, and prefer reversible changes to irreversible ones."
+                .to_string(),
+        }
+    }
+}
+
+/// One model's opinion of a plan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Opinion {
+    pub model: String,
+    pub approve: bool,
+    pub reasoning: String,
+    #[serde(default)]
+    pub concerns: Vec<String>,
+}
+
+/// What the reviewing model answers
+#[derive(Debug, Deserialize)]
+pub struct ReviewVerdict {
+    pub approve: bool,
+    #[serde(default)]
+    pub destructive: bool,
+    #[serde(default)]
+    pub concerns: Vec<String>,
+    #[serde(default)]
+    pub reasoning: String,
+}
+
+impl ReviewVerdict {
+    /// The reviewer's opinion; a plan judged destructive is never approved
+    pub fn into_opinion(self, model: &str) -> Opinion {
+        let mut concerns = self.concerns;
+        if self.destructive && !concerns.iter().any(|c| c.contains("destructive")) {
+            concerns.push("destructive".to_string());
+        }
+        Opinion {
+            model: model.to_string(),
+            approve: self.approve && !self.destructive,
+            reasoning: self.reasoning,
+            concerns,
+        }
+    }
+}
+
+/// A tool call of a reviewed plan
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedCall {
+    pub tool: String,
+    /// Risk level, when the call needs approval
+    pub risk: String,
+    pub input: serde_json::Value,
+    /// Approval rule the call matched
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub rule: String,
+    #[serde(skip)]
+    pub(crate) key: String,
+}
+
+/// A plan, the planner's case for it, and the reviewer's opinion
+#[derive(Debug, Clone, Serialize)]
+pub struct Review {
+    pub task_id: String,
+    pub goal_id: String,
+    pub calls: Vec<PlannedCall>,
+    pub planner: Opinion,
+    pub reviewer: Opinion,
+    pub created_at: i64,
+    #[serde(skip)]
+    pub tokens_used: i32,
+    /// When the plan is escalated if no operator has ruled (0 = never)
+    pub escalate_at: i64,
+    /// Notification channels the escalation goes to
+    #[serde(skip)]
+    pub escalate_to: Vec<String>,
+    pub escalated: bool,
+    /// When the plan is denied if no operator has ruled (0 = never)
+    pub deny_at: i64,
+}
+
+impl Review {
+    pub fn agreed(&self) -> bool {
+        self.reviewer.approve
+    }
+
+    /// Whether the approval policy, not a model, sent the plan to an operator
+    pub fn by_policy(&self) -> bool {
+        self.reviewer.model == crate::approvals::POLICY_REVIEWER
+    }
+
+    /// Both opinions, as recorded in the decision ledger
+    pub fn opinions(&self) -> Vec<String> {
+        [&self.planner, &self.reviewer]
+            .iter()
+            .zip(["planner", "reviewer"])
+            .map(|(o, role)| {
+                let verdict = if o.approve { "approve" } else { "reject" };
+                let mut text = format!("{role} {} ({verdict}): {}", o.model, o.reasoning);
+                if !o.concerns.is_empty() {
+                    text.push_str(&format!(" Concerns: {}", o.concerns.join("; ")));
+                }
+                text
+            })
+            .collect()
+    }
+
+    /// One line per risky call, e.g. `fs.delete (high) {"path":"/home"}`
+    pub fn risky_calls(&self) -> String {
+        self.calls
+            .iter()
+            .filter(|c| !c.risk.is_empty())
+            .map(|c| format!("{} ({}) {}", c.tool, c.risk, c.input))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Risky plans under review, and the ones operators approved
+pub struct PeerReview {
+    config: PeerReviewConfig,
+    approvals: ApprovalPolicy,
+    /// Tool name → risk level
+    tool_risks: Mutex<HashMap<String, String>>,
+    risks_refreshed: Mutex<Option<Instant>>,
+    /// Plans escalated to an operator, by task
+    escalations: Mutex<HashMap<String, Review>>,
+    /// Calls an operator approved, by task
+    approved: Mutex<HashMap<String, HashSet<String>>>,
+}
+
+impl Default for PeerReview {
+    fn default() -> Self {
+        Self::with_config(PeerReviewConfig::default())
+    }
+}
+
+impl PeerReview {
+    fn with_config(config: PeerReviewConfig) -> Self {
+        Self {
+            config,
+            approvals: ApprovalPolicy::default(),
+            tool_risks: Mutex::new(HashMap::new()),
+            risks_refreshed: Mutex::new(None),
+            escalations: Mutex::new(HashMap::new()),
+            approved: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Load the configuration from `path`; review stays off when it is
+    /// missing or invalid
+    pub fn load(path: &str) -> Self {
+        let config = match std::fs::read_to_string(path) {
+            Ok(contents) => match toml::from_str::<PeerReviewConfig>(&contents) {
+                Ok(config) => {
+                    if config.enabled {
+                        info!(
+                            "Peer review enabled for {} risk tools",
+                            config.risk_levels.join("/")
+                        );
+                    }
+                    config
+                }
+                Err(e) => {
+                    warn!("Invalid peer review configuration {path}: {e}; peer review disabled");
+                    PeerReviewConfig::default()
+                }
+            },
+            Err(_) => PeerReviewConfig::default(),
+        };
+        Self::with_config(config)
+    }
+
+    /// Apply the approval rules of `approvals`
+    pub fn with_approvals(mut self, approvals: ApprovalPolicy) -> Self {
+        self.approvals = approvals;
+        self
+    }
+
+    /// Provider of the reviewing model for a plan made by `planner_model`
+    pub fn reviewer_provider(&self, planner_model: &str) -> String {
+        if !self.config.reviewer_provider.is_empty() {
+            return self.config.reviewer_provider.clone();
+        }
+        if planner_model.contains("claude") {
+            "openai".to_string()
+        } else {
+            "claude".to_string()
+        }
+    }
+
+    pub fn reviewer_model_class<'a>(&'a self, task_model_class: &'a str) -> &'a str {
+        if self.config.reviewer_model_class.is_empty() {
+            task_model_class
+        } else {
+            &self.config.reviewer_model_class
+        }
+    }
+
+    /// The calls of `task_id`'s plan, when it needs approval: `agent` is
+    /// not exempt from it, a call is risky or an approval rule matches it,
+    /// and an operator has not approved these exact calls. An approval is
+    /// used up by the plan it covers.
+    pub async fn plan_to_review(
+        &self,
+        clients: &ServiceClients,
+        task_id: &str,
+        agent: &str,
+        calls: &[(&str, &[u8])],
+    ) -> Option<Vec<PlannedCall>> {
+        if !self.config.enabled && self.approvals.is_empty() {
+            return None;
+        }
+        self.refresh_risks(clients).await;
+        let planned: Vec<PlannedCall> = {
+            let risks = self.tool_risks.lock().unwrap();
+            calls
+                .iter()
+                .map(|(tool, input_json)| {
+                    let level = risks.get(*tool).map(String::as_str).unwrap_or_default();
+                    let input =
+                        serde_json::from_slice(input_json).unwrap_or(serde_json::Value::Null);
+                    let (risk, rule) = match self.approvals.rule_for(tool, level, &input) {
+                        Some(rule) if rule.exempts(agent) => {
+                            debug!(
+                                "Task {task_id}: {tool} approved for {agent} by {}",
+                                rule.name
+                            );
+                            (String::new(), String::new())
+                        }
+                        Some(rule) if rule.approver == crate::approvals::Approver::None => {
+                            (String::new(), String::new())
+                        }
+                        Some(rule) => {
+                            let risk = if level.is_empty() { "unrated" } else { level };
+                            (risk.to_string(), rule.name.clone())
+                        }
+                        None if self.config.enabled
+                            && self.config.risk_levels.iter().any(|r| r == level) =>
+                        {
+                            (level.to_string(), String::new())
+                        }
+                        None => (String::new(), String::new()),
+                    };
+                    PlannedCall {
+                        tool: tool.to_string(),
+                        risk,
+                        input,
+                        rule,
+                        key: crate::task_checkpoint::call_key(tool, input_json),
+                    }
+                })
+                .collect()
+        };
+        if planned.iter().all(|c| c.risk.is_empty()) {
+            return None;
+        }
+        let mut approved = self.approved.lock().unwrap();
+        if approved
+            .get(task_id)
+            .is_some_and(|keys| planned.iter().all(|c| keys.contains(&c.key)))
+        {
+            approved.remove(task_id);
+            info!("Task {task_id}: running the plan an operator approved");
+            return None;
+        }
+        Some(planned)
+    }
+
+    /// Prompt asking the reviewer about a plan
+    pub fn prompt(&self, task: &str, calls: &[PlannedCall], planner_reasoning: &str) -> String {
+        let mut prompt = format!("Task: {task}\n\nPlanned tool calls, in order:\n");
+        for (i, call) in calls.iter().enumerate() {
+            let risk = if call.risk.is_empty() {
+                String::new()
+            } else {
+                format!(" [{} risk]", call.risk)
+            };
+            prompt.push_str(&format!("{}. {}{risk} {}\n", i + 1, call.tool, call.input));
+        }
+        if !planner_reasoning.is_empty() {
+            prompt.push_str(&format!("\nThe planner's reasoning: {planner_reasoning}\n"));
+        }
+        prompt.push_str(&format!(
+            "\nPolicy: {}\n\nWill any call delete or damage user data? Is any command \
+             destructive or irreversible beyond what the task asks? Does every call serve the \
+             task? Respond with ONLY a JSON object: {{\"approve\": true or false, \
+             \"destructive\": true or false, \"concerns\": [\"...\"], \"reasoning\": \"why\"}}\n",
+            self.config.policy
+        ));
+        prompt
+    }
+
+    /// Approval rule that sends `calls` straight to an operator, if any
+    pub fn operator_rule(&self, calls: &[PlannedCall]) -> Option<String> {
+        self.approvals.operator_rule(calls)
+    }
+
+    /// Hold a plan the reviewer objected to until an operator rules on it,
+    /// setting when it is escalated and denied if none does
+    pub fn escalate(&self, mut review: Review) {
+        let chain = self.approvals.chain(&review.calls);
+        let after = |secs: i64| {
+            if secs > 0 {
+                review.created_at + secs
+            } else {
+                0
+            }
+        };
+        review.escalate_at = after(chain.escalate_after_secs);
+        review.deny_at = after(chain.deny_after_secs);
+        review.escalate_to = chain.escalate_to;
+        self.escalations
+            .lock()
+            .unwrap()
+            .insert(review.task_id.clone(), review);
+    }
+
+    /// Plans left unanswered past their escalation time, which are marked
+    /// escalated, and past their deny time, which are removed
+    pub fn overdue(&self, now: i64) -> (Vec<Review>, Vec<Review>) {
+        let mut escalations = self.escalations.lock().unwrap();
+        let denied: Vec<String> = escalations
+            .values()
+            .filter(|r| r.deny_at > 0 && r.deny_at <= now)
+            .map(|r| r.task_id.clone())
+            .collect();
+        let denied = denied
+            .iter()
+            .filter_map(|t| escalations.remove(t))
+            .collect();
+        let escalated = escalations
+            .values_mut()
+            .filter(|r| !r.escalated && r.escalate_at > 0 && r.escalate_at <= now)
+            .map(|r| {
+                r.escalated = true;
+                r.clone()
+            })
+            .collect();
+        (escalated, denied)
+    }
+
+    /// Plans waiting for an operator, oldest first
+    pub fn escalations(&self) -> Vec<Review> {
+        let mut reviews: Vec<Review> = self.escalations.lock().unwrap().values().cloned().collect();
+        reviews.sort_by_key(|r| r.created_at);
+        reviews
+    }
+
+    /// Approve the escalated plans of `goal_id`: when their tasks plan the
+    /// same calls again, they run without another review
+    pub fn approve(&self, goal_id: &str) -> Vec<Review> {
+        let reviews = self.take(goal_id);
+        let mut approved = self.approved.lock().unwrap();
+        for review in &reviews {
+            approved.insert(
+                review.task_id.clone(),
+                review.calls.iter().map(|c| c.key.clone()).collect(),
+            );
+        }
+        reviews
+    }
+
+    /// Reject the escalated plans of `goal_id`
+    pub fn reject(&self, goal_id: &str) -> Vec<Review> {
+        self.take(goal_id)
+    }
+
+    fn take(&self, goal_id: &str) -> Vec<Review> {
+        let mut escalations = self.escalations.lock().unwrap();
+        let tasks: Vec<String> = escalations
+            .values()
+            .filter(|r| r.goal_id == goal_id)
+            .map(|r| r.task_id.clone())
+            .collect();
+        tasks.iter().filter_map(|t| escalations.remove(t)).collect()
+    }
+
+    /// Remember the risk levels of the tools
+    fn set_tool_risks(&self, tools: &[crate::proto::tools::ToolDefinition]) {
+        *self.tool_risks.lock().unwrap() = tools
+            .iter()
+            .map(|t| (t.name.clone(), t.risk_level.clone()))
+            .collect();
+    }
+
+    async fn refresh_risks(&self, clients: &ServiceClients) {
+        let stale = self
+            .risks_refreshed
+            .lock()
+            .unwrap()
+            .is_none_or(|at| at.elapsed() >= RISK_REFRESH_INTERVAL);
+        if !stale {
+            return;
+        }
+        let Ok(mut client) = clients.tools().await else {
+            return;
+        };
+        let request = crate::proto::tools::ListToolsRequest::default();
+        match client.list_tools(request).await {
+            Ok(response) => {
+                self.set_tool_risks(&response.into_inner().tools);
+                *self.risks_refreshed.lock().unwrap() = Some(Instant::now());
+            }
+            Err(e) => debug!("Cannot refresh tool risk levels: {e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn review(task_id: &str, goal_id: &str, calls: Vec<PlannedCall>) -> Review {
+        let opinion = |model: &str, approve| Opinion {
+            model: model.into(),
+            approve,
+            reasoning: String::new(),
+            concerns: Vec::new(),
+        };
+        Review {
+            task_id: task_id.into(),
+            goal_id: goal_id.into(),
+            calls,
+            planner: opinion("claude-sonnet", true),
+            reviewer: opinion("gpt-4o", false),
+            created_at: 0,
+            tokens_used: 0,
+            escalate_at: 0,
+            escalate_to: Vec::new(),
+            escalated: false,
+            deny_at: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_risky_plan_needs_review_until_approved() {
+        let peer_review = PeerReview::with_config(PeerReviewConfig {
+            enabled: true,
+            ..Default::default()
+        });
+        peer_review.set_tool_risks(&[
+            crate::proto::tools::ToolDefinition {
+                name: "fs.delete".into(),
+                risk_level: "high".into(),
+                ..Default::default()
+            },
+            crate::proto::tools::ToolDefinition {
+                name: "fs.list".into(),
+                risk_level: "low".into(),
+                ..Default::default()
+            },
+        ]);
+        *peer_review.risks_refreshed.lock().unwrap() = Some(Instant::now());
+        let clients = ServiceClients::new();
+
+        let safe: &[(&str, &[u8])] = &[("fs.list", br#"{"path":"/home"}"#)];
+        assert!(peer_review
+            .plan_to_review(&clients, "t1", "system-1", safe)
+            .await
+            .is_none());
+
+        let risky: &[(&str, &[u8])] = &[
+            ("fs.list", br#"{"path":"/home"}"#),
+            ("fs.delete", br#"{"path":"/home/alice"}"#),
+        ];
+        let calls = peer_review
+            .plan_to_review(&clients, "t1", "system-1", risky)
+            .await
+            .unwrap();
+        assert_eq!(calls[1].risk, "high");
+        assert!(calls[0].risk.is_empty());
+        assert!(peer_review
+            .prompt("Free disk space", &calls, "")
+            .contains("[high risk]"));
+
+        peer_review.escalate(review("t1", "g1", calls));
+        assert_eq!(peer_review.escalations().len(), 1);
+        assert!(peer_review.approve("other-goal").is_empty());
+        assert_eq!(peer_review.approve("g1").len(), 1);
+        assert!(peer_review.escalations().is_empty());
+
+        // The approved calls run once without review...
+        assert!(peer_review
+            .plan_to_review(&clients, "t1", "system-1", risky)
+            .await
+            .is_none());
+        // ...and a plan that differs is reviewed again
+        let changed: &[(&str, &[u8])] = &[("fs.delete", br#"{"path":"/home"}"#)];
+        assert!(peer_review
+            .plan_to_review(&clients, "t1", "system-1", risky)
+            .await
+            .is_some());
+        assert!(peer_review
+            .plan_to_review(&clients, "t1", "system-1", changed)
+            .await
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_approval_rules_exempt_agents_and_deny_unanswered_plans() {
+        let peer_review = PeerReview::default().with_approvals(ApprovalPolicy {
+            rules: vec![crate::approvals::ApprovalRule {
+                name: "etc-writes".into(),
+                tools: vec!["fs.write".into()],
+                paths: vec!["/etc".into()],
+                auto_approve: vec!["security*".into()],
+                approver: crate::approvals::Approver::Operator,
+                escalate_after_secs: 900,
+                deny_after_secs: 3600,
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+        peer_review.set_tool_risks(&[crate::proto::tools::ToolDefinition {
+            name: "fs.write".into(),
+            risk_level: "medium".into(),
+            ..Default::default()
+        }]);
+        *peer_review.risks_refreshed.lock().unwrap() = Some(Instant::now());
+        let clients = ServiceClients::new();
+        let write: &[(&str, &[u8])] = &[("fs.write", br#"{"path":"/etc/hosts"}"#)];
+
+        // Peer review is off, but the rule still applies
+        assert!(peer_review
+            .plan_to_review(&clients, "t1", "security-1", write)
+            .await
+            .is_none());
+        let calls = peer_review
+            .plan_to_review(&clients, "t1", "system-1", write)
+            .await
+            .unwrap();
+        assert_eq!(calls[0].rule, "etc-writes");
+        assert_eq!(peer_review.operator_rule(&calls).unwrap(), "etc-writes");
+
+        let mut held = review("t1", "g1", calls);
+        held.created_at = 1000;
+        peer_review.escalate(held);
+        let waiting = &peer_review.escalations()[0];
+        assert_eq!((waiting.escalate_at, waiting.deny_at), (1900, 4600));
+
+        let (escalated, denied) = peer_review.overdue(1899);
+        assert!(escalated.is_empty() && denied.is_empty());
+        let (escalated, _) = peer_review.overdue(2000);
+        assert_eq!(escalated.len(), 1);
+        // Escalated once only
+        assert!(peer_review.overdue(2100).0.is_empty());
+        let (_, denied) = peer_review.overdue(4600);
+        assert_eq!(denied.len(), 1);
+        assert!(peer_review.escalations().is_empty());
+    }
+
+    #[test]
+    fn test_destructive_verdict_is_never_approval() {
+        let verdict: ReviewVerdict = serde_json::from_str(
+            r#"{"approve": true, "destructive": true, "reasoning": "removes a home directory"}"#,
+        )
+        .unwrap();
+        let opinion = verdict.into_opinion("gpt-4o");
+        assert!(!opinion.approve);
+        assert_eq!(opinion.concerns, vec!["destructive"]);
+
+        let peer_review = PeerReview::default();
+        assert_eq!(peer_review.reviewer_provider("claude-sonnet-4"), "openai");
+        assert_eq!(peer_review.reviewer_provider("gpt-4o"), "claude");
+        let r = review("t1", "g1", Vec::new());
+        assert!(!r.agreed());
+        assert!(r.opinions()[1].starts_with("reviewer gpt-4o (reject)"));
+    }
+}
@@ -10,7 +10,18 @@
 //! - Available updates
 //!
 //! Deduplicates goals: won't create one if a similar goal is already active.
-
+//!
+//! Noisy metrics can make the same rule fire over and over, so generation is
+//! also bounded by the quotas in proactive.toml: goals per hour (overall and
+//! per rule), concurrently active proactive goals, and a cut-off once the API
+//! budget is mostly spent. Generation also pauses while the API gateway's
+//! budget degradation ladder is at "reduced_tokens" or beyond, and inside
+//! calendar blackout and maintenance windows. Suppressed goals are recorded
+//! as decisions so they can be reviewed later.
+
+use anyhow::Context;
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::RwLock;
@@ -19,6 +30,12 @@ use tracing::{debug, error, info, warn};
 
 use crate::OrchestratorState;
 
+/// Default location of the proactive quota configuration
+pub const PROACTIVE_QUOTAS_PATH: &str = "/etc/aios/proactive.toml";
+
+/// Source recorded on proactive goals
+const PROACTIVE_SOURCE: &str = "proactive-monitor";
+
 /// Configuration for the proactive goal generator
 pub struct ProactiveConfig {
     /// How often to check for proactive goals
@@ -29,6 +46,8 @@ pub struct ProactiveConfig {
     pub memory_threshold: f64,
     /// Disk usage threshold (%) to trigger a goal
     pub disk_threshold: f64,
+    /// Limits on goal generation
+    pub quotas: ProactiveQuotas,
 }
 
 impl Default for ProactiveConfig {
@@ -38,7 +57,116 @@ impl Default for ProactiveConfig {
             cpu_threshold: 90.0,
             memory_threshold: 85.0,
             disk_threshold: 90.0,
+            quotas: ProactiveQuotas::default(),
+        }
+    }
+}
+
+/// Limits on how many goals the generator may create (proactive.toml)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ProactiveQuotas {
+    /// Most proactive goals created per rolling hour, across all rules
+    pub max_goals_per_hour: usize,
+    /// Most proactive goals active at the same time
+    pub max_concurrent_goals: usize,
+    /// Most goals a single rule may create per rolling hour
+    pub max_goals_per_rule_per_hour: usize,
+    /// Per-rule overrides of `max_goals_per_rule_per_hour`, by rule name
+    pub rules: HashMap<String, usize>,
+    /// Suppress generation while any API budget is more than this share
+    /// spent (percent; 0 disables the check)
+    pub budget_suppress_percent: f64,
+}
+
+impl Default for ProactiveQuotas {
+    fn default() -> Self {
+        Self {
+            max_goals_per_hour: 12,
+            max_concurrent_goals: 4,
+            max_goals_per_rule_per_hour: 3,
+            rules: HashMap::new(),
+            budget_suppress_percent: 80.0,
+        }
+    }
+}
+
+impl ProactiveQuotas {
+    /// Load quotas from `path`. A missing file yields the defaults; an
+    /// invalid one is logged and ignored.
+    pub fn load(path: &str) -> Self {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => Self::from_toml(&contents).unwrap_or_else(|e| {
+                warn!("Ignoring invalid proactive quotas in {path}: {e}");
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn from_toml(contents: &str) -> anyhow::Result<Self> {
+        toml::from_str(contents).context("Failed to parse proactive quotas")
+    }
+
+    fn rule_limit(&self, rule: &str) -> usize {
+        self.rules
+            .get(rule)
+            .copied()
+            .unwrap_or(self.max_goals_per_rule_per_hour)
+    }
+}
+
+/// Rolling record of proactive goals created in the last hour
+#[derive(Debug, Default)]
+struct Throttle {
+    created: VecDeque<(i64, &'static str)>,
+}
+
+impl Throttle {
+    /// Why a goal from `rule` may not be created now, if it may not
+    fn check(
+        &mut self,
+        quotas: &ProactiveQuotas,
+        rule: &str,
+        now: i64,
+        active: usize,
+        budget_percent: Option<f64>,
+    ) -> Option<String> {
+        while self.created.front().is_some_and(|(t, _)| now - t >= 3600) {
+            self.created.pop_front();
+        }
+        if let Some(spent) = budget_percent {
+            if quotas.budget_suppress_percent > 0.0 && spent > quotas.budget_suppress_percent {
+                return Some(format!(
+                    "API budget {spent:.0}% spent (limit {:.0}%)",
+                    quotas.budget_suppress_percent
+                ));
+            }
         }
+        if active >= quotas.max_concurrent_goals {
+            return Some(format!(
+                "{active} proactive goals already active (limit {})",
+                quotas.max_concurrent_goals
+            ));
+        }
+        if self.created.len() >= quotas.max_goals_per_hour {
+            return Some(format!(
+                "hourly quota of {} proactive goals reached",
+                quotas.max_goals_per_hour
+            ));
+        }
+        let from_rule = self.created.iter().filter(|(_, r)| *r == rule).count();
+        let rule_limit = quotas.rule_limit(rule);
+        if from_rule >= rule_limit {
+            return Some(format!(
+                "rule '{rule}' reached its hourly quota of {rule_limit}"
+            ));
+        }
+        None
+    }
+
+    fn record(&mut self, rule: &'static str, now: i64) {
+        self.created.push_back((now, rule));
     }
 }
 
@@ -53,6 +181,7 @@ pub async fn run_proactive_loop(
         config.check_interval.as_secs()
     );
 
+    let mut throttle = Throttle::default();
     loop {
         tokio::select! {
             _ = cancel.cancelled() => {
@@ -60,7 +189,7 @@ pub async fn run_proactive_loop(
                 break;
             }
             _ = tokio::time::sleep(config.check_interval) => {
-                if let Err(e) = proactive_check(&state, &config).await {
+                if let Err(e) = proactive_check(&state, &config, &mut throttle).await {
                     error!("Proactive check error: {e}");
                 }
             }
@@ -74,15 +203,18 @@ pub async fn run_proactive_loop(
 async fn proactive_check(
     state: &Arc<RwLock<OrchestratorState>>,
     config: &ProactiveConfig,
+    throttle: &mut Throttle,
 ) -> anyhow::Result<()> {
     debug!("Running proactive system check");
 
-    let mut goals_to_create: Vec<(String, i32)> = Vec::new();
+    // (rule, description, priority)
+    let mut goals_to_create: Vec<(&'static str, String, i32)> = Vec::new();
 
     // Check CPU usage
     let cpu = crate::read_cpu_percent();
     if cpu > config.cpu_threshold {
         goals_to_create.push((
+            "cpu",
             format!(
                 "Investigate high CPU usage ({cpu:.1}% > {:.0}% threshold). \
                  Identify top processes and take corrective action.",
@@ -98,6 +230,7 @@ async fn proactive_check(
         let mem_percent = (mem_used / mem_total) * 100.0;
         if mem_percent > config.memory_threshold {
             goals_to_create.push((
+                "memory",
                 format!(
                     "Investigate high memory usage ({mem_percent:.1}% > {:.0}% threshold). \
                      Identify memory-heavy processes and free memory.",
@@ -112,6 +245,7 @@ async fn proactive_check(
     let disk_percent = read_disk_usage_percent();
     if disk_percent > config.disk_threshold {
         goals_to_create.push((
+            "disk",
             format!(
                 "Disk usage critically high ({disk_percent:.1}% > {:.0}% threshold). \
                  Clean up temporary files, old logs, and unnecessary data.",
@@ -133,6 +267,7 @@ async fn proactive_check(
     if !failed_agents.is_empty() {
         let names: Vec<String> = failed_agents.iter().map(|a| a.agent_id.clone()).collect();
         goals_to_create.push((
+            "agents",
             format!(
                 "Restart failed agents: {}. Investigate root cause.",
                 names.join(", ")
@@ -150,6 +285,7 @@ async fn proactive_check(
     if !unhealthy.is_empty() {
         let names: Vec<String> = unhealthy.iter().map(|s| s.name.clone()).collect();
         goals_to_create.push((
+            "services",
             format!(
                 "Services unhealthy: {}. Restart and investigate root cause.",
                 names.join(", ")
@@ -166,6 +302,7 @@ async fn proactive_check(
                 let days_old = age.as_secs() / 86400;
                 if days_old > 30 {
                     goals_to_create.push((
+                        "cert",
                         format!(
                             "TLS certificate is {days_old} days old. Rotate certificates \
                              using sec.cert_rotate before expiry."
@@ -189,6 +326,7 @@ async fn proactive_check(
                 let hours_since = (now_ts - last_ts) / 3600;
                 if hours_since > 24 {
                     goals_to_create.push((
+                        "backup",
                         format!(
                             "No backup in {hours_since} hours. Run system backup \
                              to protect data and configurations."
@@ -215,6 +353,7 @@ async fn proactive_check(
             .unwrap_or(false);
         if !ping_ok {
             goals_to_create.push((
+                "network",
                 "Network connectivity issue: DNS and ping to 1.1.1.1 failed. \
                  Diagnose network configuration and restore connectivity."
                     .to_string(),
@@ -233,6 +372,7 @@ async fn proactive_check(
             .count();
         if error_count > 50 {
             goals_to_create.push((
+                "logs",
                 format!(
                     "Log anomaly: {error_count} ERROR/CRITICAL entries in recent logs. \
                      Investigate root cause and resolve recurring errors."
@@ -250,6 +390,7 @@ async fn proactive_check(
         .unwrap_or(0);
     if ww_count > 0 {
         goals_to_create.push((
+            "world_writable",
             format!(
                 "Security: {ww_count} world-writable files found in /etc. \
                  Fix file permissions to prevent unauthorized modification."
@@ -258,6 +399,7 @@ async fn proactive_check(
         ));
     }
 
+    let clients = state_r.clients.clone();
     drop(state_r);
 
     // Submit goals, deduplicating against active goals
@@ -266,9 +408,17 @@ async fn proactive_check(
         return Ok(());
     }
 
-    let mut state_w = state.write().await;
+    let budget_percent = if config.quotas.budget_suppress_percent > 0.0 {
+        budget_spent_percent(&clients).await
+    } else {
+        None
+    };
+
+    let mut state_w = crate::liveness::write_state(state, "proactive.check").await;
+    let mut active = state_w.goal_engine.active_goal_count_from(PROACTIVE_SOURCE);
+    let mut suppressed = Vec::new();
 
-    for (description, priority) in goals_to_create {
+    for (rule, description, priority) in goals_to_create {
         // Check for duplicate: skip if a similar goal is already active
         if has_similar_active_goal(&state_w, &description).await {
             debug!(
@@ -278,13 +428,32 @@ async fn proactive_check(
             continue;
         }
 
+        let now = chrono::Utc::now().timestamp();
+        let suppression = state_w
+            .budget_degradation
+            .proactive_suppression()
+            .or_else(|| state_w.calendar.proactive_suppression(now))
+            .or_else(|| throttle.check(&config.quotas, rule, now, active, budget_percent));
+        if let Some(reason) = suppression {
+            info!(
+                "Suppressed proactive goal from rule '{rule}' ({reason}): {}",
+                &description[..80.min(description.len())]
+            );
+            state_w.decision_logger.log_decision(
+                "proactive_goal",
+                std::slice::from_ref(&description),
+                "suppressed",
+                &reason,
+                "reactive",
+                PROACTIVE_SOURCE,
+            );
+            suppressed.push((rule, description, reason));
+            continue;
+        }
+
         match state_w
             .goal_engine
-            .submit_goal(
-                description.clone(),
-                priority,
-                "proactive-monitor".to_string(),
-            )
+            .submit_goal(description.clone(), priority, PROACTIVE_SOURCE.to_string())
             .await
         {
             Ok(goal_id) => {
@@ -292,6 +461,8 @@ async fn proactive_check(
                     "Proactive goal created: {goal_id} — {}",
                     &description[..80.min(description.len())]
                 );
+                throttle.record(rule, now);
+                active += 1;
 
                 // Decompose into tasks
                 if let Ok(tasks) = state_w
@@ -301,6 +472,7 @@ async fn proactive_check(
                 {
                     state_w.goal_engine.add_tasks(&goal_id, tasks);
                 }
+                state_w.autonomy_waker.wake();
 
                 // Log the decision
                 state_w.decision_logger.log_decision(
@@ -309,7 +481,7 @@ async fn proactive_check(
                     "created",
                     &description,
                     "reactive",
-                    "proactive-monitor",
+                    PROACTIVE_SOURCE,
                 );
             }
             Err(e) => {
@@ -317,10 +489,68 @@ async fn proactive_check(
             }
         }
     }
+    drop(state_w);
+
+    if !suppressed.is_empty() {
+        record_suppressed(&clients, suppressed).await;
+    }
 
     Ok(())
 }
 
+/// Share of the most-used API budget that has been spent, in percent.
+/// None when the gateway cannot be reached or has no budget configured.
+async fn budget_spent_percent(clients: &crate::clients::ServiceClients) -> Option<f64> {
+    let mut client = clients.api_gateway().await.ok()?;
+    let budget = client
+        .get_budget(crate::proto::common::Empty {})
+        .await
+        .ok()?
+        .into_inner();
+    if budget.budget_exceeded {
+        return Some(100.0);
+    }
+    [
+        (budget.claude_used_usd, budget.claude_monthly_budget_usd),
+        (budget.openai_used_usd, budget.openai_monthly_budget_usd),
+    ]
+    .into_iter()
+    .filter(|(_, limit)| *limit > 0.0)
+    .map(|(used, limit)| used / limit * 100.0)
+    .reduce(f64::max)
+}
+
+/// Persist suppressed generations to memory so they can be reviewed later
+async fn record_suppressed(
+    clients: &crate::clients::ServiceClients,
+    suppressed: Vec<(&'static str, String, String)>,
+) {
+    let mut client = match clients.memory().await {
+        Ok(c) => c,
+        Err(e) => {
+            warn!("Cannot record suppressed proactive goals: {e}");
+            return;
+        }
+    };
+    for (rule, description, reason) in suppressed {
+        let decision = crate::proto::memory::Decision {
+            id: uuid::Uuid::new_v4().to_string(),
+            context: format!("proactive_goal:{rule}"),
+            options_json: serde_json::to_vec(&[&description]).unwrap_or_default(),
+            chosen: "suppressed".to_string(),
+            reasoning: reason,
+            intelligence_level: "reactive".to_string(),
+            model_used: PROACTIVE_SOURCE.to_string(),
+            outcome: String::new(),
+            timestamp: chrono::Utc::now().timestamp(),
+        };
+        if let Err(e) = client.store_decision(decision).await {
+            warn!("Failed to record suppressed proactive goal: {e}");
+            return;
+        }
+    }
+}
+
 /// Check if a similar goal is already active (simple keyword overlap check)
 async fn has_similar_active_goal(state: &OrchestratorState, description: &str) -> bool {
     let (goals, _) = state.goal_engine.list_goals("", 100, 0).await;
@@ -382,6 +612,42 @@ mod tests {
         assert_eq!(config.disk_threshold, 90.0);
     }
 
+    #[test]
+    fn test_throttle_enforces_quotas() {
+        let quotas = ProactiveQuotas::from_toml(
+            r#"
+            max_goals_per_hour = 3
+            max_concurrent_goals = 2
+            max_goals_per_rule_per_hour = 1
+
+            [rules]
+            disk = 2
+            "#,
+        )
+        .unwrap();
+        assert_eq!(quotas.budget_suppress_percent, 80.0);
+
+        let mut throttle = Throttle::default();
+        assert!(throttle.check(&quotas, "cpu", 0, 0, None).is_none());
+        throttle.record("cpu", 0);
+        assert!(throttle.check(&quotas, "cpu", 10, 0, None).is_some());
+        assert!(throttle.check(&quotas, "disk", 10, 0, None).is_none());
+        throttle.record("disk", 10);
+        throttle.record("disk", 20);
+        // Global hourly quota reached
+        assert!(throttle.check(&quotas, "memory", 30, 0, None).is_some());
+        // An hour later the window has moved on
+        assert!(throttle.check(&quotas, "cpu", 3600, 0, None).is_none());
+        // Concurrency and budget limits apply regardless of history
+        assert!(throttle.check(&quotas, "memory", 7200, 2, None).is_some());
+        assert!(throttle
+            .check(&quotas, "memory", 7200, 0, Some(85.0))
+            .is_some());
+        assert!(throttle
+            .check(&quotas, "memory", 7200, 0, Some(50.0))
+            .is_none());
+    }
+
     #[test]
     fn test_read_disk_usage() {
         let percent = read_disk_usage_percent();
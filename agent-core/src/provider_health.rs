@@ -0,0 +1,200 @@
+//! Provider Health — follows the API gateway's provider circuit breakers
+//!
+//! The gateway keeps a circuit breaker per provider and skips providers
+//! whose breaker is open. This monitor reads the breakers (GetProviderHealth)
+//! whenever the gateway publishes a breaker state change on the event bus
+//! (`gateway.provider_health`), and every CHECK_INTERVAL while the event bus
+//! cannot be reached. Tasks whose provider is only the planner's hint or the
+//! default are then sent to a healthy provider instead of one whose breaker
+//! is open; a goal's explicit provider preference is kept.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::sync::RwLock;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info};
+
+use crate::OrchestratorState;
+
+/// How often the breakers are read while the event bus is down
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Event bus topic of the gateway's breaker state changes
+const HEALTH_TOPIC: &str = "gateway.provider_health";
+
+/// Breaker state of one provider
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderStatus {
+    pub provider: String,
+    pub state: String,
+    pub configured: bool,
+    /// While open: Unix time of the next probe
+    pub open_until: i64,
+}
+
+/// The gateway's providers, in failover order
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ProviderHealth {
+    pub providers: Vec<ProviderStatus>,
+}
+
+impl ProviderHealth {
+    pub fn update(&mut self, list: crate::proto::api_gateway::ProviderHealthList, now: i64) {
+        self.providers = list
+            .providers
+            .into_iter()
+            .map(|p| ProviderStatus {
+                open_until: if p.state == "open" {
+                    now + p.retry_in_secs
+                } else {
+                    0
+                },
+                provider: p.provider,
+                state: p.state,
+                configured: p.configured,
+            })
+            .collect();
+    }
+
+    /// Whether requests to `provider` may be made: unknown providers are
+    /// assumed healthy, and an open breaker is due a probe once its
+    /// cooldown ends
+    pub fn is_healthy(&self, provider: &str, now: i64) -> bool {
+        self.providers
+            .iter()
+            .find(|p| p.provider == provider)
+            .is_none_or(|p| p.state != "open" || now >= p.open_until)
+    }
+
+    /// `provider`, or the first healthy configured provider in failover
+    /// order if its breaker is open
+    pub fn pick(&self, provider: &str, now: i64) -> String {
+        if provider.is_empty() || self.is_healthy(provider, now) {
+            return provider.to_string();
+        }
+        self.providers
+            .iter()
+            .find(|p| p.configured && self.is_healthy(&p.provider, now))
+            .map_or_else(|| provider.to_string(), |p| p.provider.clone())
+    }
+}
+
+/// Follow the gateway's breakers until cancelled
+pub async fn run_provider_health_monitor(
+    state: Arc<RwLock<OrchestratorState>>,
+    cancel: CancellationToken,
+) {
+    info!("Provider health monitor started");
+    let clients = state.read().await.clients.clone();
+    loop {
+        check(&state).await;
+        let subscription = match clients.memory().await {
+            Ok(mut memory) => memory
+                .subscribe(crate::proto::memory::SubscribeRequest {
+                    subscriber: String::new(),
+                    topics: vec![HEALTH_TOPIC.to_string()],
+                    from_latest: true,
+                })
+                .await
+                .map(|r| r.into_inner())
+                .map_err(anyhow::Error::from),
+            Err(e) => Err(e),
+        };
+        let mut deliveries = match subscription {
+            Ok(deliveries) => deliveries,
+            Err(e) => {
+                debug!("Provider health monitor polling; event bus unavailable: {e}");
+                tokio::select! {
+                    _ = cancel.cancelled() => break,
+                    _ = tokio::time::sleep(CHECK_INTERVAL) => continue,
+                }
+            }
+        };
+        loop {
+            tokio::select! {
+                _ = cancel.cancelled() => {
+                    info!("Provider health monitor stopped");
+                    return;
+                }
+                delivery = deliveries.message() => match delivery {
+                    Ok(Some(_)) => check(&state).await,
+                    Ok(None) => break,
+                    Err(e) => {
+                        debug!("Provider health monitor lost the event bus: {e}");
+                        break;
+                    }
+                },
+            }
+        }
+    }
+    info!("Provider health monitor stopped");
+}
+
+/// Read the gateway's breakers
+async fn check(state: &Arc<RwLock<OrchestratorState>>) {
+    let clients = state.read().await.clients.clone();
+    let health = match clients.api_gateway().await {
+        Ok(mut client) => {
+            client
+                .get_provider_health(crate::proto::common::Empty {})
+                .await
+        }
+        Err(e) => {
+            debug!("Provider health check skipped: {e}");
+            return;
+        }
+    };
+    let health = match health {
+        Ok(h) => h.into_inner(),
+        Err(e) => {
+            debug!("Provider health check failed: {e}");
+            return;
+        }
+    };
+
+    let mut s = crate::liveness::write_state(state, "provider_health.check").await;
+    s.provider_health
+        .update(health, chrono::Utc::now().timestamp());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::api_gateway::{ProviderHealth as Breaker, ProviderHealthList};
+
+    fn breaker(provider: &str, state: &str, configured: bool) -> Breaker {
+        Breaker {
+            provider: provider.into(),
+            state: state.into(),
+            configured,
+            retry_in_secs: if state == "open" { 30 } else { 0 },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_pick_skips_open_breakers_until_their_cooldown_ends() {
+        let mut health = ProviderHealth::default();
+        assert_eq!(health.pick("qwen3", 100), "qwen3");
+
+        health.update(
+            ProviderHealthList {
+                providers: vec![
+                    breaker("qwen3", "open", true),
+                    breaker("claude", "closed", false),
+                    breaker("openai", "half_open", true),
+                    breaker("local", "closed", true),
+                ],
+            },
+            100,
+        );
+        assert!(!health.is_healthy("qwen3", 100));
+        assert_eq!(health.pick("qwen3", 100), "openai");
+        assert_eq!(health.pick("claude", 100), "claude");
+        assert_eq!(health.pick("", 100), "");
+        // Due a probe once the cooldown ends
+        assert_eq!(health.pick("qwen3", 130), "qwen3");
+    }
+}
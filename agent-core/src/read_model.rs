@@ -0,0 +1,121 @@
+//! Read Model — console queries served from a snapshot of the state
+//!
+//! Console reads used to take the orchestrator state lock, so a busy
+//! dashboard held off the autonomy loop and the gRPC handlers that write
+//! the state. Every GET endpoint and the WebSocket feed now read a
+//! [`ConsoleSnapshot`] instead: a copy of the goals (with their tasks,
+//! messages and transitions), agents and counters, taken under a short read
+//! lock and shared without any lock held.
+//!
+//! The snapshot is refreshed every `AIOS_CONSOLE_SNAPSHOT_MS` (1000) and
+//! right after a console write, so the console sees its own changes at
+//! once and the execution path's within one interval.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::{watch, Notify, RwLock};
+use tokio_util::sync::CancellationToken;
+use tracing::debug;
+
+use crate::autonomy::LoopMetrics;
+use crate::calendar::{CalendarWindow, ScheduleConflict};
+use crate::degradation::BudgetDegradation;
+use crate::goal_engine::GoalEngine;
+use crate::proto::common::AgentRegistration;
+use crate::tool_usage::ToolUsageSummary;
+use crate::usage::UsageLedger;
+use crate::OrchestratorState;
+
+/// A point-in-time copy of what the console shows
+pub struct ConsoleSnapshot {
+    /// Unix time the snapshot was taken
+    pub taken_at: i64,
+    /// Read replica of the goal engine; answers the same queries
+    pub goals: GoalEngine,
+    pub agents: Vec<AgentRegistration>,
+    pub pending_tasks: usize,
+    pub active_agents: usize,
+    pub uptime_seconds: u64,
+    pub autonomy: LoopMetrics,
+    pub tool_usage: Vec<ToolUsageSummary>,
+    pub budget: BudgetDegradation,
+    pub calendar_windows: Vec<CalendarWindow>,
+    pub schedule_conflicts: Vec<ScheduleConflict>,
+    /// Shared rather than copied; it has its own lock
+    pub usage: Arc<UsageLedger>,
+}
+
+impl ConsoleSnapshot {
+    /// Copy the console's view of `state` under one read lock
+    pub async fn capture(state: &RwLock<OrchestratorState>) -> Self {
+        let s = state.read().await;
+        let snapshot = Self {
+            taken_at: chrono::Utc::now().timestamp(),
+            goals: s.goal_engine.read_replica(),
+            agents: s.agent_router.list_agents().await,
+            pending_tasks: s.task_planner.pending_task_count(),
+            active_agents: s.agent_router.active_agent_count(),
+            uptime_seconds: s.started_at.elapsed().as_secs(),
+            autonomy: s.autonomy_metrics.lock().unwrap().clone(),
+            tool_usage: s.tool_usage.lock().unwrap().summaries(),
+            budget: s.budget_degradation.clone(),
+            calendar_windows: s.calendar.windows(),
+            schedule_conflicts: s.calendar.conflicts(),
+            usage: s.usage.clone(),
+        };
+        snapshot
+    }
+}
+
+/// The latest console snapshot, shared with every console handler
+#[derive(Clone)]
+pub struct ReadModel {
+    snapshot: watch::Receiver<Arc<ConsoleSnapshot>>,
+    refresh: Arc<Notify>,
+}
+
+impl ReadModel {
+    /// The latest snapshot
+    pub fn current(&self) -> Arc<ConsoleSnapshot> {
+        self.snapshot.borrow().clone()
+    }
+
+    /// Take a new snapshot now, after the console changed the state
+    pub fn invalidate(&self) {
+        self.refresh.notify_one();
+    }
+}
+
+/// Take the first snapshot of `state` and keep it fresh until `cancel`
+/// fires
+pub async fn start(state: Arc<RwLock<OrchestratorState>>, cancel: CancellationToken) -> ReadModel {
+    let interval = Duration::from_millis(
+        std::env::var("AIOS_CONSOLE_SNAPSHOT_MS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(1000),
+    );
+    let (tx, rx) = watch::channel(Arc::new(ConsoleSnapshot::capture(&state).await));
+    let refresh = Arc::new(Notify::new());
+    let model = ReadModel {
+        snapshot: rx,
+        refresh: refresh.clone(),
+    };
+
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = cancel.cancelled() => break,
+                _ = refresh.notified() => {}
+                _ = tokio::time::sleep(interval) => {}
+            }
+            let snapshot = ConsoleSnapshot::capture(&state).await;
+            if tx.send(Arc::new(snapshot)).is_err() {
+                break;
+            }
+        }
+        debug!("Console read model stopped");
+    });
+    model
+}
@@ -0,0 +1,627 @@
+//! Desired-state reconciliation
+//!
+//! Operators declare how the system should look in desired.toml — which
+//! services run and start at boot, which ports are open or closed, which
+//! certificates must stay valid — and the reconciler keeps it that way.
+//! Every few minutes it observes each declared resource through the tools
+//! service (`service.status`, `net.port_scan`, `sec.cert_check`), diffs the
+//! observation against the declaration, and submits a targeted remediation
+//! goal for every resource that drifted.
+//!
+//! Each drifted resource has at most one remediation goal in flight. When
+//! a resource is still drifted after `max_attempts` remediation goals have
+//! finished, the reconciler stops trying and notifies an operator; once the
+//! resource converges its history is forgotten. A resource the tools cannot
+//! observe (the tools service is down, the tool failed) is left alone until
+//! it can be. Remediation holds off inside calendar blackout and maintenance
+//! windows, like proactive goals.
+//!
+//! ```toml
+//! interval_secs = 300
+//!
+//! [[service]]
+//! name = "nginx"
+//! running = true
+//! enabled = true
+//!
+//! [[port]]
+//! port = 443
+//!
+//! [[cert]]
+//! path = "/etc/nginx/tls/server.crt"
+//! min_days = 14
+//! ```
+
+use anyhow::Context;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use crate::clients::ServiceClients;
+use crate::goal_engine::GoalEngine;
+use crate::OrchestratorState;
+
+/// Default location of the desired-state manifest
+pub const DESIRED_STATE_PATH: &str = "/etc/aios/desired.toml";
+
+/// Source recorded on remediation goals
+const RECONCILE_SOURCE: &str = "reconciler";
+
+/// desired.toml layout
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct DesiredState {
+    /// Seconds between reconciliation passes
+    pub interval_secs: u64,
+    /// Remediation goals tried per drift before an operator is called in
+    pub max_attempts: u32,
+    pub service: Vec<DesiredService>,
+    pub port: Vec<DesiredPort>,
+    pub cert: Vec<DesiredCert>,
+}
+
+impl Default for DesiredState {
+    fn default() -> Self {
+        Self {
+            interval_secs: 300,
+            max_attempts: 3,
+            service: Vec::new(),
+            port: Vec::new(),
+            cert: Vec::new(),
+        }
+    }
+}
+
+/// A service and the state it should be in
+#[derive(Debug, Clone, Deserialize)]
+pub struct DesiredService {
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub running: bool,
+    /// Whether it starts at boot; unchecked when absent
+    #[serde(default)]
+    pub enabled: Option<bool>,
+}
+
+/// A TCP port that should be open (listening) or closed
+#[derive(Debug, Clone, Deserialize)]
+pub struct DesiredPort {
+    pub port: u16,
+    #[serde(default = "default_host")]
+    pub host: String,
+    #[serde(default = "default_true")]
+    pub open: bool,
+}
+
+/// A certificate that should exist and stay valid
+#[derive(Debug, Clone, Deserialize)]
+pub struct DesiredCert {
+    pub path: String,
+    /// Days of validity that must remain
+    #[serde(default = "default_min_days")]
+    pub min_days: i64,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_host() -> String {
+    "127.0.0.1".to_string()
+}
+
+fn default_min_days() -> i64 {
+    14
+}
+
+impl DesiredState {
+    /// Load the manifest from `path`. A missing file declares nothing; an
+    /// invalid one is logged and ignored.
+    pub fn load(path: &str) -> Self {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => Self::from_toml(&contents).unwrap_or_else(|e| {
+                warn!("Ignoring invalid desired state in {path}: {e}");
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn from_toml(contents: &str) -> anyhow::Result<Self> {
+        toml::from_str(contents).context("Failed to parse desired state")
+    }
+
+    fn is_empty(&self) -> bool {
+        self.service.is_empty() && self.port.is_empty() && self.cert.is_empty()
+    }
+}
+
+/// A declared resource whose observed state differs from the declaration
+#[derive(Debug, Clone, PartialEq)]
+pub struct Drift {
+    /// Resource identity, e.g. `service:nginx` or `port:127.0.0.1:443`
+    pub resource: String,
+    /// What was observed
+    pub detail: String,
+    /// What the remediation goal should do
+    pub remediation: String,
+    pub priority: i32,
+}
+
+impl Drift {
+    fn goal_description(&self) -> String {
+        format!(
+            "Desired state drift on {}: {}. {}",
+            self.resource, self.detail, self.remediation
+        )
+    }
+}
+
+/// Drift of a service from its `service.status` output
+fn service_drift(desired: &DesiredService, status: &Value) -> Vec<Drift> {
+    let name = &desired.name;
+    let resource = format!("service:{name}");
+    let state = status["status"].as_str().unwrap_or("unknown");
+    let running = state == "active" || state == "running";
+    let mut drifts = Vec::new();
+    if desired.running != running {
+        drifts.push(if desired.running {
+            Drift {
+                resource: resource.clone(),
+                detail: format!("service {name} is not running (status: {state})"),
+                remediation: format!(
+                    "Start {name} with service.start, check its logs if it fails to stay up, \
+                     and confirm with service.status that it is running."
+                ),
+                priority: 8,
+            }
+        } else {
+            Drift {
+                resource: resource.clone(),
+                detail: format!("service {name} is running but should be stopped"),
+                remediation: format!(
+                    "Stop {name} with service.stop and confirm with service.status."
+                ),
+                priority: 6,
+            }
+        });
+    }
+    if let (Some(want), Some(enabled)) = (desired.enabled, status["enabled"].as_bool()) {
+        if want != enabled {
+            let (is, should) = if want {
+                ("not enabled", "enable it")
+            } else {
+                ("enabled", "disable it")
+            };
+            drifts.push(Drift {
+                resource: format!("{resource}:enabled"),
+                detail: format!("service {name} is {is} at boot"),
+                remediation: format!(
+                    "Change the boot-time setting of {name} ({should}) without \
+                     changing whether it is running now."
+                ),
+                priority: 5,
+            });
+        }
+    }
+    drifts
+}
+
+/// Drift of a port from its `net.port_scan` output
+fn port_drift(desired: &DesiredPort, scan: &Value) -> Option<Drift> {
+    let open = scan["open"].as_bool()?;
+    if open == desired.open {
+        return None;
+    }
+    let (host, port) = (&desired.host, desired.port);
+    let resource = format!("port:{host}:{port}");
+    Some(if desired.open {
+        Drift {
+            resource,
+            detail: format!("port {port} on {host} is not accepting connections"),
+            remediation: format!(
+                "Find the service that should listen on port {port}, restore it, and check \
+                 that the firewall allows the port."
+            ),
+            priority: 7,
+        }
+    } else {
+        Drift {
+            resource,
+            detail: format!("port {port} on {host} is open but should be closed"),
+            remediation: format!(
+                "Identify what listens on port {port}; stop it if it should not run, or \
+                 block the port with the firewall."
+            ),
+            priority: 7,
+        }
+    })
+}
+
+/// Drift of a certificate from its `sec.cert_check` output
+fn cert_drift(desired: &DesiredCert, check: &Value) -> Option<Drift> {
+    let path = &desired.path;
+    let resource = format!("cert:{path}");
+    let remediation = format!(
+        "Renew or rotate the certificate at {path} (sec.cert_rotate for aiOS certificates) \
+         and reload the services that use it."
+    );
+    if !check["exists"].as_bool().unwrap_or(false) {
+        return Some(Drift {
+            resource,
+            detail: format!("certificate {path} is missing"),
+            remediation,
+            priority: 8,
+        });
+    }
+    let Some(days) = check["expires_in_days"].as_i64() else {
+        return Some(Drift {
+            resource,
+            detail: format!("certificate {path} cannot be read"),
+            remediation,
+            priority: 7,
+        });
+    };
+    if days < 0 {
+        Some(Drift {
+            resource,
+            detail: format!("certificate {path} expired {} days ago", -days),
+            remediation,
+            priority: 9,
+        })
+    } else if days < desired.min_days {
+        Some(Drift {
+            resource,
+            detail: format!(
+                "certificate {path} expires in {days} days (at least {} required)",
+                desired.min_days
+            ),
+            remediation,
+            priority: 6,
+        })
+    } else {
+        None
+    }
+}
+
+/// Remediation history of a drifted resource
+#[derive(Debug)]
+struct Remediation {
+    goal_id: String,
+    attempts: u32,
+    gave_up: bool,
+}
+
+/// What to do about a drifted resource this pass
+#[derive(Debug, PartialEq)]
+enum Step {
+    Remediate,
+    /// Its remediation goal is still running
+    Pending,
+    /// Out of attempts: tell an operator (once)
+    GiveUp,
+    /// Already handed to an operator
+    Waiting,
+}
+
+/// Remediation history of the resources currently drifted
+#[derive(Debug, Default)]
+struct Reconciler {
+    remediations: HashMap<String, Remediation>,
+}
+
+impl Reconciler {
+    /// Forget the history of resources that converged
+    fn converged(&mut self, drifts: &[Drift]) {
+        self.remediations
+            .retain(|resource, _| drifts.iter().any(|d| &d.resource == resource));
+    }
+
+    fn step(&mut self, resource: &str, goals: &GoalEngine, max_attempts: u32) -> Step {
+        let Some(remediation) = self.remediations.get_mut(resource) else {
+            return Step::Remediate;
+        };
+        if remediation.gave_up {
+            return Step::Waiting;
+        }
+        let finished = matches!(
+            goals.goal_status(&remediation.goal_id),
+            None | Some("completed") | Some("failed") | Some("cancelled")
+        );
+        if !finished {
+            Step::Pending
+        } else if remediation.attempts >= max_attempts {
+            remediation.gave_up = true;
+            Step::GiveUp
+        } else {
+            Step::Remediate
+        }
+    }
+
+    fn remediating(&mut self, resource: &str, goal_id: String) {
+        let remediation = self
+            .remediations
+            .entry(resource.to_string())
+            .or_insert(Remediation {
+                goal_id: String::new(),
+                attempts: 0,
+                gave_up: false,
+            });
+        remediation.goal_id = goal_id;
+        remediation.attempts += 1;
+    }
+}
+
+/// Run the reconciliation loop
+pub async fn run_reconcile_loop(
+    state: Arc<RwLock<OrchestratorState>>,
+    desired: DesiredState,
+    cancel: CancellationToken,
+) {
+    if desired.is_empty() {
+        info!("No desired state declared in {DESIRED_STATE_PATH}; reconciler idle");
+        return;
+    }
+    info!(
+        "Reconciler started: {} services, {} ports, {} certificates (interval={}s)",
+        desired.service.len(),
+        desired.port.len(),
+        desired.cert.len(),
+        desired.interval_secs
+    );
+
+    let interval = Duration::from_secs(desired.interval_secs.max(10));
+    let mut reconciler = Reconciler::default();
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => break,
+            _ = tokio::time::sleep(interval) => {
+                reconcile(&state, &desired, &mut reconciler).await;
+            }
+        }
+    }
+
+    info!("Reconciler stopped");
+}
+
+/// Single reconciliation pass
+async fn reconcile(
+    state: &Arc<RwLock<OrchestratorState>>,
+    desired: &DesiredState,
+    reconciler: &mut Reconciler,
+) {
+    let clients = state.read().await.clients.clone();
+    let drifts = observe(&clients, desired).await;
+    reconciler.converged(&drifts);
+    if drifts.is_empty() {
+        debug!("Reconciler: system matches the desired state");
+        return;
+    }
+
+    let mut state_w = crate::liveness::write_state(state, "reconcile.check").await;
+    if let Some(reason) = state_w
+        .calendar
+        .proactive_suppression(chrono::Utc::now().timestamp())
+    {
+        debug!(
+            "Reconciler holding off {} remediations: {reason}",
+            drifts.len()
+        );
+        return;
+    }
+
+    for drift in drifts {
+        match reconciler.step(&drift.resource, &state_w.goal_engine, desired.max_attempts) {
+            Step::Pending | Step::Waiting => continue,
+            Step::GiveUp => {
+                warn!(
+                    "Reconciler giving up on {} after {} remediation goals: {}",
+                    drift.resource, desired.max_attempts, drift.detail
+                );
+                state_w.notifier.notify(
+                    "reconcile_failed",
+                    crate::event_bus::EventSeverity::Warning,
+                    &format!("Cannot converge {}", drift.resource),
+                    &format!(
+                        "{} is still drifted after {} remediation goals: {}",
+                        drift.resource, desired.max_attempts, drift.detail
+                    ),
+                    &drift.resource,
+                );
+            }
+            Step::Remediate => {
+                let description = drift.goal_description();
+                let goal_id = match state_w
+                    .goal_engine
+                    .submit_goal(
+                        description.clone(),
+                        drift.priority,
+                        RECONCILE_SOURCE.to_string(),
+                    )
+                    .await
+                {
+                    Ok(goal_id) => goal_id,
+                    Err(e) => {
+                        warn!("Failed to create remediation goal: {e}");
+                        continue;
+                    }
+                };
+                info!("Remediation goal {goal_id} for {}", drift.resource);
+                reconciler.remediating(&drift.resource, goal_id.clone());
+
+                if let Ok(tasks) = state_w
+                    .task_planner
+                    .decompose_goal(&goal_id, &description)
+                    .await
+                {
+                    state_w.goal_engine.add_tasks(&goal_id, tasks);
+                }
+                state_w.autonomy_waker.wake();
+                state_w.decision_logger.log_decision(
+                    "reconcile",
+                    &[goal_id],
+                    "remediate",
+                    &description,
+                    "reactive",
+                    RECONCILE_SOURCE,
+                );
+            }
+        }
+    }
+}
+
+/// Observe every declared resource and collect the drift
+async fn observe(clients: &ServiceClients, desired: &DesiredState) -> Vec<Drift> {
+    let mut drifts = Vec::new();
+    for service in &desired.service {
+        let input = serde_json::json!({ "name": service.name });
+        if let Some(status) = observe_tool(clients, "service.status", input).await {
+            drifts.extend(service_drift(service, &status));
+        }
+    }
+    for port in &desired.port {
+        let input = serde_json::json!({ "host": port.host, "port": port.port });
+        if let Some(scan) = observe_tool(clients, "net.port_scan", input).await {
+            drifts.extend(port_drift(port, &scan));
+        }
+    }
+    for cert in &desired.cert {
+        let input = serde_json::json!({ "path": cert.path });
+        if let Some(check) = observe_tool(clients, "sec.cert_check", input).await {
+            drifts.extend(cert_drift(cert, &check));
+        }
+    }
+    drifts
+}
+
+/// Output of a read-only observation tool, None when it could not run
+async fn observe_tool(clients: &ServiceClients, tool_name: &str, input: Value) -> Option<Value> {
+    let mut client = match clients.tools().await {
+        Ok(client) => client,
+        Err(e) => {
+            debug!("Tools service unavailable for reconciliation: {e}");
+            return None;
+        }
+    };
+    let response = client
+        .execute(tonic::Request::new(crate::proto::tools::ExecuteRequest {
+            tool_name: tool_name.to_string(),
+            agent_id: "autonomy-loop".to_string(),
+            task_id: String::new(),
+            input_json: serde_json::to_vec(&input).ok()?,
+            reason: "Desired-state reconciliation".to_string(),
+            workload: String::new(),
+            resume_token: String::new(),
+            allow_experimental: false,
+        }))
+        .await;
+    match response {
+        Ok(response) => {
+            let response = response.into_inner();
+            if !response.success {
+                debug!(
+                    "{tool_name} failed during reconciliation: {}",
+                    response.error
+                );
+                return None;
+            }
+            serde_json::from_slice(&response.output_json).ok()
+        }
+        Err(e) => {
+            debug!("{tool_name} gRPC failed during reconciliation: {e}");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_manifest_and_drift() {
+        let desired = DesiredState::from_toml(
+            r#"
+            [[service]]
+            name = "nginx"
+            enabled = true
+
+            [[port]]
+            port = 443
+
+            [[port]]
+            port = 23
+            open = false
+
+            [[cert]]
+            path = "/etc/nginx/tls/server.crt"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(desired.interval_secs, 300);
+        assert!(desired.service[0].running);
+        assert_eq!(desired.port[0].host, "127.0.0.1");
+        assert_eq!(desired.cert[0].min_days, 14);
+        assert!(DesiredState::from_toml("").unwrap().is_empty());
+
+        let nginx = &desired.service[0];
+        assert!(service_drift(nginx, &json!({"status": "active", "enabled": true})).is_empty());
+        let drifts = service_drift(nginx, &json!({"status": "failed", "enabled": false}));
+        assert_eq!(drifts.len(), 2);
+        assert_eq!(drifts[0].resource, "service:nginx");
+        assert!(drifts[0].detail.contains("status: failed"));
+        assert_eq!(drifts[1].resource, "service:nginx:enabled");
+        // Unknown boot state is not drift
+        assert!(service_drift(nginx, &json!({"status": "running", "enabled": null})).is_empty());
+
+        assert!(port_drift(&desired.port[0], &json!({"open": true})).is_none());
+        assert!(port_drift(&desired.port[1], &json!({"open": false})).is_none());
+        let closed = port_drift(&desired.port[0], &json!({"open": false})).unwrap();
+        assert_eq!(closed.resource, "port:127.0.0.1:443");
+        assert!(port_drift(&desired.port[1], &json!({"open": true})).is_some());
+
+        let cert = &desired.cert[0];
+        assert!(cert_drift(cert, &json!({"exists": true, "expires_in_days": 90})).is_none());
+        let expiring = cert_drift(cert, &json!({"exists": true, "expires_in_days": 3})).unwrap();
+        assert!(expiring.detail.contains("expires in 3 days"));
+        let expired = cert_drift(cert, &json!({"exists": true, "expires_in_days": -2})).unwrap();
+        assert_eq!(expired.priority, 9);
+        assert!(cert_drift(cert, &json!({"exists": false})).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_one_remediation_at_a_time_until_giving_up() {
+        let mut goals = GoalEngine::new();
+        let mut reconciler = Reconciler::default();
+        let resource = "service:nginx";
+
+        assert_eq!(reconciler.step(resource, &goals, 2), Step::Remediate);
+        let first = goals
+            .submit_goal("Start nginx".into(), 8, RECONCILE_SOURCE.into())
+            .await
+            .unwrap();
+        reconciler.remediating(resource, first.clone());
+        assert_eq!(reconciler.step(resource, &goals, 2), Step::Pending);
+
+        goals.update_status(&first, "completed", "test", "test");
+        assert_eq!(reconciler.step(resource, &goals, 2), Step::Remediate);
+        let second = goals
+            .submit_goal("Start nginx".into(), 8, RECONCILE_SOURCE.into())
+            .await
+            .unwrap();
+        reconciler.remediating(resource, second.clone());
+        goals.update_status(&second, "failed", "test", "test");
+        assert_eq!(reconciler.step(resource, &goals, 2), Step::GiveUp);
+        assert_eq!(reconciler.step(resource, &goals, 2), Step::Waiting);
+
+        // Converging clears the history
+        reconciler.converged(&[]);
+        assert_eq!(reconciler.step(resource, &goals, 2), Step::Remediate);
+    }
+}
@@ -5,12 +5,14 @@
 
 use anyhow::{Context, Result};
 use std::time::Duration;
-use tonic::transport::{Channel, Endpoint};
+use tonic::transport::Endpoint;
 use tracing::{debug, info};
 
+use crate::api_version::VersionedChannel;
+
 /// Client for executing operations on remote aiOS nodes
 pub struct RemoteExecutor {
-    channels: std::collections::HashMap<String, Channel>,
+    channels: std::collections::HashMap<String, VersionedChannel>,
 }
 
 impl RemoteExecutor {
@@ -20,8 +22,9 @@ impl RemoteExecutor {
         }
     }
 
-    /// Get or create a channel to a remote node
-    async fn get_channel(&mut self, address: &str) -> Result<Channel> {
+    /// Get or create a channel to `service` on a remote node, which may run
+    /// an older API version during a rolling update
+    async fn get_channel(&mut self, address: &str, service: &str) -> Result<VersionedChannel> {
         if let Some(channel) = self.channels.get(address) {
             return Ok(channel.clone());
         }
@@ -37,6 +40,7 @@ impl RemoteExecutor {
             .context("Failed to connect to remote node")?;
 
         info!("Connected to remote node at {address}");
+        let channel = VersionedChannel::negotiate(channel, service).await;
         self.channels.insert(address.to_string(), channel.clone());
         Ok(channel)
     }
@@ -49,7 +53,12 @@ impl RemoteExecutor {
         priority: i32,
         source: &str,
     ) -> Result<String> {
-        let channel = self.get_channel(address).await?;
+        let channel = self
+            .get_channel(
+                address,
+                crate::proto::orchestrator::orchestrator_server::SERVICE_NAME,
+            )
+            .await?;
         let mut client =
             crate::proto::orchestrator::orchestrator_client::OrchestratorClient::new(channel);
 
@@ -80,7 +89,12 @@ impl RemoteExecutor {
         task_id: &str,
         input_json: &[u8],
     ) -> Result<(bool, Vec<u8>, String)> {
-        let channel = self.get_channel(tools_address).await?;
+        let channel = self
+            .get_channel(
+                tools_address,
+                crate::proto::tools::tool_registry_server::SERVICE_NAME,
+            )
+            .await?;
         let mut client =
             crate::proto::tools::tool_registry_client::ToolRegistryClient::new(channel);
 
@@ -90,6 +104,9 @@ impl RemoteExecutor {
             task_id: task_id.to_string(),
             input_json: input_json.to_vec(),
             reason: "Remote execution from cluster".to_string(),
+            workload: crate::workload::current().to_string(),
+            resume_token: String::new(),
+            allow_experimental: false,
         });
 
         let response = client
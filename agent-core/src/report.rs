@@ -0,0 +1,787 @@
+//! Weekly Operations Report
+//!
+//! Every Monday morning (`AIOS_REPORT_CRON`, "0 8 * * 1", in
+//! `AIOS_REPORT_TIMEZONE`) the orchestrator compiles the past week — goals
+//! completed and failed, incidents from long-term memory, API cost and
+//! budget, notable decisions, and self-improvements (self-updates and
+//! benchmark runs) — and has the AI write it up as a report. The model only
+//! gets the aggregated data and is asked to link every goal it mentions;
+//! goals it leaves out are listed with their links at the end, and when
+//! inference is unavailable the report is rendered from the data directly.
+//!
+//! Reports are written to /var/lib/aios/reports as `weekly-<ISO week>.md`
+//! and `.html`, and sent as a `weekly_report` notification, so a route in
+//! notifications.toml decides who gets them by email.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Utc};
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::clients::ServiceClients;
+use crate::event_bus::EventSeverity;
+use crate::goal_engine::GoalQuery;
+use crate::OrchestratorState;
+
+/// Where reports are written
+pub const REPORTS_DIR: &str = "/var/lib/aios/reports";
+
+/// Default schedule: Mondays at 08:00
+pub const REPORT_CRON: &str = "0 8 * * 1";
+
+/// Notification event type reports are sent as
+pub const REPORT_EVENT: &str = "weekly_report";
+
+/// Days a report covers
+const PERIOD_DAYS: i64 = 7;
+
+/// Goals listed per section; the rest are only counted
+const MAX_GOALS_LISTED: usize = 30;
+
+/// Notable decisions listed
+const MAX_DECISIONS: usize = 15;
+
+/// Decisions the autonomy loop makes for every task; only listed when taken
+/// at a tactical or strategic level
+const ROUTINE_DECISIONS: &[&str] = &["task_routing", "goal_completion"];
+
+/// Tools whose use marks a goal as a self-update
+const SELF_UPDATE_TOOLS: &[&str] = &["self.update", "self.rebuild"];
+
+/// A goal as it appears in the report
+#[derive(Debug, Clone, Serialize)]
+pub struct GoalSummary {
+    pub id: String,
+    pub description: String,
+    pub status: String,
+    pub source: String,
+    pub updated_at: i64,
+    /// Console link to the goal
+    pub link: String,
+}
+
+/// An incident recorded during the week
+#[derive(Debug, Clone, Serialize)]
+pub struct IncidentSummary {
+    pub id: String,
+    pub description: String,
+    pub root_cause: String,
+    pub resolution: String,
+    pub timestamp: i64,
+}
+
+/// API spend for the week
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CostSummary {
+    pub total_cost_usd: f64,
+    pub total_requests: i32,
+    pub total_tokens: i32,
+    /// Cost per provider, highest first
+    pub by_provider: Vec<(String, f64)>,
+    /// Share of the most-used monthly budget spent, in percent
+    pub budget_spent_percent: f64,
+    /// Degradation step in force when the report was written
+    pub degradation: String,
+}
+
+/// A decision worth a reader's attention
+#[derive(Debug, Clone, Serialize)]
+pub struct DecisionSummary {
+    pub context: String,
+    pub chosen: String,
+    pub reasoning: String,
+    pub intelligence_level: String,
+    pub outcome: String,
+}
+
+/// A benchmark run during the week
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchmarkSummary {
+    pub started_at: i64,
+    pub trigger: String,
+    pub build: String,
+    /// Names of the metrics that regressed
+    pub regressions: Vec<String>,
+}
+
+/// Everything a report is written from
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportData {
+    /// ISO week, e.g. "2026-W41"
+    pub week: String,
+    pub period_start: i64,
+    pub period_end: i64,
+    pub goals_created: usize,
+    pub goals_completed: Vec<GoalSummary>,
+    pub goals_failed: Vec<GoalSummary>,
+    pub completed_count: usize,
+    pub failed_count: usize,
+    /// Goals still open at the end of the week
+    pub open_count: usize,
+    pub incidents: Vec<IncidentSummary>,
+    /// None when the API gateway could not be reached
+    pub cost: Option<CostSummary>,
+    pub decisions: Vec<DecisionSummary>,
+    /// Goals that updated or rebuilt aiOS itself
+    pub self_updates: Vec<GoalSummary>,
+    pub benchmarks: Vec<BenchmarkSummary>,
+}
+
+impl ReportData {
+    /// Every goal the report may link to
+    fn goals(&self) -> impl Iterator<Item = &GoalSummary> {
+        self.goals_completed
+            .iter()
+            .chain(&self.goals_failed)
+            .chain(&self.self_updates)
+    }
+}
+
+/// A written report
+#[derive(Debug, Clone, Serialize)]
+pub struct WeeklyReport {
+    pub week: String,
+    pub title: String,
+    pub generated_at: i64,
+    /// Whether the AI wrote the text or it was rendered from the data
+    pub ai_written: bool,
+    pub markdown_path: String,
+    pub html_path: String,
+    pub markdown: String,
+}
+
+/// A report on disk
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportFile {
+    pub week: String,
+    pub markdown_path: String,
+    pub html_path: String,
+    pub modified: i64,
+}
+
+/// Console link to a goal
+fn goal_link(goal_id: &str) -> String {
+    let base =
+        std::env::var("AIOS_CONSOLE_URL").unwrap_or_else(|_| "http://localhost:9090".to_string());
+    format!("{}/#goal={goal_id}", base.trim_end_matches('/'))
+}
+
+/// ISO week label of `at`
+pub fn iso_week(at: DateTime<Utc>) -> String {
+    let week = at.iso_week();
+    format!("{}-W{:02}", week.year(), week.week())
+}
+
+/// Collect the week ending at `until` from the goal engine, memory and the
+/// API gateway
+pub async fn gather(state: &RwLock<OrchestratorState>, until: DateTime<Utc>) -> ReportData {
+    let since = until.timestamp() - PERIOD_DAYS * 86_400;
+    let summarize = |g: &crate::proto::common::Goal| GoalSummary {
+        id: g.id.clone(),
+        description: g.description.clone(),
+        status: g.status.clone(),
+        source: g.source.clone(),
+        updated_at: g.updated_at,
+        link: goal_link(&g.id),
+    };
+
+    let (mut data, clients) = {
+        let s = state.read().await;
+        let (goals, _) = s
+            .goal_engine
+            .search_goals(&GoalQuery::default(), i32::MAX, 0)
+            .await;
+        let mut completed: Vec<GoalSummary> = Vec::new();
+        let mut failed: Vec<GoalSummary> = Vec::new();
+        let mut self_updates = Vec::new();
+        let mut goals_created = 0;
+        let mut open_count = 0;
+        for goal in &goals {
+            if goal.created_at >= since {
+                goals_created += 1;
+            }
+            match goal.status.as_str() {
+                "completed" | "failed" | "cancelled" => {}
+                _ => open_count += 1,
+            }
+            if goal.updated_at < since {
+                continue;
+            }
+            match goal.status.as_str() {
+                "completed" => completed.push(summarize(goal)),
+                "failed" => failed.push(summarize(goal)),
+                _ => {}
+            }
+            let updated_self = s.goal_engine.get_goal_tasks(&goal.id).iter().any(|t| {
+                t.required_tools
+                    .iter()
+                    .any(|tool| SELF_UPDATE_TOOLS.contains(&tool.as_str()))
+            });
+            if updated_self {
+                self_updates.push(summarize(goal));
+            }
+        }
+        for list in [&mut completed, &mut failed] {
+            list.sort_by_key(|g| std::cmp::Reverse(g.updated_at));
+        }
+        let (completed_count, failed_count) = (completed.len(), failed.len());
+        completed.truncate(MAX_GOALS_LISTED);
+        failed.truncate(MAX_GOALS_LISTED);
+        self_updates.truncate(MAX_GOALS_LISTED);
+
+        let decisions = s
+            .decision_logger
+            .recent(usize::MAX)
+            .into_iter()
+            .take_while(|d| d.timestamp >= since)
+            .filter(|d| {
+                !ROUTINE_DECISIONS.contains(&d.context.as_str())
+                    || matches!(d.intelligence_level.as_str(), "tactical" | "strategic")
+            })
+            .take(MAX_DECISIONS)
+            .map(|d| DecisionSummary {
+                context: d.context.clone(),
+                chosen: d.chosen.clone(),
+                reasoning: d.reasoning.clone(),
+                intelligence_level: d.intelligence_level.clone(),
+                outcome: d.outcome.clone().unwrap_or_default(),
+            })
+            .collect();
+
+        let data = ReportData {
+            week: iso_week(until),
+            period_start: since,
+            period_end: until.timestamp(),
+            goals_created,
+            goals_completed: completed,
+            goals_failed: failed,
+            completed_count,
+            failed_count,
+            open_count,
+            incidents: Vec::new(),
+            cost: None,
+            decisions,
+            self_updates,
+            benchmarks: Vec::new(),
+        };
+        (data, s.clients.clone())
+    };
+
+    data.incidents = incidents_since(&clients, since).await;
+    data.cost = cost_since(&clients).await;
+    data.benchmarks = benchmarks_since(&clients, since).await;
+    data
+}
+
+async fn incidents_since(clients: &ServiceClients, since: i64) -> Vec<IncidentSummary> {
+    let mut client = match clients.memory().await {
+        Ok(client) => client,
+        Err(e) => {
+            warn!("Weekly report without incidents; memory unavailable: {e}");
+            return Vec::new();
+        }
+    };
+    let request = crate::proto::memory::IncidentsRequest {
+        since,
+        limit: 0,
+        requesting_agent: "orchestrator".to_string(),
+    };
+    match client.list_incidents(request).await {
+        Ok(response) => response
+            .into_inner()
+            .incidents
+            .into_iter()
+            .map(|i| IncidentSummary {
+                id: i.id,
+                description: i.description,
+                root_cause: i.root_cause,
+                resolution: i.resolution,
+                timestamp: i.timestamp,
+            })
+            .collect(),
+        Err(e) => {
+            warn!("Weekly report without incidents: {e}");
+            Vec::new()
+        }
+    }
+}
+
+async fn cost_since(clients: &ServiceClients) -> Option<CostSummary> {
+    let mut client = clients.api_gateway().await.ok()?;
+    let usage = client
+        .get_usage(crate::proto::api_gateway::UsageRequest {
+            provider: String::new(),
+            days: PERIOD_DAYS as i32,
+        })
+        .await
+        .ok()?
+        .into_inner();
+    let mut by_provider: Vec<(String, f64)> = Vec::new();
+    for record in &usage.records {
+        match by_provider.iter_mut().find(|(p, _)| *p == record.provider) {
+            Some((_, cost)) => *cost += record.cost_usd,
+            None => by_provider.push((record.provider.clone(), record.cost_usd)),
+        }
+    }
+    by_provider.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let budget = client
+        .get_budget(crate::proto::common::Empty {})
+        .await
+        .map(|r| r.into_inner())
+        .unwrap_or_default();
+    Some(CostSummary {
+        total_cost_usd: usage.total_cost_usd,
+        total_requests: usage.total_requests,
+        total_tokens: usage.total_tokens,
+        by_provider,
+        budget_spent_percent: budget.spent_percent,
+        degradation: budget.degradation,
+    })
+}
+
+async fn benchmarks_since(clients: &ServiceClients, since: i64) -> Vec<BenchmarkSummary> {
+    let Ok(mut client) = clients.memory().await else {
+        return Vec::new();
+    };
+    let runs = client
+        .get_benchmark_runs(crate::proto::memory::BenchmarkRunsRequest { limit: 50 })
+        .await
+        .map(|r| r.into_inner().runs)
+        .unwrap_or_default();
+    runs.into_iter()
+        .filter(|run| run.started_at >= since)
+        .map(|run| BenchmarkSummary {
+            started_at: run.started_at,
+            trigger: run.trigger,
+            build: run.build,
+            regressions: serde_json::from_slice::<Vec<crate::benchmark::Regression>>(
+                &run.regressions_json,
+            )
+            .unwrap_or_default()
+            .into_iter()
+            .map(|r| r.name)
+            .collect(),
+        })
+        .collect()
+}
+
+fn day(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp, 0)
+        .map(|t| t.format("%a %Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+fn goal_line(goal: &GoalSummary) -> String {
+    format!(
+        "- [{}]({}) — {}, {}",
+        goal.description.replace(['[', ']'], ""),
+        goal.link,
+        goal.status,
+        day(goal.updated_at)
+    )
+}
+
+/// Title of the report for `data`
+fn title(data: &ReportData) -> String {
+    format!("aiOS weekly operations report — {}", data.week)
+}
+
+/// The report rendered straight from the data, used when the AI cannot
+/// write it
+pub fn render_markdown(data: &ReportData) -> String {
+    let mut md = format!(
+        "# {}\n\n{} to {}\n\n## Summary\n\n- Goals created: {}\n- Completed: {}\n- Failed: {}\n- Still open: {}\n- Incidents: {}\n",
+        title(data),
+        day(data.period_start),
+        day(data.period_end),
+        data.goals_created,
+        data.completed_count,
+        data.failed_count,
+        data.open_count,
+        data.incidents.len(),
+    );
+
+    md.push_str("\n## Cost\n\n");
+    match &data.cost {
+        Some(cost) => {
+            md.push_str(&format!(
+                "- Spent: ${:.2} over {} requests ({} tokens)\n- Monthly budget used: {:.0}%\n",
+                cost.total_cost_usd,
+                cost.total_requests,
+                cost.total_tokens,
+                cost.budget_spent_percent
+            ));
+            for (provider, usd) in &cost.by_provider {
+                md.push_str(&format!("- {provider}: ${usd:.2}\n"));
+            }
+            if !cost.degradation.is_empty() {
+                md.push_str(&format!(
+                    "- Budget degradation in force: {}\n",
+                    cost.degradation
+                ));
+            }
+        }
+        None => md.push_str("Usage data was unavailable.\n"),
+    }
+
+    for (heading, goals, count) in [
+        (
+            "Completed goals",
+            &data.goals_completed,
+            data.completed_count,
+        ),
+        ("Failed goals", &data.goals_failed, data.failed_count),
+    ] {
+        if goals.is_empty() {
+            continue;
+        }
+        md.push_str(&format!("\n## {heading}\n\n"));
+        for goal in goals {
+            md.push_str(&goal_line(goal));
+            md.push('\n');
+        }
+        if count > goals.len() {
+            md.push_str(&format!("- …and {} more\n", count - goals.len()));
+        }
+    }
+
+    if !data.incidents.is_empty() {
+        md.push_str("\n## Incidents\n\n");
+        for incident in &data.incidents {
+            md.push_str(&format!(
+                "- {}: {}",
+                day(incident.timestamp),
+                incident.description
+            ));
+            if !incident.root_cause.is_empty() {
+                md.push_str(&format!(" Cause: {}.", incident.root_cause));
+            }
+            if !incident.resolution.is_empty() {
+                md.push_str(&format!(" Resolution: {}.", incident.resolution));
+            }
+            md.push('\n');
+        }
+    }
+
+    if !data.decisions.is_empty() {
+        md.push_str("\n## Notable decisions\n\n");
+        for decision in &data.decisions {
+            md.push_str(&format!(
+                "- {} ({}): {} — {}\n",
+                decision.context, decision.intelligence_level, decision.chosen, decision.reasoning
+            ));
+        }
+    }
+
+    if !data.self_updates.is_empty() || !data.benchmarks.is_empty() {
+        md.push_str("\n## Self-improvements\n\n");
+        for goal in &data.self_updates {
+            md.push_str(&goal_line(goal));
+            md.push('\n');
+        }
+        for run in &data.benchmarks {
+            let outcome = if run.regressions.is_empty() {
+                "no regressions".to_string()
+            } else {
+                format!("regressed: {}", run.regressions.join(", "))
+            };
+            md.push_str(&format!(
+                "- Benchmark ({}) of build {} on {}: {outcome}\n",
+                run.trigger,
+                run.build,
+                day(run.started_at)
+            ));
+        }
+    }
+    md
+}
+
+/// Append the links of goals the text does not link to, so every goal in
+/// the report can be followed back
+fn ensure_links(mut markdown: String, data: &ReportData) -> String {
+    let mut missing: Vec<&GoalSummary> = Vec::new();
+    for goal in data.goals() {
+        if !markdown.contains(&goal.link) && !missing.iter().any(|g| g.id == goal.id) {
+            missing.push(goal);
+        }
+    }
+    if !missing.is_empty() {
+        markdown.push_str("\n\n## Referenced goals\n\n");
+        for goal in missing {
+            markdown.push_str(&goal_line(goal));
+            markdown.push('\n');
+        }
+    }
+    markdown
+}
+
+/// Have the AI write the report from `data`
+async fn write_with_ai(clients: &ServiceClients, data: &ReportData) -> Option<String> {
+    let system_prompt = "You write the weekly operations report of aiOS, an AI-run operating \
+        system, for its operators. Use only the facts in the data you are given; do not invent \
+        numbers, goals or events. Start with a level-1 heading, then a short executive summary, \
+        then sections for goals, incidents, cost, notable decisions and self-improvements. \
+        Whenever you mention a goal, link it as a markdown link with its `link`. Respond with \
+        the markdown report only.";
+    let prompt = format!(
+        "Report data for {}:\n{}",
+        data.week,
+        serde_json::to_string_pretty(data).ok()?
+    );
+    let mut client = clients.api_gateway().await.ok()?;
+    let request = tonic::Request::new(crate::proto::api_gateway::ApiInferRequest {
+        prompt,
+        system_prompt: system_prompt.to_string(),
+        max_tokens: 4096,
+        temperature: 0.3,
+        preferred_provider: String::new(),
+        requesting_agent: "weekly-report".to_string(),
+        task_id: String::new(),
+        allow_fallback: true,
+        response_schema: String::new(),
+        sections: Vec::new(),
+        session_id: String::new(),
+        turn_kind: String::new(),
+        model_class: String::new(),
+        workload: String::new(),
+        goal_id: String::new(),
+        goal_budget: None,
+        bypass_cache: false,
+    });
+    match client.infer(request).await {
+        Ok(response) => {
+            let text = response.into_inner().text;
+            let text = text.trim();
+            (!text.is_empty()).then(|| text.to_string())
+        }
+        Err(e) => {
+            warn!("AI could not write the weekly report: {e}");
+            None
+        }
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// The report as a standalone HTML page
+pub fn to_html(title: &str, markdown: &str) -> String {
+    let parser = pulldown_cmark::Parser::new_ext(markdown, pulldown_cmark::Options::ENABLE_TABLES);
+    let mut body = String::new();
+    pulldown_cmark::html::push_html(&mut body, parser);
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         <style>body{{font-family:sans-serif;max-width:860px;margin:2em auto;line-height:1.5}}\
+         table{{border-collapse:collapse}}td,th{{border:1px solid #ccc;padding:4px 8px}}</style>\n\
+         </head>\n<body>\n{body}</body>\n</html>\n",
+        escape_html(title)
+    )
+}
+
+/// Compile, write and send the report for the week ending now
+pub async fn generate(state: &RwLock<OrchestratorState>, dir: &str) -> Result<WeeklyReport> {
+    let now = Utc::now();
+    let data = gather(state, now).await;
+    let (clients, notifier) = {
+        let s = state.read().await;
+        (s.clients.clone(), s.notifier.clone())
+    };
+
+    let (markdown, ai_written) = match write_with_ai(&clients, &data).await {
+        Some(text) => (ensure_links(text, &data), true),
+        None => (render_markdown(&data), false),
+    };
+    let title = title(&data);
+    let html = to_html(&title, &markdown);
+
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {dir}"))?;
+    let markdown_path = format!("{dir}/weekly-{}.md", data.week);
+    let html_path = format!("{dir}/weekly-{}.html", data.week);
+    std::fs::write(&markdown_path, &markdown)
+        .with_context(|| format!("Failed to write {markdown_path}"))?;
+    std::fs::write(&html_path, &html).with_context(|| format!("Failed to write {html_path}"))?;
+
+    notifier.notify(
+        REPORT_EVENT,
+        EventSeverity::Info,
+        &title,
+        &markdown,
+        &data.week,
+    );
+    info!("Weekly report {} written to {markdown_path}", data.week);
+    Ok(WeeklyReport {
+        week: data.week,
+        title,
+        generated_at: now.timestamp(),
+        ai_written,
+        markdown_path,
+        html_path,
+        markdown,
+    })
+}
+
+/// Reports in `dir`, newest first
+pub fn list_reports(dir: &str) -> Vec<ReportFile> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut reports: Vec<ReportFile> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let week = name
+                .strip_prefix("weekly-")?
+                .strip_suffix(".md")?
+                .to_string();
+            let modified = entry
+                .metadata()
+                .and_then(|m| m.modified())
+                .ok()
+                .map(|t| DateTime::<Utc>::from(t).timestamp())
+                .unwrap_or_default();
+            Some(ReportFile {
+                markdown_path: format!("{dir}/weekly-{week}.md"),
+                html_path: format!("{dir}/weekly-{week}.html"),
+                week,
+                modified,
+            })
+        })
+        .collect();
+    reports.sort_by(|a, b| b.week.cmp(&a.week));
+    reports
+}
+
+/// Path of the HTML report for `week`, if it is a week label and exists
+pub fn report_html_path(dir: &str, week: &str) -> Option<String> {
+    if week.is_empty() || !week.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return None;
+    }
+    let path = format!("{dir}/weekly-{week}.html");
+    std::path::Path::new(&path).exists().then_some(path)
+}
+
+/// Write a report on the configured schedule until `cancel` fires
+pub async fn run_weekly_reports(state: Arc<RwLock<OrchestratorState>>, cancel: CancellationToken) {
+    let cron = std::env::var("AIOS_REPORT_CRON").unwrap_or_else(|_| REPORT_CRON.to_string());
+    if let Err(e) = crate::scheduler::check_cron(&cron) {
+        warn!("Weekly reports disabled: {e}");
+        return;
+    }
+    let tz_name = std::env::var("AIOS_REPORT_TIMEZONE")
+        .unwrap_or_else(|_| crate::scheduler::DEFAULT_TIMEZONE.to_string());
+    let tz = match crate::scheduler::parse_timezone(&tz_name) {
+        Ok(tz) => tz,
+        Err(e) => {
+            warn!("Weekly reports disabled: {e}");
+            return;
+        }
+    };
+
+    loop {
+        let Some(next) = crate::scheduler::next_run(&cron, tz, Utc::now()) else {
+            warn!("Weekly report schedule '{cron}' never runs");
+            return;
+        };
+        let wait = (next - Utc::now()).to_std().unwrap_or_default();
+        info!("Next weekly report at {next}");
+        tokio::select! {
+            _ = cancel.cancelled() => break,
+            _ = tokio::time::sleep(wait) => {}
+        }
+        if let Err(e) = generate(&state, REPORTS_DIR).await {
+            warn!("Weekly report failed: {e:#}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn goal(id: &str, status: &str) -> GoalSummary {
+        GoalSummary {
+            id: id.into(),
+            description: format!("Goal {id}"),
+            status: status.into(),
+            source: "user".into(),
+            updated_at: 1_760_000_000,
+            link: format!("http://localhost:9090/#goal={id}"),
+        }
+    }
+
+    fn data() -> ReportData {
+        ReportData {
+            week: "2026-W41".into(),
+            period_start: 1_759_400_000,
+            period_end: 1_760_000_000,
+            goals_created: 3,
+            goals_completed: vec![goal("g1", "completed")],
+            goals_failed: vec![goal("g2", "failed")],
+            completed_count: 1,
+            failed_count: 1,
+            open_count: 1,
+            incidents: vec![IncidentSummary {
+                id: "inc-1".into(),
+                description: "nginx stopped answering".into(),
+                root_cause: "worker crash".into(),
+                resolution: "restarted".into(),
+                timestamp: 1_759_900_000,
+            }],
+            cost: Some(CostSummary {
+                total_cost_usd: 12.5,
+                total_requests: 40,
+                total_tokens: 90_000,
+                by_provider: vec![("claude".into(), 12.5)],
+                budget_spent_percent: 30.0,
+                degradation: String::new(),
+            }),
+            decisions: Vec::new(),
+            self_updates: vec![goal("g3", "completed")],
+            benchmarks: vec![BenchmarkSummary {
+                started_at: 1_759_950_000,
+                trigger: "post_update".into(),
+                build: "abc123".into(),
+                regressions: vec!["inference".into()],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_render_markdown_links_goals() {
+        let md = render_markdown(&data());
+        assert!(md.starts_with("# aiOS weekly operations report — 2026-W41"));
+        assert!(md.contains("[Goal g1](http://localhost:9090/#goal=g1)"));
+        assert!(md.contains("nginx stopped answering Cause: worker crash."));
+        assert!(md.contains("$12.50"));
+        assert!(md.contains("regressed: inference"));
+        // Rendered from the data, every goal is already linked
+        assert_eq!(ensure_links(md.clone(), &data()), md);
+    }
+
+    #[test]
+    fn test_ensure_links_appends_unlinked_goals() {
+        let text = "# Report\n\nSee [Goal g1](http://localhost:9090/#goal=g1).".to_string();
+        let md = ensure_links(text, &data());
+        let referenced = md.split("## Referenced goals").nth(1).unwrap();
+        assert!(!referenced.contains("#goal=g1"));
+        assert!(referenced.contains("#goal=g2"));
+        assert!(referenced.contains("#goal=g3"));
+    }
+
+    #[test]
+    fn test_html_and_week_label() {
+        let html = to_html("A <b> report", "# Title\n\n- item");
+        assert!(html.contains("<title>A &lt;b&gt; report</title>"));
+        assert!(html.contains("<h1>Title</h1>"));
+        let at = DateTime::from_timestamp(1_767_225_600, 0).unwrap(); // 2026-01-01
+        assert_eq!(iso_week(at), "2026-W01");
+        assert!(report_html_path("/tmp", "../etc").is_none());
+    }
+}
@@ -0,0 +1,522 @@
+//! Resilient inter-service calls — retries, circuit breakers, endpoint choice
+//!
+//! Every client stub [`crate::clients::ServiceClients`] hands out runs on a
+//! [`ResilientChannel`], so one flaky service costs its callers a few
+//! bounded retries instead of an error on every autonomy tick.
+//!
+//! A call that does not reach its service — a transport error, or
+//! UNAVAILABLE before any response — is retried up to the policy's attempt
+//! limit with jittered exponential backoff. Errors the service itself
+//! returns (NOT_FOUND, INTERNAL, ...) go back to the caller unchanged and
+//! count as the endpoint being up.
+//!
+//! Each endpoint has a circuit breaker. After `FAILURE_THRESHOLD`
+//! consecutive failures it opens and calls skip the endpoint for
+//! `OPEN_COOLDOWN`; then a single probe call decides whether it closes
+//! again. Endpoints come from service discovery while the service has a
+//! live heartbeat there, with the configured address as fallback. When
+//! every endpoint's breaker is open, calls fail fast with UNAVAILABLE.
+//!
+//! Each call's latency (retries included) and outcome is recorded for its
+//! method's SLO (see [`crate::slo`]).
+
+use http_body_util::{BodyExt, Full};
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use tonic::body::BoxBody;
+use tonic::codegen::{BoxFuture, StdError};
+use tonic::transport::{Channel, Endpoint};
+use tower::ServiceExt;
+use tracing::{debug, info, warn};
+
+use crate::api_version::VersionedChannel;
+use crate::discovery::ServiceRegistry;
+use crate::slo::SloTracker;
+
+/// Consecutive failures that open an endpoint's breaker
+const FAILURE_THRESHOLD: u32 = 5;
+
+/// How long an open breaker keeps calls away from its endpoint
+const OPEN_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// gRPC status code UNAVAILABLE, as sent in the `grpc-status` header
+const GRPC_UNAVAILABLE: &str = "14";
+
+/// How many times a call is tried and how long to wait in between
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(2),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before retrying after failed attempt `attempt` (1-based):
+    /// between half and all of the exponential backoff, so callers that
+    /// failed together do not retry together
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponential = self
+            .base_delay
+            .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+            .min(self.max_delay);
+        exponential / 2 + exponential.mul_f64(random_fraction() / 2.0)
+    }
+}
+
+/// A number in [0, 1]; std's randomly keyed hasher is random enough for
+/// jitter
+pub(crate) fn random_fraction() -> f64 {
+    let random = std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish();
+    random as f64 / u64::MAX as f64
+}
+
+#[derive(Debug)]
+enum BreakerState {
+    Closed {
+        failures: u32,
+    },
+    Open {
+        until: Instant,
+    },
+    /// A probe call is out; another is let through if it never reports
+    HalfOpen {
+        since: Instant,
+    },
+}
+
+/// Circuit breaker for one endpoint
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    state: Mutex<BreakerState>,
+}
+
+impl CircuitBreaker {
+    pub fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            threshold,
+            cooldown,
+            state: Mutex::new(BreakerState::Closed { failures: 0 }),
+        }
+    }
+
+    /// Whether a call may go to the endpoint now. An open breaker whose
+    /// cooldown has passed lets one probe call through.
+    pub fn allow(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let probe = match *state {
+            BreakerState::Closed { .. } => return true,
+            BreakerState::Open { until } => Instant::now() >= until,
+            BreakerState::HalfOpen { since } => since.elapsed() >= self.cooldown,
+        };
+        if probe {
+            *state = BreakerState::HalfOpen {
+                since: Instant::now(),
+            };
+        }
+        probe
+    }
+
+    pub fn record_success(&self) {
+        *self.state.lock().unwrap() = BreakerState::Closed { failures: 0 };
+    }
+
+    /// Count a failure; returns true when it opened the breaker
+    pub fn record_failure(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let failures = match *state {
+            BreakerState::Closed { failures } => failures + 1,
+            BreakerState::HalfOpen { .. } => self.threshold,
+            BreakerState::Open { .. } => return false,
+        };
+        if failures >= self.threshold {
+            *state = BreakerState::Open {
+                until: Instant::now() + self.cooldown,
+            };
+            true
+        } else {
+            *state = BreakerState::Closed { failures };
+            false
+        }
+    }
+
+    /// "closed", "open" or "half_open"
+    pub fn state(&self) -> &'static str {
+        match *self.state.lock().unwrap() {
+            BreakerState::Closed { .. } => "closed",
+            BreakerState::Open { .. } => "open",
+            BreakerState::HalfOpen { .. } => "half_open",
+        }
+    }
+}
+
+/// The endpoints of one service, their channels and breakers
+pub struct ServiceEndpoints {
+    /// Fully qualified gRPC service name, for version negotiation
+    service: String,
+    /// Name the service registers under in discovery
+    discovery_name: String,
+    default_addr: String,
+    discovery: Option<Arc<RwLock<ServiceRegistry>>>,
+    policy: RetryPolicy,
+    channels: tokio::sync::Mutex<HashMap<String, VersionedChannel>>,
+    breakers: Mutex<HashMap<String, Arc<CircuitBreaker>>>,
+    slo: Option<Arc<SloTracker>>,
+}
+
+impl ServiceEndpoints {
+    pub fn new(
+        service: &str,
+        discovery_name: &str,
+        default_addr: String,
+        discovery: Option<Arc<RwLock<ServiceRegistry>>>,
+        policy: RetryPolicy,
+    ) -> Self {
+        Self {
+            service: service.to_string(),
+            discovery_name: discovery_name.to_string(),
+            default_addr,
+            discovery,
+            policy,
+            channels: tokio::sync::Mutex::new(HashMap::new()),
+            breakers: Mutex::new(HashMap::new()),
+            slo: None,
+        }
+    }
+
+    /// Record every call's latency and outcome in `tracker`
+    pub fn with_slo(mut self, tracker: Arc<SloTracker>) -> Self {
+        self.slo = Some(tracker);
+        self
+    }
+
+    pub fn default_addr(&self) -> &str {
+        &self.default_addr
+    }
+
+    /// Addresses to try, in order: the discovered one while the service
+    /// heartbeats, then the configured one
+    pub async fn candidates(&self) -> Vec<String> {
+        let mut addrs = Vec::with_capacity(2);
+        if let Some(ref registry) = self.discovery {
+            if let Some(info) = registry.read().await.lookup(&self.discovery_name) {
+                let addr = format!("http://{}", info.address);
+                debug!("Resolved {} via discovery: {addr}", self.discovery_name);
+                addrs.push(addr);
+            }
+        }
+        if !addrs.contains(&self.default_addr) {
+            addrs.push(self.default_addr.clone());
+        }
+        addrs
+    }
+
+    fn breaker(&self, addr: &str) -> Arc<CircuitBreaker> {
+        self.breakers
+            .lock()
+            .unwrap()
+            .entry(addr.to_string())
+            .or_insert_with(|| Arc::new(CircuitBreaker::new(FAILURE_THRESHOLD, OPEN_COOLDOWN)))
+            .clone()
+    }
+
+    /// The first candidate whose breaker lets a call through
+    async fn choose(&self) -> Result<(String, Arc<CircuitBreaker>), tonic::Status> {
+        for addr in self.candidates().await {
+            let breaker = self.breaker(&addr);
+            if breaker.allow() {
+                return Ok((addr, breaker));
+            }
+        }
+        Err(tonic::Status::unavailable(format!(
+            "Circuit open for every endpoint of {}",
+            self.discovery_name
+        )))
+    }
+
+    /// Breaker state of every endpoint called so far, by address
+    pub fn circuit_states(&self) -> Vec<(String, &'static str)> {
+        let mut states: Vec<(String, &'static str)> = self
+            .breakers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(addr, breaker)| (addr.clone(), breaker.state()))
+            .collect();
+        states.sort();
+        states
+    }
+
+    /// The channel to `addr`, created on first use
+    async fn channel(&self, addr: &str) -> Result<VersionedChannel, tonic::Status> {
+        let mut channels = self.channels.lock().await;
+        if let Some(channel) = channels.get(addr) {
+            return Ok(channel.clone());
+        }
+        let channel = connect(addr, &self.service).await?;
+        channels.insert(addr.to_string(), channel.clone());
+        Ok(channel)
+    }
+}
+
+/// Connect and negotiate the API version `service` speaks at `addr`; an
+/// endpoint that cannot be reached yet gets a channel that connects on use.
+/// Retrying is left to the calls.
+async fn connect(addr: &str, service: &str) -> Result<VersionedChannel, tonic::Status> {
+    let endpoint = Endpoint::from_shared(addr.to_string())
+        .map_err(|e| tonic::Status::invalid_argument(format!("Invalid endpoint {addr}: {e}")))?
+        .connect_timeout(Duration::from_secs(5))
+        .timeout(Duration::from_secs(300))
+        .tcp_keepalive(Some(Duration::from_secs(10)));
+    match endpoint.connect().await {
+        Ok(channel) => {
+            let channel = VersionedChannel::negotiate(channel, service).await;
+            if channel.is_legacy() {
+                info!("Connected to {addr} (pre-versioning API)");
+            } else {
+                info!("Connected to {addr}");
+            }
+            Ok(channel)
+        }
+        Err(e) => {
+            warn!("Connection to {addr} failed: {e}");
+            Ok(VersionedChannel::current(endpoint.connect_lazy()))
+        }
+    }
+}
+
+/// Client channel that retries unreachable calls and routes around
+/// endpoints whose breaker is open
+#[derive(Clone)]
+pub struct ResilientChannel {
+    endpoints: Arc<ServiceEndpoints>,
+}
+
+impl ResilientChannel {
+    pub fn new(endpoints: Arc<ServiceEndpoints>) -> Self {
+        Self { endpoints }
+    }
+}
+
+impl tower::Service<http::Request<BoxBody>> for ResilientChannel {
+    type Response = <Channel as tower::Service<http::Request<BoxBody>>>::Response;
+    type Error = StdError;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: http::Request<BoxBody>) -> Self::Future {
+        let endpoints = self.endpoints.clone();
+        Box::pin(async move {
+            let started = Instant::now();
+            let method = req.uri().path().to_string();
+            let result = Self::call_with_retries(&endpoints, req).await;
+            if let Some(ref slo) = endpoints.slo {
+                let failed = match &result {
+                    Ok(response) => response
+                        .headers()
+                        .get("grpc-status")
+                        .is_some_and(|status| status != "0"),
+                    Err(_) => true,
+                };
+                slo.record(&method, started.elapsed(), failed);
+            }
+            result
+        })
+    }
+}
+
+impl ResilientChannel {
+    /// Send `req`, retrying while it does not reach its service
+    async fn call_with_retries(
+        endpoints: &ServiceEndpoints,
+        req: http::Request<BoxBody>,
+    ) -> Result<<Self as tower::Service<http::Request<BoxBody>>>::Response, StdError> {
+        // Requests are single messages, so the body can be kept for retries
+        let (parts, body) = req.into_parts();
+        let body = body.collect().await?.to_bytes();
+        let policy = &endpoints.policy;
+
+        let mut attempt = 1;
+        loop {
+            let (addr, breaker) = endpoints.choose().await?;
+            let mut channel = endpoints.channel(&addr).await?;
+            let mut request = http::Request::new(tonic::body::boxed(
+                Full::new(body.clone()).map_err(|never| -> tonic::Status { match never {} }),
+            ));
+            *request.method_mut() = parts.method.clone();
+            *request.uri_mut() = parts.uri.clone();
+            *request.version_mut() = parts.version;
+            *request.headers_mut() = parts.headers.clone();
+
+            let outcome = match channel.ready().await {
+                Ok(ready) => tower::Service::call(ready, request).await,
+                Err(e) => Err(e),
+            };
+            let error = match outcome {
+                Ok(response) if !is_unavailable(&response) => {
+                    breaker.record_success();
+                    return Ok(response);
+                }
+                Ok(response) => {
+                    record_failure(&breaker, &addr);
+                    if attempt >= policy.max_attempts {
+                        return Ok(response);
+                    }
+                    "UNAVAILABLE".to_string()
+                }
+                Err(e) => {
+                    record_failure(&breaker, &addr);
+                    if attempt >= policy.max_attempts {
+                        return Err(e.into());
+                    }
+                    e.to_string()
+                }
+            };
+            let delay = policy.backoff(attempt);
+            debug!(
+                "{} via {addr} failed (attempt {attempt}): {error}; retrying in {delay:?}",
+                parts.uri.path()
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+fn record_failure(breaker: &CircuitBreaker, addr: &str) {
+    if breaker.record_failure() {
+        warn!("Circuit opened for {addr}; calls skip it for {OPEN_COOLDOWN:?}");
+    }
+}
+
+/// Whether a response is a trailers-only UNAVAILABLE
+fn is_unavailable<B>(response: &http::Response<B>) -> bool {
+    response
+        .headers()
+        .get("grpc-status")
+        .is_some_and(|status| status == GRPC_UNAVAILABLE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_is_jittered_and_capped() {
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(300),
+        };
+        for _ in 0..20 {
+            let first = policy.backoff(1);
+            assert!(first >= Duration::from_millis(50) && first <= Duration::from_millis(100));
+            let second = policy.backoff(2);
+            assert!(second >= Duration::from_millis(100) && second <= Duration::from_millis(200));
+            assert!(policy.backoff(10) <= Duration::from_millis(300));
+        }
+    }
+
+    #[test]
+    fn test_breaker_opens_probes_and_closes() {
+        let breaker = CircuitBreaker::new(2, Duration::from_millis(20));
+        assert!(!breaker.record_failure());
+        assert!(breaker.allow());
+        assert!(breaker.record_failure());
+        assert_eq!(breaker.state(), "open");
+        assert!(!breaker.allow());
+
+        std::thread::sleep(Duration::from_millis(25));
+        // One probe after the cooldown; a failed probe opens it again
+        assert!(breaker.allow());
+        assert!(!breaker.allow());
+        assert!(breaker.record_failure());
+        assert!(!breaker.allow());
+
+        std::thread::sleep(Duration::from_millis(25));
+        assert!(breaker.allow());
+        breaker.record_success();
+        assert_eq!(breaker.state(), "closed");
+        assert!(breaker.allow());
+    }
+
+    #[tokio::test]
+    async fn test_candidates_prefer_live_discovery_entry() {
+        let registry = Arc::new(RwLock::new(ServiceRegistry::new()));
+        let endpoints = ServiceEndpoints::new(
+            "aios.v1.memory.MemoryService",
+            "memory",
+            "http://127.0.0.1:50053".into(),
+            Some(registry.clone()),
+            RetryPolicy::default(),
+        );
+        assert_eq!(endpoints.candidates().await, vec!["http://127.0.0.1:50053"]);
+
+        registry.write().await.register(
+            "memory",
+            "10.0.0.7:50053".parse().unwrap(),
+            "grpc",
+            "0.1.0",
+        );
+        assert_eq!(
+            endpoints.candidates().await,
+            vec!["http://10.0.0.7:50053", "http://127.0.0.1:50053"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_service_retries_then_fails_fast() {
+        let endpoints = Arc::new(ServiceEndpoints::new(
+            crate::proto::memory::memory_service_server::SERVICE_NAME,
+            "memory",
+            "http://127.0.0.1:1".into(),
+            None,
+            RetryPolicy {
+                max_attempts: 2,
+                base_delay: Duration::from_millis(1),
+                max_delay: Duration::from_millis(2),
+            },
+        ));
+        let mut client = crate::proto::memory::memory_service_client::MemoryServiceClient::new(
+            ResilientChannel::new(endpoints.clone()),
+        );
+
+        // Three calls of two attempts each pass the failure threshold
+        for _ in 0..3 {
+            assert!(client
+                .get_active_goals(crate::proto::memory::Empty {})
+                .await
+                .is_err());
+        }
+        assert_eq!(
+            endpoints.circuit_states(),
+            vec![("http://127.0.0.1:1".to_string(), "open")]
+        );
+        let status = client
+            .get_active_goals(crate::proto::memory::Empty {})
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unavailable);
+        assert!(status.message().contains("Circuit open"));
+    }
+}
@@ -2,11 +2,18 @@
 //!
 //! Determines when a goal is complete by checking if all tasks
 //! have finished, and aggregates results into a goal-level summary.
+//!
+//! Agents' results may carry typed sections besides their output: artifacts
+//! go to the artifact store, metrics to the memory service's metrics,
+//! memory entries to the knowledge base, and follow-ups become subgoals.
 
 use std::collections::HashMap;
-use tracing::info;
+use tracing::{info, warn};
 
-use crate::proto::common::TaskResult;
+use crate::artifacts::ArtifactStore;
+use crate::clients::ServiceClients;
+use crate::goal_engine::SubgoalSpec;
+use crate::proto::common::{Artifact, FollowUp, MemoryEntry, MetricSample, TaskResult};
 
 /// Stores task results and determines goal completion
 pub struct ResultAggregator {
@@ -99,6 +106,102 @@ impl ResultAggregator {
     }
 }
 
+/// Sections of a result routed to other services once it is recorded
+#[derive(Debug, Default)]
+pub struct RoutedSections {
+    pub artifacts: Vec<Artifact>,
+    pub metrics: Vec<MetricSample>,
+    pub memory_entries: Vec<MemoryEntry>,
+}
+
+impl RoutedSections {
+    /// Move the routed sections out of a result
+    pub fn take(result: &mut TaskResult) -> Self {
+        Self {
+            artifacts: std::mem::take(&mut result.artifacts),
+            metrics: std::mem::take(&mut result.metrics),
+            memory_entries: std::mem::take(&mut result.memory_entries),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.artifacts.is_empty() && self.metrics.is_empty() && self.memory_entries.is_empty()
+    }
+}
+
+/// Follow-ups of a result as subgoals of its goal
+pub fn follow_up_specs(follow_ups: &[FollowUp]) -> Vec<SubgoalSpec> {
+    follow_ups
+        .iter()
+        .filter(|f| !f.description.trim().is_empty())
+        .map(|f| SubgoalSpec {
+            description: f.description.trim().to_string(),
+            priority: (f.priority > 0).then_some(f.priority),
+        })
+        .collect()
+}
+
+/// Name a reported metric is recorded under, so agents cannot overwrite
+/// system metrics
+pub fn metric_key(producer: &str, key: &str) -> String {
+    format!("agent.{producer}.{key}")
+}
+
+/// Record a result's artifacts, metrics and memory entries. Failures are
+/// logged; the result itself has already been recorded.
+pub async fn route_sections(
+    clients: &ServiceClients,
+    artifacts: &ArtifactStore,
+    goal_id: &str,
+    task_id: &str,
+    producer: &str,
+    sections: RoutedSections,
+) {
+    for artifact in &sections.artifacts {
+        if let Err(e) = artifacts.record(goal_id, task_id, producer, artifact) {
+            warn!(
+                "Failed to record artifact {} of task {task_id}: {e}",
+                artifact.path
+            );
+        }
+    }
+    if sections.metrics.is_empty() && sections.memory_entries.is_empty() {
+        return;
+    }
+    let mut memory = match clients.memory().await {
+        Ok(client) => client,
+        Err(e) => {
+            warn!("Memory service unavailable; dropping metrics and memory entries of task {task_id}: {e}");
+            return;
+        }
+    };
+    let now = chrono::Utc::now().timestamp();
+    for metric in sections.metrics {
+        let update = crate::proto::memory::MetricUpdate {
+            key: metric_key(producer, &metric.key),
+            value: metric.value,
+            timestamp: now,
+        };
+        if let Err(e) = memory.update_metric(update).await {
+            warn!(
+                "Failed to record metric {} of task {task_id}: {e}",
+                metric.key
+            );
+        }
+    }
+    for entry in sections.memory_entries {
+        let knowledge = crate::proto::memory::KnowledgeEntry {
+            title: entry.title,
+            content: entry.content,
+            source: format!("agent:{producer}"),
+            tags: entry.tags,
+        };
+        if let Err(e) = memory.add_knowledge(knowledge).await {
+            warn!("Failed to store memory entry of task {task_id}: {e}");
+        }
+    }
+}
+
 /// Summary of goal execution
 #[derive(Debug, Default)]
 pub struct GoalSummary {
@@ -129,6 +232,7 @@ mod tests {
                 duration_ms: 100,
                 tokens_used: 50,
                 model_used: "tinyllama".into(),
+                ..Default::default()
             },
         );
 
@@ -151,6 +255,7 @@ mod tests {
                 duration_ms: 100,
                 tokens_used: 50,
                 model_used: "tinyllama".into(),
+                ..Default::default()
             },
         );
         agg.record_result(
@@ -163,6 +268,7 @@ mod tests {
                 duration_ms: 5000,
                 tokens_used: 0,
                 model_used: "mistral".into(),
+                ..Default::default()
             },
         );
 
@@ -186,6 +292,7 @@ mod tests {
                 duration_ms: 100,
                 tokens_used: 50,
                 model_used: "tinyllama".into(),
+                ..Default::default()
             },
         );
 
@@ -220,6 +327,7 @@ mod tests {
                     duration_ms: 100,
                     tokens_used: 50,
                     model_used: "tinyllama".into(),
+                    ..Default::default()
                 },
             );
         }
@@ -239,6 +347,7 @@ mod tests {
                 duration_ms: 100,
                 tokens_used: 50,
                 model_used: "tinyllama".into(),
+                ..Default::default()
             },
         );
         agg.record_result(
@@ -251,6 +360,7 @@ mod tests {
                 duration_ms: 200,
                 tokens_used: 75,
                 model_used: "mistral".into(),
+                ..Default::default()
             },
         );
 
@@ -271,6 +381,7 @@ mod tests {
                 duration_ms: 100,
                 tokens_used: 50,
                 model_used: "tinyllama".into(),
+                ..Default::default()
             },
         );
         agg.record_result(
@@ -283,6 +394,7 @@ mod tests {
                 duration_ms: 200,
                 tokens_used: 50,
                 model_used: "tinyllama".into(),
+                ..Default::default()
             },
         );
 
@@ -304,6 +416,7 @@ mod tests {
                     duration_ms: 100,
                     tokens_used: 50,
                     model_used: "tinyllama".into(),
+                    ..Default::default()
                 },
             );
         }
@@ -332,6 +445,7 @@ mod tests {
                     duration_ms: 100,
                     tokens_used: 50,
                     model_used: "tinyllama".into(),
+                    ..Default::default()
                 },
             );
         }
@@ -346,6 +460,7 @@ mod tests {
                 duration_ms: 100,
                 tokens_used: 50,
                 model_used: "mistral".into(),
+                ..Default::default()
             },
         );
 
@@ -378,6 +493,7 @@ mod tests {
                 duration_ms: 100,
                 tokens_used: 50,
                 model_used: "tinyllama".into(),
+                ..Default::default()
             },
         );
 
@@ -406,6 +522,7 @@ mod tests {
                 duration_ms: 100,
                 tokens_used: 50,
                 model_used: "tinyllama".into(),
+                ..Default::default()
             },
         );
         agg.record_result(
@@ -418,6 +535,7 @@ mod tests {
                 duration_ms: 200,
                 tokens_used: 100,
                 model_used: "mistral".into(),
+                ..Default::default()
             },
         );
 
@@ -426,4 +544,43 @@ mod tests {
         assert_eq!(agg.total_tokens("goal-1"), 50);
         assert_eq!(agg.total_tokens("goal-2"), 100);
     }
+
+    #[test]
+    fn test_routed_sections() {
+        let mut result = TaskResult {
+            task_id: "task-1".into(),
+            success: true,
+            artifacts: vec![Artifact {
+                path: "/tmp/build.log".into(),
+                ..Default::default()
+            }],
+            metrics: vec![MetricSample {
+                key: "files_scanned".into(),
+                value: 1200.0,
+            }],
+            follow_ups: vec![
+                FollowUp {
+                    description: "Rotate /var/log/big.log".into(),
+                    priority: 0,
+                },
+                FollowUp {
+                    description: "  ".into(),
+                    priority: 9,
+                },
+            ],
+            ..Default::default()
+        };
+        let sections = RoutedSections::take(&mut result);
+        assert!(!sections.is_empty());
+        assert!(result.artifacts.is_empty() && result.metrics.is_empty());
+        assert_eq!(
+            metric_key("scan-agent", &sections.metrics[0].key),
+            "agent.scan-agent.files_scanned"
+        );
+
+        let specs = follow_up_specs(&result.follow_ups);
+        assert_eq!(specs.len(), 1);
+        assert_eq!(specs[0].priority, None);
+        assert!(RoutedSections::default().is_empty());
+    }
 }
@@ -1,8 +1,16 @@
 //! Cron-Like Scheduled Goals
 //!
 //! Evaluates cron expressions on a 60-second tick and creates goals when due.
+//!
+//! Each schedule runs in its own IANA timezone (UTC unless given), so
+//! "0 2 * * *" in Europe/Berlin fires at 02:00 Berlin time all year. Run
+//! times are computed on the local wall clock: a time skipped when clocks
+//! go forward runs when the gap ends, and a time repeated when clocks go
+//! back runs only the first time.
 
 use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, LocalResult, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -18,6 +26,10 @@ pub struct ScheduledGoal {
     pub priority: i32,
     pub enabled: bool,
     pub last_run: Option<i64>,
+    /// IANA timezone the cron expression is read in; empty means UTC
+    pub timezone: String,
+    /// Unix time of the next run, kept by the scheduler
+    pub next_run: Option<i64>,
 }
 
 /// Goal scheduler with cron expression evaluation
@@ -34,8 +46,11 @@ impl GoalScheduler {
         }
     }
 
-    /// Initialize database and load schedules
-    pub fn load(&mut self) -> Result<()> {
+    /// Open the database, creating it and its table if needed
+    fn open_db(&self) -> Result<rusqlite::Connection> {
+        if let Some(parent) = std::path::Path::new(&self.db_path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
         let conn =
             rusqlite::Connection::open(&self.db_path).context("Failed to open scheduler DB")?;
 
@@ -49,9 +64,21 @@ impl GoalScheduler {
                 last_run INTEGER
             )",
         )?;
+        // Databases created before per-schedule timezones lack the column;
+        // the ALTER fails harmlessly when it already exists.
+        let _ = conn.execute(
+            "ALTER TABLE scheduled_goals ADD COLUMN timezone TEXT NOT NULL DEFAULT 'UTC'",
+            [],
+        );
+        Ok(conn)
+    }
+
+    /// Initialize database and load schedules
+    pub fn load(&mut self) -> Result<()> {
+        let conn = self.open_db()?;
 
         let mut stmt = conn.prepare(
-            "SELECT id, cron_expr, goal_template, priority, enabled, last_run FROM scheduled_goals",
+            "SELECT id, cron_expr, goal_template, priority, enabled, last_run, timezone FROM scheduled_goals",
         )?;
 
         let schedules: Vec<ScheduledGoal> = stmt
@@ -63,12 +90,16 @@ impl GoalScheduler {
                     priority: row.get(3)?,
                     enabled: row.get::<_, i32>(4)? != 0,
                     last_run: row.get(5)?,
+                    timezone: row.get(6)?,
+                    next_run: None,
                 })
             })?
             .filter_map(|r| r.ok())
             .collect();
 
-        for schedule in schedules {
+        let now = Utc::now();
+        for mut schedule in schedules {
+            schedule.next_run = schedule_next_run(&schedule, now);
             self.schedules.insert(schedule.id.clone(), schedule);
         }
 
@@ -76,54 +107,65 @@ impl GoalScheduler {
         Ok(())
     }
 
-    /// Add a new schedule
-    pub fn add_schedule(&mut self, schedule: ScheduledGoal) -> Result<()> {
-        let conn = rusqlite::Connection::open(&self.db_path)?;
+    /// Add a new schedule, rejecting an invalid cron expression or timezone
+    pub fn add_schedule(&mut self, mut schedule: ScheduledGoal) -> Result<()> {
+        check_cron(&schedule.cron_expr)?;
+        parse_timezone(&schedule.timezone)?;
+        if schedule.timezone.is_empty() {
+            schedule.timezone = DEFAULT_TIMEZONE.to_string();
+        }
+        schedule.next_run = schedule_next_run(&schedule, Utc::now());
+
+        let conn = self.open_db()?;
         conn.execute(
-            "INSERT OR REPLACE INTO scheduled_goals (id, cron_expr, goal_template, priority, enabled) VALUES (?1, ?2, ?3, ?4, ?5)",
-            rusqlite::params![schedule.id, schedule.cron_expr, schedule.goal_template, schedule.priority, schedule.enabled as i32],
+            "INSERT OR REPLACE INTO scheduled_goals (id, cron_expr, goal_template, priority, enabled, timezone) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            rusqlite::params![schedule.id, schedule.cron_expr, schedule.goal_template, schedule.priority, schedule.enabled as i32, schedule.timezone],
         )?;
         self.schedules.insert(schedule.id.clone(), schedule);
         Ok(())
     }
 
-    /// Remove a schedule
-    pub fn remove_schedule(&mut self, id: &str) -> Result<()> {
-        let conn = rusqlite::Connection::open(&self.db_path)?;
-        conn.execute("DELETE FROM scheduled_goals WHERE id = ?1", [id])?;
-        self.schedules.remove(id);
-        Ok(())
+    /// Add a built-in schedule unless it already exists, so that operators
+    /// can edit or disable it without it being reset on every start
+    pub fn ensure_schedule(&mut self, schedule: ScheduledGoal) -> Result<()> {
+        if self.schedules.contains_key(&schedule.id) {
+            return Ok(());
+        }
+        self.add_schedule(schedule)
     }
 
-    /// List all schedules
+    /// Remove a schedule; false if there was none with that id
+    pub fn remove_schedule(&mut self, id: &str) -> Result<bool> {
+        let conn = self.open_db()?;
+        let deleted = conn.execute("DELETE FROM scheduled_goals WHERE id = ?1", [id])?;
+        Ok(self.schedules.remove(id).is_some() || deleted > 0)
+    }
+
+    /// List all schedules, soonest next run first
     pub fn list_schedules(&self) -> Vec<&ScheduledGoal> {
-        self.schedules.values().collect()
+        let mut schedules: Vec<&ScheduledGoal> = self.schedules.values().collect();
+        schedules.sort_by(|a, b| {
+            let at = |s: &ScheduledGoal| s.next_run.unwrap_or(i64::MAX);
+            at(a).cmp(&at(b)).then_with(|| a.id.cmp(&b.id))
+        });
+        schedules
     }
 
     /// Check which schedules are due
-    pub fn check_due(&self, now: &chrono::DateTime<chrono::Utc>) -> Vec<&ScheduledGoal> {
+    pub fn check_due(&self, now: &DateTime<Utc>) -> Vec<&ScheduledGoal> {
         self.schedules
             .values()
-            .filter(|s| {
-                if !s.enabled {
-                    return false;
-                }
-                // Don't fire more than once per minute
-                if let Some(last) = s.last_run {
-                    if now.timestamp() - last < 60 {
-                        return false;
-                    }
-                }
-                matches_cron(&s.cron_expr, now)
-            })
+            .filter(|s| s.enabled && s.next_run.is_some_and(|at| at <= now.timestamp()))
             .collect()
     }
 
-    /// Mark a schedule as having run
+    /// Mark a schedule as having run and move it to its next run
     pub fn mark_run(&mut self, id: &str, timestamp: i64) {
         if let Some(schedule) = self.schedules.get_mut(id) {
             schedule.last_run = Some(timestamp);
-            if let Ok(conn) = rusqlite::Connection::open(&self.db_path) {
+            let after = DateTime::from_timestamp(timestamp, 0).unwrap_or_else(Utc::now);
+            schedule.next_run = schedule_next_run(schedule, after);
+            if let Ok(conn) = self.open_db() {
                 conn.execute(
                     "UPDATE scheduled_goals SET last_run = ?1 WHERE id = ?2",
                     rusqlite::params![timestamp, id],
@@ -133,6 +175,14 @@ impl GoalScheduler {
         }
     }
 
+    /// Move a schedule past a run it skipped, without recording it as run
+    pub fn skip_run(&mut self, id: &str, timestamp: i64) {
+        if let Some(schedule) = self.schedules.get_mut(id) {
+            let after = DateTime::from_timestamp(timestamp, 0).unwrap_or_else(Utc::now);
+            schedule.next_run = schedule_next_run(schedule, after);
+        }
+    }
+
     /// Run the scheduler loop
     pub async fn run(
         scheduler: Arc<RwLock<Self>>,
@@ -156,18 +206,24 @@ impl GoalScheduler {
                             .collect()
                     };
 
+                    let blackout = state.read().await.calendar.blackout_at(now.timestamp());
+                    if let Some(window) = blackout {
+                        let mut sched = scheduler.write().await;
+                        for (id, _, _) in &due_ids {
+                            info!(
+                                "Skipping scheduled goal {id}: inside blackout '{}' ({})",
+                                window.summary, window.calendar
+                            );
+                            sched.skip_run(id, now.timestamp());
+                        }
+                        continue;
+                    }
+
                     for (id, goal_template, priority) in due_ids {
                         info!("Scheduled goal due: {}", &goal_template[..60.min(goal_template.len())]);
-                        let mut state_w = state.write().await;
-                        match state_w.goal_engine.submit_goal(
-                            goal_template.clone(),
-                            priority,
-                            format!("scheduler:{id}"),
-                        ).await {
-                            Ok(goal_id) => {
-                                if let Ok(tasks) = state_w.task_planner.decompose_goal(&goal_id, &goal_template).await {
-                                    state_w.goal_engine.add_tasks(&goal_id, tasks);
-                                }
+                        let mut state_w = crate::liveness::write_state(&state, "scheduler.run").await;
+                        match submit_scheduled_goal(&mut state_w, &id, &goal_template, priority).await {
+                            Ok(_) => {
                                 drop(state_w);
                                 let mut sched = scheduler.write().await;
                                 sched.mark_run(&id, now.timestamp());
@@ -183,27 +239,146 @@ impl GoalScheduler {
     }
 }
 
-/// Simple cron expression matcher (minute hour day month weekday)
-fn matches_cron(expression: &str, now: &chrono::DateTime<chrono::Utc>) -> bool {
-    use chrono::Datelike;
-    use chrono::Timelike;
+/// Submit the goal of schedule `id` and decompose it into tasks
+pub async fn submit_scheduled_goal(
+    state: &mut crate::OrchestratorState,
+    id: &str,
+    goal_template: &str,
+    priority: i32,
+) -> Result<String> {
+    let goal_id = state
+        .goal_engine
+        .submit_goal(
+            goal_template.to_string(),
+            priority,
+            format!("scheduler:{id}"),
+        )
+        .await?;
+    if let Ok(tasks) = state
+        .task_planner
+        .decompose_goal(&goal_id, goal_template)
+        .await
+    {
+        state.goal_engine.add_tasks(&goal_id, tasks);
+    }
+    state.autonomy_waker.wake();
+    Ok(goal_id)
+}
+
+/// Timezone of schedules created without one
+pub const DEFAULT_TIMEZONE: &str = "UTC";
 
+/// How far ahead to look for a next run; covers "29 2" in leap years
+const MAX_SEARCH_DAYS: u32 = 366 * 4 + 1;
+
+/// Allowed values of the cron fields: minute, hour, day, month, weekday
+/// (1 = Monday .. 7 = Sunday)
+const FIELD_RANGES: [(u32, u32); 5] = [(0, 59), (0, 23), (1, 31), (1, 12), (1, 7)];
+
+/// Parse an IANA timezone name; empty means UTC
+pub fn parse_timezone(name: &str) -> Result<Tz> {
+    if name.is_empty() {
+        return Ok(Tz::UTC);
+    }
+    name.parse::<Tz>()
+        .map_err(|_| anyhow::anyhow!("Unknown timezone '{name}'"))
+}
+
+/// Check that `expression` has five fields of `*`, `*/n` or a
+/// comma-separated list of values in range
+pub fn check_cron(expression: &str) -> Result<()> {
     let parts: Vec<&str> = expression.split_whitespace().collect();
     if parts.len() != 5 {
-        return false;
+        anyhow::bail!("Cron expression '{expression}' must have 5 fields");
     }
+    for (part, (min, max)) in parts.iter().zip(FIELD_RANGES) {
+        let valid = match part.strip_prefix("*/") {
+            _ if *part == "*" => true,
+            Some(interval) => interval.parse::<u32>().is_ok_and(|n| n > 0),
+            None => part.split(',').all(|n| {
+                n.trim()
+                    .parse::<u32>()
+                    .is_ok_and(|n| (min..=max).contains(&n))
+            }),
+        };
+        if !valid {
+            anyhow::bail!("Invalid cron field '{part}' in '{expression}'");
+        }
+    }
+    Ok(())
+}
 
-    let checks = [
-        (parts[0], now.minute()),
-        (parts[1], now.hour()),
-        (parts[2], now.day()),
-        (parts[3], now.month()),
-        (parts[4], now.weekday().num_days_from_monday() + 1),
-    ];
-
-    checks
-        .iter()
-        .all(|(pattern, value)| matches_field(pattern, *value))
+/// Next run of `schedule` strictly after `after`, as Unix time
+fn schedule_next_run(schedule: &ScheduledGoal, after: DateTime<Utc>) -> Option<i64> {
+    let tz = parse_timezone(&schedule.timezone).ok()?;
+    next_run(&schedule.cron_expr, tz, after).map(|at| at.timestamp())
+}
+
+/// The first time strictly after `after` at which `expression` matches the
+/// wall clock in `tz`
+pub fn next_run(expression: &str, tz: Tz, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let parts: Vec<&str> = expression.split_whitespace().collect();
+    if parts.len() != 5 {
+        return None;
+    }
+    let start = after.with_timezone(&tz).naive_local();
+    let mut day = start.date();
+    for _ in 0..MAX_SEARCH_DAYS {
+        if matches_day(&parts, day) {
+            for hour in (0..24).filter(|h| matches_field(parts[1], *h)) {
+                for minute in (0..60).filter(|m| matches_field(parts[0], *m)) {
+                    let local = day.and_hms_opt(hour, minute, 0)?;
+                    if local <= start {
+                        continue;
+                    }
+                    if let Some(at) = resolve_local(tz, local).filter(|at| *at > after) {
+                        return Some(at);
+                    }
+                }
+            }
+        }
+        day = day.succ_opt()?;
+    }
+    None
+}
+
+/// Human-readable local times of the next `count` runs after `after`
+pub fn preview(expression: &str, tz: Tz, after: DateTime<Utc>, count: usize) -> Vec<String> {
+    let mut runs = Vec::with_capacity(count);
+    let mut from = after;
+    while runs.len() < count {
+        let Some(at) = next_run(expression, tz, from) else {
+            break;
+        };
+        runs.push(
+            at.with_timezone(&tz)
+                .format("%a %Y-%m-%d %H:%M %Z")
+                .to_string(),
+        );
+        from = at;
+    }
+    runs
+}
+
+/// Whether the day, month and weekday fields match `day`
+fn matches_day(parts: &[&str], day: NaiveDate) -> bool {
+    matches_field(parts[2], day.day())
+        && matches_field(parts[3], day.month())
+        && matches_field(parts[4], day.weekday().num_days_from_monday() + 1)
+}
+
+/// The instant a wall-clock time in `tz` refers to. A time repeated when
+/// clocks go back is its first occurrence; a time skipped when they go
+/// forward is the end of the gap.
+pub fn resolve_local(tz: Tz, local: NaiveDateTime) -> Option<DateTime<Utc>> {
+    let at = match tz.from_local_datetime(&local) {
+        LocalResult::Single(at) | LocalResult::Ambiguous(at, _) => at,
+        LocalResult::None => (1..=180).find_map(|minutes| {
+            tz.from_local_datetime(&(local + chrono::Duration::minutes(minutes)))
+                .earliest()
+        })?,
+    };
+    Some(at.with_timezone(&Utc))
 }
 
 fn matches_field(pattern: &str, value: u32) -> bool {
@@ -248,6 +423,110 @@ mod tests {
         assert!(!matches_field("10", 5));
     }
 
+    fn utc(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_next_run_follows_local_time_across_dst() {
+        let berlin = parse_timezone("Europe/Berlin").unwrap();
+        // 02:00 is 01:00 UTC in winter and 00:00 UTC in summer
+        assert_eq!(
+            next_run("0 2 * * *", berlin, utc("2026-01-10T12:00:00Z")),
+            Some(utc("2026-01-11T01:00:00Z"))
+        );
+        assert_eq!(
+            next_run("0 2 * * *", berlin, utc("2026-07-10T12:00:00Z")),
+            Some(utc("2026-07-11T00:00:00Z"))
+        );
+
+        // Clocks skip 02:00-03:00 on 29 March: the run moves to 03:00 CEST
+        let skipped = next_run("30 2 * * *", berlin, utc("2026-03-28T12:00:00Z")).unwrap();
+        assert_eq!(skipped, utc("2026-03-29T01:00:00Z"));
+        assert_eq!(
+            next_run("30 2 * * *", berlin, skipped),
+            Some(utc("2026-03-30T00:30:00Z"))
+        );
+
+        // 02:30 happens twice on 25 October: only the first one runs
+        let repeated = next_run("30 2 * * *", berlin, utc("2026-10-24T12:00:00Z")).unwrap();
+        assert_eq!(repeated, utc("2026-10-25T00:30:00Z"));
+        assert_eq!(
+            next_run("30 2 * * *", berlin, repeated),
+            Some(utc("2026-10-26T01:30:00Z"))
+        );
+    }
+
+    #[test]
+    fn test_preview_and_validation() {
+        let berlin = parse_timezone("Europe/Berlin").unwrap();
+        assert_eq!(
+            preview("0 2 * * *", berlin, utc("2026-03-28T12:00:00Z"), 2),
+            vec!["Sun 2026-03-29 03:00 CEST", "Mon 2026-03-30 02:00 CEST"]
+        );
+        assert_eq!(parse_timezone("").unwrap(), Tz::UTC);
+        assert!(parse_timezone("Mars/Olympus").is_err());
+        assert!(check_cron("*/5 2 1,15 * 1").is_ok());
+        assert!(check_cron("0 2 * *").is_err());
+        assert!(check_cron("0 24 * * *").is_err());
+        assert!(check_cron("*/0 * * * *").is_err());
+        assert_eq!(next_run("0 0 30 2 *", berlin, Utc::now()), None);
+    }
+
+    #[test]
+    fn test_schedules_persist_fire_and_delete() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("data/scheduler.db");
+        let db = db.to_str().unwrap();
+
+        // No load(): creating a schedule sets up the database itself
+        let mut scheduler = GoalScheduler::new(db);
+        scheduler
+            .add_schedule(ScheduledGoal {
+                id: "nightly".into(),
+                cron_expr: "0 2 * * *".into(),
+                goal_template: "Rotate logs".into(),
+                priority: 3,
+                enabled: true,
+                last_run: None,
+                timezone: String::new(),
+                next_run: None,
+            })
+            .unwrap();
+        assert!(scheduler
+            .add_schedule(ScheduledGoal {
+                id: "broken".into(),
+                cron_expr: "0 2 * *".into(),
+                goal_template: "Never".into(),
+                priority: 3,
+                enabled: true,
+                last_run: None,
+                timezone: String::new(),
+                next_run: None,
+            })
+            .is_err());
+
+        let mut reloaded = GoalScheduler::new(db);
+        reloaded.load().unwrap();
+        let listed = reloaded.list_schedules();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].timezone, DEFAULT_TIMEZONE);
+        let next = listed[0].next_run.unwrap();
+
+        let before = DateTime::from_timestamp(next - 1, 0).unwrap();
+        let at = DateTime::from_timestamp(next, 0).unwrap();
+        assert!(reloaded.check_due(&before).is_empty());
+        assert_eq!(reloaded.check_due(&at).len(), 1);
+        reloaded.mark_run("nightly", next);
+        assert!(reloaded.check_due(&at).is_empty());
+
+        assert!(reloaded.remove_schedule("nightly").unwrap());
+        assert!(!reloaded.remove_schedule("nightly").unwrap());
+        let mut empty = GoalScheduler::new(db);
+        empty.load().unwrap();
+        assert!(empty.list_schedules().is_empty());
+    }
+
     #[test]
     fn test_goal_scheduler_new() {
         let scheduler = GoalScheduler::new("/tmp/test_scheduler.db");
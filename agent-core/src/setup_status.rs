@@ -0,0 +1,144 @@
+//! Setup status — what the first-boot setup wizard recorded
+//!
+//! aios-init's setup wizard writes its outcome to /var/lib/aios/setup.toml.
+//! `GetSetupStatus` reports it step by step, checking the models selected
+//! for download against the model directory, so a dashboard can tell
+//! whether the system is ready to use or setup still has to be run.
+
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::proto::orchestrator::{SetupStatus, SetupStep};
+
+/// Where aios-init records the outcome of setup
+pub const SETUP_STATE_PATH: &str = "/var/lib/aios/setup.toml";
+
+/// setup.toml layout, as aios-init writes it
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct SetupState {
+    completed_at: i64,
+    hostname: String,
+    autonomy_level: String,
+    api_providers: Vec<String>,
+    model_dir: String,
+    models: Vec<String>,
+    operator: String,
+}
+
+fn step(name: &str, done: bool, detail: String) -> SetupStep {
+    SetupStep {
+        name: name.into(),
+        done,
+        detail,
+    }
+}
+
+/// Setup status from the state file at `path`. A missing or unreadable file
+/// means setup has not run.
+pub fn load(path: &str) -> SetupStatus {
+    let state: Option<SetupState> = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| toml::from_str(&contents).ok());
+    let Some(state) = state else {
+        let pending = "setup has not run; run `aios-init --setup` on the console";
+        return SetupStatus {
+            steps: [
+                "hostname",
+                "api_keys",
+                "autonomy_level",
+                "models",
+                "operator",
+            ]
+            .iter()
+            .map(|name| step(name, false, pending.into()))
+            .collect(),
+            ..Default::default()
+        };
+    };
+
+    let present = state
+        .models
+        .iter()
+        .filter(|file| Path::new(&state.model_dir).join(file).exists())
+        .count();
+    let steps = vec![
+        step(
+            "hostname",
+            !state.hostname.is_empty(),
+            state.hostname.clone(),
+        ),
+        step(
+            "api_keys",
+            true,
+            if state.api_providers.is_empty() {
+                "none; local models only".into()
+            } else {
+                state.api_providers.join(", ")
+            },
+        ),
+        step(
+            "autonomy_level",
+            !state.autonomy_level.is_empty(),
+            state.autonomy_level.clone(),
+        ),
+        step(
+            "models",
+            present == state.models.len(),
+            format!("{present} of {} downloaded", state.models.len()),
+        ),
+        step(
+            "operator",
+            !state.operator.is_empty(),
+            state.operator.clone(),
+        ),
+    ];
+
+    SetupStatus {
+        completed: state.completed_at > 0 && steps.iter().all(|s| s.done),
+        completed_at: state.completed_at,
+        steps,
+        hostname: state.hostname,
+        autonomy_level: state.autonomy_level,
+        api_providers: state.api_providers,
+        models: state.models,
+        operator: state.operator,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_checks_models_on_disk() {
+        let dir = std::env::temp_dir().join(format!("aios-setup-status-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("setup.toml");
+
+        let status = load(path.to_str().unwrap());
+        assert!(!status.completed);
+        assert_eq!(status.steps.len(), 5);
+        assert!(status.steps.iter().all(|s| !s.done));
+
+        std::fs::write(
+            &path,
+            format!(
+                "completed_at = 1700000000\nhostname = \"node-1\"\nautonomy_level = \"supervised\"\n\
+                 api_providers = []\nmodel_dir = \"{}\"\nmodels = [\"tiny.gguf\"]\noperator = \"ops\"\n",
+                dir.display()
+            ),
+        )
+        .unwrap();
+        let status = load(path.to_str().unwrap());
+        assert!(!status.completed);
+        assert_eq!(status.steps[3].detail, "0 of 1 downloaded");
+
+        std::fs::write(dir.join("tiny.gguf"), "").unwrap();
+        let status = load(path.to_str().unwrap());
+        assert!(status.completed);
+        assert_eq!(status.steps[1].detail, "none; local models only");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,384 @@
+//! Shutdown drain and warm restart
+//!
+//! On SIGTERM the orchestrator stops accepting new work, lets in-flight AI
+//! tasks finish up to a deadline, and then interrupts whatever is left at the
+//! next inference boundary. Multi-round reasoning loops checkpoint their
+//! conversation after every completed round, so an interrupted task resumes
+//! at the round it reached instead of starting over: on restart the
+//! checkpoints are loaded and the reasoning loop picks them up when the task
+//! is scheduled again.
+//!
+//! Tool calls are never abandoned mid-flight; a round's tool batch always
+//! completes before the loop checks for an interrupt.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+/// Task checkpoint database
+pub const CHECKPOINT_DB_PATH: &str = "/var/lib/aios/data/checkpoints.db";
+
+/// How long in-flight tasks get to finish after SIGTERM
+/// (override with AIOS_DRAIN_TIMEOUT_SECS)
+pub const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long interrupted tasks get to reach their next await point and
+/// unwind after the drain deadline
+const INTERRUPT_GRACE: Duration = Duration::from_secs(5);
+
+/// Drain timeout from the environment, or the default
+pub fn drain_timeout() -> Duration {
+    std::env::var("AIOS_DRAIN_TIMEOUT_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_DRAIN_TIMEOUT)
+}
+
+/// One completed reasoning round
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckpointTurn {
+    pub round: u32,
+    pub ai_response: String,
+    pub tool_results: Vec<serde_json::Value>,
+}
+
+/// Saved progress of a multi-round reasoning task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskCheckpoint {
+    pub task_id: String,
+    pub goal_id: String,
+    /// Round to run next
+    pub next_round: u32,
+    pub tokens_used: i32,
+    pub all_succeeded: bool,
+    pub turns: Vec<CheckpointTurn>,
+    pub saved_at: i64,
+}
+
+/// SQLite-backed checkpoint storage
+struct CheckpointStore {
+    conn: rusqlite::Connection,
+}
+
+impl CheckpointStore {
+    fn open(path: &str) -> Result<Self> {
+        if let Some(parent) = std::path::Path::new(path).parent() {
+            std::fs::create_dir_all(parent).ok();
+        }
+        let conn = rusqlite::Connection::open(path)
+            .with_context(|| format!("Failed to open checkpoint database at {path}"))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS task_checkpoints (
+                task_id TEXT PRIMARY KEY,
+                goal_id TEXT NOT NULL,
+                checkpoint TEXT NOT NULL,
+                saved_at INTEGER NOT NULL
+            )",
+        )
+        .context("Failed to create checkpoint table")?;
+        Ok(Self { conn })
+    }
+
+    fn save(&self, checkpoint: &TaskCheckpoint) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO task_checkpoints (task_id, goal_id, checkpoint, saved_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![
+                checkpoint.task_id,
+                checkpoint.goal_id,
+                serde_json::to_string(checkpoint)?,
+                checkpoint.saved_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn remove(&self, task_id: &str) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM task_checkpoints WHERE task_id = ?1", [task_id])?;
+        Ok(())
+    }
+
+    fn load_all(&self) -> Result<Vec<TaskCheckpoint>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT checkpoint FROM task_checkpoints")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        let mut checkpoints = Vec::new();
+        for row in rows {
+            match serde_json::from_str(&row?) {
+                Ok(cp) => checkpoints.push(cp),
+                Err(e) => warn!("Skipping unreadable task checkpoint: {e}"),
+            }
+        }
+        Ok(checkpoints)
+    }
+}
+
+/// Tracks in-flight tasks, drains them on shutdown, and holds checkpoints
+#[derive(Default)]
+pub struct Drain {
+    draining: AtomicBool,
+    /// Fired at the drain deadline; reasoning loops stop at the next inference
+    interrupt: CancellationToken,
+    in_flight: Mutex<HashSet<String>>,
+    idle: Notify,
+    store: Option<Mutex<CheckpointStore>>,
+    /// Checkpoints loaded at startup, taken when their task runs again
+    resumable: Mutex<HashMap<String, TaskCheckpoint>>,
+}
+
+impl Drain {
+    /// A drain backed by the checkpoint database at `path`, loading any
+    /// checkpoints left by the previous run. Falls back to in-memory only
+    /// if the database cannot be opened.
+    pub fn open(path: &str) -> Self {
+        let store = match CheckpointStore::open(path) {
+            Ok(store) => store,
+            Err(e) => {
+                warn!("Task checkpoints disabled: {e}");
+                return Self::default();
+            }
+        };
+        let resumable = match store.load_all() {
+            Ok(checkpoints) => checkpoints
+                .into_iter()
+                .map(|cp| (cp.task_id.clone(), cp))
+                .collect(),
+            Err(e) => {
+                warn!("Failed to load task checkpoints: {e}");
+                HashMap::new()
+            }
+        };
+        Self {
+            store: Some(Mutex::new(store)),
+            resumable: Mutex::new(resumable),
+            ..Self::default()
+        }
+    }
+
+    /// Whether shutdown has begun; no new work should be started
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+
+    /// Cancelled when the drain deadline passes
+    pub fn interrupted(&self) -> &CancellationToken {
+        &self.interrupt
+    }
+
+    /// Mark a task as in flight until the returned guard is dropped
+    pub fn track(self: &Arc<Self>, task_id: &str) -> InFlight {
+        self.in_flight.lock().unwrap().insert(task_id.to_string());
+        InFlight {
+            drain: self.clone(),
+            task_id: task_id.to_string(),
+        }
+    }
+
+    /// Tasks currently in flight
+    pub fn in_flight(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.in_flight.lock().unwrap().iter().cloned().collect();
+        ids.sort();
+        ids
+    }
+
+    /// Number of checkpoints waiting for their task to run again
+    pub fn resumable_count(&self) -> usize {
+        self.resumable.lock().unwrap().len()
+    }
+
+    /// Drop loaded checkpoints whose task is not in `task_ids` (finished,
+    /// cancelled, or deleted while the orchestrator was down)
+    pub fn retain_tasks(&self, task_ids: &HashSet<String>) {
+        let stale: Vec<String> = {
+            let mut resumable = self.resumable.lock().unwrap();
+            let stale = resumable
+                .keys()
+                .filter(|id| !task_ids.contains(*id))
+                .cloned()
+                .collect();
+            resumable.retain(|id, _| task_ids.contains(id));
+            stale
+        };
+        for task_id in stale {
+            self.remove_stored(&task_id);
+        }
+    }
+
+    /// Take the checkpoint to resume a task from, if one was saved
+    pub fn take_checkpoint(&self, task_id: &str) -> Option<TaskCheckpoint> {
+        self.resumable.lock().unwrap().remove(task_id)
+    }
+
+    /// Persist a task's progress
+    pub fn checkpoint(&self, checkpoint: &TaskCheckpoint) {
+        if let Some(ref store) = self.store {
+            if let Err(e) = store.lock().unwrap().save(checkpoint) {
+                warn!("Failed to checkpoint task {}: {e}", checkpoint.task_id);
+            }
+        }
+    }
+
+    /// Discard a task's checkpoint once it has finished
+    pub fn finish(&self, task_id: &str) {
+        self.resumable.lock().unwrap().remove(task_id);
+        self.remove_stored(task_id);
+    }
+
+    fn remove_stored(&self, task_id: &str) {
+        if let Some(ref store) = self.store {
+            if let Err(e) = store.lock().unwrap().remove(task_id) {
+                warn!("Failed to remove checkpoint for task {task_id}: {e}");
+            }
+        }
+    }
+
+    /// Stop accepting work and wait up to `timeout` for in-flight tasks to
+    /// finish. Tasks still running at the deadline are interrupted and given
+    /// a short grace period to unwind. Returns the tasks that were
+    /// interrupted.
+    pub async fn drain(&self, timeout: Duration) -> Vec<String> {
+        self.draining.store(true, Ordering::SeqCst);
+        let pending = self.in_flight();
+        if pending.is_empty() {
+            return Vec::new();
+        }
+        info!(
+            "Draining {} in-flight tasks (deadline {}s)",
+            pending.len(),
+            timeout.as_secs()
+        );
+        if self.wait_idle(timeout).await {
+            info!("All in-flight tasks finished");
+            return Vec::new();
+        }
+
+        let interrupted = self.in_flight();
+        warn!(
+            "Drain deadline reached; interrupting {} tasks: {}",
+            interrupted.len(),
+            interrupted.join(", ")
+        );
+        self.interrupt.cancel();
+        if !self.wait_idle(INTERRUPT_GRACE).await {
+            warn!(
+                "Tasks still running after interrupt: {}",
+                self.in_flight().join(", ")
+            );
+        }
+        interrupted
+    }
+
+    /// Wait until nothing is in flight; false on timeout
+    async fn wait_idle(&self, timeout: Duration) -> bool {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let notified = self.idle.notified();
+            if self.in_flight.lock().unwrap().is_empty() {
+                return true;
+            }
+            if tokio::time::timeout_at(deadline, notified).await.is_err() {
+                return false;
+            }
+        }
+    }
+}
+
+/// Guard marking a task as in flight
+pub struct InFlight {
+    drain: Arc<Drain>,
+    task_id: String,
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.drain.in_flight.lock().unwrap().remove(&self.task_id);
+        self.drain.idle.notify_waiters();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkpoint(task_id: &str, next_round: u32) -> TaskCheckpoint {
+        TaskCheckpoint {
+            task_id: task_id.to_string(),
+            goal_id: "goal-1".to_string(),
+            next_round,
+            tokens_used: 120,
+            all_succeeded: true,
+            turns: vec![CheckpointTurn {
+                round: 0,
+                ai_response: "{\"tool_calls\": []}".to_string(),
+                tool_results: vec![serde_json::json!({"tool": "fs.list", "success": true})],
+            }],
+            saved_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_checkpoints_survive_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("checkpoints.db");
+        let path = path.to_str().unwrap();
+
+        let drain = Drain::open(path);
+        drain.checkpoint(&checkpoint("task-a", 1));
+        drain.checkpoint(&checkpoint("task-b", 2));
+        drain.checkpoint(&checkpoint("task-c", 1));
+        drain.finish("task-c");
+        drop(drain);
+
+        let restarted = Drain::open(path);
+        assert_eq!(restarted.resumable_count(), 2);
+        restarted.retain_tasks(&HashSet::from(["task-b".to_string()]));
+        let cp = restarted.take_checkpoint("task-b").unwrap();
+        assert_eq!(cp.next_round, 2);
+        assert_eq!(cp.turns[0].tool_results[0]["tool"], "fs.list");
+        assert!(restarted.take_checkpoint("task-b").is_none());
+
+        // task-a was pruned from the database as well
+        assert_eq!(Drain::open(path).resumable_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_drain_waits_for_in_flight_tasks() {
+        let drain = Arc::new(Drain::default());
+        let guard = drain.track("task-1");
+        assert_eq!(drain.in_flight(), vec!["task-1".to_string()]);
+
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            drop(guard);
+        });
+        let interrupted = drain.drain(Duration::from_secs(5)).await;
+        assert!(interrupted.is_empty());
+        assert!(drain.is_draining());
+        assert!(!drain.interrupted().is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn test_drain_interrupts_at_deadline() {
+        let drain = Arc::new(Drain::default());
+        let guard = drain.track("slow-task");
+        let token = drain.interrupted().clone();
+        tokio::spawn(async move {
+            token.cancelled().await;
+            drop(guard);
+        });
+
+        let interrupted = drain.drain(Duration::from_millis(50)).await;
+        assert_eq!(interrupted, vec!["slow-task".to_string()]);
+        assert!(drain.interrupted().is_cancelled());
+        assert!(drain.in_flight().is_empty());
+    }
+}
@@ -0,0 +1,559 @@
+//! Latency and Error SLOs per gRPC Method
+//!
+//! Every call made through [`crate::resilience::ResilientChannel`] is
+//! recorded here by method ("aios.v1.tools.ToolRegistry/Execute"), so hot
+//! paths keep rolling p50/p95/p99 latencies and error counts without the
+//! services doing anything.
+//!
+//! Methods with an objective in slo.toml also get burn rates: how fast they
+//! spend their error budget (the share of calls allowed to be slow or to
+//! fail). A method whose burn rate passes the alert threshold over both the
+//! short and the long window publishes `slo.burn_rate`, and `slo.recovered`
+//! once it drops back below.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::event_bus::{EventSeverity, SystemEvent};
+
+/// Default location of the SLO configuration
+pub const SLO_CONFIG_PATH: &str = "/etc/aios/slo.toml";
+
+/// How often burn rates are checked for alerts
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Samples kept per method, whatever the window, to bound memory
+const MAX_SAMPLES: usize = 20_000;
+
+/// Calls needed in the short window before a method can alert
+const MIN_ALERT_CALLS: usize = 10;
+
+/// slo.toml layout
+#[derive(Debug, Deserialize)]
+struct SloFile {
+    /// Also apply the built-in objectives alongside the configured ones
+    #[serde(default = "default_true")]
+    builtin: bool,
+    #[serde(default = "default_window_secs")]
+    window_secs: u64,
+    #[serde(default = "default_short_window_secs")]
+    short_window_secs: u64,
+    #[serde(default = "default_burn_rate_alert")]
+    burn_rate_alert: f64,
+    #[serde(default)]
+    slo: Vec<SloObjective>,
+}
+
+/// Objective for one method
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SloObjective {
+    /// Fully qualified method, e.g. "aios.v1.memory.MemoryService/AssembleContext"
+    pub method: String,
+    /// Calls slower than this count against the latency objective
+    pub latency_ms: u64,
+    /// Share of calls that must finish within `latency_ms`
+    #[serde(default = "default_latency_objective")]
+    pub latency_objective: f64,
+    /// Share of calls that must succeed
+    #[serde(default = "default_error_objective")]
+    pub error_objective: f64,
+}
+
+/// Objectives and windows
+#[derive(Debug, Clone)]
+pub struct SloConfig {
+    pub objectives: Vec<SloObjective>,
+    pub window: Duration,
+    pub short_window: Duration,
+    /// Burn rate over both windows that raises an alert; 14.4 spends a
+    /// 30-day budget in about two days
+    pub burn_rate_alert: f64,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_window_secs() -> u64 {
+    3600
+}
+
+fn default_short_window_secs() -> u64 {
+    300
+}
+
+fn default_burn_rate_alert() -> f64 {
+    14.4
+}
+
+fn default_latency_objective() -> f64 {
+    0.99
+}
+
+fn default_error_objective() -> f64 {
+    0.999
+}
+
+/// Objectives that apply unless slo.toml turns them off
+pub fn builtin_objectives() -> Vec<SloObjective> {
+    [
+        ("aios.v1.tools.ToolRegistry/Execute", 5000, 0.95),
+        ("aios.v1.memory.MemoryService/AssembleContext", 500, 0.99),
+        ("aios.v1.memory.MemoryService/SemanticSearch", 250, 0.99),
+    ]
+    .into_iter()
+    .map(|(method, latency_ms, latency_objective)| SloObjective {
+        method: method.to_string(),
+        latency_ms,
+        latency_objective,
+        error_objective: default_error_objective(),
+    })
+    .collect()
+}
+
+impl Default for SloConfig {
+    fn default() -> Self {
+        Self {
+            objectives: builtin_objectives(),
+            window: Duration::from_secs(default_window_secs()),
+            short_window: Duration::from_secs(default_short_window_secs()),
+            burn_rate_alert: default_burn_rate_alert(),
+        }
+    }
+}
+
+/// Load SLOs from `path`. A missing file yields the built-ins; an invalid
+/// one is logged and the built-ins are used.
+pub fn load(path: &str) -> SloConfig {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => from_toml(&contents).unwrap_or_else(|e| {
+            warn!("Ignoring invalid SLO config in {path}: {e}");
+            SloConfig::default()
+        }),
+        Err(_) => SloConfig::default(),
+    }
+}
+
+/// Parse an slo.toml document; configured objectives replace built-ins for
+/// the same method
+pub fn from_toml(contents: &str) -> Result<SloConfig> {
+    let file: SloFile = toml::from_str(contents).context("Failed to parse SLO config")?;
+    if file.short_window_secs == 0 || file.short_window_secs > file.window_secs {
+        anyhow::bail!("short_window_secs must be non-zero and at most window_secs");
+    }
+    let mut objectives = if file.builtin {
+        builtin_objectives()
+    } else {
+        Vec::new()
+    };
+    for objective in file.slo {
+        let valid = |share: f64| share > 0.0 && share < 1.0;
+        if !valid(objective.latency_objective) || !valid(objective.error_objective) {
+            anyhow::bail!(
+                "SLO for {} needs objectives between 0 and 1 (exclusive)",
+                objective.method
+            );
+        }
+        objectives.retain(|o| o.method != objective.method);
+        objectives.push(objective);
+    }
+    Ok(SloConfig {
+        objectives,
+        window: Duration::from_secs(file.window_secs),
+        short_window: Duration::from_secs(file.short_window_secs),
+        burn_rate_alert: file.burn_rate_alert,
+    })
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    at: Instant,
+    latency_ms: f64,
+    error: bool,
+}
+
+/// Rolling latencies, error counts and burn rates of one method
+#[derive(Debug, Clone, Serialize)]
+pub struct MethodSlo {
+    pub method: String,
+    /// Calls in the long window
+    pub calls: usize,
+    pub errors: usize,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub objective: Option<SloObjective>,
+    /// Budget burn rates over the long and short windows; 1.0 spends the
+    /// budget exactly as fast as the objective allows
+    pub latency_burn_rate: f64,
+    pub error_burn_rate: f64,
+    pub short_latency_burn_rate: f64,
+    pub short_error_burn_rate: f64,
+    /// Burning faster than the alert threshold over both windows
+    pub alerting: bool,
+}
+
+/// Records calls and judges them against the configured objectives
+pub struct SloTracker {
+    config: SloConfig,
+    samples: Mutex<HashMap<String, VecDeque<Sample>>>,
+    /// Methods whose burn-rate alert is currently raised
+    alerting: Mutex<HashSet<String>>,
+}
+
+/// A method that started or stopped burning its budget too fast
+#[derive(Debug, Clone, PartialEq)]
+pub enum SloTransition {
+    Burning(String),
+    Recovered(String),
+}
+
+impl SloTracker {
+    pub fn new(config: SloConfig) -> Self {
+        Self {
+            config,
+            samples: Mutex::new(HashMap::new()),
+            alerting: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Record one call; `method` may carry the leading slash of a gRPC path
+    pub fn record(&self, method: &str, latency: Duration, error: bool) {
+        self.record_at(method, latency, error, Instant::now());
+    }
+
+    fn record_at(&self, method: &str, latency: Duration, error: bool, at: Instant) {
+        let method = method.trim_start_matches('/');
+        let mut samples = self.samples.lock().unwrap();
+        let window = samples.entry(method.to_string()).or_default();
+        window.push_back(Sample {
+            at,
+            latency_ms: latency.as_secs_f64() * 1000.0,
+            error,
+        });
+        while window.len() > MAX_SAMPLES
+            || window
+                .front()
+                .is_some_and(|s| at.saturating_duration_since(s.at) > self.config.window)
+        {
+            window.pop_front();
+        }
+    }
+
+    /// Every method called in the long window, slowest p99 first
+    pub fn report(&self) -> Vec<MethodSlo> {
+        self.report_at(Instant::now())
+    }
+
+    fn report_at(&self, now: Instant) -> Vec<MethodSlo> {
+        let alerting = self.alerting.lock().unwrap().clone();
+        let samples = self.samples.lock().unwrap();
+        let mut report: Vec<MethodSlo> = samples
+            .iter()
+            .filter_map(|(method, window)| {
+                let mut slo = self.summarize(method, window, now)?;
+                slo.alerting = alerting.contains(method);
+                Some(slo)
+            })
+            .collect();
+        report.sort_by(|a, b| b.p99_ms.total_cmp(&a.p99_ms));
+        report
+    }
+
+    fn summarize(
+        &self,
+        method: &str,
+        window: &VecDeque<Sample>,
+        now: Instant,
+    ) -> Option<MethodSlo> {
+        let recent: Vec<&Sample> = window
+            .iter()
+            .filter(|s| now.saturating_duration_since(s.at) <= self.config.window)
+            .collect();
+        if recent.is_empty() {
+            return None;
+        }
+        let mut latencies: Vec<f64> = recent.iter().map(|s| s.latency_ms).collect();
+        latencies.sort_by(f64::total_cmp);
+        let objective = self
+            .config
+            .objectives
+            .iter()
+            .find(|o| o.method == method)
+            .cloned();
+        let short: Vec<&Sample> = recent
+            .iter()
+            .copied()
+            .filter(|s| now.saturating_duration_since(s.at) <= self.config.short_window)
+            .collect();
+        let (latency_burn_rate, error_burn_rate) = burn_rates(&recent, objective.as_ref());
+        let (short_latency_burn_rate, short_error_burn_rate) =
+            burn_rates(&short, objective.as_ref());
+        Some(MethodSlo {
+            method: method.to_string(),
+            calls: recent.len(),
+            errors: recent.iter().filter(|s| s.error).count(),
+            p50_ms: percentile(&latencies, 0.50),
+            p95_ms: percentile(&latencies, 0.95),
+            p99_ms: percentile(&latencies, 0.99),
+            objective,
+            latency_burn_rate,
+            error_burn_rate,
+            short_latency_burn_rate,
+            short_error_burn_rate,
+            alerting: false,
+        })
+    }
+
+    /// Raise or clear burn-rate alerts; returns what changed
+    pub fn check(&self) -> Vec<SloTransition> {
+        self.check_at(Instant::now())
+    }
+
+    fn check_at(&self, now: Instant) -> Vec<SloTransition> {
+        let threshold = self.config.burn_rate_alert;
+        let report = self.report_at(now);
+        let mut alerting = self.alerting.lock().unwrap();
+        let mut transitions = Vec::new();
+        for slo in report.iter().filter(|s| s.objective.is_some()) {
+            let short_calls = self.short_calls(&slo.method, now);
+            let burning = short_calls >= MIN_ALERT_CALLS
+                && ((slo.latency_burn_rate >= threshold
+                    && slo.short_latency_burn_rate >= threshold)
+                    || (slo.error_burn_rate >= threshold
+                        && slo.short_error_burn_rate >= threshold));
+            if burning && alerting.insert(slo.method.clone()) {
+                transitions.push(SloTransition::Burning(slo.method.clone()));
+            } else if !burning && alerting.remove(&slo.method) {
+                transitions.push(SloTransition::Recovered(slo.method.clone()));
+            }
+        }
+        // Methods that stopped being called recover too
+        let called: HashSet<&String> = report.iter().map(|s| &s.method).collect();
+        let idle: Vec<String> = alerting
+            .iter()
+            .filter(|m| !called.contains(m))
+            .cloned()
+            .collect();
+        for method in idle {
+            alerting.remove(&method);
+            transitions.push(SloTransition::Recovered(method));
+        }
+        transitions
+    }
+
+    fn short_calls(&self, method: &str, now: Instant) -> usize {
+        self.samples
+            .lock()
+            .unwrap()
+            .get(method)
+            .map(|w| {
+                w.iter()
+                    .filter(|s| now.saturating_duration_since(s.at) <= self.config.short_window)
+                    .count()
+            })
+            .unwrap_or(0)
+    }
+}
+
+/// (latency, error) burn rates of `samples` against `objective`
+fn burn_rates(samples: &[&Sample], objective: Option<&SloObjective>) -> (f64, f64) {
+    let Some(objective) = objective else {
+        return (0.0, 0.0);
+    };
+    if samples.is_empty() {
+        return (0.0, 0.0);
+    }
+    let calls = samples.len() as f64;
+    let slow = samples
+        .iter()
+        .filter(|s| s.latency_ms > objective.latency_ms as f64)
+        .count() as f64;
+    let errors = samples.iter().filter(|s| s.error).count() as f64;
+    (
+        (slow / calls) / (1.0 - objective.latency_objective),
+        (errors / calls) / (1.0 - objective.error_objective),
+    )
+}
+
+/// Nearest-rank percentile of sorted values
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = (p * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Check burn rates every minute and publish alert transitions as events
+pub async fn run(
+    tracker: Arc<SloTracker>,
+    events: mpsc::Sender<SystemEvent>,
+    cancel: CancellationToken,
+) {
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => return,
+            _ = interval.tick() => {}
+        }
+        let transitions = tracker.check();
+        if transitions.is_empty() {
+            continue;
+        }
+        let report = tracker.report();
+        for transition in transitions {
+            let (event_type, method, severity) = match &transition {
+                SloTransition::Burning(method) => ("slo.burn_rate", method, EventSeverity::Warning),
+                SloTransition::Recovered(method) => ("slo.recovered", method, EventSeverity::Info),
+            };
+            let slo = report.iter().find(|s| &s.method == method);
+            match &transition {
+                SloTransition::Burning(_) => warn!(
+                    "{method} is burning its SLO budget (latency {:.1}x, errors {:.1}x)",
+                    slo.map_or(0.0, |s| s.latency_burn_rate),
+                    slo.map_or(0.0, |s| s.error_burn_rate)
+                ),
+                SloTransition::Recovered(_) => info!("{method} is back within its SLO"),
+            }
+            crate::event_bus::publish_event(
+                &events,
+                event_type,
+                "slo",
+                serde_json::to_value(slo).unwrap_or_default(),
+                severity,
+            )
+            .await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracker(window_secs: u64, short_secs: u64) -> SloTracker {
+        SloTracker::new(SloConfig {
+            objectives: vec![SloObjective {
+                method: "svc/Hot".into(),
+                latency_ms: 100,
+                latency_objective: 0.9,
+                error_objective: 0.99,
+            }],
+            window: Duration::from_secs(window_secs),
+            short_window: Duration::from_secs(short_secs),
+            burn_rate_alert: 2.0,
+        })
+    }
+
+    #[test]
+    fn test_percentiles_and_window() {
+        let t = tracker(60, 10);
+        let start = Instant::now();
+        for ms in 1..=100 {
+            t.record_at("/svc/Cold", Duration::from_millis(ms), false, start);
+        }
+        let report = t.report_at(start);
+        assert_eq!(report.len(), 1);
+        let cold = &report[0];
+        assert_eq!(cold.method, "svc/Cold");
+        assert_eq!(cold.calls, 100);
+        assert_eq!(cold.p50_ms, 50.0);
+        assert_eq!(cold.p95_ms, 95.0);
+        assert_eq!(cold.p99_ms, 99.0);
+        assert!(cold.objective.is_none());
+        assert_eq!(cold.latency_burn_rate, 0.0);
+
+        // Samples age out of the long window
+        assert!(t.report_at(start + Duration::from_secs(61)).is_empty());
+    }
+
+    #[test]
+    fn test_burn_rate_alert_and_recovery() {
+        let t = tracker(60, 10);
+        let start = Instant::now();
+        // 30% of calls slow against a 10% budget: burn rate 3x
+        for i in 0..20 {
+            let ms = if i % 10 < 3 { 500 } else { 10 };
+            t.record_at("svc/Hot", Duration::from_millis(ms), false, start);
+        }
+        let hot = t.report_at(start).into_iter().next().unwrap();
+        assert!((hot.latency_burn_rate - 3.0).abs() < 1e-9);
+        assert_eq!(hot.error_burn_rate, 0.0);
+        assert_eq!(
+            t.check_at(start),
+            vec![SloTransition::Burning("svc/Hot".into())]
+        );
+        assert!(t.check_at(start).is_empty());
+        assert!(t.report_at(start)[0].alerting);
+
+        // The short window clears first: fast calls only
+        let later = start + Duration::from_secs(30);
+        for _ in 0..20 {
+            t.record_at("svc/Hot", Duration::from_millis(10), false, later);
+        }
+        assert_eq!(
+            t.check_at(later),
+            vec![SloTransition::Recovered("svc/Hot".into())]
+        );
+    }
+
+    #[test]
+    fn test_error_burn_needs_enough_calls() {
+        let t = tracker(60, 10);
+        let start = Instant::now();
+        for _ in 0..5 {
+            t.record_at("svc/Hot", Duration::from_millis(1), true, start);
+        }
+        assert!(t.check_at(start).is_empty());
+        for _ in 0..5 {
+            t.record_at("svc/Hot", Duration::from_millis(1), true, start);
+        }
+        assert_eq!(
+            t.check_at(start),
+            vec![SloTransition::Burning("svc/Hot".into())]
+        );
+    }
+
+    #[test]
+    fn test_from_toml() {
+        let config = from_toml(
+            r#"
+            burn_rate_alert = 6.0
+
+            [[slo]]
+            method = "aios.v1.tools.ToolRegistry/Execute"
+            latency_ms = 2000
+
+            [[slo]]
+            method = "aios.v1.runtime.AIRuntime/Infer"
+            latency_ms = 30000
+            latency_objective = 0.9
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.burn_rate_alert, 6.0);
+        assert_eq!(config.objectives.len(), 4);
+        let execute = config
+            .objectives
+            .iter()
+            .find(|o| o.method.ends_with("/Execute"))
+            .unwrap();
+        assert_eq!(execute.latency_ms, 2000);
+        assert_eq!(execute.latency_objective, 0.99);
+
+        assert!(from_toml(
+            "builtin = false\n[[slo]]\nmethod = \"a/B\"\nlatency_ms = 1\nerror_objective = 1.0"
+        )
+        .is_err());
+        assert!(from_toml("window_secs = 60\nshort_window_secs = 120").is_err());
+        assert!(from_toml("builtin = false").unwrap().objectives.is_empty());
+    }
+}
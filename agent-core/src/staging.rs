@@ -0,0 +1,838 @@
+//! Staging — rehearse risky goals in a disposable sandbox first
+//!
+//! A goal is staged when it carries the `staging` label (subgoals follow
+//! their ancestors), or when the AI reaches for a tool whose risk level is
+//! listed in staging.toml. From then on its tool calls do not touch the
+//! host: they go to the tools service of a Podman container started from
+//! the staging image, with copies of the host configuration in
+//! `clone_paths` mounted at their usual places.
+//!
+//! Every call made in the sandbox is recorded. When the staged goal
+//! completes, the sandbox is thrown away, the run is marked verified and
+//! the operator is asked (through the `approval` notification) whether to
+//! replay the successful calls on the real system, in the same order,
+//! through `POST /api/goals/:goal_id/staging/replay`. A staged goal that
+//! fails leaves the host untouched.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use crate::clients::ServiceClients;
+use crate::goal_engine::GoalEngine;
+use crate::remote_exec::RemoteExecutor;
+use crate::OrchestratorState;
+
+/// Default location of the staging configuration
+pub const STAGING_CONFIG_PATH: &str = "/etc/aios/staging.toml";
+
+/// Where the configuration clones of staged goals are kept
+pub const STAGING_DIR: &str = "/var/lib/aios/staging";
+
+/// Label that sends a goal (and its subgoals) through staging
+pub const STAGING_LABEL: &str = "staging";
+
+/// How long a new sandbox may take to answer
+const SANDBOX_READY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How often staged goals are checked for completion
+const RECONCILE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// How often tool risk levels are refreshed from the tools service
+const RISK_REFRESH_INTERVAL: Duration = Duration::from_secs(300);
+
+/// staging.toml layout
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StagingConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Image with the aiOS tools service, started once per staged goal
+    #[serde(default = "default_image")]
+    pub image: String,
+    /// Port the tools service listens on inside the image
+    #[serde(default = "default_tools_port")]
+    pub tools_port: u16,
+    /// Host configuration copied into the sandbox
+    #[serde(default = "default_clone_paths")]
+    pub clone_paths: Vec<String>,
+    /// Tool risk levels that put a goal into staging
+    #[serde(default = "default_risk_levels")]
+    pub risk_levels: Vec<String>,
+}
+
+fn default_image() -> String {
+    "localhost/aios-staging:latest".to_string()
+}
+
+fn default_tools_port() -> u16 {
+    50052
+}
+
+fn default_clone_paths() -> Vec<String> {
+    ["/etc/aios", "/etc/systemd/system", "/etc/nginx"]
+        .map(String::from)
+        .to_vec()
+}
+
+fn default_risk_levels() -> Vec<String> {
+    vec!["high".to_string(), "critical".to_string()]
+}
+
+impl Default for StagingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            image: default_image(),
+            tools_port: default_tools_port(),
+            clone_paths: default_clone_paths(),
+            risk_levels: default_risk_levels(),
+        }
+    }
+}
+
+/// Where a staged goal stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StagingStatus {
+    /// Running in the sandbox
+    Running,
+    /// Completed in the sandbox; waiting for the operator to replay it
+    Verified,
+    /// Failed in the sandbox, or the sandbox could not be started
+    Failed,
+    Replaying,
+    Replayed,
+    ReplayFailed,
+}
+
+/// A tool call made in the sandbox
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordedCall {
+    pub task_id: String,
+    pub tool_name: String,
+    pub input: serde_json::Value,
+    pub success: bool,
+    pub error: String,
+    /// Whether the call has been replayed on the host
+    pub replayed: bool,
+    pub replay_error: String,
+}
+
+/// The staging run of one goal
+#[derive(Debug, Clone, Serialize)]
+pub struct StagingRun {
+    pub goal_id: String,
+    pub status: StagingStatus,
+    /// Why the goal was staged
+    pub reason: String,
+    pub container: String,
+    pub tools_address: String,
+    pub started_at: i64,
+    pub finished_at: i64,
+    pub calls: Vec<RecordedCall>,
+    pub error: String,
+}
+
+impl StagingRun {
+    /// The calls a replay repeats: those that succeeded in the sandbox
+    pub fn verified_calls(&self) -> impl Iterator<Item = &RecordedCall> {
+        self.calls.iter().filter(|c| c.success)
+    }
+}
+
+/// Staging runs and the sandboxes behind them
+pub struct Staging {
+    config: StagingConfig,
+    runs: Mutex<HashMap<String, StagingRun>>,
+    /// Tool name → risk level, for the tools whose level stages a goal
+    risky_tools: Mutex<HashMap<String, String>>,
+    remote: tokio::sync::Mutex<RemoteExecutor>,
+    /// Held while a sandbox starts, so parallel tasks share one
+    provisioning: tokio::sync::Mutex<()>,
+    dir: String,
+}
+
+impl Default for Staging {
+    fn default() -> Self {
+        Self::with_config(StagingConfig::default(), STAGING_DIR)
+    }
+}
+
+impl Staging {
+    fn with_config(config: StagingConfig, dir: &str) -> Self {
+        Self {
+            config,
+            runs: Mutex::new(HashMap::new()),
+            risky_tools: Mutex::new(HashMap::new()),
+            remote: tokio::sync::Mutex::new(RemoteExecutor::new()),
+            provisioning: tokio::sync::Mutex::new(()),
+            dir: dir.to_string(),
+        }
+    }
+
+    /// Load the staging configuration from `path`; staging stays off when
+    /// it is missing or invalid
+    pub fn load(path: &str) -> Self {
+        let config = match std::fs::read_to_string(path) {
+            Ok(contents) => match toml::from_str::<StagingConfig>(&contents) {
+                Ok(config) => {
+                    if config.enabled {
+                        info!(
+                            "Staging enabled with image {} for {} risk tools",
+                            config.image,
+                            config.risk_levels.join("/")
+                        );
+                    }
+                    config
+                }
+                Err(e) => {
+                    warn!("Invalid staging configuration {path}: {e}; staging disabled");
+                    StagingConfig::default()
+                }
+            },
+            Err(_) => StagingConfig::default(),
+        };
+        Self::with_config(config, STAGING_DIR)
+    }
+
+    /// Every staging run, most recent first
+    pub fn runs(&self) -> Vec<StagingRun> {
+        let mut runs: Vec<StagingRun> = self.runs.lock().unwrap().values().cloned().collect();
+        runs.sort_by_key(|r| std::cmp::Reverse(r.started_at));
+        runs
+    }
+
+    /// The staging run of `goal_id`, if it was staged
+    pub fn run(&self, goal_id: &str) -> Option<StagingRun> {
+        self.runs.lock().unwrap().get(goal_id).cloned()
+    }
+
+    /// Remember the risk levels of the tools that stage a goal
+    fn set_tool_risks(&self, tools: &[crate::proto::tools::ToolDefinition]) {
+        *self.risky_tools.lock().unwrap() = tools
+            .iter()
+            .filter(|t| self.config.risk_levels.contains(&t.risk_level))
+            .map(|t| (t.name.clone(), t.risk_level.clone()))
+            .collect();
+    }
+
+    /// Why a goal about to call `tool_names` should be staged, if it should
+    pub fn staging_reason(&self, labeled: bool, tool_names: &[&str]) -> Option<String> {
+        if !self.config.enabled {
+            return None;
+        }
+        if labeled {
+            return Some(format!("goal is labeled '{STAGING_LABEL}'"));
+        }
+        let risky = self.risky_tools.lock().unwrap();
+        tool_names.iter().find_map(|name| {
+            risky
+                .get(*name)
+                .map(|risk| format!("{name} is a {risk} risk tool"))
+        })
+    }
+
+    /// Address of the sandbox `goal_id`'s calls go to: its running sandbox,
+    /// or a new one when the goal should be staged now. None runs the calls
+    /// on the host.
+    async fn route(
+        &self,
+        clients: &ServiceClients,
+        goal_id: &str,
+        labeled: bool,
+        tool_names: &[&str],
+    ) -> Result<Option<String>> {
+        if !self.config.enabled {
+            return Ok(None);
+        }
+        let _provisioning = self.provisioning.lock().await;
+        if let Some(run) = self.run(goal_id) {
+            match run.status {
+                StagingStatus::Running => return Ok(Some(run.tools_address)),
+                // A verified or replayed goal has been approved for the host
+                StagingStatus::Verified
+                | StagingStatus::Replaying
+                | StagingStatus::Replayed
+                | StagingStatus::ReplayFailed => return Ok(None),
+                // A retried goal is staged afresh
+                StagingStatus::Failed => {}
+            }
+        }
+        let Some(reason) = self.staging_reason(labeled, tool_names) else {
+            return Ok(None);
+        };
+        info!("Staging goal {goal_id}: {reason}");
+        match self.provision(clients, goal_id).await {
+            Ok((container, tools_address)) => {
+                self.runs.lock().unwrap().insert(
+                    goal_id.to_string(),
+                    StagingRun {
+                        goal_id: goal_id.to_string(),
+                        status: StagingStatus::Running,
+                        reason,
+                        container,
+                        tools_address: tools_address.clone(),
+                        started_at: chrono::Utc::now().timestamp(),
+                        finished_at: 0,
+                        calls: Vec::new(),
+                        error: String::new(),
+                    },
+                );
+                Ok(Some(tools_address))
+            }
+            Err(e) => {
+                let now = chrono::Utc::now().timestamp();
+                self.runs.lock().unwrap().insert(
+                    goal_id.to_string(),
+                    StagingRun {
+                        goal_id: goal_id.to_string(),
+                        status: StagingStatus::Failed,
+                        reason,
+                        container: String::new(),
+                        tools_address: String::new(),
+                        started_at: now,
+                        finished_at: now,
+                        calls: Vec::new(),
+                        error: format!("{e:#}"),
+                    },
+                );
+                Err(e)
+            }
+        }
+    }
+
+    /// Clone the host configuration and start a sandbox on it, returning
+    /// the container name and its tools service address
+    async fn provision(&self, clients: &ServiceClients, goal_id: &str) -> Result<(String, String)> {
+        let root = format!("{}/{goal_id}/root", self.dir);
+        let mut volumes = Vec::new();
+        for path in &self.config.clone_paths {
+            if !Path::new(path).exists() {
+                continue;
+            }
+            let clone = format!("{root}{path}");
+            copy_tree(Path::new(path), Path::new(&clone))
+                .with_context(|| format!("Failed to clone {path}"))?;
+            volumes.push(format!("{clone}:{path}"));
+        }
+
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .and_then(|l| l.local_addr())
+            .context("No free port for the sandbox")?
+            .port();
+        let container = format!("aios-staging-{}", &goal_id[..goal_id.len().min(12)]);
+        run_tool(
+            clients,
+            "container.create",
+            serde_json::json!({
+                "image": self.config.image,
+                "name": container,
+                "ports": [format!("127.0.0.1:{port}:{}", self.config.tools_port)],
+                "env": { "AIOS_STAGING": "1" },
+                "volumes": volumes,
+            }),
+        )
+        .await?;
+        run_tool(
+            clients,
+            "container.start",
+            serde_json::json!({ "name": container }),
+        )
+        .await?;
+
+        let address = format!("http://127.0.0.1:{port}");
+        if let Err(e) = wait_ready(&address).await {
+            self.teardown(clients, goal_id, &container).await;
+            return Err(e);
+        }
+        info!("Sandbox {container} for goal {goal_id} is up at {address}");
+        Ok((container, address))
+    }
+
+    /// Run one tool call in the sandbox of `goal_id` and record it
+    async fn execute(
+        &self,
+        goal_id: &str,
+        address: &str,
+        task_id: &str,
+        tool_name: &str,
+        input_json: &[u8],
+    ) -> Result<serde_json::Value> {
+        let outcome = self
+            .remote
+            .lock()
+            .await
+            .execute_remote_tool(address, tool_name, "autonomy-loop", task_id, input_json)
+            .await;
+        let (success, error, output) = match &outcome {
+            Ok((true, output, _)) => (true, String::new(), output.clone()),
+            Ok((false, _, error)) => (false, error.clone(), Vec::new()),
+            Err(e) => (false, format!("{e:#}"), Vec::new()),
+        };
+        if let Some(run) = self.runs.lock().unwrap().get_mut(goal_id) {
+            run.calls.push(RecordedCall {
+                task_id: task_id.to_string(),
+                tool_name: tool_name.to_string(),
+                input: serde_json::from_slice(input_json).unwrap_or_default(),
+                success,
+                error: error.clone(),
+                replayed: false,
+                replay_error: String::new(),
+            });
+        }
+        if !success {
+            anyhow::bail!("Tool '{tool_name}' failed in staging: {error}");
+        }
+        let output: serde_json::Value = serde_json::from_slice(&output).unwrap_or_else(|_| {
+            serde_json::Value::String(String::from_utf8_lossy(&output).to_string())
+        });
+        Ok(serde_json::json!({
+            "tool": tool_name,
+            "success": true,
+            "output": output,
+            "staged": true,
+        }))
+    }
+
+    /// Stop and delete a sandbox and its configuration clone. Best effort.
+    async fn teardown(&self, clients: &ServiceClients, goal_id: &str, container: &str) {
+        if !container.is_empty() {
+            let input = serde_json::json!({ "name": container, "remove": true });
+            if let Err(e) = run_tool(clients, "container.stop", input).await {
+                warn!("Failed to remove sandbox {container}: {e:#}");
+            }
+        }
+        let _ = std::fs::remove_dir_all(format!("{}/{goal_id}", self.dir));
+        self.remote.lock().await.close_all();
+    }
+
+    /// Settle the runs of staged goals that have finished: completed goals
+    /// are verified and offered for replay, failed ones are discarded
+    pub async fn reconcile(&self, state: &RwLock<OrchestratorState>) {
+        let running: Vec<StagingRun> = self
+            .runs()
+            .into_iter()
+            .filter(|r| r.status == StagingStatus::Running)
+            .collect();
+        if running.is_empty() {
+            return;
+        }
+        let (finished, clients) = {
+            let s = state.read().await;
+            let finished: Vec<(StagingRun, String)> = running
+                .into_iter()
+                .filter_map(|run| {
+                    let status = s.goal_engine.goal_status(&run.goal_id)?.to_string();
+                    matches!(status.as_str(), "completed" | "failed" | "cancelled")
+                        .then_some((run, status))
+                })
+                .collect();
+            (finished, s.clients.clone())
+        };
+
+        for (run, goal_status) in finished {
+            self.teardown(&clients, &run.goal_id, &run.container).await;
+            let verified = goal_status == "completed";
+            let calls = {
+                let mut runs = self.runs.lock().unwrap();
+                let Some(stored) = runs.get_mut(&run.goal_id) else {
+                    continue;
+                };
+                stored.status = if verified {
+                    StagingStatus::Verified
+                } else {
+                    StagingStatus::Failed
+                };
+                stored.finished_at = chrono::Utc::now().timestamp();
+                stored.verified_calls().count()
+            };
+
+            let mut s = crate::liveness::write_state(state, "staging.reconcile").await;
+            if verified {
+                s.goal_engine.add_message(
+                    &run.goal_id,
+                    "system",
+                    &format!(
+                        "The goal succeeded in the staging sandbox with {calls} tool calls. \
+                         Nothing has been changed on this system yet; replay the verified \
+                         calls here with POST /api/goals/{}/staging/replay.",
+                        run.goal_id
+                    ),
+                );
+                s.notifier.notify(
+                    "approval",
+                    crate::event_bus::EventSeverity::Warning,
+                    &format!("Goal {} verified in staging", run.goal_id),
+                    &format!("{calls} tool calls are ready to replay on the real system"),
+                    &run.goal_id,
+                );
+                info!("Goal {} verified in staging", run.goal_id);
+            } else {
+                s.goal_engine.add_message(
+                    &run.goal_id,
+                    "system",
+                    &format!(
+                        "The goal {goal_status} in the staging sandbox; nothing was changed on \
+                         this system."
+                    ),
+                );
+                info!(
+                    "Staged goal {} {goal_status}; sandbox discarded",
+                    run.goal_id
+                );
+            }
+        }
+    }
+
+    /// Replay the verified calls of `goal_id` on the host, in order,
+    /// stopping at the first failure
+    pub async fn replay(&self, clients: &ServiceClients, goal_id: &str) -> Result<StagingRun> {
+        let calls: Vec<(usize, RecordedCall)> = {
+            let mut runs = self.runs.lock().unwrap();
+            let run = runs
+                .get_mut(goal_id)
+                .with_context(|| format!("Goal {goal_id} was not staged"))?;
+            if run.status != StagingStatus::Verified {
+                anyhow::bail!(
+                    "Goal {goal_id} cannot be replayed while its staging run is {:?}",
+                    run.status
+                );
+            }
+            run.status = StagingStatus::Replaying;
+            run.calls
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| c.success)
+                .map(|(i, c)| (i, c.clone()))
+                .collect()
+        };
+
+        let mut failed = false;
+        for (index, call) in calls {
+            let outcome = replay_call(clients, goal_id, &call).await;
+            let mut runs = self.runs.lock().unwrap();
+            let Some(recorded) = runs.get_mut(goal_id).and_then(|r| r.calls.get_mut(index)) else {
+                break;
+            };
+            recorded.replayed = true;
+            if let Err(e) = outcome {
+                warn!(
+                    "Replay of '{}' for goal {goal_id} failed: {e:#}",
+                    call.tool_name
+                );
+                recorded.replay_error = format!("{e:#}");
+                failed = true;
+                break;
+            }
+        }
+
+        let mut runs = self.runs.lock().unwrap();
+        let run = runs
+            .get_mut(goal_id)
+            .with_context(|| format!("Goal {goal_id} was not staged"))?;
+        run.status = if failed {
+            StagingStatus::ReplayFailed
+        } else {
+            StagingStatus::Replayed
+        };
+        Ok(run.clone())
+    }
+}
+
+/// Staging decision for the tool calls of one task
+pub struct StagingContext {
+    staging: Arc<Staging>,
+    /// Goal whose staging run the calls belong to: the task's goal, or the
+    /// staged ancestor it inherits staging from
+    goal_id: String,
+    labeled: bool,
+}
+
+impl StagingContext {
+    /// Staging context of a task of `goal_id`
+    pub fn new(staging: Arc<Staging>, goals: &GoalEngine, goal_id: &str) -> Self {
+        let staged_ancestor = goals.ancestry(goal_id).into_iter().find(|id| {
+            goals.has_label(id, STAGING_LABEL)
+                || staging
+                    .run(id)
+                    .is_some_and(|r| r.status == StagingStatus::Running)
+        });
+        match staged_ancestor {
+            Some(id) => Self {
+                labeled: goals.has_label(&id, STAGING_LABEL),
+                goal_id: id,
+                staging,
+            },
+            None => Self {
+                staging,
+                goal_id: goal_id.to_string(),
+                labeled: false,
+            },
+        }
+    }
+
+    /// Sandbox address the calls go to, or None for the host
+    pub async fn route(
+        &self,
+        clients: &ServiceClients,
+        tool_names: &[&str],
+    ) -> Result<Option<String>> {
+        self.staging
+            .route(clients, &self.goal_id, self.labeled, tool_names)
+            .await
+    }
+
+    /// Run a tool call in the sandbox at `address`
+    pub async fn execute(
+        &self,
+        address: &str,
+        task_id: &str,
+        tool_name: &str,
+        input_json: &[u8],
+    ) -> Result<serde_json::Value> {
+        self.staging
+            .execute(&self.goal_id, address, task_id, tool_name, input_json)
+            .await
+    }
+}
+
+/// Run a host tool for the sandbox lifecycle
+async fn run_tool(
+    clients: &ServiceClients,
+    tool_name: &str,
+    input: serde_json::Value,
+) -> Result<serde_json::Value> {
+    let mut client = clients
+        .tools()
+        .await
+        .map_err(|e| anyhow::anyhow!("Cannot connect to tools service: {e}"))?;
+    let response = client
+        .execute(tonic::Request::new(crate::proto::tools::ExecuteRequest {
+            tool_name: tool_name.to_string(),
+            agent_id: "autonomy-loop".to_string(),
+            task_id: String::new(),
+            input_json: serde_json::to_vec(&input)?,
+            reason: "Staging sandbox".to_string(),
+            workload: crate::workload::current().to_string(),
+            resume_token: String::new(),
+            allow_experimental: false,
+        }))
+        .await
+        .map_err(|e| anyhow::anyhow!("Tool execution gRPC failed: {e}"))?
+        .into_inner();
+    crate::usage::record_tool(&response);
+    if !response.success {
+        anyhow::bail!("{tool_name} failed: {}", response.error);
+    }
+    Ok(serde_json::from_slice(&response.output_json).unwrap_or_default())
+}
+
+/// Repeat one verified call on the host
+async fn replay_call(clients: &ServiceClients, goal_id: &str, call: &RecordedCall) -> Result<()> {
+    let mut client = clients
+        .tools()
+        .await
+        .map_err(|e| anyhow::anyhow!("Cannot connect to tools service: {e}"))?;
+    let response = client
+        .execute(tonic::Request::new(crate::proto::tools::ExecuteRequest {
+            tool_name: call.tool_name.clone(),
+            agent_id: "autonomy-loop".to_string(),
+            task_id: call.task_id.clone(),
+            input_json: serde_json::to_vec(&call.input)?,
+            reason: format!("Replaying the verified staging run of goal {goal_id}"),
+            // Replays are started by the operator
+            workload: crate::workload::INTERACTIVE.to_string(),
+            resume_token: String::new(),
+            allow_experimental: false,
+        }))
+        .await
+        .map_err(|e| anyhow::anyhow!("Tool execution gRPC failed: {e}"))?
+        .into_inner();
+    if !response.success {
+        anyhow::bail!("{}", response.error);
+    }
+    Ok(())
+}
+
+/// Wait until the tools service at `address` accepts connections
+async fn wait_ready(address: &str) -> Result<()> {
+    let endpoint = tonic::transport::Endpoint::from_shared(address.to_string())?
+        .connect_timeout(Duration::from_secs(2));
+    let deadline = tokio::time::Instant::now() + SANDBOX_READY_TIMEOUT;
+    loop {
+        match endpoint.connect().await {
+            Ok(_) => return Ok(()),
+            Err(e) if tokio::time::Instant::now() >= deadline => {
+                anyhow::bail!("Sandbox at {address} did not come up: {e}")
+            }
+            Err(e) => debug!("Sandbox at {address} not ready yet: {e}"),
+        }
+        tokio::time::sleep(Duration::from_secs(2)).await;
+    }
+}
+
+/// Copy a file or directory tree; files that cannot be read are skipped
+fn copy_tree(from: &Path, to: &Path) -> Result<()> {
+    let metadata = std::fs::symlink_metadata(from)?;
+    if metadata.is_dir() {
+        std::fs::create_dir_all(to)?;
+        for entry in std::fs::read_dir(from)? {
+            let entry = entry?;
+            copy_tree(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    } else if metadata.is_file() {
+        if let Some(parent) = to.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        if let Err(e) = std::fs::copy(from, to) {
+            debug!("Not cloning {}: {e}", from.display());
+        }
+    }
+    Ok(())
+}
+
+/// Refresh tool risk levels and settle finished staged goals until
+/// `cancel` fires
+pub async fn run_staging(state: Arc<RwLock<OrchestratorState>>, cancel: CancellationToken) {
+    let (staging, clients) = {
+        let s = state.read().await;
+        (s.staging.clone(), s.clients.clone())
+    };
+    if !staging.config.enabled {
+        return;
+    }
+    let mut last_refresh: Option<tokio::time::Instant> = None;
+    loop {
+        if last_refresh.is_none_or(|at| at.elapsed() >= RISK_REFRESH_INTERVAL) {
+            if let Ok(mut client) = clients.tools().await {
+                let request = crate::proto::tools::ListToolsRequest::default();
+                match client.list_tools(request).await {
+                    Ok(response) => {
+                        staging.set_tool_risks(&response.into_inner().tools);
+                        last_refresh = Some(tokio::time::Instant::now());
+                    }
+                    Err(e) => debug!("Cannot refresh tool risk levels: {e}"),
+                }
+            }
+        }
+        staging.reconcile(&state).await;
+        tokio::select! {
+            _ = cancel.cancelled() => break,
+            _ = tokio::time::sleep(RECONCILE_INTERVAL) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn staging() -> Staging {
+        let staging = Staging::with_config(
+            StagingConfig {
+                enabled: true,
+                ..Default::default()
+            },
+            "/tmp/aios-staging-test",
+        );
+        staging.set_tool_risks(&[
+            crate::proto::tools::ToolDefinition {
+                name: "pkg.install".into(),
+                risk_level: "high".into(),
+                ..Default::default()
+            },
+            crate::proto::tools::ToolDefinition {
+                name: "fs.read".into(),
+                risk_level: "low".into(),
+                ..Default::default()
+            },
+        ]);
+        staging
+    }
+
+    #[test]
+    fn test_staging_reason() {
+        let staging = staging();
+        assert_eq!(staging.staging_reason(false, &["fs.read"]), None);
+        assert_eq!(
+            staging
+                .staging_reason(false, &["fs.read", "pkg.install"])
+                .as_deref(),
+            Some("pkg.install is a high risk tool")
+        );
+        assert!(staging.staging_reason(true, &["fs.read"]).is_some());
+        assert_eq!(
+            Staging::default().staging_reason(true, &["pkg.install"]),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_context_follows_staged_ancestor() {
+        let staging = Arc::new(staging());
+        let mut goals = GoalEngine::new();
+        let parent = goals
+            .submit_goal("Upgrade nginx".into(), 1, "test".into())
+            .await
+            .unwrap();
+        goals
+            .update_labels(&parent, &[STAGING_LABEL.into()], &[])
+            .unwrap();
+        let children = goals
+            .spawn_subgoals(
+                &parent,
+                "t1",
+                vec![crate::goal_engine::SubgoalSpec {
+                    description: "Install the new package".into(),
+                    priority: None,
+                }],
+            )
+            .await
+            .unwrap();
+
+        let context = StagingContext::new(staging.clone(), &goals, &children[0]);
+        assert_eq!(context.goal_id, parent);
+        assert!(context.labeled);
+
+        let other = goals
+            .submit_goal("Read the hostname".into(), 1, "test".into())
+            .await
+            .unwrap();
+        let context = StagingContext::new(staging, &goals, &other);
+        assert_eq!(context.goal_id, other);
+        assert!(!context.labeled);
+    }
+
+    #[tokio::test]
+    async fn test_replay_requires_verified_run() {
+        let staging = staging();
+        let clients = ServiceClients::new();
+        assert!(staging.replay(&clients, "missing").await.is_err());
+
+        staging.runs.lock().unwrap().insert(
+            "g1".into(),
+            StagingRun {
+                goal_id: "g1".into(),
+                status: StagingStatus::Running,
+                reason: String::new(),
+                container: String::new(),
+                tools_address: String::new(),
+                started_at: 0,
+                finished_at: 0,
+                calls: Vec::new(),
+                error: String::new(),
+            },
+        );
+        assert!(staging.replay(&clients, "g1").await.is_err());
+
+        staging.runs.lock().unwrap().get_mut("g1").unwrap().status = StagingStatus::Verified;
+        let run = staging.replay(&clients, "g1").await.unwrap();
+        assert_eq!(run.status, StagingStatus::Replayed);
+    }
+}
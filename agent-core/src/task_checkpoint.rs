@@ -0,0 +1,328 @@
+//! Task checkpoints — long-running tasks resume where they stopped
+//!
+//! Tasks that span hours (large downloads, builds, migrations) keep a
+//! checkpoint in working memory: the tool calls that completed, with their
+//! outputs, and the progress token of each call that stopped before
+//! finishing. The checkpoint is written after every tool call, so it
+//! survives a restart of the orchestrator or the tools service whether or
+//! not the shutdown was clean.
+//!
+//! When a task runs again after a restart, a call identical to one that
+//! completed before the restart is not repeated: the recorded output is
+//! returned, marked `resumed`. A call that stopped midway is sent again
+//! with its progress token as the resume token, so resumable tools
+//! (web.download keeps its partial file) continue instead of starting
+//! over. Calls completed in the current run are always executed again — a
+//! task may read the same thing twice on purpose.
+//!
+//! The shutdown drain's checkpoints ([`crate::shutdown`]) resume the
+//! reasoning conversation; these resume the work the conversation did. A
+//! checkpoint is dropped once its task finishes with every call succeeded.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tracing::{debug, info};
+
+use crate::clients::ServiceClients;
+use crate::proto::tools::ExecuteResponse;
+
+/// A tool call that completed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CompletedCall {
+    key: String,
+    output: serde_json::Value,
+    /// Orchestrator run the call completed in
+    run: String,
+}
+
+/// Saved progress of one task
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Checkpoint {
+    completed: Vec<CompletedCall>,
+    /// Progress tokens of calls that stopped midway, by call
+    progress: HashMap<String, String>,
+}
+
+/// How a tool call picks up from the task's checkpoint
+#[derive(Debug, PartialEq)]
+pub enum Resume {
+    /// Completed before the restart; this was its output
+    Completed(serde_json::Value),
+    /// Stopped midway; continue from this progress token
+    Continue(String),
+    /// Nothing to resume
+    Fresh,
+}
+
+/// Checkpoints of the tasks run since the orchestrator started, loaded
+/// from working memory on first use
+pub struct TaskCheckpoints {
+    run: String,
+    tasks: Mutex<HashMap<String, Checkpoint>>,
+}
+
+impl Default for TaskCheckpoints {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TaskCheckpoints {
+    pub fn new() -> Self {
+        Self {
+            run: uuid::Uuid::new_v4().to_string(),
+            tasks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// How a call of `tool_name` with `input_json` picks up for `task_id`
+    pub async fn resume(
+        &self,
+        clients: &ServiceClients,
+        task_id: &str,
+        tool_name: &str,
+        input_json: &[u8],
+    ) -> Resume {
+        if task_id.is_empty() {
+            return Resume::Fresh;
+        }
+        self.load(clients, task_id).await;
+        let key = call_key(tool_name, input_json);
+        let Ok(mut tasks) = self.tasks.lock() else {
+            return Resume::Fresh;
+        };
+        let Some(checkpoint) = tasks.get_mut(task_id) else {
+            return Resume::Fresh;
+        };
+        // Each call from before the restart stands in for one call after it
+        if let Some(call) = checkpoint
+            .completed
+            .iter_mut()
+            .find(|c| c.key == key && c.run != self.run)
+        {
+            call.run = self.run.clone();
+            return Resume::Completed(call.output.clone());
+        }
+        match checkpoint.progress.get(&key) {
+            Some(token) => Resume::Continue(token.clone()),
+            None => Resume::Fresh,
+        }
+    }
+
+    /// Record the outcome of a call, saving the checkpoint when it changed
+    pub async fn record(
+        &self,
+        clients: &ServiceClients,
+        task_id: &str,
+        tool_name: &str,
+        input_json: &[u8],
+        resp: &ExecuteResponse,
+    ) {
+        if task_id.is_empty() {
+            return;
+        }
+        let state_json = {
+            let Ok(mut tasks) = self.tasks.lock() else {
+                return;
+            };
+            let checkpoint = tasks.entry(task_id.to_string()).or_default();
+            if !checkpoint.apply(tool_name, input_json, resp, &self.run) {
+                return;
+            }
+            serde_json::to_vec(checkpoint).unwrap_or_default()
+        };
+        let checkpoint = crate::proto::memory::TaskCheckpoint {
+            task_id: task_id.to_string(),
+            state_json,
+            updated_at: chrono::Utc::now().timestamp(),
+        };
+        match clients.memory().await {
+            Ok(mut client) => {
+                if let Err(e) = client.store_task_checkpoint(checkpoint).await {
+                    debug!("Failed to store checkpoint of task {task_id}: {e}");
+                }
+            }
+            Err(e) => debug!("Memory service unavailable for task checkpoint: {e}"),
+        }
+    }
+
+    /// Drop the checkpoint of a finished task
+    pub async fn finish(&self, clients: &ServiceClients, task_id: &str) {
+        let had_checkpoint = self
+            .tasks
+            .lock()
+            .map(|mut tasks| tasks.remove(task_id).is_some())
+            .unwrap_or(false);
+        if !had_checkpoint {
+            return;
+        }
+        let request = crate::proto::memory::TaskCheckpointRequest {
+            task_id: task_id.to_string(),
+        };
+        match clients.memory().await {
+            Ok(mut client) => {
+                if let Err(e) = client.delete_task_checkpoint(request).await {
+                    debug!("Failed to delete checkpoint of task {task_id}: {e}");
+                }
+            }
+            Err(e) => debug!("Memory service unavailable for task checkpoint: {e}"),
+        }
+    }
+
+    /// Fetch the task's checkpoint from working memory the first time the
+    /// task is seen in this run
+    async fn load(&self, clients: &ServiceClients, task_id: &str) {
+        if self
+            .tasks
+            .lock()
+            .map_or(true, |tasks| tasks.contains_key(task_id))
+        {
+            return;
+        }
+        let request = crate::proto::memory::TaskCheckpointRequest {
+            task_id: task_id.to_string(),
+        };
+        let stored = match clients.memory().await {
+            Ok(mut client) => match client.get_task_checkpoint(request).await {
+                Ok(response) => Some(response.into_inner()),
+                Err(e) => {
+                    debug!("Failed to load checkpoint of task {task_id}: {e}");
+                    None
+                }
+            },
+            Err(e) => {
+                debug!("Memory service unavailable for task checkpoint: {e}");
+                None
+            }
+        };
+        let checkpoint = stored
+            .filter(|c| !c.task_id.is_empty())
+            .and_then(|c| serde_json::from_slice::<Checkpoint>(&c.state_json).ok())
+            .unwrap_or_default();
+        if !checkpoint.completed.is_empty() || !checkpoint.progress.is_empty() {
+            info!(
+                "Resuming task {task_id} from its checkpoint: {} completed calls, {} in progress",
+                checkpoint.completed.len(),
+                checkpoint.progress.len()
+            );
+        }
+        if let Ok(mut tasks) = self.tasks.lock() {
+            tasks.entry(task_id.to_string()).or_insert(checkpoint);
+        }
+    }
+}
+
+impl Checkpoint {
+    /// Fold a call's outcome in; false when nothing changed
+    fn apply(
+        &mut self,
+        tool_name: &str,
+        input_json: &[u8],
+        resp: &ExecuteResponse,
+        run: &str,
+    ) -> bool {
+        let key = call_key(tool_name, input_json);
+        if resp.success {
+            self.progress.remove(&key);
+            let output = serde_json::from_slice(&resp.output_json).unwrap_or_else(|_| {
+                serde_json::Value::String(String::from_utf8_lossy(&resp.output_json).to_string())
+            });
+            self.completed.push(CompletedCall {
+                key,
+                output,
+                run: run.to_string(),
+            });
+            true
+        } else if !resp.progress_token.is_empty() {
+            self.progress.insert(key, resp.progress_token.clone());
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Identity of a call: the tool and its input with keys in a stable order
+pub fn call_key(tool_name: &str, input_json: &[u8]) -> String {
+    let input = serde_json::from_slice::<serde_json::Value>(input_json)
+        .map(|v| v.to_string())
+        .unwrap_or_else(|_| String::from_utf8_lossy(input_json).to_string());
+    format!("{tool_name}:{input}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(success: bool, output: &str, progress_token: &str) -> ExecuteResponse {
+        ExecuteResponse {
+            success,
+            output_json: output.as_bytes().to_vec(),
+            progress_token: progress_token.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resume_after_restart() {
+        let input = br#"{"url":"https://example.com/big.iso","destination":"/tmp/big.iso"}"#;
+        let mut checkpoint = Checkpoint::default();
+        assert!(checkpoint.apply(
+            "fs.mkdir",
+            br#"{"path":"/tmp"}"#,
+            &response(true, r#"{"created":true}"#, ""),
+            "run-1"
+        ));
+        assert!(checkpoint.apply(
+            "web.download",
+            input,
+            &response(false, "", "bytes=1048576"),
+            "run-1"
+        ));
+        // A failure without progress leaves nothing to resume
+        assert!(!checkpoint.apply("fs.read", b"{}", &response(false, "", ""), "run-1"));
+
+        // The restarted orchestrator loads the stored checkpoint
+        let restarted = TaskCheckpoints::new();
+        let stored = serde_json::to_vec(&checkpoint).unwrap();
+        restarted
+            .tasks
+            .lock()
+            .unwrap()
+            .insert("task-1".into(), serde_json::from_slice(&stored).unwrap());
+        let clients = ServiceClients::new();
+        // Keys compare regardless of the input's formatting
+        assert_eq!(
+            restarted
+                .resume(&clients, "task-1", "fs.mkdir", br#"{ "path": "/tmp" }"#)
+                .await,
+            Resume::Completed(serde_json::json!({"created": true}))
+        );
+        // ...once: a second identical call runs again
+        assert_eq!(
+            restarted
+                .resume(&clients, "task-1", "fs.mkdir", br#"{"path":"/tmp"}"#)
+                .await,
+            Resume::Fresh
+        );
+        assert_eq!(
+            restarted
+                .resume(&clients, "task-1", "web.download", input)
+                .await,
+            Resume::Continue("bytes=1048576".into())
+        );
+
+        // Completing the download clears its progress token
+        let mut tasks = restarted.tasks.lock().unwrap();
+        let checkpoint = tasks.get_mut("task-1").unwrap();
+        checkpoint.apply(
+            "web.download",
+            input,
+            &response(true, "{}", ""),
+            &restarted.run,
+        );
+        assert!(checkpoint.progress.is_empty());
+        assert_eq!(checkpoint.completed.len(), 2);
+    }
+}
@@ -0,0 +1,180 @@
+//! Task event stream — lifecycle events pushed to gRPC subscribers
+//!
+//! Every status change the goal engine records is also published here, as
+//! are tool calls the autonomy loop runs and dispatches to agents, so
+//! `StreamTaskEvents` clients see a task move through queued, in_progress,
+//! tool_call and completed or failed without polling `GetGoalStatus`.
+//!
+//! Publishing never blocks: subscribers that fall more than the channel's
+//! capacity behind lose the oldest events and are told how many.
+
+use tokio::sync::broadcast;
+
+use crate::goal_engine::StateTransition;
+use crate::proto::orchestrator::TaskEvent;
+
+/// Events buffered per subscriber before the oldest are dropped
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// Goal statuses after which a goal's stream ends
+pub const TERMINAL_GOAL_STATUSES: &[&str] = &["completed", "failed", "cancelled"];
+
+/// Publisher of task lifecycle events; clones share subscribers
+#[derive(Clone)]
+pub struct TaskEvents {
+    sender: broadcast::Sender<TaskEvent>,
+}
+
+impl Default for TaskEvents {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TaskEvents {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self { sender }
+    }
+
+    /// Receive every event published from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<TaskEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Publish a recorded status change
+    pub fn transition(&self, transition: &StateTransition) {
+        self.publish(from_transition(transition.clone()));
+    }
+
+    /// Publish the outcome of a tool call made for a task
+    pub fn tool_call(
+        &self,
+        goal_id: &str,
+        task_id: &str,
+        tool_name: &str,
+        outcome: &serde_json::Value,
+    ) {
+        let success = outcome.get("success").and_then(|v| v.as_bool()) == Some(true);
+        self.publish(TaskEvent {
+            goal_id: goal_id.to_string(),
+            task_id: task_id.to_string(),
+            kind: "tool_call".into(),
+            entity_type: "task".into(),
+            tool_name: tool_name.to_string(),
+            success,
+            error: outcome
+                .get("error")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+            actor: "autonomy".into(),
+            timestamp: chrono::Utc::now().timestamp(),
+            ..Default::default()
+        });
+    }
+
+    /// Publish the hand-off of a task to an agent
+    pub fn dispatched(&self, goal_id: &str, task_id: &str, agent_id: &str) {
+        self.publish(TaskEvent {
+            goal_id: goal_id.to_string(),
+            task_id: task_id.to_string(),
+            kind: "dispatched".into(),
+            entity_type: "task".into(),
+            cause: format!("routed to agent {agent_id}"),
+            actor: agent_id.to_string(),
+            timestamp: chrono::Utc::now().timestamp(),
+            ..Default::default()
+        });
+    }
+
+    /// Having no subscribers is not an error
+    fn publish(&self, event: TaskEvent) {
+        let _ = self.sender.send(event);
+    }
+}
+
+/// Event for a recorded status change: a task that is (re)queued reads as
+/// `queued`, any other change as the status reached
+pub fn from_transition(t: StateTransition) -> TaskEvent {
+    let kind = match (t.entity_type.as_str(), t.to_status.as_str()) {
+        ("task", "pending") => "queued".to_string(),
+        _ => t.to_status.clone(),
+    };
+    TaskEvent {
+        task_id: if t.entity_type == "task" {
+            t.entity_id
+        } else {
+            String::new()
+        },
+        goal_id: t.goal_id,
+        kind,
+        entity_type: t.entity_type,
+        from_status: t.from_status,
+        to_status: t.to_status,
+        cause: t.cause,
+        actor: t.actor,
+        timestamp: t.timestamp,
+        ..Default::default()
+    }
+}
+
+/// Whether the event ends its goal's stream
+pub fn ends_goal(event: &TaskEvent) -> bool {
+    event.entity_type == "goal" && TERMINAL_GOAL_STATUSES.contains(&event.to_status.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::goal_engine::GoalEngine;
+
+    #[tokio::test]
+    async fn test_goal_engine_publishes_lifecycle() {
+        let mut engine = GoalEngine::new();
+        let mut events = engine.task_events().subscribe();
+        let goal_id = engine
+            .submit_goal("Rotate logs".into(), 5, "test".into())
+            .await
+            .unwrap();
+        let task = crate::proto::common::Task {
+            id: "task-1".into(),
+            goal_id: goal_id.clone(),
+            status: "pending".into(),
+            ..Default::default()
+        };
+        engine.add_tasks(&goal_id, vec![task]);
+        engine.update_task_status(&goal_id, "task-1", "in_progress", "picked", "autonomy");
+        engine.task_events().tool_call(
+            &goal_id,
+            "task-1",
+            "fs.delete",
+            &serde_json::json!({"success": false, "error": "permission denied"}),
+        );
+        engine.complete_task(&goal_id, "task-1", "done", "autonomy");
+        engine.update_status(&goal_id, "completed", "all tasks done", "autonomy");
+
+        let mut kinds = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            if event.kind == "tool_call" {
+                assert!(!event.success);
+                assert_eq!(event.error, "permission denied");
+            }
+            kinds.push(format!("{}:{}", event.entity_type, event.kind));
+            if ends_goal(&event) {
+                break;
+            }
+        }
+        assert_eq!(
+            kinds,
+            vec![
+                "goal:pending",
+                "task:queued",
+                "task:in_progress",
+                "task:tool_call",
+                "task:completed",
+                "goal:completed",
+            ]
+        );
+    }
+}
@@ -2,12 +2,19 @@
 //!
 //! Uses local AI models to break down goals into a DAG of tasks,
 //! determines intelligence levels, and identifies required tools.
+//!
+//! A failed task is retried when its [`TaskRetryPolicy`] covers the kind
+//! of failure and attempts remain: it waits as `retrying` for a backoff
+//! with decorrelated jitter, then goes back to `pending`.
 
 use anyhow::Result;
+use serde::Deserialize;
 use std::collections::HashMap;
+use std::time::Duration;
 use uuid::Uuid;
 
 use crate::proto::common::Task;
+use crate::proto::orchestrator::TaskDependency;
 
 /// Intelligence levels for task routing
 #[derive(Debug, Clone, PartialEq)]
@@ -43,10 +50,140 @@ impl IntelligenceLevel {
     }
 }
 
+/// Tool namespaces routine enough for a fast model at tactical level
+const ROUTINE_NAMESPACES: &[&str] = &["monitor", "fs", "process", "service", "net", "hw"];
+
+/// Why a task failed, as far as retrying it goes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    /// Backends unreachable, a timeout or rate limiting; likely to pass
+    Transient,
+    /// A tool call failed outright
+    ToolError,
+    /// The tools ran but verification found the objective unmet
+    Unverified,
+    /// An agent reported the task failed
+    Agent,
+    /// Retrying cannot help: a denied call, unusable model output
+    Permanent,
+}
+
+impl FailureKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Transient => "transient",
+            Self::ToolError => "tool_error",
+            Self::Unverified => "unverified",
+            Self::Agent => "agent",
+            Self::Permanent => "permanent",
+        }
+    }
+}
+
+/// Failure kind of a task whose tool calls failed with the tools service's
+/// failure `classes`: permanent if any was denied, transient if all timed
+/// out, were rate limited or found no staging sandbox
+pub fn classify_tool_failures<'a>(classes: impl IntoIterator<Item = &'a str>) -> FailureKind {
+    let classes: Vec<&str> = classes.into_iter().collect();
+    if classes.contains(&"denied") {
+        FailureKind::Permanent
+    } else if !classes.is_empty()
+        && classes
+            .iter()
+            .all(|c| matches!(*c, "timeout" | "rate_limited" | "staging"))
+    {
+        FailureKind::Transient
+    } else {
+        FailureKind::ToolError
+    }
+}
+
+/// How often a failed task is tried again. Tasks take the default unless
+/// their input carries a `retry` object with any of these fields.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TaskRetryPolicy {
+    /// Attempts in all, the first included; 1 never retries
+    pub max_attempts: u32,
+    /// Shortest wait before a retry
+    pub backoff_ms: u64,
+    /// Longest wait before a retry
+    pub max_backoff_ms: u64,
+    /// Failure kinds worth retrying (see [`FailureKind::as_str`])
+    pub retry_on: Vec<String>,
+}
+
+impl Default for TaskRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff_ms: 2_000,
+            max_backoff_ms: 300_000,
+            retry_on: vec!["transient".into()],
+        }
+    }
+}
+
+impl TaskRetryPolicy {
+    /// Policy of `task`: the `retry` object of its input, else the default
+    pub fn for_task(task: &Task) -> Self {
+        let retry = serde_json::from_slice::<serde_json::Value>(&task.input_json)
+            .ok()
+            .and_then(|input| input.get("retry").cloned());
+        match retry.map(serde_json::from_value::<Self>) {
+            Some(Ok(policy)) => policy,
+            Some(Err(e)) => {
+                tracing::warn!("Task {} has an invalid retry policy: {e}", task.id);
+                Self::default()
+            }
+            None => Self::default(),
+        }
+    }
+
+    pub fn retries(&self, kind: FailureKind) -> bool {
+        self.retry_on.iter().any(|k| k == kind.as_str())
+    }
+
+    /// Wait before the next retry, `previous_ms` having been the last one
+    /// (0 before the first): decorrelated jitter, a random time between the
+    /// base backoff and three times the previous wait, capped
+    pub fn next_delay(&self, previous_ms: u64) -> Duration {
+        let cap = self.max_backoff_ms.max(self.backoff_ms);
+        let upper = previous_ms.max(self.backoff_ms).saturating_mul(3).min(cap);
+        let spread = (upper - self.backoff_ms.min(upper)) as f64;
+        Duration::from_millis(
+            self.backoff_ms.min(upper) + (spread * crate::resilience::random_fraction()) as u64,
+        )
+    }
+}
+
+/// What became of a failed task
+#[derive(Debug, Clone, PartialEq)]
+pub enum FailureOutcome {
+    /// Waiting `delay` before attempt `attempt` of `max_attempts`
+    Retry {
+        attempt: u32,
+        max_attempts: u32,
+        delay: Duration,
+    },
+    /// Failed for good after `attempts` attempts
+    Failed { attempts: u32 },
+}
+
+/// Retry bookkeeping of a task that has failed at least once
+struct RetryState {
+    /// Attempts failed so far
+    failures: u32,
+    last_delay_ms: u64,
+    /// Unix time in milliseconds the task goes back to pending
+    retry_at: i64,
+}
+
 /// Task planner state
 pub struct TaskPlanner {
     pending_tasks: HashMap<String, Task>,
     _task_dependencies: HashMap<String, Vec<String>>,
+    retries: HashMap<String, RetryState>,
     /// Optional gRPC service clients for AI-powered decomposition.
     /// When present, tactical/strategic goals are decomposed using AI
     /// instead of keyword heuristics.
@@ -58,6 +195,7 @@ impl TaskPlanner {
         Self {
             pending_tasks: HashMap::new(),
             _task_dependencies: HashMap::new(),
+            retries: HashMap::new(),
             clients: None,
         }
     }
@@ -67,6 +205,7 @@ impl TaskPlanner {
         Self {
             pending_tasks: HashMap::new(),
             _task_dependencies: HashMap::new(),
+            retries: HashMap::new(),
             clients: Some(clients),
         }
     }
@@ -84,6 +223,13 @@ impl TaskPlanner {
         }
     }
 
+    /// Register tasks planned without `decompose_goal`
+    pub fn add_tasks(&mut self, tasks: &[Task]) {
+        for task in tasks {
+            self.pending_tasks.insert(task.id.clone(), task.clone());
+        }
+    }
+
     /// Decompose a goal into tasks
     ///
     /// For simple goals, uses heuristic decomposition.
@@ -91,7 +237,7 @@ impl TaskPlanner {
     pub async fn decompose_goal(&mut self, goal_id: &str, description: &str) -> Result<Vec<Task>> {
         let level = self.classify_complexity(description);
 
-        let tasks = match level {
+        let mut tasks = match level {
             IntelligenceLevel::Reactive => self.heuristic_decompose(goal_id, description).await?,
             IntelligenceLevel::Operational => {
                 self.single_task_decompose(goal_id, description, &level)
@@ -103,6 +249,10 @@ impl TaskPlanner {
             }
         };
 
+        for task in &mut tasks {
+            self.annotate_model_hints(task);
+        }
+
         // Register tasks
         for task in &tasks {
             self.pending_tasks.insert(task.id.clone(), task.clone());
@@ -152,8 +302,12 @@ impl TaskPlanner {
              Goal: {description}\n\n\
              Available tool namespaces: fs, process, service, net, firewall, pkg, sec, monitor, \
              web, git, code, plugin, container, email\n\n\
+             For each step, \"model\" is \"fast\" if a small cheap model can do it (routine \
+             checks, listings, single commands) or \"strong\" if it needs careful reasoning. \
+             \"after\" lists the numbers (from 1) of earlier steps that must finish first; \
+             use [] for steps that can start right away, so independent steps run in parallel.\n\n\
              Respond with ONLY a JSON array:\n\
-             [{{\"description\": \"step description\", \"tools\": [\"namespace\"]}}]"
+             [{{\"description\": \"step description\", \"tools\": [\"namespace\"], \"model\": \"fast\", \"after\": []}}]"
         );
 
         let system_prompt = "You are aiOS task planner. Decompose goals into executable steps. \
@@ -162,19 +316,31 @@ impl TaskPlanner {
         // Try API gateway first
         let result = match clients.api_gateway().await {
             Ok(mut client) => {
-                let request =
-                    tonic::Request::new(crate::proto::api_gateway::ApiInferRequest {
-                        prompt: prompt.clone(),
-                        system_prompt: system_prompt.to_string(),
-                        max_tokens: 1024,
-                        temperature: 0.3,
-                        preferred_provider: String::new(),
-                        requesting_agent: "task-planner".to_string(),
-                        task_id: String::new(),
-                        allow_fallback: true,
-                    });
+                let request = tonic::Request::new(crate::proto::api_gateway::ApiInferRequest {
+                    prompt: prompt.clone(),
+                    system_prompt: system_prompt.to_string(),
+                    max_tokens: 1024,
+                    temperature: 0.3,
+                    preferred_provider: String::new(),
+                    requesting_agent: "task-planner".to_string(),
+                    task_id: String::new(),
+                    allow_fallback: true,
+                    response_schema: String::new(),
+                    sections: Vec::new(),
+                    session_id: String::new(),
+                    turn_kind: String::new(),
+                    model_class: String::new(),
+                    workload: crate::workload::current().to_string(),
+                    goal_id: crate::usage::current_goal_id(),
+                    goal_budget: crate::usage::current_budget(),
+                    bypass_cache: false,
+                });
                 match client.infer(request).await {
-                    Ok(resp) => Some(resp.into_inner().text),
+                    Ok(resp) => {
+                        let resp = resp.into_inner();
+                        crate::usage::record_inference(resp.tokens_used.into(), resp.cost_usd);
+                        Some(resp.text)
+                    }
                     Err(e) => {
                         tracing::debug!("API gateway decomposition failed: {e}");
                         None
@@ -192,19 +358,23 @@ impl TaskPlanner {
             Some(text) => Some(text),
             None => match clients.runtime().await {
                 Ok(mut client) => {
-                    let request =
-                        tonic::Request::new(crate::proto::runtime::InferRequest {
-                            model: String::new(),
-                            prompt,
-                            system_prompt: system_prompt.to_string(),
-                            max_tokens: 1024,
-                            temperature: 0.3,
-                            intelligence_level: level.as_str().to_string(),
-                            requesting_agent: "task-planner".to_string(),
-                            task_id: String::new(),
-                        });
+                    let request = tonic::Request::new(crate::proto::runtime::InferRequest {
+                        model: String::new(),
+                        prompt,
+                        system_prompt: system_prompt.to_string(),
+                        max_tokens: 1024,
+                        temperature: 0.3,
+                        intelligence_level: level.as_str().to_string(),
+                        requesting_agent: "task-planner".to_string(),
+                        task_id: String::new(),
+                        workload: crate::workload::current().to_string(),
+                    });
                     match client.infer(request).await {
-                        Ok(resp) => Some(resp.into_inner().text),
+                        Ok(resp) => {
+                            let resp = resp.into_inner();
+                            crate::usage::record_inference(resp.tokens_used.into(), 0.0);
+                            Some(resp.text)
+                        }
                         Err(e) => {
                             tracing::debug!("Runtime decomposition failed: {e}");
                             None
@@ -289,6 +459,8 @@ impl TaskPlanner {
 
         let mut tasks = Vec::new();
         let mut prev_task_id: Option<String> = None;
+        // Task id of each step, by step number - 1; None for skipped steps
+        let mut step_ids: Vec<Option<String>> = Vec::new();
 
         for (i, step) in steps.iter().enumerate() {
             let desc = step
@@ -297,6 +469,7 @@ impl TaskPlanner {
                 .unwrap_or("")
                 .to_string();
             if desc.is_empty() {
+                step_ids.push(None);
                 continue;
             }
 
@@ -310,11 +483,26 @@ impl TaskPlanner {
                 })
                 .unwrap_or_default();
 
+            let model_class = match step.get("model").and_then(|v| v.as_str()) {
+                Some(class @ ("fast" | "strong")) => class.to_string(),
+                _ => String::new(),
+            };
+
             let task_id = Uuid::new_v4().to_string();
-            let depends_on = if let Some(ref prev) = prev_task_id {
-                vec![prev.clone()]
-            } else {
-                vec![]
+            // Steps only name earlier steps, so the plan cannot have cycles;
+            // without "after" a step follows the one before it
+            let depends_on = match step.get("after").and_then(|v| v.as_array()) {
+                Some(after) => {
+                    let mut deps: Vec<String> = after
+                        .iter()
+                        .filter_map(|v| v.as_u64())
+                        .filter(|&n| n >= 1 && (n as usize) <= i)
+                        .filter_map(|n| step_ids[n as usize - 1].clone())
+                        .collect();
+                    deps.dedup();
+                    deps
+                }
+                None => prev_task_id.iter().cloned().collect(),
             };
 
             tasks.push(Task {
@@ -336,8 +524,11 @@ impl TaskPlanner {
                 started_at: 0,
                 completed_at: 0,
                 error: String::new(),
+                model_class,
+                provider_hint: String::new(),
             });
 
+            step_ids.push(Some(task_id.clone()));
             prev_task_id = Some(task_id);
         }
 
@@ -393,6 +584,8 @@ impl TaskPlanner {
                 started_at: 0,
                 completed_at: 0,
                 error: String::new(),
+                model_class: String::new(),
+                provider_hint: String::new(),
             });
 
             if let Some(prev) = &prev_task_id {
@@ -564,6 +757,8 @@ impl TaskPlanner {
             started_at: 0,
             completed_at: 0,
             error: String::new(),
+            model_class: String::new(),
+            provider_hint: String::new(),
         };
         Ok(vec![task])
     }
@@ -593,10 +788,60 @@ impl TaskPlanner {
             started_at: 0,
             completed_at: 0,
             error: String::new(),
+            model_class: String::new(),
+            provider_hint: String::new(),
         };
         Ok(vec![task])
     }
 
+    /// Set a task's model class and provider hint. Reactive and operational
+    /// tasks, and tactical ones using only routine tools, get the fast class;
+    /// the rest get strong. A class chosen by the AI planner is kept. Fast is
+    /// upgraded to strong when earlier fast tasks using the same tools mostly
+    /// failed. Providers come from `AIOS_FAST_PROVIDER` / `AIOS_STRONG_PROVIDER`
+    /// when set.
+    fn annotate_model_hints(&self, task: &mut Task) {
+        if task.model_class.is_empty() {
+            let routine = !task.required_tools.is_empty()
+                && task
+                    .required_tools
+                    .iter()
+                    .all(|t| ROUTINE_NAMESPACES.contains(&t.split('.').next().unwrap_or_default()));
+            task.model_class = match IntelligenceLevel::from_str(&task.intelligence_level) {
+                IntelligenceLevel::Reactive | IntelligenceLevel::Operational => "fast",
+                IntelligenceLevel::Tactical if routine => "fast",
+                _ => "strong",
+            }
+            .to_string();
+        }
+        if task.model_class == "fast" && self.fast_model_struggles(&task.required_tools) {
+            task.model_class = "strong".to_string();
+        }
+
+        let hint_var = if task.model_class == "fast" {
+            "AIOS_FAST_PROVIDER"
+        } else {
+            "AIOS_STRONG_PROVIDER"
+        };
+        task.provider_hint = std::env::var(hint_var).unwrap_or_default();
+    }
+
+    /// Whether finished fast-class tasks sharing any of `tools` failed at
+    /// least twice and more often than they succeeded
+    fn fast_model_struggles(&self, tools: &[String]) -> bool {
+        let (mut failed, mut completed) = (0, 0);
+        for task in self.pending_tasks.values().filter(|t| {
+            t.model_class == "fast" && t.required_tools.iter().any(|tool| tools.contains(tool))
+        }) {
+            match task.status.as_str() {
+                "failed" => failed += 1,
+                "completed" => completed += 1,
+                _ => {}
+            }
+        }
+        failed >= 2 && failed > completed
+    }
+
     /// Infer which tools a task might need based on description keywords
     fn infer_required_tools(&self, description: &str) -> Vec<String> {
         let desc_lower = description.to_lowercase();
@@ -685,6 +930,7 @@ impl TaskPlanner {
 
     /// Mark a task as completed
     pub fn complete_task(&mut self, task_id: &str, output: Vec<u8>) {
+        self.retries.remove(task_id);
         if let Some(task) = self.pending_tasks.get_mut(task_id) {
             task.status = "completed".to_string();
             task.output_json = output;
@@ -714,6 +960,29 @@ impl TaskPlanner {
         }
     }
 
+    /// Mark a task as preempted: stopped so a more urgent goal can run, and
+    /// not dispatched again until restored
+    pub fn preempt_task(&mut self, task_id: &str) {
+        if let Some(task) = self.pending_tasks.get_mut(task_id) {
+            task.status = "preempted".to_string();
+        }
+    }
+
+    /// Re-queue every preempted task as pending; their IDs
+    pub fn restore_preempted(&mut self) -> Vec<String> {
+        let mut restored: Vec<String> = self
+            .pending_tasks
+            .values_mut()
+            .filter(|t| t.status == "preempted")
+            .map(|t| {
+                t.status = "pending".to_string();
+                t.id.clone()
+            })
+            .collect();
+        restored.sort();
+        restored
+    }
+
     /// Mark a task as failed
     pub fn fail_task(&mut self, task_id: &str, error: &str) {
         if let Some(task) = self.pending_tasks.get_mut(task_id) {
@@ -723,6 +992,68 @@ impl TaskPlanner {
         }
     }
 
+    /// Record a failed attempt of a task. It waits as `retrying` if its
+    /// retry policy covers `kind` and attempts remain, and fails otherwise.
+    pub fn fail_or_retry(
+        &mut self,
+        task_id: &str,
+        error: &str,
+        kind: FailureKind,
+        now_ms: i64,
+    ) -> FailureOutcome {
+        let Some(task) = self.pending_tasks.get_mut(task_id) else {
+            return FailureOutcome::Failed { attempts: 1 };
+        };
+        let policy = TaskRetryPolicy::for_task(task);
+        let retry = self
+            .retries
+            .entry(task_id.to_string())
+            .or_insert(RetryState {
+                failures: 0,
+                last_delay_ms: 0,
+                retry_at: 0,
+            });
+        retry.failures += 1;
+
+        if !policy.retries(kind) || retry.failures >= policy.max_attempts {
+            let attempts = retry.failures;
+            self.retries.remove(task_id);
+            self.fail_task(task_id, error);
+            return FailureOutcome::Failed { attempts };
+        }
+
+        let delay = policy.next_delay(retry.last_delay_ms);
+        retry.last_delay_ms = delay.as_millis() as u64;
+        retry.retry_at = now_ms + delay.as_millis() as i64;
+        task.status = "retrying".to_string();
+        task.error = error.to_string();
+        FailureOutcome::Retry {
+            attempt: retry.failures + 1,
+            max_attempts: policy.max_attempts,
+            delay,
+        }
+    }
+
+    /// Re-queue as pending the retrying tasks whose backoff has passed by
+    /// `now_ms`; their IDs with the attempt each starts
+    pub fn due_retries(&mut self, now_ms: i64) -> Vec<(String, u32)> {
+        let mut due: Vec<(String, u32)> = self
+            .pending_tasks
+            .values_mut()
+            .filter(|t| t.status == "retrying")
+            .filter_map(|t| {
+                let retry = self.retries.get(&t.id);
+                if retry.is_some_and(|r| r.retry_at > now_ms) {
+                    return None;
+                }
+                t.status = "pending".to_string();
+                Some((t.id.clone(), retry.map_or(1, |r| r.failures + 1)))
+            })
+            .collect();
+        due.sort();
+        due
+    }
+
     /// Get a single task by ID
     pub fn get_task(&self, task_id: &str) -> Option<&Task> {
         self.pending_tasks.get(task_id)
@@ -751,9 +1082,12 @@ impl TaskPlanner {
             })
     }
 
-    /// Get up to `max` unblocked pending tasks for parallel dispatch.
+    /// Get up to `max` unblocked pending tasks for parallel dispatch: every
+    /// task whose dependencies have completed, so independent branches of a
+    /// goal run side by side. Oldest first.
     pub fn next_tasks(&self, max: usize) -> Vec<&Task> {
-        self.pending_tasks
+        let mut ready: Vec<&Task> = self
+            .pending_tasks
             .values()
             .filter(|t| t.status == "pending")
             .filter(|t| {
@@ -763,11 +1097,69 @@ impl TaskPlanner {
                         .map_or(true, |dep| dep.status == "completed")
                 })
             })
-            .take(max)
-            .collect()
+            .collect();
+        ready.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+        ready.truncate(max);
+        ready
     }
 }
 
+/// Dependency graph of a goal's tasks: what each waits for, what of that
+/// has not completed yet, and its depth — the longest chain of
+/// dependencies before it, so tasks of equal depth can run in parallel.
+/// Dependencies on tasks outside `tasks` count as met.
+pub fn dependency_graph(tasks: &[Task]) -> Vec<TaskDependency> {
+    let by_id: HashMap<&str, &Task> = tasks.iter().map(|t| (t.id.as_str(), t)).collect();
+    let mut depths: HashMap<&str, i32> = HashMap::new();
+    tasks
+        .iter()
+        .map(|task| TaskDependency {
+            task_id: task.id.clone(),
+            depends_on: task.depends_on.clone(),
+            blocked_by: task
+                .depends_on
+                .iter()
+                .filter(|dep| {
+                    by_id
+                        .get(dep.as_str())
+                        .is_some_and(|d| d.status != "completed")
+                })
+                .cloned()
+                .collect(),
+            depth: depth_of(&task.id, &by_id, &mut depths, &mut Vec::new()),
+        })
+        .collect()
+}
+
+fn depth_of<'a>(
+    id: &'a str,
+    by_id: &HashMap<&'a str, &'a Task>,
+    depths: &mut HashMap<&'a str, i32>,
+    visiting: &mut Vec<&'a str>,
+) -> i32 {
+    if let Some(&depth) = depths.get(id) {
+        return depth;
+    }
+    // A cycle in persisted tasks must not recurse forever
+    if visiting.contains(&id) {
+        return 0;
+    }
+    let Some(task) = by_id.get(id) else {
+        return 0;
+    };
+    visiting.push(id);
+    let depth = task
+        .depends_on
+        .iter()
+        .filter(|dep| by_id.contains_key(dep.as_str()))
+        .map(|dep| depth_of(dep, by_id, depths, visiting) + 1)
+        .max()
+        .unwrap_or(0);
+    visiting.pop();
+    depths.insert(id, depth);
+    depth
+}
+
 /// Extract a service name from a goal description
 fn extract_service_name(desc: &str) -> String {
     let known_services = [
@@ -1026,6 +1418,89 @@ mod tests {
         assert!(!tasks[1].depends_on.is_empty(), "Second task should depend on first");
     }
 
+    #[test]
+    fn test_parse_ai_decomposition_dag() {
+        let mut planner = TaskPlanner::new();
+        let json = r#"[
+            {"description": "Check disk usage", "tools": ["monitor"], "after": []},
+            {"description": "Check open ports", "tools": ["net"], "after": []},
+            {"description": "Write report", "tools": ["fs"], "after": [1, 2]},
+            {"description": "Bogus reference", "tools": ["fs"], "after": [4, 9]}
+        ]"#;
+        let tasks = planner
+            .parse_ai_decomposition(json, "goal-1", &IntelligenceLevel::Tactical)
+            .unwrap();
+        assert!(tasks[0].depends_on.is_empty());
+        assert!(tasks[1].depends_on.is_empty());
+        assert_eq!(
+            tasks[2].depends_on,
+            vec![tasks[0].id.clone(), tasks[1].id.clone()]
+        );
+        assert!(tasks[3].depends_on.is_empty());
+
+        // Both independent branches are ready at once
+        planner.load_persisted_tasks(tasks.clone());
+        let ready: Vec<&str> = planner
+            .next_tasks(10)
+            .iter()
+            .map(|t| t.id.as_str())
+            .collect();
+        assert_eq!(ready.len(), 3);
+        assert!(!ready.contains(&tasks[2].id.as_str()));
+
+        planner.complete_task(&tasks[0].id, vec![]);
+        let goal_tasks: Vec<Task> = tasks
+            .iter()
+            .map(|t| planner.get_task(&t.id).unwrap().clone())
+            .collect();
+        let graph = dependency_graph(&goal_tasks);
+        assert_eq!(graph[2].depth, 1);
+        assert_eq!(graph[2].blocked_by, vec![tasks[1].id.clone()]);
+        assert_eq!(graph[0].depth, 0);
+
+        planner.complete_task(&tasks[1].id, vec![]);
+        assert!(planner.next_tasks(10).iter().any(|t| t.id == tasks[2].id));
+    }
+
+    #[test]
+    fn test_dependency_graph_tolerates_cycles() {
+        let task = |id: &str, dep: &str| Task {
+            id: id.into(),
+            depends_on: vec![dep.into()],
+            status: "pending".into(),
+            ..Default::default()
+        };
+        let graph = dependency_graph(&[task("a", "b"), task("b", "a"), task("c", "b")]);
+        assert_eq!(graph.len(), 3);
+        assert!(graph[2].depth >= 1);
+    }
+
+    #[test]
+    fn test_preempted_tasks_wait_until_restored() {
+        let mut planner = TaskPlanner::new();
+        planner.load_persisted_tasks(vec![
+            Task {
+                id: "a".into(),
+                status: "pending".into(),
+                ..Default::default()
+            },
+            Task {
+                id: "b".into(),
+                status: "pending".into(),
+                ..Default::default()
+            },
+        ]);
+        planner.mark_in_progress("a");
+        planner.preempt_task("a");
+        planner.preempt_task("b");
+        assert!(planner.next_tasks(10).is_empty());
+        assert_eq!(planner.pending_task_count(), 0);
+
+        assert_eq!(planner.restore_preempted(), vec!["a", "b"]);
+        assert_eq!(planner.next_tasks(10).len(), 2);
+        assert!(planner.restore_preempted().is_empty());
+    }
+
     #[test]
     fn test_parse_ai_decomposition_with_think_tags() {
         let planner = TaskPlanner::new();
@@ -1061,6 +1536,8 @@ mod tests {
             started_at: 0,
             completed_at: 0,
             error: String::new(),
+            model_class: String::new(),
+            provider_hint: String::new(),
         };
         planner.pending_tasks.insert("t1".into(), task);
         assert_eq!(planner.get_tasks_for_goal("g1").len(), 1);
@@ -1121,6 +1598,77 @@ mod tests {
         assert!(task.completed_at > 0);
     }
 
+    #[tokio::test]
+    async fn test_transient_failures_retry_until_attempts_run_out() {
+        let mut planner = TaskPlanner::new();
+        let tasks = planner
+            .decompose_goal("goal-1", "Check status")
+            .await
+            .unwrap();
+        let task_id = tasks[0].id.clone();
+
+        let FailureOutcome::Retry {
+            attempt,
+            max_attempts,
+            delay,
+        } = planner.fail_or_retry(&task_id, "backends down", FailureKind::Transient, 0)
+        else {
+            panic!("expected a retry");
+        };
+        assert_eq!((attempt, max_attempts), (2, 3));
+        assert!(delay >= Duration::from_secs(2) && delay <= Duration::from_secs(6));
+        assert_eq!(planner.get_task(&task_id).unwrap().status, "retrying");
+        assert!(planner.next_tasks(10).is_empty());
+        assert!(planner.due_retries(0).is_empty());
+
+        let due = planner.due_retries(delay.as_millis() as i64);
+        assert_eq!(due, vec![(task_id.clone(), 2)]);
+        assert_eq!(planner.next_tasks(10).len(), 1);
+
+        assert!(matches!(
+            planner.fail_or_retry(&task_id, "backends down", FailureKind::Transient, 0),
+            FailureOutcome::Retry { attempt: 3, .. }
+        ));
+        planner.due_retries(i64::MAX);
+        assert_eq!(
+            planner.fail_or_retry(&task_id, "backends down", FailureKind::Transient, 0),
+            FailureOutcome::Failed { attempts: 3 }
+        );
+        assert_eq!(planner.get_task(&task_id).unwrap().status, "failed");
+    }
+
+    #[test]
+    fn test_retry_policy_from_task_input() {
+        let mut task = Task {
+            id: "t".into(),
+            input_json: br#"{"retry": {"max_attempts": 5, "retry_on": ["tool_error"]}}"#.to_vec(),
+            ..Default::default()
+        };
+        let policy = TaskRetryPolicy::for_task(&task);
+        assert_eq!(policy.max_attempts, 5);
+        assert_eq!(policy.backoff_ms, 2_000);
+        assert!(policy.retries(FailureKind::ToolError));
+        assert!(!policy.retries(FailureKind::Transient));
+
+        task.input_json = br#"{"retry": {"attempts": 5}}"#.to_vec();
+        assert_eq!(TaskRetryPolicy::for_task(&task), TaskRetryPolicy::default());
+
+        for previous in [0, 2_000, 50_000, 1_000_000] {
+            let delay = policy.next_delay(previous).as_millis() as u64;
+            assert!((2_000..=300_000).contains(&delay));
+            assert!(delay <= (previous.max(2_000) * 3));
+        }
+        assert_eq!(
+            classify_tool_failures(["timeout", "rate_limited"]),
+            FailureKind::Transient
+        );
+        assert_eq!(
+            classify_tool_failures(["timeout", "denied"]),
+            FailureKind::Permanent
+        );
+        assert_eq!(classify_tool_failures(["error"]), FailureKind::ToolError);
+    }
+
     #[tokio::test]
     async fn test_next_task() {
         let mut planner = TaskPlanner::new();
@@ -1141,6 +1689,47 @@ mod tests {
         assert!(next.is_none());
     }
 
+    #[tokio::test]
+    async fn test_model_hints() {
+        let mut planner = TaskPlanner::new();
+        let tasks = planner
+            .decompose_goal("goal-1", "Check status")
+            .await
+            .unwrap();
+        assert_eq!(tasks[0].model_class, "fast");
+
+        let mut task = tasks[0].clone();
+        task.model_class.clear();
+        task.intelligence_level = "strategic".into();
+        planner.annotate_model_hints(&mut task);
+        assert_eq!(task.model_class, "strong");
+
+        // Tactical tasks with only routine tools are fast
+        task.model_class.clear();
+        task.intelligence_level = "tactical".into();
+        task.required_tools = vec!["monitor".into(), "fs.read".into()];
+        planner.annotate_model_hints(&mut task);
+        assert_eq!(task.model_class, "fast");
+        task.model_class.clear();
+        task.required_tools = vec!["pkg".into()];
+        planner.annotate_model_hints(&mut task);
+        assert_eq!(task.model_class, "strong");
+
+        // Repeated fast-model failures with the same tools upgrade to strong
+        for i in 0..2 {
+            let mut failed = task.clone();
+            failed.id = format!("failed-{i}");
+            failed.model_class = "fast".into();
+            failed.required_tools = vec!["monitor".into()];
+            failed.status = "failed".into();
+            planner.pending_tasks.insert(failed.id.clone(), failed);
+        }
+        task.model_class = "fast".into();
+        task.required_tools = vec!["monitor".into()];
+        planner.annotate_model_hints(&mut task);
+        assert_eq!(task.model_class, "strong");
+    }
+
     #[test]
     fn test_complete_nonexistent_task() {
         let mut planner = TaskPlanner::new();
@@ -0,0 +1,125 @@
+//! Task workers — tasks the orchestrator is executing itself
+//!
+//! Tasks no agent takes (heuristic tool calls or a reasoning loop) each run
+//! on a worker of their own, so one slow model call does not hold up the
+//! rest of the backlog. The autonomy loop starts at most
+//! `AutonomyConfig.max_concurrent_tasks` workers, fewer while the machine
+//! is loaded. Every worker has a cancellation token; cancelling a goal
+//! stops the workers of its tasks, and preemption those of lower-priority
+//! goals.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio_util::sync::CancellationToken;
+
+/// CPU load from which fewer tasks run at once
+const SHED_CPU_PERCENT: f64 = 75.0;
+
+/// CPU load at which only one task runs at a time
+const CRITICAL_CPU_PERCENT: f64 = 90.0;
+
+/// How many tasks may run at once, out of `max`, at the given CPU load
+pub fn capacity(max: usize, cpu_percent: f64) -> usize {
+    let max = max.max(1);
+    if cpu_percent >= CRITICAL_CPU_PERCENT {
+        1
+    } else if cpu_percent >= SHED_CPU_PERCENT {
+        (max / 2).max(1)
+    } else {
+        max
+    }
+}
+
+/// Workers currently executing tasks, by task id
+#[derive(Default)]
+pub struct TaskWorkers {
+    running: Mutex<HashMap<String, CancellationToken>>,
+}
+
+impl TaskWorkers {
+    /// Number of tasks executing
+    pub fn active(&self) -> usize {
+        self.running.lock().unwrap().len()
+    }
+
+    /// IDs of the tasks executing
+    pub fn running(&self) -> Vec<String> {
+        self.running.lock().unwrap().keys().cloned().collect()
+    }
+
+    /// Register a worker for `task_id`; it counts as running until the
+    /// returned slot is dropped
+    pub fn claim(self: &Arc<Self>, task_id: &str) -> WorkerSlot {
+        let token = CancellationToken::new();
+        self.running
+            .lock()
+            .unwrap()
+            .insert(task_id.to_string(), token.clone());
+        WorkerSlot {
+            workers: self.clone(),
+            task_id: task_id.to_string(),
+            token,
+        }
+    }
+
+    /// Cancel the workers of `task_ids`; the number cancelled
+    pub fn cancel(&self, task_ids: &[String]) -> usize {
+        let running = self.running.lock().unwrap();
+        task_ids
+            .iter()
+            .filter_map(|id| running.get(id))
+            .inspect(|token| token.cancel())
+            .count()
+    }
+}
+
+/// A running worker's place in [`TaskWorkers`]
+pub struct WorkerSlot {
+    workers: Arc<TaskWorkers>,
+    task_id: String,
+    token: CancellationToken,
+}
+
+impl WorkerSlot {
+    /// Cancelled when the task's goal is cancelled
+    pub fn token(&self) -> &CancellationToken {
+        &self.token
+    }
+}
+
+impl Drop for WorkerSlot {
+    fn drop(&mut self) {
+        self.workers.running.lock().unwrap().remove(&self.task_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capacity_degrades_under_load() {
+        assert_eq!(capacity(10, 20.0), 10);
+        assert_eq!(capacity(10, 80.0), 5);
+        assert_eq!(capacity(10, 95.0), 1);
+        assert_eq!(capacity(1, 80.0), 1);
+        assert_eq!(capacity(0, 0.0), 1);
+    }
+
+    #[test]
+    fn test_claim_cancel_and_release() {
+        let workers = Arc::new(TaskWorkers::default());
+        let a = workers.claim("task-a");
+        let b = workers.claim("task-b");
+        assert_eq!(workers.active(), 2);
+
+        assert_eq!(workers.cancel(&["task-a".into(), "task-z".into()]), 1);
+        assert!(a.token().is_cancelled());
+        assert!(!b.token().is_cancelled());
+
+        drop(a);
+        assert_eq!(workers.active(), 1);
+        drop(b);
+        assert_eq!(workers.active(), 0);
+    }
+}
@@ -0,0 +1,176 @@
+//! Telemetry — logging, and distributed tracing of goals across services
+//!
+//! Logs go to stdout as before. When `OTEL_EXPORTER_OTLP_ENDPOINT` (or
+//! `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT`) is set, spans are also exported
+//! over OTLP/gRPC, as service `OTEL_SERVICE_NAME` (default
+//! `aios-orchestrator`), sampled per `OTEL_TRACES_SAMPLER`.
+//!
+//! A goal produces one trace. SubmitGoal continues the caller's trace, or
+//! starts one, and the goal's trace context is kept in [`GoalTraces`]; the
+//! autonomy loop parents the spans of the goal's (and its subgoals') tasks
+//! on it. Goals created any other way get a trace when their work is first
+//! traced. Every outgoing call carries the current span as a W3C
+//! `traceparent` header, and the services continue the trace from it, so
+//! task planning, gateway inference and tool executions all land in the
+//! goal's trace.
+
+use opentelemetry::propagation::Injector;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use crate::goal_engine::GoalEngine;
+
+#[path = "otel.rs"]
+mod otel;
+
+pub use otel::{init, server_span, shutdown};
+
+/// Goals whose trace context is kept; the oldest are forgotten first
+const MAX_GOAL_TRACES: usize = 10_000;
+
+struct HeaderCarrier<'a>(&'a mut http::HeaderMap);
+
+impl Injector for HeaderCarrier<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(name), Ok(value)) = (
+            http::header::HeaderName::from_bytes(key.as_bytes()),
+            http::HeaderValue::from_str(&value),
+        ) {
+            self.0.insert(name, value);
+        }
+    }
+}
+
+/// Add the current span's trace context to the headers of an outgoing call
+pub fn inject(headers: &mut http::HeaderMap) {
+    let context = tracing::Span::current().context();
+    opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&context, &mut HeaderCarrier(headers))
+    });
+}
+
+/// The trace each goal's work belongs to
+#[derive(Default)]
+pub struct GoalTraces {
+    inner: Mutex<GoalTracesInner>,
+}
+
+#[derive(Default)]
+struct GoalTracesInner {
+    contexts: HashMap<String, opentelemetry::Context>,
+    order: VecDeque<String>,
+}
+
+impl GoalTracesInner {
+    fn insert(&mut self, goal_id: &str, context: opentelemetry::Context) {
+        if self.contexts.insert(goal_id.to_string(), context).is_none() {
+            self.order.push_back(goal_id.to_string());
+        }
+        while self.order.len() > MAX_GOAL_TRACES {
+            if let Some(oldest) = self.order.pop_front() {
+                self.contexts.remove(&oldest);
+            }
+        }
+    }
+}
+
+impl GoalTraces {
+    /// Make `span` the root of `goal_id`'s trace
+    pub fn record(&self, goal_id: &str, span: &tracing::Span) {
+        self.inner.lock().unwrap().insert(goal_id, span.context());
+    }
+
+    /// Parent `span` on the trace of `goal_id`, or of its nearest ancestor
+    /// that has one. When none has, the root goal's trace starts here.
+    pub fn attach(&self, goals: &GoalEngine, goal_id: &str, span: &tracing::Span) {
+        let ancestry = goals.ancestry(goal_id);
+        let mut inner = self.inner.lock().unwrap();
+        let context = match ancestry.iter().find_map(|id| inner.contexts.get(id)) {
+            Some(context) => context.clone(),
+            None => {
+                let root = ancestry.last().map_or(goal_id, |id| id.as_str());
+                let context = tracing::info_span!(parent: None, "goal", goal_id = %root).context();
+                inner.insert(root, context.clone());
+                context
+            }
+        };
+        span.set_parent(context);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::trace::{TraceContextExt, TracerProvider as _};
+    use opentelemetry_sdk::propagation::TraceContextPropagator;
+    use opentelemetry_sdk::trace::TracerProvider;
+    use tracing::instrument::WithSubscriber;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[tokio::test]
+    async fn test_goal_trace_propagates_to_subgoals_and_calls() {
+        opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+        let provider = TracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+        async {
+            let mut goals = GoalEngine::new();
+            let root = goals
+                .submit_goal("Upgrade the fleet".into(), 1, "user".into())
+                .await
+                .unwrap();
+            let child = goals
+                .spawn_subgoals(
+                    &root,
+                    "t1",
+                    vec![crate::goal_engine::SubgoalSpec {
+                        description: "Upgrade node-1".into(),
+                        priority: None,
+                    }],
+                )
+                .await
+                .unwrap()
+                .remove(0);
+
+            let traces = GoalTraces::default();
+            let submit = tracing::info_span!("submit_goal");
+            traces.record(&root, &submit);
+            let trace_id = submit.context().span().span_context().trace_id();
+
+            let task = tracing::info_span!("execute_task");
+            traces.attach(&goals, &child, &task);
+            assert_eq!(task.context().span().span_context().trace_id(), trace_id);
+
+            // A goal without a recorded trace gets one
+            let other = goals
+                .submit_goal("Rotate logs".into(), 1, "proactive".into())
+                .await
+                .unwrap();
+            let first = tracing::info_span!("execute_task");
+            let second = tracing::info_span!("execute_task");
+            traces.attach(&goals, &other, &first);
+            traces.attach(&goals, &other, &second);
+            let other_trace = first.context().span().span_context().trace_id();
+            assert_ne!(other_trace, trace_id);
+            assert_eq!(
+                second.context().span().span_context().trace_id(),
+                other_trace
+            );
+
+            // An outgoing call carries the trace, and the service continues it
+            let mut headers = http::HeaderMap::new();
+            task.in_scope(|| inject(&mut headers));
+            let traceparent = headers["traceparent"].to_str().unwrap();
+            assert!(traceparent.contains(&trace_id.to_string()));
+
+            let mut request = http::Request::new(());
+            *request.headers_mut() = headers;
+            let served = server_span(&request);
+            assert_eq!(served.context().span().span_context().trace_id(), trace_id);
+        }
+        .with_subscriber(subscriber)
+        .await;
+    }
+}
@@ -0,0 +1,541 @@
+//! Local Timers — short-delay follow-ups and retries without full goals
+//!
+//! The goal scheduler works on a 60-second cron tick. Local timers cover the
+//! sub-minute cases ("re-check service health in 30s", "retry webhook in 5s
+//! with backoff"): each timer runs one tool call after a delay, optionally
+//! retrying failures with exponential backoff or repeating a fixed number of
+//! times. Timers are held in memory only and do not survive a restart.
+
+use anyhow::Result;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Notify};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use crate::event_bus::{EventSeverity, SystemEvent};
+
+/// Most timers that may be pending at once
+pub const MAX_PENDING_TIMERS: usize = 1000;
+
+/// Longest accepted delay; anything later belongs in a scheduled goal
+pub const MAX_TIMER_DELAY: Duration = Duration::from_secs(3600);
+
+/// Shortest accepted interval between repeated runs
+pub const MIN_REPEAT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Most retries a single run may request
+pub const MAX_RETRIES: u32 = 10;
+
+/// Firings kept for inspection
+const MAX_RECENT_FIRINGS: usize = 100;
+
+/// Exponential backoff applied when a timer's tool call fails
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub multiplier: f64,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_secs(1),
+            multiplier: 2.0,
+            max_backoff: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before retry number `retry` (1-based)
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = self
+            .multiplier
+            .max(1.0)
+            .powi(retry.saturating_sub(1) as i32);
+        self.initial_backoff
+            .mul_f64(factor.min(u32::MAX as f64))
+            .min(self.max_backoff)
+    }
+}
+
+/// What a caller asks for when scheduling a timer
+#[derive(Debug, Clone)]
+pub struct TimerSpec {
+    /// Agent or component that requested the timer
+    pub owner: String,
+    pub tool_name: String,
+    pub input_json: Vec<u8>,
+    pub delay: Duration,
+    /// Interval between repeated runs (required when `repeat_count > 0`)
+    pub repeat_interval: Option<Duration>,
+    /// Additional runs after the first
+    pub repeat_count: u32,
+    pub retry: Option<RetryPolicy>,
+}
+
+/// A scheduled timer
+#[derive(Debug, Clone)]
+pub struct Timer {
+    pub id: String,
+    pub spec: TimerSpec,
+    pub due: Instant,
+    /// Retries used by the current run
+    pub attempt: u32,
+    /// Runs completed so far
+    pub runs: u32,
+    pub in_flight: bool,
+    seq: u64,
+}
+
+/// Outcome of one timer run, kept for inspection
+#[derive(Debug, Clone)]
+pub struct TimerFiring {
+    pub timer_id: String,
+    pub owner: String,
+    pub tool_name: String,
+    pub success: bool,
+    pub output: String,
+    pub attempt: u32,
+    pub fired_at: i64,
+}
+
+/// Deadline-ordered timer storage.
+///
+/// The heap uses lazy deletion: each arm gets a fresh sequence number, and
+/// heap entries whose sequence no longer matches the stored timer are skipped.
+#[derive(Default)]
+pub struct TimerQueue {
+    heap: BinaryHeap<Reverse<(Instant, u64, String)>>,
+    timers: HashMap<String, Timer>,
+    recent: VecDeque<TimerFiring>,
+    next_seq: u64,
+}
+
+impl TimerQueue {
+    /// Validate and arm a new timer, returning its id
+    pub fn schedule(&mut self, spec: TimerSpec, now: Instant) -> Result<String> {
+        if spec.tool_name.is_empty() {
+            anyhow::bail!("timer requires a tool_name");
+        }
+        if spec.delay > MAX_TIMER_DELAY {
+            anyhow::bail!(
+                "delay {:?} exceeds {:?}; use a scheduled goal instead",
+                spec.delay,
+                MAX_TIMER_DELAY
+            );
+        }
+        if spec.repeat_count > 0 {
+            match spec.repeat_interval {
+                Some(interval) if interval >= MIN_REPEAT_INTERVAL => {}
+                _ => anyhow::bail!(
+                    "repeating timers need a repeat interval of at least {:?}",
+                    MIN_REPEAT_INTERVAL
+                ),
+            }
+        }
+        if spec
+            .retry
+            .as_ref()
+            .is_some_and(|r| r.max_retries > MAX_RETRIES)
+        {
+            anyhow::bail!("at most {MAX_RETRIES} retries are allowed");
+        }
+        if self.timers.len() >= MAX_PENDING_TIMERS {
+            anyhow::bail!("too many pending timers (max {MAX_PENDING_TIMERS})");
+        }
+
+        let id = uuid::Uuid::new_v4().to_string();
+        let timer = Timer {
+            id: id.clone(),
+            due: now + spec.delay,
+            spec,
+            attempt: 0,
+            runs: 0,
+            in_flight: false,
+            seq: 0,
+        };
+        self.arm(timer);
+        Ok(id)
+    }
+
+    /// Cancel a timer. A run already in flight finishes but is not re-armed.
+    pub fn cancel(&mut self, id: &str) -> bool {
+        self.timers.remove(id).is_some()
+    }
+
+    /// Earliest deadline among armed timers
+    pub fn next_due(&mut self) -> Option<Instant> {
+        while let Some(Reverse((due, seq, id))) = self.heap.peek() {
+            if self.is_live(id, *seq) {
+                return Some(*due);
+            }
+            self.heap.pop();
+        }
+        None
+    }
+
+    /// Remove every timer due at `now` from the heap and mark it in flight
+    pub fn pop_due(&mut self, now: Instant) -> Vec<Timer> {
+        let mut due = Vec::new();
+        while let Some(Reverse((deadline, seq, id))) = self.heap.peek() {
+            if *deadline > now {
+                break;
+            }
+            let (seq, id) = (*seq, id.clone());
+            self.heap.pop();
+            if !self.is_live(&id, seq) {
+                continue;
+            }
+            if let Some(timer) = self.timers.get_mut(&id) {
+                timer.in_flight = true;
+                due.push(timer.clone());
+            }
+        }
+        due
+    }
+
+    /// Record a run's outcome and re-arm the timer for a retry or repeat.
+    ///
+    /// Returns whether the timer was re-armed.
+    pub fn complete(&mut self, id: &str, success: bool, output: String, now: Instant) -> bool {
+        let Some(mut timer) = self.timers.remove(id) else {
+            return false;
+        };
+        self.recent.push_back(TimerFiring {
+            timer_id: timer.id.clone(),
+            owner: timer.spec.owner.clone(),
+            tool_name: timer.spec.tool_name.clone(),
+            success,
+            output,
+            attempt: timer.attempt,
+            fired_at: chrono::Utc::now().timestamp(),
+        });
+        if self.recent.len() > MAX_RECENT_FIRINGS {
+            self.recent.pop_front();
+        }
+
+        timer.in_flight = false;
+        if !success {
+            if let Some(retry) = &timer.spec.retry {
+                if timer.attempt < retry.max_retries {
+                    timer.attempt += 1;
+                    timer.due = now + retry.backoff(timer.attempt);
+                    self.arm(timer);
+                    return true;
+                }
+            }
+        }
+        timer.runs += 1;
+        if timer.runs <= timer.spec.repeat_count {
+            timer.attempt = 0;
+            timer.due = now + timer.spec.repeat_interval.unwrap_or(MIN_REPEAT_INTERVAL);
+            self.arm(timer);
+            return true;
+        }
+        false
+    }
+
+    /// Pending timers, soonest first
+    pub fn list(&self) -> Vec<&Timer> {
+        let mut timers: Vec<&Timer> = self.timers.values().collect();
+        timers.sort_by_key(|t| t.due);
+        timers
+    }
+
+    /// Most recent firings, oldest first
+    pub fn recent(&self) -> impl Iterator<Item = &TimerFiring> {
+        self.recent.iter()
+    }
+
+    fn arm(&mut self, mut timer: Timer) {
+        self.next_seq += 1;
+        timer.seq = self.next_seq;
+        self.heap
+            .push(Reverse((timer.due, timer.seq, timer.id.clone())));
+        self.timers.insert(timer.id.clone(), timer);
+    }
+
+    fn is_live(&self, id: &str, seq: u64) -> bool {
+        self.timers
+            .get(id)
+            .is_some_and(|t| t.seq == seq && !t.in_flight)
+    }
+}
+
+/// Shared handle to the timer queue; wakes the run loop when timers change
+#[derive(Default)]
+pub struct LocalTimers {
+    queue: Mutex<TimerQueue>,
+    changed: Notify,
+}
+
+impl LocalTimers {
+    pub fn schedule(&self, spec: TimerSpec) -> Result<String> {
+        let id = self.lock().schedule(spec, Instant::now())?;
+        self.changed.notify_one();
+        Ok(id)
+    }
+
+    pub fn cancel(&self, id: &str) -> bool {
+        let removed = self.lock().cancel(id);
+        if removed {
+            self.changed.notify_one();
+        }
+        removed
+    }
+
+    /// Pending timers (soonest first) and recent firings
+    pub fn snapshot(&self) -> (Vec<Timer>, Vec<TimerFiring>) {
+        let queue = self.lock();
+        (
+            queue.list().into_iter().cloned().collect(),
+            queue.recent().cloned().collect(),
+        )
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, TimerQueue> {
+        self.queue.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Fire due timers until cancelled.
+    ///
+    /// Each run executes in its own task so a slow tool call never delays
+    /// other timers. A run that fails with no retries left publishes a
+    /// `timer.failed` event.
+    pub async fn run(
+        timers: Arc<Self>,
+        clients: Arc<crate::clients::ServiceClients>,
+        events: mpsc::Sender<SystemEvent>,
+        cancel: CancellationToken,
+    ) {
+        info!("Local timers started");
+        loop {
+            let next = timers.lock().next_due();
+            let sleep = match next {
+                Some(due) => tokio::time::sleep_until(tokio::time::Instant::from_std(due)),
+                None => tokio::time::sleep(Duration::from_secs(60)),
+            };
+            tokio::select! {
+                _ = cancel.cancelled() => {
+                    info!("Local timers shutting down");
+                    break;
+                }
+                _ = timers.changed.notified() => continue,
+                _ = sleep => {}
+            }
+
+            let due = timers.lock().pop_due(Instant::now());
+            for timer in due {
+                let timers = timers.clone();
+                let clients = clients.clone();
+                let events = events.clone();
+                tokio::spawn(async move {
+                    debug!(
+                        "Timer {} firing {} (attempt {})",
+                        timer.id, timer.spec.tool_name, timer.attempt
+                    );
+                    let (success, output) = match fire(&clients, &timer).await {
+                        Ok(output) => (true, output),
+                        Err(e) => (false, e.to_string()),
+                    };
+                    let rearmed =
+                        timers
+                            .lock()
+                            .complete(&timer.id, success, output.clone(), Instant::now());
+                    if rearmed {
+                        timers.changed.notify_one();
+                    } else if !success {
+                        warn!("Timer {} failed: {output}", timer.id);
+                        crate::event_bus::publish_event(
+                            &events,
+                            "timer.failed",
+                            &timer.spec.owner,
+                            serde_json::json!({
+                                "timer_id": timer.id,
+                                "tool_name": timer.spec.tool_name,
+                                "error": output,
+                                "attempts": timer.attempt + 1,
+                            }),
+                            EventSeverity::Warning,
+                        )
+                        .await;
+                    }
+                });
+            }
+        }
+    }
+}
+
+/// Execute a timer's tool call via the tools gRPC service
+async fn fire(clients: &crate::clients::ServiceClients, timer: &Timer) -> Result<String> {
+    let mut client = clients
+        .tools()
+        .await
+        .map_err(|e| anyhow::anyhow!("Cannot connect to tools service: {e}"))?;
+
+    let response = client
+        .execute(tonic::Request::new(crate::proto::tools::ExecuteRequest {
+            tool_name: timer.spec.tool_name.clone(),
+            agent_id: timer.spec.owner.clone(),
+            task_id: String::new(),
+            input_json: timer.spec.input_json.clone(),
+            reason: format!("Local timer {}", timer.id),
+            workload: String::new(),
+            resume_token: String::new(),
+            allow_experimental: false,
+        }))
+        .await
+        .map_err(|e| anyhow::anyhow!("Tool execution gRPC failed: {e}"))?
+        .into_inner();
+
+    if response.success {
+        Ok(String::from_utf8_lossy(&response.output_json).to_string())
+    } else {
+        anyhow::bail!("Tool '{}' failed: {}", timer.spec.tool_name, response.error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(delay_ms: u64) -> TimerSpec {
+        TimerSpec {
+            owner: "test".into(),
+            tool_name: "monitor.cpu".into(),
+            input_json: b"{}".to_vec(),
+            delay: Duration::from_millis(delay_ms),
+            repeat_interval: None,
+            repeat_count: 0,
+            retry: None,
+        }
+    }
+
+    #[test]
+    fn test_pop_due_in_deadline_order() {
+        let mut queue = TimerQueue::default();
+        let now = Instant::now();
+        let late = queue.schedule(spec(500), now).unwrap();
+        let early = queue.schedule(spec(50), now).unwrap();
+
+        assert_eq!(queue.next_due(), Some(now + Duration::from_millis(50)));
+        assert!(queue.pop_due(now).is_empty());
+
+        let due = queue.pop_due(now + Duration::from_millis(100));
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].id, early);
+        // In-flight timers are not reported as the next deadline
+        assert_eq!(queue.next_due(), Some(now + Duration::from_millis(500)));
+
+        assert!(!queue.complete(&early, true, "{}".into(), now));
+        let due = queue.pop_due(now + Duration::from_secs(1));
+        assert_eq!(due[0].id, late);
+    }
+
+    #[test]
+    fn test_retry_with_backoff() {
+        let mut queue = TimerQueue::default();
+        let now = Instant::now();
+        let mut s = spec(0);
+        s.retry = Some(RetryPolicy {
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(100),
+            multiplier: 3.0,
+            max_backoff: Duration::from_millis(250),
+        });
+        let id = queue.schedule(s, now).unwrap();
+
+        queue.pop_due(now);
+        assert!(queue.complete(&id, false, "boom".into(), now));
+        assert_eq!(queue.next_due(), Some(now + Duration::from_millis(100)));
+
+        queue.pop_due(now + Duration::from_millis(100));
+        assert!(queue.complete(&id, false, "boom".into(), now));
+        // 300ms capped at max_backoff
+        assert_eq!(queue.next_due(), Some(now + Duration::from_millis(250)));
+
+        queue.pop_due(now + Duration::from_millis(250));
+        assert!(!queue.complete(&id, false, "boom".into(), now));
+        assert!(queue.list().is_empty());
+        assert_eq!(queue.recent().count(), 3);
+    }
+
+    #[test]
+    fn test_repeat_count() {
+        let mut queue = TimerQueue::default();
+        let now = Instant::now();
+        let mut s = spec(0);
+        s.repeat_count = 2;
+        s.repeat_interval = Some(Duration::from_millis(200));
+        let id = queue.schedule(s, now).unwrap();
+
+        for _ in 0..2 {
+            assert_eq!(queue.pop_due(now + Duration::from_secs(1)).len(), 1);
+            assert!(queue.complete(&id, true, "{}".into(), now));
+            assert_eq!(queue.next_due(), Some(now + Duration::from_millis(200)));
+        }
+        assert_eq!(queue.pop_due(now + Duration::from_secs(1)).len(), 1);
+        assert!(!queue.complete(&id, true, "{}".into(), now));
+        assert_eq!(queue.next_due(), None);
+    }
+
+    #[test]
+    fn test_cancel_stops_rearm() {
+        let mut queue = TimerQueue::default();
+        let now = Instant::now();
+        let mut s = spec(0);
+        s.retry = Some(RetryPolicy::default());
+        let id = queue.schedule(s, now).unwrap();
+
+        queue.pop_due(now);
+        assert!(queue.cancel(&id));
+        assert!(!queue.complete(&id, false, "boom".into(), now));
+        assert_eq!(queue.next_due(), None);
+        assert!(!queue.cancel(&id));
+    }
+
+    #[test]
+    fn test_schedule_validation() {
+        let mut queue = TimerQueue::default();
+        let now = Instant::now();
+
+        let mut s = spec(0);
+        s.delay = MAX_TIMER_DELAY + Duration::from_secs(1);
+        assert!(queue.schedule(s, now).is_err());
+
+        let mut s = spec(0);
+        s.repeat_count = 5;
+        s.repeat_interval = Some(Duration::from_millis(10));
+        assert!(queue.schedule(s, now).is_err());
+
+        let mut s = spec(0);
+        s.tool_name.clear();
+        assert!(queue.schedule(s, now).is_err());
+
+        let mut s = spec(0);
+        s.retry = Some(RetryPolicy {
+            max_retries: MAX_RETRIES + 1,
+            ..RetryPolicy::default()
+        });
+        assert!(queue.schedule(s, now).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_local_timers_snapshot() {
+        let timers = LocalTimers::default();
+        let id = timers.schedule(spec(1000)).unwrap();
+        let (pending, recent) = timers.snapshot();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].id, id);
+        assert!(recent.is_empty());
+        assert!(timers.cancel(&id));
+        assert!(timers.snapshot().0.is_empty());
+    }
+}
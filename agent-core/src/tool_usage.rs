@@ -0,0 +1,400 @@
+//! Tool Usage — per-tool analytics that shape the tool catalog in prompts
+//!
+//! Every tool call the autonomy loop makes is recorded: how often the tool
+//! runs, how often it succeeds, which input fields it is usually given, and
+//! the words of the tasks it succeeded on. The catalog sent to the model
+//! lists the tools most relevant to the task first — by name and
+//! description match and by past success on similar tasks — with their
+//! success rate, typical inputs and the argument shapes their input schemas
+//! declare, and trims every other tool to its bare name. The prompt stays small while the model is steered toward tools
+//! that worked before.
+//!
+//! The number of recommended tools comes from `AIOS_TOOL_CATALOG_RECOMMEND`
+//! (12).
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::proto::tools::ToolDefinition;
+
+/// Calls before a tool's success rate is trusted
+const MIN_CALLS: u32 = 3;
+
+/// Distinct task words remembered per tool
+const MAX_TASK_WORDS: usize = 200;
+
+/// Words too common to say anything about a task
+const STOP_WORDS: &[&str] = &[
+    "the", "and", "for", "with", "from", "that", "this", "into", "all", "any", "are", "was",
+    "will", "can", "its", "our", "your", "use", "using", "make", "sure", "task", "please",
+];
+
+#[derive(Debug, Default, Clone)]
+struct ToolStats {
+    invocations: u32,
+    successes: u32,
+    /// Calls that passed each top-level input field
+    input_fields: HashMap<String, u32>,
+    /// Successful calls whose task mentioned each word
+    task_words: HashMap<String, u32>,
+}
+
+/// Summary of one tool's usage, as reported by the management API
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolUsageSummary {
+    pub tool: String,
+    pub invocations: u32,
+    pub successes: u32,
+    pub success_rate: f64,
+    pub typical_inputs: Vec<String>,
+}
+
+/// Usage analytics for every tool the autonomy loop has called
+#[derive(Debug, Default)]
+pub struct ToolUsage {
+    tools: HashMap<String, ToolStats>,
+}
+
+impl ToolUsage {
+    /// Record one call of `tool` made for a task described by `task`
+    pub fn record(&mut self, tool: &str, task: &str, input_json: &[u8], success: bool) {
+        let stats = self.tools.entry(tool.to_string()).or_default();
+        stats.invocations += 1;
+        if let Ok(serde_json::Value::Object(input)) = serde_json::from_slice(input_json) {
+            for field in input.keys() {
+                *stats.input_fields.entry(field.clone()).or_default() += 1;
+            }
+        }
+        if !success {
+            return;
+        }
+        stats.successes += 1;
+        for word in words(task) {
+            *stats.task_words.entry(word).or_default() += 1;
+        }
+        if stats.task_words.len() > MAX_TASK_WORDS {
+            // Forget the rarest words
+            let mut counts: Vec<u32> = stats.task_words.values().copied().collect();
+            counts.sort_unstable();
+            let cutoff = counts[counts.len() - MAX_TASK_WORDS];
+            stats.task_words.retain(|_, n| *n > cutoff);
+        }
+    }
+
+    /// Share of calls that succeeded, once the tool has run `MIN_CALLS` times
+    pub fn success_rate(&self, tool: &str) -> Option<f64> {
+        self.tools
+            .get(tool)
+            .filter(|s| s.invocations >= MIN_CALLS)
+            .map(|s| s.successes as f64 / s.invocations as f64)
+    }
+
+    /// Input fields passed in at least half of the tool's calls, most
+    /// common first
+    pub fn typical_inputs(&self, tool: &str) -> Vec<String> {
+        let Some(stats) = self.tools.get(tool) else {
+            return Vec::new();
+        };
+        let mut fields: Vec<(&String, u32)> = stats
+            .input_fields
+            .iter()
+            .filter(|(_, n)| **n * 2 >= stats.invocations)
+            .map(|(f, n)| (f, *n))
+            .collect();
+        fields.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        fields.into_iter().map(|(f, _)| f.clone()).collect()
+    }
+
+    /// Usage of every recorded tool, most used first
+    pub fn summaries(&self) -> Vec<ToolUsageSummary> {
+        let mut summaries: Vec<ToolUsageSummary> = self
+            .tools
+            .iter()
+            .map(|(tool, s)| ToolUsageSummary {
+                tool: tool.clone(),
+                invocations: s.invocations,
+                successes: s.successes,
+                success_rate: s.successes as f64 / s.invocations.max(1) as f64,
+                typical_inputs: self.typical_inputs(tool),
+            })
+            .collect();
+        summaries.sort_by(|a, b| b.invocations.cmp(&a.invocations).then(a.tool.cmp(&b.tool)));
+        summaries
+    }
+
+    /// How relevant `tool` is to a task with the given words
+    fn relevance(&self, tool: &ToolDefinition, task_words: &HashSet<String>) -> f64 {
+        let name_words = words(&tool.name.replace(['.', '_'], " "));
+        let description_words = words(&tool.description);
+        let mut score = 2.0 * name_words.intersection(task_words).count() as f64
+            + description_words.intersection(task_words).count() as f64;
+
+        if let Some(stats) = self.tools.get(&tool.name) {
+            if stats.successes > 0 {
+                // Share of past successes on tasks mentioning each word
+                let learned: f64 = task_words
+                    .iter()
+                    .filter_map(|w| stats.task_words.get(w))
+                    .map(|n| *n as f64 / stats.successes as f64)
+                    .sum();
+                score += 3.0 * learned;
+            }
+        }
+        if let Some(rate) = self.success_rate(&tool.name) {
+            score *= 0.5 + rate;
+        }
+        score
+    }
+
+    /// The tool catalog for a prompt: the `recommend` tools most relevant
+    /// to `task` in full, then every other tool by name only. Without any
+    /// relevant tool, every tool is listed with its description.
+    pub fn catalog(&self, task: &str, tools: &[ToolDefinition], recommend: usize) -> String {
+        let task_words = words(task);
+        let mut ranked: Vec<(f64, &ToolDefinition)> = tools
+            .iter()
+            .map(|t| (self.relevance(t, &task_words), t))
+            .filter(|(score, _)| *score > 0.0)
+            .collect();
+        ranked.sort_by(|a, b| {
+            b.0.partial_cmp(&a.0)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.1.name.cmp(&b.1.name))
+        });
+        ranked.truncate(recommend);
+
+        let mut catalog = format!("Available tools ({} total):\n", tools.len());
+        if ranked.is_empty() {
+            for (ns, names) in by_namespace(tools.iter(), describe) {
+                catalog.push_str(&format!("[{ns}] {}\n", names.join(", ")));
+            }
+            catalog.push('\n');
+            return catalog;
+        }
+
+        catalog.push_str("Recommended for this task:\n");
+        for (_, tool) in &ranked {
+            catalog.push_str(&format!("- {}", describe(tool)));
+            let mut notes = Vec::new();
+            if tool.experimental {
+                notes.push("experimental".to_string());
+            }
+            if let Some(rate) = self.success_rate(&tool.name) {
+                notes.push(format!("{:.0}% success", rate * 100.0));
+            }
+            let inputs = self.typical_inputs(&tool.name);
+            if !inputs.is_empty() {
+                notes.push(format!("typical input: {}", inputs.join(", ")));
+            }
+            if !notes.is_empty() {
+                catalog.push_str(&format!(" ({})", notes.join("; ")));
+            }
+            catalog.push('\n');
+            if let Some(args) = argument_shape(&tool.input_schema) {
+                catalog.push_str(&format!("  args: {args}\n"));
+            }
+        }
+
+        let recommended: HashSet<&str> = ranked.iter().map(|(_, t)| t.name.as_str()).collect();
+        let rest = tools
+            .iter()
+            .filter(|t| !recommended.contains(t.name.as_str()));
+        let rest = by_namespace(rest, |t| t.name.clone());
+        if !rest.is_empty() {
+            catalog.push_str("Other tools:\n");
+            for (ns, names) in rest {
+                catalog.push_str(&format!("[{ns}] {}\n", names.join(", ")));
+            }
+        }
+        catalog.push('\n');
+        catalog
+    }
+}
+
+fn describe(tool: &ToolDefinition) -> String {
+    if tool.description.is_empty() {
+        tool.name.clone()
+    } else {
+        format!("{} — {}", tool.name, tool.description)
+    }
+}
+
+/// A tool's arguments as declared by its input schema, required ones
+/// first: `{path: string, encoding?: auto|utf8|base64}`
+fn argument_shape(schema: &[u8]) -> Option<String> {
+    let schema: serde_json::Value = serde_json::from_slice(schema).ok()?;
+    let properties = schema.get("properties")?.as_object()?;
+    if properties.is_empty() {
+        return None;
+    }
+    let required: HashSet<&str> = schema
+        .get("required")
+        .and_then(|r| r.as_array())
+        .map(|r| r.iter().filter_map(|v| v.as_str()).collect())
+        .unwrap_or_default();
+    let mut names: Vec<&String> = properties.keys().collect();
+    names.sort_by_key(|name| !required.contains(name.as_str()));
+    let args: Vec<String> = names
+        .into_iter()
+        .map(|name| {
+            let optional = if required.contains(name.as_str()) {
+                ""
+            } else {
+                "?"
+            };
+            format!("{name}{optional}: {}", value_shape(&properties[name]))
+        })
+        .collect();
+    Some(format!("{{{}}}", args.join(", ")))
+}
+
+/// The type of one schema property: its allowed values, or its type
+fn value_shape(property: &serde_json::Value) -> String {
+    if let Some(values) = property.get("enum").and_then(|e| e.as_array()) {
+        let values: Vec<String> = values
+            .iter()
+            .map(|v| {
+                v.as_str()
+                    .map(str::to_string)
+                    .unwrap_or_else(|| v.to_string())
+            })
+            .collect();
+        return values.join("|");
+    }
+    match property.get("type") {
+        Some(serde_json::Value::String(t)) if t == "array" => match property.get("items") {
+            Some(items) => format!("[{}]", value_shape(items)),
+            None => "array".to_string(),
+        },
+        Some(serde_json::Value::String(t)) => t.clone(),
+        Some(serde_json::Value::Array(types)) => types
+            .iter()
+            .filter_map(|t| t.as_str())
+            .collect::<Vec<_>>()
+            .join("|"),
+        _ => "any".to_string(),
+    }
+}
+
+/// Tools rendered by `render`, grouped by namespace in name order
+fn by_namespace<'a>(
+    tools: impl Iterator<Item = &'a ToolDefinition>,
+    render: impl Fn(&ToolDefinition) -> String,
+) -> BTreeMap<String, Vec<String>> {
+    let mut sorted: Vec<&ToolDefinition> = tools.collect();
+    sorted.sort_by(|a, b| a.name.cmp(&b.name));
+    let mut groups: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for tool in sorted {
+        let ns = if tool.namespace.is_empty() {
+            "other".to_string()
+        } else {
+            tool.namespace.clone()
+        };
+        groups.entry(ns).or_default().push(render(tool));
+    }
+    groups
+}
+
+/// Distinct lowercase words of `text`, ignoring short and common words
+fn words(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.len() >= 3)
+        .map(str::to_lowercase)
+        .filter(|w| !STOP_WORDS.contains(&w.as_str()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool(name: &str, description: &str) -> ToolDefinition {
+        ToolDefinition {
+            name: name.into(),
+            namespace: name.split('.').next().unwrap_or_default().into(),
+            description: description.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_record_tracks_rates_and_inputs() {
+        let mut usage = ToolUsage::default();
+        usage.record(
+            "fs.search",
+            "find logs",
+            br#"{"path": "/var", "pattern": "*.log"}"#,
+            true,
+        );
+        usage.record("fs.search", "find logs", br#"{"path": "/etc"}"#, true);
+        assert_eq!(usage.success_rate("fs.search"), None);
+        usage.record("fs.search", "find logs", br#"{"path": "/"}"#, false);
+        usage.record("fs.search", "find logs", br#"{"path": "/tmp"}"#, true);
+
+        assert_eq!(usage.success_rate("fs.search"), Some(0.75));
+        assert_eq!(usage.typical_inputs("fs.search"), vec!["path"]);
+        let summary = &usage.summaries()[0];
+        assert_eq!(summary.invocations, 4);
+        assert_eq!(summary.successes, 3);
+    }
+
+    #[test]
+    fn test_catalog_ranks_and_trims() {
+        let tools = vec![
+            tool("monitor.disk", "Show disk usage per mount"),
+            tool("fs.disk_usage", "Measure the size of a directory"),
+            tool("net.ping", "Ping a host"),
+            tool("pkg.search", "Search available packages"),
+        ];
+        let mut usage = ToolUsage::default();
+        for _ in 0..3 {
+            usage.record(
+                "fs.disk_usage",
+                "Find what is filling /var",
+                br#"{"path": "/var"}"#,
+                true,
+            );
+        }
+
+        let catalog = usage.catalog("Find what is filling the disk under /var", &tools, 2);
+        let recommended = catalog.find("Recommended").unwrap();
+        let others = catalog.find("Other tools").unwrap();
+        let learned = catalog.find("fs.disk_usage —").unwrap();
+        assert!(recommended < learned && learned < catalog.find("monitor.disk —").unwrap());
+        assert!(catalog.contains("100% success; typical input: path"));
+        // Unrelated tools are listed by name only
+        assert!(catalog[others..].contains("[net] net.ping"));
+        assert!(!catalog.contains("Ping a host"));
+
+        // No relevant tool: the full catalog
+        let catalog = ToolUsage::default().catalog("xyzzy", &tools, 2);
+        assert!(catalog.contains("[net] net.ping — Ping a host"));
+        assert!(!catalog.contains("Recommended"));
+    }
+
+    #[test]
+    fn test_argument_shape_from_input_schema() {
+        let schema = br#"{
+            "type": "object",
+            "properties": {
+                "encoding": { "type": "string", "enum": ["auto", "utf8", "base64"] },
+                "path": { "type": "string" },
+                "paths": { "type": "array", "items": { "type": "string" } },
+                "length": { "type": "integer" }
+            },
+            "required": ["path"]
+        }"#;
+        assert_eq!(
+            argument_shape(schema).unwrap(),
+            "{path: string, encoding?: auto|utf8|base64, length?: integer, paths?: [string]}"
+        );
+        assert!(argument_shape(b"").is_none());
+        assert!(argument_shape(br#"{"type": "object", "properties": {}}"#).is_none());
+
+        let mut read = tool("fs.read", "Read file contents");
+        read.input_schema = schema.to_vec();
+        let catalog = ToolUsage::default().catalog("read a file", &[read], 2);
+        assert!(catalog.contains("\n  args: {path: string, encoding?: "));
+    }
+}
@@ -0,0 +1,434 @@
+//! Usage — what each goal cost to run
+//!
+//! Tokens and API cost come from the gateway's and runtime's inference
+//! responses, CPU time and bytes written from the tools service's execute
+//! responses, wall clock from the time the orchestrator spent working on
+//! the goal's tasks, and agents' tokens and durations from the task results
+//! they report. Usage is recorded against the goal whose work incurred it
+//! and rolled up over subgoals when read, so `GetGoalStatus`,
+//! `GET /api/goals/:goal_id/usage` and the console's ranking of the most
+//! expensive goals all include the work a goal spawned.
+//!
+//! Like the workload, the goal being charged is carried as a task-local, so
+//! the request builders record what their calls used without every
+//! signature on the way passing it down. Tasks spawned from inside a scope
+//! re-enter it with [`scope`].
+//!
+//! A goal's metadata may set a budget for it and its subgoals:
+//! `budget_tokens`, `budget_usd` and `budget_policy` ("downgrade", the
+//! default, or "fail"). Gateway calls carry the goal they are charged to
+//! and the nearest budget up its ancestry, with what the ledger says was
+//! already spent against it; the gateway enforces the budget.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+
+use crate::goal_engine::GoalEngine;
+use crate::proto::api_gateway::GoalBudget;
+use crate::proto::tools::ExecuteResponse;
+
+pub const USAGE_DB_PATH: &str = "/var/lib/aios/data/usage.db";
+
+/// Resources consumed on behalf of a goal
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct GoalUsage {
+    pub tokens_used: i64,
+    /// Estimated API cost; local inference is free
+    pub cost_usd: f64,
+    pub inference_calls: i32,
+    pub tool_calls: i32,
+    /// CPU time of the tools' handler threads
+    pub tool_cpu_ms: i64,
+    pub bytes_written: i64,
+    pub wall_clock_ms: i64,
+}
+
+impl std::ops::AddAssign<&GoalUsage> for GoalUsage {
+    fn add_assign(&mut self, other: &GoalUsage) {
+        self.tokens_used += other.tokens_used;
+        self.cost_usd += other.cost_usd;
+        self.inference_calls += other.inference_calls;
+        self.tool_calls += other.tool_calls;
+        self.tool_cpu_ms += other.tool_cpu_ms;
+        self.bytes_written += other.bytes_written;
+        self.wall_clock_ms += other.wall_clock_ms;
+    }
+}
+
+impl From<GoalUsage> for crate::proto::orchestrator::GoalUsage {
+    fn from(usage: GoalUsage) -> Self {
+        Self {
+            tokens_used: usage.tokens_used,
+            cost_usd: usage.cost_usd,
+            inference_calls: usage.inference_calls,
+            tool_calls: usage.tool_calls,
+            tool_cpu_ms: usage.tool_cpu_ms,
+            bytes_written: usage.bytes_written,
+            wall_clock_ms: usage.wall_clock_ms,
+        }
+    }
+}
+
+/// SQLite-backed usage totals, one row per goal
+#[derive(Default)]
+pub struct UsageLedger {
+    conn: Option<Mutex<rusqlite::Connection>>,
+}
+
+impl UsageLedger {
+    /// A ledger backed by the database at `path`; usage is not recorded if
+    /// it cannot be opened
+    pub fn open(path: &str) -> Self {
+        match Self::open_db(path) {
+            Ok(conn) => Self {
+                conn: Some(Mutex::new(conn)),
+            },
+            Err(e) => {
+                warn!("Usage ledger disabled: {e}");
+                Self::default()
+            }
+        }
+    }
+
+    fn open_db(path: &str) -> Result<rusqlite::Connection> {
+        if let Some(parent) = std::path::Path::new(path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = rusqlite::Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS goal_usage (
+                goal_id TEXT PRIMARY KEY,
+                tokens_used INTEGER NOT NULL,
+                cost_usd REAL NOT NULL,
+                inference_calls INTEGER NOT NULL,
+                tool_calls INTEGER NOT NULL,
+                tool_cpu_ms INTEGER NOT NULL,
+                bytes_written INTEGER NOT NULL,
+                wall_clock_ms INTEGER NOT NULL
+            );",
+        )?;
+        Ok(conn)
+    }
+
+    /// Add `delta` to what `goal_id` has used
+    pub fn record(&self, goal_id: &str, delta: &GoalUsage) -> Result<()> {
+        let Some(ref conn) = self.conn else {
+            return Ok(());
+        };
+        let conn = conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        conn.execute(
+            "INSERT INTO goal_usage (goal_id, tokens_used, cost_usd, inference_calls, tool_calls,
+                tool_cpu_ms, bytes_written, wall_clock_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+             ON CONFLICT(goal_id) DO UPDATE SET
+                tokens_used = tokens_used + excluded.tokens_used,
+                cost_usd = cost_usd + excluded.cost_usd,
+                inference_calls = inference_calls + excluded.inference_calls,
+                tool_calls = tool_calls + excluded.tool_calls,
+                tool_cpu_ms = tool_cpu_ms + excluded.tool_cpu_ms,
+                bytes_written = bytes_written + excluded.bytes_written,
+                wall_clock_ms = wall_clock_ms + excluded.wall_clock_ms",
+            rusqlite::params![
+                goal_id,
+                delta.tokens_used,
+                delta.cost_usd,
+                delta.inference_calls,
+                delta.tool_calls,
+                delta.tool_cpu_ms,
+                delta.bytes_written,
+                delta.wall_clock_ms,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Usage of every goal that has any
+    pub fn all(&self) -> Result<Vec<(String, GoalUsage)>> {
+        let Some(ref conn) = self.conn else {
+            return Ok(Vec::new());
+        };
+        let conn = conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let mut stmt = conn.prepare(
+            "SELECT goal_id, tokens_used, cost_usd, inference_calls, tool_calls, tool_cpu_ms,
+                bytes_written, wall_clock_ms
+             FROM goal_usage",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get(0)?,
+                GoalUsage {
+                    tokens_used: row.get(1)?,
+                    cost_usd: row.get(2)?,
+                    inference_calls: row.get(3)?,
+                    tool_calls: row.get(4)?,
+                    tool_cpu_ms: row.get(5)?,
+                    bytes_written: row.get(6)?,
+                    wall_clock_ms: row.get(7)?,
+                },
+            ))
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// Usage of `goal_id`'s own tasks
+    pub fn own(&self, goal_id: &str) -> GoalUsage {
+        self.all()
+            .unwrap_or_default()
+            .into_iter()
+            .find(|(id, _)| id == goal_id)
+            .map(|(_, usage)| usage)
+            .unwrap_or_default()
+    }
+
+    /// Usage of `goal_id` and all its subgoals
+    pub fn total(&self, goals: &GoalEngine, goal_id: &str) -> GoalUsage {
+        let mut total = GoalUsage::default();
+        for (id, usage) in self.all().unwrap_or_default() {
+            if goals.ancestry(&id).iter().any(|a| a == goal_id) {
+                total += &usage;
+            }
+        }
+        total
+    }
+
+    /// Root goals with their rolled-up usage, most expensive first
+    pub fn ranking(&self, goals: &GoalEngine) -> Vec<(String, GoalUsage)> {
+        let mut roots: std::collections::HashMap<String, GoalUsage> =
+            std::collections::HashMap::new();
+        for (id, usage) in self.all().unwrap_or_default() {
+            let root = goals.ancestry(&id).pop().unwrap_or(id);
+            *roots.entry(root).or_default() += &usage;
+        }
+        let mut ranking: Vec<_> = roots.into_iter().collect();
+        ranking.sort_by(|a, b| {
+            b.1.cost_usd
+                .total_cmp(&a.1.cost_usd)
+                .then(b.1.tokens_used.cmp(&a.1.tokens_used))
+                .then(b.1.wall_clock_ms.cmp(&a.1.wall_clock_ms))
+        });
+        ranking
+    }
+}
+
+/// Budget set by the metadata of `goal_id` or its nearest ancestor that
+/// has one, with what the goal holding it and its subgoals have spent
+pub fn goal_budget(ledger: &UsageLedger, goals: &GoalEngine, goal_id: &str) -> Option<GoalBudget> {
+    goals.ancestry(goal_id).into_iter().find_map(|id| {
+        let metadata: serde_json::Value = serde_json::from_slice(goals.get_metadata(&id)?).ok()?;
+        let max_tokens = metadata.get("budget_tokens").and_then(|v| v.as_i64());
+        let max_usd = metadata.get("budget_usd").and_then(|v| v.as_f64());
+        if max_tokens.is_none() && max_usd.is_none() {
+            return None;
+        }
+        let spent = ledger.total(goals, &id);
+        Some(GoalBudget {
+            max_tokens: max_tokens.unwrap_or_default(),
+            max_usd: max_usd.unwrap_or_default(),
+            on_exceeded: metadata
+                .get("budget_policy")
+                .and_then(|v| v.as_str())
+                .unwrap_or("downgrade")
+                .to_string(),
+            spent_tokens: spent.tokens_used,
+            spent_usd: spent.cost_usd,
+            goal_id: id,
+        })
+    })
+}
+
+/// The goal that the requests of a scope are charged to
+#[derive(Clone)]
+pub struct Charge {
+    ledger: Arc<UsageLedger>,
+    goal_id: String,
+    budget: Option<GoalBudget>,
+}
+
+impl Charge {
+    /// Charge `goal_id`; None for work outside any goal
+    pub fn new(ledger: &Arc<UsageLedger>, goals: &GoalEngine, goal_id: &str) -> Option<Self> {
+        (!goal_id.is_empty()).then(|| Self {
+            ledger: ledger.clone(),
+            goal_id: goal_id.to_string(),
+            budget: goal_budget(ledger, goals, goal_id),
+        })
+    }
+
+    pub fn record(&self, delta: &GoalUsage) {
+        if let Err(e) = self.ledger.record(&self.goal_id, delta) {
+            warn!("Failed to record usage of goal {}: {e}", self.goal_id);
+        }
+    }
+}
+
+tokio::task_local! {
+    static CHARGE: Option<Charge>;
+}
+
+/// Run `fut` with its usage charged to `charge`
+pub async fn scope<F: Future>(charge: Option<Charge>, fut: F) -> F::Output {
+    CHARGE.scope(charge, fut).await
+}
+
+/// Charge of the current scope, None outside any
+pub fn current() -> Option<Charge> {
+    CHARGE.try_with(|c| c.clone()).ok().flatten()
+}
+
+/// Goal the current scope's gateway calls are attributed to, "" outside any
+pub fn current_goal_id() -> String {
+    current().map(|c| c.goal_id).unwrap_or_default()
+}
+
+/// Budget the current scope's gateway calls are held to
+pub fn current_budget() -> Option<GoalBudget> {
+    current().and_then(|c| c.budget)
+}
+
+/// Charge an inference call to the current scope's goal
+pub fn record_inference(tokens_used: i64, cost_usd: f64) {
+    if let Some(charge) = current() {
+        charge.record(&GoalUsage {
+            tokens_used,
+            cost_usd,
+            inference_calls: 1,
+            ..Default::default()
+        });
+    }
+}
+
+/// Charge a tool call to the current scope's goal
+pub fn record_tool(response: &ExecuteResponse) {
+    if let Some(charge) = current() {
+        charge.record(&GoalUsage {
+            tool_calls: 1,
+            tool_cpu_ms: response.cpu_time_ms,
+            bytes_written: response.bytes_written,
+            ..Default::default()
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_usage_rolls_up_over_subgoals() {
+        let mut goals = GoalEngine::new();
+        let root = goals
+            .submit_goal("Upgrade the fleet".into(), 1, "user".into())
+            .await
+            .unwrap();
+        let other = goals
+            .submit_goal("Rotate logs".into(), 1, "proactive".into())
+            .await
+            .unwrap();
+        let child = goals
+            .spawn_subgoals(
+                &root,
+                "t1",
+                vec![crate::goal_engine::SubgoalSpec {
+                    description: "Upgrade node-1".into(),
+                    priority: None,
+                }],
+            )
+            .await
+            .unwrap()
+            .remove(0);
+
+        let ledger = Arc::new(UsageLedger::open(":memory:"));
+        scope(Charge::new(&ledger, &goals, &root), async {
+            record_inference(1200, 0.02);
+            record_inference(800, 0.01);
+        })
+        .await;
+        scope(Charge::new(&ledger, &goals, &child), async {
+            record_tool(&ExecuteResponse {
+                cpu_time_ms: 40,
+                bytes_written: 4096,
+                ..Default::default()
+            });
+        })
+        .await;
+        ledger
+            .record(
+                &other,
+                &GoalUsage {
+                    wall_clock_ms: 500,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        // Outside any scope nothing is charged
+        record_inference(5000, 1.0);
+
+        let own = ledger.own(&child);
+        assert_eq!(own.tool_calls, 1);
+        assert_eq!(own.bytes_written, 4096);
+        assert_eq!(own.tokens_used, 0);
+
+        let total = ledger.total(&goals, &root);
+        assert_eq!(total.tokens_used, 2000);
+        assert_eq!(total.inference_calls, 2);
+        assert!((total.cost_usd - 0.03).abs() < 1e-9);
+        assert_eq!(total.tool_cpu_ms, 40);
+        assert_eq!(ledger.own(&root).tool_calls, 0);
+
+        let ranking = ledger.ranking(&goals);
+        assert_eq!(ranking.len(), 2);
+        assert_eq!(ranking[0].0, root);
+        assert_eq!(ranking[1].0, other);
+    }
+
+    #[tokio::test]
+    async fn test_subgoals_carry_the_nearest_budget() {
+        let mut goals = GoalEngine::new();
+        let root = goals
+            .submit_goal("Upgrade the fleet".into(), 1, "user".into())
+            .await
+            .unwrap();
+        goals.set_metadata(
+            &root,
+            br#"{"budget_usd": 0.5, "budget_policy": "fail"}"#.to_vec(),
+        );
+        let child = goals
+            .spawn_subgoals(
+                &root,
+                "t1",
+                vec![crate::goal_engine::SubgoalSpec {
+                    description: "Upgrade node-1".into(),
+                    priority: None,
+                }],
+            )
+            .await
+            .unwrap()
+            .remove(0);
+        let ledger = Arc::new(UsageLedger::open(":memory:"));
+        scope(Charge::new(&ledger, &goals, &child), async {
+            record_inference(700, 0.2);
+        })
+        .await;
+
+        let charge = Charge::new(&ledger, &goals, &child);
+        let (goal_id, budget) =
+            scope(charge, async { (current_goal_id(), current_budget()) }).await;
+        assert_eq!(goal_id, child);
+        let budget = budget.unwrap();
+        assert_eq!(budget.goal_id, root);
+        assert_eq!(budget.max_usd, 0.5);
+        assert_eq!(budget.max_tokens, 0);
+        assert_eq!(budget.on_exceeded, "fail");
+        assert_eq!(budget.spent_tokens, 700);
+
+        assert!(goal_budget(&ledger, &goals, "unknown").is_none());
+        assert_eq!(current_goal_id(), "");
+    }
+}
@@ -0,0 +1,89 @@
+//! Workload — which requests may use capacity reserved for interactive work
+//!
+//! Local inference slots, tool-execution slots and a share of the API
+//! budget are reserved for interactive work so that background autonomy
+//! cannot starve the chat and the console. The runtime, tools and API
+//! gateway enforce the reservations; the orchestrator tags its requests
+//! with `workload = "interactive"` when they serve a goal a person
+//! submitted (a root goal whose source is the console, the chat or the
+//! CLI), and dispatches those goals' tasks ahead of background ones.
+//!
+//! The workload of the reasoning loop being run is carried as a
+//! task-local, so the request builders it reaches tag their calls without
+//! every signature on the way passing it down. Tasks spawned from inside
+//! a loop re-enter the scope with [`scope`].
+
+use std::future::Future;
+
+use crate::goal_engine::GoalEngine;
+
+/// Workload tag of requests that may use reserved capacity
+pub const INTERACTIVE: &str = "interactive";
+
+/// Workload tag of everything else
+pub const BACKGROUND: &str = "";
+
+/// Goal sources that stand for a person waiting on the result
+const INTERACTIVE_SOURCES: &[&str] = &["management-console", "chat-console", "cli", "user"];
+
+tokio::task_local! {
+    static WORKLOAD: &'static str;
+}
+
+/// Workload of the goal: interactive when its root goal was submitted by a
+/// person, background otherwise
+pub fn for_goal(goals: &GoalEngine, goal_id: &str) -> &'static str {
+    let root = goals.ancestry(goal_id).pop().unwrap_or_default();
+    match goals.goal_source(&root) {
+        Some(source) if INTERACTIVE_SOURCES.contains(&source) => INTERACTIVE,
+        _ => BACKGROUND,
+    }
+}
+
+/// Run `fut` with `workload` as the workload of the requests it makes
+pub async fn scope<F: Future>(workload: &'static str, fut: F) -> F::Output {
+    WORKLOAD.scope(workload, fut).await
+}
+
+/// Workload of the current scope, background outside any
+pub fn current() -> &'static str {
+    WORKLOAD.try_with(|w| *w).unwrap_or(BACKGROUND)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_workload_follows_root_goal_source() {
+        let mut goals = GoalEngine::new();
+        let console = goals
+            .submit_goal("Restart nginx".into(), 1, "management-console".into())
+            .await
+            .unwrap();
+        let proactive = goals
+            .submit_goal("Rotate logs".into(), 1, "proactive".into())
+            .await
+            .unwrap();
+        let sub = goals
+            .spawn_subgoals(
+                &console,
+                "t1",
+                vec![crate::goal_engine::SubgoalSpec {
+                    description: "Check config".into(),
+                    priority: None,
+                }],
+            )
+            .await
+            .unwrap()
+            .remove(0);
+
+        assert_eq!(for_goal(&goals, &console), INTERACTIVE);
+        assert_eq!(for_goal(&goals, &sub), INTERACTIVE);
+        assert_eq!(for_goal(&goals, &proactive), BACKGROUND);
+        assert_eq!(for_goal(&goals, "missing"), BACKGROUND);
+
+        assert_eq!(current(), BACKGROUND);
+        assert_eq!(scope(INTERACTIVE, async { current() }).await, INTERACTIVE);
+    }
+}
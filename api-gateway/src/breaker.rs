@@ -0,0 +1,363 @@
+//! Provider Breakers — a circuit breaker per provider
+//!
+//! Every call the router makes to a provider is recorded in the provider's
+//! breaker: failed calls, calls refused for rate limiting (HTTP 429) and
+//! calls slower than `AIOS_BREAKER_SLOW_MS` (default 120000). A breaker
+//! opens when its provider is rate limited, or when at least
+//! `AIOS_BREAKER_MIN_CALLS` (default 5) of the last `AIOS_BREAKER_WINDOW`
+//! (default 20) calls were made and `AIOS_BREAKER_ERROR_RATE` (default 0.5)
+//! of them failed or were slow. The router skips a provider while its
+//! breaker is open: for `AIOS_BREAKER_COOLDOWN_SECS` (default 30), doubled
+//! for every consecutive opening up to 10 minutes, or for as long as the
+//! provider's Retry-After asked if that is longer. Once the cooldown ends
+//! the breaker is half-open and the next call is a probe, which closes the
+//! breaker if it succeeds and opens it again if it does not.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::proto::api_gateway::ProviderHealth;
+
+/// Longest cooldown of a breaker that keeps opening
+const MAX_COOLDOWN: Duration = Duration::from_secs(600);
+
+/// A provider refused a call for exceeding its rate limit (HTTP 429)
+#[derive(Debug)]
+pub struct RateLimited {
+    pub provider: String,
+    /// How long the provider asked callers to wait, if it said
+    pub retry_after: Option<Duration>,
+    pub body: String,
+}
+
+impl RateLimited {
+    /// Read a 429 response, including its Retry-After header (in seconds)
+    pub async fn from_response(provider: &str, response: reqwest::Response) -> Self {
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .map(Duration::from_secs);
+        Self {
+            provider: provider.to_string(),
+            retry_after,
+            body: response.text().await.unwrap_or_default(),
+        }
+    }
+}
+
+impl fmt::Display for RateLimited {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} API error 429 Too Many Requests: {}",
+            self.provider, self.body
+        )
+    }
+}
+
+impl std::error::Error for RateLimited {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    /// Calls go through
+    Closed,
+    /// Calls are skipped until the cooldown ends
+    Open,
+    /// The cooldown ended; the next call is a probe
+    HalfOpen,
+}
+
+impl BreakerState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BreakerState::Closed => "closed",
+            BreakerState::Open => "open",
+            BreakerState::HalfOpen => "half_open",
+        }
+    }
+}
+
+impl fmt::Display for BreakerState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// When breakers open, and for how long
+#[derive(Debug, Clone)]
+pub struct BreakerConfig {
+    /// Recent calls the error rate is computed over
+    pub window: usize,
+    /// Calls needed in the window before the error rate can open a breaker
+    pub min_calls: usize,
+    /// Share of failed or slow calls that opens a breaker
+    pub error_rate: f64,
+    /// Successful calls slower than this count as failures
+    pub slow_ms: i64,
+    /// Cooldown after the first opening
+    pub cooldown: Duration,
+}
+
+impl Default for BreakerConfig {
+    fn default() -> Self {
+        Self {
+            window: 20,
+            min_calls: 5,
+            error_rate: 0.5,
+            slow_ms: 120_000,
+            cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+impl BreakerConfig {
+    pub fn from_env() -> Self {
+        fn var<T: std::str::FromStr>(name: &str, default: T) -> T {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+        let defaults = Self::default();
+        Self {
+            window: var("AIOS_BREAKER_WINDOW", defaults.window).max(1),
+            min_calls: var("AIOS_BREAKER_MIN_CALLS", defaults.min_calls).max(1),
+            error_rate: var("AIOS_BREAKER_ERROR_RATE", defaults.error_rate),
+            slow_ms: var("AIOS_BREAKER_SLOW_MS", defaults.slow_ms),
+            cooldown: Duration::from_secs(var(
+                "AIOS_BREAKER_COOLDOWN_SECS",
+                defaults.cooldown.as_secs(),
+            )),
+        }
+    }
+}
+
+/// A recorded call
+#[derive(Debug, Clone, Copy)]
+struct Call {
+    failed: bool,
+    rate_limited: bool,
+    latency_ms: i64,
+}
+
+#[derive(Debug, Default)]
+struct Breaker {
+    calls: VecDeque<Call>,
+    open_until: Option<Instant>,
+    /// Consecutive openings, without a successful probe in between
+    trips: u32,
+    last_error: String,
+}
+
+impl Breaker {
+    fn state(&self, now: Instant) -> BreakerState {
+        match self.open_until {
+            None => BreakerState::Closed,
+            Some(until) if now < until => BreakerState::Open,
+            Some(_) => BreakerState::HalfOpen,
+        }
+    }
+
+    fn error_rate(&self) -> f64 {
+        if self.calls.is_empty() {
+            return 0.0;
+        }
+        self.calls.iter().filter(|c| c.failed).count() as f64 / self.calls.len() as f64
+    }
+
+    fn open(&mut self, cooldown: Duration, retry_after: Option<Duration>, now: Instant) {
+        let backoff = cooldown
+            .saturating_mul(1 << self.trips.min(16))
+            .min(MAX_COOLDOWN);
+        self.open_until = Some(now + backoff.max(retry_after.unwrap_or_default()));
+        self.trips += 1;
+    }
+}
+
+/// Circuit breakers of all providers
+pub struct ProviderBreakers {
+    config: BreakerConfig,
+    breakers: HashMap<String, Breaker>,
+}
+
+impl ProviderBreakers {
+    pub fn new(config: BreakerConfig) -> Self {
+        Self {
+            config,
+            breakers: HashMap::new(),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(BreakerConfig::from_env())
+    }
+
+    pub fn state(&self, provider: &str, now: Instant) -> BreakerState {
+        self.breakers
+            .get(provider)
+            .map_or(BreakerState::Closed, |b| b.state(now))
+    }
+
+    /// Whether calls to `provider` may be made
+    pub fn allows(&self, provider: &str, now: Instant) -> bool {
+        self.state(provider, now) != BreakerState::Open
+    }
+
+    /// Record a call to `provider`: its latency, or the error it failed
+    /// with. Returns the states before and after, if the call moved the
+    /// breaker.
+    pub fn record(
+        &mut self,
+        provider: &str,
+        outcome: Result<i64, &anyhow::Error>,
+        now: Instant,
+    ) -> Option<(BreakerState, BreakerState)> {
+        let config = &self.config;
+        let breaker = self.breakers.entry(provider.to_string()).or_default();
+        let before = breaker.state(now);
+
+        let rate_limited = outcome.err().and_then(|e| e.downcast_ref::<RateLimited>());
+        let call = match outcome {
+            Ok(latency_ms) => Call {
+                failed: latency_ms > config.slow_ms,
+                rate_limited: false,
+                latency_ms,
+            },
+            Err(e) => {
+                breaker.last_error = format!("{e:#}");
+                Call {
+                    failed: true,
+                    rate_limited: rate_limited.is_some(),
+                    latency_ms: 0,
+                }
+            }
+        };
+        breaker.calls.push_back(call);
+        while breaker.calls.len() > config.window {
+            breaker.calls.pop_front();
+        }
+
+        if let Some(limited) = rate_limited {
+            breaker.open(config.cooldown, limited.retry_after, now);
+        } else if before != BreakerState::Closed {
+            // A probe, or a call made while every provider was open
+            if call.failed {
+                breaker.open(config.cooldown, None, now);
+            } else {
+                breaker.open_until = None;
+                breaker.trips = 0;
+                breaker.calls.clear();
+            }
+        } else if breaker.calls.len() >= config.min_calls
+            && breaker.error_rate() >= config.error_rate
+        {
+            breaker.open(config.cooldown, None, now);
+        }
+
+        let after = breaker.state(now);
+        (after != before).then_some((before, after))
+    }
+
+    /// Breaker state and recent call statistics of `provider`
+    pub fn health(&self, provider: &str, configured: bool, now: Instant) -> ProviderHealth {
+        let Some(breaker) = self.breakers.get(provider) else {
+            return ProviderHealth {
+                provider: provider.to_string(),
+                state: BreakerState::Closed.as_str().to_string(),
+                configured,
+                ..Default::default()
+            };
+        };
+        let succeeded: Vec<i64> = breaker
+            .calls
+            .iter()
+            .filter(|c| !c.rate_limited && c.latency_ms > 0)
+            .map(|c| c.latency_ms)
+            .collect();
+        ProviderHealth {
+            provider: provider.to_string(),
+            state: breaker.state(now).as_str().to_string(),
+            configured,
+            calls: breaker.calls.len() as i32,
+            error_rate: breaker.error_rate(),
+            rate_limited: breaker.calls.iter().filter(|c| c.rate_limited).count() as i32,
+            avg_latency_ms: if succeeded.is_empty() {
+                0
+            } else {
+                succeeded.iter().sum::<i64>() / succeeded.len() as i64
+            },
+            retry_in_secs: breaker.open_until.map_or(0, |until| {
+                until.saturating_duration_since(now).as_secs() as i64
+            }),
+            trips: breaker.trips as i32,
+            last_error: breaker.last_error.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_breaker_opens_probes_and_closes() {
+        let mut breakers = ProviderBreakers::new(BreakerConfig::default());
+        let start = Instant::now();
+        let failure = anyhow::anyhow!("OpenAI API error 500 Internal Server Error");
+
+        // Failures below the minimum number of calls keep the breaker closed
+        for _ in 0..2 {
+            assert!(breakers.record("openai", Ok(800), start).is_none());
+        }
+        assert!(breakers.record("openai", Err(&failure), start).is_none());
+        assert!(breakers.record("openai", Err(&failure), start).is_none());
+        assert!(breakers.allows("openai", start));
+        // A slow call counts as a failure: 3 of 5 calls
+        assert_eq!(
+            breakers.record("openai", Ok(300_000), start),
+            Some((BreakerState::Closed, BreakerState::Open))
+        );
+        assert!(!breakers.allows("openai", start));
+        let health = breakers.health("openai", true, start);
+        assert_eq!(health.state, "open");
+        assert_eq!(health.calls, 5);
+        assert_eq!(health.avg_latency_ms, 100_533);
+        assert_eq!(health.retry_in_secs, 30);
+        assert!(health.last_error.contains("500"));
+
+        // After the cooldown a failed probe doubles it
+        let later = start + Duration::from_secs(30);
+        assert_eq!(breakers.state("openai", later), BreakerState::HalfOpen);
+        assert_eq!(
+            breakers.record("openai", Err(&failure), later),
+            Some((BreakerState::HalfOpen, BreakerState::Open))
+        );
+        assert_eq!(breakers.health("openai", true, later).retry_in_secs, 60);
+
+        // A successful probe closes it and forgets the failures
+        let later = later + Duration::from_secs(60);
+        assert_eq!(
+            breakers.record("openai", Ok(500), later),
+            Some((BreakerState::HalfOpen, BreakerState::Closed))
+        );
+        let health = breakers.health("openai", true, later);
+        assert_eq!((health.calls, health.trips), (0, 0));
+
+        // Rate limiting opens a breaker at once, for the Retry-After asked
+        let limited = anyhow::Error::new(RateLimited {
+            provider: "Qwen3".into(),
+            retry_after: Some(Duration::from_secs(90)),
+            body: String::new(),
+        });
+        assert_eq!(
+            breakers.record("qwen3", Err(&limited), start),
+            Some((BreakerState::Closed, BreakerState::Open))
+        );
+        let health = breakers.health("qwen3", true, start);
+        assert_eq!((health.rate_limited, health.retry_in_secs), (1, 90));
+        assert_eq!(breakers.health("claude", false, start).state, "closed");
+    }
+}
@@ -1,9 +1,61 @@
 //! Budget Manager — tracks API spending and enforces limits
+//!
+//! As the most-used provider budget fills up, requests are degraded step by
+//! step rather than cut off at 100%: past 50% spent they use cheaper
+//! models, past 80% their max_tokens is capped as well (the orchestrator
+//! also stops generating proactive goals), and past 95% only the local
+//! model serves them. Thresholds come from `AIOS_BUDGET_DEGRADE_PERCENT`
+//! ("50,80,95") and the token cap from `AIOS_DEGRADED_MAX_TOKENS` (1024).
+//!
+//! A share of the budget is reserved for interactive requests (chat and
+//! console-submitted goals, `workload = "interactive"`). Background
+//! requests climb the same ladder against the unreserved share only, so
+//! with the default 20% reserve they are served locally from 76% spent
+//! while interactive ones still reach paid providers. The share comes from
+//! `AIOS_INTERACTIVE_RESERVE_PERCENT` (20).
+//!
+//! Calls are also attributed to the goal they carry, and a goal may carry a
+//! token and dollar budget covering it and its subgoals. Once the goal's
+//! spend (the larger of what the gateway counted and what the orchestrator
+//! reports) reaches the budget, its further calls are served by the local
+//! model only, or rejected when the budget says `on_exceeded = "fail"`.
+//!
+//! Every move between degradation steps is published on the event bus as
+//! `gateway.budget_degradation`.
+
+use std::collections::{BTreeMap, HashMap};
 
 use chrono::Datelike;
 use tracing::{info, warn};
 
-use crate::proto::api_gateway::{BudgetStatus, UsageRecord, UsageResponse};
+use crate::proto::api_gateway::{
+    ApiInferRequest, BudgetStatus, GoalBudget, GoalCost, ProviderCost, UsageRecord, UsageResponse,
+};
+use crate::proto::common::InferenceResponse;
+use crate::stream::StreamUsage;
+
+/// Steps of the budget degradation ladder, least restrictive first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Degradation {
+    None,
+    /// Requests use each provider's cheaper model ("fast" model class)
+    CheaperProviders,
+    /// Cheaper models, and max_tokens is capped
+    ReducedTokens,
+    /// Only the local model serves requests
+    LocalOnly,
+}
+
+impl Degradation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Degradation::None => "",
+            Degradation::CheaperProviders => "cheaper_providers",
+            Degradation::ReducedTokens => "reduced_tokens",
+            Degradation::LocalOnly => "local_only",
+        }
+    }
+}
 
 /// Tracks API usage and enforces budget limits
 pub struct BudgetManager {
@@ -13,6 +65,24 @@ pub struct BudgetManager {
     openai_used: f64,
     usage_records: Vec<UsageRecord>,
     month_start: i64,
+    /// Percent spent at which each degradation step starts
+    degrade_thresholds: [f64; 3],
+    /// max_tokens cap from the ReducedTokens step on
+    degraded_max_tokens: i32,
+    /// Percent of the budget only interactive requests may spend
+    interactive_reserve_percent: f64,
+    /// Spend attributed to each goal
+    goals: HashMap<String, GoalSpend>,
+    /// Tokens and cost counted against each budget, by the goal holding it
+    budget_spend: HashMap<String, (i64, f64)>,
+    bus: Option<crate::bus::BusPublisher>,
+}
+
+/// What a goal's calls spent, by provider, and the budget they last carried
+#[derive(Default)]
+struct GoalSpend {
+    providers: BTreeMap<String, ProviderCost>,
+    budget: Option<GoalBudget>,
 }
 
 impl BudgetManager {
@@ -24,12 +94,54 @@ impl BudgetManager {
             openai_used: 0.0,
             usage_records: Vec::new(),
             month_start: current_month_start(),
+            degrade_thresholds: [50.0, 80.0, 95.0],
+            degraded_max_tokens: 1024,
+            interactive_reserve_percent: 20.0,
+            goals: HashMap::new(),
+            budget_spend: HashMap::new(),
+            bus: None,
         }
     }
 
-    /// Record API usage
-    pub fn record_usage(&mut self, provider: &str, tokens: i32, model: &str) {
+    /// Publish degradation step changes through `bus`
+    pub fn with_bus(mut self, bus: crate::bus::BusPublisher) -> Self {
+        self.bus = Some(bus);
+        self
+    }
+
+    /// Read the degradation thresholds and token cap from the environment
+    pub fn with_degradation_from_env(mut self) -> Self {
+        if let Ok(value) = std::env::var("AIOS_BUDGET_DEGRADE_PERCENT") {
+            let parsed: Vec<f64> = value
+                .split(',')
+                .filter_map(|v| v.trim().parse().ok())
+                .collect();
+            match <[f64; 3]>::try_from(parsed) {
+                Ok(thresholds) => self.degrade_thresholds = thresholds,
+                Err(_) => warn!(
+                    "Ignoring AIOS_BUDGET_DEGRADE_PERCENT={value}: expected three percentages"
+                ),
+            }
+        }
+        if let Some(max_tokens) = std::env::var("AIOS_DEGRADED_MAX_TOKENS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+        {
+            self.degraded_max_tokens = max_tokens;
+        }
+        if let Some(reserve) = std::env::var("AIOS_INTERACTIVE_RESERVE_PERCENT")
+            .ok()
+            .and_then(|v| v.parse::<f64>().ok())
+        {
+            self.interactive_reserve_percent = reserve.clamp(0.0, 90.0);
+        }
+        self
+    }
+
+    /// Record API usage, returning its estimated cost
+    pub fn record_usage(&mut self, provider: &str, tokens: i32, model: &str) -> f64 {
         self.maybe_reset_monthly();
+        let before = self.degradation();
 
         let cost = match provider {
             "claude" => {
@@ -71,6 +183,44 @@ impl BudgetManager {
                 (self.claude_used / self.claude_monthly_budget * 100.0) as u32
             );
         }
+
+        let after = self.degradation();
+        if after != before {
+            warn!(
+                "Budget degradation {:?} -> {:?} ({:.0}% spent)",
+                before,
+                after,
+                self.spent_percent()
+            );
+            if let Some(bus) = &self.bus {
+                bus.publish(
+                    "gateway.budget_degradation",
+                    serde_json::json!({
+                        "from": before.as_str(),
+                        "degradation": after.as_str(),
+                        "spent_percent": self.spent_percent(),
+                    }),
+                );
+            }
+        }
+        cost
+    }
+
+    /// Record a request's API usage under the agent and task it names,
+    /// returning its estimated cost
+    pub fn record_request_usage(
+        &mut self,
+        request: &ApiInferRequest,
+        provider: &str,
+        tokens: i32,
+        model: &str,
+    ) -> f64 {
+        let cost = self.record_usage(provider, tokens, model);
+        if let Some(record) = self.usage_records.last_mut() {
+            record.requesting_agent = request.requesting_agent.clone();
+            record.task_id = request.task_id.clone();
+        }
+        cost
     }
 
     /// Check if overall budget is exceeded
@@ -88,6 +238,197 @@ impl BudgetManager {
         }
     }
 
+    /// Share of the most-used provider budget spent, in percent
+    pub fn spent_percent(&self) -> f64 {
+        [
+            (self.claude_used, self.claude_monthly_budget),
+            (self.openai_used, self.openai_monthly_budget),
+        ]
+        .into_iter()
+        .filter(|(_, limit)| *limit > 0.0)
+        .map(|(used, limit)| used / limit * 100.0)
+        .fold(0.0, f64::max)
+    }
+
+    /// Degradation step for the current spend
+    pub fn degradation(&self) -> Degradation {
+        self.degradation_at(self.spent_percent())
+    }
+
+    /// Degradation step for a request of the given workload; background
+    /// requests see the spend as a share of the unreserved budget
+    pub fn degradation_for(&self, workload: &str) -> Degradation {
+        let spent = self.spent_percent();
+        if workload == "interactive" {
+            return self.degradation_at(spent);
+        }
+        let unreserved = (100.0 - self.interactive_reserve_percent).max(1.0);
+        self.degradation_at(spent * 100.0 / unreserved)
+    }
+
+    fn degradation_at(&self, spent: f64) -> Degradation {
+        let [cheaper, reduced, local] = self.degrade_thresholds;
+        if spent >= local {
+            Degradation::LocalOnly
+        } else if spent >= reduced {
+            Degradation::ReducedTokens
+        } else if spent >= cheaper {
+            Degradation::CheaperProviders
+        } else {
+            Degradation::None
+        }
+    }
+
+    /// `request` as the current degradation step allows it to run
+    pub fn degrade_request(&self, request: &ApiInferRequest) -> ApiInferRequest {
+        let level = self.degradation_for(&request.workload);
+        let mut request = request.clone();
+        if level >= Degradation::CheaperProviders {
+            request.model_class = "fast".to_string();
+        }
+        if level >= Degradation::ReducedTokens {
+            request.max_tokens = if request.max_tokens <= 0 {
+                self.degraded_max_tokens
+            } else {
+                request.max_tokens.min(self.degraded_max_tokens)
+            };
+        }
+        if level >= Degradation::LocalOnly {
+            // The local fallback chain leads to paid providers
+            request.preferred_provider = "local".to_string();
+            request.allow_fallback = false;
+        }
+        request
+    }
+
+    /// `request` as its goal's budget allows it to run: unchanged within
+    /// budget, local only past it, or an error past a budget that fails
+    pub fn apply_goal_budget(
+        &mut self,
+        request: &ApiInferRequest,
+    ) -> Result<ApiInferRequest, String> {
+        let mut request = request.clone();
+        if request.goal_id.is_empty() {
+            return Ok(request);
+        }
+        self.goals
+            .entry(request.goal_id.clone())
+            .or_default()
+            .budget = request.goal_budget.clone();
+        let Some(budget) = &request.goal_budget else {
+            return Ok(request);
+        };
+        if !self.is_goal_budget_exceeded(budget) {
+            return Ok(request);
+        }
+        let (tokens, cost) = self.goal_budget_spend(budget);
+        if budget.on_exceeded == "fail" {
+            return Err(format!(
+                "Budget of goal {} exceeded: {tokens} tokens, ${cost:.2} spent",
+                budget.goal_id
+            ));
+        }
+        info!(
+            "Budget of goal {} exceeded ({tokens} tokens, ${cost:.2}); serving it locally",
+            budget.goal_id
+        );
+        request.preferred_provider = "local".to_string();
+        request.allow_fallback = false;
+        Ok(request)
+    }
+
+    /// Record what a stream used, including a stream that ended early.
+    /// Returns its cost.
+    pub fn record_stream_usage(
+        &mut self,
+        request: &ApiInferRequest,
+        provider: &str,
+        usage: &StreamUsage,
+    ) -> f64 {
+        let tokens = usage.tokens_used();
+        if tokens == 0 {
+            return 0.0;
+        }
+        let cost = self.record_request_usage(request, provider, tokens, &usage.model);
+        let response = InferenceResponse {
+            tokens_used: tokens,
+            cost_usd: cost,
+            model_used: usage.model.clone(),
+            cached_tokens: usage.cached_tokens,
+            ..Default::default()
+        };
+        self.record_goal_usage(request, provider, &response);
+        cost
+    }
+
+    /// Attribute a served request to its goal and the goal's budget
+    pub fn record_goal_usage(
+        &mut self,
+        request: &ApiInferRequest,
+        provider: &str,
+        response: &InferenceResponse,
+    ) {
+        if request.goal_id.is_empty() {
+            return;
+        }
+        let tokens = i64::from(response.tokens_used);
+        let spend = self.goals.entry(request.goal_id.clone()).or_default();
+        let entry = spend
+            .providers
+            .entry(provider.to_string())
+            .or_insert_with(|| ProviderCost {
+                provider: provider.to_string(),
+                ..Default::default()
+            });
+        entry.tokens_used += tokens;
+        entry.cost_usd += response.cost_usd;
+        entry.requests += 1;
+        if let Some(budget) = &request.goal_budget {
+            let spent = self.budget_spend.entry(budget.goal_id.clone()).or_default();
+            spent.0 += tokens;
+            spent.1 += response.cost_usd;
+        }
+    }
+
+    /// Tokens and cost spent against `budget`
+    fn goal_budget_spend(&self, budget: &GoalBudget) -> (i64, f64) {
+        let (tokens, cost) = self
+            .budget_spend
+            .get(&budget.goal_id)
+            .copied()
+            .unwrap_or_default();
+        (tokens.max(budget.spent_tokens), cost.max(budget.spent_usd))
+    }
+
+    fn is_goal_budget_exceeded(&self, budget: &GoalBudget) -> bool {
+        let (tokens, cost) = self.goal_budget_spend(budget);
+        (budget.max_tokens > 0 && tokens >= budget.max_tokens)
+            || (budget.max_usd > 0.0 && cost >= budget.max_usd)
+    }
+
+    /// What the gateway attributed to `goal_id`
+    pub fn get_goal_cost(&self, goal_id: &str) -> GoalCost {
+        let Some(spend) = self.goals.get(goal_id) else {
+            return GoalCost {
+                goal_id: goal_id.to_string(),
+                ..Default::default()
+            };
+        };
+        let providers: Vec<ProviderCost> = spend.providers.values().cloned().collect();
+        GoalCost {
+            goal_id: goal_id.to_string(),
+            tokens_used: providers.iter().map(|p| p.tokens_used).sum(),
+            cost_usd: providers.iter().map(|p| p.cost_usd).sum(),
+            requests: providers.iter().map(|p| p.requests).sum(),
+            providers,
+            budget_exceeded: spend
+                .budget
+                .as_ref()
+                .is_some_and(|b| self.is_goal_budget_exceeded(b)),
+            budget: spend.budget.clone(),
+        }
+    }
+
     /// Get budget status
     pub fn get_status(&self) -> BudgetStatus {
         let now = chrono::Utc::now();
@@ -110,6 +451,8 @@ impl BudgetManager {
             days_remaining,
             daily_rate_usd: daily_rate,
             budget_exceeded: self.is_budget_exceeded(),
+            degradation: self.degradation().as_str().to_string(),
+            spent_percent: self.spent_percent(),
         }
     }
 
@@ -338,6 +681,21 @@ mod tests {
         assert!(record.timestamp > 0);
     }
 
+    #[test]
+    fn test_request_usage_is_attributed_to_the_requesting_agent() {
+        let mut bm = BudgetManager::new(100.0, 50.0);
+        let request = ApiInferRequest {
+            requesting_agent: "user:alice".into(),
+            task_id: "task-1".into(),
+            ..Default::default()
+        };
+        bm.record_request_usage(&request, "claude", 1000, "claude-sonnet");
+
+        let record = &bm.get_usage("", 30).records[0];
+        assert_eq!(record.requesting_agent, "user:alice");
+        assert_eq!(record.task_id, "task-1");
+    }
+
     #[test]
     fn test_pre_check_within_budget() {
         let bm = BudgetManager::new(100.0, 50.0);
@@ -370,6 +728,126 @@ mod tests {
         assert_eq!(bm.remaining_budget("unknown"), 0.0);
     }
 
+    #[test]
+    fn test_degradation_ladder() {
+        // Claude tokens cost $9 per million here: 1000 tokens are 9% of $0.1
+        let mut bm = BudgetManager::new(0.1, 0.0);
+        let request = ApiInferRequest {
+            max_tokens: 4096,
+            preferred_provider: "claude".into(),
+            allow_fallback: true,
+            workload: "interactive".into(),
+            ..Default::default()
+        };
+        assert_eq!(bm.degradation(), Degradation::None);
+        assert_eq!(bm.degrade_request(&request), request);
+
+        bm.record_usage("claude", 6000, "claude-sonnet");
+        assert_eq!(bm.degradation(), Degradation::CheaperProviders);
+        let degraded = bm.degrade_request(&request);
+        assert_eq!(degraded.model_class, "fast");
+        assert_eq!(degraded.max_tokens, 4096);
+
+        bm.record_usage("claude", 3000, "claude-sonnet");
+        assert_eq!(bm.degradation(), Degradation::ReducedTokens);
+        assert_eq!(bm.degrade_request(&request).max_tokens, 1024);
+
+        bm.record_usage("claude", 2000, "claude-sonnet");
+        assert_eq!(bm.degradation(), Degradation::LocalOnly);
+        let degraded = bm.degrade_request(&request);
+        assert_eq!(degraded.preferred_provider, "local");
+        assert!(!degraded.allow_fallback);
+        assert_eq!(bm.get_status().degradation, "local_only");
+    }
+
+    #[test]
+    fn test_background_requests_leave_interactive_reserve() {
+        // 9000 tokens are 81% of the budget: past the 20% reserve's start
+        let mut bm = BudgetManager::new(0.1, 0.0);
+        bm.record_usage("claude", 9000, "claude-sonnet");
+        let background = ApiInferRequest {
+            preferred_provider: "claude".into(),
+            allow_fallback: true,
+            ..Default::default()
+        };
+        let interactive = ApiInferRequest {
+            workload: "interactive".into(),
+            ..background.clone()
+        };
+
+        assert_eq!(bm.degradation_for(""), Degradation::LocalOnly);
+        assert_eq!(bm.degrade_request(&background).preferred_provider, "local");
+        assert_eq!(
+            bm.degradation_for("interactive"),
+            Degradation::ReducedTokens
+        );
+        assert_eq!(
+            bm.degrade_request(&interactive).preferred_provider,
+            "claude"
+        );
+    }
+
+    #[test]
+    fn test_goal_budget_attribution_and_enforcement() {
+        let mut bm = BudgetManager::new(100.0, 50.0);
+        let budget = GoalBudget {
+            goal_id: "root".into(),
+            max_tokens: 1500,
+            ..Default::default()
+        };
+        let request = ApiInferRequest {
+            preferred_provider: "claude".into(),
+            allow_fallback: true,
+            goal_id: "child".into(),
+            goal_budget: Some(budget.clone()),
+            ..Default::default()
+        };
+        let response = InferenceResponse {
+            tokens_used: 1000,
+            cost_usd: 0.01,
+            ..Default::default()
+        };
+
+        let allowed = bm.apply_goal_budget(&request).unwrap();
+        assert_eq!(allowed.preferred_provider, "claude");
+        bm.record_goal_usage(&allowed, "claude", &response);
+        bm.record_goal_usage(&allowed, "openai", &response);
+
+        let cost = bm.get_goal_cost("child");
+        assert_eq!(cost.tokens_used, 2000);
+        assert_eq!(cost.requests, 2);
+        assert_eq!(cost.providers.len(), 2);
+        assert!(cost.budget_exceeded);
+
+        // Past the budget the goal is served locally, or rejected
+        let downgraded = bm.apply_goal_budget(&request).unwrap();
+        assert_eq!(downgraded.preferred_provider, "local");
+        assert!(!downgraded.allow_fallback);
+        let failing = ApiInferRequest {
+            goal_budget: Some(GoalBudget {
+                on_exceeded: "fail".into(),
+                ..budget.clone()
+            }),
+            ..request.clone()
+        };
+        assert!(bm.apply_goal_budget(&failing).is_err());
+
+        // Spend the orchestrator reports counts too
+        let reported = ApiInferRequest {
+            goal_id: "other".into(),
+            goal_budget: Some(GoalBudget {
+                goal_id: "other".into(),
+                max_usd: 1.0,
+                spent_usd: 1.5,
+                on_exceeded: "fail".into(),
+                ..Default::default()
+            }),
+            ..request
+        };
+        assert!(bm.apply_goal_budget(&reported).is_err());
+        assert_eq!(bm.get_goal_cost("unknown").requests, 0);
+    }
+
     #[test]
     fn test_initial_state() {
         let bm = BudgetManager::new(100.0, 50.0);
@@ -0,0 +1,69 @@
+//! Event bus publisher — messages for the memory service's event bus
+//!
+//! Publishing never blocks the caller: messages are queued and sent by a
+//! background task, which connects to the memory service (`AIOS_MEMORY_ADDR`)
+//! when it has something to send. Messages queued while the memory service
+//! cannot be reached are dropped, as are messages beyond a full queue.
+
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+use crate::proto::memory::BusMessage;
+
+/// Messages queued for sending
+const QUEUE_CAPACITY: usize = 256;
+
+/// Source the messages are published as
+const SOURCE: &str = "api-gateway";
+
+/// Handle for publishing on the event bus; clones share the queue
+#[derive(Clone)]
+pub struct BusPublisher {
+    sender: mpsc::Sender<BusMessage>,
+}
+
+impl BusPublisher {
+    /// Start the sending task. Must be called within a Tokio runtime.
+    pub fn start() -> Self {
+        let addr = std::env::var("AIOS_MEMORY_ADDR")
+            .unwrap_or_else(|_| "http://127.0.0.1:50053".to_string());
+        let (sender, mut receiver) = mpsc::channel::<BusMessage>(QUEUE_CAPACITY);
+        tokio::spawn(async move {
+            let mut client = None;
+            while let Some(message) = receiver.recv().await {
+                if client.is_none() {
+                    match crate::identity::connect_memory(addr.clone()).await {
+                        Ok(c) => client = Some(c),
+                        Err(e) => {
+                            debug!("Dropped bus message on {}: {e}", message.topic);
+                            continue;
+                        }
+                    }
+                }
+                let Some(memory) = client.as_mut() else {
+                    continue;
+                };
+                let topic = message.topic.clone();
+                if let Err(e) = memory.publish(message).await {
+                    warn!("Failed to publish on {topic}: {e}");
+                    client = None;
+                }
+            }
+        });
+        Self { sender }
+    }
+
+    /// Queue `payload` for publishing on `topic`
+    pub fn publish(&self, topic: &str, payload: serde_json::Value) {
+        let message = BusMessage {
+            topic: topic.to_string(),
+            source: SOURCE.to_string(),
+            payload_json: payload.to_string().into_bytes(),
+            timestamp: 0,
+            event: None,
+        };
+        if self.sender.try_send(message).is_err() {
+            debug!("Event bus queue full; dropped a message on {topic}");
+        }
+    }
+}
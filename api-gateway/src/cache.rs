@@ -0,0 +1,369 @@
+//! Response Cache — content-addressed inference results kept in SQLite
+//!
+//! Single-shot requests are keyed by the SHA-256 of what determines their
+//! answer: the rendered prompt, the system prompt, the response schema and
+//! the model asked for (preferred provider, model class and max tokens). A
+//! repeat within the TTL (`AIOS_RESPONSE_CACHE_TTL_SECS`, default one hour)
+//! is answered from the cache at no cost, so identical goal decompositions
+//! and tool-catalog prompts are paid for once.
+//!
+//! Entries beyond `AIOS_RESPONSE_CACHE_MAX_ENTRIES` (1000) or
+//! `AIOS_RESPONSE_CACHE_MAX_BYTES` (64 MiB) are evicted, least recently
+//! used first. The cache is kept in `AIOS_RESPONSE_CACHE_DB` so it outlives
+//! restarts, and in memory when that file cannot be opened. Requests with
+//! `bypass_cache` skip the lookup; their response replaces the cached one.
+
+use anyhow::Result;
+use prost::Message;
+use rusqlite::{params, Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+use std::sync::Mutex;
+use tracing::{debug, warn};
+
+use crate::compress;
+use crate::proto::api_gateway::{ApiInferRequest, CacheStats};
+use crate::proto::common::InferenceResponse;
+
+const DEFAULT_DB_PATH: &str = "/var/lib/aios/api-gateway/response_cache.db";
+const DEFAULT_TTL_SECS: i64 = 3600;
+const DEFAULT_MAX_ENTRIES: usize = 1000;
+const DEFAULT_MAX_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Hits, misses and savings since the gateway started
+#[derive(Debug, Default)]
+struct Counters {
+    hits: i64,
+    misses: i64,
+    evictions: i64,
+    tokens_saved: i64,
+    cost_saved_usd: f64,
+}
+
+/// Inference responses by request digest
+pub struct ResponseCache {
+    conn: Mutex<Connection>,
+    ttl_secs: i64,
+    max_entries: usize,
+    max_bytes: u64,
+    counters: Mutex<Counters>,
+}
+
+/// Digest of everything in `request` that determines its response
+pub fn key(request: &ApiInferRequest) -> String {
+    let prompt = compress::render(&request.prompt, &request.sections);
+    let max_tokens = request.max_tokens.to_string();
+    let mut hasher = Sha256::new();
+    for field in [
+        prompt.as_str(),
+        &request.system_prompt,
+        &request.response_schema,
+        &request.preferred_provider,
+        &request.model_class,
+        &max_tokens,
+    ] {
+        // Length-prefixed so field boundaries cannot shift
+        hasher.update((field.len() as u64).to_le_bytes());
+        hasher.update(field.as_bytes());
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+impl ResponseCache {
+    pub fn open(db_path: &str, ttl_secs: i64, max_entries: usize, max_bytes: u64) -> Result<Self> {
+        if let Some(parent) = std::path::Path::new(db_path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let conn = Connection::open(db_path)?;
+        conn.execute_batch("PRAGMA journal_mode=WAL; PRAGMA synchronous=NORMAL;")?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS responses (
+                key TEXT PRIMARY KEY,
+                response BLOB NOT NULL,
+                bytes INTEGER NOT NULL,
+                created_at INTEGER NOT NULL,
+                last_used_at INTEGER NOT NULL,
+                hits INTEGER NOT NULL DEFAULT 0
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_responses_last_used ON responses(last_used_at);",
+        )?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+            ttl_secs,
+            max_entries: max_entries.max(1),
+            max_bytes,
+            counters: Mutex::new(Counters::default()),
+        })
+    }
+
+    /// An empty cache held in memory
+    pub fn in_memory() -> Self {
+        Self::open(
+            ":memory:",
+            DEFAULT_TTL_SECS,
+            DEFAULT_MAX_ENTRIES,
+            DEFAULT_MAX_BYTES,
+        )
+        .expect("in-memory SQLite database")
+    }
+
+    pub fn from_env() -> Self {
+        let path =
+            std::env::var("AIOS_RESPONSE_CACHE_DB").unwrap_or_else(|_| DEFAULT_DB_PATH.into());
+        let ttl_secs = std::env::var("AIOS_RESPONSE_CACHE_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_TTL_SECS);
+        let max_entries = std::env::var("AIOS_RESPONSE_CACHE_MAX_ENTRIES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_MAX_ENTRIES);
+        let max_bytes = std::env::var("AIOS_RESPONSE_CACHE_MAX_BYTES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_MAX_BYTES);
+        Self::open(&path, ttl_secs, max_entries, max_bytes).unwrap_or_else(|e| {
+            warn!("Cannot open the response cache {path}: {e}; caching in memory");
+            Self::open(":memory:", ttl_secs, max_entries, max_bytes)
+                .expect("in-memory SQLite database")
+        })
+    }
+
+    /// The cached response to the request with digest `key`; a hit costs
+    /// nothing
+    pub fn get(&self, key: &str) -> Option<InferenceResponse> {
+        let now = chrono::Utc::now().timestamp();
+        let cached = self.lookup(key, now).unwrap_or_else(|e| {
+            warn!("Response cache lookup failed: {e}");
+            None
+        });
+        let mut counters = self.counters.lock().unwrap();
+        match cached {
+            Some(response) => {
+                counters.hits += 1;
+                counters.tokens_saved += i64::from(response.tokens_used);
+                counters.cost_saved_usd += response.cost_usd;
+                Some(InferenceResponse {
+                    cost_usd: 0.0,
+                    ..response
+                })
+            }
+            None => {
+                counters.misses += 1;
+                None
+            }
+        }
+    }
+
+    fn lookup(&self, key: &str, now: i64) -> Result<Option<InferenceResponse>> {
+        let conn = self.conn.lock().unwrap();
+        let row: Option<(Vec<u8>, i64)> = conn
+            .query_row(
+                "SELECT response, created_at FROM responses WHERE key = ?1",
+                params![key],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        let Some((blob, created_at)) = row else {
+            return Ok(None);
+        };
+        if now - created_at >= self.ttl_secs {
+            conn.execute("DELETE FROM responses WHERE key = ?1", params![key])?;
+            return Ok(None);
+        }
+        conn.execute(
+            "UPDATE responses SET last_used_at = ?2, hits = hits + 1 WHERE key = ?1",
+            params![key, now],
+        )?;
+        Ok(Some(InferenceResponse::decode(blob.as_slice())?))
+    }
+
+    /// Keep `response` as the answer to the request with digest `key`
+    pub fn put(&self, key: &str, response: &InferenceResponse) {
+        if let Err(e) = self.store(key, response, chrono::Utc::now().timestamp()) {
+            warn!("Failed to cache a response: {e}");
+        }
+    }
+
+    fn store(&self, key: &str, response: &InferenceResponse, now: i64) -> Result<()> {
+        let blob = response.encode_to_vec();
+        if blob.len() as u64 > self.max_bytes {
+            debug!("Response of {} bytes is too large to cache", blob.len());
+            return Ok(());
+        }
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO responses (key, response, bytes, created_at, last_used_at, hits)
+             VALUES (?1, ?2, ?3, ?4, ?4, 0)",
+            params![key, blob, blob.len() as i64, now],
+        )?;
+        let mut evicted = conn.execute(
+            "DELETE FROM responses WHERE created_at <= ?1",
+            params![now - self.ttl_secs],
+        )?;
+        // Least recently used first, until within both limits
+        loop {
+            let (entries, bytes): (i64, i64) = conn.query_row(
+                "SELECT COUNT(*), COALESCE(SUM(bytes), 0) FROM responses",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?;
+            if entries as usize <= self.max_entries && bytes as u64 <= self.max_bytes {
+                break;
+            }
+            evicted += conn.execute(
+                "DELETE FROM responses WHERE key = (
+                    SELECT key FROM responses ORDER BY last_used_at, created_at LIMIT 1
+                )",
+                [],
+            )?;
+        }
+        self.counters.lock().unwrap().evictions += evicted as i64;
+        Ok(())
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let (entries, size_bytes) = self
+            .conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT COUNT(*), COALESCE(SUM(bytes), 0) FROM responses",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap_or((0, 0));
+        let counters = self.counters.lock().unwrap();
+        let lookups = counters.hits + counters.misses;
+        CacheStats {
+            hits: counters.hits,
+            misses: counters.misses,
+            hit_rate: if lookups == 0 {
+                0.0
+            } else {
+                counters.hits as f64 / lookups as f64
+            },
+            entries,
+            size_bytes,
+            evictions: counters.evictions,
+            tokens_saved: counters.tokens_saved,
+            cost_saved_usd: counters.cost_saved_usd,
+            ttl_secs: self.ttl_secs,
+            max_entries: self.max_entries as i64,
+            max_bytes: self.max_bytes as i64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(prompt: &str, system_prompt: &str, response_schema: &str) -> ApiInferRequest {
+        ApiInferRequest {
+            prompt: prompt.into(),
+            system_prompt: system_prompt.into(),
+            response_schema: response_schema.into(),
+            ..Default::default()
+        }
+    }
+
+    fn response(text: &str, tokens_used: i32, cost_usd: f64) -> InferenceResponse {
+        InferenceResponse {
+            text: text.into(),
+            tokens_used,
+            latency_ms: 50,
+            model_used: "test-model".into(),
+            intelligence_level: "strategic".into(),
+            compression: None,
+            cached_tokens: 0,
+            cost_usd,
+        }
+    }
+
+    #[test]
+    fn test_key_covers_prompt_system_schema_and_model() {
+        let base = request("prompt1", "system1", "");
+        assert_eq!(key(&base), key(&request("prompt1", "system1", "")));
+        assert_ne!(key(&base), key(&request("prompt2", "system1", "")));
+        assert_ne!(key(&base), key(&request("prompt1", "system2", "")));
+        assert_ne!(
+            key(&base),
+            key(&request("prompt1", "system1", r#"{"type":"object"}"#))
+        );
+        let fast = ApiInferRequest {
+            model_class: "fast".into(),
+            ..base.clone()
+        };
+        assert_ne!(key(&base), key(&fast));
+        // Field boundaries are part of the digest
+        assert_ne!(key(&request("ab", "c", "")), key(&request("a", "bc", "")));
+    }
+
+    #[test]
+    fn test_hits_are_free_and_counted() {
+        let cache = ResponseCache::in_memory();
+        let k = key(&request("test prompt", "system", ""));
+        assert!(cache.get(&k).is_none());
+
+        cache.put(&k, &response("cached response", 100, 0.25));
+        let cached = cache.get(&k).unwrap();
+        assert_eq!(cached.text, "cached response");
+        assert_eq!(cached.tokens_used, 100);
+        assert_eq!(cached.model_used, "test-model");
+        assert_eq!(cached.cost_usd, 0.0);
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (1, 1, 1));
+        assert_eq!(stats.tokens_saved, 100);
+        assert!((stats.cost_saved_usd - 0.25).abs() < 1e-9);
+        assert!((stats.hit_rate - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_entries_expire_and_are_evicted_least_recently_used() {
+        let cache = ResponseCache::open(":memory:", 60, 2, DEFAULT_MAX_BYTES).unwrap();
+        cache.store("a", &response("a", 1, 0.0), 1000).unwrap();
+        cache.store("b", &response("b", 1, 0.0), 1001).unwrap();
+        // Using "a" makes "b" the least recently used
+        assert!(cache.lookup("a", 1002).unwrap().is_some());
+        cache.store("c", &response("c", 1, 0.0), 1003).unwrap();
+        assert!(cache.lookup("b", 1004).unwrap().is_none());
+        assert!(cache.lookup("a", 1004).unwrap().is_some());
+        assert_eq!(cache.stats().entries, 2);
+        assert_eq!(cache.stats().evictions, 1);
+
+        // Past the TTL
+        assert!(cache.lookup("c", 1063).unwrap().is_none());
+
+        // The byte limit holds too
+        let small = ResponseCache::open(":memory:", 60, 100, 200).unwrap();
+        for i in 0..10 {
+            let text = "x".repeat(50);
+            small
+                .store(&i.to_string(), &response(&text, 1, 0.0), 1000 + i)
+                .unwrap();
+        }
+        assert!(small.stats().size_bytes <= 200);
+        assert!(small.lookup("9", 1010).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_cache_persists_across_opens() {
+        let dir = std::env::temp_dir().join(format!("aios-cache-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("cache.db");
+        let path = path.to_str().unwrap();
+        {
+            let cache = ResponseCache::open(path, 3600, 10, DEFAULT_MAX_BYTES).unwrap();
+            cache.put("k", &response("kept", 5, 0.01));
+        }
+        let reopened = ResponseCache::open(path, 3600, 10, DEFAULT_MAX_BYTES).unwrap();
+        assert_eq!(reopened.get("k").unwrap().text, "kept");
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
@@ -2,16 +2,24 @@
 
 use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
 use tracing::info;
 
+use crate::breaker::RateLimited;
 use crate::proto::common::InferenceResponse;
+use crate::session::Turn;
+use crate::stream::{self, EventStream, StreamUsage};
+use crate::structured;
 
 /// Claude API client
+#[derive(Clone)]
 pub struct ClaudeClient {
     api_key: String,
     client: reqwest::Client,
     base_url: String,
     model: String,
+    /// Cheaper model for tasks planned as "fast" ("" disables)
+    fast_model: String,
 }
 
 #[derive(Serialize)]
@@ -19,14 +27,48 @@ struct ClaudeRequest {
     model: String,
     max_tokens: i32,
     temperature: f32,
-    system: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    system: Vec<ClaudeBlock>,
     messages: Vec<ClaudeMessage>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<ClaudeTool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+}
+
+#[derive(Serialize)]
+struct ClaudeTool {
+    name: String,
+    description: String,
+    input_schema: serde_json::Value,
 }
 
 #[derive(Serialize)]
 struct ClaudeMessage {
     role: String,
-    content: String,
+    content: Vec<ClaudeBlock>,
+}
+
+#[derive(Serialize)]
+struct ClaudeBlock {
+    #[serde(rename = "type")]
+    block_type: &'static str,
+    text: String,
+    /// Prompt caching breakpoint: the prefix up to this block is cached
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cache_control: Option<serde_json::Value>,
+}
+
+impl ClaudeBlock {
+    fn text(text: &str) -> Self {
+        Self {
+            block_type: "text",
+            text: text.to_string(),
+            cache_control: None,
+        }
+    }
 }
 
 #[derive(Deserialize)]
@@ -42,19 +84,29 @@ struct ClaudeResponse {
 struct ClaudeContent {
     #[serde(rename = "type")]
     content_type: String,
+    #[serde(default)]
     text: String,
+    /// Arguments of a tool_use block
+    #[serde(default)]
+    input: serde_json::Value,
 }
 
 #[derive(Deserialize)]
 struct ClaudeUsage {
     input_tokens: i32,
     output_tokens: i32,
+    #[serde(default)]
+    cache_creation_input_tokens: Option<i32>,
+    #[serde(default)]
+    cache_read_input_tokens: Option<i32>,
 }
 
 impl ClaudeClient {
     pub fn new(api_key: String) -> Self {
         let model = std::env::var("CLAUDE_MODEL")
             .unwrap_or_else(|_| "claude-sonnet-4-20250514".to_string());
+        let fast_model = std::env::var("CLAUDE_FAST_MODEL")
+            .unwrap_or_else(|_| "claude-3-5-haiku-latest".to_string());
         Self {
             api_key,
             client: reqwest::Client::builder()
@@ -63,9 +115,21 @@ impl ClaudeClient {
                 .unwrap_or_else(|_| reqwest::Client::new()),
             base_url: "https://api.anthropic.com".to_string(),
             model,
+            fast_model,
         }
     }
 
+    /// This client with the fast model, if one is configured
+    pub fn fast_variant(&self) -> Option<Self> {
+        (!self.fast_model.is_empty()).then(|| Self {
+            api_key: self.api_key.clone(),
+            client: self.client.clone(),
+            base_url: self.base_url.clone(),
+            model: self.fast_model.clone(),
+            fast_model: self.fast_model.clone(),
+        })
+    }
+
     /// Get the model name this client is configured for
     pub fn model_name(&self) -> &str {
         &self.model
@@ -82,68 +146,262 @@ impl ClaudeClient {
         system_prompt: &str,
         max_tokens: i32,
         temperature: f32,
+        response_schema: Option<&serde_json::Value>,
+    ) -> Result<InferenceResponse> {
+        self.infer_turns(
+            &[Turn::user(prompt)],
+            system_prompt,
+            max_tokens,
+            temperature,
+            response_schema,
+            false,
+        )
+        .await
+    }
+
+    /// Send a conversation to Claude. With `cache`, the system prompt and
+    /// the conversation so far are marked for prompt caching so the next
+    /// turn of a session reads them from the cache.
+    pub async fn infer_turns(
+        &self,
+        turns: &[Turn],
+        system_prompt: &str,
+        max_tokens: i32,
+        temperature: f32,
+        response_schema: Option<&serde_json::Value>,
+        cache: bool,
     ) -> Result<InferenceResponse> {
         if !self.is_available() {
             bail!("Claude API key not configured");
         }
 
+        let (request_body, tool_schema) = self.request_body(
+            turns,
+            system_prompt,
+            max_tokens,
+            temperature,
+            response_schema,
+            cache,
+        );
+
+        let start = std::time::Instant::now();
+        let response = self.send(&request_body).await?;
+        let latency = start.elapsed().as_millis() as i64;
+
+        let claude_response: ClaudeResponse = response.json().await?;
+
+        let usage = &claude_response.usage;
+        let cached_tokens = usage.cache_read_input_tokens.unwrap_or(0);
+        let tokens_used = usage.input_tokens
+            + usage.output_tokens
+            + usage.cache_creation_input_tokens.unwrap_or(0)
+            + cached_tokens;
+
+        let tool_result = tool_schema.and_then(|(_, wrapped)| {
+            claude_response
+                .content
+                .iter()
+                .find(|c| c.content_type == "tool_use")
+                .map(|c| structured::claude_tool_result(c.input.clone(), wrapped).to_string())
+        });
+        let text = tool_result.unwrap_or_else(|| {
+            claude_response
+                .content
+                .into_iter()
+                .filter(|c| c.content_type == "text")
+                .map(|c| c.text)
+                .collect::<Vec<_>>()
+                .join("")
+        });
+
+        info!(
+            "Claude response: {} tokens ({} cached), {}ms latency",
+            tokens_used, cached_tokens, latency
+        );
+
+        Ok(InferenceResponse {
+            text,
+            tokens_used,
+            latency_ms: latency,
+            model_used: claude_response.model,
+            intelligence_level: "strategic".to_string(),
+            compression: None,
+            cached_tokens,
+            // Priced by the router when it records the usage
+            cost_usd: 0.0,
+        })
+    }
+
+    /// The request body for a conversation, and the wrapping of the
+    /// response schema if there is one
+    fn request_body(
+        &self,
+        turns: &[Turn],
+        system_prompt: &str,
+        max_tokens: i32,
+        temperature: f32,
+        response_schema: Option<&serde_json::Value>,
+        cache: bool,
+    ) -> (ClaudeRequest, Option<(serde_json::Value, bool)>) {
         let max_tokens = if max_tokens <= 0 { 4096 } else { max_tokens };
         let temperature = if temperature <= 0.0 { 0.3 } else { temperature };
 
+        // Claude has no JSON schema mode: a forced call to a tool whose input
+        // schema is the response schema yields the structured result
+        let tool_schema = response_schema.map(structured::claude_tool_schema);
+        let (tools, tool_choice) = match &tool_schema {
+            Some((input_schema, _)) => (
+                vec![ClaudeTool {
+                    name: structured::CLAUDE_TOOL_NAME.to_string(),
+                    description: "Return the response in the required structure".to_string(),
+                    input_schema: input_schema.clone(),
+                }],
+                Some(serde_json::json!({
+                    "type": "tool",
+                    "name": structured::CLAUDE_TOOL_NAME,
+                })),
+            ),
+            None => (Vec::new(), None),
+        };
+
+        let breakpoint = cache.then(|| serde_json::json!({"type": "ephemeral"}));
+        let mut system = Vec::new();
+        if !system_prompt.is_empty() {
+            system.push(ClaudeBlock {
+                cache_control: breakpoint.clone(),
+                ..ClaudeBlock::text(system_prompt)
+            });
+        }
+        let mut messages: Vec<ClaudeMessage> = turns
+            .iter()
+            .map(|t| ClaudeMessage {
+                role: t.role.to_string(),
+                content: vec![ClaudeBlock::text(&t.content)],
+            })
+            .collect();
+        if let Some(block) = messages.last_mut().and_then(|m| m.content.last_mut()) {
+            block.cache_control = breakpoint;
+        }
+
         let request_body = ClaudeRequest {
             model: self.model.clone(),
             max_tokens,
             temperature,
-            system: system_prompt.to_string(),
-            messages: vec![ClaudeMessage {
-                role: "user".to_string(),
-                content: prompt.to_string(),
-            }],
+            system,
+            messages,
+            tools,
+            tool_choice,
+            stream: false,
         };
 
-        let start = std::time::Instant::now();
+        (request_body, tool_schema)
+    }
 
+    /// Post a request body, failing on an error status
+    async fn send(&self, request_body: &ClaudeRequest) -> Result<reqwest::Response> {
         let response = self
             .client
             .post(format!("{}/v1/messages", self.base_url))
             .header("x-api-key", &self.api_key)
             .header("anthropic-version", "2023-06-01")
             .header("content-type", "application/json")
-            .json(&request_body)
+            .json(request_body)
             .send()
             .await?;
 
-        let latency = start.elapsed().as_millis() as i64;
-
+        if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(RateLimited::from_response("Claude", response).await.into());
+        }
         if !response.status().is_success() {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
             bail!("Claude API error {status}: {body}");
         }
+        Ok(response)
+    }
 
-        let claude_response: ClaudeResponse = response.json().await?;
-
-        let text = claude_response
-            .content
-            .into_iter()
-            .filter(|c| c.content_type == "text")
-            .map(|c| c.text)
-            .collect::<Vec<_>>()
-            .join("");
+    /// Stream a conversation from Claude, sending each text delta to
+    /// `chunks` and keeping `usage` current. Fails if `chunks` is closed
+    /// before the end.
+    pub async fn stream_turns(
+        &self,
+        turns: &[Turn],
+        system_prompt: &str,
+        max_tokens: i32,
+        temperature: f32,
+        usage: &mut StreamUsage,
+        chunks: &mpsc::Sender<String>,
+    ) -> Result<InferenceResponse> {
+        if !self.is_available() {
+            bail!("Claude API key not configured");
+        }
+        let (request_body, _) =
+            self.request_body(turns, system_prompt, max_tokens, temperature, None, false);
+        let request_body = ClaudeRequest {
+            stream: true,
+            ..request_body
+        };
 
-        let tokens_used = claude_response.usage.input_tokens + claude_response.usage.output_tokens;
+        let start = std::time::Instant::now();
+        let mut events = EventStream::new(self.send(&request_body).await?);
+        *usage = StreamUsage::estimate(system_prompt, turns, &self.model);
+        let mut text = String::new();
+        let mut completed = false;
+        while let Some(event) = events.next().await? {
+            let data: serde_json::Value = serde_json::from_str(&event.data)?;
+            match event.event.as_str() {
+                "message_start" => {
+                    let message = &data["message"];
+                    let count = |field: &str| message["usage"][field].as_i64().unwrap_or(0) as i32;
+                    usage.cached_tokens = count("cache_read_input_tokens");
+                    usage.input_tokens = count("input_tokens")
+                        + count("cache_creation_input_tokens")
+                        + usage.cached_tokens;
+                    if let Some(model) = message["model"].as_str() {
+                        usage.model = model.to_string();
+                    }
+                }
+                "content_block_delta" => {
+                    if let Some(delta) = data["delta"]["text"].as_str() {
+                        usage.add_text(delta);
+                        text.push_str(delta);
+                        stream::forward(chunks, delta).await?;
+                    }
+                }
+                "message_delta" => {
+                    if let Some(tokens) = data["usage"]["output_tokens"].as_i64() {
+                        usage.report_output(tokens as i32);
+                    }
+                }
+                "message_stop" => {
+                    completed = true;
+                    break;
+                }
+                "error" => bail!("Claude stream error: {}", data["error"]["message"]),
+                _ => {}
+            }
+        }
+        if !completed {
+            bail!("Claude stream ended before the message was complete");
+        }
 
+        let latency = start.elapsed().as_millis() as i64;
         info!(
-            "Claude response: {} tokens, {}ms latency",
-            tokens_used, latency
+            "Claude stream: {} tokens ({} cached), {}ms latency",
+            usage.tokens_used(),
+            usage.cached_tokens,
+            latency
         );
-
         Ok(InferenceResponse {
             text,
-            tokens_used,
+            tokens_used: usage.tokens_used(),
             latency_ms: latency,
-            model_used: claude_response.model,
+            model_used: usage.model.clone(),
             intelligence_level: "strategic".to_string(),
+            compression: None,
+            cached_tokens: usage.cached_tokens,
+            // Priced by the gateway when it records the usage
+            cost_usd: 0.0,
         })
     }
 
@@ -0,0 +1,379 @@
+//! Prompt Compression — fit sectioned prompts into a provider's context window
+//!
+//! Callers may send the prompt as sections instead of one string. Each
+//! section is pinned (the task, tool catalog and instructions; never
+//! changed), a retrieved chunk (dropped lowest relevance first), or
+//! conversation history (summarized by the local model). Before a request
+//! goes to a provider the sections are fitted to that provider's context
+//! window, leaving room for the response; the response carries a report of
+//! what was dropped or summarized so the caller can log it.
+//!
+//! Context windows come from `CLAUDE_CONTEXT_TOKENS` (200000),
+//! `OPENAI_CONTEXT_TOKENS` (128000), `QWEN3_CONTEXT_TOKENS` (128000) and
+//! `LOCAL_LLM_CONTEXT_TOKENS` (8192).
+
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+use tracing::{info, warn};
+
+use crate::budget::BudgetManager;
+use crate::openai::OpenAiClient;
+use crate::proto::api_gateway::{ApiInferRequest, PromptSection};
+use crate::proto::common::CompressionReport;
+
+/// Response tokens reserved when the request sets no limit
+const DEFAULT_OUTPUT_TOKENS: usize = 4096;
+
+/// Longest history summary requested from the local model
+const MAX_SUMMARY_TOKENS: usize = 1024;
+
+/// Context window, in tokens, of each provider
+pub struct ContextWindows {
+    windows: HashMap<&'static str, usize>,
+}
+
+impl ContextWindows {
+    pub fn from_env() -> Self {
+        let window = |var: &str, default: usize| {
+            std::env::var(var)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        };
+        Self {
+            windows: HashMap::from([
+                ("claude", window("CLAUDE_CONTEXT_TOKENS", 200_000)),
+                ("openai", window("OPENAI_CONTEXT_TOKENS", 128_000)),
+                ("qwen3", window("QWEN3_CONTEXT_TOKENS", 128_000)),
+                ("local", window("LOCAL_LLM_CONTEXT_TOKENS", 8192)),
+            ]),
+        }
+    }
+
+    pub fn window(&self, provider: &str) -> usize {
+        self.windows.get(provider).copied().unwrap_or(8192)
+    }
+}
+
+/// Rough token estimation (4 chars per token)
+pub fn estimate_tokens(text: &str) -> usize {
+    text.len().div_ceil(4)
+}
+
+/// The prompt text followed by every section, in order
+pub fn render(prompt: &str, sections: &[PromptSection]) -> String {
+    let mut text = prompt.to_string();
+    for section in sections {
+        text.push_str(&section.text);
+    }
+    text
+}
+
+/// Tokens kept free for the response: the requested maximum, but never
+/// more than a quarter of the window
+fn output_reserve(max_tokens: i32, window: usize) -> usize {
+    let requested = if max_tokens <= 0 {
+        DEFAULT_OUTPUT_TOKENS
+    } else {
+        max_tokens as usize
+    };
+    requested.min(window / 4)
+}
+
+fn section_tokens(sections: &[Option<PromptSection>]) -> usize {
+    sections
+        .iter()
+        .flatten()
+        .map(|s| estimate_tokens(&s.text))
+        .sum()
+}
+
+/// Drop chunk sections, lowest relevance first, until everything fits in
+/// `budget` tokens. Returns the labels dropped.
+fn drop_chunks(sections: &mut [Option<PromptSection>], budget: usize) -> Vec<String> {
+    let mut chunks: Vec<usize> = sections
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| s.as_ref().is_some_and(|s| s.kind == "chunk"))
+        .map(|(i, _)| i)
+        .collect();
+    chunks.sort_by(|a, b| {
+        let relevance = |i: &usize| sections[*i].as_ref().map_or(0.0, |s| s.relevance);
+        relevance(a)
+            .partial_cmp(&relevance(b))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    let mut dropped = Vec::new();
+    for i in chunks {
+        if section_tokens(sections) <= budget {
+            break;
+        }
+        if let Some(section) = sections[i].take() {
+            dropped.push(section.label);
+        }
+    }
+    dropped
+}
+
+/// Prompt asking the local model to condense conversation history
+fn summary_prompt(history: &str, max_tokens: usize) -> String {
+    format!(
+        "Summarize the conversation below in at most {} words. Keep every user \
+         requirement, decision, fact, and unresolved question; drop pleasantries \
+         and repetition. Respond with the summary only.\n\n{history}",
+        max_tokens * 3 / 4
+    )
+}
+
+/// Fit `request` into `provider`'s context window, alongside
+/// `history_tokens` of replayed session turns. Returns the request with its
+/// sections rendered into `prompt`, and a report when anything was dropped
+/// or summarized. Fails when the pinned sections alone do not fit, so the
+/// router can fall back to a provider with a larger window.
+pub async fn fit(
+    request: &ApiInferRequest,
+    provider: &str,
+    history_tokens: usize,
+    windows: &ContextWindows,
+    local: &OpenAiClient,
+    budget: &mut BudgetManager,
+) -> Result<(ApiInferRequest, Option<CompressionReport>)> {
+    if request.sections.is_empty() {
+        return Ok((request.clone(), None));
+    }
+    let window = windows.window(provider);
+    let fixed =
+        estimate_tokens(&request.system_prompt) + estimate_tokens(&request.prompt) + history_tokens;
+    let available = window
+        .saturating_sub(output_reserve(request.max_tokens, window))
+        .saturating_sub(fixed);
+    let mut sections: Vec<Option<PromptSection>> =
+        request.sections.iter().cloned().map(Some).collect();
+    let original = fixed + section_tokens(&sections);
+    let mut report = CompressionReport {
+        provider: provider.to_string(),
+        context_window: window as i32,
+        original_tokens: original as i32,
+        ..Default::default()
+    };
+
+    if section_tokens(&sections) > available {
+        report.dropped = drop_chunks(&mut sections, available);
+    }
+
+    let history: Vec<usize> = sections
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| s.as_ref().is_some_and(|s| s.kind == "history"))
+        .map(|(i, _)| i)
+        .collect();
+    if section_tokens(&sections) > available && !history.is_empty() {
+        let labels: Vec<String> = history
+            .iter()
+            .filter_map(|i| sections[*i].as_ref().map(|s| s.label.clone()))
+            .collect();
+        let text: String = history
+            .iter()
+            .filter_map(|i| sections[*i].take())
+            .map(|s| s.text)
+            .collect();
+        let room = available.saturating_sub(section_tokens(&sections));
+        let summary_tokens = room.min(MAX_SUMMARY_TOKENS);
+
+        // Keep the newest history that fits the local model's own window
+        let local_chars = (windows.window("local") * 3 / 4).saturating_sub(summary_tokens) * 4;
+        let skip = text.chars().count().saturating_sub(local_chars);
+        let text: String = text.chars().skip(skip).collect();
+
+        let summary = if summary_tokens == 0 {
+            Err(anyhow::anyhow!("no room left for a summary"))
+        } else {
+            local
+                .infer(
+                    &summary_prompt(&text, summary_tokens),
+                    "",
+                    summary_tokens as i32,
+                    0.2,
+                    None,
+                )
+                .await
+        };
+        match summary {
+            Ok(summary) => {
+                budget.record_usage("local", summary.tokens_used, &summary.model_used);
+                sections[history[0]] = Some(PromptSection {
+                    kind: "pinned".to_string(),
+                    text: format!(
+                        "Summary of earlier conversation:\n{}\n",
+                        summary.text.trim()
+                    ),
+                    relevance: 0.0,
+                    label: "history summary".to_string(),
+                });
+                report.summarized = labels;
+            }
+            Err(e) => {
+                warn!("History summarization failed, dropping history instead: {e}");
+                report.dropped.extend(labels);
+            }
+        }
+    }
+
+    let used = section_tokens(&sections);
+    if used > available {
+        bail!(
+            "Prompt needs {} tokens but the {provider} context window leaves {} after compression",
+            fixed + used,
+            fixed + available
+        );
+    }
+
+    let sections: Vec<PromptSection> = sections.into_iter().flatten().collect();
+    let compressed = ApiInferRequest {
+        prompt: render(&request.prompt, &sections),
+        sections: Vec::new(),
+        ..request.clone()
+    };
+    if report.dropped.is_empty() && report.summarized.is_empty() {
+        return Ok((compressed, None));
+    }
+    report.final_tokens = (fixed + used) as i32;
+    info!(
+        "Compressed prompt for {provider} from {} to {} tokens ({} dropped, {} summarized)",
+        report.original_tokens,
+        report.final_tokens,
+        report.dropped.len(),
+        report.summarized.len()
+    );
+    Ok((compressed, Some(report)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section(kind: &str, label: &str, tokens: usize, relevance: f64) -> PromptSection {
+        PromptSection {
+            kind: kind.into(),
+            text: "x".repeat(tokens * 4),
+            relevance,
+            label: label.into(),
+        }
+    }
+
+    fn request(sections: Vec<PromptSection>) -> ApiInferRequest {
+        ApiInferRequest {
+            prompt: String::new(),
+            system_prompt: String::new(),
+            max_tokens: 100,
+            temperature: 0.3,
+            preferred_provider: String::new(),
+            requesting_agent: "test-agent".into(),
+            task_id: String::new(),
+            allow_fallback: false,
+            response_schema: String::new(),
+            sections,
+            session_id: String::new(),
+            turn_kind: String::new(),
+            model_class: String::new(),
+            workload: String::new(),
+            goal_id: String::new(),
+            goal_budget: None,
+            bypass_cache: false,
+        }
+    }
+
+    fn windows(local: usize) -> ContextWindows {
+        ContextWindows {
+            windows: HashMap::from([("local", local)]),
+        }
+    }
+
+    fn unreachable_local() -> OpenAiClient {
+        OpenAiClient::with_config(
+            "local-no-key-needed".into(),
+            "http://127.0.0.1:9".into(),
+            "local".into(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_fit_drops_lowest_relevance_chunks() {
+        let mut budget = BudgetManager::new(100.0, 50.0);
+        let req = request(vec![
+            section("pinned", "task", 200, 0.0),
+            section("chunk", "memory:a", 300, 0.9),
+            section("chunk", "memory:b", 300, 0.2),
+            section("chunk", "memory:c", 300, 0.5),
+        ]);
+
+        // 1000 tokens, 100 reserved for output: room for the task and two chunks
+        let (fitted, report) = fit(
+            &req,
+            "local",
+            0,
+            &windows(1000),
+            &unreachable_local(),
+            &mut budget,
+        )
+        .await
+        .unwrap();
+        let report = report.unwrap();
+        assert_eq!(report.dropped, vec!["memory:b"]);
+        assert_eq!(report.original_tokens, 1100);
+        assert_eq!(report.final_tokens, 800);
+        assert!(fitted.sections.is_empty());
+        assert_eq!(estimate_tokens(&fitted.prompt), 800);
+
+        // Everything fits: rendered unchanged, no report
+        let (fitted, report) = fit(
+            &req,
+            "local",
+            0,
+            &windows(10_000),
+            &unreachable_local(),
+            &mut budget,
+        )
+        .await
+        .unwrap();
+        assert!(report.is_none());
+        assert_eq!(estimate_tokens(&fitted.prompt), 1100);
+    }
+
+    #[tokio::test]
+    async fn test_fit_keeps_pinned_sections() {
+        let mut budget = BudgetManager::new(100.0, 50.0);
+        let req = request(vec![
+            section("pinned", "task", 600, 0.0),
+            section("history", "message 1", 400, 0.0),
+        ]);
+
+        // The summarizer is unreachable, so history is dropped
+        let (_, report) = fit(
+            &req,
+            "local",
+            0,
+            &windows(1000),
+            &unreachable_local(),
+            &mut budget,
+        )
+        .await
+        .unwrap();
+        assert_eq!(report.unwrap().dropped, vec!["message 1"]);
+
+        // Pinned content alone exceeds the window
+        let req = request(vec![section("pinned", "tools", 2000, 0.0)]);
+        assert!(fit(
+            &req,
+            "local",
+            0,
+            &windows(1000),
+            &unreachable_local(),
+            &mut budget
+        )
+        .await
+        .is_err());
+    }
+}
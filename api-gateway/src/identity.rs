@@ -0,0 +1,115 @@
+//! Identity — the actor a call acts as
+//!
+//! Calls name the identity they act as in `x-aios-identity`:
+//! `<kind>:<name>` for an agent, tool, user, node or service. Infer and
+//! StreamInfer replace the `requesting_agent` a request names with that
+//! identity, so usage records and budgets are kept per actor. Identities
+//! are taken at their word here; the memory service holds their tokens and
+//! checks them.
+//!
+//! This service's own calls to the memory service act as
+//! `service:api-gateway`.
+
+use tonic::metadata::MetadataValue;
+use tonic::service::interceptor::InterceptedService;
+use tonic::transport::Channel;
+
+use crate::proto::memory::memory_service_client::MemoryServiceClient;
+
+/// Header naming the identity a call acts as
+pub const IDENTITY_HEADER: &str = "x-aios-identity";
+
+/// Identity of this service
+const SERVICE_ID: &str = "service:api-gateway";
+
+/// Kinds of identity
+const KINDS: [&str; 5] = ["agent", "tool", "user", "node", "service"];
+
+/// An acting identity, as named by a call
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Principal {
+    pub kind: String,
+    pub name: String,
+}
+
+impl Principal {
+    /// `<kind>:<name>`, None for other strings
+    pub fn parse(id: &str) -> Option<Self> {
+        let (kind, name) = id.split_once(':')?;
+        (KINDS.contains(&kind) && !name.is_empty()).then(|| Self {
+            kind: kind.to_string(),
+            name: name.to_string(),
+        })
+    }
+
+    /// Name per-actor usage is kept under: agents and services keep their
+    /// bare names, as before identities; users, nodes and tools go by their
+    /// full id so they cannot pass for an agent
+    pub fn key(&self) -> String {
+        match self.kind.as_str() {
+            "agent" | "service" => self.name.clone(),
+            _ => format!("{}:{}", self.kind, self.name),
+        }
+    }
+}
+
+/// Key of the actor a request acts for: the identity the call names, unless
+/// a service or node made the call on its own account and the request
+/// names an agent (`legacy`); then that agent
+pub fn acting<T>(request: &tonic::Request<T>, legacy: &str) -> String {
+    let caller = request
+        .metadata()
+        .get(IDENTITY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(Principal::parse);
+    match caller {
+        Some(p) if !matches!(p.kind.as_str(), "service" | "node") || legacy.is_empty() => p.key(),
+        _ => legacy.to_string(),
+    }
+}
+
+/// Interceptor naming this service on outgoing calls
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ActAsService;
+
+impl tonic::service::Interceptor for ActAsService {
+    fn call(
+        &mut self,
+        mut request: tonic::Request<()>,
+    ) -> Result<tonic::Request<()>, tonic::Status> {
+        request
+            .metadata_mut()
+            .insert(IDENTITY_HEADER, MetadataValue::from_static(SERVICE_ID));
+        Ok(request)
+    }
+}
+
+/// Memory service client whose calls act as this service
+pub type MemoryClient = MemoryServiceClient<InterceptedService<Channel, ActAsService>>;
+
+pub async fn connect_memory(addr: String) -> Result<MemoryClient, tonic::transport::Error> {
+    let channel = tonic::transport::Endpoint::from_shared(addr)?
+        .connect()
+        .await?;
+    Ok(MemoryServiceClient::with_interceptor(channel, ActAsService))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requests_are_attributed_to_the_caller() {
+        let mut request = tonic::Request::new(());
+        assert_eq!(acting(&request, "system-agent"), "system-agent");
+        request
+            .metadata_mut()
+            .insert(IDENTITY_HEADER, "tool:fs.read".parse().unwrap());
+        assert_eq!(acting(&request, "system-agent"), "tool:fs.read");
+        request
+            .metadata_mut()
+            .insert(IDENTITY_HEADER, "service:orchestrator".parse().unwrap());
+        assert_eq!(acting(&request, "system-agent"), "system-agent");
+        assert_eq!(acting(&request, ""), "orchestrator");
+    }
+}
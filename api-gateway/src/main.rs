@@ -13,17 +13,34 @@ use tokio::sync::RwLock;
 use tonic::transport::Server;
 use tracing::info;
 
+#[path = "../../agent-core/src/api_compat.rs"]
+mod api_version;
+mod breaker;
 mod budget;
+#[path = "../../agent-core/src/build_info.rs"]
+mod build_info;
+mod bus;
+mod cache;
 mod claude;
+mod compress;
+mod identity;
 mod openai;
 mod router;
+mod session;
+mod stream;
+mod structured;
+#[path = "../../agent-core/src/otel.rs"]
+mod telemetry;
 
 pub mod proto {
     pub mod common {
-        tonic::include_proto!("aios.common");
+        tonic::include_proto!("aios.v1.common");
     }
     pub mod api_gateway {
-        tonic::include_proto!("aios.api_gateway");
+        tonic::include_proto!("aios.v1.api_gateway");
+    }
+    pub mod memory {
+        tonic::include_proto!("aios.v1.memory");
     }
 }
 
@@ -52,18 +69,27 @@ impl ApiGateway for ApiGatewayService {
         &self,
         request: tonic::Request<proto::api_gateway::ApiInferRequest>,
     ) -> Result<tonic::Response<proto::common::InferenceResponse>, tonic::Status> {
-        let req = request.into_inner();
+        let requesting_agent = identity::acting(&request, &request.get_ref().requesting_agent);
+        let mut req = request.into_inner();
+        req.requesting_agent = requesting_agent;
         info!(
             "API inference request: provider={}, agent={}, task={}",
             req.preferred_provider, req.requesting_agent, req.task_id
         );
 
+        structured::parse_schema(&req.response_schema)
+            .map_err(|e| tonic::Status::invalid_argument(format!("{e:#}")))?;
+
         let mut state = self.state.write().await;
 
         // Check budget
         if state.budget_manager.is_budget_exceeded() {
             return Err(tonic::Status::resource_exhausted("API budget exceeded"));
         }
+        let req = state
+            .budget_manager
+            .apply_goal_budget(&req)
+            .map_err(tonic::Status::resource_exhausted)?;
 
         // Destructure to satisfy the borrow checker — each field is borrowed independently
         let GatewayState {
@@ -99,79 +125,140 @@ impl ApiGateway for ApiGatewayService {
         &self,
         request: tonic::Request<proto::api_gateway::ApiInferRequest>,
     ) -> Result<tonic::Response<Self::StreamInferStream>, tonic::Status> {
-        let req = request.into_inner();
+        let requesting_agent = identity::acting(&request, &request.get_ref().requesting_agent);
+        let mut req = request.into_inner();
+        req.requesting_agent = requesting_agent;
+        if !req.response_schema.is_empty() {
+            // Partial output cannot be validated against the schema
+            return Err(tonic::Status::invalid_argument(
+                "response_schema is not supported by StreamInfer; use Infer",
+            ));
+        }
+        if !req.session_id.is_empty() {
+            return Err(tonic::Status::invalid_argument(
+                "session_id is not supported by StreamInfer; use Infer",
+            ));
+        }
         let state = self.state.clone();
 
         let (tx, rx) = tokio::sync::mpsc::channel(128);
 
         tokio::spawn(async move {
-            let state = state.write().await;
+            // Prepare under the lock; the stream itself runs without it
+            let (provider, req, client) = {
+                let mut state = state.write().await;
+                let req = match state.budget_manager.apply_goal_budget(&req) {
+                    Ok(req) => state.budget_manager.degrade_request(&req),
+                    Err(e) => {
+                        let _ = tx.send(Err(tonic::Status::resource_exhausted(e))).await;
+                        return;
+                    }
+                };
+
+                let provider = state.request_router.select_provider(
+                    &req,
+                    &state.claude_client,
+                    &state.openai_client,
+                    &state.qwen3_client,
+                    &state.local_client,
+                    &state.budget_manager,
+                );
+
+                let fitted = {
+                    let GatewayState {
+                        ref request_router,
+                        ref local_client,
+                        ref mut budget_manager,
+                        ..
+                    } = *state;
+                    compress::fit(
+                        &req,
+                        &provider,
+                        0,
+                        request_router.context_windows(),
+                        local_client,
+                        budget_manager,
+                    )
+                    .await
+                };
+                let req = match fitted {
+                    Ok((req, _)) => req,
+                    Err(e) => {
+                        let _ = tx.send(Err(tonic::Status::internal(e.to_string()))).await;
+                        return;
+                    }
+                };
+
+                let client = match provider.as_str() {
+                    "claude" => stream::StreamClient::Claude(state.claude_client.clone()),
+                    "openai" => stream::StreamClient::OpenAi(state.openai_client.clone()),
+                    "qwen3" => stream::StreamClient::OpenAi(state.qwen3_client.clone()),
+                    "local" => stream::StreamClient::OpenAi(state.local_client.clone()),
+                    _ => {
+                        let _ = tx
+                            .send(Err(tonic::Status::internal("No available provider")))
+                            .await;
+                        return;
+                    }
+                };
+                (provider, req, client)
+            };
 
-            let provider = state.request_router.select_provider(
-                &req,
-                &state.claude_client,
-                &state.openai_client,
-                &state.qwen3_client,
-                &state.local_client,
-                &state.budget_manager,
-            );
-
-            let result = match provider.as_str() {
-                "claude" => {
-                    state
-                        .claude_client
-                        .infer(
-                            &req.prompt,
-                            &req.system_prompt,
-                            req.max_tokens,
-                            req.temperature,
-                        )
-                        .await
-                }
-                "openai" => {
-                    state
-                        .openai_client
-                        .infer(
-                            &req.prompt,
-                            &req.system_prompt,
-                            req.max_tokens,
-                            req.temperature,
-                        )
-                        .await
+            // Forward each delta as the provider produces it
+            let (deltas, mut delta_rx) = tokio::sync::mpsc::channel::<String>(32);
+            let chunk_tx = tx.clone();
+            let chunk_provider = provider.clone();
+            let forwarding = async move {
+                while let Some(text) = delta_rx.recv().await {
+                    let chunk = proto::api_gateway::StreamChunk {
+                        text,
+                        done: false,
+                        provider: chunk_provider.clone(),
+                        ..Default::default()
+                    };
+                    if chunk_tx.send(Ok(chunk)).await.is_err() {
+                        break;
+                    }
                 }
-                "qwen3" => {
-                    state
-                        .qwen3_client
-                        .infer(
-                            &req.prompt,
-                            &req.system_prompt,
-                            req.max_tokens,
-                            req.temperature,
-                        )
-                        .await
-                }
-                "local" => {
-                    state
-                        .local_client
-                        .infer(
-                            &req.prompt,
-                            &req.system_prompt,
-                            req.max_tokens,
-                            req.temperature,
-                        )
-                        .await
+            };
+            let mut usage = stream::StreamUsage::default();
+            let streaming = async {
+                let deltas = deltas;
+                client
+                    .stream(
+                        &req.prompt,
+                        &req.system_prompt,
+                        req.max_tokens,
+                        req.temperature,
+                        &mut usage,
+                        &deltas,
+                    )
+                    .await
+            };
+            let (result, ()) = tokio::join!(streaming, forwarding);
+
+            // Account for what was used, also when the stream ended early
+            let cost = {
+                let mut state = state.write().await;
+                let cost = state
+                    .budget_manager
+                    .record_stream_usage(&req, &provider, &usage);
+                // A caller going away says nothing about the provider
+                if !tx.is_closed() {
+                    state.request_router.record_call(&provider, result.as_ref());
                 }
-                _ => Err(anyhow::anyhow!("No available provider")),
+                cost
             };
 
             match result {
-                Ok(response) => {
-                    // Send as single chunk (streaming will be implemented per-provider later)
+                Ok(_) => {
                     let _ = tx
                         .send(Ok(proto::api_gateway::StreamChunk {
-                            text: response.text,
+                            text: String::new(),
                             done: true,
                             provider,
+                            tokens_used: usage.tokens_used(),
+                            cost_usd: cost,
                         }))
                         .await;
                 }
@@ -204,15 +291,76 @@ impl ApiGateway for ApiGatewayService {
         let usage = state.budget_manager.get_usage(&req.provider, req.days);
         Ok(tonic::Response::new(usage))
     }
+
+    async fn get_goal_cost(
+        &self,
+        request: tonic::Request<proto::api_gateway::GoalCostRequest>,
+    ) -> Result<tonic::Response<proto::api_gateway::GoalCost>, tonic::Status> {
+        let req = request.into_inner();
+        let state = self.state.read().await;
+        Ok(tonic::Response::new(
+            state.budget_manager.get_goal_cost(&req.goal_id),
+        ))
+    }
+
+    async fn get_provider_health(
+        &self,
+        _request: tonic::Request<proto::common::Empty>,
+    ) -> Result<tonic::Response<proto::api_gateway::ProviderHealthList>, tonic::Status> {
+        let state = self.state.read().await;
+        let providers = state.request_router.provider_health(
+            &state.claude_client,
+            &state.openai_client,
+            &state.qwen3_client,
+        );
+        Ok(tonic::Response::new(
+            proto::api_gateway::ProviderHealthList { providers },
+        ))
+    }
+
+    async fn get_cache_stats(
+        &self,
+        _request: tonic::Request<proto::common::Empty>,
+    ) -> Result<tonic::Response<proto::api_gateway::CacheStats>, tonic::Status> {
+        let state = self.state.read().await;
+        Ok(tonic::Response::new(state.request_router.cache_stats()))
+    }
+
+    async fn end_session(
+        &self,
+        request: tonic::Request<proto::api_gateway::EndSessionRequest>,
+    ) -> Result<tonic::Response<proto::common::Empty>, tonic::Status> {
+        let req = request.into_inner();
+        let mut state = self.state.write().await;
+        if state.request_router.end_session(&req.session_id) {
+            info!("Ended session {}", req.session_id);
+        }
+        Ok(tonic::Response::new(proto::common::Empty {}))
+    }
+
+    async fn get_api_version(
+        &self,
+        _request: tonic::Request<proto::common::Empty>,
+    ) -> Result<tonic::Response<proto::common::ApiVersion>, tonic::Status> {
+        Ok(tonic::Response::new(api_version::api_version(
+            proto::api_gateway::api_gateway_server::SERVICE_NAME,
+        )))
+    }
+
+    async fn get_build_info(
+        &self,
+        _request: tonic::Request<proto::common::Empty>,
+    ) -> Result<tonic::Response<proto::common::BuildInfo>, tonic::Status> {
+        Ok(tonic::Response::new(build_info::build_info(
+            proto::api_gateway::api_gateway_server::SERVICE_NAME,
+            Vec::new(),
+        )))
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    tracing_subscriber::fmt()
-        .with_target(false)
-        .with_level(true)
-        .compact()
-        .init();
+    let telemetry = telemetry::init("aios-api-gateway");
 
     info!("aiOS API Gateway starting...");
 
@@ -229,12 +377,16 @@ async fn main() -> Result<()> {
     // OpenAI config
     let openai_model = std::env::var("OPENAI_MODEL").unwrap_or_else(|_| "gpt-5".to_string());
 
+    // Cheaper models for tasks planned as "fast" ("" disables)
+    let openai_fast_model =
+        std::env::var("OPENAI_FAST_MODEL").unwrap_or_else(|_| "gpt-5-mini".to_string());
+    let qwen3_fast_model = std::env::var("QWEN3_FAST_MODEL").unwrap_or_default();
+
     // Local LLM provider — connects to a local llama-server instance (DeepSeek-R1, etc.)
     // This is always available (no API key needed) and serves as the final fallback.
-    let local_base_url = std::env::var("LOCAL_LLM_URL")
-        .unwrap_or_else(|_| "http://127.0.0.1:8082".to_string());
-    let local_model =
-        std::env::var("LOCAL_LLM_MODEL").unwrap_or_else(|_| "local".to_string());
+    let local_base_url =
+        std::env::var("LOCAL_LLM_URL").unwrap_or_else(|_| "http://127.0.0.1:8082".to_string());
+    let local_model = std::env::var("LOCAL_LLM_MODEL").unwrap_or_else(|_| "local".to_string());
 
     let available: Vec<&str> = [
         if !claude_key.is_empty() {
@@ -261,22 +413,30 @@ async fn main() -> Result<()> {
 
     info!("Available providers: {}", available.join(", "));
 
+    let bus = bus::BusPublisher::start();
     let state = Arc::new(RwLock::new(GatewayState {
         claude_client: claude::ClaudeClient::new(claude_key),
         openai_client: openai::OpenAiClient::with_config(
             openai_key,
             "https://api.openai.com".to_string(),
             openai_model,
-        ),
-        qwen3_client: openai::OpenAiClient::with_config(qwen3_key, qwen3_base_url, qwen3_model),
+        )
+        .with_fast_model(openai_fast_model),
+        qwen3_client: openai::OpenAiClient::with_config(qwen3_key, qwen3_base_url, qwen3_model)
+            .with_fast_model(qwen3_fast_model),
         // Local LLM uses a placeholder key — llama-server doesn't require authentication
         local_client: openai::OpenAiClient::with_config(
             "local-no-key-needed".to_string(),
             local_base_url,
             local_model,
-        ),
-        request_router: router::RequestRouter::new(),
-        budget_manager: budget::BudgetManager::new(100.0, 50.0),
+        )
+        .with_gbnf(),
+        request_router: router::RequestRouter::new()
+            .with_cache(cache::ResponseCache::from_env())
+            .with_bus(bus.clone()),
+        budget_manager: budget::BudgetManager::new(100.0, 50.0)
+            .with_degradation_from_env()
+            .with_bus(bus),
     }));
 
     let service = ApiGatewayService { state };
@@ -285,10 +445,13 @@ async fn main() -> Result<()> {
     info!("API Gateway gRPC server listening on {addr}");
 
     Server::builder()
+        .trace_fn(telemetry::server_span)
+        .layer(api_version::legacy_shim())
         .add_service(ApiGatewayServer::new(service))
         .serve(addr)
         .await
         .context("API Gateway gRPC server failed")?;
 
+    telemetry::shutdown(telemetry);
     Ok(())
 }
@@ -2,16 +2,27 @@
 
 use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
 use tracing::info;
 
+use crate::breaker::RateLimited;
 use crate::proto::common::InferenceResponse;
+use crate::session::Turn;
+use crate::stream::{self, EventStream, StreamUsage};
+use crate::structured;
 
 /// OpenAI API client
+#[derive(Clone)]
 pub struct OpenAiClient {
     api_key: String,
     client: reqwest::Client,
     base_url: String,
     model: String,
+    /// Enforce response schemas with a GBNF grammar (llama-server) instead
+    /// of a json_schema response format
+    gbnf: bool,
+    /// Cheaper model for tasks planned as "fast"
+    fast_model: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -22,12 +33,27 @@ struct OpenAiRequest {
     temperature: f32,
     #[serde(skip_serializing_if = "Option::is_none")]
     response_format: Option<ResponseFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    grammar: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+    /// With `stream`: {"include_usage": true} for a final usage chunk
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<serde_json::Value>,
 }
 
 #[derive(Serialize)]
 struct ResponseFormat {
     #[serde(rename = "type")]
     format_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    json_schema: Option<JsonSchemaFormat>,
+}
+
+#[derive(Serialize)]
+struct JsonSchemaFormat {
+    name: String,
+    schema: serde_json::Value,
 }
 
 #[derive(Serialize)]
@@ -65,6 +91,14 @@ struct OpenAiUsage {
     prompt_tokens: i32,
     completion_tokens: i32,
     total_tokens: i32,
+    #[serde(default)]
+    prompt_tokens_details: Option<OpenAiPromptTokensDetails>,
+}
+
+#[derive(Deserialize)]
+struct OpenAiPromptTokensDetails {
+    #[serde(default)]
+    cached_tokens: i32,
 }
 
 impl OpenAiClient {
@@ -80,6 +114,8 @@ impl OpenAiClient {
                 .unwrap_or_else(|_| reqwest::Client::new()),
             base_url,
             model,
+            gbnf: false,
+            fast_model: None,
         }
     }
 
@@ -93,9 +129,35 @@ impl OpenAiClient {
                 .unwrap_or_else(|_| reqwest::Client::new()),
             base_url,
             model,
+            gbnf: false,
+            fast_model: None,
         }
     }
 
+    /// Use `model` for tasks planned as "fast"
+    pub fn with_fast_model(mut self, model: String) -> Self {
+        self.fast_model = (!model.is_empty()).then_some(model);
+        self
+    }
+
+    /// This client with the fast model, if one is configured
+    pub fn fast_variant(&self) -> Option<Self> {
+        self.fast_model.as_ref().map(|model| Self {
+            api_key: self.api_key.clone(),
+            client: self.client.clone(),
+            base_url: self.base_url.clone(),
+            model: model.clone(),
+            gbnf: self.gbnf,
+            fast_model: self.fast_model.clone(),
+        })
+    }
+
+    /// Constrain structured output with a GBNF grammar, for llama-server
+    pub fn with_gbnf(mut self) -> Self {
+        self.gbnf = true;
+        self
+    }
+
     /// Get the model name this client is configured for
     pub fn model_name(&self) -> &str {
         &self.model
@@ -112,11 +174,86 @@ impl OpenAiClient {
         system_prompt: &str,
         max_tokens: i32,
         temperature: f32,
+        response_schema: Option<&serde_json::Value>,
+    ) -> Result<InferenceResponse> {
+        self.infer_turns(
+            &[Turn::user(prompt)],
+            system_prompt,
+            max_tokens,
+            temperature,
+            response_schema,
+        )
+        .await
+    }
+
+    /// Send a conversation to OpenAI. Repeated prefixes are cached by the
+    /// provider automatically.
+    pub async fn infer_turns(
+        &self,
+        turns: &[Turn],
+        system_prompt: &str,
+        max_tokens: i32,
+        temperature: f32,
+        response_schema: Option<&serde_json::Value>,
     ) -> Result<InferenceResponse> {
         if !self.is_available() {
             bail!("OpenAI API key not configured");
         }
 
+        let request_body = self.request_body(
+            turns,
+            system_prompt,
+            max_tokens,
+            temperature,
+            response_schema,
+        );
+
+        let start = std::time::Instant::now();
+        let response = self.send(&request_body).await?;
+        let latency = start.elapsed().as_millis() as i64;
+
+        let openai_response: OpenAiResponse = response.json().await?;
+
+        let text = openai_response
+            .choices
+            .first()
+            .and_then(|c| c.message.content.clone())
+            .unwrap_or_default();
+
+        let tokens_used = openai_response.usage.total_tokens;
+        let cached_tokens = openai_response
+            .usage
+            .prompt_tokens_details
+            .as_ref()
+            .map_or(0, |d| d.cached_tokens);
+
+        info!(
+            "OpenAI response: {} tokens, {}ms latency",
+            tokens_used, latency
+        );
+
+        Ok(InferenceResponse {
+            text,
+            tokens_used,
+            latency_ms: latency,
+            model_used: openai_response.model,
+            intelligence_level: "strategic".to_string(),
+            compression: None,
+            cached_tokens,
+            // Priced by the router when it records the usage
+            cost_usd: 0.0,
+        })
+    }
+
+    /// The request body for a conversation
+    fn request_body(
+        &self,
+        turns: &[Turn],
+        system_prompt: &str,
+        max_tokens: i32,
+        temperature: f32,
+        response_schema: Option<&serde_json::Value>,
+    ) -> OpenAiRequest {
         let max_tokens = if max_tokens <= 0 { 4096 } else { max_tokens };
         let temperature = if temperature <= 0.0 { 0.3 } else { temperature };
 
@@ -127,72 +264,146 @@ impl OpenAiClient {
                 content: system_prompt.to_string(),
             });
         }
-        messages.push(OpenAiMessage {
-            role: "user".to_string(),
-            content: prompt.to_string(),
-        });
-
-        // Enable JSON mode when the prompt instructs JSON output.
-        // This forces OpenAI-compatible APIs to output valid JSON.
-        let response_format = if prompt.contains("valid JSON")
+        messages.extend(turns.iter().map(|t| OpenAiMessage {
+            role: t.role.to_string(),
+            content: t.content.clone(),
+        }));
+        let prompt = turns.last().map_or("", |t| t.content.as_str());
+
+        // A response schema is enforced natively; otherwise enable JSON mode
+        // when the prompt instructs JSON output.
+        let mut grammar = None;
+        let response_format = if let Some(schema) = response_schema {
+            if self.gbnf {
+                grammar = Some(structured::to_gbnf(schema));
+                None
+            } else {
+                Some(ResponseFormat {
+                    format_type: "json_schema".to_string(),
+                    json_schema: Some(JsonSchemaFormat {
+                        name: "response".to_string(),
+                        schema: schema.clone(),
+                    }),
+                })
+            }
+        } else if prompt.contains("valid JSON")
             || prompt.contains("JSON object")
             || system_prompt.contains("respond with ONLY valid JSON")
         {
             Some(ResponseFormat {
                 format_type: "json_object".to_string(),
+                json_schema: None,
             })
         } else {
             None
         };
 
-        let request_body = OpenAiRequest {
+        OpenAiRequest {
             model: self.model.clone(),
             messages,
             max_tokens,
             temperature,
             response_format,
-        };
-
-        let start = std::time::Instant::now();
+            grammar,
+            stream: false,
+            stream_options: None,
+        }
+    }
 
+    /// Post a request body, failing on an error status
+    async fn send(&self, request_body: &OpenAiRequest) -> Result<reqwest::Response> {
         let response = self
             .client
             .post(format!("{}/v1/chat/completions", self.base_url))
             .header("Authorization", format!("Bearer {}", self.api_key))
             .header("Content-Type", "application/json")
-            .json(&request_body)
+            .json(request_body)
             .send()
             .await?;
 
-        let latency = start.elapsed().as_millis() as i64;
-
+        if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(RateLimited::from_response("OpenAI", response).await.into());
+        }
         if !response.status().is_success() {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
             bail!("OpenAI API error {status}: {body}");
         }
+        Ok(response)
+    }
 
-        let openai_response: OpenAiResponse = response.json().await?;
-
-        let text = openai_response
-            .choices
-            .first()
-            .and_then(|c| c.message.content.clone())
-            .unwrap_or_default();
+    /// Stream a conversation from OpenAI, sending each text delta to
+    /// `chunks` and keeping `usage` current. Fails if `chunks` is closed
+    /// before the end.
+    pub async fn stream_turns(
+        &self,
+        turns: &[Turn],
+        system_prompt: &str,
+        max_tokens: i32,
+        temperature: f32,
+        usage: &mut StreamUsage,
+        chunks: &mpsc::Sender<String>,
+    ) -> Result<InferenceResponse> {
+        if !self.is_available() {
+            bail!("OpenAI API key not configured");
+        }
+        let request_body = OpenAiRequest {
+            stream: true,
+            stream_options: Some(serde_json::json!({"include_usage": true})),
+            ..self.request_body(turns, system_prompt, max_tokens, temperature, None)
+        };
 
-        let tokens_used = openai_response.usage.total_tokens;
+        let start = std::time::Instant::now();
+        let mut events = EventStream::new(self.send(&request_body).await?);
+        *usage = StreamUsage::estimate(system_prompt, turns, &self.model);
+        let mut text = String::new();
+        let mut completed = false;
+        while let Some(event) = events.next().await? {
+            if event.data == "[DONE]" {
+                completed = true;
+                break;
+            }
+            let data: serde_json::Value = serde_json::from_str(&event.data)?;
+            if let Some(error) = data.get("error") {
+                bail!("OpenAI stream error: {}", error["message"]);
+            }
+            if let Some(model) = data["model"].as_str() {
+                usage.model = model.to_string();
+            }
+            if let Some(delta) = data["choices"][0]["delta"]["content"].as_str() {
+                usage.add_text(delta);
+                text.push_str(delta);
+                stream::forward(chunks, delta).await?;
+            }
+            let reported = &data["usage"];
+            if reported.is_object() {
+                usage.input_tokens = reported["prompt_tokens"].as_i64().unwrap_or(0) as i32;
+                usage.cached_tokens = reported["prompt_tokens_details"]["cached_tokens"]
+                    .as_i64()
+                    .unwrap_or(0) as i32;
+                usage.report_output(reported["completion_tokens"].as_i64().unwrap_or(0) as i32);
+            }
+        }
+        if !completed {
+            bail!("OpenAI stream ended before the response was complete");
+        }
 
+        let latency = start.elapsed().as_millis() as i64;
         info!(
-            "OpenAI response: {} tokens, {}ms latency",
-            tokens_used, latency
+            "OpenAI stream: {} tokens, {}ms latency",
+            usage.tokens_used(),
+            latency
         );
-
         Ok(InferenceResponse {
             text,
-            tokens_used,
+            tokens_used: usage.tokens_used(),
             latency_ms: latency,
-            model_used: openai_response.model,
+            model_used: usage.model.clone(),
             intelligence_level: "strategic".to_string(),
+            compression: None,
+            cached_tokens: usage.cached_tokens,
+            // Priced by the gateway when it records the usage
+            cost_usd: 0.0,
         })
     }
 
@@ -1,35 +1,93 @@
 //! Request Router — selects provider based on preference, availability, budget
+//!
+//! Each provider has a circuit breaker (see `breaker`). Providers whose
+//! breaker is open are not selected, and a failed request fails over along
+//! qwen3 → claude → openai → local, skipping them. Breaker state changes
+//! are published on the event bus as `gateway.provider_health`.
+
+use std::time::Instant;
 
 use anyhow::{bail, Result};
-use tracing::info;
+use tracing::{info, warn};
 
+use crate::breaker::ProviderBreakers;
 use crate::budget::BudgetManager;
+use crate::cache::{self, ResponseCache};
 use crate::claude::ClaudeClient;
+use crate::compress::{self, ContextWindows};
 use crate::openai::OpenAiClient;
-use crate::proto::api_gateway::ApiInferRequest;
+use crate::proto::api_gateway::{ApiInferRequest, ProviderHealth};
 use crate::proto::common::InferenceResponse;
+use crate::session::{self, SessionStore, Turn};
+use crate::structured;
 
 /// Routes API requests to the appropriate provider
 pub struct RequestRouter {
-    /// Cache of recent responses (prompt hash → response)
-    cache: std::collections::HashMap<u64, CachedResponse>,
-    cache_max_entries: usize,
+    /// Responses to single-shot requests, by request digest
+    cache: ResponseCache,
+    /// Context window of each provider, for prompt compression
+    context_windows: ContextWindows,
+    /// Multi-turn conversations held for callers
+    sessions: SessionStore,
+    /// Provider order for requests of model class "fast"
+    fast_providers: Vec<String>,
+    /// Circuit breaker of each provider
+    breakers: ProviderBreakers,
+    bus: Option<crate::bus::BusPublisher>,
 }
 
-struct CachedResponse {
-    response: InferenceResponse,
-    cached_at: i64,
-    ttl_seconds: i64,
+/// Order in which failed requests move on to other providers
+const FAILOVER_ORDER: [&str; 4] = ["qwen3", "claude", "openai", "local"];
+
+/// Whether `provider` is configured with an API key
+fn is_configured(
+    provider: &str,
+    claude: &ClaudeClient,
+    openai: &OpenAiClient,
+    qwen3: &OpenAiClient,
+) -> bool {
+    match provider {
+        "claude" => claude.is_available(),
+        "openai" => openai.is_available(),
+        "qwen3" => qwen3.is_available(),
+        "local" => true,
+        _ => false,
+    }
 }
 
 impl RequestRouter {
     pub fn new() -> Self {
         Self {
-            cache: std::collections::HashMap::new(),
-            cache_max_entries: 1000,
+            cache: ResponseCache::in_memory(),
+            context_windows: ContextWindows::from_env(),
+            sessions: SessionStore::from_env(),
+            fast_providers: std::env::var("AIOS_FAST_PROVIDERS")
+                .unwrap_or_else(|_| "openai,claude".to_string())
+                .split(',')
+                .map(|p| p.trim().to_string())
+                .filter(|p| !p.is_empty())
+                .collect(),
+            breakers: ProviderBreakers::from_env(),
+            bus: None,
         }
     }
 
+    /// Answer repeated requests from `cache`
+    pub fn with_cache(mut self, cache: ResponseCache) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    pub fn cache_stats(&self) -> crate::proto::api_gateway::CacheStats {
+        self.cache.stats()
+    }
+
+    /// Publish circuit breaker state changes through `bus`
+    pub fn with_bus(mut self, bus: crate::bus::BusPublisher) -> Self {
+        self.bus = Some(bus);
+        self
+    }
+
     /// Route a request to the best available provider
     pub async fn route_request(
         &mut self,
@@ -40,141 +98,364 @@ impl RequestRouter {
         local: &OpenAiClient,
         budget: &mut BudgetManager,
     ) -> Result<InferenceResponse> {
+        // Past the budget thresholds requests get cheaper models, fewer
+        // tokens, or only the local model; background requests get there
+        // sooner, leaving the interactive reserve
+        let request = &budget.degrade_request(request);
+
+        // A session turn replays the earlier turns; single-shot requests
+        // go through the response cache instead
+        let session = if request.session_id.is_empty() {
+            None
+        } else {
+            Some(self.sessions.resume(
+                &request.session_id,
+                &request.system_prompt,
+                &request.turn_kind,
+            )?)
+        };
+
         // Check cache
-        let cache_key = hash_request(&request.prompt, &request.system_prompt);
-        if let Some(cached) = self.get_cached(cache_key) {
-            info!("Cache hit for request");
-            return Ok(cached);
+        let cache_key = cache::key(request);
+        if session.is_none() && !request.bypass_cache {
+            if let Some(cached) = self.cache.get(&cache_key) {
+                info!("Cache hit for request");
+                return Ok(cached);
+            }
         }
 
-        // Select provider
-        let provider = self.select_provider(request, claude, openai, qwen3, local, budget);
-
-        // Build fallback chain based on what's available.
-        // "local" is always the final fallback (always available, no API key needed).
-        let fallback_order: Vec<&str> = match provider.as_str() {
-            "claude" => vec!["openai", "qwen3", "local"],
-            "openai" => vec!["claude", "qwen3", "local"],
-            "qwen3" => vec!["claude", "openai", "local"],
-            "local" => vec!["qwen3", "claude", "openai"],
-            _ => vec!["local"],
+        let (request, history) = match &session {
+            Some(state) => (
+                ApiInferRequest {
+                    prompt: session::user_content(&request.prompt, &request.turn_kind),
+                    system_prompt: state.system_prompt.clone(),
+                    ..request.clone()
+                },
+                state.turns.as_slice(),
+            ),
+            None => (request.clone(), [].as_slice()),
+        };
+        let request = &request;
+
+        // Select provider; a session stays with the provider holding its
+        // prompt cache
+        let provider = match &session {
+            Some(state) if !state.provider.is_empty() && request.preferred_provider.is_empty() => {
+                state.provider.clone()
+            }
+            _ => self.select_provider(request, claude, openai, qwen3, local, budget),
         };
 
-        // Try primary provider
-        let response = self
-            .try_provider(&provider, request, claude, openai, qwen3, local, budget)
-            .await;
-
-        let response = match response {
-            Ok(r) => Ok(r),
-            Err(e) if request.allow_fallback => {
-                info!("{provider} failed: {e}, trying fallbacks...");
-                let mut last_err = e;
-                let mut success = None;
-                for fb in &fallback_order {
-                    match self
-                        .try_provider(fb, request, claude, openai, qwen3, local, budget)
-                        .await
-                    {
-                        Ok(r) => {
-                            info!("Fallback to {fb} succeeded");
-                            success = Some(r);
-                            break;
-                        }
-                        Err(e) => {
-                            info!("Fallback {fb} also failed: {e}");
-                            last_err = e;
-                        }
+        // Build the chain: the selected provider, then the configured
+        // providers in failover order. "local" is always the final fallback
+        // (always available, no API key needed). Providers whose breaker
+        // is open are skipped.
+        let mut chain = vec![provider.clone()];
+        if request.allow_fallback {
+            chain.extend(
+                FAILOVER_ORDER
+                    .iter()
+                    .filter(|p| **p != provider && is_configured(p, claude, openai, qwen3))
+                    .map(|p| p.to_string()),
+            );
+        }
+        let now = Instant::now();
+        let (chain, open): (Vec<String>, Vec<String>) = chain
+            .into_iter()
+            .partition(|p| self.breakers.allows(p, now));
+        if chain.is_empty() {
+            let waits: Vec<String> = open
+                .iter()
+                .map(|p| {
+                    let health = self.breakers.health(p, true, now);
+                    format!("{p} for {}s", health.retry_in_secs)
+                })
+                .collect();
+            bail!("Circuit breaker open: {}", waits.join(", "));
+        }
+        if !open.is_empty() {
+            info!(
+                "Skipping providers with an open circuit breaker: {}",
+                open.join(", ")
+            );
+        }
+
+        let mut last_err = None;
+        let mut success = None;
+        for (i, p) in chain.iter().enumerate() {
+            let result = self
+                .try_provider(p, request, history, claude, openai, qwen3, local, budget)
+                .await;
+            if is_configured(p, claude, openai, qwen3) {
+                self.record_call(p, result.as_ref().map(|(r, _)| r));
+            }
+            match result {
+                Ok(r) => {
+                    if i > 0 {
+                        info!("Fallback to {p} succeeded");
                     }
+                    success = Some((p.clone(), r));
+                    break;
+                }
+                Err(e) => {
+                    info!("{p} failed: {e}");
+                    last_err = Some(e);
                 }
-                success.ok_or(last_err)
             }
-            Err(e) => Err(e),
-        }?;
+        }
+        let Some((used, (response, prompt))) = success else {
+            return Err(last_err.unwrap_or_else(|| anyhow::anyhow!("No available provider")));
+        };
 
-        // Cache the response
-        self.cache_response(cache_key, &response);
+        match &session {
+            // Extend the conversation for the next turn
+            Some(state) => self.sessions.record(
+                &request.session_id,
+                &state.system_prompt,
+                &used,
+                prompt,
+                response.text.clone(),
+            ),
+            // Cache the response
+            None => self.cache.put(&cache_key, &response),
+        }
+        budget.record_goal_usage(request, &used, &response);
 
         Ok(response)
     }
 
-    /// Try a single provider
+    /// Record a call to `provider` in its circuit breaker, publishing the
+    /// state change if the breaker moved
+    pub fn record_call(
+        &mut self,
+        provider: &str,
+        result: Result<&InferenceResponse, &anyhow::Error>,
+    ) {
+        let outcome = result.map(|r| r.latency_ms);
+        let Some((from, to)) = self.breakers.record(provider, outcome, Instant::now()) else {
+            return;
+        };
+        warn!("{provider} circuit breaker {from} -> {to}");
+        if let Some(bus) = &self.bus {
+            bus.publish(
+                "gateway.provider_health",
+                serde_json::json!({
+                    "provider": provider,
+                    "from": from.as_str(),
+                    "state": to.as_str(),
+                }),
+            );
+        }
+    }
+
+    /// Circuit breaker of every provider, in failover order
+    pub fn provider_health(
+        &self,
+        claude: &ClaudeClient,
+        openai: &OpenAiClient,
+        qwen3: &OpenAiClient,
+    ) -> Vec<ProviderHealth> {
+        let now = Instant::now();
+        FAILOVER_ORDER
+            .iter()
+            .map(|p| {
+                self.breakers
+                    .health(p, is_configured(p, claude, openai, qwen3), now)
+            })
+            .collect()
+    }
+
+    /// Forget a session. Returns whether it existed.
+    pub fn end_session(&mut self, session_id: &str) -> bool {
+        self.sessions.end(session_id)
+    }
+
+    /// Try a single provider. Replayed session turns are limited to half of
+    /// the provider's context window and a sectioned prompt is compressed to
+    /// fit the rest. With a response schema, the result is validated and the
+    /// provider gets one retry with the errors. Returns the response and the
+    /// prompt as sent.
+    #[allow(clippy::too_many_arguments)]
     async fn try_provider(
         &self,
         provider: &str,
         request: &ApiInferRequest,
+        history: &[Turn],
+        claude: &ClaudeClient,
+        openai: &OpenAiClient,
+        qwen3: &OpenAiClient,
+        local: &OpenAiClient,
+        budget: &mut BudgetManager,
+    ) -> Result<(InferenceResponse, String)> {
+        let history = session::trim(history, self.context_windows.window(provider) / 2);
+        let (request, compression) = compress::fit(
+            request,
+            provider,
+            session::turn_tokens(history),
+            &self.context_windows,
+            local,
+            budget,
+        )
+        .await?;
+        let request = &request;
+        let first = self
+            .call_provider(
+                provider, request, history, claude, openai, qwen3, local, budget,
+            )
+            .await?;
+        let first = InferenceResponse {
+            compression,
+            ..first
+        };
+        let Some(schema) = structured::parse_schema(&request.response_schema)? else {
+            return Ok((first, request.prompt.clone()));
+        };
+        let errors = match structured::conform(&first.text, &schema) {
+            Ok(text) => return Ok((InferenceResponse { text, ..first }, request.prompt.clone())),
+            Err(errors) => errors,
+        };
+
+        info!(
+            "{provider} response failed schema validation ({}), retrying",
+            errors.join("; ")
+        );
+        let retry = ApiInferRequest {
+            prompt: structured::retry_prompt(&request.prompt, &first.text, &errors, &schema),
+            ..request.clone()
+        };
+        let second = self
+            .call_provider(
+                provider, &retry, history, claude, openai, qwen3, local, budget,
+            )
+            .await?;
+        let text = structured::conform(&second.text, &schema).map_err(|errors| {
+            anyhow::anyhow!(
+                "{provider} response does not match the response schema: {}",
+                errors.join("; ")
+            )
+        })?;
+        let response = InferenceResponse {
+            text,
+            tokens_used: first.tokens_used + second.tokens_used,
+            latency_ms: first.latency_ms + second.latency_ms,
+            cost_usd: first.cost_usd + second.cost_usd,
+            compression: first.compression,
+            ..second
+        };
+        Ok((response, request.prompt.clone()))
+    }
+
+    /// Send a request, after any replayed session turns, to one provider
+    /// and record its usage
+    #[allow(clippy::too_many_arguments)]
+    #[tracing::instrument(name = "provider.infer", skip_all, fields(provider = %provider))]
+    async fn call_provider(
+        &self,
+        provider: &str,
+        request: &ApiInferRequest,
+        history: &[Turn],
         claude: &ClaudeClient,
         openai: &OpenAiClient,
         qwen3: &OpenAiClient,
         local: &OpenAiClient,
         budget: &mut BudgetManager,
     ) -> Result<InferenceResponse> {
+        let schema = structured::parse_schema(&request.response_schema)?;
+
+        // Fast requests use the provider's cheaper model when it has one
+        let fast = request.model_class == "fast";
+        let fast_claude = fast.then(|| claude.fast_variant()).flatten();
+        let claude = fast_claude.as_ref().unwrap_or(claude);
+        let fast_openai = fast.then(|| openai.fast_variant()).flatten();
+        let openai = fast_openai.as_ref().unwrap_or(openai);
+        let fast_qwen3 = fast.then(|| qwen3.fast_variant()).flatten();
+        let qwen3 = fast_qwen3.as_ref().unwrap_or(qwen3);
+
+        let mut turns = history.to_vec();
+        turns.push(Turn::user(request.prompt.as_str()));
         match provider {
             "claude" => {
                 if !claude.is_available() {
                     bail!("Claude API key not configured");
                 }
-                let r = claude
-                    .infer(
-                        &request.prompt,
+                let mut r = claude
+                    .infer_turns(
+                        &turns,
                         &request.system_prompt,
                         request.max_tokens,
                         request.temperature,
+                        schema.as_ref(),
+                        !request.session_id.is_empty(),
                     )
                     .await?;
-                budget.record_usage("claude", r.tokens_used, &r.model_used);
+                r.cost_usd =
+                    budget.record_request_usage(request, "claude", r.tokens_used, &r.model_used);
                 Ok(r)
             }
             "openai" => {
                 if !openai.is_available() {
                     bail!("OpenAI API key not configured");
                 }
-                let r = openai
-                    .infer(
-                        &request.prompt,
+                let mut r = openai
+                    .infer_turns(
+                        &turns,
                         &request.system_prompt,
                         request.max_tokens,
                         request.temperature,
+                        schema.as_ref(),
                     )
                     .await?;
-                budget.record_usage("openai", r.tokens_used, &r.model_used);
+                r.cost_usd =
+                    budget.record_request_usage(request, "openai", r.tokens_used, &r.model_used);
                 Ok(r)
             }
             "qwen3" => {
                 if !qwen3.is_available() {
                     bail!("Qwen3 API key not configured");
                 }
-                let r = qwen3
-                    .infer(
-                        &request.prompt,
+                let mut r = qwen3
+                    .infer_turns(
+                        &turns,
                         &request.system_prompt,
                         request.max_tokens,
                         request.temperature,
+                        schema.as_ref(),
                     )
                     .await?;
-                budget.record_usage("qwen3", r.tokens_used, &r.model_used);
+                r.cost_usd =
+                    budget.record_request_usage(request, "qwen3", r.tokens_used, &r.model_used);
                 Ok(r)
             }
             "local" => {
                 // Local LLM is always "available" — it uses a placeholder API key.
                 // If the local llama-server is down, the HTTP call will fail and
                 // the fallback chain will try other providers.
-                let r = local
-                    .infer(
-                        &request.prompt,
+                let mut r = local
+                    .infer_turns(
+                        &turns,
                         &request.system_prompt,
                         request.max_tokens,
                         request.temperature,
+                        schema.as_ref(),
                     )
                     .await?;
-                budget.record_usage("local", r.tokens_used, &r.model_used);
+                r.cost_usd =
+                    budget.record_request_usage(request, "local", r.tokens_used, &r.model_used);
                 Ok(r)
             }
             _ => bail!("Unknown provider: {provider}"),
         }
     }
 
+    /// Context window of each provider
+    pub fn context_windows(&self) -> &ContextWindows {
+        &self.context_windows
+    }
+
     /// Select the best provider for a request.
+    /// Requests of model class "fast" try the `AIOS_FAST_PROVIDERS` order
+    /// (default openai, claude) instead of the capability order.
+    /// Providers whose circuit breaker is open are passed over.
     /// Falls back to "local" if no API keys are configured.
     pub fn select_provider(
         &self,
@@ -190,63 +471,31 @@ impl RequestRouter {
             return request.preferred_provider.clone();
         }
 
-        // Priority: Claude > OpenAI > Qwen3 > Local (by capability)
-        if claude.is_available() && !budget.is_provider_budget_exceeded("claude") {
-            "claude".to_string()
-        } else if openai.is_available() && !budget.is_provider_budget_exceeded("openai") {
-            "openai".to_string()
-        } else if qwen3.is_available() && !budget.is_provider_budget_exceeded("qwen3") {
-            "qwen3".to_string()
-        } else {
-            // Local LLM is always available as final fallback (no API key needed)
-            "local".to_string()
-        }
-    }
-
-    fn get_cached(&self, key: u64) -> Option<InferenceResponse> {
-        let now = chrono::Utc::now().timestamp();
-        self.cache.get(&key).and_then(|cached| {
-            if now - cached.cached_at < cached.ttl_seconds {
-                Some(cached.response.clone())
-            } else {
-                None
-            }
-        })
-    }
-
-    fn cache_response(&mut self, key: u64, response: &InferenceResponse) {
-        if self.cache.len() >= self.cache_max_entries {
-            // Remove oldest entry
-            let oldest = self
-                .cache
+        let now = Instant::now();
+        let usable = |p: &str| {
+            is_configured(p, claude, openai, qwen3)
+                && !budget.is_provider_budget_exceeded(p)
+                && self.breakers.allows(p, now)
+        };
+        if request.model_class == "fast" {
+            return self
+                .fast_providers
                 .iter()
-                .min_by_key(|(_, v)| v.cached_at)
-                .map(|(k, _)| *k);
-            if let Some(k) = oldest {
-                self.cache.remove(&k);
-            }
+                .find(|p| usable(p))
+                .cloned()
+                .unwrap_or_else(|| "local".to_string());
         }
 
-        self.cache.insert(
-            key,
-            CachedResponse {
-                response: response.clone(),
-                cached_at: chrono::Utc::now().timestamp(),
-                ttl_seconds: 3600, // 1 hour default TTL
-            },
-        );
+        // Priority: Claude > OpenAI > Qwen3 > Local (by capability)
+        ["claude", "openai", "qwen3"]
+            .into_iter()
+            .find(|p| usable(p))
+            // Local LLM is always available as final fallback (no API key needed)
+            .unwrap_or("local")
+            .to_string()
     }
 }
 
-/// Simple hash for cache keys
-fn hash_request(prompt: &str, system_prompt: &str) -> u64 {
-    use std::hash::{Hash, Hasher};
-    let mut hasher = std::collections::hash_map::DefaultHasher::new();
-    prompt.hash(&mut hasher);
-    system_prompt.hash(&mut hasher);
-    hasher.finish()
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -261,6 +510,15 @@ mod tests {
             requesting_agent: "test-agent".into(),
             task_id: "task-1".into(),
             allow_fallback,
+            response_schema: String::new(),
+            sections: Vec::new(),
+            session_id: String::new(),
+            turn_kind: String::new(),
+            model_class: String::new(),
+            workload: String::new(),
+            goal_id: String::new(),
+            goal_budget: None,
+            bypass_cache: false,
         }
     }
 
@@ -333,82 +591,63 @@ mod tests {
     }
 
     #[test]
-    fn test_hash_request_deterministic() {
-        let hash1 = hash_request("prompt1", "system1");
-        let hash2 = hash_request("prompt1", "system1");
-        assert_eq!(hash1, hash2);
-    }
-
-    #[test]
-    fn test_hash_request_different_prompts() {
-        let hash1 = hash_request("prompt1", "system1");
-        let hash2 = hash_request("prompt2", "system1");
-        assert_ne!(hash1, hash2);
-    }
-
-    #[test]
-    fn test_hash_request_different_system_prompts() {
-        let hash1 = hash_request("prompt1", "system1");
-        let hash2 = hash_request("prompt1", "system2");
-        assert_ne!(hash1, hash2);
-    }
-
-    #[test]
-    fn test_cache_response_and_retrieve() {
+    fn test_select_provider_fast_model_class() {
         let mut router = RequestRouter::new();
-        let key = hash_request("test prompt", "system");
-
-        let response = InferenceResponse {
-            text: "cached response".into(),
-            tokens_used: 100,
-            latency_ms: 50,
-            model_used: "test-model".into(),
-            intelligence_level: "strategic".into(),
-        };
-
-        router.cache_response(key, &response);
-
-        let cached = router.get_cached(key);
-        assert!(cached.is_some());
-        let cached = cached.unwrap();
-        assert_eq!(cached.text, "cached response");
-        assert_eq!(cached.tokens_used, 100);
-        assert_eq!(cached.model_used, "test-model");
-    }
+        router.fast_providers = vec!["openai".into(), "claude".into()];
+        let budget = BudgetManager::new(100.0, 50.0);
+        let (claude, openai, qwen3, local) = make_clients();
+        let mut request = make_request("hello", "", false);
 
-    #[test]
-    fn test_cache_miss() {
-        let router = RequestRouter::new();
-        let key = hash_request("uncached", "prompt");
+        assert_eq!(
+            router.select_provider(&request, &claude, &openai, &qwen3, &local, &budget),
+            "claude"
+        );
+        request.model_class = "fast".into();
+        assert_eq!(
+            router.select_provider(&request, &claude, &openai, &qwen3, &local, &budget),
+            "openai"
+        );
 
-        let cached = router.get_cached(key);
-        assert!(cached.is_none());
+        // Unavailable fast providers are skipped
+        let openai = OpenAiClient::with_config(
+            String::new(),
+            "https://api.openai.com".into(),
+            "gpt-5".into(),
+        );
+        assert_eq!(
+            router.select_provider(&request, &claude, &openai, &qwen3, &local, &budget),
+            "claude"
+        );
+        router.fast_providers.clear();
+        assert_eq!(
+            router.select_provider(&request, &claude, &openai, &qwen3, &local, &budget),
+            "local"
+        );
     }
 
     #[test]
-    fn test_cache_eviction_at_capacity() {
+    fn test_select_provider_skips_open_breakers() {
         let mut router = RequestRouter::new();
-        // Fill cache to max
-        for i in 0..router.cache_max_entries + 10 {
-            let key = hash_request(&format!("prompt_{i}"), "sys");
-            let response = InferenceResponse {
-                text: format!("response_{i}"),
-                tokens_used: 10,
-                latency_ms: 5,
-                model_used: "test".into(),
-                intelligence_level: "tactical".into(),
-            };
-            router.cache_response(key, &response);
-        }
-
-        // Cache should not exceed max_entries
-        assert!(router.cache.len() <= router.cache_max_entries);
-    }
+        let budget = BudgetManager::new(100.0, 50.0);
+        let (claude, openai, qwen3, local) = make_clients();
+        let request = make_request("hello", "", true);
+
+        let limited = anyhow::Error::new(crate::breaker::RateLimited {
+            provider: "Claude".into(),
+            retry_after: None,
+            body: String::new(),
+        });
+        router.record_call("claude", Err(&limited));
+        assert_eq!(
+            router.select_provider(&request, &claude, &openai, &qwen3, &local, &budget),
+            "openai"
+        );
 
-    #[test]
-    fn test_new_router_empty_cache() {
-        let router = RequestRouter::new();
-        assert!(router.cache.is_empty());
-        assert_eq!(router.cache_max_entries, 1000);
+        let health = router.provider_health(&claude, &openai, &qwen3);
+        let order: Vec<&str> = health.iter().map(|h| h.provider.as_str()).collect();
+        assert_eq!(order, FAILOVER_ORDER);
+        assert_eq!(health[1].state, "open");
+        assert_eq!(health[1].rate_limited, 1);
+        assert!(health.iter().all(|h| h.configured));
     }
 }
@@ -0,0 +1,251 @@
+//! Sessions — gateway-held conversations for multi-turn agentic work
+//!
+//! A request carrying a `session_id` continues a conversation: the gateway
+//! replays the earlier turns to the provider, so callers send only what is
+//! new (typically the results of the tool calls the model asked for) rather
+//! than rebuilding the whole prompt every turn. Sessions stay with the
+//! provider that served them so its prompt cache keeps hitting; Claude
+//! requests mark the replayed prefix with `cache_control` breakpoints.
+//!
+//! Idle sessions expire after `AIOS_SESSION_TTL_SECS` (1800); at most
+//! `AIOS_MAX_SESSIONS` (500) are held, evicting the least recently used.
+
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+
+use crate::compress::estimate_tokens;
+
+/// Turn kind for the results of the previous turn's tool calls
+pub const TOOL_RESULTS: &str = "tool_results";
+
+/// One message of a conversation
+#[derive(Clone, Debug, PartialEq)]
+pub struct Turn {
+    /// "user" or "assistant"
+    pub role: &'static str,
+    pub content: String,
+}
+
+impl Turn {
+    pub fn user(content: impl Into<String>) -> Self {
+        Self {
+            role: "user",
+            content: content.into(),
+        }
+    }
+
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self {
+            role: "assistant",
+            content: content.into(),
+        }
+    }
+}
+
+/// Conversation state replayed for the next turn of a session
+#[derive(Default)]
+pub struct SessionState {
+    pub system_prompt: String,
+    /// Provider that served the previous turns; "" for a new session
+    pub provider: String,
+    pub turns: Vec<Turn>,
+}
+
+struct Session {
+    state: SessionState,
+    last_used: i64,
+}
+
+/// Conversations held by the gateway, keyed by session id
+pub struct SessionStore {
+    sessions: HashMap<String, Session>,
+    ttl_secs: i64,
+    max_sessions: usize,
+}
+
+impl SessionStore {
+    pub fn new(ttl_secs: i64, max_sessions: usize) -> Self {
+        Self {
+            sessions: HashMap::new(),
+            ttl_secs,
+            max_sessions: max_sessions.max(1),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let ttl_secs = std::env::var("AIOS_SESSION_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(1800);
+        let max_sessions = std::env::var("AIOS_MAX_SESSIONS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(500);
+        Self::new(ttl_secs, max_sessions)
+    }
+
+    /// State to continue session `id` with. An unknown id starts a new
+    /// session, except for a tool-results turn, which only makes sense
+    /// after an earlier turn. A non-empty `system_prompt` replaces the
+    /// stored one.
+    pub fn resume(
+        &mut self,
+        id: &str,
+        system_prompt: &str,
+        turn_kind: &str,
+    ) -> Result<SessionState> {
+        self.evict_expired(chrono::Utc::now().timestamp());
+        let mut state = match self.sessions.get(id) {
+            Some(session) => SessionState {
+                system_prompt: session.state.system_prompt.clone(),
+                provider: session.state.provider.clone(),
+                turns: session.state.turns.clone(),
+            },
+            None if turn_kind == TOOL_RESULTS => {
+                bail!("Unknown or expired session {id}; resend the full prompt")
+            }
+            None => SessionState::default(),
+        };
+        if !system_prompt.is_empty() {
+            state.system_prompt = system_prompt.to_string();
+        }
+        Ok(state)
+    }
+
+    /// Append a completed exchange to session `id`, creating it if needed
+    pub fn record(
+        &mut self,
+        id: &str,
+        system_prompt: &str,
+        provider: &str,
+        prompt: String,
+        response: String,
+    ) {
+        let now = chrono::Utc::now().timestamp();
+        if !self.sessions.contains_key(id) && self.sessions.len() >= self.max_sessions {
+            let oldest = self
+                .sessions
+                .iter()
+                .min_by_key(|(_, s)| s.last_used)
+                .map(|(k, _)| k.clone());
+            if let Some(k) = oldest {
+                self.sessions.remove(&k);
+            }
+        }
+        let session = self
+            .sessions
+            .entry(id.to_string())
+            .or_insert_with(|| Session {
+                state: SessionState::default(),
+                last_used: now,
+            });
+        session.state.system_prompt = system_prompt.to_string();
+        session.state.provider = provider.to_string();
+        session.state.turns.push(Turn::user(prompt));
+        session.state.turns.push(Turn::assistant(response));
+        session.last_used = now;
+    }
+
+    /// Forget session `id`. Returns whether it existed.
+    pub fn end(&mut self, id: &str) -> bool {
+        self.sessions.remove(id).is_some()
+    }
+
+    fn evict_expired(&mut self, now: i64) {
+        let ttl = self.ttl_secs;
+        self.sessions.retain(|_, s| now - s.last_used < ttl);
+    }
+}
+
+/// Content of the user message for a turn
+pub fn user_content(prompt: &str, turn_kind: &str) -> String {
+    if turn_kind == TOOL_RESULTS {
+        format!("Results of the tool calls you requested:\n{prompt}")
+    } else {
+        prompt.to_string()
+    }
+}
+
+/// Tokens of replayed turns
+pub fn turn_tokens(turns: &[Turn]) -> usize {
+    turns.iter().map(|t| estimate_tokens(&t.content)).sum()
+}
+
+/// The newest turns that fit in `max_tokens`, dropping the oldest
+/// exchanges first so the conversation still opens with a user turn
+pub fn trim(turns: &[Turn], max_tokens: usize) -> &[Turn] {
+    let mut start = 0;
+    while start < turns.len() && turn_tokens(&turns[start..]) > max_tokens {
+        start += 2;
+    }
+    &turns[start.min(turns.len())..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_resume_and_record() {
+        let mut store = SessionStore::new(1800, 10);
+
+        // A tool-results turn cannot open a session
+        assert!(store.resume("task-1", "sys", TOOL_RESULTS).is_err());
+
+        let state = store.resume("task-1", "sys", "").unwrap();
+        assert!(state.turns.is_empty());
+        assert_eq!(state.system_prompt, "sys");
+        store.record(
+            "task-1",
+            &state.system_prompt,
+            "claude",
+            "plan".into(),
+            "calls".into(),
+        );
+
+        // The stored system prompt applies when a turn omits it
+        let state = store.resume("task-1", "", TOOL_RESULTS).unwrap();
+        assert_eq!(state.system_prompt, "sys");
+        assert_eq!(state.provider, "claude");
+        assert_eq!(
+            state.turns,
+            vec![Turn::user("plan"), Turn::assistant("calls")]
+        );
+
+        assert!(store.end("task-1"));
+        assert!(!store.end("task-1"));
+        assert!(store.resume("task-1", "", TOOL_RESULTS).is_err());
+    }
+
+    #[test]
+    fn test_session_eviction() {
+        let mut store = SessionStore::new(1800, 2);
+        store.record("a", "", "local", "p".into(), "r".into());
+        store.record("b", "", "local", "p".into(), "r".into());
+        store.sessions.get_mut("a").unwrap().last_used -= 10;
+        store.record("c", "", "local", "p".into(), "r".into());
+        assert!(!store.sessions.contains_key("a"));
+        assert_eq!(store.sessions.len(), 2);
+
+        // Idle sessions expire
+        store.sessions.get_mut("b").unwrap().last_used -= 3600;
+        assert!(store.resume("b", "", TOOL_RESULTS).is_err());
+        assert!(store.resume("c", "", TOOL_RESULTS).is_ok());
+    }
+
+    #[test]
+    fn test_trim_drops_oldest_exchanges() {
+        let turns = vec![
+            Turn::user("x".repeat(400)),
+            Turn::assistant("x".repeat(40)),
+            Turn::user("x".repeat(40)),
+            Turn::assistant("x".repeat(40)),
+        ];
+        assert_eq!(trim(&turns, 1000).len(), 4);
+        let kept = trim(&turns, 50);
+        assert_eq!(kept.len(), 2);
+        assert_eq!(kept[0].role, "user");
+        assert!(trim(&turns, 5).is_empty());
+    }
+}
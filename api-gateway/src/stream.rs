@@ -0,0 +1,241 @@
+//! Streaming — server-sent events from the providers
+//!
+//! StreamInfer asks the provider for a streamed response and forwards each
+//! text delta as it arrives. The tokens a stream has used are tracked as
+//! it goes (`StreamUsage`): the provider's own counts once it reports them,
+//! estimates until then. A stream that ends early, because the provider
+//! failed or the caller went away, is still accounted for what it used.
+
+use anyhow::Result;
+use tokio::sync::mpsc;
+
+use crate::claude::ClaudeClient;
+use crate::compress::estimate_tokens;
+use crate::openai::OpenAiClient;
+use crate::proto::common::InferenceResponse;
+use crate::session::Turn;
+
+/// One server-sent event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SseEvent {
+    /// Event name; "" when the event has none
+    pub event: String,
+    pub data: String,
+}
+
+/// Splits a response body into server-sent events as its bytes arrive
+#[derive(Default)]
+pub struct SseParser {
+    buffer: Vec<u8>,
+}
+
+impl SseParser {
+    /// Add bytes of the body. Returns the events they completed.
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<SseEvent> {
+        self.buffer.extend_from_slice(bytes);
+        let mut events = Vec::new();
+        while let Some((end, separator)) = find_blank_line(&self.buffer) {
+            let block: Vec<u8> = self.buffer.drain(..end + separator).collect();
+            if let Some(event) = parse_event(&String::from_utf8_lossy(&block[..end])) {
+                events.push(event);
+            }
+        }
+        events
+    }
+}
+
+/// Position and length of the first blank line ending an event
+fn find_blank_line(buffer: &[u8]) -> Option<(usize, usize)> {
+    (0..buffer.len()).find_map(|i| {
+        if buffer[i..].starts_with(b"\r\n\r\n") {
+            Some((i, 4))
+        } else if buffer[i..].starts_with(b"\n\n") {
+            Some((i, 2))
+        } else {
+            None
+        }
+    })
+}
+
+fn parse_event(block: &str) -> Option<SseEvent> {
+    let mut event = String::new();
+    let mut data: Vec<&str> = Vec::new();
+    for line in block.lines() {
+        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "event" => event = value.to_string(),
+            "data" => data.push(value),
+            // Comments (": ping") and unknown fields
+            _ => {}
+        }
+    }
+    (!data.is_empty()).then(|| SseEvent {
+        event,
+        data: data.join("\n"),
+    })
+}
+
+/// Tokens a stream has used so far
+#[derive(Debug, Default, Clone)]
+pub struct StreamUsage {
+    pub input_tokens: i32,
+    pub output_tokens: i32,
+    pub cached_tokens: i32,
+    pub model: String,
+    /// Bytes of text streamed, for the output estimate
+    streamed: usize,
+    /// Whether output_tokens is the provider's count, not an estimate
+    output_reported: bool,
+}
+
+impl StreamUsage {
+    /// Start with an estimate of the prompt's tokens
+    pub fn estimate(system_prompt: &str, turns: &[Turn], model: &str) -> Self {
+        Self {
+            input_tokens: (estimate_tokens(system_prompt)
+                + turns
+                    .iter()
+                    .map(|t| estimate_tokens(&t.content))
+                    .sum::<usize>()) as i32,
+            model: model.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Count a streamed text delta
+    pub fn add_text(&mut self, delta: &str) {
+        self.streamed += delta.len();
+        if !self.output_reported {
+            self.output_tokens = self.streamed.div_ceil(4) as i32;
+        }
+    }
+
+    /// Replace the output estimate with the provider's count
+    pub fn report_output(&mut self, tokens: i32) {
+        self.output_tokens = tokens;
+        self.output_reported = true;
+    }
+
+    pub fn tokens_used(&self) -> i32 {
+        self.input_tokens + self.output_tokens
+    }
+}
+
+/// The client of the provider a stream is sent to
+pub enum StreamClient {
+    Claude(ClaudeClient),
+    OpenAi(OpenAiClient),
+}
+
+impl StreamClient {
+    /// Stream a response, sending each text delta to `chunks` and keeping
+    /// `usage` current; `usage` stays empty if the provider refused the
+    /// request. Fails if `chunks` is closed before the end.
+    pub async fn stream(
+        &self,
+        prompt: &str,
+        system_prompt: &str,
+        max_tokens: i32,
+        temperature: f32,
+        usage: &mut StreamUsage,
+        chunks: &mpsc::Sender<String>,
+    ) -> Result<InferenceResponse> {
+        let turns = [Turn::user(prompt)];
+        match self {
+            StreamClient::Claude(c) => {
+                c.stream_turns(
+                    &turns,
+                    system_prompt,
+                    max_tokens,
+                    temperature,
+                    usage,
+                    chunks,
+                )
+                .await
+            }
+            StreamClient::OpenAi(c) => {
+                c.stream_turns(
+                    &turns,
+                    system_prompt,
+                    max_tokens,
+                    temperature,
+                    usage,
+                    chunks,
+                )
+                .await
+            }
+        }
+    }
+}
+
+/// The events of a streaming response body, read as they arrive
+pub struct EventStream {
+    response: reqwest::Response,
+    parser: SseParser,
+    pending: std::collections::VecDeque<SseEvent>,
+}
+
+impl EventStream {
+    pub fn new(response: reqwest::Response) -> Self {
+        Self {
+            response,
+            parser: SseParser::default(),
+            pending: Default::default(),
+        }
+    }
+
+    /// The next event, None once the body ends
+    pub async fn next(&mut self) -> Result<Option<SseEvent>> {
+        while self.pending.is_empty() {
+            let Some(bytes) = self.response.chunk().await? else {
+                return Ok(None);
+            };
+            self.pending.extend(self.parser.push(&bytes));
+        }
+        Ok(self.pending.pop_front())
+    }
+}
+
+/// Send a text delta to the caller
+pub async fn forward(chunks: &mpsc::Sender<String>, delta: &str) -> Result<()> {
+    if chunks.send(delta.to_string()).await.is_err() {
+        anyhow::bail!("Stream cancelled by the caller");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sse_parser_handles_split_events() {
+        let mut parser = SseParser::default();
+        assert!(parser
+            .push(b"event: message_start\ndata: {\"a\"")
+            .is_empty());
+        let events = parser.push(b":1}\n\n: ping\n\ndata: [DO");
+        assert_eq!(
+            events,
+            vec![SseEvent {
+                event: "message_start".into(),
+                data: "{\"a\":1}".into(),
+            }]
+        );
+        // A multi-byte character split across chunks survives
+        let snowman = "data: \u{2603}\r\n\r\n".as_bytes();
+        let events = parser.push(b"NE]\n\n");
+        assert_eq!(events[0].data, "[DONE]");
+        assert!(parser.push(&snowman[..7]).is_empty());
+        assert_eq!(parser.push(&snowman[7..])[0].data, "\u{2603}");
+
+        let mut usage = StreamUsage::estimate("system", &[Turn::user("hello there")], "m");
+        assert_eq!(usage.input_tokens, 2 + 3);
+        usage.add_text("Hello, wor");
+        assert_eq!(usage.output_tokens, 3);
+        usage.report_output(2);
+        usage.add_text("ld!");
+        assert_eq!(usage.tokens_used(), 7);
+    }
+}
@@ -0,0 +1,398 @@
+//! Structured Outputs — JSON schema enforcement for inference responses
+//!
+//! A request with `response_schema` gets JSON that matches it, whichever
+//! provider serves it:
+//! - OpenAI-compatible APIs: `response_format` of type `json_schema`
+//! - Claude: a single forced tool whose input schema is the response schema
+//! - local llama-server: a GBNF grammar generated from the schema
+//!
+//! Provider constraints are not trusted. The router validates every result
+//! here and, on failure, retries once with the validation errors appended
+//! to the prompt. The validator covers the JSON Schema subset used for
+//! agent outputs: type, enum, const, properties, required,
+//! additionalProperties, items, and the length and range bounds.
+
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+
+/// Name of the tool Claude is forced to call with the structured result
+pub const CLAUDE_TOOL_NAME: &str = "respond";
+
+/// Property that carries a non-object result through the Claude tool
+const WRAPPED_PROPERTY: &str = "value";
+
+/// Parse the `response_schema` request field; None when it is empty
+pub fn parse_schema(schema: &str) -> Result<Option<Value>> {
+    if schema.trim().is_empty() {
+        return Ok(None);
+    }
+    let schema: Value =
+        serde_json::from_str(schema).context("response_schema is not valid JSON")?;
+    if !schema.is_object() {
+        bail!("response_schema must be a JSON object");
+    }
+    Ok(Some(schema))
+}
+
+/// Check `value` against `schema`; returns one message per violation
+pub fn validate(schema: &Value, value: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    check(schema, value, "$", &mut errors);
+    errors
+}
+
+fn check(schema: &Value, value: &Value, path: &str, errors: &mut Vec<String>) {
+    let types: Vec<&str> = match &schema["type"] {
+        Value::String(t) => vec![t.as_str()],
+        Value::Array(ts) => ts.iter().filter_map(|t| t.as_str()).collect(),
+        _ => Vec::new(),
+    };
+    if !types.is_empty() && !types.iter().any(|t| type_matches(t, value)) {
+        errors.push(format!("{path}: expected {}", types.join(" or ")));
+        return;
+    }
+    if let Some(options) = schema["enum"].as_array() {
+        if !options.contains(value) {
+            errors.push(format!(
+                "{path}: must be one of {}",
+                Value::Array(options.clone())
+            ));
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if expected != value {
+            errors.push(format!("{path}: must be {expected}"));
+        }
+    }
+
+    match value {
+        Value::Object(map) => {
+            if let Some(required) = schema["required"].as_array() {
+                for name in required.iter().filter_map(|n| n.as_str()) {
+                    if !map.contains_key(name) {
+                        errors.push(format!("{path}: missing required property '{name}'"));
+                    }
+                }
+            }
+            let properties = schema["properties"].as_object();
+            for (key, item) in map {
+                match properties.and_then(|p| p.get(key)) {
+                    Some(sub) => check(sub, item, &format!("{path}.{key}"), errors),
+                    None => match &schema["additionalProperties"] {
+                        Value::Bool(false) => {
+                            errors.push(format!("{path}: unexpected property '{key}'"))
+                        }
+                        sub @ Value::Object(_) => {
+                            check(sub, item, &format!("{path}.{key}"), errors)
+                        }
+                        _ => {}
+                    },
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(min) = schema["minItems"].as_u64() {
+                if (items.len() as u64) < min {
+                    errors.push(format!("{path}: needs at least {min} items"));
+                }
+            }
+            if let Some(max) = schema["maxItems"].as_u64() {
+                if items.len() as u64 > max {
+                    errors.push(format!("{path}: allows at most {max} items"));
+                }
+            }
+            if let Some(sub) = schema.get("items").filter(|s| s.is_object()) {
+                for (i, item) in items.iter().enumerate() {
+                    check(sub, item, &format!("{path}[{i}]"), errors);
+                }
+            }
+        }
+        Value::String(s) => {
+            let len = s.chars().count() as u64;
+            if let Some(min) = schema["minLength"].as_u64() {
+                if len < min {
+                    errors.push(format!("{path}: shorter than {min} characters"));
+                }
+            }
+            if let Some(max) = schema["maxLength"].as_u64() {
+                if len > max {
+                    errors.push(format!("{path}: longer than {max} characters"));
+                }
+            }
+        }
+        Value::Number(n) => {
+            let n = n.as_f64().unwrap_or_default();
+            if let Some(min) = schema["minimum"].as_f64() {
+                if n < min {
+                    errors.push(format!("{path}: less than the minimum {min}"));
+                }
+            }
+            if let Some(max) = schema["maximum"].as_f64() {
+                if n > max {
+                    errors.push(format!("{path}: greater than the maximum {max}"));
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn type_matches(expected: &str, value: &Value) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => {
+            value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0)
+        }
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+/// Pull the JSON document out of a model response: the whole text, a
+/// fenced code block, or the outermost object or array within it
+pub fn extract_json(text: &str) -> Result<Value> {
+    let text = text.trim();
+    if let Ok(value) = serde_json::from_str(text) {
+        return Ok(value);
+    }
+    if let Some(start) = text.find("```") {
+        let body = &text[start + 3..];
+        let body = body.strip_prefix("json").unwrap_or(body);
+        if let Some(end) = body.find("```") {
+            if let Ok(value) = serde_json::from_str(body[..end].trim()) {
+                return Ok(value);
+            }
+        }
+    }
+    let start = text.find(['{', '[']).context("response contains no JSON")?;
+    let close = if text[start..].starts_with('{') {
+        '}'
+    } else {
+        ']'
+    };
+    let end = text
+        .rfind(close)
+        .context("response contains no complete JSON")?;
+    if end < start {
+        bail!("response contains no complete JSON");
+    }
+    serde_json::from_str(&text[start..=end]).context("response is not valid JSON")
+}
+
+/// Extract and validate a response; the compact JSON text on success
+pub fn conform(text: &str, schema: &Value) -> std::result::Result<String, Vec<String>> {
+    let value = extract_json(text).map_err(|e| vec![format!("{e:#}")])?;
+    let errors = validate(schema, &value);
+    if errors.is_empty() {
+        Ok(value.to_string())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Prompt for the single retry after a response failed validation
+pub fn retry_prompt(prompt: &str, previous: &str, errors: &[String], schema: &Value) -> String {
+    format!(
+        "{prompt}\n\nYour previous response did not match the required JSON schema:\n- {}\n\n\
+         Previous response:\n{previous}\n\n\
+         Respond again with only a JSON value that matches this schema:\n{schema}",
+        errors.join("\n- ")
+    )
+}
+
+/// Claude tool input schemas must be objects; other schemas are wrapped
+/// in a single required property. Returns the schema and whether it was
+/// wrapped.
+pub fn claude_tool_schema(schema: &Value) -> (Value, bool) {
+    if schema["type"] == "object" {
+        (schema.clone(), false)
+    } else {
+        (
+            serde_json::json!({
+                "type": "object",
+                "properties": { WRAPPED_PROPERTY: schema },
+                "required": [WRAPPED_PROPERTY],
+            }),
+            true,
+        )
+    }
+}
+
+/// The structured result from a Claude tool input
+pub fn claude_tool_result(input: Value, wrapped: bool) -> Value {
+    if wrapped {
+        input.get(WRAPPED_PROPERTY).cloned().unwrap_or(Value::Null)
+    } else {
+        input
+    }
+}
+
+/// Generic JSON rules every generated grammar builds on
+const GBNF_BASE: &str = r#"ws ::= [ \t\n]*
+string ::= "\"" ( [^"\\\x00-\x1f] | "\\" ( ["\\/bfnrt] | "u" [0-9a-fA-F] [0-9a-fA-F] [0-9a-fA-F] [0-9a-fA-F] ) )* "\""
+number ::= "-"? ( [0-9] | [1-9] [0-9]* ) ( "." [0-9]+ )? ( [eE] [-+]? [0-9]+ )?
+integer ::= "-"? ( [0-9] | [1-9] [0-9]* )
+boolean ::= "true" | "false"
+null ::= "null"
+value ::= object | array | string | number | boolean | null
+object ::= "{" ws ( string ws ":" ws value ( ws "," ws string ws ":" ws value )* )? ws "}"
+array ::= "[" ws ( value ( ws "," ws value )* )? ws "]"
+"#;
+
+/// GBNF grammar (llama.cpp) that only admits JSON shaped like `schema`.
+/// Objects with declared properties emit all of them, in key order;
+/// constructs outside the supported subset fall back to any JSON value.
+pub fn to_gbnf(schema: &Value) -> String {
+    let mut rules = Vec::new();
+    let root = gbnf_rule(schema, &mut rules);
+    let mut grammar = format!("root ::= {root}\n");
+    for (i, rule) in rules.iter().enumerate() {
+        grammar.push_str(&format!("r{i} ::= {rule}\n"));
+    }
+    grammar.push_str(GBNF_BASE);
+    grammar
+}
+
+/// Expression for `schema`, adding named rules for nested structures
+fn gbnf_rule(schema: &Value, rules: &mut Vec<String>) -> String {
+    let literals = match (&schema["enum"], schema.get("const")) {
+        (Value::Array(options), _) => Some(options.clone()),
+        (_, Some(constant)) => Some(vec![constant.clone()]),
+        _ => None,
+    };
+    if let Some(literals) = literals {
+        let alternatives: Vec<String> = literals
+            .iter()
+            .map(|v| gbnf_literal(&v.to_string()))
+            .collect();
+        return format!("( {} )", alternatives.join(" | "));
+    }
+
+    let expression = match schema["type"].as_str() {
+        Some(base @ ("string" | "number" | "integer" | "boolean" | "null")) => {
+            return base.to_string()
+        }
+        Some("array") => match schema.get("items").filter(|s| s.is_object()) {
+            Some(items) => {
+                let item = gbnf_rule(items, rules);
+                format!("\"[\" ws ( {item} ( ws \",\" ws {item} )* )? ws \"]\"")
+            }
+            None => return "array".to_string(),
+        },
+        Some("object") => match schema["properties"].as_object() {
+            Some(properties) if !properties.is_empty() => {
+                let members: Vec<String> = properties
+                    .iter()
+                    .map(|(key, sub)| {
+                        let key = gbnf_literal(&Value::String(key.clone()).to_string());
+                        format!("{key} ws \":\" ws {}", gbnf_rule(sub, rules))
+                    })
+                    .collect();
+                format!("\"{{\" ws {} ws \"}}\"", members.join(" ws \",\" ws "))
+            }
+            _ => return "object".to_string(),
+        },
+        _ => return "value".to_string(),
+    };
+    rules.push(expression);
+    format!("r{}", rules.len() - 1)
+}
+
+/// Quote literal text as a GBNF string
+fn gbnf_literal(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan_schema() -> Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "action": {"type": "string", "enum": ["restart", "ignore"]},
+                "steps": {"type": "array", "items": {"type": "string"}, "minItems": 1},
+                "confidence": {"type": "number", "minimum": 0, "maximum": 1}
+            },
+            "required": ["action", "steps"],
+            "additionalProperties": false
+        })
+    }
+
+    #[test]
+    fn test_validate_reports_each_violation() {
+        let schema = plan_schema();
+        let ok = serde_json::json!({"action": "restart", "steps": ["stop", "start"]});
+        assert!(validate(&schema, &ok).is_empty());
+
+        let bad = serde_json::json!({
+            "action": "reboot",
+            "steps": [1],
+            "confidence": 2,
+            "note": "x"
+        });
+        let errors = validate(&schema, &bad);
+        assert_eq!(errors.len(), 4, "{errors:?}");
+        assert!(errors
+            .iter()
+            .any(|e| e.starts_with("$.action: must be one of")));
+        assert!(errors.contains(&"$.steps[0]: expected string".to_string()));
+        assert!(errors.contains(&"$.confidence: greater than the maximum 1".to_string()));
+        assert!(errors.contains(&"$: unexpected property 'note'".to_string()));
+
+        let errors = validate(&schema, &serde_json::json!({"action": "ignore"}));
+        assert_eq!(errors, vec!["$: missing required property 'steps'"]);
+    }
+
+    #[test]
+    fn test_conform_extracts_json_from_prose() {
+        let schema = plan_schema();
+        let text =
+            "Here is the plan:\n```json\n{\"action\": \"ignore\", \"steps\": [\"wait\"]}\n```";
+        assert_eq!(
+            conform(text, &schema).unwrap(),
+            r#"{"action":"ignore","steps":["wait"]}"#
+        );
+        let text = "Sure! {\"action\": \"restart\", \"steps\": [\"go\"]} Done.";
+        assert!(conform(text, &schema).is_ok());
+        assert!(conform("no json here", &schema).is_err());
+
+        assert!(parse_schema("").unwrap().is_none());
+        assert!(parse_schema("[1]").is_err());
+        assert!(parse_schema("{\"type\": \"object\"}").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_claude_tool_schema_wraps_non_objects() {
+        let (schema, wrapped) = claude_tool_schema(&plan_schema());
+        assert!(!wrapped);
+        assert_eq!(schema, plan_schema());
+
+        let list = serde_json::json!({"type": "array", "items": {"type": "string"}});
+        let (schema, wrapped) = claude_tool_schema(&list);
+        assert!(wrapped);
+        assert_eq!(schema["required"][0], "value");
+        let input = serde_json::json!({"value": ["a", "b"]});
+        assert_eq!(
+            claude_tool_result(input, true),
+            serde_json::json!(["a", "b"])
+        );
+    }
+
+    #[test]
+    fn test_gbnf_follows_schema_structure() {
+        let grammar = to_gbnf(&plan_schema());
+        assert!(grammar.starts_with("root ::= r"));
+        assert!(grammar.contains(r#"( "\"restart\"" | "\"ignore\"" )"#));
+        assert!(grammar.contains(r#""\"steps\"" ws ":" ws r"#));
+        assert!(grammar.contains("string ::= "));
+
+        assert!(to_gbnf(&serde_json::json!({"type": "integer"})).starts_with("root ::= integer\n"));
+        assert!(to_gbnf(&serde_json::json!({})).starts_with("root ::= value\n"));
+    }
+}
@@ -63,6 +63,9 @@ pub struct BootConfig {
     pub debug_shell: bool,
     #[serde(default = "default_clean_shutdown_flag")]
     pub clean_shutdown_flag: String,
+    /// Run the setup wizard on the console at first boot
+    #[serde(default = "default_true")]
+    pub first_boot_setup: bool,
 }
 
 impl Default for BootConfig {
@@ -71,6 +74,7 @@ impl Default for BootConfig {
             init_timeout_seconds: default_init_timeout(),
             debug_shell: false,
             clean_shutdown_flag: default_clean_shutdown_flag(),
+            first_boot_setup: true,
         }
     }
 }
@@ -441,10 +445,14 @@ fn default_log_keep() -> u32 {
     10
 }
 
+/// Path of config.toml: `AIOS_CONFIG`, else /etc/aios/config.toml
+pub fn config_path() -> String {
+    std::env::var("AIOS_CONFIG").unwrap_or_else(|_| DEFAULT_CONFIG_PATH.to_string())
+}
+
 /// Load configuration from /etc/aios/config.toml
 pub fn load_config() -> Result<AiosConfig> {
-    let config_path =
-        std::env::var("AIOS_CONFIG").unwrap_or_else(|_| DEFAULT_CONFIG_PATH.to_string());
+    let config_path = config_path();
 
     if Path::new(&config_path).exists() {
         let content = fs::read_to_string(&config_path)
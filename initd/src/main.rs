@@ -7,6 +7,15 @@
 //! - Start and supervise all aiOS services
 //! - Reap zombie processes
 //! - Handle shutdown signals
+//!
+//! Not running as PID 1 (or given `--supervised`), it runs under the host's
+//! init instead: mounts, hostname and zombie reaping are left to the host,
+//! and only the dependency-ordered, health-gated startup and supervision of
+//! the services remain. `--generate-units` writes systemd units that do
+//! the same without aios-init staying resident (see `units`).
+//!
+//! At first boot with a console attached, and on `--setup`, the setup
+//! wizard asks for what a usable system needs (see `setup`).
 
 use anyhow::{Context, Result};
 use std::fs;
@@ -20,16 +29,105 @@ use tracing::{info, warn};
 mod config;
 mod hardware;
 mod service;
+mod setup;
+mod units;
+
+const USAGE: &str = "Usage: aios-init [option]
+
+Options:
+  --supervised               Run under an existing init: start and supervise
+                             the services without mounting filesystems,
+                             setting the hostname or reaping zombies
+                             (the default when not PID 1)
+  --generate-units [dir]     Write systemd units for the installed services
+                             and aios.target (default /etc/systemd/system)
+  --wait-healthy <service>   Wait until a service accepts connections
+                             (ExecStartPost of the generated units)
+  --setup                    Run the setup wizard on this terminal: hostname,
+                             API keys, autonomy level, models and the
+                             operator account
+  -h, --help                 This text
+
+Service binaries are looked up in AIOS_BIN_DIR (default /usr/sbin).";
 
 fn main() {
-    if let Err(e) = run() {
-        eprintln!("FATAL: aios-init failed: {e:#}");
-        // PID 1 must not exit — spawn emergency shell
-        spawn_emergency_shell();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("-h" | "--help") => {
+            println!("{USAGE}");
+            return;
+        }
+        Some("--generate-units") => generate_units(args.get(1).map(String::as_str)),
+        Some("--wait-healthy") => wait_healthy(args.get(1).map(String::as_str)),
+        Some("--setup") => run_setup(),
+        Some("--supervised") | None => {
+            let supervised = !args.is_empty() || std::process::id() != 1;
+            match run(supervised) {
+                Ok(()) => return,
+                Err(e) if supervised => Err(e),
+                Err(e) => {
+                    eprintln!("FATAL: aios-init failed: {e:#}");
+                    // PID 1 must not exit — spawn emergency shell
+                    spawn_emergency_shell();
+                    return;
+                }
+            }
+        }
+        Some(other) => Err(anyhow::anyhow!("Unknown option {other}\n\n{USAGE}")),
+    };
+    if let Err(e) = result {
+        eprintln!("aios-init: {e:#}");
+        std::process::exit(1);
     }
 }
 
-fn run() -> Result<()> {
+/// `--generate-units`: write systemd units for the installed services
+fn generate_units(dir: Option<&str>) -> Result<()> {
+    init_logging()?;
+    let config = config::load_config()?;
+    let dir = Path::new(dir.unwrap_or(units::DEFAULT_UNIT_DIR));
+    for path in units::write_units(dir, &service::bin_dir(), &config.agents)? {
+        println!("{}", path.display());
+    }
+    println!(
+        "Run `systemctl daemon-reload && systemctl enable --now {}`",
+        units::TARGET
+    );
+    Ok(())
+}
+
+/// `--wait-healthy`: block until a service accepts connections
+fn wait_healthy(name: Option<&str>) -> Result<()> {
+    let Some(name) = name else {
+        anyhow::bail!("--wait-healthy needs a service name");
+    };
+    let spec = service::spec(name).with_context(|| format!("Unknown service {name}"))?;
+    spec.wait_until_ready()
+}
+
+/// `--setup`: run the setup wizard on this terminal
+fn run_setup() -> Result<()> {
+    init_logging()?;
+    let config = config::load_config()?;
+    setup_wizard(&config)?;
+    println!("Setup complete. Restart the aiOS services to apply it.");
+    Ok(())
+}
+
+/// Ask the operator on the console and apply the answers
+fn setup_wizard(config: &config::AiosConfig) -> Result<setup::SetupState> {
+    let stdin = std::io::stdin();
+    let mut console =
+        setup::Console::new(stdin.lock(), std::io::stdout(), setup::console_attached());
+    let answers = setup::ask(&mut console, config)?;
+    setup::apply(
+        &answers,
+        &setup::SetupPaths::default(),
+        &config.models.model_dir,
+    )
+}
+
+fn run(supervised: bool) -> Result<()> {
     // Initialize tracing early
     init_logging()?;
 
@@ -38,17 +136,23 @@ fn run() -> Result<()> {
     info!("========================================");
 
     // Phase 1: Mount filesystems
-    info!("Phase 1: Mounting filesystems...");
-    mount_filesystems()?;
-    info!("Filesystems mounted");
+    if supervised {
+        info!("Supervised mode: filesystems, hostname and reaping left to the host init");
+    } else {
+        info!("Phase 1: Mounting filesystems...");
+        mount_filesystems()?;
+        info!("Filesystems mounted");
+    }
 
     // Phase 2: Read configuration
     info!("Phase 2: Loading configuration...");
-    let config = config::load_config()?;
+    let mut config = config::load_config()?;
     info!("Configuration loaded: hostname={}", config.system.hostname);
 
     // Set hostname
-    set_hostname(&config.system.hostname)?;
+    if !supervised {
+        set_hostname(&config.system.hostname)?;
+    }
 
     // Phase 3: Hardware detection
     info!("Phase 3: Detecting hardware...");
@@ -63,36 +167,45 @@ fn run() -> Result<()> {
         info!("First boot detected — running initialization...");
         run_first_boot()?;
         info!("First boot initialization complete");
+
+        if !config.boot.first_boot_setup {
+            info!("First-boot setup disabled in config.toml");
+        } else if setup::console_attached() {
+            match setup_wizard(&config) {
+                Ok(_) => {
+                    config = config::load_config()?;
+                    if !supervised {
+                        set_hostname(&config.system.hostname)?;
+                    }
+                }
+                Err(e) => warn!("Setup did not finish: {e:#}; run `aios-init --setup` later"),
+            }
+        } else {
+            info!("No console attached; run `aios-init --setup` to finish setup");
+        }
+    }
+
+    // API keys from the secret manager's store, for the services
+    for (var, key) in setup::api_key_env(Path::new(setup::SECRETS_PATH)) {
+        if std::env::var_os(var).is_none() {
+            std::env::set_var(var, key);
+        }
     }
 
     // Phase 4: Start services with AI-driven dependency resolution
     info!("Phase 4: Starting services...");
     let mut supervisor = service::ServiceSupervisor::new(&config);
 
-    // Service dependency graph: each service lists what it depends on.
-    // The init daemon resolves the start order via topological sort.
-    let services: Vec<(&str, &str, &[&str])> = vec![
-        ("aios-runtime", "/usr/sbin/aios-runtime", &[]),
-        ("aios-memory", "/usr/sbin/aios-memory", &[]),
-        ("aios-tools", "/usr/sbin/aios-tools", &[]),
-        ("aios-api-gateway", "/usr/sbin/aios-api-gateway", &[]),
-        (
-            "aios-orchestrator",
-            "/usr/sbin/aios-orchestrator",
-            &[
-                "aios-runtime",
-                "aios-memory",
-                "aios-tools",
-                "aios-api-gateway",
-            ],
-        ),
-    ];
+    // Service dependency graph (service::SERVICES): each service lists what
+    // it depends on. The init daemon resolves the start order via
+    // topological sort.
+    let bin_dir = service::bin_dir();
 
     // Topological sort: start services whose dependencies have all been started
     let mut started: Vec<String> = Vec::new();
-    let mut remaining: Vec<(&str, &str, &[&str])> = services
-        .into_iter()
-        .filter(|(_, path, _)| Path::new(path).exists())
+    let mut remaining: Vec<&service::ServiceSpec> = service::SERVICES
+        .iter()
+        .filter(|spec| Path::new(&spec.binary(&bin_dir)).exists())
         .collect();
 
     let max_rounds = remaining.len() + 1;
@@ -101,18 +214,15 @@ fn run() -> Result<()> {
             break;
         }
         let mut started_this_round = Vec::new();
-        remaining.retain(|(name, path, deps)| {
+        remaining.retain(|spec| {
+            let name = spec.name;
+            let deps = spec.depends_on;
             let deps_met = deps.iter().all(|d| started.contains(&d.to_string()));
             if deps_met {
                 info!("Starting {} (deps satisfied: {:?})...", name, deps);
-                let timeout = if *name == "aios-runtime" {
-                    Duration::from_secs(30)
-                } else {
-                    Duration::from_secs(10)
-                };
-                match supervisor.start_service(name, path, &[]) {
+                match supervisor.start_service(name, &spec.binary(&bin_dir), &[]) {
                     Ok(_) => {
-                        if let Err(e) = supervisor.wait_for_health(name, timeout) {
+                        if let Err(e) = supervisor.wait_for_health(spec) {
                             warn!("{} health check failed: {e}, continuing...", name);
                         }
                         info!("{} online", name);
@@ -131,7 +241,7 @@ fn run() -> Result<()> {
     }
 
     if !remaining.is_empty() {
-        let unstarted: Vec<&str> = remaining.iter().map(|(n, _, _)| *n).collect();
+        let unstarted: Vec<&str> = remaining.iter().map(|spec| spec.name).collect();
         warn!(
             "Services with unmet dependencies not started: {:?}",
             unstarted
@@ -144,14 +254,14 @@ fn run() -> Result<()> {
     info!("========================================");
 
     // Spawn debug shell if configured
-    if config.boot.debug_shell {
+    if config.boot.debug_shell && !supervised {
         info!("Debug shell enabled, spawning /bin/sh on console...");
         spawn_debug_shell();
     }
 
     // Enter supervisor loop — reap zombies, monitor services
     let shutdown = Arc::new(AtomicBool::new(false));
-    setup_signal_handlers(shutdown.clone())?;
+    setup_signal_handlers(shutdown.clone(), !supervised)?;
 
     info!("Entering supervisor loop...");
     supervisor_loop(&mut supervisor, &shutdown)?;
@@ -260,8 +370,21 @@ fn init_logging() -> Result<()> {
     Ok(())
 }
 
-fn setup_signal_handlers(shutdown: Arc<AtomicBool>) -> Result<()> {
-    // SIGCHLD — reap zombie processes (PID 1 duty)
+fn setup_signal_handlers(shutdown: Arc<AtomicBool>, reap: bool) -> Result<()> {
+    // SIGCHLD — reap zombie processes (PID 1 duty). Under another init the
+    // supervisor collects its own children's exit status instead.
+    if reap {
+        spawn_reaper();
+    }
+
+    // Register SIGTERM/SIGINT for shutdown
+    let shutdown_clone = shutdown.clone();
+    ctrlc_handler(shutdown_clone);
+
+    Ok(())
+}
+
+fn spawn_reaper() {
     std::thread::spawn(move || loop {
         // Reap any zombie children
         loop {
@@ -282,12 +405,6 @@ fn setup_signal_handlers(shutdown: Arc<AtomicBool>) -> Result<()> {
         }
         std::thread::sleep(Duration::from_millis(100));
     });
-
-    // Register SIGTERM/SIGINT for shutdown
-    let shutdown_clone = shutdown.clone();
-    ctrlc_handler(shutdown_clone);
-
-    Ok(())
 }
 
 fn ctrlc_handler(shutdown: Arc<AtomicBool>) {
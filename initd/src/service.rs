@@ -1,15 +1,112 @@
 //! Service supervisor for aiOS init
 //!
 //! Manages child services: start, health check, restart on failure.
+//! A service is healthy once its process is running and its gRPC port
+//! accepts connections; dependents are started after that.
 
 use anyhow::{bail, Context, Result};
 use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddr, TcpStream};
 use std::process::{Child, Command};
 use std::time::{Duration, Instant};
 use tracing::{error, info, warn};
 
 use crate::config::AiosConfig;
 
+/// Directory the service binaries are installed in, unless `AIOS_BIN_DIR`
+/// says otherwise
+pub const DEFAULT_BIN_DIR: &str = "/usr/sbin";
+
+/// An aiOS service: its binary, what it needs running first, and the port
+/// that answers once it is ready
+#[derive(Debug, Clone, Copy)]
+pub struct ServiceSpec {
+    pub name: &'static str,
+    pub depends_on: &'static [&'static str],
+    pub port: u16,
+    pub health_timeout: Duration,
+}
+
+/// Every aiOS service in dependency order
+pub const SERVICES: &[ServiceSpec] = &[
+    ServiceSpec {
+        name: "aios-runtime",
+        depends_on: &[],
+        port: 50055,
+        health_timeout: Duration::from_secs(30),
+    },
+    ServiceSpec {
+        name: "aios-memory",
+        depends_on: &[],
+        port: 50053,
+        health_timeout: Duration::from_secs(10),
+    },
+    ServiceSpec {
+        name: "aios-tools",
+        depends_on: &[],
+        port: 50052,
+        health_timeout: Duration::from_secs(10),
+    },
+    ServiceSpec {
+        name: "aios-api-gateway",
+        depends_on: &[],
+        port: 50054,
+        health_timeout: Duration::from_secs(10),
+    },
+    ServiceSpec {
+        name: "aios-orchestrator",
+        depends_on: &[
+            "aios-runtime",
+            "aios-memory",
+            "aios-tools",
+            "aios-api-gateway",
+        ],
+        port: 50051,
+        health_timeout: Duration::from_secs(10),
+    },
+];
+
+/// The service named `name`
+pub fn spec(name: &str) -> Option<&'static ServiceSpec> {
+    SERVICES.iter().find(|s| s.name == name)
+}
+
+/// Directory holding the service binaries (`AIOS_BIN_DIR`)
+pub fn bin_dir() -> String {
+    std::env::var("AIOS_BIN_DIR").unwrap_or_else(|_| DEFAULT_BIN_DIR.to_string())
+}
+
+impl ServiceSpec {
+    /// Path of the service binary under `bin_dir`
+    pub fn binary(&self, bin_dir: &str) -> String {
+        format!("{}/{}", bin_dir.trim_end_matches('/'), self.name)
+    }
+
+    /// Whether the service accepts connections on its port
+    pub fn port_open(&self) -> bool {
+        let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, self.port));
+        TcpStream::connect_timeout(&addr, Duration::from_millis(500)).is_ok()
+    }
+
+    /// Wait until the service accepts connections, up to its health timeout.
+    /// Used on its own by `aios-init --wait-healthy` under systemd.
+    pub fn wait_until_ready(&self) -> Result<()> {
+        let start = Instant::now();
+        while start.elapsed() < self.health_timeout {
+            if self.port_open() {
+                return Ok(());
+            }
+            std::thread::sleep(Duration::from_millis(500));
+        }
+        bail!(
+            "Service {} not accepting connections on port {} within {:?}",
+            self.name,
+            self.port,
+            self.health_timeout
+        );
+    }
+}
+
 /// A running service managed by the supervisor
 #[allow(dead_code)]
 struct ManagedService {
@@ -64,15 +161,19 @@ impl ServiceSupervisor {
         Ok(())
     }
 
-    /// Wait for a service to become healthy
-    pub fn wait_for_health(&self, name: &str, timeout: Duration) -> Result<()> {
+    /// Wait for a service to become healthy: running, and accepting
+    /// connections on its gRPC port
+    pub fn wait_for_health(&mut self, spec: &ServiceSpec) -> Result<()> {
+        let name = spec.name;
+        let timeout = spec.health_timeout;
         let start = Instant::now();
         let check_interval = Duration::from_millis(500);
 
         while start.elapsed() < timeout {
-            if self.is_service_alive(name) {
-                // Process liveness serves as the health signal; each service
-                // also exposes gRPC health endpoints for deeper checks.
+            if !self.is_service_alive(name) {
+                bail!("Service {name} exited during startup");
+            }
+            if spec.port_open() {
                 return Ok(());
             }
             std::thread::sleep(check_interval);
@@ -81,15 +182,12 @@ impl ServiceSupervisor {
         bail!("Service {name} did not become healthy within {timeout:?}");
     }
 
-    /// Check if a service process is still alive
-    fn is_service_alive(&self, name: &str) -> bool {
-        if let Some(service) = self.services.get(name) {
-            // Check if the process is still running
-            // Since we can't peek without consuming, check /proc on Linux
-            let pid = service.process.id();
-            std::path::Path::new(&format!("/proc/{pid}")).exists()
-        } else {
-            false
+    /// Check if a service process is still alive. A child already reaped
+    /// by PID 1's reaper counts as exited.
+    fn is_service_alive(&mut self, name: &str) -> bool {
+        match self.services.get_mut(name) {
+            Some(service) => matches!(service.process.try_wait(), Ok(None)),
+            None => false,
         }
     }
 
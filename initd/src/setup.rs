@@ -0,0 +1,558 @@
+//! First-boot setup — the console wizard
+//!
+//! On first boot with a console attached (or whenever `aios-init --setup`
+//! is run) the operator is asked, in turn, for the hostname, the cloud API
+//! keys, the autonomy level, the local models to download and the operator
+//! account. The answers are written where the services read them:
+//!
+//! - hostname and autonomy level into config.toml, its comments kept
+//! - API keys into secrets.toml (`[api_keys]`, mode 600), the secret
+//!   manager's store, from which aios-init also exports them to the services
+//! - the operator account into operators.toml, the password Argon2-hashed,
+//!   which the management console signs operators in against
+//! - models into the model directory, fetched with wget
+//!
+//! and the outcome into /var/lib/aios/setup.toml, which the orchestrator's
+//! `GetSetupStatus` RPC reports. API keys are recorded there by provider
+//! only.
+
+use anyhow::{bail, Context, Result};
+use argon2::password_hash::{rand_core::OsRng, PasswordHasher, SaltString};
+use argon2::Argon2;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{BufRead, Write};
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::{info, warn};
+
+use crate::config::{self, AiosConfig};
+
+/// Where the outcome of setup is recorded
+pub const SETUP_STATE_PATH: &str = "/var/lib/aios/setup.toml";
+
+/// The secret manager's store
+pub const SECRETS_PATH: &str = "/etc/aios/secrets.toml";
+
+/// Operator accounts
+pub const OPERATORS_PATH: &str = "/etc/aios/operators.toml";
+
+/// Autonomy levels offered; the first is the default when config.toml
+/// names none of them
+pub const AUTONOMY_LEVELS: [&str; 3] = ["full", "supervised", "manual"];
+
+/// Shortest operator password accepted
+const MIN_PASSWORD_LEN: usize = 8;
+
+/// Cloud providers whose API keys setup asks for: name, and the
+/// environment variable the services read the key from
+pub const API_PROVIDERS: [(&str, &str); 2] =
+    [("claude", "CLAUDE_API_KEY"), ("openai", "OPENAI_API_KEY")];
+
+/// A model setup can download
+pub struct ModelChoice {
+    pub name: &'static str,
+    pub file: &'static str,
+    pub url: &'static str,
+    pub size: &'static str,
+}
+
+/// Models offered, the first selected by default
+pub const MODEL_CATALOG: [ModelChoice; 2] = [
+    ModelChoice {
+        name: "TinyLlama 1.1B (operational)",
+        file: "tinyllama-1.1b-chat.Q4_K_M.gguf",
+        url: "https://huggingface.co/TheBloke/TinyLlama-1.1B-Chat-v1.0-GGUF/resolve/main/tinyllama-1.1b-chat-v1.0.Q4_K_M.gguf",
+        size: "669 MB",
+    },
+    ModelChoice {
+        name: "Mistral 7B Instruct (tactical)",
+        file: "mistral-7b-instruct.Q4_K_M.gguf",
+        url: "https://huggingface.co/TheBloke/Mistral-7B-Instruct-v0.2-GGUF/resolve/main/mistral-7b-instruct-v0.2.Q4_K_M.gguf",
+        size: "4.4 GB",
+    },
+];
+
+/// Files setup writes
+pub struct SetupPaths {
+    pub config: PathBuf,
+    pub secrets: PathBuf,
+    pub operators: PathBuf,
+    pub state: PathBuf,
+}
+
+impl Default for SetupPaths {
+    fn default() -> Self {
+        Self {
+            config: PathBuf::from(config::config_path()),
+            secrets: PathBuf::from(SECRETS_PATH),
+            operators: PathBuf::from(OPERATORS_PATH),
+            state: PathBuf::from(SETUP_STATE_PATH),
+        }
+    }
+}
+
+/// What the operator chose
+pub struct Answers {
+    pub hostname: String,
+    /// Provider and key, for the providers given a key
+    pub api_keys: Vec<(String, String)>,
+    pub autonomy_level: String,
+    pub models: Vec<&'static ModelChoice>,
+    pub operator: String,
+    pub password: String,
+}
+
+/// Outcome of setup, as recorded in setup.toml
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SetupState {
+    /// Unix time setup finished
+    pub completed_at: i64,
+    pub hostname: String,
+    pub autonomy_level: String,
+    /// Providers given an API key
+    pub api_providers: Vec<String>,
+    pub model_dir: String,
+    /// Model files selected for download
+    pub models: Vec<String>,
+    pub operator: String,
+}
+
+/// The console the wizard talks to
+pub struct Console<R, W> {
+    input: R,
+    output: W,
+    /// Whether input is a terminal whose echo can be turned off
+    tty: bool,
+}
+
+impl<R: BufRead, W: Write> Console<R, W> {
+    pub fn new(input: R, output: W, tty: bool) -> Self {
+        Self { input, output, tty }
+    }
+
+    /// Ask `question`, showing `default`; the trimmed answer or `default`
+    fn ask(&mut self, question: &str, default: &str) -> Result<String> {
+        if default.is_empty() {
+            write!(self.output, "{question}: ")?;
+        } else {
+            write!(self.output, "{question} [{default}]: ")?;
+        }
+        self.output.flush()?;
+        let answer = self.read_line()?;
+        Ok(if answer.is_empty() {
+            default.to_string()
+        } else {
+            answer
+        })
+    }
+
+    /// Ask for a secret, without echoing it on a terminal
+    fn ask_secret(&mut self, question: &str) -> Result<String> {
+        write!(self.output, "{question}: ")?;
+        self.output.flush()?;
+        let echo = self.tty.then(disable_echo).flatten();
+        let answer = self.read_line();
+        if let Some(saved) = echo {
+            restore_echo(&saved);
+            writeln!(self.output)?;
+        }
+        answer
+    }
+
+    fn read_line(&mut self) -> Result<String> {
+        let mut line = String::new();
+        if self.input.read_line(&mut line)? == 0 {
+            bail!("Setup aborted: console input closed");
+        }
+        Ok(line.trim().to_string())
+    }
+
+    fn say(&mut self, text: &str) -> Result<()> {
+        writeln!(self.output, "{text}")?;
+        Ok(())
+    }
+}
+
+/// Turn off terminal echo on stdin; the settings to restore
+fn disable_echo() -> Option<nix::sys::termios::Termios> {
+    use nix::sys::termios::{tcgetattr, tcsetattr, LocalFlags, SetArg};
+    let stdin = std::io::stdin();
+    let saved = tcgetattr(&stdin).ok()?;
+    let mut quiet = saved.clone();
+    quiet.local_flags.remove(LocalFlags::ECHO);
+    tcsetattr(&stdin, SetArg::TCSANOW, &quiet).ok()?;
+    Some(saved)
+}
+
+fn restore_echo(saved: &nix::sys::termios::Termios) {
+    let _ =
+        nix::sys::termios::tcsetattr(std::io::stdin(), nix::sys::termios::SetArg::TCSANOW, saved);
+}
+
+/// Whether stdin is a terminal someone can answer the wizard on
+pub fn console_attached() -> bool {
+    nix::unistd::isatty(std::io::stdin().as_raw_fd()).unwrap_or(false)
+}
+
+/// Whether `name` is a valid hostname: 1-63 letters, digits and inner
+/// hyphens
+pub fn valid_hostname(name: &str) -> bool {
+    (1..=63).contains(&name.len())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        && !name.starts_with('-')
+        && !name.ends_with('-')
+}
+
+/// Whether `name` is a valid operator name: 1-32 lowercase letters,
+/// digits, `_` and `-`, starting with a letter
+pub fn valid_operator(name: &str) -> bool {
+    (1..=32).contains(&name.len())
+        && name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+}
+
+/// Models picked by a comma-separated list of catalog numbers; `none`
+/// picks none. None if any number is not in the catalog.
+pub fn parse_model_selection(answer: &str) -> Option<Vec<&'static ModelChoice>> {
+    if answer.eq_ignore_ascii_case("none") {
+        return Some(Vec::new());
+    }
+    let mut models: Vec<&'static ModelChoice> = Vec::new();
+    for n in answer.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+        let model = n
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| n.checked_sub(1))
+            .and_then(|i| MODEL_CATALOG.get(i))?;
+        if !models.iter().any(|m| m.file == model.file) {
+            models.push(model);
+        }
+    }
+    Some(models)
+}
+
+/// Walk the operator through setup, re-asking until each answer is valid
+pub fn ask<R: BufRead, W: Write>(
+    console: &mut Console<R, W>,
+    current: &AiosConfig,
+) -> Result<Answers> {
+    console.say("\naiOS setup\n==========\n")?;
+
+    let hostname = loop {
+        let name = console.ask("Hostname", &current.system.hostname)?;
+        if valid_hostname(&name) {
+            break name;
+        }
+        console.say("  Letters, digits and inner hyphens only, at most 63.")?;
+    };
+
+    console.say("\nCloud API keys let agents use hosted models; press Enter to skip.")?;
+    let mut api_keys = Vec::new();
+    for (provider, _) in API_PROVIDERS {
+        let key = console.ask_secret(&format!("{provider} API key"))?;
+        if !key.is_empty() {
+            api_keys.push((provider.to_string(), key));
+        }
+    }
+
+    console.say(&format!(
+        "\nAutonomy levels: {}",
+        AUTONOMY_LEVELS.join(", ")
+    ))?;
+    let default_level = if AUTONOMY_LEVELS.contains(&current.system.autonomy_level.as_str()) {
+        current.system.autonomy_level.as_str()
+    } else {
+        AUTONOMY_LEVELS[0]
+    };
+    let autonomy_level = loop {
+        let level = console.ask("Autonomy level", default_level)?;
+        if AUTONOMY_LEVELS.contains(&level.as_str()) {
+            break level;
+        }
+        console.say("  Choose one of the levels listed.")?;
+    };
+
+    console.say("\nLocal models:")?;
+    for (i, model) in MODEL_CATALOG.iter().enumerate() {
+        console.say(&format!("  {}. {} — {}", i + 1, model.name, model.size))?;
+    }
+    let models = loop {
+        let answer = console.ask("Models to download (numbers, or none)", "1")?;
+        if let Some(models) = parse_model_selection(&answer) {
+            break models;
+        }
+        console.say("  Give numbers from the list, separated by commas.")?;
+    };
+
+    console.say("\nOperator account")?;
+    let operator = loop {
+        let name = console.ask("Operator name", "admin")?;
+        if valid_operator(&name) {
+            break name;
+        }
+        console.say("  Lowercase letters, digits, _ and -, starting with a letter.")?;
+    };
+    let password = loop {
+        let password = console.ask_secret("Password")?;
+        if password.len() < MIN_PASSWORD_LEN {
+            console.say(&format!(
+                "  At least {MIN_PASSWORD_LEN} characters, please."
+            ))?;
+            continue;
+        }
+        if console.ask_secret("Password again")? == password {
+            break password;
+        }
+        console.say("  The passwords differ.")?;
+    };
+
+    Ok(Answers {
+        hostname,
+        api_keys,
+        autonomy_level,
+        models,
+        operator,
+        password,
+    })
+}
+
+/// Write `answers` out and record the outcome. Models already in
+/// `model_dir` are not fetched again; a failed download is logged and left
+/// for the next `aios-init --setup`.
+pub fn apply(answers: &Answers, paths: &SetupPaths, model_dir: &str) -> Result<SetupState> {
+    update_config(&paths.config, answers)?;
+    if !answers.api_keys.is_empty() {
+        store_api_keys(&paths.secrets, &answers.api_keys)?;
+    }
+    add_operator(&paths.operators, &answers.operator, &answers.password)?;
+    for model in &answers.models {
+        if let Err(e) = download_model(model, model_dir) {
+            warn!("Failed to download {}: {e:#}", model.file);
+        }
+    }
+
+    let state = SetupState {
+        completed_at: now(),
+        hostname: answers.hostname.clone(),
+        autonomy_level: answers.autonomy_level.clone(),
+        api_providers: answers.api_keys.iter().map(|(p, _)| p.clone()).collect(),
+        model_dir: model_dir.to_string(),
+        models: answers.models.iter().map(|m| m.file.to_string()).collect(),
+        operator: answers.operator.clone(),
+    };
+    write_file(
+        &paths.state,
+        &toml::to_string(&state).context("Failed to encode setup state")?,
+        0o644,
+    )?;
+    info!("Setup recorded in {}", paths.state.display());
+    Ok(state)
+}
+
+/// Set hostname and autonomy level in config.toml, keeping the rest of the
+/// file as it is
+fn update_config(path: &Path, answers: &Answers) -> Result<()> {
+    let mut doc = read_document(path)?;
+    let system = doc
+        .entry("system")
+        .or_insert(toml_edit::table())
+        .as_table_mut()
+        .context("[system] in config.toml is not a table")?;
+    system["hostname"] = toml_edit::value(&answers.hostname);
+    system["autonomy_level"] = toml_edit::value(&answers.autonomy_level);
+    write_file(path, &doc.to_string(), 0o600)
+}
+
+/// Add API keys to `[api_keys]` in the secret manager's store
+fn store_api_keys(path: &Path, keys: &[(String, String)]) -> Result<()> {
+    let mut doc = read_document(path)?;
+    let table = doc
+        .entry("api_keys")
+        .or_insert(toml_edit::table())
+        .as_table_mut()
+        .context("[api_keys] in secrets.toml is not a table")?;
+    for (provider, key) in keys {
+        table[provider.as_str()] = toml_edit::value(key);
+    }
+    write_file(path, &doc.to_string(), 0o600)
+}
+
+/// Add or replace operator `name` in operators.toml
+fn add_operator(path: &Path, name: &str, password: &str) -> Result<()> {
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|e| anyhow::anyhow!("Failed to hash the operator password: {e}"))?
+        .to_string();
+
+    let mut doc = read_document(path)?;
+    let operators = doc
+        .entry("operator")
+        .or_insert(toml_edit::ArrayOfTables::new().into())
+        .as_array_of_tables_mut()
+        .context("operator in operators.toml is not an array of tables")?;
+    operators.retain(|op| op.get("name").and_then(|n| n.as_str()) != Some(name));
+    let mut operator = toml_edit::Table::new();
+    operator["name"] = toml_edit::value(name);
+    operator["password_hash"] = toml_edit::value(hash);
+    operator["created_at"] = toml_edit::value(now());
+    operators.push(operator);
+    write_file(path, &doc.to_string(), 0o600)
+}
+
+fn download_model(model: &ModelChoice, model_dir: &str) -> Result<()> {
+    let path = Path::new(model_dir).join(model.file);
+    if path.exists() {
+        info!("{} already present", model.file);
+        return Ok(());
+    }
+    fs::create_dir_all(model_dir).with_context(|| format!("Failed to create {model_dir}"))?;
+    let partial = path.with_extension("gguf.part");
+    info!("Downloading {} ({})...", model.name, model.size);
+    let status = Command::new("wget")
+        .args(["-c", "-q", "--show-progress", "-O"])
+        .arg(&partial)
+        .arg(model.url)
+        .status()
+        .context("Failed to run wget")?;
+    if !status.success() {
+        bail!("wget exited with status {}", status.code().unwrap_or(-1));
+    }
+    fs::rename(&partial, &path)
+        .with_context(|| format!("Failed to move {} into place", path.display()))?;
+    info!("Downloaded {}", path.display());
+    Ok(())
+}
+
+/// API keys in the secret manager's store, as the environment variables the
+/// services read them from
+pub fn api_key_env(secrets: &Path) -> Vec<(&'static str, String)> {
+    let Ok(doc) = read_document(secrets) else {
+        return Vec::new();
+    };
+    API_PROVIDERS
+        .iter()
+        .filter_map(|(provider, var)| {
+            let key = doc.get("api_keys")?.get(provider)?.as_str()?;
+            Some((*var, key.to_string()))
+        })
+        .collect()
+}
+
+fn read_document(path: &Path) -> Result<toml_edit::DocumentMut> {
+    match fs::read_to_string(path) {
+        Ok(contents) => contents
+            .parse()
+            .with_context(|| format!("Failed to parse {}", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Default::default()),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+/// Replace `path` with `contents`, created with `mode`
+fn write_file(path: &Path, contents: &str, mode: u32) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("tmp");
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(mode)
+        .open(&tmp)
+        .with_context(|| format!("Failed to write {}", tmp.display()))?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&tmp, path).with_context(|| format!("Failed to replace {}", path.display()))?;
+    Ok(())
+}
+
+fn now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn default_config() -> AiosConfig {
+        toml::from_str("").unwrap()
+    }
+
+    #[test]
+    fn test_wizard_reasks_invalid_answers() {
+        let input = "bad_host!\nnode-1\nsk-claude\n\nreckless\nsupervised\n3\n1,2,1\nRoot\nops\nshort\nlongenough\nlongenough\n";
+        let mut output = Vec::new();
+        let mut console = Console::new(Cursor::new(input), &mut output, false);
+        let answers = ask(&mut console, &default_config()).unwrap();
+
+        assert_eq!(answers.hostname, "node-1");
+        assert_eq!(
+            answers.api_keys,
+            vec![("claude".to_string(), "sk-claude".to_string())]
+        );
+        assert_eq!(answers.autonomy_level, "supervised");
+        assert_eq!(answers.models.len(), 2);
+        assert_eq!(answers.operator, "ops");
+        assert_eq!(answers.password, "longenough");
+
+        let mut console = Console::new(Cursor::new("node-1\n"), Vec::new(), false);
+        assert!(ask(&mut console, &default_config()).is_err());
+    }
+
+    #[test]
+    fn test_apply_writes_config_secrets_and_operator() {
+        let dir = std::env::temp_dir().join(format!("aios-setup-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let paths = SetupPaths {
+            config: dir.join("config.toml"),
+            secrets: dir.join("secrets.toml"),
+            operators: dir.join("operators.toml"),
+            state: dir.join("setup.toml"),
+        };
+        fs::write(
+            &paths.config,
+            "# site config\n[system]\nhostname = \"aios\"\nlog_level = \"debug\"\n",
+        )
+        .unwrap();
+        let answers = Answers {
+            hostname: "node-1".into(),
+            api_keys: vec![("openai".into(), "sk-openai".into())],
+            autonomy_level: "manual".into(),
+            models: Vec::new(),
+            operator: "ops".into(),
+            password: "longenough".into(),
+        };
+
+        let state = apply(&answers, &paths, dir.join("models").to_str().unwrap()).unwrap();
+        assert_eq!(state.api_providers, vec!["openai"]);
+        let config = fs::read_to_string(&paths.config).unwrap();
+        assert!(config.starts_with("# site config\n"));
+        assert!(config.contains("hostname = \"node-1\""));
+        assert!(config.contains("log_level = \"debug\""));
+        assert_eq!(
+            api_key_env(&paths.secrets),
+            vec![("OPENAI_API_KEY", "sk-openai".to_string())]
+        );
+        let operators = fs::read_to_string(&paths.operators).unwrap();
+        assert!(operators.contains("name = \"ops\""));
+        assert!(operators.contains("$argon2id$"));
+        assert!(!operators.contains("longenough"));
+        let recorded: SetupState =
+            toml::from_str(&fs::read_to_string(&paths.state).unwrap()).unwrap();
+        assert_eq!(recorded, state);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,173 @@
+//! systemd units — running the aiOS services under an existing init
+//!
+//! `aios-init --generate-units [dir]` writes a unit per installed service
+//! and aios.target grouping them. Requires=/After= carry the dependency
+//! order aios-init follows as PID 1, and each unit's ExecStartPost runs
+//! `aios-init --wait-healthy <service>`, so systemd counts a service as
+//! started, and starts its dependents, only once its gRPC port answers.
+//! Restart limits come from the `[agents]` section of config.toml.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+use crate::config::AgentsConfig;
+use crate::service::{ServiceSpec, SERVICES};
+
+/// Where `--generate-units` writes without a directory argument
+pub const DEFAULT_UNIT_DIR: &str = "/etc/systemd/system";
+
+/// Target starting every aiOS service
+pub const TARGET: &str = "aios.target";
+
+/// Optional environment for every service (API keys, `AIOS_*` overrides)
+const ENVIRONMENT_FILE: &str = "/etc/default/aios";
+
+/// Unit file for `spec`, its binaries under `bin_dir`
+pub fn render_unit(spec: &ServiceSpec, bin_dir: &str, agents: &AgentsConfig) -> String {
+    let deps: Vec<String> = spec
+        .depends_on
+        .iter()
+        .map(|d| format!("{d}.service"))
+        .collect();
+    let after: Vec<&str> = std::iter::once("network-online.target")
+        .chain(deps.iter().map(String::as_str))
+        .collect();
+    let mut unit = format!(
+        "# Generated by aios-init --generate-units\n\
+         [Unit]\n\
+         Description=aiOS {name}\n\
+         PartOf={TARGET}\n\
+         Wants=network-online.target\n\
+         After={after}\n",
+        name = spec.name,
+        after = after.join(" "),
+    );
+    if !deps.is_empty() {
+        unit.push_str(&format!("Requires={}\n", deps.join(" ")));
+    }
+    unit.push_str(&format!(
+        "StartLimitIntervalSec={window}\n\
+         StartLimitBurst={burst}\n\
+         \n\
+         [Service]\n\
+         Type=simple\n\
+         EnvironmentFile=-{ENVIRONMENT_FILE}\n\
+         ExecStart={binary}\n\
+         ExecStartPost={bin_dir}/aios-init --wait-healthy {name}\n\
+         TimeoutStartSec={timeout}\n\
+         Restart=on-failure\n\
+         RestartSec=2\n",
+        window = agents.restart_window_seconds,
+        burst = agents.max_restart_attempts,
+        binary = spec.binary(bin_dir),
+        bin_dir = bin_dir.trim_end_matches('/'),
+        name = spec.name,
+        timeout = spec.health_timeout.as_secs() + 15,
+    ));
+    unit
+}
+
+/// aios.target, wanting every service in `specs`
+pub fn render_target(specs: &[&ServiceSpec]) -> String {
+    let wants: Vec<String> = specs
+        .iter()
+        .map(|s| format!("{}.service", s.name))
+        .collect();
+    format!(
+        "# Generated by aios-init --generate-units\n\
+         [Unit]\n\
+         Description=aiOS services\n\
+         Wants={}\n\
+         \n\
+         [Install]\n\
+         WantedBy=multi-user.target\n",
+        wants.join(" ")
+    )
+}
+
+/// Write units for the services installed under `bin_dir`, and aios.target,
+/// into `dir`; the files written
+pub fn write_units(dir: &Path, bin_dir: &str, agents: &AgentsConfig) -> Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let installed: Vec<&ServiceSpec> = SERVICES
+        .iter()
+        .filter(|s| Path::new(&s.binary(bin_dir)).exists())
+        .collect();
+    if installed.is_empty() {
+        warn!("No aiOS service binaries found in {bin_dir}; set AIOS_BIN_DIR");
+    }
+
+    let mut written = Vec::new();
+    for spec in &installed {
+        for dep in spec.depends_on {
+            if !installed.iter().any(|s| s.name == *dep) {
+                warn!("{} requires {dep}, which is not installed", spec.name);
+            }
+        }
+        let path = dir.join(format!("{}.service", spec.name));
+        fs::write(&path, render_unit(spec, bin_dir, agents))
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        written.push(path);
+    }
+    let path = dir.join(TARGET);
+    fs::write(&path, render_target(&installed))
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    written.push(path);
+
+    info!("Wrote {} unit files to {}", written.len(), dir.display());
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::spec;
+
+    #[test]
+    fn test_render_unit_orders_and_gates_on_dependencies() {
+        let agents = AgentsConfig::default();
+        let unit = render_unit(
+            spec("aios-orchestrator").unwrap(),
+            "/usr/local/sbin/",
+            &agents,
+        );
+        assert!(unit.contains("Requires=aios-runtime.service aios-memory.service"));
+        assert!(unit.contains("After=network-online.target aios-runtime.service"));
+        assert!(unit.contains("ExecStart=/usr/local/sbin/aios-orchestrator\n"));
+        assert!(unit.contains(
+            "ExecStartPost=/usr/local/sbin/aios-init --wait-healthy aios-orchestrator\n"
+        ));
+        assert!(unit.contains(&format!(
+            "StartLimitBurst={}\n",
+            agents.max_restart_attempts
+        )));
+
+        let unit = render_unit(spec("aios-memory").unwrap(), "/usr/sbin", &agents);
+        assert!(!unit.contains("Requires="));
+        assert!(unit.contains("After=network-online.target\n"));
+    }
+
+    #[test]
+    fn test_write_units_only_for_installed_services() {
+        let root = std::env::temp_dir().join(format!("aios-units-{}", std::process::id()));
+        let bin_dir = root.join("bin");
+        let unit_dir = root.join("units");
+        fs::create_dir_all(&bin_dir).unwrap();
+        fs::write(bin_dir.join("aios-memory"), "").unwrap();
+
+        let written = write_units(
+            &unit_dir,
+            bin_dir.to_str().unwrap(),
+            &AgentsConfig::default(),
+        )
+        .unwrap();
+        assert_eq!(written.len(), 2);
+        let target = fs::read_to_string(unit_dir.join(TARGET)).unwrap();
+        assert!(target.contains("Wants=aios-memory.service\n"));
+        assert!(!unit_dir.join("aios-orchestrator.service").exists());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
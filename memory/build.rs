@@ -1,4 +1,8 @@
+#[path = "../agent-core/build_support.rs"]
+mod build_support;
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    build_support::emit_build_info();
     tonic_build::configure()
         .build_server(true)
         .build_client(true)
@@ -6,6 +10,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             &[
                 "../agent-core/proto/common.proto",
                 "../agent-core/proto/memory.proto",
+                "../agent-core/proto/runtime.proto",
             ],
             &["../agent-core/proto/"],
         )?;
@@ -0,0 +1,491 @@
+//! Access Control — per-collection read policies and the access audit log
+//!
+//! Search and context assembly carry the requesting agent's identity.
+//! Policies in memory-access.toml name which identities may read a
+//! collection, optionally only the records carrying a tag (knowledge entries
+//! tagged `pii`, say); records a requester may not read are withheld from
+//! results. Collections without a policy are readable by everyone.
+//!
+//! Every read is written to the access log with the requester, the query,
+//! and the records returned or withheld, so a compliance review can ask who
+//! read a given record or collection (`GetAccessLog`).
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+use serde::Deserialize;
+use std::sync::Mutex;
+use tracing::warn;
+
+use crate::proto::memory::{AccessEntry, AccessLogRequest, SearchResult};
+use crate::retention::{self, SubjectColumns};
+
+/// Default location of the access policy configuration
+pub const ACCESS_POLICY_PATH: &str = "/etc/aios/memory-access.toml";
+
+/// Identity recorded for requests that do not name one
+pub const ANONYMOUS: &str = "anonymous";
+
+/// Pseudo-collection guarding the access log itself
+pub const ACCESS_LOG_COLLECTION: &str = "access_log";
+
+/// Pseudo-collection guarding `EraseSubject`
+pub const ERASURE_COLLECTION: &str = "erasure";
+
+/// Columns of each table a person or other subject may be mentioned in
+const SUBJECT_COLUMNS: &[SubjectColumns] = &[
+    ("access_log", &["query", "denied"]),
+    ("access_log_records", &["record_id"]),
+];
+
+/// Who may read a collection, or the records in it carrying `tag`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct CollectionPolicy {
+    pub collection: String,
+    #[serde(default)]
+    pub tag: String,
+    /// Agent or service names; a trailing `*` matches a prefix
+    pub readers: Vec<String>,
+}
+
+/// memory-access.toml layout
+#[derive(Debug, Deserialize)]
+struct AccessConfig {
+    /// Keep the built-in policies for collections the file does not mention
+    #[serde(default = "default_builtin")]
+    builtin: bool,
+    #[serde(default, rename = "policy")]
+    policies: Vec<CollectionPolicy>,
+}
+
+fn default_builtin() -> bool {
+    true
+}
+
+/// The read policies in force
+#[derive(Debug, Clone)]
+pub struct AccessPolicy {
+    policies: Vec<CollectionPolicy>,
+}
+
+fn policy(collection: &str, tag: &str, readers: &[&str]) -> CollectionPolicy {
+    CollectionPolicy {
+        collection: collection.into(),
+        tag: tag.into(),
+        readers: readers.iter().map(|r| r.to_string()).collect(),
+    }
+}
+
+impl AccessPolicy {
+    /// Built-in policies: incidents and config changes (file contents,
+    /// often secrets-adjacent) to the orchestrator and the agents that act
+    /// on them; personal data and secrets in the knowledge base, the
+    /// access log and subject erasure to the orchestrator and the security
+    /// agent. The orchestrator is any verified node.
+    pub fn builtin() -> Self {
+        let privileged = ["orchestrator", "node:*", "security-agent"];
+        Self {
+            policies: vec![
+                policy(
+                    "incidents",
+                    "",
+                    &[
+                        "orchestrator",
+                        "node:*",
+                        "security-agent",
+                        "monitoring-agent",
+                        "system-agent",
+                    ],
+                ),
+                policy(
+                    "config_changes",
+                    "",
+                    &["orchestrator", "node:*", "security-agent", "system-agent"],
+                ),
+                policy("knowledge", "pii", &privileged),
+                policy("knowledge", "email", &privileged),
+                policy("knowledge", "secret", &privileged),
+                policy(ACCESS_LOG_COLLECTION, "", &privileged),
+                policy(ERASURE_COLLECTION, "", &privileged),
+            ],
+        }
+    }
+
+    /// Policies from the configuration at `path`.
+    /// A missing file yields the built-ins; an invalid one is logged and ignored.
+    pub fn load(path: &str) -> Self {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => Self::from_toml(&contents).unwrap_or_else(|e| {
+                warn!("Ignoring invalid memory access policy in {path}: {e}");
+                Self::builtin()
+            }),
+            Err(_) => Self::builtin(),
+        }
+    }
+
+    /// Policies from a memory-access.toml document. A file policy replaces
+    /// the built-in one for the same collection and tag.
+    pub fn from_toml(contents: &str) -> Result<Self> {
+        let config: AccessConfig =
+            toml::from_str(contents).context("Failed to parse memory access policy")?;
+        let mut policies = if config.builtin {
+            Self::builtin().policies
+        } else {
+            Vec::new()
+        };
+        for p in config.policies {
+            policies.retain(|b| b.collection != p.collection || b.tag != p.tag);
+            policies.push(p);
+        }
+        Ok(Self { policies })
+    }
+
+    /// Whether `requester` may read a record in `collection` carrying `tags`
+    pub fn allows(&self, requester: &str, collection: &str, tags: &[&str]) -> bool {
+        self.policies
+            .iter()
+            .filter(|p| {
+                p.collection == collection && (p.tag.is_empty() || tags.contains(&p.tag.as_str()))
+            })
+            .all(|p| p.readers.iter().any(|r| reader_matches(r, requester)))
+    }
+
+    /// Whether a policy grants `requester` reads by name, which makes it a
+    /// reserved identity; a bare `*` names nobody
+    pub fn names(&self, requester: &str) -> bool {
+        self.policies
+            .iter()
+            .flat_map(|p| &p.readers)
+            .any(|r| r != "*" && reader_matches(r, requester))
+    }
+
+    /// Split search results into those `requester` may read and the
+    /// `collection/id` references of those withheld
+    pub fn filter(
+        &self,
+        requester: &str,
+        results: Vec<SearchResult>,
+    ) -> (Vec<SearchResult>, Vec<String>) {
+        let mut allowed = Vec::new();
+        let mut denied = Vec::new();
+        for result in results {
+            let tags = result_tags(&result);
+            let tags: Vec<&str> = tags.iter().map(String::as_str).collect();
+            if self.allows(requester, &result.collection, &tags) {
+                allowed.push(result);
+            } else {
+                denied.push(record_ref(&result.collection, &result.id));
+            }
+        }
+        (allowed, denied)
+    }
+}
+
+fn reader_matches(reader: &str, requester: &str) -> bool {
+    match reader.strip_suffix('*') {
+        Some(prefix) => requester.starts_with(prefix),
+        None => reader == requester,
+    }
+}
+
+/// Tags a search result carries in its metadata (knowledge entries)
+fn result_tags(result: &SearchResult) -> Vec<String> {
+    serde_json::from_slice::<serde_json::Value>(&result.metadata_json)
+        .ok()
+        .and_then(|m| m["tags"].as_str().map(str::to_string))
+        .map(|tags| {
+            tags.split(',')
+                .map(|t| t.trim().to_lowercase())
+                .filter(|t| !t.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Requester identity, with a placeholder for unnamed callers
+pub fn requester(requesting_agent: &str) -> &str {
+    if requesting_agent.is_empty() {
+        ANONYMOUS
+    } else {
+        requesting_agent
+    }
+}
+
+/// `collection/id` reference used in the access log
+pub fn record_ref(collection: &str, id: &str) -> String {
+    format!("{collection}/{id}")
+}
+
+/// Persistent log of memory reads
+pub struct AccessLog {
+    conn: Mutex<Connection>,
+}
+
+impl AccessLog {
+    pub fn new(db_path: &str) -> Result<Self> {
+        if let Some(parent) = std::path::Path::new(db_path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let conn = Connection::open(db_path)?;
+        conn.execute_batch("PRAGMA journal_mode=WAL; PRAGMA synchronous=NORMAL;")?;
+
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS access_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp INTEGER NOT NULL,
+                requester TEXT NOT NULL,
+                operation TEXT NOT NULL,
+                query TEXT NOT NULL,
+                collections TEXT NOT NULL,
+                denied TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS access_log_records (
+                access_id INTEGER NOT NULL,
+                collection TEXT NOT NULL,
+                record_id TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_access_time ON access_log(timestamp);
+            CREATE INDEX IF NOT EXISTS idx_access_requester ON access_log(requester);
+            CREATE INDEX IF NOT EXISTS idx_access_records ON access_log_records(collection, record_id);",
+        )?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Record one read; `entry.records` and `entry.denied` hold
+    /// `collection/id` references
+    pub fn record(&self, entry: &AccessEntry) -> Result<()> {
+        let mut conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO access_log (timestamp, requester, operation, query, collections, denied)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                entry.timestamp,
+                entry.requester,
+                entry.operation,
+                entry.query,
+                serde_json::to_string(&entry.collections)?,
+                serde_json::to_string(&entry.denied)?,
+            ],
+        )?;
+        let access_id = tx.last_insert_rowid();
+        for record in &entry.records {
+            let (collection, id) = record.split_once('/').unwrap_or(("", record));
+            tx.execute(
+                "INSERT INTO access_log_records (access_id, collection, record_id) VALUES (?1, ?2, ?3)",
+                params![access_id, collection, id],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Reads matching the request, most recent first
+    pub fn query(&self, request: &AccessLogRequest) -> Result<Vec<AccessEntry>> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let limit = if request.limit <= 0 {
+            100
+        } else {
+            request.limit
+        };
+
+        let mut stmt = conn.prepare(
+            "SELECT id, timestamp, requester, operation, query, collections, denied FROM access_log a
+             WHERE timestamp >= ?1
+               AND (?2 = '' OR requester = ?2)
+               AND ((?3 = '' AND ?4 = '') OR EXISTS (
+                   SELECT 1 FROM access_log_records r
+                   WHERE r.access_id = a.id
+                     AND (?3 = '' OR r.collection = ?3)
+                     AND (?4 = '' OR r.record_id = ?4)))
+             ORDER BY timestamp DESC, id DESC LIMIT ?5",
+        )?;
+        let rows = stmt.query_map(
+            params![
+                request.since,
+                request.requester,
+                request.collection,
+                request.record_id,
+                limit
+            ],
+            |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, String>(5)?,
+                    row.get::<_, String>(6)?,
+                ))
+            },
+        )?;
+
+        let mut records_stmt = conn
+            .prepare("SELECT collection, record_id FROM access_log_records WHERE access_id = ?1")?;
+        let mut entries = Vec::new();
+        for row in rows {
+            let (id, timestamp, requester, operation, query, collections, denied) = row?;
+            let records = records_stmt
+                .query_map(params![id], |r| {
+                    Ok(record_ref(&r.get::<_, String>(0)?, &r.get::<_, String>(1)?))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            entries.push(AccessEntry {
+                id,
+                timestamp,
+                requester,
+                operation,
+                query,
+                collections: serde_json::from_str(&collections).unwrap_or_default(),
+                records,
+                denied: serde_json::from_str(&denied).unwrap_or_default(),
+            });
+        }
+        Ok(entries)
+    }
+
+    /// Drop entries older than `before` (Unix seconds); returns how many
+    pub fn prune(&self, before: i64) -> Result<usize> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        conn.execute(
+            "DELETE FROM access_log_records WHERE access_id IN (SELECT id FROM access_log WHERE timestamp < ?1)",
+            params![before],
+        )?;
+        Ok(conn.execute(
+            "DELETE FROM access_log WHERE timestamp < ?1",
+            params![before],
+        )?)
+    }
+
+    /// Delete the entries whose query mentions `pattern` (normalized, see
+    /// [`crate::retention`]); rows removed per table
+    pub fn erase_subject(&self, pattern: &str) -> Result<Vec<(String, usize)>> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let mut removed = retention::erase_rows(&conn, SUBJECT_COLUMNS, pattern)?;
+        let orphaned = conn.execute(
+            "DELETE FROM access_log_records WHERE access_id NOT IN (SELECT id FROM access_log)",
+            [],
+        )?;
+        if let Some((_, n)) = removed
+            .iter_mut()
+            .find(|(table, _)| table == "access_log_records")
+        {
+            *n += orphaned;
+        }
+        retention::truncate_wal(&conn)?;
+        Ok(removed)
+    }
+
+    /// Entries still mentioning `pattern`
+    pub fn count_subject(&self, pattern: &str) -> Result<usize> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        retention::count_rows(&conn, SUBJECT_COLUMNS, pattern)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn knowledge(id: &str, tags: &str) -> SearchResult {
+        SearchResult {
+            id: id.into(),
+            content: String::new(),
+            metadata_json: serde_json::to_vec(&serde_json::json!({"tags": tags})).unwrap(),
+            relevance: 0.5,
+            collection: "knowledge".into(),
+        }
+    }
+
+    #[test]
+    fn test_policies_withhold_restricted_records() {
+        let policy = AccessPolicy::from_toml(
+            r#"
+            [[policy]]
+            collection = "incidents"
+            readers = ["ops-*"]
+            "#,
+        )
+        .unwrap();
+
+        assert!(policy.allows("ops-agent", "incidents", &[]));
+        assert!(!policy.allows("orchestrator", "incidents", &[]));
+        assert!(policy.allows(ANONYMOUS, "procedures", &[]));
+
+        let (allowed, denied) = policy.filter(
+            "task-agent",
+            vec![knowledge("1", "web,nginx"), knowledge("2", "users, PII")],
+        );
+        assert_eq!(allowed.len(), 1);
+        assert_eq!(denied, vec!["knowledge/2".to_string()]);
+        assert_eq!(
+            policy
+                .filter("orchestrator", vec![knowledge("2", "pii")])
+                .0
+                .len(),
+            1
+        );
+
+        // Named readers are reserved; unproven callers match no policy
+        assert!(policy.names("ops-agent") && policy.names("node:n1"));
+        assert!(!policy.names("task-agent"));
+        assert!(!policy.allows("unverified:agent:security-agent", "knowledge", &["pii"]));
+        assert!(policy.allows("node:n1", "knowledge", &["pii"]));
+    }
+
+    #[test]
+    fn test_access_log_answers_who_read_a_record() {
+        let log = AccessLog::new(":memory:").unwrap();
+        let entry = |requester: &str, timestamp: i64, records: &[&str]| AccessEntry {
+            timestamp,
+            requester: requester.into(),
+            operation: "semantic_search".into(),
+            query: "nginx".into(),
+            collections: vec!["procedures".into()],
+            records: records.iter().map(|r| r.to_string()).collect(),
+            ..Default::default()
+        };
+        log.record(&entry("task-agent", 100, &["procedures/p1", "knowledge/7"]))
+            .unwrap();
+        log.record(&entry("web-agent", 200, &["procedures/p2"]))
+            .unwrap();
+
+        let readers = log
+            .query(&AccessLogRequest {
+                collection: "procedures".into(),
+                record_id: "p1".into(),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(readers.len(), 1);
+        assert_eq!(readers[0].requester, "task-agent");
+        assert_eq!(readers[0].records, vec!["procedures/p1", "knowledge/7"]);
+
+        let all = log.query(&AccessLogRequest::default()).unwrap();
+        assert_eq!(all[0].requester, "web-agent");
+
+        assert_eq!(log.prune(150).unwrap(), 1);
+        assert_eq!(log.query(&AccessLogRequest::default()).unwrap().len(), 1);
+    }
+}
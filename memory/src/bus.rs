@@ -0,0 +1,278 @@
+//! Event Bus — durable topics shared by all services
+//!
+//! Messages published on a topic are stored in SQLite (`AIOS_EVENT_BUS_DB`)
+//! under a sequence number and kept for `AIOS_EVENT_BUS_RETENTION_HOURS`
+//! (default 72). Subscribers receive the messages on the topics they match
+//! as they are published, instead of polling. A named subscriber is
+//! durable: the bus keeps the sequence number it last acknowledged, and a
+//! new subscription under the same name resumes right after it, so messages
+//! published while it was down are delivered once it reconnects.
+//!
+//! Every event accepted by PushEvent is also published, on
+//! `events.<category>.<type>`, so event consumers can subscribe to them.
+
+use anyhow::Result;
+use prost::Message;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::sync::Mutex;
+use tokio::sync::watch;
+
+use crate::proto::memory::{BusDelivery, BusMessage, Event};
+
+/// Topic prefix of the events accepted by PushEvent
+pub const EVENTS_TOPIC: &str = "events";
+
+/// Durable message log with per-subscriber positions
+pub struct EventBus {
+    conn: Mutex<Connection>,
+    latest: watch::Sender<u64>,
+}
+
+impl EventBus {
+    pub fn new(db_path: &str) -> Result<Self> {
+        if let Some(parent) = std::path::Path::new(db_path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let conn = Connection::open(db_path)?;
+        conn.execute_batch("PRAGMA journal_mode=WAL; PRAGMA synchronous=NORMAL;")?;
+
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS bus_messages (
+                seq INTEGER PRIMARY KEY AUTOINCREMENT,
+                topic TEXT NOT NULL,
+                timestamp INTEGER NOT NULL,
+                message BLOB NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_bus_timestamp ON bus_messages(timestamp);
+
+            CREATE TABLE IF NOT EXISTS bus_subscribers (
+                subscriber TEXT PRIMARY KEY,
+                acked INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );",
+        )?;
+
+        let latest: Option<i64> =
+            conn.query_row("SELECT MAX(seq) FROM bus_messages", [], |row| row.get(0))?;
+        let (latest, _) = watch::channel(latest.unwrap_or(0) as u64);
+        Ok(Self {
+            conn: Mutex::new(conn),
+            latest,
+        })
+    }
+
+    /// Store a message and wake its subscribers. Returns its sequence number.
+    pub fn publish(&self, mut message: BusMessage) -> Result<u64> {
+        if message.topic.is_empty() {
+            anyhow::bail!("Message has no topic");
+        }
+        if message.timestamp == 0 {
+            message.timestamp = chrono::Utc::now().timestamp();
+        }
+        let seq = {
+            let conn = self
+                .conn
+                .lock()
+                .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+            conn.execute(
+                "INSERT INTO bus_messages (topic, timestamp, message) VALUES (?1, ?2, ?3)",
+                params![message.topic, message.timestamp, message.encode_to_vec()],
+            )?;
+            conn.last_insert_rowid() as u64
+        };
+        self.latest.send_replace(seq);
+        Ok(seq)
+    }
+
+    /// Publish an accepted event on `events.<category>.<type>`
+    pub fn publish_event(&self, event: &Event) -> Result<u64> {
+        self.publish(BusMessage {
+            topic: format!("{EVENTS_TOPIC}.{}.{}", event.category, event.event_type),
+            source: event.source.clone(),
+            payload_json: event.data_json.clone(),
+            timestamp: event.timestamp,
+            event: Some(event.clone()),
+        })
+    }
+
+    /// Up to `limit` messages after `after` on a topic matching `topics`
+    /// (all topics if empty), and the sequence number scanned up to
+    pub fn read_after(
+        &self,
+        after: u64,
+        topics: &[String],
+        limit: usize,
+    ) -> Result<(Vec<BusDelivery>, u64)> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let mut stmt = conn.prepare(
+            "SELECT seq, topic, message FROM bus_messages WHERE seq > ?1 ORDER BY seq LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![after as i64, limit as i64], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Vec<u8>>(2)?,
+            ))
+        })?;
+
+        let mut deliveries = Vec::new();
+        let mut scanned = after;
+        for row in rows {
+            let (seq, topic, bytes) = row?;
+            scanned = seq as u64;
+            if topics.is_empty() || topics.iter().any(|p| topic_matches(p, &topic)) {
+                deliveries.push(BusDelivery {
+                    seq: seq as u64,
+                    message: Some(BusMessage::decode(bytes.as_slice())?),
+                });
+            }
+        }
+        Ok((deliveries, scanned))
+    }
+
+    /// Sequence number `subscriber` last acknowledged, None if it is new
+    pub fn acked(&self, subscriber: &str) -> Result<Option<u64>> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let acked: Option<i64> = conn
+            .query_row(
+                "SELECT acked FROM bus_subscribers WHERE subscriber = ?1",
+                params![subscriber],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(acked.map(|s| s as u64))
+    }
+
+    /// Record that `subscriber` handled every message up to `seq`. Acks
+    /// never move a subscriber backwards.
+    pub fn ack(&self, subscriber: &str, seq: u64) -> Result<()> {
+        if subscriber.is_empty() {
+            anyhow::bail!("Only named subscribers can acknowledge");
+        }
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        conn.execute(
+            "INSERT INTO bus_subscribers (subscriber, acked, updated_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(subscriber) DO UPDATE SET
+                acked = MAX(acked, excluded.acked),
+                updated_at = excluded.updated_at",
+            params![subscriber, seq as i64, chrono::Utc::now().timestamp()],
+        )?;
+        Ok(())
+    }
+
+    /// Sequence number of the newest message (0 if none)
+    pub fn latest_seq(&self) -> u64 {
+        *self.latest.borrow()
+    }
+
+    /// Changes to the newest sequence number, to wait for new messages
+    pub fn watch(&self) -> watch::Receiver<u64> {
+        self.latest.subscribe()
+    }
+
+    /// Delete messages older than `before` (Unix seconds)
+    pub fn prune(&self, before: i64) -> Result<usize> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        Ok(conn.execute(
+            "DELETE FROM bus_messages WHERE timestamp < ?1",
+            params![before],
+        )?)
+    }
+}
+
+/// Whether `topic` matches `pattern`, where "*" matches any run of characters
+pub fn topic_matches(pattern: &str, topic: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = topic.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(topic: &str) -> BusMessage {
+        BusMessage {
+            topic: topic.into(),
+            source: "test".into(),
+            payload_json: b"{}".to_vec(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_bus_delivers_matching_topics_durably() {
+        let path = std::env::temp_dir().join(format!("aios-bus-{}.db", uuid::Uuid::new_v4()));
+        let path = path.to_str().unwrap();
+
+        let bus = EventBus::new(path).unwrap();
+        let mut watch = bus.watch();
+        assert!(bus.publish(message("")).is_err());
+        bus.publish(message("gateway.budget_degradation")).unwrap();
+        bus.publish_event(&Event {
+            category: "service".into(),
+            event_type: "unit_failed".into(),
+            source: "agent.system".into(),
+            ..Default::default()
+        })
+        .unwrap();
+        let last = bus.publish(message("runtime.model_failed")).unwrap();
+        assert!(watch.has_changed().unwrap());
+        assert_eq!(*watch.borrow_and_update(), last);
+
+        let topics = vec!["events.service.*".to_string(), "gateway.*".to_string()];
+        let (deliveries, scanned) = bus.read_after(0, &topics, 10).unwrap();
+        assert_eq!(scanned, last);
+        assert_eq!(deliveries.len(), 2);
+        let event = deliveries[1].message.as_ref().unwrap().event.as_ref();
+        assert_eq!(event.unwrap().event_type, "unit_failed");
+
+        let (all, _) = bus.read_after(1, &[], 10).unwrap();
+        assert_eq!(all.len(), 2);
+
+        // Positions survive a restart and never move backwards
+        assert_eq!(bus.acked("orchestrator").unwrap(), None);
+        bus.ack("orchestrator", 2).unwrap();
+        bus.ack("orchestrator", 1).unwrap();
+        assert!(bus.ack("", 1).is_err());
+        drop(bus);
+        let bus = EventBus::new(path).unwrap();
+        assert_eq!(bus.acked("orchestrator").unwrap(), Some(2));
+        assert_eq!(bus.latest_seq(), last);
+        assert_eq!(bus.publish(message("gateway.x")).unwrap(), last + 1);
+
+        assert_eq!(bus.prune(i64::MAX).unwrap(), 4);
+        assert!(bus.read_after(0, &[], 10).unwrap().0.is_empty());
+        drop(bus);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{path}{suffix}"));
+        }
+    }
+}
@@ -0,0 +1,148 @@
+//! Collection dumps — whole tables as JSON, for debugging and backup
+//!
+//! `aios-memctl dump` writes a collection out and `aios-memctl restore`
+//! loads it back. A dump is a JSON array with one object per row, keyed by
+//! column name. BLOB columns (tool inputs and outputs, embeddings) become
+//! `{"$text": "..."}` when they hold UTF-8 and `{"$hex": "..."}` otherwise,
+//! so dumps stay readable and restore byte for byte.
+//!
+//! Restoring inserts or replaces rows by primary key; rows missing from the
+//! dump are left alone. Only tables listed here can be dumped — the name is
+//! spliced into SQL.
+
+use anyhow::{bail, Result};
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use rusqlite::Connection;
+use serde_json::{Map, Value};
+
+/// Working memory tables that can be dumped
+pub const WORKING_COLLECTIONS: &[&str] = &[
+    "goals",
+    "tasks",
+    "tool_calls",
+    "decisions",
+    "patterns",
+    "agent_states",
+    "benchmark_runs",
+    "task_checkpoints",
+    "chat_sessions",
+    "chat_messages",
+];
+
+/// Long-term memory tables that can be dumped
+pub const LONGTERM_COLLECTIONS: &[&str] = &["procedures", "incidents", "config_changes"];
+
+/// Every row of `table`, one JSON object per row
+pub fn dump_table(conn: &Connection, table: &str) -> Result<Vec<Value>> {
+    check_table(table)?;
+    let mut stmt = conn.prepare(&format!("SELECT * FROM {table}"))?;
+    let columns: Vec<String> = stmt.column_names().iter().map(|c| c.to_string()).collect();
+    let rows = stmt.query_map([], |row| {
+        let mut record = Map::new();
+        for (i, column) in columns.iter().enumerate() {
+            record.insert(column.clone(), to_json(row.get_ref(i)?));
+        }
+        Ok(Value::Object(record))
+    })?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+/// Insert or replace `records` into `table`, all or nothing
+pub fn restore_table(conn: &mut Connection, table: &str, records: &[Value]) -> Result<usize> {
+    check_table(table)?;
+    let known: Vec<String> = {
+        let mut stmt = conn.prepare("SELECT name FROM pragma_table_info(?1)")?;
+        let names = stmt.query_map([table], |row| row.get(0))?;
+        names.filter_map(|r| r.ok()).collect()
+    };
+
+    let tx = conn.transaction()?;
+    for (i, record) in records.iter().enumerate() {
+        let Some(fields) = record.as_object() else {
+            bail!("Record {i} of {table} is not an object");
+        };
+        if let Some(unknown) = fields.keys().find(|k| !known.contains(k)) {
+            bail!("Record {i} of {table} has unknown column '{unknown}'");
+        }
+        let columns: Vec<&String> = fields.keys().collect();
+        let placeholders: Vec<String> = (1..=columns.len()).map(|n| format!("?{n}")).collect();
+        let values = fields
+            .values()
+            .map(from_json)
+            .collect::<Result<Vec<SqlValue>>>()?;
+        tx.execute(
+            &format!(
+                "INSERT OR REPLACE INTO {table} ({}) VALUES ({})",
+                columns
+                    .iter()
+                    .map(|c| c.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                placeholders.join(", ")
+            ),
+            rusqlite::params_from_iter(values),
+        )?;
+    }
+    tx.commit()?;
+    Ok(records.len())
+}
+
+fn check_table(table: &str) -> Result<()> {
+    if WORKING_COLLECTIONS.contains(&table) || LONGTERM_COLLECTIONS.contains(&table) {
+        Ok(())
+    } else {
+        bail!("Unknown collection '{table}'")
+    }
+}
+
+fn to_json(value: ValueRef<'_>) -> Value {
+    match value {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(i) => Value::from(i),
+        ValueRef::Real(f) => Value::from(f),
+        ValueRef::Text(t) => Value::String(String::from_utf8_lossy(t).to_string()),
+        ValueRef::Blob(b) => match std::str::from_utf8(b) {
+            Ok(text) => serde_json::json!({ "$text": text }),
+            Err(_) => serde_json::json!({ "$hex": hex(b) }),
+        },
+    }
+}
+
+fn from_json(value: &Value) -> Result<SqlValue> {
+    Ok(match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(*b as i64),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => SqlValue::Integer(i),
+            None => SqlValue::Real(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => SqlValue::Text(s.clone()),
+        Value::Object(blob) => {
+            if let Some(text) = blob.get("$text").and_then(|v| v.as_str()) {
+                SqlValue::Blob(text.as_bytes().to_vec())
+            } else if let Some(encoded) = blob.get("$hex").and_then(|v| v.as_str()) {
+                SqlValue::Blob(unhex(encoded)?)
+            } else {
+                bail!("Objects must be {{\"$text\"}} or {{\"$hex\"}} blobs");
+            }
+        }
+        Value::Array(_) => bail!("Arrays are not column values"),
+    })
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn unhex(encoded: &str) -> Result<Vec<u8>> {
+    if !encoded.len().is_multiple_of(2) {
+        bail!("Odd-length hex blob");
+    }
+    (0..encoded.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&encoded[i..i + 2], 16)
+                .map_err(|e| anyhow::anyhow!("Bad hex blob: {e}"))
+        })
+        .collect()
+}
@@ -0,0 +1,640 @@
+//! Embedding Providers — vectors for long-term memory and the knowledge base
+//!
+//! One provider is selected with `AIOS_EMBEDDING_PROVIDER`:
+//! - `hash` (default): 64-dimension bag-of-words hashing, no model needed
+//! - `runtime`: a local GGUF model served by the AI runtime's `Embed` RPC
+//!   (`AIOS_EMBEDDING_URL`, default the runtime on port 50055)
+//! - `llama-server`: a local OpenAI-compatible `/v1/embeddings` endpoint,
+//!   such as a llama-server started with `--embedding` (`AIOS_EMBEDDING_URL`)
+//! - `openai`: the OpenAI embeddings API (`OPENAI_API_KEY`)
+//! - `cohere`: the Cohere embed API (`COHERE_API_KEY`)
+//! - `onnx`: a sentence-transformer ONNX model run in-process, with its
+//!   `tokenizer.json` alongside (requires the `onnx` build feature)
+//!
+//! `AIOS_EMBEDDING_MODEL` picks the model (for `runtime`, the name it is
+//! loaded under, by default whichever embedding model is loaded; for `onnx`, the path to the .onnx file). Every collection records which provider produced its vectors
+//! and their dimensionality; when the provider changes, stored vectors are
+//! re-embedded in the background. Until then, vectors of a different size
+//! score zero and searches fall back to keyword relevance.
+
+use anyhow::{bail, Context, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+use crate::proto::runtime::ai_runtime_client::AiRuntimeClient;
+use crate::proto::runtime::EmbedRequest;
+use crate::MemoryState;
+
+/// Dimensionality of the built-in hashed embeddings
+pub const HASH_DIMENSIONS: usize = 64;
+
+/// Provider id of the built-in hashed embeddings; also assumed for vectors
+/// stored before providers were recorded
+pub const HASH_PROVIDER: &str = "hash";
+
+/// Texts per request when re-embedding stored vectors
+const REEMBED_BATCH: usize = 32;
+
+/// Longest input, in tokens, fed to an in-process model
+#[cfg(feature = "onnx")]
+const ONNX_MAX_TOKENS: usize = 256;
+
+/// What a text is embedded for; some providers embed queries and documents
+/// differently
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Purpose {
+    Document,
+    Query,
+}
+
+/// Provider selection, read from the environment
+#[derive(Debug, Clone)]
+pub struct EmbeddingConfig {
+    pub provider: String,
+    pub model: String,
+    pub url: String,
+    pub api_key: String,
+}
+
+impl EmbeddingConfig {
+    pub fn from_env() -> Self {
+        let provider = std::env::var("AIOS_EMBEDDING_PROVIDER")
+            .unwrap_or_else(|_| HASH_PROVIDER.to_string())
+            .to_lowercase();
+        let (default_model, default_url, key_var) = match provider.as_str() {
+            "runtime" => ("embedding", "http://127.0.0.1:50055", ""),
+            "llama-server" => ("local", "http://127.0.0.1:8090/v1/embeddings", ""),
+            "openai" => (
+                "text-embedding-3-small",
+                "https://api.openai.com/v1/embeddings",
+                "OPENAI_API_KEY",
+            ),
+            "cohere" => (
+                "embed-english-v3.0",
+                "https://api.cohere.com/v2/embed",
+                "COHERE_API_KEY",
+            ),
+            "onnx" => ("/var/lib/aios/models/embedding/model.onnx", "", ""),
+            _ => ("", "", ""),
+        };
+        Self {
+            model: std::env::var("AIOS_EMBEDDING_MODEL").unwrap_or_else(|_| default_model.into()),
+            url: std::env::var("AIOS_EMBEDDING_URL").unwrap_or_else(|_| default_url.into()),
+            api_key: if key_var.is_empty() {
+                String::new()
+            } else {
+                std::env::var(key_var).unwrap_or_default()
+            },
+            provider,
+        }
+    }
+}
+
+/// Wire format of a remote provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HttpApi {
+    /// `{model, input}` → `{data: [{index, embedding}]}` (OpenAI, llama-server)
+    OpenAi,
+    /// `{model, texts, input_type, embedding_types}` → `{embeddings: {float}}`
+    Cohere,
+}
+
+/// The configured embedding provider
+pub enum Embedder {
+    Hash,
+    Runtime {
+        id: String,
+        model: String,
+        client: AiRuntimeClient<tonic::transport::Channel>,
+    },
+    Http {
+        id: String,
+        api: HttpApi,
+        url: String,
+        model: String,
+        api_key: String,
+        client: reqwest::Client,
+    },
+    #[cfg(feature = "onnx")]
+    Onnx {
+        id: String,
+        model: Arc<onnx::OnnxEmbedder>,
+    },
+}
+
+impl Embedder {
+    pub fn from_config(config: &EmbeddingConfig) -> Result<Self> {
+        let http = |api: HttpApi| -> Result<Self> {
+            if config.url.is_empty() {
+                bail!(
+                    "No endpoint configured for the {} embedding provider",
+                    config.provider
+                );
+            }
+            // Local llama-servers are the only endpoints without a key
+            if config.provider != "llama-server" && config.api_key.is_empty() {
+                bail!(
+                    "No API key configured for the {} embedding provider",
+                    config.provider
+                );
+            }
+            Ok(Self::Http {
+                id: format!("{}:{}", config.provider, config.model),
+                api,
+                url: config.url.clone(),
+                model: config.model.clone(),
+                api_key: config.api_key.clone(),
+                client: reqwest::Client::builder()
+                    .timeout(std::time::Duration::from_secs(60))
+                    .build()
+                    .unwrap_or_else(|_| reqwest::Client::new()),
+            })
+        };
+        match config.provider.as_str() {
+            HASH_PROVIDER => Ok(Self::Hash),
+            "runtime" => {
+                let channel = tonic::transport::Endpoint::from_shared(config.url.clone())
+                    .with_context(|| format!("Invalid runtime address {}", config.url))?
+                    .timeout(std::time::Duration::from_secs(60))
+                    .connect_lazy();
+                Ok(Self::Runtime {
+                    id: format!("runtime:{}", config.model),
+                    model: config.model.clone(),
+                    client: AiRuntimeClient::new(channel),
+                })
+            }
+            "llama-server" | "openai" => http(HttpApi::OpenAi),
+            "cohere" => http(HttpApi::Cohere),
+            #[cfg(feature = "onnx")]
+            "onnx" => Ok(Self::Onnx {
+                id: format!("onnx:{}", config.model),
+                model: Arc::new(onnx::OnnxEmbedder::load(&config.model)?),
+            }),
+            #[cfg(not(feature = "onnx"))]
+            "onnx" => bail!("The memory service was built without the onnx feature"),
+            other => bail!("Unknown embedding provider '{other}'"),
+        }
+    }
+
+    /// Provider id recorded with every stored vector
+    pub fn id(&self) -> &str {
+        match self {
+            Self::Hash => HASH_PROVIDER,
+            Self::Runtime { id, .. } | Self::Http { id, .. } => id,
+            #[cfg(feature = "onnx")]
+            Self::Onnx { id, .. } => id,
+        }
+    }
+
+    /// Embed a batch of texts, one vector per text
+    pub async fn embed(&self, texts: &[String], purpose: Purpose) -> Result<Vec<Vec<f32>>> {
+        let vectors = match self {
+            Self::Hash => texts.iter().map(|t| hash_embedding(t)).collect(),
+            Self::Runtime { model, client, .. } => {
+                let mut request = tonic::Request::new(EmbedRequest {
+                    model: model.clone(),
+                    texts: texts.to_vec(),
+                    requesting_agent: "memory".to_string(),
+                });
+                request.metadata_mut().insert(
+                    crate::identity::IDENTITY_HEADER,
+                    tonic::metadata::MetadataValue::from_static("service:memory"),
+                );
+                let response =
+                    client.clone().embed(request).await.map_err(|e| {
+                        anyhow::anyhow!("Runtime embedding failed: {}", e.message())
+                    })?;
+                response
+                    .into_inner()
+                    .embeddings
+                    .into_iter()
+                    .map(|e| e.values)
+                    .collect()
+            }
+            Self::Http {
+                api,
+                url,
+                model,
+                api_key,
+                client,
+                ..
+            } => {
+                let body = match api {
+                    HttpApi::OpenAi => serde_json::json!({"model": model, "input": texts}),
+                    HttpApi::Cohere => serde_json::json!({
+                        "model": model,
+                        "texts": texts,
+                        "input_type": match purpose {
+                            Purpose::Document => "search_document",
+                            Purpose::Query => "search_query",
+                        },
+                        "embedding_types": ["float"],
+                    }),
+                };
+                let mut request = client.post(url).json(&body);
+                if !api_key.is_empty() {
+                    request = request.bearer_auth(api_key);
+                }
+                let response = request.send().await.context("Embedding request failed")?;
+                let status = response.status();
+                if !status.is_success() {
+                    let text = response.text().await.unwrap_or_default();
+                    bail!("Embedding provider returned {status}: {text}");
+                }
+                let body: serde_json::Value = response
+                    .json()
+                    .await
+                    .context("Invalid embedding response")?;
+                parse_response(*api, body)?
+            }
+            #[cfg(feature = "onnx")]
+            Self::Onnx { model, .. } => {
+                let model = model.clone();
+                let texts = texts.to_vec();
+                tokio::task::spawn_blocking(move || {
+                    texts
+                        .iter()
+                        .map(|t| model.embed(t))
+                        .collect::<Result<Vec<_>>>()
+                })
+                .await
+                .context("Embedding task panicked")??
+            }
+        };
+        if vectors.len() != texts.len() {
+            bail!(
+                "Embedding provider returned {} vectors for {} texts",
+                vectors.len(),
+                texts.len()
+            );
+        }
+        Ok(vectors)
+    }
+
+    /// Embed a single text
+    pub async fn embed_one(&self, text: &str, purpose: Purpose) -> Result<Vec<f32>> {
+        let mut vectors = self.embed(&[text.to_string()], purpose).await?;
+        Ok(vectors.pop().unwrap_or_default())
+    }
+}
+
+#[derive(Deserialize)]
+struct OpenAiEmbeddings {
+    data: Vec<OpenAiEmbedding>,
+}
+
+#[derive(Deserialize)]
+struct OpenAiEmbedding {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+#[derive(Deserialize)]
+struct CohereEmbeddings {
+    embeddings: CohereFloats,
+}
+
+#[derive(Deserialize)]
+struct CohereFloats {
+    float: Vec<Vec<f32>>,
+}
+
+fn parse_response(api: HttpApi, body: serde_json::Value) -> Result<Vec<Vec<f32>>> {
+    match api {
+        HttpApi::OpenAi => {
+            let mut parsed: OpenAiEmbeddings =
+                serde_json::from_value(body).context("Unexpected embedding response")?;
+            parsed.data.sort_by_key(|e| e.index);
+            Ok(parsed.data.into_iter().map(|e| e.embedding).collect())
+        }
+        HttpApi::Cohere => {
+            let parsed: CohereEmbeddings =
+                serde_json::from_value(body).context("Unexpected embedding response")?;
+            Ok(parsed.embeddings.float)
+        }
+    }
+}
+
+/// Generate a simple bag-of-words embedding vector for text.
+/// Returns a normalized vector of hashed word frequencies.
+pub fn hash_embedding(text: &str) -> Vec<f32> {
+    let words: Vec<String> = text
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.len() > 2)
+        .map(|w| w.to_string())
+        .collect();
+
+    let dim = HASH_DIMENSIONS;
+    let mut vec = vec![0.0f32; dim];
+    if words.is_empty() {
+        return vec;
+    }
+
+    let mut word_counts: HashMap<String, usize> = HashMap::new();
+    for word in &words {
+        *word_counts.entry(word.clone()).or_insert(0) += 1;
+    }
+
+    for (word, count) in &word_counts {
+        // Simple hash-based projection
+        let hash = word
+            .bytes()
+            .fold(0u64, |acc, b| acc.wrapping_mul(31).wrapping_add(b as u64));
+        let idx = (hash % dim as u64) as usize;
+        vec[idx] += *count as f32;
+        // Also fill a second bin for better distribution
+        let idx2 = ((hash >> 16) % dim as u64) as usize;
+        vec[idx2] += (*count as f32) * 0.5;
+    }
+
+    // L2 normalize
+    let norm: f32 = vec.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        for v in &mut vec {
+            *v /= norm;
+        }
+    }
+    vec
+}
+
+/// Cosine similarity between two vectors; 0 when their sizes differ
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f64 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
+    let norm_a: f32 = a.iter().map(|v| v * v).sum::<f32>().sqrt();
+    let norm_b: f32 = b.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    (dot / (norm_a * norm_b)) as f64
+}
+
+/// Serialize embedding to bytes
+pub fn embedding_to_bytes(embedding: &[f32]) -> Vec<u8> {
+    embedding.iter().flat_map(|f| f.to_le_bytes()).collect()
+}
+
+/// Deserialize embedding from bytes
+pub fn bytes_to_embedding(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect()
+}
+
+// ── Per-collection metadata ─────────────────────────────
+
+/// Create the table recording each collection's provider and dimensionality
+pub fn init_collection_meta(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS embedding_collections (
+            collection TEXT PRIMARY KEY,
+            provider TEXT NOT NULL,
+            dimensions INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        )",
+    )?;
+    Ok(())
+}
+
+/// Record the provider and dimensionality of a collection's vectors
+pub fn set_collection_meta(
+    conn: &Connection,
+    collection: &str,
+    provider: &str,
+    dimensions: usize,
+) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO embedding_collections (collection, provider, dimensions, updated_at)
+         VALUES (?1, ?2, ?3, ?4)",
+        params![
+            collection,
+            provider,
+            dimensions as i64,
+            chrono::Utc::now().timestamp()
+        ],
+    )?;
+    Ok(())
+}
+
+/// Provider and dimensionality a collection's vectors were produced with
+pub fn collection_meta(conn: &Connection, collection: &str) -> Result<Option<(String, usize)>> {
+    let meta = conn
+        .query_row(
+            "SELECT provider, dimensions FROM embedding_collections WHERE collection = ?1",
+            [collection],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as usize)),
+        )
+        .optional()?;
+    Ok(meta)
+}
+
+/// Re-embed long-term procedures whose vectors are missing or came from a
+/// different provider. Runs in the background after startup; anything left
+/// when the provider fails is picked up on the next start.
+pub async fn reembed_stale(state: Arc<RwLock<MemoryState>>, embedder: Arc<Embedder>) {
+    let provider = embedder.id().to_string();
+    let mut done = 0usize;
+    loop {
+        let batch = match state
+            .read()
+            .await
+            .longterm
+            .stale_procedures(&provider, REEMBED_BATCH)
+        {
+            Ok(batch) => batch,
+            Err(e) => {
+                warn!("Cannot list procedures to re-embed: {e}");
+                return;
+            }
+        };
+        if batch.is_empty() {
+            break;
+        }
+        if done == 0 {
+            info!("Re-embedding stored procedures with {provider}");
+        }
+
+        let texts: Vec<String> = batch.iter().map(|(_, text)| text.clone()).collect();
+        let vectors = match embedder.embed(&texts, Purpose::Document).await {
+            Ok(vectors) => vectors,
+            Err(e) => {
+                warn!("Re-embedding paused after {done} procedures: {e}");
+                return;
+            }
+        };
+        let state = state.read().await;
+        for ((id, _), vector) in batch.iter().zip(&vectors) {
+            if let Err(e) = state
+                .longterm
+                .set_procedure_embedding(id, &provider, vector)
+            {
+                warn!("Re-embedding stopped at procedure {id}: {e}");
+                return;
+            }
+        }
+        done += batch.len();
+    }
+    if done > 0 {
+        info!("Re-embedded {done} procedures with {provider}");
+        if let Err(e) = state.read().await.longterm.save_index() {
+            warn!("Saving the vector index failed: {e}");
+        }
+    }
+}
+
+#[cfg(feature = "onnx")]
+mod onnx {
+    use anyhow::{anyhow, bail, Context, Result};
+    use tract_onnx::prelude::*;
+
+    /// A sentence-transformer model: mean-pooled, L2-normalized token states
+    pub struct OnnxEmbedder {
+        model: TypedRunnableModel<TypedModel>,
+        tokenizer: tokenizers::Tokenizer,
+        input_names: Vec<String>,
+    }
+
+    impl OnnxEmbedder {
+        /// Load `model_path` and the `tokenizer.json` next to it
+        pub fn load(model_path: &str) -> Result<Self> {
+            let tokenizer_path = std::path::Path::new(model_path).with_file_name("tokenizer.json");
+            let tokenizer = tokenizers::Tokenizer::from_file(&tokenizer_path)
+                .map_err(|e| anyhow!("Failed to load {}: {e}", tokenizer_path.display()))?;
+            let model = tract_onnx::onnx()
+                .model_for_path(model_path)
+                .with_context(|| format!("Failed to load ONNX model {model_path}"))?;
+            let input_names = model
+                .input_outlets()?
+                .iter()
+                .map(|outlet| model.node(outlet.node).name.clone())
+                .collect();
+            let model = model.into_optimized()?.into_runnable()?;
+            Ok(Self {
+                model,
+                tokenizer,
+                input_names,
+            })
+        }
+
+        pub fn embed(&self, text: &str) -> Result<Vec<f32>> {
+            let encoding = self
+                .tokenizer
+                .encode(text, true)
+                .map_err(|e| anyhow!("Tokenization failed: {e}"))?;
+            let len = encoding.get_ids().len().min(super::ONNX_MAX_TOKENS);
+            let mask = &encoding.get_attention_mask()[..len];
+
+            let mut inputs = TVec::new();
+            for name in &self.input_names {
+                let values = match name.as_str() {
+                    "input_ids" => encoding.get_ids(),
+                    "attention_mask" => encoding.get_attention_mask(),
+                    "token_type_ids" => encoding.get_type_ids(),
+                    other => bail!("Unsupported model input '{other}'"),
+                };
+                let values: Vec<i64> = values[..len].iter().map(|&v| v as i64).collect();
+                let tensor: Tensor =
+                    tract_ndarray::Array2::from_shape_vec((1, len), values)?.into();
+                inputs.push(tensor.into());
+            }
+
+            let outputs = self.model.run(inputs)?;
+            let hidden = outputs[0]
+                .to_array_view::<f32>()?
+                .into_dimensionality::<tract_ndarray::Ix3>()?;
+            let dims = hidden.shape()[2];
+            let mut pooled = vec![0.0f32; dims];
+            let mut count = 0.0f32;
+            for (t, &m) in mask.iter().enumerate() {
+                if m == 0 {
+                    continue;
+                }
+                count += 1.0;
+                for (d, value) in pooled.iter_mut().enumerate() {
+                    *value += hidden[[0, t, d]];
+                }
+            }
+            let norm = pooled.iter().map(|v| v * v).sum::<f32>().sqrt();
+            if count > 0.0 && norm > 0.0 {
+                for v in &mut pooled {
+                    *v /= norm;
+                }
+            }
+            Ok(pooled)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provider_responses_parse_in_order() {
+        let openai = serde_json::json!({
+            "data": [
+                {"index": 1, "embedding": [0.0, 1.0]},
+                {"index": 0, "embedding": [1.0, 0.0]},
+            ]
+        });
+        assert_eq!(
+            parse_response(HttpApi::OpenAi, openai).unwrap(),
+            vec![vec![1.0, 0.0], vec![0.0, 1.0]]
+        );
+
+        let cohere = serde_json::json!({"embeddings": {"float": [[0.5, 0.5, 0.5]]}});
+        assert_eq!(
+            parse_response(HttpApi::Cohere, cohere).unwrap(),
+            vec![vec![0.5, 0.5, 0.5]]
+        );
+        assert!(parse_response(HttpApi::OpenAi, serde_json::json!({})).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_provider_selection() {
+        let config = |provider: &str, api_key: &str| EmbeddingConfig {
+            provider: provider.to_string(),
+            model: "m".to_string(),
+            url: "http://127.0.0.1:1/v1/embeddings".to_string(),
+            api_key: api_key.to_string(),
+        };
+        assert_eq!(
+            Embedder::from_config(&config("hash", "")).unwrap().id(),
+            "hash"
+        );
+        assert_eq!(
+            Embedder::from_config(&config("runtime", "")).unwrap().id(),
+            "runtime:m"
+        );
+        assert_eq!(
+            Embedder::from_config(&config("llama-server", ""))
+                .unwrap()
+                .id(),
+            "llama-server:m"
+        );
+        assert!(Embedder::from_config(&config("openai", "")).is_err());
+        assert!(Embedder::from_config(&config("cohere", "key")).is_ok());
+        assert!(Embedder::from_config(&config("word2vec", "")).is_err());
+    }
+
+    #[test]
+    fn test_collection_meta_round_trip() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_collection_meta(&conn).unwrap();
+        assert!(collection_meta(&conn, "procedures").unwrap().is_none());
+        set_collection_meta(&conn, "procedures", "openai:m", 1536).unwrap();
+        set_collection_meta(&conn, "procedures", "cohere:m", 1024).unwrap();
+        assert_eq!(
+            collection_meta(&conn, "procedures").unwrap(),
+            Some(("cohere:m".to_string(), 1024))
+        );
+    }
+}
@@ -0,0 +1,503 @@
+//! Event Schemas — the operational event taxonomy and its validation
+//!
+//! Every event has a category (service, storage, security, ...), a type
+//! within that category, a severity, and the version of the payload schema
+//! it follows. Agents written before the taxonomy send "category.type" in
+//! `category` and only a `critical` flag; `validate` splits the name and
+//! fills in the rest, so stored events always carry the typed fields.
+//!
+//! Categories are a closed set. Types within a category are open, but a
+//! type with a registered schema must carry the schema's payload fields
+//! with the right JSON kinds, so the anomaly detector, proactive rules, and
+//! dashboards can read them without string matching. event-schemas.toml
+//! adds categories and schemas or replaces the built-in ones.
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use tracing::warn;
+
+use crate::proto::memory::{Event, EventField, EventSchemaList};
+
+/// Default location of the event schema configuration
+pub const EVENT_SCHEMAS_PATH: &str = "/etc/aios/event-schemas.toml";
+
+/// Severities, least to most severe
+pub const SEVERITIES: &[&str] = &["info", "warning", "error", "critical"];
+
+/// Position of a severity in `SEVERITIES`; unknown values rank lowest
+pub fn severity_rank(severity: &str) -> usize {
+    SEVERITIES.iter().position(|s| *s == severity).unwrap_or(0)
+}
+
+const BUILTIN_CATEGORIES: &[&str] = &[
+    "system",
+    "service",
+    "package",
+    "network",
+    "storage",
+    "security",
+    "monitoring",
+    "task",
+    "agent",
+    "creator",
+    "web",
+    "plugin",
+];
+
+/// JSON kind a payload field must have
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldKind {
+    String,
+    Number,
+    Bool,
+    Object,
+    Array,
+    Any,
+}
+
+impl FieldKind {
+    fn name(self) -> &'static str {
+        match self {
+            Self::String => "string",
+            Self::Number => "number",
+            Self::Bool => "bool",
+            Self::Object => "object",
+            Self::Array => "array",
+            Self::Any => "any",
+        }
+    }
+
+    fn matches(self, value: &serde_json::Value) -> bool {
+        match self {
+            Self::String => value.is_string(),
+            Self::Number => value.is_number(),
+            Self::Bool => value.is_boolean(),
+            Self::Object => value.is_object(),
+            Self::Array => value.is_array(),
+            Self::Any => true,
+        }
+    }
+}
+
+/// Payload contract for one event type
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct EventSchema {
+    pub category: String,
+    #[serde(rename = "type")]
+    pub event_type: String,
+    #[serde(default = "default_version")]
+    pub version: i32,
+    /// Severity for events that do not state one
+    #[serde(default = "default_severity")]
+    pub severity: String,
+    /// Required payload fields and their kinds
+    #[serde(default)]
+    pub fields: BTreeMap<String, FieldKind>,
+}
+
+fn default_version() -> i32 {
+    1
+}
+
+fn default_severity() -> String {
+    "info".into()
+}
+
+/// event-schemas.toml layout
+#[derive(Debug, Deserialize)]
+struct SchemaConfig {
+    /// Keep the built-in categories and schemas
+    #[serde(default = "default_builtin")]
+    builtin: bool,
+    #[serde(default)]
+    categories: Vec<String>,
+    #[serde(default, rename = "schema")]
+    schemas: Vec<EventSchema>,
+}
+
+fn default_builtin() -> bool {
+    true
+}
+
+fn schema(
+    category: &str,
+    event_type: &str,
+    severity: &str,
+    fields: &[(&str, FieldKind)],
+) -> EventSchema {
+    EventSchema {
+        category: category.into(),
+        event_type: event_type.into(),
+        version: 1,
+        severity: severity.into(),
+        fields: fields.iter().map(|(f, k)| (f.to_string(), *k)).collect(),
+    }
+}
+
+/// The categories and schemas in force
+#[derive(Debug, Clone)]
+pub struct EventRegistry {
+    categories: Vec<String>,
+    schemas: Vec<EventSchema>,
+}
+
+impl EventRegistry {
+    /// Built-in taxonomy: the categories the agents emit and schemas for
+    /// the events other components act on
+    pub fn builtin() -> Self {
+        use FieldKind::*;
+        Self {
+            categories: BUILTIN_CATEGORIES.iter().map(|c| c.to_string()).collect(),
+            schemas: vec![
+                schema(
+                    "system",
+                    "health",
+                    "info",
+                    &[("cpu", Number), ("memory", Number), ("disk", Number)],
+                ),
+                schema("service", "restarted", "info", &[("service", String)]),
+                schema(
+                    "service",
+                    "restart_failed",
+                    "error",
+                    &[("service", String), ("error", String)],
+                ),
+                schema(
+                    "storage",
+                    "disk_unhealthy",
+                    "critical",
+                    &[("devices", Array)],
+                ),
+                schema(
+                    "storage",
+                    "backup_created",
+                    "info",
+                    &[("backup_id", String)],
+                ),
+                schema(
+                    "storage",
+                    "backup_failed",
+                    "error",
+                    &[("error", String), ("destination", String)],
+                ),
+                schema(
+                    "storage",
+                    "fsck_errors",
+                    "warning",
+                    &[("device", String), ("found", Number), ("fixed", Number)],
+                ),
+                schema(
+                    "monitoring",
+                    "alerts_triggered",
+                    "warning",
+                    &[("new_alerts", Array), ("total_active", Number)],
+                ),
+                schema(
+                    "monitoring",
+                    "anomalies_detected",
+                    "warning",
+                    &[("count", Number), ("anomalies", Array)],
+                ),
+                schema("security", "intrusion_detected", "critical", &[]),
+                schema("security", "canary_tripped", "critical", &[]),
+                schema(
+                    "task",
+                    "plan_executed",
+                    "info",
+                    &[("steps_total", Number), ("success", Bool)],
+                ),
+            ],
+        }
+    }
+
+    /// Registry from the configuration at `path`.
+    /// A missing file yields the built-ins; an invalid one is logged and ignored.
+    pub fn load(path: &str) -> Self {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => Self::from_toml(&contents).unwrap_or_else(|e| {
+                warn!("Ignoring invalid event schemas in {path}: {e}");
+                Self::builtin()
+            }),
+            Err(_) => Self::builtin(),
+        }
+    }
+
+    /// Registry from an event-schemas.toml document. A file schema replaces
+    /// the built-in one for the same category and type.
+    pub fn from_toml(contents: &str) -> Result<Self> {
+        let config: SchemaConfig =
+            toml::from_str(contents).context("Failed to parse event schemas")?;
+        let mut registry = if config.builtin {
+            Self::builtin()
+        } else {
+            Self {
+                categories: Vec::new(),
+                schemas: Vec::new(),
+            }
+        };
+        for category in config.categories {
+            if !registry.categories.contains(&category) {
+                registry.categories.push(category);
+            }
+        }
+        for s in config.schemas {
+            if !registry.categories.contains(&s.category) {
+                bail!(
+                    "Schema {}.{} names an unknown category",
+                    s.category,
+                    s.event_type
+                );
+            }
+            if !SEVERITIES.contains(&s.severity.as_str()) {
+                bail!(
+                    "Schema {}.{} has unknown severity '{}'",
+                    s.category,
+                    s.event_type,
+                    s.severity
+                );
+            }
+            registry
+                .schemas
+                .retain(|b| b.category != s.category || b.event_type != s.event_type);
+            registry.schemas.push(s);
+        }
+        Ok(registry)
+    }
+
+    pub fn schema(&self, category: &str, event_type: &str) -> Option<&EventSchema> {
+        self.schemas
+            .iter()
+            .find(|s| s.category == category && s.event_type == event_type)
+    }
+
+    /// Normalize `event` into the taxonomy and check it against its schema
+    pub fn validate(&self, event: &mut Event) -> Result<()> {
+        if event.event_type.is_empty() {
+            if let Some((category, event_type)) = event.category.split_once('.') {
+                event.event_type = event_type.to_string();
+                event.category = category.to_string();
+            }
+        }
+        if !self.categories.contains(&event.category) {
+            bail!(
+                "Unknown event category '{}' (expected one of: {})",
+                event.category,
+                self.categories.join(", ")
+            );
+        }
+        if event.event_type.is_empty() {
+            bail!("Event type is required");
+        }
+        let schema = self.schema(&event.category, &event.event_type);
+
+        // The legacy critical flag and the severity always agree
+        event.severity = event.severity.to_lowercase();
+        if event.critical {
+            event.severity = "critical".into();
+        } else if event.severity.is_empty() {
+            event.severity = schema.map_or("info", |s| s.severity.as_str()).into();
+        } else if !SEVERITIES.contains(&event.severity.as_str()) {
+            bail!(
+                "Unknown severity '{}' (expected one of: {})",
+                event.severity,
+                SEVERITIES.join(", ")
+            );
+        }
+        event.critical = event.severity == "critical";
+
+        let Some(schema) = schema else {
+            if event.schema_version == 0 {
+                event.schema_version = 1;
+            }
+            return Ok(());
+        };
+        if event.schema_version == 0 {
+            event.schema_version = schema.version;
+        } else if event.schema_version > schema.version {
+            bail!(
+                "{}.{} schema version {} is newer than the registered version {}",
+                event.category,
+                event.event_type,
+                event.schema_version,
+                schema.version
+            );
+        }
+
+        let payload: serde_json::Value = if event.data_json.is_empty() {
+            serde_json::json!({})
+        } else {
+            serde_json::from_slice(&event.data_json).context("Event data is not valid JSON")?
+        };
+        let Some(payload) = payload.as_object() else {
+            bail!("Event data must be a JSON object");
+        };
+        let problems: Vec<String> = schema
+            .fields
+            .iter()
+            .filter_map(|(field, kind)| match payload.get(field) {
+                None => Some(format!("missing '{field}'")),
+                Some(value) if !kind.matches(value) => {
+                    Some(format!("'{field}' must be a {}", kind.name()))
+                }
+                Some(_) => None,
+            })
+            .collect();
+        if !problems.is_empty() {
+            bail!(
+                "{}.{} event does not match its schema: {}",
+                event.category,
+                event.event_type,
+                problems.join(", ")
+            );
+        }
+        Ok(())
+    }
+
+    /// The registry as returned by `ListEventSchemas`
+    pub fn to_proto(&self) -> EventSchemaList {
+        EventSchemaList {
+            categories: self.categories.clone(),
+            severities: SEVERITIES.iter().map(|s| s.to_string()).collect(),
+            schemas: self
+                .schemas
+                .iter()
+                .map(|s| crate::proto::memory::EventSchema {
+                    category: s.category.clone(),
+                    event_type: s.event_type.clone(),
+                    version: s.version,
+                    severity: s.severity.clone(),
+                    fields: s
+                        .fields
+                        .iter()
+                        .map(|(name, kind)| EventField {
+                            name: name.clone(),
+                            kind: kind.name().to_string(),
+                        })
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(category: &str, data: serde_json::Value, critical: bool) -> Event {
+        Event {
+            id: "e1".into(),
+            timestamp: 0,
+            category: category.into(),
+            source: "test-agent".into(),
+            data_json: serde_json::to_vec(&data).unwrap(),
+            critical,
+            event_type: String::new(),
+            severity: String::new(),
+            schema_version: 0,
+        }
+    }
+
+    #[test]
+    fn test_legacy_events_are_normalized() {
+        let registry = EventRegistry::builtin();
+
+        let mut e = event(
+            "service.restart_failed",
+            serde_json::json!({"service": "nginx", "error": "timeout"}),
+            false,
+        );
+        registry.validate(&mut e).unwrap();
+        assert_eq!(e.category, "service");
+        assert_eq!(e.event_type, "restart_failed");
+        assert_eq!(e.severity, "error");
+        assert_eq!(e.schema_version, 1);
+        assert!(!e.critical);
+
+        // Types without a schema are accepted; the critical flag wins
+        let mut e = event("network.link_down", serde_json::json!({}), true);
+        registry.validate(&mut e).unwrap();
+        assert_eq!(e.severity, "critical");
+
+        let mut e = event("service", serde_json::json!({}), false);
+        e.event_type = "reloaded".into();
+        e.severity = "Warning".into();
+        registry.validate(&mut e).unwrap();
+        assert_eq!(e.severity, "warning");
+    }
+
+    #[test]
+    fn test_invalid_events_are_rejected() {
+        let registry = EventRegistry::builtin();
+
+        let mut e = event("weather.rain", serde_json::json!({}), false);
+        assert!(registry.validate(&mut e).is_err());
+
+        let mut e = event("service", serde_json::json!({}), false);
+        assert!(registry.validate(&mut e).is_err());
+
+        let mut e = event(
+            "storage.fsck_errors",
+            serde_json::json!({"device": "/dev/sda1", "found": "3"}),
+            false,
+        );
+        let err = registry.validate(&mut e).unwrap_err().to_string();
+        assert!(err.contains("'found' must be a number"), "{err}");
+        assert!(err.contains("missing 'fixed'"), "{err}");
+
+        let mut e = event(
+            "service.restarted",
+            serde_json::json!({"service": "nginx"}),
+            false,
+        );
+        e.schema_version = 2;
+        assert!(registry.validate(&mut e).is_err());
+
+        let mut e = event("service.restarted", serde_json::json!({}), false);
+        e.severity = "urgent".into();
+        assert!(registry.validate(&mut e).is_err());
+    }
+
+    #[test]
+    fn test_config_extends_taxonomy() {
+        let registry = EventRegistry::from_toml(
+            r#"
+            categories = ["backup"]
+
+            [[schema]]
+            category = "backup"
+            type = "verified"
+            version = 2
+            fields = { snapshot = "string", bytes = "number" }
+
+            [[schema]]
+            category = "service"
+            type = "restarted"
+            severity = "warning"
+            "#,
+        )
+        .unwrap();
+
+        let mut e = event(
+            "backup.verified",
+            serde_json::json!({"snapshot": "s1", "bytes": 10}),
+            false,
+        );
+        registry.validate(&mut e).unwrap();
+        assert_eq!(e.schema_version, 2);
+
+        // The file schema replaced the built-in one (no required fields)
+        let mut e = event("service.restarted", serde_json::json!({}), false);
+        registry.validate(&mut e).unwrap();
+        assert_eq!(e.severity, "warning");
+
+        assert!(EventRegistry::from_toml("[[schema]]\ncategory = \"nope\"\ntype = \"x\"").is_err());
+        assert!(EventRegistry::from_toml("builtin = false")
+            .unwrap()
+            .to_proto()
+            .categories
+            .is_empty());
+    }
+}
@@ -0,0 +1,739 @@
+//! Knowledge Graph — entities and relationships over long-term memory
+//!
+//! Hosts, services, packages, incidents and goals are stored as entities
+//! (`kind:name` ids) with typed, directed relations between them:
+//! - host `runs` service, host `has_package` package
+//! - service `depends_on` service (from systemd Requires/Wants)
+//! - incident `affects` service/package, goal `targets` service/package
+//!
+//! Entities and relations are extracted from tool outputs, events, incidents
+//! and goals as they are stored. `GraphQuery` walks the graph (neighbors,
+//! shortest path), and context assembly renders the neighborhood of entities
+//! a task mentions, so "what depends on postgres?" is answered from edges
+//! rather than guessed from text.
+
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Mutex;
+
+use crate::proto::memory::{
+    Event, GoalRecord, GraphEntity, GraphRelation, Incident, ToolCallRecord,
+};
+use crate::retention::{self, SubjectColumns};
+
+/// Columns of each table a person or other subject may be mentioned in
+const SUBJECT_COLUMNS: &[SubjectColumns] = &[
+    ("entities", &["id", "name", "properties_json"]),
+    ("relations", &["source", "target"]),
+];
+
+/// Deepest walk a query may request
+pub const MAX_DEPTH: i32 = 6;
+
+/// Most entities a neighborhood walk visits
+const MAX_VISITED: usize = 200;
+
+/// Entity id for a kind and name; service names drop their `.service` suffix
+pub fn entity_id(kind: &str, name: &str) -> String {
+    format!("{kind}:{}", normalize_name(kind, name))
+}
+
+fn normalize_name(kind: &str, name: &str) -> String {
+    let name = name.trim().to_lowercase();
+    match kind {
+        "service" => name.trim_end_matches(".service").to_string(),
+        _ => name,
+    }
+}
+
+/// Which way to follow relations from an entity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Out,
+    In,
+    Both,
+}
+
+impl Direction {
+    pub fn parse(direction: &str) -> Self {
+        match direction {
+            "out" => Self::Out,
+            "in" => Self::In,
+            _ => Self::Both,
+        }
+    }
+}
+
+/// Entity/relationship store with SQLite persistence
+pub struct KnowledgeGraph {
+    conn: Mutex<Connection>,
+    /// Host entity id of the machine this service runs on
+    host: String,
+}
+
+impl KnowledgeGraph {
+    pub fn new(db_path: &str, hostname: &str) -> Result<Self> {
+        if let Some(parent) = std::path::Path::new(db_path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let conn = Connection::open(db_path)?;
+        conn.execute_batch("PRAGMA journal_mode=WAL; PRAGMA synchronous=NORMAL;")?;
+
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS entities (
+                id TEXT PRIMARY KEY,
+                kind TEXT NOT NULL,
+                name TEXT NOT NULL,
+                properties_json TEXT NOT NULL DEFAULT '{}',
+                first_seen INTEGER NOT NULL,
+                last_seen INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS relations (
+                source TEXT NOT NULL,
+                relation TEXT NOT NULL,
+                target TEXT NOT NULL,
+                first_seen INTEGER NOT NULL,
+                last_seen INTEGER NOT NULL,
+                PRIMARY KEY (source, relation, target)
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_entities_name ON entities(name);
+            CREATE INDEX IF NOT EXISTS idx_relations_target ON relations(target);",
+        )?;
+
+        let graph = Self {
+            conn: Mutex::new(conn),
+            host: entity_id("host", hostname),
+        };
+        graph.upsert_entity("host", hostname, serde_json::json!({}))?;
+        Ok(graph)
+    }
+
+    /// Add an entity or refresh it, merging `properties` into the stored ones
+    pub fn upsert_entity(
+        &self,
+        kind: &str,
+        name: &str,
+        properties: serde_json::Value,
+    ) -> Result<String> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        upsert_entity(&conn, kind, name, properties)
+    }
+
+    /// Resolve an entity id (`service:postgresql`) or a bare name to an id
+    pub fn resolve(&self, entity: &str) -> Result<Option<String>> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let name = entity.trim().to_lowercase();
+        let by_id = if let Some((kind, rest)) = name.split_once(':') {
+            conn.query_row(
+                "SELECT id FROM entities WHERE id = ?1",
+                params![entity_id(kind, rest)],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?
+        } else {
+            None
+        };
+        if by_id.is_some() {
+            return Ok(by_id);
+        }
+        Ok(conn
+            .query_row(
+                "SELECT id FROM entities WHERE name = ?1 OR name = ?2 ORDER BY last_seen DESC LIMIT 1",
+                params![name, name.trim_end_matches(".service")],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?)
+    }
+
+    /// Entities a text mentions by name, most recently seen first. Names
+    /// shorter than 3 characters are ignored; a word also matches a name it
+    /// prefixes by at least 4 characters ("postgres" → "postgresql").
+    pub fn mentioned(&self, text: &str, limit: usize) -> Result<Vec<String>> {
+        let words: HashSet<String> = text
+            .to_lowercase()
+            .split(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_' || c == '.'))
+            .map(|w| w.trim_matches('.').to_string())
+            .filter(|w| w.len() >= 3)
+            .collect();
+        if words.is_empty() {
+            return Ok(Vec::new());
+        }
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let mut stmt = conn.prepare(
+            "SELECT id, name FROM entities WHERE kind != 'host' OR id != ?1 ORDER BY last_seen DESC",
+        )?;
+        let rows = stmt.query_map(params![self.host], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        let mut found = Vec::new();
+        for row in rows {
+            let (id, name) = row?;
+            let hit = words.contains(&name)
+                || words
+                    .iter()
+                    .any(|w| w.len() >= 4 && name.len() > w.len() && name.starts_with(w.as_str()));
+            if hit {
+                found.push(id);
+                if found.len() >= limit {
+                    break;
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    /// Entities and relations within `depth` hops of `entity`, following
+    /// relations in `direction`, optionally only of type `relation`
+    pub fn neighbors(
+        &self,
+        entity: &str,
+        direction: Direction,
+        relation: &str,
+        depth: i32,
+    ) -> Result<(Vec<GraphEntity>, Vec<GraphRelation>)> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let depth = depth.clamp(1, MAX_DEPTH);
+
+        let mut visited: HashSet<String> = HashSet::from([entity.to_string()]);
+        let mut relations: Vec<GraphRelation> = Vec::new();
+        let mut seen_relations: HashSet<(String, String, String)> = HashSet::new();
+        let mut frontier = vec![entity.to_string()];
+        for _ in 0..depth {
+            let mut next = Vec::new();
+            for id in &frontier {
+                for edge in edges(&conn, id, direction, relation)? {
+                    let other = if edge.source == *id {
+                        edge.target.clone()
+                    } else {
+                        edge.source.clone()
+                    };
+                    let key = (
+                        edge.source.clone(),
+                        edge.relation.clone(),
+                        edge.target.clone(),
+                    );
+                    if seen_relations.insert(key) {
+                        relations.push(edge);
+                    }
+                    if visited.len() < MAX_VISITED && visited.insert(other.clone()) {
+                        next.push(other);
+                    }
+                }
+            }
+            if next.is_empty() {
+                break;
+            }
+            frontier = next;
+        }
+
+        let mut entities = Vec::new();
+        for id in &visited {
+            if let Some(entity) = load_entity(&conn, id)? {
+                entities.push(entity);
+            }
+        }
+        entities.sort_by(|a, b| a.id.cmp(&b.id));
+        relations.retain(|r| visited.contains(&r.source) && visited.contains(&r.target));
+        Ok((entities, relations))
+    }
+
+    /// Shortest chain of entity ids from `from` to `to` (relations followed
+    /// in `direction`), or empty if none within `max_depth` hops
+    pub fn path(
+        &self,
+        from: &str,
+        to: &str,
+        direction: Direction,
+        max_depth: i32,
+    ) -> Result<Vec<String>> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let max_depth = max_depth.clamp(1, MAX_DEPTH) as usize;
+
+        let mut parents: HashMap<String, String> = HashMap::new();
+        let mut queue = VecDeque::from([(from.to_string(), 0usize)]);
+        let mut visited: HashSet<String> = HashSet::from([from.to_string()]);
+        while let Some((id, hops)) = queue.pop_front() {
+            if id == to {
+                let mut path = vec![id];
+                while let Some(parent) = parents.get(path.last().map(String::as_str).unwrap_or(""))
+                {
+                    path.push(parent.clone());
+                }
+                path.reverse();
+                return Ok(path);
+            }
+            if hops >= max_depth {
+                continue;
+            }
+            for edge in edges(&conn, &id, direction, "")? {
+                let other = if edge.source == id {
+                    edge.target
+                } else {
+                    edge.source
+                };
+                if visited.insert(other.clone()) {
+                    parents.insert(other.clone(), id.clone());
+                    queue.push_back((other, hops + 1));
+                }
+            }
+        }
+        Ok(Vec::new())
+    }
+
+    // --- Extraction ---
+
+    /// Entities and relations from a successful tool call's output
+    pub fn extract_tool_call(&self, record: &ToolCallRecord) -> Result<()> {
+        if !record.success {
+            return Ok(());
+        }
+        let input: serde_json::Value =
+            serde_json::from_slice(&record.input_json).unwrap_or_default();
+        let output: serde_json::Value =
+            serde_json::from_slice(&record.output_json).unwrap_or_default();
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+
+        match record.tool_name.as_str() {
+            "service.status" => {
+                let Some(name) = input["name"].as_str() else {
+                    return Ok(());
+                };
+                let service = upsert_entity(
+                    &conn,
+                    "service",
+                    name,
+                    serde_json::json!({"status": output["status"]}),
+                )?;
+                relate(&conn, &self.host, "runs", &service)?;
+                for dependency in string_list(&output["requires"]) {
+                    let dependency =
+                        upsert_entity(&conn, "service", &dependency, serde_json::json!({}))?;
+                    relate(&conn, &service, "depends_on", &dependency)?;
+                }
+                for dependent in string_list(&output["required_by"]) {
+                    let dependent =
+                        upsert_entity(&conn, "service", &dependent, serde_json::json!({}))?;
+                    relate(&conn, &dependent, "depends_on", &service)?;
+                }
+            }
+            "service.list" => {
+                for entry in output["services"].as_array().into_iter().flatten() {
+                    if let Some(name) = entry["name"].as_str() {
+                        let service = upsert_entity(
+                            &conn,
+                            "service",
+                            name,
+                            serde_json::json!({"status": entry["status"]}),
+                        )?;
+                        relate(&conn, &self.host, "runs", &service)?;
+                    }
+                }
+            }
+            "service.start" | "service.stop" | "service.restart" => {
+                if let Some(name) = input["name"].as_str() {
+                    let service = upsert_entity(
+                        &conn,
+                        "service",
+                        name,
+                        serde_json::json!({"last_action": record.tool_name}),
+                    )?;
+                    relate(&conn, &self.host, "runs", &service)?;
+                }
+            }
+            "pkg.install" => {
+                if let Some(name) = input["name"].as_str() {
+                    let package = upsert_entity(
+                        &conn,
+                        "package",
+                        name,
+                        serde_json::json!({"version": output["version"]}),
+                    )?;
+                    relate(&conn, &self.host, "has_package", &package)?;
+                }
+            }
+            "pkg.remove" => {
+                if let Some(name) = input["name"].as_str() {
+                    conn.execute(
+                        "DELETE FROM relations WHERE source = ?1 AND relation = 'has_package' AND target = ?2",
+                        params![self.host, entity_id("package", name)],
+                    )?;
+                }
+            }
+            "pkg.list_installed" => {
+                for entry in output["packages"].as_array().into_iter().flatten() {
+                    if let Some(name) = entry["name"].as_str() {
+                        let package = upsert_entity(
+                            &conn,
+                            "package",
+                            name,
+                            serde_json::json!({"version": entry["version"]}),
+                        )?;
+                        relate(&conn, &self.host, "has_package", &package)?;
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Services, packages and hosts named in an event's payload
+    pub fn extract_event(&self, event: &Event) -> Result<()> {
+        let data: serde_json::Value = serde_json::from_slice(&event.data_json).unwrap_or_default();
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let host = match data["host"].as_str().or(data["hostname"].as_str()) {
+            Some(name) => upsert_entity(&conn, "host", name, serde_json::json!({}))?,
+            None => self.host.clone(),
+        };
+        if let Some(name) = data["service"].as_str().or(data["unit"].as_str()) {
+            let service = upsert_entity(&conn, "service", name, serde_json::json!({}))?;
+            relate(&conn, &host, "runs", &service)?;
+        }
+        if let Some(name) = data["package"].as_str() {
+            let package = upsert_entity(&conn, "package", name, serde_json::json!({}))?;
+            relate(&conn, &host, "has_package", &package)?;
+        }
+        Ok(())
+    }
+
+    /// An incident and the known services/packages its description names
+    pub fn extract_incident(&self, incident: &Incident) -> Result<()> {
+        let text = format!(
+            "{} {} {}",
+            incident.description, incident.root_cause, incident.resolution
+        );
+        let mentioned = self.mentioned(&text, 10)?;
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let id = upsert_entity(
+            &conn,
+            "incident",
+            &incident.id,
+            serde_json::json!({
+                "description": incident.description,
+                "resolved": !incident.resolution.is_empty(),
+            }),
+        )?;
+        for target in mentioned.iter().filter(|m| is_infrastructure(m)) {
+            relate(&conn, &id, "affects", target)?;
+        }
+        Ok(())
+    }
+
+    /// A goal and the known services/packages its description names
+    pub fn extract_goal(&self, goal: &GoalRecord) -> Result<()> {
+        let mentioned = self.mentioned(&goal.description, 10)?;
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let id = upsert_entity(
+            &conn,
+            "goal",
+            &goal.id,
+            serde_json::json!({"description": goal.description, "status": goal.status}),
+        )?;
+        for target in mentioned.iter().filter(|m| is_infrastructure(m)) {
+            relate(&conn, &id, "targets", target)?;
+        }
+        Ok(())
+    }
+
+    /// Delete every entity mentioning `pattern` (normalized, see
+    /// [`crate::retention`]) with its relations; rows removed per table
+    pub fn erase_subject(&self, pattern: &str) -> Result<Vec<(String, usize)>> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        conn.execute_batch("PRAGMA secure_delete=ON;")?;
+        let (_, entity_columns) = SUBJECT_COLUMNS[0];
+        let filter = entity_columns
+            .iter()
+            .map(|c| format!("instr(lower({c}), ?1) > 0"))
+            .collect::<Vec<_>>()
+            .join(" OR ");
+        let attached = conn.execute(
+            &format!(
+                "DELETE FROM relations WHERE source IN (SELECT id FROM entities WHERE {filter})
+                 OR target IN (SELECT id FROM entities WHERE {filter})"
+            ),
+            params![pattern],
+        )?;
+        let mut removed = retention::erase_rows(&conn, SUBJECT_COLUMNS, pattern)?;
+        if let Some((_, n)) = removed.iter_mut().find(|(table, _)| table == "relations") {
+            *n += attached;
+        }
+        retention::truncate_wal(&conn)?;
+        Ok(removed)
+    }
+
+    /// Entities and relations still mentioning `pattern`
+    pub fn count_subject(&self, pattern: &str) -> Result<usize> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        retention::count_rows(&conn, SUBJECT_COLUMNS, pattern)
+    }
+}
+
+fn is_infrastructure(id: &str) -> bool {
+    id.starts_with("service:") || id.starts_with("package:") || id.starts_with("host:")
+}
+
+fn string_list(value: &serde_json::Value) -> Vec<String> {
+    value
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|v| v.as_str().map(str::to_string))
+        .collect()
+}
+
+fn upsert_entity(
+    conn: &Connection,
+    kind: &str,
+    name: &str,
+    properties: serde_json::Value,
+) -> Result<String> {
+    let id = entity_id(kind, name);
+    let now = chrono::Utc::now().timestamp();
+    let mut merged: serde_json::Map<String, serde_json::Value> = conn
+        .query_row(
+            "SELECT properties_json FROM entities WHERE id = ?1",
+            params![id],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()?
+        .and_then(|p| serde_json::from_str(&p).ok())
+        .unwrap_or_default();
+    if let serde_json::Value::Object(properties) = properties {
+        for (key, value) in properties {
+            if !value.is_null() {
+                merged.insert(key, value);
+            }
+        }
+    }
+    conn.execute(
+        "INSERT INTO entities (id, kind, name, properties_json, first_seen, last_seen)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?5)
+         ON CONFLICT(id) DO UPDATE SET properties_json = ?4, last_seen = ?5",
+        params![
+            id,
+            kind,
+            normalize_name(kind, name),
+            serde_json::Value::Object(merged).to_string(),
+            now
+        ],
+    )?;
+    Ok(id)
+}
+
+fn relate(conn: &Connection, source: &str, relation: &str, target: &str) -> Result<()> {
+    if source == target {
+        return Ok(());
+    }
+    let now = chrono::Utc::now().timestamp();
+    conn.execute(
+        "INSERT INTO relations (source, relation, target, first_seen, last_seen)
+         VALUES (?1, ?2, ?3, ?4, ?4)
+         ON CONFLICT(source, relation, target) DO UPDATE SET last_seen = ?4",
+        params![source, relation, target, now],
+    )?;
+    Ok(())
+}
+
+fn edges(
+    conn: &Connection,
+    id: &str,
+    direction: Direction,
+    relation: &str,
+) -> Result<Vec<GraphRelation>> {
+    let sql = match direction {
+        Direction::Out => "SELECT source, relation, target, last_seen FROM relations WHERE source = ?1 AND (?2 = '' OR relation = ?2)",
+        Direction::In => "SELECT source, relation, target, last_seen FROM relations WHERE target = ?1 AND (?2 = '' OR relation = ?2)",
+        Direction::Both => "SELECT source, relation, target, last_seen FROM relations WHERE (source = ?1 OR target = ?1) AND (?2 = '' OR relation = ?2)",
+    };
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt.query_map(params![id, relation], |row| {
+        Ok(GraphRelation {
+            source: row.get(0)?,
+            relation: row.get(1)?,
+            target: row.get(2)?,
+            last_seen: row.get(3)?,
+        })
+    })?;
+    Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+}
+
+fn load_entity(conn: &Connection, id: &str) -> Result<Option<GraphEntity>> {
+    Ok(conn
+        .query_row(
+            "SELECT id, kind, name, properties_json, last_seen FROM entities WHERE id = ?1",
+            params![id],
+            |row| {
+                Ok(GraphEntity {
+                    id: row.get(0)?,
+                    kind: row.get(1)?,
+                    name: row.get(2)?,
+                    properties_json: row.get::<_, String>(3)?.into_bytes(),
+                    last_seen: row.get(4)?,
+                })
+            },
+        )
+        .optional()?)
+}
+
+/// Plain-text facts about an entity's neighborhood, for prompts
+pub fn describe(entity: &str, entities: &[GraphEntity], relations: &[GraphRelation]) -> String {
+    let label = |id: &str| -> String {
+        entities
+            .iter()
+            .find(|e| e.id == id)
+            .map(|e| format!("{} ({})", e.name, e.kind))
+            .unwrap_or_else(|| id.to_string())
+    };
+    let mut lines = vec![format!("Known relationships of {}:", label(entity))];
+    for relation in relations {
+        lines.push(format!(
+            "- {} {} {}",
+            label(&relation.source),
+            relation.relation.replace('_', " "),
+            label(&relation.target)
+        ));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool_call(
+        tool: &str,
+        input: serde_json::Value,
+        output: serde_json::Value,
+    ) -> ToolCallRecord {
+        ToolCallRecord {
+            tool_name: tool.into(),
+            input_json: serde_json::to_vec(&input).unwrap(),
+            output_json: serde_json::to_vec(&output).unwrap(),
+            success: true,
+            ..Default::default()
+        }
+    }
+
+    fn graph() -> KnowledgeGraph {
+        let graph = KnowledgeGraph::new(":memory:", "box1").unwrap();
+        graph
+            .extract_tool_call(&tool_call(
+                "service.status",
+                serde_json::json!({"name": "postgresql"}),
+                serde_json::json!({"status": "active", "required_by": ["webapp.service"]}),
+            ))
+            .unwrap();
+        graph
+            .extract_tool_call(&tool_call(
+                "service.status",
+                serde_json::json!({"name": "worker"}),
+                serde_json::json!({"status": "active", "requires": ["webapp"]}),
+            ))
+            .unwrap();
+        graph
+    }
+
+    #[test]
+    fn test_dependents_from_tool_outputs() {
+        let graph = graph();
+        let postgres = graph.resolve("postgres").unwrap();
+        assert!(postgres.is_none());
+        let postgres = graph.resolve("postgresql").unwrap().unwrap();
+        assert_eq!(postgres, "service:postgresql");
+
+        // Direct and transitive dependents of postgres
+        let (entities, relations) = graph
+            .neighbors(&postgres, Direction::In, "depends_on", 2)
+            .unwrap();
+        let ids: Vec<_> = entities.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec!["service:postgresql", "service:webapp", "service:worker"]
+        );
+        assert_eq!(relations.len(), 2);
+        assert!(describe(&postgres, &entities, &relations)
+            .contains("webapp (service) depends on postgresql (service)"));
+
+        assert_eq!(
+            graph
+                .path("service:worker", "service:postgresql", Direction::Out, 6)
+                .unwrap(),
+            vec!["service:worker", "service:webapp", "service:postgresql"]
+        );
+        assert!(graph
+            .path("service:postgresql", "service:worker", Direction::Out, 6)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_mentions_link_incidents_and_goals() {
+        let graph = graph();
+        assert_eq!(
+            graph
+                .mentioned("What depends on postgres on this box?", 5)
+                .unwrap(),
+            vec!["service:postgresql"]
+        );
+
+        graph
+            .extract_incident(&Incident {
+                id: "inc-1".into(),
+                description: "webapp returned 502s".into(),
+                ..Default::default()
+            })
+            .unwrap();
+        graph
+            .extract_goal(&GoalRecord {
+                id: "g-1".into(),
+                description: "Restart webapp".into(),
+                status: "pending".into(),
+                ..Default::default()
+            })
+            .unwrap();
+        let (_, relations) = graph
+            .neighbors("service:webapp", Direction::In, "", 1)
+            .unwrap();
+        let sources: HashSet<_> = relations.iter().map(|r| r.source.as_str()).collect();
+        assert!(sources.contains("incident:inc-1"));
+        assert!(sources.contains("goal:g-1"));
+        assert!(sources.contains("service:worker"));
+    }
+}
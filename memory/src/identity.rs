@@ -0,0 +1,565 @@
+//! Identity Registry — one identity for every actor
+//!
+//! Agents, tools, console users, cluster nodes and services are registered
+//! under a stable id, `<kind>:<name>` (`agent:security-agent`,
+//! `user:alice`, `node:3f2a…`), with their roles, public key and metadata,
+//! in SQLite (`AIOS_IDENTITY_DB`). Registering can issue a token; only its
+//! SHA-256 is kept.
+//!
+//! Every gRPC call names the identity it acts as in the `x-aios-identity`
+//! header, with the identity's token in `x-aios-identity-token`. A call
+//! made as an identity that holds a token without that token is rejected.
+//! Access policies and the access log key off the acting identity only
+//! when the call proved it with the token; any other caller acts as
+//! `unverified:<id>` (or `anonymous` without the header), which no policy
+//! names. The `requesting_agent` a request names is never trusted.
+//!
+//! Reserved identities — nodes, services, users and the names access
+//! policies grant reads to — are registered only by a verified node or
+//! service, or with the bootstrap token this service keeps in
+//! `AIOS_IDENTITY_BOOTSTRAP_TOKEN_PATH` (readable by root only), which the
+//! orchestrator presents to register its node the first time. Users are
+//! registered by the orchestrator when they sign in to the console. An identity
+//! holding a token is re-registered only by itself or by a verified node
+//! or service.
+
+use anyhow::{bail, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::proto::memory::Identity;
+
+/// Header naming the identity a call acts as
+pub const IDENTITY_HEADER: &str = "x-aios-identity";
+
+/// Header carrying the acting identity's token
+pub const TOKEN_HEADER: &str = "x-aios-identity-token";
+
+/// Kinds of identity
+pub const KINDS: [&str; 5] = ["agent", "tool", "user", "node", "service"];
+
+/// Prefix of the key callers act as when they did not prove their identity
+pub const UNVERIFIED: &str = "unverified:";
+
+/// Default location of the bootstrap token
+pub const DEFAULT_BOOTSTRAP_TOKEN_PATH: &str = "/var/lib/aios/identity/bootstrap.token";
+
+/// An acting identity, as named by a call
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Principal {
+    pub kind: String,
+    pub name: String,
+}
+
+impl Principal {
+    /// `<kind>:<name>`, None for other strings
+    pub fn parse(id: &str) -> Option<Self> {
+        let (kind, name) = id.split_once(':')?;
+        (KINDS.contains(&kind) && !name.is_empty()).then(|| Self {
+            kind: kind.to_string(),
+            name: name.to_string(),
+        })
+    }
+
+    pub fn id(&self) -> String {
+        format!("{}:{}", self.kind, self.name)
+    }
+
+    /// Name per-actor state is kept under. Agents and services keep their
+    /// bare names, so existing policies and history still apply to them;
+    /// users, nodes and tools go by their full id so they cannot pass for
+    /// an agent.
+    pub fn key(&self) -> String {
+        match self.kind.as_str() {
+            "agent" | "service" => self.name.clone(),
+            _ => self.id(),
+        }
+    }
+
+    /// Whether the identity is a service or node
+    pub fn is_infrastructure(&self) -> bool {
+        matches!(self.kind.as_str(), "service" | "node")
+    }
+
+    /// Whether identities of this kind are reserved: nodes, services, and
+    /// users, whom the orchestrator registers once they sign in with an
+    /// operator account
+    pub fn is_reserved_kind(&self) -> bool {
+        self.is_infrastructure() || self.kind == "user"
+    }
+}
+
+/// The identity a call was made as, as checked by [`IdentityCheck`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Caller {
+    pub principal: Principal,
+    /// The call carried the identity's token
+    pub verified: bool,
+}
+
+/// The identity a call proved with its token, if any
+pub fn verified<T>(request: &tonic::Request<T>) -> Option<&Principal> {
+    request
+        .extensions()
+        .get::<Caller>()
+        .filter(|c| c.verified)
+        .map(|c| &c.principal)
+}
+
+/// Key access policies and the access log know the caller by: the
+/// identity's key when the call proved it, `unverified:<id>` when it only
+/// named it, and "" (anonymous) when it named none
+pub fn acting<T>(request: &tonic::Request<T>) -> String {
+    match request.extensions().get::<Caller>() {
+        Some(Caller {
+            principal,
+            verified: true,
+        }) => principal.key(),
+        Some(Caller { principal, .. }) => format!("{UNVERIFIED}{}", principal.id()),
+        None => String::new(),
+    }
+}
+
+/// The bootstrap token at `path`, created (mode 0600) if missing
+pub fn bootstrap_token(path: &str) -> Result<String> {
+    if let Ok(token) = std::fs::read_to_string(path) {
+        if !token.trim().is_empty() {
+            return Ok(token.trim().to_string());
+        }
+    }
+    if let Some(parent) = std::path::Path::new(path).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let token = new_token();
+    std::fs::write(path, &token)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(token)
+}
+
+/// Registered identities and the hashes of their tokens
+pub struct IdentityRegistry {
+    conn: Mutex<Connection>,
+    /// Hash of the token that registers reserved identities
+    bootstrap_hash: Option<String>,
+}
+
+impl IdentityRegistry {
+    pub fn new(db_path: &str) -> Result<Self> {
+        if let Some(parent) = std::path::Path::new(db_path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let conn = Connection::open(db_path)?;
+        conn.execute_batch("PRAGMA journal_mode=WAL; PRAGMA synchronous=NORMAL;")?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS identities (
+                id TEXT PRIMARY KEY,
+                kind TEXT NOT NULL,
+                name TEXT NOT NULL,
+                roles TEXT NOT NULL,
+                public_key TEXT NOT NULL,
+                metadata TEXT NOT NULL,
+                token_hash TEXT,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_identities_kind ON identities(kind);",
+        )?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+            bootstrap_hash: None,
+        })
+    }
+
+    /// Accept `token` for registering reserved identities
+    pub fn with_bootstrap_token(mut self, token: &str) -> Self {
+        self.bootstrap_hash = Some(token_hash(token));
+        self
+    }
+
+    fn is_bootstrap(&self, token: &str) -> bool {
+        self.bootstrap_hash
+            .as_deref()
+            .is_some_and(|hash| hash == token_hash(token))
+    }
+
+    /// Create or update an identity, keeping its creation time and, unless
+    /// `issue_token`, its token. Returns the identity and any new token.
+    pub fn register(
+        &self,
+        identity: &Identity,
+        issue_token: bool,
+    ) -> Result<(Identity, Option<String>)> {
+        if !KINDS.contains(&identity.kind.as_str()) {
+            bail!("Unknown identity kind '{}'", identity.kind);
+        }
+        if identity.name.is_empty() || identity.name.contains(char::is_whitespace) {
+            bail!("Identity names must be non-empty and without whitespace");
+        }
+        let id = format!("{}:{}", identity.kind, identity.name);
+        let token = issue_token.then(new_token);
+        let now = chrono::Utc::now().timestamp();
+        {
+            let conn = self
+                .conn
+                .lock()
+                .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+            conn.execute(
+                "INSERT INTO identities
+                    (id, kind, name, roles, public_key, metadata, token_hash, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8)
+                 ON CONFLICT(id) DO UPDATE SET
+                    roles = excluded.roles,
+                    public_key = excluded.public_key,
+                    metadata = excluded.metadata,
+                    token_hash = COALESCE(excluded.token_hash, token_hash),
+                    updated_at = excluded.updated_at",
+                params![
+                    id,
+                    identity.kind,
+                    identity.name,
+                    serde_json::to_string(&identity.roles)?,
+                    identity.public_key,
+                    serde_json::to_string(&identity.metadata)?,
+                    token.as_deref().map(token_hash),
+                    now,
+                ],
+            )?;
+        }
+        let registered = self
+            .get(&id)?
+            .ok_or_else(|| anyhow::anyhow!("Identity {id} vanished"))?;
+        Ok((registered, token))
+    }
+
+    pub fn get(&self, id: &str) -> Result<Option<Identity>> {
+        Ok(self.lookup(id)?.map(|(identity, _)| identity))
+    }
+
+    /// Identities of `kind` ("" for all), by id
+    pub fn list(&self, kind: &str) -> Result<Vec<Identity>> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {COLUMNS} FROM identities WHERE ?1 = '' OR kind = ?1 ORDER BY id"
+        ))?;
+        let rows = stmt.query_map(params![kind], row_to_identity)?;
+        rows.map(|row| Ok(row?.0)).collect()
+    }
+
+    /// The identity `id`, if `token` is its token
+    pub fn verify(&self, id: &str, token: &str) -> Result<Identity> {
+        match self.lookup(id)? {
+            Some((identity, Some(hash))) if hash == token_hash(token) => Ok(identity),
+            Some((_, Some(_))) => bail!("Wrong token for {id}"),
+            Some((_, None)) => bail!("{id} has no token"),
+            None => bail!("Unknown identity {id}"),
+        }
+    }
+
+    /// Whether a call may act as `id`, and whether it proved it: identities
+    /// holding a token must present it; others, and calls carrying the
+    /// bootstrap token, pass unverified
+    pub fn check(&self, id: &str, token: Option<&str>) -> Result<bool> {
+        if token.is_some_and(|t| self.is_bootstrap(t)) {
+            return Ok(false);
+        }
+        match self.lookup(id)? {
+            Some((_, Some(hash))) => match token {
+                Some(token) if token_hash(token) == hash => Ok(true),
+                Some(_) => bail!("Wrong token for {id}"),
+                None => bail!("Calls as {id} must carry its token"),
+            },
+            _ => Ok(false),
+        }
+    }
+
+    /// Whether a call may register `id`. Reserved identities need a
+    /// verified node or service, or the bootstrap token; an identity holding
+    /// a token is re-registered only by itself, a verified node or service,
+    /// or with the bootstrap token.
+    pub fn authorize_registration(
+        &self,
+        id: &Principal,
+        reserved: bool,
+        verified: Option<&Principal>,
+        token: Option<&str>,
+    ) -> Result<()> {
+        if token.is_some_and(|t| self.is_bootstrap(t))
+            || verified.is_some_and(Principal::is_infrastructure)
+        {
+            return Ok(());
+        }
+        let id_str = id.id();
+        let holds_token = self
+            .lookup(&id_str)?
+            .is_some_and(|(_, hash)| hash.is_some());
+        if holds_token && verified != Some(id) {
+            bail!("Only {id_str} may re-register itself");
+        }
+        if reserved && !holds_token {
+            bail!("{id_str} is reserved: only a verified node or service registers it");
+        }
+        Ok(())
+    }
+
+    fn lookup(&self, id: &str) -> Result<Option<(Identity, Option<String>)>> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        Ok(conn
+            .query_row(
+                &format!("SELECT {COLUMNS} FROM identities WHERE id = ?1"),
+                params![id],
+                row_to_identity,
+            )
+            .optional()?)
+    }
+}
+
+const COLUMNS: &str =
+    "id, kind, name, roles, public_key, metadata, token_hash, created_at, updated_at";
+
+fn row_to_identity(row: &rusqlite::Row<'_>) -> rusqlite::Result<(Identity, Option<String>)> {
+    let roles: String = row.get(3)?;
+    let metadata: String = row.get(5)?;
+    let token_hash: Option<String> = row.get(6)?;
+    Ok((
+        Identity {
+            id: row.get(0)?,
+            kind: row.get(1)?,
+            name: row.get(2)?,
+            roles: serde_json::from_str(&roles).unwrap_or_default(),
+            public_key: row.get(4)?,
+            metadata: serde_json::from_str::<HashMap<String, String>>(&metadata)
+                .unwrap_or_default(),
+            created_at: row.get(7)?,
+            updated_at: row.get(8)?,
+            has_token: token_hash.is_some(),
+        },
+        token_hash,
+    ))
+}
+
+fn new_token() -> String {
+    format!(
+        "{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+fn token_hash(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Server interceptor rejecting calls made as an identity without its
+/// token, and recording whether each call proved the identity it names
+#[derive(Clone)]
+pub struct IdentityCheck {
+    registry: Arc<IdentityRegistry>,
+}
+
+impl IdentityCheck {
+    pub fn new(registry: Arc<IdentityRegistry>) -> Self {
+        Self { registry }
+    }
+}
+
+impl tonic::service::Interceptor for IdentityCheck {
+    fn call(
+        &mut self,
+        mut request: tonic::Request<()>,
+    ) -> Result<tonic::Request<()>, tonic::Status> {
+        let Some(id) = request.metadata().get(IDENTITY_HEADER) else {
+            return Ok(request);
+        };
+        let principal = id
+            .to_str()
+            .ok()
+            .and_then(Principal::parse)
+            .ok_or_else(|| tonic::Status::unauthenticated("Malformed x-aios-identity"))?;
+        let token = request
+            .metadata()
+            .get(TOKEN_HEADER)
+            .and_then(|v| v.to_str().ok());
+        let verified = self
+            .registry
+            .check(&principal.id(), token)
+            .map_err(|e| tonic::Status::unauthenticated(e.to_string()))?;
+        request.extensions_mut().insert(Caller {
+            principal,
+            verified,
+        });
+        Ok(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_issues_and_checks_tokens() {
+        let registry = IdentityRegistry::new(":memory:").unwrap();
+        let agent = Identity {
+            kind: "agent".into(),
+            name: "security-agent".into(),
+            roles: vec!["security".into()],
+            ..Default::default()
+        };
+        assert!(registry
+            .register(
+                &Identity {
+                    kind: "robot".into(),
+                    ..agent.clone()
+                },
+                false
+            )
+            .is_err());
+
+        let (registered, token) = registry.register(&agent, true).unwrap();
+        let token = token.unwrap();
+        assert_eq!(registered.id, "agent:security-agent");
+        assert!(registered.has_token);
+        assert!(registry.verify("agent:security-agent", &token).is_ok());
+        assert!(registry.verify("agent:security-agent", "guess").is_err());
+
+        // Re-registering keeps the token unless a new one is issued
+        let (updated, none) = registry.register(&agent, false).unwrap();
+        assert!(none.is_none() && updated.has_token);
+        assert_eq!(updated.created_at, registered.created_at);
+
+        // A token-holding identity must present its token; others pass
+        // unverified
+        assert!(registry
+            .check("agent:security-agent", Some(&token))
+            .unwrap());
+        assert!(registry.check("agent:security-agent", None).is_err());
+        assert!(!registry.check("user:alice", None).unwrap());
+        assert!(registry
+            .check("agent:security-agent", Some("guess"))
+            .is_err());
+        assert!(!registry.check("user:alice", Some(&token)).unwrap());
+
+        let user = Identity {
+            kind: "user".into(),
+            name: "alice".into(),
+            ..Default::default()
+        };
+        registry.register(&user, false).unwrap();
+        assert_eq!(registry.list("").unwrap().len(), 2);
+        assert_eq!(registry.list("user").unwrap()[0].id, "user:alice");
+
+        // Agents keep their names as keys; users cannot pass for one
+        let as_agent = Principal::parse("agent:security-agent").unwrap();
+        assert_eq!(as_agent.key(), "security-agent");
+        let as_user = Principal::parse("user:security-agent").unwrap();
+        assert_eq!(as_user.key(), "user:security-agent");
+        assert!(Principal::parse("security-agent").is_none());
+    }
+
+    /// The request as the interceptor passes it on; None when rejected
+    fn checked(
+        registry: &Arc<IdentityRegistry>,
+        id: &str,
+        token: Option<&str>,
+    ) -> Option<tonic::Request<()>> {
+        let mut request = tonic::Request::new(());
+        request
+            .metadata_mut()
+            .insert(IDENTITY_HEADER, id.parse().unwrap());
+        if let Some(token) = token {
+            request
+                .metadata_mut()
+                .insert(TOKEN_HEADER, token.parse().unwrap());
+        }
+        tonic::service::Interceptor::call(&mut IdentityCheck::new(registry.clone()), request).ok()
+    }
+
+    #[test]
+    fn test_only_verified_callers_act_as_their_identity() {
+        let registry = Arc::new(IdentityRegistry::new(":memory:").unwrap());
+        let agent = Identity {
+            kind: "agent".into(),
+            name: "security-agent".into(),
+            ..Default::default()
+        };
+        let (_, token) = registry.register(&agent, true).unwrap();
+        let token = token.unwrap();
+
+        let request = checked(&registry, "agent:security-agent", Some(&token)).unwrap();
+        assert_eq!(acting(&request), "security-agent");
+        assert!(checked(&registry, "agent:security-agent", None).is_none());
+
+        // Named but unproven identities match no policy
+        let request = checked(&registry, "agent:system-agent", None).unwrap();
+        assert_eq!(acting(&request), "unverified:agent:system-agent");
+        assert!(verified(&request).is_none());
+        assert_eq!(acting(&tonic::Request::new(())), "");
+    }
+
+    #[test]
+    fn test_reserved_identities_need_a_verified_registrar() {
+        let registry = IdentityRegistry::new(":memory:")
+            .unwrap()
+            .with_bootstrap_token("boot");
+        let node = Principal::parse("node:n1").unwrap();
+        let agent = Principal::parse("agent:security-agent").unwrap();
+        let alice = Principal::parse("user:alice").unwrap();
+
+        // Nobody squats a reserved name; the bootstrap token registers it
+        assert!(node.is_reserved_kind() && alice.is_reserved_kind());
+        assert!(!agent.is_reserved_kind());
+        assert!(registry
+            .authorize_registration(&node, true, None, None)
+            .is_err());
+        assert!(registry
+            .authorize_registration(&node, true, Some(&alice), Some("guess"))
+            .is_err());
+        assert!(registry
+            .authorize_registration(&node, true, None, Some("boot"))
+            .is_ok());
+        assert!(!registry.check("node:n1", Some("boot")).unwrap());
+
+        // A verified node registers reserved agents
+        assert!(registry
+            .authorize_registration(&agent, true, Some(&node), None)
+            .is_ok());
+        registry
+            .register(
+                &Identity {
+                    kind: "agent".into(),
+                    name: "security-agent".into(),
+                    ..Default::default()
+                },
+                true,
+            )
+            .unwrap();
+        // ...which then re-register only themselves
+        assert!(registry
+            .authorize_registration(&agent, true, Some(&agent), None)
+            .is_ok());
+        assert!(registry
+            .authorize_registration(&agent, true, Some(&alice), None)
+            .is_err());
+
+        // Unreserved names are first come, first served
+        assert!(registry
+            .authorize_registration(&alice, false, None, None)
+            .is_ok());
+    }
+}
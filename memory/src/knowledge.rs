@@ -1,86 +1,27 @@
 //! Knowledge Base — stores learned facts, documentation, procedures
 //!
-//! Hybrid search: keyword matching + simple vector embeddings stored in SQLite.
-//! Embeddings are lightweight bag-of-words TF vectors stored as BLOBs.
+//! Hybrid search: keyword matching + vector embeddings stored in SQLite as
+//! BLOBs. Vectors come from the configured embedding provider (see
+//! `embedding`).
 
 use anyhow::Result;
 use rusqlite::{params, Connection};
-use std::collections::HashMap;
 use std::sync::Mutex;
 
+use crate::embedding::{self, bytes_to_embedding, cosine_similarity, embedding_to_bytes};
 use crate::proto::memory::*;
-
-/// Generate a simple bag-of-words embedding vector for text
-/// Returns a normalized vector of word frequencies
-fn generate_embedding(text: &str) -> Vec<f32> {
-    let words: Vec<String> = text
-        .to_lowercase()
-        .split(|c: char| !c.is_alphanumeric())
-        .filter(|w| w.len() > 2)
-        .map(|w| w.to_string())
-        .collect();
-
-    if words.is_empty() {
-        return vec![0.0; 64];
-    }
-
-    // Hash each word into a fixed-size vector (dimension 64)
-    let dim = 64;
-    let mut vec = vec![0.0f32; dim];
-    let mut word_counts: HashMap<String, usize> = HashMap::new();
-
-    for word in &words {
-        *word_counts.entry(word.clone()).or_insert(0) += 1;
-    }
-
-    for (word, count) in &word_counts {
-        // Simple hash-based projection
-        let hash = word
-            .bytes()
-            .fold(0u64, |acc, b| acc.wrapping_mul(31).wrapping_add(b as u64));
-        let idx = (hash % dim as u64) as usize;
-        vec[idx] += *count as f32;
-        // Also fill a second bin for better distribution
-        let idx2 = ((hash >> 16) % dim as u64) as usize;
-        vec[idx2] += (*count as f32) * 0.5;
-    }
-
-    // L2 normalize
-    let norm: f32 = vec.iter().map(|v| v * v).sum::<f32>().sqrt();
-    if norm > 0.0 {
-        for v in &mut vec {
-            *v /= norm;
-        }
-    }
-
-    vec
-}
-
-/// Cosine similarity between two vectors
-fn cosine_similarity(a: &[f32], b: &[f32]) -> f64 {
-    if a.len() != b.len() || a.is_empty() {
-        return 0.0;
-    }
-    let dot: f32 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
-    let norm_a: f32 = a.iter().map(|v| v * v).sum::<f32>().sqrt();
-    let norm_b: f32 = b.iter().map(|v| v * v).sum::<f32>().sqrt();
-    if norm_a == 0.0 || norm_b == 0.0 {
-        return 0.0;
-    }
-    (dot / (norm_a * norm_b)) as f64
-}
-
-/// Serialize embedding to bytes
-fn embedding_to_bytes(embedding: &[f32]) -> Vec<u8> {
-    embedding.iter().flat_map(|f| f.to_le_bytes()).collect()
-}
-
-/// Deserialize embedding from bytes
-fn bytes_to_embedding(bytes: &[u8]) -> Vec<f32> {
-    bytes
-        .chunks_exact(4)
-        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
-        .collect()
+use crate::retention::{self, Category, SubjectColumns};
+use crate::retrieval;
+
+/// Columns a person or other subject may be mentioned in
+const SUBJECT_COLUMNS: &[SubjectColumns] = &[
+    ("knowledge", &["title", "content", "source", "tags"]),
+    ("knowledge_fts", &["body"]),
+];
+
+/// Text a knowledge entry's embedding is computed from
+pub fn entry_text(title: &str, content: &str, tags: &str) -> String {
+    format!("{title} {content} {tags}")
 }
 
 /// In-process knowledge base with SQLite storage and vector embeddings
@@ -104,16 +45,28 @@ impl KnowledgeBase {
             );
 
             CREATE INDEX IF NOT EXISTS idx_knowledge_title ON knowledge(title);
-            CREATE INDEX IF NOT EXISTS idx_knowledge_source ON knowledge(source);",
+            CREATE INDEX IF NOT EXISTS idx_knowledge_source ON knowledge(source);
+
+            CREATE VIRTUAL TABLE IF NOT EXISTS knowledge_fts USING fts5(
+                entry_id UNINDEXED,
+                body
+            );",
         )?;
+        embedding::init_collection_meta(&conn)?;
 
         Ok(Self {
             conn: Mutex::new(conn),
         })
     }
 
-    /// Add a knowledge entry with automatic embedding generation
-    pub fn add_entry(&mut self, entry: &KnowledgeEntry) -> Result<()> {
+    /// Add a knowledge entry with its embedding from `provider`. Without one
+    /// the entry is found by keyword only.
+    pub fn add_entry(
+        &mut self,
+        entry: &KnowledgeEntry,
+        provider: &str,
+        embedding: Option<&[f32]>,
+    ) -> Result<()> {
         let conn = self
             .conn
             .lock()
@@ -121,28 +74,84 @@ impl KnowledgeBase {
         let tags = entry.tags.join(",");
         let now = chrono::Utc::now().timestamp();
 
-        // Generate embedding from title + content + tags
-        let full_text = format!("{} {} {}", entry.title, entry.content, tags);
-        let embedding = generate_embedding(&full_text);
-        let embedding_bytes = embedding_to_bytes(&embedding);
+        let embedding_bytes = embedding.map(embedding_to_bytes);
 
         conn.execute(
             "INSERT INTO knowledge (title, content, source, tags, embedding, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
             params![entry.title, entry.content, entry.source, tags, embedding_bytes, now],
         )?;
+        conn.execute(
+            "INSERT INTO knowledge_fts (entry_id, body) VALUES (?1, ?2)",
+            params![
+                conn.last_insert_rowid(),
+                entry_text(&entry.title, &entry.content, &tags)
+            ],
+        )?;
+        if let Some(vector) = embedding {
+            embedding::set_collection_meta(&conn, "knowledge", provider, vector.len())?;
+        }
 
         Ok(())
     }
 
-    /// Hybrid search: combines keyword relevance with vector similarity
-    pub fn search(&self, query: &str, n_results: i32) -> Result<Vec<SearchResult>> {
+    /// Full-text (BM25) search, best match first
+    pub fn fts_search(&self, query: &str, n_results: i32) -> Result<Vec<SearchResult>> {
+        let Some(expression) = retrieval::fts_query(query) else {
+            return Ok(Vec::new());
+        };
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let limit = if n_results <= 0 { 10 } else { n_results };
+
+        let mut stmt = conn.prepare(
+            "SELECT k.rowid, k.title, k.content, k.source, k.tags, bm25(knowledge_fts)
+             FROM knowledge_fts JOIN knowledge k ON k.rowid = knowledge_fts.entry_id
+             WHERE knowledge_fts MATCH ?1 ORDER BY bm25(knowledge_fts) LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![expression, limit], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, Option<String>>(4)?.unwrap_or_default(),
+                row.get::<_, f64>(5)?,
+            ))
+        })?;
+        let mut results = Vec::new();
+        for row in rows {
+            let (id, title, content, source, tags, score) = row?;
+            results.push(SearchResult {
+                id: id.to_string(),
+                content: format!("[{source}] {title}: {content}"),
+                metadata_json: serde_json::to_vec(&serde_json::json!({
+                    "source": source,
+                    "tags": tags,
+                }))
+                .unwrap_or_default(),
+                relevance: retrieval::bm25_relevance(score),
+                collection: "knowledge".into(),
+            });
+        }
+        Ok(results)
+    }
+
+    /// Hybrid search: combines keyword relevance with vector similarity.
+    /// An empty `query_embedding` scores by keyword only.
+    pub fn search(
+        &self,
+        query: &str,
+        query_embedding: &[f32],
+        n_results: i32,
+    ) -> Result<Vec<SearchResult>> {
         let conn = self
             .conn
             .lock()
             .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
         let limit = if n_results <= 0 { 10 } else { n_results };
         let keywords: Vec<&str> = query.split_whitespace().collect();
-        let query_embedding = generate_embedding(query);
 
         let mut stmt = conn.prepare(
             "SELECT rowid, title, content, source, tags, embedding FROM knowledge ORDER BY created_at DESC LIMIT ?1",
@@ -162,7 +171,7 @@ impl KnowledgeBase {
 
         for row in rows {
             let (id, title, content, source, tags, embedding_bytes) = row?;
-            let full_text = format!("{title} {content} {tags}");
+            let full_text = entry_text(&title, &content, &tags);
 
             // Keyword score
             let keyword_score = keyword_relevance(&keywords, &full_text);
@@ -170,7 +179,7 @@ impl KnowledgeBase {
             // Vector similarity score
             let vector_score = if let Some(ref bytes) = embedding_bytes {
                 let stored_embedding = bytes_to_embedding(bytes);
-                cosine_similarity(&query_embedding, &stored_embedding)
+                cosine_similarity(query_embedding, &stored_embedding)
             } else {
                 0.0
             };
@@ -202,6 +211,70 @@ impl KnowledgeBase {
 
         Ok(results)
     }
+
+    /// Delete the entries of `category` older than `before` (Unix seconds):
+    /// those tagged `email`, those scraped from the web (tagged `web` or
+    /// with a URL source), or all of them
+    pub fn expire(&self, category: Category, before: i64) -> Result<usize> {
+        let tagged = |tag: &str| {
+            format!(
+                "instr(',' || replace(lower(COALESCE(tags, '')), ' ', '') || ',', ',{tag},') > 0"
+            )
+        };
+        let filter = match category {
+            Category::Email => tagged("email"),
+            Category::Web => format!("({} OR source LIKE 'http%')", tagged("web")),
+            Category::Knowledge => "1".to_string(),
+            _ => return Ok(0),
+        };
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let removed = conn.execute(
+            &format!("DELETE FROM knowledge WHERE {filter} AND created_at < ?1"),
+            params![before],
+        )?;
+        if removed > 0 {
+            drop_orphaned_fts(&conn)?;
+        }
+        Ok(removed)
+    }
+
+    /// Delete every entry mentioning `pattern` (normalized, see
+    /// [`crate::retention`]); rows removed per table
+    pub fn erase_subject(&self, pattern: &str) -> Result<Vec<(String, usize)>> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let mut removed = retention::erase_rows(&conn, SUBJECT_COLUMNS, pattern)?;
+        let orphaned = drop_orphaned_fts(&conn)?;
+        if let Some((_, n)) = removed
+            .iter_mut()
+            .find(|(table, _)| table == "knowledge_fts")
+        {
+            *n += orphaned;
+        }
+        Ok(removed)
+    }
+
+    /// Entries still mentioning `pattern`
+    pub fn count_subject(&self, pattern: &str) -> Result<usize> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        retention::count_rows(&conn, SUBJECT_COLUMNS, pattern)
+    }
+}
+
+/// Remove index entries whose knowledge entry no longer exists
+fn drop_orphaned_fts(conn: &Connection) -> Result<usize> {
+    Ok(conn.execute(
+        "DELETE FROM knowledge_fts WHERE entry_id NOT IN (SELECT id FROM knowledge)",
+        [],
+    )?)
 }
 
 fn keyword_relevance(keywords: &[&str], text: &str) -> f64 {
@@ -219,11 +292,29 @@ fn keyword_relevance(keywords: &[&str], text: &str) -> f64 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::embedding::hash_embedding;
+
+    impl KnowledgeBase {
+        /// Add with the built-in hashed embedding
+        fn add_hashed(&mut self, entry: &KnowledgeEntry) -> Result<()> {
+            let text = entry_text(&entry.title, &entry.content, &entry.tags.join(","));
+            self.add_entry(
+                entry,
+                embedding::HASH_PROVIDER,
+                Some(&hash_embedding(&text)),
+            )
+        }
+
+        /// Search with the built-in hashed query embedding
+        fn search_hashed(&self, query: &str, n_results: i32) -> Result<Vec<SearchResult>> {
+            self.search(query, &hash_embedding(query), n_results)
+        }
+    }
 
     #[test]
     fn test_add_and_search() {
         let mut kb = KnowledgeBase::new().unwrap();
-        kb.add_entry(&KnowledgeEntry {
+        kb.add_hashed(&KnowledgeEntry {
             title: "Nginx Configuration".into(),
             content: "Nginx serves HTTP traffic on port 80 and HTTPS on 443".into(),
             source: "man page".into(),
@@ -231,7 +322,7 @@ mod tests {
         })
         .unwrap();
 
-        kb.add_entry(&KnowledgeEntry {
+        kb.add_hashed(&KnowledgeEntry {
             title: "Firewall Rules".into(),
             content: "nftables manages firewall rules for packet filtering".into(),
             source: "docs".into(),
@@ -239,11 +330,11 @@ mod tests {
         })
         .unwrap();
 
-        let results = kb.search("nginx http", 10).unwrap();
+        let results = kb.search_hashed("nginx http", 10).unwrap();
         assert!(!results.is_empty());
         assert!(results[0].content.contains("Nginx"));
 
-        let results = kb.search("firewall", 10).unwrap();
+        let results = kb.search_hashed("firewall", 10).unwrap();
         assert!(!results.is_empty());
         assert!(results[0].content.contains("nftables"));
     }
@@ -251,14 +342,14 @@ mod tests {
     #[test]
     fn test_search_no_results() {
         let kb = KnowledgeBase::new().unwrap();
-        let results = kb.search("nonexistent_xyz", 10).unwrap();
+        let results = kb.search_hashed("nonexistent_xyz", 10).unwrap();
         assert!(results.is_empty());
     }
 
     #[test]
     fn test_search_empty_database() {
         let kb = KnowledgeBase::new().unwrap();
-        let results = kb.search("anything", 10).unwrap();
+        let results = kb.search_hashed("anything", 10).unwrap();
         assert!(results.is_empty());
     }
 
@@ -266,7 +357,7 @@ mod tests {
     fn test_search_default_limit() {
         let kb = KnowledgeBase::new().unwrap();
         // n_results=0 should default to 10
-        let results = kb.search("anything", 0).unwrap();
+        let results = kb.search_hashed("anything", 0).unwrap();
         assert!(results.is_empty());
     }
 
@@ -274,7 +365,7 @@ mod tests {
     fn test_search_result_limit() {
         let mut kb = KnowledgeBase::new().unwrap();
         for i in 0..10 {
-            kb.add_entry(&KnowledgeEntry {
+            kb.add_hashed(&KnowledgeEntry {
                 title: format!("Topic {i}"),
                 content: format!("This is about topic {i} with keyword searchable"),
                 source: "docs".into(),
@@ -283,14 +374,14 @@ mod tests {
             .unwrap();
         }
 
-        let results = kb.search("searchable topic", 3).unwrap();
+        let results = kb.search_hashed("searchable topic", 3).unwrap();
         assert!(results.len() <= 3);
     }
 
     #[test]
     fn test_search_by_tags() {
         let mut kb = KnowledgeBase::new().unwrap();
-        kb.add_entry(&KnowledgeEntry {
+        kb.add_hashed(&KnowledgeEntry {
             title: "Kubernetes".into(),
             content: "Container orchestration platform".into(),
             source: "docs".into(),
@@ -299,14 +390,14 @@ mod tests {
         .unwrap();
 
         // Search by tag content
-        let results = kb.search("k8s", 10).unwrap();
+        let results = kb.search_hashed("k8s", 10).unwrap();
         assert!(!results.is_empty());
     }
 
     #[test]
     fn test_search_results_sorted_by_relevance() {
         let mut kb = KnowledgeBase::new().unwrap();
-        kb.add_entry(&KnowledgeEntry {
+        kb.add_hashed(&KnowledgeEntry {
             title: "Nginx HTTP Server".into(),
             content: "Nginx serves HTTP traffic and handles reverse proxy".into(),
             source: "docs".into(),
@@ -314,7 +405,7 @@ mod tests {
         })
         .unwrap();
 
-        kb.add_entry(&KnowledgeEntry {
+        kb.add_hashed(&KnowledgeEntry {
             title: "Docker".into(),
             content: "Docker is a containerization platform for nginx and other services".into(),
             source: "docs".into(),
@@ -322,7 +413,7 @@ mod tests {
         })
         .unwrap();
 
-        let results = kb.search("nginx http", 10).unwrap();
+        let results = kb.search_hashed("nginx http", 10).unwrap();
         // Results should be sorted by relevance descending
         if results.len() >= 2 {
             assert!(results[0].relevance >= results[1].relevance);
@@ -332,7 +423,7 @@ mod tests {
     #[test]
     fn test_search_result_metadata() {
         let mut kb = KnowledgeBase::new().unwrap();
-        kb.add_entry(&KnowledgeEntry {
+        kb.add_hashed(&KnowledgeEntry {
             title: "Test Entry".into(),
             content: "Some content for testing".into(),
             source: "manual".into(),
@@ -340,7 +431,7 @@ mod tests {
         })
         .unwrap();
 
-        let results = kb.search("test", 10).unwrap();
+        let results = kb.search_hashed("test", 10).unwrap();
         assert!(!results.is_empty());
         assert_eq!(results[0].collection, "knowledge");
         assert!(results[0].content.contains("manual")); // Source embedded in content
@@ -370,7 +461,7 @@ mod tests {
     fn test_add_multiple_entries() {
         let mut kb = KnowledgeBase::new().unwrap();
         for i in 0..20 {
-            kb.add_entry(&KnowledgeEntry {
+            kb.add_hashed(&KnowledgeEntry {
                 title: format!("Entry {i}"),
                 content: format!("Content for entry number {i}"),
                 source: "batch".into(),
@@ -379,14 +470,14 @@ mod tests {
             .unwrap();
         }
 
-        let results = kb.search("entry content", 100).unwrap();
+        let results = kb.search_hashed("entry content", 100).unwrap();
         assert_eq!(results.len(), 20);
     }
 
     #[test]
     fn test_search_source_filtering_in_content() {
         let mut kb = KnowledgeBase::new().unwrap();
-        kb.add_entry(&KnowledgeEntry {
+        kb.add_hashed(&KnowledgeEntry {
             title: "API Docs".into(),
             content: "REST API documentation for the service".into(),
             source: "swagger".into(),
@@ -394,9 +485,36 @@ mod tests {
         })
         .unwrap();
 
-        let results = kb.search("API", 10).unwrap();
+        let results = kb.search_hashed("API", 10).unwrap();
         assert!(!results.is_empty());
         // Content format is "[source] title: content"
         assert!(results[0].content.contains("[swagger]"));
     }
+
+    #[test]
+    fn test_fts_search() {
+        let mut kb = KnowledgeBase::new().unwrap();
+        kb.add_hashed(&KnowledgeEntry {
+            title: "Nginx ports".into(),
+            content: "Nginx listens on port 80".into(),
+            source: "docs".into(),
+            tags: vec!["web".into()],
+        })
+        .unwrap();
+        kb.add_hashed(&KnowledgeEntry {
+            title: "Firewall".into(),
+            content: "Rules live in nftables".into(),
+            source: "docs".into(),
+            tags: vec![],
+        })
+        .unwrap();
+
+        let results = kb.fts_search("which port does nginx use", 10).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(
+            results[0].content,
+            "[docs] Nginx ports: Nginx listens on port 80"
+        );
+        assert_eq!(kb.fts_search("web", 10).unwrap().len(), 1);
+    }
 }
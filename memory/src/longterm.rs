@@ -2,81 +2,63 @@
 //!
 //! Stores procedures, incidents, config changes.
 //! Provides hybrid search combining keyword matching and vector similarity.
+//! Vectors are computed by the configured embedding provider before they
+//! reach this module (see `embedding`); procedure vectors are also kept in
+//! an HNSW index (see `vector_index`) so search does not scan them all.
 
 use anyhow::Result;
-use rusqlite::{params, Connection};
-use std::collections::HashMap;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
+use tracing::{info, warn};
 
+use crate::embedding::{self, bytes_to_embedding, cosine_similarity, embedding_to_bytes};
 use crate::proto::memory::*;
+use crate::retention::{self, Category, SubjectColumns};
+use crate::retrieval;
+use crate::vector_index::VectorIndex;
 
-/// Generate a simple bag-of-words embedding vector
-fn generate_embedding(text: &str) -> Vec<f32> {
-    let words: Vec<String> = text
-        .to_lowercase()
-        .split(|c: char| !c.is_alphanumeric())
-        .filter(|w| w.len() > 2)
-        .map(|w| w.to_string())
-        .collect();
+/// Nearest procedures fetched from the vector index per requested result
+const ANN_CANDIDATES_PER_RESULT: usize = 4;
 
-    let dim = 64;
-    let mut vec = vec![0.0f32; dim];
-    if words.is_empty() {
-        return vec;
-    }
-
-    let mut word_counts: HashMap<String, usize> = HashMap::new();
-    for word in &words {
-        *word_counts.entry(word.clone()).or_insert(0) += 1;
-    }
-
-    for (word, count) in &word_counts {
-        let hash = word
-            .bytes()
-            .fold(0u64, |acc, b| acc.wrapping_mul(31).wrapping_add(b as u64));
-        let idx = (hash % dim as u64) as usize;
-        vec[idx] += *count as f32;
-        let idx2 = ((hash >> 16) % dim as u64) as usize;
-        vec[idx2] += (*count as f32) * 0.5;
-    }
+/// Columns of each table a person or other subject may be mentioned in
+const SUBJECT_COLUMNS: &[SubjectColumns] = &[
+    ("procedures", &["name", "description", "steps_json", "tags"]),
+    (
+        "incidents",
+        &[
+            "description",
+            "symptoms_json",
+            "root_cause",
+            "resolution",
+            "prevention",
+        ],
+    ),
+    ("config_changes", &["file_path", "content", "reason"]),
+    ("longterm_fts", &["content", "body"]),
+];
 
-    let norm: f32 = vec.iter().map(|v| v * v).sum::<f32>().sqrt();
-    if norm > 0.0 {
-        for v in &mut vec {
-            *v /= norm;
-        }
-    }
-    vec
+/// Text a procedure's embedding is computed from
+pub fn procedure_text(name: &str, description: &str, tags: &str) -> String {
+    format!("{name} {description} {tags}")
 }
 
-/// Cosine similarity between two vectors
-fn cosine_similarity(a: &[f32], b: &[f32]) -> f64 {
-    if a.len() != b.len() || a.is_empty() {
-        return 0.0;
-    }
-    let dot: f32 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
-    let norm_a: f32 = a.iter().map(|v| v * v).sum::<f32>().sqrt();
-    let norm_b: f32 = b.iter().map(|v| v * v).sum::<f32>().sqrt();
-    if norm_a == 0.0 || norm_b == 0.0 {
-        return 0.0;
-    }
-    (dot / (norm_a * norm_b)) as f64
-}
-
-fn embedding_to_bytes(embedding: &[f32]) -> Vec<u8> {
-    embedding.iter().flat_map(|f| f.to_le_bytes()).collect()
-}
-
-fn bytes_to_embedding(bytes: &[u8]) -> Vec<f32> {
-    bytes
-        .chunks_exact(4)
-        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
-        .collect()
+/// Size and shape of the procedure vector index
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexStats {
+    pub entries: usize,
+    pub dimensions: usize,
+    pub generation: i64,
 }
 
 /// Long-term memory with SQLite storage and vector embeddings
 pub struct LongTermMemory {
     conn: Mutex<Connection>,
+    /// Procedure vectors; lock after `conn` when both are needed
+    index: Mutex<VectorIndex>,
+    /// Where the index is persisted; `None` for in-memory databases
+    index_path: Option<PathBuf>,
 }
 
 impl LongTermMemory {
@@ -128,15 +110,59 @@ impl LongTermMemory {
             CREATE INDEX IF NOT EXISTS idx_config_path ON config_changes(file_path);",
         )?;
 
+        // Databases created before embedding providers were recorded lack
+        // the column; their vectors came from the built-in hashing
+        let has_provider: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('procedures') WHERE name = 'embedding_provider'",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_provider {
+            conn.execute_batch("ALTER TABLE procedures ADD COLUMN embedding_provider TEXT")?;
+        }
+        embedding::init_collection_meta(&conn)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS vector_index_state (
+                collection TEXT PRIMARY KEY,
+                generation INTEGER NOT NULL
+            );",
+        )?;
+
+        // Full-text index over every collection: `content` is what search
+        // results show, `body` is what is matched
+        conn.execute_batch(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS longterm_fts USING fts5(
+                collection UNINDEXED,
+                record_id UNINDEXED,
+                content UNINDEXED,
+                body
+            );",
+        )?;
+        let indexed: i64 =
+            conn.query_row("SELECT COUNT(*) FROM longterm_fts", [], |row| row.get(0))?;
+        if indexed == 0 {
+            for collection in crate::dump::LONGTERM_COLLECTIONS {
+                reindex_fts(&conn, collection)?;
+            }
+        }
+
+        let index_path = (db_path != ":memory:").then(|| PathBuf::from(format!("{db_path}.hnsw")));
+        let index = open_index(&conn, index_path.as_deref())?;
+
         Ok(Self {
             conn: Mutex::new(conn),
+            index: Mutex::new(index),
+            index_path,
         })
     }
 
-    /// Hybrid keyword + vector search across collections
+    /// Hybrid keyword + vector search across collections. `query_embedding`
+    /// comes from the current provider; stored vectors of another size only
+    /// contribute their keyword score.
     pub fn semantic_search(
         &self,
         query: &str,
+        query_embedding: &[f32],
         collections: &[String],
         n_results: i32,
         min_relevance: f64,
@@ -148,7 +174,6 @@ impl LongTermMemory {
         let mut results = Vec::new();
         let limit = if n_results <= 0 { 10 } else { n_results };
         let keywords: Vec<&str> = query.split_whitespace().collect();
-        let query_embedding = generate_embedding(query);
 
         let collections_to_search = if collections.is_empty() {
             vec![
@@ -163,23 +188,43 @@ impl LongTermMemory {
         for collection in &collections_to_search {
             match collection.as_str() {
                 "procedures" | "decisions" => {
+                    // The most recently used procedures (which may lack a
+                    // vector) plus the nearest ones from the vector index
                     let mut stmt = conn.prepare(
                         "SELECT id, name, description, embedding FROM procedures ORDER BY last_used DESC LIMIT ?1",
                     )?;
-                    let rows = stmt.query_map(params![limit], |row| {
+                    let read = |row: &rusqlite::Row<'_>| {
                         Ok((
                             row.get::<_, String>(0)?,
                             row.get::<_, String>(1)?,
                             row.get::<_, String>(2)?,
                             row.get::<_, Option<Vec<u8>>>(3)?,
                         ))
-                    })?;
-                    for row in rows {
-                        let (id, name, description, embedding_bytes) = row?;
+                    };
+                    let mut rows = stmt
+                        .query_map(params![limit], read)?
+                        .collect::<rusqlite::Result<Vec<_>>>()?;
+                    let mut seen: HashSet<String> = rows.iter().map(|r| r.0.clone()).collect();
+                    let nearest = self
+                        .index
+                        .lock()
+                        .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?
+                        .search(query_embedding, limit as usize * ANN_CANDIDATES_PER_RESULT);
+                    let mut by_id = conn.prepare(
+                        "SELECT id, name, description, embedding FROM procedures WHERE id = ?1",
+                    )?;
+                    for (id, _) in nearest {
+                        if seen.insert(id.clone()) {
+                            if let Some(row) = by_id.query_map(params![id], read)?.next() {
+                                rows.push(row?);
+                            }
+                        }
+                    }
+                    for (id, name, description, embedding_bytes) in rows {
                         let content = format!("{name}: {description}");
                         let kw_score = keyword_relevance(&keywords, &content);
                         let vec_score = if let Some(ref bytes) = embedding_bytes {
-                            cosine_similarity(&query_embedding, &bytes_to_embedding(bytes))
+                            cosine_similarity(query_embedding, &bytes_to_embedding(bytes))
                         } else {
                             0.0
                         };
@@ -263,21 +308,81 @@ impl LongTermMemory {
         Ok(results)
     }
 
-    pub fn store_procedure(&self, procedure: &Procedure) -> Result<()> {
+    /// Full-text (BM25) search across collections, best match first
+    pub fn fts_search(
+        &self,
+        query: &str,
+        collections: &[String],
+        n_results: i32,
+    ) -> Result<Vec<SearchResult>> {
+        let Some(expression) = retrieval::fts_query(query) else {
+            return Ok(Vec::new());
+        };
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let limit = if n_results <= 0 { 10 } else { n_results };
+        // "decisions" are stored as procedures, as in semantic_search
+        let wanted: Vec<&str> = collections
+            .iter()
+            .map(|c| match c.as_str() {
+                "decisions" => "procedures",
+                other => other,
+            })
+            .collect();
+
+        let mut stmt = conn.prepare(
+            "SELECT collection, record_id, content, bm25(longterm_fts) FROM longterm_fts
+             WHERE longterm_fts MATCH ?1 ORDER BY bm25(longterm_fts) LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![expression, limit * 4], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, f64>(3)?,
+            ))
+        })?;
+        let mut results = Vec::new();
+        for row in rows {
+            let (collection, id, content, score) = row?;
+            if !wanted.is_empty() && !wanted.contains(&collection.as_str()) {
+                continue;
+            }
+            results.push(SearchResult {
+                id,
+                content,
+                metadata_json: vec![],
+                relevance: retrieval::bm25_relevance(score),
+                collection,
+            });
+            if results.len() >= limit as usize {
+                break;
+            }
+        }
+        Ok(results)
+    }
+
+    /// Store a procedure with its embedding from `provider`. Without one
+    /// (the provider was unavailable), the procedure is embedded on the
+    /// next re-embedding pass.
+    pub fn store_procedure(
+        &self,
+        procedure: &Procedure,
+        provider: &str,
+        embedding: Option<&[f32]>,
+    ) -> Result<()> {
         let conn = self
             .conn
             .lock()
             .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
         let tags = procedure.tags.join(",");
-
-        // Generate embedding from name + description + tags
-        let full_text = format!("{} {} {}", procedure.name, procedure.description, tags);
-        let embedding = generate_embedding(&full_text);
-        let embedding_bytes = embedding_to_bytes(&embedding);
+        let embedding_bytes = embedding.map(embedding_to_bytes);
 
         conn.execute(
-            "INSERT OR REPLACE INTO procedures (id, name, description, steps_json, success_count, fail_count, avg_duration_ms, tags, embedding, created_at, last_used)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            "INSERT OR REPLACE INTO procedures (id, name, description, steps_json, success_count, fail_count, avg_duration_ms, tags, embedding, embedding_provider, created_at, last_used)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
             params![
                 procedure.id,
                 procedure.name,
@@ -288,13 +393,148 @@ impl LongTermMemory {
                 procedure.avg_duration_ms,
                 tags,
                 embedding_bytes,
+                embedding.map(|_| provider),
                 procedure.created_at,
                 procedure.last_used,
             ],
         )?;
+        index_fts(
+            &conn,
+            "procedures",
+            &procedure.id,
+            &format!("{}: {}", procedure.name, procedure.description),
+            &format!("{} {} {}", procedure.name, procedure.description, tags),
+        )?;
+        if let Some(vector) = embedding {
+            embedding::set_collection_meta(&conn, "procedures", provider, vector.len())?;
+        }
+        self.index_vector(&conn, &procedure.id, embedding)
+    }
+
+    /// Procedures whose vector is missing or was not produced by `provider`,
+    /// with the text to embed
+    pub fn stale_procedures(&self, provider: &str, limit: usize) -> Result<Vec<(String, String)>> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let mut stmt = conn.prepare(
+            "SELECT id, name, description, tags FROM procedures
+             WHERE embedding IS NULL OR COALESCE(embedding_provider, ?1) != ?2
+             LIMIT ?3",
+        )?;
+        let rows = stmt.query_map(
+            params![embedding::HASH_PROVIDER, provider, limit as i64],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    procedure_text(
+                        &row.get::<_, String>(1)?,
+                        &row.get::<_, String>(2)?,
+                        &row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+                    ),
+                ))
+            },
+        )?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// Replace a procedure's vector after re-embedding
+    pub fn set_procedure_embedding(
+        &self,
+        id: &str,
+        provider: &str,
+        embedding: &[f32],
+    ) -> Result<()> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        conn.execute(
+            "UPDATE procedures SET embedding = ?1, embedding_provider = ?2 WHERE id = ?3",
+            params![embedding_to_bytes(embedding), provider, id],
+        )?;
+        embedding::set_collection_meta(&conn, "procedures", provider, embedding.len())?;
+        self.index_vector(&conn, id, Some(embedding))
+    }
+
+    /// Insert (or, without a vector, drop) a procedure in the vector index.
+    /// A vector of a new size means the provider changed: the index is
+    /// rebuilt for it from whatever already has that size.
+    fn index_vector(&self, conn: &Connection, id: &str, vector: Option<&[f32]>) -> Result<()> {
+        let generation = bump_index_generation(conn)?;
+        let mut index = self
+            .index
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        match vector {
+            Some(vector) if vector.len() != index.dimensions() => {
+                *index = build_index(conn, vector.len(), generation)?;
+            }
+            Some(vector) => index.insert(id, vector)?,
+            None => {
+                index.remove(id);
+            }
+        }
+        index.set_generation(generation);
         Ok(())
     }
 
+    /// Rebuild the procedure vector index from stored vectors and persist it
+    pub fn rebuild_index(&self) -> Result<IndexStats> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let dimensions = embedding::collection_meta(&conn, "procedures")?
+            .map(|(_, d)| d)
+            .unwrap_or(0);
+        let generation = index_generation(&conn)?;
+        let mut index = self
+            .index
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        *index = build_index(&conn, dimensions, generation)?;
+        if let Some(path) = &self.index_path {
+            index.save(path)?;
+        }
+        Ok(stats(&index))
+    }
+
+    /// Persist the vector index if it changed since it was last written;
+    /// true if it was written
+    pub fn save_index(&self) -> Result<bool> {
+        let Some(path) = &self.index_path else {
+            return Ok(false);
+        };
+        let mut index = self
+            .index
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        if !index.is_dirty() {
+            return Ok(false);
+        }
+        index.save(path)?;
+        Ok(true)
+    }
+
+    pub fn index_stats(&self) -> Result<IndexStats> {
+        let index = self
+            .index
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        Ok(stats(&index))
+    }
+
+    /// Provider and dimensionality of a collection's vectors
+    pub fn collection_embedding(&self, collection: &str) -> Result<Option<(String, usize)>> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        embedding::collection_meta(&conn, collection)
+    }
+
     pub fn store_incident(&self, incident: &Incident) -> Result<()> {
         let conn = self
             .conn
@@ -314,9 +554,48 @@ impl LongTermMemory {
                 incident.timestamp,
             ],
         )?;
+        index_fts(
+            &conn,
+            "incidents",
+            &incident.id,
+            &format!(
+                "{} | Cause: {} | Resolution: {}",
+                incident.description, incident.root_cause, incident.resolution
+            ),
+            &format!(
+                "{} {} {} {}",
+                incident.description, incident.root_cause, incident.resolution, incident.prevention
+            ),
+        )?;
         Ok(())
     }
 
+    /// Incidents recorded at or after `since`, most recent first
+    pub fn list_incidents(&self, since: i64, limit: i32) -> Result<Vec<Incident>> {
+        let limit = if limit > 0 { limit } else { 100 };
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let mut stmt = conn.prepare(
+            "SELECT id, description, symptoms_json, root_cause, resolution, resolved_by, prevention, timestamp
+             FROM incidents WHERE timestamp >= ?1 ORDER BY timestamp DESC LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![since, limit], |row| {
+            Ok(Incident {
+                id: row.get(0)?,
+                description: row.get(1)?,
+                symptoms_json: row.get::<_, Option<Vec<u8>>>(2)?.unwrap_or_default(),
+                root_cause: row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+                resolution: row.get::<_, Option<String>>(4)?.unwrap_or_default(),
+                resolved_by: row.get::<_, Option<String>>(5)?.unwrap_or_default(),
+                prevention: row.get::<_, Option<String>>(6)?.unwrap_or_default(),
+                timestamp: row.get(7)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
     pub fn store_config_change(&self, change: &ConfigChange) -> Result<()> {
         let conn = self
             .conn
@@ -334,10 +613,277 @@ impl LongTermMemory {
                 change.timestamp,
             ],
         )?;
+        index_fts(
+            &conn,
+            "config_changes",
+            &change.id,
+            &format!("{}: {}", change.file_path, change.reason),
+            &format!("{} {}", change.file_path, change.reason),
+        )?;
+        Ok(())
+    }
+
+    /// Delete the incidents or config changes older than `before` (Unix
+    /// seconds) with their full-text index entries
+    pub fn expire(&self, category: Category, before: i64) -> Result<usize> {
+        let table = match category {
+            Category::Incidents => "incidents",
+            Category::ConfigChanges => "config_changes",
+            _ => return Ok(0),
+        };
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let removed = conn.execute(
+            &format!("DELETE FROM {table} WHERE timestamp < ?1"),
+            params![before],
+        )?;
+        if removed > 0 {
+            drop_orphaned_fts(&conn)?;
+        }
+        Ok(removed)
+    }
+
+    /// Delete every record mentioning `pattern` (normalized, see
+    /// [`crate::retention`]); rows removed per table
+    pub fn erase_subject(&self, pattern: &str) -> Result<Vec<(String, usize)>> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let mut removed = retention::erase_rows(&conn, SUBJECT_COLUMNS, pattern)?;
+        if removed
+            .iter()
+            .any(|(table, n)| table == "procedures" && *n > 0)
+        {
+            self.drop_orphaned_vectors(&conn)?;
+        }
+        let orphaned = drop_orphaned_fts(&conn)?;
+        if let Some((_, n)) = removed
+            .iter_mut()
+            .find(|(table, _)| table == "longterm_fts")
+        {
+            *n += orphaned;
+        }
+        retention::truncate_wal(&conn)?;
+        Ok(removed)
+    }
+
+    /// Records still mentioning `pattern`
+    pub fn count_subject(&self, pattern: &str) -> Result<usize> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        retention::count_rows(&conn, SUBJECT_COLUMNS, pattern)
+    }
+
+    /// Every row of a long-term memory table (see [`crate::dump`])
+    pub fn dump(&self, collection: &str) -> Result<Vec<serde_json::Value>> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        crate::dump::dump_table(&conn, collection)
+    }
+
+    /// Insert or replace dumped rows into a long-term memory table and
+    /// rebuild its full-text index
+    pub fn restore(&self, collection: &str, records: &[serde_json::Value]) -> Result<usize> {
+        let mut conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let restored = crate::dump::restore_table(&mut conn, collection, records)?;
+        reindex_fts(&conn, collection)?;
+        if collection == "procedures" {
+            let dimensions = embedding::collection_meta(&conn, "procedures")?
+                .map(|(_, d)| d)
+                .unwrap_or(0);
+            let generation = bump_index_generation(&conn)?;
+            *self
+                .index
+                .lock()
+                .map_err(|e| anyhow::anyhow!("Lock error: {e}"))? =
+                build_index(&conn, dimensions, generation)?;
+        }
+        Ok(restored)
+    }
+
+    /// Remove index entries whose procedure no longer exists
+    fn drop_orphaned_vectors(&self, conn: &Connection) -> Result<()> {
+        let mut stmt = conn.prepare("SELECT id FROM procedures")?;
+        let existing = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<HashSet<_>>>()?;
+        let generation = bump_index_generation(conn)?;
+        let mut index = self
+            .index
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let orphaned: Vec<String> = index
+            .ids()
+            .filter(|id| !existing.contains(*id))
+            .map(str::to_string)
+            .collect();
+        for id in orphaned {
+            index.remove(&id);
+        }
+        index.set_generation(generation);
         Ok(())
     }
 }
 
+/// Load the persisted procedure index, or rebuild it when missing, corrupt
+/// or saved before the latest vector change
+fn open_index(conn: &Connection, path: Option<&Path>) -> Result<VectorIndex> {
+    let dimensions = embedding::collection_meta(conn, "procedures")?
+        .map(|(_, d)| d)
+        .unwrap_or(0);
+    let generation = index_generation(conn)?;
+    if let Some(path) = path.filter(|p| p.exists()) {
+        match VectorIndex::load(path) {
+            Ok(index) if index.generation() == generation && index.dimensions() == dimensions => {
+                return Ok(index);
+            }
+            Ok(_) => info!("Vector index {} is out of date, rebuilding", path.display()),
+            Err(e) => warn!("Cannot load vector index, rebuilding: {e}"),
+        }
+    }
+    let mut index = build_index(conn, dimensions, generation)?;
+    if let Some(path) = path {
+        if index.len() > 0 {
+            info!("Built vector index over {} procedures", index.len());
+        }
+        if let Err(e) = index.save(path) {
+            warn!("Cannot save vector index: {e}");
+        }
+    }
+    Ok(index)
+}
+
+/// Index every stored procedure vector of `dimensions` size
+fn build_index(conn: &Connection, dimensions: usize, generation: i64) -> Result<VectorIndex> {
+    if dimensions == 0 {
+        let mut index = VectorIndex::new(0);
+        index.set_generation(generation);
+        return Ok(index);
+    }
+    let mut stmt = conn.prepare(
+        "SELECT id, embedding FROM procedures
+         WHERE embedding IS NOT NULL AND length(embedding) = ?1",
+    )?;
+    let rows = stmt
+        .query_map(params![(dimensions * 4) as i64], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                bytes_to_embedding(&row.get::<_, Vec<u8>>(1)?),
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let mut index = VectorIndex::build(
+        dimensions,
+        rows.iter().map(|(id, v)| (id.as_str(), v.as_slice())),
+    );
+    index.set_generation(generation);
+    Ok(index)
+}
+
+/// Counter bumped on every change to procedure vectors
+fn index_generation(conn: &Connection) -> Result<i64> {
+    let generation = conn
+        .query_row(
+            "SELECT generation FROM vector_index_state WHERE collection = 'procedures'",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(generation.unwrap_or(0))
+}
+
+fn bump_index_generation(conn: &Connection) -> Result<i64> {
+    conn.execute(
+        "INSERT INTO vector_index_state (collection, generation) VALUES ('procedures', 1)
+         ON CONFLICT(collection) DO UPDATE SET generation = generation + 1",
+        [],
+    )?;
+    index_generation(conn)
+}
+
+fn stats(index: &VectorIndex) -> IndexStats {
+    IndexStats {
+        entries: index.len(),
+        dimensions: index.dimensions(),
+        generation: index.generation(),
+    }
+}
+
+/// Remove index entries whose record no longer exists
+fn drop_orphaned_fts(conn: &Connection) -> Result<usize> {
+    let mut removed = 0;
+    for collection in crate::dump::LONGTERM_COLLECTIONS {
+        removed += conn.execute(
+            &format!(
+                "DELETE FROM longterm_fts WHERE collection = ?1
+                 AND record_id NOT IN (SELECT id FROM {collection})"
+            ),
+            params![collection],
+        )?;
+    }
+    Ok(removed)
+}
+
+/// Rebuild a collection's full-text index entries from its table
+fn reindex_fts(conn: &Connection, collection: &str) -> Result<()> {
+    let select = match collection {
+        "procedures" => {
+            "SELECT 'procedures', id, name || ': ' || description,
+                    name || ' ' || description || ' ' || COALESCE(tags, '')
+             FROM procedures"
+        }
+        "incidents" => {
+            "SELECT 'incidents', id,
+                    description || ' | Cause: ' || COALESCE(root_cause, '') || ' | Resolution: ' || COALESCE(resolution, ''),
+                    description || ' ' || COALESCE(root_cause, '') || ' ' || COALESCE(resolution, '') || ' ' || COALESCE(prevention, '')
+             FROM incidents"
+        }
+        "config_changes" => {
+            "SELECT 'config_changes', id, file_path || ': ' || reason, file_path || ' ' || reason
+             FROM config_changes"
+        }
+        _ => return Ok(()),
+    };
+    conn.execute(
+        "DELETE FROM longterm_fts WHERE collection = ?1",
+        params![collection],
+    )?;
+    conn.execute(
+        &format!("INSERT INTO longterm_fts (collection, record_id, content, body) {select}"),
+        [],
+    )?;
+    Ok(())
+}
+
+/// Add or replace a record in the full-text index
+fn index_fts(
+    conn: &Connection,
+    collection: &str,
+    id: &str,
+    content: &str,
+    body: &str,
+) -> Result<()> {
+    conn.execute(
+        "DELETE FROM longterm_fts WHERE collection = ?1 AND record_id = ?2",
+        params![collection, id],
+    )?;
+    conn.execute(
+        "INSERT INTO longterm_fts (collection, record_id, content, body) VALUES (?1, ?2, ?3, ?4)",
+        params![collection, id, content, body],
+    )?;
+    Ok(())
+}
+
 /// Simple keyword-based relevance scoring
 fn keyword_relevance(keywords: &[&str], text: &str) -> f64 {
     if keywords.is_empty() {
@@ -356,11 +902,40 @@ fn keyword_relevance(keywords: &[&str], text: &str) -> f64 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::embedding::hash_embedding;
+
+    impl LongTermMemory {
+        /// Store with the built-in hashed embedding
+        fn store_hashed(&self, procedure: &Procedure) -> Result<()> {
+            let text = procedure_text(
+                &procedure.name,
+                &procedure.description,
+                &procedure.tags.join(","),
+            );
+            self.store_procedure(
+                procedure,
+                embedding::HASH_PROVIDER,
+                Some(&hash_embedding(&text)),
+            )
+        }
+
+        /// Search with the built-in hashed query embedding
+        fn search_hashed(
+            &self,
+            query: &str,
+            collections: &[String],
+            n_results: i32,
+            min_relevance: f64,
+        ) -> Result<Vec<SearchResult>> {
+            let embedding = hash_embedding(query);
+            self.semantic_search(query, &embedding, collections, n_results, min_relevance)
+        }
+    }
 
     #[test]
     fn test_store_and_search_procedure() {
         let lt = LongTermMemory::new(":memory:").unwrap();
-        lt.store_procedure(&Procedure {
+        lt.store_hashed(&Procedure {
             id: "proc-1".into(),
             name: "restart_nginx".into(),
             description: "Restart nginx web server when it becomes unresponsive".into(),
@@ -375,7 +950,7 @@ mod tests {
         .unwrap();
 
         let results = lt
-            .semantic_search("nginx restart", &["procedures".into()], 10, 0.1)
+            .search_hashed("nginx restart", &["procedures".into()], 10, 0.1)
             .unwrap();
         assert!(!results.is_empty());
         assert!(results[0].content.contains("nginx"));
@@ -415,13 +990,32 @@ mod tests {
         .unwrap();
 
         let results = lt
-            .semantic_search("nginx memory", &["incidents".into()], 10, 0.1)
+            .search_hashed("nginx memory", &["incidents".into()], 10, 0.1)
             .unwrap();
         assert!(!results.is_empty());
         assert!(results[0].content.contains("nginx") || results[0].content.contains("Nginx"));
         assert_eq!(results[0].collection, "incidents");
     }
 
+    #[test]
+    fn test_list_incidents_since() {
+        let lt = LongTermMemory::new(":memory:").unwrap();
+        for (id, timestamp) in [("inc-old", 100), ("inc-a", 1000), ("inc-b", 2000)] {
+            lt.store_incident(&Incident {
+                id: id.into(),
+                description: format!("{id} happened"),
+                timestamp,
+                ..Default::default()
+            })
+            .unwrap();
+        }
+
+        let incidents = lt.list_incidents(500, 0).unwrap();
+        let ids: Vec<&str> = incidents.iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(ids, vec!["inc-b", "inc-a"]);
+        assert_eq!(lt.list_incidents(0, 1).unwrap().len(), 1);
+    }
+
     #[test]
     fn test_store_and_search_config_change() {
         let lt = LongTermMemory::new(":memory:").unwrap();
@@ -436,7 +1030,7 @@ mod tests {
         .unwrap();
 
         let results = lt
-            .semantic_search("nginx config", &["config_changes".into()], 10, 0.1)
+            .search_hashed("nginx config", &["config_changes".into()], 10, 0.1)
             .unwrap();
         assert!(!results.is_empty());
         assert_eq!(results[0].collection, "config_changes");
@@ -445,7 +1039,7 @@ mod tests {
     #[test]
     fn test_search_across_collections() {
         let lt = LongTermMemory::new(":memory:").unwrap();
-        lt.store_procedure(&Procedure {
+        lt.store_hashed(&Procedure {
             id: "proc-1".into(),
             name: "restart_nginx".into(),
             description: "Restart the nginx web server".into(),
@@ -472,7 +1066,7 @@ mod tests {
         .unwrap();
 
         // Search all collections (empty = all)
-        let results = lt.semantic_search("nginx", &[], 10, 0.1).unwrap();
+        let results = lt.search_hashed("nginx", &[], 10, 0.1).unwrap();
         assert!(results.len() >= 2);
     }
 
@@ -480,7 +1074,7 @@ mod tests {
     fn test_search_with_no_results() {
         let lt = LongTermMemory::new(":memory:").unwrap();
         let results = lt
-            .semantic_search("nonexistent_keyword_xyz", &[], 10, 0.1)
+            .search_hashed("nonexistent_keyword_xyz", &[], 10, 0.1)
             .unwrap();
         assert!(results.is_empty());
     }
@@ -488,7 +1082,7 @@ mod tests {
     #[test]
     fn test_search_min_relevance_filtering() {
         let lt = LongTermMemory::new(":memory:").unwrap();
-        lt.store_procedure(&Procedure {
+        lt.store_hashed(&Procedure {
             id: "proc-1".into(),
             name: "restart_nginx".into(),
             description: "Restart the nginx web server".into(),
@@ -505,13 +1099,13 @@ mod tests {
         // Query with one matching and one non-matching keyword
         // "nginx" matches but "kubernetes" does not => relevance = 0.5
         let results = lt
-            .semantic_search("nginx kubernetes", &["procedures".into()], 10, 0.8)
+            .search_hashed("nginx kubernetes", &["procedures".into()], 10, 0.8)
             .unwrap();
         // Should be filtered out since relevance (0.5) < min_relevance (0.8)
         assert!(results.is_empty());
 
         let results = lt
-            .semantic_search("nginx kubernetes", &["procedures".into()], 10, 0.3)
+            .search_hashed("nginx kubernetes", &["procedures".into()], 10, 0.3)
             .unwrap();
         assert!(!results.is_empty());
     }
@@ -520,7 +1114,7 @@ mod tests {
     fn test_search_result_limit() {
         let lt = LongTermMemory::new(":memory:").unwrap();
         for i in 0..10 {
-            lt.store_procedure(&Procedure {
+            lt.store_hashed(&Procedure {
                 id: format!("proc-{i}"),
                 name: format!("restart_service_{i}"),
                 description: format!("Restart service number {i}"),
@@ -536,7 +1130,7 @@ mod tests {
         }
 
         let results = lt
-            .semantic_search("restart service", &["procedures".into()], 3, 0.1)
+            .search_hashed("restart service", &["procedures".into()], 3, 0.1)
             .unwrap();
         assert!(results.len() <= 3);
     }
@@ -545,7 +1139,7 @@ mod tests {
     fn test_search_default_limit() {
         let lt = LongTermMemory::new(":memory:").unwrap();
         // n_results=0 should default to 10
-        let results = lt.semantic_search("anything", &[], 0, 0.0).unwrap();
+        let results = lt.search_hashed("anything", &[], 0, 0.0).unwrap();
         // No data, just verifying it doesn't panic with limit=0
         assert!(results.is_empty());
     }
@@ -553,7 +1147,7 @@ mod tests {
     #[test]
     fn test_store_procedure_with_tags() {
         let lt = LongTermMemory::new(":memory:").unwrap();
-        lt.store_procedure(&Procedure {
+        lt.store_hashed(&Procedure {
             id: "proc-1".into(),
             name: "deploy_app".into(),
             description: "Deploy application to production".into(),
@@ -568,7 +1162,7 @@ mod tests {
         .unwrap();
 
         let results = lt
-            .semantic_search("deploy production", &["procedures".into()], 10, 0.1)
+            .search_hashed("deploy production", &["procedures".into()], 10, 0.1)
             .unwrap();
         assert!(!results.is_empty());
     }
@@ -578,7 +1172,7 @@ mod tests {
         let lt = LongTermMemory::new(":memory:").unwrap();
         // Searching an unknown collection should return no results, not error
         let results = lt
-            .semantic_search("anything", &["unknown_collection".into()], 10, 0.0)
+            .search_hashed("anything", &["unknown_collection".into()], 10, 0.0)
             .unwrap();
         assert!(results.is_empty());
     }
@@ -586,7 +1180,7 @@ mod tests {
     #[test]
     fn test_results_sorted_by_relevance() {
         let lt = LongTermMemory::new(":memory:").unwrap();
-        lt.store_procedure(&Procedure {
+        lt.store_hashed(&Procedure {
             id: "proc-1".into(),
             name: "nginx_restart".into(),
             description: "Restart nginx web server".into(),
@@ -600,7 +1194,7 @@ mod tests {
         })
         .unwrap();
 
-        lt.store_procedure(&Procedure {
+        lt.store_hashed(&Procedure {
             id: "proc-2".into(),
             name: "nginx_reload_config".into(),
             description: "Reload nginx configuration after changes to web server config".into(),
@@ -615,7 +1209,7 @@ mod tests {
         .unwrap();
 
         let results = lt
-            .semantic_search("nginx web server", &["procedures".into()], 10, 0.1)
+            .search_hashed("nginx web server", &["procedures".into()], 10, 0.1)
             .unwrap();
 
         // Results should be sorted by relevance (descending)
@@ -623,4 +1217,191 @@ mod tests {
             assert!(results[0].relevance >= results[1].relevance);
         }
     }
+
+    #[test]
+    fn test_provider_change_marks_procedures_stale() {
+        let lt = LongTermMemory::new(":memory:").unwrap();
+        let procedure = Procedure {
+            id: "proc-1".into(),
+            name: "rotate_logs".into(),
+            description: "Rotate and compress logs".into(),
+            ..Default::default()
+        };
+        lt.store_hashed(&procedure).unwrap();
+        lt.store_procedure(
+            &Procedure {
+                id: "proc-2".into(),
+                ..procedure.clone()
+            },
+            "openai:m",
+            None,
+        )
+        .unwrap();
+
+        // Unembedded procedures are always stale; hashed ones only once
+        // the provider changes
+        let stale = lt.stale_procedures("hash", 10).unwrap();
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale[0].0, "proc-2");
+        assert_eq!(lt.stale_procedures("openai:m", 10).unwrap().len(), 2);
+
+        lt.set_procedure_embedding("proc-1", "openai:m", &[0.5; 8])
+            .unwrap();
+        lt.set_procedure_embedding("proc-2", "openai:m", &[0.5; 8])
+            .unwrap();
+        assert!(lt.stale_procedures("openai:m", 10).unwrap().is_empty());
+        assert_eq!(
+            lt.collection_embedding("procedures").unwrap(),
+            Some(("openai:m".to_string(), 8))
+        );
+    }
+
+    #[test]
+    fn test_fts_search_matches_terms_across_collections() {
+        let lt = LongTermMemory::new(":memory:").unwrap();
+        lt.store_hashed(&Procedure {
+            id: "proc-1".into(),
+            name: "restart_nginx".into(),
+            description: "Restart the nginx web server".into(),
+            tags: vec!["web".into()],
+            ..Default::default()
+        })
+        .unwrap();
+        lt.store_incident(&Incident {
+            id: "inc-1".into(),
+            description: "nginx stopped answering".into(),
+            root_cause: "worker crash".into(),
+            ..Default::default()
+        })
+        .unwrap();
+        // Re-storing replaces the indexed text rather than duplicating it
+        lt.store_incident(&Incident {
+            id: "inc-1".into(),
+            description: "nginx stopped answering".into(),
+            root_cause: "config typo".into(),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let results = lt.fts_search("nginx crash", &[], 10).unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.relevance > 0.0));
+
+        let results = lt
+            .fts_search("nginx", &["decisions".to_string()], 10)
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].collection, "procedures");
+        assert!(lt.fts_search("typo", &[], 10).unwrap()[0]
+            .content
+            .contains("config typo"));
+        assert!(lt.fts_search("\"", &[], 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_restore_rebuilds_fts() {
+        let lt = LongTermMemory::new(":memory:").unwrap();
+        lt.store_incident(&Incident {
+            id: "inc-1".into(),
+            description: "disk filled by journal".into(),
+            symptoms_json: b"[\"ENOSPC\"]".to_vec(),
+            ..Default::default()
+        })
+        .unwrap();
+        let dumped = lt.dump("incidents").unwrap();
+
+        let restored = LongTermMemory::new(":memory:").unwrap();
+        assert_eq!(restored.restore("incidents", &dumped).unwrap(), 1);
+        assert_eq!(restored.dump("incidents").unwrap(), dumped);
+        assert_eq!(restored.fts_search("journal", &[], 10).unwrap().len(), 1);
+        // Restoring again replaces rather than duplicates
+        restored.restore("incidents", &dumped).unwrap();
+        assert_eq!(restored.fts_search("journal", &[], 10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_search_reaches_old_procedures_through_index() {
+        let lt = LongTermMemory::new(":memory:").unwrap();
+        lt.store_hashed(&Procedure {
+            id: "proc-old".into(),
+            name: "rotate_certificates".into(),
+            description: "Rotate expiring TLS certificates".into(),
+            last_used: 1,
+            ..Default::default()
+        })
+        .unwrap();
+        // Newer procedures crowd it out of the most-recently-used window
+        for i in 0..50 {
+            lt.store_hashed(&Procedure {
+                id: format!("proc-{i}"),
+                name: format!("cleanup_{i}"),
+                description: format!("Remove cache{i} files from spool{i}"),
+                last_used: 100 + i,
+                ..Default::default()
+            })
+            .unwrap();
+        }
+        assert_eq!(lt.index_stats().unwrap().entries, 51);
+
+        let results = lt
+            .search_hashed("rotate certificates", &["procedures".into()], 3, 0.3)
+            .unwrap();
+        assert_eq!(results[0].id, "proc-old");
+
+        // Storing without a vector drops it from the index
+        lt.store_procedure(
+            &Procedure {
+                id: "proc-old".into(),
+                name: "rotate_certificates".into(),
+                last_used: 1,
+                ..Default::default()
+            },
+            embedding::HASH_PROVIDER,
+            None,
+        )
+        .unwrap();
+        assert_eq!(lt.index_stats().unwrap().entries, 50);
+    }
+
+    #[test]
+    fn test_vector_index_persists_and_detects_stale_file() {
+        let db = std::env::temp_dir().join(format!("aios-longterm-{}.db", uuid::Uuid::new_v4()));
+        let db_path = db.to_str().unwrap();
+        let index_path = PathBuf::from(format!("{db_path}.hnsw"));
+        {
+            let lt = LongTermMemory::new(db_path).unwrap();
+            for i in 0..5 {
+                lt.store_hashed(&Procedure {
+                    id: format!("proc-{i}"),
+                    name: format!("job_{i}"),
+                    description: "nightly backup".into(),
+                    ..Default::default()
+                })
+                .unwrap();
+            }
+            assert!(lt.save_index().unwrap());
+            assert!(!lt.save_index().unwrap());
+            // A change after the save leaves the file one generation behind
+            lt.store_hashed(&Procedure {
+                id: "proc-5".into(),
+                name: "job_5".into(),
+                description: "nightly backup".into(),
+                ..Default::default()
+            })
+            .unwrap();
+        }
+        let saved = VectorIndex::load(&index_path).unwrap();
+        assert_eq!(saved.len(), 5);
+
+        let reopened = LongTermMemory::new(db_path).unwrap();
+        let stats = reopened.index_stats().unwrap();
+        assert_eq!(stats.entries, 6);
+        assert_eq!(stats.dimensions, embedding::HASH_DIMENSIONS);
+        assert_eq!(reopened.rebuild_index().unwrap(), stats);
+        assert_eq!(VectorIndex::load(&index_path).unwrap().len(), 6);
+
+        for suffix in ["", "-wal", "-shm", ".hnsw"] {
+            let _ = std::fs::remove_file(format!("{db_path}{suffix}"));
+        }
+    }
 }
@@ -10,20 +10,40 @@ use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tonic::transport::Server;
-use tracing::info;
+use tracing::{info, warn};
 
+mod access;
+#[path = "../../agent-core/src/api_compat.rs"]
+mod api_version;
+#[path = "../../agent-core/src/build_info.rs"]
+mod build_info;
+mod bus;
+mod dump;
+mod embedding;
+mod events;
+mod graph;
+mod identity;
 mod knowledge;
 mod longterm;
+mod metrics;
 mod migration;
 mod operational;
+mod rerank;
+mod retention;
+mod retrieval;
+mod spill;
+mod vector_index;
 mod working;
 
 pub mod proto {
     pub mod common {
-        tonic::include_proto!("aios.common");
+        tonic::include_proto!("aios.v1.common");
     }
     pub mod memory {
-        tonic::include_proto!("aios.memory");
+        tonic::include_proto!("aios.v1.memory");
+    }
+    pub mod runtime {
+        tonic::include_proto!("aios.v1.runtime");
     }
 }
 
@@ -35,11 +55,129 @@ pub struct MemoryState {
     pub working: working::WorkingMemory,
     pub longterm: longterm::LongTermMemory,
     pub knowledge: knowledge::KnowledgeBase,
+    pub graph: graph::KnowledgeGraph,
 }
 
 /// gRPC service implementation
 pub struct MemoryServiceImpl {
     state: Arc<RwLock<MemoryState>>,
+    embedder: Arc<embedding::Embedder>,
+    reranker: rerank::Reranker,
+    access: access::AccessPolicy,
+    access_log: Arc<access::AccessLog>,
+    metrics: Arc<metrics::MetricHistory>,
+    events: events::EventRegistry,
+    bus: Arc<bus::EventBus>,
+    identities: Arc<identity::IdentityRegistry>,
+}
+
+impl MemoryServiceImpl {
+    /// Write a read to the access log; a failure is logged, not returned
+    fn audit(
+        &self,
+        requester: &str,
+        operation: &str,
+        query: &str,
+        collections: &[String],
+        records: Vec<String>,
+        denied: Vec<String>,
+    ) {
+        let entry = proto::memory::AccessEntry {
+            id: 0,
+            timestamp: chrono::Utc::now().timestamp(),
+            requester: requester.to_string(),
+            operation: operation.to_string(),
+            query: query.to_string(),
+            collections: collections.to_vec(),
+            records,
+            denied,
+        };
+        if let Err(e) = self.access_log.record(&entry) {
+            warn!("Failed to record {operation} by {requester} in the access log: {e}");
+        }
+    }
+
+    /// Embed a document for storage; None if the provider failed, in which
+    /// case the record is stored without a vector and re-embedded later
+    async fn embed_document(&self, text: &str) -> Option<Vec<f32>> {
+        match self
+            .embedder
+            .embed_one(text, embedding::Purpose::Document)
+            .await
+        {
+            Ok(vector) => Some(vector),
+            Err(e) => {
+                warn!("Embedding with {} failed: {e}", self.embedder.id());
+                None
+            }
+        }
+    }
+
+    /// Embed documents for storage in one request; all None if the provider
+    /// failed
+    async fn embed_documents(&self, texts: &[String]) -> Vec<Option<Vec<f32>>> {
+        match self
+            .embedder
+            .embed(texts, embedding::Purpose::Document)
+            .await
+        {
+            Ok(vectors) => vectors.into_iter().map(Some).collect(),
+            Err(e) => {
+                warn!(
+                    "Embedding {} documents with {} failed: {e}",
+                    texts.len(),
+                    self.embedder.id()
+                );
+                vec![None; texts.len()]
+            }
+        }
+    }
+
+    /// Rerank the searched tiers' candidates in one request; on failure the
+    /// fused retrieval order stands
+    async fn rerank(
+        &self,
+        query: &str,
+        candidates: &mut [(String, Vec<proto::memory::ContextChunk>)],
+    ) {
+        let searched = |tier: &str| tier == "longterm" || tier == "knowledge";
+        let passages: Vec<String> = candidates
+            .iter()
+            .filter(|(tier, _)| searched(tier))
+            .flat_map(|(_, chunks)| chunks.iter().map(|c| c.content.clone()))
+            .collect();
+        let scores = match self.reranker.rerank(query, &passages).await {
+            Ok(Some(scores)) => scores,
+            Ok(None) => return,
+            Err(e) => {
+                warn!("Rerank with {} failed: {e}", self.reranker.name());
+                return;
+            }
+        };
+        let mut offset = 0;
+        for (tier, chunks) in candidates.iter_mut() {
+            if searched(tier) {
+                let end = offset + chunks.len();
+                retrieval::apply_rerank(chunks, &scores[offset..end]);
+                offset = end;
+            }
+        }
+    }
+
+    /// Embed a search query; empty (keyword-only search) if the provider failed
+    async fn embed_query(&self, text: &str) -> Vec<f32> {
+        match self
+            .embedder
+            .embed_one(text, embedding::Purpose::Query)
+            .await
+        {
+            Ok(vector) => vector,
+            Err(e) => {
+                warn!("Query embedding with {} failed: {e}", self.embedder.id());
+                Vec::new()
+            }
+        }
+    }
 }
 
 #[tonic::async_trait]
@@ -50,8 +188,17 @@ impl MemoryService for MemoryServiceImpl {
         &self,
         request: tonic::Request<proto::memory::Event>,
     ) -> Result<tonic::Response<proto::memory::Empty>, tonic::Status> {
-        let event = request.into_inner();
+        let mut event = request.into_inner();
+        self.events
+            .validate(&mut event)
+            .map_err(|e| tonic::Status::invalid_argument(format!("Invalid event: {e:#}")))?;
+        if let Err(e) = self.bus.publish_event(&event) {
+            warn!("Failed to publish event {} on the event bus: {e}", event.id);
+        }
         let mut state = self.state.write().await;
+        if let Err(e) = state.graph.extract_event(&event) {
+            warn!("Graph extraction from event {} failed: {e}", event.id);
+        }
         state.operational.push_event(event);
         Ok(tonic::Response::new(proto::memory::Empty {}))
     }
@@ -62,17 +209,215 @@ impl MemoryService for MemoryServiceImpl {
     ) -> Result<tonic::Response<proto::memory::EventList>, tonic::Status> {
         let req = request.into_inner();
         let state = self.state.read().await;
-        let events = state
-            .operational
-            .get_recent(req.count as usize, &req.category, &req.source);
+        let events = state.operational.query_events(&req);
         Ok(tonic::Response::new(proto::memory::EventList { events }))
     }
 
+    async fn read_events(
+        &self,
+        request: tonic::Request<proto::memory::ReadEventsRequest>,
+    ) -> Result<tonic::Response<proto::memory::EventBatch>, tonic::Status> {
+        let req = request.into_inner();
+        let mut state = self.state.write().await;
+        let batch = state
+            .operational
+            .read_events(&req)
+            .map_err(|e| tonic::Status::internal(format!("Failed to read events: {e}")))?;
+        if batch.lost > 0 {
+            warn!(
+                "Consumer '{}' missed {} events that left the buffer",
+                req.consumer, batch.lost
+            );
+        }
+        Ok(tonic::Response::new(batch))
+    }
+
+    async fn publish(
+        &self,
+        request: tonic::Request<proto::memory::BusMessage>,
+    ) -> Result<tonic::Response<proto::memory::PublishAck>, tonic::Status> {
+        let mut message = request.into_inner();
+        if message
+            .topic
+            .starts_with(&format!("{}.", bus::EVENTS_TOPIC))
+        {
+            return Err(tonic::Status::invalid_argument(
+                "events.* topics are published by PushEvent",
+            ));
+        }
+        message.event = None;
+        let seq = self
+            .bus
+            .publish(message)
+            .map_err(|e| tonic::Status::invalid_argument(format!("Failed to publish: {e}")))?;
+        Ok(tonic::Response::new(proto::memory::PublishAck { seq }))
+    }
+
+    type SubscribeStream =
+        tokio_stream::wrappers::ReceiverStream<Result<proto::memory::BusDelivery, tonic::Status>>;
+
+    async fn subscribe(
+        &self,
+        request: tonic::Request<proto::memory::SubscribeRequest>,
+    ) -> Result<tonic::Response<Self::SubscribeStream>, tonic::Status> {
+        let req = request.into_inner();
+        let bus = self.bus.clone();
+        let mut watch = bus.watch();
+        let mut position = if req.subscriber.is_empty() {
+            bus.latest_seq()
+        } else {
+            match bus.acked(&req.subscriber) {
+                Ok(Some(acked)) => acked,
+                Ok(None) if req.from_latest => {
+                    let latest = bus.latest_seq();
+                    bus.ack(&req.subscriber, latest)
+                        .map_err(|e| tonic::Status::internal(e.to_string()))?;
+                    latest
+                }
+                Ok(None) => 0,
+                Err(e) => return Err(tonic::Status::internal(e.to_string())),
+            }
+        };
+        info!(
+            "Bus subscriber '{}' subscribed to {:?} after {position}",
+            req.subscriber, req.topics
+        );
+
+        let (tx, rx) = tokio::sync::mpsc::channel(BUS_BATCH);
+        tokio::spawn(async move {
+            loop {
+                let (deliveries, scanned) = match bus.read_after(position, &req.topics, BUS_BATCH) {
+                    Ok(read) => read,
+                    Err(e) => {
+                        let _ = tx.send(Err(tonic::Status::internal(e.to_string()))).await;
+                        return;
+                    }
+                };
+                for delivery in deliveries {
+                    if tx.send(Ok(delivery)).await.is_err() {
+                        return;
+                    }
+                }
+                if scanned > position {
+                    position = scanned;
+                    continue;
+                }
+                tokio::select! {
+                    changed = watch.changed() => if changed.is_err() { return },
+                    _ = tx.closed() => return,
+                }
+            }
+        });
+
+        Ok(tonic::Response::new(
+            tokio_stream::wrappers::ReceiverStream::new(rx),
+        ))
+    }
+
+    async fn ack(
+        &self,
+        request: tonic::Request<proto::memory::BusAck>,
+    ) -> Result<tonic::Response<proto::memory::Empty>, tonic::Status> {
+        let req = request.into_inner();
+        self.bus
+            .ack(&req.subscriber, req.seq)
+            .map_err(|e| tonic::Status::invalid_argument(format!("Failed to ack: {e}")))?;
+        Ok(tonic::Response::new(proto::memory::Empty {}))
+    }
+
+    // --- Identities ---
+
+    async fn register_identity(
+        &self,
+        request: tonic::Request<proto::memory::IdentityRegistration>,
+    ) -> Result<tonic::Response<proto::memory::RegisteredIdentity>, tonic::Status> {
+        let verified = identity::verified(&request).cloned();
+        let token = request
+            .metadata()
+            .get(identity::TOKEN_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let req = request.into_inner();
+        let identity = req.identity.unwrap_or_default();
+        let id = format!("{}:{}", identity.kind, identity.name);
+        let principal = identity::Principal::parse(&id).ok_or_else(|| {
+            tonic::Status::invalid_argument(format!("Failed to register: invalid identity {id}"))
+        })?;
+        // Nodes, services, users and the names access policies grant reads to
+        let reserved = principal.is_reserved_kind() || self.access.names(&principal.key());
+        self.identities
+            .authorize_registration(&principal, reserved, verified.as_ref(), token.as_deref())
+            .map_err(|e| tonic::Status::permission_denied(e.to_string()))?;
+        let (identity, token) = self
+            .identities
+            .register(&identity, req.issue_token)
+            .map_err(|e| tonic::Status::invalid_argument(format!("Failed to register: {e}")))?;
+        info!("Registered identity {}", identity.id);
+        Ok(tonic::Response::new(proto::memory::RegisteredIdentity {
+            identity: Some(identity),
+            token: token.unwrap_or_default(),
+        }))
+    }
+
+    async fn get_identity(
+        &self,
+        request: tonic::Request<proto::memory::IdentityRequest>,
+    ) -> Result<tonic::Response<proto::memory::Identity>, tonic::Status> {
+        let req = request.into_inner();
+        let identity = self
+            .identities
+            .get(&req.id)
+            .map_err(|e| tonic::Status::internal(format!("Failed to read identity: {e}")))?
+            .ok_or_else(|| tonic::Status::not_found(format!("Unknown identity {}", req.id)))?;
+        Ok(tonic::Response::new(identity))
+    }
+
+    async fn list_identities(
+        &self,
+        request: tonic::Request<proto::memory::IdentityListRequest>,
+    ) -> Result<tonic::Response<proto::memory::IdentityList>, tonic::Status> {
+        let req = request.into_inner();
+        let identities = self
+            .identities
+            .list(&req.kind)
+            .map_err(|e| tonic::Status::internal(format!("Failed to list identities: {e}")))?;
+        Ok(tonic::Response::new(proto::memory::IdentityList {
+            identities,
+        }))
+    }
+
+    async fn verify_identity(
+        &self,
+        request: tonic::Request<proto::memory::IdentityCredentials>,
+    ) -> Result<tonic::Response<proto::memory::Identity>, tonic::Status> {
+        let req = request.into_inner();
+        let identity = self
+            .identities
+            .verify(&req.id, &req.token)
+            .map_err(|e| tonic::Status::unauthenticated(e.to_string()))?;
+        Ok(tonic::Response::new(identity))
+    }
+
+    async fn list_event_schemas(
+        &self,
+        _request: tonic::Request<proto::memory::Empty>,
+    ) -> Result<tonic::Response<proto::memory::EventSchemaList>, tonic::Status> {
+        Ok(tonic::Response::new(self.events.to_proto()))
+    }
+
     async fn update_metric(
         &self,
         request: tonic::Request<proto::memory::MetricUpdate>,
     ) -> Result<tonic::Response<proto::memory::Empty>, tonic::Status> {
         let metric = request.into_inner();
+        let timestamp = if metric.timestamp > 0 {
+            metric.timestamp
+        } else {
+            chrono::Utc::now().timestamp()
+        };
+        if let Err(e) = self.metrics.record(&metric.key, metric.value, timestamp) {
+            warn!("Failed to record history for metric {}: {e}", metric.key);
+        }
         let mut state = self.state.write().await;
         state.operational.update_metric(metric);
         Ok(tonic::Response::new(proto::memory::Empty {}))
@@ -91,6 +436,60 @@ impl MemoryService for MemoryServiceImpl {
         Ok(tonic::Response::new(value))
     }
 
+    async fn query_metric_range(
+        &self,
+        request: tonic::Request<proto::memory::MetricRangeRequest>,
+    ) -> Result<tonic::Response<proto::memory::MetricSeries>, tonic::Status> {
+        let req = request.into_inner();
+        if req.key.is_empty() {
+            return Err(tonic::Status::invalid_argument("key is required"));
+        }
+        let to = if req.to > 0 {
+            req.to
+        } else {
+            chrono::Utc::now().timestamp()
+        };
+        let from = if req.from > 0 { req.from } else { to - 3600 };
+        if to < from {
+            return Err(tonic::Status::invalid_argument(format!(
+                "Range end {to} is before its start {from}"
+            )));
+        }
+        let step = if req.step > 0 {
+            req.step
+        } else {
+            metrics::default_step(from, to)
+        };
+        let agg = if req.agg.is_empty() {
+            "avg".to_string()
+        } else {
+            req.agg.to_lowercase()
+        };
+        if !metrics::AGGREGATIONS.contains(&agg.as_str()) {
+            return Err(tonic::Status::invalid_argument(format!(
+                "Unknown aggregation '{agg}' (expected one of: {})",
+                metrics::AGGREGATIONS.join(", ")
+            )));
+        }
+        if (to - from) / step + 1 > metrics::MAX_POINTS {
+            return Err(tonic::Status::invalid_argument(format!(
+                "Range would return more than {} points; use a larger step",
+                metrics::MAX_POINTS
+            )));
+        }
+
+        let points = self
+            .metrics
+            .query_range(&req.key, from, to, &agg, step)
+            .map_err(|e| tonic::Status::internal(format!("Metric range query failed: {e}")))?;
+        Ok(tonic::Response::new(proto::memory::MetricSeries {
+            key: req.key,
+            agg,
+            step,
+            points,
+        }))
+    }
+
     async fn get_system_snapshot(
         &self,
         _request: tonic::Request<proto::memory::Empty>,
@@ -112,6 +511,9 @@ impl MemoryService for MemoryServiceImpl {
             .working
             .store_goal(&goal)
             .map_err(|e| tonic::Status::internal(format!("Failed to store goal: {e}")))?;
+        if let Err(e) = state.graph.extract_goal(&goal) {
+            warn!("Graph extraction from goal {} failed: {e}", goal.id);
+        }
         Ok(tonic::Response::new(proto::memory::Empty {}))
     }
 
@@ -176,6 +578,12 @@ impl MemoryService for MemoryServiceImpl {
             .working
             .store_tool_call(&record)
             .map_err(|e| tonic::Status::internal(format!("Failed to store tool call: {e}")))?;
+        if let Err(e) = state.graph.extract_tool_call(&record) {
+            warn!(
+                "Graph extraction from {} output failed: {e}",
+                record.tool_name
+            );
+        }
         Ok(tonic::Response::new(proto::memory::Empty {}))
     }
 
@@ -257,23 +665,167 @@ impl MemoryService for MemoryServiceImpl {
         Ok(tonic::Response::new(agent_state))
     }
 
+    async fn store_benchmark_run(
+        &self,
+        request: tonic::Request<proto::memory::BenchmarkRun>,
+    ) -> Result<tonic::Response<proto::memory::Empty>, tonic::Status> {
+        let run = request.into_inner();
+        let state = self.state.read().await;
+        state
+            .working
+            .store_benchmark_run(&run)
+            .map_err(|e| tonic::Status::internal(format!("Failed to store benchmark run: {e}")))?;
+        Ok(tonic::Response::new(proto::memory::Empty {}))
+    }
+
+    async fn get_benchmark_runs(
+        &self,
+        request: tonic::Request<proto::memory::BenchmarkRunsRequest>,
+    ) -> Result<tonic::Response<proto::memory::BenchmarkRunList>, tonic::Status> {
+        let req = request.into_inner();
+        let state = self.state.read().await;
+        let runs = state
+            .working
+            .get_benchmark_runs(req.limit)
+            .map_err(|e| tonic::Status::internal(format!("Failed to get benchmark runs: {e}")))?;
+        Ok(tonic::Response::new(proto::memory::BenchmarkRunList {
+            runs,
+        }))
+    }
+
+    async fn store_task_checkpoint(
+        &self,
+        request: tonic::Request<proto::memory::TaskCheckpoint>,
+    ) -> Result<tonic::Response<proto::memory::Empty>, tonic::Status> {
+        let checkpoint = request.into_inner();
+        let state = self.state.read().await;
+        state
+            .working
+            .store_task_checkpoint(&checkpoint)
+            .map_err(|e| {
+                tonic::Status::internal(format!("Failed to store task checkpoint: {e}"))
+            })?;
+        Ok(tonic::Response::new(proto::memory::Empty {}))
+    }
+
+    async fn get_task_checkpoint(
+        &self,
+        request: tonic::Request<proto::memory::TaskCheckpointRequest>,
+    ) -> Result<tonic::Response<proto::memory::TaskCheckpoint>, tonic::Status> {
+        let req = request.into_inner();
+        let state = self.state.read().await;
+        let checkpoint = state
+            .working
+            .get_task_checkpoint(&req.task_id)
+            .map_err(|e| tonic::Status::internal(format!("Failed to get task checkpoint: {e}")))?;
+        Ok(tonic::Response::new(checkpoint.unwrap_or_default()))
+    }
+
+    async fn delete_task_checkpoint(
+        &self,
+        request: tonic::Request<proto::memory::TaskCheckpointRequest>,
+    ) -> Result<tonic::Response<proto::memory::Empty>, tonic::Status> {
+        let req = request.into_inner();
+        let state = self.state.read().await;
+        state
+            .working
+            .delete_task_checkpoint(&req.task_id)
+            .map_err(|e| {
+                tonic::Status::internal(format!("Failed to delete task checkpoint: {e}"))
+            })?;
+        Ok(tonic::Response::new(proto::memory::Empty {}))
+    }
+
+    async fn append_chat_message(
+        &self,
+        request: tonic::Request<proto::memory::ChatMessage>,
+    ) -> Result<tonic::Response<proto::memory::Empty>, tonic::Status> {
+        let message = request.into_inner();
+        if message.session_id.is_empty() {
+            return Err(tonic::Status::invalid_argument("session_id is required"));
+        }
+        let state = self.state.read().await;
+        state
+            .working
+            .append_chat_message(&message)
+            .map_err(|e| tonic::Status::internal(format!("Failed to store chat message: {e}")))?;
+        Ok(tonic::Response::new(proto::memory::Empty {}))
+    }
+
+    async fn get_chat_session(
+        &self,
+        request: tonic::Request<proto::memory::ChatSessionRequest>,
+    ) -> Result<tonic::Response<proto::memory::ChatSession>, tonic::Status> {
+        let req = request.into_inner();
+        let state = self.state.read().await;
+        let session = state
+            .working
+            .get_chat_session(&req.session_id)
+            .map_err(|e| tonic::Status::internal(format!("Failed to get chat session: {e}")))?;
+        Ok(tonic::Response::new(session.unwrap_or_default()))
+    }
+
+    async fn list_chat_sessions(
+        &self,
+        request: tonic::Request<proto::memory::ChatSessionListRequest>,
+    ) -> Result<tonic::Response<proto::memory::ChatSessionList>, tonic::Status> {
+        let req = request.into_inner();
+        let state = self.state.read().await;
+        let sessions = state
+            .working
+            .list_chat_sessions(req.limit)
+            .map_err(|e| tonic::Status::internal(format!("Failed to list chat sessions: {e}")))?;
+        Ok(tonic::Response::new(proto::memory::ChatSessionList {
+            sessions,
+        }))
+    }
+
+    async fn store_chat_summary(
+        &self,
+        request: tonic::Request<proto::memory::ChatSummary>,
+    ) -> Result<tonic::Response<proto::memory::Empty>, tonic::Status> {
+        let summary = request.into_inner();
+        let state = self.state.read().await;
+        state
+            .working
+            .store_chat_summary(&summary)
+            .map_err(|e| tonic::Status::internal(format!("Failed to store chat summary: {e}")))?;
+        Ok(tonic::Response::new(proto::memory::Empty {}))
+    }
+
     // --- Long-Term Memory ---
 
     async fn semantic_search(
         &self,
         request: tonic::Request<proto::memory::SemanticSearchRequest>,
     ) -> Result<tonic::Response<proto::memory::SearchResults>, tonic::Status> {
+        let acting = identity::acting(&request);
         let req = request.into_inner();
+        let requester = access::requester(&acting);
+        let query_embedding = self.embed_query(&req.query).await;
         let state = self.state.read().await;
         let results = state
             .longterm
             .semantic_search(
                 &req.query,
+                &query_embedding,
                 &req.collections,
                 req.n_results,
                 req.min_relevance,
             )
             .map_err(|e| tonic::Status::internal(format!("Semantic search failed: {e}")))?;
+        let (results, denied) = self.access.filter(requester, results);
+        self.audit(
+            requester,
+            "semantic_search",
+            &req.query,
+            &req.collections,
+            results
+                .iter()
+                .map(|r| access::record_ref(&r.collection, &r.id))
+                .collect(),
+            denied,
+        );
         Ok(tonic::Response::new(proto::memory::SearchResults {
             results,
         }))
@@ -284,14 +836,48 @@ impl MemoryService for MemoryServiceImpl {
         request: tonic::Request<proto::memory::Procedure>,
     ) -> Result<tonic::Response<proto::memory::Empty>, tonic::Status> {
         let procedure = request.into_inner();
+        let text = longterm::procedure_text(
+            &procedure.name,
+            &procedure.description,
+            &procedure.tags.join(","),
+        );
+        let vector = self.embed_document(&text).await;
         let state = self.state.read().await;
         state
             .longterm
-            .store_procedure(&procedure)
+            .store_procedure(&procedure, self.embedder.id(), vector.as_deref())
             .map_err(|e| tonic::Status::internal(format!("Failed to store procedure: {e}")))?;
         Ok(tonic::Response::new(proto::memory::Empty {}))
     }
 
+    async fn store_procedures(
+        &self,
+        request: tonic::Request<proto::memory::ProcedureBatch>,
+    ) -> Result<tonic::Response<proto::memory::Empty>, tonic::Status> {
+        let procedures = request.into_inner().procedures;
+        if procedures.is_empty() {
+            return Ok(tonic::Response::new(proto::memory::Empty {}));
+        }
+        let texts: Vec<String> = procedures
+            .iter()
+            .map(|p| longterm::procedure_text(&p.name, &p.description, &p.tags.join(",")))
+            .collect();
+        let vectors = self.embed_documents(&texts).await;
+        let state = self.state.read().await;
+        for (procedure, vector) in procedures.iter().zip(&vectors) {
+            state
+                .longterm
+                .store_procedure(procedure, self.embedder.id(), vector.as_deref())
+                .map_err(|e| {
+                    tonic::Status::internal(format!(
+                        "Failed to store procedure {}: {e}",
+                        procedure.id
+                    ))
+                })?;
+        }
+        Ok(tonic::Response::new(proto::memory::Empty {}))
+    }
+
     async fn store_incident(
         &self,
         request: tonic::Request<proto::memory::Incident>,
@@ -302,9 +888,54 @@ impl MemoryService for MemoryServiceImpl {
             .longterm
             .store_incident(&incident)
             .map_err(|e| tonic::Status::internal(format!("Failed to store incident: {e}")))?;
+        if let Err(e) = state.graph.extract_incident(&incident) {
+            warn!("Graph extraction from incident {} failed: {e}", incident.id);
+        }
         Ok(tonic::Response::new(proto::memory::Empty {}))
     }
 
+    async fn list_incidents(
+        &self,
+        request: tonic::Request<proto::memory::IncidentsRequest>,
+    ) -> Result<tonic::Response<proto::memory::IncidentList>, tonic::Status> {
+        let acting = identity::acting(&request);
+        let req = request.into_inner();
+        let requester = access::requester(&acting);
+        let collections = vec!["incidents".to_string()];
+        if !self.access.allows(requester, "incidents", &[]) {
+            self.audit(
+                requester,
+                "list_incidents",
+                "",
+                &collections,
+                vec![],
+                vec!["incidents".into()],
+            );
+            return Err(tonic::Status::permission_denied(format!(
+                "{requester} may not read incidents"
+            )));
+        }
+        let state = self.state.read().await;
+        let incidents = state
+            .longterm
+            .list_incidents(req.since, req.limit)
+            .map_err(|e| tonic::Status::internal(format!("Failed to list incidents: {e}")))?;
+        self.audit(
+            requester,
+            "list_incidents",
+            "",
+            &collections,
+            incidents
+                .iter()
+                .map(|i| access::record_ref("incidents", &i.id))
+                .collect(),
+            vec![],
+        );
+        Ok(tonic::Response::new(proto::memory::IncidentList {
+            incidents,
+        }))
+    }
+
     async fn store_config_change(
         &self,
         request: tonic::Request<proto::memory::ConfigChange>,
@@ -324,12 +955,27 @@ impl MemoryService for MemoryServiceImpl {
         &self,
         request: tonic::Request<proto::memory::SemanticSearchRequest>,
     ) -> Result<tonic::Response<proto::memory::SearchResults>, tonic::Status> {
+        let acting = identity::acting(&request);
         let req = request.into_inner();
+        let requester = access::requester(&acting);
+        let query_embedding = self.embed_query(&req.query).await;
         let state = self.state.read().await;
         let results = state
             .knowledge
-            .search(&req.query, req.n_results)
+            .search(&req.query, &query_embedding, req.n_results)
             .map_err(|e| tonic::Status::internal(format!("Knowledge search failed: {e}")))?;
+        let (results, denied) = self.access.filter(requester, results);
+        self.audit(
+            requester,
+            "search_knowledge",
+            &req.query,
+            &["knowledge".to_string()],
+            results
+                .iter()
+                .map(|r| access::record_ref(&r.collection, &r.id))
+                .collect(),
+            denied,
+        );
         Ok(tonic::Response::new(proto::memory::SearchResults {
             results,
         }))
@@ -340,10 +986,12 @@ impl MemoryService for MemoryServiceImpl {
         request: tonic::Request<proto::memory::KnowledgeEntry>,
     ) -> Result<tonic::Response<proto::memory::Empty>, tonic::Status> {
         let entry = request.into_inner();
+        let text = knowledge::entry_text(&entry.title, &entry.content, &entry.tags.join(","));
+        let vector = self.embed_document(&text).await;
         let mut state = self.state.write().await;
         state
             .knowledge
-            .add_entry(&entry)
+            .add_entry(&entry, self.embedder.id(), vector.as_deref())
             .map_err(|e| tonic::Status::internal(format!("Failed to add knowledge: {e}")))?;
         Ok(tonic::Response::new(proto::memory::Empty {}))
     }
@@ -354,134 +1002,750 @@ impl MemoryService for MemoryServiceImpl {
         &self,
         request: tonic::Request<proto::memory::ContextRequest>,
     ) -> Result<tonic::Response<proto::memory::ContextResponse>, tonic::Status> {
+        let acting = identity::acting(&request);
         let req = request.into_inner();
-        let state = self.state.read().await;
-
-        let mut chunks = Vec::new();
+        let requester = access::requester(&acting).to_string();
         let max_tokens = if req.max_tokens == 0 {
             4000
         } else {
             req.max_tokens
         };
-        let mut total_tokens = 0i32;
 
         // Gather from each requested tier
-        let tiers = if req.memory_tiers.is_empty() {
+        let tiers: Vec<String> = if req.memory_tiers.is_empty() {
             vec![
                 "operational".to_string(),
                 "working".to_string(),
                 "longterm".to_string(),
                 "knowledge".to_string(),
+                "graph".to_string(),
             ]
         } else {
             req.memory_tiers
+                .into_iter()
+                .map(|t| {
+                    if t == "long_term" {
+                        "longterm".into()
+                    } else {
+                        t
+                    }
+                })
+                .collect()
+        };
+        let searches = tiers.iter().any(|t| t == "longterm" || t == "knowledge");
+        let query = &req.task_description;
+        let query_embedding = if searches {
+            self.embed_query(query).await
+        } else {
+            Vec::new()
         };
 
-        for tier in &tiers {
-            if total_tokens >= max_tokens {
-                break;
-            }
-            let _remaining = max_tokens - total_tokens;
-
-            match tier.as_str() {
-                "operational" => {
-                    let events = state.operational.get_recent(10, "", "");
-                    for event in events {
-                        let content = String::from_utf8_lossy(&event.data_json).to_string();
-                        let tokens = estimate_tokens(&content);
-                        if total_tokens + tokens > max_tokens {
-                            break;
-                        }
-                        chunks.push(proto::memory::ContextChunk {
-                            source: "operational".into(),
-                            content,
-                            relevance: 0.8,
-                            tokens,
-                        });
-                        total_tokens += tokens;
-                    }
-                }
-                "working" => {
-                    if let Ok(goals) = state.working.get_active_goals() {
-                        for goal in goals.iter().take(5) {
+        // Candidates per tier, in tier order; records the requester may not
+        // read are withheld before fusion
+        let mut candidates: Vec<(String, Vec<proto::memory::ContextChunk>)> = Vec::new();
+        let mut denied = Vec::new();
+        let mut permitted = |results: Vec<proto::memory::SearchResult>| {
+            let (allowed, withheld) = self.access.filter(&requester, results);
+            denied.extend(withheld);
+            allowed
+        };
+        {
+            let state = self.state.read().await;
+            for tier in &tiers {
+                let chunks = match tier.as_str() {
+                    "operational" => state
+                        .operational
+                        .get_recent(10, "", "")
+                        .into_iter()
+                        .map(|event| {
+                            let content = String::from_utf8_lossy(&event.data_json).to_string();
+                            retrieval::unranked_chunk(tier, "events", &event.id, content, 0.8)
+                        })
+                        .collect(),
+                    "working" => state
+                        .working
+                        .get_active_goals()
+                        .unwrap_or_default()
+                        .iter()
+                        .take(5)
+                        .map(|goal| {
                             let content = format!(
                                 "Goal [{}]: {} (status: {})",
                                 goal.id, goal.description, goal.status
                             );
-                            let tokens = estimate_tokens(&content);
-                            if total_tokens + tokens > max_tokens {
-                                break;
-                            }
-                            chunks.push(proto::memory::ContextChunk {
-                                source: "working".into(),
-                                content,
-                                relevance: 0.7,
-                                tokens,
-                            });
-                            total_tokens += tokens;
-                        }
-                    }
-                }
-                "longterm" => {
-                    if let Ok(results) = state.longterm.semantic_search(
-                        &req.task_description,
-                        &["decisions".into(), "procedures".into()],
-                        5,
-                        0.3,
-                    ) {
-                        for result in results {
-                            let tokens = estimate_tokens(&result.content);
-                            if total_tokens + tokens > max_tokens {
-                                break;
-                            }
-                            chunks.push(proto::memory::ContextChunk {
-                                source: "longterm".into(),
-                                content: result.content,
-                                relevance: result.relevance,
-                                tokens,
-                            });
-                            total_tokens += tokens;
-                        }
+                            retrieval::unranked_chunk(tier, "goals", &goal.id, content, 0.7)
+                        })
+                        .collect(),
+                    "longterm" => {
+                        let collections = ["decisions".to_string(), "procedures".to_string()];
+                        let fts = permitted(
+                            state
+                                .longterm
+                                .fts_search(query, &collections, retrieval::CANDIDATES_PER_TIER)
+                                .unwrap_or_default(),
+                        );
+                        let vector = permitted(
+                            state
+                                .longterm
+                                .semantic_search(
+                                    query,
+                                    &query_embedding,
+                                    &collections,
+                                    retrieval::CANDIDATES_PER_TIER,
+                                    0.3,
+                                )
+                                .unwrap_or_default(),
+                        );
+                        retrieval::fuse(tier, &[("fts", fts), ("vector", vector)])
                     }
-                }
-                "knowledge" => {
-                    if let Ok(results) = state.knowledge.search(&req.task_description, 5) {
-                        for result in results {
-                            let tokens = estimate_tokens(&result.content);
-                            if total_tokens + tokens > max_tokens {
-                                break;
-                            }
-                            chunks.push(proto::memory::ContextChunk {
-                                source: "knowledge".into(),
-                                content: result.content,
-                                relevance: result.relevance,
-                                tokens,
-                            });
-                            total_tokens += tokens;
-                        }
+                    "knowledge" => {
+                        let fts = permitted(
+                            state
+                                .knowledge
+                                .fts_search(query, retrieval::CANDIDATES_PER_TIER)
+                                .unwrap_or_default(),
+                        );
+                        let vector = permitted(
+                            state
+                                .knowledge
+                                .search(query, &query_embedding, retrieval::CANDIDATES_PER_TIER)
+                                .unwrap_or_default(),
+                        );
+                        retrieval::fuse(tier, &[("fts", fts), ("vector", vector)])
                     }
-                }
-                _ => {}
+                    "graph" => graph_chunks(&state.graph, query).unwrap_or_default(),
+                    _ => continue,
+                };
+                candidates.push((tier.clone(), chunks));
+            }
+        }
+
+        self.rerank(query, &mut candidates).await;
+
+        // Near-identical chunks keep their first (higher priority) copy, then
+        // the budget is filled in tier order
+        let ordered = candidates
+            .into_iter()
+            .flat_map(|(_, chunks)| chunks)
+            .collect();
+        let mut chunks = Vec::new();
+        let mut total_tokens = 0i32;
+        for chunk in retrieval::dedupe(ordered, retrieval::DUPLICATE_THRESHOLD) {
+            if total_tokens >= max_tokens {
+                break;
+            }
+            if total_tokens + chunk.tokens > max_tokens {
+                continue;
             }
+            total_tokens += chunk.tokens;
+            chunks.push(chunk);
         }
 
         // Sort by relevance
-        chunks.sort_by(|a, b| {
-            b.relevance
-                .partial_cmp(&a.relevance)
-                .unwrap_or(std::cmp::Ordering::Equal)
-        });
+        retrieval::sort_by_relevance(&mut chunks);
+
+        // A record found by both retrievers is withheld once
+        denied.sort();
+        denied.dedup();
+        self.audit(
+            &requester,
+            "assemble_context",
+            query,
+            &tiers,
+            chunks
+                .iter()
+                .filter_map(|c| c.provenance.as_ref())
+                .map(|p| access::record_ref(&p.collection, &p.record_id))
+                .collect(),
+            denied,
+        );
 
         Ok(tonic::Response::new(proto::memory::ContextResponse {
             chunks,
             total_tokens,
         }))
     }
+
+    // --- Knowledge Graph ---
+
+    async fn graph_query(
+        &self,
+        request: tonic::Request<proto::memory::GraphQueryRequest>,
+    ) -> Result<tonic::Response<proto::memory::GraphQueryResponse>, tonic::Status> {
+        let acting = identity::acting(&request);
+        let req = request.into_inner();
+        let requester = access::requester(&acting);
+        let direction = graph::Direction::parse(&req.direction);
+        let state = self.state.read().await;
+        let internal =
+            |e: anyhow::Error| tonic::Status::internal(format!("Graph query failed: {e}"));
+
+        let entity = state
+            .graph
+            .resolve(&req.entity)
+            .map_err(internal)?
+            .ok_or_else(|| tonic::Status::not_found(format!("Unknown entity: {}", req.entity)))?;
+        let response = match req.mode.as_str() {
+            "path" => {
+                let target = state
+                    .graph
+                    .resolve(&req.target)
+                    .map_err(internal)?
+                    .ok_or_else(|| {
+                        tonic::Status::not_found(format!("Unknown entity: {}", req.target))
+                    })?;
+                let depth = if req.max_depth <= 0 {
+                    graph::MAX_DEPTH
+                } else {
+                    req.max_depth
+                };
+                let path = state
+                    .graph
+                    .path(&entity, &target, direction, depth)
+                    .map_err(internal)?;
+                proto::memory::GraphQueryResponse {
+                    entities: Vec::new(),
+                    relations: Vec::new(),
+                    path,
+                }
+            }
+            "" | "neighbors" => {
+                let depth = if req.max_depth <= 0 { 1 } else { req.max_depth };
+                let (entities, relations) = state
+                    .graph
+                    .neighbors(&entity, direction, &req.relation, depth)
+                    .map_err(internal)?;
+                proto::memory::GraphQueryResponse {
+                    entities,
+                    relations,
+                    path: Vec::new(),
+                }
+            }
+            other => {
+                return Err(tonic::Status::invalid_argument(format!(
+                    "Unknown graph query mode '{other}' (expected neighbors or path)"
+                )))
+            }
+        };
+
+        let records = response
+            .entities
+            .iter()
+            .map(|e| e.id.as_str())
+            .chain(response.path.iter().map(String::as_str))
+            .map(|id| access::record_ref("graph", id))
+            .collect();
+        self.audit(
+            requester,
+            "graph_query",
+            format!("{} {} {}", req.mode, req.entity, req.target).trim(),
+            &["graph".to_string()],
+            records,
+            Vec::new(),
+        );
+        Ok(tonic::Response::new(response))
+    }
+
+    // --- Retention ---
+
+    async fn erase_subject(
+        &self,
+        request: tonic::Request<proto::memory::EraseSubjectRequest>,
+    ) -> Result<tonic::Response<proto::memory::ErasureReport>, tonic::Status> {
+        let acting = identity::acting(&request);
+        let req = request.into_inner();
+        let requester = access::requester(&acting);
+        let pattern = retention::normalize_pattern(&req.pattern);
+        let digest = retention::pattern_digest(&req.pattern);
+        // The log names the subject by digest only
+        let query = format!("sha256:{digest} reason:{}", req.reason);
+        let collections = vec![access::ERASURE_COLLECTION.to_string()];
+        if !self
+            .access
+            .allows(requester, access::ERASURE_COLLECTION, &[])
+        {
+            self.audit(
+                requester,
+                "erase_subject",
+                &query,
+                &collections,
+                vec![],
+                collections.clone(),
+            );
+            return Err(tonic::Status::permission_denied(format!(
+                "{requester} may not erase records"
+            )));
+        }
+        if pattern.chars().count() < retention::MIN_PATTERN_LEN {
+            return Err(tonic::Status::invalid_argument(format!(
+                "Erasure pattern must be at least {} characters",
+                retention::MIN_PATTERN_LEN
+            )));
+        }
+
+        let erasure_id = uuid::Uuid::new_v4().to_string();
+        let mut erased = Vec::new();
+        let mut failures = Vec::new();
+        let remaining = {
+            let mut state = self.state.write().await;
+            let results = [
+                ("operational", state.operational.erase_subject(&pattern)),
+                ("working", state.working.erase_subject(&pattern)),
+                ("longterm", state.longterm.erase_subject(&pattern)),
+                ("knowledge", state.knowledge.erase_subject(&pattern)),
+                ("graph", state.graph.erase_subject(&pattern)),
+                ("access_log", self.access_log.erase_subject(&pattern)),
+            ];
+            for (store, result) in results {
+                match result {
+                    Ok(tables) => erased.extend(tables.into_iter().map(|(table, n)| {
+                        proto::memory::ErasedRecords {
+                            store: store.to_string(),
+                            table,
+                            removed: n as u64,
+                        }
+                    })),
+                    Err(e) => failures.push(format!("{store}: {e}")),
+                }
+            }
+            // Search every store again: the proof that nothing is left
+            let counts = [
+                state.operational.count_subject(&pattern),
+                state.working.count_subject(&pattern),
+                state.longterm.count_subject(&pattern),
+                state.knowledge.count_subject(&pattern),
+                state.graph.count_subject(&pattern),
+                self.access_log.count_subject(&pattern),
+            ];
+            let mut remaining = 0;
+            for count in counts {
+                match count {
+                    Ok(n) => remaining += n as u64,
+                    Err(e) => failures.push(format!("verification: {e}")),
+                }
+            }
+            remaining
+        };
+
+        let records: Vec<String> = std::iter::once(format!("erasure/{erasure_id}"))
+            .chain(
+                erased
+                    .iter()
+                    .filter(|e| e.removed > 0)
+                    .map(|e| format!("{}/{}: {}", e.store, e.table, e.removed)),
+            )
+            .collect();
+        self.audit(
+            requester,
+            "erase_subject",
+            &query,
+            &collections,
+            records,
+            failures.clone(),
+        );
+        let total: u64 = erased.iter().map(|e| e.removed).sum();
+        if !failures.is_empty() {
+            warn!(
+                "Erasure {erasure_id} by {requester} incomplete: {}",
+                failures.join("; ")
+            );
+            return Err(tonic::Status::internal(format!(
+                "Erasure {erasure_id} incomplete after removing {total} records: {}",
+                failures.join("; ")
+            )));
+        }
+        info!("Erasure {erasure_id} by {requester} removed {total} records, {remaining} matches remain");
+
+        Ok(tonic::Response::new(proto::memory::ErasureReport {
+            erasure_id,
+            pattern_sha256: digest,
+            erased,
+            remaining,
+            verified: remaining == 0,
+            timestamp: chrono::Utc::now().timestamp(),
+        }))
+    }
+
+    // --- Debugging and backup ---
+
+    async fn list_patterns(
+        &self,
+        request: tonic::Request<proto::memory::PatternListRequest>,
+    ) -> Result<tonic::Response<proto::memory::PatternList>, tonic::Status> {
+        let req = request.into_inner();
+        let state = self.state.read().await;
+        let patterns = state
+            .working
+            .list_patterns(req.limit)
+            .map_err(|e| tonic::Status::internal(format!("Failed to list patterns: {e}")))?;
+        Ok(tonic::Response::new(proto::memory::PatternList {
+            patterns,
+        }))
+    }
+
+    async fn dump_collection(
+        &self,
+        request: tonic::Request<proto::memory::CollectionDumpRequest>,
+    ) -> Result<tonic::Response<proto::memory::CollectionDump>, tonic::Status> {
+        let acting = identity::acting(&request);
+        let req = request.into_inner();
+        let requester = access::requester(&acting);
+        let collections = vec![req.collection.clone()];
+        if !self.access.allows(requester, &req.collection, &[]) {
+            self.audit(
+                requester,
+                "dump_collection",
+                "",
+                &collections,
+                vec![],
+                vec![req.collection.clone()],
+            );
+            return Err(tonic::Status::permission_denied(format!(
+                "{requester} may not read {}",
+                req.collection
+            )));
+        }
+        let state = self.state.read().await;
+        let records = if dump::LONGTERM_COLLECTIONS.contains(&req.collection.as_str()) {
+            state.longterm.dump(&req.collection)
+        } else {
+            state.working.dump(&req.collection)
+        }
+        .map_err(|e| tonic::Status::invalid_argument(format!("Failed to dump: {e}")))?;
+        self.audit(
+            requester,
+            "dump_collection",
+            "",
+            &collections,
+            vec![format!("{} records", records.len())],
+            vec![],
+        );
+        let records_json = serde_json::to_vec(&records)
+            .map_err(|e| tonic::Status::internal(format!("Failed to encode dump: {e}")))?;
+        Ok(tonic::Response::new(proto::memory::CollectionDump {
+            collection: req.collection,
+            records_json,
+            requesting_agent: req.requesting_agent,
+        }))
+    }
+
+    async fn restore_collection(
+        &self,
+        request: tonic::Request<proto::memory::CollectionDump>,
+    ) -> Result<tonic::Response<proto::memory::RestoreResult>, tonic::Status> {
+        let acting = identity::acting(&request);
+        let dump = request.into_inner();
+        let requester = access::requester(&acting);
+        if !self.access.allows(requester, &dump.collection, &[]) {
+            return Err(tonic::Status::permission_denied(format!(
+                "{requester} may not restore {}",
+                dump.collection
+            )));
+        }
+        let records: Vec<serde_json::Value> = serde_json::from_slice(&dump.records_json)
+            .map_err(|e| tonic::Status::invalid_argument(format!("Invalid dump: {e}")))?;
+        let state = self.state.read().await;
+        let restored = if dump::LONGTERM_COLLECTIONS.contains(&dump.collection.as_str()) {
+            state.longterm.restore(&dump.collection, &records)
+        } else {
+            state.working.restore(&dump.collection, &records)
+        }
+        .map_err(|e| tonic::Status::invalid_argument(format!("Failed to restore: {e}")))?;
+        info!(
+            "{requester} restored {restored} records into {}",
+            dump.collection
+        );
+        Ok(tonic::Response::new(proto::memory::RestoreResult {
+            restored: restored as i32,
+        }))
+    }
+
+    async fn rebuild_vector_index(
+        &self,
+        request: tonic::Request<proto::memory::RebuildVectorIndexRequest>,
+    ) -> Result<tonic::Response<proto::memory::VectorIndexStats>, tonic::Status> {
+        let acting = identity::acting(&request);
+        let requester = access::requester(&acting);
+        if !self.access.allows(requester, "procedures", &[]) {
+            return Err(tonic::Status::permission_denied(format!(
+                "{requester} may not rebuild the procedures index"
+            )));
+        }
+        let started = std::time::Instant::now();
+        // Rebuilding reads every stored vector; keep it off the async workers
+        let state = self.state.clone().read_owned().await;
+        let stats = tokio::task::spawn_blocking(move || state.longterm.rebuild_index())
+            .await
+            .map_err(|e| tonic::Status::internal(format!("Index rebuild panicked: {e}")))?
+            .map_err(|e| tonic::Status::internal(format!("Failed to rebuild index: {e}")))?;
+        let duration_ms = started.elapsed().as_millis() as i64;
+        info!(
+            "{requester} rebuilt the procedure vector index: {} entries in {duration_ms}ms",
+            stats.entries
+        );
+        Ok(tonic::Response::new(proto::memory::VectorIndexStats {
+            entries: stats.entries as i64,
+            dimensions: stats.dimensions as i32,
+            duration_ms,
+        }))
+    }
+
+    // --- Access Audit ---
+
+    async fn get_access_log(
+        &self,
+        request: tonic::Request<proto::memory::AccessLogRequest>,
+    ) -> Result<tonic::Response<proto::memory::AccessLogEntries>, tonic::Status> {
+        let acting = identity::acting(&request);
+        let req = request.into_inner();
+        let requester = access::requester(&acting);
+        if !self
+            .access
+            .allows(requester, access::ACCESS_LOG_COLLECTION, &[])
+        {
+            return Err(tonic::Status::permission_denied(format!(
+                "{requester} may not read the access log"
+            )));
+        }
+        let entries = self
+            .access_log
+            .query(&req)
+            .map_err(|e| tonic::Status::internal(format!("Access log query failed: {e}")))?;
+        Ok(tonic::Response::new(proto::memory::AccessLogEntries {
+            entries,
+        }))
+    }
+
+    async fn get_api_version(
+        &self,
+        _request: tonic::Request<proto::common::Empty>,
+    ) -> Result<tonic::Response<proto::common::ApiVersion>, tonic::Status> {
+        Ok(tonic::Response::new(api_version::api_version(
+            proto::memory::memory_service_server::SERVICE_NAME,
+        )))
+    }
+
+    async fn get_build_info(
+        &self,
+        _request: tonic::Request<proto::common::Empty>,
+    ) -> Result<tonic::Response<proto::common::BuildInfo>, tonic::Status> {
+        Ok(tonic::Response::new(build_info::build_info(
+            proto::memory::memory_service_server::SERVICE_NAME,
+            Vec::new(),
+        )))
+    }
+}
+
+/// Relationship facts for the entities a task mentions
+fn graph_chunks(
+    graph: &graph::KnowledgeGraph,
+    task: &str,
+) -> Result<Vec<proto::memory::ContextChunk>> {
+    let mut chunks = Vec::new();
+    for entity in graph.mentioned(task, 3)? {
+        let (entities, relations) = graph.neighbors(&entity, graph::Direction::Both, "", 2)?;
+        if relations.is_empty() {
+            continue;
+        }
+        let content = graph::describe(&entity, &entities, &relations);
+        let mut chunk = retrieval::unranked_chunk("graph", "entities", &entity, content, 0.9);
+        if let Some(provenance) = chunk.provenance.as_mut() {
+            provenance.retrievers = vec!["graph".into()];
+        }
+        chunks.push(chunk);
+    }
+    Ok(chunks)
+}
+
+/// Bus messages a subscription reads, and buffers for its client, at a time
+const BUS_BATCH: usize = 100;
+
+/// Drop event bus messages past `AIOS_EVENT_BUS_RETENTION_HOURS` (default
+/// 72), hourly
+async fn prune_event_bus(bus: Arc<bus::EventBus>) {
+    let retention_hours: i64 = std::env::var("AIOS_EVENT_BUS_RETENTION_HOURS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(72);
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+    loop {
+        interval.tick().await;
+        let before = chrono::Utc::now().timestamp() - retention_hours * 3600;
+        match bus.prune(before) {
+            Ok(0) => {}
+            Ok(n) => info!("Pruned {n} event bus messages older than {retention_hours} hours"),
+            Err(e) => warn!("Event bus pruning failed: {e}"),
+        }
+    }
+}
+
+/// Drop access log entries past the retention period
+/// (`AIOS_ACCESS_LOG_RETENTION_DAYS`, default 90), hourly
+async fn prune_access_log(log: Arc<access::AccessLog>) {
+    let retention_days: i64 = std::env::var("AIOS_ACCESS_LOG_RETENTION_DAYS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(90);
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+    loop {
+        interval.tick().await;
+        let before = chrono::Utc::now().timestamp() - retention_days * 86_400;
+        match log.prune(before) {
+            Ok(0) => {}
+            Ok(n) => info!("Pruned {n} access log entries older than {retention_days} days"),
+            Err(e) => warn!("Access log pruning failed: {e}"),
+        }
+    }
+}
+
+/// Write the procedure vector index to disk every few minutes when it has
+/// changed, so a restart does not have to rebuild it
+async fn persist_vector_index(state: Arc<RwLock<MemoryState>>) {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(300));
+    loop {
+        interval.tick().await;
+        let state = state.clone().read_owned().await;
+        match tokio::task::spawn_blocking(move || state.longterm.save_index()).await {
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => warn!("Saving the vector index failed: {e}"),
+            Err(e) => warn!("Saving the vector index panicked: {e}"),
+        }
+    }
+}
+
+/// Expire records past their category's retention period (retention.toml)
+/// from the working, long-term and knowledge tiers
+async fn expire_records(state: Arc<RwLock<MemoryState>>, config: retention::RetentionConfig) {
+    let period = std::time::Duration::from_secs(config.interval_mins.max(1) * 60);
+    let mut interval = tokio::time::interval(period);
+    loop {
+        interval.tick().await;
+        let now = chrono::Utc::now().timestamp();
+        let state = state.read().await;
+        for category in retention::Category::ALL {
+            let Some(before) = config.cutoff(category, now) else {
+                continue;
+            };
+            let results = [
+                state.working.expire(category, before),
+                state.longterm.expire(category, before),
+                state.knowledge.expire(category, before),
+            ];
+            let mut removed = 0;
+            for result in results {
+                match result {
+                    Ok(n) => removed += n,
+                    Err(e) => warn!("Expiring {} records failed: {e}", category.name()),
+                }
+            }
+            if removed > 0 {
+                info!(
+                    "Expired {removed} {} records older than {} days",
+                    category.name(),
+                    config.days(category)
+                );
+            }
+        }
+    }
 }
 
-/// Rough token estimation (4 chars per token)
-fn estimate_tokens(text: &str) -> i32 {
-    (text.len() as f64 / 4.0).ceil() as i32
+/// Roll raw metric samples older than `AIOS_METRIC_RAW_RETENTION_HOURS`
+/// (default 24) into one-minute buckets and drop history older than
+/// `AIOS_METRIC_RETENTION_DAYS` (default 30), hourly
+async fn compact_metrics(history: Arc<metrics::MetricHistory>) {
+    let env_i64 = |name: &str, default: i64| {
+        std::env::var(name)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default)
+    };
+    let raw_hours = env_i64("AIOS_METRIC_RAW_RETENTION_HOURS", 24);
+    let retention_days = env_i64("AIOS_METRIC_RETENTION_DAYS", 30);
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+    loop {
+        interval.tick().await;
+        let now = chrono::Utc::now().timestamp();
+        match history.compact(now - raw_hours * 3600, now - retention_days * 86_400) {
+            Ok(0) => {}
+            Ok(n) => info!("Compacted {n} metric history rows"),
+            Err(e) => warn!("Metric history compaction failed: {e}"),
+        }
+    }
+}
+
+/// Publish event buffer occupancy as `memory.events.*` metrics each minute,
+/// warn when it passes `AIOS_EVENT_HIGH_WATER` (fraction of capacity,
+/// default 0.8) or drops events, and prune the spill store hourly
+async fn monitor_event_buffer(
+    state: Arc<RwLock<MemoryState>>,
+    history: Arc<metrics::MetricHistory>,
+) {
+    let high_water: f64 = std::env::var("AIOS_EVENT_HIGH_WATER")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0.8);
+    let spill_hours: i64 = std::env::var("AIOS_EVENT_SPILL_RETENTION_HOURS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(24);
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+    let mut dropped_before = 0;
+    let mut ticks: u64 = 0;
+    loop {
+        interval.tick().await;
+        let now = chrono::Utc::now().timestamp();
+        let mut state = state.write().await;
+        let stats = state.operational.take_stats();
+
+        if stats.high_water as f64 >= stats.capacity as f64 * high_water {
+            warn!(
+                "Event buffer reached {}/{} entries; raise AIOS_EVENT_BUFFER_CAPACITY or enable AIOS_EVENT_SPILL_DB",
+                stats.high_water, stats.capacity
+            );
+        }
+        if stats.dropped > dropped_before {
+            warn!(
+                "Event buffer dropped {} events in the last minute",
+                stats.dropped - dropped_before
+            );
+        }
+        dropped_before = stats.dropped;
+
+        let mut values = vec![
+            ("memory.events.capacity".to_string(), stats.capacity as f64),
+            ("memory.events.buffered".to_string(), stats.buffered as f64),
+            (
+                "memory.events.high_water".to_string(),
+                stats.high_water as f64,
+            ),
+            ("memory.events.dropped".to_string(), stats.dropped as f64),
+            ("memory.events.spilled".to_string(), stats.spilled as f64),
+        ];
+        for (consumer, lag) in &stats.consumer_lag {
+            values.push((format!("memory.events.lag.{consumer}"), *lag as f64));
+        }
+        for (key, value) in values {
+            if let Err(e) = history.record(&key, value, now) {
+                warn!("Failed to record history for metric {key}: {e}");
+            }
+            state
+                .operational
+                .update_metric(proto::memory::MetricUpdate {
+                    key,
+                    value,
+                    timestamp: now,
+                });
+        }
+
+        if ticks.is_multiple_of(60) {
+            match state.operational.prune_spill(now - spill_hours * 3600) {
+                Ok(0) => {}
+                Ok(n) => info!("Pruned {n} spilled events older than {spill_hours} hours"),
+                Err(e) => warn!("Event spill pruning failed: {e}"),
+            }
+        }
+        ticks += 1;
+    }
 }
 
 #[tokio::main]
@@ -498,21 +1762,111 @@ async fn main() -> Result<()> {
         .unwrap_or_else(|_| "/var/lib/aios/memory/working.db".into());
     let longterm_db = std::env::var("AIOS_LONGTERM_DB")
         .unwrap_or_else(|_| "/var/lib/aios/memory/longterm.db".into());
+    let access_db = std::env::var("AIOS_ACCESS_LOG_DB")
+        .unwrap_or_else(|_| "/var/lib/aios/memory/access.db".into());
+    let metrics_db = std::env::var("AIOS_METRICS_DB")
+        .unwrap_or_else(|_| "/var/lib/aios/memory/metrics.db".into());
+    let graph_db =
+        std::env::var("AIOS_GRAPH_DB").unwrap_or_else(|_| "/var/lib/aios/memory/graph.db".into());
+    let bus_db =
+        std::env::var("AIOS_EVENT_BUS_DB").unwrap_or_else(|_| "/var/lib/aios/memory/bus.db".into());
+    let identity_db = std::env::var("AIOS_IDENTITY_DB")
+        .unwrap_or_else(|_| "/var/lib/aios/memory/identity.db".into());
+    let capacity: usize = std::env::var("AIOS_EVENT_BUFFER_CAPACITY")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(10000);
+    let mut operational = operational::OperationalMemory::new(capacity);
+    if let Ok(spill_db) = std::env::var("AIOS_EVENT_SPILL_DB") {
+        operational = operational.with_spill(spill::EventSpill::new(&spill_db)?)?;
+        info!("Event buffer overflow spills to {spill_db}");
+    }
+    let hostname = std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .map(|h| h.trim().to_string())
+        .ok()
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| "localhost".into());
 
     let state = Arc::new(RwLock::new(MemoryState {
-        operational: operational::OperationalMemory::new(10000),
+        operational,
         working: working::WorkingMemory::new(&working_db)?,
         longterm: longterm::LongTermMemory::new(&longterm_db)?,
         knowledge: knowledge::KnowledgeBase::new()?,
+        graph: graph::KnowledgeGraph::new(&graph_db, &hostname)?,
     }));
 
-    let service = MemoryServiceImpl { state };
+    let embedder = match embedding::Embedder::from_config(&embedding::EmbeddingConfig::from_env()) {
+        Ok(embedder) => embedder,
+        Err(e) => {
+            warn!("Embedding provider unavailable, using hashed embeddings: {e}");
+            embedding::Embedder::Hash
+        }
+    };
+    let embedder = Arc::new(embedder);
+    info!("Embedding provider: {}", embedder.id());
+    tokio::spawn(embedding::reembed_stale(state.clone(), embedder.clone()));
+    match state.read().await.longterm.index_stats() {
+        Ok(stats) => info!(
+            "Procedure vector index: {} entries, {} dimensions",
+            stats.entries, stats.dimensions
+        ),
+        Err(e) => warn!("Procedure vector index unavailable: {e}"),
+    }
+    tokio::spawn(persist_vector_index(state.clone()));
+    tokio::spawn(expire_records(
+        state.clone(),
+        retention::RetentionConfig::load(retention::RETENTION_CONFIG_PATH),
+    ));
+
+    let reranker = match rerank::Reranker::from_config(&rerank::RerankConfig::from_env()) {
+        Ok(reranker) => reranker,
+        Err(e) => {
+            warn!("Reranker unavailable, keeping fused retrieval order: {e}");
+            rerank::Reranker::None
+        }
+    };
+    info!("Context reranker: {}", reranker.name());
+
+    let access_log = Arc::new(access::AccessLog::new(&access_db)?);
+    tokio::spawn(prune_access_log(access_log.clone()));
+
+    let metrics = Arc::new(metrics::MetricHistory::new(&metrics_db)?);
+    tokio::spawn(compact_metrics(metrics.clone()));
+    tokio::spawn(monitor_event_buffer(state.clone(), metrics.clone()));
+
+    let bus = Arc::new(bus::EventBus::new(&bus_db)?);
+    tokio::spawn(prune_event_bus(bus.clone()));
+
+    let bootstrap_path = std::env::var("AIOS_IDENTITY_BOOTSTRAP_TOKEN_PATH")
+        .unwrap_or_else(|_| identity::DEFAULT_BOOTSTRAP_TOKEN_PATH.into());
+    let mut identities = identity::IdentityRegistry::new(&identity_db)?;
+    match identity::bootstrap_token(&bootstrap_path) {
+        Ok(token) => identities = identities.with_bootstrap_token(&token),
+        Err(e) => warn!("No identity bootstrap token in {bootstrap_path}: {e}"),
+    }
+    let identities = Arc::new(identities);
+
+    let service = MemoryServiceImpl {
+        state,
+        embedder,
+        reranker,
+        access: access::AccessPolicy::load(access::ACCESS_POLICY_PATH),
+        access_log,
+        metrics,
+        events: events::EventRegistry::load(events::EVENT_SCHEMAS_PATH),
+        bus,
+        identities: identities.clone(),
+    };
 
     let addr: SocketAddr = "0.0.0.0:50053".parse()?;
     info!("Memory Service gRPC server listening on {addr}");
 
     Server::builder()
-        .add_service(MemoryServiceServer::new(service))
+        .layer(api_version::legacy_shim())
+        .add_service(MemoryServiceServer::with_interceptor(
+            service,
+            identity::IdentityCheck::new(identities),
+        ))
         .serve(addr)
         .await
         .context("Memory Service gRPC server failed")?;
@@ -0,0 +1,372 @@
+//! aiOS Memory Control — query the memory tiers directly
+//!
+//! A debugging client for the memory service: shows what each tier holds
+//! (recent events, active goals, semantic search, learned patterns), dumps
+//! and restores collections, erases a subject's records, rebuilds the
+//! procedure vector index, and tails the event stream. Useful when context assembly fed the model the wrong facts
+//! and you need to see what it had to choose from.
+//!
+//! Run with a command for one-shot use, or without arguments for a REPL
+//! reading commands from stdin. The service address comes from
+//! `AIOS_MEMORY_ADDR`. Calls are made as `service:memctl` unless
+//! `--as <identity>` is given (a bare name is an agent), with the token in
+//! `AIOS_IDENTITY_TOKEN`; access policies and the audit log apply, and
+//! only a call carrying the identity's token is granted that identity's
+//! reads.
+
+use anyhow::{bail, Context, Result};
+use std::io::{BufRead, Write};
+use std::time::Duration;
+
+pub mod proto {
+    pub mod common {
+        tonic::include_proto!("aios.v1.common");
+    }
+    pub mod memory {
+        tonic::include_proto!("aios.v1.memory");
+    }
+}
+
+use proto::memory::memory_service_client::MemoryServiceClient;
+use tonic::service::interceptor::InterceptedService;
+use tonic::transport::Channel;
+
+type Client = MemoryServiceClient<InterceptedService<Channel, ActAs>>;
+
+/// Names the identity calls are made as, with its token when given
+#[derive(Clone)]
+struct ActAs {
+    id: tonic::metadata::AsciiMetadataValue,
+    token: Option<tonic::metadata::AsciiMetadataValue>,
+}
+
+impl tonic::service::Interceptor for ActAs {
+    fn call(
+        &mut self,
+        mut request: tonic::Request<()>,
+    ) -> Result<tonic::Request<()>, tonic::Status> {
+        let metadata = request.metadata_mut();
+        metadata.insert("x-aios-identity", self.id.clone());
+        if let Some(token) = &self.token {
+            metadata.insert("x-aios-identity-token", token.clone());
+        }
+        Ok(request)
+    }
+}
+
+/// `<kind>:<name>` for `--as`; a bare name is an agent
+fn identity_id(name: &str) -> String {
+    if name.contains(':') {
+        name.to_string()
+    } else {
+        format!("agent:{name}")
+    }
+}
+
+const USAGE: &str = "Usage: aios-memctl [--as <identity>] [command]
+
+Commands:
+  events [n] [category]          Recent events, newest last
+  goals                          Active goals
+  search <query> [collections]   Semantic search (collections comma separated)
+  patterns [limit]               Learned patterns, most used first
+  dump <collection> [file]       Write a collection as JSON (stdout without file)
+  restore <collection> <file>    Insert or replace records from a dump
+  erase <pattern> [reason]       Delete every record mentioning pattern
+  reindex                        Rebuild the procedure vector index
+  tail [consumer]                Follow the event stream
+  help                           This text
+
+Without a command, reads commands from stdin.";
+
+/// Poll interval of `tail` once it has caught up
+const TAIL_POLL: Duration = Duration::from_secs(1);
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let mut id = "service:memctl".to_string();
+    if let Some(i) = args.iter().position(|a| a == "--as") {
+        if i + 1 >= args.len() {
+            bail!("--as needs an identity");
+        }
+        id = identity_id(&args.remove(i + 1));
+        args.remove(i);
+    }
+    let agent = id
+        .split_once(':')
+        .map_or(id.as_str(), |(_, name)| name)
+        .to_string();
+    if args.iter().any(|a| a == "-h" || a == "--help") {
+        println!("{USAGE}");
+        return Ok(());
+    }
+
+    let addr =
+        std::env::var("AIOS_MEMORY_ADDR").unwrap_or_else(|_| "http://127.0.0.1:50053".to_string());
+    let channel = Channel::from_shared(addr.clone())?
+        .connect()
+        .await
+        .with_context(|| format!("Cannot connect to the memory service at {addr}"))?;
+    let act_as = ActAs {
+        id: id.parse().context("Invalid identity")?,
+        token: match std::env::var("AIOS_IDENTITY_TOKEN") {
+            Ok(token) => Some(
+                token
+                    .trim()
+                    .parse()
+                    .context("Invalid AIOS_IDENTITY_TOKEN")?,
+            ),
+            Err(_) => None,
+        },
+    };
+    let mut client = MemoryServiceClient::with_interceptor(channel, act_as);
+
+    if !args.is_empty() {
+        return run(&mut client, &agent, &args).await;
+    }
+
+    // REPL: one command per line, errors reported and the loop continues
+    let stdin = std::io::stdin();
+    loop {
+        print!("memctl> ");
+        std::io::stdout().flush()?;
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        let words: Vec<String> = line.split_whitespace().map(String::from).collect();
+        match words.first().map(String::as_str) {
+            None => continue,
+            Some("quit" | "exit") => return Ok(()),
+            Some(_) => {
+                if let Err(e) = run(&mut client, &agent, &words).await {
+                    eprintln!("error: {e:#}");
+                }
+            }
+        }
+    }
+}
+
+async fn run(client: &mut Client, agent: &str, args: &[String]) -> Result<()> {
+    let arg = |i: usize| args.get(i).map(String::as_str);
+    match args[0].as_str() {
+        "events" => {
+            let count = arg(1).map(str::parse).transpose()?.unwrap_or(20);
+            let events = client
+                .get_recent_events(proto::memory::RecentEventsRequest {
+                    count,
+                    category: arg(2).unwrap_or_default().to_string(),
+                    ..Default::default()
+                })
+                .await?
+                .into_inner()
+                .events;
+            for event in events.iter().rev() {
+                print_event(None, event);
+            }
+        }
+        "goals" => {
+            let goals = client
+                .get_active_goals(proto::memory::Empty {})
+                .await?
+                .into_inner()
+                .goals;
+            for goal in goals {
+                println!(
+                    "{}  [{}] p{}  {}  {}",
+                    goal.id,
+                    goal.status,
+                    goal.priority,
+                    timestamp(goal.created_at),
+                    goal.description
+                );
+            }
+        }
+        "search" => {
+            let Some(query) = arg(1) else {
+                bail!("search needs a query");
+            };
+            let collections = arg(2)
+                .map(|c| c.split(',').map(String::from).collect())
+                .unwrap_or_default();
+            let results = client
+                .semantic_search(proto::memory::SemanticSearchRequest {
+                    query: query.to_string(),
+                    collections,
+                    n_results: 10,
+                    min_relevance: 0.0,
+                    requesting_agent: agent.to_string(),
+                })
+                .await?
+                .into_inner()
+                .results;
+            for result in results {
+                println!(
+                    "{:.3}  {}/{}  {}",
+                    result.relevance, result.collection, result.id, result.content
+                );
+            }
+        }
+        "patterns" => {
+            let limit = arg(1).map(str::parse).transpose()?.unwrap_or(0);
+            let patterns = client
+                .list_patterns(proto::memory::PatternListRequest { limit })
+                .await?
+                .into_inner()
+                .patterns;
+            for p in patterns {
+                println!(
+                    "{:>5} uses  {:>5.1}%  {}  {} => {}",
+                    p.uses,
+                    p.success_rate * 100.0,
+                    p.id,
+                    p.trigger,
+                    p.action
+                );
+            }
+        }
+        "dump" => {
+            let Some(collection) = arg(1) else {
+                bail!("dump needs a collection");
+            };
+            let dump = client
+                .dump_collection(proto::memory::CollectionDumpRequest {
+                    collection: collection.to_string(),
+                    requesting_agent: agent.to_string(),
+                })
+                .await?
+                .into_inner();
+            let records: serde_json::Value = serde_json::from_slice(&dump.records_json)?;
+            let pretty = serde_json::to_string_pretty(&records)?;
+            match arg(2) {
+                Some(file) => {
+                    std::fs::write(file, pretty)?;
+                    let count = records.as_array().map_or(0, Vec::len);
+                    println!("Wrote {count} records of {collection} to {file}");
+                }
+                None => println!("{pretty}"),
+            }
+        }
+        "restore" => {
+            let (Some(collection), Some(file)) = (arg(1), arg(2)) else {
+                bail!("restore needs a collection and a file");
+            };
+            let records_json =
+                std::fs::read(file).with_context(|| format!("Cannot read {file}"))?;
+            let restored = client
+                .restore_collection(proto::memory::CollectionDump {
+                    collection: collection.to_string(),
+                    records_json,
+                    requesting_agent: agent.to_string(),
+                })
+                .await?
+                .into_inner()
+                .restored;
+            println!("Restored {restored} records into {collection}");
+        }
+        "erase" => {
+            let Some(pattern) = arg(1) else {
+                bail!("erase needs a pattern");
+            };
+            let report = client
+                .erase_subject(proto::memory::EraseSubjectRequest {
+                    pattern: pattern.to_string(),
+                    requesting_agent: agent.to_string(),
+                    reason: args[2..].join(" "),
+                })
+                .await?
+                .into_inner();
+            for erased in report.erased.iter().filter(|e| e.removed > 0) {
+                println!("{:>6}  {}/{}", erased.removed, erased.store, erased.table);
+            }
+            println!(
+                "Erasure {} ({}): {} matches remain{}",
+                report.erasure_id,
+                timestamp(report.timestamp),
+                report.remaining,
+                if report.verified { ", verified" } else { "" }
+            );
+        }
+        "reindex" => {
+            let stats = client
+                .rebuild_vector_index(proto::memory::RebuildVectorIndexRequest {
+                    requesting_agent: agent.to_string(),
+                })
+                .await?
+                .into_inner();
+            println!(
+                "Indexed {} procedures ({} dimensions) in {}ms",
+                stats.entries, stats.dimensions, stats.duration_ms
+            );
+        }
+        "tail" => {
+            let consumer = arg(1).unwrap_or_default().to_string();
+            // Start at the newest event unless a named cursor says otherwise
+            let mut after_seq = 0;
+            if consumer.is_empty() {
+                loop {
+                    let batch = client
+                        .read_events(proto::memory::ReadEventsRequest {
+                            after_seq,
+                            max: 1000,
+                            ..Default::default()
+                        })
+                        .await?
+                        .into_inner();
+                    after_seq = batch.cursor;
+                    if !batch.more {
+                        break;
+                    }
+                }
+            }
+            loop {
+                let batch = client
+                    .read_events(proto::memory::ReadEventsRequest {
+                        consumer: consumer.clone(),
+                        after_seq,
+                        max: 100,
+                    })
+                    .await?
+                    .into_inner();
+                if batch.lost > 0 {
+                    println!("... {} events lost", batch.lost);
+                }
+                for sequenced in &batch.events {
+                    if let Some(event) = &sequenced.event {
+                        print_event(Some(sequenced.seq), event);
+                    }
+                }
+                after_seq = batch.cursor;
+                if !batch.more {
+                    tokio::time::sleep(TAIL_POLL).await;
+                }
+            }
+        }
+        "help" => println!("{USAGE}"),
+        other => bail!("Unknown command '{other}' (try help)"),
+    }
+    Ok(())
+}
+
+fn print_event(seq: Option<u64>, event: &proto::memory::Event) {
+    let seq = seq.map(|s| format!("#{s} ")).unwrap_or_default();
+    let kind = if event.event_type.is_empty() {
+        event.category.clone()
+    } else {
+        format!("{}.{}", event.category, event.event_type)
+    };
+    println!(
+        "{seq}{}  {:<8} {kind}  {}  {}",
+        timestamp(event.timestamp),
+        event.severity,
+        event.source,
+        String::from_utf8_lossy(&event.data_json)
+    );
+}
+
+fn timestamp(secs: i64) -> String {
+    chrono::DateTime::from_timestamp(secs, 0)
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| secs.to_string())
+}
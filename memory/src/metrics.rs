@@ -0,0 +1,253 @@
+//! Metric History — retained metric samples with range queries
+//!
+//! Operational memory keeps only the latest value of each metric. Every
+//! update is also appended here: raw samples are kept for a short window
+//! (`AIOS_METRIC_RAW_RETENTION_HOURS`, default 24), then folded into
+//! one-minute rollups (count, sum, min, max, last) kept for
+//! `AIOS_METRIC_RETENTION_DAYS` (default 30). `QueryMetricRange` aggregates
+//! both into evenly spaced buckets, so consumers get a downsampled series
+//! without re-sampling raw events themselves.
+
+use anyhow::{bail, Result};
+use rusqlite::{params, Connection};
+use std::sync::Mutex;
+
+use crate::proto::memory::MetricPoint;
+
+/// Rollup bucket width in seconds
+const ROLLUP_SECS: i64 = 60;
+
+/// Most points a range query may return
+pub const MAX_POINTS: i64 = 10_000;
+
+/// Points returned when the caller leaves the step to the service
+const DEFAULT_POINTS: i64 = 300;
+
+/// Supported bucket aggregations
+pub const AGGREGATIONS: &[&str] = &["avg", "min", "max", "sum", "count", "last"];
+
+/// Retained metric samples and rollups
+pub struct MetricHistory {
+    conn: Mutex<Connection>,
+}
+
+impl MetricHistory {
+    pub fn new(db_path: &str) -> Result<Self> {
+        if let Some(parent) = std::path::Path::new(db_path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let conn = Connection::open(db_path)?;
+        conn.execute_batch("PRAGMA journal_mode=WAL; PRAGMA synchronous=NORMAL;")?;
+
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS metric_samples (
+                key TEXT NOT NULL,
+                timestamp INTEGER NOT NULL,
+                value REAL NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS metric_rollups (
+                key TEXT NOT NULL,
+                bucket INTEGER NOT NULL,
+                count INTEGER NOT NULL,
+                sum REAL NOT NULL,
+                min REAL NOT NULL,
+                max REAL NOT NULL,
+                last REAL NOT NULL,
+                PRIMARY KEY (key, bucket)
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_samples_key_time ON metric_samples(key, timestamp);",
+        )?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Append a sample
+    pub fn record(&self, key: &str, value: f64, timestamp: i64) -> Result<()> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        conn.execute(
+            "INSERT INTO metric_samples (key, timestamp, value) VALUES (?1, ?2, ?3)",
+            params![key, timestamp, value],
+        )?;
+        Ok(())
+    }
+
+    /// Aggregate `key` over [from, to] into buckets of `step` seconds.
+    /// Buckets without samples are omitted.
+    pub fn query_range(
+        &self,
+        key: &str,
+        from: i64,
+        to: i64,
+        agg: &str,
+        step: i64,
+    ) -> Result<Vec<MetricPoint>> {
+        if to < from {
+            bail!("Range end {to} is before its start {from}");
+        }
+        if step <= 0 {
+            bail!("step must be positive");
+        }
+        if (to - from) / step + 1 > MAX_POINTS {
+            bail!("Range would return more than {MAX_POINTS} points; use a larger step");
+        }
+        let value = match agg {
+            "avg" => "SUM(sum) / SUM(count)",
+            "min" => "MIN(min)",
+            "max" => "MAX(max)",
+            "sum" => "SUM(sum)",
+            "count" => "SUM(count)",
+            // SQLite takes bare columns from the row holding MAX(ts)
+            "last" => "last",
+            other => bail!(
+                "Unknown aggregation '{other}' (expected one of: {})",
+                AGGREGATIONS.join(", ")
+            ),
+        };
+
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let sql = format!(
+            "SELECT (ts - ?2) / ?4 AS b, {value}, SUM(count), MAX(ts) FROM (
+                SELECT timestamp AS ts, 1 AS count, value AS sum, value AS min, value AS max, value AS last
+                FROM metric_samples WHERE key = ?1 AND timestamp BETWEEN ?2 AND ?3
+                UNION ALL
+                SELECT bucket AS ts, count, sum, min, max, last
+                FROM metric_rollups WHERE key = ?1 AND bucket BETWEEN ?2 AND ?3
+             ) GROUP BY b ORDER BY b"
+        );
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(params![key, from, to, step], |row| {
+            Ok(MetricPoint {
+                timestamp: from + row.get::<_, i64>(0)? * step,
+                value: row.get(1)?,
+                samples: row.get(2)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// Fold raw samples older than `raw_before` into one-minute rollups and
+    /// drop rollups older than `keep_after`. Returns the rows removed.
+    pub fn compact(&self, raw_before: i64, keep_after: i64) -> Result<usize> {
+        let mut conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let tx = conn.transaction()?;
+        // Whole minutes only, so a rollup never mixes with raw samples that
+        // are still kept
+        let raw_before = raw_before - raw_before.rem_euclid(ROLLUP_SECS);
+        tx.execute(
+            "INSERT INTO metric_rollups (key, bucket, count, sum, min, max, last)
+             SELECT key, b, COUNT(*), SUM(value), MIN(value), MAX(value), MAX(last) FROM (
+                 SELECT key, timestamp - (timestamp % ?2) AS b, value,
+                        LAST_VALUE(value) OVER (
+                            PARTITION BY key, timestamp - (timestamp % ?2) ORDER BY timestamp
+                            ROWS BETWEEN UNBOUNDED PRECEDING AND UNBOUNDED FOLLOWING
+                        ) AS last
+                 FROM metric_samples WHERE timestamp < ?1
+             ) WHERE true GROUP BY key, b
+             ON CONFLICT(key, bucket) DO UPDATE SET
+                 count = count + excluded.count,
+                 sum = sum + excluded.sum,
+                 min = MIN(min, excluded.min),
+                 max = MAX(max, excluded.max),
+                 last = excluded.last",
+            params![raw_before, ROLLUP_SECS],
+        )?;
+        let mut removed = tx.execute(
+            "DELETE FROM metric_samples WHERE timestamp < ?1",
+            params![raw_before],
+        )?;
+        removed += tx.execute(
+            "DELETE FROM metric_rollups WHERE bucket < ?1",
+            params![keep_after],
+        )?;
+        tx.commit()?;
+        Ok(removed)
+    }
+}
+
+/// Step giving about `DEFAULT_POINTS` points over a range (at least 1s)
+pub fn default_step(from: i64, to: i64) -> i64 {
+    ((to - from) / DEFAULT_POINTS).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history() -> MetricHistory {
+        let history = MetricHistory::new(":memory:").unwrap();
+        // cpu.usage = 10, 20, ..., 120 at t = 0, 10, ..., 110
+        for i in 0..12 {
+            history
+                .record("cpu.usage", (i + 1) as f64 * 10.0, i * 10)
+                .unwrap();
+        }
+        history
+    }
+
+    #[test]
+    fn test_range_aggregation() {
+        let history = history();
+        let points = history.query_range("cpu.usage", 0, 119, "avg", 60).unwrap();
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0].timestamp, 0);
+        assert_eq!(points[0].value, 35.0);
+        assert_eq!(points[0].samples, 6);
+        assert_eq!(points[1].timestamp, 60);
+        assert_eq!(points[1].value, 95.0);
+
+        let max = history.query_range("cpu.usage", 0, 119, "max", 60).unwrap();
+        assert_eq!(max[1].value, 120.0);
+        let last = history
+            .query_range("cpu.usage", 0, 119, "last", 60)
+            .unwrap();
+        assert_eq!(last[0].value, 60.0);
+
+        assert!(history.query_range("cpu.usage", 0, 119, "p95", 60).is_err());
+        assert!(history
+            .query_range("cpu.usage", 0, 1_000_000, "avg", 1)
+            .is_err());
+        assert!(history
+            .query_range("mem.used", 0, 119, "avg", 60)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_compaction_keeps_aggregates() {
+        let history = history();
+        let before = history.query_range("cpu.usage", 0, 119, "avg", 60).unwrap();
+
+        // The first minute becomes a rollup; the second stays raw
+        assert_eq!(history.compact(90, 0).unwrap(), 6);
+        let after = history.query_range("cpu.usage", 0, 119, "avg", 60).unwrap();
+        assert_eq!(before, after);
+        let last = history
+            .query_range("cpu.usage", 0, 119, "last", 60)
+            .unwrap();
+        assert_eq!(last[0].value, 60.0);
+
+        // Rollups past retention are dropped
+        assert_eq!(history.compact(90, 60).unwrap(), 1);
+        assert_eq!(
+            history
+                .query_range("cpu.usage", 0, 119, "count", 120)
+                .unwrap()[0]
+                .samples,
+            6
+        );
+    }
+}
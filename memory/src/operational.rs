@@ -1,47 +1,260 @@
 //! Operational Memory — in-memory ring buffer for hot data
 //!
 //! Sub-millisecond access, stores recent events and current metrics.
+//!
+//! Every event gets a sequence number. Events evicted when the buffer is
+//! full are dropped, or written to the spill store when one is configured;
+//! consumers that read with a cursor (`read_events`) resume where they left
+//! off and are told how many events they missed.
 
 use std::collections::{HashMap, VecDeque};
 
-use crate::proto::memory::{Event, MetricUpdate, MetricValue, SystemSnapshot};
+use anyhow::Result;
+use tracing::warn;
+
+use crate::events::severity_rank;
+use crate::proto::memory::{
+    Event, EventBatch, MetricUpdate, MetricValue, ReadEventsRequest, RecentEventsRequest,
+    SequencedEvent, SystemSnapshot,
+};
+use crate::retention;
+use crate::spill::EventSpill;
+
+/// Events returned by one `read_events` call when the request sets no limit
+const DEFAULT_READ_BATCH: usize = 100;
+
+/// Largest batch `read_events` returns
+const MAX_READ_BATCH: usize = 1000;
+
+/// Ring buffer occupancy and overflow counters
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BufferStats {
+    pub capacity: usize,
+    pub buffered: usize,
+    /// Most events buffered at once since the last `take_stats`
+    pub high_water: usize,
+    /// Evicted events lost since startup
+    pub dropped: u64,
+    /// Evicted events written to the spill store since startup
+    pub spilled: u64,
+    /// Events each named consumer has yet to read
+    pub consumer_lag: Vec<(String, u64)>,
+}
 
 /// In-memory ring buffer for operational data
 pub struct OperationalMemory {
-    events: VecDeque<Event>,
+    events: VecDeque<(u64, Event)>,
     metrics: HashMap<String, MetricValue>,
     max_entries: usize,
+    next_seq: u64,
+    spill: Option<EventSpill>,
+    cursors: HashMap<String, u64>,
+    high_water: usize,
+    dropped: u64,
+    spilled: u64,
 }
 
 impl OperationalMemory {
     pub fn new(max_entries: usize) -> Self {
+        let max_entries = max_entries.max(1);
         Self {
             events: VecDeque::with_capacity(max_entries),
             metrics: HashMap::new(),
             max_entries,
+            next_seq: 1,
+            spill: None,
+            cursors: HashMap::new(),
+            high_water: 0,
+            dropped: 0,
+            spilled: 0,
         }
     }
 
+    /// Write evicted events to `spill` instead of dropping them. Sequence
+    /// numbers continue after the highest one already spilled.
+    pub fn with_spill(mut self, spill: EventSpill) -> Result<Self> {
+        self.next_seq = self.next_seq.max(spill.max_seq()? + 1);
+        self.spill = Some(spill);
+        Ok(self)
+    }
+
     /// Push a new event into the ring buffer
     pub fn push_event(&mut self, event: Event) {
         if self.events.len() >= self.max_entries {
-            self.events.pop_front();
+            if let Some((seq, evicted)) = self.events.pop_front() {
+                match self.spill.as_ref().map(|s| s.store(seq, &evicted)) {
+                    Some(Ok(())) => self.spilled += 1,
+                    Some(Err(e)) => {
+                        warn!("Failed to spill event {seq}: {e}");
+                        self.dropped += 1;
+                    }
+                    None => self.dropped += 1,
+                }
+            }
         }
-        self.events.push_back(event);
+        self.events.push_back((self.next_seq, event));
+        self.next_seq += 1;
+        self.high_water = self.high_water.max(self.events.len());
     }
 
     /// Get recent events with optional filtering
     pub fn get_recent(&self, count: usize, category: &str, source: &str) -> Vec<Event> {
+        self.query_events(&RecentEventsRequest {
+            count: count as i32,
+            category: category.to_string(),
+            source: source.to_string(),
+            ..Default::default()
+        })
+    }
+
+    /// Get recent events matching every filter set in `req`. The category
+    /// filter also accepts the pre-taxonomy "category.type" form.
+    pub fn query_events(&self, req: &RecentEventsRequest) -> Vec<Event> {
+        let min_rank = severity_rank(&req.min_severity);
         self.events
             .iter()
             .rev()
-            .filter(|e| category.is_empty() || e.category == category)
-            .filter(|e| source.is_empty() || e.source == source)
-            .take(count)
+            .map(|(_, e)| e)
+            .filter(|e| {
+                req.category.is_empty()
+                    || e.category == req.category
+                    || req
+                        .category
+                        .split_once('.')
+                        .is_some_and(|(c, t)| c == e.category && t == e.event_type)
+            })
+            .filter(|e| req.source.is_empty() || e.source == req.source)
+            .filter(|e| req.event_type.is_empty() || e.event_type == req.event_type)
+            .filter(|e| severity_rank(&e.severity) >= min_rank)
+            .take(req.count.max(0) as usize)
             .cloned()
             .collect()
     }
 
+    /// Events in order after a cursor: `after_seq`, or the named consumer's
+    /// saved position (a new consumer starts at the oldest buffered event).
+    /// Spilled events are read before buffered ones; events that are in
+    /// neither are counted in `lost`. A named consumer's cursor moves to the
+    /// last event returned.
+    pub fn read_events(&mut self, req: &ReadEventsRequest) -> Result<EventBatch> {
+        let limit = match req.max {
+            n if n <= 0 => DEFAULT_READ_BATCH,
+            n => (n as usize).min(MAX_READ_BATCH),
+        };
+        let oldest_buffered = self.events.front().map_or(self.next_seq, |(seq, _)| *seq);
+        let after = if req.after_seq > 0 {
+            req.after_seq
+        } else {
+            self.cursors
+                .get(&req.consumer)
+                .copied()
+                .unwrap_or(oldest_buffered - 1)
+        };
+
+        let mut events = Vec::new();
+        if after + 1 < oldest_buffered {
+            if let Some(spill) = &self.spill {
+                events = spill.read_after(after, limit)?;
+                events.retain(|(seq, _)| *seq < oldest_buffered);
+            }
+        }
+        let last_spilled = events.last().map_or(after, |(seq, _)| *seq);
+        if events.len() < limit {
+            let remaining = limit - events.len();
+            events.extend(
+                self.events
+                    .iter()
+                    .filter(|(seq, _)| *seq > last_spilled)
+                    .take(remaining)
+                    .cloned(),
+            );
+        }
+
+        // Gaps in the sequence are events that were dropped
+        let mut cursor = after;
+        let mut lost = 0;
+        for (seq, _) in &events {
+            lost += seq - cursor - 1;
+            cursor = *seq;
+        }
+        if events.is_empty() {
+            lost = (self.next_seq - 1).saturating_sub(after);
+            cursor = after.max(self.next_seq - 1);
+        }
+        if !req.consumer.is_empty() {
+            self.cursors.insert(req.consumer.clone(), cursor);
+        }
+
+        Ok(EventBatch {
+            events: events
+                .into_iter()
+                .map(|(seq, event)| SequencedEvent {
+                    seq,
+                    event: Some(event),
+                })
+                .collect(),
+            cursor,
+            lost,
+            more: cursor + 1 < self.next_seq,
+        })
+    }
+
+    /// Delete spilled events older than `before` (Unix seconds)
+    pub fn prune_spill(&self, before: i64) -> Result<usize> {
+        match &self.spill {
+            Some(spill) => spill.prune(before),
+            None => Ok(0),
+        }
+    }
+
+    /// Drop buffered and spilled events mentioning `pattern` (normalized,
+    /// see [`crate::retention`]); events removed per store
+    pub fn erase_subject(&mut self, pattern: &str) -> Result<Vec<(String, usize)>> {
+        let before = self.events.len();
+        self.events
+            .retain(|(_, event)| !retention::event_matches(event, pattern));
+        let mut removed = vec![("events".to_string(), before - self.events.len())];
+        if let Some(spill) = &self.spill {
+            removed.push(("spilled_events".to_string(), spill.erase_subject(pattern)?));
+        }
+        Ok(removed)
+    }
+
+    /// Buffered and spilled events still mentioning `pattern`
+    pub fn count_subject(&self, pattern: &str) -> Result<usize> {
+        let buffered = self
+            .events
+            .iter()
+            .filter(|(_, event)| retention::event_matches(event, pattern))
+            .count();
+        let spilled = match &self.spill {
+            Some(spill) => spill.count_subject(pattern)?,
+            None => 0,
+        };
+        Ok(buffered + spilled)
+    }
+
+    /// Current occupancy and counters; resets the high-water mark
+    pub fn take_stats(&mut self) -> BufferStats {
+        let latest = self.next_seq - 1;
+        let mut consumer_lag: Vec<(String, u64)> = self
+            .cursors
+            .iter()
+            .map(|(name, cursor)| (name.clone(), latest.saturating_sub(*cursor)))
+            .collect();
+        consumer_lag.sort();
+        let stats = BufferStats {
+            capacity: self.max_entries,
+            buffered: self.events.len(),
+            high_water: self.high_water,
+            dropped: self.dropped,
+            spilled: self.spilled,
+            consumer_lag,
+        };
+        self.high_water = self.events.len();
+        stats
+    }
+
     /// Update a metric value
     pub fn update_metric(&mut self, update: MetricUpdate) {
         self.metrics.insert(
@@ -108,6 +321,9 @@ mod tests {
             source: "test".to_string(),
             data_json: b"{}".to_vec(),
             critical: false,
+            event_type: String::new(),
+            severity: "info".to_string(),
+            schema_version: 1,
         }
     }
 
@@ -350,4 +566,103 @@ mod tests {
         let events = mem.get_recent(1, "", "");
         assert!(events[0].critical);
     }
+
+    #[test]
+    fn test_query_events_by_type_and_severity() {
+        let mut mem = OperationalMemory::new(100);
+        let mut failed = make_event("1", "service");
+        failed.event_type = "restart_failed".into();
+        failed.severity = "error".into();
+        let mut restarted = make_event("2", "service");
+        restarted.event_type = "restarted".into();
+        mem.push_event(failed);
+        mem.push_event(restarted);
+
+        let query = |category: &str, event_type: &str, min_severity: &str| {
+            mem.query_events(&RecentEventsRequest {
+                count: 10,
+                category: category.into(),
+                source: String::new(),
+                event_type: event_type.into(),
+                min_severity: min_severity.into(),
+            })
+        };
+        assert_eq!(query("service", "", "").len(), 2);
+        assert_eq!(query("service.restarted", "", "")[0].id, "2");
+        assert_eq!(query("", "restart_failed", "")[0].id, "1");
+        let severe = query("", "", "warning");
+        assert_eq!(severe.len(), 1);
+        assert_eq!(severe[0].id, "1");
+    }
+
+    fn read(mem: &mut OperationalMemory, consumer: &str, max: i32) -> EventBatch {
+        mem.read_events(&ReadEventsRequest {
+            consumer: consumer.into(),
+            after_seq: 0,
+            max,
+        })
+        .unwrap()
+    }
+
+    fn ids(batch: &EventBatch) -> Vec<String> {
+        batch
+            .events
+            .iter()
+            .map(|e| e.event.as_ref().unwrap().id.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_cursor_reports_dropped_events() {
+        let mut mem = OperationalMemory::new(3);
+        mem.push_event(make_event("1", "a"));
+        mem.push_event(make_event("2", "a"));
+
+        let batch = read(&mut mem, "anomaly", 1);
+        assert_eq!(ids(&batch), vec!["1"]);
+        assert!(batch.more);
+
+        // A burst evicts events 2 and 3 before the consumer returns
+        for i in 3..=6 {
+            mem.push_event(make_event(&i.to_string(), "a"));
+        }
+        let batch = read(&mut mem, "anomaly", 10);
+        assert_eq!(ids(&batch), vec!["4", "5", "6"]);
+        assert_eq!(batch.lost, 2);
+        assert_eq!(batch.cursor, 6);
+        assert!(!batch.more);
+
+        let stats = mem.take_stats();
+        assert_eq!(stats.dropped, 3);
+        assert_eq!(stats.high_water, 3);
+        assert_eq!(stats.consumer_lag, vec![("anomaly".to_string(), 0)]);
+    }
+
+    #[test]
+    fn test_spilled_events_are_replayed() {
+        let mut mem = OperationalMemory::new(2)
+            .with_spill(EventSpill::new(":memory:").unwrap())
+            .unwrap();
+        read(&mut mem, "slow", 10);
+        for i in 1..=5 {
+            mem.push_event(make_event(&i.to_string(), "a"));
+        }
+
+        let batch = read(&mut mem, "slow", 2);
+        assert_eq!(ids(&batch), vec!["1", "2"]);
+        let batch = read(&mut mem, "slow", 10);
+        assert_eq!(ids(&batch), vec!["3", "4", "5"]);
+        assert_eq!(batch.lost, 0);
+        assert_eq!(mem.take_stats().spilled, 3);
+
+        // An explicit position overrides the saved cursor
+        let batch = mem
+            .read_events(&ReadEventsRequest {
+                consumer: String::new(),
+                after_seq: 4,
+                max: 0,
+            })
+            .unwrap();
+        assert_eq!(ids(&batch), vec!["5"]);
+    }
 }
@@ -0,0 +1,231 @@
+//! Rerankers — reorder retrieved context by relevance to the task
+//!
+//! One reranker is selected with `AIOS_RERANK_PROVIDER`:
+//! - `none` (default): keep the fused retrieval order
+//! - `cross_encoder`: a local cross-encoder behind a `/v1/rerank` endpoint,
+//!   such as a llama-server started with `--reranking` (`AIOS_RERANK_URL`)
+//! - `llm`: a local chat model behind `/v1/chat/completions` that scores
+//!   each passage
+//!
+//! `AIOS_RERANK_MODEL` picks the model. A failed rerank is logged and the
+//! fused order is used instead.
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+
+/// Longest passage, in characters, shown to an LLM reranker
+const LLM_PASSAGE_CHARS: usize = 400;
+
+/// Reranker selection, read from the environment
+#[derive(Debug, Clone)]
+pub struct RerankConfig {
+    pub provider: String,
+    pub model: String,
+    pub url: String,
+}
+
+impl RerankConfig {
+    pub fn from_env() -> Self {
+        let provider = std::env::var("AIOS_RERANK_PROVIDER")
+            .unwrap_or_else(|_| "none".to_string())
+            .to_lowercase();
+        let default_url = match provider.as_str() {
+            "cross_encoder" => "http://127.0.0.1:8091/v1/rerank",
+            "llm" => "http://127.0.0.1:8080/v1/chat/completions",
+            _ => "",
+        };
+        Self {
+            model: std::env::var("AIOS_RERANK_MODEL").unwrap_or_else(|_| "local".into()),
+            url: std::env::var("AIOS_RERANK_URL").unwrap_or_else(|_| default_url.into()),
+            provider,
+        }
+    }
+}
+
+/// The configured reranker
+pub enum Reranker {
+    None,
+    CrossEncoder {
+        url: String,
+        model: String,
+        client: reqwest::Client,
+    },
+    Llm {
+        url: String,
+        model: String,
+        client: reqwest::Client,
+    },
+}
+
+impl Reranker {
+    pub fn from_config(config: &RerankConfig) -> Result<Self> {
+        if config.provider != "none" && config.url.is_empty() {
+            bail!(
+                "No endpoint configured for the {} reranker",
+                config.provider
+            );
+        }
+        let client = || {
+            reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(10))
+                .build()
+                .unwrap_or_else(|_| reqwest::Client::new())
+        };
+        match config.provider.as_str() {
+            "none" => Ok(Self::None),
+            "cross_encoder" => Ok(Self::CrossEncoder {
+                url: config.url.clone(),
+                model: config.model.clone(),
+                client: client(),
+            }),
+            "llm" => Ok(Self::Llm {
+                url: config.url.clone(),
+                model: config.model.clone(),
+                client: client(),
+            }),
+            other => bail!("Unknown reranker '{other}'"),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::CrossEncoder { .. } => "cross_encoder",
+            Self::Llm { .. } => "llm",
+        }
+    }
+
+    /// Score each passage against the query (0-1, one per passage); None
+    /// when reranking is disabled
+    pub async fn rerank(&self, query: &str, passages: &[String]) -> Result<Option<Vec<f64>>> {
+        if passages.is_empty() {
+            return Ok(None);
+        }
+        let scores = match self {
+            Self::None => return Ok(None),
+            Self::CrossEncoder { url, model, client } => {
+                let body = serde_json::json!({
+                    "model": model,
+                    "query": query,
+                    "documents": passages,
+                    "top_n": passages.len(),
+                });
+                let body = post_json(client, url, &body).await?;
+                parse_cross_encoder(body, passages.len())?
+            }
+            Self::Llm { url, model, client } => {
+                let body = serde_json::json!({
+                    "model": model,
+                    "messages": [{"role": "user", "content": llm_prompt(query, passages)}],
+                    "max_tokens": 256,
+                    "temperature": 0.0,
+                    "response_format": {"type": "json_object"},
+                });
+                let body = post_json(client, url, &body).await?;
+                parse_llm(body, passages.len())?
+            }
+        };
+        Ok(Some(scores))
+    }
+}
+
+async fn post_json(
+    client: &reqwest::Client,
+    url: &str,
+    body: &serde_json::Value,
+) -> Result<serde_json::Value> {
+    let response = client
+        .post(url)
+        .json(body)
+        .send()
+        .await
+        .context("Rerank request failed")?;
+    let status = response.status();
+    if !status.is_success() {
+        let text = response.text().await.unwrap_or_default();
+        bail!("Reranker returned {status}: {text}");
+    }
+    response.json().await.context("Invalid rerank response")
+}
+
+#[derive(Deserialize)]
+struct CrossEncoderResults {
+    results: Vec<CrossEncoderResult>,
+}
+
+#[derive(Deserialize)]
+struct CrossEncoderResult {
+    index: usize,
+    relevance_score: f64,
+}
+
+/// Cross-encoder scores are logits; squash them into 0-1
+fn parse_cross_encoder(body: serde_json::Value, expected: usize) -> Result<Vec<f64>> {
+    let parsed: CrossEncoderResults =
+        serde_json::from_value(body).context("Unexpected rerank response")?;
+    let mut scores = vec![0.0; expected];
+    for result in parsed.results {
+        if let Some(score) = scores.get_mut(result.index) {
+            *score = 1.0 / (1.0 + (-result.relevance_score).exp());
+        }
+    }
+    Ok(scores)
+}
+
+fn llm_prompt(query: &str, passages: &[String]) -> String {
+    let mut prompt = format!(
+        "Rate how useful each passage is for the task, from 0 (irrelevant) to 10 \
+         (directly answers it). Respond with JSON {{\"scores\": [..]}} holding one \
+         number per passage, in order.\n\nTask: {query}\n\n"
+    );
+    for (i, passage) in passages.iter().enumerate() {
+        let passage: String = passage.chars().take(LLM_PASSAGE_CHARS).collect();
+        prompt.push_str(&format!("[{i}] {passage}\n"));
+    }
+    prompt
+}
+
+#[derive(Deserialize)]
+struct LlmScores {
+    scores: Vec<f64>,
+}
+
+fn parse_llm(body: serde_json::Value, expected: usize) -> Result<Vec<f64>> {
+    let content = body["choices"][0]["message"]["content"]
+        .as_str()
+        .context("Rerank response has no message content")?;
+    let parsed: LlmScores =
+        serde_json::from_str(content.trim()).context("Reranker did not return scores")?;
+    if parsed.scores.len() != expected {
+        bail!(
+            "Reranker returned {} scores for {expected} passages",
+            parsed.scores.len()
+        );
+    }
+    Ok(parsed
+        .scores
+        .into_iter()
+        .map(|s| (s / 10.0).clamp(0.0, 1.0))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rerank_responses() {
+        let body = serde_json::json!({"results": [
+            {"index": 1, "relevance_score": 4.0},
+            {"index": 0, "relevance_score": -4.0},
+        ]});
+        let scores = parse_cross_encoder(body, 2).unwrap();
+        assert!(scores[1] > 0.95 && scores[0] < 0.05);
+
+        let body = serde_json::json!({"choices": [
+            {"message": {"content": "{\"scores\": [7, 15]}"}}
+        ]});
+        assert_eq!(parse_llm(body.clone(), 2).unwrap(), vec![0.7, 1.0]);
+        assert!(parse_llm(body, 3).is_err());
+    }
+}
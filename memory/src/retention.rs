@@ -0,0 +1,286 @@
+//! Retention — per-category expiry and subject erasure
+//!
+//! Emails, scraped pages, tool transcripts and the rest of what agents
+//! record are kept for a bounded time. retention.toml gives the days each
+//! category is kept (0 keeps it forever); the memory service expires older
+//! records from every tier on an interval.
+//!
+//! `EraseSubject` removes every record mentioning a subject (an email
+//! address, a name) from all stores: records whose text or JSON matches
+//! the pattern are deleted with SQLite's secure_delete on and the write-ahead
+//! logs truncated, then the stores are searched again to prove nothing is
+//! left. The erasure is written to the access log under the pattern's
+//! SHA-256, so the log does not itself retain the subject.
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+use crate::proto::memory::Event;
+
+/// Default location of the retention configuration
+pub const RETENTION_CONFIG_PATH: &str = "/etc/aios/retention.toml";
+
+/// Shortest pattern `EraseSubject` accepts, so a stray character cannot
+/// erase most of memory
+pub const MIN_PATTERN_LEN: usize = 3;
+
+/// Kinds of record with a retention period of their own
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    /// Knowledge tagged `email` and `email.*` tool calls
+    Email,
+    /// Knowledge scraped from the web and `web.*` tool calls
+    Web,
+    /// Every tool call transcript
+    ToolCalls,
+    /// Reasoning decisions
+    Decisions,
+    /// Finished goals with their tasks and checkpoints
+    Goals,
+    Incidents,
+    ConfigChanges,
+    /// Every knowledge entry
+    Knowledge,
+}
+
+impl Category {
+    pub const ALL: [Category; 8] = [
+        Category::Email,
+        Category::Web,
+        Category::ToolCalls,
+        Category::Decisions,
+        Category::Goals,
+        Category::Incidents,
+        Category::ConfigChanges,
+        Category::Knowledge,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Category::Email => "email",
+            Category::Web => "web",
+            Category::ToolCalls => "tool_calls",
+            Category::Decisions => "decisions",
+            Category::Goals => "goals",
+            Category::Incidents => "incidents",
+            Category::ConfigChanges => "config_changes",
+            Category::Knowledge => "knowledge",
+        }
+    }
+}
+
+/// Days each category is kept; 0 keeps it forever
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetentionDays {
+    pub email: u32,
+    pub web: u32,
+    pub tool_calls: u32,
+    pub decisions: u32,
+    pub goals: u32,
+    pub incidents: u32,
+    pub config_changes: u32,
+    pub knowledge: u32,
+}
+
+impl Default for RetentionDays {
+    fn default() -> Self {
+        Self {
+            email: 30,
+            web: 30,
+            tool_calls: 90,
+            decisions: 180,
+            goals: 180,
+            incidents: 0,
+            config_changes: 365,
+            knowledge: 0,
+        }
+    }
+}
+
+/// retention.toml layout
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct RetentionConfig {
+    /// Minutes between expiry passes
+    pub interval_mins: u64,
+    pub days: RetentionDays,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            interval_mins: 60,
+            days: RetentionDays::default(),
+        }
+    }
+}
+
+impl RetentionConfig {
+    /// Configuration at `path`.
+    /// A missing file yields the defaults; an invalid one is logged and ignored.
+    pub fn load(path: &str) -> Self {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => Self::from_toml(&contents).unwrap_or_else(|e| {
+                warn!("Ignoring invalid retention configuration in {path}: {e}");
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn from_toml(contents: &str) -> Result<Self> {
+        toml::from_str(contents).context("Failed to parse retention configuration")
+    }
+
+    /// Days `category` is kept; 0 keeps it forever
+    pub fn days(&self, category: Category) -> u32 {
+        let days = &self.days;
+        match category {
+            Category::Email => days.email,
+            Category::Web => days.web,
+            Category::ToolCalls => days.tool_calls,
+            Category::Decisions => days.decisions,
+            Category::Goals => days.goals,
+            Category::Incidents => days.incidents,
+            Category::ConfigChanges => days.config_changes,
+            Category::Knowledge => days.knowledge,
+        }
+    }
+
+    /// Unix time before which `category` expires, None if kept forever
+    pub fn cutoff(&self, category: Category, now: i64) -> Option<i64> {
+        match self.days(category) {
+            0 => None,
+            days => Some(now - i64::from(days) * 86_400),
+        }
+    }
+}
+
+/// A table and the columns a subject may be mentioned in
+pub type SubjectColumns = (&'static str, &'static [&'static str]);
+
+/// Pattern as matched: trimmed and lowercased
+pub fn normalize_pattern(pattern: &str) -> String {
+    pattern.trim().to_lowercase()
+}
+
+/// Hex SHA-256 of the normalized pattern, recorded in place of the subject
+pub fn pattern_digest(pattern: &str) -> String {
+    Sha256::digest(normalize_pattern(pattern).as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// SQL condition matching rows where any of `columns` contains `?1`,
+/// case-insensitively; BLOB columns (JSON) are matched as text
+fn subject_filter(columns: &[&str]) -> String {
+    columns
+        .iter()
+        .map(|c| format!("instr(lower(CAST({c} AS TEXT)), ?1) > 0"))
+        .collect::<Vec<_>>()
+        .join(" OR ")
+}
+
+/// Delete the rows of `tables` mentioning `pattern` (normalized), with
+/// secure_delete on so freed pages are zeroed. Rows removed per table.
+pub fn erase_rows(
+    conn: &Connection,
+    tables: &[SubjectColumns],
+    pattern: &str,
+) -> Result<Vec<(String, usize)>> {
+    conn.execute_batch("PRAGMA secure_delete=ON;")?;
+    let mut removed = Vec::new();
+    for (table, columns) in tables {
+        let n = conn.execute(
+            &format!("DELETE FROM {table} WHERE {}", subject_filter(columns)),
+            params![pattern],
+        )?;
+        removed.push((table.to_string(), n));
+    }
+    Ok(removed)
+}
+
+/// Rows of `tables` still mentioning `pattern` (normalized)
+pub fn count_rows(conn: &Connection, tables: &[SubjectColumns], pattern: &str) -> Result<usize> {
+    let mut total = 0;
+    for (table, columns) in tables {
+        let n: i64 = conn.query_row(
+            &format!(
+                "SELECT COUNT(*) FROM {table} WHERE {}",
+                subject_filter(columns)
+            ),
+            params![pattern],
+            |row| row.get(0),
+        )?;
+        total += n as usize;
+    }
+    Ok(total)
+}
+
+/// Copy the write-ahead log into the database and truncate it, so deleted
+/// rows do not survive in the log
+pub fn truncate_wal(conn: &Connection) -> Result<()> {
+    conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")?;
+    Ok(())
+}
+
+/// Whether an event mentions `pattern` (normalized)
+pub fn event_matches(event: &Event, pattern: &str) -> bool {
+    [
+        event.id.as_str(),
+        event.category.as_str(),
+        event.source.as_str(),
+        event.event_type.as_str(),
+        &String::from_utf8_lossy(&event.data_json),
+    ]
+    .iter()
+    .any(|field| field.to_lowercase().contains(pattern))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_defaults_and_overrides() {
+        let config = RetentionConfig::from_toml("[days]\nemail = 7\nknowledge = 365\n").unwrap();
+        assert_eq!(config.interval_mins, 60);
+        assert_eq!(config.days(Category::Email), 7);
+        assert_eq!(config.days(Category::ToolCalls), 90);
+        assert_eq!(
+            config.cutoff(Category::Knowledge, 1_000_000_000),
+            Some(968_464_000)
+        );
+        assert_eq!(config.cutoff(Category::Incidents, 1_000_000_000), None);
+        assert!(RetentionConfig::from_toml("[days]\nemails = 7\n").is_err());
+    }
+
+    #[test]
+    fn test_erase_rows_matches_text_and_json() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE notes (id INTEGER PRIMARY KEY, body TEXT, data BLOB);
+             INSERT INTO notes (body, data) VALUES ('mail from Alice@Example.com', NULL);
+             INSERT INTO notes (body, data) VALUES ('unrelated', CAST('{\"to\":\"alice@example.com\"}' AS BLOB));
+             INSERT INTO notes (body, data) VALUES ('bob@example.com', NULL);",
+        )
+        .unwrap();
+        let tables: &[SubjectColumns] = &[("notes", &["body", "data"])];
+        let pattern = normalize_pattern(" ALICE@example.com ");
+
+        assert_eq!(count_rows(&conn, tables, &pattern).unwrap(), 2);
+        let removed = erase_rows(&conn, tables, &pattern).unwrap();
+        assert_eq!(removed, vec![("notes".to_string(), 2)]);
+        assert_eq!(count_rows(&conn, tables, &pattern).unwrap(), 0);
+        assert_eq!(count_rows(&conn, tables, "bob@").unwrap(), 1);
+        assert_eq!(
+            pattern_digest("alice@example.com"),
+            pattern_digest(" ALICE@example.com")
+        );
+    }
+}
@@ -0,0 +1,251 @@
+//! Hybrid Retrieval — fuses full-text and vector results for context assembly
+//!
+//! Each searchable tier is queried twice: SQLite FTS5 (BM25) for exact terms
+//! and the embedding search for meaning. The ranked lists are merged with
+//! reciprocal rank fusion, optionally reordered by a reranker (see
+//! `rerank`), and near-identical chunks are dropped before packing the token
+//! budget. Every chunk carries its provenance so prompts can cite it.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::proto::memory::{ChunkProvenance, ContextChunk, SearchResult};
+
+/// Reciprocal rank fusion constant; damps the weight of top ranks
+const RRF_K: f64 = 60.0;
+
+/// Results taken from each retriever, per tier
+pub const CANDIDATES_PER_TIER: i32 = 10;
+
+/// Word-set similarity above which two chunks count as the same memory
+pub const DUPLICATE_THRESHOLD: f64 = 0.85;
+
+/// FTS5 MATCH expression for free text: each word quoted (so operators and
+/// punctuation in the query are inert) and OR-ed. None if nothing searchable.
+pub fn fts_query(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.len() > 1)
+        .map(|w| format!("\"{}\"", w.to_lowercase()))
+        .collect();
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" OR "))
+    }
+}
+
+/// Map a BM25 score (lower is better, usually negative) into 0-1
+pub fn bm25_relevance(score: f64) -> f64 {
+    let s = (-score).max(0.0);
+    s / (1.0 + s)
+}
+
+/// Stable reference to a memory record, shown to the model
+pub fn citation(tier: &str, collection: &str, record_id: &str) -> String {
+    format!("{tier}:{collection}/{record_id}")
+}
+
+/// Chunk for a record that was not ranked against the query
+pub fn unranked_chunk(
+    tier: &str,
+    collection: &str,
+    record_id: &str,
+    content: String,
+    relevance: f64,
+) -> ContextChunk {
+    ContextChunk {
+        source: tier.to_string(),
+        tokens: estimate_tokens(&content),
+        content,
+        relevance,
+        provenance: Some(ChunkProvenance {
+            tier: tier.to_string(),
+            collection: collection.to_string(),
+            record_id: record_id.to_string(),
+            retrievers: vec!["recent".into()],
+            fused_score: 0.0,
+            rerank_score: 0.0,
+            citation: citation(tier, collection, record_id),
+        }),
+    }
+}
+
+/// Merge ranked lists from several retrievers with reciprocal rank fusion.
+/// A record found by more than one retriever keeps the content from the
+/// first list it appears in and lists every retriever that found it.
+pub fn fuse(tier: &str, lists: &[(&str, Vec<SearchResult>)]) -> Vec<ContextChunk> {
+    let mut order: Vec<(String, String)> = Vec::new();
+    let mut fused: HashMap<(String, String), (SearchResult, Vec<String>, f64)> = HashMap::new();
+
+    for (retriever, results) in lists {
+        for (rank, result) in results.iter().enumerate() {
+            let key = (result.collection.clone(), result.id.clone());
+            let score = 1.0 / (RRF_K + rank as f64 + 1.0);
+            match fused.get_mut(&key) {
+                Some((_, retrievers, total)) => {
+                    if !retrievers.iter().any(|r| r == retriever) {
+                        retrievers.push(retriever.to_string());
+                    }
+                    *total += score;
+                }
+                None => {
+                    order.push(key.clone());
+                    fused.insert(key, (result.clone(), vec![retriever.to_string()], score));
+                }
+            }
+        }
+    }
+
+    // Normalize so a record ranked first by every retriever scores 1.0
+    let best = lists.len().max(1) as f64 / (RRF_K + 1.0);
+    let mut chunks: Vec<ContextChunk> = order
+        .into_iter()
+        .filter_map(|key| fused.remove(&key))
+        .map(|(result, retrievers, score)| {
+            let fused_score = (score / best).min(1.0);
+            ContextChunk {
+                source: tier.to_string(),
+                tokens: estimate_tokens(&result.content),
+                relevance: fused_score,
+                provenance: Some(ChunkProvenance {
+                    tier: tier.to_string(),
+                    citation: citation(tier, &result.collection, &result.id),
+                    collection: result.collection,
+                    record_id: result.id,
+                    retrievers,
+                    fused_score,
+                    rerank_score: 0.0,
+                }),
+                content: result.content,
+            }
+        })
+        .collect();
+    sort_by_relevance(&mut chunks);
+    chunks
+}
+
+/// Replace relevance with reranker scores (one per chunk, 0-1) and reorder
+pub fn apply_rerank(chunks: &mut [ContextChunk], scores: &[f64]) {
+    for (chunk, score) in chunks.iter_mut().zip(scores) {
+        chunk.relevance = *score;
+        if let Some(provenance) = chunk.provenance.as_mut() {
+            provenance.rerank_score = *score;
+        }
+    }
+    sort_by_relevance(chunks);
+}
+
+/// Drop chunks whose words nearly match an earlier chunk. Order is kept, so
+/// the earlier (higher priority) copy survives.
+pub fn dedupe(chunks: Vec<ContextChunk>, threshold: f64) -> Vec<ContextChunk> {
+    let mut kept: Vec<(ContextChunk, HashSet<String>)> = Vec::new();
+    for chunk in chunks {
+        let words = word_set(&chunk.content);
+        if kept
+            .iter()
+            .any(|(_, other)| jaccard(&words, other) >= threshold)
+        {
+            continue;
+        }
+        kept.push((chunk, words));
+    }
+    kept.into_iter().map(|(chunk, _)| chunk).collect()
+}
+
+fn word_set(text: &str) -> HashSet<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_string())
+        .collect()
+}
+
+fn jaccard(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    let shared = a.intersection(b).count();
+    shared as f64 / (a.len() + b.len() - shared) as f64
+}
+
+pub fn sort_by_relevance(chunks: &mut [ContextChunk]) {
+    chunks.sort_by(|a, b| {
+        b.relevance
+            .partial_cmp(&a.relevance)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+}
+
+/// Rough token estimation (4 chars per token)
+pub fn estimate_tokens(text: &str) -> i32 {
+    (text.len() as f64 / 4.0).ceil() as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(collection: &str, id: &str, content: &str) -> SearchResult {
+        SearchResult {
+            id: id.into(),
+            content: content.into(),
+            metadata_json: vec![],
+            relevance: 0.5,
+            collection: collection.into(),
+        }
+    }
+
+    #[test]
+    fn test_fuse_prefers_records_found_by_both_retrievers() {
+        let fts = vec![
+            result("procedures", "a", "restart nginx"),
+            result("procedures", "b", "rotate logs"),
+        ];
+        let vector = vec![
+            result("procedures", "b", "rotate logs"),
+            result("procedures", "c", "clear cache"),
+        ];
+        let chunks = fuse("longterm", &[("fts", fts), ("vector", vector)]);
+
+        assert_eq!(chunks.len(), 3);
+        let top = chunks[0].provenance.as_ref().unwrap();
+        assert_eq!(top.record_id, "b");
+        assert_eq!(top.retrievers, vec!["fts", "vector"]);
+        assert_eq!(top.citation, "longterm:procedures/b");
+        assert!(chunks[0].relevance > chunks[1].relevance);
+    }
+
+    #[test]
+    fn test_rerank_and_dedupe() {
+        let mut chunks = fuse(
+            "knowledge",
+            &[(
+                "fts",
+                vec![
+                    result("knowledge", "1", "Nginx listens on port 80"),
+                    result("knowledge", "2", "nginx listens on port 80."),
+                    result("knowledge", "3", "Firewall rules live in nftables"),
+                ],
+            )],
+        );
+        apply_rerank(&mut chunks, &[0.2, 0.1, 0.9]);
+        assert_eq!(chunks[0].provenance.as_ref().unwrap().record_id, "3");
+        assert_eq!(chunks[0].provenance.as_ref().unwrap().rerank_score, 0.9);
+
+        let chunks = dedupe(chunks, DUPLICATE_THRESHOLD);
+        let ids: Vec<_> = chunks
+            .iter()
+            .map(|c| c.provenance.as_ref().unwrap().record_id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["3", "1"]);
+    }
+
+    #[test]
+    fn test_fts_query_quotes_terms() {
+        assert_eq!(
+            fts_query("disk: usage AND \"x\"").as_deref(),
+            Some("\"disk\" OR \"usage\" OR \"and\"")
+        );
+        assert!(fts_query("? !").is_none());
+    }
+}
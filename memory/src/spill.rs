@@ -0,0 +1,144 @@
+//! Event Spill — SQLite overflow for the operational ring buffer
+//!
+//! When enabled (`AIOS_EVENT_SPILL_DB`), events evicted from the ring buffer
+//! are written here with their sequence number instead of being dropped, so
+//! a consumer reading with a cursor (`ReadEvents`) can catch up after a
+//! burst. Spilled events are kept for `AIOS_EVENT_SPILL_RETENTION_HOURS`
+//! (default 24).
+
+use anyhow::Result;
+use prost::Message;
+use rusqlite::{params, Connection};
+use std::sync::Mutex;
+
+use crate::proto::memory::Event;
+use crate::retention;
+
+/// Overflow store for evicted events
+pub struct EventSpill {
+    conn: Mutex<Connection>,
+}
+
+impl EventSpill {
+    pub fn new(db_path: &str) -> Result<Self> {
+        if let Some(parent) = std::path::Path::new(db_path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let conn = Connection::open(db_path)?;
+        conn.execute_batch("PRAGMA journal_mode=WAL; PRAGMA synchronous=NORMAL;")?;
+
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS spilled_events (
+                seq INTEGER PRIMARY KEY,
+                timestamp INTEGER NOT NULL,
+                event BLOB NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_spilled_timestamp ON spilled_events(timestamp);",
+        )?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Store an evicted event under its sequence number
+    pub fn store(&self, seq: u64, event: &Event) -> Result<()> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        conn.execute(
+            "INSERT OR REPLACE INTO spilled_events (seq, timestamp, event) VALUES (?1, ?2, ?3)",
+            params![seq as i64, event.timestamp, event.encode_to_vec()],
+        )?;
+        Ok(())
+    }
+
+    /// Up to `limit` spilled events with a sequence number above `after`,
+    /// oldest first
+    pub fn read_after(&self, after: u64, limit: usize) -> Result<Vec<(u64, Event)>> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let mut stmt = conn.prepare(
+            "SELECT seq, event FROM spilled_events WHERE seq > ?1 ORDER BY seq LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![after as i64, limit as i64], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, Vec<u8>>(1)?))
+        })?;
+
+        let mut events = Vec::new();
+        for row in rows {
+            let (seq, bytes) = row?;
+            events.push((seq as u64, Event::decode(bytes.as_slice())?));
+        }
+        Ok(events)
+    }
+
+    /// Highest sequence number spilled so far (0 if none)
+    pub fn max_seq(&self) -> Result<u64> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let seq: Option<i64> =
+            conn.query_row("SELECT MAX(seq) FROM spilled_events", [], |row| row.get(0))?;
+        Ok(seq.unwrap_or(0) as u64)
+    }
+
+    /// Delete spilled events older than `before` (Unix seconds)
+    pub fn prune(&self, before: i64) -> Result<usize> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        Ok(conn.execute(
+            "DELETE FROM spilled_events WHERE timestamp < ?1",
+            params![before],
+        )?)
+    }
+
+    /// Sequence numbers of spilled events mentioning `pattern`
+    fn matching(conn: &Connection, pattern: &str) -> Result<Vec<i64>> {
+        let mut stmt = conn.prepare("SELECT seq, event FROM spilled_events")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, Vec<u8>>(1)?))
+        })?;
+        let mut seqs = Vec::new();
+        for row in rows {
+            let (seq, bytes) = row?;
+            if retention::event_matches(&Event::decode(bytes.as_slice())?, pattern) {
+                seqs.push(seq);
+            }
+        }
+        Ok(seqs)
+    }
+
+    /// Delete the spilled events mentioning `pattern` (normalized, see
+    /// [`crate::retention`]); returns how many
+    pub fn erase_subject(&self, pattern: &str) -> Result<usize> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        conn.execute_batch("PRAGMA secure_delete=ON;")?;
+        let seqs = Self::matching(&conn, pattern)?;
+        for seq in &seqs {
+            conn.execute("DELETE FROM spilled_events WHERE seq = ?1", params![seq])?;
+        }
+        retention::truncate_wal(&conn)?;
+        Ok(seqs.len())
+    }
+
+    /// Spilled events still mentioning `pattern`
+    pub fn count_subject(&self, pattern: &str) -> Result<usize> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        Ok(Self::matching(&conn, pattern)?.len())
+    }
+}
@@ -0,0 +1,607 @@
+//! Approximate nearest-neighbour index (HNSW) over long-term memory vectors
+//!
+//! Keeps `semantic_search` from scanning every stored vector once procedures
+//! grow into the hundreds of thousands. The graph lives in memory and is
+//! persisted to a file next to the long-term database. SQLite stays the
+//! source of truth: the file records the embedding generation it was saved
+//! at, and a missing, corrupt or stale file is rebuilt from stored vectors.
+
+use anyhow::{bail, Context, Result};
+use sha2::{Digest, Sha256};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Links per node above the bottom layer
+const M: usize = 16;
+/// Links per node on the bottom layer, which every node is part of
+const M0: usize = 2 * M;
+/// Candidate list size while inserting
+const EF_CONSTRUCTION: usize = 100;
+/// Smallest candidate list size while searching
+const EF_SEARCH: usize = 64;
+/// Cap on node levels, far above what `M` produces in practice
+const MAX_LEVEL: usize = 16;
+
+const MAGIC: &[u8; 8] = b"AIOSHNSW";
+const FORMAT_VERSION: u32 = 1;
+
+/// A node's similarity to the query, ordered by similarity
+#[derive(Clone, Copy, PartialEq)]
+struct Scored {
+    similarity: f32,
+    node: u32,
+}
+
+impl Eq for Scored {}
+
+impl PartialOrd for Scored {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Scored {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.similarity
+            .total_cmp(&other.similarity)
+            .then(other.node.cmp(&self.node))
+    }
+}
+
+struct Node {
+    id: String,
+    /// Unit-length copy of the stored vector, so similarity is a dot product
+    vector: Vec<f32>,
+    /// Neighbours per layer, bottom layer first
+    links: Vec<Vec<u32>>,
+    /// Replaced or removed; still traversed, never returned
+    deleted: bool,
+}
+
+/// HNSW graph over unit-normalized vectors of one dimensionality
+pub struct VectorIndex {
+    dimensions: usize,
+    nodes: Vec<Node>,
+    ids: HashMap<String, u32>,
+    entry: Option<u32>,
+    deleted: usize,
+    /// Embedding generation of the long-term database this index reflects
+    generation: i64,
+    dirty: bool,
+}
+
+impl VectorIndex {
+    pub fn new(dimensions: usize) -> Self {
+        Self {
+            dimensions,
+            nodes: Vec::new(),
+            ids: HashMap::new(),
+            entry: None,
+            deleted: 0,
+            generation: 0,
+            dirty: false,
+        }
+    }
+
+    /// Build an index from scratch; vectors of another size are skipped
+    pub fn build<'a>(
+        dimensions: usize,
+        entries: impl IntoIterator<Item = (&'a str, &'a [f32])>,
+    ) -> Self {
+        let mut index = Self::new(dimensions);
+        for (id, vector) in entries {
+            // Only fails on a dimension mismatch
+            let _ = index.insert(id, vector);
+        }
+        index
+    }
+
+    pub fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    /// Live (searchable) entries
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn generation(&self) -> i64 {
+        self.generation
+    }
+
+    pub fn set_generation(&mut self, generation: i64) {
+        if self.generation != generation {
+            self.generation = generation;
+            self.dirty = true;
+        }
+    }
+
+    /// Changed since it was last loaded or saved
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.ids.keys().map(String::as_str)
+    }
+
+    /// Add `id`, replacing its previous vector
+    pub fn insert(&mut self, id: &str, vector: &[f32]) -> Result<()> {
+        if vector.len() != self.dimensions {
+            bail!(
+                "vector has {} dimensions, index has {}",
+                vector.len(),
+                self.dimensions
+            );
+        }
+        self.remove(id);
+        let vector = normalize(vector);
+        let level = level_for(id);
+        let idx = self.nodes.len() as u32;
+        self.nodes.push(Node {
+            id: id.to_string(),
+            vector,
+            links: vec![Vec::new(); level + 1],
+            deleted: false,
+        });
+        self.ids.insert(id.to_string(), idx);
+        self.dirty = true;
+
+        let Some(mut entry) = self.entry else {
+            self.entry = Some(idx);
+            return Ok(());
+        };
+        let query = self.nodes[idx as usize].vector.clone();
+        let top = self.nodes[entry as usize].links.len() - 1;
+        for layer in (level + 1..=top).rev() {
+            entry = self.greedy_closest(&query, entry, layer);
+        }
+        let mut entries = vec![entry];
+        for layer in (0..=level.min(top)).rev() {
+            let found = self.search_layer(&query, &entries, EF_CONSTRUCTION, layer);
+            let neighbours = self.select_neighbours(&found, M);
+            self.nodes[idx as usize].links[layer] = neighbours.clone();
+            let max_links = if layer == 0 { M0 } else { M };
+            for neighbour in neighbours {
+                self.link(neighbour, idx, layer, max_links);
+            }
+            entries = found.into_iter().map(|s| s.node).collect();
+        }
+        if level > top {
+            self.entry = Some(idx);
+        }
+
+        // Tombstones slow every search down; drop them once they dominate
+        if self.deleted > 1024 && self.deleted > self.nodes.len() / 2 {
+            self.compact();
+        }
+        Ok(())
+    }
+
+    /// Stop returning `id`; true if it was indexed
+    pub fn remove(&mut self, id: &str) -> bool {
+        let Some(idx) = self.ids.remove(id) else {
+            return false;
+        };
+        self.nodes[idx as usize].deleted = true;
+        self.deleted += 1;
+        self.dirty = true;
+        true
+    }
+
+    /// Up to `k` ids most similar to `query` (cosine), best first
+    pub fn search(&self, query: &[f32], k: usize) -> Vec<(String, f64)> {
+        let Some(mut entry) = self.entry else {
+            return Vec::new();
+        };
+        if query.len() != self.dimensions || k == 0 {
+            return Vec::new();
+        }
+        let query = normalize(query);
+        let top = self.nodes[entry as usize].links.len() - 1;
+        for layer in (1..=top).rev() {
+            entry = self.greedy_closest(&query, entry, layer);
+        }
+        // Widen the search by the tombstones it has to skip
+        let ef = (k + self.deleted.min(k * 4)).max(EF_SEARCH);
+        self.search_layer(&query, &[entry], ef, 0)
+            .into_iter()
+            .filter(|s| !self.nodes[s.node as usize].deleted)
+            .take(k)
+            .map(|s| (self.nodes[s.node as usize].id.clone(), s.similarity as f64))
+            .collect()
+    }
+
+    /// Rebuild without tombstones
+    pub fn compact(&mut self) {
+        let nodes = std::mem::take(&mut self.nodes);
+        let mut rebuilt = Self::new(self.dimensions);
+        for node in nodes.iter().filter(|n| !n.deleted) {
+            // Vectors were checked on their way in
+            let _ = rebuilt.insert(&node.id, &node.vector);
+        }
+        rebuilt.generation = self.generation;
+        *self = rebuilt;
+    }
+
+    /// Write the index to `path`, atomically replacing any previous file
+    pub fn save(&mut self, path: &Path) -> Result<()> {
+        let tmp = path.with_extension("hnsw.tmp");
+        let file = std::fs::File::create(&tmp)
+            .with_context(|| format!("cannot create {}", tmp.display()))?;
+        let mut out = BufWriter::new(file);
+        out.write_all(MAGIC)?;
+        write_u32(&mut out, FORMAT_VERSION)?;
+        out.write_all(&self.generation.to_le_bytes())?;
+        write_u32(&mut out, self.dimensions as u32)?;
+        write_u32(&mut out, self.entry.unwrap_or(u32::MAX))?;
+        write_u32(&mut out, self.nodes.len() as u32)?;
+        for node in &self.nodes {
+            write_u32(&mut out, node.id.len() as u32)?;
+            out.write_all(node.id.as_bytes())?;
+            out.write_all(&[node.deleted as u8])?;
+            for value in &node.vector {
+                out.write_all(&value.to_le_bytes())?;
+            }
+            write_u32(&mut out, node.links.len() as u32)?;
+            for links in &node.links {
+                write_u32(&mut out, links.len() as u32)?;
+                for link in links {
+                    write_u32(&mut out, *link)?;
+                }
+            }
+        }
+        out.into_inner()
+            .map_err(|e| anyhow::anyhow!("cannot write {}: {e}", tmp.display()))?
+            .sync_all()?;
+        std::fs::rename(&tmp, path)
+            .with_context(|| format!("cannot replace {}", path.display()))?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Read an index written by [`VectorIndex::save`]
+    pub fn load(path: &Path) -> Result<Self> {
+        let file =
+            std::fs::File::open(path).with_context(|| format!("cannot open {}", path.display()))?;
+        let mut input = BufReader::new(file);
+        let mut magic = [0u8; 8];
+        input.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            bail!("{} is not a vector index", path.display());
+        }
+        let version = read_u32(&mut input)?;
+        if version != FORMAT_VERSION {
+            bail!("unsupported vector index version {version}");
+        }
+        let mut generation = [0u8; 8];
+        input.read_exact(&mut generation)?;
+        let mut index = Self::new(read_u32(&mut input)? as usize);
+        index.generation = i64::from_le_bytes(generation);
+        let entry = read_u32(&mut input)?;
+        let count = read_u32(&mut input)? as usize;
+
+        for idx in 0..count {
+            let id_len = read_u32(&mut input)? as usize;
+            let mut id = vec![0u8; id_len];
+            input.read_exact(&mut id)?;
+            let id = String::from_utf8(id).context("invalid id in vector index")?;
+            let mut deleted = [0u8; 1];
+            input.read_exact(&mut deleted)?;
+            let mut vector = Vec::with_capacity(index.dimensions);
+            for _ in 0..index.dimensions {
+                let mut value = [0u8; 4];
+                input.read_exact(&mut value)?;
+                vector.push(f32::from_le_bytes(value));
+            }
+            let levels = read_u32(&mut input)? as usize;
+            if levels == 0 || levels > MAX_LEVEL + 1 {
+                bail!("corrupt vector index: node {idx} has {levels} levels");
+            }
+            let mut links = Vec::with_capacity(levels);
+            for _ in 0..levels {
+                let n = read_u32(&mut input)? as usize;
+                let mut layer = Vec::with_capacity(n);
+                for _ in 0..n {
+                    let link = read_u32(&mut input)?;
+                    if link as usize >= count {
+                        bail!("corrupt vector index: link to missing node {link}");
+                    }
+                    layer.push(link);
+                }
+                links.push(layer);
+            }
+            if deleted[0] != 0 {
+                index.deleted += 1;
+            } else {
+                index.ids.insert(id.clone(), idx as u32);
+            }
+            index.nodes.push(Node {
+                id,
+                vector,
+                links,
+                deleted: deleted[0] != 0,
+            });
+        }
+        index.entry = match entry {
+            u32::MAX => None,
+            e if (e as usize) < count => Some(e),
+            e => bail!("corrupt vector index: entry point {e} out of range"),
+        };
+        Ok(index)
+    }
+
+    /// Walk one layer towards the node closest to `query`
+    fn greedy_closest(&self, query: &[f32], mut current: u32, layer: usize) -> u32 {
+        let mut best = dot(query, &self.nodes[current as usize].vector);
+        loop {
+            let mut moved = false;
+            if let Some(links) = self.nodes[current as usize].links.get(layer) {
+                for &n in links {
+                    let similarity = dot(query, &self.nodes[n as usize].vector);
+                    if similarity > best {
+                        best = similarity;
+                        current = n;
+                        moved = true;
+                    }
+                }
+            }
+            if !moved {
+                return current;
+            }
+        }
+    }
+
+    /// The `ef` nodes closest to `query` reachable on `layer` from `entries`,
+    /// best first
+    fn search_layer(&self, query: &[f32], entries: &[u32], ef: usize, layer: usize) -> Vec<Scored> {
+        let mut visited: HashSet<u32> = HashSet::new();
+        let mut candidates: BinaryHeap<Scored> = BinaryHeap::new();
+        let mut results: BinaryHeap<Reverse<Scored>> = BinaryHeap::new();
+        for &node in entries {
+            if visited.insert(node) {
+                let scored = Scored {
+                    similarity: dot(query, &self.nodes[node as usize].vector),
+                    node,
+                };
+                candidates.push(scored);
+                results.push(Reverse(scored));
+            }
+        }
+        while results.len() > ef {
+            results.pop();
+        }
+
+        while let Some(candidate) = candidates.pop() {
+            let worst = results.peek().map(|r| r.0.similarity).unwrap_or(f32::MIN);
+            if results.len() >= ef && candidate.similarity < worst {
+                break;
+            }
+            let Some(links) = self.nodes[candidate.node as usize].links.get(layer) else {
+                continue;
+            };
+            for &n in links {
+                if !visited.insert(n) {
+                    continue;
+                }
+                let similarity = dot(query, &self.nodes[n as usize].vector);
+                let worst = results.peek().map(|r| r.0.similarity).unwrap_or(f32::MIN);
+                if results.len() < ef || similarity > worst {
+                    let scored = Scored {
+                        similarity,
+                        node: n,
+                    };
+                    candidates.push(scored);
+                    results.push(Reverse(scored));
+                    if results.len() > ef {
+                        results.pop();
+                    }
+                }
+            }
+        }
+
+        let mut found: Vec<Scored> = results.into_iter().map(|r| r.0).collect();
+        found.sort_by(|a, b| b.cmp(a));
+        found
+    }
+
+    /// Link `from` to `to` on `layer`, keeping at most `max_links`
+    fn link(&mut self, from: u32, to: u32, layer: usize, max_links: usize) {
+        let Some(links) = self.nodes[from as usize].links.get(layer) else {
+            return;
+        };
+        let mut links = links.clone();
+        links.push(to);
+        if links.len() > max_links {
+            let base = &self.nodes[from as usize].vector;
+            let mut scored: Vec<Scored> = links
+                .iter()
+                .map(|&n| Scored {
+                    similarity: dot(base, &self.nodes[n as usize].vector),
+                    node: n,
+                })
+                .collect();
+            scored.sort_by(|a, b| b.cmp(a));
+            links = self.select_neighbours(&scored, max_links);
+        }
+        self.nodes[from as usize].links[layer] = links;
+    }
+
+    /// Pick up to `m` of `candidates` (best first) to link to. A candidate
+    /// closer to an already picked one than to the base is skipped at first,
+    /// so links also reach out of a dense cluster towards outliers that
+    /// would otherwise become unreachable; skipped ones fill what is left.
+    fn select_neighbours(&self, candidates: &[Scored], m: usize) -> Vec<u32> {
+        let mut selected: Vec<u32> = Vec::with_capacity(m);
+        let mut skipped = Vec::new();
+        for candidate in candidates {
+            if selected.len() >= m {
+                break;
+            }
+            let vector = &self.nodes[candidate.node as usize].vector;
+            if selected
+                .iter()
+                .all(|&s| candidate.similarity > dot(vector, &self.nodes[s as usize].vector))
+            {
+                selected.push(candidate.node);
+            } else {
+                skipped.push(candidate.node);
+            }
+        }
+        let room = m - selected.len();
+        selected.extend(skipped.into_iter().take(room));
+        selected
+    }
+}
+
+/// Node level drawn from an exponential distribution, seeded by the id so
+/// rebuilding the same entries gives the same graph
+fn level_for(id: &str) -> usize {
+    let digest = Sha256::digest(id.as_bytes());
+    let bits = u64::from_le_bytes(digest[..8].try_into().expect("8 bytes"));
+    // Uniform in (0, 1]
+    let uniform = (bits >> 11) as f64 / (1u64 << 53) as f64;
+    let uniform = 1.0 - uniform;
+    let level = (-uniform.ln() / (M as f64).ln()).floor() as usize;
+    level.min(MAX_LEVEL)
+}
+
+fn normalize(vector: &[f32]) -> Vec<f32> {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        return vector.to_vec();
+    }
+    vector.iter().map(|v| v / norm).collect()
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn write_u32(out: &mut impl Write, value: u32) -> std::io::Result<()> {
+    out.write_all(&value.to_le_bytes())
+}
+
+fn read_u32(input: &mut impl Read) -> std::io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    input.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brute_force(entries: &[(String, Vec<f32>)], query: &[f32], k: usize) -> Vec<String> {
+        let query = normalize(query);
+        let mut scored: Vec<(f32, &String)> = entries
+            .iter()
+            .map(|(id, v)| (dot(&query, &normalize(v)), id))
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored
+            .into_iter()
+            .take(k)
+            .map(|(_, id)| id.clone())
+            .collect()
+    }
+
+    /// Pseudo-random vector, the same for the same seed
+    fn vector(seed: u64, dims: usize) -> Vec<f32> {
+        let mut state = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (0..dims)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                ((state >> 33) as f32 / (1u64 << 31) as f32) - 0.5
+            })
+            .collect()
+    }
+
+    fn entries(n: usize) -> Vec<(String, Vec<f32>)> {
+        (0..n)
+            .map(|i| (format!("proc-{i}"), vector(i as u64, 32)))
+            .collect()
+    }
+
+    #[test]
+    fn test_search_matches_brute_force() {
+        let entries = entries(1000);
+        let dims = entries[0].1.len();
+        let index = VectorIndex::build(
+            dims,
+            entries.iter().map(|(id, v)| (id.as_str(), v.as_slice())),
+        );
+        assert_eq!(index.len(), 1000);
+
+        let mut hits = 0;
+        let mut total = 0;
+        for q in 0..20 {
+            let query = vector(10_000 + q, 32);
+            let expected = brute_force(&entries, &query, 10);
+            let found: Vec<String> = index
+                .search(&query, 10)
+                .into_iter()
+                .map(|(id, _)| id)
+                .collect();
+            total += expected.len();
+            hits += expected.iter().filter(|id| found.contains(id)).count();
+        }
+        // Approximate, but should find nearly all true neighbours
+        assert!(hits * 10 >= total * 9, "recall {hits}/{total}");
+    }
+
+    #[test]
+    fn test_insert_replaces_and_remove_hides() {
+        let mut index = VectorIndex::new(3);
+        index.insert("a", &[1.0, 0.0, 0.0]).unwrap();
+        index.insert("b", &[0.0, 1.0, 0.0]).unwrap();
+        assert!(index.insert("c", &[1.0, 0.0]).is_err());
+
+        assert_eq!(index.search(&[1.0, 0.1, 0.0], 1)[0].0, "a");
+        index.insert("a", &[0.0, 0.0, 1.0]).unwrap();
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.search(&[0.0, 0.0, 1.0], 1)[0].0, "a");
+
+        assert!(index.remove("b"));
+        assert!(!index.remove("b"));
+        let found = index.search(&[0.0, 1.0, 0.0], 5);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0, "a");
+
+        index.compact();
+        assert_eq!(index.nodes.len(), 1);
+        assert_eq!(index.search(&[0.0, 0.0, 1.0], 1)[0].0, "a");
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let path = std::env::temp_dir().join(format!("aios-hnsw-{}.hnsw", uuid::Uuid::new_v4()));
+        let entries = entries(200);
+        let mut index = VectorIndex::build(
+            entries[0].1.len(),
+            entries.iter().map(|(id, v)| (id.as_str(), v.as_slice())),
+        );
+        index.remove("proc-7");
+        index.set_generation(42);
+        assert!(index.is_dirty());
+        index.save(&path).unwrap();
+        assert!(!index.is_dirty());
+
+        let loaded = VectorIndex::load(&path).unwrap();
+        assert_eq!(loaded.generation(), 42);
+        assert_eq!(loaded.len(), 199);
+        assert!(!loaded.ids().any(|id| id == "proc-7"));
+        let query = vector(3, 32);
+        assert_eq!(loaded.search(&query, 5), index.search(&query, 5));
+
+        std::fs::write(&path, b"not an index").unwrap();
+        assert!(VectorIndex::load(&path).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -1,13 +1,44 @@
 //! Working Memory — SQLite-backed warm storage
 //!
-//! Stores goals, tasks, tool calls, decisions, patterns, and agent state.
+//! Stores goals, tasks, tool calls, decisions, patterns, agent state,
+//! self-benchmark runs, checkpoints of long-running tasks, and the
+//! management console's chat sessions.
 //! Retention: 30 days default, then migrated to long-term.
 
 use anyhow::Result;
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, OptionalExtension};
 use std::sync::Mutex;
 
 use crate::proto::memory::*;
+use crate::retention::{self, Category, SubjectColumns};
+
+/// How long a task checkpoint is kept without being updated
+const CHECKPOINT_RETENTION_SECS: i64 = 30 * 86400;
+
+/// Characters of the first message kept as a chat session's title
+const CHAT_TITLE_CHARS: usize = 60;
+
+/// Chat sessions listed when the request gives no limit
+const DEFAULT_CHAT_SESSIONS: i32 = 50;
+
+/// Columns of each table a person or other subject may be mentioned in
+const SUBJECT_COLUMNS: &[SubjectColumns] = &[
+    ("goals", &["description", "result", "metadata_json"]),
+    (
+        "tasks",
+        &["description", "input_json", "output_json", "error"],
+    ),
+    ("tool_calls", &["input_json", "output_json", "reason"]),
+    (
+        "decisions",
+        &["context", "options_json", "chosen", "reasoning", "outcome"],
+    ),
+    ("patterns", &["trigger", "action", "created_from"]),
+    ("agent_states", &["state_json"]),
+    ("task_checkpoints", &["state_json"]),
+    ("chat_sessions", &["title", "summary"]),
+    ("chat_messages", &["content"]),
+];
 
 /// SQLite-backed working memory
 pub struct WorkingMemory {
@@ -92,13 +123,51 @@ impl WorkingMemory {
                 updated_at INTEGER NOT NULL
             );
 
+            CREATE TABLE IF NOT EXISTS benchmark_runs (
+                id TEXT PRIMARY KEY,
+                started_at INTEGER NOT NULL,
+                duration_ms INTEGER NOT NULL,
+                trigger TEXT NOT NULL,
+                build TEXT NOT NULL,
+                results_json BLOB NOT NULL,
+                regressions_json BLOB NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS task_checkpoints (
+                task_id TEXT PRIMARY KEY,
+                state_json BLOB NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS chat_sessions (
+                session_id TEXT PRIMARY KEY,
+                title TEXT NOT NULL,
+                summary TEXT NOT NULL DEFAULT '',
+                summarized_through INTEGER NOT NULL DEFAULT 0,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS chat_messages (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                session_id TEXT NOT NULL,
+                role TEXT NOT NULL,
+                content TEXT NOT NULL,
+                model TEXT NOT NULL DEFAULT '',
+                tokens INTEGER NOT NULL DEFAULT 0,
+                timestamp INTEGER NOT NULL
+            );
+
             CREATE INDEX IF NOT EXISTS idx_goals_status ON goals(status);
             CREATE INDEX IF NOT EXISTS idx_tasks_goal ON tasks(goal_id);
             CREATE INDEX IF NOT EXISTS idx_tasks_status ON tasks(status);
             CREATE INDEX IF NOT EXISTS idx_tool_calls_task ON tool_calls(task_id);
             CREATE INDEX IF NOT EXISTS idx_tool_calls_tool ON tool_calls(tool_name);
             CREATE INDEX IF NOT EXISTS idx_decisions_context ON decisions(context);
-            CREATE INDEX IF NOT EXISTS idx_patterns_trigger ON patterns(trigger);",
+            CREATE INDEX IF NOT EXISTS idx_patterns_trigger ON patterns(trigger);
+            CREATE INDEX IF NOT EXISTS idx_benchmark_runs_started ON benchmark_runs(started_at);
+            CREATE INDEX IF NOT EXISTS idx_chat_sessions_updated ON chat_sessions(updated_at);
+            CREATE INDEX IF NOT EXISTS idx_chat_messages_session ON chat_messages(session_id);",
         )?;
 
         Ok(Self {
@@ -370,6 +439,34 @@ impl WorkingMemory {
         Ok(())
     }
 
+    /// Learned patterns, most used first
+    pub fn list_patterns(&self, limit: i32) -> Result<Vec<Pattern>> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let limit = if limit <= 0 { 50 } else { limit };
+        let mut stmt = conn.prepare(
+            "SELECT id, trigger, action, success_rate, uses, last_used, created_from
+             FROM patterns ORDER BY uses DESC, success_rate DESC LIMIT ?1",
+        )?;
+        let patterns = stmt
+            .query_map(params![limit], |row| {
+                Ok(Pattern {
+                    id: row.get(0)?,
+                    trigger: row.get(1)?,
+                    action: row.get(2)?,
+                    success_rate: row.get(3)?,
+                    uses: row.get(4)?,
+                    last_used: row.get::<_, Option<i64>>(5)?.unwrap_or(0),
+                    created_from: row.get::<_, Option<String>>(6)?.unwrap_or_default(),
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(patterns)
+    }
+
     // --- Pattern Learning ---
 
     /// Extract and store a pattern from a successful task completion
@@ -477,6 +574,346 @@ impl WorkingMemory {
         )?;
         Ok(state)
     }
+
+    // --- Benchmark Runs ---
+
+    pub fn store_benchmark_run(&self, run: &BenchmarkRun) -> Result<()> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        conn.execute(
+            "INSERT OR REPLACE INTO benchmark_runs
+             (id, started_at, duration_ms, trigger, build, results_json, regressions_json)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                run.id,
+                run.started_at,
+                run.duration_ms,
+                run.trigger,
+                run.build,
+                run.results_json,
+                run.regressions_json
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Most recent benchmark runs, newest first
+    pub fn get_benchmark_runs(&self, limit: i32) -> Result<Vec<BenchmarkRun>> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let limit = if limit > 0 { limit } else { 20 };
+        let mut stmt = conn.prepare(
+            "SELECT id, started_at, duration_ms, trigger, build, results_json, regressions_json
+             FROM benchmark_runs ORDER BY started_at DESC LIMIT ?1",
+        )?;
+        let runs = stmt
+            .query_map(params![limit], |row| {
+                Ok(BenchmarkRun {
+                    id: row.get(0)?,
+                    started_at: row.get(1)?,
+                    duration_ms: row.get(2)?,
+                    trigger: row.get(3)?,
+                    build: row.get(4)?,
+                    results_json: row.get(5)?,
+                    regressions_json: row.get(6)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(runs)
+    }
+
+    // --- Task Checkpoints ---
+
+    /// Save a task's checkpoint, dropping checkpoints untouched for longer
+    /// than the retention period (tasks that never finished)
+    pub fn store_task_checkpoint(&self, checkpoint: &TaskCheckpoint) -> Result<()> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        conn.execute(
+            "INSERT OR REPLACE INTO task_checkpoints (task_id, state_json, updated_at)
+             VALUES (?1, ?2, ?3)",
+            params![
+                checkpoint.task_id,
+                checkpoint.state_json,
+                checkpoint.updated_at
+            ],
+        )?;
+        conn.execute(
+            "DELETE FROM task_checkpoints WHERE updated_at < ?1",
+            params![checkpoint.updated_at - CHECKPOINT_RETENTION_SECS],
+        )?;
+        Ok(())
+    }
+
+    /// A task's checkpoint, if it has one
+    pub fn get_task_checkpoint(&self, task_id: &str) -> Result<Option<TaskCheckpoint>> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let checkpoint = conn
+            .query_row(
+                "SELECT task_id, state_json, updated_at FROM task_checkpoints WHERE task_id = ?1",
+                params![task_id],
+                |row| {
+                    Ok(TaskCheckpoint {
+                        task_id: row.get(0)?,
+                        state_json: row.get(1)?,
+                        updated_at: row.get(2)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(checkpoint)
+    }
+
+    pub fn delete_task_checkpoint(&self, task_id: &str) -> Result<()> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        conn.execute(
+            "DELETE FROM task_checkpoints WHERE task_id = ?1",
+            params![task_id],
+        )?;
+        Ok(())
+    }
+
+    // --- Chat Sessions ---
+
+    /// Add a message to its session, starting the session (titled after
+    /// the message) if this is its first
+    pub fn append_chat_message(&self, message: &ChatMessage) -> Result<()> {
+        let mut conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let title: String = message.content.chars().take(CHAT_TITLE_CHARS).collect();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT OR IGNORE INTO chat_sessions (session_id, title, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?3)",
+            params![message.session_id, title.trim(), message.timestamp],
+        )?;
+        tx.execute(
+            "UPDATE chat_sessions SET updated_at = MAX(updated_at, ?2) WHERE session_id = ?1",
+            params![message.session_id, message.timestamp],
+        )?;
+        tx.execute(
+            "INSERT INTO chat_messages (session_id, role, content, model, tokens, timestamp)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                message.session_id,
+                message.role,
+                message.content,
+                message.model,
+                message.tokens,
+                message.timestamp
+            ],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// A chat session with its messages in order, if it exists
+    pub fn get_chat_session(&self, session_id: &str) -> Result<Option<ChatSession>> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let session = conn
+            .query_row(
+                "SELECT session_id, title, summary, summarized_through, created_at, updated_at
+                 FROM chat_sessions WHERE session_id = ?1",
+                params![session_id],
+                chat_session_from_row,
+            )
+            .optional()?;
+        let Some(mut session) = session else {
+            return Ok(None);
+        };
+
+        let mut stmt = conn.prepare(
+            "SELECT session_id, role, content, model, tokens, timestamp
+             FROM chat_messages WHERE session_id = ?1 ORDER BY id ASC",
+        )?;
+        session.messages = stmt
+            .query_map(params![session_id], |row| {
+                Ok(ChatMessage {
+                    session_id: row.get(0)?,
+                    role: row.get(1)?,
+                    content: row.get(2)?,
+                    model: row.get(3)?,
+                    tokens: row.get(4)?,
+                    timestamp: row.get(5)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+        session.message_count = session.messages.len() as i32;
+        Ok(Some(session))
+    }
+
+    /// Chat sessions without their messages, most recently active first
+    pub fn list_chat_sessions(&self, limit: i32) -> Result<Vec<ChatSession>> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let limit = if limit <= 0 {
+            DEFAULT_CHAT_SESSIONS
+        } else {
+            limit
+        };
+        let mut stmt = conn.prepare(
+            "SELECT s.session_id, s.title, s.summary, s.summarized_through, s.created_at,
+                    s.updated_at, (SELECT COUNT(*) FROM chat_messages m
+                                   WHERE m.session_id = s.session_id)
+             FROM chat_sessions s ORDER BY s.updated_at DESC LIMIT ?1",
+        )?;
+        let sessions = stmt
+            .query_map(params![limit], |row| {
+                let mut session = chat_session_from_row(row)?;
+                session.message_count = row.get(6)?;
+                Ok(session)
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(sessions)
+    }
+
+    /// Replace a session's summary of its earlier messages
+    pub fn store_chat_summary(&self, summary: &ChatSummary) -> Result<()> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let updated = conn.execute(
+            "UPDATE chat_sessions SET summary = ?2, summarized_through = ?3 WHERE session_id = ?1",
+            params![
+                summary.session_id,
+                summary.summary,
+                summary.summarized_through
+            ],
+        )?;
+        if updated == 0 {
+            anyhow::bail!("No chat session {}", summary.session_id);
+        }
+        Ok(())
+    }
+
+    // --- Retention ---
+
+    /// Delete the working memory records of `category` older than `before`
+    /// (Unix seconds); goals expire once finished, with their tasks and
+    /// checkpoints
+    pub fn expire(&self, category: Category, before: i64) -> Result<usize> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let removed = match category {
+            Category::Email => conn.execute(
+                "DELETE FROM tool_calls WHERE tool_name LIKE 'email.%' AND timestamp < ?1",
+                params![before],
+            )?,
+            Category::Web => conn.execute(
+                "DELETE FROM tool_calls WHERE tool_name LIKE 'web.%' AND timestamp < ?1",
+                params![before],
+            )?,
+            Category::ToolCalls => conn.execute(
+                "DELETE FROM tool_calls WHERE timestamp < ?1",
+                params![before],
+            )?,
+            Category::Decisions => conn.execute(
+                "DELETE FROM decisions WHERE timestamp < ?1",
+                params![before],
+            )?,
+            Category::Goals => {
+                let finished =
+                    "SELECT id FROM goals WHERE status IN ('completed', 'failed', 'cancelled')
+                                AND COALESCE(NULLIF(completed_at, 0), created_at) < ?1";
+                let checkpoints = conn.execute(
+                    &format!(
+                        "DELETE FROM task_checkpoints WHERE task_id IN
+                         (SELECT id FROM tasks WHERE goal_id IN ({finished}))"
+                    ),
+                    params![before],
+                )?;
+                let tasks = conn.execute(
+                    &format!("DELETE FROM tasks WHERE goal_id IN ({finished})"),
+                    params![before],
+                )?;
+                let goals = conn.execute(
+                    &format!("DELETE FROM goals WHERE id IN ({finished})"),
+                    params![before],
+                )?;
+                checkpoints + tasks + goals
+            }
+            _ => 0,
+        };
+        Ok(removed)
+    }
+
+    /// Delete every record mentioning `pattern` (normalized, see
+    /// [`crate::retention`]); rows removed per table
+    pub fn erase_subject(&self, pattern: &str) -> Result<Vec<(String, usize)>> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let removed = retention::erase_rows(&conn, SUBJECT_COLUMNS, pattern)?;
+        retention::truncate_wal(&conn)?;
+        Ok(removed)
+    }
+
+    /// Records still mentioning `pattern`
+    pub fn count_subject(&self, pattern: &str) -> Result<usize> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        retention::count_rows(&conn, SUBJECT_COLUMNS, pattern)
+    }
+
+    // --- Dumps ---
+
+    /// Every row of a working memory table (see [`crate::dump`])
+    pub fn dump(&self, collection: &str) -> Result<Vec<serde_json::Value>> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        crate::dump::dump_table(&conn, collection)
+    }
+
+    /// Insert or replace dumped rows into a working memory table
+    pub fn restore(&self, collection: &str, records: &[serde_json::Value]) -> Result<usize> {
+        let mut conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        crate::dump::restore_table(&mut conn, collection, records)
+    }
+}
+
+fn chat_session_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<ChatSession> {
+    Ok(ChatSession {
+        session_id: row.get(0)?,
+        title: row.get(1)?,
+        summary: row.get(2)?,
+        summarized_through: row.get(3)?,
+        created_at: row.get(4)?,
+        updated_at: row.get(5)?,
+        ..Default::default()
+    })
 }
 
 #[cfg(test)]
@@ -605,6 +1042,40 @@ mod tests {
         wm.store_tool_call(&record).unwrap();
     }
 
+    #[test]
+    fn test_dump_restore_roundtrip() {
+        let wm = test_db();
+        wm.store_tool_call(&ToolCallRecord {
+            id: "tc-1".into(),
+            task_id: "task-1".into(),
+            tool_name: "fs.read".into(),
+            input_json: b"{\"path\":\"/etc/hosts\"}".to_vec(),
+            output_json: vec![0xff, 0x00, 0x7f],
+            success: true,
+            timestamp: 1000,
+            ..Default::default()
+        })
+        .unwrap();
+
+        let dumped = wm.dump("tool_calls").unwrap();
+        assert_eq!(dumped.len(), 1);
+        assert_eq!(
+            dumped[0]["input_json"]["$text"],
+            "{\"path\":\"/etc/hosts\"}"
+        );
+        assert_eq!(dumped[0]["output_json"]["$hex"], "ff007f");
+
+        let restored = test_db();
+        assert_eq!(restored.restore("tool_calls", &dumped).unwrap(), 1);
+        assert_eq!(restored.dump("tool_calls").unwrap(), dumped);
+
+        // Unknown tables and columns are refused
+        assert!(wm.dump("sqlite_master").is_err());
+        let mut bad = dumped[0].clone();
+        bad["nope"] = serde_json::json!(1);
+        assert!(restored.restore("tool_calls", &[bad]).is_err());
+    }
+
     #[test]
     fn test_store_decision() {
         let wm = test_db();
@@ -646,6 +1117,30 @@ mod tests {
         assert!(result.found);
     }
 
+    #[test]
+    fn test_list_patterns_most_used_first() {
+        let wm = test_db();
+        for (id, uses) in [("rare", 1), ("common", 40), ("mid", 7)] {
+            wm.store_pattern(&Pattern {
+                id: id.into(),
+                trigger: format!("{id} trigger"),
+                action: "act".into(),
+                success_rate: 0.5,
+                uses,
+                ..Default::default()
+            })
+            .unwrap();
+        }
+        let ids: Vec<String> = wm
+            .list_patterns(0)
+            .unwrap()
+            .into_iter()
+            .map(|p| p.id)
+            .collect();
+        assert_eq!(ids, vec!["common", "mid", "rare"]);
+        assert_eq!(wm.list_patterns(1).unwrap().len(), 1);
+    }
+
     #[test]
     fn test_update_pattern_stats_success() {
         let wm = test_db();
@@ -740,6 +1235,105 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_benchmark_runs_newest_first() {
+        let wm = test_db();
+        for (id, started_at) in [("run-1", 100), ("run-2", 300), ("run-3", 200)] {
+            wm.store_benchmark_run(&BenchmarkRun {
+                id: id.into(),
+                started_at,
+                duration_ms: 5000,
+                trigger: "nightly".into(),
+                build: "0.1.0+1".into(),
+                results_json: b"[]".to_vec(),
+                regressions_json: b"[]".to_vec(),
+            })
+            .unwrap();
+        }
+
+        let runs = wm.get_benchmark_runs(2).unwrap();
+        let ids: Vec<&str> = runs.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["run-2", "run-3"]);
+        assert_eq!(wm.get_benchmark_runs(0).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_task_checkpoint_lifecycle() {
+        let wm = test_db();
+        assert!(wm.get_task_checkpoint("task-1").unwrap().is_none());
+
+        let checkpoint = |task_id: &str, updated_at| TaskCheckpoint {
+            task_id: task_id.into(),
+            state_json: b"{\"completed\":[]}".to_vec(),
+            updated_at,
+        };
+        wm.store_task_checkpoint(&checkpoint("stale", 1_000))
+            .unwrap();
+        wm.store_task_checkpoint(&checkpoint("task-1", 1_000 + CHECKPOINT_RETENTION_SECS + 1))
+            .unwrap();
+        let stored = wm.get_task_checkpoint("task-1").unwrap().unwrap();
+        assert_eq!(stored.state_json, b"{\"completed\":[]}");
+        // Storing one checkpoint expires the ones left behind
+        assert!(wm.get_task_checkpoint("stale").unwrap().is_none());
+
+        wm.delete_task_checkpoint("task-1").unwrap();
+        assert!(wm.get_task_checkpoint("task-1").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_chat_session_lifecycle() {
+        let wm = test_db();
+        assert!(wm.get_chat_session("chat-1").unwrap().is_none());
+
+        let message = |session_id: &str, role: &str, content: &str, timestamp| ChatMessage {
+            session_id: session_id.into(),
+            role: role.into(),
+            content: content.into(),
+            model: String::new(),
+            tokens: 0,
+            timestamp,
+        };
+        wm.append_chat_message(&message("chat-1", "user", "Why is the disk full?", 100))
+            .unwrap();
+        wm.append_chat_message(&message(
+            "chat-1",
+            "assistant",
+            "Old logs in /var/log.",
+            101,
+        ))
+        .unwrap();
+        wm.append_chat_message(&message("chat-2", "user", "Hello", 200))
+            .unwrap();
+
+        let session = wm.get_chat_session("chat-1").unwrap().unwrap();
+        assert_eq!(session.title, "Why is the disk full?");
+        assert_eq!(session.created_at, 100);
+        assert_eq!(session.updated_at, 101);
+        let roles: Vec<&str> = session.messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, vec!["user", "assistant"]);
+
+        wm.store_chat_summary(&ChatSummary {
+            session_id: "chat-1".into(),
+            summary: "Disk full from logs".into(),
+            summarized_through: 2,
+        })
+        .unwrap();
+        assert!(wm
+            .store_chat_summary(&ChatSummary {
+                session_id: "missing".into(),
+                ..Default::default()
+            })
+            .is_err());
+
+        let sessions = wm.list_chat_sessions(0).unwrap();
+        let ids: Vec<&str> = sessions.iter().map(|s| s.session_id.as_str()).collect();
+        assert_eq!(ids, vec!["chat-2", "chat-1"]);
+        assert_eq!(sessions[1].message_count, 2);
+        assert_eq!(sessions[1].summary, "Disk full from logs");
+        assert!(sessions[1].messages.is_empty());
+        assert_eq!(wm.list_chat_sessions(1).unwrap().len(), 1);
+    }
+
     #[test]
     fn test_goal_upsert() {
         let wm = test_db();
@@ -845,4 +1439,42 @@ mod tests {
         let tasks = wm.get_tasks_for_goal("goal-1").unwrap();
         assert_eq!(tasks.len(), 5);
     }
+
+    #[test]
+    fn test_expire_and_erase_subject() {
+        let wm = test_db();
+        for (id, tool, timestamp) in [
+            ("tc-1", "email.send", 100),
+            ("tc-2", "email.send", 5000),
+            ("tc-3", "fs.read", 100),
+        ] {
+            wm.store_tool_call(&ToolCallRecord {
+                id: id.into(),
+                tool_name: tool.into(),
+                input_json: format!("{{\"to\":\"alice@example.com\",\"id\":\"{id}\"}}")
+                    .into_bytes(),
+                success: true,
+                timestamp,
+                ..Default::default()
+            })
+            .unwrap();
+        }
+        wm.store_goal(&GoalRecord {
+            id: "goal-1".into(),
+            description: "Reply to Alice".into(),
+            status: "completed".into(),
+            created_at: 100,
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert_eq!(wm.expire(Category::Email, 1000).unwrap(), 1);
+        assert_eq!(wm.expire(Category::Goals, 1000).unwrap(), 1);
+        assert_eq!(wm.dump("tool_calls").unwrap().len(), 2);
+
+        assert_eq!(wm.count_subject("alice@example.com").unwrap(), 2);
+        let removed = wm.erase_subject("alice@example.com").unwrap();
+        assert!(removed.contains(&("tool_calls".to_string(), 2)));
+        assert_eq!(wm.count_subject("alice@example.com").unwrap(), 0);
+    }
 }
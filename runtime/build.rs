@@ -1,8 +1,13 @@
+#[path = "../agent-core/build_support.rs"]
+mod build_support;
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    build_support::emit_build_info();
     tonic_build::configure().build_server(true).compile_protos(
         &[
             "../agent-core/proto/common.proto",
             "../agent-core/proto/runtime.proto",
+            "../agent-core/proto/memory.proto",
         ],
         &["../agent-core/proto/"],
     )?;
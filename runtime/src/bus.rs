@@ -0,0 +1,106 @@
+//! Event bus publisher — messages for the memory service's event bus
+//!
+//! Publishing never blocks the caller: messages are queued and sent by a
+//! background task, which connects to the memory service (`AIOS_MEMORY_ADDR`)
+//! when it has something to send. Messages queued while the memory service
+//! cannot be reached are dropped, as are messages beyond a full queue.
+//! Messages are sent as the `service:runtime` identity.
+
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+use crate::proto::memory::memory_service_client::MemoryServiceClient;
+use crate::proto::memory::BusMessage;
+
+/// Messages queued for sending
+const QUEUE_CAPACITY: usize = 256;
+
+/// Source the messages are published as
+const SOURCE: &str = "runtime";
+
+/// Identity the messages are sent as (`x-aios-identity`)
+const IDENTITY: &str = "service:runtime";
+
+/// Handle for publishing on the event bus; clones share the queue
+#[derive(Clone)]
+pub struct BusPublisher {
+    sender: mpsc::Sender<BusMessage>,
+}
+
+impl BusPublisher {
+    /// Start the sending task. Must be called within a Tokio runtime.
+    pub fn start() -> Self {
+        let addr = std::env::var("AIOS_MEMORY_ADDR")
+            .unwrap_or_else(|_| "http://127.0.0.1:50053".to_string());
+        let (sender, mut receiver) = mpsc::channel::<BusMessage>(QUEUE_CAPACITY);
+        tokio::spawn(async move {
+            let mut client = None;
+            while let Some(message) = receiver.recv().await {
+                if client.is_none() {
+                    match connect(addr.clone()).await {
+                        Ok(c) => client = Some(c),
+                        Err(e) => {
+                            debug!("Dropped bus message on {}: {e}", message.topic);
+                            continue;
+                        }
+                    }
+                }
+                let Some(memory) = client.as_mut() else {
+                    continue;
+                };
+                let topic = message.topic.clone();
+                if let Err(e) = memory.publish(message).await {
+                    warn!("Failed to publish on {topic}: {e}");
+                    client = None;
+                }
+            }
+        });
+        Self { sender }
+    }
+
+    /// Queue `payload` for publishing on `topic`
+    pub fn publish(&self, topic: &str, payload: serde_json::Value) {
+        let message = BusMessage {
+            topic: topic.to_string(),
+            source: SOURCE.to_string(),
+            payload_json: payload.to_string().into_bytes(),
+            timestamp: 0,
+            event: None,
+        };
+        if self.sender.try_send(message).is_err() {
+            debug!("Event bus queue full; dropped a message on {topic}");
+        }
+    }
+}
+
+/// Interceptor naming this service on every call
+#[derive(Debug, Clone, Copy, Default)]
+struct ActAsService;
+
+impl tonic::service::Interceptor for ActAsService {
+    fn call(
+        &mut self,
+        mut request: tonic::Request<()>,
+    ) -> Result<tonic::Request<()>, tonic::Status> {
+        request.metadata_mut().insert(
+            "x-aios-identity",
+            tonic::metadata::MetadataValue::from_static(IDENTITY),
+        );
+        Ok(request)
+    }
+}
+
+/// Connect to the memory service, naming this service on every call
+async fn connect(
+    addr: String,
+) -> Result<
+    MemoryServiceClient<
+        tonic::service::interceptor::InterceptedService<tonic::transport::Channel, ActAsService>,
+    >,
+    tonic::transport::Error,
+> {
+    let channel = tonic::transport::Endpoint::from_shared(addr)?
+        .connect()
+        .await?;
+    Ok(MemoryServiceClient::with_interceptor(channel, ActAsService))
+}
@@ -6,23 +6,30 @@ use std::sync::Arc;
 use std::time::Instant;
 
 use tokio::sync::Mutex;
+use tokio_stream::StreamExt;
 use tonic::{Request, Response, Status};
 use tracing::{error, info, warn};
 
 use crate::inference::InferenceEngine;
 use crate::model_manager::ModelManager;
-use crate::proto::common::{Empty, HealthStatus, Status as ProtoStatus};
+use crate::proto::common::{ApiVersion, BuildInfo, Empty, HealthStatus, Status as ProtoStatus};
 use crate::proto::runtime::ai_runtime_server::AiRuntime;
 use crate::proto::runtime::{
-    InferChunk, InferRequest, InferResponse, LoadModelRequest, ModelList, ModelStatus,
-    UnloadModelRequest,
+    EmbedRequest, EmbedResponse, InferChunk, InferRequest, InferResponse, LoadModelRequest,
+    ModelList, ModelStatus, UnloadModelRequest,
 };
+use crate::reservation::SlotPool;
+
+/// Model name that stands for whichever embedding model is loaded
+const EMBEDDING_ALIAS: &str = "embedding";
 
 /// Shared gRPC service implementation.
 pub struct AIRuntimeService {
     pub model_manager: Arc<Mutex<ModelManager>>,
     pub inference_engine: Arc<InferenceEngine>,
     pub start_time: Instant,
+    /// Inference slots, with a share reserved for interactive requests
+    pub slots: SlotPool,
 }
 
 #[tonic::async_trait]
@@ -97,6 +104,7 @@ impl AiRuntime for AIRuntimeService {
         );
 
         let (port, model_name) = self.resolve_model(&req).await?;
+        let _slot = self.acquire_slot(&req).await?;
 
         match self.inference_engine.infer(port, &model_name, &req).await {
             Ok(resp) => Ok(Response::new(resp)),
@@ -126,13 +134,28 @@ impl AiRuntime for AIRuntimeService {
         );
 
         let (port, model_name) = self.resolve_model(&req).await?;
+        let slot = self.acquire_slot(&req).await?;
 
         match self
             .inference_engine
             .stream_infer(port, &model_name, &req)
             .await
         {
-            Ok(stream) => Ok(Response::new(stream)),
+            // Forward the chunks so the slot is held until the stream ends
+            Ok(mut stream) => {
+                let (tx, rx) = tokio::sync::mpsc::channel(32);
+                tokio::spawn(async move {
+                    let _slot = slot;
+                    while let Some(chunk) = stream.next().await {
+                        if tx.send(chunk).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+                Ok(Response::new(tokio_stream::wrappers::ReceiverStream::new(
+                    rx,
+                )))
+            }
             Err(e) => {
                 error!(model = %model_name, "Stream inference failed: {e:#}");
                 Err(Status::internal(format!("Stream inference failed: {e:#}")))
@@ -140,6 +163,48 @@ impl AiRuntime for AIRuntimeService {
         }
     }
 
+    // ------------------------------------------------------------------
+    // Embed
+    // ------------------------------------------------------------------
+    async fn embed(
+        &self,
+        request: Request<EmbedRequest>,
+    ) -> Result<Response<EmbedResponse>, Status> {
+        let req = request.into_inner();
+        info!(
+            model = %req.model,
+            texts = req.texts.len(),
+            agent = %req.requesting_agent,
+            "gRPC Embed"
+        );
+        if req.texts.is_empty() {
+            return Ok(Response::new(EmbedResponse {
+                model_used: req.model,
+                ..Default::default()
+            }));
+        }
+
+        let (port, model_name) = self.resolve_embedding_model(&req.model).await?;
+        // Embedding is background work; it never takes a reserved slot
+        let _slot = self
+            .slots
+            .acquire("")
+            .await
+            .map_err(|_| Status::unavailable("Inference slots closed"))?;
+
+        match self
+            .inference_engine
+            .embed(port, &model_name, &req.texts)
+            .await
+        {
+            Ok(resp) => Ok(Response::new(resp)),
+            Err(e) => {
+                error!(model = %model_name, "Embedding failed: {e:#}");
+                Err(Status::internal(format!("Embedding failed: {e:#}")))
+            }
+        }
+    }
+
     // ------------------------------------------------------------------
     // HealthCheck
     // ------------------------------------------------------------------
@@ -175,6 +240,28 @@ impl AiRuntime for AIRuntimeService {
             details,
         }))
     }
+
+    // ------------------------------------------------------------------
+    // GetApiVersion
+    // ------------------------------------------------------------------
+    async fn get_api_version(
+        &self,
+        _request: Request<Empty>,
+    ) -> Result<Response<ApiVersion>, Status> {
+        Ok(Response::new(crate::api_version::api_version(
+            crate::proto::runtime::ai_runtime_server::SERVICE_NAME,
+        )))
+    }
+
+    async fn get_build_info(
+        &self,
+        _request: Request<Empty>,
+    ) -> Result<Response<BuildInfo>, Status> {
+        Ok(Response::new(crate::build_info::build_info(
+            crate::proto::runtime::ai_runtime_server::SERVICE_NAME,
+            Vec::new(),
+        )))
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -189,6 +276,12 @@ impl AIRuntimeService {
 
         // 1. Explicit model name.
         if !req.model.is_empty() {
+            if mgr.serves_embeddings(&req.model) == Some(true) {
+                return Err(Status::failed_precondition(format!(
+                    "Model '{}' serves embeddings, not completions",
+                    req.model
+                )));
+            }
             if let Some(port) = mgr.model_port(&req.model) {
                 return Ok((port, req.model.clone()));
             }
@@ -217,10 +310,10 @@ impl AIRuntimeService {
             }
         }
 
-        // 3. Last resort: any ready model.
+        // 3. Last resort: any ready completion model.
         let models = mgr.list_models();
         for m in &models {
-            if m.status == "ready" {
+            if m.status == "ready" && !m.embedding {
                 if let Some(port) = mgr.model_port(&m.model_name) {
                     return Ok((port, m.model_name.clone()));
                 }
@@ -231,6 +324,39 @@ impl AIRuntimeService {
             "No model available for inference.  Load a model first with LoadModel.",
         ))
     }
+
+    /// Resolve the model to embed with: the named one, or for an empty name
+    /// (or the `embedding` alias) the first ready embedding model
+    async fn resolve_embedding_model(&self, model: &str) -> Result<(u16, String), Status> {
+        let mut mgr = self.model_manager.lock().await;
+        let name = if model.is_empty() || model == EMBEDDING_ALIAS {
+            mgr.first_ready_embedding_model().ok_or_else(|| {
+                Status::unavailable(
+                    "No embedding model available.  Load one with LoadModel and embedding set.",
+                )
+            })?
+        } else if mgr.serves_embeddings(model) == Some(false) {
+            return Err(Status::failed_precondition(format!(
+                "Model '{model}' was not loaded for embeddings"
+            )));
+        } else {
+            model.to_string()
+        };
+        mgr.model_port(&name)
+            .map(|port| (port, name.clone()))
+            .ok_or_else(|| Status::unavailable(format!("Embedding model '{name}' is not ready")))
+    }
+
+    /// Wait for an inference slot; background requests only get the shared ones
+    async fn acquire_slot(
+        &self,
+        req: &InferRequest,
+    ) -> Result<tokio::sync::OwnedSemaphorePermit, Status> {
+        self.slots
+            .acquire(&req.workload)
+            .await
+            .map_err(|_| Status::unavailable("Inference slots closed"))
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -246,6 +372,7 @@ mod tests {
             model_manager: Arc::new(Mutex::new(ModelManager::new())),
             inference_engine: Arc::new(InferenceEngine::new()),
             start_time: Instant::now(),
+            slots: SlotPool::new(2, 20.0),
         }
     }
 
@@ -285,6 +412,7 @@ mod tests {
             intelligence_level: String::new(),
             requesting_agent: "test".to_string(),
             task_id: "t1".to_string(),
+            workload: String::new(),
         };
         let err = svc.infer(Request::new(req)).await.unwrap_err();
         assert_eq!(err.code(), tonic::Code::Unavailable);
@@ -302,6 +430,7 @@ mod tests {
             intelligence_level: "reactive".to_string(),
             requesting_agent: "test".to_string(),
             task_id: "t1".to_string(),
+            workload: String::new(),
         };
         let err = svc.infer(Request::new(req)).await.unwrap_err();
         assert_eq!(err.code(), tonic::Code::InvalidArgument);
@@ -319,11 +448,40 @@ mod tests {
             intelligence_level: "strategic".to_string(),
             requesting_agent: "test".to_string(),
             task_id: "t1".to_string(),
+            workload: String::new(),
         };
         let err = svc.infer(Request::new(req)).await.unwrap_err();
         assert_eq!(err.code(), tonic::Code::FailedPrecondition);
     }
 
+    #[tokio::test]
+    async fn test_embed_without_model() {
+        let svc = make_service();
+        let empty = svc
+            .embed(Request::new(EmbedRequest::default()))
+            .await
+            .expect("embedding nothing should succeed")
+            .into_inner();
+        assert!(empty.embeddings.is_empty());
+
+        let req = EmbedRequest {
+            model: "nomic-embed".to_string(),
+            texts: vec!["disk full".to_string()],
+            requesting_agent: "memory".to_string(),
+        };
+        let err = svc.embed(Request::new(req)).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::Unavailable);
+
+        let req = EmbedRequest {
+            model: EMBEDDING_ALIAS.to_string(),
+            texts: vec!["disk full".to_string()],
+            requesting_agent: "memory".to_string(),
+        };
+        let err = svc.embed(Request::new(req)).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::Unavailable);
+        assert!(err.message().contains("No embedding model"));
+    }
+
     #[tokio::test]
     async fn test_unload_nonexistent() {
         let svc = make_service();
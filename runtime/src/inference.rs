@@ -1,7 +1,8 @@
 //! Inference engine — calls the llama-server OpenAI-compatible API.
 //!
 //! Each managed model exposes `/v1/chat/completions` on its allocated port.
-//! This module provides both single-shot and streaming inference wrappers.
+//! This module provides both single-shot and streaming inference wrappers,
+//! and batch embedding through `/v1/embeddings`.
 
 use std::time::Instant;
 
@@ -11,7 +12,7 @@ use tokio::sync::mpsc;
 use tokio_stream::wrappers::ReceiverStream;
 use tracing::{debug, error, info, warn};
 
-use crate::proto::runtime::{InferChunk, InferRequest, InferResponse};
+use crate::proto::runtime::{EmbedResponse, Embedding, InferChunk, InferRequest, InferResponse};
 
 // ---------------------------------------------------------------------------
 // HTTP request / response types (llama.cpp OpenAI-compat API)
@@ -58,6 +59,22 @@ struct ChatDelta {
     content: Option<String>,
 }
 
+#[derive(Debug, Serialize)]
+struct EmbeddingsRequest<'a> {
+    input: &'a [String],
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingsResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingData {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
 #[derive(Debug, Deserialize)]
 #[allow(dead_code)]
 struct UsageInfo {
@@ -354,6 +371,69 @@ impl InferenceEngine {
 
         Ok(ReceiverStream::new(rx))
     }
+
+    // ------------------------------------------------------------------
+    // Embeddings
+    // ------------------------------------------------------------------
+
+    /// Embed `texts` in one request to the llama-server instance on `port`.
+    /// Vectors come back in the order of `texts`.
+    pub async fn embed(
+        &self,
+        port: u16,
+        model_name: &str,
+        texts: &[String],
+    ) -> Result<EmbedResponse> {
+        let url = format!("http://127.0.0.1:{port}/v1/embeddings");
+        let start = Instant::now();
+
+        let resp = self
+            .http_client
+            .post(&url)
+            .json(&EmbeddingsRequest { input: texts })
+            .send()
+            .await
+            .with_context(|| format!("HTTP request to llama-server on port {port} failed"))?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body_text = resp
+                .text()
+                .await
+                .unwrap_or_else(|_| "<unreadable>".to_string());
+            bail!("llama-server returned HTTP {status} on port {port}: {body_text}");
+        }
+
+        let parsed: EmbeddingsResponse = resp
+            .json()
+            .await
+            .context("Failed to parse embeddings response JSON")?;
+        let embeddings = ordered_embeddings(parsed);
+        if embeddings.len() != texts.len() {
+            bail!(
+                "llama-server returned {} embeddings for {} texts",
+                embeddings.len(),
+                texts.len()
+            );
+        }
+
+        let latency_ms = start.elapsed().as_millis() as i64;
+        let dimensions = embeddings.first().map_or(0, |e| e.values.len()) as i32;
+        debug!(
+            model = %model_name,
+            texts = texts.len(),
+            dimensions,
+            latency_ms,
+            "Embedding complete"
+        );
+
+        Ok(EmbedResponse {
+            embeddings,
+            model_used: model_name.to_string(),
+            dimensions,
+            latency_ms,
+        })
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -375,6 +455,18 @@ fn build_messages(system_prompt: &str, user_prompt: &str) -> Vec<ChatMessage> {
     msgs
 }
 
+/// Vectors of an embeddings response in input order
+fn ordered_embeddings(mut response: EmbeddingsResponse) -> Vec<Embedding> {
+    response.data.sort_by_key(|d| d.index);
+    response
+        .data
+        .into_iter()
+        .map(|d| Embedding {
+            values: d.embedding,
+        })
+        .collect()
+}
+
 // ---------------------------------------------------------------------------
 // Tests
 // ---------------------------------------------------------------------------
@@ -434,6 +526,23 @@ mod tests {
         assert!(resp.choices[0].finish_reason.is_none());
     }
 
+    #[test]
+    fn test_embeddings_response_in_input_order() {
+        let json = r#"{
+            "object": "list",
+            "data": [
+                {"object": "embedding", "index": 1, "embedding": [0.0, 1.0]},
+                {"object": "embedding", "index": 0, "embedding": [1.0, 0.0]}
+            ],
+            "model": "nomic-embed"
+        }"#;
+        let resp: EmbeddingsResponse = serde_json::from_str(json).unwrap();
+        let embeddings = ordered_embeddings(resp);
+        assert_eq!(embeddings.len(), 2);
+        assert_eq!(embeddings[0].values, vec![1.0, 0.0]);
+        assert_eq!(embeddings[1].values, vec![0.0, 1.0]);
+    }
+
     #[test]
     fn test_chat_completion_done_chunk_deserialize() {
         let json = r#"{
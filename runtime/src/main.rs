@@ -2,11 +2,12 @@
 //!
 //! Exposes a gRPC interface on port 50055 that lets other aiOS services:
 //!   - Load / unload GGUF models (spawns llama-server processes)
-//!   - Run single-shot or streaming inference
+//!   - Run single-shot or streaming inference, and embed text
 //!   - Query model health and availability
 //!
 //! Each loaded model is backed by an independent `llama-server` process
 //! communicating over the OpenAI-compatible HTTP API on a per-model port.
+//! GGUF embedding models found at startup are served with `--embedding`.
 
 use std::sync::Arc;
 use std::time::{Duration, Instant};
@@ -16,16 +17,25 @@ use tokio::sync::Mutex;
 use tonic::transport::Server;
 use tracing::{error, info};
 
+#[path = "../../agent-core/src/api_compat.rs"]
+mod api_version;
+#[path = "../../agent-core/src/build_info.rs"]
+mod build_info;
+mod bus;
 mod grpc_service;
 mod inference;
 mod model_manager;
+mod reservation;
 
 pub mod proto {
     pub mod runtime {
-        tonic::include_proto!("aios.runtime");
+        tonic::include_proto!("aios.v1.runtime");
     }
     pub mod common {
-        tonic::include_proto!("aios.common");
+        tonic::include_proto!("aios.v1.common");
+    }
+    pub mod memory {
+        tonic::include_proto!("aios.v1.memory");
     }
 }
 
@@ -51,14 +61,28 @@ async fn main() -> Result<()> {
     let inference_engine = Arc::new(InferenceEngine::new());
     let start_time = Instant::now();
 
-    // Spawn background health-check task.
+    // Spawn background health-check task; models that fail are announced
+    // on the event bus as `runtime.model_failed`
     let health_mgr = Arc::clone(&model_manager);
+    let bus = bus::BusPublisher::start();
     tokio::spawn(async move {
         let mut interval = tokio::time::interval(HEALTH_CHECK_INTERVAL);
         loop {
             interval.tick().await;
             let mut mgr = health_mgr.lock().await;
+            let failed_before = failed_models(&mgr);
             mgr.health_check_all().await;
+            for model in mgr.list_models() {
+                if model.status.starts_with("error") && !failed_before.contains(&model.model_name) {
+                    bus.publish(
+                        "runtime.model_failed",
+                        serde_json::json!({
+                            "model": model.model_name,
+                            "status": model.status,
+                        }),
+                    );
+                }
+            }
         }
     });
 
@@ -102,6 +126,7 @@ async fn main() -> Result<()> {
                             path = %path.display(),
                             size_mb = file_size / 1_000_000,
                             ctx,
+                            embedding = model_manager::is_embedding_gguf(&file_name),
                             "Auto-loading model"
                         );
 
@@ -112,6 +137,7 @@ async fn main() -> Result<()> {
                             gpu_layers: 0,
                             threads,
                             port: 0,
+                            embedding: model_manager::is_embedding_gguf(&file_name),
                         };
 
                         match mgr.load_model(req).await {
@@ -135,6 +161,7 @@ async fn main() -> Result<()> {
         model_manager,
         inference_engine,
         start_time,
+        slots: reservation::SlotPool::from_env(),
     };
 
     let addr = "[::]:50055".parse().context("Invalid listen address")?;
@@ -162,6 +189,7 @@ async fn main() -> Result<()> {
     };
 
     Server::builder()
+        .layer(api_version::legacy_shim())
         .add_service(AiRuntimeServer::new(service))
         .serve_with_shutdown(addr, shutdown)
         .await
@@ -175,6 +203,15 @@ async fn main() -> Result<()> {
 // Tests
 // ---------------------------------------------------------------------------
 
+/// Names of the models in an error state
+fn failed_models(mgr: &ModelManager) -> Vec<String> {
+    mgr.list_models()
+        .into_iter()
+        .filter(|m| m.status.starts_with("error"))
+        .map(|m| m.model_name)
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -197,6 +234,7 @@ mod tests {
             intelligence_level: String::new(),
             requesting_agent: String::new(),
             task_id: String::new(),
+            workload: String::new(),
         };
     }
 
@@ -3,7 +3,8 @@
 //! Each loaded model runs as an independent llama-server process bound to a
 //! unique port on 127.0.0.1.  The manager handles lifecycle (spawn, health
 //! polling, graceful / forced shutdown) and provides model selection by
-//! intelligence level.
+//! intelligence level.  Models loaded for embeddings run with `--embedding`
+//! and are kept out of completion routing.
 
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -53,6 +54,8 @@ pub(crate) struct ManagedModel {
     context_length: i32,
     gpu_layers: i32,
     threads: i32,
+    /// Serves `/v1/embeddings` rather than completions
+    embedding: bool,
 }
 
 /// Top-level model manager that owns all managed models.
@@ -179,6 +182,7 @@ impl ModelManager {
             ctx,
             gpu_layers,
             threads,
+            embedding = req.embedding,
             "Spawning llama-server"
         );
 
@@ -197,6 +201,7 @@ impl ModelManager {
             .arg(port.to_string())
             .arg("--host")
             .arg("127.0.0.1")
+            .args(req.embedding.then_some("--embedding"))
             .kill_on_drop(true)
             .stdout(std::process::Stdio::null())
             .stderr(std::process::Stdio::null())
@@ -217,6 +222,7 @@ impl ModelManager {
             context_length: ctx,
             gpu_layers,
             threads,
+            embedding: req.embedding,
         };
 
         // Wait for the health endpoint to come up (up to 120 s for large models).
@@ -502,12 +508,13 @@ impl ModelManager {
     }
 
     /// Try models in priority order, using partial name matching against loaded
-    /// model names.  Returns the first model that is ready.
+    /// model names.  Returns the first completion model that is ready.
     fn first_ready_from(&self, candidates: &[&str]) -> Option<String> {
         for candidate in candidates {
             let candidate_lower = candidate.to_lowercase();
             for (name, model) in &self.models {
                 if matches!(model.status, ModelState::Ready)
+                    && !model.embedding
                     && name.to_lowercase().contains(&candidate_lower)
                 {
                     return Some(name.clone());
@@ -527,9 +534,33 @@ impl ModelManager {
     fn first_ready_model(&self) -> Option<String> {
         self.models
             .values()
-            .find(|m| matches!(m.status, ModelState::Ready))
+            .find(|m| matches!(m.status, ModelState::Ready) && !m.embedding)
             .map(|m| m.name.clone())
     }
+
+    // ------------------------------------------------------------------
+    // Embedding models
+    // ------------------------------------------------------------------
+
+    /// Whether a loaded model serves embeddings (`None` if it is not loaded).
+    pub fn serves_embeddings(&self, name: &str) -> Option<bool> {
+        self.models.get(name).map(|m| m.embedding)
+    }
+
+    /// The first ready model loaded for embeddings.
+    pub fn first_ready_embedding_model(&self) -> Option<String> {
+        self.models
+            .values()
+            .find(|m| matches!(m.status, ModelState::Ready) && m.embedding)
+            .map(|m| m.name.clone())
+    }
+}
+
+/// Whether a GGUF file holds an embedding model, judged by its name
+/// (`nomic-embed-text-v1.5`, `mxbai-embed-large`, `bge-small-en` …).
+pub fn is_embedding_gguf(file_stem: &str) -> bool {
+    let stem = file_stem.to_lowercase();
+    stem.contains("embed") || ["bge-", "gte-", "e5-"].iter().any(|p| stem.starts_with(p))
 }
 
 // ---------------------------------------------------------------------------
@@ -544,6 +575,7 @@ fn model_to_status(m: &ManagedModel) -> ModelStatus {
         loaded_at: m.loaded_at,
         last_used: m.last_used,
         request_count: m.request_count,
+        embedding: m.embedding,
     }
 }
 
@@ -596,6 +628,7 @@ mod tests {
                 context_length: 4096,
                 gpu_layers: 0,
                 threads: 4,
+                embedding: false,
             },
         );
         // Partial match should find it
@@ -621,6 +654,7 @@ mod tests {
                 context_length: 4096,
                 gpu_layers: 0,
                 threads: 4,
+                embedding: false,
             },
         );
         mgr.models.insert(
@@ -637,6 +671,7 @@ mod tests {
                 context_length: 4096,
                 gpu_layers: 0,
                 threads: 4,
+                embedding: false,
             },
         );
         let selected = mgr.select_model_for_level("tactical");
@@ -644,6 +679,44 @@ mod tests {
         assert!(selected.unwrap().contains("DeepSeek"), "tactical should prefer DeepSeek-R1 over mistral");
     }
 
+    #[test]
+    fn test_embedding_models_kept_out_of_routing() {
+        let mut mgr = ModelManager::new();
+        mgr.models.insert(
+            "nomic-embed-text-v1.5".to_string(),
+            ManagedModel {
+                name: "nomic-embed-text-v1.5".to_string(),
+                path: PathBuf::from("/tmp/nomic.gguf"),
+                process: None,
+                port: 8081,
+                status: ModelState::Ready,
+                loaded_at: 1000,
+                last_used: 2000,
+                request_count: 0,
+                context_length: 2048,
+                gpu_layers: 0,
+                threads: 2,
+                embedding: true,
+            },
+        );
+        assert!(mgr.select_model_for_level("operational").is_none());
+        assert!(mgr.first_ready_model().is_none());
+        assert_eq!(
+            mgr.first_ready_embedding_model().as_deref(),
+            Some("nomic-embed-text-v1.5")
+        );
+        assert_eq!(mgr.serves_embeddings("nomic-embed-text-v1.5"), Some(true));
+        assert_eq!(mgr.serves_embeddings("mistral-7b"), None);
+    }
+
+    #[test]
+    fn test_is_embedding_gguf() {
+        assert!(is_embedding_gguf("nomic-embed-text-v1.5.Q8_0"));
+        assert!(is_embedding_gguf("bge-small-en-v1.5-q8_0"));
+        assert!(!is_embedding_gguf("DeepSeek-R1-Distill-Qwen-8B-Q4_K_M"));
+        assert!(!is_embedding_gguf("tinyllama-1.1b"));
+    }
+
     #[test]
     fn test_allocate_port_default() {
         let mut mgr = ModelManager::new();
@@ -679,6 +752,7 @@ mod tests {
             context_length: 2048,
             gpu_layers: 0,
             threads: 4,
+            embedding: false,
         };
         let s = model_to_status(&m);
         assert_eq!(s.model_name, "test-model");
@@ -0,0 +1,109 @@
+//! Interactive reservation — inference slots held back for interactive work
+//!
+//! Local inference runs through a fixed number of slots, one per request
+//! in flight against the model servers (a stream holds its slot until it
+//! ends). A share of the slots is reserved for requests tagged
+//! `workload = "interactive"` (chat and console-submitted goals);
+//! background work (the autonomy loop, schedules, proactive goals) only
+//! ever holds the shared slots, so it cannot occupy the model servers
+//! while someone waits on an answer. Interactive requests take a reserved
+//! slot first and fall back to a shared one when the reserve is in use.
+//!
+//! `AIOS_RUNTIME_SLOTS` sets the slot count (default 2) and
+//! `AIOS_INTERACTIVE_RESERVE_PERCENT` the reserved share (default 20,
+//! rounded up to at least one slot).
+
+use std::sync::Arc;
+
+use tokio::sync::{AcquireError, OwnedSemaphorePermit, Semaphore};
+
+/// Workload tag of requests that may use reserved capacity
+pub const INTERACTIVE: &str = "interactive";
+
+const DEFAULT_SLOTS: usize = 2;
+const DEFAULT_RESERVE_PERCENT: f64 = 20.0;
+
+/// Inference slots split into a shared pool and an interactive reserve
+pub struct SlotPool {
+    shared: Arc<Semaphore>,
+    reserved: Arc<Semaphore>,
+}
+
+impl SlotPool {
+    /// `total` slots of which `reserve_percent` (rounded up, at least one
+    /// when there are two or more slots) is kept for interactive work
+    pub fn new(total: usize, reserve_percent: f64) -> Self {
+        let total = total.max(1);
+        let reserved = if total > 1 && reserve_percent > 0.0 {
+            ((total as f64 * reserve_percent / 100.0).ceil() as usize).clamp(1, total - 1)
+        } else {
+            0
+        };
+        Self {
+            shared: Arc::new(Semaphore::new(total - reserved)),
+            reserved: Arc::new(Semaphore::new(reserved)),
+        }
+    }
+
+    /// Pool sized from `AIOS_RUNTIME_SLOTS` and `AIOS_INTERACTIVE_RESERVE_PERCENT`
+    pub fn from_env() -> Self {
+        let total = std::env::var("AIOS_RUNTIME_SLOTS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_SLOTS);
+        let reserve = std::env::var("AIOS_INTERACTIVE_RESERVE_PERCENT")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_RESERVE_PERCENT);
+        Self::new(total, reserve)
+    }
+
+    /// Wait for a slot; the slot is released when the permit is dropped
+    pub async fn acquire(&self, workload: &str) -> Result<OwnedSemaphorePermit, AcquireError> {
+        if workload == INTERACTIVE {
+            tokio::select! {
+                biased;
+                permit = self.reserved.clone().acquire_owned() => permit,
+                permit = self.shared.clone().acquire_owned() => permit,
+            }
+        } else {
+            self.shared.clone().acquire_owned().await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_background_cannot_take_reserved_slots() {
+        let pool = SlotPool::new(4, 20.0);
+        let mut held = Vec::new();
+        for _ in 0..3 {
+            held.push(pool.acquire("").await.unwrap());
+        }
+        // The shared slots are exhausted: background waits...
+        let waiting =
+            tokio::time::timeout(Duration::from_millis(50), pool.acquire("background")).await;
+        assert!(waiting.is_err());
+        // ...while an interactive call still gets the reserved one
+        let interactive =
+            tokio::time::timeout(Duration::from_millis(50), pool.acquire(INTERACTIVE)).await;
+        assert!(interactive.is_ok());
+
+        drop(held.pop());
+        let freed = tokio::time::timeout(Duration::from_millis(50), pool.acquire("")).await;
+        assert!(freed.is_ok());
+    }
+
+    #[test]
+    fn test_reserve_sizing() {
+        assert_eq!(SlotPool::new(1, 20.0).reserved.available_permits(), 0);
+        assert_eq!(SlotPool::new(2, 20.0).reserved.available_permits(), 1);
+        assert_eq!(SlotPool::new(16, 20.0).reserved.available_permits(), 4);
+        assert_eq!(SlotPool::new(16, 20.0).shared.available_permits(), 12);
+        assert_eq!(SlotPool::new(4, 0.0).shared.available_permits(), 4);
+    }
+}
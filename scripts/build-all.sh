@@ -5,8 +5,8 @@
 # Builds all components in dependency order:
 #   1. Kernel
 #   2. Initramfs
-#   3. Rust workspace (aios-init, aios-orchestrator, aios-tools,
-#      aios-memory, aios-api-gateway)
+#   3. Rust workspace (aios-init, aios-orchestrator, aiosctl, aios-tools,
+#      aios-memory, aios-memctl, aios-api-gateway)
 #   4. llama.cpp (llama-server)
 #   5. Download models (optional)
 #   6. Root filesystem
@@ -201,7 +201,7 @@ cargo build \
 
 # Copy binaries to output
 mkdir -p build/output/bin
-for bin_name in aios-init aios-orchestrator aios-tools aios-memory aios-api-gateway; do
+for bin_name in aios-init aios-orchestrator aiosctl aios-tools aios-memory aios-memctl aios-api-gateway; do
     src="target/x86_64-unknown-linux-musl/release/${bin_name}"
     if [ -f "$src" ]; then
         cp "$src" "build/output/bin/${bin_name}"
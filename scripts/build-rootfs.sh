@@ -29,7 +29,7 @@ BUSYBOX_URL="https://busybox.net/downloads/binaries/1.36.1-defconfig-multiarch-m
 
 # Rust release binaries (cross-compiled for x86_64 Linux)
 RUST_TARGET_DIR="target/x86_64-unknown-linux-musl/release"
-RUST_BINARIES=(aios-init aios-orchestrator aios-tools aios-memory aios-api-gateway)
+RUST_BINARIES=(aios-init aios-orchestrator aiosctl aios-tools aios-memory aios-memctl aios-api-gateway)
 
 # -----------------------------------------------------------
 # Color helpers
@@ -334,6 +334,9 @@ root:x:0:0:root:/root:/bin/sh
 daemon:x:1:1:daemon:/usr/sbin:/bin/false
 nobody:x:65534:65534:nobody:/nonexistent:/bin/false
 aios:x:1000:1000:aiOS Service Account:/var/lib/aios:/bin/false
+aios-net:x:1001:1001:aiOS net tools:/nonexistent:/bin/false
+aios-fs:x:1002:1002:aiOS fs tools:/nonexistent:/bin/false
+aios-pkg:x:1003:1003:aiOS pkg tools:/nonexistent:/bin/false
 EOF
 
 # /etc/group
@@ -342,6 +345,9 @@ root:x:0:
 daemon:x:1:
 nogroup:x:65534:
 aios:x:1000:
+aios-net:x:1001:
+aios-fs:x:1002:
+aios-pkg:x:1003:
 EOF
 
 # /etc/shadow (root has no password — serial console only; set one in production)
@@ -350,6 +356,9 @@ root::0:0:99999:7:::
 daemon:*:0:0:99999:7:::
 nobody:*:0:0:99999:7:::
 aios:*:0:0:99999:7:::
+aios-net:*:0:0:99999:7:::
+aios-fs:*:0:0:99999:7:::
+aios-pkg:*:0:0:99999:7:::
 EOF
 sudo chmod 640 "${MNT_DIR}/etc/shadow"
 
@@ -315,8 +315,10 @@ sha256sum -c SHA256SUMS
 - **aios-init** — PID 1 init daemon with service supervision
 - **aios-runtime** — AI inference runtime (llama.cpp integration)
 - **aios-orchestrator** — Multi-agent goal decomposition and orchestration
+- **aiosctl** — Command-line client for headless hosts: goals, task output, agents, nodes, budget, schedules, ad hoc tool calls
 - **aios-tools** — System tool execution sandbox
 - **aios-memory** — Persistent and working memory with vector search
+- **aios-memctl** — Memory debugging CLI: query tiers, dump/restore collections, tail events
 - **aios-api-gateway** — Management console and REST API (port 9090)
 
 ## Known Issues
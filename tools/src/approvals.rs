@@ -0,0 +1,408 @@
+//! Approval queue — critical-risk executions wait for an operator
+//!
+//! An execution of a tool whose risk level is `critical`, or that asks for
+//! confirmation, is parked before it runs: it waits here, holding neither
+//! an execution slot nor the registry lock, until an operator approves or
+//! denies it with the Approve / Deny RPCs (the console's pending approvals
+//! panel), or until it expires. Only a user whose token the memory
+//! service verifies may decide, and never on an execution made as that
+//! same user. Denied and expired executions fail with the `denied` failure
+//! class without running. Cancelling the task an execution belongs to
+//! denies it.
+//!
+//! The caller waits on its Execute call for the decision. The orchestrator
+//! gives tool calls 300 s, so a parked execution expires after at most
+//! [`MAX_WAIT_SECS`], leaving the approved call time to run; one whose
+//! caller gave up before then is withdrawn from the queue.
+//!
+//! tool-approvals.toml:
+//!
+//! ```toml
+//! enabled = true
+//! expire_after_secs = 240   # at most 240; 0 = 240
+//! # Agents whose critical calls run without approval ("security*" by prefix)
+//! auto_approve = []
+//! ```
+//!
+//! The staging sandbox (`AIOS_STAGING` set) runs everything unapproved:
+//! nothing it touches is real.
+
+use anyhow::{bail, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tracing::{info, warn};
+
+use crate::proto::tools::{ExecuteRequest, PendingApproval, ToolDefinition};
+
+/// Default location of the approval queue configuration
+pub const TOOL_APPROVALS_CONFIG_PATH: &str = "/etc/aios/tool-approvals.toml";
+
+/// Longest a parked execution waits: the orchestrator's 300 s deadline on
+/// tool calls, less time for the approved call to run
+pub const MAX_WAIT_SECS: u64 = 240;
+
+/// Characters of a parked call's input shown to the operator
+const MAX_INPUT_CHARS: usize = 4000;
+
+/// tool-approvals.toml layout
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApprovalConfig {
+    pub enabled: bool,
+    /// Seconds a parked execution waits before it is denied; 0 or more
+    /// than MAX_WAIT_SECS = MAX_WAIT_SECS
+    pub expire_after_secs: u64,
+    /// Agents whose executions need no approval; "security*" matches by
+    /// prefix
+    pub auto_approve: Vec<String>,
+}
+
+impl Default for ApprovalConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            expire_after_secs: MAX_WAIT_SECS,
+            auto_approve: Vec::new(),
+        }
+    }
+}
+
+impl ApprovalConfig {
+    /// Configuration at `path`; a missing or invalid file yields the
+    /// defaults. Disabled in the staging sandbox.
+    pub fn load(path: &str) -> Self {
+        let mut config = match std::fs::read_to_string(path) {
+            Ok(contents) => toml::from_str(&contents).unwrap_or_else(|e| {
+                warn!("Ignoring invalid tool approval config in {path}: {e}");
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        };
+        if std::env::var_os("AIOS_STAGING").is_some() {
+            config.enabled = false;
+        }
+        if config.expire_after_secs > MAX_WAIT_SECS {
+            warn!(
+                "Tool approvals expire after {MAX_WAIT_SECS}s at most, not {}s: callers stop waiting by then",
+                config.expire_after_secs
+            );
+        }
+        config
+    }
+
+    /// Seconds a parked execution waits
+    pub fn expire_after(&self) -> u64 {
+        match self.expire_after_secs {
+            0 => MAX_WAIT_SECS,
+            secs => secs.min(MAX_WAIT_SECS),
+        }
+    }
+
+    /// Whether `agent`'s execution of `tool` waits for approval
+    pub fn requires(&self, tool: &ToolDefinition, agent: &str) -> bool {
+        self.enabled
+            && (tool.risk_level == "critical" || tool.requires_confirmation)
+            && !self.auto_approve.iter().any(|p| match p.strip_suffix('*') {
+                Some(prefix) => agent.starts_with(prefix),
+                None => p == agent,
+            })
+    }
+}
+
+/// How a parked execution was decided
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    Approved { by: String },
+    Denied { by: String, reason: String },
+    Expired,
+}
+
+impl Decision {
+    /// Why the execution did not run, for its error; None when approved
+    pub fn refusal(&self) -> Option<String> {
+        match self {
+            Self::Approved { .. } => None,
+            Self::Denied { by, reason } if reason.is_empty() => {
+                Some(format!("Approval denied by {by}"))
+            }
+            Self::Denied { by, reason } => Some(format!("Approval denied by {by}: {reason}")),
+            Self::Expired => Some("Approval expired before anyone decided".to_string()),
+        }
+    }
+}
+
+struct Parked {
+    approval: PendingApproval,
+    decide: oneshot::Sender<Decision>,
+}
+
+/// Executions waiting for approval
+pub struct ApprovalQueue {
+    config: ApprovalConfig,
+    parked: Mutex<HashMap<String, Parked>>,
+}
+
+impl ApprovalQueue {
+    pub fn new(config: ApprovalConfig) -> Self {
+        Self {
+            config,
+            parked: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn config(&self) -> &ApprovalConfig {
+        &self.config
+    }
+
+    /// Park an execution until it is decided or expires. Dropping the
+    /// returned future (the caller went away) withdraws it.
+    pub async fn wait(&self, request: &ExecuteRequest, tool: &ToolDefinition) -> Decision {
+        let now = chrono::Utc::now().timestamp();
+        let expire_after = self.config.expire_after();
+        let input: String = String::from_utf8_lossy(&request.input_json)
+            .chars()
+            .take(MAX_INPUT_CHARS)
+            .collect();
+        let approval = PendingApproval {
+            approval_id: uuid::Uuid::new_v4().to_string(),
+            tool_name: request.tool_name.clone(),
+            agent_id: request.agent_id.clone(),
+            task_id: request.task_id.clone(),
+            reason: request.reason.clone(),
+            input_json: input,
+            risk_level: tool.risk_level.clone(),
+            requested_at: now,
+            expires_at: now + expire_after as i64,
+        };
+        let id = approval.approval_id.clone();
+        info!(
+            "{} for {} waits for approval ({id})",
+            request.tool_name, request.agent_id
+        );
+
+        let (decide, decided) = oneshot::channel();
+        self.lock().insert(id.clone(), Parked { approval, decide });
+        let _withdraw = Withdraw {
+            queue: self,
+            id: &id,
+        };
+
+        let decision = tokio::time::timeout(Duration::from_secs(expire_after), decided)
+            .await
+            .ok()
+            .and_then(Result::ok)
+            .unwrap_or(Decision::Expired);
+        if decision == Decision::Expired {
+            warn!("Approval {id} of {} expired", request.tool_name);
+        }
+        decision
+    }
+
+    /// Parked executions, oldest first
+    pub fn pending(&self) -> Vec<PendingApproval> {
+        let mut pending: Vec<PendingApproval> =
+            self.lock().values().map(|p| p.approval.clone()).collect();
+        pending.sort_by_key(|a| a.requested_at);
+        pending
+    }
+
+    /// Let a parked execution run; `by` may not be the actor that made it
+    pub fn approve(&self, approval_id: &str, by: &str) -> Result<Option<PendingApproval>> {
+        self.decide(approval_id, Decision::Approved { by: by.to_string() })
+    }
+
+    /// Fail a parked execution without running it; `by` may not be the
+    /// actor that made it
+    pub fn deny(
+        &self,
+        approval_id: &str,
+        by: &str,
+        reason: &str,
+    ) -> Result<Option<PendingApproval>> {
+        self.decide(
+            approval_id,
+            Decision::Denied {
+                by: by.to_string(),
+                reason: reason.to_string(),
+            },
+        )
+    }
+
+    /// Deny the executions parked for `task_ids`, returning their ids
+    pub fn deny_tasks(&self, task_ids: &[String], reason: &str) -> Vec<String> {
+        let ids: Vec<String> = self
+            .lock()
+            .values()
+            .filter(|p| task_ids.contains(&p.approval.task_id))
+            .map(|p| p.approval.approval_id.clone())
+            .collect();
+        ids.into_iter()
+            .filter(|id| matches!(self.deny(id, "cancellation", reason), Ok(Some(_))))
+            .collect()
+    }
+
+    fn decide(&self, approval_id: &str, decision: Decision) -> Result<Option<PendingApproval>> {
+        let by = match &decision {
+            Decision::Approved { by } | Decision::Denied { by, .. } => by.as_str(),
+            Decision::Expired => "",
+        };
+        let parked = {
+            let mut parked = self.lock();
+            match parked.get(approval_id) {
+                Some(p) if p.approval.agent_id == by => {
+                    bail!("{by} cannot decide on its own execution")
+                }
+                Some(_) => parked.remove(approval_id),
+                None => None,
+            }
+        };
+        let Some(parked) = parked else {
+            return Ok(None);
+        };
+        info!(
+            "Approval {approval_id} of {}: {decision:?}",
+            parked.approval.tool_name
+        );
+        // The waiter may have gone away in the meantime
+        let _ = parked.decide.send(decision);
+        Ok(Some(parked.approval))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Parked>> {
+        self.parked.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Removes a parked execution whose waiter finished or went away
+struct Withdraw<'a> {
+    queue: &'a ApprovalQueue,
+    id: &'a str,
+}
+
+impl Drop for Withdraw<'_> {
+    fn drop(&mut self) {
+        if let Some(parked) = self.queue.lock().remove(self.id) {
+            warn!(
+                "Approval {} of {} withdrawn: the caller stopped waiting",
+                self.id, parked.approval.tool_name
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn tool(risk_level: &str) -> ToolDefinition {
+        ToolDefinition {
+            name: "process.kill".into(),
+            risk_level: risk_level.into(),
+            requires_confirmation: risk_level == "critical",
+            ..Default::default()
+        }
+    }
+
+    fn request(task_id: &str) -> ExecuteRequest {
+        ExecuteRequest {
+            tool_name: "process.kill".into(),
+            agent_id: "system-agent".into(),
+            task_id: task_id.into(),
+            input_json: br#"{"pid": 4242}"#.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    async fn parked(queue: &ApprovalQueue, count: usize) -> Vec<PendingApproval> {
+        for _ in 0..100 {
+            let pending = queue.pending();
+            if pending.len() == count {
+                return pending;
+            }
+            tokio::task::yield_now().await;
+        }
+        panic!("expected {count} parked executions");
+    }
+
+    #[test]
+    fn test_requires_approval_for_critical_tools() {
+        let config = ApprovalConfig {
+            auto_approve: vec!["security*".into()],
+            ..Default::default()
+        };
+        assert!(config.requires(&tool("critical"), "system-agent"));
+        assert!(!config.requires(&tool("high"), "system-agent"));
+        assert!(!config.requires(&tool("critical"), "security-agent"));
+
+        let disabled = ApprovalConfig {
+            enabled: false,
+            ..Default::default()
+        };
+        assert!(!disabled.requires(&tool("critical"), "system-agent"));
+    }
+
+    #[test]
+    fn test_expiry_stays_within_the_callers_deadline() {
+        let expiring = |expire_after_secs| ApprovalConfig {
+            expire_after_secs,
+            ..Default::default()
+        };
+        assert_eq!(ApprovalConfig::default().expire_after(), MAX_WAIT_SECS);
+        assert_eq!(expiring(60).expire_after(), 60);
+        assert_eq!(expiring(0).expire_after(), MAX_WAIT_SECS);
+        assert_eq!(expiring(900).expire_after(), MAX_WAIT_SECS);
+    }
+
+    #[tokio::test]
+    async fn test_parked_execution_waits_for_a_decision() {
+        let queue = Arc::new(ApprovalQueue::new(ApprovalConfig::default()));
+
+        let waiter = queue.clone();
+        let approved =
+            tokio::spawn(async move { waiter.wait(&request("task-1"), &tool("critical")).await });
+        let pending = parked(&queue, 1).await;
+        assert_eq!(pending[0].input_json, r#"{"pid": 4242}"#);
+        assert!(pending[0].expires_at > pending[0].requested_at);
+        // Whoever made the execution cannot decide on it
+        assert!(queue
+            .approve(&pending[0].approval_id, "system-agent")
+            .is_err());
+        assert!(queue
+            .approve(&pending[0].approval_id, "user:alice")
+            .unwrap()
+            .is_some());
+        assert_eq!(
+            approved.await.unwrap(),
+            Decision::Approved {
+                by: "user:alice".into()
+            }
+        );
+        assert!(queue.pending().is_empty());
+
+        // Cancelling the task denies what it has parked
+        let waiter = queue.clone();
+        let denied =
+            tokio::spawn(async move { waiter.wait(&request("task-2"), &tool("critical")).await });
+        parked(&queue, 1).await;
+        assert!(queue.deny_tasks(&["task-1".into()], "stop").is_empty());
+        assert_eq!(queue.deny_tasks(&["task-2".into()], "stop").len(), 1);
+        let refusal = denied.await.unwrap().refusal().unwrap();
+        assert_eq!(refusal, "Approval denied by cancellation: stop");
+        assert!(queue.approve("unknown", "user:alice").unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_unanswered_execution_expires() {
+        let queue = ApprovalQueue::new(ApprovalConfig {
+            expire_after_secs: 1,
+            ..Default::default()
+        });
+        let decision = queue.wait(&request("task-1"), &tool("critical")).await;
+        assert_eq!(decision, Decision::Expired);
+        assert!(queue.pending().is_empty());
+    }
+}
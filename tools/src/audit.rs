@@ -1,14 +1,75 @@
 //! Audit logging — hash-chained ledger of all tool executions
+//!
+//! Risky executions also carry an [`ExecutionSnapshot`] of the environment
+//! they ran in, stored with the record and covered by its chain hash. The
+//! paths an execution targets are kept the same way, so the policy
+//! simulator can replay path policies against the ledger.
+//! Every record is also appended to an [`AuditMirror`] so losing or
+//! editing the database does not go unnoticed.
 
 use anyhow::Result;
 use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use tracing::info;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use tracing::{info, warn};
+
+use crate::audit_mirror::AuditMirror;
 
 /// Hash-chained audit ledger stored in SQLite
 pub struct AuditLog {
     conn: Connection,
     last_hash: String,
+    /// Snapshots waiting for their execution's audit record
+    pending_snapshots: HashMap<String, String>,
+    /// Target paths waiting for their execution's audit record
+    pending_targets: HashMap<String, String>,
+    /// Second copy every record is written to
+    mirror: Option<AuditMirror>,
+}
+
+/// One row of the ledger
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LedgerRecord {
+    /// Row id; 0 for a record the database failed to store
+    pub id: i64,
+    pub execution_id: String,
+    pub tool_name: String,
+    pub agent_id: String,
+    pub task_id: String,
+    pub reason: String,
+    pub success: bool,
+    pub duration_ms: i64,
+    pub timestamp: String,
+    pub prev_hash: String,
+    pub hash: String,
+    pub snapshot: Option<String>,
+    /// Path fields of the input, as a JSON object (see [`target_paths`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub targets: Option<String>,
+}
+
+/// Input fields naming a path a tool reads, writes or removes
+pub const TARGET_FIELDS: &[&str] = &["path", "source", "destination", "link", "output_path"];
+
+/// The path fields of a tool input as a JSON object; None if it has none
+pub fn target_paths(input_json: &[u8]) -> Option<String> {
+    let input: serde_json::Value = serde_json::from_slice(input_json).ok()?;
+    let targets: serde_json::Map<String, serde_json::Value> = TARGET_FIELDS
+        .iter()
+        .filter_map(|key| {
+            let path = input.get(*key)?.as_str()?;
+            Some((key.to_string(), serde_json::Value::from(path)))
+        })
+        .collect();
+    if targets.is_empty() {
+        None
+    } else {
+        Some(serde_json::Value::Object(targets).to_string())
+    }
 }
 
 impl AuditLog {
@@ -39,6 +100,27 @@ impl AuditLog {
             CREATE INDEX IF NOT EXISTS idx_audit_time ON audit_log(timestamp);",
         )?;
 
+        // Ledgers created before snapshots were recorded lack the column
+        let has_snapshot: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('audit_log') WHERE name = 'snapshot'",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_snapshot {
+            conn.execute_batch("ALTER TABLE audit_log ADD COLUMN snapshot TEXT")?;
+        }
+        let has_targets: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('audit_log') WHERE name = 'targets'",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_targets {
+            conn.execute_batch("ALTER TABLE audit_log ADD COLUMN targets TEXT")?;
+        }
+        conn.execute_batch(
+            "CREATE INDEX IF NOT EXISTS idx_audit_execution ON audit_log(execution_id)",
+        )?;
+
         // Load last hash for chain continuity
         let last_hash = conn
             .query_row(
@@ -48,7 +130,52 @@ impl AuditLog {
             )
             .unwrap_or_else(|_| "genesis".to_string());
 
-        Ok(Self { conn, last_hash })
+        Ok(Self {
+            conn,
+            last_hash,
+            pending_snapshots: HashMap::new(),
+            pending_targets: HashMap::new(),
+            mirror: None,
+        })
+    }
+
+    /// Write every record to `mirror` as well. A mirror without records is
+    /// first filled with the ledger so far.
+    pub fn attach_mirror(&mut self, mirror: AuditMirror) -> Result<()> {
+        if mirror.is_empty() {
+            let records = read_records(&self.conn)?;
+            if !records.is_empty() {
+                warn!(
+                    "Audit mirror is empty; copying {} ledger records into it",
+                    records.len()
+                );
+            }
+            for record in &records {
+                mirror.append(record)?;
+            }
+        }
+        self.mirror = Some(mirror);
+        Ok(())
+    }
+
+    /// Attach an environment snapshot to the next record for `execution_id`
+    pub fn attach_snapshot(&mut self, execution_id: &str, snapshot: &ExecutionSnapshot) {
+        match serde_json::to_string(snapshot) {
+            Ok(json) => {
+                self.pending_snapshots
+                    .insert(execution_id.to_string(), json);
+            }
+            Err(e) => tracing::error!("Failed to serialize execution snapshot: {e}"),
+        }
+    }
+
+    /// Attach the paths `input_json` targets to the next record for
+    /// `execution_id`
+    pub fn attach_targets(&mut self, execution_id: &str, input_json: &[u8]) {
+        if let Some(targets) = target_paths(input_json) {
+            self.pending_targets
+                .insert(execution_id.to_string(), targets);
+        }
     }
 
     /// Record an audit entry with hash chaining
@@ -63,19 +190,23 @@ impl AuditLog {
         duration_ms: i64,
     ) {
         let timestamp = chrono::Utc::now().to_rfc3339();
-
-        // Compute hash: SHA256(prev_hash + execution_id + tool_name + agent_id + timestamp)
-        let mut hasher = Sha256::new();
-        hasher.update(&self.last_hash);
-        hasher.update(execution_id);
-        hasher.update(tool_name);
-        hasher.update(agent_id);
-        hasher.update(&timestamp);
-        let hash = format!("{:x}", hasher.finalize());
+        let snapshot = self.pending_snapshots.remove(execution_id);
+        let targets = self.pending_targets.remove(execution_id);
+
+        // Compute hash: SHA256(prev_hash + execution_id + tool_name + agent_id + timestamp [+ snapshot] [+ targets])
+        let hash = chain_hash(
+            &self.last_hash,
+            execution_id,
+            tool_name,
+            agent_id,
+            &timestamp,
+            snapshot.as_deref(),
+            targets.as_deref(),
+        );
 
         let result = self.conn.execute(
-            "INSERT INTO audit_log (execution_id, tool_name, agent_id, task_id, reason, success, duration_ms, timestamp, prev_hash, hash)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            "INSERT INTO audit_log (execution_id, tool_name, agent_id, task_id, reason, success, duration_ms, timestamp, prev_hash, hash, snapshot, targets)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
             rusqlite::params![
                 execution_id,
                 tool_name,
@@ -87,66 +218,269 @@ impl AuditLog {
                 timestamp,
                 &self.last_hash,
                 &hash,
+                snapshot,
+                targets,
             ],
         );
 
-        match result {
+        let id = match result {
             Ok(_) => {
-                self.last_hash = hash;
                 info!(
                     "Audit: tool={tool_name} agent={agent_id} success={success} duration={duration_ms}ms"
                 );
+                self.conn.last_insert_rowid()
             }
             Err(e) => {
                 tracing::error!("Failed to write audit log: {e}");
+                0
             }
+        };
+
+        // Mirrored even when the database write failed, so the record
+        // survives and verification reports the gap
+        if let Some(ref mirror) = self.mirror {
+            let record = LedgerRecord {
+                id,
+                execution_id: execution_id.to_string(),
+                tool_name: tool_name.to_string(),
+                agent_id: agent_id.to_string(),
+                task_id: task_id.to_string(),
+                reason: reason.to_string(),
+                success,
+                duration_ms,
+                timestamp,
+                prev_hash: self.last_hash.clone(),
+                hash: hash.clone(),
+                snapshot,
+                targets,
+            };
+            if let Err(e) = mirror.append(&record) {
+                tracing::error!("Failed to write audit mirror: {e}");
+            }
+        }
+        if id != 0 {
+            self.last_hash = hash;
         }
     }
 
     /// Verify the audit chain integrity
     pub fn verify_chain(&self) -> Result<bool> {
-        let mut stmt = self.conn.prepare(
-            "SELECT execution_id, tool_name, agent_id, timestamp, prev_hash, hash FROM audit_log ORDER BY id ASC",
-        )?;
+        Ok(chain_intact(&read_records(&self.conn)?))
+    }
 
-        let mut expected_prev = "genesis".to_string();
-        let rows = stmt.query_map([], |row| {
-            Ok((
-                row.get::<_, String>(0)?,
-                row.get::<_, String>(1)?,
-                row.get::<_, String>(2)?,
-                row.get::<_, String>(3)?,
-                row.get::<_, String>(4)?,
-                row.get::<_, String>(5)?,
-            ))
-        })?;
-
-        for row in rows {
-            let (exec_id, tool_name, agent_id, timestamp, prev_hash, stored_hash) = row?;
-
-            // Verify prev_hash matches what we expect
-            if prev_hash != expected_prev {
-                return Ok(false);
-            }
+    /// Records written at or after `since`, oldest first
+    pub fn records_since(&self, since: chrono::DateTime<chrono::Utc>) -> Result<Vec<LedgerRecord>> {
+        // RFC 3339 timestamps in UTC sort as text
+        let since = since.to_rfc3339();
+        Ok(read_records(&self.conn)?
+            .into_iter()
+            .filter(|r| r.timestamp >= since)
+            .collect())
+    }
+}
 
-            // Recompute hash
-            let mut hasher = Sha256::new();
-            hasher.update(&prev_hash);
-            hasher.update(&exec_id);
-            hasher.update(&tool_name);
-            hasher.update(&agent_id);
-            hasher.update(&timestamp);
-            let computed = format!("{:x}", hasher.finalize());
-
-            if computed != stored_hash {
-                return Ok(false);
-            }
+/// All ledger records, oldest first
+pub fn read_records(conn: &Connection) -> Result<Vec<LedgerRecord>> {
+    read_records_after(conn, 0, usize::MAX)
+}
+
+/// Up to `limit` ledger records after row `after_id`, oldest first
+pub fn read_records_after(
+    conn: &Connection,
+    after_id: i64,
+    limit: usize,
+) -> Result<Vec<LedgerRecord>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, execution_id, tool_name, agent_id, task_id, reason, success, duration_ms,
+            timestamp, prev_hash, hash, snapshot, targets
+         FROM audit_log WHERE id > ?1 ORDER BY id ASC LIMIT ?2",
+    )?;
+    // A negative limit is no limit
+    let limit = i64::try_from(limit).unwrap_or(-1);
+    let rows = stmt.query_map(rusqlite::params![after_id, limit], |row| {
+        Ok(LedgerRecord {
+            id: row.get(0)?,
+            execution_id: row.get(1)?,
+            tool_name: row.get(2)?,
+            agent_id: row.get(3)?,
+            task_id: row.get(4)?,
+            reason: row.get(5)?,
+            success: row.get::<_, i64>(6)? != 0,
+            duration_ms: row.get(7)?,
+            timestamp: row.get(8)?,
+            prev_hash: row.get(9)?,
+            hash: row.get(10)?,
+            snapshot: row.get(11)?,
+            targets: row.get(12)?,
+        })
+    })?;
+    Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+}
+
+/// Whether `records`, oldest first, form an unbroken chain from genesis
+pub fn chain_intact(records: &[LedgerRecord]) -> bool {
+    let mut expected_prev = "genesis";
+    for record in records {
+        // Each record follows the previous one and matches its own hash
+        if record.prev_hash != expected_prev || !hash_matches(record) {
+            return false;
+        }
+        expected_prev = &record.hash;
+    }
+    true
+}
+
+/// Whether `record`'s hash covers its contents and previous hash
+pub fn hash_matches(record: &LedgerRecord) -> bool {
+    chain_hash(
+        &record.prev_hash,
+        &record.execution_id,
+        &record.tool_name,
+        &record.agent_id,
+        &record.timestamp,
+        record.snapshot.as_deref(),
+        record.targets.as_deref(),
+    ) == record.hash
+}
+
+/// Chain hash of one record; the snapshot and targets are covered only when
+/// present so records written before they existed still verify.
+fn chain_hash(
+    prev_hash: &str,
+    execution_id: &str,
+    tool_name: &str,
+    agent_id: &str,
+    timestamp: &str,
+    snapshot: Option<&str>,
+    targets: Option<&str>,
+) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(prev_hash);
+    hasher.update(execution_id);
+    hasher.update(tool_name);
+    hasher.update(agent_id);
+    hasher.update(timestamp);
+    if let Some(snapshot) = snapshot {
+        hasher.update(snapshot);
+    }
+    if let Some(targets) = targets {
+        hasher.update("targets");
+        hasher.update(targets);
+    }
+    format!("{:x}", hasher.finalize())
+}
 
-            expected_prev = stored_hash;
+/// Environment variables copied into snapshots; values of anything else
+/// (including variables a call asks to set) are never recorded.
+const SNAPSHOT_ENV_VARS: &[&str] = &[
+    "PATH",
+    "HOME",
+    "USER",
+    "LOGNAME",
+    "SHELL",
+    "LANG",
+    "PWD",
+    "LD_PRELOAD",
+    "LD_LIBRARY_PATH",
+    "AIOS_SANDBOX_NO_NETWORK",
+];
+
+/// The environment a risky tool execution ran in
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionSnapshot {
+    /// Program the execution runs: the requested command, or the tool service itself
+    pub binary_path: String,
+    pub binary_sha256: Option<String>,
+    pub pid: u32,
+    pub euid: u32,
+    pub egid: u32,
+    pub cwd: String,
+    /// Allow-listed variables from the tool service's environment
+    pub env: BTreeMap<String, String>,
+    /// Names of variables the call asked to set (values omitted)
+    pub requested_env: Vec<String>,
+    pub cgroup: Option<String>,
+    pub sandbox_profile: Option<String>,
+    pub captured_at: String,
+}
+
+impl ExecutionSnapshot {
+    /// Capture the environment for a call to `tool_name` with `input_json`
+    pub fn capture(input_json: &[u8], sandbox_profile: Option<&str>) -> Self {
+        let input: serde_json::Value = serde_json::from_slice(input_json).unwrap_or_default();
+        let str_field = |key: &str| input.get(key).and_then(|v| v.as_str());
+
+        let binary = str_field("command")
+            .map(|command| resolve_binary(command).unwrap_or_else(|| PathBuf::from(command)))
+            .or_else(|| std::env::current_exe().ok())
+            .unwrap_or_default();
+        let cwd = ["cwd", "working_dir", "source_path"]
+            .iter()
+            .find_map(|key| str_field(key))
+            .map(str::to_string)
+            .or_else(|| {
+                std::env::current_dir()
+                    .ok()
+                    .map(|d| d.display().to_string())
+            })
+            .unwrap_or_default();
+        let requested_env = input
+            .get("env")
+            .and_then(|e| e.as_object())
+            .map(|e| e.keys().cloned().collect())
+            .unwrap_or_default();
+
+        Self {
+            binary_sha256: hash_binary(&binary),
+            binary_path: binary.display().to_string(),
+            pid: std::process::id(),
+            euid: unsafe { libc::geteuid() },
+            egid: unsafe { libc::getegid() },
+            cwd,
+            env: SNAPSHOT_ENV_VARS
+                .iter()
+                .filter_map(|name| std::env::var(name).ok().map(|v| (name.to_string(), v)))
+                .collect(),
+            requested_env,
+            cgroup: std::fs::read_to_string("/proc/self/cgroup")
+                .ok()
+                .map(|c| c.trim().to_string()),
+            sandbox_profile: sandbox_profile.map(str::to_string),
+            captured_at: chrono::Utc::now().to_rfc3339(),
         }
+    }
+}
 
-        Ok(true)
+/// Resolve a command name against PATH the way the process spawner would
+fn resolve_binary(command: &str) -> Option<PathBuf> {
+    if command.contains('/') {
+        return std::fs::canonicalize(command).ok();
     }
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .map(|dir| dir.join(command))
+        .find(|candidate| candidate.is_file())
+        .and_then(|path| std::fs::canonicalize(path).ok())
+}
+
+/// SHA-256 of a binary, cached by path, size and modification time
+fn hash_binary(path: &Path) -> Option<String> {
+    type Key = (PathBuf, u64, SystemTime);
+    static CACHE: Mutex<Option<HashMap<Key, String>>> = Mutex::new(None);
+
+    let meta = std::fs::metadata(path).ok()?;
+    let key = (path.to_path_buf(), meta.len(), meta.modified().ok()?);
+    let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    let cache = cache.get_or_insert_with(HashMap::new);
+    if let Some(hash) = cache.get(&key) {
+        return Some(hash.clone());
+    }
+
+    let mut file = std::fs::File::open(path).ok()?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).ok()?;
+    let hash = format!("{:x}", hasher.finalize());
+    cache.insert(key, hash.clone());
+    Some(hash)
 }
 
 #[cfg(test)]
@@ -154,6 +488,121 @@ mod tests {
     use super::*;
     use tempfile::NamedTempFile;
 
+    #[test]
+    fn test_audit_log_with_snapshot() {
+        let tmp = NamedTempFile::new().unwrap();
+        let mut log = AuditLog::new(tmp.path().to_str().unwrap()).unwrap();
+
+        let snapshot = ExecutionSnapshot::capture(
+            br#"{"command": "sh", "env": {"TOKEN": "secret"}, "cwd": "/srv"}"#,
+            Some("net-isolated"),
+        );
+        assert!(snapshot.binary_path.ends_with("sh"));
+        assert_eq!(snapshot.binary_sha256.as_ref().map(|h| h.len()), Some(64));
+        assert_eq!(snapshot.cwd, "/srv");
+        assert_eq!(snapshot.requested_env, vec!["TOKEN".to_string()]);
+        assert!(!snapshot.env.values().any(|v| v == "secret"));
+
+        log.record("exec-1", "fs.read", "agent-1", "task-1", "test", true, 50);
+        log.attach_snapshot("exec-2", &snapshot);
+        log.record(
+            "exec-2",
+            "process.spawn",
+            "agent-1",
+            "task-1",
+            "test",
+            true,
+            80,
+        );
+        assert!(log.verify_chain().unwrap());
+
+        let stored: String = log
+            .conn
+            .query_row(
+                "SELECT snapshot FROM audit_log WHERE execution_id = 'exec-2'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        let stored: ExecutionSnapshot = serde_json::from_str(&stored).unwrap();
+        assert_eq!(stored, snapshot);
+
+        // Tampering with the snapshot breaks the chain
+        log.conn
+            .execute(
+                "UPDATE audit_log SET snapshot = replace(snapshot, 'net-isolated', 'none')",
+                [],
+            )
+            .unwrap();
+        assert!(!log.verify_chain().unwrap());
+    }
+
+    #[test]
+    fn test_audit_log_with_targets() {
+        assert_eq!(target_paths(br#"{"command": "ls"}"#), None);
+        assert_eq!(target_paths(b"not json"), None);
+
+        let tmp = NamedTempFile::new().unwrap();
+        let mut log = AuditLog::new(tmp.path().to_str().unwrap()).unwrap();
+        log.attach_targets(
+            "exec-1",
+            br#"{"source": "/etc/hosts", "destination": "/tmp/hosts", "overwrite": true}"#,
+        );
+        log.record("exec-1", "fs.copy", "agent-1", "task-1", "test", true, 20);
+        assert!(log.verify_chain().unwrap());
+
+        let records = log
+            .records_since(chrono::Utc::now() - chrono::Duration::hours(1))
+            .unwrap();
+        let targets: serde_json::Value =
+            serde_json::from_str(records[0].targets.as_deref().unwrap()).unwrap();
+        assert_eq!(
+            targets,
+            serde_json::json!({"source": "/etc/hosts", "destination": "/tmp/hosts"})
+        );
+        assert!(log
+            .records_since(chrono::Utc::now() + chrono::Duration::hours(1))
+            .unwrap()
+            .is_empty());
+
+        // Tampering with the targets breaks the chain
+        log.conn
+            .execute(
+                "UPDATE audit_log SET targets = replace(targets, '/etc', '/srv')",
+                [],
+            )
+            .unwrap();
+        assert!(!log.verify_chain().unwrap());
+    }
+
+    #[test]
+    fn test_audit_log_migrates_legacy_schema() {
+        let tmp = NamedTempFile::new().unwrap();
+        let path = tmp.path().to_str().unwrap();
+        Connection::open(path)
+            .unwrap()
+            .execute_batch(
+                "CREATE TABLE audit_log (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    execution_id TEXT NOT NULL,
+                    tool_name TEXT NOT NULL,
+                    agent_id TEXT NOT NULL,
+                    task_id TEXT NOT NULL,
+                    reason TEXT NOT NULL,
+                    success INTEGER NOT NULL,
+                    duration_ms INTEGER NOT NULL,
+                    timestamp TEXT NOT NULL,
+                    prev_hash TEXT NOT NULL,
+                    hash TEXT NOT NULL
+                );",
+            )
+            .unwrap();
+
+        let mut log = AuditLog::new(path).unwrap();
+        log.record("exec-1", "fs.read", "agent-1", "task-1", "test", true, 50);
+        assert!(log.verify_chain().unwrap());
+    }
+
     #[test]
     fn test_audit_log() {
         let tmp = NamedTempFile::new().unwrap();
@@ -0,0 +1,790 @@
+//! Audit mirror — an append-only second copy of the ledger
+//!
+//! The ledger database is a single SQLite file; a bad cleanup or a root
+//! shell can delete or rewrite it. Every record is therefore also appended
+//! to a gzip log kept on another path, one gzip member per record, each
+//! line carrying an HMAC under a key only the tools service reads. Nothing
+//! rewrites the log, so it keeps what the database loses. The log's records
+//! must form the ledger's hash chain; this is checked when the log is
+//! opened and on every append.
+//!
+//! Every few minutes the two are compared: the records since they last
+//! agreed, and the next window of older records, so every record is
+//! compared again once per pass over the ledger while each comparison reads
+//! a bounded stretch of both. Records missing from either side, records
+//! that differ, lines whose HMAC fails and a broken hash chain are
+//! divergences; a new set of them raises an alarm incident in long-term
+//! memory, resolved once the stores agree again.
+
+use anyhow::{Context, Result};
+use flate2::bufread::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+use crate::audit::{self, LedgerRecord};
+
+/// Default location of the mirror log, away from the ledger database
+pub const MIRROR_LOG_PATH: &str = "/var/log/aios/audit-ledger.log.gz";
+
+/// HMAC key of the mirror log, created on first start
+pub const MIRROR_KEY_PATH: &str = "/etc/aios/security/audit-mirror.key";
+
+/// How often the ledger and the mirror are compared
+const VERIFY_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Records newer than this when a comparison starts may be on one side
+/// only because they are being written
+const IN_FLIGHT_SECS: i64 = 5;
+
+/// Records read from each side per comparison; a longer stretch is
+/// compared over several
+const BATCH: usize = 10_000;
+
+/// Divergences listed in an incident; the rest are counted
+const MAX_LISTED: usize = 20;
+
+/// Previous hash of the first ledger record
+const GENESIS: &str = "genesis";
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Line of the mirror log
+#[derive(Serialize, Deserialize)]
+struct MirrorLine {
+    #[serde(flatten)]
+    record: LedgerRecord,
+    hmac: String,
+}
+
+/// A point in the log, and where the ledger stands there
+#[derive(Clone)]
+struct Position {
+    /// End of the last gzip member read
+    offset: u64,
+    lines: usize,
+    /// Last ledger record up to here (0 before the first)
+    id: i64,
+    /// Hash the next record must follow
+    hash: String,
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            lines: 0,
+            id: 0,
+            hash: GENESIS.to_string(),
+        }
+    }
+}
+
+/// Line of the log as read
+struct MirrorEntry {
+    /// None for a line that is unreadable or fails its HMAC
+    record: Option<LedgerRecord>,
+    problem: Option<String>,
+    /// Position after the line
+    at: Position,
+}
+
+/// How far the log has been compared with the ledger
+#[derive(Default)]
+struct MirrorCursor {
+    /// Where the two last agreed; later records are compared every time
+    verified: Position,
+    /// Where comparing the older records again resumes
+    swept: Position,
+    /// Divergences compared past that restoring the database cannot
+    /// clear: bad lines and records that never reached the database
+    problems: Vec<String>,
+}
+
+/// Whether `record` is the next link of a chain ending in `tip`. Records
+/// the database failed to store (id 0) do not extend the chain.
+fn follows(tip: &str, record: &LedgerRecord) -> bool {
+    record.prev_hash == tip && audit::hash_matches(record)
+}
+
+/// Append-only, HMAC-protected copy of the ledger
+#[derive(Clone)]
+pub struct AuditMirror {
+    path: PathBuf,
+    key: Vec<u8>,
+    cursor: Arc<Mutex<MirrorCursor>>,
+    /// Hash the next appended record must follow
+    tip: Arc<Mutex<String>>,
+}
+
+impl AuditMirror {
+    /// Mirror log at `path` signed with the key at `key_path`, which is
+    /// generated if it does not exist yet. The log is read and verified
+    /// once here.
+    pub fn open(path: &str, key_path: &str) -> Result<Self> {
+        if let Some(parent) = Path::new(path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let key = load_or_create_key(Path::new(key_path))?;
+        Self::with_key(PathBuf::from(path), key)
+    }
+
+    fn with_key(path: PathBuf, key: Vec<u8>) -> Result<Self> {
+        let mirror = Self {
+            path,
+            key,
+            cursor: Arc::default(),
+            tip: Arc::default(),
+        };
+        let mut at = Position::default();
+        let mut records = 0;
+        loop {
+            let (entries, corrupt) = mirror.read_from(&at, BATCH)?;
+            for entry in &entries {
+                records += usize::from(entry.record.is_some());
+                if let Some(problem) = &entry.problem {
+                    error!("Audit mirror {}: {problem}", mirror.path.display());
+                }
+            }
+            if let Some(problem) = corrupt {
+                error!("Audit mirror {}: {problem}", mirror.path.display());
+            }
+            let Some(last) = entries.last() else { break };
+            at = last.at.clone();
+            if entries.len() < BATCH {
+                break;
+            }
+        }
+        if records > 0 {
+            info!(
+                "Audit mirror {} holds {records} records",
+                mirror.path.display()
+            );
+        }
+        *mirror.tip.lock().unwrap() = at.hash;
+        Ok(mirror)
+    }
+
+    /// Whether the log holds no records yet
+    pub fn is_empty(&self) -> bool {
+        std::fs::metadata(&self.path).map_or(true, |m| m.len() == 0)
+    }
+
+    /// Append a record as its own gzip member. A record that does not
+    /// follow the last one is still kept, and reported.
+    pub fn append(&self, record: &LedgerRecord) -> Result<()> {
+        let mut tip = self.tip.lock().unwrap();
+        if !follows(&tip, record) {
+            error!(
+                "Audit record {} ({}) breaks the mirror's hash chain; the ledger may have been rewritten",
+                record.id, record.tool_name
+            );
+        }
+        let line = MirrorLine {
+            hmac: hmac_sha256(&self.key, &serde_json::to_vec(record)?),
+            record: record.clone(),
+        };
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        serde_json::to_writer(&mut encoder, &line)?;
+        encoder.write_all(b"\n")?;
+        let member = encoder.finish()?;
+
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .mode(0o640)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open {}", self.path.display()))?;
+        file.write_all(&member)?;
+        file.sync_data()?;
+        if record.id != 0 {
+            *tip = record.hash.clone();
+        }
+        Ok(())
+    }
+
+    /// Up to `max` lines of the log after `from`, and the corruption that
+    /// stopped reading, if any. A member still being written is left for
+    /// the next read.
+    fn read_from(&self, from: &Position, max: usize) -> Result<(Vec<MirrorEntry>, Option<String>)> {
+        let file = match std::fs::File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((Vec::new(), None)),
+            Err(e) => return Err(e.into()),
+        };
+        let mut reader = BufReader::new(file);
+        reader.seek(SeekFrom::Start(from.offset))?;
+        let mut at = from.clone();
+        let mut entries = Vec::new();
+        while entries.len() < max && !reader.fill_buf()?.is_empty() {
+            let mut decoder = GzDecoder::new(reader);
+            let mut text = String::new();
+            match decoder.read_to_string(&mut text) {
+                Ok(_) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+                Err(e) => {
+                    let problem = format!("Mirror log is corrupt at byte {}: {e}", at.offset);
+                    return Ok((entries, Some(problem)));
+                }
+            }
+            reader = decoder.into_inner();
+            at.offset = reader.stream_position()?;
+            for line in text.lines() {
+                at.lines += 1;
+                let (record, problem) = self.check_line(line, at.lines, &at.hash)?;
+                if let Some(record) = record.as_ref().filter(|r| r.id != 0) {
+                    at.id = record.id;
+                    at.hash = record.hash.clone();
+                }
+                entries.push(MirrorEntry {
+                    record,
+                    problem,
+                    at: at.clone(),
+                });
+            }
+        }
+        Ok((entries, None))
+    }
+
+    /// The record of line `number`, unless it is unreadable or fails its
+    /// HMAC, and what is wrong with the line
+    fn check_line(
+        &self,
+        line: &str,
+        number: usize,
+        tip: &str,
+    ) -> Result<(Option<LedgerRecord>, Option<String>)> {
+        let Ok(parsed) = serde_json::from_str::<MirrorLine>(line) else {
+            return Ok((
+                None,
+                Some(format!("Mirror log line {number} is unreadable")),
+            ));
+        };
+        if !hmac_valid(
+            &self.key,
+            &serde_json::to_vec(&parsed.record)?,
+            &parsed.hmac,
+        ) {
+            return Ok((
+                None,
+                Some(format!("Mirror log line {number} fails its HMAC")),
+            ));
+        }
+        let problem = (!follows(tip, &parsed.record))
+            .then(|| format!("Mirror log line {number} breaks the hash chain"));
+        Ok((Some(parsed.record), problem))
+    }
+}
+
+/// Compare the ledger database with the mirror; every divergence found,
+/// ignoring records written at or after `cutoff`
+pub fn cross_verify(
+    db_path: &str,
+    mirror: &AuditMirror,
+    cutoff: chrono::DateTime<chrono::Utc>,
+) -> Result<Vec<String>> {
+    let mut cursor = mirror.cursor.lock().unwrap();
+    let read = cursor.verified.offset;
+    match std::fs::metadata(&mirror.path) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            if read > 0 {
+                *cursor = MirrorCursor::default();
+                cursor.problems.push("Mirror log was deleted".to_string());
+            }
+        }
+        Err(e) => return Err(e.into()),
+        Ok(meta) if meta.len() < read => {
+            *cursor = MirrorCursor::default();
+            cursor.problems.push(format!(
+                "Mirror log shrank from {read} to {} bytes",
+                meta.len()
+            ));
+        }
+        Ok(_) => {}
+    }
+    if cursor.swept.offset >= cursor.verified.offset {
+        cursor.swept = Position::default();
+    }
+
+    // The mirror is written after the database, so read it first
+    let (mut window, _) = mirror.read_from(&cursor.swept, BATCH)?;
+    window.retain(|e| e.at.offset <= cursor.verified.offset);
+    let (tail, corrupt) = mirror.read_from(&cursor.verified, BATCH)?;
+    let mut divergences = cursor.problems.clone();
+    divergences.extend(corrupt);
+    if !Path::new(db_path).exists() {
+        divergences.push(format!("Ledger database {db_path} is missing"));
+        return Ok(divergences);
+    }
+    let conn =
+        rusqlite::Connection::open_with_flags(db_path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let settled = |r: &LedgerRecord| {
+        chrono::DateTime::parse_from_rfc3339(&r.timestamp).map_or(true, |t| t < cutoff)
+    };
+
+    // Records already compared, a window at a time, so edits and removals
+    // made since show up within one pass over the ledger
+    if let Some(end) = window.last().map(|e| e.at.clone()) {
+        let (found, covered) = compare(&conn, &cursor.swept, &window, end.id, &settled)?;
+        if found.is_empty() && covered > 0 {
+            cursor.swept = window[covered - 1].at.clone();
+        }
+        divergences.extend(found);
+    }
+
+    // Records since the two last agreed; lines and records only the mirror
+    // can be wrong about are kept once compared past
+    let upto = match tail.last() {
+        Some(last) if tail.len() == BATCH => last.at.id,
+        _ => i64::MAX,
+    };
+    let (found, covered) = compare(&conn, &cursor.verified, &tail, upto, &settled)?;
+    let mut mirror_only = Vec::new();
+    let mut passed = 0;
+    for (i, entry) in tail[..covered].iter().enumerate() {
+        if entry.record.as_ref().is_some_and(|r| !settled(r)) {
+            break;
+        }
+        mirror_only.extend(entry.problem.clone());
+        if let Some(record) = entry.record.as_ref().filter(|r| r.id == 0) {
+            mirror_only.push(format!(
+                "{} by {} at {} never reached the ledger database",
+                record.tool_name, record.agent_id, record.timestamp
+            ));
+        }
+        passed = i + 1;
+    }
+    if found.is_empty() && passed > 0 {
+        cursor.verified = tail[passed - 1].at.clone();
+        cursor.problems.extend(mirror_only.iter().cloned());
+    }
+    divergences.extend(mirror_only);
+    divergences.extend(found);
+    Ok(divergences)
+}
+
+/// Divergences between the mirror `entries` read from `from` and the
+/// ledger records after `from` up to record `upto`, and how many entries
+/// were compared. Both sides stop at BATCH records; only as far as both
+/// reach is compared.
+fn compare(
+    conn: &rusqlite::Connection,
+    from: &Position,
+    entries: &[MirrorEntry],
+    upto: i64,
+    settled: &dyn Fn(&LedgerRecord) -> bool,
+) -> Result<(Vec<String>, usize)> {
+    let stored = audit::read_records_after(conn, from.id, BATCH)?;
+    let upto = match stored.last() {
+        Some(last) if stored.len() == BATCH => upto.min(last.id),
+        _ => upto,
+    };
+    let stored: Vec<&LedgerRecord> = stored.iter().filter(|r| r.id <= upto).collect();
+    let covered = entries.iter().take_while(|e| e.at.id <= upto).count();
+    let mirrored: Vec<&LedgerRecord> = entries[..covered]
+        .iter()
+        .filter_map(|e| e.record.as_ref())
+        .filter(|r| r.id != 0)
+        .collect();
+
+    let mut divergences = Vec::new();
+    let mut tip = from.hash.as_str();
+    for record in &stored {
+        if !follows(tip, record) {
+            divergences.push("Ledger hash chain is broken".to_string());
+            break;
+        }
+        tip = &record.hash;
+    }
+    let by_hash: HashMap<&str, &LedgerRecord> =
+        mirrored.iter().map(|r| (r.hash.as_str(), *r)).collect();
+    for record in stored.iter().filter(|r| settled(r)) {
+        match by_hash.get(record.hash.as_str()) {
+            None => divergences.push(format!(
+                "Ledger record {} ({}) is missing from the mirror",
+                record.id, record.tool_name
+            )),
+            Some(copy) if *copy != *record => divergences.push(format!(
+                "Ledger record {} ({}) differs from the mirror",
+                record.id, record.tool_name
+            )),
+            Some(_) => {}
+        }
+    }
+    let in_db: HashSet<&str> = stored.iter().map(|r| r.hash.as_str()).collect();
+    for record in mirrored
+        .iter()
+        .filter(|r| settled(r) && !in_db.contains(r.hash.as_str()))
+    {
+        divergences.push(format!(
+            "Ledger record {} ({}) was removed from the database",
+            record.id, record.tool_name
+        ));
+    }
+    Ok((divergences, covered))
+}
+
+/// Compare the ledger and the mirror every few minutes, raising an
+/// incident when they diverge
+pub async fn run_verifier(mirror: AuditMirror, db_path: &'static str) {
+    let mut open_incident: Option<String> = None;
+    let mut reported: Vec<String> = Vec::new();
+    loop {
+        tokio::time::sleep(VERIFY_INTERVAL).await;
+        let cutoff = chrono::Utc::now() - chrono::Duration::seconds(IN_FLIGHT_SECS);
+        let check = mirror.clone();
+        let divergences = match tokio::task::spawn_blocking(move || {
+            cross_verify(db_path, &check, cutoff)
+        })
+        .await
+        {
+            Ok(Ok(divergences)) => divergences,
+            Ok(Err(e)) => {
+                warn!("Audit cross-verification failed: {e}");
+                continue;
+            }
+            Err(e) => {
+                warn!("Audit cross-verification panicked: {e}");
+                continue;
+            }
+        };
+
+        let now = chrono::Utc::now().timestamp();
+        if divergences.is_empty() {
+            if let Some(id) = open_incident.take() {
+                info!("Audit ledger and mirror agree again; resolving incident {id}");
+                store_incident(crate::proto::memory::Incident {
+                    id,
+                    description: "Audit ledger and mirror diverged".to_string(),
+                    resolution: "Ledger and mirror agree again".to_string(),
+                    resolved_by: "audit-mirror".to_string(),
+                    timestamp: now,
+                    ..Default::default()
+                })
+                .await;
+            }
+            reported.clear();
+            continue;
+        }
+        if divergences == reported {
+            continue;
+        }
+
+        error!(
+            "Audit ledger tampering suspected: {} divergences between the ledger and its mirror",
+            divergences.len()
+        );
+        for divergence in divergences.iter().take(MAX_LISTED) {
+            error!("Audit divergence: {divergence}");
+        }
+        let id = open_incident
+            .clone()
+            .unwrap_or_else(|| format!("audit-divergence-{now}"));
+        store_incident(crate::proto::memory::Incident {
+            id: id.clone(),
+            description: format!(
+                "Audit ledger and its mirror diverge ({} differences); the ledger may have been tampered with",
+                divergences.len()
+            ),
+            symptoms_json: serde_json::to_vec(&serde_json::json!({
+                "divergences": divergences.iter().take(MAX_LISTED).collect::<Vec<_>>(),
+                "total": divergences.len(),
+                "ledger": db_path,
+                "mirror": mirror.path,
+            }))
+            .unwrap_or_default(),
+            timestamp: now,
+            ..Default::default()
+        })
+        .await;
+        open_incident = Some(id);
+        reported = divergences;
+    }
+}
+
+async fn store_incident(incident: crate::proto::memory::Incident) {
+    let addr =
+        std::env::var("AIOS_MEMORY_ADDR").unwrap_or_else(|_| "http://127.0.0.1:50053".to_string());
+    match crate::identity::connect_memory(addr).await {
+        Ok(mut client) => {
+            if let Err(e) = client.store_incident(incident).await {
+                warn!("Failed to record audit divergence incident: {e}");
+            }
+        }
+        Err(e) => warn!("Cannot record audit divergence incident: {e}"),
+    }
+}
+
+fn load_or_create_key(path: &Path) -> Result<Vec<u8>> {
+    match std::fs::read(path) {
+        Ok(key) if key.len() >= 32 => return Ok(key),
+        Ok(_) => anyhow::bail!("Audit mirror key {} is too short", path.display()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+    let mut key = vec![0u8; 32];
+    std::fs::File::open("/dev/urandom")?.read_exact(&mut key)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)
+        .with_context(|| format!("Failed to create {}", path.display()))?
+        .write_all(&key)?;
+    info!("Generated audit mirror key at {}", path.display());
+    Ok(key)
+}
+
+/// HMAC-SHA256 of `message`, hex encoded
+fn hmac_sha256(key: &[u8], message: &[u8]) -> String {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(message);
+    format!("{:x}", mac.finalize().into_bytes())
+}
+
+/// Whether `expected`, hex encoded, is the HMAC-SHA256 of `message`
+fn hmac_valid(key: &[u8], message: &[u8], expected: &str) -> bool {
+    let Some(expected) = expected
+        .as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+        })
+        .collect::<Option<Vec<u8>>>()
+    else {
+        return false;
+    };
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(message);
+    mac.verify_slice(&expected).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditLog;
+
+    fn setup(dir: &Path) -> (String, AuditMirror, AuditLog) {
+        let db = dir.join("audit.db").to_str().unwrap().to_string();
+        let mirror = AuditMirror::open(
+            dir.join("mirror.log.gz").to_str().unwrap(),
+            dir.join("mirror.key").to_str().unwrap(),
+        )
+        .unwrap();
+        let log = AuditLog::new(&db).unwrap();
+        (db, mirror, log)
+    }
+
+    fn later() -> chrono::DateTime<chrono::Utc> {
+        chrono::Utc::now() + chrono::Duration::seconds(60)
+    }
+
+    /// Every record of the log, and what is wrong with it
+    fn read_all(mirror: &AuditMirror) -> (Vec<LedgerRecord>, Vec<String>) {
+        let (entries, corrupt) = mirror.read_from(&Position::default(), usize::MAX).unwrap();
+        let records = entries.iter().filter_map(|e| e.record.clone()).collect();
+        let problems = entries
+            .into_iter()
+            .filter_map(|e| e.problem)
+            .chain(corrupt)
+            .collect();
+        (records, problems)
+    }
+
+    #[test]
+    fn test_hmac_sha256() {
+        // RFC 4231 test case 2
+        let mac = "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843";
+        assert_eq!(hmac_sha256(b"Jefe", b"what do ya want for nothing?"), mac);
+        assert!(hmac_valid(b"Jefe", b"what do ya want for nothing?", mac));
+        assert!(!hmac_valid(b"Jefe", b"what do ya want for something?", mac));
+        assert!(!hmac_valid(b"Jefe", b"what do ya want for nothing?", "zz"));
+    }
+
+    #[test]
+    fn test_mirror_agrees_then_detects_tampering() {
+        let dir = tempfile::tempdir().unwrap();
+        let (db, mirror, mut log) = setup(dir.path());
+        log.record("exec-1", "fs.read", "agent-1", "task-1", "test", true, 5);
+
+        // Attaching backfills what the ledger already holds
+        log.attach_mirror(mirror.clone()).unwrap();
+        log.record("exec-2", "fs.write", "agent-1", "task-1", "test", true, 7);
+        log.record("exec-3", "fs.delete", "agent-1", "task-1", "test", false, 9);
+        assert_eq!(read_all(&mirror).0.len(), 3);
+        assert!(cross_verify(&db, &mirror, later()).unwrap().is_empty());
+
+        // Records compared before are compared again, but not those still
+        // being written
+        let conn = rusqlite::Connection::open(&db).unwrap();
+        conn.execute("DELETE FROM audit_log WHERE execution_id = 'exec-3'", [])
+            .unwrap();
+        let earlier = chrono::Utc::now() - chrono::Duration::seconds(60);
+        assert!(cross_verify(&db, &mirror, earlier).unwrap().is_empty());
+
+        let divergences = cross_verify(&db, &mirror, later()).unwrap();
+        assert_eq!(
+            divergences,
+            vec!["Ledger record 3 (fs.delete) was removed from the database".to_string()]
+        );
+
+        conn.execute(
+            "UPDATE audit_log SET reason = 'edited' WHERE execution_id = 'exec-2'",
+            [],
+        )
+        .unwrap();
+        let divergences = cross_verify(&db, &mirror, later()).unwrap();
+        assert!(
+            divergences.contains(&"Ledger record 2 (fs.write) differs from the mirror".to_string())
+        );
+
+        drop(conn);
+        std::fs::remove_file(&db).unwrap();
+        assert_eq!(
+            cross_verify(&db, &mirror, later()).unwrap(),
+            vec![format!("Ledger database {db} is missing")]
+        );
+    }
+
+    #[test]
+    fn test_mirror_compares_from_where_they_last_agreed() {
+        let dir = tempfile::tempdir().unwrap();
+        let (db, mirror, mut log) = setup(dir.path());
+        log.attach_mirror(mirror.clone()).unwrap();
+        log.record("exec-1", "fs.read", "agent-1", "task-1", "test", true, 5);
+        log.record("exec-2", "fs.write", "agent-1", "task-1", "test", true, 7);
+        assert!(cross_verify(&db, &mirror, later()).unwrap().is_empty());
+        let agreed = mirror.cursor.lock().unwrap().verified.clone();
+        assert_eq!(agreed.id, 2);
+        assert_eq!(
+            agreed.offset,
+            std::fs::metadata(&mirror.path).unwrap().len()
+        );
+
+        // A record written past the mirror holds the position back
+        drop(log);
+        let mut unmirrored = AuditLog::new(&db).unwrap();
+        unmirrored.record("exec-3", "fs.delete", "agent-1", "task-1", "test", true, 9);
+        assert_eq!(
+            cross_verify(&db, &mirror, later()).unwrap(),
+            vec!["Ledger record 3 (fs.delete) is missing from the mirror".to_string()]
+        );
+        assert_eq!(mirror.cursor.lock().unwrap().verified.offset, agreed.offset);
+
+        // ...until the two agree again
+        let conn = rusqlite::Connection::open(&db).unwrap();
+        let record = audit::read_records_after(&conn, 2, 1).unwrap().remove(0);
+        mirror.append(&record).unwrap();
+        assert!(cross_verify(&db, &mirror, later()).unwrap().is_empty());
+        let cursor = mirror.cursor.lock().unwrap();
+        assert_eq!(
+            (cursor.verified.id, cursor.verified.hash.as_str()),
+            (3, record.hash.as_str())
+        );
+    }
+
+    #[test]
+    fn test_mirror_rejects_forged_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let (db, mirror, mut log) = setup(dir.path());
+        log.attach_mirror(mirror.clone()).unwrap();
+        log.record("exec-1", "fs.read", "agent-1", "task-1", "test", true, 5);
+
+        // A line appended without the key
+        let forged = AuditMirror::with_key(mirror.path.clone(), vec![7; 32]).unwrap();
+        let (mut records, _) = read_all(&mirror);
+        records[0].execution_id = "exec-forged".into();
+        forged.append(&records[0]).unwrap();
+
+        let (records, problems) = read_all(&mirror);
+        assert_eq!(records.len(), 1);
+        assert_eq!(
+            problems,
+            vec!["Mirror log line 2 fails its HMAC".to_string()]
+        );
+        // Still reported once compared past
+        for _ in 0..2 {
+            assert_eq!(
+                cross_verify(&db, &mirror, later()).unwrap(),
+                vec!["Mirror log line 2 fails its HMAC".to_string()]
+            );
+        }
+    }
+
+    #[test]
+    fn test_mirror_verifies_the_hash_chain() {
+        let dir = tempfile::tempdir().unwrap();
+        let (db, mirror, mut log) = setup(dir.path());
+        log.attach_mirror(mirror.clone()).unwrap();
+        log.record("exec-1", "fs.read", "agent-1", "task-1", "test", true, 5);
+        log.record("exec-2", "fs.write", "agent-1", "task-1", "test", true, 7);
+        assert!(read_all(&mirror).1.is_empty());
+
+        // The ledger is emptied and started over; its next record no longer
+        // follows the mirror's
+        drop(log);
+        std::fs::remove_file(&db).unwrap();
+        let mut log = AuditLog::new(&db).unwrap();
+        log.attach_mirror(mirror.clone()).unwrap();
+        log.record("exec-3", "fs.delete", "agent-1", "task-1", "test", true, 9);
+
+        let (records, problems) = read_all(&mirror);
+        assert_eq!(records.len(), 3);
+        assert_eq!(
+            problems,
+            vec!["Mirror log line 3 breaks the hash chain".to_string()]
+        );
+
+        // Reopening reads the whole log again and carries the chain on
+        let reopened = AuditMirror::with_key(mirror.path.clone(), mirror.key.clone()).unwrap();
+        assert_eq!(*reopened.tip.lock().unwrap(), records[2].hash);
+    }
+
+    #[test]
+    fn test_mirror_reads_on_from_the_last_member() {
+        let dir = tempfile::tempdir().unwrap();
+        let (db, mirror, mut log) = setup(dir.path());
+        log.attach_mirror(mirror.clone()).unwrap();
+        log.record("exec-1", "fs.read", "agent-1", "task-1", "test", true, 5);
+        assert!(cross_verify(&db, &mirror, later()).unwrap().is_empty());
+        let offset = mirror.cursor.lock().unwrap().verified.offset;
+        assert_eq!(offset, std::fs::metadata(&mirror.path).unwrap().len());
+
+        // A member still being written is left for the next read
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&mirror.path)
+            .unwrap();
+        file.write_all(&[0x1f, 0x8b, 0x08]).unwrap();
+        assert!(cross_verify(&db, &mirror, later()).unwrap().is_empty());
+        assert_eq!(mirror.cursor.lock().unwrap().verified.offset, offset);
+
+        // Truncating the log is reported, and it is read from the start
+        let file = std::fs::OpenOptions::new()
+            .write(true)
+            .open(&mirror.path)
+            .unwrap();
+        file.set_len(0).unwrap();
+        assert_eq!(
+            cross_verify(&db, &mirror, later()).unwrap(),
+            vec![
+                format!("Mirror log shrank from {offset} to 0 bytes"),
+                "Ledger record 1 (fs.read) is missing from the mirror".to_string(),
+            ]
+        );
+    }
+}
@@ -1,4 +1,8 @@
 //! Backup manager for reversible tool operations
+//!
+//! Reversible tools get a copy of the file they change before they run.
+//! Snapshots taken by `disk.snapshot_create` are recorded once it succeeds,
+//! so rolling back that execution returns the data to the snapshot.
 
 use anyhow::Result;
 use std::collections::HashMap;
@@ -18,6 +22,8 @@ struct BackupEntry {
     execution_id: String,
     tool_name: String,
     backup_path: Option<PathBuf>,
+    /// Snapshot taken by the execution, restored on rollback
+    snapshot_id: Option<String>,
     input_data: Vec<u8>,
     created_at: i64,
 }
@@ -41,8 +47,11 @@ impl BackupManager {
     ) -> String {
         let backup_id = Uuid::new_v4().to_string();
 
-        // For file operations, back up the target file
-        let backup_path = if tool_name.starts_with("fs.") {
+        // For file operations (including rendered templates and patched
+        // documents), back up the target file
+        let backup_path = if tool_name.starts_with("fs.")
+            || matches!(tool_name, "template.render" | "data.merge_patch")
+        {
             self.backup_file_from_input(input_json, &backup_id)
         } else {
             None
@@ -54,6 +63,7 @@ impl BackupManager {
                 execution_id: execution_id.to_string(),
                 tool_name: tool_name.to_string(),
                 backup_path,
+                snapshot_id: None,
                 input_data: input_json.to_vec(),
                 created_at: chrono::Utc::now().timestamp(),
             },
@@ -63,6 +73,34 @@ impl BackupManager {
         backup_id
     }
 
+    /// Record the snapshot a successful `disk.snapshot_create` took, so
+    /// rolling back the execution restores it. Returns the snapshot id.
+    pub fn record_snapshot(
+        &mut self,
+        execution_id: &str,
+        tool_name: &str,
+        output_json: &[u8],
+    ) -> Option<String> {
+        if tool_name != "disk.snapshot_create" {
+            return None;
+        }
+        let output: serde_json::Value = serde_json::from_slice(output_json).ok()?;
+        let snapshot_id = output.get("id")?.as_str()?.to_string();
+        self.backups.insert(
+            execution_id.to_string(),
+            BackupEntry {
+                execution_id: execution_id.to_string(),
+                tool_name: tool_name.to_string(),
+                backup_path: None,
+                snapshot_id: Some(snapshot_id.clone()),
+                input_data: Vec::new(),
+                created_at: chrono::Utc::now().timestamp(),
+            },
+        );
+        info!("Recorded snapshot {snapshot_id} for rollback of {execution_id}");
+        Some(snapshot_id)
+    }
+
     /// Restore from a backup
     pub async fn rollback(&mut self, execution_id: &str) -> Result<bool> {
         let entry = match self.backups.remove(execution_id) {
@@ -70,6 +108,14 @@ impl BackupManager {
             None => return Ok(false),
         };
 
+        if let Some(snapshot_id) = entry.snapshot_id {
+            tokio::task::spawn_blocking(move || {
+                crate::disk::snapshot::Snapshots::open_default().rollback(&snapshot_id)
+            })
+            .await??;
+            return Ok(true);
+        }
+
         if let Some(backup_path) = &entry.backup_path {
             // Extract target path from input
             if let Ok(input) = serde_json::from_slice::<serde_json::Value>(&entry.input_data) {
@@ -143,6 +189,26 @@ mod tests {
         assert!(bm.backups.contains_key("exec-1"));
     }
 
+    #[test]
+    fn test_record_snapshot_only_for_snapshot_create() {
+        let (mut bm, _dir) = setup_backup_manager();
+        let output = br#"{"id": "3f2a", "backend": "btrfs"}"#;
+
+        assert_eq!(bm.record_snapshot("exec-1", "disk.mount", output), None);
+        assert_eq!(
+            bm.record_snapshot("exec-2", "disk.snapshot_create", b"not json"),
+            None
+        );
+        assert!(bm.backups.is_empty());
+
+        let id = bm.record_snapshot("exec-3", "disk.snapshot_create", output);
+        assert_eq!(id.as_deref(), Some("3f2a"));
+        assert_eq!(
+            bm.backups.get("exec-3").unwrap().snapshot_id.as_deref(),
+            Some("3f2a")
+        );
+    }
+
     #[test]
     fn test_create_backup_fs_tool_with_existing_file() {
         let (mut bm, dir) = setup_backup_manager();
@@ -239,6 +305,7 @@ mod tests {
                 execution_id: "old-exec".to_string(),
                 tool_name: "fs.write".to_string(),
                 backup_path: None,
+                snapshot_id: None,
                 input_data: vec![],
                 created_at: 0, // epoch -- very old
             },
@@ -249,6 +316,7 @@ mod tests {
                 execution_id: "new-exec".to_string(),
                 tool_name: "fs.write".to_string(),
                 backup_path: None,
+                snapshot_id: None,
                 input_data: vec![],
                 created_at: chrono::Utc::now().timestamp(),
             },
@@ -320,6 +388,7 @@ mod tests {
                 execution_id: "recent".to_string(),
                 tool_name: "net.ping".to_string(),
                 backup_path: None,
+                snapshot_id: None,
                 input_data: vec![],
                 created_at: chrono::Utc::now().timestamp(),
             },
@@ -0,0 +1,601 @@
+//! calc.eval — Safe expression evaluator with aggregate functions over arrays
+//!
+//! A small recursive-descent parser: no I/O, no user-defined functions, and
+//! bounded input length and nesting depth, so any expression terminates quickly.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// Longest accepted expression
+const MAX_EXPRESSION_LEN: usize = 4096;
+
+/// Deepest accepted nesting of sub-expressions
+const MAX_DEPTH: usize = 64;
+
+#[derive(Deserialize)]
+struct Input {
+    expression: String,
+    #[serde(default)]
+    variables: HashMap<String, Value>,
+}
+
+/// A value during evaluation
+#[derive(Debug, Clone, PartialEq)]
+enum Val {
+    Num(f64),
+    Bool(bool),
+    Array(Vec<f64>),
+}
+
+impl Val {
+    fn from_json(name: &str, value: &Value) -> Result<Self> {
+        match value {
+            Value::Number(n) => Ok(Self::Num(n.as_f64().unwrap_or(f64::NAN))),
+            Value::Bool(b) => Ok(Self::Bool(*b)),
+            Value::Array(items) => items
+                .iter()
+                .map(|item| {
+                    item.as_f64().ok_or_else(|| {
+                        anyhow::anyhow!("variable '{name}' must contain only numbers")
+                    })
+                })
+                .collect::<Result<Vec<_>>>()
+                .map(Self::Array),
+            _ => anyhow::bail!("variable '{name}' must be a number, boolean, or numeric array"),
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        match self {
+            Self::Num(n) => json!(n),
+            Self::Bool(b) => json!(b),
+            Self::Array(items) => json!(items),
+        }
+    }
+
+    fn num(&self, what: &str) -> Result<f64> {
+        match self {
+            Self::Num(n) => Ok(*n),
+            _ => anyhow::bail!("{what} expects a number, got {}", self.kind()),
+        }
+    }
+
+    fn bool(&self, what: &str) -> Result<bool> {
+        match self {
+            Self::Bool(b) => Ok(*b),
+            _ => anyhow::bail!("{what} expects a boolean, got {}", self.kind()),
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Num(_) => "number",
+            Self::Bool(_) => "boolean",
+            Self::Array(_) => "array",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(f64),
+    Ident(String),
+    Op(&'static str),
+}
+
+const OPERATORS: &[&str] = &[
+    "<=", ">=", "==", "!=", "&&", "||", "+", "-", "*", "/", "%", "^", "<", ">", "!", "(", ")", "[",
+    "]", ",",
+];
+
+fn tokenize(src: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let bytes = src.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i] as char;
+        if c.is_ascii_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit()
+            || (c == '.' && bytes.get(i + 1).is_some_and(u8::is_ascii_digit))
+        {
+            let start = i;
+            while i < bytes.len() && (bytes[i].is_ascii_digit() || bytes[i] == b'.') {
+                i += 1;
+            }
+            // Exponent: 1e3, 2.5E-4
+            if i < bytes.len() && (bytes[i] == b'e' || bytes[i] == b'E') {
+                let mut j = i + 1;
+                if j < bytes.len() && (bytes[j] == b'+' || bytes[j] == b'-') {
+                    j += 1;
+                }
+                if j < bytes.len() && bytes[j].is_ascii_digit() {
+                    i = j;
+                    while i < bytes.len() && bytes[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let text = &src[start..i];
+            let n = text
+                .parse()
+                .with_context(|| format!("invalid number '{text}'"))?;
+            tokens.push(Token::Num(n));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(src[start..i].to_string()));
+        } else if let Some(op) = OPERATORS.iter().find(|op| src[i..].starts_with(**op)) {
+            tokens.push(Token::Op(op));
+            i += op.len();
+        } else {
+            anyhow::bail!("unexpected character '{c}' at position {i}");
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+    variables: &'a HashMap<String, Val>,
+}
+
+impl Parser<'_> {
+    fn peek_op(&self) -> Option<&'static str> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Op(op)) => Some(op),
+            _ => None,
+        }
+    }
+
+    fn eat(&mut self, op: &str) -> bool {
+        if self.peek_op() == Some(op) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, op: &str) -> Result<()> {
+        if self.eat(op) {
+            Ok(())
+        } else {
+            anyhow::bail!("expected '{op}' at token {}", self.pos + 1)
+        }
+    }
+
+    fn expr(&mut self) -> Result<Val> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            anyhow::bail!("expression nested too deeply (max {MAX_DEPTH})");
+        }
+        let result = self.or();
+        self.depth -= 1;
+        result
+    }
+
+    fn or(&mut self) -> Result<Val> {
+        let mut left = self.and()?;
+        while self.eat("||") {
+            let right = self.and()?;
+            left = Val::Bool(left.bool("||")? || right.bool("||")?);
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Val> {
+        let mut left = self.comparison()?;
+        while self.eat("&&") {
+            let right = self.comparison()?;
+            left = Val::Bool(left.bool("&&")? && right.bool("&&")?);
+        }
+        Ok(left)
+    }
+
+    fn comparison(&mut self) -> Result<Val> {
+        let left = self.additive()?;
+        let Some(op) = self
+            .peek_op()
+            .filter(|op| matches!(*op, "<" | "<=" | ">" | ">=" | "==" | "!="))
+        else {
+            return Ok(left);
+        };
+        self.pos += 1;
+        let right = self.additive()?;
+        if let (Val::Bool(a), Val::Bool(b)) = (&left, &right) {
+            return match op {
+                "==" => Ok(Val::Bool(a == b)),
+                "!=" => Ok(Val::Bool(a != b)),
+                _ => anyhow::bail!("'{op}' cannot compare booleans"),
+            };
+        }
+        let (a, b) = (left.num(op)?, right.num(op)?);
+        Ok(Val::Bool(match op {
+            "<" => a < b,
+            "<=" => a <= b,
+            ">" => a > b,
+            ">=" => a >= b,
+            "==" => a == b,
+            _ => a != b,
+        }))
+    }
+
+    fn additive(&mut self) -> Result<Val> {
+        let mut left = self.multiplicative()?;
+        while let Some(op) = self.peek_op().filter(|op| matches!(*op, "+" | "-")) {
+            self.pos += 1;
+            let right = self.multiplicative()?;
+            left = arithmetic(op, &left, &right)?;
+        }
+        Ok(left)
+    }
+
+    fn multiplicative(&mut self) -> Result<Val> {
+        let mut left = self.unary()?;
+        while let Some(op) = self.peek_op().filter(|op| matches!(*op, "*" | "/" | "%")) {
+            self.pos += 1;
+            let right = self.unary()?;
+            left = arithmetic(op, &left, &right)?;
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Val> {
+        if self.eat("-") {
+            let v = self.unary()?;
+            return arithmetic("*", &Val::Num(-1.0), &v);
+        }
+        if self.eat("!") {
+            let v = self.unary()?;
+            return Ok(Val::Bool(!v.bool("!")?));
+        }
+        self.power()
+    }
+
+    fn power(&mut self) -> Result<Val> {
+        let base = self.primary()?;
+        if self.eat("^") {
+            // Right-associative: 2^3^2 == 2^(3^2)
+            let exponent = self.unary()?;
+            return arithmetic("^", &base, &exponent);
+        }
+        Ok(base)
+    }
+
+    fn primary(&mut self) -> Result<Val> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("unexpected end of expression"))?;
+        self.pos += 1;
+        match token {
+            Token::Num(n) => Ok(Val::Num(n)),
+            Token::Op("(") => {
+                let v = self.expr()?;
+                self.expect(")")?;
+                Ok(v)
+            }
+            Token::Op("[") => {
+                let mut items = Vec::new();
+                if !self.eat("]") {
+                    loop {
+                        items.push(self.expr()?.num("array literal")?);
+                        if self.eat("]") {
+                            break;
+                        }
+                        self.expect(",")?;
+                    }
+                }
+                Ok(Val::Array(items))
+            }
+            Token::Ident(name) if self.eat("(") => {
+                let mut args = Vec::new();
+                if !self.eat(")") {
+                    loop {
+                        args.push(self.expr()?);
+                        if self.eat(")") {
+                            break;
+                        }
+                        self.expect(",")?;
+                    }
+                }
+                call(&name, &args)
+            }
+            Token::Ident(name) => match name.as_str() {
+                "true" => Ok(Val::Bool(true)),
+                "false" => Ok(Val::Bool(false)),
+                "pi" => Ok(Val::Num(std::f64::consts::PI)),
+                _ => self
+                    .variables
+                    .get(&name)
+                    .cloned()
+                    .ok_or_else(|| anyhow::anyhow!("unknown variable '{name}'")),
+            },
+            Token::Op(op) => anyhow::bail!("unexpected '{op}'"),
+        }
+    }
+}
+
+/// Apply a binary arithmetic operator, broadcasting scalars over arrays.
+fn arithmetic(op: &str, left: &Val, right: &Val) -> Result<Val> {
+    let apply = |a: f64, b: f64| -> Result<f64> {
+        Ok(match op {
+            "+" => a + b,
+            "-" => a - b,
+            "*" => a * b,
+            "/" if b == 0.0 => anyhow::bail!("division by zero"),
+            "/" => a / b,
+            "%" if b == 0.0 => anyhow::bail!("modulo by zero"),
+            "%" => a % b,
+            _ => a.powf(b),
+        })
+    };
+    match (left, right) {
+        (Val::Num(a), Val::Num(b)) => apply(*a, *b).map(Val::Num),
+        (Val::Array(xs), Val::Num(b)) => xs
+            .iter()
+            .map(|x| apply(*x, *b))
+            .collect::<Result<_>>()
+            .map(Val::Array),
+        (Val::Num(a), Val::Array(ys)) => ys
+            .iter()
+            .map(|y| apply(*a, *y))
+            .collect::<Result<_>>()
+            .map(Val::Array),
+        (Val::Array(xs), Val::Array(ys)) if xs.len() == ys.len() => xs
+            .iter()
+            .zip(ys)
+            .map(|(x, y)| apply(*x, *y))
+            .collect::<Result<_>>()
+            .map(Val::Array),
+        (Val::Array(xs), Val::Array(ys)) => anyhow::bail!(
+            "'{op}' on arrays of different lengths ({} and {})",
+            xs.len(),
+            ys.len()
+        ),
+        _ => anyhow::bail!(
+            "'{op}' expects numbers or arrays, got {} and {}",
+            left.kind(),
+            right.kind()
+        ),
+    }
+}
+
+/// Flatten numbers and arrays in `args` into one sample list.
+fn samples(name: &str, args: &[Val]) -> Result<Vec<f64>> {
+    let mut out = Vec::new();
+    for arg in args {
+        match arg {
+            Val::Num(n) => out.push(*n),
+            Val::Array(items) => out.extend(items),
+            Val::Bool(_) => anyhow::bail!("{name}() expects numbers or arrays"),
+        }
+    }
+    Ok(out)
+}
+
+fn nonempty(name: &str, xs: Vec<f64>) -> Result<Vec<f64>> {
+    if xs.is_empty() {
+        anyhow::bail!("{name}() of an empty set");
+    }
+    Ok(xs)
+}
+
+fn mean(xs: &[f64]) -> f64 {
+    xs.iter().sum::<f64>() / xs.len() as f64
+}
+
+fn variance(xs: &[f64]) -> f64 {
+    let m = mean(xs);
+    xs.iter().map(|x| (x - m).powi(2)).sum::<f64>() / xs.len() as f64
+}
+
+/// Linear-interpolated percentile, `p` in 0..=100
+fn percentile(mut xs: Vec<f64>, p: f64) -> Result<f64> {
+    if !(0.0..=100.0).contains(&p) {
+        anyhow::bail!("percentile must be between 0 and 100, got {p}");
+    }
+    xs.sort_by(|a, b| a.total_cmp(b));
+    let rank = p / 100.0 * (xs.len() - 1) as f64;
+    let (lo, hi) = (rank.floor() as usize, rank.ceil() as usize);
+    Ok(xs[lo] + (xs[hi] - xs[lo]) * (rank - lo as f64))
+}
+
+fn call(name: &str, args: &[Val]) -> Result<Val> {
+    let arity = |n: usize| -> Result<()> {
+        if args.len() != n {
+            anyhow::bail!("{name}() takes {n} argument(s), got {}", args.len());
+        }
+        Ok(())
+    };
+    let scalar = |f: fn(f64) -> f64| -> Result<Val> {
+        arity(1)?;
+        match &args[0] {
+            Val::Num(x) => Ok(Val::Num(f(*x))),
+            Val::Array(xs) => Ok(Val::Array(xs.iter().map(|x| f(*x)).collect())),
+            Val::Bool(_) => anyhow::bail!("{name}() expects a number or array"),
+        }
+    };
+
+    match name {
+        "sum" => Ok(Val::Num(samples(name, args)?.iter().sum())),
+        "count" => Ok(Val::Num(samples(name, args)?.len() as f64)),
+        "avg" | "mean" => Ok(Val::Num(mean(&nonempty(name, samples(name, args)?)?))),
+        "min" => Ok(Val::Num(
+            nonempty(name, samples(name, args)?)?
+                .into_iter()
+                .fold(f64::INFINITY, f64::min),
+        )),
+        "max" => Ok(Val::Num(
+            nonempty(name, samples(name, args)?)?
+                .into_iter()
+                .fold(f64::NEG_INFINITY, f64::max),
+        )),
+        "median" => Ok(Val::Num(percentile(
+            nonempty(name, samples(name, args)?)?,
+            50.0,
+        )?)),
+        "variance" => Ok(Val::Num(variance(&nonempty(name, samples(name, args)?)?))),
+        "stddev" => Ok(Val::Num(
+            variance(&nonempty(name, samples(name, args)?)?).sqrt(),
+        )),
+        "percentile" => {
+            arity(2)?;
+            let xs = nonempty(name, samples(name, &args[..1])?)?;
+            Ok(Val::Num(percentile(xs, args[1].num("percentile")?)?))
+        }
+        "round" if args.len() == 2 => {
+            let factor = 10f64.powi(args[1].num("round")? as i32);
+            Ok(Val::Num((args[0].num("round")? * factor).round() / factor))
+        }
+        "round" => scalar(f64::round),
+        "abs" => scalar(f64::abs),
+        "floor" => scalar(f64::floor),
+        "ceil" => scalar(f64::ceil),
+        "sqrt" => scalar(f64::sqrt),
+        "ln" => scalar(f64::ln),
+        "log10" => scalar(f64::log10),
+        "exp" => scalar(f64::exp),
+        "pow" => {
+            arity(2)?;
+            arithmetic("^", &args[0], &args[1])
+        }
+        _ => anyhow::bail!("unknown function '{name}'"),
+    }
+}
+
+/// Evaluate `expression` with the given variables.
+fn evaluate(expression: &str, variables: &HashMap<String, Val>) -> Result<Val> {
+    if expression.len() > MAX_EXPRESSION_LEN {
+        anyhow::bail!("expression longer than {MAX_EXPRESSION_LEN} characters");
+    }
+    let mut parser = Parser {
+        tokens: tokenize(expression)?,
+        pos: 0,
+        depth: 0,
+        variables,
+    };
+    let result = parser.expr()?;
+    if parser.pos < parser.tokens.len() {
+        anyhow::bail!("unexpected trailing input at token {}", parser.pos + 1);
+    }
+    let finite = match &result {
+        Val::Num(n) => n.is_finite(),
+        Val::Array(xs) => xs.iter().all(|x| x.is_finite()),
+        Val::Bool(_) => true,
+    };
+    if !finite {
+        anyhow::bail!("result is not a finite number");
+    }
+    Ok(result)
+}
+
+pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
+    let input: Input = serde_json::from_slice(input).context("calc.eval: invalid JSON input")?;
+    let variables = input
+        .variables
+        .iter()
+        .map(|(name, value)| Ok((name.clone(), Val::from_json(name, value)?)))
+        .collect::<Result<HashMap<_, _>>>()
+        .context("calc.eval: invalid variables")?;
+
+    let result = evaluate(&input.expression, &variables)
+        .with_context(|| format!("calc.eval: cannot evaluate '{}'", input.expression))?;
+
+    let output = json!({
+        "expression": input.expression,
+        "result": result.to_json(),
+        "type": result.kind(),
+    });
+    serde_json::to_vec(&output).context("calc.eval: failed to serialise output")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(expression: &str) -> Result<Val> {
+        let variables = HashMap::from([
+            ("cpu".to_string(), Val::Array(vec![70.0, 85.0, 90.0, 75.0])),
+            ("threshold".to_string(), Val::Num(80.0)),
+            ("enabled".to_string(), Val::Bool(true)),
+        ]);
+        evaluate(expression, &variables)
+    }
+
+    #[test]
+    fn test_arithmetic_precedence() {
+        assert_eq!(eval("1 + 2 * 3").unwrap(), Val::Num(7.0));
+        assert_eq!(eval("(1 + 2) * 3").unwrap(), Val::Num(9.0));
+        assert_eq!(eval("2 ^ 3 ^ 2").unwrap(), Val::Num(512.0));
+        assert_eq!(eval("-2 ^ 2").unwrap(), Val::Num(-4.0));
+        assert_eq!(eval("10 % 4 - 1.5e1 / 3").unwrap(), Val::Num(-3.0));
+    }
+
+    #[test]
+    fn test_aggregates_and_comparison() {
+        assert_eq!(eval("avg(cpu)").unwrap(), Val::Num(80.0));
+        assert_eq!(eval("max(cpu) - min(cpu)").unwrap(), Val::Num(20.0));
+        assert_eq!(eval("median(cpu)").unwrap(), Val::Num(80.0));
+        assert_eq!(eval("percentile(cpu, 100)").unwrap(), Val::Num(90.0));
+        assert_eq!(eval("count(cpu, 1, [2, 3])").unwrap(), Val::Num(7.0));
+        assert_eq!(
+            eval("round(stddev([2, 4, 4, 4, 5, 5, 7, 9]), 2)").unwrap(),
+            Val::Num(2.0)
+        );
+        assert_eq!(
+            eval("avg(cpu) >= threshold && enabled").unwrap(),
+            Val::Bool(true)
+        );
+        assert_eq!(eval("!(max(cpu) < 90)").unwrap(), Val::Bool(true));
+    }
+
+    #[test]
+    fn test_array_broadcasting() {
+        assert_eq!(
+            eval("cpu / 10").unwrap(),
+            Val::Array(vec![7.0, 8.5, 9.0, 7.5])
+        );
+        assert_eq!(eval("sum([1, 2] * [3, 4])").unwrap(), Val::Num(11.0));
+        assert!(eval("[1, 2] + [1, 2, 3]").is_err());
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(eval("1 / 0")
+            .unwrap_err()
+            .to_string()
+            .contains("division by zero"));
+        assert!(eval("avg([])").is_err());
+        assert!(eval("unknown_var + 1").is_err());
+        assert!(eval("system(1)").is_err());
+        assert!(eval("1 +").is_err());
+        assert!(eval("1 2").is_err());
+        assert!(eval("enabled + 1").is_err());
+        assert!(eval(&format!("{}1{}", "(".repeat(100), ")".repeat(100))).is_err());
+    }
+
+    #[test]
+    fn test_execute_json_contract() {
+        let input = json!({
+            "expression": "avg(samples) > 80",
+            "variables": {"samples": [79.5, 81.5, 82]},
+        });
+        let out: Value =
+            serde_json::from_slice(&execute(&serde_json::to_vec(&input).unwrap()).unwrap())
+                .unwrap();
+        assert_eq!(out["result"], true);
+        assert_eq!(out["type"], "boolean");
+
+        let bad = json!({"expression": "1", "variables": {"x": ["a"]}});
+        assert!(execute(&serde_json::to_vec(&bad).unwrap()).is_err());
+    }
+}
@@ -0,0 +1,36 @@
+//! Calculation tools — exact arithmetic and statistics over metric data.
+//!
+//! Each submodule exposes `pub fn execute(input: &[u8]) -> Result<Vec<u8>>`.
+
+pub mod eval;
+
+use serde_json::json;
+
+use crate::registry::{make_tool, with_input_schema, Registry};
+
+/// Register every calculation tool with the registry.
+pub fn register_tools(reg: &mut Registry) {
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "calc.eval",
+            "calc",
+            "Evaluate an arithmetic/boolean expression exactly, e.g. \"avg(samples) > 80\". \
+             'variables' maps names to numbers, booleans, or numeric arrays. \
+             Operators: + - * / % ^ < <= > >= == != && || !; arrays broadcast over arithmetic. \
+             Functions: sum avg mean min max count median stddev variance percentile(arr, p) abs round(x, digits) floor ceil sqrt ln log10 exp pow",
+            vec![],
+            "low",
+            true,
+            false,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "expression": { "type": "string" },
+                "variables": { "type": "object", "additionalProperties": {} }
+            },
+            "required": ["expression"]
+        }),
+    ));
+}
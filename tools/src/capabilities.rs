@@ -6,6 +6,9 @@
 use std::collections::{HashMap, HashSet};
 use tracing::{info, warn};
 
+/// Lets an agent call experimental tools without its goal opting in
+pub const EXPERIMENTAL_CAPABILITY: &str = "tools_experimental";
+
 /// Defines capabilities required for a tool namespace
 #[derive(Debug, Clone)]
 pub struct CapabilityRequirement {
@@ -14,8 +17,8 @@ pub struct CapabilityRequirement {
     pub risk_level: RiskLevel,
 }
 
-/// Risk level for a tool operation
-#[derive(Debug, Clone, PartialEq)]
+/// Risk level for a tool operation, ordered from least to most risky
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub enum RiskLevel {
     /// Read-only operations, no side effects
     Low,
@@ -27,7 +30,30 @@ pub enum RiskLevel {
     Critical,
 }
 
+impl RiskLevel {
+    /// Parse a risk level as written in tool definitions ("low" … "critical")
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "low" => Some(Self::Low),
+            "medium" => Some(Self::Medium),
+            "high" => Some(Self::High),
+            "critical" => Some(Self::Critical),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+            Self::Critical => "critical",
+        }
+    }
+}
+
 /// Validates agent capabilities against tool requirements
+#[derive(Clone)]
 pub struct CapabilityChecker {
     /// Agent ID → set of capabilities
     agent_capabilities: HashMap<String, HashSet<String>>,
@@ -69,6 +95,8 @@ impl CapabilityChecker {
             "sec_manage",
             "monitor_read",
             "hw_read",
+            "disk_read",
+            "disk_manage",
             "git_read",
             "git_write",
             "code_gen",
@@ -77,6 +105,8 @@ impl CapabilityChecker {
             "plugin_read",
             "plugin_manage",
             "plugin_execute",
+            "composite_read",
+            "composite_manage",
             "container_read",
             "container_manage",
             "email_send",
@@ -143,6 +173,8 @@ impl CapabilityChecker {
             "fs_permissions",
             "monitor_read",
             "process_manage",
+            "disk_read",
+            "disk_manage",
         ]
         .into_iter()
         .map(String::from)
@@ -174,6 +206,9 @@ impl CapabilityChecker {
             "plugin_read",
             "plugin_manage",
             "plugin_execute",
+            "composite_read",
+            "composite_manage",
+            EXPERIMENTAL_CAPABILITY,
         ]
         .into_iter()
         .map(String::from)
@@ -186,7 +221,11 @@ impl CapabilityChecker {
             .collect();
         self.register_agent("web-agent", &web_caps);
 
-        info!("Registered 10 default agents with capabilities");
+        // Plugins fired by their event triggers run as this agent
+        let trigger_caps: Vec<String> = vec!["plugin_execute".to_string()];
+        self.register_agent(crate::plugin::events::TRIGGER_AGENT_ID, &trigger_caps);
+
+        info!("Registered 11 default agents with capabilities");
     }
 
     /// Register default tool capability requirements
@@ -204,6 +243,7 @@ impl CapabilityChecker {
             ("fs.move", vec!["fs_write"], RiskLevel::Medium),
             ("fs.symlink", vec!["fs_write"], RiskLevel::Medium),
             ("fs.delete", vec!["fs_write", "fs_delete"], RiskLevel::High),
+            ("fs.restore_deleted", vec!["fs_write"], RiskLevel::High),
             (
                 "fs.chmod",
                 vec!["fs_write", "fs_permissions"],
@@ -257,14 +297,20 @@ impl CapabilityChecker {
             // Security
             ("sec.check_perms", vec!["sec_read"], RiskLevel::Low),
             ("sec.audit_query", vec!["sec_read"], RiskLevel::Low),
+            ("sec.audit_detail", vec!["sec_read"], RiskLevel::Low),
             ("sec.grant", vec!["sec_manage"], RiskLevel::Critical),
             ("sec.revoke", vec!["sec_manage"], RiskLevel::Critical),
             ("sec.audit", vec!["sec_read"], RiskLevel::Low),
             ("sec.scan", vec!["sec_read"], RiskLevel::Medium),
+            ("sec.cert_check", vec!["sec_read"], RiskLevel::Low),
             ("sec.cert_generate", vec!["sec_manage"], RiskLevel::High),
             ("sec.cert_rotate", vec!["sec_manage"], RiskLevel::Critical),
             ("sec.file_integrity", vec!["sec_read"], RiskLevel::Low),
             ("sec.scan_rootkits", vec!["sec_read"], RiskLevel::Medium),
+            ("sec.canary_plant", vec!["sec_manage"], RiskLevel::Medium),
+            ("sec.canary_list", vec!["sec_read"], RiskLevel::Low),
+            ("sec.canary_remove", vec!["sec_manage"], RiskLevel::Medium),
+            ("sec.canary_check", vec!["sec_read"], RiskLevel::Low),
             // Monitor — all read-only
             ("monitor.cpu", vec!["monitor_read"], RiskLevel::Low),
             ("monitor.memory", vec!["monitor_read"], RiskLevel::Low),
@@ -279,6 +325,24 @@ impl CapabilityChecker {
             ("monitor.fs_watch", vec!["monitor_read"], RiskLevel::Low),
             // Hardware
             ("hw.info", vec!["hw_read"], RiskLevel::Low),
+            // Block devices
+            ("disk.list_block_devices", vec!["disk_read"], RiskLevel::Low),
+            ("disk.mount", vec!["disk_manage"], RiskLevel::High),
+            ("disk.umount", vec!["disk_manage"], RiskLevel::High),
+            ("disk.format", vec!["disk_manage"], RiskLevel::Critical),
+            ("disk.resize_fs", vec!["disk_manage"], RiskLevel::High),
+            (
+                "disk.snapshot_create",
+                vec!["disk_manage"],
+                RiskLevel::Medium,
+            ),
+            ("disk.snapshot_list", vec!["disk_read"], RiskLevel::Low),
+            (
+                "disk.snapshot_rollback",
+                vec!["disk_manage"],
+                RiskLevel::Critical,
+            ),
+            ("disk.snapshot_delete", vec!["disk_manage"], RiskLevel::High),
             // Web connectivity
             (
                 "web.http_request",
@@ -322,6 +386,7 @@ impl CapabilityChecker {
             // Self-update
             ("self.inspect", vec!["self_read"], RiskLevel::Low),
             ("self.health", vec!["self_read"], RiskLevel::Low),
+            ("self.benchmark", vec!["self_read"], RiskLevel::Low),
             ("self.update", vec!["self_update"], RiskLevel::Critical),
             ("self.rebuild", vec!["self_update"], RiskLevel::Critical),
             // Process (cgroup)
@@ -347,6 +412,34 @@ impl CapabilityChecker {
             ("container.logs", vec!["container_read"], RiskLevel::Low),
             // Email
             ("email.send", vec!["email_send"], RiskLevel::Medium),
+            // Paging through a truncated output needs no extra capability —
+            // the handle is only known to whoever ran the original tool
+            ("output.page", vec![], RiskLevel::Low),
+            // Hashing
+            ("hash.file", vec!["fs_read"], RiskLevel::Low),
+            ("hash.string", vec![], RiskLevel::Low),
+            ("hash.verify", vec!["fs_read"], RiskLevel::Low),
+            // Structured data
+            ("data.jq", vec!["fs_read"], RiskLevel::Low),
+            (
+                "data.merge_patch",
+                vec!["fs_read", "fs_write"],
+                RiskLevel::Medium,
+            ),
+            ("data.convert", vec!["fs_read", "fs_write"], RiskLevel::Low),
+            // Text processing
+            ("text.grep", vec!["fs_read"], RiskLevel::Low),
+            ("text.split", vec!["fs_read"], RiskLevel::Low),
+            ("text.diff", vec!["fs_read"], RiskLevel::Low),
+            ("text.count", vec!["fs_read"], RiskLevel::Low),
+            // Pure computation, no system access
+            ("calc.eval", vec![], RiskLevel::Low),
+            // Templating writes files like fs.write
+            (
+                "template.render",
+                vec!["fs_read", "fs_write"],
+                RiskLevel::Medium,
+            ),
             // Plugin management
             (
                 "plugin.create",
@@ -365,6 +458,18 @@ impl CapabilityChecker {
                 vec!["plugin_manage", "fs_write"],
                 RiskLevel::Medium,
             ),
+            // Composite management; composites themselves are checked step by step
+            (
+                "composite.define",
+                vec!["composite_manage", "fs_write"],
+                RiskLevel::High,
+            ),
+            ("composite.list", vec!["composite_read"], RiskLevel::Low),
+            (
+                "composite.delete",
+                vec!["composite_manage"],
+                RiskLevel::High,
+            ),
         ];
 
         for (pattern, caps, risk) in requirements {
@@ -386,6 +491,30 @@ impl CapabilityChecker {
             .insert(agent_id.to_string(), capabilities.iter().cloned().collect());
     }
 
+    /// Replace what a tool requires, or add a requirement for a new tool
+    pub fn set_requirement(&mut self, tool_name: &str, capabilities: &[String], risk: RiskLevel) {
+        let requirement = CapabilityRequirement {
+            tool_pattern: tool_name.to_string(),
+            required_capabilities: capabilities.to_vec(),
+            risk_level: risk,
+        };
+        match self
+            .tool_requirements
+            .iter_mut()
+            .find(|r| r.tool_pattern == tool_name)
+        {
+            Some(existing) => *existing = requirement,
+            None => self.tool_requirements.push(requirement),
+        }
+    }
+
+    /// Whether an agent holds a capability
+    pub fn has_capability(&self, agent_id: &str, capability: &str) -> bool {
+        self.agent_capabilities
+            .get(agent_id)
+            .is_some_and(|caps| caps.contains(capability))
+    }
+
     /// Check if an agent has permission to execute a tool
     pub fn check_permission(&self, agent_id: &str, tool_name: &str) -> CapabilityCheckResult {
         // Find the capability requirement for this tool
@@ -472,6 +601,15 @@ impl CapabilityChecker {
         }
     }
 
+    /// Capabilities a tool requires (empty for unknown tools)
+    pub fn required_capabilities(&self, tool_name: &str) -> &[String] {
+        self.tool_requirements
+            .iter()
+            .find(|r| r.tool_pattern == tool_name)
+            .map(|r| r.required_capabilities.as_slice())
+            .unwrap_or_default()
+    }
+
     /// Get the risk level for a tool
     pub fn get_risk_level(&self, tool_name: &str) -> RiskLevel {
         self.tool_requirements
@@ -539,6 +677,14 @@ mod tests {
         assert!(!result.allowed);
     }
 
+    #[test]
+    fn test_experimental_capability() {
+        let checker = CapabilityChecker::new();
+        assert!(checker.has_capability("creator-agent", EXPERIMENTAL_CAPABILITY));
+        assert!(!checker.has_capability("autonomy-loop", EXPERIMENTAL_CAPABILITY));
+        assert!(!checker.has_capability("unknown-agent", "fs_read"));
+    }
+
     #[test]
     fn test_risk_levels() {
         let checker = CapabilityChecker::new();
@@ -651,6 +797,31 @@ mod tests {
         assert_eq!(checker.get_risk_level("container.logs"), RiskLevel::Low);
     }
 
+    #[test]
+    fn test_disk_tools_registered() {
+        let checker = CapabilityChecker::new();
+        assert_eq!(
+            checker.get_risk_level("disk.list_block_devices"),
+            RiskLevel::Low
+        );
+        assert_eq!(checker.get_risk_level("disk.mount"), RiskLevel::High);
+        assert_eq!(checker.get_risk_level("disk.format"), RiskLevel::Critical);
+        assert_eq!(
+            checker.get_risk_level("disk.snapshot_rollback"),
+            RiskLevel::Critical
+        );
+        assert!(
+            checker
+                .check_permission("storage-agent", "disk.mount")
+                .allowed
+        );
+        assert!(
+            !checker
+                .check_permission("system-agent", "disk.format")
+                .allowed
+        );
+    }
+
     #[test]
     fn test_new_monitor_and_process_tools_registered() {
         let checker = CapabilityChecker::new();
@@ -5,29 +5,65 @@
 pub mod generate;
 pub mod scaffold;
 
-use crate::registry::{make_tool, Registry};
+use serde_json::json;
+
+use crate::registry::{make_tool, with_input_schema, Registry};
 
 /// Register every code tool with the registry.
 pub fn register_tools(reg: &mut Registry) {
-    reg.register_tool(make_tool(
-        "code.scaffold",
-        "code",
-        "Create a new project from a template with directory structure, config files, and README",
-        vec!["fs.write", "code.gen"],
-        "medium",
-        false,
-        true,
-        15000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "code.scaffold",
+            "code",
+            "Create a new project from a template with directory structure, config files, and README",
+            vec!["fs.write", "code.gen"],
+            "medium",
+            false,
+            true,
+            15000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string" },
+                "project_type": {
+                    "type": "string",
+                    "enum": ["rust", "python", "node", "generic"],
+                    "default": "generic"
+                },
+                "path": { "type": "string", "description": "Directory to create the project in" },
+                "description": { "type": "string" }
+            },
+            "required": ["name", "path"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "code.generate",
-        "code",
-        "Generate source code files based on a description, writing the result to a file",
-        vec!["code.gen"],
-        "medium",
-        false,
-        true,
-        30000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "code.generate",
+            "code",
+            "Generate source code files based on a description, writing the result to a file",
+            vec!["code.gen"],
+            "medium",
+            false,
+            true,
+            30000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "file_path": {
+                    "type": "string",
+                    "description": "Where to write the generated file"
+                },
+                "description": { "type": "string", "description": "What the code should do" },
+                "language": {
+                    "type": "string",
+                    "description": "Such as \"rust\", \"python\" or \"javascript\""
+                },
+                "create_dirs": { "type": "boolean", "default": true }
+            },
+            "required": ["file_path", "description"]
+        }),
     ));
 }
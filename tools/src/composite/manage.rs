@@ -0,0 +1,113 @@
+//! Composite management — define, list and delete composite tools
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use serde_json::json;
+use tracing::info;
+
+use super::{load_all, CompositeTool, COMPOSITE_DIR};
+use crate::registry::Registry;
+
+/// Input for composite.delete
+#[derive(Debug, Deserialize)]
+struct DeleteInput {
+    name: String,
+}
+
+/// Definition file of a composite
+fn definition_path(dir: &Path, name: &str) -> PathBuf {
+    let short = name.strip_prefix("composite.").unwrap_or(name);
+    dir.join(format!("{short}.json"))
+}
+
+/// Parse a composite definition, accepting names without the namespace
+fn parse(input: &[u8]) -> Result<CompositeTool> {
+    let mut composite: CompositeTool =
+        serde_json::from_slice(input).context("Invalid composite definition")?;
+    if !composite.name.starts_with("composite.") {
+        composite.name = format!("composite.{}", composite.name);
+    }
+    composite.validate()?;
+    Ok(composite)
+}
+
+/// Execute composite.define — validate the definition and store it
+pub fn execute_define(input: &[u8]) -> Result<Vec<u8>> {
+    let composite = parse(input)?;
+    let dir = Path::new(COMPOSITE_DIR);
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create composite directory: {COMPOSITE_DIR}"))?;
+    let path = definition_path(dir, &composite.name);
+    let replaced = path.exists();
+    std::fs::write(&path, serde_json::to_vec_pretty(&composite)?)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    info!(
+        "Composite {} defined with {} steps",
+        composite.name,
+        composite.steps.len()
+    );
+
+    let output = json!({
+        "name": composite.name,
+        "steps": composite.steps.len(),
+        "replaced": replaced,
+        "path": path.to_string_lossy(),
+    });
+    Ok(serde_json::to_vec(&output)?)
+}
+
+/// Register a composite just stored by composite.define. A definition
+/// whose steps cannot run — unknown tools, plugins, other composites — is
+/// removed again and the error returned.
+pub fn register_defined(reg: &mut Registry, input: &[u8]) -> Result<()> {
+    let composite = parse(input)?;
+    match composite.definition(reg) {
+        Ok(tool) => {
+            reg.register_composite(tool, composite);
+            Ok(())
+        }
+        Err(e) => {
+            let _ =
+                std::fs::remove_file(definition_path(Path::new(COMPOSITE_DIR), &composite.name));
+            Err(e)
+        }
+    }
+}
+
+/// Execute composite.list — every stored composite definition
+pub fn execute_list(_input: &[u8]) -> Result<Vec<u8>> {
+    let composites = load_all(Path::new(COMPOSITE_DIR));
+    let output = json!({
+        "count": composites.len(),
+        "composites": composites,
+    });
+    serde_json::to_vec(&output).context("Failed to serialize composite.list output")
+}
+
+/// Execute composite.delete — remove a composite's definition
+pub fn execute_delete(input: &[u8]) -> Result<Vec<u8>> {
+    let input: DeleteInput =
+        serde_json::from_slice(input).context("Invalid JSON input for composite.delete")?;
+    let path = definition_path(Path::new(COMPOSITE_DIR), &input.name);
+    if !path.exists() {
+        bail!("Composite not found: {}", input.name);
+    }
+    std::fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+    info!("Composite {} deleted", input.name);
+
+    Ok(serde_json::to_vec(&json!({ "deleted": input.name }))?)
+}
+
+/// Deregister a composite removed by composite.delete
+pub fn deregister_deleted(reg: &mut Registry, input: &[u8]) {
+    if let Ok(input) = serde_json::from_slice::<DeleteInput>(input) {
+        let name = if input.name.starts_with("composite.") {
+            input.name
+        } else {
+            format!("composite.{}", input.name)
+        };
+        reg.deregister_tool(&name);
+    }
+}
@@ -0,0 +1,771 @@
+//! Composite Tools — named procedures built from existing tools
+//!
+//! A composite tool is a parameterized DAG of calls to registered tools.
+//! Each step names a tool and an input; string values in the input may
+//! reference the composite's parameters (`{{params.path}}`) or the output of
+//! an earlier step (`{{check.output.status}}`). A step runs after the steps
+//! in its `after` list — by default the step before it — and only when its
+//! `when` condition holds (`check.success`, `!check.success`,
+//! `check.output.state == "active"`).
+//!
+//! Definitions are JSON files in COMPOSITE_DIR, registered as tools in the
+//! "composite" namespace. The executor runs a composite as one call: every
+//! step passes its own tool's permission checks, a failed step rolls back
+//! the reversible steps before it, and the composite gets a single audit
+//! entry, so common procedures stop being re-planned on every task.
+
+pub mod manage;
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use tracing::{info, warn};
+
+use crate::capabilities::RiskLevel;
+use crate::proto::tools::ToolDefinition;
+use crate::registry::{make_tool, with_input_schema, Registry};
+
+/// Directory where composite tool definitions are stored
+pub const COMPOSITE_DIR: &str = "/var/lib/aios/composites";
+
+/// Names taken by the composite management tools
+const RESERVED: &[&str] = &["define", "list", "delete"];
+
+/// Most steps a composite may have
+const MAX_STEPS: usize = 32;
+
+/// A composite tool definition
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompositeTool {
+    /// Full tool name, "composite.<name>"
+    pub name: String,
+    pub description: String,
+    #[serde(default)]
+    pub params: Vec<CompositeParam>,
+    pub steps: Vec<CompositeStep>,
+    /// Run the steps as one critical section, which cancellation and
+    /// shutdown wait for
+    #[serde(default)]
+    pub critical: bool,
+    /// Registered as an experimental tool; also set when a step's tool is
+    #[serde(default)]
+    pub experimental: bool,
+}
+
+/// A parameter callers pass in the composite's input object
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompositeParam {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub required: bool,
+    /// Value used when the caller omits the parameter
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<Value>,
+}
+
+/// One tool call within a composite
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompositeStep {
+    pub id: String,
+    pub tool: String,
+    /// Tool input; strings may contain `{{ref}}` templates
+    #[serde(default)]
+    pub input: Value,
+    /// Steps that must run first (default: the previous step)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<Vec<String>>,
+    /// Condition under which the step runs; skipped otherwise
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when: Option<String>,
+}
+
+impl CompositeTool {
+    /// Steps this step depends on
+    fn dependencies(&self, index: usize) -> Vec<&str> {
+        match &self.steps[index].after {
+            Some(after) => after.iter().map(String::as_str).collect(),
+            None if index > 0 => vec![self.steps[index - 1].id.as_str()],
+            None => Vec::new(),
+        }
+    }
+
+    /// Check the definition's structure: names, step references and
+    /// templates, and that the steps form a DAG
+    pub fn validate(&self) -> Result<()> {
+        let short = self.name.strip_prefix("composite.").unwrap_or("");
+        if short.is_empty()
+            || RESERVED.contains(&short)
+            || !short
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            bail!(
+                "Composite name must be 'composite.<name>' with alphanumeric, '_' or '-' characters, got '{}'",
+                self.name
+            );
+        }
+        if self.steps.is_empty() {
+            bail!("Composite {} has no steps", self.name);
+        }
+        if self.steps.len() > MAX_STEPS {
+            bail!(
+                "Composite {} has {} steps, at most {MAX_STEPS} are allowed",
+                self.name,
+                self.steps.len()
+            );
+        }
+
+        let mut params = HashSet::new();
+        for param in &self.params {
+            if !params.insert(param.name.as_str()) {
+                bail!("Duplicate parameter '{}'", param.name);
+            }
+        }
+        let mut ids = HashSet::new();
+        for step in &self.steps {
+            if step.id.is_empty() || step.id == "params" || step.id.contains('.') {
+                bail!("Invalid step id '{}'", step.id);
+            }
+            if !ids.insert(step.id.as_str()) {
+                bail!("Duplicate step id '{}'", step.id);
+            }
+        }
+        for (i, step) in self.steps.iter().enumerate() {
+            for dep in self.dependencies(i) {
+                if !ids.contains(dep) {
+                    bail!("Step '{}' runs after unknown step '{dep}'", step.id);
+                }
+            }
+        }
+
+        let order = self.order()?;
+        for &i in &order {
+            let step = &self.steps[i];
+            let ancestors = self.ancestors(i);
+            let mut refs = Vec::new();
+            collect_refs(&step.input, &mut refs);
+            if let Some(when) = &step.when {
+                refs.push(parse_condition(when)?.reference);
+            }
+            for reference in refs {
+                let root = reference.split('.').next().unwrap_or_default();
+                let known = if root == "params" {
+                    reference
+                        .split('.')
+                        .nth(1)
+                        .is_some_and(|p| params.contains(p))
+                } else {
+                    ancestors.contains(root)
+                };
+                if !known {
+                    bail!(
+                        "Step '{}' references '{reference}', which is neither a parameter nor an earlier step",
+                        step.id
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Step indices in execution order: dependencies first, otherwise in
+    /// definition order. Fails on a cycle.
+    pub fn order(&self) -> Result<Vec<usize>> {
+        let index: HashMap<&str, usize> = self
+            .steps
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (s.id.as_str(), i))
+            .collect();
+        let mut done = vec![false; self.steps.len()];
+        let mut order = Vec::with_capacity(self.steps.len());
+        while order.len() < self.steps.len() {
+            let next = (0..self.steps.len()).find(|&i| {
+                !done[i]
+                    && self
+                        .dependencies(i)
+                        .iter()
+                        .all(|d| index.get(d).is_some_and(|&j| done[j]))
+            });
+            let Some(i) = next else {
+                bail!("Steps of {} form a cycle", self.name);
+            };
+            done[i] = true;
+            order.push(i);
+        }
+        Ok(order)
+    }
+
+    /// Ids of every step that runs before step `index` is allowed to
+    fn ancestors(&self, index: usize) -> HashSet<&str> {
+        let mut ancestors = HashSet::new();
+        let mut pending = self.dependencies(index);
+        while let Some(id) = pending.pop() {
+            if ancestors.insert(id) {
+                if let Some(i) = self.steps.iter().position(|s| s.id == id) {
+                    pending.extend(self.dependencies(i));
+                }
+            }
+        }
+        ancestors
+    }
+
+    /// The tool definition the composite is registered under, derived from
+    /// its steps' tools. Fails when a step's tool is missing, is itself a
+    /// composite, or is a plugin (plugins run outside the executor).
+    pub fn definition(&self, registry: &Registry) -> Result<ToolDefinition> {
+        let mut capabilities: Vec<String> = Vec::new();
+        let mut risk = RiskLevel::Low;
+        let mut idempotent = true;
+        let mut reversible = true;
+        let mut timeout_ms = 0;
+        let mut experimental = self.experimental;
+        for step in &self.steps {
+            let tool = registry
+                .get_tool(&step.tool)
+                .with_context(|| format!("Step '{}' uses unknown tool {}", step.id, step.tool))?;
+            if matches!(tool.namespace.as_str(), "composite" | "plugin") {
+                bail!(
+                    "Step '{}' uses {}: composites may only call built-in tools",
+                    step.id,
+                    step.tool
+                );
+            }
+            for cap in tool.required_capabilities {
+                if !capabilities.contains(&cap) {
+                    capabilities.push(cap);
+                }
+            }
+            let step_risk = parse_risk(&tool.risk_level);
+            if step_risk > risk {
+                risk = step_risk;
+            }
+            idempotent &= tool.idempotent;
+            reversible &= tool.reversible;
+            timeout_ms += tool.timeout_ms;
+            experimental |= tool.experimental;
+        }
+
+        let mut tool = make_tool(
+            &self.name,
+            "composite",
+            &self.description,
+            capabilities.iter().map(String::as_str).collect(),
+            risk_name(&risk),
+            idempotent,
+            reversible,
+            timeout_ms,
+        );
+        tool.input_schema = self.input_schema().to_string().into_bytes();
+        tool.critical_section = self.critical;
+        tool.experimental = experimental;
+        Ok(tool)
+    }
+
+    /// JSON schema of the composite's input object
+    fn input_schema(&self) -> Value {
+        let properties: Map<String, Value> = self
+            .params
+            .iter()
+            .map(|p| {
+                let mut schema = json!({ "description": p.description });
+                if let Some(default) = &p.default {
+                    schema["default"] = default.clone();
+                }
+                (p.name.clone(), schema)
+            })
+            .collect();
+        let required: Vec<&str> = self
+            .params
+            .iter()
+            .filter(|p| p.required)
+            .map(|p| p.name.as_str())
+            .collect();
+        json!({ "type": "object", "properties": properties, "required": required })
+    }
+
+    /// Parameter values from a call's input: defaults filled in, required
+    /// parameters present and unknown ones rejected
+    pub fn bind_params(&self, input_json: &[u8]) -> Result<Value> {
+        let mut input: Map<String, Value> = if input_json.is_empty() {
+            Map::new()
+        } else {
+            serde_json::from_slice(input_json).context("Composite input must be a JSON object")?
+        };
+        if let Some(unknown) = input
+            .keys()
+            .find(|k| !self.params.iter().any(|p| &p.name == *k))
+        {
+            bail!("Unknown parameter '{unknown}' for {}", self.name);
+        }
+        for param in &self.params {
+            if input.contains_key(&param.name) {
+                continue;
+            }
+            match &param.default {
+                Some(default) => {
+                    input.insert(param.name.clone(), default.clone());
+                }
+                None if param.required => {
+                    bail!("Missing required parameter '{}'", param.name)
+                }
+                None => {}
+            }
+        }
+        Ok(Value::Object(input))
+    }
+}
+
+/// Values visible to templates and conditions while a composite runs:
+/// `params` and, per finished step, `{success, skipped, output}`
+pub struct RunContext {
+    root: Value,
+}
+
+impl RunContext {
+    pub fn new(params: Value) -> Self {
+        Self {
+            root: json!({ "params": params }),
+        }
+    }
+
+    /// Record a step's result
+    pub fn record(&mut self, step: &str, success: bool, skipped: bool, output: Value) {
+        self.root[step] = json!({ "success": success, "skipped": skipped, "output": output });
+    }
+
+    /// Value of a dotted reference, null when absent
+    fn resolve(&self, reference: &str) -> Value {
+        let pointer = format!("/{}", reference.replace('.', "/"));
+        self.root.pointer(&pointer).cloned().unwrap_or(Value::Null)
+    }
+
+    /// Whether a step's `when` condition holds
+    pub fn evaluate(&self, condition: &str) -> Result<bool> {
+        let condition = parse_condition(condition)?;
+        let value = self.resolve(&condition.reference);
+        Ok(match condition.comparison {
+            None => truthy(&value) != condition.negated,
+            Some((equal, literal)) => (value == literal) == equal,
+        })
+    }
+
+    /// A step's input with every template replaced. A string that is one
+    /// template becomes the referenced value itself; templates inside
+    /// longer strings are replaced by the value's text.
+    pub fn render(&self, input: &Value) -> Value {
+        match input {
+            Value::String(s) => {
+                let trimmed = s.trim();
+                if let Some(reference) = trimmed
+                    .strip_prefix("{{")
+                    .and_then(|t| t.strip_suffix("}}"))
+                    .filter(|r| !r.contains("{{"))
+                {
+                    return self.resolve(reference.trim());
+                }
+                let mut rendered = String::new();
+                let mut rest = s.as_str();
+                while let Some(start) = rest.find("{{") {
+                    let Some(end) = rest[start..].find("}}") else {
+                        break;
+                    };
+                    rendered.push_str(&rest[..start]);
+                    match self.resolve(rest[start + 2..start + end].trim()) {
+                        Value::String(text) => rendered.push_str(&text),
+                        Value::Null => {}
+                        value => rendered.push_str(&value.to_string()),
+                    }
+                    rest = &rest[start + end + 2..];
+                }
+                rendered.push_str(rest);
+                Value::String(rendered)
+            }
+            Value::Array(items) => Value::Array(items.iter().map(|v| self.render(v)).collect()),
+            Value::Object(fields) => Value::Object(
+                fields
+                    .iter()
+                    .map(|(k, v)| (k.clone(), self.render(v)))
+                    .collect(),
+            ),
+            other => other.clone(),
+        }
+    }
+}
+
+/// A parsed `when` condition
+struct Condition {
+    reference: String,
+    negated: bool,
+    /// `(true, v)` for `== v`, `(false, v)` for `!= v`
+    comparison: Option<(bool, Value)>,
+}
+
+fn parse_condition(condition: &str) -> Result<Condition> {
+    let condition = condition.trim();
+    let (reference, comparison) = if let Some((r, v)) = condition.split_once("!=") {
+        (r, Some((false, v)))
+    } else if let Some((r, v)) = condition.split_once("==") {
+        (r, Some((true, v)))
+    } else {
+        (condition, None)
+    };
+    let reference = reference.trim();
+    let (reference, negated) = match reference.strip_prefix('!') {
+        Some(r) if comparison.is_none() => (r.trim(), true),
+        _ => (reference, false),
+    };
+    if reference.is_empty()
+        || !reference
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+    {
+        bail!("Invalid condition '{condition}'");
+    }
+    // Literals are JSON; anything else is a bare string
+    let comparison = comparison.map(|(equal, literal)| {
+        let literal = literal.trim();
+        let value = serde_json::from_str(literal).unwrap_or_else(|_| json!(literal));
+        (equal, value)
+    });
+    Ok(Condition {
+        reference: reference.to_string(),
+        negated,
+        comparison,
+    })
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|n| n != 0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(a) => !a.is_empty(),
+        Value::Object(o) => !o.is_empty(),
+    }
+}
+
+/// References in every `{{...}}` template of a step input
+fn collect_refs(input: &Value, refs: &mut Vec<String>) {
+    match input {
+        Value::String(s) => {
+            let mut rest = s.as_str();
+            while let Some(start) = rest.find("{{") {
+                let Some(end) = rest[start..].find("}}") else {
+                    break;
+                };
+                refs.push(rest[start + 2..start + end].trim().to_string());
+                rest = &rest[start + end + 2..];
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|v| collect_refs(v, refs)),
+        Value::Object(fields) => fields.values().for_each(|v| collect_refs(v, refs)),
+        _ => {}
+    }
+}
+
+fn parse_risk(risk: &str) -> RiskLevel {
+    match risk {
+        "low" => RiskLevel::Low,
+        "medium" => RiskLevel::Medium,
+        "high" => RiskLevel::High,
+        _ => RiskLevel::Critical,
+    }
+}
+
+fn risk_name(risk: &RiskLevel) -> &'static str {
+    match risk {
+        RiskLevel::Low => "low",
+        RiskLevel::Medium => "medium",
+        RiskLevel::High => "high",
+        RiskLevel::Critical => "critical",
+    }
+}
+
+/// Register the meta-tools for composite management
+pub fn register_tools(reg: &mut Registry) {
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "composite.define",
+            "composite",
+            "Define or replace a composite tool: a named, parameterized sequence of existing tool calls with {{params.x}} / {{step.output.field}} templates and optional 'when' conditions",
+            vec!["composite_manage", "fs_write"],
+            "high",
+            true,
+            false,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string" },
+                "description": { "type": "string" },
+                "params": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "name": { "type": "string" },
+                            "description": { "type": "string" },
+                            "required": { "type": "boolean" },
+                            "default": {}
+                        },
+                        "required": ["name"]
+                    }
+                },
+                "steps": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "id": { "type": "string" },
+                            "tool": { "type": "string" },
+                            "input": { "description": "Strings may contain {{ref}} templates" },
+                            "after": {
+                                "type": "array",
+                                "items": { "type": "string" },
+                                "description": "Steps that must run first; default the previous step"
+                            },
+                            "when": {
+                                "type": "string",
+                                "description": "Condition under which the step runs"
+                            }
+                        },
+                        "required": ["id", "tool"]
+                    }
+                },
+                "critical": {
+                    "type": "boolean",
+                    "description": "Run the steps as one critical section"
+                },
+                "experimental": { "type": "boolean" }
+            },
+            "required": ["name", "description", "steps"]
+        }),
+    ));
+
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "composite.list",
+            "composite",
+            "List all composite tools with their parameters and steps",
+            vec!["composite_read"],
+            "low",
+            true,
+            false,
+            5000,
+        ),
+        json!({ "type": "object" }),
+    ));
+
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "composite.delete",
+            "composite",
+            "Delete a composite tool by name",
+            vec!["composite_manage"],
+            "high",
+            false,
+            false,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": { "name": { "type": "string" } },
+            "required": ["name"]
+        }),
+    ));
+}
+
+/// Load every composite in COMPOSITE_DIR into the registry. Called at
+/// startup, after composite.define or composite.delete, and on every
+/// reload, once the tools composites call are registered.
+pub fn scan_and_register_composites(reg: &mut Registry) {
+    register_composites_in(Path::new(COMPOSITE_DIR), reg);
+}
+
+/// Read every *.json composite definition in `dir`
+pub fn load_all(dir: &Path) -> Vec<CompositeTool> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut composites = Vec::new();
+    for path in entries.flatten().map(|e| e.path()) {
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        match std::fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|c| serde_json::from_str::<CompositeTool>(&c).map_err(Into::into))
+        {
+            Ok(composite) => composites.push(composite),
+            Err(e) => warn!("Failed to load composite {}: {e}", path.display()),
+        }
+    }
+    composites.sort_by(|a, b| a.name.cmp(&b.name));
+    composites
+}
+
+/// Register the composites defined in `dir`, skipping invalid ones
+fn register_composites_in(dir: &Path, reg: &mut Registry) {
+    let mut count = 0;
+    for composite in load_all(dir) {
+        let registered = composite.validate().and_then(|_| composite.definition(reg));
+        match registered {
+            Ok(tool) => {
+                reg.register_composite(tool, composite);
+                count += 1;
+            }
+            Err(e) => warn!("Skipping composite {}: {e}", composite.name),
+        }
+    }
+    if count > 0 {
+        info!("Loaded {count} composite tools from {}", dir.display());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn composite(steps: Value) -> CompositeTool {
+        serde_json::from_value(json!({
+            "name": "composite.restart_service",
+            "description": "Restart a service and confirm it is running",
+            "params": [
+                {"name": "service", "required": true},
+                {"name": "lines", "default": 20}
+            ],
+            "steps": steps,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_validate_and_order() {
+        let c = composite(json!([
+            {"id": "restart", "tool": "service.restart", "input": {"name": "{{params.service}}"}},
+            {"id": "status", "tool": "service.status", "input": {"name": "{{params.service}}"}},
+            {"id": "logs", "tool": "monitor.logs", "after": ["status"],
+             "when": "status.output.active != true",
+             "input": {"unit": "{{params.service}}", "lines": "{{params.lines}}"}},
+        ]));
+        c.validate().unwrap();
+        assert_eq!(c.order().unwrap(), vec![0, 1, 2]);
+
+        // A DAG runs dependencies first
+        let c = composite(json!([
+            {"id": "b", "tool": "service.status", "after": ["a"]},
+            {"id": "a", "tool": "service.restart", "after": []},
+        ]));
+        assert_eq!(c.order().unwrap(), vec![1, 0]);
+
+        let cycle = composite(json!([
+            {"id": "a", "tool": "service.status", "after": ["b"]},
+            {"id": "b", "tool": "service.status", "after": ["a"]},
+        ]));
+        assert!(cycle.validate().is_err());
+
+        // Steps may only read parameters and steps that ran before them
+        let forward = composite(json!([
+            {"id": "a", "tool": "service.status", "after": [], "input": {"name": "{{b.output.name}}"}},
+            {"id": "b", "tool": "service.status", "after": []},
+        ]));
+        assert!(forward.validate().is_err());
+        let unknown = composite(json!([
+            {"id": "a", "tool": "service.status", "input": {"name": "{{params.svc}}"}},
+        ]));
+        assert!(unknown.validate().is_err());
+    }
+
+    #[test]
+    fn test_bind_render_and_evaluate() {
+        let c = composite(json!([{"id": "status", "tool": "service.status"}]));
+        assert!(c.bind_params(b"{}").is_err());
+        assert!(c
+            .bind_params(br#"{"service": "nginx", "user": "x"}"#)
+            .is_err());
+        let params = c.bind_params(br#"{"service": "nginx"}"#).unwrap();
+        assert_eq!(params["lines"], 20);
+
+        let mut ctx = RunContext::new(params);
+        ctx.record("status", true, false, json!({"active": false, "pid": 42}));
+        let input = json!({
+            "unit": "{{params.service}}",
+            "lines": "{{ params.lines }}",
+            "message": "{{params.service}} pid {{status.output.pid}}",
+        });
+        assert_eq!(
+            ctx.render(&input),
+            json!({"unit": "nginx", "lines": 20, "message": "nginx pid 42"})
+        );
+
+        assert!(ctx.evaluate("status.success").unwrap());
+        assert!(!ctx.evaluate("!status.success").unwrap());
+        assert!(ctx.evaluate("status.output.active != true").unwrap());
+        assert!(ctx.evaluate("params.service == nginx").unwrap());
+        assert!(!ctx.evaluate("missing.output").unwrap());
+        assert!(ctx.evaluate("a b").is_err());
+    }
+
+    #[test]
+    fn test_register_composites_in() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut reg = Registry::new();
+        reg.register_tool(make_tool(
+            "service.status",
+            "service",
+            "Service status",
+            vec!["service_read"],
+            "low",
+            true,
+            false,
+            5000,
+        ));
+        reg.register_tool(make_tool(
+            "service.restart",
+            "service",
+            "Restart a service",
+            vec!["service_manage"],
+            "medium",
+            false,
+            false,
+            30000,
+        ));
+        let good = composite(json!([
+            {"id": "restart", "tool": "service.restart", "input": {"name": "{{params.service}}"}},
+            {"id": "status", "tool": "service.status", "input": {"name": "{{params.service}}"}},
+        ]));
+        std::fs::write(
+            dir.path().join("restart_service.json"),
+            serde_json::to_vec(&good).unwrap(),
+        )
+        .unwrap();
+        let mut bad = composite(json!([{"id": "a", "tool": "service.missing"}]));
+        bad.name = "composite.bad".into();
+        std::fs::write(
+            dir.path().join("bad.json"),
+            serde_json::to_vec(&bad).unwrap(),
+        )
+        .unwrap();
+
+        register_composites_in(dir.path(), &mut reg);
+        assert!(reg.get_composite("composite.bad").is_none());
+        assert_eq!(reg.get_composite("composite.restart_service"), Some(&good));
+        let tool = reg.get_tool("composite.restart_service").unwrap();
+        assert_eq!(tool.namespace, "composite");
+        assert_eq!(tool.risk_level, "medium");
+        assert_eq!(tool.timeout_ms, 35000);
+        assert_eq!(
+            tool.required_capabilities,
+            vec!["service_manage", "service_read"]
+        );
+        assert!(!tool.idempotent);
+    }
+}
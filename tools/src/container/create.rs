@@ -2,7 +2,8 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+
+use crate::sandbox::command;
 
 #[derive(Deserialize)]
 struct CreateInput {
@@ -28,7 +29,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
     let req: CreateInput =
         serde_json::from_slice(input).context("Invalid container.create input")?;
 
-    let mut cmd = Command::new("podman");
+    let mut cmd = command("podman");
     cmd.arg("create");
 
     if !req.name.is_empty() {
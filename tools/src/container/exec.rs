@@ -2,7 +2,8 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+
+use crate::sandbox::command;
 
 #[derive(Deserialize)]
 struct ExecInput {
@@ -21,7 +22,7 @@ struct ExecOutput {
 pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
     let req: ExecInput = serde_json::from_slice(input).context("Invalid container.exec input")?;
 
-    let mut cmd = Command::new("podman");
+    let mut cmd = command("podman");
     cmd.arg("exec").arg(&req.name);
     for arg in &req.command {
         cmd.arg(arg);
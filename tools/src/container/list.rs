@@ -2,7 +2,8 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+
+use crate::sandbox::command;
 
 #[derive(Deserialize)]
 struct ListInput {
@@ -28,7 +29,7 @@ struct ListOutput {
 pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
     let req: ListInput = serde_json::from_slice(input).context("Invalid container.list input")?;
 
-    let mut cmd = Command::new("podman");
+    let mut cmd = command("podman");
     cmd.args(["ps", "--format", "json"]);
     if req.all {
         cmd.arg("--all");
@@ -2,7 +2,8 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+
+use crate::sandbox::command;
 
 #[derive(Deserialize)]
 struct LogsInput {
@@ -26,7 +27,7 @@ struct LogsOutput {
 pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
     let req: LogsInput = serde_json::from_slice(input).context("Invalid container.logs input")?;
 
-    let output = Command::new("podman")
+    let output = command("podman")
         .args(["logs", "--tail", &req.tail.to_string(), &req.name])
         .output()
         .context("Failed to run podman logs")?;
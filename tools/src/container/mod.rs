@@ -7,73 +7,145 @@ pub mod logs;
 pub mod start;
 pub mod stop;
 
-use crate::registry::{make_tool, Registry};
+use serde_json::json;
+
+use crate::registry::{make_tool, with_input_schema, Registry};
 
 /// Register every container tool with the registry.
 pub fn register_tools(reg: &mut Registry) {
-    reg.register_tool(make_tool(
-        "container.create",
-        "container",
-        "Create a new Podman container from an image",
-        vec!["container.manage"],
-        "medium",
-        false,
-        true,
-        30000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "container.create",
+            "container",
+            "Create a new Podman container from an image",
+            vec!["container.manage"],
+            "medium",
+            false,
+            true,
+            30000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "image": { "type": "string" },
+                "name": { "type": "string" },
+                "ports": {
+                    "type": "array",
+                    "items": { "type": "string", "description": "\"host:container\"" }
+                },
+                "env": { "type": "object", "additionalProperties": { "type": "string" } },
+                "volumes": {
+                    "type": "array",
+                    "items": { "type": "string", "description": "\"host:container\"" }
+                }
+            },
+            "required": ["image"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "container.start",
-        "container",
-        "Start a stopped container",
-        vec!["container.manage"],
-        "low",
-        true,
-        true,
-        10000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "container.start",
+            "container",
+            "Start a stopped container",
+            vec!["container.manage"],
+            "low",
+            true,
+            true,
+            10000,
+        ),
+        json!({
+            "type": "object",
+            "properties": { "name": { "type": "string", "description": "Container name or id" } },
+            "required": ["name"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "container.stop",
-        "container",
-        "Stop a running container",
-        vec!["container.manage"],
-        "low",
-        true,
-        true,
-        15000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "container.stop",
+            "container",
+            "Stop a running container; with \"remove\": true, also delete it",
+            vec!["container.manage"],
+            "low",
+            true,
+            true,
+            15000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string", "description": "Container name or id" },
+                "timeout": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "description": "Seconds before the container is killed",
+                    "default": 10
+                },
+                "remove": { "type": "boolean", "description": "Also delete the container" }
+            },
+            "required": ["name"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "container.list",
-        "container",
-        "List all containers with status and port info",
-        vec!["container.read"],
-        "low",
-        true,
-        false,
-        5000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "container.list",
+            "container",
+            "List all containers with status and port info",
+            vec!["container.read"],
+            "low",
+            true,
+            false,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "all": { "type": "boolean", "description": "Include stopped containers" }
+            }
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "container.exec",
-        "container",
-        "Execute a command in a running container",
-        vec!["container.manage"],
-        "high",
-        false,
-        false,
-        30000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "container.exec",
+            "container",
+            "Execute a command in a running container",
+            vec!["container.manage"],
+            "high",
+            false,
+            false,
+            30000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string", "description": "Container name or id" },
+                "command": { "type": "array", "items": { "type": "string" } }
+            },
+            "required": ["name", "command"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "container.logs",
-        "container",
-        "Get container logs",
-        vec!["container.read"],
-        "low",
-        true,
-        false,
-        10000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "container.logs",
+            "container",
+            "Get container logs",
+            vec!["container.read"],
+            "low",
+            true,
+            false,
+            10000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string", "description": "Container name or id" },
+                "tail": { "type": "integer", "minimum": 1, "default": 100 }
+            },
+            "required": ["name"]
+        }),
     ));
 }
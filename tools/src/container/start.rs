@@ -2,7 +2,8 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+
+use crate::sandbox::command;
 
 #[derive(Deserialize)]
 struct StartInput {
@@ -18,7 +19,7 @@ struct StartOutput {
 pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
     let req: StartInput = serde_json::from_slice(input).context("Invalid container.start input")?;
 
-    let output = Command::new("podman")
+    let output = command("podman")
         .args(["start", &req.name])
         .output()
         .context("Failed to run podman start")?;
@@ -2,13 +2,17 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+
+use crate::sandbox::command;
 
 #[derive(Deserialize)]
 struct StopInput {
     name: String,
     #[serde(default = "default_timeout")]
     timeout: u32,
+    /// Also delete the container once stopped
+    #[serde(default)]
+    remove: bool,
 }
 
 fn default_timeout() -> u32 {
@@ -24,7 +28,7 @@ struct StopOutput {
 pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
     let req: StopInput = serde_json::from_slice(input).context("Invalid container.stop input")?;
 
-    let output = Command::new("podman")
+    let output = command("podman")
         .args(["stop", "--time", &req.timeout.to_string(), &req.name])
         .output()
         .context("Failed to run podman stop")?;
@@ -34,6 +38,17 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
         anyhow::bail!("podman stop failed: {err}");
     }
 
+    if req.remove {
+        let output = command("podman")
+            .args(["rm", &req.name])
+            .output()
+            .context("Failed to run podman rm")?;
+        if !output.status.success() {
+            let err = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!("podman rm failed: {err}");
+        }
+    }
+
     let result = StopOutput {
         success: true,
         name: req.name,
@@ -0,0 +1,81 @@
+//! data.convert — Convert documents between JSON, YAML, and TOML
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+
+use super::Format;
+
+#[derive(Serialize)]
+struct Output {
+    from: String,
+    to: String,
+    /// Converted text; omitted when written to `output_path`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output_path: Option<String>,
+}
+
+pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
+    let input: Value = serde_json::from_slice(input).context("data.convert: invalid JSON input")?;
+    let to = input
+        .get("to")
+        .and_then(|t| t.as_str())
+        .ok_or_else(|| anyhow::anyhow!("data.convert: missing required field 'to'"))?;
+    let to = Format::parse(to).context("data.convert: invalid target format")?;
+    let output_path = input
+        .get("output_path")
+        .and_then(|p| p.as_str())
+        .map(String::from);
+    let doc = super::load_document(&input).context("data.convert: failed to load document")?;
+
+    let text = to
+        .serialize(&doc.value)
+        .with_context(|| format!("data.convert: failed to convert to {}", to.as_str()))?;
+
+    if let Some(path) = &output_path {
+        crate::fs::write_atomically(path, text.as_bytes())
+            .context("data.convert: failed to write output")?;
+    }
+
+    let output = Output {
+        from: doc.format.as_str().to_string(),
+        to: to.as_str().to_string(),
+        output: if output_path.is_none() {
+            Some(text)
+        } else {
+            None
+        },
+        output_path,
+    };
+    serde_json::to_vec(&output).context("data.convert: failed to serialise output")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_convert_yaml_to_toml() {
+        let input = json!({
+            "document": "name: web\nports:\n  - 80\n  - 443\n",
+            "format": "yaml",
+            "to": "toml",
+        });
+        let out: Value =
+            serde_json::from_slice(&execute(&serde_json::to_vec(&input).unwrap()).unwrap())
+                .unwrap();
+        assert_eq!(out["from"], "yaml");
+        let table: toml::Table = out["output"].as_str().unwrap().parse().unwrap();
+        assert_eq!(table["name"].as_str(), Some("web"));
+        assert_eq!(table["ports"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_convert_null_to_toml_fails() {
+        let input = json!({"document": {"a": null}, "to": "toml"});
+        assert!(execute(&serde_json::to_vec(&input).unwrap()).is_err());
+    }
+}
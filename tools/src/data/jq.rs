@@ -0,0 +1,59 @@
+//! data.jq — JSONPath queries over JSON, YAML, or TOML documents
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+use serde_json_path::JsonPath;
+
+#[derive(Serialize)]
+struct Output {
+    query: String,
+    count: usize,
+    matches: Vec<Value>,
+}
+
+pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
+    let input: Value = serde_json::from_slice(input).context("data.jq: invalid JSON input")?;
+    let query = input
+        .get("query")
+        .and_then(|q| q.as_str())
+        .ok_or_else(|| anyhow::anyhow!("data.jq: missing required field 'query'"))?;
+    let path = JsonPath::parse(query)
+        .map_err(|e| anyhow::anyhow!("data.jq: invalid JSONPath '{query}': {e}"))?;
+    let doc = super::load_document(&input).context("data.jq: failed to load document")?;
+
+    let matches: Vec<Value> = path.query(&doc.value).all().into_iter().cloned().collect();
+    let output = Output {
+        query: query.to_string(),
+        count: matches.len(),
+        matches,
+    };
+    serde_json::to_vec(&output).context("data.jq: failed to serialise output")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_query_yaml_document() {
+        let input = json!({
+            "document": "servers:\n  - name: a\n    port: 80\n  - name: b\n    port: 443\n",
+            "format": "yaml",
+            "query": "$.servers[?@.port > 80].name",
+        });
+        let out: Value =
+            serde_json::from_slice(&execute(&serde_json::to_vec(&input).unwrap()).unwrap())
+                .unwrap();
+        assert_eq!(out["count"], 1);
+        assert_eq!(out["matches"], json!(["b"]));
+    }
+
+    #[test]
+    fn test_invalid_query() {
+        let input = json!({"document": {}, "query": "servers["});
+        let err = execute(&serde_json::to_vec(&input).unwrap()).unwrap_err();
+        assert!(err.to_string().contains("invalid JSONPath"));
+    }
+}
@@ -0,0 +1,128 @@
+//! data.merge_patch — Apply an RFC 7386 merge patch to a structured document
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Serialize)]
+struct Output {
+    changed: bool,
+    format: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    written: bool,
+    /// The patched document; omitted when it was written back to `path`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+}
+
+/// Apply `patch` to `target` per RFC 7386: objects merge recursively,
+/// `null` deletes a key, and anything else replaces the target.
+pub fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch_map) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(serde_json::Map::new());
+    }
+    let Value::Object(target_map) = target else {
+        return;
+    };
+    for (key, value) in patch_map {
+        if value.is_null() {
+            target_map.remove(key);
+        } else {
+            merge_patch(target_map.entry(key.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
+    let input: Value =
+        serde_json::from_slice(input).context("data.merge_patch: invalid JSON input")?;
+    let patch = input
+        .get("patch")
+        .ok_or_else(|| anyhow::anyhow!("data.merge_patch: missing required field 'patch'"))?;
+    let dry_run = input
+        .get("dry_run")
+        .and_then(|d| d.as_bool())
+        .unwrap_or(false);
+    let doc = super::load_document(&input).context("data.merge_patch: failed to load document")?;
+
+    let mut patched = doc.value.clone();
+    merge_patch(&mut patched, patch);
+    let changed = patched != doc.value;
+
+    let written = match &doc.path {
+        Some(path) if changed && !dry_run => {
+            let text = doc
+                .format
+                .serialize(&patched)
+                .context("data.merge_patch: failed to encode result")?;
+            crate::fs::write_atomically(path, text.as_bytes())
+                .context("data.merge_patch: failed to write result")?;
+            true
+        }
+        _ => false,
+    };
+
+    let output = Output {
+        changed,
+        format: doc.format.as_str().to_string(),
+        result: if doc.path.is_none() || dry_run {
+            Some(patched)
+        } else {
+            None
+        },
+        path: doc.path,
+        written,
+    };
+    serde_json::to_vec(&output).context("data.merge_patch: failed to serialise output")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_merge_patch_rfc7386() {
+        let mut target = json!({"a": "b", "c": {"d": "e", "f": "g"}, "list": [1, 2]});
+        merge_patch(
+            &mut target,
+            &json!({"a": "z", "c": {"f": null}, "list": [3], "new": {"x": 1}}),
+        );
+        assert_eq!(
+            target,
+            json!({"a": "z", "c": {"d": "e"}, "list": [3], "new": {"x": 1}})
+        );
+    }
+
+    #[test]
+    fn test_merge_patch_toml_file_in_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "[server]\nport = 80\nhost = \"0.0.0.0\"\n").unwrap();
+
+        let input = json!({"path": path, "patch": {"server": {"port": 8080}}});
+        let out: Value =
+            serde_json::from_slice(&execute(&serde_json::to_vec(&input).unwrap()).unwrap())
+                .unwrap();
+        assert_eq!(out["changed"], true);
+        assert_eq!(out["written"], true);
+        assert!(out.get("result").is_none());
+
+        let written: toml::Table = std::fs::read_to_string(&path).unwrap().parse().unwrap();
+        assert_eq!(written["server"]["port"].as_integer(), Some(8080));
+        assert_eq!(written["server"]["host"].as_str(), Some("0.0.0.0"));
+
+        // A no-op patch leaves the file untouched
+        let input = json!({"path": path, "patch": {"server": {"port": 8080}}});
+        let out: Value =
+            serde_json::from_slice(&execute(&serde_json::to_vec(&input).unwrap()).unwrap())
+                .unwrap();
+        assert_eq!(out["changed"], false);
+        assert_eq!(out["written"], false);
+    }
+}
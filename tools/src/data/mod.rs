@@ -0,0 +1,242 @@
+//! Structured data tools — JSONPath queries, merge patches, and format conversion
+//! across JSON, YAML, and TOML.
+//!
+//! Each submodule exposes `pub fn execute(input: &[u8]) -> Result<Vec<u8>>`.
+//! Documents come from a file (`path`) or inline (`document`), and are handled
+//! internally as `serde_json::Value` regardless of their on-disk format.
+
+pub mod convert;
+pub mod jq;
+pub mod merge_patch;
+
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+
+use crate::registry::{make_tool, with_input_schema, Registry};
+
+/// Serialization formats understood by the data tools
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "yaml" | "yml" => Ok(Self::Yaml),
+            "toml" => Ok(Self::Toml),
+            other => anyhow::bail!("unsupported format '{other}' (expected json, yaml, or toml)"),
+        }
+    }
+
+    /// Infer a format from a file extension
+    pub fn from_path(path: &str) -> Option<Self> {
+        let ext = std::path::Path::new(path).extension()?.to_str()?;
+        Self::parse(ext).ok()
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Yaml => "yaml",
+            Self::Toml => "toml",
+        }
+    }
+
+    pub fn deserialize(&self, text: &str) -> Result<Value> {
+        match self {
+            Self::Json => serde_json::from_str(text).context("invalid JSON"),
+            Self::Yaml => serde_yaml::from_str(text).context("invalid YAML"),
+            Self::Toml => {
+                let table: toml::Table = text.parse().context("invalid TOML")?;
+                serde_json::to_value(table).context("TOML is not representable as JSON")
+            }
+        }
+    }
+
+    pub fn serialize(&self, value: &Value) -> Result<String> {
+        match self {
+            Self::Json => {
+                let mut text = serde_json::to_string_pretty(value)?;
+                text.push('\n');
+                Ok(text)
+            }
+            Self::Yaml => serde_yaml::to_string(value).context("cannot encode as YAML"),
+            Self::Toml => toml::to_string_pretty(value).context(
+                "cannot encode as TOML (top level must be a table and nulls are not allowed)",
+            ),
+        }
+    }
+}
+
+/// A document loaded from a file or inline input
+pub struct Document {
+    pub value: Value,
+    pub format: Format,
+    pub path: Option<String>,
+}
+
+/// Load the document described by `path`/`document`/`format` in a tool input.
+///
+/// An inline `document` may be a string in `format` or any JSON value. The
+/// format defaults to the file extension, then JSON.
+pub fn load_document(input: &Value) -> Result<Document> {
+    let path = input.get("path").and_then(|p| p.as_str());
+    let format = match input.get("format").and_then(|f| f.as_str()) {
+        Some(name) => Format::parse(name)?,
+        None => path.and_then(Format::from_path).unwrap_or(Format::Json),
+    };
+
+    let value = match (path, input.get("document")) {
+        (Some(path), None) => {
+            let text =
+                std::fs::read_to_string(path).with_context(|| format!("failed to read {path}"))?;
+            format
+                .deserialize(&text)
+                .with_context(|| format!("failed to parse {path} as {}", format.as_str()))?
+        }
+        (None, Some(Value::String(text))) => format
+            .deserialize(text)
+            .with_context(|| format!("failed to parse document as {}", format.as_str()))?,
+        (None, Some(value)) => value.clone(),
+        _ => anyhow::bail!("provide exactly one of 'path' or 'document'"),
+    };
+
+    Ok(Document {
+        value,
+        format,
+        path: path.map(String::from),
+    })
+}
+
+/// Register every data tool with the registry.
+pub fn register_tools(reg: &mut Registry) {
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "data.jq",
+            "data",
+            "Query a JSON, YAML, or TOML document ('path' or inline 'document') with a JSONPath expression ('query', e.g. $.servers[?@.port > 80].name)",
+            vec!["fs.read"],
+            "low",
+            true,
+            false,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "query": { "type": "string" },
+                "path": { "type": "string", "description": "Document file; or give document" },
+                "document": {
+                    "description": "Inline document: any JSON value, or a string in format"
+                },
+                "format": {
+                    "type": "string",
+                    "description": "json, yaml or toml; defaults to the file extension, then json"
+                }
+            },
+            "required": ["query"]
+        }),
+    ));
+
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "data.merge_patch",
+            "data",
+            "Apply an RFC 7386 JSON merge patch ('patch'; null removes a key) to a JSON, YAML, or TOML document. Files are rewritten in place in their own format unless dry_run is set.",
+            vec!["fs.read", "fs.write"],
+            "medium",
+            true,
+            true,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "path": { "type": "string", "description": "Document file; or give document" },
+                "document": {
+                    "description": "Inline document: any JSON value, or a string in format"
+                },
+                "format": {
+                    "type": "string",
+                    "description": "json, yaml or toml; defaults to the file extension, then json"
+                },
+                "patch": { "description": "RFC 7396 merge patch" },
+                "dry_run": {
+                    "type": "boolean",
+                    "description": "Return the result without writing the file"
+                }
+            },
+            "required": ["patch"]
+        }),
+    ));
+
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "data.convert",
+            "data",
+            "Convert a document between JSON, YAML, and TOML ('to'; source format from 'format' or the file extension). Optionally write the result to 'output_path'.",
+            vec!["fs.read", "fs.write"],
+            "low",
+            true,
+            false,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "path": { "type": "string", "description": "Document file; or give document" },
+                "document": {
+                    "description": "Inline document: any JSON value, or a string in format"
+                },
+                "format": {
+                    "type": "string",
+                    "description": "json, yaml or toml; defaults to the file extension, then json"
+                },
+                "to": { "type": "string", "description": "json, yaml or toml" },
+                "output_path": {
+                    "type": "string",
+                    "description": "Write the result here instead of returning it"
+                }
+            },
+            "required": ["to"]
+        }),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_format_roundtrip() {
+        let value = json!({"server": {"host": "0.0.0.0", "port": 8080, "tags": ["a", "b"]}});
+        for format in [Format::Json, Format::Yaml, Format::Toml] {
+            let text = format.serialize(&value).unwrap();
+            assert_eq!(format.deserialize(&text).unwrap(), value, "{format:?}");
+        }
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(Format::from_path("/etc/app.yml"), Some(Format::Yaml));
+        assert_eq!(Format::from_path("Cargo.toml"), Some(Format::Toml));
+        assert_eq!(Format::from_path("/etc/hosts"), None);
+    }
+
+    #[test]
+    fn test_load_document_sources() {
+        let doc = load_document(&json!({"document": "a: 1", "format": "yaml"})).unwrap();
+        assert_eq!(doc.value, json!({"a": 1}));
+
+        let doc = load_document(&json!({"document": {"a": 1}})).unwrap();
+        assert_eq!(doc.format, Format::Json);
+        assert_eq!(doc.value, json!({"a": 1}));
+
+        assert!(load_document(&json!({})).is_err());
+    }
+}
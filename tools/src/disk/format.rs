@@ -0,0 +1,139 @@
+//! disk.format — Create a filesystem on an unmounted device
+//!
+//! Everything on the device is lost. The device must not be mounted, nor
+//! anything built on it; one that already holds a filesystem or partitions
+//! is only formatted with `force`.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+struct Input {
+    device: String,
+    /// ext4, xfs, btrfs or vfat
+    #[serde(default = "default_fstype")]
+    fstype: String,
+    #[serde(default)]
+    label: Option<String>,
+    /// Overwrite an existing filesystem or partition table
+    #[serde(default)]
+    force: bool,
+}
+
+fn default_fstype() -> String {
+    "ext4".to_string()
+}
+
+#[derive(Serialize)]
+struct Output {
+    formatted: bool,
+    device: String,
+    fstype: String,
+    label: Option<String>,
+    /// UUID of the new filesystem, for fstab entries
+    uuid: Option<String>,
+}
+
+pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
+    let input: Input = serde_json::from_slice(input).context("Invalid JSON input")?;
+    let device_path = super::device_path(&input.device)?;
+
+    let devices = super::block_devices()?;
+    let device = super::find(&devices, &device_path)
+        .with_context(|| format!("No block device {device_path}"))?;
+    if device.read_only {
+        anyhow::bail!("{device_path} is read-only");
+    }
+    let mounts = device.mounts();
+    if !mounts.is_empty() {
+        anyhow::bail!(
+            "{device_path} is in use, mounted at {}; unmount it first",
+            mounts.join(", ")
+        );
+    }
+    if !input.force {
+        if let Some(existing) = &device.fstype {
+            anyhow::bail!(
+                "{device_path} already holds a {existing} filesystem; set force to erase it"
+            );
+        }
+        if !device.children.is_empty() {
+            anyhow::bail!(
+                "{device_path} has {} partitions or volumes; set force to erase them",
+                device.children.len()
+            );
+        }
+    }
+
+    let (program, args) = mkfs_command(
+        &input.fstype,
+        input.label.as_deref(),
+        input.force,
+        &device_path,
+    )?;
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    super::run(&program, &args)?;
+
+    // The new filesystem's UUID, once udev has seen it
+    let _ = super::run("udevadm", &["settle"]);
+    let uuid = super::block_devices()
+        .ok()
+        .and_then(|devices| super::find(&devices, &device_path).and_then(|d| d.uuid.clone()));
+
+    let output = Output {
+        formatted: true,
+        device: device_path,
+        fstype: input.fstype,
+        label: input.label,
+        uuid,
+    };
+    serde_json::to_vec(&output).context("Failed to serialize output")
+}
+
+/// The mkfs program and arguments creating `fstype` on `device`
+fn mkfs_command(
+    fstype: &str,
+    label: Option<&str>,
+    force: bool,
+    device: &str,
+) -> Result<(String, Vec<String>)> {
+    let (label_flag, force_flag) = match fstype {
+        // mke2fs asks before formatting a whole disk; -F answers for it
+        "ext4" => ("-L", Some("-F")),
+        "xfs" | "btrfs" => ("-L", force.then_some("-f")),
+        "vfat" => ("-n", None),
+        other => anyhow::bail!("Unsupported filesystem '{other}' (ext4, xfs, btrfs, vfat)"),
+    };
+    let mut args = Vec::new();
+    if let Some(flag) = force_flag {
+        args.push(flag.to_string());
+    }
+    if let Some(label) = label.filter(|l| !l.is_empty()) {
+        args.push(label_flag.to_string());
+        args.push(label.to_string());
+    }
+    args.push(device.to_string());
+    Ok((format!("mkfs.{fstype}"), args))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mkfs_command_per_filesystem() {
+        let (program, args) = mkfs_command("ext4", Some("models"), false, "/dev/sdb").unwrap();
+        assert_eq!(program, "mkfs.ext4");
+        assert_eq!(args, vec!["-F", "-L", "models", "/dev/sdb"]);
+
+        let (_, args) = mkfs_command("xfs", None, false, "/dev/sdb").unwrap();
+        assert_eq!(args, vec!["/dev/sdb"]);
+        let (_, args) = mkfs_command("btrfs", Some(""), true, "/dev/sdb").unwrap();
+        assert_eq!(args, vec!["-f", "/dev/sdb"]);
+        let (program, args) = mkfs_command("vfat", Some("EFI"), true, "/dev/sdb1").unwrap();
+        assert_eq!(program, "mkfs.vfat");
+        assert_eq!(args, vec!["-n", "EFI", "/dev/sdb1"]);
+
+        assert!(mkfs_command("ntfs", None, false, "/dev/sdb").is_err());
+    }
+}
@@ -0,0 +1,37 @@
+//! disk.list_block_devices — Block devices, their partitions and mounts
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use super::BlockDevice;
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Input {
+    /// Only this device and what it holds
+    device: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Output {
+    devices: Vec<BlockDevice>,
+}
+
+pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
+    let input: Input = if input.is_empty() {
+        Input::default()
+    } else {
+        serde_json::from_slice(input).context("Invalid JSON input")?
+    };
+
+    let mut devices = super::block_devices()?;
+    if let Some(device) = input.device {
+        let path = super::device_path(&device)?;
+        let found = super::find(&devices, &path)
+            .cloned()
+            .with_context(|| format!("No block device {path}"))?;
+        devices = vec![found];
+    }
+
+    serde_json::to_vec(&Output { devices }).context("Failed to serialize output")
+}
@@ -0,0 +1,568 @@
+//! Block-device tools — list devices, mount, unmount, format and resize.
+//!
+//! Devices are read with `lsblk`; changes go through `mount`, `umount`,
+//! `mkfs.*` and each filesystem's own resize tool, so storage goals ("add
+//! the new disk to /var/lib/aios/models") run as audited tool calls. Mounts
+//! can be made persistent in /etc/fstab. `disk.format` is critical: plans
+//! using it go through the approval chain, and it refuses devices that are
+//! mounted or, unless forced, already hold a filesystem or partitions.
+//!
+//! Application data on btrfs subvolumes and LVM volumes can be snapshotted
+//! before a change and rolled back after it (see [`snapshot`]).
+//!
+//! Each submodule exposes `pub fn execute(input: &[u8]) -> Result<Vec<u8>>`.
+
+pub mod format;
+pub mod list_block_devices;
+pub mod mount;
+pub mod resize_fs;
+pub mod snapshot;
+pub mod snapshot_create;
+pub mod snapshot_delete;
+pub mod snapshot_list;
+pub mod snapshot_rollback;
+pub mod umount;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::path::{Component, Path};
+
+use crate::registry::{critical, make_tool, with_input_schema, Registry};
+use crate::sandbox::command;
+
+/// Static filesystem table updated by persistent mounts
+pub const FSTAB_PATH: &str = "/etc/fstab";
+
+/// Mount points that are never unmounted
+const PROTECTED_MOUNTS: &[&str] = &["/", "/boot", "/boot/efi", "/usr", "/var", "/etc"];
+
+/// Columns requested from lsblk
+const LSBLK_COLUMNS: &str = "NAME,PATH,SIZE,TYPE,FSTYPE,MOUNTPOINT,LABEL,UUID,MODEL,RO";
+
+/// A block device as reported by lsblk, with its partitions and holders
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BlockDevice {
+    pub name: String,
+    pub path: String,
+    pub size_bytes: u64,
+    /// disk, part, lvm, crypt, loop, rom, ...
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub fstype: Option<String>,
+    pub mountpoint: Option<String>,
+    pub label: Option<String>,
+    pub uuid: Option<String>,
+    pub model: Option<String>,
+    pub read_only: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<BlockDevice>,
+}
+
+impl BlockDevice {
+    /// Where this device and everything built on it is mounted, swap
+    /// included
+    pub fn mounts(&self) -> Vec<&str> {
+        let mut mounts: Vec<&str> = self.mountpoint.as_deref().into_iter().collect();
+        for child in &self.children {
+            mounts.extend(child.mounts());
+        }
+        mounts
+    }
+}
+
+/// Block devices on this machine, as a tree of disks and what they hold
+pub fn block_devices() -> Result<Vec<BlockDevice>> {
+    let stdout = run("lsblk", &["--json", "--bytes", "--output", LSBLK_COLUMNS])?;
+    parse_lsblk(&stdout)
+}
+
+/// Parse `lsblk --json` output. Older util-linux versions print sizes and
+/// flags as strings, newer ones as numbers and booleans.
+fn parse_lsblk(json: &str) -> Result<Vec<BlockDevice>> {
+    let value: Value = serde_json::from_str(json).context("Invalid lsblk output")?;
+    Ok(value
+        .get("blockdevices")
+        .and_then(Value::as_array)
+        .map(|devices| devices.iter().map(device_from_lsblk).collect())
+        .unwrap_or_default())
+}
+
+fn device_from_lsblk(value: &Value) -> BlockDevice {
+    let text = |key: &str| {
+        value
+            .get(key)
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    };
+    let name = text("name").unwrap_or_default();
+    BlockDevice {
+        path: text("path").unwrap_or_else(|| format!("/dev/{name}")),
+        size_bytes: match value.get("size") {
+            Some(Value::Number(n)) => n.as_u64().unwrap_or(0),
+            Some(Value::String(s)) => s.parse().unwrap_or(0),
+            _ => 0,
+        },
+        kind: text("type").unwrap_or_default(),
+        fstype: text("fstype"),
+        mountpoint: text("mountpoint"),
+        label: text("label"),
+        uuid: text("uuid"),
+        model: text("model"),
+        read_only: match value.get("ro") {
+            Some(Value::Bool(b)) => *b,
+            Some(Value::Number(n)) => n.as_u64() == Some(1),
+            Some(Value::String(s)) => s == "1",
+            _ => false,
+        },
+        children: value
+            .get("children")
+            .and_then(Value::as_array)
+            .map(|children| children.iter().map(device_from_lsblk).collect())
+            .unwrap_or_default(),
+        name,
+    }
+}
+
+/// The device at `path`, searched through the whole tree
+pub fn find<'a>(devices: &'a [BlockDevice], path: &str) -> Option<&'a BlockDevice> {
+    devices.iter().find_map(|device| {
+        if device.path == path {
+            Some(device)
+        } else {
+            find(&device.children, path)
+        }
+    })
+}
+
+/// The device mounted at `mountpoint`
+pub fn find_mounted<'a>(devices: &'a [BlockDevice], mountpoint: &str) -> Option<&'a BlockDevice> {
+    devices.iter().find_map(|device| {
+        if device.mountpoint.as_deref() == Some(mountpoint) {
+            Some(device)
+        } else {
+            find_mounted(&device.children, mountpoint)
+        }
+    })
+}
+
+/// Device path for a name such as `sdb` or `/dev/nvme1n1p1`
+pub fn device_path(device: &str) -> Result<String> {
+    let device = device.trim();
+    let path = if device.starts_with('/') {
+        device.to_string()
+    } else {
+        format!("/dev/{device}")
+    };
+    if !path.starts_with("/dev/")
+        || Path::new(&path)
+            .components()
+            .any(|c| c == Component::ParentDir)
+    {
+        anyhow::bail!("'{device}' is not a device under /dev");
+    }
+    Ok(path)
+}
+
+/// Absolute mount point without `..` components
+pub fn mount_point(target: &str) -> Result<String> {
+    let path = Path::new(target);
+    if !path.is_absolute() || path.components().any(|c| c == Component::ParentDir) {
+        anyhow::bail!("mount point '{target}' must be an absolute path without '..'");
+    }
+    let normalized = target.trim_end_matches('/');
+    Ok(if normalized.is_empty() {
+        "/"
+    } else {
+        normalized
+    }
+    .to_string())
+}
+
+/// Whether `mountpoint` holds the system and must stay mounted
+pub fn is_protected(mountpoint: &str) -> bool {
+    PROTECTED_MOUNTS.contains(&mountpoint)
+}
+
+/// Run a command, returning its stdout or failing with its stderr
+pub fn run(program: &str, args: &[&str]) -> Result<String> {
+    let output = command(program)
+        .args(args)
+        .output()
+        .with_context(|| format!("Failed to execute {program}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("{program} failed: {}", stderr.trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// fstab fields escape whitespace as octal
+fn fstab_escape(field: &str) -> String {
+    field.replace(' ', "\\040").replace('\t', "\\011")
+}
+
+/// fstab `contents` with `spec` mounted at `target`, replacing any entry
+/// for that mount point
+pub fn fstab_with(contents: &str, spec: &str, target: &str, fstype: &str, options: &str) -> String {
+    let pass = if matches!(fstype, "ext2" | "ext3" | "ext4" | "vfat") {
+        2
+    } else {
+        0
+    };
+    let mut updated = fstab_without(contents, target).unwrap_or_else(|| contents.to_string());
+    if !updated.is_empty() && !updated.ends_with('\n') {
+        updated.push('\n');
+    }
+    updated.push_str(&format!(
+        "{}\t{}\t{fstype}\t{options}\t0\t{pass}\n",
+        fstab_escape(spec),
+        fstab_escape(target)
+    ));
+    updated
+}
+
+/// fstab `contents` without entries for `target`, or None when it has none
+pub fn fstab_without(contents: &str, target: &str) -> Option<String> {
+    let target = fstab_escape(target);
+    let mut removed = false;
+    let mut kept = String::with_capacity(contents.len());
+    for line in contents.lines() {
+        let trimmed = line.trim_start();
+        if !trimmed.starts_with('#') && trimmed.split_whitespace().nth(1) == Some(target.as_str()) {
+            removed = true;
+            continue;
+        }
+        kept.push_str(line);
+        kept.push('\n');
+    }
+    removed.then_some(kept)
+}
+
+/// Register every block-device tool with the registry.
+pub fn register_tools(reg: &mut Registry) {
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "disk.list_block_devices",
+            "disk",
+            "List block devices with size, type, filesystem, label, UUID, model and mount point, as a tree of disks and their partitions",
+            vec!["disk.read"],
+            "low",
+            true,
+            false,
+            10000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "device": { "type": "string", "description": "Only this device and what it holds" }
+            }
+        }),
+    ));
+
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "disk.mount",
+            "disk",
+            "Mount a formatted device at a directory (created if missing); \"persist\": true also adds it to /etc/fstab by UUID",
+            vec!["disk.manage"],
+            "high",
+            true,
+            true,
+            30000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "device": { "type": "string" },
+                "target": { "type": "string", "description": "Mount point" },
+                "fstype": {
+                    "type": "string",
+                    "description": "Detected from the device when omitted"
+                },
+                "options": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Mount options such as \"noatime\""
+                },
+                "read_only": { "type": "boolean" },
+                "create_target": { "type": "boolean", "default": true },
+                "allow_non_empty": {
+                    "type": "boolean",
+                    "description": "Mount over a directory that has contents"
+                },
+                "persist": { "type": "boolean", "description": "Add the mount to /etc/fstab" }
+            },
+            "required": ["device", "target"]
+        }),
+    ));
+
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "disk.umount",
+            "disk",
+            "Unmount a device or mount point; \"forget\": true also removes its /etc/fstab entry",
+            vec!["disk.manage"],
+            "high",
+            true,
+            true,
+            30000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "target": { "type": "string", "description": "Device (/dev/...) or mount point" },
+                "lazy": { "type": "boolean" },
+                "forget": { "type": "boolean", "description": "Also remove its /etc/fstab entry" }
+            },
+            "required": ["target"]
+        }),
+    ));
+
+    reg.register_tool(critical(with_input_schema(
+        make_tool(
+            "disk.format",
+            "disk",
+            "Create a filesystem (ext4, xfs, btrfs, vfat) on an unmounted device, erasing it. Refuses devices that already hold a filesystem or partitions unless \"force\": true",
+            vec!["disk.manage"],
+            "critical",
+            false,
+            false,
+            600000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "device": { "type": "string" },
+                "fstype": {
+                    "type": "string",
+                    "enum": ["ext4", "xfs", "btrfs", "vfat"],
+                    "default": "ext4"
+                },
+                "label": { "type": "string" },
+                "force": {
+                    "type": "boolean",
+                    "description": "Overwrite an existing filesystem or partition table"
+                }
+            },
+            "required": ["device"]
+        }),
+    )));
+
+    reg.register_tool(critical(with_input_schema(
+        make_tool(
+            "disk.resize_fs",
+            "disk",
+            "Resize the filesystem on a device or mount point to a size such as \"50G\", or grow it to fill its device when no size is given (ext2-4, xfs, btrfs)",
+            vec!["disk.manage"],
+            "high",
+            false,
+            false,
+            600000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "target": { "type": "string", "description": "Device (/dev/...) or mount point" },
+                "size": {
+                    "type": "string",
+                    "description": "New size such as \"50G\"; omitted to fill the device"
+                }
+            },
+            "required": ["target"]
+        }),
+    )));
+
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "disk.snapshot_create",
+            "disk",
+            "Take a point-in-time snapshot of a directory on a btrfs subvolume or LVM volume (or of a logical volume's device) before changing it; rolling back the execution restores it",
+            vec!["disk.manage"],
+            "medium",
+            false,
+            false,
+            60000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "target": {
+                    "type": "string",
+                    "description": "Directory on a btrfs subvolume or LVM volume, or a logical volume's device"
+                },
+                "label": { "type": "string" },
+                "size": {
+                    "type": "string",
+                    "description": "Copy-on-write space of an LVM snapshot, such as \"2G\" or \"10%ORIGIN\""
+                }
+            },
+            "required": ["target"]
+        }),
+    ));
+
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "disk.snapshot_list",
+            "disk",
+            "List snapshots taken with disk.snapshot_create, optionally only those of one source",
+            vec!["disk.read"],
+            "low",
+            true,
+            false,
+            10000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "source": {
+                    "type": "string",
+                    "description": "Only snapshots of this subvolume or logical volume"
+                }
+            }
+        }),
+    ));
+
+    reg.register_tool(critical(with_input_schema(
+        make_tool(
+            "disk.snapshot_rollback",
+            "disk",
+            "Return a subvolume or logical volume to a snapshot (by id, or the newest of a source), discarding every change made since",
+            vec!["disk.manage"],
+            "critical",
+            false,
+            false,
+            300000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "id": {
+                    "type": "string",
+                    "description": "Snapshot id, or a source to roll back to its newest snapshot"
+                }
+            },
+            "required": ["id"]
+        }),
+    )));
+
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "disk.snapshot_delete",
+            "disk",
+            "Delete a snapshot taken with disk.snapshot_create",
+            vec!["disk.manage"],
+            "high",
+            true,
+            false,
+            60000,
+        ),
+        json!({
+            "type": "object",
+            "properties": { "id": { "type": "string", "description": "Snapshot id" } },
+            "required": ["id"]
+        }),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LSBLK: &str = r#"{"blockdevices": [
+        {"name": "nvme0n1", "path": "/dev/nvme0n1", "size": 512110190592, "type": "disk",
+         "fstype": null, "mountpoint": null, "label": null, "uuid": null,
+         "model": "Samsung SSD 980", "ro": false, "children": [
+            {"name": "nvme0n1p1", "path": "/dev/nvme0n1p1", "size": 536870912, "type": "part",
+             "fstype": "vfat", "mountpoint": "/boot/efi", "label": null, "uuid": "AB12-CD34",
+             "model": null, "ro": false},
+            {"name": "nvme0n1p2", "path": "/dev/nvme0n1p2", "size": 511571509248, "type": "part",
+             "fstype": "ext4", "mountpoint": "/", "label": "root", "uuid": "0b5c-root",
+             "model": null, "ro": false}
+        ]},
+        {"name": "sdb", "size": "2000398934016", "type": "disk", "fstype": null,
+         "mountpoint": null, "label": null, "uuid": null, "model": "WDC WD20 ", "ro": "0"}
+    ]}"#;
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_run_applies_the_active_profile() {
+        let limits = crate::sandbox::ResourceLimits {
+            max_file_descriptors: 37,
+            allow_network: true,
+            ..Default::default()
+        };
+        let open_files =
+            crate::sandbox::with_limits(Some(&limits), || run("sh", &["-c", "ulimit -n"]));
+        assert_eq!(open_files.unwrap().trim(), "37");
+    }
+
+    #[test]
+    fn test_parse_lsblk_tree() {
+        let devices = parse_lsblk(LSBLK).unwrap();
+        assert_eq!(devices.len(), 2);
+        let system = &devices[0];
+        assert_eq!(system.children.len(), 2);
+        assert_eq!(system.mounts(), vec!["/boot/efi", "/"]);
+
+        // String sizes and flags from older lsblk, path derived from name
+        let spare = find(&devices, "/dev/sdb").unwrap();
+        assert_eq!(spare.size_bytes, 2_000_398_934_016);
+        assert_eq!(spare.model.as_deref(), Some("WDC WD20"));
+        assert!(!spare.read_only);
+        assert!(spare.mounts().is_empty());
+
+        let root = find_mounted(&devices, "/").unwrap();
+        assert_eq!(root.uuid.as_deref(), Some("0b5c-root"));
+        assert!(find(&devices, "/dev/sdc").is_none());
+    }
+
+    #[test]
+    fn test_device_and_mount_point_validation() {
+        assert_eq!(device_path("sdb").unwrap(), "/dev/sdb");
+        assert_eq!(
+            device_path("/dev/mapper/vg-data").unwrap(),
+            "/dev/mapper/vg-data"
+        );
+        assert!(device_path("/dev/../etc/passwd").is_err());
+        assert!(device_path("/tmp/image").is_err());
+
+        assert_eq!(
+            mount_point("/var/lib/aios/models/").unwrap(),
+            "/var/lib/aios/models"
+        );
+        assert_eq!(mount_point("/").unwrap(), "/");
+        assert!(mount_point("models").is_err());
+        assert!(mount_point("/mnt/../etc").is_err());
+        assert!(is_protected("/boot/efi"));
+        assert!(!is_protected("/var/lib/aios/models"));
+    }
+
+    #[test]
+    fn test_fstab_entries_replace_and_remove() {
+        let fstab = "# /etc/fstab\nUUID=0b5c-root / ext4 defaults 0 1\nUUID=old /var/lib/aios/models xfs defaults 0 0";
+
+        let added = fstab_with(
+            fstab,
+            "UUID=new",
+            "/var/lib/aios/models",
+            "ext4",
+            "defaults",
+        );
+        assert_eq!(
+            added,
+            "# /etc/fstab\nUUID=0b5c-root / ext4 defaults 0 1\nUUID=new\t/var/lib/aios/models\text4\tdefaults\t0\t2\n"
+        );
+
+        let spaced = fstab_with("", "/dev/sdb1", "/mnt/my data", "xfs", "noatime");
+        assert_eq!(spaced, "/dev/sdb1\t/mnt/my\\040data\txfs\tnoatime\t0\t0\n");
+        assert_eq!(fstab_without(&spaced, "/mnt/my data").unwrap(), "");
+
+        assert!(fstab_without(fstab, "/srv").is_none());
+        let removed = fstab_without(&added, "/var/lib/aios/models").unwrap();
+        assert!(!removed.contains("models"));
+        assert!(removed.contains("# /etc/fstab\n"));
+    }
+}
@@ -0,0 +1,138 @@
+//! disk.mount — Mount a formatted device, optionally persisting it in fstab
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Deserialize)]
+struct Input {
+    device: String,
+    /// Mount point
+    target: String,
+    /// Detected from the device when omitted
+    #[serde(default)]
+    fstype: Option<String>,
+    /// Mount options such as "noatime"
+    #[serde(default)]
+    options: Vec<String>,
+    #[serde(default)]
+    read_only: bool,
+    /// Create the mount point when it does not exist
+    #[serde(default = "default_true")]
+    create_target: bool,
+    /// Mount over a directory that has contents, hiding them
+    #[serde(default)]
+    allow_non_empty: bool,
+    /// Add the mount to /etc/fstab so it survives a reboot
+    #[serde(default)]
+    persist: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Serialize)]
+struct Output {
+    mounted: bool,
+    device: String,
+    target: String,
+    fstype: String,
+    /// The device was already mounted there
+    already_mounted: bool,
+    persisted: bool,
+}
+
+pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
+    let input: Input = serde_json::from_slice(input).context("Invalid JSON input")?;
+    let device_path = super::device_path(&input.device)?;
+    let target = super::mount_point(&input.target)?;
+
+    let devices = super::block_devices()?;
+    let device = super::find(&devices, &device_path)
+        .with_context(|| format!("No block device {device_path}"))?;
+    let fstype = input
+        .fstype
+        .clone()
+        .or_else(|| device.fstype.clone())
+        .with_context(|| {
+            format!("{device_path} holds no filesystem; create one with disk.format first")
+        })?;
+
+    let already_mounted = match device.mountpoint.as_deref() {
+        Some(mounted) if mounted == target => true,
+        Some(mounted) => anyhow::bail!("{device_path} is already mounted at {mounted}"),
+        None => false,
+    };
+
+    let mut options = input.options.clone();
+    if input.read_only {
+        options.push("ro".to_string());
+    }
+    let options = options.join(",");
+
+    if !already_mounted {
+        prepare_target(&target, input.create_target, input.allow_non_empty)?;
+
+        let mut args = vec!["-t", fstype.as_str()];
+        if !options.is_empty() {
+            args.extend(["-o", options.as_str()]);
+        }
+        args.extend([device_path.as_str(), target.as_str()]);
+        super::run("mount", &args)?;
+    }
+
+    if input.persist {
+        let spec = device
+            .uuid
+            .as_ref()
+            .map(|uuid| format!("UUID={uuid}"))
+            .unwrap_or_else(|| device_path.clone());
+        let options = if options.is_empty() {
+            "defaults"
+        } else {
+            options.as_str()
+        };
+        let contents = std::fs::read_to_string(super::FSTAB_PATH).unwrap_or_default();
+        let updated = super::fstab_with(&contents, &spec, &target, &fstype, options);
+        crate::fs::write_atomically(super::FSTAB_PATH, updated.as_bytes())
+            .context("Mounted, but failed to update /etc/fstab")?;
+    }
+
+    let output = Output {
+        mounted: true,
+        device: device_path,
+        target,
+        fstype,
+        already_mounted,
+        persisted: input.persist,
+    };
+    serde_json::to_vec(&output).context("Failed to serialize output")
+}
+
+/// Make sure the mount point is an empty directory, unless mounting over
+/// contents was asked for
+fn prepare_target(target: &str, create: bool, allow_non_empty: bool) -> Result<()> {
+    let path = Path::new(target);
+    if !path.exists() {
+        if !create {
+            anyhow::bail!("Mount point {target} does not exist");
+        }
+        return std::fs::create_dir_all(path)
+            .with_context(|| format!("Failed to create mount point {target}"));
+    }
+    if !path.is_dir() {
+        anyhow::bail!("Mount point {target} is not a directory");
+    }
+    let has_contents = std::fs::read_dir(path)
+        .with_context(|| format!("Failed to read mount point {target}"))?
+        .next()
+        .is_some();
+    if has_contents && !allow_non_empty {
+        anyhow::bail!(
+            "{target} is not empty and the mount would hide its contents; move them onto the \
+             device first, or set allow_non_empty"
+        );
+    }
+    Ok(())
+}
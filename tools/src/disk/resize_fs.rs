@@ -0,0 +1,142 @@
+//! disk.resize_fs — Grow or shrink the filesystem on a device
+//!
+//! ext2-4 are resized with `resize2fs`, which can also shrink them while
+//! unmounted. XFS and btrfs are resized through their mount point; XFS can
+//! only grow.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+struct Input {
+    /// Device (/dev/...) or mount point
+    target: String,
+    /// New size such as "50G"; omitted to fill the device
+    #[serde(default)]
+    size: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Output {
+    resized: bool,
+    device: String,
+    fstype: String,
+    mountpoint: Option<String>,
+    /// The requested size, or "max"
+    size: String,
+}
+
+pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
+    let input: Input = serde_json::from_slice(input).context("Invalid JSON input")?;
+    if let Some(size) = &input.size {
+        validate_size(size)?;
+    }
+
+    let devices = super::block_devices()?;
+    let device = if input.target.trim_start().starts_with("/dev/") {
+        let path = super::device_path(&input.target)?;
+        super::find(&devices, &path).with_context(|| format!("No block device {path}"))?
+    } else {
+        let target = super::mount_point(&input.target)?;
+        super::find_mounted(&devices, &target)
+            .with_context(|| format!("No block device is mounted at {target}"))?
+    };
+    let fstype = device
+        .fstype
+        .clone()
+        .with_context(|| format!("{} holds no filesystem", device.path))?;
+
+    let (program, args) = resize_command(
+        &fstype,
+        &device.path,
+        device.mountpoint.as_deref(),
+        input.size.as_deref(),
+    )?;
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    super::run(program, &args)?;
+
+    let output = Output {
+        resized: true,
+        device: device.path.clone(),
+        fstype,
+        mountpoint: device.mountpoint.clone(),
+        size: input.size.unwrap_or_else(|| "max".to_string()),
+    };
+    serde_json::to_vec(&output).context("Failed to serialize output")
+}
+
+/// Digits with an optional K, M, G or T suffix
+fn validate_size(size: &str) -> Result<()> {
+    let digits = size.trim_end_matches(['K', 'M', 'G', 'T', 'k', 'm', 'g', 't']);
+    let suffix_len = size.len() - digits.len();
+    if digits.is_empty() || suffix_len > 1 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        anyhow::bail!("Invalid size '{size}'; use a number with an optional K, M, G or T suffix");
+    }
+    Ok(())
+}
+
+/// The resize program and arguments for a filesystem
+fn resize_command(
+    fstype: &str,
+    device: &str,
+    mountpoint: Option<&str>,
+    size: Option<&str>,
+) -> Result<(&'static str, Vec<String>)> {
+    let mounted = || {
+        mountpoint.map(str::to_string).with_context(|| {
+            format!("{fstype} filesystems are resized while mounted; mount {device} first")
+        })
+    };
+    match fstype {
+        "ext2" | "ext3" | "ext4" => {
+            let mut args = vec![device.to_string()];
+            args.extend(size.map(str::to_string));
+            Ok(("resize2fs", args))
+        }
+        "xfs" => {
+            if size.is_some() {
+                anyhow::bail!("XFS filesystems can only grow to fill their device; omit size");
+            }
+            Ok(("xfs_growfs", vec![mounted()?]))
+        }
+        "btrfs" => Ok((
+            "btrfs",
+            vec![
+                "filesystem".to_string(),
+                "resize".to_string(),
+                size.unwrap_or("max").to_string(),
+                mounted()?,
+            ],
+        )),
+        other => anyhow::bail!("Resizing {other} filesystems is not supported"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resize_command_per_filesystem() {
+        let (program, args) = resize_command("ext4", "/dev/sdb1", None, Some("50G")).unwrap();
+        assert_eq!(program, "resize2fs");
+        assert_eq!(args, vec!["/dev/sdb1", "50G"]);
+
+        let (program, args) =
+            resize_command("xfs", "/dev/sdb1", Some("/var/lib/aios/models"), None).unwrap();
+        assert_eq!(program, "xfs_growfs");
+        assert_eq!(args, vec!["/var/lib/aios/models"]);
+        assert!(resize_command("xfs", "/dev/sdb1", Some("/srv"), Some("10G")).is_err());
+        assert!(resize_command("xfs", "/dev/sdb1", None, None).is_err());
+
+        let (_, args) = resize_command("btrfs", "/dev/sdb1", Some("/srv"), None).unwrap();
+        assert_eq!(args, vec!["filesystem", "resize", "max", "/srv"]);
+        assert!(resize_command("vfat", "/dev/sdb1", None, None).is_err());
+
+        assert!(validate_size("512M").is_ok());
+        assert!(validate_size("2048").is_ok());
+        assert!(validate_size("50GB").is_err());
+        assert!(validate_size("-5G").is_err());
+        assert!(validate_size("G").is_err());
+    }
+}
@@ -0,0 +1,477 @@
+//! Snapshots — point-in-time copies of application data
+//!
+//! `disk.snapshot_create` takes a snapshot of the btrfs subvolume holding a
+//! directory (a read-only snapshot under `.aios-snapshots` at the root of
+//! its filesystem), or of an LVM logical volume (a copy-on-write snapshot
+//! volume next to it). Both are cheap enough to take before every change
+//! to a database or model directory.
+//!
+//! Rolling back a btrfs snapshot swaps a writable copy of it in for the
+//! subvolume and keeps the snapshot. Rolling back an LVM snapshot merges it
+//! into its origin, unmounting and remounting the origin around the merge,
+//! which consumes the snapshot. The executor records every snapshot in the
+//! backup manager, so rolling back the execution that took it, or a
+//! composite that fails after it, restores the data.
+//!
+//! `index.json` under [`SNAPSHOT_DIR`] records what was taken, of what.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::{info, warn};
+
+/// Default location of the snapshot index
+pub const SNAPSHOT_DIR: &str = "/var/lib/aios/snapshots";
+
+/// Directory at the root of a btrfs filesystem holding its snapshots
+pub const BTRFS_SNAPSHOT_DIR: &str = ".aios-snapshots";
+
+/// Copy-on-write space of an LVM snapshot when none is given
+const DEFAULT_LVM_SIZE: &str = "10%ORIGIN";
+
+/// Serialises index updates across concurrent tool executions
+static INDEX_LOCK: Mutex<()> = Mutex::new(());
+
+/// What a snapshot was taken with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Backend {
+    Btrfs,
+    Lvm,
+}
+
+/// A snapshot taken by `disk.snapshot_create`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotEntry {
+    pub id: String,
+    pub backend: Backend,
+    /// The subvolume path, or the logical volume's device
+    pub source: String,
+    /// The snapshot subvolume path, or `vg/lv` of the snapshot volume
+    pub snapshot: String,
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Unix seconds
+    pub created_at: i64,
+}
+
+impl SnapshotEntry {
+    /// Whether the snapshot itself still exists
+    pub fn present(&self) -> bool {
+        match self.backend {
+            Backend::Btrfs => Path::new(&self.snapshot).exists(),
+            Backend::Lvm => Path::new("/dev").join(&self.snapshot).exists(),
+        }
+    }
+}
+
+/// The snapshot index
+pub struct Snapshots {
+    dir: PathBuf,
+}
+
+impl Snapshots {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// The index at [`SNAPSHOT_DIR`]
+    pub fn open_default() -> Self {
+        Self::new(SNAPSHOT_DIR)
+    }
+
+    /// Snapshot `target` — a directory on a btrfs subvolume or an LVM
+    /// logical volume, or an LVM logical volume's device
+    pub fn create(
+        &self,
+        target: &str,
+        label: Option<String>,
+        size: Option<&str>,
+    ) -> Result<SnapshotEntry> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let short = &id[..8];
+        let entry = match locate(target)? {
+            Source::Btrfs { subvolume, root } => {
+                let dir = Path::new(&root).join(BTRFS_SNAPSHOT_DIR);
+                fs::create_dir_all(&dir)
+                    .with_context(|| format!("cannot create {}", dir.display()))?;
+                let name = Path::new(&subvolume)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| "root".to_string());
+                let snapshot = dir.join(format!("{name}-{short}"));
+                let snapshot = snapshot.to_string_lossy().into_owned();
+                super::run(
+                    "btrfs",
+                    &["subvolume", "snapshot", "-r", &subvolume, &snapshot],
+                )?;
+                SnapshotEntry {
+                    id,
+                    backend: Backend::Btrfs,
+                    source: subvolume,
+                    snapshot,
+                    label,
+                    created_at: chrono::Utc::now().timestamp(),
+                }
+            }
+            Source::Lvm { device, vg, lv } => {
+                let name = format!("aios-snap-{short}");
+                let (size_flag, size) = lvm_size(size.unwrap_or(DEFAULT_LVM_SIZE))?;
+                super::run(
+                    "lvcreate",
+                    &[
+                        "--snapshot",
+                        "--name",
+                        &name,
+                        size_flag,
+                        &size,
+                        &format!("{vg}/{lv}"),
+                    ],
+                )?;
+                SnapshotEntry {
+                    id,
+                    backend: Backend::Lvm,
+                    source: device,
+                    snapshot: format!("{vg}/{name}"),
+                    label,
+                    created_at: chrono::Utc::now().timestamp(),
+                }
+            }
+        };
+
+        let _lock = INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut entries = self.load_index()?;
+        entries.push(entry.clone());
+        self.save_index(&entries)?;
+        info!(
+            "Snapshot {} of {} taken as {}",
+            entry.id, entry.source, entry.snapshot
+        );
+        Ok(entry)
+    }
+
+    /// Snapshots taken, oldest first
+    pub fn list(&self) -> Result<Vec<SnapshotEntry>> {
+        let _lock = INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        self.load_index()
+    }
+
+    /// Snapshot by id, or the newest of a source
+    pub fn get(&self, selector: &str) -> Result<SnapshotEntry> {
+        let entries = self.list()?;
+        entries
+            .iter()
+            .find(|e| e.id == selector)
+            .or_else(|| entries.iter().rev().find(|e| e.source == selector))
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("no snapshot matches '{selector}'"))
+    }
+
+    /// Return the snapshot's source to the state it was in when the
+    /// snapshot was taken. Everything written to it since is lost.
+    pub fn rollback(&self, selector: &str) -> Result<SnapshotEntry> {
+        let entry = self.get(selector)?;
+        if !entry.present() {
+            anyhow::bail!("snapshot {} no longer exists", entry.snapshot);
+        }
+        match entry.backend {
+            Backend::Btrfs => rollback_btrfs(&entry)?,
+            Backend::Lvm => {
+                rollback_lvm(&entry)?;
+                // Merging consumed the snapshot volume
+                self.forget(&entry.id)?;
+            }
+        }
+        info!("Rolled {} back to snapshot {}", entry.source, entry.id);
+        Ok(entry)
+    }
+
+    /// Delete a snapshot and its index entry
+    pub fn delete(&self, selector: &str) -> Result<SnapshotEntry> {
+        let entry = self.get(selector)?;
+        if entry.present() {
+            match entry.backend {
+                Backend::Btrfs => {
+                    super::run("btrfs", &["subvolume", "delete", &entry.snapshot])?;
+                }
+                Backend::Lvm => {
+                    super::run("lvremove", &["--yes", &entry.snapshot])?;
+                }
+            }
+        }
+        self.forget(&entry.id)?;
+        Ok(entry)
+    }
+
+    fn forget(&self, id: &str) -> Result<()> {
+        let _lock = INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut entries = self.load_index()?;
+        entries.retain(|e| e.id != id);
+        self.save_index(&entries)
+    }
+
+    fn index_path(&self) -> PathBuf {
+        self.dir.join("index.json")
+    }
+
+    fn load_index(&self) -> Result<Vec<SnapshotEntry>> {
+        match fs::read(self.index_path()) {
+            Ok(bytes) => serde_json::from_slice(&bytes).context("snapshot index is corrupt"),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e).context("cannot read snapshot index"),
+        }
+    }
+
+    fn save_index(&self, entries: &[SnapshotEntry]) -> Result<()> {
+        let bytes = serde_json::to_vec_pretty(entries)?;
+        crate::fs::write_atomically(&self.index_path().to_string_lossy(), &bytes)
+    }
+}
+
+/// What a snapshot target resolves to
+#[derive(Debug, PartialEq)]
+enum Source {
+    Btrfs {
+        subvolume: String,
+        root: String,
+    },
+    Lvm {
+        device: String,
+        vg: String,
+        lv: String,
+    },
+}
+
+fn locate(target: &str) -> Result<Source> {
+    if target.starts_with("/dev/") {
+        let (vg, lv) = logical_volume(target)?
+            .with_context(|| format!("{target} is not an LVM logical volume"))?;
+        return Ok(Source::Lvm {
+            device: target.to_string(),
+            vg,
+            lv,
+        });
+    }
+
+    let path = fs::canonicalize(target).with_context(|| format!("cannot resolve {target}"))?;
+    let path = path.to_string_lossy().into_owned();
+    let found = super::run(
+        "findmnt",
+        &["-n", "-r", "-o", "SOURCE,FSTYPE,TARGET", "--target", &path],
+    )?;
+    let (source, fstype, mountpoint) = parse_findmnt(&found)
+        .with_context(|| format!("cannot find the filesystem holding {path}"))?;
+
+    if fstype == "btrfs" {
+        if super::run("btrfs", &["subvolume", "show", &path]).is_err() {
+            anyhow::bail!(
+                "{path} is not a btrfs subvolume; create one for the data with \
+                 `btrfs subvolume create` to snapshot it"
+            );
+        }
+        if path == mountpoint {
+            anyhow::bail!(
+                "{path} is the root of a mounted filesystem and cannot be rolled back in \
+                 place; keep the data in a subvolume below it"
+            );
+        }
+        return Ok(Source::Btrfs {
+            subvolume: path,
+            root: mountpoint,
+        });
+    }
+    match logical_volume(&source)? {
+        Some((vg, lv)) => Ok(Source::Lvm {
+            device: source,
+            vg,
+            lv,
+        }),
+        None => anyhow::bail!(
+            "{path} is on {fstype} at {source}, neither a btrfs subvolume nor an LVM logical volume"
+        ),
+    }
+}
+
+/// Volume group and name of the logical volume at `device`, if it is one.
+/// Snapshots of snapshots are refused.
+fn logical_volume(device: &str) -> Result<Option<(String, String)>> {
+    let Ok(stdout) = super::run(
+        "lvs",
+        &[
+            "--noheadings",
+            "--separator",
+            "|",
+            "-o",
+            "vg_name,lv_name,lv_attr",
+            device,
+        ],
+    ) else {
+        return Ok(None);
+    };
+    let Some((vg, lv, attr)) = parse_lvs(&stdout) else {
+        return Ok(None);
+    };
+    if attr.starts_with(['s', 'S']) {
+        anyhow::bail!("{device} is itself a snapshot");
+    }
+    Ok(Some((vg, lv)))
+}
+
+fn parse_lvs(stdout: &str) -> Option<(String, String, String)> {
+    let line = stdout.lines().find(|l| !l.trim().is_empty())?;
+    let mut fields = line.trim().split('|').map(str::trim);
+    let vg = fields.next().filter(|f| !f.is_empty())?;
+    let lv = fields.next().filter(|f| !f.is_empty())?;
+    let attr = fields.next().unwrap_or_default();
+    Some((vg.to_string(), lv.to_string(), attr.to_string()))
+}
+
+/// Source device, filesystem type and mount point from `findmnt -r`
+fn parse_findmnt(stdout: &str) -> Option<(String, String, String)> {
+    let line = stdout.lines().next()?;
+    let mut fields = line.split_whitespace().map(|f| f.replace("\\x20", " "));
+    let source = fields.next()?;
+    // btrfs subvolume mounts read `/dev/sda2[/@data]`
+    let source = source.split('[').next().unwrap_or_default().to_string();
+    Some((source, fields.next()?, fields.next()?))
+}
+
+/// lvcreate flag and value for a snapshot size: `-l` for a percentage
+/// such as "10%ORIGIN", `-L` for a size such as "2G"
+fn lvm_size(size: &str) -> Result<(&'static str, String)> {
+    let size = size.trim();
+    let valid = |digits: &str| !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit());
+    if let Some((percent, of)) = size.split_once('%') {
+        if valid(percent) && matches!(of, "ORIGIN" | "FREE" | "VG") {
+            return Ok(("-l", size.to_string()));
+        }
+    } else {
+        let digits = size.trim_end_matches(['K', 'M', 'G', 'T', 'k', 'm', 'g', 't']);
+        if valid(digits) && size.len() - digits.len() <= 1 {
+            return Ok(("-L", size.to_string()));
+        }
+    }
+    anyhow::bail!("Invalid snapshot size '{size}'; use a size such as \"2G\" or \"10%ORIGIN\"")
+}
+
+fn rollback_btrfs(entry: &SnapshotEntry) -> Result<()> {
+    let short = &entry.id[..8.min(entry.id.len())];
+    let restored = format!("{}.aios-restore-{short}", entry.source);
+    let replaced = format!("{}.aios-replaced-{short}", entry.source);
+    super::run(
+        "btrfs",
+        &["subvolume", "snapshot", &entry.snapshot, &restored],
+    )?;
+    if let Err(e) = fs::rename(&entry.source, &replaced) {
+        let _ = super::run("btrfs", &["subvolume", "delete", &restored]);
+        return Err(e).with_context(|| format!("cannot move {} aside", entry.source));
+    }
+    if let Err(e) = fs::rename(&restored, &entry.source) {
+        let _ = fs::rename(&replaced, &entry.source);
+        return Err(e).with_context(|| format!("cannot put the snapshot at {}", entry.source));
+    }
+    if let Err(e) = super::run("btrfs", &["subvolume", "delete", &replaced]) {
+        warn!(
+            "Rolled back {}, but could not delete {replaced}: {e}",
+            entry.source
+        );
+    }
+    Ok(())
+}
+
+fn rollback_lvm(entry: &SnapshotEntry) -> Result<()> {
+    let mounted = super::run(
+        "findmnt",
+        &["-n", "-r", "-o", "TARGET", "--source", &entry.source],
+    )
+    .unwrap_or_default();
+    let mountpoints: Vec<String> = mounted
+        .lines()
+        .map(|l| l.trim().replace("\\x20", " "))
+        .filter(|l| !l.is_empty())
+        .collect();
+    if let Some(protected) = mountpoints.iter().find(|m| super::is_protected(m)) {
+        anyhow::bail!(
+            "{} holds {protected} and cannot be rolled back while running",
+            entry.source
+        );
+    }
+    for mountpoint in &mountpoints {
+        super::run("umount", &[mountpoint])?;
+    }
+
+    let merged = super::run("lvconvert", &["--merge", "--yes", &entry.snapshot]).and_then(|_| {
+        if mountpoints.is_empty() {
+            return Ok(String::new());
+        }
+        // A merge into a volume that was open starts on its next activation
+        super::run("lvchange", &["-an", &entry.source])?;
+        super::run("lvchange", &["-ay", &entry.source])
+    });
+
+    for mountpoint in &mountpoints {
+        if let Err(e) = super::run("mount", &[&entry.source, mountpoint]) {
+            warn!("Could not remount {} at {mountpoint}: {e}", entry.source);
+        }
+    }
+    merged.map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tool_output() {
+        assert_eq!(
+            parse_lvs("  vg0|models|-wi-ao----\n"),
+            Some(("vg0".into(), "models".into(), "-wi-ao----".into()))
+        );
+        assert_eq!(parse_lvs("\n"), None);
+
+        let (source, fstype, target) =
+            parse_findmnt("/dev/sda2[/@data] btrfs /var/lib/my\\x20data\n").unwrap();
+        assert_eq!(source, "/dev/sda2");
+        assert_eq!(fstype, "btrfs");
+        assert_eq!(target, "/var/lib/my data");
+    }
+
+    #[test]
+    fn test_lvm_size() {
+        assert_eq!(lvm_size("10%ORIGIN").unwrap(), ("-l", "10%ORIGIN".into()));
+        assert_eq!(lvm_size("2G").unwrap(), ("-L", "2G".into()));
+        assert!(lvm_size("10%DISK").is_err());
+        assert!(lvm_size("2GB").is_err());
+        assert!(lvm_size("").is_err());
+    }
+
+    #[test]
+    fn test_index_lookup_and_forget() {
+        let dir = tempfile::tempdir().unwrap();
+        let snapshots = Snapshots::new(dir.path());
+        assert!(snapshots.list().unwrap().is_empty());
+
+        let entry = |id: &str, created_at| SnapshotEntry {
+            id: id.into(),
+            backend: Backend::Btrfs,
+            source: "/var/lib/aios/models".into(),
+            snapshot: format!("/var/.aios-snapshots/models-{id}"),
+            label: None,
+            created_at,
+        };
+        snapshots
+            .save_index(&[entry("first", 1), entry("second", 2)])
+            .unwrap();
+
+        // The newest snapshot of a source, or one by id
+        assert_eq!(snapshots.get("/var/lib/aios/models").unwrap().id, "second");
+        assert_eq!(snapshots.get("first").unwrap().created_at, 1);
+        assert!(snapshots.get("/srv").is_err());
+
+        snapshots.forget("second").unwrap();
+        assert_eq!(snapshots.get("/var/lib/aios/models").unwrap().id, "first");
+        assert!(!snapshots.get("first").unwrap().present());
+    }
+}
@@ -0,0 +1,30 @@
+//! disk.snapshot_create — Point-in-time snapshot of a btrfs subvolume or
+//! LVM logical volume
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use super::snapshot::Snapshots;
+
+#[derive(Deserialize)]
+struct Input {
+    /// Directory on a btrfs subvolume or LVM volume, or a logical volume's
+    /// device
+    target: String,
+    /// Shown in disk.snapshot_list
+    #[serde(default)]
+    label: Option<String>,
+    /// Copy-on-write space of an LVM snapshot, such as "2G" or "10%ORIGIN"
+    #[serde(default)]
+    size: Option<String>,
+}
+
+pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
+    let input: Input = serde_json::from_slice(input).context("Invalid JSON input")?;
+    let entry = Snapshots::open_default().create(
+        &input.target,
+        input.label.filter(|l| !l.is_empty()),
+        input.size.as_deref(),
+    )?;
+    serde_json::to_vec(&entry).context("Failed to serialize output")
+}
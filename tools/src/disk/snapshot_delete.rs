@@ -0,0 +1,28 @@
+//! disk.snapshot_delete — Remove a snapshot that is no longer needed
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use super::snapshot::{SnapshotEntry, Snapshots};
+
+#[derive(Deserialize)]
+struct Input {
+    /// Snapshot id
+    id: String,
+}
+
+#[derive(Serialize)]
+struct Output {
+    deleted: bool,
+    snapshot: SnapshotEntry,
+}
+
+pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
+    let input: Input = serde_json::from_slice(input).context("Invalid JSON input")?;
+    let snapshot = Snapshots::open_default().delete(&input.id)?;
+    let output = Output {
+        deleted: true,
+        snapshot,
+    };
+    serde_json::to_vec(&output).context("Failed to serialize output")
+}
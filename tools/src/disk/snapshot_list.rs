@@ -0,0 +1,46 @@
+//! disk.snapshot_list — Snapshots taken by disk.snapshot_create
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use super::snapshot::{SnapshotEntry, Snapshots};
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Input {
+    /// Only snapshots of this subvolume or logical volume
+    source: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Listed {
+    #[serde(flatten)]
+    entry: SnapshotEntry,
+    /// False once the snapshot was removed outside aiOS, or an LVM
+    /// snapshot overflowed
+    present: bool,
+}
+
+#[derive(Serialize)]
+struct Output {
+    snapshots: Vec<Listed>,
+}
+
+pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
+    let input: Input = if input.is_empty() {
+        Input::default()
+    } else {
+        serde_json::from_slice(input).context("Invalid JSON input")?
+    };
+
+    let snapshots = Snapshots::open_default()
+        .list()?
+        .into_iter()
+        .filter(|e| input.source.as_ref().is_none_or(|s| *s == e.source))
+        .map(|entry| Listed {
+            present: entry.present(),
+            entry,
+        })
+        .collect();
+    serde_json::to_vec(&Output { snapshots }).context("Failed to serialize output")
+}
@@ -0,0 +1,29 @@
+//! disk.snapshot_rollback — Return data to a snapshot, discarding every
+//! change made since
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use super::snapshot::{SnapshotEntry, Snapshots};
+
+#[derive(Deserialize)]
+struct Input {
+    /// Snapshot id, or a source to roll back to its newest snapshot
+    id: String,
+}
+
+#[derive(Serialize)]
+struct Output {
+    rolled_back: bool,
+    snapshot: SnapshotEntry,
+}
+
+pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
+    let input: Input = serde_json::from_slice(input).context("Invalid JSON input")?;
+    let snapshot = Snapshots::open_default().rollback(&input.id)?;
+    let output = Output {
+        rolled_back: true,
+        snapshot,
+    };
+    serde_json::to_vec(&output).context("Failed to serialize output")
+}
@@ -0,0 +1,83 @@
+//! disk.umount — Unmount a device or mount point, optionally dropping its
+//! fstab entry
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+struct Input {
+    /// Device (/dev/...) or mount point
+    target: String,
+    /// Detach now and clean up once the filesystem is no longer busy
+    #[serde(default)]
+    lazy: bool,
+    /// Also remove the mount point's /etc/fstab entry
+    #[serde(default)]
+    forget: bool,
+}
+
+#[derive(Serialize)]
+struct Output {
+    /// False when nothing was mounted there
+    unmounted: bool,
+    target: String,
+    forgotten: bool,
+}
+
+pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
+    let input: Input = serde_json::from_slice(input).context("Invalid JSON input")?;
+
+    let devices = super::block_devices()?;
+    let target = if input.target.trim_start().starts_with("/dev/") {
+        let path = super::device_path(&input.target)?;
+        super::find(&devices, &path)
+            .with_context(|| format!("No block device {path}"))?
+            .mountpoint
+            .clone()
+    } else {
+        Some(super::mount_point(&input.target)?)
+    };
+
+    let mut unmounted = false;
+    if let Some(target) = target.as_deref() {
+        if super::is_protected(target) {
+            anyhow::bail!("Refusing to unmount system mount point {target}");
+        }
+        if is_mounted(target) {
+            let mut args = Vec::new();
+            if input.lazy {
+                args.push("--lazy");
+            }
+            args.push(target);
+            super::run("umount", &args)?;
+            unmounted = true;
+        }
+    }
+
+    let mut forgotten = false;
+    if let (true, Some(target)) = (input.forget, target.as_deref()) {
+        let contents = std::fs::read_to_string(super::FSTAB_PATH).unwrap_or_default();
+        if let Some(updated) = super::fstab_without(&contents, target) {
+            crate::fs::write_atomically(super::FSTAB_PATH, updated.as_bytes())
+                .context("Failed to update /etc/fstab")?;
+            forgotten = true;
+        }
+    }
+
+    let output = Output {
+        unmounted,
+        target: target.unwrap_or(input.target),
+        forgotten,
+    };
+    serde_json::to_vec(&output).context("Failed to serialize output")
+}
+
+/// Whether anything is mounted at `target`, block device or not
+fn is_mounted(target: &str) -> bool {
+    let mounts = std::fs::read_to_string("/proc/self/mounts").unwrap_or_default();
+    mounts.lines().any(|line| {
+        line.split_whitespace()
+            .nth(1)
+            .is_some_and(|point| point.replace("\\040", " ").replace("\\011", "\t") == target)
+    })
+}
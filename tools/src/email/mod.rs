@@ -4,18 +4,34 @@
 
 pub mod send;
 
-use crate::registry::{make_tool, Registry};
+use serde_json::json;
+
+use crate::registry::{make_tool, with_input_schema, Registry};
 
 /// Register email tools with the registry.
 pub fn register_tools(reg: &mut Registry) {
-    reg.register_tool(make_tool(
-        "email.send",
-        "email",
-        "Send an email via SMTP. Input: {\"to\": \"recipient@email.com\", \"subject\": \"Subject line\", \"body\": \"Email body text\"}. Optional: from, reply_to, cc.",
-        vec!["email_send"],
-        "medium",
-        false,
-        false,
-        30000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "email.send",
+            "email",
+            "Send an email via SMTP. Input: {\"to\": \"recipient@email.com\", \"subject\": \"Subject line\", \"body\": \"Email body text\"}. Optional: from, reply_to, cc.",
+            vec!["email_send"],
+            "medium",
+            false,
+            false,
+            30000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "to": { "type": "string" },
+                "subject": { "type": "string" },
+                "body": { "type": "string", "description": "Plain text" },
+                "from": { "type": "string" },
+                "reply_to": { "type": "string" },
+                "cc": { "type": "string", "description": "Comma-separated addresses" }
+            },
+            "required": ["to", "subject", "body"]
+        }),
     ));
 }
@@ -1,19 +1,35 @@
 //! Tool execution pipeline
 //!
-//! Pipeline: validate input → check capabilities → rate limit → backup → execute (sandbox) → audit
+//! Pipeline: validate input → check capabilities → rate limit → sandbox profile policy
+//! → Linux capability policy → backup → execute (under the profile's limits, as the namespace's execution
+//! user for lower-risk tools) → cap output size → audit
+//! (with an environment snapshot for high-risk tools)
 
 use anyhow::Result;
 use std::collections::HashMap;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use std::time::Instant;
 use tracing::{info, warn};
 use uuid::Uuid;
 
-use crate::audit::AuditLog;
+use crate::audit::{AuditLog, ExecutionSnapshot};
 use crate::backup::BackupManager;
-use crate::capabilities::CapabilityChecker;
-use crate::proto::tools::{ExecuteRequest, ExecuteResponse};
+use crate::capabilities::{CapabilityChecker, RiskLevel, EXPERIMENTAL_CAPABILITY};
+use crate::composite::{CompositeTool, RunContext};
+use crate::linux_caps::{CapSet, LinuxCapsPolicy, LINUX_CAPS_PATH};
+use crate::output::OutputStore;
+use crate::plugin::daemon::DaemonSupervisor;
+use crate::plugin::queue::PluginQueue;
+use crate::policy_sim::Policy;
+use crate::privsep::{PrivsepPolicy, PRIVSEP_CONFIG_PATH};
+use crate::proto::tools::{ExecuteRequest, ExecuteResponse, ToolDefinition};
 use crate::registry::Registry;
+use crate::running::{
+    kill_children, supervise, tool_timeout, track_children, Outcome, Progress, RunningExecutions,
+    FAILURE_CANCELLED, FAILURE_DENIED, FAILURE_ERROR, FAILURE_INVALID_INPUT, FAILURE_RATE_LIMITED,
+    FAILURE_TIMEOUT,
+};
+use crate::sandbox::{ResourceLimits, SandboxProfiles, SANDBOX_PROFILES_PATH};
 
 /// Token bucket for rate limiting
 struct TokenBucket {
@@ -83,6 +99,59 @@ impl RateLimiter {
     }
 }
 
+/// CPU time and bytes written to storage by a tool handler's thread. Each
+/// handler runs on a thread of its own, so the thread's counters are the
+/// handler's; child processes it starts are not included.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceUsage {
+    pub cpu_time_ms: i64,
+    pub bytes_written: i64,
+}
+
+impl ResourceUsage {
+    /// Counters of the calling thread so far
+    fn of_current_thread() -> Self {
+        #[cfg(target_os = "linux")]
+        let cpu_time_ms = {
+            let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+            if unsafe { libc::getrusage(libc::RUSAGE_THREAD, &mut usage) } == 0 {
+                let ms = |t: libc::timeval| t.tv_sec * 1000 + t.tv_usec / 1000;
+                ms(usage.ru_utime) + ms(usage.ru_stime)
+            } else {
+                0
+            }
+        };
+        #[cfg(not(target_os = "linux"))]
+        let cpu_time_ms = 0;
+        let bytes_written = std::fs::read_to_string("/proc/thread-self/io")
+            .ok()
+            .and_then(|io| {
+                io.lines()
+                    .find_map(|line| line.strip_prefix("write_bytes:")?.trim().parse().ok())
+            })
+            .unwrap_or(0);
+        Self {
+            cpu_time_ms,
+            bytes_written,
+        }
+    }
+
+    /// Usage between `start` and these counters
+    fn since(self, start: Self) -> Self {
+        Self {
+            cpu_time_ms: (self.cpu_time_ms - start.cpu_time_ms).max(0),
+            bytes_written: (self.bytes_written - start.bytes_written).max(0),
+        }
+    }
+}
+
+impl std::ops::AddAssign for ResourceUsage {
+    fn add_assign(&mut self, other: Self) {
+        self.cpu_time_ms += other.cpu_time_ms;
+        self.bytes_written += other.bytes_written;
+    }
+}
+
 /// Executes tools through the full pipeline
 pub struct Executor {
     /// Map of tool name → handler function
@@ -91,17 +160,39 @@ pub struct Executor {
     capability_checker: CapabilityChecker,
     /// Rate limiter
     rate_limiter: Mutex<RateLimiter>,
+    /// Full copies of truncated outputs, served by `output.page`
+    output_store: Arc<OutputStore>,
+    /// Named sandbox profiles applied per tool
+    sandbox_profiles: SandboxProfiles,
+    /// Execution users for lower-risk tools, per namespace
+    privsep: PrivsepPolicy,
+    /// Linux capabilities granted per tool
+    linux_caps: LinuxCapsPolicy,
+    /// Executions in progress, for cancellation
+    running: Arc<RunningExecutions>,
+    /// Per-plugin limits on plugin script runs
+    plugin_queue: Arc<PluginQueue>,
+    /// Daemon plugins running in the background
+    daemons: Arc<DaemonSupervisor>,
 }
 
 /// A tool handler function
-type ToolHandler = Box<dyn Fn(&[u8]) -> Result<Vec<u8>> + Send + Sync>;
+type ToolHandler = Arc<dyn Fn(&[u8]) -> Result<Vec<u8>> + Send + Sync>;
 
 impl Executor {
     pub fn new() -> Self {
+        let sandbox_profiles = SandboxProfiles::load(SANDBOX_PROFILES_PATH);
         let mut executor = Self {
             handlers: HashMap::new(),
             capability_checker: CapabilityChecker::new(),
             rate_limiter: Mutex::new(RateLimiter::new(10.0, 50.0)),
+            output_store: Arc::new(OutputStore::new()),
+            daemons: Arc::new(DaemonSupervisor::from_profiles(&sandbox_profiles)),
+            sandbox_profiles,
+            privsep: PrivsepPolicy::load(PRIVSEP_CONFIG_PATH),
+            linux_caps: LinuxCapsPolicy::load(LINUX_CAPS_PATH),
+            running: Arc::new(RunningExecutions::new()),
+            plugin_queue: Arc::new(PluginQueue::from_env()),
         };
         executor.register_handlers();
         executor
@@ -112,394 +203,574 @@ impl Executor {
         // Filesystem tools
         self.handlers.insert(
             "fs.read".into(),
-            Box::new(|input| crate::fs::read::execute(input)),
+            Arc::new(|input| crate::fs::read::execute(input)),
         );
         self.handlers.insert(
             "fs.write".into(),
-            Box::new(|input| crate::fs::write::execute(input)),
+            Arc::new(|input| crate::fs::write::execute(input)),
         );
         self.handlers.insert(
             "fs.delete".into(),
-            Box::new(|input| crate::fs::delete::execute(input)),
+            Arc::new(|input| crate::fs::delete::execute(input)),
+        );
+        self.handlers.insert(
+            "fs.restore_deleted".into(),
+            Arc::new(|input| crate::fs::restore_deleted::execute(input)),
         );
         self.handlers.insert(
             "fs.list".into(),
-            Box::new(|input| crate::fs::list::execute(input)),
+            Arc::new(|input| crate::fs::list::execute(input)),
         );
         self.handlers.insert(
             "fs.stat".into(),
-            Box::new(|input| crate::fs::stat::execute(input)),
+            Arc::new(|input| crate::fs::stat::execute(input)),
         );
         self.handlers.insert(
             "fs.mkdir".into(),
-            Box::new(|input| crate::fs::mkdir::execute(input)),
+            Arc::new(|input| crate::fs::mkdir::execute(input)),
         );
         self.handlers.insert(
             "fs.move".into(),
-            Box::new(|input| crate::fs::move_file::execute(input)),
+            Arc::new(|input| crate::fs::move_file::execute(input)),
         );
         self.handlers.insert(
             "fs.copy".into(),
-            Box::new(|input| crate::fs::copy::execute(input)),
+            Arc::new(|input| crate::fs::copy::execute(input)),
         );
         self.handlers.insert(
             "fs.chmod".into(),
-            Box::new(|input| crate::fs::chmod::execute(input)),
+            Arc::new(|input| crate::fs::chmod::execute(input)),
         );
         self.handlers.insert(
             "fs.chown".into(),
-            Box::new(|input| crate::fs::chown::execute(input)),
+            Arc::new(|input| crate::fs::chown::execute(input)),
         );
         self.handlers.insert(
             "fs.symlink".into(),
-            Box::new(|input| crate::fs::symlink::execute(input)),
+            Arc::new(|input| crate::fs::symlink::execute(input)),
         );
         self.handlers.insert(
             "fs.search".into(),
-            Box::new(|input| crate::fs::search::execute(input)),
+            Arc::new(|input| crate::fs::search::execute(input)),
         );
         self.handlers.insert(
             "fs.disk_usage".into(),
-            Box::new(|input| crate::fs::disk_usage::execute(input)),
+            Arc::new(|input| crate::fs::disk_usage::execute(input)),
         );
 
         // Process tools
         self.handlers.insert(
             "process.list".into(),
-            Box::new(|input| crate::process::list::execute(input)),
+            Arc::new(|input| crate::process::list::execute(input)),
         );
         self.handlers.insert(
             "process.spawn".into(),
-            Box::new(|input| crate::process::spawn::execute(input)),
+            Arc::new(|input| crate::process::spawn::execute(input)),
         );
         self.handlers.insert(
             "process.kill".into(),
-            Box::new(|input| crate::process::kill::execute(input)),
+            Arc::new(|input| crate::process::kill::execute(input)),
         );
         self.handlers.insert(
             "process.info".into(),
-            Box::new(|input| crate::process::info::execute(input)),
+            Arc::new(|input| crate::process::info::execute(input)),
         );
         self.handlers.insert(
             "process.signal".into(),
-            Box::new(|input| crate::process::signal::execute(input)),
+            Arc::new(|input| crate::process::signal::execute(input)),
         );
 
         // Service tools
         self.handlers.insert(
             "service.list".into(),
-            Box::new(|input| crate::service::list::execute(input)),
+            Arc::new(|input| crate::service::list::execute(input)),
         );
         self.handlers.insert(
             "service.start".into(),
-            Box::new(|input| crate::service::start::execute(input)),
+            Arc::new(|input| crate::service::start::execute(input)),
         );
         self.handlers.insert(
             "service.stop".into(),
-            Box::new(|input| crate::service::stop::execute(input)),
+            Arc::new(|input| crate::service::stop::execute(input)),
         );
         self.handlers.insert(
             "service.restart".into(),
-            Box::new(|input| crate::service::restart::execute(input)),
+            Arc::new(|input| crate::service::restart::execute(input)),
         );
         self.handlers.insert(
             "service.status".into(),
-            Box::new(|input| crate::service::status::execute(input)),
+            Arc::new(|input| crate::service::status::execute(input)),
         );
 
         // Network tools
         self.handlers.insert(
             "net.interfaces".into(),
-            Box::new(|input| crate::net::interfaces::execute(input)),
+            Arc::new(|input| crate::net::interfaces::execute(input)),
         );
         self.handlers.insert(
             "net.ping".into(),
-            Box::new(|input| crate::net::ping::execute(input)),
+            Arc::new(|input| crate::net::ping::execute(input)),
         );
         self.handlers.insert(
             "net.dns".into(),
-            Box::new(|input| crate::net::dns::execute(input)),
+            Arc::new(|input| crate::net::dns::execute(input)),
         );
         self.handlers.insert(
             "net.http_get".into(),
-            Box::new(|input| crate::net::http_get::execute(input)),
+            Arc::new(|input| crate::net::http_get::execute(input)),
         );
         self.handlers.insert(
             "net.port_scan".into(),
-            Box::new(|input| crate::net::port_scan::execute(input)),
+            Arc::new(|input| crate::net::port_scan::execute(input)),
         );
 
         // Firewall tools
         self.handlers.insert(
             "firewall.rules".into(),
-            Box::new(|input| crate::firewall::rules::execute(input)),
+            Arc::new(|input| crate::firewall::rules::execute(input)),
         );
         self.handlers.insert(
             "firewall.add_rule".into(),
-            Box::new(|input| crate::firewall::add_rule::execute(input)),
+            Arc::new(|input| crate::firewall::add_rule::execute(input)),
         );
         self.handlers.insert(
             "firewall.delete_rule".into(),
-            Box::new(|input| crate::firewall::delete_rule::execute(input)),
+            Arc::new(|input| crate::firewall::delete_rule::execute(input)),
         );
 
         // Package tools
         self.handlers.insert(
             "pkg.install".into(),
-            Box::new(|input| crate::pkg::install::execute(input)),
+            Arc::new(|input| crate::pkg::install::execute(input)),
         );
         self.handlers.insert(
             "pkg.remove".into(),
-            Box::new(|input| crate::pkg::remove::execute(input)),
+            Arc::new(|input| crate::pkg::remove::execute(input)),
         );
         self.handlers.insert(
             "pkg.search".into(),
-            Box::new(|input| crate::pkg::search::execute(input)),
+            Arc::new(|input| crate::pkg::search::execute(input)),
         );
         self.handlers.insert(
             "pkg.update".into(),
-            Box::new(|input| crate::pkg::update::execute(input)),
+            Arc::new(|input| crate::pkg::update::execute(input)),
         );
         self.handlers.insert(
             "pkg.list_installed".into(),
-            Box::new(|input| crate::pkg::list_installed::execute(input)),
+            Arc::new(|input| crate::pkg::list_installed::execute(input)),
         );
 
         // Security tools
         self.handlers.insert(
             "sec.check_perms".into(),
-            Box::new(|input| crate::sec::check_perms::execute(input)),
+            Arc::new(|input| crate::sec::check_perms::execute(input)),
         );
         self.handlers.insert(
             "sec.audit_query".into(),
-            Box::new(|input| crate::sec::audit_query::execute(input)),
+            Arc::new(|input| crate::sec::audit_query::execute(input)),
+        );
+        self.handlers.insert(
+            "sec.audit_detail".into(),
+            Arc::new(crate::sec::audit_detail::execute),
         );
 
         // Monitor tools
         self.handlers.insert(
             "monitor.cpu".into(),
-            Box::new(|input| crate::monitor::cpu::execute(input)),
+            Arc::new(|input| crate::monitor::cpu::execute(input)),
         );
         self.handlers.insert(
             "monitor.memory".into(),
-            Box::new(|input| crate::monitor::memory::execute(input)),
+            Arc::new(|input| crate::monitor::memory::execute(input)),
         );
         self.handlers.insert(
             "monitor.disk".into(),
-            Box::new(|input| crate::monitor::disk::execute(input)),
+            Arc::new(|input| crate::monitor::disk::execute(input)),
         );
         self.handlers.insert(
             "monitor.network".into(),
-            Box::new(|input| crate::monitor::network::execute(input)),
+            Arc::new(|input| crate::monitor::network::execute(input)),
         );
         self.handlers.insert(
             "monitor.logs".into(),
-            Box::new(|input| crate::monitor::logs::execute(input)),
+            Arc::new(|input| crate::monitor::logs::execute(input)),
         );
 
         // Hardware tools
         self.handlers.insert(
             "hw.info".into(),
-            Box::new(|input| crate::hw::info::execute(input)),
+            Arc::new(|input| crate::hw::info::execute(input)),
+        );
+
+        // Block-device tools
+        self.handlers.insert(
+            "disk.list_block_devices".into(),
+            Arc::new(|input| crate::disk::list_block_devices::execute(input)),
+        );
+        self.handlers.insert(
+            "disk.mount".into(),
+            Arc::new(|input| crate::disk::mount::execute(input)),
+        );
+        self.handlers.insert(
+            "disk.umount".into(),
+            Arc::new(|input| crate::disk::umount::execute(input)),
+        );
+        self.handlers.insert(
+            "disk.format".into(),
+            Arc::new(|input| crate::disk::format::execute(input)),
+        );
+        self.handlers.insert(
+            "disk.resize_fs".into(),
+            Arc::new(|input| crate::disk::resize_fs::execute(input)),
+        );
+        self.handlers.insert(
+            "disk.snapshot_create".into(),
+            Arc::new(|input| crate::disk::snapshot_create::execute(input)),
+        );
+        self.handlers.insert(
+            "disk.snapshot_list".into(),
+            Arc::new(|input| crate::disk::snapshot_list::execute(input)),
+        );
+        self.handlers.insert(
+            "disk.snapshot_rollback".into(),
+            Arc::new(|input| crate::disk::snapshot_rollback::execute(input)),
+        );
+        self.handlers.insert(
+            "disk.snapshot_delete".into(),
+            Arc::new(|input| crate::disk::snapshot_delete::execute(input)),
         );
 
         // Web connectivity tools
         self.handlers.insert(
             "web.http_request".into(),
-            Box::new(|input| crate::web::http_request::execute(input)),
+            Arc::new(|input| crate::web::http_request::execute(input)),
         );
         self.handlers.insert(
             "web.scrape".into(),
-            Box::new(|input| crate::web::scrape::execute(input)),
+            Arc::new(|input| crate::web::scrape::execute(input)),
         );
         self.handlers.insert(
             "web.webhook".into(),
-            Box::new(|input| crate::web::webhook::execute(input)),
+            Arc::new(|input| crate::web::webhook::execute(input)),
         );
         self.handlers.insert(
             "web.download".into(),
-            Box::new(|input| crate::web::download::execute(input)),
+            Arc::new(|input| crate::web::download::execute(input)),
         );
         self.handlers.insert(
             "web.api_call".into(),
-            Box::new(|input| crate::web::api_call::execute(input)),
+            Arc::new(|input| crate::web::api_call::execute(input)),
         );
 
         // Git tools
         self.handlers.insert(
             "git.init".into(),
-            Box::new(|input| crate::git::operations::execute_init(input)),
+            Arc::new(|input| crate::git::operations::execute_init(input)),
         );
         self.handlers.insert(
             "git.clone".into(),
-            Box::new(|input| crate::git::operations::execute_clone(input)),
+            Arc::new(|input| crate::git::operations::execute_clone(input)),
         );
         self.handlers.insert(
             "git.add".into(),
-            Box::new(|input| crate::git::operations::execute_add(input)),
+            Arc::new(|input| crate::git::operations::execute_add(input)),
         );
         self.handlers.insert(
             "git.commit".into(),
-            Box::new(|input| crate::git::operations::execute_commit(input)),
+            Arc::new(|input| crate::git::operations::execute_commit(input)),
         );
         self.handlers.insert(
             "git.push".into(),
-            Box::new(|input| crate::git::operations::execute_push(input)),
+            Arc::new(|input| crate::git::operations::execute_push(input)),
         );
         self.handlers.insert(
             "git.pull".into(),
-            Box::new(|input| crate::git::operations::execute_pull(input)),
+            Arc::new(|input| crate::git::operations::execute_pull(input)),
         );
         self.handlers.insert(
             "git.branch".into(),
-            Box::new(|input| crate::git::operations::execute_branch(input)),
+            Arc::new(|input| crate::git::operations::execute_branch(input)),
         );
         self.handlers.insert(
             "git.status".into(),
-            Box::new(|input| crate::git::operations::execute_status(input)),
+            Arc::new(|input| crate::git::operations::execute_status(input)),
         );
         self.handlers.insert(
             "git.log".into(),
-            Box::new(|input| crate::git::operations::execute_log(input)),
+            Arc::new(|input| crate::git::operations::execute_log(input)),
         );
         self.handlers.insert(
             "git.diff".into(),
-            Box::new(|input| crate::git::operations::execute_diff(input)),
+            Arc::new(|input| crate::git::operations::execute_diff(input)),
         );
 
         // Code tools
         self.handlers.insert(
             "code.scaffold".into(),
-            Box::new(|input| crate::code::scaffold::execute(input)),
+            Arc::new(|input| crate::code::scaffold::execute(input)),
         );
         self.handlers.insert(
             "code.generate".into(),
-            Box::new(|input| crate::code::generate::execute(input)),
+            Arc::new(|input| crate::code::generate::execute(input)),
         );
 
         // Self-update tools
         self.handlers.insert(
             "self.inspect".into(),
-            Box::new(|input| crate::self_update::inspect::execute(input)),
+            Arc::new(|input| crate::self_update::inspect::execute(input)),
         );
         self.handlers.insert(
             "self.update".into(),
-            Box::new(|input| crate::self_update::update::execute(input)),
+            Arc::new(|input| crate::self_update::update::execute(input)),
         );
         self.handlers.insert(
             "self.rebuild".into(),
-            Box::new(|input| crate::self_update::update::execute_rebuild(input)),
+            Arc::new(|input| crate::self_update::update::execute_rebuild(input)),
         );
         self.handlers.insert(
             "self.health".into(),
-            Box::new(|input| crate::self_update::inspect::execute_health(input)),
+            Arc::new(|input| crate::self_update::inspect::execute_health(input)),
+        );
+        self.handlers.insert(
+            "self.benchmark".into(),
+            Arc::new(crate::self_update::benchmark::execute),
         );
 
         // Plugin tools
         self.handlers.insert(
             "plugin.create".into(),
-            Box::new(|input| crate::plugin::create::execute(input)),
+            Arc::new(|input| crate::plugin::create::execute(input)),
         );
         self.handlers.insert(
             "plugin.list".into(),
-            Box::new(|input| crate::plugin::manage::execute_list(input)),
+            Arc::new(|input| crate::plugin::manage::execute_list(input)),
         );
         self.handlers.insert(
             "plugin.delete".into(),
-            Box::new(|input| crate::plugin::manage::execute_delete(input)),
+            Arc::new(|input| crate::plugin::manage::execute_delete(input)),
         );
         self.handlers.insert(
             "plugin.install_deps".into(),
-            Box::new(|input| crate::plugin::create::execute_install_deps(input)),
+            Arc::new(|input| crate::plugin::create::execute_install_deps(input)),
         );
         self.handlers.insert(
             "plugin.from_template".into(),
-            Box::new(|input| crate::plugin::templates::execute(input)),
+            Arc::new(|input| crate::plugin::templates::execute(input)),
+        );
+
+        // Composite management tools
+        self.handlers.insert(
+            "composite.define".into(),
+            Arc::new(crate::composite::manage::execute_define),
+        );
+        self.handlers.insert(
+            "composite.list".into(),
+            Arc::new(crate::composite::manage::execute_list),
+        );
+        self.handlers.insert(
+            "composite.delete".into(),
+            Arc::new(crate::composite::manage::execute_delete),
         );
 
         // Security tools (new)
         self.handlers.insert(
             "sec.grant".into(),
-            Box::new(|input| crate::sec::grant::execute(input)),
+            Arc::new(|input| crate::sec::grant::execute(input)),
         );
         self.handlers.insert(
             "sec.revoke".into(),
-            Box::new(|input| crate::sec::revoke::execute(input)),
+            Arc::new(|input| crate::sec::revoke::execute(input)),
         );
         self.handlers.insert(
             "sec.audit".into(),
-            Box::new(|input| crate::sec::audit::execute(input)),
+            Arc::new(|input| crate::sec::audit::execute(input)),
         );
         self.handlers.insert(
             "sec.scan".into(),
-            Box::new(|input| crate::sec::scan::execute(input)),
+            Arc::new(|input| crate::sec::scan::execute(input)),
+        );
+        self.handlers.insert(
+            "sec.cert_check".into(),
+            Arc::new(crate::sec::cert_check::execute),
         );
         self.handlers.insert(
             "sec.cert_generate".into(),
-            Box::new(|input| crate::sec::cert_generate::execute(input)),
+            Arc::new(|input| crate::sec::cert_generate::execute(input)),
         );
         self.handlers.insert(
             "sec.cert_rotate".into(),
-            Box::new(|input| crate::sec::cert_rotate::execute(input)),
+            Arc::new(|input| crate::sec::cert_rotate::execute(input)),
         );
         self.handlers.insert(
             "sec.file_integrity".into(),
-            Box::new(|input| crate::sec::file_integrity::execute(input)),
+            Arc::new(|input| crate::sec::file_integrity::execute(input)),
         );
         self.handlers.insert(
             "sec.scan_rootkits".into(),
-            Box::new(|input| crate::sec::scan_rootkits::execute(input)),
+            Arc::new(|input| crate::sec::scan_rootkits::execute(input)),
+        );
+        self.handlers.insert(
+            "sec.canary_plant".into(),
+            Arc::new(crate::sec::canary_plant::execute),
+        );
+        self.handlers.insert(
+            "sec.canary_list".into(),
+            Arc::new(crate::sec::canary_list::execute),
+        );
+        self.handlers.insert(
+            "sec.canary_remove".into(),
+            Arc::new(crate::sec::canary_remove::execute),
+        );
+        self.handlers.insert(
+            "sec.canary_check".into(),
+            Arc::new(crate::sec::canary_check::execute),
         );
 
         // Monitor tools (new)
         self.handlers.insert(
             "monitor.ebpf_trace".into(),
-            Box::new(|input| crate::monitor::ebpf::execute(input)),
+            Arc::new(|input| crate::monitor::ebpf::execute(input)),
         );
         self.handlers.insert(
             "monitor.fs_watch".into(),
-            Box::new(|input| crate::monitor::fs_events::execute(input)),
+            Arc::new(|input| crate::monitor::fs_events::execute(input)),
         );
 
         // Process tools (new)
         self.handlers.insert(
             "process.cgroup".into(),
-            Box::new(|input| crate::process::cgroup::execute(input)),
+            Arc::new(|input| crate::process::cgroup::execute(input)),
         );
 
         // Email tools
         self.handlers.insert(
             "email.send".into(),
-            Box::new(|input| crate::email::send::execute(input)),
+            Arc::new(|input| crate::email::send::execute(input)),
+        );
+
+        // Hashing tools
+        self.handlers
+            .insert("hash.file".into(), Arc::new(crate::hash::file::execute));
+        self.handlers
+            .insert("hash.string".into(), Arc::new(crate::hash::string::execute));
+        self.handlers
+            .insert("hash.verify".into(), Arc::new(crate::hash::verify::execute));
+
+        // Structured data tools
+        self.handlers
+            .insert("data.jq".into(), Arc::new(crate::data::jq::execute));
+        self.handlers.insert(
+            "data.merge_patch".into(),
+            Arc::new(crate::data::merge_patch::execute),
+        );
+        self.handlers.insert(
+            "data.convert".into(),
+            Arc::new(crate::data::convert::execute),
+        );
+
+        // Text-processing tools
+        self.handlers
+            .insert("text.grep".into(), Arc::new(crate::text::grep::execute));
+        self.handlers
+            .insert("text.split".into(), Arc::new(crate::text::split::execute));
+        self.handlers
+            .insert("text.diff".into(), Arc::new(crate::text::diff::execute));
+        self.handlers
+            .insert("text.count".into(), Arc::new(crate::text::count::execute));
+
+        // Calculation tools
+        self.handlers
+            .insert("calc.eval".into(), Arc::new(crate::calc::eval::execute));
+
+        // Template tools
+        self.handlers.insert(
+            "template.render".into(),
+            Arc::new(crate::template::render::execute),
+        );
+
+        // Output paging
+        let output_store = self.output_store.clone();
+        self.handlers.insert(
+            "output.page".into(),
+            Arc::new(move |input| output_store.execute_page(input)),
         );
 
         // Container tools
         self.handlers.insert(
             "container.create".into(),
-            Box::new(|input| crate::container::create::execute(input)),
+            Arc::new(|input| crate::container::create::execute(input)),
         );
         self.handlers.insert(
             "container.start".into(),
-            Box::new(|input| crate::container::start::execute(input)),
+            Arc::new(|input| crate::container::start::execute(input)),
         );
         self.handlers.insert(
             "container.stop".into(),
-            Box::new(|input| crate::container::stop::execute(input)),
+            Arc::new(|input| crate::container::stop::execute(input)),
         );
         self.handlers.insert(
             "container.list".into(),
-            Box::new(|input| crate::container::list::execute(input)),
+            Arc::new(|input| crate::container::list::execute(input)),
         );
         self.handlers.insert(
             "container.exec".into(),
-            Box::new(|input| crate::container::exec::execute(input)),
+            Arc::new(|input| crate::container::exec::execute(input)),
         );
         self.handlers.insert(
             "container.logs".into(),
-            Box::new(|input| crate::container::logs::execute(input)),
+            Arc::new(|input| crate::container::logs::execute(input)),
         );
     }
 
+    /// Cap an output at the tool's size limit, retaining the full output for paging
+    pub fn limit_output(&self, tool_name: &str, execution_id: &str, output: Vec<u8>) -> Vec<u8> {
+        self.output_store.limit(tool_name, execution_id, output)
+    }
+
+    /// Sandbox profile name and limits a tool runs under, if any
+    pub fn sandbox_limits(&self, tool: &ToolDefinition) -> Option<(&str, &ResourceLimits)> {
+        self.sandbox_profiles.resolve(tool)
+    }
+
+    /// Executions in progress, shared with the Cancel RPC, which must not
+    /// wait for the registry lock a running execution holds
+    pub fn running(&self) -> Arc<RunningExecutions> {
+        self.running.clone()
+    }
+
+    /// Queue plugin script runs wait in for a slot of their plugin
+    pub fn plugin_queue(&self) -> Arc<PluginQueue> {
+        self.plugin_queue.clone()
+    }
+
+    /// Supervisor of the daemon plugins
+    pub fn daemons(&self) -> Arc<DaemonSupervisor> {
+        self.daemons.clone()
+    }
+
+    /// Limits of a named sandbox profile
+    pub fn sandbox_profile(&self, name: &str) -> Option<&ResourceLimits> {
+        self.sandbox_profiles.get(name)
+    }
+
+    /// The capability and sandbox path policies executions are checked
+    /// against, for the policy simulator
+    pub fn policy(&self) -> Policy {
+        Policy {
+            capabilities: self.capability_checker.clone(),
+            sandbox: self.sandbox_profiles.clone(),
+            approvals: Vec::new(),
+        }
+    }
+
+    /// A tool definition as reported to clients, with its effective sandbox profile
+    pub fn describe(&self, mut tool: ToolDefinition) -> ToolDefinition {
+        if let Some((name, _)) = self.sandbox_profiles.resolve(&tool) {
+            tool.sandbox_profile = name.to_string();
+        }
+        tool
+    }
+
     /// Execute a tool through the full pipeline
     pub async fn execute(
         &self,
@@ -516,6 +787,90 @@ impl Executor {
             .get_tool(&request.tool_name)
             .ok_or_else(|| anyhow::anyhow!("Unknown tool: {}", request.tool_name))?;
 
+        // Experimental tools run only for goals that opted in to them, and
+        // for agents allowed to try them
+        if tool_def.experimental
+            && !request.allow_experimental
+            && !self
+                .capability_checker
+                .has_capability(&request.agent_id, EXPERIMENTAL_CAPABILITY)
+        {
+            warn!(
+                "Experimental tool denied: agent={} tool={}",
+                request.agent_id, request.tool_name
+            );
+            audit_log.record(
+                &execution_id,
+                &request.tool_name,
+                &request.agent_id,
+                &request.task_id,
+                &request.reason,
+                false,
+                start.elapsed().as_millis() as i64,
+            );
+            return Ok(ExecuteResponse {
+                success: false,
+                output_json: vec![],
+                error: format!(
+                    "{} is experimental: only goals that opt in to experimental tools may call it",
+                    request.tool_name
+                ),
+                execution_id,
+                duration_ms: start.elapsed().as_millis() as i64,
+                backup_id: String::new(),
+                failure_class: FAILURE_DENIED.to_string(),
+                progress_token: String::new(),
+                cpu_time_ms: 0,
+                bytes_written: 0,
+                experimental: true,
+            });
+        }
+
+        // Input must match the tool's declared schema
+        if let Err(e) = crate::schema::validate_input(&request.input_json, &tool_def.input_schema) {
+            warn!(
+                "Invalid input: agent={} tool={}: {e}",
+                request.agent_id, request.tool_name
+            );
+            audit_log.record(
+                &execution_id,
+                &request.tool_name,
+                &request.agent_id,
+                &request.task_id,
+                &request.reason,
+                false,
+                start.elapsed().as_millis() as i64,
+            );
+            return Ok(ExecuteResponse {
+                success: false,
+                output_json: vec![],
+                error: format!("{}: {e}", request.tool_name),
+                execution_id,
+                duration_ms: start.elapsed().as_millis() as i64,
+                backup_id: String::new(),
+                failure_class: FAILURE_INVALID_INPUT.to_string(),
+                progress_token: String::new(),
+                cpu_time_ms: 0,
+                bytes_written: 0,
+                experimental: tool_def.experimental,
+            });
+        }
+
+        // Composite tools run each step through the checks below
+        if let Some(composite) = registry.get_composite(&request.tool_name) {
+            return self
+                .execute_composite(
+                    composite,
+                    registry,
+                    audit_log,
+                    backup_manager,
+                    request,
+                    execution_id,
+                    start,
+                )
+                .await;
+        }
+
         // 2. Capability-based access control
         let cap_result = self
             .capability_checker
@@ -545,6 +900,11 @@ impl Executor {
                 execution_id,
                 duration_ms: start.elapsed().as_millis() as i64,
                 backup_id: String::new(),
+                failure_class: FAILURE_DENIED.to_string(),
+                progress_token: String::new(),
+                cpu_time_ms: 0,
+                bytes_written: 0,
+                experimental: tool_def.experimental,
             });
         }
 
@@ -566,6 +926,11 @@ impl Executor {
                     execution_id,
                     duration_ms: start.elapsed().as_millis() as i64,
                     backup_id: String::new(),
+                    failure_class: FAILURE_RATE_LIMITED.to_string(),
+                    progress_token: String::new(),
+                    cpu_time_ms: 0,
+                    bytes_written: 0,
+                    experimental: tool_def.experimental,
                 });
             }
         }
@@ -575,8 +940,93 @@ impl Executor {
             request.agent_id, request.tool_name, cap_result.risk_level
         );
 
-        // 4. Pre-execution backup if tool is reversible
-        let backup_id = if tool_def.reversible {
+        // Every record from here on names the paths the call targets
+        audit_log.attach_targets(&execution_id, &request.input_json);
+
+        // 4. Sandbox profile policy
+        let sandbox = self.sandbox_profiles.resolve(&tool_def);
+        if let Some((profile, limits)) = sandbox {
+            let required = self
+                .capability_checker
+                .required_capabilities(&request.tool_name);
+            if let Err(e) = limits.check_request(required, &request.input_json) {
+                warn!(
+                    "Sandbox profile {profile} denied: agent={} tool={}: {e}",
+                    request.agent_id, request.tool_name
+                );
+                audit_log.record(
+                    &execution_id,
+                    &request.tool_name,
+                    &request.agent_id,
+                    &request.task_id,
+                    &request.reason,
+                    false,
+                    start.elapsed().as_millis() as i64,
+                );
+                return Ok(ExecuteResponse {
+                    success: false,
+                    output_json: vec![],
+                    error: format!("Sandbox profile '{profile}' denied: {e}"),
+                    execution_id,
+                    duration_ms: start.elapsed().as_millis() as i64,
+                    backup_id: String::new(),
+                    failure_class: FAILURE_DENIED.to_string(),
+                    progress_token: String::new(),
+                    cpu_time_ms: 0,
+                    bytes_written: 0,
+                    experimental: tool_def.experimental,
+                });
+            }
+        }
+
+        // Linux capabilities the tool's child processes may hold
+        let linux_caps = match self.linux_caps.check(
+            &request.tool_name,
+            self.capability_checker
+                .required_capabilities(&request.tool_name),
+        ) {
+            Ok(caps) => caps,
+            Err(e) => {
+                warn!(
+                    "Linux capability policy denied: agent={} tool={}: {e}",
+                    request.agent_id, request.tool_name
+                );
+                audit_log.record(
+                    &execution_id,
+                    &request.tool_name,
+                    &request.agent_id,
+                    &request.task_id,
+                    &request.reason,
+                    false,
+                    start.elapsed().as_millis() as i64,
+                );
+                return Ok(ExecuteResponse {
+                    success: false,
+                    output_json: vec![],
+                    error: format!("Linux capability policy denied: {e}"),
+                    execution_id,
+                    duration_ms: start.elapsed().as_millis() as i64,
+                    backup_id: String::new(),
+                    failure_class: FAILURE_DENIED.to_string(),
+                    progress_token: String::new(),
+                    cpu_time_ms: 0,
+                    bytes_written: 0,
+                    experimental: tool_def.experimental,
+                });
+            }
+        };
+
+        // Forensic snapshot of the execution environment for risky tools
+        if matches!(cap_result.risk_level, RiskLevel::High | RiskLevel::Critical) {
+            let snapshot = ExecutionSnapshot::capture(
+                &request.input_json,
+                sandbox.map(|(profile, _)| profile),
+            );
+            audit_log.attach_snapshot(&execution_id, &snapshot);
+        }
+
+        // 5. Pre-execution backup if tool is reversible
+        let mut backup_id = if tool_def.reversible {
             let bid = backup_manager.create_backup(
                 &execution_id,
                 &request.tool_name,
@@ -587,38 +1037,84 @@ impl Executor {
             None
         };
 
-        // 5. Execute the tool (sandbox high-risk tools)
-        let result = if let Some(handler) = self.handlers.get(&request.tool_name) {
-            match handler(&request.input_json) {
-                Ok(output) => ExecuteResponse {
-                    success: true,
-                    output_json: output,
-                    error: String::new(),
-                    execution_id: execution_id.clone(),
-                    duration_ms: start.elapsed().as_millis() as i64,
-                    backup_id: backup_id.unwrap_or_default(),
-                },
-                Err(e) => ExecuteResponse {
-                    success: false,
-                    output_json: vec![],
-                    error: e.to_string(),
-                    execution_id: execution_id.clone(),
-                    duration_ms: start.elapsed().as_millis() as i64,
-                    backup_id: backup_id.unwrap_or_default(),
-                },
+        // 6. Execute the tool under its sandbox profile and execution user,
+        // until it finishes, times out or its task is cancelled. Critical
+        // sections hold off cancellation and shutdown until they end.
+        let section = tool_def.critical_section.then(|| {
+            self.running
+                .enter_critical(&execution_id, &request.tool_name)
+        });
+        let limits = sandbox.map(|(_, limits)| limits);
+        let progress = Progress::new(&request.resume_token);
+        let mut usage = ResourceUsage::default();
+        let outcome = self
+            .run_handler(
+                &tool_def,
+                &cap_result.risk_level,
+                limits,
+                linux_caps,
+                &request.input_json,
+                &execution_id,
+                &request.task_id,
+                &progress,
+                &mut usage,
+            )
+            .await;
+        let (output, error, failure_class) = match outcome {
+            Some(Outcome::Completed(Ok(output))) => {
+                if let Some(snapshot_id) =
+                    backup_manager.record_snapshot(&execution_id, &request.tool_name, &output)
+                {
+                    backup_id = Some(snapshot_id);
+                }
+                (
+                    self.limit_output(&request.tool_name, &execution_id, output),
+                    String::new(),
+                    "",
+                )
             }
-        } else {
-            ExecuteResponse {
-                success: false,
-                output_json: vec![],
-                error: format!("No handler registered for tool: {}", request.tool_name),
-                execution_id: execution_id.clone(),
-                duration_ms: start.elapsed().as_millis() as i64,
-                backup_id: String::new(),
+            Some(Outcome::Completed(Err(e))) => (vec![], e.to_string(), FAILURE_ERROR),
+            Some(Outcome::TimedOut(timeout)) => {
+                let mut error = format!("Timed out after {}ms", timeout.as_millis());
+                if section.is_some() {
+                    // The hard deadline of a critical section: undo the
+                    // partial change
+                    error.push_str(&self.roll_back_section(backup_manager, &execution_id).await);
+                }
+                (vec![], error, FAILURE_TIMEOUT)
             }
+            Some(Outcome::Cancelled) => (
+                vec![],
+                "Cancelled: the task was cancelled".to_string(),
+                FAILURE_CANCELLED,
+            ),
+            None => (
+                vec![],
+                format!("No handler registered for tool: {}", request.tool_name),
+                FAILURE_ERROR,
+            ),
+        };
+        drop(section);
+        let result = ExecuteResponse {
+            success: failure_class.is_empty(),
+            output_json: output,
+            error,
+            execution_id: execution_id.clone(),
+            duration_ms: start.elapsed().as_millis() as i64,
+            backup_id: backup_id.unwrap_or_default(),
+            failure_class: failure_class.to_string(),
+            // Where a resumable tool got to before it stopped
+            progress_token: if failure_class.is_empty() {
+                String::new()
+            } else {
+                progress.token()
+            },
+            cpu_time_ms: usage.cpu_time_ms,
+            bytes_written: usage.bytes_written,
+            experimental: tool_def.experimental,
         };
 
-        // 6. Audit log
+        // 7. Audit log
         audit_log.record(
             &execution_id,
             &request.tool_name,
@@ -631,4 +1127,370 @@ impl Executor {
 
         Ok(result)
     }
+
+    /// Roll back a critical section that overran its deadline, describing
+    /// the result for the execution's error
+    async fn roll_back_section(
+        &self,
+        backup_manager: &mut BackupManager,
+        execution_id: &str,
+    ) -> String {
+        match backup_manager.rollback(execution_id).await {
+            Ok(true) => {
+                warn!("Critical section {execution_id} overran its deadline and was rolled back");
+                "; critical section rolled back".to_string()
+            }
+            Ok(false) => "; critical section had no backup to roll back".to_string(),
+            Err(e) => {
+                warn!("Rollback of critical section {execution_id} failed: {e}");
+                format!("; rollback of critical section failed: {e}")
+            }
+        }
+    }
+
+    /// Run a tool's handler on its own thread under its sandbox limits,
+    /// execution user and Linux capabilities, until it finishes, the tool's
+    /// timeout passes or its task is cancelled; in the last two cases the
+    /// tool's child processes are killed. `None` when the tool has no
+    /// handler.
+    #[allow(clippy::too_many_arguments)]
+    async fn run_handler(
+        &self,
+        tool_def: &ToolDefinition,
+        risk: &RiskLevel,
+        limits: Option<&ResourceLimits>,
+        linux_caps: CapSet,
+        input: &[u8],
+        execution_id: &str,
+        task_id: &str,
+        progress: &Progress,
+        usage: &mut ResourceUsage,
+    ) -> Option<Outcome<Result<Vec<u8>>>> {
+        let handler = self.handlers.get(&tool_def.name)?.clone();
+        let user = self.privsep.user_for(tool_def, risk).cloned();
+        // Unprivileged users hold nothing beyond the grant; root tools are
+        // only narrowed when the policy maps capabilities for them
+        let granted = (user.is_some() || !linux_caps.is_empty()).then_some(linux_caps);
+        let limits = limits.cloned();
+        let input = input.to_vec();
+        let progress = progress.clone();
+        let execution = execution_id.to_string();
+
+        // A timed-out handler's thread is left to finish on its own once
+        // its children are killed
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let spawned = std::thread::Builder::new()
+            .name(format!("tool:{}", tool_def.name))
+            .spawn(move || {
+                let run = || {
+                    crate::linux_caps::with_granted(granted, || {
+                        crate::sandbox::with_limits(limits.as_ref(), || {
+                            track_children(&execution, || progress.enter(|| handler(&input)))
+                        })
+                    })?
+                };
+                let before = ResourceUsage::of_current_thread();
+                let result =
+                    crate::privsep::with_user(user.as_ref(), run).and_then(|output| output);
+                let _ = tx.send((result, ResourceUsage::of_current_thread().since(before)));
+            });
+        if let Err(e) = spawned {
+            return Some(Outcome::Completed(Err(anyhow::anyhow!(
+                "Failed to start tool thread: {e}"
+            ))));
+        }
+        let work = async {
+            match rx.await {
+                Ok((result, used)) => {
+                    *usage += used;
+                    result
+                }
+                Err(_) => Err(anyhow::anyhow!("Tool handler panicked")),
+            }
+        };
+
+        let outcome = supervise(
+            &self.running,
+            execution_id,
+            task_id,
+            &tool_def.name,
+            tool_timeout(tool_def.timeout_ms),
+            work,
+        )
+        .await;
+        if !matches!(outcome, Outcome::Completed(_)) {
+            let killed = kill_children(execution_id);
+            warn!(
+                "Stopped {} ({execution_id}) after {:?}: killed {killed} process groups",
+                tool_def.name, outcome
+            );
+        }
+        Some(outcome)
+    }
+
+    /// Execute a composite tool. Its steps run in order, each through the
+    /// capability, rate, sandbox and Linux capability checks of its own
+    /// tool; a failed step rolls back the reversible steps before it. The
+    /// composite gets one audit entry, and its output reports every step.
+    #[allow(clippy::too_many_arguments)]
+    async fn execute_composite(
+        &self,
+        composite: &CompositeTool,
+        registry: &Registry,
+        audit_log: &mut AuditLog,
+        backup_manager: &mut BackupManager,
+        request: ExecuteRequest,
+        execution_id: String,
+        start: Instant,
+    ) -> Result<ExecuteResponse> {
+        info!(
+            "Executing composite: agent={} tool={} steps={}",
+            request.agent_id,
+            request.tool_name,
+            composite.steps.len()
+        );
+        let tool_def = registry.get_tool(&request.tool_name).unwrap_or_default();
+        if matches!(tool_def.risk_level.as_str(), "high" | "critical") {
+            let snapshot = ExecutionSnapshot::capture(&request.input_json, None);
+            audit_log.attach_snapshot(&execution_id, &snapshot);
+        }
+        // A critical composite runs its steps as one critical section whose
+        // hard deadline is the composite's timeout
+        let section = tool_def.critical_section.then(|| {
+            self.running
+                .enter_critical(&execution_id, &request.tool_name)
+        });
+        let deadline = section
+            .as_ref()
+            .and_then(|_| tool_timeout(tool_def.timeout_ms));
+
+        let mut steps = serde_json::Map::new();
+        let mut backups: Vec<String> = Vec::new();
+        let mut result = serde_json::Value::Null;
+        let mut error = None;
+        let mut failure_class = "";
+        let mut usage = ResourceUsage::default();
+        match composite.bind_params(&request.input_json) {
+            Err(e) => {
+                error = Some(e.to_string());
+                failure_class = FAILURE_ERROR;
+            }
+            Ok(params) => {
+                let mut ctx = RunContext::new(params);
+                for i in composite.order()? {
+                    let step = &composite.steps[i];
+                    if let Some(deadline) = deadline.filter(|d| start.elapsed() >= *d) {
+                        error = Some(format!(
+                            "Critical section deadline of {}ms passed before step '{}'",
+                            deadline.as_millis(),
+                            step.id
+                        ));
+                        failure_class = FAILURE_TIMEOUT;
+                        break;
+                    }
+                    let run = match step.when.as_deref().map(|w| ctx.evaluate(w)) {
+                        Some(Err(e)) => Err((FAILURE_ERROR, e.to_string())),
+                        Some(Ok(false)) => {
+                            ctx.record(&step.id, false, true, serde_json::Value::Null);
+                            steps.insert(
+                                step.id.clone(),
+                                serde_json::json!({ "tool": step.tool, "skipped": true }),
+                            );
+                            continue;
+                        }
+                        _ => {
+                            let input = serde_json::to_vec(&ctx.render(&step.input))?;
+                            let step_execution_id = format!("{execution_id}:{}", step.id);
+                            self.run_step(
+                                registry,
+                                backup_manager,
+                                &mut backups,
+                                &request,
+                                &step.tool,
+                                &step_execution_id,
+                                &input,
+                                &mut usage,
+                            )
+                            .await
+                        }
+                    };
+                    match run {
+                        Ok(output) => {
+                            let output = serde_json::from_slice(&output).unwrap_or_else(|_| {
+                                serde_json::Value::String(
+                                    String::from_utf8_lossy(&output).into_owned(),
+                                )
+                            });
+                            ctx.record(&step.id, true, false, output.clone());
+                            steps.insert(
+                                step.id.clone(),
+                                serde_json::json!({ "tool": step.tool, "success": true }),
+                            );
+                            result = output;
+                        }
+                        Err((class, e)) => {
+                            warn!(
+                                "Composite {} step '{}' ({}) failed: {e}",
+                                request.tool_name, step.id, step.tool
+                            );
+                            steps.insert(
+                                step.id.clone(),
+                                serde_json::json!({ "tool": step.tool, "success": false, "error": e, "failure_class": class }),
+                            );
+                            error = Some(format!("Step '{}' ({}) failed: {e}", step.id, step.tool));
+                            failure_class = class;
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        // Undo completed steps, newest first
+        let mut rolled_back = Vec::new();
+        if error.is_some() {
+            for step_execution_id in backups.iter().rev() {
+                match backup_manager.rollback(step_execution_id).await {
+                    Ok(true) => rolled_back.push(step_execution_id.clone()),
+                    Ok(false) => {}
+                    Err(e) => warn!("Rollback of {step_execution_id} failed: {e}"),
+                }
+            }
+        }
+        drop(section);
+
+        let success = error.is_none();
+        let output = serde_json::json!({
+            "steps": steps,
+            "result": result,
+            "rolled_back": rolled_back,
+        });
+        let duration_ms = start.elapsed().as_millis() as i64;
+        audit_log.record(
+            &execution_id,
+            &request.tool_name,
+            &request.agent_id,
+            &request.task_id,
+            &request.reason,
+            success,
+            duration_ms,
+        );
+        Ok(ExecuteResponse {
+            success,
+            output_json: self.limit_output(
+                &request.tool_name,
+                &execution_id,
+                serde_json::to_vec(&output)?,
+            ),
+            error: error.unwrap_or_default(),
+            execution_id,
+            duration_ms,
+            backup_id: String::new(),
+            failure_class: failure_class.to_string(),
+            progress_token: String::new(),
+            cpu_time_ms: usage.cpu_time_ms,
+            bytes_written: usage.bytes_written,
+            experimental: registry
+                .get_tool(&request.tool_name)
+                .is_some_and(|tool| tool.experimental),
+        })
+    }
+
+    /// Run one composite step through its tool's checks, backing it up
+    /// into `backups` first when the tool is reversible. Fails with the
+    /// failure class and error.
+    #[allow(clippy::too_many_arguments)]
+    async fn run_step(
+        &self,
+        registry: &Registry,
+        backup_manager: &mut BackupManager,
+        backups: &mut Vec<String>,
+        request: &ExecuteRequest,
+        tool_name: &str,
+        execution_id: &str,
+        input: &[u8],
+        usage: &mut ResourceUsage,
+    ) -> std::result::Result<Vec<u8>, (&'static str, String)> {
+        let denied = |e: String| (FAILURE_DENIED, e);
+        let tool_def = registry
+            .get_tool(tool_name)
+            .ok_or_else(|| (FAILURE_ERROR, format!("Unknown tool: {tool_name}")))?;
+        crate::schema::validate_input(input, &tool_def.input_schema)
+            .map_err(|e| (FAILURE_INVALID_INPUT, format!("{tool_name}: {e}")))?;
+        let cap_result = self
+            .capability_checker
+            .check_permission(&request.agent_id, tool_name);
+        if !cap_result.allowed {
+            return Err(denied(format!(
+                "Capability denied: missing {:?}",
+                cap_result.missing_capabilities
+            )));
+        }
+        {
+            let mut limiter = self
+                .rate_limiter
+                .lock()
+                .map_err(|e| (FAILURE_ERROR, format!("Rate limiter lock error: {e}")))?;
+            if !limiter.check(&request.agent_id, tool_name) {
+                return Err((FAILURE_RATE_LIMITED, "Rate limit exceeded".to_string()));
+            }
+        }
+        let required = self.capability_checker.required_capabilities(tool_name);
+        let sandbox = self.sandbox_profiles.resolve(&tool_def);
+        if let Some((profile, limits)) = sandbox {
+            limits
+                .check_request(required, input)
+                .map_err(|e| denied(format!("Sandbox profile '{profile}' denied: {e}")))?;
+        }
+        let linux_caps = self
+            .linux_caps
+            .check(tool_name, required)
+            .map_err(|e| denied(format!("Linux capability policy denied: {e}")))?;
+
+        if tool_def.reversible {
+            backup_manager.create_backup(execution_id, tool_name, input);
+            backups.push(execution_id.to_string());
+        }
+        let _section = tool_def
+            .critical_section
+            .then(|| self.running.enter_critical(execution_id, tool_name));
+        let outcome = self
+            .run_handler(
+                &tool_def,
+                &cap_result.risk_level,
+                sandbox.map(|(_, limits)| limits),
+                linux_caps,
+                input,
+                execution_id,
+                &request.task_id,
+                &Progress::default(),
+                usage,
+            )
+            .await;
+        match outcome {
+            Some(Outcome::Completed(Ok(output))) => {
+                // A snapshot taken by a step is restored when a later one fails
+                if backup_manager
+                    .record_snapshot(execution_id, tool_name, &output)
+                    .is_some()
+                {
+                    backups.push(execution_id.to_string());
+                }
+                Ok(output)
+            }
+            Some(Outcome::Completed(Err(e))) => Err((FAILURE_ERROR, e.to_string())),
+            Some(Outcome::TimedOut(timeout)) => Err((
+                FAILURE_TIMEOUT,
+                format!("Timed out after {}ms", timeout.as_millis()),
+            )),
+            Some(Outcome::Cancelled) => Err((
+                FAILURE_CANCELLED,
+                "Cancelled: the task was cancelled".to_string(),
+            )),
+            None => Err((
+                FAILURE_ERROR,
+                format!("No handler registered for tool: {tool_name}"),
+            )),
+        }
+    }
 }
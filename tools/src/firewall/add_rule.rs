@@ -2,7 +2,8 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+
+use crate::sandbox::command;
 
 #[derive(Deserialize)]
 struct Input {
@@ -32,7 +33,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
 fn add_pf_rule(_chain: &str, rule: &str, action: &str) -> Result<bool> {
     // On macOS with PF, we add the rule to the active ruleset
     // First, get current rules
-    let current = Command::new("pfctl")
+    let current = command("pfctl")
         .args(["-s", "rules"])
         .output()
         .context("Failed to read current PF rules")?;
@@ -49,7 +50,7 @@ fn add_pf_rule(_chain: &str, rule: &str, action: &str) -> Result<bool> {
     let tmp_path = "/tmp/aios_pf_rules.conf";
     std::fs::write(tmp_path, &combined).context("Failed to write temporary PF rules file")?;
 
-    let output = Command::new("pfctl")
+    let output = command("pfctl")
         .args(["-f", tmp_path])
         .output()
         .context("Failed to reload PF rules")?;
@@ -74,7 +75,7 @@ fn add_nft_rule(chain: &str, rule: &str, action: &str) -> Result<bool> {
     // Assumes a table "filter" exists, which is the common default
     let full_rule = format!("{} {}", rule, action);
 
-    let output = Command::new("nft")
+    let output = command("nft")
         .args(["add", "rule", "inet", "filter", chain, &full_rule])
         .output()
         .context("Failed to execute nft add rule")?;
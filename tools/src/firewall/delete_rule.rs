@@ -2,7 +2,8 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+
+use crate::sandbox::command;
 
 #[derive(Deserialize)]
 struct Input {
@@ -31,7 +32,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
 fn delete_pf_rule(_chain: &str, index: u32) -> Result<bool> {
     // On macOS with PF, we need to remove a rule by its line number
     // Get current rules
-    let current = Command::new("pfctl")
+    let current = command("pfctl")
         .args(["-s", "rules"])
         .output()
         .context("Failed to read current PF rules")?;
@@ -60,7 +61,7 @@ fn delete_pf_rule(_chain: &str, index: u32) -> Result<bool> {
     std::fs::write(tmp_path, format!("{}\n", combined))
         .context("Failed to write temporary PF rules file")?;
 
-    let output = Command::new("pfctl")
+    let output = command("pfctl")
         .args(["-f", tmp_path])
         .output()
         .context("Failed to reload PF rules")?;
@@ -81,7 +82,7 @@ fn delete_pf_rule(_chain: &str, index: u32) -> Result<bool> {
 fn delete_nft_rule(chain: &str, index: u32) -> Result<bool> {
     // On Linux with nftables, we need the rule handle to delete
     // First, list rules with handles
-    let list_output = Command::new("nft")
+    let list_output = command("nft")
         .args(["-a", "list", "chain", "inet", "filter", chain])
         .output()
         .context("Failed to list nft rules with handles")?;
@@ -129,7 +130,7 @@ fn delete_nft_rule(chain: &str, index: u32) -> Result<bool> {
     };
 
     // Delete the rule by handle
-    let output = Command::new("nft")
+    let output = command("nft")
         .args([
             "delete",
             "rule",
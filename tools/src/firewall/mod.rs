@@ -7,40 +7,76 @@ pub mod add_rule;
 pub mod delete_rule;
 pub mod rules;
 
-use crate::registry::{make_tool, Registry};
+use serde_json::json;
+
+use crate::registry::{critical, make_tool, with_input_schema, Registry};
 
 /// Register every firewall tool with the registry.
 pub fn register_tools(reg: &mut Registry) {
-    reg.register_tool(make_tool(
-        "firewall.rules",
-        "firewall",
-        "List all current firewall rules",
-        vec!["firewall.read"],
-        "low",
-        true,
-        false,
-        5000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "firewall.rules",
+            "firewall",
+            "List all current firewall rules",
+            vec!["firewall.read"],
+            "low",
+            true,
+            false,
+            5000,
+        ),
+        json!({ "type": "object" }),
     ));
 
-    reg.register_tool(make_tool(
-        "firewall.add_rule",
-        "firewall",
-        "Add a new firewall rule to a chain with the specified action",
-        vec!["firewall.manage"],
-        "critical",
-        false,
-        true,
-        10000,
-    ));
+    reg.register_tool(critical(with_input_schema(
+        make_tool(
+            "firewall.add_rule",
+            "firewall",
+            "Add a new firewall rule to a chain with the specified action",
+            vec!["firewall.manage"],
+            "critical",
+            false,
+            true,
+            10000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "chain": { "type": "string", "description": "Chain such as \"input\"" },
+                "rule": {
+                    "type": "string",
+                    "description": "Match expression such as \"tcp dport 22\""
+                },
+                "action": {
+                    "type": "string",
+                    "description": "Verdict such as \"accept\" or \"drop\""
+                }
+            },
+            "required": ["chain", "rule", "action"]
+        }),
+    )));
 
-    reg.register_tool(make_tool(
-        "firewall.delete_rule",
-        "firewall",
-        "Delete a firewall rule by chain and index",
-        vec!["firewall.manage"],
-        "critical",
-        false,
-        false,
-        10000,
-    ));
+    reg.register_tool(critical(with_input_schema(
+        make_tool(
+            "firewall.delete_rule",
+            "firewall",
+            "Delete a firewall rule by chain and index",
+            vec!["firewall.manage"],
+            "critical",
+            false,
+            false,
+            10000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "chain": { "type": "string" },
+                "index": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "description": "Position in the chain, as listed by firewall.rules"
+                }
+            },
+            "required": ["chain", "index"]
+        }),
+    )));
 }
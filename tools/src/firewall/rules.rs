@@ -2,7 +2,8 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+
+use crate::sandbox::command;
 
 #[derive(Deserialize)]
 struct Input {}
@@ -38,7 +39,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
 
 fn list_pf_rules() -> Result<Vec<RuleEntry>> {
     // On macOS, use pfctl to list rules
-    let output = Command::new("pfctl")
+    let output = command("pfctl")
         .args(["-s", "rules"])
         .output()
         .context("Failed to execute pfctl. Ensure you have sufficient privileges.")?;
@@ -98,7 +99,7 @@ fn list_pf_rules() -> Result<Vec<RuleEntry>> {
 
 fn list_nft_rules() -> Result<Vec<RuleEntry>> {
     // On Linux, use nft to list rules
-    let output = Command::new("nft")
+    let output = command("nft")
         .args(["list", "ruleset"])
         .output()
         .context("Failed to execute nft. Ensure nftables is installed.")?;
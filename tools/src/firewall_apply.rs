@@ -76,7 +76,7 @@ impl FirewallApplicator {
 
     /// Detect if nftables is available
     fn detect_nftables() -> bool {
-        std::process::Command::new("nft")
+        crate::sandbox::command("nft")
             .arg("--version")
             .output()
             .map(|o| o.status.success())
@@ -1,19 +1,29 @@
-//! fs.delete — Delete a file or directory
+//! fs.delete — Delete a file or directory into the trash
 
 use anyhow::{Context, Result};
 use serde_json::json;
 use std::fs;
 use std::path::Path;
 
+use super::trash::Trash;
+
 /// Delete the entry at `path`.
 ///
 /// When `recursive` is true and the path is a directory the entire subtree is
 /// removed (`rm -rf` semantics). When `recursive` is false and the path is a
 /// non-empty directory the call fails.
 ///
-/// Input  JSON: `{ "path": "/absolute/path", "recursive": bool }`
-/// Output JSON: `{ "deleted": true }`
+/// Deleted entries go to the trash (see [`super::trash`]) and can be brought
+/// back with `fs.restore_deleted`. `permanent` skips the trash; an entry
+/// larger than the whole trash quota is only deleted with `permanent`.
+///
+/// Input  JSON: `{ "path": "/absolute/path", "recursive": bool, "permanent": bool }`
+/// Output JSON: `{ "deleted": true, "trashed": bool, "trash_id": "..." }`
 pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
+    delete(input, &Trash::open_default())
+}
+
+fn delete(input: &[u8], trash: &Trash) -> Result<Vec<u8>> {
     let v: serde_json::Value =
         serde_json::from_slice(input).context("fs.delete: invalid JSON input")?;
 
@@ -27,26 +37,108 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
         .and_then(|r| r.as_bool())
         .unwrap_or(false);
 
+    let permanent = v
+        .get("permanent")
+        .and_then(|p| p.as_bool())
+        .unwrap_or(false);
+
     let p = Path::new(path);
 
-    if !p.exists() {
+    if fs::symlink_metadata(p).is_err() {
         anyhow::bail!("fs.delete: path does not exist: {path}");
     }
 
-    if p.is_dir() {
-        if recursive {
+    let is_dir = p.is_dir() && !p.is_symlink();
+    if is_dir && !recursive {
+        let empty = fs::read_dir(p)
+            .with_context(|| format!("fs.delete: failed to read directory {path}"))?
+            .next()
+            .is_none();
+        if !empty {
+            anyhow::bail!("fs.delete: failed to remove directory {path} (is it empty?)");
+        }
+    }
+
+    let trashed = if permanent || trash.contains(p) {
+        None
+    } else {
+        Some(trash.put(p).with_context(|| {
+            format!("fs.delete: failed to move {path} to the trash (set permanent to skip it)")
+        })?)
+    };
+
+    if trashed.is_none() {
+        if is_dir {
             fs::remove_dir_all(path)
                 .with_context(|| format!("fs.delete: failed to recursively remove {path}"))?;
         } else {
-            fs::remove_dir(path).with_context(|| {
-                format!("fs.delete: failed to remove directory {path} (is it empty?)")
-            })?;
+            fs::remove_file(path)
+                .with_context(|| format!("fs.delete: failed to remove file {path}"))?;
         }
-    } else {
-        fs::remove_file(path)
-            .with_context(|| format!("fs.delete: failed to remove file {path}"))?;
     }
 
-    let output = json!({ "deleted": true });
+    let output = json!({
+        "deleted": true,
+        "trashed": trashed.is_some(),
+        "trash_id": trashed.map(|entry| entry.id).unwrap_or_default(),
+    });
     serde_json::to_vec(&output).context("fs.delete: failed to serialise output")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::trash::TrashConfig;
+
+    fn run(input: serde_json::Value, trash: &Trash) -> Result<serde_json::Value> {
+        let out = delete(&serde_json::to_vec(&input).unwrap(), trash)?;
+        Ok(serde_json::from_slice(&out).unwrap())
+    }
+
+    #[test]
+    fn test_delete_goes_to_trash_unless_permanent() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash = Trash::new(dir.path().join("trash"), TrashConfig::default());
+        let file = dir.path().join("report.txt");
+        fs::write(&file, "q3").unwrap();
+
+        let out = run(json!({ "path": file }), &trash).unwrap();
+        assert_eq!(out["trashed"], true);
+        assert!(!file.exists());
+        assert_eq!(trash.list().unwrap()[0].id, out["trash_id"]);
+
+        fs::write(&file, "q4").unwrap();
+        let out = run(json!({ "path": file, "permanent": true }), &trash).unwrap();
+        assert_eq!(out["trashed"], false);
+        assert!(!file.exists());
+        assert_eq!(trash.list().unwrap().len(), 1);
+
+        let full = dir.path().join("full");
+        fs::create_dir_all(&full).unwrap();
+        fs::write(full.join("a"), "a").unwrap();
+        assert!(run(json!({ "path": full }), &trash).is_err());
+        assert!(full.exists());
+        run(json!({ "path": full, "recursive": true }), &trash).unwrap();
+        assert!(!full.exists());
+    }
+
+    #[test]
+    fn test_oversized_delete_needs_permanent() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = TrashConfig {
+            quota_mb: 1,
+            ..Default::default()
+        };
+        let trash = Trash::new(dir.path().join("trash"), config);
+        let big = dir.path().join("disk.img");
+        fs::write(&big, vec![0u8; 2 * 1024 * 1024]).unwrap();
+
+        let err = run(json!({ "path": big }), &trash).unwrap_err();
+        assert!(format!("{err:#}").contains("more than the whole trash quota"));
+        assert!(big.exists());
+
+        let out = run(json!({ "path": big, "permanent": true }), &trash).unwrap();
+        assert_eq!(out["trashed"], false);
+        assert!(!big.exists());
+    }
+}
@@ -1,5 +1,6 @@
-//! Filesystem tools — read, write, delete, list, stat, mkdir, move, copy,
-//! chmod, chown, symlink, search, and disk_usage.
+//! Filesystem tools — read, write, delete, restore_deleted, list, stat,
+//! mkdir, move, copy, chmod, chown, symlink, search, and disk_usage.
+//! Deletions go to a quota-managed trash (see [`trash`]).
 //!
 //! Each submodule exposes `pub fn execute(input: &[u8]) -> Result<Vec<u8>>`
 //! which deserialises JSON input, performs the operation, and returns JSON output.
@@ -13,155 +14,342 @@ pub mod list;
 pub mod mkdir;
 pub mod move_file;
 pub mod read;
+pub mod restore_deleted;
 pub mod search;
 pub mod stat;
 pub mod symlink;
+pub mod trash;
 pub mod write;
 
-use crate::registry::{make_tool, Registry};
+use anyhow::{Context, Result};
+use serde_json::json;
+use std::path::Path;
+
+use crate::registry::{make_tool, with_input_schema, Registry};
+
+/// Write via a sibling temp file and rename so readers never see a partial file.
+/// Missing parent directories are created.
+pub(crate) fn write_atomically(path: &str, data: &[u8]) -> Result<()> {
+    let target = Path::new(path);
+    if let Some(parent) = target.parent() {
+        if !parent.as_os_str().is_empty() && !parent.exists() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("cannot create parent dirs for {path}"))?;
+        }
+    }
+    let tmp = format!("{path}.aios-tmp");
+    std::fs::write(&tmp, data).with_context(|| format!("failed to write {tmp}"))?;
+    std::fs::rename(&tmp, target).with_context(|| format!("failed to replace {path}"))
+}
 
 /// Register every filesystem tool with the registry.
 pub fn register_tools(reg: &mut Registry) {
-    reg.register_tool(make_tool(
-        "fs.read",
-        "fs",
-        "Read file contents and return them as a UTF-8 string",
-        vec!["fs.read"],
-        "low",
-        true,
-        false,
-        5000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "fs.read",
+            "fs",
+            "Read file contents. Text is returned as UTF-8 and binary data as base64 (encoding: auto|utf8|base64). Supports offset/length ranges and hash_only for a SHA-256 without content.",
+            vec!["fs.read"],
+            "low",
+            true,
+            false,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "path": { "type": "string", "description": "Absolute path" },
+                "encoding": {
+                    "type": "string",
+                    "enum": ["auto", "utf8", "base64"],
+                    "default": "auto"
+                },
+                "offset": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "description": "Byte offset to start at"
+                },
+                "length": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "description": "Bytes to read; at most 8 MiB per call"
+                },
+                "hash_only": {
+                    "type": "boolean",
+                    "description": "Return the SHA-256 of the range instead of its content"
+                }
+            },
+            "required": ["path"]
+        }),
+    ));
+
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "fs.write",
+            "fs",
+            "Write content to a file, creating it if it does not exist. Backs up the original first. Set encoding to base64 for binary data.",
+            vec!["fs.write"],
+            "medium",
+            false,
+            true,
+            10000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "path": { "type": "string", "description": "Absolute path" },
+                "content": { "type": "string" },
+                "encoding": { "type": "string", "enum": ["utf8", "base64"], "default": "utf8" }
+            },
+            "required": ["path", "content"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "fs.write",
-        "fs",
-        "Write content to a file, creating it if it does not exist. Backs up the original first.",
-        vec!["fs.write"],
-        "medium",
-        false,
-        true,
-        10000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "fs.delete",
+            "fs",
+            "Delete a file or directory into the trash, where it is kept for the retention window. Supports recursive deletion; set permanent to skip the trash.",
+            vec!["fs.delete"],
+            "high",
+            false,
+            false,
+            10000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "path": { "type": "string", "description": "Absolute path" },
+                "recursive": { "type": "boolean" },
+                "permanent": { "type": "boolean", "description": "Skip the trash" }
+            },
+            "required": ["path"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "fs.delete",
-        "fs",
-        "Delete a file or directory. Supports recursive deletion.",
-        vec!["fs.delete"],
-        "high",
-        false,
-        false,
-        10000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "fs.restore_deleted",
+            "fs",
+            "Restore a file or directory from the trash by trash id or original path, optionally to another destination. With list (or no id or path), list the trash.",
+            vec!["fs.write"],
+            "high",
+            false,
+            false,
+            30000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "id": { "type": "string", "description": "Trash entry id" },
+                "path": {
+                    "type": "string",
+                    "description": "Original path; restores the newest entry deleted from it"
+                },
+                "destination": {
+                    "type": "string",
+                    "description": "Restore somewhere other than the original path"
+                },
+                "overwrite": { "type": "boolean" },
+                "list": { "type": "boolean", "description": "List the trash instead of restoring" }
+            }
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "fs.list",
-        "fs",
-        "List directory contents with name, type, size, and last-modified timestamp",
-        vec!["fs.read"],
-        "low",
-        true,
-        false,
-        5000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "fs.list",
+            "fs",
+            "List directory contents with name, type, size, and last-modified timestamp",
+            vec!["fs.read"],
+            "low",
+            true,
+            false,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": { "path": { "type": "string", "description": "Absolute path" } },
+            "required": ["path"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "fs.stat",
-        "fs",
-        "Return file metadata: size, permissions, timestamps, and type flags",
-        vec!["fs.read"],
-        "low",
-        true,
-        false,
-        5000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "fs.stat",
+            "fs",
+            "Return file metadata: size, permissions, timestamps, and type flags",
+            vec!["fs.read"],
+            "low",
+            true,
+            false,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": { "path": { "type": "string", "description": "Absolute path" } },
+            "required": ["path"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "fs.mkdir",
-        "fs",
-        "Create a directory. Supports recursive creation of parent directories.",
-        vec!["fs.write"],
-        "low",
-        true,
-        true,
-        5000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "fs.mkdir",
+            "fs",
+            "Create a directory. Supports recursive creation of parent directories.",
+            vec!["fs.write"],
+            "low",
+            true,
+            true,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "path": { "type": "string", "description": "Absolute path" },
+                "recursive": { "type": "boolean" }
+            },
+            "required": ["path"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "fs.move",
-        "fs",
-        "Move or rename a file or directory",
-        vec!["fs.write", "fs.delete"],
-        "medium",
-        false,
-        true,
-        10000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "fs.move",
+            "fs",
+            "Move or rename a file or directory",
+            vec!["fs.write", "fs.delete"],
+            "medium",
+            false,
+            true,
+            10000,
+        ),
+        json!({
+            "type": "object",
+            "properties": { "source": { "type": "string" }, "destination": { "type": "string" } },
+            "required": ["source", "destination"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "fs.copy",
-        "fs",
-        "Copy a file to a new location",
-        vec!["fs.read", "fs.write"],
-        "medium",
-        false,
-        true,
-        30000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "fs.copy",
+            "fs",
+            "Copy a file to a new location",
+            vec!["fs.read", "fs.write"],
+            "medium",
+            false,
+            true,
+            30000,
+        ),
+        json!({
+            "type": "object",
+            "properties": { "source": { "type": "string" }, "destination": { "type": "string" } },
+            "required": ["source", "destination"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "fs.chmod",
-        "fs",
-        "Change file permissions using an octal mode string",
-        vec!["fs.write"],
-        "high",
-        true,
-        true,
-        5000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "fs.chmod",
+            "fs",
+            "Change file permissions using an octal mode string",
+            vec!["fs.write"],
+            "high",
+            true,
+            true,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "path": { "type": "string", "description": "Absolute path" },
+                "mode": { "type": "string", "description": "Octal mode such as \"0755\"" }
+            },
+            "required": ["path", "mode"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "fs.chown",
-        "fs",
-        "Change file ownership (uid / gid)",
-        vec!["fs.admin"],
-        "critical",
-        true,
-        true,
-        5000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "fs.chown",
+            "fs",
+            "Change file ownership (uid / gid)",
+            vec!["fs.admin"],
+            "critical",
+            true,
+            true,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "path": { "type": "string", "description": "Absolute path" },
+                "uid": { "type": "integer", "minimum": 0 },
+                "gid": { "type": "integer", "minimum": 0 }
+            },
+            "required": ["path", "uid", "gid"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "fs.symlink",
-        "fs",
-        "Create a symbolic link pointing to a target path",
-        vec!["fs.write"],
-        "medium",
-        false,
-        true,
-        5000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "fs.symlink",
+            "fs",
+            "Create a symbolic link pointing to a target path",
+            vec!["fs.write"],
+            "medium",
+            false,
+            true,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "target": { "type": "string", "description": "Path the link points to" },
+                "link": { "type": "string", "description": "Path of the new link" }
+            },
+            "required": ["target", "link"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "fs.search",
-        "fs",
-        "Search for files matching a glob pattern under a directory tree",
-        vec!["fs.read"],
-        "low",
-        true,
-        false,
-        30000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "fs.search",
+            "fs",
+            "Search for files matching a glob pattern under a directory tree",
+            vec!["fs.read"],
+            "low",
+            true,
+            false,
+            30000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "directory": { "type": "string" },
+                "pattern": { "type": "string", "description": "Glob such as \"*.log\"" },
+                "max_depth": { "type": "integer", "minimum": 0, "description": "0 = unlimited" }
+            },
+            "required": ["directory", "pattern"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "fs.disk_usage",
-        "fs",
-        "Report disk usage (total, used, available) for the filesystem containing a path",
-        vec!["fs.read"],
-        "low",
-        true,
-        false,
-        5000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "fs.disk_usage",
+            "fs",
+            "Report disk usage (total, used, available) for the filesystem containing a path",
+            vec!["fs.read"],
+            "low",
+            true,
+            false,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": { "path": { "type": "string" } },
+            "required": ["path"]
+        }),
     ));
 }
@@ -68,7 +68,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
 }
 
 /// Recursively copy a directory tree.
-fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<()> {
+pub(crate) fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<()> {
     fs::create_dir_all(dst)?;
 
     for entry_result in fs::read_dir(src)? {
@@ -1,13 +1,30 @@
-//! fs.read — Read file contents
+//! fs.read — Read file contents (text, binary, ranged, or hash-only)
 
 use anyhow::{Context, Result};
+use base64::Engine;
 use serde_json::json;
-use std::fs;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
 
-/// Read the file at `path` and return its contents as a UTF-8 string.
+/// Largest range returned in a single call; callers page with `offset`.
+const MAX_READ_BYTES: u64 = 8 * 1024 * 1024;
+
+/// Buffer size used when hashing without loading the file into memory.
+const HASH_CHUNK_BYTES: usize = 64 * 1024;
+
+/// Read the file at `path`.
+///
+/// Input  JSON: `{ "path": "/absolute/path", "encoding": "auto" | "utf8" | "base64",
+///                 "offset": <u64>, "length": <u64>, "hash_only": <bool> }`
+/// Output JSON: `{ "content": "...", "encoding": "utf8" | "base64", "size": <u64>,
+///                 "offset": <u64>, "length": <u64>, "eof": <bool>, "next_offset": <u64>? }`
 ///
-/// Input  JSON: `{ "path": "/absolute/path" }`
-/// Output JSON: `{ "content": "...", "size": <u64> }`
+/// `encoding` defaults to `auto`, which returns UTF-8 text when the range is
+/// valid UTF-8 and base64 otherwise; `utf8` fails on binary data instead.
+/// `size` is always the full file size. With `hash_only` the range is streamed
+/// through SHA-256 and `{ "sha256", "size", "offset", "length" }` is returned
+/// without any content.
 pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
     let v: serde_json::Value =
         serde_json::from_slice(input).context("fs.read: invalid JSON input")?;
@@ -16,16 +33,176 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
         .get("path")
         .and_then(|p| p.as_str())
         .ok_or_else(|| anyhow::anyhow!("fs.read: missing required field 'path'"))?;
+    let encoding = v.get("encoding").and_then(|e| e.as_str()).unwrap_or("auto");
+    if !matches!(encoding, "auto" | "utf8" | "base64") {
+        anyhow::bail!(
+            "fs.read: unsupported encoding '{encoding}' (expected auto, utf8, or base64)"
+        );
+    }
+    let offset = v.get("offset").and_then(|o| o.as_u64()).unwrap_or(0);
+    let requested = v.get("length").and_then(|l| l.as_u64());
+    let hash_only = v
+        .get("hash_only")
+        .and_then(|h| h.as_bool())
+        .unwrap_or(false);
 
-    let content =
-        fs::read_to_string(path).with_context(|| format!("fs.read: failed to read {path}"))?;
+    let mut file = File::open(path).with_context(|| format!("fs.read: failed to open {path}"))?;
+    let size = file
+        .metadata()
+        .with_context(|| format!("fs.read: failed to stat {path}"))?
+        .len();
+    let offset = offset.min(size);
+    file.seek(SeekFrom::Start(offset))
+        .with_context(|| format!("fs.read: failed to seek {path}"))?;
 
-    let size = content.len() as u64;
+    if hash_only {
+        let length = requested.unwrap_or(size - offset).min(size - offset);
+        let sha256 = hash_range(&mut file, length)
+            .with_context(|| format!("fs.read: failed to hash {path}"))?;
+        let output = json!({
+            "sha256": sha256,
+            "size": size,
+            "offset": offset,
+            "length": length,
+        });
+        return serde_json::to_vec(&output).context("fs.read: failed to serialise output");
+    }
 
+    let length = requested
+        .unwrap_or(size - offset)
+        .min(size - offset)
+        .min(MAX_READ_BYTES);
+    let mut buf = Vec::with_capacity(length as usize);
+    file.take(length)
+        .read_to_end(&mut buf)
+        .with_context(|| format!("fs.read: failed to read {path}"))?;
+
+    let (content, encoding) = match encoding {
+        "base64" => (
+            base64::engine::general_purpose::STANDARD.encode(&buf),
+            "base64",
+        ),
+        _ => match String::from_utf8(buf) {
+            Ok(text) => (text, "utf8"),
+            Err(_) if encoding == "utf8" => anyhow::bail!(
+                "fs.read: {path} is not valid UTF-8; use encoding \"base64\" for binary files"
+            ),
+            Err(e) => (
+                base64::engine::general_purpose::STANDARD.encode(e.into_bytes()),
+                "base64",
+            ),
+        },
+    };
+
+    let end = offset + length;
     let output = json!({
         "content": content,
+        "encoding": encoding,
         "size": size,
+        "offset": offset,
+        "length": length,
+        "eof": end >= size,
+        "next_offset": if end < size { Some(end) } else { None },
     });
 
     serde_json::to_vec(&output).context("fs.read: failed to serialise output")
 }
+
+/// SHA-256 of the next `length` bytes, read in fixed-size chunks.
+fn hash_range(file: &mut File, length: u64) -> Result<String> {
+    let mut hasher = Sha256::new();
+    let mut reader = file.take(length);
+    let mut chunk = vec![0u8; HASH_CHUNK_BYTES];
+    loop {
+        let n = reader.read(&mut chunk)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&chunk[..n]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(input: serde_json::Value) -> Result<serde_json::Value> {
+        let out = execute(&serde_json::to_vec(&input)?)?;
+        Ok(serde_json::from_slice(&out)?)
+    }
+
+    #[test]
+    fn test_read_text_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hello.txt");
+        std::fs::write(&path, "Hello, aiOS!").unwrap();
+
+        let out = read(json!({ "path": path })).unwrap();
+        assert_eq!(out["content"], "Hello, aiOS!");
+        assert_eq!(out["encoding"], "utf8");
+        assert_eq!(out["size"], 12);
+        assert_eq!(out["eof"], true);
+        assert!(out["next_offset"].is_null());
+    }
+
+    #[test]
+    fn test_read_binary_file_falls_back_to_base64() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("blob.bin");
+        let data = [0u8, 159, 146, 150, 255, 1];
+        std::fs::write(&path, data).unwrap();
+
+        let out = read(json!({ "path": path })).unwrap();
+        assert_eq!(out["encoding"], "base64");
+        let decoded = base64::engine::general_purpose::STANDARD
+            .decode(out["content"].as_str().unwrap())
+            .unwrap();
+        assert_eq!(decoded, data);
+
+        let err = read(json!({ "path": path, "encoding": "utf8" })).unwrap_err();
+        assert!(err.to_string().contains("not valid UTF-8"));
+    }
+
+    #[test]
+    fn test_read_range() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("range.txt");
+        std::fs::write(&path, "0123456789").unwrap();
+
+        let out = read(json!({ "path": path, "offset": 2, "length": 5 })).unwrap();
+        assert_eq!(out["content"], "23456");
+        assert_eq!(out["size"], 10);
+        assert_eq!(out["eof"], false);
+        assert_eq!(out["next_offset"], 7);
+
+        let out = read(json!({ "path": path, "offset": 7, "length": 100 })).unwrap();
+        assert_eq!(out["content"], "789");
+        assert_eq!(out["eof"], true);
+
+        let out =
+            read(json!({ "path": path, "offset": 4, "length": 3, "encoding": "base64" })).unwrap();
+        assert_eq!(out["content"], "NDU2");
+    }
+
+    #[test]
+    fn test_read_hash_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hash.txt");
+        std::fs::write(&path, "abc").unwrap();
+
+        let out = read(json!({ "path": path, "hash_only": true })).unwrap();
+        assert_eq!(
+            out["sha256"],
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(out["size"], 3);
+        assert!(out.get("content").is_none());
+    }
+
+    #[test]
+    fn test_read_rejects_unknown_encoding() {
+        let err = read(json!({ "path": "/etc/hostname", "encoding": "hex" })).unwrap_err();
+        assert!(err.to_string().contains("unsupported encoding"));
+    }
+}
@@ -0,0 +1,106 @@
+//! fs.restore_deleted — Bring back something fs.delete moved to the trash
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::json;
+use std::path::Path;
+
+use super::trash::Trash;
+
+#[derive(Deserialize, Default)]
+struct Input {
+    /// Trash entry to restore
+    #[serde(default)]
+    id: String,
+    /// Original path; restores the newest entry deleted from it
+    #[serde(default)]
+    path: String,
+    /// Restore somewhere other than the original path
+    #[serde(default)]
+    destination: String,
+    /// Replace whatever is at the target now
+    #[serde(default)]
+    overwrite: bool,
+    /// List the trash instead of restoring
+    #[serde(default)]
+    list: bool,
+}
+
+/// Restore a trash entry by `id`, or the newest one deleted from `path`.
+///
+/// With `list` (or neither `id` nor `path`), return the trash's entries
+/// instead.
+///
+/// Input  JSON: `{ "id": "...", "path": "/original/path", "destination": "/elsewhere", "overwrite": bool, "list": bool }`
+/// Output JSON: `{ "restored": true, "id": "...", "restored_to": "/path" }`
+///          or: `{ "entries": [...], "used_bytes": <u64>, "quota_bytes": <u64>, "retention_days": <u32> }`
+pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
+    restore(input, &Trash::open_default())
+}
+
+fn restore(input: &[u8], trash: &Trash) -> Result<Vec<u8>> {
+    let input: Input = if input.is_empty() {
+        Input::default()
+    } else {
+        serde_json::from_slice(input).context("fs.restore_deleted: invalid JSON input")?
+    };
+
+    let output = if input.list || (input.id.is_empty() && input.path.is_empty()) {
+        let entries = trash
+            .list()
+            .context("fs.restore_deleted: cannot read the trash")?;
+        let used_bytes: u64 = entries.iter().map(|e| e.size_bytes).sum();
+        json!({
+            "entries": entries,
+            "used_bytes": used_bytes,
+            "quota_bytes": trash.config().quota_bytes(),
+            "retention_days": trash.config().retention_days,
+        })
+    } else {
+        let selector = if input.id.is_empty() {
+            &input.path
+        } else {
+            &input.id
+        };
+        let destination = (!input.destination.is_empty()).then(|| Path::new(&input.destination));
+        let (entry, target) = trash
+            .restore(selector, destination, input.overwrite)
+            .context("fs.restore_deleted")?;
+        json!({
+            "restored": true,
+            "id": entry.id,
+            "original_path": entry.original_path,
+            "restored_to": target,
+        })
+    };
+    serde_json::to_vec(&output).context("fs.restore_deleted: failed to serialise output")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::trash::TrashConfig;
+
+    #[test]
+    fn test_list_and_restore_to_destination() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash = Trash::new(dir.path().join("trash"), TrashConfig::default());
+        let file = dir.path().join("app.conf");
+        std::fs::write(&file, "port = 80").unwrap();
+        let entry = trash.put(&file).unwrap();
+
+        let listed: serde_json::Value =
+            serde_json::from_slice(&restore(b"", &trash).unwrap()).unwrap();
+        assert_eq!(listed["entries"][0]["id"], entry.id.as_str());
+        assert_eq!(listed["used_bytes"], 9);
+
+        let copy = dir.path().join("restored/app.conf");
+        let input = json!({ "path": entry.original_path, "destination": copy });
+        let out: serde_json::Value =
+            serde_json::from_slice(&restore(&serde_json::to_vec(&input).unwrap(), &trash).unwrap())
+                .unwrap();
+        assert_eq!(out["restored"], true);
+        assert_eq!(std::fs::read_to_string(&copy).unwrap(), "port = 80");
+        assert!(!file.exists());
+    }
+}
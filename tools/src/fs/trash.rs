@@ -0,0 +1,447 @@
+//! Trash — deletions that can be undone
+//!
+//! `fs.delete` moves what it deletes into the trash instead of unlinking it,
+//! recording where it came from, and `fs.restore_deleted` puts it back. The
+//! trash is bounded two ways: the tool service purges entries older than
+//! the retention window every hour, and a deletion that would push the
+//! trash over its quota first purges the oldest entries to make room.
+//! Anything already in the trash, and anything deleted with
+//! `"permanent": true`, is removed outright; deleting something larger than
+//! the whole quota fails unless it is permanent.
+//!
+//! Entries live under `items/<id>` in the trash directory, with
+//! `index.json` recording their original paths. Both are readable by root
+//! only. trash.toml sets the bounds:
+//!
+//! ```toml
+//! retention_days = 7   # 0 keeps entries until the quota needs the room
+//! quota_mb = 1024
+//! ```
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Default location of the trash
+pub const TRASH_DIR: &str = "/var/lib/aios/trash";
+
+/// Default location of the trash configuration
+pub const TRASH_CONFIG_PATH: &str = "/etc/aios/trash.toml";
+
+/// How often expired entries are purged
+const PURGE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Serialises index updates across concurrent tool executions
+static INDEX_LOCK: Mutex<()> = Mutex::new(());
+
+/// trash.toml layout
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TrashConfig {
+    /// Days an entry is kept; 0 keeps it until the quota needs the room
+    pub retention_days: u32,
+    /// Total size of the trash
+    pub quota_mb: u64,
+}
+
+impl Default for TrashConfig {
+    fn default() -> Self {
+        Self {
+            retention_days: 7,
+            quota_mb: 1024,
+        }
+    }
+}
+
+impl TrashConfig {
+    /// Configuration at `path`; a missing or invalid file yields the defaults
+    pub fn load(path: &str) -> Self {
+        let Ok(contents) = fs::read_to_string(path) else {
+            return Self::default();
+        };
+        toml::from_str(&contents).unwrap_or_else(|e| {
+            warn!("Ignoring invalid trash config in {path}: {e}");
+            Self::default()
+        })
+    }
+
+    pub fn quota_bytes(&self) -> u64 {
+        self.quota_mb.saturating_mul(1024 * 1024)
+    }
+}
+
+/// Something deleted into the trash
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrashEntry {
+    pub id: String,
+    pub original_path: String,
+    /// Unix seconds
+    pub deleted_at: i64,
+    pub size_bytes: u64,
+    pub is_dir: bool,
+}
+
+/// A trash directory and its bounds
+pub struct Trash {
+    dir: PathBuf,
+    config: TrashConfig,
+}
+
+impl Trash {
+    pub fn new(dir: impl Into<PathBuf>, config: TrashConfig) -> Self {
+        Self {
+            dir: dir.into(),
+            config,
+        }
+    }
+
+    /// The trash at [`TRASH_DIR`], bounded by [`TRASH_CONFIG_PATH`]
+    pub fn open_default() -> Self {
+        Self::new(TRASH_DIR, TrashConfig::load(TRASH_CONFIG_PATH))
+    }
+
+    pub fn config(&self) -> &TrashConfig {
+        &self.config
+    }
+
+    /// Whether `path` is the trash itself or inside it
+    pub fn contains(&self, path: &Path) -> bool {
+        let dir = fs::canonicalize(&self.dir).unwrap_or_else(|_| self.dir.clone());
+        absolute(path).is_ok_and(|p| p.starts_with(&dir))
+    }
+
+    /// Move `path` into the trash, purging the oldest entries if it needs
+    /// the room. Fails, leaving it in place, when it is larger than the
+    /// whole quota.
+    pub fn put(&self, path: &Path) -> Result<TrashEntry> {
+        let original_path = absolute(path)?;
+        let metadata = fs::symlink_metadata(path)
+            .with_context(|| format!("cannot stat {}", path.display()))?;
+        let size_bytes = size_of(path)?;
+        let quota = self.config.quota_bytes();
+        if size_bytes > quota {
+            bail!(
+                "{} is {size_bytes} bytes, more than the whole trash quota of {quota}",
+                path.display()
+            );
+        }
+
+        let _lock = INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut entries = self.load_index()?;
+        let mut used: u64 = entries.iter().map(|e| e.size_bytes).sum();
+        while used + size_bytes > quota && !entries.is_empty() {
+            let oldest = entries.remove(0);
+            used = used.saturating_sub(oldest.size_bytes);
+            self.remove_item(&oldest);
+        }
+
+        let entry = TrashEntry {
+            id: uuid::Uuid::new_v4().to_string(),
+            original_path: original_path.to_string_lossy().into_owned(),
+            deleted_at: chrono::Utc::now().timestamp(),
+            size_bytes,
+            is_dir: metadata.is_dir(),
+        };
+        let moved = self
+            .items_dir()
+            .and_then(|items| move_path(path, &items.join(&entry.id)));
+        if moved.is_ok() {
+            entries.push(entry.clone());
+        }
+        // Saved either way: the purged entries are gone
+        self.save_index(&entries)?;
+        moved?;
+        Ok(entry)
+    }
+
+    /// Entries in the trash, oldest first
+    pub fn list(&self) -> Result<Vec<TrashEntry>> {
+        let _lock = INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        self.load_index()
+    }
+
+    /// Move an entry — by id, or the newest deleted from an original path —
+    /// back to its original path or to `destination`. An existing file at
+    /// the target is replaced only with `overwrite`.
+    pub fn restore(
+        &self,
+        selector: &str,
+        destination: Option<&Path>,
+        overwrite: bool,
+    ) -> Result<(TrashEntry, PathBuf)> {
+        let _lock = INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut entries = self.load_index()?;
+        let index = entries
+            .iter()
+            .position(|e| e.id == selector)
+            .or_else(|| entries.iter().rposition(|e| e.original_path == selector))
+            .ok_or_else(|| anyhow::anyhow!("nothing in the trash matches '{selector}'"))?;
+
+        let target = destination
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| PathBuf::from(&entries[index].original_path));
+        if let Ok(existing) = fs::symlink_metadata(&target) {
+            if !overwrite {
+                anyhow::bail!(
+                    "{} already exists; pass overwrite or a destination",
+                    target.display()
+                );
+            }
+            let removed = if existing.is_dir() {
+                fs::remove_dir_all(&target)
+            } else {
+                fs::remove_file(&target)
+            };
+            removed.with_context(|| format!("cannot replace {}", target.display()))?;
+        }
+        if let Some(parent) = target.parent() {
+            if !parent.as_os_str().is_empty() && !parent.exists() {
+                fs::create_dir_all(parent).with_context(|| {
+                    format!("cannot create parent dirs for {}", target.display())
+                })?;
+            }
+        }
+
+        move_path(&self.dir.join("items").join(&entries[index].id), &target)?;
+        let entry = entries.remove(index);
+        self.save_index(&entries)?;
+        Ok((entry, target))
+    }
+
+    /// Purge entries deleted more than the retention window before `now`
+    pub fn purge_expired(&self, now: i64) -> Result<Vec<TrashEntry>> {
+        if self.config.retention_days == 0 {
+            return Ok(Vec::new());
+        }
+        let cutoff = now - i64::from(self.config.retention_days) * 86400;
+        let _lock = INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let (expired, kept): (Vec<TrashEntry>, Vec<TrashEntry>) = self
+            .load_index()?
+            .into_iter()
+            .partition(|e| e.deleted_at < cutoff);
+        if expired.is_empty() {
+            return Ok(expired);
+        }
+        for entry in &expired {
+            self.remove_item(entry);
+        }
+        self.save_index(&kept)?;
+        Ok(expired)
+    }
+
+    /// Bytes held by the trash
+    pub fn used_bytes(&self) -> Result<u64> {
+        Ok(self.list()?.iter().map(|e| e.size_bytes).sum())
+    }
+
+    fn index_path(&self) -> PathBuf {
+        self.dir.join("index.json")
+    }
+
+    /// The directory entries are kept in, created root-only
+    fn items_dir(&self) -> Result<PathBuf> {
+        let items = self.dir.join("items");
+        if !items.exists() {
+            fs::create_dir_all(&items)
+                .with_context(|| format!("cannot create trash at {}", items.display()))?;
+            for dir in [&self.dir, &items] {
+                fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+            }
+        }
+        Ok(items)
+    }
+
+    fn load_index(&self) -> Result<Vec<TrashEntry>> {
+        match fs::read(self.index_path()) {
+            Ok(bytes) => serde_json::from_slice(&bytes).context("trash index is corrupt"),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e).context("cannot read trash index"),
+        }
+    }
+
+    fn save_index(&self, entries: &[TrashEntry]) -> Result<()> {
+        let bytes = serde_json::to_vec_pretty(entries)?;
+        super::write_atomically(&self.index_path().to_string_lossy(), &bytes)
+    }
+
+    fn remove_item(&self, entry: &TrashEntry) {
+        let item = self.dir.join("items").join(&entry.id);
+        let removed = if entry.is_dir {
+            fs::remove_dir_all(&item)
+        } else {
+            fs::remove_file(&item)
+        };
+        match removed {
+            Ok(()) => info!(
+                "Purged {} from the trash ({} bytes)",
+                entry.original_path, entry.size_bytes
+            ),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!("Failed to purge {} from the trash: {e}", item.display()),
+        }
+    }
+}
+
+/// Purge expired entries every [`PURGE_INTERVAL`], rereading trash.toml
+/// each time
+pub async fn run_purger() {
+    let mut interval = tokio::time::interval(PURGE_INTERVAL);
+    loop {
+        interval.tick().await;
+        let purged = tokio::task::spawn_blocking(|| {
+            Trash::open_default().purge_expired(chrono::Utc::now().timestamp())
+        })
+        .await;
+        match purged {
+            Ok(Ok(expired)) if !expired.is_empty() => {
+                info!("Purged {} expired trash entries", expired.len());
+            }
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => warn!("Trash purge failed: {e:#}"),
+            Err(e) => warn!("Trash purge panicked: {e}"),
+        }
+    }
+}
+
+/// `path` made absolute without following its last component, so a
+/// deleted symlink is recorded as the link
+fn absolute(path: &Path) -> Result<PathBuf> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("{} has no file name", path.display()))?;
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let parent =
+        fs::canonicalize(parent).with_context(|| format!("cannot resolve {}", parent.display()))?;
+    Ok(parent.join(file_name))
+}
+
+/// Bytes under `path`, not following symlinks
+fn size_of(path: &Path) -> Result<u64> {
+    let metadata = fs::symlink_metadata(path)?;
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+    let mut total = 0;
+    for entry in fs::read_dir(path)? {
+        total += size_of(&entry?.path())?;
+    }
+    Ok(total)
+}
+
+/// Rename, falling back to copy-then-delete across filesystems
+fn move_path(source: &Path, destination: &Path) -> Result<()> {
+    if fs::rename(source, destination).is_ok() {
+        return Ok(());
+    }
+    let metadata = fs::symlink_metadata(source)?;
+    let removed = if metadata.is_dir() {
+        super::move_file::copy_dir_recursive(source, destination)
+            .with_context(|| format!("cannot copy {}", source.display()))?;
+        fs::remove_dir_all(source)
+    } else if metadata.file_type().is_symlink() {
+        std::os::unix::fs::symlink(fs::read_link(source)?, destination)?;
+        fs::remove_file(source)
+    } else {
+        fs::copy(source, destination)
+            .with_context(|| format!("cannot copy {}", source.display()))?;
+        fs::remove_file(source)
+    };
+    removed.with_context(|| format!("copied but cannot remove {}", source.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trash_in(dir: &Path, quota_mb: u64) -> Trash {
+        Trash::new(
+            dir.join("trash"),
+            TrashConfig {
+                retention_days: 7,
+                quota_mb,
+            },
+        )
+    }
+
+    #[test]
+    fn test_put_and_restore() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash = trash_in(dir.path(), 1);
+        let file = dir.path().join("notes.txt");
+        fs::write(&file, "keep me").unwrap();
+        let tree = dir.path().join("site");
+        fs::create_dir_all(tree.join("css")).unwrap();
+        fs::write(tree.join("css/main.css"), "body {}").unwrap();
+
+        let entry = trash.put(&file).unwrap();
+        trash.put(&tree).unwrap();
+        assert!(!file.exists() && !tree.exists());
+        assert_eq!(entry.size_bytes, 7);
+        assert_eq!(trash.used_bytes().unwrap(), 14);
+        assert!(trash.contains(&trash.dir.join("items").join(&entry.id)));
+
+        // By original path, back where it was
+        let (_, target) = trash.restore(&tree.to_string_lossy(), None, false).unwrap();
+        assert_eq!(target, fs::canonicalize(dir.path()).unwrap().join("site"));
+        assert_eq!(
+            fs::read_to_string(tree.join("css/main.css")).unwrap(),
+            "body {}"
+        );
+
+        // An existing file is only replaced on request
+        fs::write(&file, "new").unwrap();
+        assert!(trash.restore(&entry.id, None, false).is_err());
+        trash.restore(&entry.id, None, true).unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "keep me");
+        assert!(trash.list().unwrap().is_empty());
+        assert!(trash.restore(&entry.id, None, false).is_err());
+    }
+
+    #[test]
+    fn test_quota_purges_oldest_and_refuses_oversized() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash = trash_in(dir.path(), 1);
+        let half = vec![0u8; 600 * 1024];
+        for name in ["a", "b"] {
+            fs::write(dir.path().join(name), &half).unwrap();
+            trash.put(&dir.path().join(name)).unwrap();
+        }
+        let paths: Vec<String> = trash
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|e| e.original_path)
+            .collect();
+        assert_eq!(paths.len(), 1);
+        assert!(paths[0].ends_with("/b"));
+
+        let big = dir.path().join("big");
+        fs::write(&big, vec![0u8; 2 * 1024 * 1024]).unwrap();
+        assert!(trash.put(&big).is_err());
+        assert!(big.exists());
+        assert_eq!(trash.list().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_purge_expired() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash = trash_in(dir.path(), 1);
+        let file = dir.path().join("old.log");
+        fs::write(&file, "x").unwrap();
+        let entry = trash.put(&file).unwrap();
+
+        assert!(trash.purge_expired(entry.deleted_at).unwrap().is_empty());
+        let purged = trash.purge_expired(entry.deleted_at + 8 * 86400).unwrap();
+        assert_eq!(purged, vec![entry.clone()]);
+        assert!(!trash.dir.join("items").join(&entry.id).exists());
+        assert!(trash.list().unwrap().is_empty());
+    }
+}
@@ -1,6 +1,7 @@
 //! fs.write — Write content to a file (with optional backup)
 
 use anyhow::{Context, Result};
+use base64::Engine;
 use serde_json::json;
 use std::fs;
 use std::path::Path;
@@ -11,7 +12,9 @@ use std::path::Path;
 /// overwriting so the caller can roll back manually if the backup manager is
 /// not involved.
 ///
-/// Input  JSON: `{ "path": "/absolute/path", "content": "..." }`
+/// `encoding` defaults to `utf8`; pass `base64` to write binary data.
+///
+/// Input  JSON: `{ "path": "/absolute/path", "content": "...", "encoding": "utf8" | "base64" }`
 /// Output JSON: `{ "bytes_written": <u64> }`
 pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
     let v: serde_json::Value =
@@ -27,6 +30,16 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
         .and_then(|c| c.as_str())
         .ok_or_else(|| anyhow::anyhow!("fs.write: missing required field 'content'"))?;
 
+    let bytes = match v.get("encoding").and_then(|e| e.as_str()).unwrap_or("utf8") {
+        "utf8" => content.as_bytes().to_vec(),
+        "base64" => base64::engine::general_purpose::STANDARD
+            .decode(content)
+            .context("fs.write: content is not valid base64")?,
+        other => {
+            anyhow::bail!("fs.write: unsupported encoding '{other}' (expected utf8 or base64)")
+        }
+    };
+
     // Create parent directories if they don't exist
     if let Some(parent) = Path::new(path).parent() {
         if !parent.exists() {
@@ -42,8 +55,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
             .with_context(|| format!("fs.write: failed to create backup at {backup_path}"))?;
     }
 
-    let bytes = content.as_bytes();
-    fs::write(path, bytes).with_context(|| format!("fs.write: failed to write {path}"))?;
+    fs::write(path, &bytes).with_context(|| format!("fs.write: failed to write {path}"))?;
 
     let output = json!({
         "bytes_written": bytes.len() as u64,
@@ -51,3 +63,24 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
 
     serde_json::to_vec(&output).context("fs.write: failed to serialise output")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_base64_binary() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("blob.bin");
+        let input = json!({ "path": path, "content": "AJ+Slv8B", "encoding": "base64" });
+
+        let out: serde_json::Value =
+            serde_json::from_slice(&execute(&serde_json::to_vec(&input).unwrap()).unwrap())
+                .unwrap();
+        assert_eq!(out["bytes_written"], 6);
+        assert_eq!(fs::read(&path).unwrap(), [0u8, 159, 146, 150, 255, 1]);
+
+        let bad = json!({ "path": path, "content": "not base64!", "encoding": "base64" });
+        assert!(execute(&serde_json::to_vec(&bad).unwrap()).is_err());
+    }
+}
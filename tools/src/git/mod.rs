@@ -4,117 +4,225 @@
 
 pub mod operations;
 
-use crate::registry::{make_tool, Registry};
+use serde_json::json;
+
+use crate::registry::{make_tool, with_input_schema, Registry};
 
 /// Register every git tool with the registry.
 pub fn register_tools(reg: &mut Registry) {
-    reg.register_tool(make_tool(
-        "git.init",
-        "git",
-        "Initialize a new git repository at the specified path",
-        vec!["git.write"],
-        "low",
-        false,
-        true,
-        5000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "git.init",
+            "git",
+            "Initialize a new git repository at the specified path",
+            vec!["git.write"],
+            "low",
+            false,
+            true,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": { "path": { "type": "string" }, "bare": { "type": "boolean" } },
+            "required": ["path"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "git.clone",
-        "git",
-        "Clone a remote git repository to a local path",
-        vec!["git.write", "net.read"],
-        "medium",
-        false,
-        true,
-        120000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "git.clone",
+            "git",
+            "Clone a remote git repository to a local path",
+            vec!["git.write", "net.read"],
+            "medium",
+            false,
+            true,
+            120000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "url": { "type": "string" },
+                "destination": { "type": "string" },
+                "branch": { "type": "string" },
+                "depth": { "type": "integer", "minimum": 0, "description": "0 = full history" }
+            },
+            "required": ["url", "destination"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "git.add",
-        "git",
-        "Stage files for commit in a git repository",
-        vec!["git.write"],
-        "low",
-        true,
-        true,
-        5000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "git.add",
+            "git",
+            "Stage files for commit in a git repository",
+            vec!["git.write"],
+            "low",
+            true,
+            true,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "repo_path": { "type": "string" },
+                "files": { "type": "array", "items": { "type": "string" } },
+                "all": { "type": "boolean" }
+            },
+            "required": ["repo_path"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "git.commit",
-        "git",
-        "Create a commit with the staged changes and a message",
-        vec!["git.write"],
-        "low",
-        false,
-        false,
-        10000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "git.commit",
+            "git",
+            "Create a commit with the staged changes and a message",
+            vec!["git.write"],
+            "low",
+            false,
+            false,
+            10000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "repo_path": { "type": "string" },
+                "message": { "type": "string" },
+                "author": { "type": "string", "description": "\"Name <email>\"" }
+            },
+            "required": ["repo_path", "message"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "git.push",
-        "git",
-        "Push local commits to a remote repository",
-        vec!["git.write", "net.write"],
-        "high",
-        false,
-        false,
-        60000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "git.push",
+            "git",
+            "Push local commits to a remote repository",
+            vec!["git.write", "net.write"],
+            "high",
+            false,
+            false,
+            60000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "repo_path": { "type": "string" },
+                "remote": { "type": "string", "default": "origin" },
+                "branch": { "type": "string" }
+            },
+            "required": ["repo_path"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "git.pull",
-        "git",
-        "Pull latest changes from a remote repository",
-        vec!["git.write", "net.read"],
-        "medium",
-        false,
-        false,
-        60000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "git.pull",
+            "git",
+            "Pull latest changes from a remote repository",
+            vec!["git.write", "net.read"],
+            "medium",
+            false,
+            false,
+            60000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "repo_path": { "type": "string" },
+                "remote": { "type": "string", "default": "origin" },
+                "branch": { "type": "string" }
+            },
+            "required": ["repo_path"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "git.branch",
-        "git",
-        "Create, list, or switch branches in a git repository",
-        vec!["git.write"],
-        "low",
-        true,
-        true,
-        5000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "git.branch",
+            "git",
+            "Create, list, or switch branches in a git repository",
+            vec!["git.write"],
+            "low",
+            true,
+            true,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "repo_path": { "type": "string" },
+                "action": {
+                    "type": "string",
+                    "enum": ["create", "list", "switch", "delete"],
+                    "default": "list"
+                },
+                "name": { "type": "string" }
+            },
+            "required": ["repo_path"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "git.status",
-        "git",
-        "Show the working tree status of a git repository",
-        vec!["git.read"],
-        "low",
-        true,
-        false,
-        5000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "git.status",
+            "git",
+            "Show the working tree status of a git repository",
+            vec!["git.read"],
+            "low",
+            true,
+            false,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": { "repo_path": { "type": "string" } },
+            "required": ["repo_path"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "git.log",
-        "git",
-        "Show commit history of a git repository",
-        vec!["git.read"],
-        "low",
-        true,
-        false,
-        5000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "git.log",
+            "git",
+            "Show commit history of a git repository",
+            vec!["git.read"],
+            "low",
+            true,
+            false,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "repo_path": { "type": "string" },
+                "count": { "type": "integer", "minimum": 1, "default": 10 }
+            },
+            "required": ["repo_path"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "git.diff",
-        "git",
-        "Show changes between commits, working tree, and staging area",
-        vec!["git.read"],
-        "low",
-        true,
-        false,
-        10000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "git.diff",
+            "git",
+            "Show changes between commits, working tree, and staging area",
+            vec!["git.read"],
+            "low",
+            true,
+            false,
+            10000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "repo_path": { "type": "string" },
+                "staged": { "type": "boolean" },
+                "commit": { "type": "string" }
+            },
+            "required": ["repo_path"]
+        }),
     ));
 }
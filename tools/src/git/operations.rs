@@ -2,7 +2,8 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+
+use crate::sandbox::command;
 
 // ── git.init ──────────────────────────────────────────────────────
 
@@ -31,7 +32,7 @@ pub fn execute_init(input: &[u8]) -> Result<Vec<u8>> {
     }
     args.push(&input.path);
 
-    let output = Command::new("git")
+    let output = command("git")
         .args(&args)
         .output()
         .context("Failed to execute git init")?;
@@ -89,7 +90,7 @@ pub fn execute_clone(input: &[u8]) -> Result<Vec<u8>> {
     args.push(input.url.clone());
     args.push(input.destination.clone());
 
-    let output = Command::new("git")
+    let output = command("git")
         .args(&args)
         .output()
         .context("Failed to execute git clone")?;
@@ -136,7 +137,7 @@ pub fn execute_add(input: &[u8]) -> Result<Vec<u8>> {
         args.extend(&file_refs);
     }
 
-    let output = Command::new("git")
+    let output = command("git")
         .args(&args)
         .current_dir(&input.repo_path)
         .output()
@@ -189,7 +190,7 @@ pub fn execute_commit(input: &[u8]) -> Result<Vec<u8>> {
         args.push(input.author);
     }
 
-    let output = Command::new("git")
+    let output = command("git")
         .args(&args)
         .current_dir(&input.repo_path)
         .output()
@@ -201,7 +202,7 @@ pub fn execute_commit(input: &[u8]) -> Result<Vec<u8>> {
     }
 
     // Get the commit hash
-    let hash_output = Command::new("git")
+    let hash_output = command("git")
         .args(["rev-parse", "HEAD"])
         .current_dir(&input.repo_path)
         .output()
@@ -249,7 +250,7 @@ pub fn execute_push(input: &[u8]) -> Result<Vec<u8>> {
         args.push(&input.branch);
     }
 
-    let output = Command::new("git")
+    let output = command("git")
         .args(&args)
         .current_dir(&input.repo_path)
         .output()
@@ -294,7 +295,7 @@ pub fn execute_pull(input: &[u8]) -> Result<Vec<u8>> {
         args.push(&input.branch);
     }
 
-    let output = Command::new("git")
+    let output = command("git")
         .args(&args)
         .current_dir(&input.repo_path)
         .output()
@@ -344,7 +345,7 @@ pub fn execute_branch(input: &[u8]) -> Result<Vec<u8>> {
 
     match input.action.as_str() {
         "create" => {
-            let output = Command::new("git")
+            let output = command("git")
                 .args(["branch", &input.name])
                 .current_dir(&input.repo_path)
                 .output()
@@ -356,7 +357,7 @@ pub fn execute_branch(input: &[u8]) -> Result<Vec<u8>> {
             }
         }
         "switch" | "checkout" => {
-            let output = Command::new("git")
+            let output = command("git")
                 .args(["checkout", &input.name])
                 .current_dir(&input.repo_path)
                 .output()
@@ -368,7 +369,7 @@ pub fn execute_branch(input: &[u8]) -> Result<Vec<u8>> {
             }
         }
         "delete" => {
-            let output = Command::new("git")
+            let output = command("git")
                 .args(["branch", "-d", &input.name])
                 .current_dir(&input.repo_path)
                 .output()
@@ -383,7 +384,7 @@ pub fn execute_branch(input: &[u8]) -> Result<Vec<u8>> {
     }
 
     // Always list branches and current
-    let list_output = Command::new("git")
+    let list_output = command("git")
         .args(["branch", "--list"])
         .current_dir(&input.repo_path)
         .output()
@@ -433,7 +434,7 @@ struct StatusOutput {
 pub fn execute_status(input: &[u8]) -> Result<Vec<u8>> {
     let input: StatusInput = serde_json::from_slice(input).context("Invalid JSON input")?;
 
-    let output = Command::new("git")
+    let output = command("git")
         .args(["status", "--porcelain=v1", "-b"])
         .current_dir(&input.repo_path)
         .output()
@@ -508,7 +509,7 @@ struct LogOutput {
 pub fn execute_log(input: &[u8]) -> Result<Vec<u8>> {
     let input: LogInput = serde_json::from_slice(input).context("Invalid JSON input")?;
 
-    let output = Command::new("git")
+    let output = command("git")
         .args([
             "log",
             &format!("-{}", input.count),
@@ -564,7 +565,7 @@ pub fn execute_diff(input: &[u8]) -> Result<Vec<u8>> {
         args.push(input.commit);
     }
 
-    let output = Command::new("git")
+    let output = command("git")
         .args(&args)
         .current_dir(&input.repo_path)
         .output()
@@ -0,0 +1,44 @@
+//! hash.file — Digest of a file, streamed in chunks
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+
+use super::Algorithm;
+
+#[derive(Deserialize)]
+struct Input {
+    path: String,
+    algorithm: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Output {
+    path: String,
+    algorithm: String,
+    digest: String,
+    size: u64,
+}
+
+pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
+    let input: Input = serde_json::from_slice(input).context("hash.file: invalid JSON input")?;
+    let algorithm = Algorithm::parse(input.algorithm.as_deref())?;
+
+    let file = File::open(&input.path)
+        .with_context(|| format!("hash.file: failed to open {}", input.path))?;
+    let size = file
+        .metadata()
+        .with_context(|| format!("hash.file: failed to stat {}", input.path))?
+        .len();
+    let digest = algorithm
+        .digest_reader(file)
+        .with_context(|| format!("hash.file: failed to read {}", input.path))?;
+
+    let output = Output {
+        path: input.path,
+        algorithm: algorithm.as_str().to_string(),
+        digest,
+        size,
+    };
+    serde_json::to_vec(&output).context("hash.file: failed to serialise output")
+}
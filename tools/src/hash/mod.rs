@@ -0,0 +1,229 @@
+//! Hashing tools — file, string, and verify digests (SHA256, SHA512, BLAKE3).
+//!
+//! Each submodule exposes `pub fn execute(input: &[u8]) -> Result<Vec<u8>>`.
+//! Files are hashed in fixed-size chunks so large artifacts never have to fit
+//! in memory.
+
+pub mod file;
+pub mod string;
+pub mod verify;
+
+use anyhow::Result;
+use serde_json::json;
+use sha2::{Digest, Sha256, Sha512};
+use std::io::Read;
+
+use crate::registry::{make_tool, with_input_schema, Registry};
+
+/// Read buffer size for streaming file hashes
+const CHUNK_BYTES: usize = 64 * 1024;
+
+/// Supported digest algorithms
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Algorithm {
+    Sha256,
+    Sha512,
+    Blake3,
+}
+
+impl Algorithm {
+    /// Parse an algorithm name; defaults to SHA256 when `name` is `None`.
+    pub fn parse(name: Option<&str>) -> Result<Self> {
+        match name.map(|n| n.to_ascii_lowercase()).as_deref() {
+            None | Some("sha256") | Some("sha-256") => Ok(Self::Sha256),
+            Some("sha512") | Some("sha-512") => Ok(Self::Sha512),
+            Some("blake3") => Ok(Self::Blake3),
+            Some(other) => anyhow::bail!(
+                "unsupported hash algorithm '{other}' (expected sha256, sha512, or blake3)"
+            ),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256",
+            Self::Sha512 => "sha512",
+            Self::Blake3 => "blake3",
+        }
+    }
+
+    /// Hex digest of everything readable from `reader`.
+    pub fn digest_reader(&self, mut reader: impl Read) -> Result<String> {
+        let mut hasher = Hasher::new(*self);
+        let mut chunk = vec![0u8; CHUNK_BYTES];
+        loop {
+            let n = reader.read(&mut chunk)?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&chunk[..n]);
+        }
+        Ok(hasher.finalize_hex())
+    }
+
+    /// Hex digest of an in-memory buffer.
+    pub fn digest_bytes(&self, data: &[u8]) -> String {
+        let mut hasher = Hasher::new(*self);
+        hasher.update(data);
+        hasher.finalize_hex()
+    }
+}
+
+/// Incremental hasher over any supported algorithm
+enum Hasher {
+    Sha256(Sha256),
+    Sha512(Sha512),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl Hasher {
+    fn new(algorithm: Algorithm) -> Self {
+        match algorithm {
+            Algorithm::Sha256 => Self::Sha256(Sha256::new()),
+            Algorithm::Sha512 => Self::Sha512(Sha512::new()),
+            Algorithm::Blake3 => Self::Blake3(Box::new(blake3::Hasher::new())),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Sha256(h) => h.update(data),
+            Self::Sha512(h) => h.update(data),
+            Self::Blake3(h) => {
+                h.update(data);
+            }
+        }
+    }
+
+    fn finalize_hex(self) -> String {
+        match self {
+            Self::Sha256(h) => format!("{:x}", h.finalize()),
+            Self::Sha512(h) => format!("{:x}", h.finalize()),
+            Self::Blake3(h) => h.finalize().to_hex().to_string(),
+        }
+    }
+}
+
+/// Register every hashing tool with the registry.
+pub fn register_tools(reg: &mut Registry) {
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "hash.file",
+            "hash",
+            "Compute the digest of a file (algorithm: sha256|sha512|blake3, default sha256) without loading it into memory",
+            vec!["fs.read"],
+            "low",
+            true,
+            false,
+            60000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "path": { "type": "string" },
+                "algorithm": {
+                    "type": "string",
+                    "description": "sha256 (default), sha512 or blake3"
+                }
+            },
+            "required": ["path"]
+        }),
+    ));
+
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "hash.string",
+            "hash",
+            "Compute the digest of a string (encoding: utf8|base64) with sha256, sha512, or blake3",
+            vec![],
+            "low",
+            true,
+            false,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "input": { "type": "string" },
+                "algorithm": {
+                    "type": "string",
+                    "description": "sha256 (default), sha512 or blake3"
+                },
+                "encoding": { "type": "string", "enum": ["utf8", "base64"], "default": "utf8" }
+            },
+            "required": ["input"]
+        }),
+    ));
+
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "hash.verify",
+            "hash",
+            "Verify a file or string against an expected digest (\"<hex>\" or \"<algorithm>:<hex>\") and report whether it matches",
+            vec!["fs.read"],
+            "low",
+            true,
+            false,
+            60000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "path": { "type": "string", "description": "File to verify; or give input" },
+                "input": { "type": "string", "description": "String to verify" },
+                "encoding": { "type": "string", "enum": ["utf8", "base64"], "default": "utf8" },
+                "expected": {
+                    "type": "string",
+                    "description": "Hex digest, optionally prefixed with the algorithm (\"sha512:ab12...\")"
+                },
+                "algorithm": {
+                    "type": "string",
+                    "description": "sha256 (default), sha512 or blake3"
+                }
+            },
+            "required": ["expected"]
+        }),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_algorithm() {
+        assert_eq!(Algorithm::parse(None).unwrap(), Algorithm::Sha256);
+        assert_eq!(
+            Algorithm::parse(Some("SHA-512")).unwrap(),
+            Algorithm::Sha512
+        );
+        assert_eq!(Algorithm::parse(Some("blake3")).unwrap(), Algorithm::Blake3);
+        assert!(Algorithm::parse(Some("md5")).is_err());
+    }
+
+    #[test]
+    fn test_known_digests() {
+        assert_eq!(
+            Algorithm::Sha256.digest_bytes(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert!(Algorithm::Sha512
+            .digest_bytes(b"abc")
+            .starts_with("ddaf35a193617aba"));
+        assert_eq!(
+            Algorithm::Blake3.digest_bytes(b"abc"),
+            "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85"
+        );
+    }
+
+    #[test]
+    fn test_digest_reader_matches_bytes() {
+        let data = vec![7u8; CHUNK_BYTES * 2 + 17];
+        for algorithm in [Algorithm::Sha256, Algorithm::Sha512, Algorithm::Blake3] {
+            assert_eq!(
+                algorithm.digest_reader(&data[..]).unwrap(),
+                algorithm.digest_bytes(&data)
+            );
+        }
+    }
+}
@@ -0,0 +1,47 @@
+//! hash.string — Digest of an in-memory string
+
+use anyhow::{Context, Result};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+use super::Algorithm;
+
+#[derive(Deserialize)]
+struct Input {
+    input: String,
+    algorithm: Option<String>,
+    /// `utf8` (default) hashes the string's bytes; `base64` decodes it first
+    encoding: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Output {
+    algorithm: String,
+    digest: String,
+    length: usize,
+}
+
+/// Bytes to hash for `input` under `encoding`
+pub(super) fn decode_input(input: &str, encoding: Option<&str>) -> Result<Vec<u8>> {
+    match encoding.unwrap_or("utf8") {
+        "utf8" => Ok(input.as_bytes().to_vec()),
+        "base64" => base64::engine::general_purpose::STANDARD
+            .decode(input)
+            .context("input is not valid base64"),
+        other => anyhow::bail!("unsupported encoding '{other}' (expected utf8 or base64)"),
+    }
+}
+
+pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
+    let input: Input = serde_json::from_slice(input).context("hash.string: invalid JSON input")?;
+    let algorithm = Algorithm::parse(input.algorithm.as_deref())?;
+    let data = decode_input(&input.input, input.encoding.as_deref())
+        .context("hash.string: failed to decode input")?;
+
+    let output = Output {
+        algorithm: algorithm.as_str().to_string(),
+        digest: algorithm.digest_bytes(&data),
+        length: data.len(),
+    };
+    serde_json::to_vec(&output).context("hash.string: failed to serialise output")
+}
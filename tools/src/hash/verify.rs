@@ -0,0 +1,132 @@
+//! hash.verify — Compare a file or string against an expected digest
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+
+use super::Algorithm;
+
+#[derive(Deserialize)]
+struct Input {
+    /// File to verify; exactly one of `path` or `input` is required
+    path: Option<String>,
+    /// String to verify instead of a file
+    input: Option<String>,
+    encoding: Option<String>,
+    /// Hex digest, optionally prefixed with the algorithm (`sha512:ab12…`)
+    expected: String,
+    algorithm: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Output {
+    #[serde(rename = "match")]
+    matches: bool,
+    algorithm: String,
+    expected: String,
+    actual: String,
+}
+
+/// Split `"<algorithm>:<hex>"` into its parts; a bare hex digest uses `algorithm`.
+fn parse_expected(expected: &str, algorithm: Option<&str>) -> Result<(Algorithm, String)> {
+    let (prefix, digest) = match expected.split_once(':') {
+        Some((prefix, digest)) => (Some(prefix), digest),
+        None => (None, expected),
+    };
+    let algorithm = match (prefix, algorithm) {
+        (Some(p), Some(a)) if Algorithm::parse(Some(p))? != Algorithm::parse(Some(a))? => {
+            anyhow::bail!("expected digest is {p} but algorithm {a} was requested")
+        }
+        (Some(p), _) => Algorithm::parse(Some(p))?,
+        (None, a) => Algorithm::parse(a)?,
+    };
+    Ok((algorithm, digest.trim().to_ascii_lowercase()))
+}
+
+pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
+    let input: Input = serde_json::from_slice(input).context("hash.verify: invalid JSON input")?;
+    let (algorithm, expected) = parse_expected(&input.expected, input.algorithm.as_deref())
+        .context("hash.verify: invalid expected digest")?;
+
+    let actual = match (&input.path, &input.input) {
+        (Some(path), None) => {
+            let file =
+                File::open(path).with_context(|| format!("hash.verify: failed to open {path}"))?;
+            algorithm
+                .digest_reader(file)
+                .with_context(|| format!("hash.verify: failed to read {path}"))?
+        }
+        (None, Some(text)) => {
+            let data = super::string::decode_input(text, input.encoding.as_deref())
+                .context("hash.verify: failed to decode input")?;
+            algorithm.digest_bytes(&data)
+        }
+        _ => anyhow::bail!("hash.verify: provide exactly one of 'path' or 'input'"),
+    };
+
+    let output = Output {
+        matches: actual == expected,
+        algorithm: algorithm.as_str().to_string(),
+        expected,
+        actual,
+    };
+    serde_json::to_vec(&output).context("hash.verify: failed to serialise output")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn verify(input: serde_json::Value) -> Result<serde_json::Value> {
+        Ok(serde_json::from_slice(&execute(&serde_json::to_vec(
+            &input,
+        )?)?)?)
+    }
+
+    #[test]
+    fn test_parse_expected() {
+        let (alg, digest) = parse_expected("SHA512:ABCD", None).unwrap();
+        assert_eq!(alg, Algorithm::Sha512);
+        assert_eq!(digest, "abcd");
+
+        let (alg, _) = parse_expected("abcd", Some("blake3")).unwrap();
+        assert_eq!(alg, Algorithm::Blake3);
+
+        assert!(parse_expected("sha256:abcd", Some("sha512")).is_err());
+    }
+
+    #[test]
+    fn test_verify_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("artifact.bin");
+        std::fs::write(&path, "abc").unwrap();
+
+        let ok = verify(serde_json::json!({
+            "path": path,
+            "expected": "sha256:BA7816BF8F01CFEA414140DE5DAE2223B00361A396177A9CB410FF61F20015AD",
+        }))
+        .unwrap();
+        assert_eq!(ok["match"], true);
+
+        let bad = verify(serde_json::json!({ "path": path, "expected": "deadbeef" })).unwrap();
+        assert_eq!(bad["match"], false);
+        assert_eq!(bad["algorithm"], "sha256");
+    }
+
+    #[test]
+    fn test_verify_string_requires_one_source() {
+        let ok = verify(serde_json::json!({
+            "input": "YWJj",
+            "encoding": "base64",
+            "algorithm": "blake3",
+            "expected": "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85",
+        }))
+        .unwrap();
+        assert_eq!(ok["match"], true);
+
+        assert!(verify(serde_json::json!({ "expected": "00" })).is_err());
+        assert!(
+            verify(serde_json::json!({ "path": "/x", "input": "y", "expected": "00" })).is_err()
+        );
+    }
+}
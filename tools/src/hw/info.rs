@@ -2,7 +2,8 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+
+use crate::sandbox::command;
 
 #[derive(Deserialize)]
 struct Input {}
@@ -39,7 +40,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
 
 fn get_hw_info_macos() -> Result<Output> {
     // CPU model
-    let cpu_output = Command::new("sysctl")
+    let cpu_output = command("sysctl")
         .args(["-n", "machdep.cpu.brand_string"])
         .output()
         .context("Failed to get CPU info")?;
@@ -50,9 +51,7 @@ fn get_hw_info_macos() -> Result<Output> {
 
     // If the above fails (e.g., on Apple Silicon), try the chip name
     let cpu = if cpu.is_empty() {
-        let chip = Command::new("sysctl")
-            .args(["-n", "machdep.cpu.brand"])
-            .output();
+        let chip = command("sysctl").args(["-n", "machdep.cpu.brand"]).output();
         match chip {
             Ok(out) => {
                 let s = String::from_utf8_lossy(&out.stdout).trim().to_string();
@@ -70,7 +69,7 @@ fn get_hw_info_macos() -> Result<Output> {
     };
 
     // RAM
-    let ram_output = Command::new("sysctl")
+    let ram_output = command("sysctl")
         .args(["-n", "hw.memsize"])
         .output()
         .context("Failed to get RAM info")?;
@@ -97,7 +96,7 @@ fn get_hw_info_macos() -> Result<Output> {
 }
 
 fn get_cpu_from_system_profiler() -> String {
-    let output = Command::new("system_profiler")
+    let output = command("system_profiler")
         .args(["SPHardwareDataType"])
         .output();
 
@@ -122,7 +121,7 @@ fn get_cpu_from_system_profiler() -> String {
 }
 
 fn get_gpu_macos() -> String {
-    let output = Command::new("system_profiler")
+    let output = command("system_profiler")
         .args(["SPDisplaysDataType"])
         .output();
 
@@ -148,13 +147,13 @@ fn get_gpu_macos() -> String {
 }
 
 fn get_storage_macos() -> Result<Vec<StorageDevice>> {
-    let _output = Command::new("diskutil")
+    let _output = command("diskutil")
         .args(["list", "-plist"])
         .output()
         .context("Failed to execute diskutil")?;
 
     // Parse the simpler text output instead
-    let text_output = Command::new("diskutil")
+    let text_output = command("diskutil")
         .arg("list")
         .output()
         .context("Failed to execute diskutil list")?;
@@ -177,7 +176,7 @@ fn get_storage_macos() -> Result<Vec<StorageDevice>> {
 
     // If diskutil didn't find anything, fall back to df
     if devices.is_empty() {
-        let df_output = Command::new("df")
+        let df_output = command("df")
             .args(["-g"])
             .output()
             .context("Failed to execute df")?;
@@ -199,7 +198,7 @@ fn get_storage_macos() -> Result<Vec<StorageDevice>> {
 }
 
 fn get_disk_size_macos(disk: &str) -> f64 {
-    let output = Command::new("diskutil").args(["info", disk]).output();
+    let output = command("diskutil").args(["info", disk]).output();
 
     match output {
         Ok(out) => {
@@ -268,7 +267,7 @@ fn get_hw_info_linux() -> Result<Output> {
 }
 
 fn get_gpu_linux() -> String {
-    let output = Command::new("lspci").output();
+    let output = command("lspci").output();
 
     match output {
         Ok(out) => {
@@ -288,7 +287,7 @@ fn get_gpu_linux() -> String {
 }
 
 fn get_storage_linux() -> Result<Vec<StorageDevice>> {
-    let output = Command::new("lsblk")
+    let output = command("lsblk")
         .args(["-bno", "NAME,SIZE,TYPE"])
         .output()
         .context("Failed to execute lsblk")?;
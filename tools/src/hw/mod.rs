@@ -4,18 +4,23 @@
 
 pub mod info;
 
-use crate::registry::{make_tool, Registry};
+use serde_json::json;
+
+use crate::registry::{make_tool, with_input_schema, Registry};
 
 /// Register every hardware tool with the registry.
 pub fn register_tools(reg: &mut Registry) {
-    reg.register_tool(make_tool(
-        "hw.info",
-        "hw",
-        "Return system hardware information: CPU model, RAM, GPU, and storage devices",
-        vec!["hw.read"],
-        "low",
-        true,
-        false,
-        10000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "hw.info",
+            "hw",
+            "Return system hardware information: CPU model, RAM, GPU, and storage devices",
+            vec!["hw.read"],
+            "low",
+            true,
+            false,
+            10000,
+        ),
+        json!({ "type": "object" }),
     ));
 }
@@ -0,0 +1,167 @@
+//! Identity — the actor a call acts as
+//!
+//! Calls name the identity they act as in `x-aios-identity`:
+//! `<kind>:<name>` for an agent, tool, user, node or service. Execute
+//! replaces the `agent_id` a request names with that identity, so capability
+//! grants, policies and the audit log key off the actor the caller
+//! registered as. Identities are taken at their word here; the memory
+//! service holds their tokens and checks them. Approval decisions are the
+//! exception: the deciding user's token, in `x-aios-identity-token`, is
+//! checked with the memory service before the decision counts.
+//!
+//! This service's own calls to the memory service act as `service:tools`.
+
+use tonic::metadata::MetadataValue;
+use tonic::service::interceptor::InterceptedService;
+use tonic::transport::Channel;
+
+use crate::proto::memory::memory_service_client::MemoryServiceClient;
+use crate::proto::memory::IdentityCredentials;
+
+/// Header naming the identity a call acts as
+pub const IDENTITY_HEADER: &str = "x-aios-identity";
+
+/// Header carrying the acting identity's token
+pub const TOKEN_HEADER: &str = "x-aios-identity-token";
+
+/// Identity of this service
+const SERVICE_ID: &str = "service:tools";
+
+/// Kinds of identity
+const KINDS: [&str; 5] = ["agent", "tool", "user", "node", "service"];
+
+/// An acting identity, as named by a call
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Principal {
+    pub kind: String,
+    pub name: String,
+}
+
+impl Principal {
+    /// `<kind>:<name>`, None for other strings
+    pub fn parse(id: &str) -> Option<Self> {
+        let (kind, name) = id.split_once(':')?;
+        (KINDS.contains(&kind) && !name.is_empty()).then(|| Self {
+            kind: kind.to_string(),
+            name: name.to_string(),
+        })
+    }
+
+    /// Name per-actor state is kept under: agents and services keep their
+    /// bare names, so capability grants for an agent still apply; users,
+    /// nodes and tools go by their full id so they cannot pass for an agent
+    pub fn key(&self) -> String {
+        match self.kind.as_str() {
+            "agent" | "service" => self.name.clone(),
+            _ => format!("{}:{}", self.kind, self.name),
+        }
+    }
+}
+
+/// Key of the actor a request acts for: the identity the call names, unless
+/// a service or node made the call on its own account and the request
+/// names an agent (`legacy`); then that agent
+pub fn acting<T>(request: &tonic::Request<T>, legacy: &str) -> String {
+    let caller = request
+        .metadata()
+        .get(IDENTITY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(Principal::parse);
+    match caller {
+        Some(p) if !matches!(p.kind.as_str(), "service" | "node") || legacy.is_empty() => p.key(),
+        _ => legacy.to_string(),
+    }
+}
+
+/// Key of the user a call acts as, once the memory service has checked the
+/// token the call carries; an error for any other caller
+pub async fn verified_user<T>(
+    memory: &mut MemoryClient,
+    request: &tonic::Request<T>,
+) -> Result<String, tonic::Status> {
+    let header = |name| {
+        request
+            .metadata()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+    };
+    let user = header(IDENTITY_HEADER)
+        .and_then(|id| Principal::parse(&id))
+        .filter(|p| p.kind == "user")
+        .ok_or_else(|| tonic::Status::permission_denied("Only a user can decide approvals"))?;
+    let token = header(TOKEN_HEADER).ok_or_else(|| {
+        tonic::Status::unauthenticated(format!("user:{} did not present its token", user.name))
+    })?;
+    memory
+        .verify_identity(IdentityCredentials {
+            id: format!("user:{}", user.name),
+            token,
+        })
+        .await
+        .map_err(|e| tonic::Status::unauthenticated(e.message().to_string()))?;
+    Ok(user.key())
+}
+
+/// Interceptor naming this service on outgoing calls
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ActAsService;
+
+impl tonic::service::Interceptor for ActAsService {
+    fn call(
+        &mut self,
+        mut request: tonic::Request<()>,
+    ) -> Result<tonic::Request<()>, tonic::Status> {
+        request
+            .metadata_mut()
+            .insert(IDENTITY_HEADER, MetadataValue::from_static(SERVICE_ID));
+        Ok(request)
+    }
+}
+
+/// Memory service client whose calls act as this service
+pub type MemoryClient = MemoryServiceClient<InterceptedService<Channel, ActAsService>>;
+
+pub async fn connect_memory(addr: String) -> Result<MemoryClient, tonic::transport::Error> {
+    let channel = tonic::transport::Endpoint::from_shared(addr)?
+        .connect()
+        .await?;
+    Ok(MemoryServiceClient::with_interceptor(channel, ActAsService))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request_as(id: &str) -> tonic::Request<()> {
+        let mut request = tonic::Request::new(());
+        request
+            .metadata_mut()
+            .insert(IDENTITY_HEADER, id.parse().unwrap());
+        request
+    }
+
+    #[test]
+    fn test_acting_prefers_the_calling_identity() {
+        assert_eq!(
+            acting(&request_as("agent:security-agent"), "other"),
+            "security-agent"
+        );
+        assert_eq!(
+            acting(&request_as("user:alice"), "security-agent"),
+            "user:alice"
+        );
+        // Nodes and services act for the agent the request names
+        assert_eq!(
+            acting(&request_as("node:n1"), "system-agent"),
+            "system-agent"
+        );
+        assert_eq!(acting(&request_as("node:n1"), ""), "node:n1");
+        // Without a valid identity, the request's own field stands
+        assert_eq!(acting(&request_as("alice"), "system-agent"), "system-agent");
+        assert_eq!(
+            acting(&tonic::Request::new(()), "system-agent"),
+            "system-agent"
+        );
+    }
+}
@@ -0,0 +1,497 @@
+//! Linux capabilities per tool
+//!
+//! Maps the capability strings tools require (`firewall_manage`, ...) and
+//! individual tools (`net.ping`) to the Linux capabilities they actually need
+//! (CAP_NET_ADMIN, CAP_NET_RAW, ...), configured in linux-caps.toml. The
+//! executor rejects a tool whose mapping includes a capability outside the
+//! policy's `allowed` set, and grants exactly the mapped set to the child
+//! processes the tool starts, all of which are built with
+//! [`crate::sandbox::command`]:
+//!
+//! - a child of a tool running as a privsep execution user receives the set
+//!   as ambient capabilities, which survive exec for unprivileged programs;
+//! - a child of a tool running as root has its bounding and inheritable sets
+//!   trimmed to the granted set, so the program it execs holds nothing else.
+//!
+//! The in-process handler of a dropped tool also gets the set in its
+//! effective capabilities for the duration of the call.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::cell::Cell;
+use std::collections::HashMap;
+use tracing::warn;
+
+/// Default location of the Linux capability policy
+pub const LINUX_CAPS_PATH: &str = "/etc/aios/linux-caps.toml";
+
+/// Capability names understood in linux-caps.toml, with their numbers
+const CAP_NAMES: &[(&str, u32)] = &[
+    ("CAP_CHOWN", 0),
+    ("CAP_DAC_OVERRIDE", 1),
+    ("CAP_DAC_READ_SEARCH", 2),
+    ("CAP_FOWNER", 3),
+    ("CAP_FSETID", 4),
+    ("CAP_KILL", 5),
+    ("CAP_SETGID", 6),
+    ("CAP_SETUID", 7),
+    ("CAP_SETPCAP", 8),
+    ("CAP_NET_BIND_SERVICE", 10),
+    ("CAP_NET_BROADCAST", 11),
+    ("CAP_NET_ADMIN", 12),
+    ("CAP_NET_RAW", 13),
+    ("CAP_IPC_LOCK", 14),
+    ("CAP_SYS_MODULE", 16),
+    ("CAP_SYS_RAWIO", 17),
+    ("CAP_SYS_CHROOT", 18),
+    ("CAP_SYS_PTRACE", 19),
+    ("CAP_SYS_ADMIN", 21),
+    ("CAP_SYS_BOOT", 22),
+    ("CAP_SYS_NICE", 23),
+    ("CAP_SYS_RESOURCE", 24),
+    ("CAP_SYS_TIME", 25),
+    ("CAP_MKNOD", 27),
+    ("CAP_AUDIT_WRITE", 29),
+    ("CAP_SYSLOG", 34),
+    ("CAP_BPF", 39),
+];
+
+/// Highest capability number the bounding set is trimmed up to
+const CAP_LAST: u32 = 40;
+
+/// A set of Linux capabilities as a bitmask
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CapSet(u64);
+
+impl CapSet {
+    /// Parse capability names (`CAP_NET_RAW` or `net_raw`, any case)
+    pub fn from_names<S: AsRef<str>>(names: &[S]) -> Result<Self> {
+        let mut bits = 0u64;
+        for name in names {
+            let upper = name.as_ref().to_uppercase();
+            let full = if upper.starts_with("CAP_") {
+                upper
+            } else {
+                format!("CAP_{upper}")
+            };
+            let (_, n) = CAP_NAMES
+                .iter()
+                .find(|(cap, _)| *cap == full)
+                .ok_or_else(|| anyhow::anyhow!("unknown Linux capability '{}'", name.as_ref()))?;
+            bits |= 1 << n;
+        }
+        Ok(Self(bits))
+    }
+
+    /// Capability names in the set, lowest number first
+    pub fn names(&self) -> Vec<&'static str> {
+        CAP_NAMES
+            .iter()
+            .filter(|(_, n)| self.0 & (1 << n) != 0)
+            .map(|(name, _)| *name)
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    /// Capabilities in `self` but not in `other`
+    pub fn difference(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+
+    fn contains(&self, cap: u32) -> bool {
+        self.0 & (1 << cap) != 0
+    }
+}
+
+/// linux-caps.toml layout
+#[derive(Debug, Default, Deserialize)]
+struct CapsConfig {
+    allowed: Option<Vec<String>>,
+    /// Tool capability string → Linux capabilities
+    #[serde(default)]
+    capabilities: HashMap<String, Vec<String>>,
+    /// Tool name or `namespace.*` pattern → Linux capabilities
+    #[serde(default)]
+    tools: HashMap<String, Vec<String>>,
+}
+
+/// Which Linux capabilities tools need, and which the policy permits
+#[derive(Debug, Clone)]
+pub struct LinuxCapsPolicy {
+    allowed: CapSet,
+    by_capability: HashMap<String, CapSet>,
+    by_tool: HashMap<String, CapSet>,
+}
+
+impl LinuxCapsPolicy {
+    /// Built-in mapping: raw sockets for ping and port scans, network admin
+    /// for firewall changes, CAP_KILL for process management, ownership
+    /// changes for fs permissions
+    pub fn builtin() -> Self {
+        let set = |names: &[&str]| CapSet::from_names(names).unwrap_or_default();
+        Self {
+            allowed: set(&[
+                "CAP_CHOWN",
+                "CAP_FOWNER",
+                "CAP_KILL",
+                "CAP_NET_ADMIN",
+                "CAP_NET_BIND_SERVICE",
+                "CAP_NET_RAW",
+            ]),
+            by_capability: [
+                ("firewall_manage", set(&["CAP_NET_ADMIN", "CAP_NET_RAW"])),
+                ("net_scan", set(&["CAP_NET_RAW"])),
+                ("process_manage", set(&["CAP_KILL"])),
+                ("fs_permissions", set(&["CAP_CHOWN", "CAP_FOWNER"])),
+            ]
+            .into_iter()
+            .map(|(cap, caps)| (cap.to_string(), caps))
+            .collect(),
+            by_tool: [("net.ping", set(&["CAP_NET_RAW"]))]
+                .into_iter()
+                .map(|(tool, caps)| (tool.to_string(), caps))
+                .collect(),
+        }
+    }
+
+    /// Built-in policy overlaid with the configuration at `path`.
+    /// A missing file yields the built-ins; an invalid one is logged and ignored.
+    pub fn load(path: &str) -> Self {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => Self::from_toml(&contents).unwrap_or_else(|e| {
+                warn!("Ignoring invalid Linux capability policy in {path}: {e}");
+                Self::builtin()
+            }),
+            Err(_) => Self::builtin(),
+        }
+    }
+
+    /// Built-in policy overlaid with a linux-caps.toml document. `allowed`
+    /// replaces the built-in list; mapping entries replace built-in entries
+    /// of the same key.
+    pub fn from_toml(contents: &str) -> Result<Self> {
+        let config: CapsConfig =
+            toml::from_str(contents).context("Failed to parse Linux capability policy")?;
+        let mut policy = Self::builtin();
+        if let Some(allowed) = config.allowed {
+            policy.allowed = CapSet::from_names(&allowed)?;
+        }
+        for (cap, names) in config.capabilities {
+            policy
+                .by_capability
+                .insert(cap, CapSet::from_names(&names)?);
+        }
+        for (tool, names) in config.tools {
+            policy.by_tool.insert(tool, CapSet::from_names(&names)?);
+        }
+        Ok(policy)
+    }
+
+    /// Linux capabilities a tool needs, from its required capability strings
+    /// and any entry for the tool itself or its namespace
+    pub fn required(&self, tool_name: &str, required_capabilities: &[String]) -> CapSet {
+        let mut caps = required_capabilities
+            .iter()
+            .filter_map(|c| self.by_capability.get(c))
+            .fold(CapSet::default(), |acc, c| acc.union(*c));
+        if let Some(c) = self.by_tool.get(tool_name) {
+            caps = caps.union(*c);
+        }
+        if let Some((namespace, _)) = tool_name.split_once('.') {
+            if let Some(c) = self.by_tool.get(&format!("{namespace}.*")) {
+                caps = caps.union(*c);
+            }
+        }
+        caps
+    }
+
+    /// The capabilities to grant a tool, or an error naming those the
+    /// policy forbids
+    pub fn check(&self, tool_name: &str, required_capabilities: &[String]) -> Result<CapSet> {
+        let caps = self.required(tool_name, required_capabilities);
+        let forbidden = caps.difference(self.allowed);
+        if !forbidden.is_empty() {
+            anyhow::bail!(
+                "requires Linux capabilities forbidden by policy: {}",
+                forbidden.names().join(", ")
+            );
+        }
+        Ok(caps)
+    }
+}
+
+thread_local! {
+    /// Capabilities granted to the current tool handler's child processes
+    static GRANTED: Cell<Option<CapSet>> = const { Cell::new(None) };
+}
+
+/// Run `f` with `caps` granted to every [`crate::sandbox::command`] built on
+/// this thread. When the thread runs as an unprivileged execution user, the
+/// capabilities are also raised in its effective set for the duration.
+pub fn with_granted<T>(caps: Option<CapSet>, f: impl FnOnce() -> T) -> Result<T> {
+    struct Restore {
+        granted: Option<CapSet>,
+        effective: Option<u64>,
+    }
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            GRANTED.with(|g| g.set(self.granted));
+            if let Some(effective) = self.effective {
+                if let Err(e) = sys::set_effective(effective) {
+                    warn!("Failed to restore effective capabilities: {e}");
+                }
+            }
+        }
+    }
+
+    let mut restore = Restore {
+        granted: GRANTED.with(|g| g.replace(caps)),
+        effective: None,
+    };
+    if let Some(caps) = caps {
+        if !caps.is_empty() && !nix::unistd::geteuid().is_root() {
+            let previous = sys::effective()?;
+            sys::set_effective(caps.0).context("Failed to raise granted capabilities")?;
+            restore.effective = Some(previous);
+        }
+    }
+    let out = f();
+    drop(restore);
+    Ok(out)
+}
+
+/// Apply the thread's granted capabilities to a child process, if any
+pub fn apply_granted(cmd: &mut std::process::Command) {
+    let Some(caps) = GRANTED.with(|g| g.get()) else {
+        return;
+    };
+
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::process::CommandExt;
+        let dropped = !nix::unistd::geteuid().is_root();
+        unsafe {
+            cmd.pre_exec(move || {
+                if dropped {
+                    sys::raise_ambient(caps)
+                } else {
+                    sys::restrict_root(caps)
+                }
+            });
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = (cmd, caps);
+}
+
+/// Raw capget/capset/prctl wrappers
+mod sys {
+    use super::{CapSet, CAP_LAST};
+
+    const VERSION_3: u32 = 0x2008_0522;
+
+    #[repr(C)]
+    struct Header {
+        version: u32,
+        pid: i32,
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy, Default)]
+    struct Data {
+        effective: u32,
+        permitted: u32,
+        inheritable: u32,
+    }
+
+    #[derive(Default)]
+    struct Sets {
+        effective: u64,
+        permitted: u64,
+        inheritable: u64,
+    }
+
+    fn get() -> std::io::Result<Sets> {
+        let mut header = Header {
+            version: VERSION_3,
+            pid: 0,
+        };
+        let mut data = [Data::default(); 2];
+        let ret = unsafe { libc::syscall(libc::SYS_capget, &mut header, data.as_mut_ptr()) };
+        if ret != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let join = |lo: u32, hi: u32| (hi as u64) << 32 | lo as u64;
+        Ok(Sets {
+            effective: join(data[0].effective, data[1].effective),
+            permitted: join(data[0].permitted, data[1].permitted),
+            inheritable: join(data[0].inheritable, data[1].inheritable),
+        })
+    }
+
+    fn set(sets: &Sets) -> std::io::Result<()> {
+        let mut header = Header {
+            version: VERSION_3,
+            pid: 0,
+        };
+        let split = |v: u64| (v as u32, (v >> 32) as u32);
+        let (e0, e1) = split(sets.effective);
+        let (p0, p1) = split(sets.permitted);
+        let (i0, i1) = split(sets.inheritable);
+        let data = [
+            Data {
+                effective: e0,
+                permitted: p0,
+                inheritable: i0,
+            },
+            Data {
+                effective: e1,
+                permitted: p1,
+                inheritable: i1,
+            },
+        ];
+        let ret = unsafe { libc::syscall(libc::SYS_capset, &mut header, data.as_ptr()) };
+        if ret != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// The calling thread's effective set
+    pub fn effective() -> std::io::Result<u64> {
+        Ok(get()?.effective)
+    }
+
+    /// Replace the calling thread's effective set (within its permitted set)
+    pub fn set_effective(effective: u64) -> std::io::Result<()> {
+        let mut sets = get()?;
+        sets.effective = effective & sets.permitted;
+        set(&sets)
+    }
+
+    /// In an unprivileged child: make `caps` inheritable and ambient so the
+    /// exec'd program holds exactly them
+    pub fn raise_ambient(caps: CapSet) -> std::io::Result<()> {
+        let mut sets = get()?;
+        sets.inheritable = caps.0 & sets.permitted;
+        set(&sets)?;
+        unsafe {
+            libc::prctl(
+                libc::PR_CAP_AMBIENT,
+                libc::PR_CAP_AMBIENT_CLEAR_ALL,
+                0,
+                0,
+                0,
+            );
+        }
+        for cap in 0..=CAP_LAST {
+            if caps.contains(cap)
+                && unsafe {
+                    libc::prctl(
+                        libc::PR_CAP_AMBIENT,
+                        libc::PR_CAP_AMBIENT_RAISE,
+                        cap as libc::c_ulong,
+                        0,
+                        0,
+                    )
+                } != 0
+            {
+                return Err(std::io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+
+    /// In a root child: drop every other capability from the bounding and
+    /// inheritable sets so the exec'd program holds only `caps`
+    pub fn restrict_root(caps: CapSet) -> std::io::Result<()> {
+        for cap in 0..=CAP_LAST {
+            if !caps.contains(cap) {
+                let ret =
+                    unsafe { libc::prctl(libc::PR_CAPBSET_DROP, cap as libc::c_ulong, 0, 0, 0) };
+                // EINVAL: capability unknown to this kernel
+                if ret != 0 && std::io::Error::last_os_error().raw_os_error() != Some(libc::EINVAL)
+                {
+                    return Err(std::io::Error::last_os_error());
+                }
+            }
+        }
+        let mut sets = get()?;
+        sets.inheritable &= caps.0;
+        set(&sets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn caps(names: &[&str]) -> CapSet {
+        CapSet::from_names(names).unwrap()
+    }
+
+    #[test]
+    fn test_capset_names() {
+        let set = caps(&["net_raw", "CAP_NET_ADMIN"]);
+        assert_eq!(set.names(), vec!["CAP_NET_ADMIN", "CAP_NET_RAW"]);
+        assert!(CapSet::from_names(&["CAP_FLY"]).is_err());
+    }
+
+    #[test]
+    fn test_required_maps_capabilities_and_tools() {
+        let policy = LinuxCapsPolicy::builtin();
+        let firewall = policy.required("firewall.add_rule", &["firewall_manage".to_string()]);
+        assert_eq!(firewall, caps(&["CAP_NET_ADMIN", "CAP_NET_RAW"]));
+        let ping = policy.required("net.ping", &["net_read".to_string()]);
+        assert_eq!(ping, caps(&["CAP_NET_RAW"]));
+        assert!(policy
+            .required("net.dns", &["net_read".to_string()])
+            .is_empty());
+    }
+
+    #[test]
+    fn test_check_rejects_forbidden_caps() {
+        let policy = LinuxCapsPolicy::from_toml(
+            "allowed = [\"CAP_NET_RAW\"]\n[tools]\n\"hw.*\" = [\"CAP_SYS_RAWIO\"]\n",
+        )
+        .unwrap();
+        assert!(policy.check("net.ping", &[]).is_ok());
+        let err = policy
+            .check("firewall.add_rule", &["firewall_manage".to_string()])
+            .unwrap_err();
+        assert!(err.to_string().contains("CAP_NET_ADMIN"));
+        assert!(!err.to_string().contains("CAP_NET_RAW"));
+        let err = policy.check("hw.info", &[]).unwrap_err();
+        assert!(err.to_string().contains("CAP_SYS_RAWIO"));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_root_child_holds_only_granted_caps() {
+        if !nix::unistd::geteuid().is_root() {
+            return;
+        }
+        let status = std::thread::spawn(|| {
+            with_granted(Some(caps(&["CAP_NET_RAW"])), || {
+                crate::sandbox::command("grep")
+                    .args(["^CapEff:", "/proc/self/status"])
+                    .output()
+                    .unwrap()
+            })
+            .unwrap()
+        })
+        .join()
+        .unwrap();
+        let line = String::from_utf8_lossy(&status.stdout);
+        let eff =
+            u64::from_str_radix(line.trim().trim_start_matches("CapEff:").trim(), 16).unwrap();
+        assert_eq!(eff, 1 << 13, "unexpected CapEff: {line}");
+    }
+}
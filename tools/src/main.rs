@@ -5,44 +5,71 @@
 //! validate → check permissions → backup → execute → audit.
 
 use anyhow::{Context, Result};
+use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use tonic::transport::Server;
-use tracing::{info, warn};
+use tracing::{info, warn, Instrument};
 
+#[path = "../../agent-core/src/api_compat.rs"]
+mod api_version;
+mod approvals;
 mod audit;
+mod audit_mirror;
 mod backup;
+#[path = "../../agent-core/src/build_info.rs"]
+mod build_info;
+pub mod calc;
 pub mod capabilities;
 pub mod code;
+pub mod composite;
 pub mod container;
+pub mod data;
+pub mod disk;
 pub mod email;
 mod executor;
 pub mod firewall;
 pub mod firewall_apply;
 pub mod fs;
 pub mod git;
+pub mod hash;
 pub mod hw;
+mod identity;
+pub mod linux_caps;
 pub mod monitor;
 pub mod net;
+pub mod output;
 pub mod pkg;
 pub mod plugin;
+mod policy_sim;
+pub mod privsep;
 pub mod process;
 mod registry;
+mod reservation;
+mod running;
 pub mod sandbox;
 mod schema;
 pub mod sec;
 pub mod secrets;
 pub mod self_update;
 pub mod service;
+mod siem;
+#[path = "../../agent-core/src/otel.rs"]
+mod telemetry;
+pub mod template;
+pub mod text;
 pub mod web;
 
 pub mod proto {
     pub mod common {
-        tonic::include_proto!("aios.common");
+        tonic::include_proto!("aios.v1.common");
     }
     pub mod tools {
-        tonic::include_proto!("aios.tools");
+        tonic::include_proto!("aios.v1.tools");
+    }
+    pub mod memory {
+        tonic::include_proto!("aios.v1.memory");
     }
 }
 
@@ -54,11 +81,54 @@ pub struct ToolRegistryState {
     pub executor: executor::Executor,
     pub audit_log: audit::AuditLog,
     pub backup_manager: backup::BackupManager,
+    /// Tools added with the Register RPC, kept across reloads
+    pub external_tools: HashMap<String, proto::tools::ToolDefinition>,
+}
+
+impl ToolRegistryState {
+    /// Rebuild the registry from the built-in tools, the plugin directory,
+    /// the externally registered tools and the composite directory
+    pub fn reload(&mut self) -> registry::ReloadDiff {
+        let mut next = registry::Registry::new();
+        register_builtin_tools(&mut next);
+        plugin::scan_and_register_plugins(&mut next);
+        for tool in self.external_tools.values() {
+            next.register_tool(tool.clone());
+        }
+        composite::scan_and_register_composites(&mut next);
+        let diff = self.registry.replace(next);
+        if !diff.is_empty() {
+            info!(
+                "Tool registry reloaded: {} added, {} changed, {} removed",
+                diff.added.len(),
+                diff.changed.len(),
+                diff.removed.len()
+            );
+        }
+        diff
+    }
+}
+
+/// The user deciding an approval, checked with the memory service
+async fn verified_decider<T>(request: &tonic::Request<T>) -> Result<String, tonic::Status> {
+    let addr =
+        std::env::var("AIOS_MEMORY_ADDR").unwrap_or_else(|_| "http://127.0.0.1:50053".to_string());
+    let mut memory = identity::connect_memory(addr)
+        .await
+        .map_err(|e| tonic::Status::unavailable(format!("Cannot verify the decider: {e}")))?;
+    identity::verified_user(&mut memory, request).await
 }
 
 /// gRPC service implementation
+#[derive(Clone)]
 pub struct ToolRegistryService {
     state: Arc<Mutex<ToolRegistryState>>,
+    /// Executions in progress, reachable without the state lock
+    running: Arc<running::RunningExecutions>,
+    /// Admission slots, with a share reserved for interactive calls
+    slots: reservation::SlotPool,
+    /// Critical-risk executions waiting for an operator
+    approvals: Arc<approvals::ApprovalQueue>,
 }
 
 #[tonic::async_trait]
@@ -69,10 +139,18 @@ impl ToolRegistry for ToolRegistryService {
     ) -> Result<tonic::Response<proto::tools::ListToolsResponse>, tonic::Status> {
         let req = request.into_inner();
         let state = self.state.lock().await;
-        let tools = state.registry.list_tools(&req.namespace);
+        let (tools, total) = state
+            .registry
+            .search_tools(&req)
+            .map_err(tonic::Status::invalid_argument)?;
+        let tools = tools
+            .into_iter()
+            .map(|tool| state.executor.describe(tool))
+            .collect();
 
         Ok(tonic::Response::new(proto::tools::ListToolsResponse {
             tools,
+            total,
         }))
     }
 
@@ -87,19 +165,58 @@ impl ToolRegistry for ToolRegistryService {
             .registry
             .get_tool(&req.name)
             .ok_or_else(|| tonic::Status::not_found(format!("Tool not found: {}", req.name)))
-            .map(tonic::Response::new)
+            .map(|tool| tonic::Response::new(state.executor.describe(tool)))
     }
 
     async fn execute(
         &self,
         request: tonic::Request<proto::tools::ExecuteRequest>,
     ) -> Result<tonic::Response<proto::tools::ExecuteResponse>, tonic::Status> {
-        let req = request.into_inner();
+        let agent_id = identity::acting(&request, &request.get_ref().agent_id);
+        let mut req = request.into_inner();
+        req.agent_id = agent_id;
         info!(
             "Executing tool: {} (agent: {}, reason: {})",
             req.tool_name, req.agent_id, req.reason
         );
 
+        // Critical-risk executions wait for approval before taking a slot
+        // or the registry lock; the executor rejects malformed input
+        // without asking anyone
+        let tool = self.state.lock().await.registry.get_tool(&req.tool_name);
+        if let Some(tool) = tool.filter(|t| {
+            self.approvals.config().requires(t, &req.agent_id)
+                && schema::validate_input(&req.input_json, &t.input_schema).is_ok()
+        }) {
+            let decision = self.approvals.wait(&req, &tool).await;
+            if let Some(error) = decision.refusal() {
+                let execution_id = uuid::Uuid::new_v4().to_string();
+                self.state.lock().await.audit_log.record(
+                    &execution_id,
+                    &req.tool_name,
+                    &req.agent_id,
+                    &req.task_id,
+                    &format!("{error}: {}", req.reason),
+                    false,
+                    0,
+                );
+                return Ok(tonic::Response::new(proto::tools::ExecuteResponse {
+                    success: false,
+                    error,
+                    execution_id,
+                    failure_class: running::FAILURE_DENIED.to_string(),
+                    ..Default::default()
+                }));
+            }
+        }
+
+        // Admission before the registry lock: background calls cannot fill
+        // the slots reserved for interactive ones
+        let _slot = self
+            .slots
+            .acquire(&req.workload)
+            .await
+            .map_err(|_| tonic::Status::unavailable("Execution slots closed"))?;
         let mut state = self.state.lock().await;
 
         // Destructure to avoid simultaneous borrow conflicts
@@ -108,16 +225,23 @@ impl ToolRegistry for ToolRegistryService {
             ref executor,
             ref mut audit_log,
             ref mut backup_manager,
+            ..
         } = *state;
 
         // Execute through the pipeline
         let response = executor
             .execute(registry, audit_log, backup_manager, req.clone())
+            .instrument(tracing::info_span!(
+                "tool.execute",
+                tool = %req.tool_name,
+                task_id = %req.task_id
+            ))
             .await
             .map_err(|e| tonic::Status::internal(format!("Execution failed: {e}")))?;
 
         // Plugin execution fallback: if no handler registered and tool is a plugin,
-        // try running the plugin script directly
+        // try running the plugin script directly. The script runs outside the
+        // registry lock, queued behind other instances of the same plugin.
         if !response.success
             && response.error.contains("No handler registered")
             && req.tool_name.starts_with("plugin.")
@@ -126,23 +250,121 @@ impl ToolRegistry for ToolRegistryService {
                 .tool_name
                 .strip_prefix("plugin.")
                 .unwrap_or(&req.tool_name);
+
+            // Daemon plugins are driven through their control tool
+            if let Some(daemon) = short_name.strip_suffix(".control") {
+                let started = std::time::Instant::now();
+                let result = executor.daemons().control(daemon, &req.input_json);
+                let duration_ms = started.elapsed().as_millis() as i64;
+                audit_log.record(
+                    &response.execution_id,
+                    &req.tool_name,
+                    &req.agent_id,
+                    &req.task_id,
+                    &format!("Daemon control: {}", req.reason),
+                    result.is_ok(),
+                    duration_ms,
+                );
+                return Ok(tonic::Response::new(match result {
+                    Ok(output) => proto::tools::ExecuteResponse {
+                        success: true,
+                        output_json: output,
+                        error: String::new(),
+                        failure_class: String::new(),
+                        duration_ms,
+                        ..response
+                    },
+                    Err(e) => proto::tools::ExecuteResponse {
+                        error: e.to_string(),
+                        failure_class: running::FAILURE_ERROR.to_string(),
+                        duration_ms,
+                        ..response
+                    },
+                }));
+            }
+
             let script_path = format!("{}/{}.py", plugin::PLUGIN_DIR, short_name);
 
             if std::path::Path::new(&script_path).exists() {
                 info!("Falling back to plugin script execution: {}", script_path);
-                let sandbox = sandbox::Sandbox::new(sandbox::ResourceLimits {
-                    allow_network: true,
-                    max_cpu_time: std::time::Duration::from_secs(30),
-                    writable_paths: vec!["/tmp".to_string()],
-                    ..Default::default()
-                });
-
-                match sandbox
-                    .execute("python3", &[&script_path], &req.input_json)
-                    .await
-                {
+                let limits = registry
+                    .get_tool(&req.tool_name)
+                    .and_then(|tool| {
+                        executor
+                            .sandbox_limits(&tool)
+                            .map(|(_, limits)| limits.clone())
+                    })
+                    .or_else(|| executor.sandbox_profile("plugin").cloned())
+                    .unwrap_or_default();
+                let sandbox = sandbox::Sandbox::new(limits);
+                let timeout = registry
+                    .get_tool(&req.tool_name)
+                    .and_then(|tool| running::tool_timeout(tool.timeout_ms));
+                let running = executor.running();
+                let queue = executor.plugin_queue();
+                drop(state);
+
+                // Waiting in the queue can be cancelled but does not count
+                // toward the timeout. The sandbox kills the script's process
+                // group when a run is dropped; killing every child of the
+                // service would take down the plugins running alongside.
+                let metadata = plugin::load_metadata(short_name);
+                let queued = running::supervise(
+                    &running,
+                    &response.execution_id,
+                    &req.task_id,
+                    &req.tool_name,
+                    None,
+                    queue.acquire(short_name, metadata.as_ref()),
+                )
+                .await;
+                let outcome = match queued {
+                    running::Outcome::Completed(Ok(_permit)) => {
+                        running::supervise(
+                            &running,
+                            &response.execution_id,
+                            &req.task_id,
+                            &req.tool_name,
+                            timeout,
+                            sandbox.execute("python3", &[&script_path], &req.input_json),
+                        )
+                        .await
+                    }
+                    running::Outcome::Completed(Err(e)) => running::Outcome::Completed(Err(e)),
+                    _ => running::Outcome::Cancelled,
+                };
+                let outcome = match outcome {
+                    running::Outcome::Completed(result) => result,
+                    running::Outcome::TimedOut(timeout) => Ok(sandbox::SandboxResult {
+                        success: false,
+                        output: vec![],
+                        error: format!("Execution timed out after {timeout:?}"),
+                        exit_code: -1,
+                        duration_ms: timeout.as_millis() as u64,
+                        resource_usage: Default::default(),
+                    }),
+                    running::Outcome::Cancelled => Ok(sandbox::SandboxResult {
+                        success: false,
+                        output: vec![],
+                        error: "Cancelled: the task was cancelled".to_string(),
+                        exit_code: -1,
+                        duration_ms: 0,
+                        resource_usage: Default::default(),
+                    }),
+                };
+                let mut state = self.state.lock().await;
+                match outcome {
                     Ok(result) => {
-                        audit_log.record(
+                        let failure_class = if result.success {
+                            ""
+                        } else if result.error.starts_with("Execution timed out") {
+                            running::FAILURE_TIMEOUT
+                        } else if result.error.starts_with("Cancelled") {
+                            running::FAILURE_CANCELLED
+                        } else {
+                            running::FAILURE_ERROR
+                        };
+                        state.audit_log.record(
                             &response.execution_id,
                             &req.tool_name,
                             &req.agent_id,
@@ -153,15 +375,29 @@ impl ToolRegistry for ToolRegistryService {
                         );
                         return Ok(tonic::Response::new(proto::tools::ExecuteResponse {
                             success: result.success,
-                            output_json: result.output,
+                            output_json: state.executor.limit_output(
+                                &req.tool_name,
+                                &response.execution_id,
+                                result.output,
+                            ),
                             error: result.error,
                             execution_id: response.execution_id,
                             duration_ms: result.duration_ms as i64,
                             backup_id: String::new(),
+                            failure_class: failure_class.to_string(),
+                            progress_token: String::new(),
+                            cpu_time_ms: 0,
+                            bytes_written: 0,
+                            experimental: response.experimental,
                         }));
                     }
                     Err(e) => {
                         warn!("Plugin script execution failed: {e}");
+                        return Ok(tonic::Response::new(proto::tools::ExecuteResponse {
+                            error: format!("Plugin could not run: {e}"),
+                            failure_class: running::FAILURE_ERROR.to_string(),
+                            ..response
+                        }));
                     }
                 }
             }
@@ -173,6 +409,23 @@ impl ToolRegistry for ToolRegistryService {
             plugin::scan_and_register_plugins(registry);
         }
 
+        // Register or drop composites as they are defined and deleted; a
+        // definition whose steps cannot run is rejected
+        if response.success && req.tool_name == "composite.define" {
+            if let Err(e) = composite::manage::register_defined(registry, &req.input_json) {
+                warn!("Composite definition rejected: {e}");
+                return Ok(tonic::Response::new(proto::tools::ExecuteResponse {
+                    success: false,
+                    output_json: vec![],
+                    error: format!("Composite definition rejected: {e}"),
+                    ..response
+                }));
+            }
+        }
+        if response.success && req.tool_name == "composite.delete" {
+            composite::manage::deregister_deleted(registry, &req.input_json);
+        }
+
         // Plugin chaining: if a plugin succeeded, check metadata for next_plugins
         if response.success && req.tool_name.starts_with("plugin.") {
             let short_name = req
@@ -222,6 +475,9 @@ impl ToolRegistry for ToolRegistryService {
                                 agent_id: req.agent_id.clone(),
                                 task_id: req.task_id.clone(),
                                 reason: format!("Chained from {}", req.tool_name),
+                                workload: req.workload.clone(),
+                                resume_token: String::new(),
+                                allow_experimental: req.allow_experimental,
                             };
                             let chain_resp = executor
                                 .execute(registry, audit_log, backup_manager, chain_req)
@@ -278,6 +534,7 @@ impl ToolRegistry for ToolRegistryService {
         info!("Registering external tool: {}", tool.name);
 
         let mut state = self.state.lock().await;
+        state.external_tools.insert(tool.name.clone(), tool.clone());
         state.registry.register_tool(tool);
 
         Ok(tonic::Response::new(proto::tools::RegisterToolResponse {
@@ -292,6 +549,7 @@ impl ToolRegistry for ToolRegistryService {
     ) -> Result<tonic::Response<proto::tools::Status>, tonic::Status> {
         let req = request.into_inner();
         let mut state = self.state.lock().await;
+        state.external_tools.remove(&req.tool_name);
         state.registry.deregister_tool(&req.tool_name);
 
         Ok(tonic::Response::new(proto::tools::Status {
@@ -299,15 +557,142 @@ impl ToolRegistry for ToolRegistryService {
             message: format!("Tool {} deregistered", req.tool_name),
         }))
     }
+
+    async fn reload(
+        &self,
+        _request: tonic::Request<proto::common::Empty>,
+    ) -> Result<tonic::Response<proto::tools::ReloadResponse>, tonic::Status> {
+        let mut state = self.state.lock().await;
+        let diff = state.reload();
+
+        Ok(tonic::Response::new(proto::tools::ReloadResponse {
+            added: diff.added,
+            changed: diff.changed,
+            removed: diff.removed,
+            tool_count: state.registry.tool_count() as i32,
+        }))
+    }
+
+    async fn cancel(
+        &self,
+        request: tonic::Request<proto::tools::CancelRequest>,
+    ) -> Result<tonic::Response<proto::tools::CancelResponse>, tonic::Status> {
+        let req = request.into_inner();
+        let mut cancellation = self.running.cancel_tasks(&req.task_ids, &req.reason);
+        // Executions still waiting for approval never start
+        let parked = self.approvals.deny_tasks(&req.task_ids, &req.reason);
+        cancellation.cancelled.extend(parked);
+
+        Ok(tonic::Response::new(proto::tools::CancelResponse {
+            execution_ids: cancellation.cancelled,
+            deferred_execution_ids: cancellation.deferred,
+        }))
+    }
+
+    async fn list_pending_approvals(
+        &self,
+        _request: tonic::Request<proto::common::Empty>,
+    ) -> Result<tonic::Response<proto::tools::PendingApprovalList>, tonic::Status> {
+        Ok(tonic::Response::new(proto::tools::PendingApprovalList {
+            approvals: self.approvals.pending(),
+        }))
+    }
+
+    async fn approve(
+        &self,
+        request: tonic::Request<proto::tools::ApprovalDecisionRequest>,
+    ) -> Result<tonic::Response<proto::tools::ApprovalDecisionResponse>, tonic::Status> {
+        let decided_by = verified_decider(&request).await?;
+        let req = request.into_inner();
+        let approval = self
+            .approvals
+            .approve(&req.approval_id, &decided_by)
+            .map_err(|e| tonic::Status::permission_denied(e.to_string()))?;
+
+        Ok(tonic::Response::new(
+            proto::tools::ApprovalDecisionResponse {
+                found: approval.is_some(),
+                approval,
+            },
+        ))
+    }
+
+    async fn deny(
+        &self,
+        request: tonic::Request<proto::tools::ApprovalDecisionRequest>,
+    ) -> Result<tonic::Response<proto::tools::ApprovalDecisionResponse>, tonic::Status> {
+        let decided_by = verified_decider(&request).await?;
+        let req = request.into_inner();
+        let approval = self
+            .approvals
+            .deny(&req.approval_id, &decided_by, &req.reason)
+            .map_err(|e| tonic::Status::permission_denied(e.to_string()))?;
+
+        Ok(tonic::Response::new(
+            proto::tools::ApprovalDecisionResponse {
+                found: approval.is_some(),
+                approval,
+            },
+        ))
+    }
+
+    async fn simulate_policy(
+        &self,
+        request: tonic::Request<proto::tools::PolicySimulationRequest>,
+    ) -> Result<tonic::Response<proto::tools::PolicySimulationReport>, tonic::Status> {
+        let req = request.into_inner();
+        let days = if req.days > 0 { req.days } else { 7 };
+        let max_listed = if req.max_listed > 0 {
+            req.max_listed
+        } else {
+            100
+        };
+        let since = chrono::Utc::now() - chrono::Duration::days(i64::from(days));
+
+        let state = self.state.lock().await;
+        let current = state.executor.policy();
+        let proposed = current
+            .with_changes(&req.policy_toml)
+            .map_err(|e| tonic::Status::invalid_argument(format!("{e:#}")))?;
+        let records = state
+            .audit_log
+            .records_since(since)
+            .map_err(|e| tonic::Status::internal(format!("Failed to read audit log: {e}")))?;
+
+        let mut report = policy_sim::simulate(
+            &records,
+            &state.registry,
+            &current,
+            &proposed,
+            max_listed as usize,
+        );
+        report.since = since.timestamp();
+        Ok(tonic::Response::new(report))
+    }
+
+    async fn get_api_version(
+        &self,
+        _request: tonic::Request<proto::common::Empty>,
+    ) -> Result<tonic::Response<proto::common::ApiVersion>, tonic::Status> {
+        Ok(tonic::Response::new(api_version::api_version(
+            proto::tools::tool_registry_server::SERVICE_NAME,
+        )))
+    }
+
+    async fn get_build_info(
+        &self,
+        _request: tonic::Request<proto::common::Empty>,
+    ) -> Result<tonic::Response<proto::common::BuildInfo>, tonic::Status> {
+        Ok(tonic::Response::new(build_info::build_info(
+            proto::tools::tool_registry_server::SERVICE_NAME,
+            Vec::new(),
+        )))
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    tracing_subscriber::fmt()
-        .with_target(false)
-        .with_level(true)
-        .compact()
-        .init();
+    let telemetry = telemetry::init("aios-tools");
 
     info!("aiOS Tool Registry starting...");
 
@@ -318,27 +703,165 @@ async fn main() -> Result<()> {
     // Load any previously-created plugins from disk
     plugin::scan_and_register_plugins(&mut reg);
 
+    // Composites last: their steps must already be registered
+    composite::scan_and_register_composites(&mut reg);
+
+    // Mirror the ledger into an append-only log and compare the two
+    let mut audit_log = audit::AuditLog::new(siem::LEDGER_DB_PATH)?;
+    match audit_mirror::AuditMirror::open(
+        audit_mirror::MIRROR_LOG_PATH,
+        audit_mirror::MIRROR_KEY_PATH,
+    ) {
+        Ok(mirror) => match audit_log.attach_mirror(mirror.clone()) {
+            Ok(()) => {
+                tokio::spawn(audit_mirror::run_verifier(mirror, siem::LEDGER_DB_PATH));
+            }
+            Err(e) => warn!("Audit mirror disabled: {e}"),
+        },
+        Err(e) => warn!("Audit mirror disabled: {e}"),
+    }
+
     let state = Arc::new(Mutex::new(ToolRegistryState {
         registry: reg,
         executor: executor::Executor::new(),
-        audit_log: audit::AuditLog::new("/var/lib/aios/ledger/audit.db")?,
+        audit_log,
         backup_manager: backup::BackupManager::new("/var/lib/aios/cache/backups"),
+        external_tools: HashMap::new(),
     }));
 
-    let service = ToolRegistryService { state };
+    // Reload the registry when plugins change; a burst of file events
+    // (script and metadata written together) causes a single reload
+    let (reload_tx, mut reload_rx) = tokio::sync::mpsc::unbounded_channel();
+    let _plugin_watcher = plugin::start_hot_reload_watcher(move || {
+        let _ = reload_tx.send(());
+    });
+    let reload_state = state.clone();
+    tokio::spawn(async move {
+        while reload_rx.recv().await.is_some() {
+            tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+            while reload_rx.try_recv().is_ok() {}
+            let diff = {
+                let mut state = reload_state.lock().await;
+                let diff = state.reload();
+                state.executor.daemons().reconcile();
+                diff
+            };
+            for name in diff.added.iter().chain(&diff.changed) {
+                info!("Plugin hot-reload: {name} is now available");
+            }
+            for name in &diff.removed {
+                info!("Plugin hot-reload: {name} removed");
+            }
+        }
+    });
+
+    // Forward the audit ledger and capability grants to a SIEM, if configured
+    if let Some(siem_config) = siem::SiemConfig::load(siem::SIEM_CONFIG_PATH) {
+        tokio::spawn(siem::run(siem_config));
+    }
+
+    // Purge trash entries past their retention window
+    tokio::spawn(fs::trash::run_purger());
+
+    // Watch planted canaries for access
+    if let Err(e) = sec::canary::start_watcher() {
+        warn!("Canary watcher unavailable, relying on sec.canary_check sweeps: {e}");
+    }
+
+    // Start the daemon plugins
+    let daemons = state.lock().await.executor.daemons();
+    daemons.reconcile();
+
+    let running = state.lock().await.executor.running();
+    let service = ToolRegistryService {
+        state,
+        running: running.clone(),
+        slots: reservation::SlotPool::from_env(),
+        approvals: Arc::new(approvals::ApprovalQueue::new(
+            approvals::ApprovalConfig::load(approvals::TOOL_APPROVALS_CONFIG_PATH),
+        )),
+    };
+
+    // Run the plugins whose event triggers match new events
+    let triggered = service.clone();
+    tokio::spawn(plugin::events::run_event_triggers(move |firing| {
+        let service = triggered.clone();
+        async move {
+            let tool_name = firing.tool_name.clone();
+            let request = tonic::Request::new(proto::tools::ExecuteRequest {
+                tool_name: firing.tool_name,
+                agent_id: plugin::events::TRIGGER_AGENT_ID.into(),
+                input_json: firing.input,
+                reason: firing.reason,
+                workload: "background".into(),
+                ..Default::default()
+            });
+            match service.execute(request).await {
+                Ok(response) if !response.get_ref().success => {
+                    warn!(
+                        "Triggered plugin {tool_name} failed: {}",
+                        response.into_inner().error
+                    );
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Triggered plugin {tool_name} failed: {e}"),
+            }
+        }
+    }));
 
     let addr: SocketAddr = "0.0.0.0:50052".parse()?;
     info!("Tool Registry gRPC server listening on {addr}");
 
     Server::builder()
+        .trace_fn(telemetry::server_span)
+        .layer(api_version::legacy_shim())
         .add_service(ToolRegistryServer::new(service))
-        .serve(addr)
+        .serve_with_shutdown(addr, shutdown_signal(running))
         .await
         .context("Tool Registry gRPC server failed")?;
 
+    daemons.shutdown();
+    info!("Tool Registry shut down cleanly");
+    telemetry::shutdown(telemetry);
     Ok(())
 }
 
+/// Resolve on SIGINT or SIGTERM — including the restart after a
+/// self-update — once no critical section is in progress
+async fn shutdown_signal(running: Arc<running::RunningExecutions>) {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => info!("Received SIGINT, shutting down..."),
+                    _ = sigterm.recv() => info!("Received SIGTERM, shutting down..."),
+                }
+            }
+            Err(e) => {
+                warn!("Failed to listen for SIGTERM: {e}");
+                let _ = tokio::signal::ctrl_c().await;
+                info!("Received SIGINT, shutting down...");
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+        info!("Received SIGINT, shutting down...");
+    }
+
+    let sections = running.critical_sections();
+    if !sections.is_empty() {
+        info!(
+            "Shutdown waits for critical sections to end: {}",
+            sections.join(", ")
+        );
+        running.critical_idle().await;
+    }
+}
+
 /// Register all built-in system tools
 fn register_builtin_tools(reg: &mut registry::Registry) {
     // Filesystem tools
@@ -359,6 +882,8 @@ fn register_builtin_tools(reg: &mut registry::Registry) {
     monitor::register_tools(reg);
     // Hardware tools
     hw::register_tools(reg);
+    // Block-device tools
+    disk::register_tools(reg);
     // Web connectivity tools
     web::register_tools(reg);
     // Git tools
@@ -369,10 +894,24 @@ fn register_builtin_tools(reg: &mut registry::Registry) {
     self_update::register_tools(reg);
     // Plugin meta-tools
     plugin::register_tools(reg);
+    // Composite meta-tools
+    composite::register_tools(reg);
     // Container tools (Podman)
     container::register_tools(reg);
     // Email tools
     email::register_tools(reg);
+    // Paging for truncated outputs
+    output::register_tools(reg);
+    // Hashing tools
+    hash::register_tools(reg);
+    // Config templating
+    template::register_tools(reg);
+    // Structured data tools
+    data::register_tools(reg);
+    // Text-processing tools
+    text::register_tools(reg);
+    // Calculation tools
+    calc::register_tools(reg);
 
     info!("Registered {} built-in tools", reg.tool_count());
 }
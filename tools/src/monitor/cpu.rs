@@ -2,7 +2,8 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+
+use crate::sandbox::command;
 
 #[derive(Deserialize)]
 struct Input {}
@@ -38,7 +39,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
 
 fn get_cpu_macos() -> Result<(f64, u32, [f64; 3])> {
     // Get core count from sysctl
-    let cores_output = Command::new("sysctl")
+    let cores_output = command("sysctl")
         .args(["-n", "hw.ncpu"])
         .output()
         .context("Failed to get CPU core count")?;
@@ -49,7 +50,7 @@ fn get_cpu_macos() -> Result<(f64, u32, [f64; 3])> {
         .unwrap_or(1);
 
     // Get load averages from sysctl
-    let load_output = Command::new("sysctl")
+    let load_output = command("sysctl")
         .args(["-n", "vm.loadavg"])
         .output()
         .context("Failed to get load averages")?;
@@ -59,7 +60,7 @@ fn get_cpu_macos() -> Result<(f64, u32, [f64; 3])> {
 
     // Get CPU usage from top (snapshot mode)
     // On macOS: top -l 1 -n 0 prints a header with CPU usage
-    let top_output = Command::new("top")
+    let top_output = command("top")
         .args(["-l", "2", "-n", "0", "-s", "1"])
         .output()
         .context("Failed to get CPU usage from top")?;
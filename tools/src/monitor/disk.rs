@@ -2,7 +2,8 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+
+use crate::sandbox::command;
 
 #[derive(Deserialize)]
 struct Input {
@@ -39,7 +40,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
 
     // Use df to get disk usage
     // -k: 1K blocks for consistent parsing
-    let output = Command::new("df")
+    let output = command("df")
         .args(["-k", &path])
         .output()
         .with_context(|| format!("Failed to execute df for path: {}", path))?;
@@ -2,7 +2,8 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+
+use crate::sandbox::command;
 
 #[derive(Deserialize)]
 struct Input {
@@ -49,7 +50,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
 
 fn read_logs_macos(lines: u32, service: &str) -> Result<Vec<String>> {
     // Use the `log` command on macOS with --last to get recent entries
-    let mut cmd = Command::new("log");
+    let mut cmd = command("log");
     cmd.args(["show", "--last", "1h", "--style", "compact"]);
 
     if !service.is_empty() {
@@ -74,7 +75,7 @@ fn read_logs_macos(lines: u32, service: &str) -> Result<Vec<String>> {
 
 fn read_logs_linux(lines: u32, service: &str) -> Result<Vec<String>> {
     // Use journalctl on Linux
-    let mut cmd = Command::new("journalctl");
+    let mut cmd = command("journalctl");
     cmd.args(["-n", &lines.to_string(), "--no-pager", "-o", "short-iso"]);
 
     if !service.is_empty() {
@@ -2,7 +2,8 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+
+use crate::sandbox::command;
 
 #[derive(Deserialize)]
 struct Input {}
@@ -33,7 +34,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
 
 fn get_memory_macos() -> Result<Output> {
     // Get total physical memory from sysctl
-    let total_output = Command::new("sysctl")
+    let total_output = command("sysctl")
         .args(["-n", "hw.memsize"])
         .output()
         .context("Failed to get total memory from sysctl")?;
@@ -46,7 +47,7 @@ fn get_memory_macos() -> Result<Output> {
     let total_mb = total_bytes / (1024 * 1024);
 
     // Get memory usage from vm_stat
-    let vm_output = Command::new("vm_stat")
+    let vm_output = command("vm_stat")
         .output()
         .context("Failed to execute vm_stat")?;
 
@@ -107,7 +108,7 @@ fn get_memory_macos() -> Result<Output> {
 }
 
 fn get_page_size() -> u64 {
-    let output = Command::new("sysctl").args(["-n", "hw.pagesize"]).output();
+    let output = command("sysctl").args(["-n", "hw.pagesize"]).output();
 
     match output {
         Ok(out) => String::from_utf8_lossy(&out.stdout)
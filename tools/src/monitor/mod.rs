@@ -11,84 +11,142 @@ pub mod logs;
 pub mod memory;
 pub mod network;
 
-use crate::registry::{make_tool, Registry};
+use serde_json::json;
+
+use crate::registry::{make_tool, with_input_schema, Registry};
 
 /// Register every monitor tool with the registry.
 pub fn register_tools(reg: &mut Registry) {
-    reg.register_tool(make_tool(
-        "monitor.cpu",
-        "monitor",
-        "Report current CPU usage percentage, core count, and load averages",
-        vec!["monitor.read"],
-        "low",
-        true,
-        false,
-        5000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "monitor.cpu",
+            "monitor",
+            "Report current CPU usage percentage, core count, and load averages",
+            vec!["monitor.read"],
+            "low",
+            true,
+            false,
+            5000,
+        ),
+        json!({ "type": "object" }),
     ));
 
-    reg.register_tool(make_tool(
-        "monitor.memory",
-        "monitor",
-        "Report memory usage: total, used, available, and utilisation percentage",
-        vec!["monitor.read"],
-        "low",
-        true,
-        false,
-        5000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "monitor.memory",
+            "monitor",
+            "Report memory usage: total, used, available, and utilisation percentage",
+            vec!["monitor.read"],
+            "low",
+            true,
+            false,
+            5000,
+        ),
+        json!({ "type": "object" }),
     ));
 
-    reg.register_tool(make_tool(
-        "monitor.disk",
-        "monitor",
-        "Report disk usage for the filesystem containing a given path",
-        vec!["monitor.read"],
-        "low",
-        true,
-        false,
-        5000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "monitor.disk",
+            "monitor",
+            "Report disk usage for the filesystem containing a given path",
+            vec!["monitor.read"],
+            "low",
+            true,
+            false,
+            5000,
+        ),
+        json!({ "type": "object", "properties": { "path": { "type": "string", "default": "/" } } }),
     ));
 
-    reg.register_tool(make_tool(
-        "monitor.network",
-        "monitor",
-        "Report network I/O statistics for a given interface",
-        vec!["monitor.read"],
-        "low",
-        true,
-        false,
-        5000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "monitor.network",
+            "monitor",
+            "Report network I/O statistics for a given interface",
+            vec!["monitor.read"],
+            "low",
+            true,
+            false,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": { "interface": { "type": "string", "default": "en0" } }
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "monitor.logs",
-        "monitor",
-        "Read recent system log entries, optionally filtered by service name",
-        vec!["monitor.read"],
-        "low",
-        true,
-        false,
-        10000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "monitor.logs",
+            "monitor",
+            "Read recent system log entries, optionally filtered by service name",
+            vec!["monitor.read"],
+            "low",
+            true,
+            false,
+            10000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "lines": { "type": "integer", "minimum": 1, "default": 100 },
+                "service": { "type": "string", "description": "Only this service's log" }
+            }
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "monitor.ebpf_trace",
-        "monitor",
-        "Trace syscalls: process spawns, file opens, and network connections",
-        vec!["monitor.read"],
-        "medium",
-        true,
-        false,
-        30000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "monitor.ebpf_trace",
+            "monitor",
+            "Trace syscalls: process spawns, file opens, and network connections",
+            vec!["monitor.read"],
+            "medium",
+            true,
+            false,
+            30000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "trace_type": {
+                    "type": "string",
+                    "enum": ["process_spawns", "file_opens", "network_connections"],
+                    "default": "process_spawns"
+                },
+                "duration_secs": { "type": "integer", "minimum": 1, "default": 5 },
+                "pid": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Only this process (file_opens)"
+                }
+            }
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "monitor.fs_watch",
-        "monitor",
-        "Monitor filesystem for recently modified files under a given path",
-        vec!["monitor.read"],
-        "low",
-        true,
-        false,
-        10000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "monitor.fs_watch",
+            "monitor",
+            "Monitor filesystem for recently modified files under a given path",
+            vec!["monitor.read"],
+            "low",
+            true,
+            false,
+            10000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "path": { "type": "string" },
+                "recursive": { "type": "boolean", "default": true },
+                "since_timestamp": {
+                    "type": "integer",
+                    "description": "Unix seconds; only changes after it"
+                }
+            },
+            "required": ["path"]
+        }),
     ));
 }
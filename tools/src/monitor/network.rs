@@ -2,7 +2,8 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+
+use crate::sandbox::command;
 
 #[derive(Deserialize)]
 struct Input {
@@ -48,7 +49,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
 
 fn get_network_stats_macos(interface: &str) -> Result<Output> {
     // Use netstat -I <iface> -b to get byte and packet counts
-    let output = Command::new("netstat")
+    let output = command("netstat")
         .args(["-I", interface, "-b"])
         .output()
         .with_context(|| format!("Failed to get netstat for interface {}", interface))?;
@@ -54,9 +54,9 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
 }
 
 fn resolve_with_host_command(hostname: &str) -> Option<Vec<String>> {
-    use std::process::Command;
+    use crate::sandbox::command;
 
-    let output = Command::new("host").arg(hostname).output().ok()?;
+    let output = command("host").arg(hostname).output().ok()?;
 
     if !output.status.success() {
         return None;
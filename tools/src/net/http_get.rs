@@ -2,7 +2,8 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+
+use crate::sandbox::command;
 
 #[derive(Deserialize)]
 struct Input {
@@ -23,7 +24,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
     // -w: write out the HTTP status code after the body
     // -L: follow redirects
     // --max-time: timeout in seconds
-    let output = Command::new("curl")
+    let output = command("curl")
         .args([
             "-s",
             "-S",
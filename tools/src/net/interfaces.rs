@@ -2,7 +2,8 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+
+use crate::sandbox::command;
 
 #[derive(Deserialize)]
 struct Input {}
@@ -31,7 +32,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
 
     if cfg!(target_os = "macos") {
         // Use ifconfig on macOS
-        let output = Command::new("ifconfig")
+        let output = command("ifconfig")
             .output()
             .context("Failed to execute ifconfig")?;
 
@@ -94,7 +95,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
         }
     } else {
         // On Linux, use ip command
-        let output = Command::new("ip")
+        let output = command("ip")
             .args(["-o", "link", "show"])
             .output()
             .context("Failed to execute ip link show")?;
@@ -140,7 +141,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
 
 #[cfg(not(target_os = "macos"))]
 fn get_linux_ip(iface: &str) -> String {
-    let output = Command::new("ip")
+    let output = command("ip")
         .args(["-o", "-4", "addr", "show", iface])
         .output();
 
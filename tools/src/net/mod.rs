@@ -8,62 +8,101 @@ pub mod interfaces;
 pub mod ping;
 pub mod port_scan;
 
-use crate::registry::{make_tool, Registry};
+use serde_json::json;
+
+use crate::registry::{make_tool, with_input_schema, Registry};
 
 /// Register every network tool with the registry.
 pub fn register_tools(reg: &mut Registry) {
-    reg.register_tool(make_tool(
-        "net.interfaces",
-        "net",
-        "List all network interfaces with name, IP address, MAC address, and status",
-        vec!["net.read"],
-        "low",
-        true,
-        false,
-        5000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "net.interfaces",
+            "net",
+            "List all network interfaces with name, IP address, MAC address, and status",
+            vec!["net.read"],
+            "low",
+            true,
+            false,
+            5000,
+        ),
+        json!({ "type": "object" }),
     ));
 
-    reg.register_tool(make_tool(
-        "net.ping",
-        "net",
-        "Ping a remote host and return success status and latency",
-        vec!["net.read"],
-        "low",
-        true,
-        false,
-        30000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "net.ping",
+            "net",
+            "Ping a remote host and return success status and latency",
+            vec!["net.read"],
+            "low",
+            true,
+            false,
+            30000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "host": { "type": "string" },
+                "count": { "type": "integer", "minimum": 1, "default": 3 }
+            },
+            "required": ["host"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "net.dns",
-        "net",
-        "Perform a DNS lookup for a hostname and return resolved addresses",
-        vec!["net.read"],
-        "low",
-        true,
-        false,
-        10000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "net.dns",
+            "net",
+            "Perform a DNS lookup for a hostname and return resolved addresses",
+            vec!["net.read"],
+            "low",
+            true,
+            false,
+            10000,
+        ),
+        json!({
+            "type": "object",
+            "properties": { "hostname": { "type": "string" } },
+            "required": ["hostname"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "net.http_get",
-        "net",
-        "Perform an HTTP GET request and return the status code and response body",
-        vec!["net.http"],
-        "medium",
-        true,
-        false,
-        30000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "net.http_get",
+            "net",
+            "Perform an HTTP GET request and return the status code and response body",
+            vec!["net.http"],
+            "medium",
+            true,
+            false,
+            30000,
+        ),
+        json!({
+            "type": "object",
+            "properties": { "url": { "type": "string" } },
+            "required": ["url"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "net.port_scan",
-        "net",
-        "Check whether a specific TCP port is open on a given host",
-        vec!["net.read"],
-        "medium",
-        true,
-        false,
-        10000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "net.port_scan",
+            "net",
+            "Check whether a specific TCP port is open on a given host",
+            vec!["net.read"],
+            "medium",
+            true,
+            false,
+            10000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "host": { "type": "string" },
+                "port": { "type": "integer", "minimum": 0, "maximum": 65535 }
+            },
+            "required": ["host", "port"]
+        }),
     ));
 }
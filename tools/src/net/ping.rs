@@ -2,7 +2,8 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+
+use crate::sandbox::command;
 
 #[derive(Deserialize)]
 struct Input {
@@ -26,7 +27,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
 
     let count = if input.count == 0 { 3 } else { input.count };
 
-    let output = Command::new("ping")
+    let output = command("ping")
         .args([
             "-c",
             &count.to_string(),
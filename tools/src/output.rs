@@ -0,0 +1,477 @@
+//! Tool output size limits and paging
+//!
+//! Oversized tool outputs are truncated to a per-tool cap before they leave
+//! the tool service. The full output is retained in a bounded in-memory store
+//! and can be read back piecewise with the `output.page` tool using the
+//! `page_handle` from the `_truncated` marker.
+
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use tracing::{info, warn};
+
+use crate::registry::{make_tool, with_input_schema, Registry};
+
+/// Default inline output cap for a single tool call
+pub const DEFAULT_OUTPUT_LIMIT: usize = 64 * 1024;
+
+/// Tighter inline cap for tools that routinely return bulk text
+const TEXT_OUTPUT_LIMIT: usize = 32 * 1024;
+
+/// Total bytes of full outputs retained for paging
+const STORE_BUDGET_BYTES: usize = 64 * 1024 * 1024;
+
+/// Default and maximum page sizes for `output.page`
+const DEFAULT_PAGE_BYTES: usize = 16 * 1024;
+const MAX_PAGE_BYTES: usize = 32 * 1024;
+
+/// Room left under the cap for the truncation marker
+const MARKER_RESERVE: usize = 512;
+
+/// Inline output cap for a tool
+pub fn output_limit_for(tool_name: &str) -> usize {
+    match tool_name {
+        // Pages are already bounded by MAX_PAGE_BYTES
+        "output.page" => usize::MAX,
+        "fs.read" | "git.diff" | "git.log" | "monitor.logs" | "container.logs" | "net.http_get"
+        | "web.scrape" | "web.http_request" | "web.api_call" => TEXT_OUTPUT_LIMIT,
+        _ => DEFAULT_OUTPUT_LIMIT,
+    }
+}
+
+/// Register the paging tool with the registry.
+pub fn register_tools(reg: &mut Registry) {
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "output.page",
+            "output",
+            "Read the remainder of a truncated tool output. Input: {\"handle\": \"<page_handle>\", \"offset\": <bytes>, \"length\": <bytes>}",
+            vec![],
+            "low",
+            true,
+            false,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "handle": {
+                    "type": "string",
+                    "description": "page_handle of the truncated output"
+                },
+                "offset": { "type": "integer", "minimum": 0 },
+                "length": { "type": "integer", "minimum": 1 }
+            },
+            "required": ["handle"]
+        }),
+    ));
+}
+
+/// Bounded store of full outputs that were truncated, keyed by execution ID
+pub struct OutputStore {
+    entries: Mutex<VecDeque<(String, Vec<u8>)>>,
+    budget_bytes: usize,
+}
+
+impl Default for OutputStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OutputStore {
+    pub fn new() -> Self {
+        Self::with_budget(STORE_BUDGET_BYTES)
+    }
+
+    pub fn with_budget(budget_bytes: usize) -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::new()),
+            budget_bytes,
+        }
+    }
+
+    /// Apply the tool's output cap. Outputs within the cap pass through
+    /// unchanged; larger ones are truncated with a `_truncated` marker and
+    /// retained under `handle` for paging.
+    pub fn limit(&self, tool_name: &str, handle: &str, output: Vec<u8>) -> Vec<u8> {
+        let limit = output_limit_for(tool_name);
+        if output.len() <= limit {
+            return output;
+        }
+
+        let total_bytes = output.len();
+        let retained = self.retain(handle, output.clone());
+        info!(
+            "Truncating {tool_name} output: {total_bytes} bytes exceeds {limit} byte cap (retained: {retained})"
+        );
+        truncate_output(&output, limit, retained.then_some(handle))
+    }
+
+    /// Keep a full output for paging, evicting the oldest entries to stay in budget.
+    fn retain(&self, handle: &str, output: Vec<u8>) -> bool {
+        if output.len() > self.budget_bytes {
+            warn!(
+                "Output of {} bytes exceeds paging budget, not retained",
+                output.len()
+            );
+            return false;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        let mut used: usize = entries.iter().map(|(_, data)| data.len()).sum();
+        while used + output.len() > self.budget_bytes {
+            match entries.pop_front() {
+                Some((_, evicted)) => used -= evicted.len(),
+                None => break,
+            }
+        }
+        entries.push_back((handle.to_string(), output));
+        true
+    }
+
+    /// Read `length` bytes of a retained output starting at `offset`.
+    /// Boundaries are moved to UTF-8 character boundaries so text is never split;
+    /// a page always holds at least one whole character, so paging advances.
+    pub fn page(&self, handle: &str, offset: usize, length: usize) -> Result<Value> {
+        let entries = self.entries.lock().unwrap();
+        let data = entries
+            .iter()
+            .find(|(h, _)| h == handle)
+            .map(|(_, data)| data)
+            .ok_or_else(|| {
+                anyhow::anyhow!("output.page: unknown or expired page handle '{handle}'")
+            })?;
+
+        let total = data.len();
+        let mut start = offset.min(total);
+        while start < total && is_continuation(data[start]) {
+            start += 1;
+        }
+        let mut end = start
+            .saturating_add(length.clamp(1, MAX_PAGE_BYTES))
+            .min(total);
+        while end < total && end > start && is_continuation(data[end]) {
+            end -= 1;
+        }
+        if end == start && start < total {
+            // `length` is shorter than the character at `start`
+            end = start + 1;
+            while end < total && is_continuation(data[end]) {
+                end += 1;
+            }
+        }
+
+        Ok(json!({
+            "handle": handle,
+            "offset": start,
+            "length": end - start,
+            "total_bytes": total,
+            "next_offset": if end < total { Some(end) } else { None },
+            "data": String::from_utf8_lossy(&data[start..end]),
+        }))
+    }
+
+    /// Handler for the `output.page` tool
+    pub fn execute_page(&self, input: &[u8]) -> Result<Vec<u8>> {
+        let v: Value = serde_json::from_slice(input).context("output.page: invalid JSON input")?;
+        let handle = v
+            .get("handle")
+            .and_then(|h| h.as_str())
+            .ok_or_else(|| anyhow::anyhow!("output.page: missing required field 'handle'"))?;
+        let offset = v.get("offset").and_then(|o| o.as_u64()).unwrap_or(0) as usize;
+        let length = v
+            .get("length")
+            .and_then(|l| l.as_u64())
+            .map(|l| l as usize)
+            .unwrap_or(DEFAULT_PAGE_BYTES);
+
+        let page = self.page(handle, offset, length)?;
+        serde_json::to_vec(&page).context("output.page: failed to serialise output")
+    }
+}
+
+fn is_continuation(byte: u8) -> bool {
+    byte & 0xC0 == 0x80
+}
+
+/// Truncate a serialized output to roughly `limit` bytes while keeping it valid
+/// JSON. The longest strings are cut and the longest arrays shortened first, so
+/// the overall shape survives; non-JSON output is wrapped as `content`.
+pub fn truncate_output(output: &[u8], limit: usize, handle: Option<&str>) -> Vec<u8> {
+    let total_bytes = output.len();
+    let target = limit.saturating_sub(MARKER_RESERVE);
+    let mut value: Value = serde_json::from_slice(output)
+        .unwrap_or_else(|_| json!({ "content": String::from_utf8_lossy(output) }));
+
+    for _ in 0..64 {
+        let size = serde_json::to_vec(&value).map(|v| v.len()).unwrap_or(0);
+        if size <= target || !shrink_largest(&mut value, size - target) {
+            break;
+        }
+    }
+
+    // Still too large (e.g. thousands of small keys) — fall back to a text prefix,
+    // leaving headroom for escaping when it is re-serialized
+    if serde_json::to_vec(&value).map(|v| v.len()).unwrap_or(0) > target {
+        let text = serde_json::to_string(&value).unwrap_or_default();
+        value = json!({ "content": truncate_str(&text, target / 2) });
+    }
+
+    let marker = json!({
+        "total_bytes": total_bytes,
+        "limit_bytes": limit,
+        "page_handle": handle,
+        "hint": match handle {
+            Some(h) => format!("Output truncated. Call output.page with {{\"handle\": \"{h}\", \"offset\": 0}} to read the full output."),
+            None => "Output truncated; the full output was too large to retain.".to_string(),
+        },
+    });
+    let value = match value {
+        Value::Object(mut map) => {
+            map.insert("_truncated".to_string(), marker);
+            Value::Object(map)
+        }
+        other => json!({ "result": other, "_truncated": marker }),
+    };
+    serde_json::to_vec(&value).unwrap_or_default()
+}
+
+/// Cut the longest string or the longest array in `value` by about `excess` bytes.
+/// Returns false when nothing is left to shrink.
+fn shrink_largest(value: &mut Value, excess: usize) -> bool {
+    let mut longest_string: Option<(usize, String)> = None;
+    let mut longest_array: Option<(usize, String)> = None;
+    find_largest(
+        value,
+        String::new(),
+        &mut longest_string,
+        &mut longest_array,
+    );
+
+    let string_len = longest_string.as_ref().map_or(0, |(len, _)| *len);
+    let array_len = longest_array.as_ref().map_or(0, |(len, _)| *len);
+
+    if string_len >= 64 && string_len >= array_len {
+        let (len, pointer) = longest_string.unwrap();
+        if let Some(Value::String(s)) = value.pointer_mut(&pointer) {
+            // Escaping can make the serialized string longer than its raw bytes,
+            // so scale the excess back to raw bytes before cutting
+            let serialized_len = serde_json::to_string(&*s).map_or(len, |j| j.len()).max(1);
+            let keep = len.saturating_sub(excess * len / serialized_len + 64);
+            let kept = truncate_str(s, keep).to_string();
+            let omitted = len - kept.len();
+            *s = format!("{kept}…[truncated {omitted} bytes]");
+            return true;
+        }
+    } else if array_len > 0 {
+        let (_, pointer) = longest_array.unwrap();
+        if let Some(Value::Array(items)) = value.pointer_mut(&pointer) {
+            if items.len() > 1 {
+                let keep = items.len() / 2;
+                let dropped = items.len() - keep;
+                items.truncate(keep);
+                items.push(Value::String(format!("…[{dropped} more items truncated]")));
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// Record the JSON pointer and serialized size of the longest string and of
+/// the longest (multi-element) array below `value`.
+fn find_largest(
+    value: &Value,
+    pointer: String,
+    longest_string: &mut Option<(usize, String)>,
+    longest_array: &mut Option<(usize, String)>,
+) {
+    match value {
+        Value::String(s)
+            if longest_string
+                .as_ref()
+                .is_none_or(|(len, _)| s.len() > *len) =>
+        {
+            *longest_string = Some((s.len(), pointer));
+        }
+        Value::Array(items) => {
+            if items.len() > 1 {
+                let size = serde_json::to_vec(value).map(|v| v.len()).unwrap_or(0);
+                if longest_array.as_ref().is_none_or(|(len, _)| size > *len) {
+                    *longest_array = Some((size, pointer.clone()));
+                }
+            }
+            for (i, item) in items.iter().enumerate() {
+                find_largest(
+                    item,
+                    format!("{pointer}/{i}"),
+                    longest_string,
+                    longest_array,
+                );
+            }
+        }
+        Value::Object(map) => {
+            for (key, item) in map {
+                let escaped = key.replace('~', "~0").replace('/', "~1");
+                find_largest(
+                    item,
+                    format!("{pointer}/{escaped}"),
+                    longest_string,
+                    longest_array,
+                );
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Longest prefix of `s` that fits in `max` bytes without splitting a character
+fn truncate_str(s: &str, max: usize) -> &str {
+    if s.len() <= max {
+        return s;
+    }
+    let mut end = max;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big_read_output(size: usize) -> Vec<u8> {
+        serde_json::to_vec(&json!({ "content": "x".repeat(size), "size": size })).unwrap()
+    }
+
+    #[test]
+    fn test_small_output_passes_through() {
+        let store = OutputStore::new();
+        let output = br#"{"cpu_percent": 12.5}"#.to_vec();
+        assert_eq!(store.limit("monitor.cpu", "exec-1", output.clone()), output);
+    }
+
+    #[test]
+    fn test_oversized_output_truncated_with_marker() {
+        let store = OutputStore::new();
+        let output = big_read_output(200 * 1024);
+        let limited = store.limit("fs.read", "exec-1", output.clone());
+        assert!(limited.len() <= TEXT_OUTPUT_LIMIT);
+
+        let v: Value = serde_json::from_slice(&limited).unwrap();
+        assert_eq!(v["size"], 200 * 1024);
+        assert!(v["content"].as_str().unwrap().contains("[truncated"));
+        assert_eq!(v["_truncated"]["page_handle"], "exec-1");
+        assert_eq!(v["_truncated"]["total_bytes"], output.len());
+    }
+
+    #[test]
+    fn test_truncate_large_array() {
+        let rows: Vec<Value> = (0..5000)
+            .map(|i| json!({ "pid": i, "name": "worker" }))
+            .collect();
+        let output = serde_json::to_vec(&json!({ "processes": rows })).unwrap();
+        let limited = truncate_output(&output, 8 * 1024, Some("h"));
+        assert!(limited.len() <= 8 * 1024);
+
+        let v: Value = serde_json::from_slice(&limited).unwrap();
+        let processes = v["processes"].as_array().unwrap();
+        assert!(processes.len() < 5000);
+        assert!(processes
+            .last()
+            .unwrap()
+            .as_str()
+            .unwrap()
+            .contains("more items"));
+    }
+
+    #[test]
+    fn test_truncate_non_json_output() {
+        let output = "line\n".repeat(10_000).into_bytes();
+        let limited = truncate_output(&output, 4096, None);
+        let v: Value = serde_json::from_slice(&limited).unwrap();
+        assert!(v["content"].as_str().unwrap().starts_with("line\n"));
+        assert!(v["_truncated"]["page_handle"].is_null());
+    }
+
+    #[test]
+    fn test_page_through_retained_output() {
+        let store = OutputStore::new();
+        let output = big_read_output(100 * 1024);
+        store.limit("fs.read", "exec-1", output.clone());
+
+        let mut reassembled = String::new();
+        let mut offset = 0;
+        loop {
+            let page = store.page("exec-1", offset, 10_000).unwrap();
+            reassembled.push_str(page["data"].as_str().unwrap());
+            match page["next_offset"].as_u64() {
+                Some(next) => offset = next as usize,
+                None => break,
+            }
+        }
+        assert_eq!(reassembled.as_bytes(), &output[..]);
+    }
+
+    #[test]
+    fn test_page_respects_char_boundaries() {
+        let store = OutputStore::with_budget(1024);
+        assert!(store.retain("h", "héllo wörld".as_bytes().to_vec()));
+        // Offset 2 falls inside 'é' and is moved forward to the next character
+        let page = store.page("h", 2, 4).unwrap();
+        assert_eq!(page["offset"], 3);
+        assert_eq!(page["data"], "llo ");
+    }
+
+    #[test]
+    fn test_page_shorter_than_a_character_still_advances() {
+        let store = OutputStore::with_budget(1024);
+        let text = "a€b😀";
+        assert!(store.retain("h", text.as_bytes().to_vec()));
+        let page = store.page("h", 1, 1).unwrap();
+        assert_eq!(page["data"], "€");
+        assert_eq!(page["next_offset"], 4);
+
+        let mut reassembled = String::new();
+        let mut offset = 0;
+        loop {
+            let page = store.page("h", offset, 1).unwrap();
+            reassembled.push_str(page["data"].as_str().unwrap());
+            match page["next_offset"].as_u64() {
+                Some(next) => offset = next as usize,
+                None => break,
+            }
+        }
+        assert_eq!(reassembled, text);
+    }
+
+    #[test]
+    fn test_page_unknown_handle() {
+        let store = OutputStore::new();
+        assert!(store.page("missing", 0, 10).is_err());
+        assert!(store
+            .execute_page(br#"{"handle": "missing"}"#)
+            .unwrap_err()
+            .to_string()
+            .contains("unknown or expired"));
+    }
+
+    #[test]
+    fn test_store_evicts_oldest() {
+        let store = OutputStore::with_budget(100);
+        assert!(store.retain("a", vec![b'a'; 60]));
+        assert!(store.retain("b", vec![b'b'; 60]));
+        assert!(store.page("a", 0, 10).is_err());
+        assert!(store.page("b", 0, 10).is_ok());
+        assert!(!store.retain("c", vec![b'c'; 200]));
+    }
+
+    #[test]
+    fn test_output_limit_for() {
+        assert_eq!(output_limit_for("fs.read"), TEXT_OUTPUT_LIMIT);
+        assert_eq!(output_limit_for("monitor.cpu"), DEFAULT_OUTPUT_LIMIT);
+        assert_eq!(output_limit_for("output.page"), usize::MAX);
+    }
+}
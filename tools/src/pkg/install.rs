@@ -2,7 +2,8 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+
+use crate::sandbox::command;
 
 #[derive(Deserialize)]
 struct Input {
@@ -29,7 +30,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
 }
 
 fn install_brew(name: &str) -> Result<(bool, String)> {
-    let output = Command::new("brew")
+    let output = command("brew")
         .args(["install", name])
         .output()
         .context("Failed to execute brew install. Ensure Homebrew is installed.")?;
@@ -40,7 +41,7 @@ fn install_brew(name: &str) -> Result<(bool, String)> {
     }
 
     // Get the installed version
-    let version_output = Command::new("brew")
+    let version_output = command("brew")
         .args(["info", "--json=v2", name])
         .output()
         .context("Failed to get package info from brew")?;
@@ -84,7 +85,7 @@ fn install_linux(name: &str) -> Result<(bool, String)> {
     // Detect package manager
     let (pm, install_args) = detect_package_manager()?;
 
-    let mut cmd = Command::new(&pm);
+    let mut cmd = command(&pm);
     for arg in &install_args {
         cmd.arg(arg);
     }
@@ -134,11 +135,11 @@ fn detect_package_manager() -> Result<(String, Vec<String>)> {
 
 fn get_linux_package_version(pm: &str, name: &str) -> String {
     let output = match pm {
-        "apt-get" => Command::new("dpkg").args(["-s", name]).output(),
-        "dnf" | "yum" => Command::new("rpm")
+        "apt-get" => command("dpkg").args(["-s", name]).output(),
+        "dnf" | "yum" => command("rpm")
             .args(["-q", "--queryformat", "%{VERSION}", name])
             .output(),
-        "pacman" => Command::new("pacman").args(["-Q", name]).output(),
+        "pacman" => command("pacman").args(["-Q", name]).output(),
         _ => return "unknown".to_string(),
     };
 
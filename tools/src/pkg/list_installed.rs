@@ -2,7 +2,8 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+
+use crate::sandbox::command;
 
 #[derive(Deserialize)]
 struct Input {}
@@ -36,7 +37,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
 }
 
 fn list_brew() -> Result<Vec<PackageEntry>> {
-    let output = Command::new("brew")
+    let output = command("brew")
         .args(["list", "--versions"])
         .output()
         .context("Failed to execute brew list")?;
@@ -73,7 +74,7 @@ fn list_linux() -> Result<Vec<PackageEntry>> {
 }
 
 fn list_dpkg() -> Result<Vec<PackageEntry>> {
-    let output = Command::new("dpkg-query")
+    let output = command("dpkg-query")
         .args(["-W", "-f", "${Package}\t${Version}\n"])
         .output()
         .context("Failed to execute dpkg-query")?;
@@ -95,7 +96,7 @@ fn list_dpkg() -> Result<Vec<PackageEntry>> {
 }
 
 fn list_rpm() -> Result<Vec<PackageEntry>> {
-    let output = Command::new("rpm")
+    let output = command("rpm")
         .args(["-qa", "--queryformat", "%{NAME}\t%{VERSION}-%{RELEASE}\n"])
         .output()
         .context("Failed to execute rpm -qa")?;
@@ -117,7 +118,7 @@ fn list_rpm() -> Result<Vec<PackageEntry>> {
 }
 
 fn list_pacman() -> Result<Vec<PackageEntry>> {
-    let output = Command::new("pacman")
+    let output = command("pacman")
         .args(["-Q"])
         .output()
         .context("Failed to execute pacman -Q")?;
@@ -9,62 +9,91 @@ pub mod remove;
 pub mod search;
 pub mod update;
 
-use crate::registry::{make_tool, Registry};
+use serde_json::json;
+
+use crate::registry::{critical, make_tool, with_input_schema, Registry};
 
 /// Register every package management tool with the registry.
 pub fn register_tools(reg: &mut Registry) {
-    reg.register_tool(make_tool(
-        "pkg.install",
-        "pkg",
-        "Install a package by name and return the installed version",
-        vec!["pkg.manage"],
-        "high",
-        false,
-        true,
-        120000,
-    ));
+    reg.register_tool(critical(with_input_schema(
+        make_tool(
+            "pkg.install",
+            "pkg",
+            "Install a package by name and return the installed version",
+            vec!["pkg.manage"],
+            "high",
+            false,
+            true,
+            120000,
+        ),
+        json!({
+            "type": "object",
+            "properties": { "name": { "type": "string", "description": "Package name" } },
+            "required": ["name"]
+        }),
+    )));
 
-    reg.register_tool(make_tool(
-        "pkg.remove",
-        "pkg",
-        "Remove an installed package by name",
-        vec!["pkg.manage"],
-        "high",
-        false,
-        false,
-        60000,
-    ));
+    reg.register_tool(critical(with_input_schema(
+        make_tool(
+            "pkg.remove",
+            "pkg",
+            "Remove an installed package by name",
+            vec!["pkg.manage"],
+            "high",
+            false,
+            false,
+            60000,
+        ),
+        json!({
+            "type": "object",
+            "properties": { "name": { "type": "string", "description": "Package name" } },
+            "required": ["name"]
+        }),
+    )));
 
-    reg.register_tool(make_tool(
-        "pkg.search",
-        "pkg",
-        "Search available packages matching a query string",
-        vec!["pkg.read"],
-        "low",
-        true,
-        false,
-        30000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "pkg.search",
+            "pkg",
+            "Search available packages matching a query string",
+            vec!["pkg.read"],
+            "low",
+            true,
+            false,
+            30000,
+        ),
+        json!({
+            "type": "object",
+            "properties": { "query": { "type": "string" } },
+            "required": ["query"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "pkg.update",
-        "pkg",
-        "Update all installed packages to their latest versions",
-        vec!["pkg.manage"],
-        "high",
-        false,
-        false,
-        300000,
-    ));
+    reg.register_tool(critical(with_input_schema(
+        make_tool(
+            "pkg.update",
+            "pkg",
+            "Update all installed packages to their latest versions",
+            vec!["pkg.manage"],
+            "high",
+            false,
+            false,
+            300000,
+        ),
+        json!({ "type": "object" }),
+    )));
 
-    reg.register_tool(make_tool(
-        "pkg.list_installed",
-        "pkg",
-        "List all currently installed packages with name and version",
-        vec!["pkg.read"],
-        "low",
-        true,
-        false,
-        15000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "pkg.list_installed",
+            "pkg",
+            "List all currently installed packages with name and version",
+            vec!["pkg.read"],
+            "low",
+            true,
+            false,
+            15000,
+        ),
+        json!({ "type": "object" }),
     ));
 }
@@ -2,7 +2,8 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+
+use crate::sandbox::command;
 
 #[derive(Deserialize)]
 struct Input {
@@ -28,7 +29,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
 }
 
 fn remove_brew(name: &str) -> Result<bool> {
-    let output = Command::new("brew")
+    let output = command("brew")
         .args(["uninstall", name])
         .output()
         .context("Failed to execute brew uninstall")?;
@@ -44,7 +45,7 @@ fn remove_brew(name: &str) -> Result<bool> {
 fn remove_linux(name: &str) -> Result<bool> {
     let (pm, remove_args) = detect_remove_command()?;
 
-    let mut cmd = Command::new(&pm);
+    let mut cmd = command(&pm);
     for arg in &remove_args {
         cmd.arg(arg);
     }
@@ -2,7 +2,8 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+
+use crate::sandbox::command;
 
 #[derive(Deserialize)]
 struct Input {
@@ -35,7 +36,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
 }
 
 fn search_brew(query: &str) -> Result<Vec<PackageEntry>> {
-    let output = Command::new("brew")
+    let output = command("brew")
         .args(["search", query])
         .output()
         .context("Failed to execute brew search")?;
@@ -67,9 +68,7 @@ fn search_brew(query: &str) -> Result<Vec<PackageEntry>> {
 }
 
 fn get_brew_info(name: &str) -> (String, String) {
-    let output = Command::new("brew")
-        .args(["info", "--json=v2", name])
-        .output();
+    let output = command("brew").args(["info", "--json=v2", name]).output();
 
     match output {
         Ok(out) if out.status.success() => {
@@ -128,7 +127,7 @@ fn search_linux(query: &str) -> Result<Vec<PackageEntry>> {
 }
 
 fn search_apt(query: &str) -> Result<Vec<PackageEntry>> {
-    let output = Command::new("apt-cache")
+    let output = command("apt-cache")
         .args(["search", query])
         .output()
         .context("Failed to execute apt-cache search")?;
@@ -158,7 +157,7 @@ fn search_apt(query: &str) -> Result<Vec<PackageEntry>> {
 }
 
 fn get_apt_version(name: &str) -> String {
-    let output = Command::new("apt-cache").args(["policy", name]).output();
+    let output = command("apt-cache").args(["policy", name]).output();
 
     match output {
         Ok(out) => {
@@ -176,7 +175,7 @@ fn get_apt_version(name: &str) -> String {
 }
 
 fn search_dnf(query: &str) -> Result<Vec<PackageEntry>> {
-    let output = Command::new("dnf")
+    let output = command("dnf")
         .args(["search", "--quiet", query])
         .output()
         .context("Failed to execute dnf search")?;
@@ -204,7 +203,7 @@ fn search_dnf(query: &str) -> Result<Vec<PackageEntry>> {
 }
 
 fn search_pacman(query: &str) -> Result<Vec<PackageEntry>> {
-    let output = Command::new("pacman")
+    let output = command("pacman")
         .args(["-Ss", query])
         .output()
         .context("Failed to execute pacman -Ss")?;
@@ -2,7 +2,8 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+
+use crate::sandbox::command;
 
 #[derive(Deserialize)]
 struct Input {}
@@ -31,13 +32,13 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
 
 fn update_brew() -> Result<u32> {
     // First, update the formula index
-    let _update = Command::new("brew")
+    let _update = command("brew")
         .arg("update")
         .output()
         .context("Failed to execute brew update")?;
 
     // Check what's outdated before upgrading
-    let outdated = Command::new("brew")
+    let outdated = command("brew")
         .args(["outdated", "--json=v2"])
         .output()
         .context("Failed to check outdated brew packages")?;
@@ -65,7 +66,7 @@ fn update_brew() -> Result<u32> {
 
     if outdated_count > 0 {
         // Perform the upgrade
-        let upgrade = Command::new("brew")
+        let upgrade = command("brew")
             .arg("upgrade")
             .output()
             .context("Failed to execute brew upgrade")?;
@@ -93,7 +94,7 @@ fn update_linux() -> Result<u32> {
 
 fn update_apt() -> Result<u32> {
     // Update package lists
-    let update = Command::new("apt-get")
+    let update = command("apt-get")
         .args(["update", "-qq"])
         .env("DEBIAN_FRONTEND", "noninteractive")
         .output()
@@ -105,7 +106,7 @@ fn update_apt() -> Result<u32> {
     }
 
     // Check how many packages can be upgraded
-    let check = Command::new("apt-get")
+    let check = command("apt-get")
         .args(["upgrade", "--dry-run", "-qq"])
         .env("DEBIAN_FRONTEND", "noninteractive")
         .output()
@@ -115,7 +116,7 @@ fn update_apt() -> Result<u32> {
     let count = stdout.lines().filter(|l| l.starts_with("Inst ")).count() as u32;
 
     if count > 0 {
-        let upgrade = Command::new("apt-get")
+        let upgrade = command("apt-get")
             .args(["upgrade", "-y", "-qq"])
             .env("DEBIAN_FRONTEND", "noninteractive")
             .output()
@@ -131,7 +132,7 @@ fn update_apt() -> Result<u32> {
 }
 
 fn update_dnf() -> Result<u32> {
-    let check = Command::new("dnf")
+    let check = command("dnf")
         .args(["check-update", "--quiet"])
         .output()
         .context("Failed to check dnf updates")?;
@@ -144,7 +145,7 @@ fn update_dnf() -> Result<u32> {
         .count() as u32;
 
     if count > 0 {
-        let update = Command::new("dnf")
+        let update = command("dnf")
             .args(["update", "-y", "--quiet"])
             .output()
             .context("Failed to execute dnf update")?;
@@ -160,7 +161,7 @@ fn update_dnf() -> Result<u32> {
 
 fn update_pacman() -> Result<u32> {
     // Check for updates
-    let check = Command::new("pacman")
+    let check = command("pacman")
         .args(["-Qu"])
         .output()
         .context("Failed to check pacman updates")?;
@@ -169,7 +170,7 @@ fn update_pacman() -> Result<u32> {
     let count = stdout.lines().filter(|l| !l.is_empty()).count() as u32;
 
     if count > 0 {
-        let update = Command::new("pacman")
+        let update = command("pacman")
             .args(["-Syu", "--noconfirm"])
             .output()
             .context("Failed to execute pacman -Syu")?;
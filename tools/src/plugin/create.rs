@@ -1,6 +1,9 @@
 //! Plugin creation and dependency installation
 //!
-//! `plugin.create` — writes a Python plugin script and metadata to disk.
+//! `plugin.create` — writes a Python plugin script and metadata to disk,
+//! once the code passes the import policy and its example inputs. With a
+//! `daemon` spec the code defines `run(config, emit)` and the plugin runs
+//! continuously under the daemon supervisor.
 //! `plugin.install_deps` — installs pip packages for a plugin.
 
 use anyhow::{bail, Context, Result};
@@ -31,6 +34,27 @@ struct CreateInput {
     /// How to pass output to chained plugins: "pipe" (default) or "merge"
     #[serde(default)]
     output_mode: Option<String>,
+    /// JSON schema for the plugin's input
+    #[serde(default)]
+    input_schema: Option<serde_json::Value>,
+    /// Instances that may run at once; 0 uses the service default
+    #[serde(default)]
+    max_concurrency: u32,
+    /// Hold a host-wide lock while running
+    #[serde(default)]
+    instance_lock: bool,
+    /// Inputs to test `main` with before the plugin is registered
+    #[serde(default)]
+    examples: Vec<serde_json::Value>,
+    /// Make this a daemon plugin defining `run(config, emit)`
+    #[serde(default)]
+    daemon: Option<super::daemon::DaemonSpec>,
+    /// Events that run the plugin
+    #[serde(default)]
+    triggers: Vec<super::triggers::EventTrigger>,
+    /// Register the plugin as an experimental tool
+    #[serde(default)]
+    experimental: bool,
 }
 
 /// Output for plugin.create
@@ -71,6 +95,19 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
         );
     }
 
+    let imports = super::validate::ImportPolicy::load(super::validate::PLUGIN_POLICY_PATH)
+        .check(&req.code, &req.capabilities);
+    if !imports.is_empty() {
+        bail!(
+            "Plugin code rejected by the import policy: {}",
+            imports
+                .iter()
+                .map(|f| format!("line {}: {}", f.line_number, f.description))
+                .collect::<Vec<_>>()
+                .join("; ")
+        );
+    }
+
     // Ensure plugin directory exists
     std::fs::create_dir_all(PLUGIN_DIR)
         .with_context(|| format!("Failed to create plugin directory: {PLUGIN_DIR}"))?;
@@ -80,7 +117,53 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
     let metadata_path = format!("{}/{}.meta.json", PLUGIN_DIR, req.name);
 
     // Wrap the user code in a standard harness
-    let wrapped_code = wrap_plugin_code(&req.name, &req.code);
+    let wrapped_code = match req.daemon {
+        Some(_) => wrap_daemon_code(&req.name, &req.code),
+        None => wrap_plugin_code(&req.name, &req.code),
+    };
+
+    // Install dependencies if any; the examples may need them
+    let deps_installed = if !req.dependencies.is_empty() {
+        match install_pip_deps(&req.dependencies) {
+            Ok(_) => true,
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to install dependencies for plugin {}: {e}",
+                    req.name
+                );
+                false
+            }
+        }
+    } else {
+        true
+    };
+
+    // Try the plugin outside PLUGIN_DIR, so a failing one is never loaded
+    let trial_path = std::env::temp_dir().join(format!(
+        "aios-plugin-{}-{}.py",
+        req.name,
+        uuid::Uuid::new_v4()
+    ));
+    std::fs::write(&trial_path, &wrapped_code)
+        .with_context(|| format!("Failed to write {}", trial_path.display()))?;
+    let limits = crate::sandbox::SandboxProfiles::load(crate::sandbox::SANDBOX_PROFILES_PATH)
+        .get("plugin")
+        .cloned()
+        .unwrap_or_default();
+    // A daemon's run never returns, so it is only checked to load
+    let (entry, examples) = match req.daemon {
+        Some(_) => ("run", &[][..]),
+        None => ("main", &req.examples[..]),
+    };
+    let failures = super::harness::run_examples(&trial_path, entry, examples, &limits);
+    let _ = std::fs::remove_file(&trial_path);
+    let failures = failures?;
+    if !failures.is_empty() {
+        bail!(
+            "Plugin {tool_name} failed validation and was not registered; fix the code and call plugin.create again: {}",
+            failures.join("; ")
+        );
+    }
 
     // Write the Python script
     std::fs::write(&script_path, &wrapped_code)
@@ -106,6 +189,13 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
         timeout_ms: 30000,
         next_plugins: req.next_plugins,
         output_mode: req.output_mode.unwrap_or_else(|| "pipe".to_string()),
+        input_schema: req.input_schema,
+        max_concurrency: req.max_concurrency,
+        instance_lock: req.instance_lock,
+        examples: req.examples,
+        daemon: req.daemon,
+        triggers: req.triggers,
+        experimental: req.experimental,
     };
 
     // Write metadata
@@ -114,22 +204,6 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
     std::fs::write(&metadata_path, &meta_json)
         .with_context(|| format!("Failed to write plugin metadata to {metadata_path}"))?;
 
-    // Install dependencies if any
-    let deps_installed = if !req.dependencies.is_empty() {
-        match install_pip_deps(&req.dependencies) {
-            Ok(_) => true,
-            Err(e) => {
-                tracing::warn!(
-                    "Failed to install dependencies for plugin {}: {e}",
-                    req.name
-                );
-                false
-            }
-        }
-    } else {
-        true
-    };
-
     info!(
         "Created plugin '{}' at {} (deps_installed: {})",
         tool_name, script_path, deps_installed
@@ -182,6 +256,63 @@ if __name__ == "__main__":
     )
 }
 
+/// Wrap AI-written daemon code. The AI writes `def run(config, emit)`,
+/// calling `emit(event_type, data, severity)` for each event; the wrapper
+/// passes the config from stdin, writes events and heartbeats as JSON lines
+/// to stdout for the supervisor, and reports a crash before exiting.
+pub(crate) fn wrap_daemon_code(name: &str, user_code: &str) -> String {
+    let heartbeat_secs = super::daemon::HEARTBEAT_SECS;
+    format!(
+        r#"#!/usr/bin/env python3
+"""aiOS Daemon Plugin: {name}
+
+Auto-generated daemon wrapper. The run() function below was written by AI.
+Config is read as JSON from stdin; events and heartbeats are written as
+JSON lines to stdout.
+"""
+
+import sys
+import json
+import threading
+import time
+import traceback
+
+_out = sys.stdout
+_out_lock = threading.Lock()
+
+def _send(line):
+    with _out_lock:
+        _out.write(json.dumps(line) + "\n")
+        _out.flush()
+
+def emit(event_type, data=None, severity="info"):
+    _send({{"event_type": str(event_type), "data": data or {{}}, "severity": str(severity)}})
+
+def _heartbeat():
+    while True:
+        _send({{"heartbeat": True}})
+        time.sleep({heartbeat_secs})
+
+# --- AI-generated plugin code ---
+
+{user_code}
+
+# --- End AI-generated code ---
+
+if __name__ == "__main__":
+    # Stray prints must not be taken for events
+    sys.stdout = sys.stderr
+    try:
+        config = json.loads(sys.stdin.read() or "{{}}")
+        threading.Thread(target=_heartbeat, daemon=True).start()
+        run(config, emit)
+    except Exception as e:
+        _send({{"error": str(e), "traceback": traceback.format_exc()}})
+        sys.exit(1)
+"#
+    )
+}
+
 /// Input for plugin.install_deps
 #[derive(Debug, Deserialize)]
 struct InstallDepsInput {
@@ -278,7 +409,7 @@ fn install_pip_deps(packages: &[String]) -> Result<()> {
     ];
     args.extend(packages.iter().cloned());
 
-    let output = std::process::Command::new("pip3")
+    let output = crate::sandbox::command("pip3")
         .args(&args)
         .output()
         .context("Failed to run pip3 install")?;
@@ -347,6 +478,20 @@ mod tests {
         assert!(err.contains("Plugin code rejected"));
     }
 
+    #[test]
+    fn test_create_rejects_denied_imports() {
+        let input = serde_json::to_vec(&serde_json::json!({
+            "name": "native_plugin",
+            "description": "test",
+            "code": "import ctypes\ndef main(d):\n    return {}",
+        }))
+        .unwrap();
+
+        let err = execute(&input).unwrap_err().to_string();
+        assert!(err.contains("import policy"));
+        assert!(err.contains("ctypes"));
+    }
+
     #[test]
     fn test_create_with_chaining_fields() {
         // Just test deserialization — we can't write to PLUGIN_DIR in tests
@@ -0,0 +1,586 @@
+//! Daemon plugins — plugins that run continuously
+//!
+//! A plugin created with a `daemon` spec defines `run(config, emit)`
+//! instead of `main(input_data)`. The supervisor starts it when the tools
+//! service starts (unless `autostart` is off), under the `plugin` sandbox
+//! profile with the spec's CPU and memory limits, and restarts it with
+//! exponential backoff when it exits or stops sending heartbeats, up to
+//! `max_restarts` times in a row. Each call of `emit` becomes a
+//! `plugin.<name>.<type>` event in the memory service's event stream, so
+//! daemons are event sources like any other service.
+//!
+//! Daemons are managed with the `plugin.<name>.control` tool: start, stop,
+//! restart and status. A daemon stopped that way stays stopped until it is
+//! started again; one whose plugin is deleted is stopped.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use super::{PluginMetadata, PLUGIN_DIR};
+use crate::sandbox::{self, ResourceLimits};
+
+/// Seconds between the heartbeats a daemon's wrapper sends
+pub const HEARTBEAT_SECS: u64 = 10;
+
+/// First restart delay; doubled for each restart in a row
+const RESTART_DELAY: Duration = Duration::from_secs(1);
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(60);
+
+/// A daemon that ran this long before exiting is restarted as if for the
+/// first time
+const STABLE_RUN: Duration = Duration::from_secs(600);
+
+/// How a daemon plugin runs (the `daemon` field of its metadata)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaemonSpec {
+    /// Start with the tools service
+    #[serde(default = "default_true")]
+    pub autostart: bool,
+    /// Restarts in a row before the daemon is left failed
+    #[serde(default = "default_max_restarts")]
+    pub max_restarts: u32,
+    /// Restart the daemon when it sends nothing, heartbeats included, for
+    /// this long
+    #[serde(default = "default_heartbeat_timeout_secs")]
+    pub heartbeat_timeout_secs: u64,
+    /// CPU time the daemon may use before it is killed and restarted
+    #[serde(default = "default_max_cpu_secs")]
+    pub max_cpu_secs: u64,
+    /// Memory limit; the `plugin` profile's when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_memory_mb: Option<u64>,
+    /// Passed to `run` as its `config`
+    #[serde(default = "default_config")]
+    pub config: serde_json::Value,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_max_restarts() -> u32 {
+    5
+}
+
+fn default_heartbeat_timeout_secs() -> u64 {
+    HEARTBEAT_SECS * 6
+}
+
+fn default_max_cpu_secs() -> u64 {
+    3600
+}
+
+fn default_config() -> serde_json::Value {
+    serde_json::json!({})
+}
+
+/// What `plugin.<name>.control` reports about a daemon
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DaemonStatus {
+    pub name: String,
+    /// starting, running, restarting, stopped or failed
+    pub state: String,
+    pub pid: u32,
+    /// Unix time the current process started
+    pub started_at: i64,
+    /// Restarts since the supervisor started the daemon
+    pub restarts: u32,
+    pub events_emitted: u64,
+    /// Unix time of the daemon's last line, heartbeat or event
+    pub last_seen: i64,
+    pub last_error: String,
+}
+
+struct Daemon {
+    status: Arc<Mutex<DaemonStatus>>,
+    stop: CancellationToken,
+}
+
+/// Runs the daemon plugins
+pub struct DaemonSupervisor {
+    plugin_dir: PathBuf,
+    limits: ResourceLimits,
+    daemons: Mutex<HashMap<String, Daemon>>,
+}
+
+impl DaemonSupervisor {
+    /// Supervisor of the daemons in `plugin_dir`, run under `limits`
+    /// adjusted by each daemon's spec
+    pub fn new(plugin_dir: PathBuf, limits: ResourceLimits) -> Self {
+        Self {
+            plugin_dir,
+            limits,
+            daemons: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Supervisor of PLUGIN_DIR under the `plugin` sandbox profile
+    pub fn from_profiles(profiles: &sandbox::SandboxProfiles) -> Self {
+        Self::new(
+            PathBuf::from(PLUGIN_DIR),
+            profiles.get("plugin").cloned().unwrap_or_default(),
+        )
+    }
+
+    fn spec(&self, name: &str) -> Option<DaemonSpec> {
+        let path = self.plugin_dir.join(format!("{name}.meta.json"));
+        let contents = std::fs::read_to_string(path).ok()?;
+        serde_json::from_str::<PluginMetadata>(&contents)
+            .ok()?
+            .daemon
+    }
+
+    /// Start daemon `name` unless it is running already
+    pub fn start(&self, name: &str, spec: &DaemonSpec) -> DaemonStatus {
+        let mut daemons = self.daemons.lock().unwrap();
+        if let Some(daemon) = daemons.get(name) {
+            let status = daemon.status.lock().unwrap().clone();
+            if !daemon.stop.is_cancelled() && status.state != "failed" {
+                return status;
+            }
+        }
+
+        let mut limits = self.limits.clone();
+        limits.max_cpu_time = Duration::from_secs(spec.max_cpu_secs);
+        if let Some(mb) = spec.max_memory_mb {
+            limits.max_memory_bytes = mb * 1024 * 1024;
+        }
+        let status = Arc::new(Mutex::new(DaemonStatus {
+            name: name.to_string(),
+            state: "starting".into(),
+            ..Default::default()
+        }));
+        let stop = CancellationToken::new();
+        info!("Starting daemon plugin {name}");
+        tokio::spawn(supervise(
+            self.plugin_dir.join(format!("{name}.py")),
+            spec.clone(),
+            limits,
+            status.clone(),
+            stop.clone(),
+        ));
+        let current = status.lock().unwrap().clone();
+        daemons.insert(name.to_string(), Daemon { status, stop });
+        current
+    }
+
+    /// Stop daemon `name`; it stays stopped until started again
+    pub fn stop(&self, name: &str) -> DaemonStatus {
+        let daemons = self.daemons.lock().unwrap();
+        let Some(daemon) = daemons.get(name) else {
+            return stopped(name);
+        };
+        info!("Stopping daemon plugin {name}");
+        daemon.stop.cancel();
+        let mut status = daemon.status.lock().unwrap();
+        if status.state != "failed" {
+            status.state = "stopped".into();
+        }
+        status.clone()
+    }
+
+    pub fn status(&self, name: &str) -> DaemonStatus {
+        self.daemons
+            .lock()
+            .unwrap()
+            .get(name)
+            .map(|daemon| daemon.status.lock().unwrap().clone())
+            .unwrap_or_else(|| stopped(name))
+    }
+
+    /// Start the autostart daemons of plugins not seen before, and forget
+    /// daemons whose plugin was deleted or is no longer a daemon. Run at
+    /// startup and whenever the plugin directory changes.
+    pub fn reconcile(&self) {
+        let names: Vec<String> = std::fs::read_dir(&self.plugin_dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| {
+                let file = entry.file_name().into_string().ok()?;
+                file.strip_suffix(".meta.json").map(String::from)
+            })
+            .collect();
+
+        let known: Vec<String> = self.daemons.lock().unwrap().keys().cloned().collect();
+        for name in known {
+            if self.spec(&name).is_none() {
+                self.stop(&name);
+                self.daemons.lock().unwrap().remove(&name);
+            }
+        }
+        for name in names {
+            let Some(spec) = self.spec(&name) else {
+                continue;
+            };
+            let seen = self.daemons.lock().unwrap().contains_key(&name);
+            if spec.autostart && !seen {
+                self.start(&name, &spec);
+            }
+        }
+    }
+
+    /// Kill every daemon, for the tools service shutting down
+    pub fn shutdown(&self) {
+        for daemon in self.daemons.lock().unwrap().values() {
+            daemon.stop.cancel();
+            let pid = daemon.status.lock().unwrap().pid;
+            if pid > 0 {
+                // SAFETY: killpg only sends a signal
+                unsafe {
+                    libc::killpg(pid as i32, libc::SIGKILL);
+                }
+            }
+        }
+    }
+
+    /// Handle `plugin.<name>.control`: `{"action": "start" | "stop" |
+    /// "restart" | "status"}`
+    pub fn control(&self, name: &str, input: &[u8]) -> Result<Vec<u8>> {
+        #[derive(Deserialize)]
+        struct ControlInput {
+            action: String,
+        }
+        let input: ControlInput =
+            serde_json::from_slice(input).context("Invalid daemon control input JSON")?;
+        let spec = || {
+            self.spec(name)
+                .with_context(|| format!("plugin.{name} is not a daemon plugin"))
+        };
+        let status = match input.action.as_str() {
+            "start" => self.start(name, &spec()?),
+            "stop" => self.stop(name),
+            "restart" => {
+                let spec = spec()?;
+                self.stop(name);
+                self.start(name, &spec)
+            }
+            "status" => self.status(name),
+            other => bail!("Unknown action '{other}' (expected start, stop, restart or status)"),
+        };
+        serde_json::to_vec(&status).context("Failed to serialize daemon status")
+    }
+}
+
+fn stopped(name: &str) -> DaemonStatus {
+    DaemonStatus {
+        name: name.to_string(),
+        state: "stopped".into(),
+        ..Default::default()
+    }
+}
+
+/// Why a daemon process ended
+enum Exit {
+    Stopped,
+    Failed(String),
+}
+
+/// Run the daemon at `script` until stopped, restarting it as its spec
+/// allows
+async fn supervise(
+    script: PathBuf,
+    spec: DaemonSpec,
+    limits: ResourceLimits,
+    status: Arc<Mutex<DaemonStatus>>,
+    stop: CancellationToken,
+) {
+    let name = status.lock().unwrap().name.clone();
+    let mut events = EventSink::default();
+    let mut in_a_row = 0;
+    loop {
+        let started = Instant::now();
+        let reason = match run_once(&script, &spec, &limits, &status, &mut events, &stop).await {
+            Exit::Stopped => {
+                status.lock().unwrap().state = "stopped".into();
+                return;
+            }
+            Exit::Failed(reason) => reason,
+        };
+        warn!("Daemon plugin {name} ended: {reason}");
+        if started.elapsed() >= STABLE_RUN {
+            in_a_row = 0;
+        }
+        if in_a_row >= spec.max_restarts {
+            let mut status = status.lock().unwrap();
+            status.state = "failed".into();
+            status.pid = 0;
+            status.last_error = reason;
+            return;
+        }
+        in_a_row += 1;
+        {
+            let mut status = status.lock().unwrap();
+            status.state = "restarting".into();
+            status.pid = 0;
+            status.restarts += 1;
+            status.last_error = reason;
+        }
+
+        let delay = (RESTART_DELAY * 2u32.saturating_pow(in_a_row - 1)).min(MAX_RESTART_DELAY);
+        tokio::select! {
+            _ = stop.cancelled() => {
+                status.lock().unwrap().state = "stopped".into();
+                return;
+            }
+            _ = tokio::time::sleep(delay) => {}
+        }
+    }
+}
+
+/// A line a daemon's wrapper writes to stdout
+#[derive(Deserialize)]
+struct DaemonLine {
+    #[serde(default)]
+    heartbeat: bool,
+    #[serde(default)]
+    event_type: String,
+    #[serde(default)]
+    data: serde_json::Value,
+    #[serde(default)]
+    severity: String,
+    #[serde(default)]
+    error: String,
+}
+
+async fn run_once(
+    script: &std::path::Path,
+    spec: &DaemonSpec,
+    limits: &ResourceLimits,
+    status: &Mutex<DaemonStatus>,
+    events: &mut EventSink,
+    stop: &CancellationToken,
+) -> Exit {
+    let mut cmd = tokio::process::Command::from(sandbox::command("python3"));
+    cmd.arg(script)
+        .env_clear()
+        .env("PATH", "/usr/bin:/bin")
+        .env("HOME", "/tmp/aios-sandbox")
+        .env("LANG", "C.UTF-8")
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .kill_on_drop(true);
+    sandbox::apply_limits(cmd.as_std_mut(), limits);
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => return Exit::Failed(format!("failed to start: {e}")),
+    };
+    let pid = child.id().unwrap_or(0);
+    let kill_group = || {
+        if pid > 0 {
+            // SAFETY: killpg only sends a signal
+            unsafe {
+                libc::killpg(pid as i32, libc::SIGKILL);
+            }
+        }
+    };
+    {
+        let mut status = status.lock().unwrap();
+        status.state = "running".into();
+        status.pid = pid;
+        status.started_at = chrono::Utc::now().timestamp();
+        status.last_seen = status.started_at;
+    }
+
+    if let Some(mut stdin) = child.stdin.take() {
+        let config = serde_json::to_vec(&spec.config).unwrap_or_default();
+        let _ = stdin.write_all(&config).await;
+    }
+    let Some(stdout) = child.stdout.take() else {
+        kill_group();
+        return Exit::Failed("no stdout".into());
+    };
+    let mut lines = BufReader::new(stdout).lines();
+    let timeout = Duration::from_secs(spec.heartbeat_timeout_secs.max(1));
+    let source = script
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default()
+        .to_string();
+
+    loop {
+        let line = tokio::select! {
+            _ = stop.cancelled() => {
+                kill_group();
+                let _ = child.wait().await;
+                return Exit::Stopped;
+            }
+            line = tokio::time::timeout(timeout, lines.next_line()) => line,
+        };
+        let line = match line {
+            Ok(Ok(Some(line))) => line,
+            Ok(_) => {
+                let exit = child.wait().await;
+                kill_group();
+                let error = status.lock().unwrap().last_error.clone();
+                return Exit::Failed(match exit {
+                    Ok(exit) if !error.is_empty() => format!("exited with {exit}: {error}"),
+                    Ok(exit) => format!("exited with {exit}"),
+                    Err(e) => e.to_string(),
+                });
+            }
+            Err(_) => {
+                kill_group();
+                let _ = child.wait().await;
+                return Exit::Failed(format!("no heartbeat for {timeout:?}"));
+            }
+        };
+
+        let Ok(line) = serde_json::from_str::<DaemonLine>(&line) else {
+            continue;
+        };
+        {
+            let mut status = status.lock().unwrap();
+            status.last_seen = chrono::Utc::now().timestamp();
+            if !line.error.is_empty() {
+                status.last_error = line.error.clone();
+            }
+            if !line.event_type.is_empty() {
+                status.events_emitted += 1;
+            }
+        }
+        if !line.heartbeat && !line.event_type.is_empty() {
+            events.push(&source, line).await;
+        }
+    }
+}
+
+/// Forwards daemon events to the memory service's event stream
+#[derive(Default)]
+struct EventSink {
+    client: Option<crate::identity::MemoryClient>,
+}
+
+impl EventSink {
+    async fn push(&mut self, plugin: &str, line: DaemonLine) {
+        if self.client.is_none() {
+            let addr = std::env::var("AIOS_MEMORY_ADDR")
+                .unwrap_or_else(|_| "http://127.0.0.1:50053".to_string());
+            match crate::identity::connect_memory(addr).await {
+                Ok(client) => self.client = Some(client),
+                Err(e) => {
+                    debug!("Dropping event of daemon plugin {plugin}: {e}");
+                    return;
+                }
+            }
+        }
+        let event = crate::proto::memory::Event {
+            id: uuid::Uuid::new_v4().to_string(),
+            timestamp: chrono::Utc::now().timestamp(),
+            category: "plugin".into(),
+            event_type: format!("{plugin}.{}", line.event_type),
+            source: format!("plugin.{plugin}"),
+            data_json: serde_json::to_vec(&line.data).unwrap_or_default(),
+            severity: line.severity,
+            ..Default::default()
+        };
+        if let Some(client) = self.client.as_mut() {
+            if let Err(e) = client.push_event(event).await {
+                warn!("Failed to push event of daemon plugin {plugin}: {e}");
+                if e.code() == tonic::Code::Unavailable {
+                    self.client = None;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(max_restarts: u32) -> DaemonSpec {
+        serde_json::from_value(serde_json::json!({ "max_restarts": max_restarts })).unwrap()
+    }
+
+    fn write_daemon(dir: &std::path::Path, name: &str, code: &str, spec: &DaemonSpec) {
+        std::fs::write(
+            dir.join(format!("{name}.py")),
+            super::super::create::wrap_daemon_code(name, code),
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join(format!("{name}.meta.json")),
+            serde_json::json!({
+                "tool_name": format!("plugin.{name}"), "description": "", "capabilities": [],
+                "dependencies": [], "author": "test", "created_at": "", "timeout_ms": 5000,
+                "daemon": spec,
+            })
+            .to_string(),
+        )
+        .unwrap();
+    }
+
+    async fn wait_for(supervisor: &DaemonSupervisor, name: &str, state: &str) -> DaemonStatus {
+        for _ in 0..200 {
+            let status = supervisor.status(name);
+            if status.state == state {
+                return status;
+            }
+            tokio::time::sleep(Duration::from_millis(25)).await;
+        }
+        panic!(
+            "{name} never reached {state}: {:?}",
+            supervisor.status(name)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_daemon_lifecycle() {
+        if sandbox::command("python3")
+            .arg("--version")
+            .output()
+            .is_err()
+        {
+            return;
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let watcher = "import time\ndef run(config, emit):\n    emit('started', {'interval': config.get('interval')})\n    while True:\n        time.sleep(0.05)\n";
+        write_daemon(dir.path(), "watcher", watcher, &spec(5));
+        let crasher = "def run(config, emit):\n    raise RuntimeError('boom')\n";
+        write_daemon(dir.path(), "crasher", crasher, &spec(1));
+        let supervisor = DaemonSupervisor::new(dir.path().to_path_buf(), ResourceLimits::default());
+
+        // Autostart daemons start with the service
+        supervisor.reconcile();
+        let status = wait_for(&supervisor, "watcher", "running").await;
+        assert!(status.pid > 0);
+
+        // A crashing daemon is restarted until it runs out of restarts
+        let status = wait_for(&supervisor, "crasher", "failed").await;
+        assert_eq!(status.restarts, 1);
+        assert!(status.last_error.contains("boom"), "{status:?}");
+
+        // Stopped daemons stay stopped across reconciles
+        let output = supervisor
+            .control("watcher", br#"{"action": "stop"}"#)
+            .unwrap();
+        let status: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(status["state"], "stopped");
+        assert_eq!(status["events_emitted"], 1);
+        supervisor.reconcile();
+        assert_eq!(supervisor.status("watcher").state, "stopped");
+
+        supervisor
+            .control("watcher", br#"{"action": "start"}"#)
+            .unwrap();
+        wait_for(&supervisor, "watcher", "running").await;
+
+        // Deleting the plugin stops its daemon
+        std::fs::remove_file(dir.path().join("watcher.meta.json")).unwrap();
+        supervisor.reconcile();
+        assert_eq!(supervisor.status("watcher").state, "stopped");
+        assert!(supervisor
+            .control("watcher", br#"{"action": "start"}"#)
+            .is_err());
+        supervisor.shutdown();
+    }
+}
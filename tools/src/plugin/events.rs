@@ -1,13 +1,49 @@
 //! Plugin Event Dispatcher
 //!
 //! Background loop that monitors trigger conditions and fires plugins.
+//!
+//! Plugins whose metadata declares event `triggers` are run by
+//! [`run_event_triggers`] whenever a matching event is published on the
+//! memory service's event bus, with the event as input, as agent
+//! `plugin-trigger`. Each trigger fires at most `max_per_minute` times a
+//! minute. A plugin never fires on its own events, and events a triggered
+//! plugin causes fire further plugins only up to a chain of
+//! `MAX_CHAIN_DEPTH`, so plugins cannot keep triggering each other. The
+//! triggers' bus subscription is durable: events published while the tools
+//! service is down fire once it is back.
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::path::Path;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
+
+use super::triggers::EventTrigger;
+use super::PluginMetadata;
+use crate::proto::memory::{BusAck, BusDelivery, Event, SubscribeRequest};
+
+/// Agent that plugins fired by event triggers run as
+pub const TRIGGER_AGENT_ID: &str = "plugin-trigger";
+
+/// Longest chain of plugins fired by each other's events
+const MAX_CHAIN_DEPTH: u32 = 3;
+
+/// How long after a triggered run the events of its plugin continue its
+/// chain
+const CHAIN_WINDOW: Duration = Duration::from_secs(300);
+
+/// Window of the per-trigger rate limit
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Interval between attempts to reach the memory service
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Durable event bus subscription of the triggers
+const TRIGGER_SUBSCRIBER: &str = "plugin-triggers";
 
 /// Trigger types that can activate a plugin
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -151,6 +187,189 @@ impl EventDispatcher {
     }
 }
 
+/// A plugin run an event triggered
+#[derive(Debug)]
+pub struct TriggerFiring {
+    pub tool_name: String,
+    pub input: Vec<u8>,
+    pub reason: String,
+}
+
+/// Rate limits and chain depths of event triggers
+#[derive(Default)]
+pub struct EventTriggers {
+    /// Recent firings of each trigger, by plugin name and trigger index
+    fired: HashMap<(String, usize), VecDeque<Instant>>,
+    /// Chain depth of each plugin's latest triggered run, and its time
+    depth: HashMap<String, (u32, Instant)>,
+}
+
+impl EventTriggers {
+    /// The plugin runs `event` fires among `plugins`, at most one per plugin
+    pub fn dispatch(
+        &mut self,
+        event: &Event,
+        plugins: &[PluginMetadata],
+        now: Instant,
+    ) -> Vec<TriggerFiring> {
+        // Events of a plugin that was triggered lately continue its chain
+        let source = event.source.strip_prefix("plugin.");
+        let depth = source
+            .and_then(|name| self.depth.get(name))
+            .filter(|(_, at)| now.duration_since(*at) < CHAIN_WINDOW)
+            .map_or(0, |(depth, _)| *depth);
+
+        let mut firings = Vec::new();
+        for meta in plugins {
+            let name = meta
+                .tool_name
+                .strip_prefix("plugin.")
+                .unwrap_or(&meta.tool_name);
+            if source == Some(name) {
+                continue;
+            }
+            let Some(index) = meta.triggers.iter().position(|t| t.matches(event)) else {
+                continue;
+            };
+            if depth >= MAX_CHAIN_DEPTH {
+                warn!(
+                    "Not firing {}: event {} ends a chain of {depth} triggered plugins",
+                    meta.tool_name, event.id
+                );
+                continue;
+            }
+            let trigger = &meta.triggers[index];
+            let fired = self.fired.entry((name.to_string(), index)).or_default();
+            while fired
+                .front()
+                .is_some_and(|at| now.duration_since(*at) >= RATE_WINDOW)
+            {
+                fired.pop_front();
+            }
+            if fired.len() >= trigger.max_per_minute as usize {
+                debug!(
+                    "Trigger {index} of {} is rate limited; event {} dropped",
+                    meta.tool_name, event.id
+                );
+                continue;
+            }
+            fired.push_back(now);
+            self.depth.insert(name.to_string(), (depth + 1, now));
+            firings.push(TriggerFiring {
+                tool_name: meta.tool_name.clone(),
+                input: trigger_input(trigger, event),
+                reason: format!(
+                    "event trigger: {}.{} {}",
+                    event.category, event.event_type, event.id
+                ),
+            });
+        }
+        firings
+    }
+}
+
+/// The trigger's input with the event added as `event`
+fn trigger_input(trigger: &EventTrigger, event: &Event) -> Vec<u8> {
+    let data: serde_json::Value = serde_json::from_slice(&event.data_json).unwrap_or_default();
+    let mut input = trigger.input.clone();
+    input.insert(
+        "event".into(),
+        serde_json::json!({
+            "id": event.id,
+            "timestamp": event.timestamp,
+            "category": event.category,
+            "event_type": event.event_type,
+            "source": event.source,
+            "severity": event.severity,
+            "data": data,
+        }),
+    );
+    serde_json::to_vec(&input).unwrap_or_default()
+}
+
+/// Metadata of the plugins in `plugin_dir` that declare event triggers.
+/// Daemon plugins run continuously and cannot be triggered.
+pub fn load_trigger_plugins(plugin_dir: &Path) -> Vec<PluginMetadata> {
+    std::fs::read_dir(plugin_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().ends_with(".meta.json"))
+        .filter_map(|entry| std::fs::read_to_string(entry.path()).ok())
+        .filter_map(|contents| serde_json::from_str::<PluginMetadata>(&contents).ok())
+        .filter(|meta| !meta.triggers.is_empty() && meta.daemon.is_none())
+        .collect()
+}
+
+/// Follow the events on the memory service's event bus and run the
+/// plugins they trigger through `invoke`, each on its own task
+pub async fn run_event_triggers<F, Fut>(invoke: F)
+where
+    F: Fn(TriggerFiring) -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let addr =
+        std::env::var("AIOS_MEMORY_ADDR").unwrap_or_else(|_| "http://127.0.0.1:50053".to_string());
+    let mut triggers = EventTriggers::default();
+
+    loop {
+        let (mut memory, mut deliveries) = match subscribe(&addr).await {
+            Ok(subscription) => subscription,
+            Err(e) => {
+                debug!("Plugin event triggers waiting for the memory service: {e}");
+                tokio::time::sleep(RECONNECT_INTERVAL).await;
+                continue;
+            }
+        };
+        debug!("Plugin event triggers subscribed to the event bus");
+
+        loop {
+            let delivery = match deliveries.message().await {
+                Ok(Some(delivery)) => delivery,
+                Ok(None) => break,
+                Err(e) => {
+                    warn!("Plugin event triggers lost the event bus: {e}");
+                    break;
+                }
+            };
+            if let Some(event) = delivery.message.and_then(|m| m.event) {
+                let plugins = load_trigger_plugins(Path::new(super::PLUGIN_DIR));
+                for firing in triggers.dispatch(&event, &plugins, Instant::now()) {
+                    info!("Event {} triggers {}", event.id, firing.tool_name);
+                    tokio::spawn(invoke(firing));
+                }
+            }
+            let ack = BusAck {
+                subscriber: TRIGGER_SUBSCRIBER.to_string(),
+                seq: delivery.seq,
+            };
+            if let Err(e) = memory.ack(ack).await {
+                warn!(
+                    "Plugin event triggers failed to acknowledge #{}: {e}",
+                    delivery.seq
+                );
+            }
+        }
+        tokio::time::sleep(RECONNECT_INTERVAL).await;
+    }
+}
+
+/// Subscribe to every event, resuming after the last one acknowledged
+async fn subscribe(
+    addr: &str,
+) -> Result<(crate::identity::MemoryClient, tonic::Streaming<BusDelivery>)> {
+    let mut client = crate::identity::connect_memory(addr.to_string()).await?;
+    let deliveries = client
+        .subscribe(SubscribeRequest {
+            subscriber: TRIGGER_SUBSCRIBER.to_string(),
+            topics: vec!["events.*".to_string()],
+            from_latest: true,
+        })
+        .await?
+        .into_inner();
+    Ok((client, deliveries))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -160,4 +379,95 @@ mod tests {
         let dispatcher = EventDispatcher::new("/tmp/test_triggers.db");
         assert!(dispatcher.triggers.is_empty());
     }
+
+    fn plugin(name: &str, triggers: serde_json::Value) -> PluginMetadata {
+        serde_json::from_value(serde_json::json!({
+            "tool_name": format!("plugin.{name}"), "description": "", "capabilities": [],
+            "dependencies": [], "author": "test", "created_at": "", "timeout_ms": 5000,
+            "triggers": triggers,
+        }))
+        .unwrap()
+    }
+
+    fn event(id: &str, source: &str) -> Event {
+        Event {
+            id: id.into(),
+            category: "plugin".into(),
+            event_type: "feed.updated".into(),
+            source: source.into(),
+            data_json: br#"{"items": 3}"#.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_event_triggers_fire_with_limits() {
+        let on_feed = serde_json::json!([{"category": "plugin", "max_per_minute": 2,
+            "input": {"mode": "summary"}}]);
+        let plugins = [
+            plugin("digest", on_feed.clone()),
+            plugin("archive", serde_json::json!([{"category": "service"}])),
+        ];
+        let mut triggers = EventTriggers::default();
+        let now = Instant::now();
+
+        let firings = triggers.dispatch(&event("e1", "plugin.feed"), &plugins, now);
+        assert_eq!(firings.len(), 1);
+        assert_eq!(firings[0].tool_name, "plugin.digest");
+        let input: serde_json::Value = serde_json::from_slice(&firings[0].input).unwrap();
+        assert_eq!(input["mode"], "summary");
+        assert_eq!(input["event"]["data"]["items"], 3);
+
+        // Rate limited within the minute, not after it
+        assert_eq!(
+            triggers
+                .dispatch(&event("e2", "plugin.feed"), &plugins, now)
+                .len(),
+            1
+        );
+        assert!(triggers
+            .dispatch(&event("e3", "plugin.feed"), &plugins, now)
+            .is_empty());
+        let later = now + RATE_WINDOW;
+        assert_eq!(
+            triggers
+                .dispatch(&event("e4", "plugin.feed"), &plugins, later)
+                .len(),
+            1
+        );
+
+        // A plugin does not fire on its own events
+        assert!(triggers
+            .dispatch(&event("e5", "plugin.digest"), &plugins, later)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_event_trigger_chains_are_bounded() {
+        // Every plugin fires on every plugin event: a would fire b, b would
+        // fire c, and so on forever
+        let any = serde_json::json!([{"category": "plugin", "max_per_minute": 100}]);
+        let plugins: Vec<_> = ["a", "b", "c", "d"]
+            .iter()
+            .map(|n| plugin(n, any.clone()))
+            .collect();
+        let mut triggers = EventTriggers::default();
+        let now = Instant::now();
+
+        let mut source = "plugin.feed".to_string();
+        for depth in 0..MAX_CHAIN_DEPTH {
+            let firings = triggers.dispatch(&event("e", &source), &plugins, now);
+            assert!(!firings.is_empty(), "chain stopped at {depth}");
+            source = firings[0].tool_name.clone();
+        }
+        assert!(triggers
+            .dispatch(&event("e", &source), &plugins, now)
+            .is_empty());
+
+        // Once the chain has gone quiet its plugins fire again
+        let later = now + CHAIN_WINDOW;
+        assert!(!triggers
+            .dispatch(&event("e", &source), &plugins, later)
+            .is_empty());
+    }
 }
@@ -0,0 +1,196 @@
+//! Plugin test harness — try a new plugin before it is registered
+//!
+//! `plugin.create` loads the plugin script in the sandbox under the
+//! `plugin` profile's limits and calls its `main` with each example input
+//! declared in its metadata. The plugin only passes when it loads, defines
+//! `main`, and returns a JSON-serializable dict for every example; what
+//! went wrong is reported so the model can fix the code and try again.
+//! Daemon plugins are only loaded and checked for `run`, since it never
+//! returns.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+use crate::sandbox::{self, ResourceLimits};
+
+/// Runs in the sandbox: loads the plugin given as first argument without
+/// running its own stdin/stdout wrapper, checks it defines the entry point
+/// given as second argument, calls main with each example read from
+/// stdin, and writes the failures to stdout. What the plugin prints goes to
+/// stderr so it cannot corrupt the report.
+const HARNESS: &str = r#"
+import importlib.util, json, sys
+
+report = sys.stdout
+sys.stdout = sys.stderr
+
+def describe(e):
+    return ("%s: %s" % (type(e).__name__, e))[:500]
+
+try:
+    spec = importlib.util.spec_from_file_location("aios_plugin", sys.argv[1])
+    plugin = importlib.util.module_from_spec(spec)
+    spec.loader.exec_module(plugin)
+    entry = sys.argv[2]
+    if not callable(getattr(plugin, entry, None)):
+        signature = "main(input_data)" if entry == "main" else entry + "(config, emit)"
+        raise TypeError("the plugin defines no %s function" % signature)
+except BaseException as e:
+    json.dump([{"example": None, "error": "failed to load: " + describe(e)}], report)
+    sys.exit(0)
+
+failures = []
+for i, example in enumerate(json.loads(sys.stdin.read() or "[]")):
+    try:
+        output = plugin.main(example)
+        if not isinstance(output, dict):
+            raise TypeError("main returned %s, not a dict" % type(output).__name__)
+        json.dumps(output)
+    except BaseException as e:
+        failures.append({"example": i, "error": describe(e)})
+json.dump(failures, report)
+"#;
+
+/// Poll interval while waiting for the harness
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Longest example input quoted back in a failure
+const MAX_QUOTED_INPUT: usize = 200;
+
+#[derive(Debug, Deserialize)]
+struct HarnessFailure {
+    example: Option<usize>,
+    error: String,
+}
+
+/// Run the plugin script at `script` against `examples` under `limits`,
+/// after checking it defines `entry` (`main`, or `run` for daemons).
+/// Returns a description of each failure; empty when the plugin passed.
+pub fn run_examples(
+    script: &Path,
+    entry: &str,
+    examples: &[serde_json::Value],
+    limits: &ResourceLimits,
+) -> Result<Vec<String>> {
+    let mut cmd = sandbox::command("python3");
+    cmd.arg("-c")
+        .arg(HARNESS)
+        .arg(script)
+        .arg(entry)
+        .env_clear()
+        .env("PATH", "/usr/bin:/bin")
+        .env("HOME", "/tmp/aios-sandbox")
+        .env("LANG", "C.UTF-8")
+        .env("PYTHONDONTWRITEBYTECODE", "1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+    sandbox::apply_limits(&mut cmd, limits);
+    let mut child = cmd.spawn().context("Failed to start the plugin harness")?;
+
+    // Written from another thread: a plugin that hangs while loading never
+    // reads its input
+    let input = serde_json::to_vec(examples)?;
+    if let Some(mut stdin) = child.stdin.take() {
+        std::thread::spawn(move || {
+            let _ = stdin.write_all(&input);
+        });
+    }
+
+    let deadline = Instant::now() + limits.max_cpu_time;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            // SAFETY: killpg only sends a signal
+            unsafe {
+                libc::killpg(child.id() as i32, libc::SIGKILL);
+            }
+            let _ = child.wait();
+            return Ok(vec![format!(
+                "the plugin did not finish its examples within {:?}",
+                limits.max_cpu_time
+            )]);
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    };
+
+    let mut output = String::new();
+    if let Some(mut stdout) = child.stdout.take() {
+        stdout.read_to_string(&mut output)?;
+    }
+    let Ok(failures) = serde_json::from_str::<Vec<HarnessFailure>>(&output) else {
+        return Ok(vec![format!("the plugin harness exited with {status}")]);
+    };
+    Ok(failures
+        .into_iter()
+        .map(|failure| match failure.example {
+            Some(i) => {
+                let mut input = examples.get(i).map(|e| e.to_string()).unwrap_or_default();
+                if input.len() > MAX_QUOTED_INPUT {
+                    let end = (0..=MAX_QUOTED_INPUT)
+                        .rev()
+                        .find(|&i| input.is_char_boundary(i))
+                        .unwrap_or(0);
+                    input.truncate(end);
+                    input.push('…');
+                }
+                format!("example {i} ({input}): {}", failure.error)
+            }
+            None => failure.error,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(code: &str, examples: &[serde_json::Value]) -> Vec<String> {
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("plugin.py");
+        std::fs::write(&script, code).unwrap();
+        run_examples(&script, "main", examples, &ResourceLimits::default()).unwrap()
+    }
+
+    #[test]
+    fn test_examples_must_return_dicts() {
+        if sandbox::command("python3")
+            .arg("--version")
+            .output()
+            .is_err()
+        {
+            return;
+        }
+        let examples = [serde_json::json!({"n": 2}), serde_json::json!({})];
+
+        let code = "def main(input_data):\n    return {'double': input_data['n'] * 2}\n";
+        let failures = run(code, &examples[..1]);
+        assert!(failures.is_empty(), "{failures:?}");
+
+        // A missing key and a non-dict result are both reported per example
+        let failures = run(code, &examples);
+        assert_eq!(failures.len(), 1);
+        assert!(failures[0].starts_with("example 1 ({}): KeyError"));
+        let failures = run("def main(input_data):\n    return [1]\n", &examples[..1]);
+        assert!(failures[0].contains("main returned list, not a dict"));
+
+        // Output printed by the plugin does not confuse the harness
+        let failures = run(
+            "print('hello')\ndef main(input_data):\n    print('working')\n    return {}\n",
+            &examples[..1],
+        );
+        assert!(failures.is_empty(), "{failures:?}");
+
+        // Code that does not load fails without examples too
+        let failures = run("def main(input_data)\n    return {}\n", &[]);
+        assert!(failures[0].starts_with("failed to load: SyntaxError"));
+        let failures = run("def run(input_data):\n    return {}\n", &[]);
+        assert!(failures[0].contains("no main(input_data) function"));
+    }
+}
@@ -4,19 +4,25 @@
 //! Plugins are Python scripts stored in PLUGIN_DIR with JSON metadata.
 //! Each plugin defines a `def main(input_data: dict) -> dict` function
 //! that receives/returns JSON via stdin/stdout.
+//!
+//! The tools service watches PLUGIN_DIR and reloads the registry when a
+//! plugin is added, edited or removed, so changes take effect immediately.
 
 pub mod create;
+pub mod daemon;
 pub mod events;
+pub mod harness;
 pub mod manage;
+pub mod queue;
 pub mod templates;
 pub mod triggers;
 pub mod validate;
 
-use crate::registry::{make_tool, Registry};
+use crate::registry::{make_tool, with_input_schema, Registry};
 use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
-use tokio::sync::Mutex;
+use serde_json::json;
+use std::path::Path;
 use tracing::{info, warn};
 
 /// Directory where plugin scripts and metadata are stored
@@ -38,78 +44,207 @@ pub struct PluginMetadata {
     /// How to pass output to chained plugins: "pipe" (default) or "merge"
     #[serde(default = "default_output_mode")]
     pub output_mode: String,
+    /// JSON schema for the plugin's input, reported in its tool definition
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_schema: Option<serde_json::Value>,
+    /// Instances that may run at once; 0 uses the service default
+    #[serde(default)]
+    pub max_concurrency: u32,
+    /// Hold an exclusive lock while running, so instances started by other
+    /// processes on the host wait too
+    #[serde(default)]
+    pub instance_lock: bool,
+    /// Inputs the plugin was tested with before it was registered
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub examples: Vec<serde_json::Value>,
+    /// Set for daemon plugins, which run continuously under the daemon
+    /// supervisor and are registered as `<tool_name>.control`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daemon: Option<daemon::DaemonSpec>,
+    /// Events that run the plugin, with the event as input
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub triggers: Vec<triggers::EventTrigger>,
+    /// Registered as an experimental tool
+    #[serde(default)]
+    pub experimental: bool,
 }
 
 fn default_output_mode() -> String {
     "pipe".to_string()
 }
 
+/// Metadata of the plugin `name` (without the `plugin.` prefix), if it has
+/// a readable .meta.json
+pub fn load_metadata(name: &str) -> Option<PluginMetadata> {
+    let contents = std::fs::read_to_string(format!("{PLUGIN_DIR}/{name}.meta.json")).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
 /// Register the 4 meta-tools for plugin management
 pub fn register_tools(reg: &mut Registry) {
-    reg.register_tool(make_tool(
-        "plugin.create",
-        "plugin",
-        "Create a new plugin tool from Python code. The AI writes a main(input_data) -> dict function.",
-        vec!["plugin_manage", "fs_write"],
-        "high",
-        false,
-        true,
-        30000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "plugin.create",
+            "plugin",
+            "Create a new plugin tool from Python code. The AI writes a main(input_data) -> dict function.",
+            vec!["plugin_manage", "fs_write"],
+            "high",
+            false,
+            true,
+            30000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string", "description": "Letters, digits and underscores" },
+                "description": { "type": "string" },
+                "code": {
+                    "type": "string",
+                    "description": "Python defining main(input_data: dict) -> dict"
+                },
+                "capabilities": { "type": "array", "items": { "type": "string" } },
+                "dependencies": {
+                    "type": "array",
+                    "items": { "type": "string", "description": "pip package" }
+                },
+                "next_plugins": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Plugins to run after this one"
+                },
+                "output_mode": {
+                    "type": "string",
+                    "enum": ["pipe", "merge"],
+                    "description": "How chained plugins receive the output",
+                    "default": "pipe"
+                },
+                "input_schema": {
+                    "type": "object",
+                    "description": "JSON Schema of the plugin's input"
+                },
+                "max_concurrency": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "description": "0 = the service default"
+                },
+                "instance_lock": {
+                    "type": "boolean",
+                    "description": "Hold a host-wide lock while running"
+                },
+                "examples": {
+                    "type": "array",
+                    "items": {},
+                    "description": "Inputs to test main with before registering"
+                },
+                "daemon": {
+                    "type": "object",
+                    "description": "Make this a daemon plugin defining run(config, emit)"
+                },
+                "triggers": {
+                    "type": "array",
+                    "items": { "type": "object" },
+                    "description": "Events that run the plugin"
+                },
+                "experimental": { "type": "boolean" }
+            },
+            "required": ["name", "description", "code"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "plugin.list",
-        "plugin",
-        "List all installed plugin tools with their descriptions and metadata",
-        vec!["plugin_read"],
-        "low",
-        true,
-        false,
-        5000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "plugin.list",
+            "plugin",
+            "List all installed plugin tools with their descriptions and metadata",
+            vec!["plugin_read"],
+            "low",
+            true,
+            false,
+            5000,
+        ),
+        json!({ "type": "object" }),
     ));
 
-    reg.register_tool(make_tool(
-        "plugin.delete",
-        "plugin",
-        "Delete a plugin tool by name, removing its script and metadata files",
-        vec!["plugin_manage"],
-        "high",
-        false,
-        false,
-        5000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "plugin.delete",
+            "plugin",
+            "Delete a plugin tool by name, removing its script and metadata files",
+            vec!["plugin_manage"],
+            "high",
+            false,
+            false,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string", "description": "Without the \"plugin.\" prefix" }
+            },
+            "required": ["name"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "plugin.install_deps",
-        "plugin",
-        "Install Python pip dependencies for a plugin",
-        vec!["plugin_manage", "pkg_manage"],
-        "high",
-        false,
-        false,
-        60000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "plugin.install_deps",
+            "plugin",
+            "Install Python pip dependencies for a plugin",
+            vec!["plugin_manage", "pkg_manage"],
+            "high",
+            false,
+            false,
+            60000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string" },
+                "packages": {
+                    "type": "array",
+                    "items": { "type": "string", "description": "pip package" }
+                }
+            }
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "plugin.from_template",
-        "plugin",
-        "Create a plugin from a pre-built template (web_scraper, log_analyzer, file_processor, api_client)",
-        vec!["plugin_manage", "fs_write"],
-        "medium",
-        false,
-        true,
-        30000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "plugin.from_template",
+            "plugin",
+            "Create a plugin from a pre-built template (web_scraper, log_analyzer, file_processor, api_client)",
+            vec!["plugin_manage", "fs_write"],
+            "medium",
+            false,
+            true,
+            30000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "template": {
+                    "type": "string",
+                    "enum": ["web_scraper", "log_analyzer", "file_processor", "api_client"]
+                },
+                "config": {}
+            },
+            "required": ["template"]
+        }),
     ));
 }
 
 /// Scan PLUGIN_DIR for *.meta.json files and register each as a tool in the registry.
-/// Called at startup and after plugin.create succeeds.
+/// Called at startup, after plugin.create succeeds, and on every reload.
 pub fn scan_and_register_plugins(reg: &mut Registry) {
-    let plugin_dir = std::path::Path::new(PLUGIN_DIR);
+    register_plugins_in(Path::new(PLUGIN_DIR), reg);
+}
+
+/// Register the plugins described by the *.meta.json files in `plugin_dir`
+fn register_plugins_in(plugin_dir: &Path, reg: &mut Registry) {
     if !plugin_dir.exists() {
         info!(
             "Plugin directory {} does not exist, skipping scan",
-            PLUGIN_DIR
+            plugin_dir.display()
         );
         return;
     }
@@ -133,8 +268,38 @@ pub fn scan_and_register_plugins(reg: &mut Registry) {
         {
             match std::fs::read_to_string(&path) {
                 Ok(contents) => match serde_json::from_str::<PluginMetadata>(&contents) {
+                    Ok(meta) if meta.daemon.is_some() => {
+                        let mut tool = make_tool(
+                            &format!("{}.control", meta.tool_name),
+                            "plugin",
+                            &format!(
+                                "Start, stop, restart or show the status of the {} daemon: {}",
+                                meta.tool_name, meta.description
+                            ),
+                            meta.capabilities.iter().map(|s| s.as_str()).collect(),
+                            "medium",
+                            false,
+                            false,
+                            5000,
+                        );
+                        tool.input_schema = serde_json::json!({
+                            "type": "object",
+                            "properties": {
+                                "action": {
+                                    "type": "string",
+                                    "enum": ["start", "stop", "restart", "status"]
+                                }
+                            },
+                            "required": ["action"]
+                        })
+                        .to_string()
+                        .into_bytes();
+                        tool.experimental = meta.experimental;
+                        reg.register_tool(tool);
+                        count += 1;
+                    }
                     Ok(meta) => {
-                        reg.register_tool(make_tool(
+                        let mut tool = make_tool(
                             &meta.tool_name,
                             "plugin",
                             &meta.description,
@@ -143,7 +308,12 @@ pub fn scan_and_register_plugins(reg: &mut Registry) {
                             false,
                             false,
                             meta.timeout_ms,
-                        ));
+                        );
+                        if let Some(schema) = &meta.input_schema {
+                            tool.input_schema = schema.to_string().into_bytes();
+                        }
+                        tool.experimental = meta.experimental;
+                        reg.register_tool(tool);
                         count += 1;
                     }
                     Err(e) => {
@@ -158,14 +328,17 @@ pub fn scan_and_register_plugins(reg: &mut Registry) {
     }
 
     if count > 0 {
-        info!("Loaded {count} plugin tools from {}", PLUGIN_DIR);
+        info!("Loaded {count} plugin tools from {}", plugin_dir.display());
     }
 }
 
 /// Start a filesystem watcher on PLUGIN_DIR for hot-reload of plugins.
-/// When a .meta.json file is created or modified, re-scan and register plugins.
-pub fn start_hot_reload_watcher(registry: Arc<Mutex<Registry>>) -> Option<RecommendedWatcher> {
-    let plugin_dir = std::path::Path::new(PLUGIN_DIR);
+/// `on_change` is called, on the watcher's thread, whenever a plugin script
+/// or .meta.json file is created, modified or removed.
+pub fn start_hot_reload_watcher(
+    on_change: impl Fn() + Send + 'static,
+) -> Option<RecommendedWatcher> {
+    let plugin_dir = Path::new(PLUGIN_DIR);
     if !plugin_dir.exists() {
         if let Err(e) = std::fs::create_dir_all(plugin_dir) {
             warn!("Cannot create plugin dir for hot-reload: {e}");
@@ -173,30 +346,20 @@ pub fn start_hot_reload_watcher(registry: Arc<Mutex<Registry>>) -> Option<Recomm
         }
     }
 
-    let reg = registry.clone();
     let mut watcher =
         match notify::recommended_watcher(move |res: Result<Event, notify::Error>| match res {
             Ok(event) => {
-                let dominated_by_meta = event
-                    .paths
-                    .iter()
-                    .any(|p| p.to_str().map_or(false, |s| s.ends_with(".meta.json")));
-                let dominated_by_py = event
-                    .paths
-                    .iter()
-                    .any(|p| p.to_str().map_or(false, |s| s.ends_with(".py")));
-
-                if dominated_by_meta || dominated_by_py {
-                    match event.kind {
-                        EventKind::Create(_) | EventKind::Modify(_) => {
-                            info!("Plugin hot-reload: detected change, rescanning");
-                            // Block on acquiring the lock (this runs in notify's thread)
-                            if let Ok(mut reg_lock) = reg.try_lock() {
-                                scan_and_register_plugins(&mut reg_lock);
-                            }
-                        }
-                        _ => {}
-                    }
+                let plugin_file = event.paths.iter().any(|p| {
+                    p.to_str()
+                        .map_or(false, |s| s.ends_with(".meta.json") || s.ends_with(".py"))
+                });
+                if plugin_file
+                    && matches!(
+                        event.kind,
+                        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+                    )
+                {
+                    on_change();
                 }
             }
             Err(e) => {
@@ -218,3 +381,40 @@ pub fn start_hot_reload_watcher(registry: Arc<Mutex<Registry>>) -> Option<Recomm
     info!("Plugin hot-reload watcher started on {}", PLUGIN_DIR);
     Some(watcher)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_register_plugins_reads_input_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("greet.meta.json"),
+            r#"{"tool_name": "plugin.greet", "description": "Say hello",
+                "capabilities": [], "dependencies": [], "author": "test",
+                "created_at": "", "timeout_ms": 5000,
+                "input_schema": {"type": "object", "required": ["name"]}}"#,
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("broken.meta.json"), "not json").unwrap();
+        std::fs::write(
+            dir.path().join("watch.meta.json"),
+            r#"{"tool_name": "plugin.watch", "description": "Watch a feed",
+                "capabilities": [], "dependencies": [], "author": "test",
+                "created_at": "", "timeout_ms": 5000, "daemon": {}}"#,
+        )
+        .unwrap();
+
+        let mut reg = Registry::new();
+        register_plugins_in(dir.path(), &mut reg);
+        assert_eq!(reg.tool_count(), 2);
+        // Daemons are registered as their control tool
+        assert!(reg.get_tool("plugin.watch").is_none());
+        assert!(reg.get_tool("plugin.watch.control").is_some());
+        let tool = reg.get_tool("plugin.greet").unwrap();
+        assert_eq!(tool.namespace, "plugin");
+        let schema: serde_json::Value = serde_json::from_slice(&tool.input_schema).unwrap();
+        assert_eq!(schema["required"][0], "name");
+    }
+}
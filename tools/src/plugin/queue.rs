@@ -0,0 +1,256 @@
+//! Plugin queue — how many instances of a plugin may run at once
+//!
+//! Plugin scripts run outside the registry lock, so calls to different
+//! plugins run side by side. Calls to the same plugin queue, first come
+//! first served, for one of its slots: `max_concurrency` in its metadata,
+//! else `AIOS_PLUGIN_CONCURRENCY` (default 1), so two instances of a plugin
+//! never race on its files unless the plugin says that is safe. At most
+//! `AIOS_PLUGIN_QUEUE_DEPTH` (default 16) calls wait per plugin; further
+//! calls fail at once.
+//!
+//! A plugin that declares `instance_lock` also holds an exclusive lock on
+//! `<plugin dir>/.locks/<name>.lock` while it runs, so instances started by
+//! other processes on the host, such as a second tools service, wait too.
+
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use super::{PluginMetadata, PLUGIN_DIR};
+
+const DEFAULT_CONCURRENCY: usize = 1;
+const DEFAULT_QUEUE_DEPTH: usize = 16;
+
+/// Slots of one plugin
+struct PluginSlots {
+    limit: usize,
+    semaphore: Arc<Semaphore>,
+    /// Calls waiting for a slot
+    waiting: AtomicUsize,
+}
+
+/// Per-plugin concurrency limits for plugin script runs
+pub struct PluginQueue {
+    default_limit: usize,
+    max_queued: usize,
+    lock_dir: PathBuf,
+    plugins: Mutex<HashMap<String, Arc<PluginSlots>>>,
+}
+
+/// A slot of a plugin, and its instance lock when it declares one; both
+/// are released when dropped
+pub struct PluginPermit {
+    _slot: OwnedSemaphorePermit,
+    _lock: Option<std::fs::File>,
+}
+
+/// Counts a call as waiting until it gets a slot or gives up
+struct Waiting<'a>(&'a AtomicUsize);
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl PluginQueue {
+    /// A queue allowing `default_limit` instances of plugins that do not
+    /// declare a limit and `max_queued` waiting calls per plugin, with
+    /// instance locks in `lock_dir`
+    pub fn new(default_limit: usize, max_queued: usize, lock_dir: PathBuf) -> Self {
+        Self {
+            default_limit: default_limit.max(1),
+            max_queued,
+            lock_dir,
+            plugins: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Queue sized from `AIOS_PLUGIN_CONCURRENCY` and `AIOS_PLUGIN_QUEUE_DEPTH`
+    pub fn from_env() -> Self {
+        let env = |name: &str, default: usize| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        };
+        Self::new(
+            env("AIOS_PLUGIN_CONCURRENCY", DEFAULT_CONCURRENCY),
+            env("AIOS_PLUGIN_QUEUE_DEPTH", DEFAULT_QUEUE_DEPTH),
+            PathBuf::from(PLUGIN_DIR).join(".locks"),
+        )
+    }
+
+    /// Slots of `name`, resized when its declared limit changed. Runs
+    /// holding a slot of the old size finish under it.
+    fn slots(&self, name: &str, limit: usize) -> Arc<PluginSlots> {
+        let mut plugins = self.plugins.lock().unwrap();
+        let slots = plugins
+            .entry(name.to_string())
+            .or_insert_with(|| PluginSlots::new(limit));
+        if slots.limit != limit {
+            *slots = PluginSlots::new(limit);
+        }
+        slots.clone()
+    }
+
+    /// Wait for a slot of plugin `name`, then for its instance lock when
+    /// `metadata` declares one. Errors when too many calls are waiting
+    /// already.
+    pub async fn acquire(
+        &self,
+        name: &str,
+        metadata: Option<&PluginMetadata>,
+    ) -> Result<PluginPermit> {
+        let limit = metadata
+            .map(|m| m.max_concurrency as usize)
+            .filter(|limit| *limit > 0)
+            .unwrap_or(self.default_limit);
+        let slots = self.slots(name, limit);
+
+        if slots.semaphore.available_permits() == 0
+            && slots.waiting.load(Ordering::SeqCst) >= self.max_queued
+        {
+            bail!(
+                "{} calls of plugin {name} are queued already",
+                self.max_queued
+            );
+        }
+        slots.waiting.fetch_add(1, Ordering::SeqCst);
+        let waiting = Waiting(&slots.waiting);
+        let slot = slots
+            .semaphore
+            .clone()
+            .acquire_owned()
+            .await
+            .context("Plugin queue closed")?;
+        drop(waiting);
+
+        let lock = match metadata {
+            Some(m) if m.instance_lock => {
+                Some(lock_instance(self.lock_dir.join(format!("{name}.lock"))).await?)
+            }
+            _ => None,
+        };
+        Ok(PluginPermit {
+            _slot: slot,
+            _lock: lock,
+        })
+    }
+}
+
+impl PluginSlots {
+    fn new(limit: usize) -> Arc<Self> {
+        Arc::new(Self {
+            limit,
+            semaphore: Arc::new(Semaphore::new(limit)),
+            waiting: AtomicUsize::new(0),
+        })
+    }
+}
+
+/// Open `path` and wait for an exclusive lock on it, held until the file is
+/// closed
+async fn lock_instance(path: PathBuf) -> Result<std::fs::File> {
+    tokio::task::spawn_blocking(move || {
+        use std::os::unix::io::AsRawFd;
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        // SAFETY: flock only operates on the descriptor, which outlives the call
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } != 0 {
+            return Err(std::io::Error::last_os_error())
+                .with_context(|| format!("Failed to lock {}", path.display()));
+        }
+        Ok(file)
+    })
+    .await?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn metadata(max_concurrency: u32, instance_lock: bool) -> PluginMetadata {
+        serde_json::from_value(serde_json::json!({
+            "tool_name": "plugin.sync", "description": "", "capabilities": [],
+            "dependencies": [], "author": "test", "created_at": "", "timeout_ms": 5000,
+            "max_concurrency": max_concurrency, "instance_lock": instance_lock,
+        }))
+        .unwrap()
+    }
+
+    async fn blocked(queue: &PluginQueue, name: &str, meta: Option<&PluginMetadata>) -> bool {
+        tokio::time::timeout(Duration::from_millis(50), queue.acquire(name, meta))
+            .await
+            .is_err()
+    }
+
+    #[tokio::test]
+    async fn test_limits_are_per_plugin() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue = PluginQueue::new(1, 1, dir.path().to_path_buf());
+
+        let first = queue.acquire("sync", None).await.unwrap();
+        assert!(blocked(&queue, "sync", None).await);
+        // Other plugins are not held up
+        let _other = queue.acquire("report", None).await.unwrap();
+
+        // A declared limit overrides the default
+        let wide = metadata(2, false);
+        let _a = queue.acquire("fetch", Some(&wide)).await.unwrap();
+        let _b = queue.acquire("fetch", Some(&wide)).await.unwrap();
+        assert!(blocked(&queue, "fetch", Some(&wide)).await);
+
+        drop(first);
+        let _second = queue.acquire("sync", None).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_rejects() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue = Arc::new(PluginQueue::new(1, 1, dir.path().to_path_buf()));
+
+        let running = queue.acquire("sync", None).await.unwrap();
+        let waiter = {
+            let queue = queue.clone();
+            tokio::spawn(async move { queue.acquire("sync", None).await.is_ok() })
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let err = queue.acquire("sync", None).await.err().unwrap();
+        assert!(err.to_string().contains("queued already"));
+
+        drop(running);
+        assert!(waiter.await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_instance_lock_is_exclusive() {
+        let dir = tempfile::tempdir().unwrap();
+        // Separate queues stand for separate processes sharing the lock file
+        let first = PluginQueue::new(1, 1, dir.path().to_path_buf());
+        let second = PluginQueue::new(1, 1, dir.path().to_path_buf());
+        let locked = metadata(0, true);
+
+        let held = first.acquire("sync", Some(&locked)).await.unwrap();
+        assert!(dir.path().join("sync.lock").exists());
+        assert!(blocked(&second, "sync", Some(&locked)).await);
+        // Without the lock the other instance does not wait
+        let unlocked = second.acquire("sync", None).await.unwrap();
+        drop(unlocked);
+
+        drop(held);
+        let _held = second.acquire("sync", Some(&locked)).await.unwrap();
+    }
+}
@@ -1,11 +1,90 @@
 //! Plugin Trigger Types and Evaluation
 //!
 //! Defines the trigger conditions that can activate plugins
-//! and the logic to evaluate them.
+//! and the logic to evaluate them. Event triggers are declared in plugin
+//! metadata and matched against the memory service's event stream by the
+//! dispatcher in `events`.
 
+use serde::{Deserialize, Serialize};
 use std::time::SystemTime;
 use tracing::debug;
 
+use crate::proto::memory::Event;
+
+/// Event severities, least to most severe
+const SEVERITIES: &[&str] = &["info", "warning", "error", "critical"];
+
+/// An event that runs a plugin (the `triggers` of its metadata)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventTrigger {
+    /// Event category, e.g. "service"; empty matches every category
+    #[serde(default)]
+    pub category: String,
+    /// Event type within the category, where `*` matches any text, e.g.
+    /// "unit_*"; empty matches every type
+    #[serde(default)]
+    pub event_type: String,
+    /// Least severity that fires (info, warning, error, critical)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub min_severity: String,
+    /// Text that must appear in the event's data
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub pattern: String,
+    /// Times the trigger may fire per minute; later matches are dropped
+    #[serde(default = "default_max_per_minute")]
+    pub max_per_minute: u32,
+    /// Input passed to the plugin along with the event
+    #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+    pub input: serde_json::Map<String, serde_json::Value>,
+}
+
+fn default_max_per_minute() -> u32 {
+    6
+}
+
+impl EventTrigger {
+    /// Whether `event` fires this trigger
+    pub fn matches(&self, event: &Event) -> bool {
+        if !self.category.is_empty() && self.category != event.category {
+            return false;
+        }
+        if !self.event_type.is_empty() && !matches_glob(&self.event_type, &event.event_type) {
+            return false;
+        }
+        if !self.min_severity.is_empty()
+            && severity_rank(&event.severity) < severity_rank(&self.min_severity)
+        {
+            return false;
+        }
+        self.pattern.is_empty()
+            || check_log_pattern(&String::from_utf8_lossy(&event.data_json), &self.pattern)
+    }
+}
+
+fn severity_rank(severity: &str) -> usize {
+    SEVERITIES.iter().position(|s| *s == severity).unwrap_or(0)
+}
+
+/// Match `text` against `pattern`, where `*` matches any run of characters
+fn matches_glob(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
 /// Evaluate a file_watch trigger — check if the file has been modified
 pub fn check_file_watch(path: &str, last_checked: Option<i64>) -> bool {
     let metadata = match std::fs::metadata(path) {
@@ -139,6 +218,41 @@ mod tests {
         assert!(check_metric_threshold(85.0, "<", 90.0));
     }
 
+    #[test]
+    fn test_event_trigger_matches() {
+        let trigger: EventTrigger = serde_json::from_value(serde_json::json!({
+            "category": "service",
+            "event_type": "unit_*",
+            "min_severity": "warning",
+            "pattern": "nginx",
+        }))
+        .unwrap();
+        assert_eq!(trigger.max_per_minute, 6);
+        let event = |event_type: &str, severity: &str, data: &str| Event {
+            category: "service".into(),
+            event_type: event_type.into(),
+            severity: severity.into(),
+            data_json: data.as_bytes().to_vec(),
+            ..Default::default()
+        };
+
+        assert!(trigger.matches(&event("unit_failed", "error", r#"{"unit": "nginx"}"#)));
+        assert!(!trigger.matches(&event("unit_failed", "info", r#"{"unit": "nginx"}"#)));
+        assert!(!trigger.matches(&event("unit_failed", "error", r#"{"unit": "sshd"}"#)));
+        assert!(!trigger.matches(&event("restarted", "error", r#"{"unit": "nginx"}"#)));
+        let other = Event {
+            category: "network".into(),
+            ..event("unit_failed", "error", r#"{"unit": "nginx"}"#)
+        };
+        assert!(!trigger.matches(&other));
+
+        assert!(matches_glob("*", ""));
+        assert!(matches_glob("a*c*e", "abcde"));
+        assert!(!matches_glob("a*a", "a"));
+        assert!(matches_glob("exact", "exact"));
+        assert!(!matches_glob("exact", "exactly"));
+    }
+
     #[test]
     fn test_check_log_pattern() {
         assert!(check_log_pattern("ERROR: disk full", "ERROR"));
@@ -1,11 +1,17 @@
 //! Plugin Validation — analyze Python code for dangerous operations
 //!
-//! Scans plugin code for risky patterns before allowing creation.
+//! Scans plugin code for risky patterns before allowing creation, and
+//! checks its imports against the plugin policy: modules no plugin may
+//! import, and modules only plugins declaring a capability may import.
 
-use anyhow::Result;
-use serde::Serialize;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use tracing::warn;
 
+/// Default location of the plugin import policy
+pub const PLUGIN_POLICY_PATH: &str = "/etc/aios/plugin-policy.toml";
+
 /// Dangerous patterns and their risk scores
 const DANGEROUS_PATTERNS: &[(&str, u32, &str)] = &[
     (
@@ -105,6 +111,110 @@ pub fn validate_plugin_code(code: &str) -> ValidationResult {
     }
 }
 
+/// Which modules plugins may import (plugin-policy.toml)
+#[derive(Debug, Deserialize)]
+pub struct ImportPolicy {
+    /// Modules no plugin may import
+    #[serde(default)]
+    pub deny: Vec<String>,
+    /// Module → capability a plugin must declare to import it
+    #[serde(default)]
+    pub require: HashMap<String, String>,
+}
+
+impl ImportPolicy {
+    /// The policy used when no plugin-policy.toml is installed
+    pub fn builtin() -> Self {
+        let deny = ["ctypes", "cffi", "pty", "marshal"];
+        let require = [
+            ("socket", "net_read"),
+            ("urllib", "net_read"),
+            ("http", "net_read"),
+            ("requests", "net_read"),
+            ("subprocess", "process_manage"),
+            ("signal", "process_manage"),
+            ("shutil", "fs_write"),
+        ];
+        Self {
+            deny: deny.into_iter().map(String::from).collect(),
+            require: require
+                .into_iter()
+                .map(|(module, capability)| (module.to_string(), capability.to_string()))
+                .collect(),
+        }
+    }
+
+    /// The policy at `path`; the built-in one if it is missing or invalid
+    pub fn load(path: &str) -> Self {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => Self::from_toml(&contents).unwrap_or_else(|e| {
+                warn!("Ignoring invalid plugin policy in {path}: {e}");
+                Self::builtin()
+            }),
+            Err(_) => Self::builtin(),
+        }
+    }
+
+    pub fn from_toml(contents: &str) -> Result<Self> {
+        toml::from_str(contents).context("Failed to parse plugin policy")
+    }
+
+    /// Imports of `code` the policy forbids for a plugin declaring
+    /// `capabilities`
+    pub fn check(&self, code: &str, capabilities: &[String]) -> Vec<ValidationFinding> {
+        let mut findings = Vec::new();
+        for (line_number, module) in imported_modules(code) {
+            let description = if self.deny.contains(&module) {
+                format!("Import of {module} is not allowed in plugins")
+            } else if let Some(capability) = self
+                .require
+                .get(&module)
+                .filter(|c| !capabilities.contains(c))
+            {
+                format!("Import of {module} requires declaring the {capability} capability")
+            } else {
+                continue;
+            };
+            findings.push(ValidationFinding {
+                pattern: format!("import {module}"),
+                risk: 100,
+                description,
+                line_number,
+            });
+        }
+        findings
+    }
+}
+
+/// Top-level modules imported by `code`, with their line numbers
+fn imported_modules(code: &str) -> Vec<(usize, String)> {
+    let top_level = |name: &str| {
+        name.trim()
+            .split(['.', ' '])
+            .next()
+            .unwrap_or("")
+            .to_string()
+    };
+    let mut modules = Vec::new();
+    for (line_num, line) in code.lines().enumerate() {
+        let trimmed = line.trim();
+        if let Some(names) = trimmed.strip_prefix("import ") {
+            for name in names.split(',') {
+                modules.push((line_num + 1, top_level(name)));
+            }
+        } else if let Some(rest) = trimmed.strip_prefix("from ") {
+            if let Some((module, _)) = rest.split_once(" import") {
+                // Relative imports stay inside the plugin
+                if !module.starts_with('.') {
+                    modules.push((line_num + 1, top_level(module)));
+                }
+            }
+        }
+    }
+    modules.retain(|(_, module)| !module.is_empty());
+    modules
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -147,4 +257,38 @@ def main(input_data):
         assert!(result.safe);
         assert_eq!(result.risk_score, 0);
     }
+
+    #[test]
+    fn test_import_policy() {
+        let code = r#"
+import json, urllib.request
+from ctypes import CDLL
+def main(input_data):
+    import subprocess as sp
+    return {}
+"#;
+        let policy = ImportPolicy::builtin();
+        let findings = policy.check(code, &[]);
+        let lines: Vec<_> = findings
+            .iter()
+            .map(|f| (f.line_number, f.pattern.as_str()))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                (2, "import urllib"),
+                (3, "import ctypes"),
+                (5, "import subprocess")
+            ]
+        );
+        assert!(findings[1].description.contains("not allowed"));
+
+        // Declared capabilities allow the guarded modules, never denied ones
+        let findings = policy.check(code, &["net_read".into(), "process_manage".into()]);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].pattern, "import ctypes");
+
+        let policy = ImportPolicy::from_toml("deny = [\"json\"]").unwrap();
+        assert_eq!(policy.check(code, &[]).len(), 1);
+    }
 }
@@ -0,0 +1,451 @@
+//! Policy Simulator — what a proposed policy would have done to recent executions
+//!
+//! Replays the audit ledger of the last days against the capability and
+//! sandbox path policies in force, and against a proposal, and reports the
+//! executions the two decide differently: those that would have been
+//! denied, those that would have needed approval, and those that would
+//! have been allowed. A proposal changes the current policy with these
+//! TOML tables:
+//!
+//! ```toml
+//! # Replace an agent's capabilities
+//! [agents]
+//! "web-agent" = ["net_read", "fs_read"]
+//!
+//! # Change what a tool requires
+//! [tools."fs.write"]
+//! capabilities = ["fs_write"]
+//! risk = "high"
+//!
+//! # Sandbox profiles and namespace mappings, as in sandbox-profiles.toml
+//! [profiles.plugin]
+//! writable_paths = ["/tmp/plugins"]
+//!
+//! # Executions that would need an operator's approval; "security*"
+//! # matches agent ids by prefix, "fs.*" tool names
+//! [[approvals]]
+//! name = "etc-writes"
+//! tools = ["fs.*"]
+//! paths = ["/etc"]
+//! auto_approve = ["security*"]
+//! ```
+//!
+//! Paths come from the targets recorded with each execution; executions
+//! recorded before targets were kept are checked without them. Records of
+//! tools that are no longer registered, and of composite tools (whose steps
+//! are checked one by one when they run), are not replayed.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::audit::{LedgerRecord, TARGET_FIELDS};
+use crate::capabilities::{CapabilityChecker, RiskLevel};
+use crate::proto::tools::{PolicySimulationReport, SimulatedExecution, ToolDefinition};
+use crate::registry::Registry;
+use crate::sandbox::SandboxProfiles;
+
+/// Executions that need an operator's approval
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ApprovalRule {
+    pub name: String,
+    pub namespace: String,
+    pub tools: Vec<String>,
+    pub risk_levels: Vec<String>,
+    pub paths: Vec<String>,
+    /// Agents whose matching executions need no approval
+    pub auto_approve: Vec<String>,
+}
+
+impl ApprovalRule {
+    fn matches(&self, tool: &ToolDefinition, risk: &str, targets: &serde_json::Value) -> bool {
+        (self.namespace.is_empty() || self.namespace == tool.namespace)
+            && (self.tools.is_empty() || self.tools.iter().any(|p| pattern_matches(p, &tool.name)))
+            && (self.risk_levels.is_empty() || self.risk_levels.iter().any(|r| r == risk))
+            && (self.paths.is_empty() || target_paths(targets).any(|p| self.covers(p)))
+    }
+
+    fn covers(&self, path: &str) -> bool {
+        self.paths.iter().any(|prefix| {
+            let prefix = prefix.trim_end_matches('/');
+            prefix.is_empty()
+                || path == prefix
+                || path
+                    .strip_prefix(prefix)
+                    .is_some_and(|rest| rest.starts_with('/'))
+        })
+    }
+}
+
+/// Exact match, or prefix match for a pattern ending in `*`
+fn pattern_matches(pattern: &str, value: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => value.starts_with(prefix),
+        None => pattern == value,
+    }
+}
+
+fn target_paths(targets: &serde_json::Value) -> impl Iterator<Item = &str> {
+    TARGET_FIELDS
+        .iter()
+        .filter_map(|key| targets.get(*key).and_then(|v| v.as_str()))
+}
+
+/// Changes a proposal makes to the current policy
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct PolicyChanges {
+    agents: HashMap<String, Vec<String>>,
+    tools: HashMap<String, ToolChange>,
+    approvals: Vec<ApprovalRule>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ToolChange {
+    capabilities: Option<Vec<String>>,
+    risk: Option<String>,
+}
+
+/// How a policy decides an execution
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+    Allowed,
+    Denied(String),
+    Approval(String),
+}
+
+impl Verdict {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Allowed => "allowed",
+            Self::Denied(_) => "denied",
+            Self::Approval(_) => "approval",
+        }
+    }
+
+    fn reason(&self) -> &str {
+        match self {
+            Self::Allowed => "",
+            Self::Denied(reason) | Self::Approval(reason) => reason,
+        }
+    }
+}
+
+/// The policies an execution is checked against before it runs
+#[derive(Clone)]
+pub struct Policy {
+    pub capabilities: CapabilityChecker,
+    pub sandbox: SandboxProfiles,
+    pub approvals: Vec<ApprovalRule>,
+}
+
+impl Policy {
+    /// This policy with the changes of a proposal applied
+    pub fn with_changes(&self, proposal: &str) -> Result<Self> {
+        let changes: PolicyChanges =
+            toml::from_str(proposal).context("Failed to parse the proposed policy")?;
+        let mut policy = self.clone();
+        for (agent, capabilities) in &changes.agents {
+            policy.capabilities.register_agent(agent, capabilities);
+        }
+        for (tool, change) in &changes.tools {
+            let capabilities = change
+                .capabilities
+                .clone()
+                .unwrap_or_else(|| policy.capabilities.required_capabilities(tool).to_vec());
+            let risk = match &change.risk {
+                Some(risk) => RiskLevel::parse(risk)
+                    .with_context(|| format!("Unknown risk level '{risk}' for {tool}"))?,
+                None => policy.capabilities.get_risk_level(tool),
+            };
+            policy
+                .capabilities
+                .set_requirement(tool, &capabilities, risk);
+        }
+        policy.sandbox = self.sandbox.overlay(proposal)?;
+        policy.approvals.extend(changes.approvals);
+        Ok(policy)
+    }
+
+    /// Decide a call by `agent` to `tool` targeting `targets` (the JSON
+    /// object of its path fields)
+    pub fn evaluate(&self, tool: &ToolDefinition, agent: &str, targets: &str) -> Verdict {
+        let check = self.capabilities.check_permission(agent, &tool.name);
+        if !check.allowed {
+            return Verdict::Denied(check.reason);
+        }
+        if let Some((profile, limits)) = self.sandbox.resolve(tool) {
+            let required = self.capabilities.required_capabilities(&tool.name);
+            if let Err(e) = limits.check_request(required, targets.as_bytes()) {
+                return Verdict::Denied(format!("Sandbox profile '{profile}' denied: {e}"));
+            }
+        }
+        let targets: serde_json::Value = serde_json::from_str(targets).unwrap_or_default();
+        let rule = self.approvals.iter().find(|rule| {
+            rule.matches(tool, check.risk_level.as_str(), &targets)
+                && !rule.auto_approve.iter().any(|p| pattern_matches(p, agent))
+        });
+        match rule {
+            Some(rule) if rule.name.is_empty() => {
+                Verdict::Approval(format!("{} calls need approval", tool.name))
+            }
+            Some(rule) => Verdict::Approval(format!("Approval rule '{}'", rule.name)),
+            None => Verdict::Allowed,
+        }
+    }
+}
+
+/// The current and proposed verdicts on one call
+type Verdicts = (Verdict, Verdict);
+
+/// Replay `records` against both policies, listing up to `max_listed` of
+/// the executions they decide differently, newest first
+pub fn simulate(
+    records: &[LedgerRecord],
+    registry: &Registry,
+    current: &Policy,
+    proposed: &Policy,
+    max_listed: usize,
+) -> PolicySimulationReport {
+    let mut report = PolicySimulationReport::default();
+    // Replays repeat the same calls; decide each one once
+    let mut decided: HashMap<(&str, &str, &str), Option<Verdicts>> = HashMap::new();
+    for record in records.iter().rev() {
+        let targets = record.targets.as_deref().unwrap_or("{}");
+        let verdicts = decided
+            .entry((&record.tool_name, &record.agent_id, targets))
+            .or_insert_with(|| {
+                if registry.get_composite(&record.tool_name).is_some() {
+                    return None;
+                }
+                let tool = registry.get_tool(&record.tool_name)?;
+                Some((
+                    current.evaluate(&tool, &record.agent_id, targets),
+                    proposed.evaluate(&tool, &record.agent_id, targets),
+                ))
+            });
+        let Some((now, then)) = verdicts else {
+            continue;
+        };
+        report.evaluated += 1;
+        if now.name() == then.name() {
+            continue;
+        }
+        match then {
+            Verdict::Denied(_) => report.newly_denied += 1,
+            Verdict::Approval(_) => report.newly_needing_approval += 1,
+            Verdict::Allowed => report.newly_allowed += 1,
+        }
+        if report.changes.len() < max_listed {
+            report.changes.push(SimulatedExecution {
+                execution_id: record.execution_id.clone(),
+                tool_name: record.tool_name.clone(),
+                agent_id: record.agent_id.clone(),
+                timestamp: record.timestamp.clone(),
+                current: now.name().to_string(),
+                proposed: then.name().to_string(),
+                reason: then.reason().to_string(),
+            });
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::make_tool;
+
+    fn record(id: &str, tool: &str, agent: &str, targets: Option<&str>) -> LedgerRecord {
+        LedgerRecord {
+            id: 0,
+            execution_id: id.to_string(),
+            tool_name: tool.to_string(),
+            agent_id: agent.to_string(),
+            task_id: "task-1".to_string(),
+            reason: String::new(),
+            success: true,
+            duration_ms: 10,
+            timestamp: "2026-10-01T12:00:00+00:00".to_string(),
+            prev_hash: String::new(),
+            hash: String::new(),
+            snapshot: None,
+            targets: targets.map(str::to_string),
+        }
+    }
+
+    fn registry() -> Registry {
+        let mut registry = Registry::new();
+        for (name, risk) in [
+            ("fs.read", "low"),
+            ("fs.write", "medium"),
+            ("net.ping", "low"),
+        ] {
+            let namespace = name.split('.').next().unwrap();
+            registry.register_tool(make_tool(
+                name,
+                namespace,
+                "",
+                vec![],
+                risk,
+                true,
+                false,
+                1000,
+            ));
+        }
+        registry
+    }
+
+    fn current() -> Policy {
+        Policy {
+            capabilities: CapabilityChecker::new(),
+            sandbox: SandboxProfiles::builtin(),
+            approvals: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_unchanged_policy_changes_nothing() {
+        let records = vec![
+            record(
+                "e1",
+                "fs.read",
+                "monitoring-agent",
+                Some(r#"{"path": "/var/log/syslog"}"#),
+            ),
+            record("e2", "fs.write", "monitoring-agent", None),
+        ];
+        let policy = current();
+        let report = simulate(
+            &records,
+            &registry(),
+            &policy,
+            &policy.with_changes("").unwrap(),
+            10,
+        );
+        assert_eq!(report.evaluated, 2);
+        assert!(report.changes.is_empty());
+    }
+
+    #[test]
+    fn test_capability_and_path_changes() {
+        let records = vec![
+            record("e1", "net.ping", "web-agent", None),
+            record(
+                "e2",
+                "fs.write",
+                "storage-agent",
+                Some(r#"{"path": "/etc/hosts"}"#),
+            ),
+            record(
+                "e3",
+                "fs.write",
+                "storage-agent",
+                Some(r#"{"path": "/srv/app.conf"}"#),
+            ),
+            record("e4", "fs.write", "security-agent", None),
+            record("e5", "composite.backup", "storage-agent", None),
+        ];
+        let current = current();
+        let proposed = current
+            .with_changes(
+                r#"
+                [agents]
+                "web-agent" = ["fs_read"]
+                "security-agent" = ["fs_read", "fs_write"]
+
+                [profiles.configs]
+                writable_paths = ["/srv"]
+
+                [namespaces]
+                fs = "configs"
+                "#,
+            )
+            .unwrap();
+        let report = simulate(&records, &registry(), &current, &proposed, 10);
+
+        assert_eq!(report.evaluated, 4);
+        assert_eq!(report.newly_denied, 2);
+        assert_eq!(report.newly_allowed, 1);
+        let changes: Vec<(&str, &str, &str)> = report
+            .changes
+            .iter()
+            .map(|c| {
+                (
+                    c.execution_id.as_str(),
+                    c.current.as_str(),
+                    c.proposed.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                ("e4", "denied", "allowed"),
+                ("e2", "allowed", "denied"),
+                ("e1", "allowed", "denied"),
+            ]
+        );
+        assert!(report.changes[1].reason.contains("/etc/hosts"));
+    }
+
+    #[test]
+    fn test_approval_rules() {
+        let records = vec![
+            record(
+                "e1",
+                "fs.write",
+                "storage-agent",
+                Some(r#"{"path": "/etc/fstab"}"#),
+            ),
+            record(
+                "e2",
+                "fs.write",
+                "storage-agent",
+                Some(r#"{"path": "/etcetera/x"}"#),
+            ),
+            record(
+                "e3",
+                "fs.write",
+                "task-agent",
+                Some(r#"{"path": "/etc/fstab"}"#),
+            ),
+            record(
+                "e4",
+                "fs.read",
+                "storage-agent",
+                Some(r#"{"path": "/etc/fstab"}"#),
+            ),
+        ];
+        let current = current();
+        let proposed = current
+            .with_changes(
+                r#"
+                [tools."fs.read"]
+                risk = "high"
+
+                [[approvals]]
+                name = "etc-writes"
+                tools = ["fs.write"]
+                paths = ["/etc"]
+                auto_approve = ["task*"]
+
+                [[approvals]]
+                risk_levels = ["high"]
+                "#,
+            )
+            .unwrap();
+        let report = simulate(&records, &registry(), &current, &proposed, 1);
+
+        assert_eq!(report.newly_needing_approval, 2);
+        assert_eq!(report.changes.len(), 1);
+        assert_eq!(report.changes[0].execution_id, "e4");
+        assert_eq!(report.changes[0].reason, "fs.read calls need approval");
+
+        assert!(current
+            .with_changes("[tools.\"fs.read\"]\nrisk = \"severe\"")
+            .is_err());
+    }
+}
@@ -0,0 +1,370 @@
+//! Privilege separation — run tool namespaces under dedicated users
+//!
+//! The tool service runs as root, but most tools need nothing of the sort.
+//! Namespaces are mapped to unprivileged execution users (aios-net, aios-fs,
+//! aios-pkg) in privsep.toml; tools in a mapped namespace whose risk level is
+//! at or below `max_risk` run with that user's identity, so a compromised
+//! low-risk path cannot write to /etc or signal arbitrary processes. Higher
+//! risk tools (pkg.install, fs.delete, ...) keep root.
+//!
+//! Only the calling thread's credentials are switched (raw setres[ug]id and
+//! setgroups syscalls act per thread on Linux), and root is kept as the saved
+//! uid so the executor can switch back afterwards. Children a handler spawns
+//! inherit the dropped identity and lose the saved root uid on exec.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use tracing::warn;
+
+use crate::capabilities::RiskLevel;
+use crate::proto::tools::ToolDefinition;
+
+/// Default location of the privilege separation configuration
+pub const PRIVSEP_CONFIG_PATH: &str = "/etc/aios/privsep.toml";
+
+/// An unprivileged identity tools run under
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecUser {
+    pub name: String,
+    pub uid: u32,
+    pub gid: u32,
+}
+
+impl ExecUser {
+    /// Look up a user in the system user database
+    pub fn lookup(name: &str) -> Option<Self> {
+        let user = nix::unistd::User::from_name(name).ok()??;
+        Some(Self {
+            name: user.name,
+            uid: user.uid.as_raw(),
+            gid: user.gid.as_raw(),
+        })
+    }
+}
+
+/// privsep.toml layout
+#[derive(Debug, Deserialize)]
+struct PrivsepConfig {
+    #[serde(default = "default_max_risk")]
+    max_risk: String,
+    /// Tool namespace → execution user ("" runs the namespace as root)
+    #[serde(default)]
+    namespaces: HashMap<String, String>,
+}
+
+fn default_max_risk() -> String {
+    "medium".into()
+}
+
+/// Which tools drop privileges, and to whom
+#[derive(Debug, Clone)]
+pub struct PrivsepPolicy {
+    namespaces: HashMap<String, ExecUser>,
+    max_risk: RiskLevel,
+}
+
+impl PrivsepPolicy {
+    /// Built-in namespace users: net → aios-net, fs → aios-fs, pkg → aios-pkg
+    pub fn builtin() -> Self {
+        Self::from_config(
+            PrivsepConfig {
+                max_risk: default_max_risk(),
+                namespaces: builtin_namespaces(),
+            },
+            ExecUser::lookup,
+        )
+        .unwrap_or_else(|_| Self {
+            namespaces: HashMap::new(),
+            max_risk: RiskLevel::Medium,
+        })
+    }
+
+    /// Built-in policy overlaid with the configuration at `path`.
+    /// A missing file yields the built-ins; an invalid one is logged and ignored.
+    pub fn load(path: &str) -> Self {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => Self::from_toml(&contents, ExecUser::lookup).unwrap_or_else(|e| {
+                warn!("Ignoring invalid privilege separation config in {path}: {e}");
+                Self::builtin()
+            }),
+            Err(_) => Self::builtin(),
+        }
+    }
+
+    /// Built-in policy overlaid with a privsep.toml document, resolving
+    /// user names with `lookup`
+    pub fn from_toml(contents: &str, lookup: impl Fn(&str) -> Option<ExecUser>) -> Result<Self> {
+        let mut config: PrivsepConfig =
+            toml::from_str(contents).context("Failed to parse privilege separation config")?;
+        let mut namespaces = builtin_namespaces();
+        namespaces.extend(config.namespaces.drain());
+        config.namespaces = namespaces;
+        Self::from_config(config, lookup)
+    }
+
+    fn from_config(
+        config: PrivsepConfig,
+        lookup: impl Fn(&str) -> Option<ExecUser>,
+    ) -> Result<Self> {
+        let max_risk = match config.max_risk.as_str() {
+            "low" => RiskLevel::Low,
+            "medium" => RiskLevel::Medium,
+            "high" => RiskLevel::High,
+            "critical" => RiskLevel::Critical,
+            other => anyhow::bail!("unknown max_risk '{other}'"),
+        };
+
+        let mut namespaces = HashMap::new();
+        for (namespace, name) in config.namespaces {
+            if name.is_empty() {
+                continue;
+            }
+            match lookup(&name) {
+                Some(user) if user.uid == 0 => {
+                    anyhow::bail!("namespace '{namespace}' maps to root user '{name}'")
+                }
+                Some(user) => {
+                    namespaces.insert(namespace, user);
+                }
+                None => warn!("Execution user '{name}' for namespace '{namespace}' does not exist; {namespace}.* tools keep root"),
+            }
+        }
+        Ok(Self {
+            namespaces,
+            max_risk,
+        })
+    }
+
+    /// User a tool runs as, or None to keep the service's identity
+    pub fn user_for(&self, tool: &ToolDefinition, risk: &RiskLevel) -> Option<&ExecUser> {
+        if *risk > self.max_risk {
+            return None;
+        }
+        self.namespaces.get(&tool.namespace)
+    }
+}
+
+fn builtin_namespaces() -> HashMap<String, String> {
+    [("net", "aios-net"), ("fs", "aios-fs"), ("pkg", "aios-pkg")]
+        .into_iter()
+        .map(|(ns, user)| (ns.to_string(), user.to_string()))
+        .collect()
+}
+
+/// Run `f` on this thread as `user`, switching back to the original identity
+/// afterwards. A no-op without a user or when the service is not root.
+/// Fails without running `f` if the identity cannot be switched.
+pub fn with_user<T>(user: Option<&ExecUser>, f: impl FnOnce() -> T) -> Result<T> {
+    let Some(user) = user else {
+        return Ok(f());
+    };
+    if !nix::unistd::geteuid().is_root() {
+        return Ok(f());
+    }
+    let _guard = ThreadIdentity::drop_to(user)
+        .with_context(|| format!("Failed to switch to execution user {}", user.name))?;
+    Ok(f())
+}
+
+/// Restores the thread's original credentials when dropped
+struct ThreadIdentity {
+    gid: libc::gid_t,
+    groups: Vec<libc::gid_t>,
+}
+
+impl ThreadIdentity {
+    #[cfg(target_os = "linux")]
+    fn drop_to(user: &ExecUser) -> Result<Self> {
+        let saved = Self {
+            gid: unsafe { libc::getegid() },
+            groups: current_groups()?,
+        };
+        // Group identity first; after the uid switch CAP_SETGID is gone.
+        // Real and effective ids change (kill() checks the real uid); the
+        // saved ids stay root so the switch can be undone. On failure,
+        // dropping `saved` restores whatever was already switched.
+        unsafe {
+            check(libc::syscall(
+                libc::SYS_setgroups,
+                1usize,
+                &user.gid as *const libc::gid_t,
+            ))?;
+            check(libc::syscall(
+                libc::SYS_setresgid,
+                user.gid,
+                user.gid,
+                -1i32,
+            ))?;
+            check(libc::syscall(
+                libc::SYS_setresuid,
+                user.uid,
+                user.uid,
+                -1i32,
+            ))?;
+        }
+        Ok(saved)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn drop_to(_user: &ExecUser) -> Result<Self> {
+        anyhow::bail!("per-thread privilege separation requires Linux")
+    }
+
+    fn restore(&self) {
+        #[cfg(target_os = "linux")]
+        unsafe {
+            let ok = check(libc::syscall(libc::SYS_setresuid, 0, 0, -1i32)).is_ok()
+                && check(libc::syscall(
+                    libc::SYS_setgroups,
+                    self.groups.len(),
+                    self.groups.as_ptr(),
+                ))
+                .is_ok()
+                && check(libc::syscall(
+                    libc::SYS_setresgid,
+                    self.gid,
+                    self.gid,
+                    -1i32,
+                ))
+                .is_ok();
+            if !ok {
+                // A worker thread stuck with a tool user's identity would run
+                // every later tool unprivileged (or worse, half-switched)
+                eprintln!(
+                    "privsep: failed to restore thread credentials: {}",
+                    std::io::Error::last_os_error()
+                );
+                std::process::abort();
+            }
+        }
+    }
+}
+
+impl Drop for ThreadIdentity {
+    fn drop(&mut self) {
+        self.restore();
+    }
+}
+
+fn check(ret: libc::c_long) -> Result<()> {
+    if ret == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error().into())
+    }
+}
+
+fn current_groups() -> Result<Vec<libc::gid_t>> {
+    let n = unsafe { libc::getgroups(0, std::ptr::null_mut()) };
+    if n < 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    let mut groups = vec![0; n as usize];
+    let n = unsafe { libc::getgroups(n, groups.as_mut_ptr()) };
+    if n < 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    groups.truncate(n as usize);
+    Ok(groups)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fake_lookup(name: &str) -> Option<ExecUser> {
+        let uid = match name {
+            "aios-net" => 1001,
+            "aios-fs" => 1002,
+            "aios-pkg" => 1003,
+            "root" => 0,
+            _ => return None,
+        };
+        Some(ExecUser {
+            name: name.to_string(),
+            uid,
+            gid: uid,
+        })
+    }
+
+    fn tool(namespace: &str) -> ToolDefinition {
+        ToolDefinition {
+            name: format!("{namespace}.x"),
+            namespace: namespace.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_policy_maps_namespaces_by_risk() {
+        let policy = PrivsepPolicy::from_toml("", fake_lookup).unwrap();
+        assert_eq!(
+            policy.user_for(&tool("fs"), &RiskLevel::Low).unwrap().uid,
+            1002
+        );
+        assert_eq!(
+            policy
+                .user_for(&tool("net"), &RiskLevel::Medium)
+                .unwrap()
+                .name,
+            "aios-net"
+        );
+        assert!(policy.user_for(&tool("pkg"), &RiskLevel::High).is_none());
+        assert!(policy.user_for(&tool("service"), &RiskLevel::Low).is_none());
+    }
+
+    #[test]
+    fn test_config_overrides_and_rejects_root() {
+        let policy = PrivsepPolicy::from_toml(
+            "max_risk = \"low\"\n[namespaces]\nfs = \"\"\nweb = \"aios-net\"\nmissing = \"nobody-here\"\n",
+            fake_lookup,
+        )
+        .unwrap();
+        assert!(policy.user_for(&tool("fs"), &RiskLevel::Low).is_none());
+        assert!(policy.user_for(&tool("net"), &RiskLevel::Medium).is_none());
+        assert_eq!(
+            policy.user_for(&tool("web"), &RiskLevel::Low).unwrap().uid,
+            1001
+        );
+        assert!(policy.user_for(&tool("missing"), &RiskLevel::Low).is_none());
+
+        let err =
+            PrivsepPolicy::from_toml("[namespaces]\nfs = \"root\"\n", fake_lookup).unwrap_err();
+        assert!(err.to_string().contains("root"));
+        assert!(PrivsepPolicy::from_toml("max_risk = \"extreme\"\n", fake_lookup).is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_with_user_drops_and_restores_thread_identity() {
+        if !nix::unistd::geteuid().is_root() {
+            return;
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let nobody = ExecUser {
+            name: "nobody".into(),
+            uid: 65534,
+            gid: 65534,
+        };
+
+        // Run on a dedicated thread so other tests keep root
+        let path = dir.path().join("denied.txt");
+        std::thread::spawn(move || {
+            let (euid, wrote) = with_user(Some(&nobody), || {
+                (
+                    nix::unistd::geteuid().as_raw(),
+                    std::fs::write(&path, "x").is_ok(),
+                )
+            })
+            .unwrap();
+            assert_eq!(euid, 65534);
+            assert!(!wrote, "unprivileged user wrote into a root-owned 0700 dir");
+            assert!(nix::unistd::geteuid().is_root());
+            assert!(nix::unistd::getuid().is_root());
+            assert!(std::fs::write(&path, "x").is_ok());
+        })
+        .join()
+        .unwrap();
+    }
+}
@@ -2,7 +2,8 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+
+use crate::sandbox::command;
 
 #[derive(Deserialize)]
 struct Input {
@@ -25,7 +26,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
 
     // Use ps to get process details on macOS
     // -p selects by PID, -o specifies output columns
-    let output = Command::new("ps")
+    let output = command("ps")
         .args([
             "-p",
             &input.pid.to_string(),
@@ -74,7 +75,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
     };
 
     // Get thread count using a separate ps call
-    let thread_output = Command::new("ps")
+    let thread_output = command("ps")
         .args(["-M", "-p", &input.pid.to_string()])
         .output();
 
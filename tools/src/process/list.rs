@@ -2,7 +2,8 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+
+use crate::sandbox::command;
 
 #[derive(Deserialize)]
 struct Input {}
@@ -28,7 +29,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
         serde_json::from_slice(input).context("Invalid JSON input")?
     };
 
-    let output = Command::new("ps")
+    let output = command("ps")
         .args(["-eo", "pid,comm,%cpu,%mem,state", "-r"])
         .output()
         .context("Failed to execute ps command")?;
@@ -10,73 +10,144 @@ pub mod list;
 pub mod signal;
 pub mod spawn;
 
-use crate::registry::{make_tool, Registry};
+use serde_json::json;
+
+use crate::registry::{make_tool, with_input_schema, Registry};
 
 /// Register every process tool with the registry.
 pub fn register_tools(reg: &mut Registry) {
-    reg.register_tool(make_tool(
-        "process.list",
-        "process",
-        "List all running processes with pid, name, cpu, memory, and status",
-        vec!["process.read"],
-        "low",
-        true,
-        false,
-        10000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "process.list",
+            "process",
+            "List all running processes with pid, name, cpu, memory, and status",
+            vec!["process.read"],
+            "low",
+            true,
+            false,
+            10000,
+        ),
+        json!({ "type": "object" }),
     ));
 
-    reg.register_tool(make_tool(
-        "process.spawn",
-        "process",
-        "Spawn a new process with the given command, arguments, and environment variables",
-        vec!["process.execute"],
-        "high",
-        false,
-        false,
-        30000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "process.spawn",
+            "process",
+            "Spawn a new process with the given command, arguments, and environment variables",
+            vec!["process.execute"],
+            "high",
+            false,
+            false,
+            30000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "command": { "type": "string" },
+                "args": { "type": "array", "items": { "type": "string" } },
+                "env": { "type": "object", "additionalProperties": { "type": "string" } }
+            },
+            "required": ["command"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "process.kill",
-        "process",
-        "Kill a process by PID with the specified signal number",
-        vec!["process.kill"],
-        "critical",
-        false,
-        false,
-        5000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "process.kill",
+            "process",
+            "Kill a process by PID with the specified signal number",
+            vec!["process.kill"],
+            "critical",
+            false,
+            false,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "pid": { "type": "integer", "minimum": 1 },
+                "signal": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Signal number",
+                    "default": 9
+                }
+            },
+            "required": ["pid"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "process.info",
-        "process",
-        "Get detailed information about a process by PID",
-        vec!["process.read"],
-        "low",
-        true,
-        false,
-        5000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "process.info",
+            "process",
+            "Get detailed information about a process by PID",
+            vec!["process.read"],
+            "low",
+            true,
+            false,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": { "pid": { "type": "integer", "minimum": 1 } },
+            "required": ["pid"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "process.signal",
-        "process",
-        "Send a named signal (e.g. SIGHUP, SIGTERM) to a process",
-        vec!["process.signal"],
-        "high",
-        false,
-        false,
-        5000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "process.signal",
+            "process",
+            "Send a named signal (e.g. SIGHUP, SIGTERM) to a process",
+            vec!["process.signal"],
+            "high",
+            false,
+            false,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "pid": { "type": "integer", "minimum": 1 },
+                "signal": {
+                    "type": "string",
+                    "description": "Signal name such as \"TERM\" or \"SIGHUP\""
+                }
+            },
+            "required": ["pid", "signal"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "process.cgroup",
-        "process",
-        "Manage cgroup v2 resource limits: create groups, assign PIDs, set CPU/memory/IO limits",
-        vec!["process.admin"],
-        "high",
-        false,
-        true,
-        10000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "process.cgroup",
+            "process",
+            "Manage cgroup v2 resource limits: create groups, assign PIDs, set CPU/memory/IO limits",
+            vec!["process.admin"],
+            "high",
+            false,
+            true,
+            10000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ["create", "assign", "status", "remove"],
+                    "default": "status"
+                },
+                "group_name": {
+                    "type": "string",
+                    "description": "Group under /sys/fs/cgroup/aios"
+                },
+                "pid": { "type": "integer", "minimum": 1, "description": "Process to assign" },
+                "cpu_weight": { "type": "integer", "minimum": 1, "description": "1-10000" },
+                "memory_max_mb": { "type": "integer", "minimum": 0 },
+                "io_weight": { "type": "integer", "minimum": 1, "description": "1-10000" }
+            }
+        }),
     ));
 }
@@ -3,7 +3,6 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::process::Command;
 
 #[derive(Deserialize)]
 struct Input {
@@ -22,7 +21,7 @@ struct Output {
 pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
     let input: Input = serde_json::from_slice(input).context("Invalid JSON input")?;
 
-    let mut cmd = Command::new(&input.command);
+    let mut cmd = crate::sandbox::command(&input.command);
     cmd.args(&input.args);
 
     for (key, value) in &input.env {
@@ -1,25 +1,54 @@
 //! Tool Registry — stores and retrieves tool definitions
 
 use std::collections::HashMap;
-use tracing::info;
+use tracing::debug;
+
+use crate::composite::CompositeTool;
+use crate::proto::tools::{ListToolsRequest, ToolDefinition};
+
+/// Order of risk levels, lowest first
+fn risk_rank(risk_level: &str) -> u8 {
+    match risk_level {
+        "low" => 0,
+        "medium" => 1,
+        "high" => 2,
+        "critical" => 3,
+        _ => 4,
+    }
+}
+
+/// Tool names added, changed and removed by a reload, each sorted
+#[derive(Debug, Default, PartialEq)]
+pub struct ReloadDiff {
+    pub added: Vec<String>,
+    pub changed: Vec<String>,
+    pub removed: Vec<String>,
+}
 
-use crate::proto::tools::ToolDefinition;
+impl ReloadDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+}
 
 /// In-memory tool registry
 pub struct Registry {
     tools: HashMap<String, ToolDefinition>,
+    /// Step definitions of the registered composite tools
+    composites: HashMap<String, CompositeTool>,
 }
 
 impl Registry {
     pub fn new() -> Self {
         Self {
             tools: HashMap::new(),
+            composites: HashMap::new(),
         }
     }
 
     /// Register a tool definition
     pub fn register_tool(&mut self, tool: ToolDefinition) {
-        info!("Registered tool: {} (ns: {})", tool.name, tool.namespace);
+        debug!("Registered tool: {} (ns: {})", tool.name, tool.namespace);
         self.tools.insert(tool.name.clone(), tool);
     }
 
@@ -28,6 +57,59 @@ impl Registry {
         self.tools.get(name).cloned()
     }
 
+    /// One page of the tools matching a ListTools request, ordered by name
+    /// unless it asks otherwise, and the number of matching tools
+    pub fn search_tools(
+        &self,
+        req: &ListToolsRequest,
+    ) -> Result<(Vec<ToolDefinition>, i32), String> {
+        let query = req.query.to_lowercase();
+        let mut tools: Vec<ToolDefinition> = self
+            .list_tools(&req.namespace)
+            .into_iter()
+            .filter(|t| req.include_experimental || !t.experimental)
+            .filter(|t| {
+                query.is_empty()
+                    || t.name.to_lowercase().contains(&query)
+                    || t.description.to_lowercase().contains(&query)
+            })
+            .collect();
+        tools.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let (field, descending) = match req.sort.strip_prefix('-') {
+            Some(field) => (field, true),
+            None => (req.sort.as_str(), false),
+        };
+        let key = |t: &ToolDefinition| match field {
+            "namespace" => t.namespace.clone(),
+            "risk_level" => risk_rank(&t.risk_level).to_string(),
+            _ => t.name.clone(),
+        };
+        match field {
+            "" => {}
+            "name" | "namespace" | "risk_level" => {
+                tools.sort_by_key(key);
+                if descending {
+                    tools.reverse();
+                }
+            }
+            _ => {
+                return Err(format!(
+                    "Cannot sort by '{field}'; expected one of: name, namespace, risk_level"
+                ))
+            }
+        }
+
+        let total = tools.len() as i32;
+        let tools = tools.into_iter().skip(req.offset.max(0) as usize);
+        let tools = if req.limit > 0 {
+            tools.take(req.limit as usize).collect()
+        } else {
+            tools.collect()
+        };
+        Ok((tools, total))
+    }
+
     /// List tools, optionally filtered by namespace
     pub fn list_tools(&self, namespace: &str) -> Vec<ToolDefinition> {
         if namespace.is_empty() {
@@ -41,15 +123,55 @@ impl Registry {
         }
     }
 
+    /// Register a composite tool under its derived definition
+    pub fn register_composite(&mut self, tool: ToolDefinition, composite: CompositeTool) {
+        self.composites.insert(tool.name.clone(), composite);
+        self.register_tool(tool);
+    }
+
+    /// Get a composite tool's steps by name
+    pub fn get_composite(&self, name: &str) -> Option<&CompositeTool> {
+        self.composites.get(name)
+    }
+
     /// Deregister a tool
     pub fn deregister_tool(&mut self, name: &str) {
         self.tools.remove(name);
+        self.composites.remove(name);
     }
 
     /// Get total tool count
     pub fn tool_count(&self) -> usize {
         self.tools.len()
     }
+
+    /// Replace every tool with those of `next`, reporting the difference
+    pub fn replace(&mut self, next: Registry) -> ReloadDiff {
+        let mut diff = ReloadDiff::default();
+        for (name, tool) in &next.tools {
+            match self.tools.get(name) {
+                None => diff.added.push(name.clone()),
+                Some(old)
+                    if old != tool || self.composites.get(name) != next.composites.get(name) =>
+                {
+                    diff.changed.push(name.clone())
+                }
+                Some(_) => {}
+            }
+        }
+        diff.removed = self
+            .tools
+            .keys()
+            .filter(|name| !next.tools.contains_key(*name))
+            .cloned()
+            .collect();
+        diff.added.sort();
+        diff.changed.sort();
+        diff.removed.sort();
+        self.tools = next.tools;
+        self.composites = next.composites;
+        diff
+    }
 }
 
 #[cfg(test)]
@@ -98,6 +220,61 @@ mod tests {
         assert_eq!(all.len(), 3);
     }
 
+    #[test]
+    fn test_search_tools_pages_and_sorts() {
+        let mut reg = Registry::new();
+        reg.register_tool(sample_tool("fs.write", "fs"));
+        reg.register_tool(sample_tool("fs.read", "fs"));
+        reg.register_tool(sample_tool("net.ping", "net"));
+
+        let req = ListToolsRequest {
+            limit: 2,
+            ..Default::default()
+        };
+        let (page, total) = reg.search_tools(&req).unwrap();
+        assert_eq!(total, 3);
+        let names: Vec<&str> = page.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["fs.read", "fs.write"]);
+
+        let req = ListToolsRequest {
+            offset: 2,
+            ..Default::default()
+        };
+        assert_eq!(reg.search_tools(&req).unwrap().0[0].name, "net.ping");
+
+        let req = ListToolsRequest {
+            query: "PING".into(),
+            sort: "-name".into(),
+            ..Default::default()
+        };
+        let (found, total) = reg.search_tools(&req).unwrap();
+        assert_eq!((found[0].name.as_str(), total), ("net.ping", 1));
+
+        let req = ListToolsRequest {
+            sort: "size".into(),
+            ..Default::default()
+        };
+        assert!(reg.search_tools(&req).is_err());
+    }
+
+    #[test]
+    fn test_search_tools_hides_experimental() {
+        let mut reg = Registry::new();
+        reg.register_tool(sample_tool("fs.read", "fs"));
+        let mut draft = sample_tool("plugin.feed_digest", "plugin");
+        draft.experimental = true;
+        reg.register_tool(draft);
+
+        let (tools, total) = reg.search_tools(&ListToolsRequest::default()).unwrap();
+        assert_eq!((tools[0].name.as_str(), total), ("fs.read", 1));
+
+        let req = ListToolsRequest {
+            include_experimental: true,
+            ..Default::default()
+        };
+        assert_eq!(reg.search_tools(&req).unwrap().1, 2);
+    }
+
     #[test]
     fn test_list_tools_by_namespace() {
         let mut reg = Registry::new();
@@ -181,6 +358,33 @@ mod tests {
         assert_eq!(tool.timeout_ms, 10000);
     }
 
+    #[test]
+    fn test_replace_reports_diff() {
+        let mut reg = Registry::new();
+        reg.register_tool(sample_tool("fs.read", "fs"));
+        reg.register_tool(sample_tool("plugin.old", "plugin"));
+        reg.register_tool(sample_tool("plugin.edited", "plugin"));
+
+        let mut next = Registry::new();
+        next.register_tool(sample_tool("fs.read", "fs"));
+        next.register_tool(sample_tool("plugin.new", "plugin"));
+        let mut edited = sample_tool("plugin.edited", "plugin");
+        edited.input_schema = br#"{"type":"object"}"#.to_vec();
+        next.register_tool(edited);
+
+        let diff = reg.replace(next);
+        assert_eq!(diff.added, vec!["plugin.new"]);
+        assert_eq!(diff.changed, vec!["plugin.edited"]);
+        assert_eq!(diff.removed, vec!["plugin.old"]);
+        assert_eq!(reg.tool_count(), 3);
+        assert!(reg.get_tool("plugin.old").is_none());
+        assert!(!reg
+            .get_tool("plugin.edited")
+            .unwrap()
+            .input_schema
+            .is_empty());
+    }
+
     #[test]
     fn test_make_tool_helper() {
         let tool = make_tool(
@@ -287,5 +491,22 @@ pub fn make_tool(
         reversible,
         timeout_ms,
         rollback_tool: String::new(),
+        sandbox_profile: String::new(),
+        critical_section: false,
+        experimental: false,
     }
 }
+
+/// Declare the JSON Schema a tool's input must satisfy; the executor
+/// rejects calls that do not before running them
+pub fn with_input_schema(mut tool: ToolDefinition, schema: serde_json::Value) -> ToolDefinition {
+    tool.input_schema = schema.to_string().into_bytes();
+    tool
+}
+
+/// Mark a tool as a critical section, one that must not be interrupted
+/// midway
+pub fn critical(mut tool: ToolDefinition) -> ToolDefinition {
+    tool.critical_section = true;
+    tool
+}
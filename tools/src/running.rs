@@ -0,0 +1,603 @@
+//! Running executions — timeout enforcement and cancellation
+//!
+//! The executor runs every tool under [`supervise`]: the tool's work races
+//! its `timeout_ms` and a cancellation of the task it runs for. When the
+//! deadline passes or the task is cancelled (the Cancel RPC, sent by the
+//! orchestrator when a goal is cancelled), the child processes the tool
+//! started are killed: each command built with [`crate::sandbox::command`]
+//! leads its own process group, registered against the execution that
+//! started it ([`track_children`]), and the groups of the stopped execution
+//! are killed whole so that orphaned grandchildren go with them. Nothing
+//! else is touched — detached `process.spawn` children and plugin daemons
+//! keep running — and groups a timed-out handler still starts afterwards
+//! are killed as they appear.
+//!
+//! Timeouts and cancellations are reported as their own failure classes so
+//! callers can retry a timed-out call without retrying a broken one.
+//!
+//! Tools that must not be interrupted midway (firewall changes, package
+//! transactions, certificate rotation) and composites declared `critical`
+//! run as critical sections: cancellations wait until the section ends and
+//! are applied then, and the service's shutdown — including the restart a
+//! self-update performs — waits for it too. The tool's timeout is the
+//! section's hard deadline; a section that overruns it is killed and its
+//! backup rolled back.
+//!
+//! Long-running tools can be resumable: as they go they report a progress
+//! token ([`report_progress`]), and when a call stops before finishing the
+//! last token is returned in `ExecuteResponse.progress_token`. A later call
+//! that passes it back as `resume_token` continues from there
+//! ([`resume_token`]) instead of starting over.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+/// Failure classes reported in `ExecuteResponse.failure_class`
+pub const FAILURE_DENIED: &str = "denied";
+pub const FAILURE_RATE_LIMITED: &str = "rate_limited";
+pub const FAILURE_ERROR: &str = "error";
+pub const FAILURE_TIMEOUT: &str = "timeout";
+pub const FAILURE_CANCELLED: &str = "cancelled";
+pub const FAILURE_INVALID_INPUT: &str = "invalid_input";
+
+/// How an execution ended
+#[derive(Debug)]
+pub enum Outcome<T> {
+    Completed(T),
+    TimedOut(Duration),
+    Cancelled,
+}
+
+struct Execution {
+    task_id: String,
+    tool: String,
+    cancel: CancellationToken,
+}
+
+/// Critical sections in progress and the cancellations waiting for them
+#[derive(Default)]
+struct CriticalSections {
+    /// Tool of each section, by execution id
+    active: HashMap<String, String>,
+    /// Tasks whose cancellation waits for the sections to end, with reasons
+    deferred: Vec<(String, String)>,
+}
+
+/// What a cancellation did to the running executions
+#[derive(Debug, Default, PartialEq)]
+pub struct Cancellation {
+    pub cancelled: Vec<String>,
+    /// Executions left to finish because a critical section is running
+    pub deferred: Vec<String>,
+}
+
+/// Executions in progress, by execution id
+#[derive(Default)]
+pub struct RunningExecutions {
+    executions: Mutex<HashMap<String, Execution>>,
+    critical: Mutex<CriticalSections>,
+    /// Woken whenever the last critical section ends
+    critical_idle: Notify,
+}
+
+impl RunningExecutions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel every execution running for one of `task_ids`. While a
+    /// critical section runs, the cancellation waits for it to end.
+    pub fn cancel_tasks(&self, task_ids: &[String], reason: &str) -> Cancellation {
+        if let Ok(mut critical) = self.critical.lock() {
+            if !critical.active.is_empty() {
+                let deferred = self.matching(task_ids);
+                info!(
+                    "Cancellation of {} executions deferred until critical sections end ({}): {reason}",
+                    deferred.len(),
+                    critical.active.values().cloned().collect::<Vec<_>>().join(", ")
+                );
+                critical.deferred.extend(
+                    task_ids
+                        .iter()
+                        .map(|task| (task.clone(), reason.to_string())),
+                );
+                return Cancellation {
+                    cancelled: Vec::new(),
+                    deferred,
+                };
+            }
+        }
+
+        let Ok(executions) = self.executions.lock() else {
+            return Cancellation::default();
+        };
+        let mut cancelled = Vec::new();
+        for (id, execution) in executions.iter() {
+            if task_ids.contains(&execution.task_id) {
+                info!(
+                    "Cancelling {} ({id}) for task {}: {reason}",
+                    execution.tool, execution.task_id
+                );
+                execution.cancel.cancel();
+                cancelled.push(id.clone());
+            }
+        }
+        Cancellation {
+            cancelled,
+            deferred: Vec::new(),
+        }
+    }
+
+    /// Ids of the executions running for one of `task_ids`
+    fn matching(&self, task_ids: &[String]) -> Vec<String> {
+        self.executions.lock().map_or(Vec::new(), |executions| {
+            executions
+                .iter()
+                .filter(|(_, e)| task_ids.contains(&e.task_id))
+                .map(|(id, _)| id.clone())
+                .collect()
+        })
+    }
+
+    /// Enter a critical section for `execution_id`. Until the returned
+    /// guard is dropped, cancellations are deferred and
+    /// [`RunningExecutions::critical_idle`] waits.
+    pub fn enter_critical(&self, execution_id: &str, tool: &str) -> CriticalSection<'_> {
+        if let Ok(mut critical) = self.critical.lock() {
+            critical
+                .active
+                .insert(execution_id.to_string(), tool.to_string());
+        }
+        info!("{tool} ({execution_id}) entered a critical section");
+        CriticalSection {
+            running: self,
+            execution_id: execution_id.to_string(),
+        }
+    }
+
+    fn exit_critical(&self, execution_id: &str) {
+        let deferred = match self.critical.lock() {
+            Ok(mut critical) => {
+                critical.active.remove(execution_id);
+                if !critical.active.is_empty() {
+                    return;
+                }
+                std::mem::take(&mut critical.deferred)
+            }
+            Err(_) => return,
+        };
+        for (task_id, reason) in deferred {
+            self.cancel_tasks(&[task_id], &format!("{reason} (deferred)"));
+        }
+        self.critical_idle.notify_waiters();
+    }
+
+    /// Tools of the critical sections in progress
+    pub fn critical_sections(&self) -> Vec<String> {
+        self.critical.lock().map_or(Vec::new(), |critical| {
+            critical.active.values().cloned().collect()
+        })
+    }
+
+    /// Wait until no critical section is in progress
+    pub async fn critical_idle(&self) {
+        loop {
+            let idle = self.critical_idle.notified();
+            if self.critical_sections().is_empty() {
+                return;
+            }
+            idle.await;
+        }
+    }
+
+    fn start(&self, execution_id: &str, task_id: &str, tool: &str) -> CancellationToken {
+        let cancel = CancellationToken::new();
+        if let Ok(mut executions) = self.executions.lock() {
+            executions.insert(
+                execution_id.to_string(),
+                Execution {
+                    task_id: task_id.to_string(),
+                    tool: tool.to_string(),
+                    cancel: cancel.clone(),
+                },
+            );
+        }
+        cancel
+    }
+
+    fn finish(&self, execution_id: &str) {
+        if let Ok(mut executions) = self.executions.lock() {
+            executions.remove(execution_id);
+        }
+    }
+}
+
+/// A critical section in progress; ends when dropped
+pub struct CriticalSection<'a> {
+    running: &'a RunningExecutions,
+    execution_id: String,
+}
+
+impl Drop for CriticalSection<'_> {
+    fn drop(&mut self) {
+        self.running.exit_critical(&self.execution_id);
+    }
+}
+
+/// Progress of one call to a resumable tool, shared between the handler's
+/// thread and the executor
+#[derive(Clone, Default)]
+pub struct Progress {
+    resume_token: String,
+    reported: Arc<Mutex<String>>,
+}
+
+thread_local! {
+    static PROGRESS: RefCell<Option<Progress>> = const { RefCell::new(None) };
+}
+
+impl Progress {
+    pub fn new(resume_token: &str) -> Self {
+        Self {
+            resume_token: resume_token.to_string(),
+            reported: Arc::default(),
+        }
+    }
+
+    /// Run `f` with this as the progress of the current thread's tool call
+    pub fn enter<R>(&self, f: impl FnOnce() -> R) -> R {
+        PROGRESS.with(|p| *p.borrow_mut() = Some(self.clone()));
+        let result = f();
+        PROGRESS.with(|p| *p.borrow_mut() = None);
+        result
+    }
+
+    /// The last token the tool reported; empty if it reported none
+    pub fn token(&self) -> String {
+        self.reported.lock().map(|t| t.clone()).unwrap_or_default()
+    }
+}
+
+/// Token the current tool call should resume from; empty to start fresh
+pub fn resume_token() -> String {
+    PROGRESS.with(|p| {
+        p.borrow()
+            .as_ref()
+            .map(|p| p.resume_token.clone())
+            .unwrap_or_default()
+    })
+}
+
+/// Report how far the current tool call has got
+pub fn report_progress(token: impl Into<String>) {
+    PROGRESS.with(|p| {
+        if let Some(progress) = p.borrow().as_ref() {
+            if let Ok(mut reported) = progress.reported.lock() {
+                *reported = token.into();
+            }
+        }
+    });
+}
+
+/// Run `work` until it completes, `timeout` passes or its task is
+/// cancelled. Without a task id the execution cannot be cancelled. The
+/// caller kills the tool's child processes when it did not complete.
+pub async fn supervise<T>(
+    running: &RunningExecutions,
+    execution_id: &str,
+    task_id: &str,
+    tool: &str,
+    timeout: Option<Duration>,
+    work: impl Future<Output = T>,
+) -> Outcome<T> {
+    let cancel = if task_id.is_empty() {
+        CancellationToken::new()
+    } else {
+        running.start(execution_id, task_id, tool)
+    };
+    let deadline = async {
+        match timeout {
+            Some(timeout) => tokio::time::sleep(timeout).await,
+            None => std::future::pending().await,
+        }
+    };
+
+    let outcome = tokio::select! {
+        result = work => Outcome::Completed(result),
+        _ = deadline => Outcome::TimedOut(timeout.unwrap_or_default()),
+        _ = cancel.cancelled() => Outcome::Cancelled,
+    };
+    running.finish(execution_id);
+    outcome
+}
+
+/// The timeout a tool definition asks for; none when `timeout_ms` is unset
+pub fn tool_timeout(timeout_ms: i32) -> Option<Duration> {
+    (timeout_ms > 0).then(|| Duration::from_millis(timeout_ms as u64))
+}
+
+/// Process groups an execution's commands lead, registered as they start
+struct Tracked {
+    tag: u64,
+    groups: Vec<i32>,
+    /// The execution was stopped: groups registered from now on are killed
+    stopped: bool,
+}
+
+#[derive(Default)]
+struct ChildGroups {
+    next_tag: u64,
+    executions: HashMap<String, Tracked>,
+}
+
+static CHILD_GROUPS: Mutex<Option<ChildGroups>> = Mutex::new(None);
+
+thread_local! {
+    /// Tag of the execution whose handler runs on this thread
+    static TRACKING: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Run `f` (a tool handler) registering the process group of every
+/// [`crate::sandbox::command`] it starts against `execution_id`, until it
+/// returns. Groups started by earlier, finished executions — detached
+/// `process.spawn` children, plugin daemons — are never registered here.
+pub fn track_children<R>(execution_id: &str, f: impl FnOnce() -> R) -> R {
+    let tag = with_child_groups(|groups| {
+        groups.next_tag += 1;
+        let tag = groups.next_tag;
+        groups.executions.insert(
+            execution_id.to_string(),
+            Tracked {
+                tag,
+                groups: Vec::new(),
+                stopped: false,
+            },
+        );
+        tag
+    });
+    let previous = TRACKING.with(|t| t.replace(tag));
+    let result = f();
+    TRACKING.with(|t| t.set(previous));
+    with_child_groups(|groups| {
+        // spawn() returns after the child reported in, so nothing the
+        // handler started is still on its way
+        drain_group_pipe(groups);
+        groups.executions.remove(execution_id);
+    });
+    result
+}
+
+/// Make `cmd` register its process group against the execution running
+/// on this thread, if any. The child must lead its own process group.
+pub fn register_child(cmd: &mut std::process::Command) {
+    let tag = TRACKING.with(Cell::get);
+    let Some((_, fd)) = group_pipe().filter(|_| tag != 0) else {
+        return;
+    };
+    // Keep the pipe from filling up with reports nobody asked for yet
+    with_child_groups(drain_group_pipe);
+
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::process::CommandExt;
+        // SAFETY: between fork and exec only getpgrp and write run, both
+        // async-signal-safe; a 16-byte write to a pipe is atomic
+        unsafe {
+            cmd.pre_exec(move || {
+                let report = [tag, libc::getpgrp() as u64];
+                libc::write(fd, report.as_ptr().cast(), std::mem::size_of_val(&report));
+                Ok(())
+            });
+        }
+    }
+}
+
+/// Kill the process groups the commands of a tool that timed out or was
+/// cancelled lead, and any it starts from now on: its handler thread is
+/// left to finish on its own. Returns the number of groups signalled.
+pub fn kill_children(execution_id: &str) -> usize {
+    with_child_groups(|groups| {
+        drain_group_pipe(groups);
+        let Some(tracked) = groups.executions.get_mut(execution_id) else {
+            return 0;
+        };
+        tracked.stopped = true;
+        let killed = tracked.groups.len();
+        for pgid in tracked.groups.drain(..) {
+            kill_group(pgid);
+        }
+        killed
+    })
+}
+
+fn with_child_groups<R>(f: impl FnOnce(&mut ChildGroups) -> R) -> R {
+    let mut groups = CHILD_GROUPS.lock().unwrap_or_else(|e| e.into_inner());
+    f(groups.get_or_insert_with(ChildGroups::default))
+}
+
+fn kill_group(pgid: i32) {
+    // SAFETY: killpg only sends a signal
+    unsafe {
+        libc::killpg(pgid, libc::SIGKILL);
+    }
+}
+
+/// Non-blocking pipe (read, write) the children of tracked commands report
+/// their process group on, between fork and exec. Closed on exec.
+fn group_pipe() -> Option<(i32, i32)> {
+    static PIPE: OnceLock<Option<(i32, i32)>> = OnceLock::new();
+    *PIPE.get_or_init(|| {
+        let mut fds = [0; 2];
+        // SAFETY: pipe2 fills the two descriptors it is given
+        let created =
+            unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC | libc::O_NONBLOCK) } == 0;
+        if !created {
+            warn!(
+                "Cannot track tool child processes: {}",
+                std::io::Error::last_os_error()
+            );
+        }
+        created.then_some((fds[0], fds[1]))
+    })
+}
+
+/// Record the process groups reported since the last drain; groups of
+/// stopped executions are killed as they turn up
+fn drain_group_pipe(groups: &mut ChildGroups) {
+    let Some((read, _)) = group_pipe() else {
+        return;
+    };
+    // SAFETY: getpgrp has no preconditions
+    let own_group = unsafe { libc::getpgrp() };
+    loop {
+        let mut report = [0u64; 2];
+        let size = std::mem::size_of_val(&report);
+        // SAFETY: reads at most `size` bytes into `report`
+        if unsafe { libc::read(read, report.as_mut_ptr().cast(), size) } != size as isize {
+            return;
+        }
+        let [tag, pgid] = report;
+        let pgid = pgid as i32;
+        // A command moved back into the service's group is not the tool's
+        if pgid <= 1 || pgid == own_group {
+            continue;
+        }
+        let Some(tracked) = groups.executions.values_mut().find(|t| t.tag == tag) else {
+            continue;
+        };
+        if tracked.stopped {
+            kill_group(pgid);
+        } else {
+            tracked.groups.push(pgid);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kill_children_kills_only_the_executions_groups() {
+        let sleep = || crate::sandbox::command("sleep").arg("30").spawn().unwrap();
+        let mut untracked = sleep();
+
+        let (started, handler_started) = std::sync::mpsc::channel();
+        let (resume, stopped) = std::sync::mpsc::channel::<()>();
+        let handler = std::thread::spawn(move || {
+            track_children("e1", || {
+                started.send(sleep()).unwrap();
+                stopped.recv().unwrap();
+                // Started after the execution was stopped
+                sleep()
+            })
+        });
+        let mut first = handler_started.recv().unwrap();
+        assert_eq!(kill_children("e1"), 1);
+        resume.send(()).unwrap();
+        let mut late = handler.join().unwrap();
+
+        use std::os::unix::process::ExitStatusExt;
+        assert_eq!(first.wait().unwrap().signal(), Some(libc::SIGKILL));
+        assert_eq!(late.wait().unwrap().signal(), Some(libc::SIGKILL));
+        assert!(untracked.try_wait().unwrap().is_none());
+        untracked.kill().unwrap();
+        untracked.wait().unwrap();
+        // A finished execution has nothing left to kill
+        assert_eq!(kill_children("e1"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_supervise_timeout_and_cancel() {
+        let running = RunningExecutions::new();
+        let outcome = supervise(&running, "e1", "t1", "test.fast", None, async { 7 }).await;
+        assert!(matches!(outcome, Outcome::Completed(7)));
+
+        let outcome = supervise(
+            &running,
+            "e2",
+            "t1",
+            "test.slow",
+            tool_timeout(20),
+            std::future::pending::<()>(),
+        )
+        .await;
+        assert!(matches!(outcome, Outcome::TimedOut(t) if t.as_millis() == 20));
+
+        // A cancelled task stops its running execution
+        let slow = supervise(
+            &running,
+            "e3",
+            "t2",
+            "test.slow",
+            None,
+            std::future::pending::<()>(),
+        );
+        let cancel = async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            running.cancel_tasks(&["t2".to_string()], "goal cancelled")
+        };
+        let (outcome, cancellation) = tokio::join!(slow, cancel);
+        assert!(matches!(outcome, Outcome::Cancelled));
+        assert_eq!(cancellation.cancelled, vec!["e3"]);
+        assert_eq!(
+            running.cancel_tasks(&["t2".to_string()], "again"),
+            Cancellation::default()
+        );
+    }
+
+    #[test]
+    fn test_progress_reported_from_tool_thread() {
+        // Outside a tool call there is nothing to resume or report to
+        assert!(resume_token().is_empty());
+        report_progress("ignored");
+
+        let progress = Progress::new("bytes=10");
+        let handle = progress.clone();
+        std::thread::spawn(move || {
+            handle.enter(|| {
+                assert_eq!(resume_token(), "bytes=10");
+                report_progress("bytes=20");
+                report_progress("bytes=30");
+            })
+        })
+        .join()
+        .unwrap();
+        assert_eq!(progress.token(), "bytes=30");
+        assert!(Progress::default().token().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_critical_section_defers_cancellation() {
+        let running = RunningExecutions::new();
+        let section = running.enter_critical("e1", "pkg.install");
+        let work = supervise(
+            &running,
+            "e1",
+            "t1",
+            "pkg.install",
+            None,
+            tokio::time::sleep(Duration::from_millis(30)),
+        );
+        let cancel = async {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            running.cancel_tasks(&["t1".to_string()], "goal cancelled")
+        };
+        let (outcome, cancellation) = tokio::join!(work, cancel);
+        assert!(matches!(outcome, Outcome::Completed(())));
+        assert_eq!(cancellation.deferred, vec!["e1"]);
+        assert!(cancellation.cancelled.is_empty());
+
+        assert_eq!(running.critical_sections(), vec!["pkg.install"]);
+        drop(section);
+        assert!(running.critical_sections().is_empty());
+        running.critical_idle().await;
+    }
+}
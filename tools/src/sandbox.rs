@@ -4,11 +4,28 @@
 //! - Linux: uses unshare/namespaces for isolation
 //! - Fallback: subprocess with restricted environment
 //! - Resource limits: memory, CPU time, file descriptors
+//!
+//! Limits are grouped into named profiles (net-isolated, read-only-fs,
+//! build-heavy, ...) defined in sandbox-profiles.toml. A tool uses the profile
+//! named in its ToolDefinition, or the one mapped to its namespace; the
+//! executor applies it to the handler and to every child process the handler
+//! starts, since all of them are built with [`command`].
 
 use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::path::{Component, Path};
+use std::sync::OnceLock;
 use std::time::Duration;
 use tracing::{info, warn};
 
+use crate::proto::tools::ToolDefinition;
+
+/// Default location of the sandbox profile configuration
+pub const SANDBOX_PROFILES_PATH: &str = "/etc/aios/sandbox-profiles.toml";
+
 /// Resource limits for sandboxed execution
 #[derive(Debug, Clone)]
 pub struct ResourceLimits {
@@ -39,6 +56,337 @@ impl Default for ResourceLimits {
     }
 }
 
+impl ResourceLimits {
+    /// Whether `path` lies under one of the writable paths (`/` allows all).
+    /// Paths containing `..` are never writable.
+    pub fn allows_write(&self, path: &str) -> bool {
+        let path = Path::new(path);
+        if path.components().any(|c| c == Component::ParentDir) {
+            return false;
+        }
+        self.writable_paths.iter().any(|w| path.starts_with(w))
+    }
+
+    /// Check an in-process tool call against these limits: tools needing
+    /// network capabilities require `allow_network`, and paths a writing tool
+    /// targets must be writable.
+    pub fn check_request(&self, required_capabilities: &[String], input_json: &[u8]) -> Result<()> {
+        let requires = |cap: &str| required_capabilities.iter().any(|c| c == cap);
+        if !self.allow_network && required_capabilities.iter().any(|c| c.starts_with("net_")) {
+            anyhow::bail!("network access is not allowed");
+        }
+        if !requires("fs_write") {
+            return Ok(());
+        }
+
+        let input: serde_json::Value = serde_json::from_slice(input_json).unwrap_or_default();
+        let mut keys = vec!["path", "destination", "link", "output_path"];
+        if requires("fs_delete") {
+            keys.push("source");
+        }
+        let targets: Vec<&str> = keys
+            .iter()
+            .filter_map(|key| input.get(*key).and_then(|v| v.as_str()))
+            .collect();
+        if targets.is_empty() && self.writable_paths.is_empty() {
+            anyhow::bail!("filesystem is read-only");
+        }
+        if let Some(path) = targets.iter().find(|p| !self.allows_write(p)) {
+            anyhow::bail!("writing to {path} is not allowed");
+        }
+        Ok(())
+    }
+}
+
+/// Profile fields as written in sandbox-profiles.toml; unset fields keep
+/// the built-in profile's value, or the default limits for new profiles
+#[derive(Debug, Default, Deserialize)]
+struct ProfileConfig {
+    max_memory_mb: Option<u64>,
+    max_cpu_secs: Option<u64>,
+    max_file_descriptors: Option<u32>,
+    max_processes: Option<u32>,
+    allow_network: Option<bool>,
+    writable_paths: Option<Vec<String>>,
+}
+
+/// sandbox-profiles.toml layout
+#[derive(Debug, Default, Deserialize)]
+struct ProfilesConfig {
+    #[serde(default)]
+    profiles: HashMap<String, ProfileConfig>,
+    /// Tool namespace → profile name
+    #[serde(default)]
+    namespaces: HashMap<String, String>,
+}
+
+/// Named sandbox profiles and the namespaces they apply to
+#[derive(Debug, Clone)]
+pub struct SandboxProfiles {
+    profiles: HashMap<String, ResourceLimits>,
+    namespaces: HashMap<String, String>,
+}
+
+impl SandboxProfiles {
+    /// Built-in profiles and namespace assignments
+    pub fn builtin() -> Self {
+        let everywhere = vec!["/".to_string()];
+        let profiles = HashMap::from([
+            (
+                "net-isolated".to_string(),
+                ResourceLimits {
+                    max_memory_bytes: 512 * 1024 * 1024,
+                    max_cpu_time: Duration::from_secs(60),
+                    max_file_descriptors: 256,
+                    max_processes: 64,
+                    allow_network: false,
+                    writable_paths: everywhere.clone(),
+                },
+            ),
+            (
+                "read-only-fs".to_string(),
+                ResourceLimits {
+                    allow_network: true,
+                    writable_paths: vec![],
+                    ..Default::default()
+                },
+            ),
+            (
+                "build-heavy".to_string(),
+                ResourceLimits {
+                    max_memory_bytes: 4 * 1024 * 1024 * 1024,
+                    max_cpu_time: Duration::from_secs(1800),
+                    max_file_descriptors: 4096,
+                    max_processes: 512,
+                    allow_network: true,
+                    writable_paths: everywhere,
+                },
+            ),
+            (
+                "plugin".to_string(),
+                ResourceLimits {
+                    allow_network: true,
+                    max_cpu_time: Duration::from_secs(30),
+                    writable_paths: vec!["/tmp".to_string()],
+                    ..Default::default()
+                },
+            ),
+        ]);
+        let namespaces = [
+            ("calc", "read-only-fs"),
+            ("hash", "read-only-fs"),
+            ("text", "read-only-fs"),
+            ("plugin", "plugin"),
+            ("self", "build-heavy"),
+        ]
+        .into_iter()
+        .map(|(ns, profile)| (ns.to_string(), profile.to_string()))
+        .collect();
+        Self {
+            profiles,
+            namespaces,
+        }
+    }
+
+    /// Built-in profiles overlaid with the configuration at `path`.
+    /// A missing file yields the built-ins; an invalid one is logged and ignored.
+    pub fn load(path: &str) -> Self {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => Self::from_toml(&contents).unwrap_or_else(|e| {
+                warn!("Ignoring invalid sandbox profiles in {path}: {e}");
+                Self::builtin()
+            }),
+            Err(_) => Self::builtin(),
+        }
+    }
+
+    /// Built-in profiles overlaid with a sandbox-profiles.toml document
+    pub fn from_toml(contents: &str) -> Result<Self> {
+        Self::builtin().overlay(contents)
+    }
+
+    /// These profiles overlaid with the `[profiles]` and `[namespaces]`
+    /// tables of a TOML document; other tables are ignored
+    pub fn overlay(&self, contents: &str) -> Result<Self> {
+        let config: ProfilesConfig =
+            toml::from_str(contents).context("Failed to parse sandbox profiles")?;
+        let mut profiles = self.clone();
+
+        for (name, overrides) in config.profiles {
+            let limits = profiles.profiles.entry(name).or_default();
+            if let Some(mb) = overrides.max_memory_mb {
+                limits.max_memory_bytes = mb * 1024 * 1024;
+            }
+            if let Some(secs) = overrides.max_cpu_secs {
+                limits.max_cpu_time = Duration::from_secs(secs);
+            }
+            if let Some(fds) = overrides.max_file_descriptors {
+                limits.max_file_descriptors = fds;
+            }
+            if let Some(procs) = overrides.max_processes {
+                limits.max_processes = procs;
+            }
+            if let Some(allow) = overrides.allow_network {
+                limits.allow_network = allow;
+            }
+            if let Some(paths) = overrides.writable_paths {
+                limits.writable_paths = paths;
+            }
+        }
+
+        for (namespace, profile) in config.namespaces {
+            if profile.is_empty() {
+                profiles.namespaces.remove(&namespace);
+            } else if profiles.profiles.contains_key(&profile) {
+                profiles.namespaces.insert(namespace, profile);
+            } else {
+                anyhow::bail!("namespace '{namespace}' references unknown profile '{profile}'");
+            }
+        }
+        Ok(profiles)
+    }
+
+    /// Look up a profile by name
+    pub fn get(&self, name: &str) -> Option<&ResourceLimits> {
+        self.profiles.get(name)
+    }
+
+    /// Profile for a tool: the one named in its definition, else its
+    /// namespace's. Tools with neither run unconfined.
+    pub fn resolve(&self, tool: &ToolDefinition) -> Option<(&str, &ResourceLimits)> {
+        let name = if tool.sandbox_profile.is_empty() {
+            self.namespaces.get(&tool.namespace)?
+        } else {
+            &tool.sandbox_profile
+        };
+        match self.profiles.get_key_value(name) {
+            Some((name, limits)) => Some((name.as_str(), limits)),
+            None => {
+                warn!(
+                    "Tool {} references unknown sandbox profile '{name}'",
+                    tool.name
+                );
+                None
+            }
+        }
+    }
+}
+
+thread_local! {
+    /// Limits of the profile the current tool handler runs under
+    static ACTIVE_LIMITS: RefCell<Option<ResourceLimits>> = const { RefCell::new(None) };
+}
+
+/// Run `f` with `limits` active for every [`command`] built on this thread
+pub fn with_limits<T>(limits: Option<&ResourceLimits>, f: impl FnOnce() -> T) -> T {
+    struct Restore(Option<ResourceLimits>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            ACTIVE_LIMITS.with(|active| *active.borrow_mut() = self.0.take());
+        }
+    }
+
+    let previous = ACTIVE_LIMITS.with(|active| active.replace(limits.cloned()));
+    let _restore = Restore(previous);
+    f()
+}
+
+/// A `std::process::Command` for `program` carrying the active profile's limits
+/// and the Linux capabilities granted to the running tool. The child leads its
+/// own process group, registered against the running execution, so a
+/// timed-out tool's whole process tree can be killed.
+/// Every child process the service starts is built here; a test keeps
+/// `Command::new` out of the rest of the crate.
+pub fn command(program: impl AsRef<OsStr>) -> std::process::Command {
+    let mut cmd = std::process::Command::new(program);
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+    ACTIVE_LIMITS.with(|active| {
+        if let Some(limits) = active.borrow().as_ref() {
+            apply_limits(&mut cmd, limits);
+        }
+    });
+    crate::linux_caps::apply_granted(&mut cmd);
+    crate::running::register_child(&mut cmd);
+    cmd
+}
+
+/// Whether this process may create network namespaces, probed once
+fn network_isolation_available() -> bool {
+    static AVAILABLE: OnceLock<bool> = OnceLock::new();
+    *AVAILABLE.get_or_init(|| {
+        #[cfg(target_os = "linux")]
+        {
+            use std::os::unix::process::CommandExt;
+            let mut probe = std::process::Command::new("true");
+            unsafe {
+                probe.pre_exec(|| {
+                    if libc::unshare(libc::CLONE_NEWNET) == 0 {
+                        Ok(())
+                    } else {
+                        Err(std::io::Error::last_os_error())
+                    }
+                });
+            }
+            let available = probe.status().is_ok_and(|s| s.success());
+            if !available {
+                warn!("Network namespaces unavailable; sandboxed commands keep network access");
+            }
+            available
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            false
+        }
+    })
+}
+
+/// Apply `limits` to a child process: rlimits for memory, CPU time, file
+/// descriptors and processes; a private network namespace when network is
+/// disallowed; and a zero file-size limit when nothing is writable.
+pub fn apply_limits(cmd: &mut std::process::Command, limits: &ResourceLimits) {
+    if !limits.allow_network {
+        cmd.env("AIOS_SANDBOX_NO_NETWORK", "1");
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::process::CommandExt;
+        let max_mem = limits.max_memory_bytes;
+        let max_cpu = limits.max_cpu_time.as_secs().max(1);
+        let max_fds = limits.max_file_descriptors as u64;
+        let max_procs = limits.max_processes as u64;
+        let read_only = limits.writable_paths.is_empty();
+        let isolate_network = !limits.allow_network && network_isolation_available();
+
+        unsafe {
+            cmd.pre_exec(move || {
+                let set = |resource, value: u64| {
+                    let limit = libc::rlimit {
+                        rlim_cur: value,
+                        rlim_max: value,
+                    };
+                    libc::setrlimit(resource, &limit);
+                };
+                set(libc::RLIMIT_AS, max_mem);
+                set(libc::RLIMIT_CPU, max_cpu);
+                set(libc::RLIMIT_NOFILE, max_fds);
+                set(libc::RLIMIT_NPROC, max_procs);
+                if read_only {
+                    set(libc::RLIMIT_FSIZE, 0);
+                }
+                if isolate_network && libc::unshare(libc::CLONE_NEWNET) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+    }
+}
+
 /// Result of sandboxed execution
 #[derive(Debug)]
 pub struct SandboxResult {
@@ -57,6 +405,21 @@ pub struct ResourceUsage {
     pub cpu_time_ms: u64,
 }
 
+/// Kills the process group of a sandboxed script that is still running
+/// when the wait for it is dropped
+struct GroupKiller(Option<u32>);
+
+impl Drop for GroupKiller {
+    fn drop(&mut self) {
+        if let Some(pgid) = self.0 {
+            // SAFETY: killpg only sends a signal
+            unsafe {
+                libc::killpg(pgid as i32, libc::SIGKILL);
+            }
+        }
+    }
+}
+
 /// Sandbox for executing tools in isolation
 pub struct Sandbox {
     limits: ResourceLimits,
@@ -116,10 +479,9 @@ impl Sandbox {
         input: &[u8],
     ) -> Result<(Vec<u8>, i32)> {
         use tokio::io::AsyncWriteExt;
-        use tokio::process::Command;
 
         // Build a restricted environment
-        let mut cmd = Command::new(command);
+        let mut cmd = tokio::process::Command::from(self::command(command));
         cmd.args(args);
 
         // Clear environment and set minimal vars
@@ -128,52 +490,19 @@ impl Sandbox {
         cmd.env("HOME", "/tmp/aios-sandbox");
         cmd.env("LANG", "C.UTF-8");
 
-        // Disable network if required
-        if !self.limits.allow_network {
-            cmd.env("AIOS_SANDBOX_NO_NETWORK", "1");
-        }
-
         cmd.stdin(std::process::Stdio::piped());
         cmd.stdout(std::process::Stdio::piped());
         cmd.stderr(std::process::Stdio::piped());
+        // Dropping the wait (timeout, cancellation) kills the script and
+        // the processes it started
+        cmd.process_group(0);
+        cmd.kill_on_drop(true);
 
-        // Apply Linux-specific resource limits via pre_exec
-        #[cfg(target_os = "linux")]
-        {
-            use std::os::unix::process::CommandExt;
-            let max_mem = self.limits.max_memory_bytes;
-            let max_fds = self.limits.max_file_descriptors;
-            let max_procs = self.limits.max_processes;
-
-            unsafe {
-                cmd.pre_exec(move || {
-                    // Set memory limit via rlimit
-                    let mem_limit = libc::rlimit {
-                        rlim_cur: max_mem,
-                        rlim_max: max_mem,
-                    };
-                    libc::setrlimit(libc::RLIMIT_AS, &mem_limit);
-
-                    // Set file descriptor limit
-                    let fd_limit = libc::rlimit {
-                        rlim_cur: max_fds as u64,
-                        rlim_max: max_fds as u64,
-                    };
-                    libc::setrlimit(libc::RLIMIT_NOFILE, &fd_limit);
-
-                    // Set process limit
-                    let proc_limit = libc::rlimit {
-                        rlim_cur: max_procs as u64,
-                        rlim_max: max_procs as u64,
-                    };
-                    libc::setrlimit(libc::RLIMIT_NPROC, &proc_limit);
-
-                    Ok(())
-                });
-            }
-        }
+        // Resource limits and network isolation
+        apply_limits(cmd.as_std_mut(), &self.limits);
 
         let mut child = cmd.spawn().context("Failed to spawn sandboxed process")?;
+        let mut group = GroupKiller(child.id());
 
         // Write input if provided
         if !input.is_empty() {
@@ -193,6 +522,7 @@ impl Sandbox {
                 anyhow::anyhow!("Execution timed out after {:?}", self.limits.max_cpu_time)
             })?
             .context("Failed to wait for sandboxed process")?;
+        group.0 = None;
 
         let exit_code = result.status.code().unwrap_or(-1);
         let mut output = result.stdout;
@@ -243,6 +573,177 @@ mod tests {
         assert!(!Sandbox::should_sandbox("monitor.cpu"));
     }
 
+    #[test]
+    fn test_builtin_profiles_resolve_by_namespace() {
+        let profiles = SandboxProfiles::builtin();
+        let tool =
+            crate::registry::make_tool("hash.file", "hash", "", vec![], "low", true, false, 5000);
+        let (name, limits) = profiles.resolve(&tool).unwrap();
+        assert_eq!(name, "read-only-fs");
+        assert!(limits.writable_paths.is_empty());
+
+        let mut tool =
+            crate::registry::make_tool("fs.write", "fs", "", vec![], "medium", false, true, 5000);
+        assert!(profiles.resolve(&tool).is_none());
+        tool.sandbox_profile = "net-isolated".into();
+        assert!(!profiles.resolve(&tool).unwrap().1.allow_network);
+        tool.sandbox_profile = "missing".into();
+        assert!(profiles.resolve(&tool).is_none());
+    }
+
+    #[test]
+    fn test_profiles_from_toml() {
+        let profiles = SandboxProfiles::from_toml(
+            r#"
+            [profiles.build-heavy]
+            max_memory_mb = 1024
+
+            [profiles.scratch]
+            allow_network = false
+            writable_paths = ["/var/tmp"]
+
+            [namespaces]
+            process = "scratch"
+            self = ""
+            "#,
+        )
+        .unwrap();
+        let heavy = profiles.get("build-heavy").unwrap();
+        assert_eq!(heavy.max_memory_bytes, 1024 * 1024 * 1024);
+        assert_eq!(heavy.max_processes, 512);
+
+        let tool = crate::registry::make_tool(
+            "process.spawn",
+            "process",
+            "",
+            vec![],
+            "high",
+            false,
+            false,
+            5000,
+        );
+        let (name, limits) = profiles.resolve(&tool).unwrap();
+        assert_eq!(name, "scratch");
+        assert_eq!(limits.max_cpu_time, Duration::from_secs(30));
+
+        let tool = crate::registry::make_tool(
+            "self.rebuild",
+            "self",
+            "",
+            vec![],
+            "critical",
+            false,
+            false,
+            5000,
+        );
+        assert!(profiles.resolve(&tool).is_none());
+
+        assert!(SandboxProfiles::from_toml("[namespaces]\nfs = \"nope\"").is_err());
+    }
+
+    #[test]
+    fn test_check_request() {
+        let profiles = SandboxProfiles::builtin();
+        let caps = |names: &[&str]| names.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+
+        let isolated = profiles.get("net-isolated").unwrap();
+        assert!(isolated.check_request(&caps(&["net_read"]), b"{}").is_err());
+        assert!(isolated
+            .check_request(&caps(&["fs_write"]), br#"{"path": "/etc/x"}"#)
+            .is_ok());
+
+        let read_only = profiles.get("read-only-fs").unwrap();
+        assert!(read_only
+            .check_request(&caps(&["fs_read"]), br#"{"path": "/etc/x"}"#)
+            .is_ok());
+        assert!(read_only
+            .check_request(&caps(&["fs_write"]), b"{}")
+            .is_err());
+
+        let plugin = profiles.get("plugin").unwrap();
+        assert!(plugin
+            .check_request(&caps(&["fs_write"]), br#"{"destination": "/tmp/out"}"#)
+            .is_ok());
+        assert!(plugin
+            .check_request(
+                &caps(&["fs_write", "fs_delete"]),
+                br#"{"source": "/etc/passwd", "destination": "/tmp/out"}"#
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_allows_write() {
+        let limits = ResourceLimits {
+            writable_paths: vec!["/tmp".into()],
+            ..Default::default()
+        };
+        assert!(limits.allows_write("/tmp/a/b"));
+        assert!(!limits.allows_write("/tmpfile"));
+        assert!(!limits.allows_write("/tmp/../etc/passwd"));
+        assert!(!limits.allows_write("/etc/passwd"));
+        let everywhere = ResourceLimits {
+            writable_paths: vec!["/".into()],
+            ..Default::default()
+        };
+        assert!(everywhere.allows_write("/etc/passwd"));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_command_uses_active_limits() {
+        let limits = ResourceLimits {
+            max_file_descriptors: 37,
+            allow_network: true,
+            ..Default::default()
+        };
+        let output = with_limits(Some(&limits), || {
+            command("sh").args(["-c", "ulimit -n"]).output().unwrap()
+        });
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "37");
+
+        // Limits do not leak past the scope
+        let output = command("sh").args(["-c", "ulimit -n"]).output().unwrap();
+        assert_ne!(String::from_utf8_lossy(&output.stdout).trim(), "37");
+    }
+
+    /// Children started any other way would escape the profile's limits,
+    /// the capability grant and the execution's process-group tracking
+    #[test]
+    fn test_children_are_only_built_with_command() {
+        fn sources(dir: &std::path::Path, found: &mut Vec<std::path::PathBuf>) {
+            for entry in std::fs::read_dir(dir).unwrap().flatten() {
+                let path = entry.path();
+                if path.is_dir() {
+                    sources(&path, found);
+                } else if path.extension().is_some_and(|e| e == "rs") {
+                    found.push(path);
+                }
+            }
+        }
+        let src = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        let mut files = Vec::new();
+        sources(&src, &mut files);
+
+        let offenders: Vec<String> = files
+            .iter()
+            .filter(|path| !path.ends_with("src/sandbox.rs"))
+            .flat_map(|path| {
+                let contents = std::fs::read_to_string(path).unwrap();
+                contents
+                    .lines()
+                    .enumerate()
+                    .filter(|(_, line)| line.contains("Command::new("))
+                    .map(|(n, _)| format!("{}:{}", path.display(), n + 1))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        assert!(
+            offenders.is_empty(),
+            "use sandbox::command instead of Command::new: {offenders:?}"
+        );
+    }
+
     #[tokio::test]
     async fn test_sandbox_execute_echo() {
         let sandbox = Sandbox::new(ResourceLimits::default());
@@ -4,14 +4,13 @@
 
 use anyhow::{bail, Result};
 
-/// Validate a JSON input against a schema
+/// Validate a JSON input against a schema; empty input is an empty object
 pub fn validate_input(input: &[u8], schema_bytes: &[u8]) -> Result<()> {
     if schema_bytes.is_empty() {
         return Ok(()); // No schema = no validation
     }
 
-    let input_value: serde_json::Value =
-        serde_json::from_slice(input).map_err(|e| anyhow::anyhow!("Invalid JSON input: {e}"))?;
+    let input_value = parse_input(input)?;
     let schema_value: serde_json::Value = serde_json::from_slice(schema_bytes)
         .map_err(|e| anyhow::anyhow!("Invalid JSON schema: {e}"))?;
 
@@ -20,7 +19,11 @@ pub fn validate_input(input: &[u8], schema_bytes: &[u8]) -> Result<()> {
         .map_err(|e| anyhow::anyhow!("Invalid JSON schema: {e}"))?;
 
     if let Err(error) = validator.validate(&input_value) {
-        bail!("Input validation failed: {}", error);
+        let at = error.instance_path.to_string();
+        if at.is_empty() {
+            bail!("Input validation failed: {error}");
+        }
+        bail!("Input validation failed at {at}: {error}");
     }
 
     Ok(())
@@ -38,3 +41,58 @@ pub fn parse_input(input: &[u8]) -> Result<serde_json::Value> {
 pub fn serialize_output(output: &serde_json::Value) -> Result<Vec<u8>> {
     serde_json::to_vec(output).map_err(|e| anyhow::anyhow!("Failed to serialize output: {e}"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn builtin_tools() -> crate::registry::Registry {
+        let mut reg = crate::registry::Registry::new();
+        crate::register_builtin_tools(&mut reg);
+        reg
+    }
+
+    #[test]
+    fn test_every_builtin_tool_declares_a_valid_schema() {
+        for tool in builtin_tools().list_tools("") {
+            assert!(
+                !tool.input_schema.is_empty(),
+                "{} declares no input schema",
+                tool.name
+            );
+            let schema: serde_json::Value = serde_json::from_slice(&tool.input_schema).unwrap();
+            assert_eq!(schema["type"], "object", "{}", tool.name);
+            assert!(
+                jsonschema::validator_for(&schema).is_ok(),
+                "{} declares an invalid schema",
+                tool.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_input_against_builtin_schema() {
+        let read = builtin_tools().get_tool("fs.read").unwrap();
+        let schema = &read.input_schema;
+
+        assert!(validate_input(br#"{"path": "/etc/hostname"}"#, schema).is_ok());
+        assert!(validate_input(br#"{"path": "/etc/hostname", "length": 64}"#, schema).is_ok());
+
+        let missing = validate_input(b"{}", schema).unwrap_err().to_string();
+        assert!(missing.contains("path"), "{missing}");
+        let wrong = validate_input(br#"{"path": 42}"#, schema)
+            .unwrap_err()
+            .to_string();
+        assert!(wrong.contains("/path"), "{wrong}");
+        let encoding = br#"{"path": "/etc/hostname", "encoding": "latin1"}"#;
+        assert!(validate_input(encoding, schema).is_err());
+        assert!(validate_input(b"not json", schema).is_err());
+    }
+
+    #[test]
+    fn test_empty_input_is_an_empty_object() {
+        let schema = br#"{"type": "object", "properties": {}}"#;
+        assert!(validate_input(b"", schema).is_ok());
+        assert!(validate_input(b"", b"").is_ok());
+    }
+}
@@ -0,0 +1,100 @@
+//! sec.audit_detail — Full audit record and environment snapshot for one execution
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+#[derive(Deserialize)]
+struct Input {
+    execution_id: String,
+}
+
+pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
+    let input: Input = serde_json::from_slice(input).context("Invalid JSON input")?;
+
+    let db_path = "/var/lib/aios/ledger/audit.db";
+    let conn = rusqlite::Connection::open(db_path)
+        .with_context(|| format!("Failed to open audit database at {}", db_path))?;
+
+    let records = lookup(&conn, &input.execution_id)?;
+    if records.is_empty() {
+        anyhow::bail!("No audit record for execution {}", input.execution_id);
+    }
+
+    let result = json!({
+        "execution_id": input.execution_id,
+        "records": records,
+    });
+    serde_json::to_vec(&result).context("Failed to serialize output")
+}
+
+/// Every audit record for an execution, oldest first, with parsed snapshots
+fn lookup(conn: &rusqlite::Connection, execution_id: &str) -> Result<Vec<Value>> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT tool_name, agent_id, task_id, reason, success, duration_ms, timestamp, hash, snapshot
+             FROM audit_log
+             WHERE execution_id = ?1
+             ORDER BY id ASC",
+        )
+        .context("Failed to prepare query")?;
+
+    let rows = stmt
+        .query_map([execution_id], |row| {
+            let snapshot: Option<String> = row.get(8)?;
+            Ok(json!({
+                "tool": row.get::<_, String>(0)?,
+                "agent": row.get::<_, String>(1)?,
+                "task_id": row.get::<_, String>(2)?,
+                "reason": row.get::<_, String>(3)?,
+                "success": row.get::<_, i32>(4)? != 0,
+                "duration_ms": row.get::<_, i64>(5)?,
+                "timestamp": row.get::<_, String>(6)?,
+                "hash": row.get::<_, String>(7)?,
+                "snapshot": snapshot
+                    .and_then(|s| serde_json::from_str::<Value>(&s).ok())
+                    .unwrap_or(Value::Null),
+            }))
+        })
+        .context("Failed to execute query")?;
+
+    let mut records = Vec::new();
+    for row in rows {
+        records.push(row.context("Failed to read audit row")?);
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::{AuditLog, ExecutionSnapshot};
+
+    #[test]
+    fn test_lookup_returns_snapshot() {
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let path = tmp.path().to_str().unwrap();
+        let mut log = AuditLog::new(path).unwrap();
+        log.attach_snapshot("exec-1", &ExecutionSnapshot::capture(b"{}", None));
+        log.record(
+            "exec-1",
+            "pkg.install",
+            "agent-1",
+            "task-1",
+            "install",
+            true,
+            900,
+        );
+        log.record("exec-2", "fs.read", "agent-1", "task-1", "read", true, 5);
+
+        let conn = rusqlite::Connection::open(path).unwrap();
+        let records = lookup(&conn, "exec-1").unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0]["tool"], "pkg.install");
+        assert_eq!(records[0]["snapshot"]["pid"], std::process::id());
+
+        let records = lookup(&conn, "exec-2").unwrap();
+        assert!(records[0]["snapshot"].is_null());
+        assert!(lookup(&conn, "missing").unwrap().is_empty());
+    }
+}
@@ -22,6 +22,7 @@ struct Output {
 
 #[derive(Serialize)]
 struct AuditEntry {
+    execution_id: String,
     tool: String,
     agent: String,
     success: bool,
@@ -53,7 +54,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
         // Query all entries
         let mut stmt = conn
             .prepare(
-                "SELECT tool_name, agent_id, success, timestamp
+                "SELECT execution_id, tool_name, agent_id, success, timestamp
                  FROM audit_log
                  ORDER BY id DESC
                  LIMIT ?1",
@@ -63,10 +64,11 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
         let rows = stmt
             .query_map(rusqlite::params![limit], |row| {
                 Ok(AuditEntry {
-                    tool: row.get::<_, String>(0)?,
-                    agent: row.get::<_, String>(1)?,
-                    success: row.get::<_, i32>(2)? != 0,
-                    timestamp: row.get::<_, String>(3)?,
+                    execution_id: row.get::<_, String>(0)?,
+                    tool: row.get::<_, String>(1)?,
+                    agent: row.get::<_, String>(2)?,
+                    success: row.get::<_, i32>(3)? != 0,
+                    timestamp: row.get::<_, String>(4)?,
                 })
             })
             .context("Failed to execute query")?;
@@ -80,7 +82,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
         // Query filtered by tool name
         let mut stmt = conn
             .prepare(
-                "SELECT tool_name, agent_id, success, timestamp
+                "SELECT execution_id, tool_name, agent_id, success, timestamp
                  FROM audit_log
                  WHERE tool_name = ?1
                  ORDER BY id DESC
@@ -91,10 +93,11 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
         let rows = stmt
             .query_map(rusqlite::params![input.tool_name, limit], |row| {
                 Ok(AuditEntry {
-                    tool: row.get::<_, String>(0)?,
-                    agent: row.get::<_, String>(1)?,
-                    success: row.get::<_, i32>(2)? != 0,
-                    timestamp: row.get::<_, String>(3)?,
+                    execution_id: row.get::<_, String>(0)?,
+                    tool: row.get::<_, String>(1)?,
+                    agent: row.get::<_, String>(2)?,
+                    success: row.get::<_, i32>(3)? != 0,
+                    timestamp: row.get::<_, String>(4)?,
                 })
             })
             .context("Failed to execute filtered query")?;
@@ -0,0 +1,42 @@
+//! sec.canary_check — Report canaries that were accessed, modified, or deleted
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::json;
+
+use super::canary::{self, CanaryStore};
+
+#[derive(Deserialize, Default)]
+struct Input {
+    /// Mark the reported trips as handled so they are not reported again
+    #[serde(default)]
+    acknowledge: bool,
+}
+
+pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
+    let input: Input = if input.is_empty() {
+        Input::default()
+    } else {
+        serde_json::from_slice(input).context("Invalid sec.canary_check input")?
+    };
+
+    let store = CanaryStore::open_default()?;
+    let checked = store.list()?.len();
+    canary::sweep(&store)?;
+
+    let trips = store.pending_trips()?;
+    if input.acknowledge {
+        if let Some(last) = trips.last() {
+            store.acknowledge(last.trip_id)?;
+        }
+    }
+
+    let result = json!({
+        "checked": checked,
+        "tripped": !trips.is_empty(),
+        "trips": trips,
+        "acknowledged": input.acknowledge && !trips.is_empty(),
+        "watcher_active": canary::watcher_active(),
+    });
+    serde_json::to_vec(&result).context("Failed to serialize output")
+}
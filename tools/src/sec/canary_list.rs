@@ -0,0 +1,35 @@
+//! sec.canary_list — List planted canaries and how often each has tripped
+
+use anyhow::{Context, Result};
+use serde_json::json;
+
+use super::canary::{self, CanaryStore};
+
+pub fn execute(_input: &[u8]) -> Result<Vec<u8>> {
+    let store = CanaryStore::open_default()?;
+    let counts = store.trip_counts()?;
+
+    let canaries: Vec<serde_json::Value> = store
+        .list()?
+        .into_iter()
+        .map(|c| {
+            let present = std::path::Path::new(&c.path).exists();
+            json!({
+                "id": c.id,
+                "kind": c.kind,
+                "path": c.path,
+                "description": c.description,
+                "created_at": c.created_at,
+                "present": present,
+                "trips": counts.get(&c.id).copied().unwrap_or(0),
+            })
+        })
+        .collect();
+
+    let result = json!({
+        "count": canaries.len(),
+        "canaries": canaries,
+        "watcher_active": canary::watcher_active(),
+    });
+    serde_json::to_vec(&result).context("Failed to serialize output")
+}
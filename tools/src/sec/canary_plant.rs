@@ -0,0 +1,42 @@
+//! sec.canary_plant — Plant a decoy file or fake credential as an intrusion tripwire
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::json;
+
+use super::canary::{self, CanaryStore};
+
+#[derive(Deserialize)]
+struct Input {
+    path: String,
+    #[serde(default = "default_kind")]
+    kind: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    content: Option<String>,
+}
+
+fn default_kind() -> String {
+    "file".into()
+}
+
+pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
+    let input: Input = serde_json::from_slice(input).context("Invalid sec.canary_plant input")?;
+
+    let store = CanaryStore::open_default()?;
+    let canary = canary::plant(
+        &store,
+        &input.kind,
+        &input.path,
+        &input.description,
+        input.content.as_deref(),
+    )?;
+
+    let result = json!({
+        "planted": true,
+        "canary": canary,
+        "watched": canary::watcher_active(),
+    });
+    serde_json::to_vec(&result).context("Failed to serialize output")
+}
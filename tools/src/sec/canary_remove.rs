@@ -0,0 +1,30 @@
+//! sec.canary_remove — Remove a planted canary and its decoy file
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::json;
+
+use super::canary::{self, CanaryStore};
+
+#[derive(Deserialize)]
+struct Input {
+    /// Canary id or planted path
+    canary: String,
+}
+
+pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
+    let input: Input = serde_json::from_slice(input).context("Invalid sec.canary_remove input")?;
+
+    let store = CanaryStore::open_default()?;
+    let Some(found) = store.get(&input.canary)? else {
+        anyhow::bail!("No canary matching '{}'", input.canary);
+    };
+    let file_removed = canary::remove(&store, &found)?;
+
+    let result = json!({
+        "removed": found.id,
+        "path": found.path,
+        "file_removed": file_removed,
+    });
+    serde_json::to_vec(&result).context("Failed to serialize output")
+}
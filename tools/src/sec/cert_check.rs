@@ -0,0 +1,84 @@
+//! sec.cert_check — Report whether a certificate file is present and when it expires
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::sandbox::command;
+
+#[derive(Deserialize)]
+struct Input {
+    path: String,
+}
+
+#[derive(Serialize)]
+struct Output {
+    path: String,
+    exists: bool,
+    /// Expiry as a unix timestamp, when the certificate could be read
+    not_after: Option<i64>,
+    /// Whole days until expiry, negative once expired
+    expires_in_days: Option<i64>,
+    valid: bool,
+}
+
+pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
+    let input: Input = serde_json::from_slice(input).context("Invalid sec.cert_check input")?;
+
+    let exists = std::path::Path::new(&input.path).is_file();
+    let not_after = if exists {
+        let output = command("openssl")
+            .args(["x509", "-noout", "-enddate", "-in", &input.path])
+            .output()
+            .context("Failed to execute openssl x509")?;
+        parse_enddate(&String::from_utf8_lossy(&output.stdout))
+    } else {
+        None
+    };
+
+    let now = chrono::Utc::now().timestamp();
+    let result = Output {
+        path: input.path,
+        exists,
+        not_after,
+        expires_in_days: not_after.map(|t| (t - now).div_euclid(86400)),
+        valid: not_after.is_some_and(|t| t > now),
+    };
+    serde_json::to_vec(&result).context("Failed to serialize output")
+}
+
+/// Expiry from `openssl x509 -enddate` output (`notAfter=Jan  1 00:00:00 2027 GMT`)
+fn parse_enddate(stdout: &str) -> Option<i64> {
+    let date = stdout.trim().strip_prefix("notAfter=")?;
+    let date = date.strip_suffix(" GMT").unwrap_or(date);
+    let date = date.split_whitespace().collect::<Vec<_>>().join(" ");
+    chrono::NaiveDateTime::parse_from_str(&date, "%b %d %H:%M:%S %Y")
+        .ok()
+        .map(|d| d.and_utc().timestamp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_enddate() {
+        assert_eq!(
+            parse_enddate("notAfter=Jan  1 00:00:00 2027 GMT\n"),
+            Some(1798761600)
+        );
+        assert_eq!(
+            parse_enddate("notAfter=Dec 31 23:59:59 2026 GMT"),
+            Some(1798761599)
+        );
+        assert_eq!(parse_enddate("unable to load certificate"), None);
+    }
+
+    #[test]
+    fn test_missing_certificate_is_invalid() {
+        let out = execute(br#"{"path":"/nonexistent/server.crt"}"#).unwrap();
+        let out: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(out["exists"], false);
+        assert_eq!(out["valid"], false);
+        assert!(out["expires_in_days"].is_null());
+    }
+}
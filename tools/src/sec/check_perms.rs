@@ -52,7 +52,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
 
 fn get_username(uid: u32) -> String {
     // Use the id command to resolve UID to name
-    let output = std::process::Command::new("id")
+    let output = crate::sandbox::command("id")
         .args(["-un", &uid.to_string()])
         .output();
 
@@ -66,7 +66,7 @@ fn get_groupname(gid: u32) -> String {
     // Use the id command to resolve GID to name
     // On macOS, use dscl or a stat-based approach
     let output = if cfg!(target_os = "macos") {
-        std::process::Command::new("dscl")
+        crate::sandbox::command("dscl")
             .args([
                 ".",
                 "-search",
@@ -76,7 +76,7 @@ fn get_groupname(gid: u32) -> String {
             ])
             .output()
     } else {
-        std::process::Command::new("getent")
+        crate::sandbox::command("getent")
             .args(["group", &gid.to_string()])
             .output()
     };
@@ -1,9 +1,16 @@
-//! Security tools — check_perms, audit_query, grant, revoke, audit, scan, certs, integrity, rootkits.
+//! Security tools — check_perms, audit_query, grant, revoke, audit, scan, certs, integrity, rootkits, canaries.
 //!
 //! Each submodule exposes `pub fn execute(input: &[u8]) -> Result<Vec<u8>>`.
 
 pub mod audit;
+pub mod audit_detail;
 pub mod audit_query;
+pub mod canary;
+pub mod canary_check;
+pub mod canary_list;
+pub mod canary_plant;
+pub mod canary_remove;
+pub mod cert_check;
 pub mod cert_generate;
 pub mod cert_rotate;
 pub mod check_perms;
@@ -13,117 +20,361 @@ pub mod revoke;
 pub mod scan;
 pub mod scan_rootkits;
 
-use crate::registry::{make_tool, Registry};
+use serde_json::json;
+
+use crate::registry::{critical, make_tool, with_input_schema, Registry};
 
 /// Register every security tool with the registry.
 pub fn register_tools(reg: &mut Registry) {
-    reg.register_tool(make_tool(
-        "sec.check_perms",
-        "sec",
-        "Check file permissions and ownership, reporting owner, group, mode, and world-writability",
-        vec!["sec.read"],
-        "low",
-        true,
-        false,
-        5000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "sec.check_perms",
+            "sec",
+            "Check file permissions and ownership, reporting owner, group, mode, and world-writability",
+            vec!["sec.read"],
+            "low",
+            true,
+            false,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": { "path": { "type": "string" } },
+            "required": ["path"]
+        }),
+    ));
+
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "sec.audit_query",
+            "sec",
+            "Query the audit log for recent tool executions, optionally filtered by tool name",
+            vec!["sec.audit"],
+            "low",
+            true,
+            false,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "tool_name": { "type": "string" },
+                "limit": { "type": "integer", "minimum": 0, "default": 50 }
+            }
+        }),
+    ));
+
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "sec.audit_detail",
+            "sec",
+            "Get the full audit record for an execution, including the environment snapshot (binary path and hash, uid, cwd, env, cgroup) captured for risky tools",
+            vec!["sec.audit"],
+            "low",
+            true,
+            false,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": { "execution_id": { "type": "string" } },
+            "required": ["execution_id"]
+        }),
+    ));
+
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "sec.grant",
+            "sec",
+            "Grant capabilities to an agent with expiration time",
+            vec!["sec.admin"],
+            "high",
+            false,
+            true,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "agent_id": { "type": "string" },
+                "capabilities": { "type": "array", "items": { "type": "string" } },
+                "reason": { "type": "string" },
+                "duration_hours": { "type": "integer", "minimum": 1, "default": 24 }
+            },
+            "required": ["agent_id", "capabilities"]
+        }),
+    ));
+
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "sec.revoke",
+            "sec",
+            "Revoke capabilities from an agent",
+            vec!["sec.admin"],
+            "high",
+            false,
+            true,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "agent_id": { "type": "string" },
+                "capabilities": { "type": "array", "items": { "type": "string" } },
+                "revoke_all": { "type": "boolean" }
+            },
+            "required": ["agent_id"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "sec.audit_query",
-        "sec",
-        "Query the audit log for recent tool executions, optionally filtered by tool name",
-        vec!["sec.audit"],
-        "low",
-        true,
-        false,
-        5000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "sec.audit",
+            "sec",
+            "Query the audit ledger with time, agent, and tool filters",
+            vec!["sec.audit"],
+            "low",
+            true,
+            false,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "agent_id": { "type": "string" },
+                "tool_name": { "type": "string" },
+                "since": { "type": "string", "description": "RFC 3339 timestamp" },
+                "until": { "type": "string", "description": "RFC 3339 timestamp" },
+                "limit": { "type": "integer", "minimum": 0, "default": 100 }
+            }
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "sec.grant",
-        "sec",
-        "Grant capabilities to an agent with expiration time",
-        vec!["sec.admin"],
-        "high",
-        false,
-        true,
-        5000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "sec.scan",
+            "sec",
+            "Security scan: check open ports, world-writable files, SUID binaries, and weak permissions",
+            vec!["sec.read"],
+            "medium",
+            true,
+            false,
+            30000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "checks": {
+                    "type": "array",
+                    "items": {
+                        "type": "string",
+                        "enum": ["open_ports", "world_writable", "suid_binaries", "weak_perms"]
+                    },
+                    "description": "Defaults to all"
+                }
+            }
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "sec.revoke",
-        "sec",
-        "Revoke capabilities from an agent",
-        vec!["sec.admin"],
-        "high",
-        false,
-        true,
-        5000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "sec.cert_check",
+            "sec",
+            "Check a certificate file: whether it exists, when it expires, and whether it is still valid",
+            vec!["sec.read"],
+            "low",
+            true,
+            false,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": { "path": { "type": "string", "description": "PEM certificate" } },
+            "required": ["path"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "sec.audit",
-        "sec",
-        "Query the audit ledger with time, agent, and tool filters",
-        vec!["sec.audit"],
-        "low",
-        true,
-        false,
-        5000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "sec.cert_generate",
+            "sec",
+            "Generate X.509 certificates (CA + server) using rcgen",
+            vec!["sec.admin", "fs_write"],
+            "high",
+            false,
+            true,
+            10000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "service_name": { "type": "string" },
+                "cert_dir": { "type": "string", "default": "/var/lib/aios/certs" },
+                "validity_years": { "type": "integer", "minimum": 1, "default": 2 }
+            },
+            "required": ["service_name"]
+        }),
+    ));
+
+    reg.register_tool(critical(with_input_schema(
+        make_tool(
+            "sec.cert_rotate",
+            "sec",
+            "Rotate TLS certificates: backup old, generate new, restart services",
+            vec!["sec.admin", "fs_write", "service_manage"],
+            "high",
+            false,
+            true,
+            30000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "service_name": { "type": "string" },
+                "cert_dir": { "type": "string", "default": "/var/lib/aios/certs" }
+            },
+            "required": ["service_name"]
+        }),
+    )));
+
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "sec.file_integrity",
+            "sec",
+            "SHA256 checksum verification of critical files against a baseline database",
+            vec!["sec.read"],
+            "medium",
+            true,
+            false,
+            30000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "mode": { "type": "string", "enum": ["baseline", "check"], "default": "check" },
+                "paths": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Defaults to the aiOS configuration and account files"
+                }
+            }
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "sec.scan",
-        "sec",
-        "Security scan: check open ports, world-writable files, SUID binaries, and weak permissions",
-        vec!["sec.read"],
-        "medium",
-        true,
-        false,
-        30000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "sec.scan_rootkits",
+            "sec",
+            "Scan for hidden processes, suspicious kernel modules, and scripts in /dev/shm and /tmp",
+            vec!["sec.read"],
+            "medium",
+            true,
+            false,
+            30000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "checks": {
+                    "type": "array",
+                    "items": {
+                        "type": "string",
+                        "enum": [
+                            "hidden_processes",
+                            "suspicious_modules",
+                            "dev_shm_scripts",
+                            "proc_anomalies"
+                        ]
+                    },
+                    "description": "Defaults to all"
+                }
+            }
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "sec.cert_generate",
-        "sec",
-        "Generate X.509 certificates (CA + server) using rcgen",
-        vec!["sec.admin", "fs_write"],
-        "high",
-        false,
-        true,
-        10000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "sec.canary_plant",
+            "sec",
+            "Plant a canary (decoy file or fake AWS/SSH/env/password credentials) that trips an alert when anything opens, reads, modifies, or deletes it",
+            vec!["sec.admin", "fs_write"],
+            "medium",
+            false,
+            true,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "path": { "type": "string" },
+                "kind": {
+                    "type": "string",
+                    "enum": ["file", "aws_credentials", "ssh_key", "env_file", "password_file"],
+                    "default": "file"
+                },
+                "description": { "type": "string" },
+                "content": {
+                    "type": "string",
+                    "description": "Decoy content; generated for the kind when omitted"
+                }
+            },
+            "required": ["path"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "sec.cert_rotate",
-        "sec",
-        "Rotate TLS certificates: backup old, generate new, restart services",
-        vec!["sec.admin", "fs_write", "service_manage"],
-        "high",
-        false,
-        true,
-        30000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "sec.canary_list",
+            "sec",
+            "List planted canaries with their paths, kinds, and trip counts",
+            vec!["sec.read"],
+            "low",
+            true,
+            false,
+            5000,
+        ),
+        json!({ "type": "object" }),
     ));
 
-    reg.register_tool(make_tool(
-        "sec.file_integrity",
-        "sec",
-        "SHA256 checksum verification of critical files against a baseline database",
-        vec!["sec.read"],
-        "medium",
-        true,
-        false,
-        30000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "sec.canary_remove",
+            "sec",
+            "Remove a planted canary by id or path, deleting its decoy file",
+            vec!["sec.admin", "fs_write"],
+            "medium",
+            false,
+            false,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "canary": { "type": "string", "description": "Canary id or planted path" }
+            },
+            "required": ["canary"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "sec.scan_rootkits",
-        "sec",
-        "Scan for hidden processes, suspicious kernel modules, and scripts in /dev/shm and /tmp",
-        vec!["sec.read"],
-        "medium",
-        true,
-        false,
-        30000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "sec.canary_check",
+            "sec",
+            "Check canaries for accesses, modifications, or deletions and return unacknowledged trips",
+            vec!["sec.read"],
+            "low",
+            false,
+            false,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "acknowledge": {
+                    "type": "boolean",
+                    "description": "Mark the reported trips as handled"
+                }
+            }
+        }),
     ));
 }
@@ -2,7 +2,8 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+
+use crate::sandbox::command;
 
 #[derive(Deserialize)]
 struct ScanInput {
@@ -82,7 +83,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
 }
 
 fn scan_open_ports() -> ScanFinding {
-    let details = Command::new("ss")
+    let details = command("ss")
         .args(["-tlnp"])
         .output()
         .ok()
@@ -105,7 +106,7 @@ fn scan_open_ports() -> ScanFinding {
 }
 
 fn scan_world_writable() -> ScanFinding {
-    let details = Command::new("find")
+    let details = command("find")
         .args([
             "/etc",
             "/var",
@@ -137,7 +138,7 @@ fn scan_world_writable() -> ScanFinding {
 }
 
 fn scan_suid_binaries() -> ScanFinding {
-    let details = Command::new("find")
+    let details = command("find")
         .args(["/usr", "/bin", "/sbin", "-perm", "-4000", "-type", "f"])
         .output()
         .ok()
@@ -2,7 +2,8 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+
+use crate::sandbox::command;
 
 #[derive(Deserialize)]
 struct RootkitScanInput {
@@ -61,7 +62,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
 
 fn check_hidden_processes() -> RootkitFinding {
     // Compare ps output with /proc entries
-    let ps_pids: Vec<u32> = Command::new("ps")
+    let ps_pids: Vec<u32> = command("ps")
         .args(["-eo", "pid"])
         .output()
         .ok()
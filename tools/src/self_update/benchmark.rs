@@ -0,0 +1,84 @@
+//! self.benchmark — Request a run of the built-in benchmark pack
+//!
+//! The benchmark pack (tool latency, inference throughput, memory search
+//! latency, end-to-end goal time) drives the other services, so it runs in
+//! the orchestrator. This handler validates the request and lets it pass
+//! through the usual permission, rate-limit, and audit pipeline; the
+//! orchestrator starts the run once the call succeeds and stores the results
+//! in working memory.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// Benchmark suites in the pack
+pub const SUITES: &[&str] = &["tool_latency", "inference", "memory_search", "goal_e2e"];
+
+/// What started a run
+const TRIGGERS: &[&str] = &["manual", "nightly", "post_update"];
+
+/// Most samples a suite may take per run
+const MAX_SAMPLES: u32 = 50;
+
+#[derive(Deserialize)]
+struct BenchmarkInput {
+    /// Suites to run (default: all)
+    #[serde(default)]
+    suites: Vec<String>,
+    #[serde(default = "default_trigger")]
+    trigger: String,
+    /// Samples per latency suite
+    #[serde(default = "default_samples")]
+    samples: u32,
+}
+
+fn default_trigger() -> String {
+    "manual".to_string()
+}
+
+fn default_samples() -> u32 {
+    10
+}
+
+#[derive(Serialize)]
+struct BenchmarkOutput {
+    accepted: bool,
+    suites: Vec<String>,
+    trigger: String,
+    samples: u32,
+}
+
+pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
+    let input: BenchmarkInput = serde_json::from_slice(input).context("Invalid JSON input")?;
+
+    let suites = if input.suites.is_empty() {
+        SUITES.iter().map(|s| s.to_string()).collect()
+    } else {
+        for suite in &input.suites {
+            if !SUITES.contains(&suite.as_str()) {
+                anyhow::bail!(
+                    "Unknown benchmark suite '{suite}' (expected one of: {})",
+                    SUITES.join(", ")
+                );
+            }
+        }
+        input.suites
+    };
+    if !TRIGGERS.contains(&input.trigger.as_str()) {
+        anyhow::bail!(
+            "Unknown trigger '{}' (expected one of: {})",
+            input.trigger,
+            TRIGGERS.join(", ")
+        );
+    }
+    if input.samples == 0 || input.samples > MAX_SAMPLES {
+        anyhow::bail!("samples must be between 1 and {MAX_SAMPLES}");
+    }
+
+    let result = BenchmarkOutput {
+        accepted: true,
+        suites,
+        trigger: input.trigger,
+        samples: input.samples,
+    };
+    serde_json::to_vec(&result).context("Failed to serialize output")
+}
@@ -3,7 +3,8 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+
+use crate::sandbox::command;
 
 // ── self.inspect ──────────────────────────────────────────────────
 
@@ -46,14 +47,14 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
     let input: InspectInput = serde_json::from_slice(input).context("Invalid JSON input")?;
 
     // Get git revision
-    let git_rev = Command::new("git")
+    let git_rev = command("git")
         .args(["rev-parse", "--short", "HEAD"])
         .current_dir(&input.source_path)
         .output()
         .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
         .unwrap_or_else(|_| "unknown".to_string());
 
-    let git_branch = Command::new("git")
+    let git_branch = command("git")
         .args(["branch", "--show-current"])
         .current_dir(&input.source_path)
         .output()
@@ -167,7 +168,7 @@ pub fn execute_health(input: &[u8]) -> Result<Vec<u8>> {
 
     // Check disk space
     let (disk_ok, disk_usage_percent) = if input.check_disk {
-        let output = Command::new("df").args(["-h", "/"]).output().ok();
+        let output = command("df").args(["-h", "/"]).output().ok();
 
         if let Some(output) = output {
             let text = String::from_utf8_lossy(&output.stdout).to_string();
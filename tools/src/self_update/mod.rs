@@ -1,56 +1,143 @@
-//! Self-update tools — inspect, update, rebuild, and health check.
+//! Self-update tools — inspect, update, rebuild, health check, and benchmark.
 //!
 //! These tools allow aiOS to inspect and update its own source code,
-//! rebuild components, and check system health.
+//! rebuild components, check system health, and measure its own performance.
 
+pub mod benchmark;
 pub mod inspect;
 pub mod update;
 
-use crate::registry::{make_tool, Registry};
+use serde_json::json;
+
+use crate::registry::{make_tool, with_input_schema, Registry};
 
 /// Register every self-update tool with the registry.
 pub fn register_tools(reg: &mut Registry) {
-    reg.register_tool(make_tool(
-        "self.inspect",
-        "self",
-        "Inspect aiOS source code, version, capabilities, and configuration",
-        vec!["self.read"],
-        "low",
-        true,
-        false,
-        10000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "self.inspect",
+            "self",
+            "Inspect aiOS source code, version, capabilities, and configuration",
+            vec!["self.read"],
+            "low",
+            true,
+            false,
+            10000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "what": {
+                    "type": "string",
+                    "enum": ["version", "components", "config", "source"],
+                    "default": "version"
+                },
+                "source_path": { "type": "string", "default": "/opt/aios" }
+            }
+        }),
+    ));
+
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "self.health",
+            "self",
+            "Check aiOS system health: service status, resource usage, component connectivity",
+            vec!["self.read"],
+            "low",
+            true,
+            false,
+            15000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "check_services": { "type": "boolean", "default": true },
+                "check_disk": { "type": "boolean", "default": true }
+            }
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "self.health",
-        "self",
-        "Check aiOS system health: service status, resource usage, component connectivity",
-        vec!["self.read"],
-        "low",
-        true,
-        false,
-        15000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "self.benchmark",
+            "self",
+            "Run the built-in benchmark pack (tool latency, inference throughput, memory search latency, end-to-end goal time) in the background and store results in working memory",
+            vec!["self.read"],
+            "low",
+            false,
+            false,
+            10000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "suites": {
+                    "type": "array",
+                    "items": {
+                        "type": "string",
+                        "enum": ["tool_latency", "inference", "memory_search", "goal_e2e"]
+                    },
+                    "description": "Defaults to all"
+                },
+                "trigger": {
+                    "type": "string",
+                    "enum": ["manual", "nightly", "post_update"],
+                    "default": "manual"
+                },
+                "samples": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "maximum": 50,
+                    "description": "Samples per latency suite",
+                    "default": 10
+                }
+            }
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "self.update",
-        "self",
-        "Pull latest aiOS source code from the repository and apply updates",
-        vec!["self.update"],
-        "critical",
-        false,
-        false,
-        120000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "self.update",
+            "self",
+            "Pull latest aiOS source code from the repository and apply updates",
+            vec!["self.update"],
+            "critical",
+            false,
+            false,
+            120000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "source_path": { "type": "string", "default": "/opt/aios" },
+                "remote": { "type": "string", "default": "origin" },
+                "branch": { "type": "string", "default": "main" }
+            }
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "self.rebuild",
-        "self",
-        "Rebuild aiOS components from source after an update",
-        vec!["self.update"],
-        "critical",
-        false,
-        false,
-        300000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "self.rebuild",
+            "self",
+            "Rebuild aiOS components from source after an update",
+            vec!["self.update"],
+            "critical",
+            false,
+            false,
+            300000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "source_path": { "type": "string", "default": "/opt/aios" },
+                "components": {
+                    "type": "array",
+                    "items": { "type": "string", "description": "Cargo package" },
+                    "description": "Defaults to the whole workspace"
+                },
+                "release": { "type": "boolean", "default": true }
+            }
+        }),
     ));
 }
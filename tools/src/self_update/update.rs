@@ -3,7 +3,8 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+
+use crate::sandbox::command;
 
 // ── self.update ───────────────────────────────────────────────────
 
@@ -48,7 +49,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
     let prev_rev = get_rev(&input.source_path);
 
     // Pull latest
-    let pull_output = Command::new("git")
+    let pull_output = command("git")
         .args(["pull", &input.remote, &input.branch])
         .current_dir(&input.source_path)
         .output()
@@ -65,7 +66,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
     let current_rev = get_rev(&input.source_path);
 
     // Count files changed
-    let diff_output = Command::new("git")
+    let diff_output = command("git")
         .args(["diff", "--name-only", &prev_rev, &current_rev])
         .current_dir(&input.source_path)
         .output()
@@ -91,7 +92,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
 }
 
 fn get_rev(path: &str) -> String {
-    Command::new("git")
+    command("git")
         .args(["rev-parse", "--short", "HEAD"])
         .current_dir(path)
         .output()
@@ -152,7 +153,7 @@ pub fn execute_rebuild(input: &[u8]) -> Result<Vec<u8>> {
         component_args = Vec::new();
     }
 
-    let mut cmd = Command::new("cargo");
+    let mut cmd = command("cargo");
     cmd.args(&args).current_dir(&input.source_path);
 
     if !component_args.is_empty() {
@@ -2,7 +2,8 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+
+use crate::sandbox::command;
 
 #[derive(Deserialize)]
 struct Input {}
@@ -31,7 +32,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
     // On macOS, use launchctl list to enumerate services
     // Output format: PID\tStatus\tLabel
     if cfg!(target_os = "macos") {
-        let output = Command::new("launchctl")
+        let output = command("launchctl")
             .arg("list")
             .output()
             .context("Failed to execute launchctl list")?;
@@ -64,7 +65,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
         }
     } else {
         // On Linux, use systemctl
-        let output = Command::new("systemctl")
+        let output = command("systemctl")
             .args([
                 "list-units",
                 "--type=service",
@@ -111,7 +112,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
 
 #[cfg(not(target_os = "macos"))]
 fn get_systemd_pid(name: &str) -> Option<u32> {
-    let output = Command::new("systemctl")
+    let output = command("systemctl")
         .args(["show", "-p", "MainPID", &format!("{}.service", name)])
         .output()
         .ok()?;
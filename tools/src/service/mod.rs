@@ -9,62 +9,95 @@ pub mod start;
 pub mod status;
 pub mod stop;
 
-use crate::registry::{make_tool, Registry};
+use serde_json::json;
+
+use crate::registry::{make_tool, with_input_schema, Registry};
 
 /// Register every service tool with the registry.
 pub fn register_tools(reg: &mut Registry) {
-    reg.register_tool(make_tool(
-        "service.list",
-        "service",
-        "List all system services with name, status, and pid",
-        vec!["service.read"],
-        "low",
-        true,
-        false,
-        10000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "service.list",
+            "service",
+            "List all system services with name, status, and pid",
+            vec!["service.read"],
+            "low",
+            true,
+            false,
+            10000,
+        ),
+        json!({ "type": "object" }),
     ));
 
-    reg.register_tool(make_tool(
-        "service.start",
-        "service",
-        "Start a system service by name",
-        vec!["service.manage"],
-        "high",
-        false,
-        true,
-        15000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "service.start",
+            "service",
+            "Start a system service by name",
+            vec!["service.manage"],
+            "high",
+            false,
+            true,
+            15000,
+        ),
+        json!({
+            "type": "object",
+            "properties": { "name": { "type": "string", "description": "Unit name" } },
+            "required": ["name"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "service.stop",
-        "service",
-        "Stop a running system service by name",
-        vec!["service.manage"],
-        "high",
-        false,
-        true,
-        15000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "service.stop",
+            "service",
+            "Stop a running system service by name",
+            vec!["service.manage"],
+            "high",
+            false,
+            true,
+            15000,
+        ),
+        json!({
+            "type": "object",
+            "properties": { "name": { "type": "string", "description": "Unit name" } },
+            "required": ["name"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "service.restart",
-        "service",
-        "Restart a system service by name (stop then start)",
-        vec!["service.manage"],
-        "high",
-        false,
-        true,
-        30000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "service.restart",
+            "service",
+            "Restart a system service by name (stop then start)",
+            vec!["service.manage"],
+            "high",
+            false,
+            true,
+            30000,
+        ),
+        json!({
+            "type": "object",
+            "properties": { "name": { "type": "string", "description": "Unit name" } },
+            "required": ["name"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "service.status",
-        "service",
-        "Get the detailed status of a system service by name",
-        vec!["service.read"],
-        "low",
-        true,
-        false,
-        5000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "service.status",
+            "service",
+            "Get the detailed status of a system service by name",
+            vec!["service.read"],
+            "low",
+            true,
+            false,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": { "name": { "type": "string", "description": "Unit name" } },
+            "required": ["name"]
+        }),
     ));
 }
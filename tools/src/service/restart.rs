@@ -2,7 +2,8 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+
+use crate::sandbox::command;
 
 #[derive(Deserialize)]
 struct Input {
@@ -30,7 +31,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
 
 fn restart_launchctl(name: &str) -> Result<(bool, u32)> {
     // Use launchctl kickstart -k which restarts the service
-    let output = Command::new("launchctl")
+    let output = command("launchctl")
         .args(["kickstart", "-kp", &format!("system/{}", name)])
         .output()
         .context("Failed to execute launchctl kickstart")?;
@@ -57,12 +58,12 @@ fn restart_launchctl(name: &str) -> Result<(bool, u32)> {
     for path in &plist_paths {
         if std::path::Path::new(path).exists() {
             // Unload (stop)
-            let _ = Command::new("launchctl").args(["unload", path]).output();
+            let _ = command("launchctl").args(["unload", path]).output();
 
             std::thread::sleep(std::time::Duration::from_millis(200));
 
             // Load (start)
-            let load_output = Command::new("launchctl")
+            let load_output = command("launchctl")
                 .args(["load", "-w", path])
                 .output()
                 .context("Failed to execute launchctl load")?;
@@ -78,7 +79,7 @@ fn restart_launchctl(name: &str) -> Result<(bool, u32)> {
 }
 
 fn get_service_pid_launchctl(name: &str) -> Option<u32> {
-    let output = Command::new("launchctl").arg("list").output().ok()?;
+    let output = command("launchctl").arg("list").output().ok()?;
     let stdout = String::from_utf8_lossy(&output.stdout);
 
     for line in stdout.lines().skip(1) {
@@ -92,7 +93,7 @@ fn get_service_pid_launchctl(name: &str) -> Option<u32> {
 }
 
 fn restart_systemd(name: &str) -> Result<(bool, u32)> {
-    let output = Command::new("systemctl")
+    let output = command("systemctl")
         .args(["restart", &format!("{}.service", name)])
         .output()
         .context("Failed to execute systemctl restart")?;
@@ -103,7 +104,7 @@ fn restart_systemd(name: &str) -> Result<(bool, u32)> {
     }
 
     // Get the new PID
-    let pid_output = Command::new("systemctl")
+    let pid_output = command("systemctl")
         .args(["show", "-p", "MainPID", &format!("{}.service", name)])
         .output()
         .context("Failed to get service PID")?;
@@ -2,7 +2,8 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+
+use crate::sandbox::command;
 
 #[derive(Deserialize)]
 struct Input {
@@ -31,7 +32,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
 fn start_launchctl(name: &str) -> Result<(bool, u32)> {
     // Try to bootstrap (load + start) the service
     // First, try `launchctl kickstart` for system domain
-    let output = Command::new("launchctl")
+    let output = command("launchctl")
         .args(["kickstart", "-k", &format!("system/{}", name)])
         .output()
         .context("Failed to execute launchctl kickstart")?;
@@ -52,7 +53,7 @@ fn start_launchctl(name: &str) -> Result<(bool, u32)> {
         let mut loaded = false;
         for path in &plist_paths {
             if std::path::Path::new(path).exists() {
-                let load_output = Command::new("launchctl")
+                let load_output = command("launchctl")
                     .args(["load", "-w", path])
                     .output()
                     .context("Failed to execute launchctl load")?;
@@ -75,10 +76,7 @@ fn start_launchctl(name: &str) -> Result<(bool, u32)> {
 }
 
 fn get_launchctl_pid(name: &str) -> Option<u32> {
-    let output = Command::new("launchctl")
-        .args(["list", name])
-        .output()
-        .ok()?;
+    let output = command("launchctl").args(["list", name]).output().ok()?;
 
     let stdout = String::from_utf8_lossy(&output.stdout);
     // launchctl list <label> outputs key-value pairs
@@ -97,7 +95,7 @@ fn get_launchctl_pid(name: &str) -> Option<u32> {
     }
 
     // Alternatively, parse the first column of `launchctl list` output
-    let list_output = Command::new("launchctl").arg("list").output().ok()?;
+    let list_output = command("launchctl").arg("list").output().ok()?;
     let stdout = String::from_utf8_lossy(&list_output.stdout);
     for line in stdout.lines().skip(1) {
         let parts: Vec<&str> = line.split('\t').collect();
@@ -110,7 +108,7 @@ fn get_launchctl_pid(name: &str) -> Option<u32> {
 }
 
 fn start_systemd(name: &str) -> Result<(bool, u32)> {
-    let output = Command::new("systemctl")
+    let output = command("systemctl")
         .args(["start", &format!("{}.service", name)])
         .output()
         .context("Failed to execute systemctl start")?;
@@ -121,7 +119,7 @@ fn start_systemd(name: &str) -> Result<(bool, u32)> {
     }
 
     // Get the PID
-    let pid_output = Command::new("systemctl")
+    let pid_output = command("systemctl")
         .args(["show", "-p", "MainPID", &format!("{}.service", name)])
         .output()
         .context("Failed to get service PID")?;
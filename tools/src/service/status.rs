@@ -2,7 +2,8 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+
+use crate::sandbox::command;
 
 #[derive(Deserialize)]
 struct Input {
@@ -15,6 +16,12 @@ struct Output {
     status: String,
     pid: Option<u32>,
     uptime: String,
+    /// Whether the service starts at boot, when the init system says
+    enabled: Option<bool>,
+    /// Services this one requires or wants (systemd only)
+    requires: Vec<String>,
+    /// Services that require or want this one (systemd only)
+    required_by: Vec<String>,
 }
 
 pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
@@ -31,7 +38,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
 
 fn status_launchctl(name: &str) -> Result<Output> {
     // Get service info from launchctl list
-    let output = Command::new("launchctl")
+    let output = command("launchctl")
         .arg("list")
         .output()
         .context("Failed to execute launchctl list")?;
@@ -64,6 +71,9 @@ fn status_launchctl(name: &str) -> Result<Output> {
                 status,
                 pid,
                 uptime,
+                enabled: None,
+                requires: Vec::new(),
+                required_by: Vec::new(),
             });
         }
     }
@@ -74,11 +84,14 @@ fn status_launchctl(name: &str) -> Result<Output> {
         status: "not_found".to_string(),
         pid: None,
         uptime: "N/A".to_string(),
+        enabled: None,
+        requires: Vec::new(),
+        required_by: Vec::new(),
     })
 }
 
 fn get_process_uptime(pid: u32) -> String {
-    let output = Command::new("ps")
+    let output = command("ps")
         .args(["-p", &pid.to_string(), "-o", "etime="])
         .output();
 
@@ -99,7 +112,7 @@ fn status_systemd(name: &str) -> Result<Output> {
     let service_name = format!("{}.service", name);
 
     // Get active state
-    let active_output = Command::new("systemctl")
+    let active_output = command("systemctl")
         .args(["show", "-p", "ActiveState", &service_name])
         .output()
         .context("Failed to execute systemctl show")?;
@@ -112,7 +125,7 @@ fn status_systemd(name: &str) -> Result<Output> {
         .to_string();
 
     // Get PID
-    let pid_output = Command::new("systemctl")
+    let pid_output = command("systemctl")
         .args(["show", "-p", "MainPID", &service_name])
         .output()
         .context("Failed to get MainPID")?;
@@ -125,7 +138,7 @@ fn status_systemd(name: &str) -> Result<Output> {
         .filter(|&p| p != 0);
 
     // Get uptime from ActiveEnterTimestamp
-    let time_output = Command::new("systemctl")
+    let time_output = command("systemctl")
         .args(["show", "-p", "ActiveEnterTimestamp", &service_name])
         .output()
         .context("Failed to get ActiveEnterTimestamp")?;
@@ -138,10 +151,73 @@ fn status_systemd(name: &str) -> Result<Output> {
         .filter(|s| !s.is_empty())
         .unwrap_or_else(|| "N/A".to_string());
 
+    // Get boot-time state; is-enabled exits non-zero for disabled units
+    let enabled_output = command("systemctl")
+        .args(["is-enabled", &service_name])
+        .output()
+        .context("Failed to execute systemctl is-enabled")?;
+    let enabled = parse_is_enabled(&String::from_utf8_lossy(&enabled_output.stdout));
+
+    // Get dependencies in both directions
+    let deps_output = command("systemctl")
+        .args([
+            "show",
+            "-p",
+            "Requires",
+            "-p",
+            "Wants",
+            "-p",
+            "RequiredBy",
+            "-p",
+            "WantedBy",
+            &service_name,
+        ])
+        .output()
+        .context("Failed to get dependencies")?;
+    let (requires, required_by) = parse_dependencies(&String::from_utf8_lossy(&deps_output.stdout));
+
     Ok(Output {
         name: name.to_string(),
         status,
         pid,
         uptime,
+        enabled,
+        requires,
+        required_by,
     })
 }
+
+/// Boot-time state from `systemctl is-enabled`; None for units systemd
+/// does not know or that cannot be enabled
+fn parse_is_enabled(stdout: &str) -> Option<bool> {
+    match stdout.trim() {
+        "enabled" | "enabled-runtime" | "alias" => Some(true),
+        "disabled" | "masked" | "masked-runtime" | "linked" | "linked-runtime" => Some(false),
+        _ => None,
+    }
+}
+
+/// Service names from `systemctl show` Requires/Wants and RequiredBy/WantedBy
+/// lines; targets, sockets and other unit types are left out
+fn parse_dependencies(show: &str) -> (Vec<String>, Vec<String>) {
+    let mut requires = Vec::new();
+    let mut required_by = Vec::new();
+    for line in show.lines() {
+        let Some((key, units)) = line.split_once('=') else {
+            continue;
+        };
+        let list = match key {
+            "Requires" | "Wants" => &mut requires,
+            "RequiredBy" | "WantedBy" => &mut required_by,
+            _ => continue,
+        };
+        for unit in units.split_whitespace() {
+            if let Some(service) = unit.strip_suffix(".service") {
+                if !list.iter().any(|s| s == service) {
+                    list.push(service.to_string());
+                }
+            }
+        }
+    }
+    (requires, required_by)
+}
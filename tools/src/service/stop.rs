@@ -2,7 +2,8 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+
+use crate::sandbox::command;
 
 #[derive(Deserialize)]
 struct Input {
@@ -29,7 +30,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
 
 fn stop_launchctl(name: &str) -> Result<bool> {
     // Try launchctl bootout for system domain
-    let output = Command::new("launchctl")
+    let output = command("launchctl")
         .args(["bootout", &format!("system/{}", name)])
         .output();
 
@@ -53,7 +54,7 @@ fn stop_launchctl(name: &str) -> Result<bool> {
 
     for path in &plist_paths {
         if std::path::Path::new(path).exists() {
-            let unload_output = Command::new("launchctl")
+            let unload_output = command("launchctl")
                 .args(["unload", path])
                 .output()
                 .context("Failed to execute launchctl unload")?;
@@ -65,7 +66,7 @@ fn stop_launchctl(name: &str) -> Result<bool> {
     }
 
     // If we can find the PID, try to stop via kill as a last resort
-    let list_output = Command::new("launchctl").arg("list").output().ok();
+    let list_output = command("launchctl").arg("list").output().ok();
     if let Some(out) = list_output {
         let stdout = String::from_utf8_lossy(&out.stdout);
         for line in stdout.lines().skip(1) {
@@ -86,7 +87,7 @@ fn stop_launchctl(name: &str) -> Result<bool> {
 }
 
 fn stop_systemd(name: &str) -> Result<bool> {
-    let output = Command::new("systemctl")
+    let output = command("systemctl")
         .args(["stop", &format!("{}.service", name)])
         .output()
         .context("Failed to execute systemctl stop")?;
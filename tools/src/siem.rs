@@ -0,0 +1,686 @@
+//! SIEM export — forwarding the audit ledger and capability grants
+//!
+//! When siem.toml enables it, a background task forwards every audit ledger
+//! record (one per tool execution) and every capability grant to an
+//! external collector: RFC 5424 syslog over TLS (RFC 5425 framing) with a
+//! JSON or CEF message, or batches of JSON events POSTed over HTTPS.
+//!
+//! The ledger and the grants database are the buffer. Records are read in
+//! id order and a per-source cursor moves past a batch only once the
+//! collector accepted it, so an outage just holds the cursor and delivery
+//! resumes where it stopped, backing off between attempts. A batch cut off
+//! mid-send is sent again; every event carries a stable `event_id` (the
+//! ledger record's chain hash, or `grant-<id>`) for the collector to
+//! deduplicate on, so each record takes effect there exactly once.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::rustls;
+use tracing::{info, warn};
+
+/// Default location of the SIEM export configuration
+pub const SIEM_CONFIG_PATH: &str = "/etc/aios/siem.toml";
+
+/// Audit ledger of tool executions
+pub const LEDGER_DB_PATH: &str = "/var/lib/aios/ledger/audit.db";
+
+/// Capability grants written by sec.grant
+pub const GRANTS_DB_PATH: &str = "/var/lib/aios/data/capabilities.db";
+
+/// Delivery cursors, one per source
+pub const CURSOR_DB_PATH: &str = "/var/lib/aios/ledger/siem.db";
+
+/// Longest wait between delivery attempts while the collector is down
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// Private enterprise number of the syslog structured data element. 32473
+/// is reserved for documentation (RFC 5612); collectors key on the name.
+const SD_ID: &str = "aios@32473";
+
+/// syslog facility authpriv (10), shifted for the PRI field
+const FACILITY_AUTHPRIV: u8 = 10 << 3;
+
+/// How events travel to the collector
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Transport {
+    /// RFC 5424 syslog over TLS; `endpoint` is host:port
+    Syslog,
+    /// JSON batches POSTed to `endpoint`, an https:// URL
+    Https,
+}
+
+/// Message body of syslog events
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[default]
+    Json,
+    /// ArcSight Common Event Format
+    Cef,
+}
+
+/// siem.toml layout
+#[derive(Debug, Clone, Deserialize)]
+pub struct SiemConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    pub transport: Transport,
+    pub endpoint: String,
+    #[serde(default)]
+    pub format: Format,
+    /// PEM bundle trusted instead of the public web roots
+    #[serde(default)]
+    pub ca_file: Option<String>,
+    /// Bearer token for the HTTPS collector
+    #[serde(default)]
+    pub token: Option<String>,
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+    /// Poll interval once caught up
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    /// HOSTNAME field of syslog messages; the system hostname if unset
+    #[serde(default)]
+    pub hostname: Option<String>,
+}
+
+fn default_batch_size() -> usize {
+    100
+}
+
+fn default_interval_secs() -> u64 {
+    5
+}
+
+impl SiemConfig {
+    /// The configuration at `path` if export is enabled. A missing file
+    /// disables export; an invalid one is logged and disables it.
+    pub fn load(path: &str) -> Option<Self> {
+        let contents = std::fs::read_to_string(path).ok()?;
+        match toml::from_str::<Self>(&contents) {
+            Ok(config) if config.enabled => Some(config),
+            Ok(_) => None,
+            Err(e) => {
+                warn!("Ignoring invalid SIEM export config in {path}: {e}");
+                None
+            }
+        }
+    }
+}
+
+/// Where an event came from
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    ToolExecution,
+    CapabilityGrant,
+}
+
+impl Source {
+    fn cursor_key(self) -> &'static str {
+        match self {
+            Source::ToolExecution => "audit_log",
+            Source::CapabilityGrant => "capability_grants",
+        }
+    }
+}
+
+/// One forwarded record
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditEvent {
+    /// Stable across re-sends, for deduplication
+    pub event_id: String,
+    pub source: Source,
+    /// Row id in the source table
+    pub seq: i64,
+    /// RFC 3339
+    pub timestamp: String,
+    pub agent_id: String,
+    /// Tool name, or "capability.grant"
+    pub action: String,
+    pub success: bool,
+    pub reason: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub capability: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<String>,
+}
+
+/// Ledger records after row `after`, oldest first
+pub fn read_ledger(
+    conn: &rusqlite::Connection,
+    after: i64,
+    limit: usize,
+) -> Result<Vec<AuditEvent>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, hash, timestamp, agent_id, tool_name, success, reason, task_id, duration_ms
+         FROM audit_log WHERE id > ?1 ORDER BY id LIMIT ?2",
+    )?;
+    let rows = stmt.query_map(rusqlite::params![after, limit as i64], |row| {
+        Ok(AuditEvent {
+            seq: row.get(0)?,
+            event_id: row.get(1)?,
+            source: Source::ToolExecution,
+            timestamp: row.get(2)?,
+            agent_id: row.get(3)?,
+            action: row.get(4)?,
+            success: row.get::<_, i32>(5)? != 0,
+            reason: row.get(6)?,
+            task_id: Some(row.get(7)?),
+            duration_ms: Some(row.get(8)?),
+            capability: None,
+            expires_at: None,
+        })
+    })?;
+    Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+}
+
+/// Capability grants after row `after`, oldest first; none before sec.grant
+/// first ran
+pub fn read_grants(
+    conn: &rusqlite::Connection,
+    after: i64,
+    limit: usize,
+) -> Result<Vec<AuditEvent>> {
+    let exists: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'capability_grants'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        return Ok(Vec::new());
+    }
+    let mut stmt = conn.prepare(
+        "SELECT id, granted_at, agent_id, capability, reason, expires_at
+         FROM capability_grants WHERE id > ?1 ORDER BY id LIMIT ?2",
+    )?;
+    let rows = stmt.query_map(rusqlite::params![after, limit as i64], |row| {
+        let seq: i64 = row.get(0)?;
+        Ok(AuditEvent {
+            event_id: format!("grant-{seq}"),
+            source: Source::CapabilityGrant,
+            seq,
+            timestamp: row.get(1)?,
+            agent_id: row.get(2)?,
+            action: "capability.grant".into(),
+            success: true,
+            capability: Some(row.get(3)?),
+            reason: row.get::<_, Option<String>>(4)?.unwrap_or_default(),
+            expires_at: Some(row.get(5)?),
+            task_id: None,
+            duration_ms: None,
+        })
+    })?;
+    Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+}
+
+/// Delivery cursors: the last row of each source the collector accepted
+pub struct Cursors {
+    conn: std::sync::Mutex<rusqlite::Connection>,
+}
+
+impl Cursors {
+    pub fn open(path: &str) -> Result<Self> {
+        if let Some(parent) = std::path::Path::new(path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = rusqlite::Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS siem_cursor (
+                source TEXT PRIMARY KEY,
+                last_id INTEGER NOT NULL
+            )",
+        )?;
+        Ok(Self {
+            conn: std::sync::Mutex::new(conn),
+        })
+    }
+
+    pub fn get(&self, source: Source) -> Result<i64> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        let last = conn
+            .query_row(
+                "SELECT last_id FROM siem_cursor WHERE source = ?1",
+                [source.cursor_key()],
+                |row| row.get(0),
+            )
+            .unwrap_or(0);
+        Ok(last)
+    }
+
+    pub fn advance(&self, source: Source, last_id: i64) -> Result<()> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Lock error: {e}"))?;
+        conn.execute(
+            "INSERT OR REPLACE INTO siem_cursor (source, last_id) VALUES (?1, ?2)",
+            rusqlite::params![source.cursor_key(), last_id],
+        )?;
+        Ok(())
+    }
+}
+
+/// RFC 5424 message for an event, without transport framing
+pub fn syslog_message(event: &AuditEvent, hostname: &str, format: Format) -> String {
+    // informational, or warning for failed executions
+    let severity = if event.success { 6 } else { 4 };
+    let msgid = match event.source {
+        Source::ToolExecution => "tool_exec",
+        Source::CapabilityGrant => "cap_grant",
+    };
+    let body = match format {
+        Format::Json => serde_json::to_string(event).unwrap_or_default(),
+        Format::Cef => cef_message(event),
+    };
+    format!(
+        "<{}>1 {} {} aios-tools - {msgid} [{SD_ID} event_id=\"{}\" agent=\"{}\" action=\"{}\"] {body}",
+        FACILITY_AUTHPRIV | severity,
+        event.timestamp,
+        header_field(hostname),
+        sd_escape(&event.event_id),
+        sd_escape(&event.agent_id),
+        sd_escape(&event.action),
+    )
+}
+
+/// CEF:0 record for an event
+pub fn cef_message(event: &AuditEvent) -> String {
+    let (signature, name, severity) = match (event.source, event.success) {
+        (Source::ToolExecution, true) => ("tool_exec", "Tool execution", 3),
+        (Source::ToolExecution, false) => ("tool_exec_failed", "Tool execution failed", 6),
+        (Source::CapabilityGrant, _) => ("cap_grant", "Capability granted", 5),
+    };
+    let mut extensions = vec![
+        ("externalId", event.event_id.clone()),
+        ("suser", event.agent_id.clone()),
+        ("act", event.action.clone()),
+        (
+            "outcome",
+            if event.success { "success" } else { "failure" }.to_string(),
+        ),
+    ];
+    if let Ok(at) = chrono::DateTime::parse_from_rfc3339(&event.timestamp) {
+        extensions.push(("rt", at.timestamp_millis().to_string()));
+    }
+    if !event.reason.is_empty() {
+        extensions.push(("msg", event.reason.clone()));
+    }
+    if let Some(ref task_id) = event.task_id {
+        extensions.push(("cs1Label", "taskId".into()));
+        extensions.push(("cs1", task_id.clone()));
+    }
+    if let Some(ref capability) = event.capability {
+        extensions.push(("cs2Label", "capability".into()));
+        extensions.push(("cs2", capability.clone()));
+    }
+    let extensions: Vec<String> = extensions
+        .into_iter()
+        .map(|(key, value)| format!("{key}={}", cef_value(&value)))
+        .collect();
+    format!(
+        "CEF:0|aiOS|aios-tools|{}|{signature}|{name}|{severity}|{}",
+        cef_header(env!("CARGO_PKG_VERSION")),
+        extensions.join(" ")
+    )
+}
+
+fn cef_header(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('|', "\\|")
+}
+
+fn cef_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('=', "\\=")
+        .replace('\r', "\\r")
+        .replace('\n', "\\n")
+}
+
+fn sd_escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace(']', "\\]")
+}
+
+/// Header fields are printable ASCII without spaces; "-" when empty
+fn header_field(value: &str) -> String {
+    let field: String = value.chars().filter(|c| c.is_ascii_graphic()).collect();
+    if field.is_empty() {
+        "-".into()
+    } else {
+        field
+    }
+}
+
+/// Connection to the collector
+enum Sink {
+    Syslog {
+        connector: tokio_rustls::TlsConnector,
+        host: String,
+        port: u16,
+        hostname: String,
+        format: Format,
+        stream: Option<Box<TlsStream<TcpStream>>>,
+    },
+    Https {
+        client: reqwest::Client,
+        url: String,
+        token: Option<String>,
+    },
+}
+
+impl Sink {
+    fn new(config: &SiemConfig) -> Result<Self> {
+        match config.transport {
+            Transport::Syslog => {
+                let (host, port) = config
+                    .endpoint
+                    .rsplit_once(':')
+                    .context("Syslog endpoint must be host:port")?;
+                let tls = rustls::ClientConfig::builder_with_provider(Arc::new(
+                    rustls::crypto::ring::default_provider(),
+                ))
+                .with_safe_default_protocol_versions()?
+                .with_root_certificates(root_store(config.ca_file.as_deref())?)
+                .with_no_client_auth();
+                let hostname = config.hostname.clone().unwrap_or_else(|| {
+                    std::fs::read_to_string("/etc/hostname")
+                        .map(|h| h.trim().to_string())
+                        .unwrap_or_default()
+                });
+                Ok(Sink::Syslog {
+                    connector: tokio_rustls::TlsConnector::from(Arc::new(tls)),
+                    host: host.to_string(),
+                    port: port.parse().context("Invalid syslog port")?,
+                    hostname,
+                    format: config.format,
+                    stream: None,
+                })
+            }
+            Transport::Https => {
+                if !config.endpoint.starts_with("https://") {
+                    bail!("HTTPS endpoint must be an https:// URL");
+                }
+                let mut builder = reqwest::Client::builder().timeout(Duration::from_secs(30));
+                if let Some(ref ca_file) = config.ca_file {
+                    let pem = std::fs::read(ca_file)
+                        .with_context(|| format!("Cannot read CA bundle {ca_file}"))?;
+                    for cert in reqwest::Certificate::from_pem_bundle(&pem)? {
+                        builder = builder.add_root_certificate(cert);
+                    }
+                }
+                Ok(Sink::Https {
+                    client: builder.build()?,
+                    url: config.endpoint.clone(),
+                    token: config.token.clone(),
+                })
+            }
+        }
+    }
+
+    /// Deliver a batch; an error means it may not have arrived
+    async fn send(&mut self, events: &[AuditEvent]) -> Result<()> {
+        match self {
+            Sink::Syslog {
+                connector,
+                host,
+                port,
+                hostname,
+                format,
+                stream,
+            } => {
+                if stream.is_none() {
+                    let tcp = TcpStream::connect((host.as_str(), *port)).await?;
+                    let server_name = rustls::pki_types::ServerName::try_from(host.clone())?;
+                    *stream = Some(Box::new(connector.connect(server_name, tcp).await?));
+                }
+                let mut frames = Vec::new();
+                for event in events.iter() {
+                    let message = syslog_message(event, hostname, *format);
+                    // RFC 5425 octet counting
+                    frames.extend_from_slice(format!("{} {message}", message.len()).as_bytes());
+                }
+                let result = match stream.as_mut() {
+                    Some(tls) => match tls.write_all(&frames).await {
+                        Ok(()) => tls.flush().await,
+                        Err(e) => Err(e),
+                    },
+                    None => Ok(()),
+                };
+                if result.is_err() {
+                    // Reconnect on the next attempt
+                    *stream = None;
+                }
+                Ok(result?)
+            }
+            Sink::Https { client, url, token } => {
+                let mut request = client.post(url.as_str()).json(events);
+                if let Some(token) = token {
+                    request = request.bearer_auth(token);
+                }
+                request.send().await?.error_for_status()?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Trust anchors: the PEM bundle at `ca_file`, or the public web roots
+fn root_store(ca_file: Option<&str>) -> Result<rustls::RootCertStore> {
+    use rustls::pki_types::pem::PemObject;
+    use rustls::pki_types::CertificateDer;
+
+    let mut roots = rustls::RootCertStore::empty();
+    match ca_file {
+        Some(path) => {
+            for cert in CertificateDer::pem_file_iter(path)
+                .with_context(|| format!("Cannot read CA bundle {path}"))?
+            {
+                roots.add(cert?)?;
+            }
+        }
+        None => roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned()),
+    }
+    Ok(roots)
+}
+
+/// Forward one batch from `source`; the number of events delivered
+async fn forward_batch(
+    sink: &mut Sink,
+    cursors: &Cursors,
+    source: Source,
+    db_path: &str,
+    batch_size: usize,
+) -> Result<usize> {
+    let after = cursors.get(source)?;
+    let events = {
+        let Ok(conn) = rusqlite::Connection::open_with_flags(
+            db_path,
+            rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY,
+        ) else {
+            // Not created yet
+            return Ok(0);
+        };
+        match source {
+            Source::ToolExecution => read_ledger(&conn, after, batch_size)?,
+            Source::CapabilityGrant => read_grants(&conn, after, batch_size)?,
+        }
+    };
+    let Some(last) = events.last().map(|e| e.seq) else {
+        return Ok(0);
+    };
+    sink.send(&events).await?;
+    cursors.advance(source, last)?;
+    Ok(events.len())
+}
+
+/// Forward the ledger and grants to the collector until the process exits
+pub async fn run(config: SiemConfig) {
+    let mut sink = match Sink::new(&config) {
+        Ok(sink) => sink,
+        Err(e) => {
+            warn!("SIEM export disabled: {e:#}");
+            return;
+        }
+    };
+    let cursors = match Cursors::open(CURSOR_DB_PATH) {
+        Ok(cursors) => cursors,
+        Err(e) => {
+            warn!("SIEM export disabled, cannot open cursors: {e:#}");
+            return;
+        }
+    };
+    info!(
+        "Forwarding audit events to {} ({:?})",
+        config.endpoint, config.transport
+    );
+
+    let batch_size = config.batch_size.max(1);
+    let interval = Duration::from_secs(config.interval_secs.max(1));
+    let mut backoff = interval;
+    loop {
+        let mut result = Ok(0);
+        for (source, db_path) in [
+            (Source::ToolExecution, LEDGER_DB_PATH),
+            (Source::CapabilityGrant, GRANTS_DB_PATH),
+        ] {
+            result = match forward_batch(&mut sink, &cursors, source, db_path, batch_size).await {
+                Ok(sent) => result.map(|total| total + sent),
+                Err(e) => Err(e),
+            };
+            if result.is_err() {
+                break;
+            }
+        }
+        match result {
+            // More may be waiting
+            Ok(sent) if sent > 0 => {
+                backoff = interval;
+                continue;
+            }
+            Ok(_) => {
+                backoff = interval;
+                tokio::time::sleep(interval).await;
+            }
+            Err(e) => {
+                warn!("SIEM delivery failed, retrying in {backoff:?}: {e:#}");
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditLog;
+
+    fn grant_event() -> AuditEvent {
+        AuditEvent {
+            event_id: "grant-7".into(),
+            source: Source::CapabilityGrant,
+            seq: 7,
+            timestamp: "2026-03-01T10:00:00+00:00".into(),
+            agent_id: "net-agent".into(),
+            action: "capability.grant".into(),
+            success: true,
+            reason: "rotate=certs | now".into(),
+            task_id: None,
+            duration_ms: None,
+            capability: Some("net_write".into()),
+            expires_at: Some("2026-03-02T10:00:00+00:00".into()),
+        }
+    }
+
+    #[test]
+    fn test_syslog_and_cef_formatting() {
+        let event = grant_event();
+        let json = syslog_message(&event, "node 1", Format::Json);
+        assert!(json.starts_with(
+            "<86>1 2026-03-01T10:00:00+00:00 node1 aios-tools - cap_grant [aios@32473 event_id=\"grant-7\""
+        ));
+        assert!(json.ends_with(&serde_json::to_string(&event).unwrap()));
+
+        let cef = cef_message(&event);
+        assert!(cef.starts_with("CEF:0|aiOS|aios-tools|"));
+        assert!(cef.contains("|cap_grant|Capability granted|5|externalId=grant-7 "));
+        assert!(cef.contains("msg=rotate\\=certs | now"));
+        assert!(cef.contains("rt=1772359200000"));
+        assert!(cef.contains("cs2=net_write"));
+    }
+
+    #[test]
+    fn test_cursor_reads_each_record_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let ledger = dir.path().join("audit.db");
+        let mut log = AuditLog::new(ledger.to_str().unwrap()).unwrap();
+        for i in 0..3 {
+            log.record(
+                &format!("exec-{i}"),
+                "fs.read",
+                "agent-1",
+                "task-1",
+                "test",
+                i != 1,
+                10,
+            );
+        }
+        let cursors = Cursors::open(dir.path().join("siem.db").to_str().unwrap()).unwrap();
+        let conn = rusqlite::Connection::open(&ledger).unwrap();
+
+        let first = read_ledger(&conn, cursors.get(Source::ToolExecution).unwrap(), 2).unwrap();
+        assert_eq!(first.len(), 2);
+        assert!(!first[1].success);
+        assert_eq!(first[0].event_id.len(), 64);
+        cursors
+            .advance(Source::ToolExecution, first[1].seq)
+            .unwrap();
+
+        let rest = read_ledger(&conn, cursors.get(Source::ToolExecution).unwrap(), 2).unwrap();
+        assert_eq!(rest.len(), 1);
+        assert_eq!(rest[0].seq, 3);
+        assert_eq!(cursors.get(Source::CapabilityGrant).unwrap(), 0);
+
+        // No grants table until sec.grant first runs
+        assert!(read_grants(&conn, 0, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_config_requires_enabled() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("siem.toml");
+        std::fs::write(
+            &path,
+            "enabled = true\ntransport = \"syslog\"\nendpoint = \"siem.corp:6514\"\nformat = \"cef\"\n",
+        )
+        .unwrap();
+        let config = SiemConfig::load(path.to_str().unwrap()).unwrap();
+        assert_eq!(config.transport, Transport::Syslog);
+        assert_eq!(config.format, Format::Cef);
+        assert_eq!(config.batch_size, 100);
+
+        std::fs::write(
+            &path,
+            "transport = \"https\"\nendpoint = \"https://siem.corp/ingest\"\n",
+        )
+        .unwrap();
+        assert!(SiemConfig::load(path.to_str().unwrap()).is_none());
+        assert!(SiemConfig::load("/nonexistent/siem.toml").is_none());
+    }
+}
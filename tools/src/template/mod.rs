@@ -0,0 +1,52 @@
+//! Template tools — render configuration files from Handlebars templates.
+//!
+//! Each submodule exposes `pub fn execute(input: &[u8]) -> Result<Vec<u8>>`.
+
+pub mod render;
+
+use serde_json::json;
+
+use crate::registry::{make_tool, with_input_schema, Registry};
+
+/// Register every template tool with the registry.
+pub fn register_tools(reg: &mut Registry) {
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "template.render",
+            "template",
+            "Render a Handlebars template (inline 'template' or 'template_path') with 'values' from goal context or inventory. \
+             Secrets are referenced by key in 'secrets' and exposed as {{secrets.<name>}}; output containing secrets must be written to 'path'. \
+             Writing to 'path' backs up the previous file.",
+            vec!["fs.read", "fs.write"],
+            "medium",
+            true,
+            true,
+            10000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "template": { "type": "string", "description": "Inline template source" },
+                "template_path": {
+                    "type": "string",
+                    "description": "Template file to read instead"
+                },
+                "values": { "description": "Values available at the top level" },
+                "secrets": {
+                    "type": "object",
+                    "description": "Name -> secret key, exposed as {{secrets.<name>}}",
+                    "additionalProperties": { "type": "string" }
+                },
+                "path": {
+                    "type": "string",
+                    "description": "Destination file; the rendered text is returned when omitted"
+                },
+                "strict": {
+                    "type": "boolean",
+                    "description": "Fail on missing variables",
+                    "default": true
+                }
+            }
+        }),
+    ));
+}
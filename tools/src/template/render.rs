@@ -0,0 +1,208 @@
+//! template.render — Render a Handlebars template, optionally writing the result
+
+use anyhow::{Context, Result};
+use handlebars::Handlebars;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+
+use crate::secrets::SecretManager;
+
+/// Secrets file consulted for `secrets` references
+const SECRETS_PATH: &str = "/etc/aios/secrets.toml";
+
+#[derive(Deserialize)]
+struct Input {
+    /// Inline template source
+    template: Option<String>,
+    /// Template file to read instead of `template`
+    template_path: Option<String>,
+    /// Values available to the template at the top level
+    #[serde(default)]
+    values: serde_json::Value,
+    /// Template name → secret key, exposed as `{{secrets.<name>}}`
+    #[serde(default)]
+    secrets: HashMap<String, String>,
+    /// Destination file; when absent the rendered text is returned
+    path: Option<String>,
+    /// Fail on missing variables instead of rendering them empty
+    #[serde(default = "default_strict")]
+    strict: bool,
+}
+
+fn default_strict() -> bool {
+    true
+}
+
+#[derive(Serialize)]
+struct Output {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rendered: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    bytes: usize,
+    sha256: String,
+    /// True when secrets were rendered, in which case the text is never returned
+    redacted: bool,
+}
+
+pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
+    let mut secrets = SecretManager::new(SECRETS_PATH);
+    let mut loaded = false;
+    render(input, |key| {
+        if !loaded {
+            loaded = true;
+            secrets.load()?;
+        }
+        Ok(secrets.get(key).map(str::to_string))
+    })
+}
+
+/// Render with secrets resolved through `lookup_secret`.
+fn render(
+    input: &[u8],
+    mut lookup_secret: impl FnMut(&str) -> Result<Option<String>>,
+) -> Result<Vec<u8>> {
+    let input: Input =
+        serde_json::from_slice(input).context("template.render: invalid JSON input")?;
+
+    let source = match (&input.template, &input.template_path) {
+        (Some(template), None) => template.clone(),
+        (None, Some(template_path)) => fs::read_to_string(template_path)
+            .with_context(|| format!("template.render: failed to read template {template_path}"))?,
+        _ => anyhow::bail!("template.render: provide exactly one of 'template' or 'template_path'"),
+    };
+
+    let mut context = match input.values {
+        serde_json::Value::Object(map) => map,
+        serde_json::Value::Null => serde_json::Map::new(),
+        _ => anyhow::bail!("template.render: 'values' must be a JSON object"),
+    };
+    if !input.secrets.is_empty() {
+        let mut resolved = serde_json::Map::new();
+        for (name, key) in &input.secrets {
+            let value = lookup_secret(key)
+                .context("template.render: failed to load secrets")?
+                .ok_or_else(|| anyhow::anyhow!("template.render: unknown secret '{key}'"))?;
+            resolved.insert(name.clone(), serde_json::Value::String(value));
+        }
+        context.insert("secrets".to_string(), serde_json::Value::Object(resolved));
+    }
+    let redacted = !input.secrets.is_empty();
+    if redacted && input.path.is_none() {
+        anyhow::bail!(
+            "template.render: templates that use secrets must be written to a 'path', not returned"
+        );
+    }
+
+    let mut hb = Handlebars::new();
+    hb.set_strict_mode(input.strict);
+    // Configs are not HTML — render values verbatim
+    hb.register_escape_fn(handlebars::no_escape);
+    let rendered = hb
+        .render_template(&source, &serde_json::Value::Object(context))
+        .context("template.render: failed to render template")?;
+
+    let sha256 = format!("{:x}", Sha256::digest(rendered.as_bytes()));
+    let bytes = rendered.len();
+
+    if let Some(path) = &input.path {
+        crate::fs::write_atomically(path, rendered.as_bytes())
+            .context("template.render: failed to write output")?;
+    }
+
+    let output = Output {
+        rendered: if input.path.is_none() {
+            Some(rendered)
+        } else {
+            None
+        },
+        path: input.path,
+        bytes,
+        sha256,
+        redacted,
+    };
+    serde_json::to_vec(&output).context("template.render: failed to serialise output")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(input: serde_json::Value, secrets: &[(&str, &str)]) -> Result<serde_json::Value> {
+        let secrets: HashMap<String, String> = secrets
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let out = render(&serde_json::to_vec(&input)?, |key| {
+            Ok(secrets.get(key).cloned())
+        })?;
+        Ok(serde_json::from_slice(&out)?)
+    }
+
+    #[test]
+    fn test_render_inline() {
+        let out = run(
+            serde_json::json!({
+                "template": "server {{host}}:{{port}}\n{{#each upstreams}}upstream {{this}};\n{{/each}}",
+                "values": {"host": "a&b", "port": 8080, "upstreams": ["x", "y"]},
+            }),
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            out["rendered"],
+            "server a&b:8080\nupstream x;\nupstream y;\n"
+        );
+        assert_eq!(out["redacted"], false);
+    }
+
+    #[test]
+    fn test_render_strict_missing_value() {
+        let input = serde_json::json!({"template": "{{missing}}"});
+        assert!(run(input, &[]).is_err());
+
+        let lenient = serde_json::json!({"template": "[{{missing}}]", "strict": false});
+        assert_eq!(run(lenient, &[]).unwrap()["rendered"], "[]");
+    }
+
+    #[test]
+    fn test_render_secrets_written_not_returned() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("conf/app.env");
+        let input = serde_json::json!({
+            "template": "API_KEY={{secrets.key}}\n",
+            "secrets": {"key": "api_keys.claude"},
+            "path": path,
+        });
+        let out = run(input, &[("api_keys.claude", "sk-test")]).unwrap();
+        assert_eq!(out["redacted"], true);
+        assert!(out.get("rendered").is_none());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "API_KEY=sk-test\n");
+
+        // Returning rendered secrets is refused
+        let leak = serde_json::json!({
+            "template": "{{secrets.key}}",
+            "secrets": {"key": "api_keys.claude"},
+        });
+        assert!(run(leak, &[("api_keys.claude", "sk-test")]).is_err());
+
+        let unknown = serde_json::json!({
+            "template": "{{secrets.key}}",
+            "secrets": {"key": "nope"},
+            "path": dir.path().join("x"),
+        });
+        assert!(run(unknown, &[]).is_err());
+    }
+
+    #[test]
+    fn test_render_requires_one_source() {
+        assert!(run(serde_json::json!({"values": {}}), &[]).is_err());
+        assert!(run(
+            serde_json::json!({"template": "a", "template_path": "/tmp/x"}),
+            &[]
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,58 @@
+//! text.count — Line, word, character, byte, and pattern counts
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Serialize)]
+struct Output {
+    lines: usize,
+    words: usize,
+    chars: usize,
+    bytes: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    matches: Option<usize>,
+}
+
+pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
+    let input: Value = serde_json::from_slice(input).context("text.count: invalid JSON input")?;
+    let text = super::load_text(&input, "input", "path").context("text.count: no input")?;
+
+    let matches = match input.get("pattern").and_then(|p| p.as_str()) {
+        Some(pattern) => {
+            let regex = Regex::new(pattern)
+                .with_context(|| format!("text.count: invalid pattern '{pattern}'"))?;
+            Some(regex.find_iter(&text).count())
+        }
+        None => None,
+    };
+
+    let output = Output {
+        lines: text.lines().count(),
+        words: text.split_whitespace().count(),
+        chars: text.chars().count(),
+        bytes: text.len(),
+        matches,
+    };
+    serde_json::to_vec(&output).context("text.count: failed to serialise output")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_count() {
+        let input = json!({"input": "héllo world\nfoo bar baz\n", "pattern": "o"});
+        let out: Value =
+            serde_json::from_slice(&execute(&serde_json::to_vec(&input).unwrap()).unwrap())
+                .unwrap();
+        assert_eq!(out["lines"], 2);
+        assert_eq!(out["words"], 5);
+        assert_eq!(out["chars"], 24);
+        assert_eq!(out["bytes"], 25);
+        assert_eq!(out["matches"], 4);
+    }
+}
@@ -0,0 +1,83 @@
+//! text.diff — Unified line diff between two texts or files
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+use similar::{ChangeTag, TextDiff};
+
+#[derive(Serialize)]
+struct Output {
+    identical: bool,
+    insertions: usize,
+    deletions: usize,
+    diff: String,
+}
+
+pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
+    let input: Value = serde_json::from_slice(input).context("text.diff: invalid JSON input")?;
+    let old = super::load_text(&input, "old", "old_path").context("text.diff: no old text")?;
+    let new = super::load_text(&input, "new", "new_path").context("text.diff: no new text")?;
+    let context = input.get("context").and_then(|c| c.as_u64()).unwrap_or(3) as usize;
+
+    let diff = TextDiff::from_lines(&old, &new);
+    let (mut insertions, mut deletions) = (0, 0);
+    for change in diff.iter_all_changes() {
+        match change.tag() {
+            ChangeTag::Insert => insertions += 1,
+            ChangeTag::Delete => deletions += 1,
+            ChangeTag::Equal => {}
+        }
+    }
+
+    let old_name = input
+        .get("old_path")
+        .and_then(|p| p.as_str())
+        .unwrap_or("old");
+    let new_name = input
+        .get("new_path")
+        .and_then(|p| p.as_str())
+        .unwrap_or("new");
+    let unified = diff
+        .unified_diff()
+        .context_radius(context)
+        .header(old_name, new_name)
+        .to_string();
+
+    let output = Output {
+        identical: insertions == 0 && deletions == 0,
+        insertions,
+        deletions,
+        diff: unified,
+    };
+    serde_json::to_vec(&output).context("text.diff: failed to serialise output")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_diff_strings() {
+        let input = json!({"old": "a\nb\nc\n", "new": "a\nB\nc\nd\n"});
+        let out: Value =
+            serde_json::from_slice(&execute(&serde_json::to_vec(&input).unwrap()).unwrap())
+                .unwrap();
+        assert_eq!(out["identical"], false);
+        assert_eq!(out["insertions"], 2);
+        assert_eq!(out["deletions"], 1);
+        let diff = out["diff"].as_str().unwrap();
+        assert!(diff.starts_with("--- old\n+++ new\n"));
+        assert!(diff.contains("-b\n+B\n"));
+    }
+
+    #[test]
+    fn test_diff_identical() {
+        let input = json!({"old": "same\n", "new": "same\n"});
+        let out: Value =
+            serde_json::from_slice(&execute(&serde_json::to_vec(&input).unwrap()).unwrap())
+                .unwrap();
+        assert_eq!(out["identical"], true);
+        assert_eq!(out["diff"], "");
+    }
+}
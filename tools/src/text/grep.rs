@@ -0,0 +1,183 @@
+//! text.grep — Regex search over files, directories, or strings with context lines
+
+use anyhow::{Context, Result};
+use regex::RegexBuilder;
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+struct Input {
+    pattern: String,
+    /// Inline text to search instead of files
+    input: Option<String>,
+    path: Option<String>,
+    #[serde(default)]
+    paths: Vec<String>,
+    /// Lines of context before and after each match
+    #[serde(default)]
+    context: usize,
+    #[serde(default)]
+    ignore_case: bool,
+    /// Report lines that do NOT match
+    #[serde(default)]
+    invert: bool,
+    #[serde(default = "default_max_matches")]
+    max_matches: usize,
+}
+
+fn default_max_matches() -> usize {
+    200
+}
+
+/// Files visited when walking directories
+const MAX_FILES: usize = 5000;
+
+#[derive(Serialize, Debug)]
+struct Match {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    line_number: usize,
+    line: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    before: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    after: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Output {
+    count: usize,
+    files_searched: usize,
+    /// True when `max_matches` stopped the search early
+    truncated: bool,
+    matches: Vec<Match>,
+}
+
+pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
+    let input: Input = serde_json::from_slice(input).context("text.grep: invalid JSON input")?;
+    let regex = RegexBuilder::new(&input.pattern)
+        .case_insensitive(input.ignore_case)
+        .build()
+        .with_context(|| format!("text.grep: invalid pattern '{}'", input.pattern))?;
+
+    let mut roots = input.paths.clone();
+    roots.extend(input.path.clone());
+
+    let mut matches = Vec::new();
+    let mut files_searched = 0;
+    let mut truncated = false;
+
+    let search = |path: Option<&str>, text: &str, matches: &mut Vec<Match>| {
+        let lines: Vec<&str> = text.lines().collect();
+        for (i, line) in lines.iter().enumerate() {
+            if regex.is_match(line) == input.invert {
+                continue;
+            }
+            if matches.len() >= input.max_matches {
+                return true;
+            }
+            matches.push(Match {
+                path: path.map(String::from),
+                line_number: i + 1,
+                line: line.to_string(),
+                before: lines[i.saturating_sub(input.context)..i]
+                    .iter()
+                    .map(|l| l.to_string())
+                    .collect(),
+                after: lines[i + 1..(i + 1 + input.context).min(lines.len())]
+                    .iter()
+                    .map(|l| l.to_string())
+                    .collect(),
+            });
+        }
+        false
+    };
+
+    match (&input.input, roots.is_empty()) {
+        (Some(text), true) => truncated = search(None, text, &mut matches),
+        (None, false) => {
+            'roots: for root in &roots {
+                for entry in walkdir::WalkDir::new(root).follow_links(false) {
+                    let entry = entry.with_context(|| format!("text.grep: cannot walk {root}"))?;
+                    if !entry.file_type().is_file() {
+                        continue;
+                    }
+                    if files_searched >= MAX_FILES {
+                        truncated = true;
+                        break 'roots;
+                    }
+                    files_searched += 1;
+                    let path = entry.path().to_string_lossy().to_string();
+                    // Binary or oversized files are skipped rather than failing the search
+                    let Ok(text) = super::read_text_file(&path) else {
+                        continue;
+                    };
+                    if search(Some(&path), &text, &mut matches) {
+                        truncated = true;
+                        break 'roots;
+                    }
+                }
+            }
+        }
+        _ => anyhow::bail!("text.grep: provide either 'input' or 'path'/'paths'"),
+    }
+
+    let output = Output {
+        count: matches.len(),
+        files_searched,
+        truncated,
+        matches,
+    };
+    serde_json::to_vec(&output).context("text.grep: failed to serialise output")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn grep(input: Value) -> Value {
+        serde_json::from_slice(&execute(&serde_json::to_vec(&input).unwrap()).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_grep_string_with_context() {
+        let out = grep(json!({
+            "pattern": "error",
+            "input": "ok 1\nERROR: disk\nok 2\nok 3\nerror: net",
+            "ignore_case": true,
+            "context": 1,
+        }));
+        assert_eq!(out["count"], 2);
+        assert_eq!(out["matches"][0]["line_number"], 2);
+        assert_eq!(out["matches"][0]["before"], json!(["ok 1"]));
+        assert_eq!(out["matches"][0]["after"], json!(["ok 2"]));
+        assert!(out["matches"][1].get("after").is_none());
+    }
+
+    #[test]
+    fn test_grep_directory_and_limits() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.log"), "x=1\ny=2\nx=3\n").unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        std::fs::write(dir.path().join("sub/b.log"), "x=4\n").unwrap();
+        std::fs::write(dir.path().join("blob.bin"), [0xffu8, 0xfe]).unwrap();
+
+        let out = grep(json!({"pattern": "^x=", "path": dir.path()}));
+        assert_eq!(out["count"], 3);
+        assert_eq!(out["files_searched"], 3);
+        assert_eq!(out["truncated"], false);
+
+        let out = grep(json!({"pattern": "^x=", "path": dir.path(), "max_matches": 1}));
+        assert_eq!(out["count"], 1);
+        assert_eq!(out["truncated"], true);
+
+        let out = grep(json!({"pattern": "^x=", "input": "x=1\ny=2", "invert": true}));
+        assert_eq!(out["matches"][0]["line"], "y=2");
+    }
+
+    #[test]
+    fn test_grep_invalid_pattern() {
+        let input = json!({"pattern": "(", "input": "x"});
+        assert!(execute(&serde_json::to_vec(&input).unwrap()).is_err());
+    }
+}
@@ -0,0 +1,153 @@
+//! Text-processing tools — grep, split, diff, and count.
+//!
+//! Each submodule exposes `pub fn execute(input: &[u8]) -> Result<Vec<u8>>`.
+//! These are cheap, local alternatives to shell pipelines run through
+//! process.spawn.
+
+pub mod count;
+pub mod diff;
+pub mod grep;
+pub mod split;
+
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+
+use crate::registry::{make_tool, with_input_schema, Registry};
+
+/// Largest file the text tools will load
+const MAX_TEXT_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Read text from the inline field `text_key` or the file named by `path_key`.
+pub(crate) fn load_text(input: &Value, text_key: &str, path_key: &str) -> Result<String> {
+    match (
+        input.get(text_key).and_then(|t| t.as_str()),
+        input.get(path_key).and_then(|p| p.as_str()),
+    ) {
+        (Some(text), None) => Ok(text.to_string()),
+        (None, Some(path)) => read_text_file(path),
+        _ => anyhow::bail!("provide exactly one of '{text_key}' or '{path_key}'"),
+    }
+}
+
+/// Read a UTF-8 file, refusing files larger than `MAX_TEXT_BYTES`.
+pub(crate) fn read_text_file(path: &str) -> Result<String> {
+    let size = std::fs::metadata(path)
+        .with_context(|| format!("failed to stat {path}"))?
+        .len();
+    if size > MAX_TEXT_BYTES {
+        anyhow::bail!("{path} is {size} bytes, larger than the {MAX_TEXT_BYTES} byte text limit");
+    }
+    std::fs::read_to_string(path).with_context(|| format!("failed to read {path} as UTF-8 text"))
+}
+
+/// Register every text tool with the registry.
+pub fn register_tools(reg: &mut Registry) {
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "text.grep",
+            "text",
+            "Search files, directories ('path'/'paths', recursive) or a string ('input') with a regex ('pattern'). Options: context lines, ignore_case, invert, max_matches.",
+            vec!["fs.read"],
+            "low",
+            true,
+            false,
+            30000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "pattern": { "type": "string", "description": "Regular expression" },
+                "input": {
+                    "type": "string",
+                    "description": "Inline text to search instead of files"
+                },
+                "path": { "type": "string" },
+                "paths": { "type": "array", "items": { "type": "string" } },
+                "context": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "description": "Lines before and after each match"
+                },
+                "ignore_case": { "type": "boolean" },
+                "invert": { "type": "boolean", "description": "Report lines that do not match" },
+                "max_matches": { "type": "integer", "minimum": 1, "default": 200 }
+            },
+            "required": ["pattern"]
+        }),
+    ));
+
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "text.split",
+            "text",
+            "Split a string or file into parts by a literal 'separator' (default newline) or a regex 'pattern', with optional trim, skip_empty, and limit",
+            vec!["fs.read"],
+            "low",
+            true,
+            false,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "input": { "type": "string", "description": "Inline text; or give path" },
+                "path": { "type": "string" },
+                "separator": { "type": "string", "default": "\n" },
+                "pattern": {
+                    "type": "string",
+                    "description": "Regular expression to split on instead of separator"
+                },
+                "limit": { "type": "integer", "minimum": 1, "description": "Most parts" },
+                "trim": { "type": "boolean" },
+                "skip_empty": { "type": "boolean" }
+            }
+        }),
+    ));
+
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "text.diff",
+            "text",
+            "Unified line diff between 'old'/'old_path' and 'new'/'new_path', with insertion and deletion counts",
+            vec!["fs.read"],
+            "low",
+            true,
+            false,
+            10000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "old": { "type": "string" },
+                "old_path": { "type": "string" },
+                "new": { "type": "string" },
+                "new_path": { "type": "string" },
+                "context": { "type": "integer", "minimum": 0, "default": 3 }
+            }
+        }),
+    ));
+
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "text.count",
+            "text",
+            "Count lines, words, characters, and bytes of a string or file, plus matches of an optional regex 'pattern'",
+            vec!["fs.read"],
+            "low",
+            true,
+            false,
+            5000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "input": { "type": "string", "description": "Inline text; or give path" },
+                "path": { "type": "string" },
+                "pattern": {
+                    "type": "string",
+                    "description": "Also count matches of this regular expression"
+                }
+            }
+        }),
+    ));
+}
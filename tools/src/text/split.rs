@@ -0,0 +1,87 @@
+//! text.split — Split text by a literal separator or regex
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Serialize)]
+struct Output {
+    count: usize,
+    parts: Vec<String>,
+}
+
+pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
+    let input: Value = serde_json::from_slice(input).context("text.split: invalid JSON input")?;
+    let text = super::load_text(&input, "input", "path").context("text.split: no input")?;
+    let trim = input.get("trim").and_then(|t| t.as_bool()).unwrap_or(false);
+    let skip_empty = input
+        .get("skip_empty")
+        .and_then(|s| s.as_bool())
+        .unwrap_or(false);
+    let limit = input
+        .get("limit")
+        .and_then(|l| l.as_u64())
+        .map(|l| l as usize);
+
+    let raw: Vec<&str> = match (
+        input.get("pattern").and_then(|p| p.as_str()),
+        input.get("separator").and_then(|s| s.as_str()),
+    ) {
+        (Some(pattern), None) => {
+            let regex = Regex::new(pattern)
+                .with_context(|| format!("text.split: invalid pattern '{pattern}'"))?;
+            match limit {
+                Some(n) => regex.splitn(&text, n).collect(),
+                None => regex.split(&text).collect(),
+            }
+        }
+        (None, separator) => {
+            let separator = separator.unwrap_or("\n");
+            if separator.is_empty() {
+                anyhow::bail!("text.split: 'separator' must not be empty");
+            }
+            match limit {
+                Some(n) => text.splitn(n, separator).collect(),
+                None => text.split(separator).collect(),
+            }
+        }
+        (Some(_), Some(_)) => anyhow::bail!("text.split: use either 'pattern' or 'separator'"),
+    };
+
+    let parts: Vec<String> = raw
+        .into_iter()
+        .map(|p| if trim { p.trim() } else { p })
+        .filter(|p| !skip_empty || !p.is_empty())
+        .map(String::from)
+        .collect();
+
+    let output = Output {
+        count: parts.len(),
+        parts,
+    };
+    serde_json::to_vec(&output).context("text.split: failed to serialise output")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn split(input: Value) -> Value {
+        serde_json::from_slice(&execute(&serde_json::to_vec(&input).unwrap()).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_split_separator_and_pattern() {
+        let out =
+            split(json!({"input": "a, b,, c", "separator": ",", "trim": true, "skip_empty": true}));
+        assert_eq!(out["parts"], json!(["a", "b", "c"]));
+
+        let out = split(json!({"input": "k1=v1  k2=v2\tk3=v3", "pattern": r"\s+", "limit": 2}));
+        assert_eq!(out["parts"], json!(["k1=v1", "k2=v2\tk3=v3"]));
+
+        let out = split(json!({"input": "l1\nl2"}));
+        assert_eq!(out["count"], 2);
+    }
+}
@@ -3,7 +3,8 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::process::Command;
+
+use crate::sandbox::command;
 
 #[derive(Deserialize)]
 struct Input {
@@ -95,7 +96,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
 
     args.push(url.clone());
 
-    let output = Command::new("curl")
+    let output = command("curl")
         .args(&args)
         .output()
         .with_context(|| format!("Failed to call API: {url}"))?;
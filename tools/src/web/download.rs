@@ -1,8 +1,21 @@
 //! web.download — Download files from URLs to local paths
+//!
+//! The file is downloaded to `<destination>.part` and moved into place once
+//! complete. Downloads are resumable: the bytes received are reported as
+//! the progress token, and a call passing the token back as its resume
+//! token continues the partial file with a range request. An interrupted
+//! download keeps its partial file; a failed one (HTTP error) removes it.
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+use std::io::Read;
+use std::process::Stdio;
+use std::time::Duration;
+
+use crate::sandbox::command;
+
+/// How often curl is polled and the size of the partial file reported
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
 
 #[derive(Deserialize)]
 struct Input {
@@ -43,37 +56,79 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
         }
     }
 
+    // Continue the partial file only when asked to; a leftover from an
+    // unrelated download must not be appended to
+    let partial = format!("{}.part", input.destination);
+    let resume =
+        !crate::running::resume_token().is_empty() && std::path::Path::new(&partial).exists();
+    if !resume {
+        let _ = std::fs::remove_file(&partial);
+    }
+
     // Download using curl
-    let output = Command::new("curl")
-        .args([
-            "-s",
-            "-S",
-            "-L",
-            "--max-time",
-            &input.timeout_secs.to_string(),
-            "-o",
-            &input.destination,
-            "-w",
-            "%{http_code}",
-            &input.url,
-        ])
-        .output()
+    let timeout = input.timeout_secs.to_string();
+    let mut args = vec![
+        "-s",
+        "-S",
+        "-L",
+        "--max-time",
+        &timeout,
+        "-o",
+        &partial,
+        "-w",
+        "%{http_code}",
+    ];
+    if resume {
+        args.extend(["-C", "-"]);
+    }
+    args.push(&input.url);
+    let mut child = command("curl")
+        .args(&args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
         .with_context(|| format!("Failed to download from: {}", input.url))?;
 
-    let status_code = String::from_utf8_lossy(&output.stdout)
-        .trim()
-        .parse::<u32>()
-        .unwrap_or(0);
+    // Report the bytes received so far while curl runs
+    let status = loop {
+        report_progress(&partial);
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        std::thread::sleep(PROGRESS_INTERVAL);
+    };
+    report_progress(&partial);
+
+    let mut stdout = String::new();
+    if let Some(mut out) = child.stdout.take() {
+        let _ = out.read_to_string(&mut stdout);
+    }
+    let status_code = stdout.trim().parse::<u32>().unwrap_or(0);
 
-    if !(200..300).contains(&status_code) {
-        // Clean up partial download on failure
-        let _ = std::fs::remove_file(&input.destination);
+    // 416 on a resumed download: the partial file was already complete
+    let complete =
+        (status.success() && (200..300).contains(&status_code)) || (resume && status_code == 416);
+    if !complete {
+        let received = std::fs::metadata(&partial).map(|m| m.len()).unwrap_or(0);
+        if status_code >= 400 || received == 0 {
+            // Clean up partial download on failure
+            let _ = std::fs::remove_file(&partial);
+            anyhow::bail!(
+                "Download failed with HTTP status {}: {}",
+                status_code,
+                input.url
+            );
+        }
+        // Interrupted midway: the partial file stays for a resumed call
         anyhow::bail!(
-            "Download failed with HTTP status {}: {}",
-            status_code,
+            "Download interrupted after {} bytes ({}): {}",
+            received,
+            status,
             input.url
         );
     }
+    std::fs::rename(&partial, &input.destination)
+        .with_context(|| format!("Failed to move download to {}", input.destination))?;
 
     // Get file size
     let size_bytes = std::fs::metadata(&input.destination)
@@ -88,3 +143,10 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
     };
     serde_json::to_vec(&result).context("Failed to serialize output")
 }
+
+/// Report the size of the partial file as the download's progress token
+fn report_progress(partial: &str) {
+    if let Ok(meta) = std::fs::metadata(partial) {
+        crate::running::report_progress(format!("bytes={}", meta.len()));
+    }
+}
@@ -8,62 +8,132 @@ pub mod http_request;
 pub mod scrape;
 pub mod webhook;
 
-use crate::registry::{make_tool, Registry};
+use serde_json::json;
+
+use crate::registry::{make_tool, with_input_schema, Registry};
 
 /// Register every web tool with the registry.
 pub fn register_tools(reg: &mut Registry) {
-    reg.register_tool(make_tool(
-        "web.http_request",
-        "web",
-        "Perform an HTTP request (GET, POST, PUT, DELETE) with custom headers, body, and authentication",
-        vec!["web.http"],
-        "medium",
-        true,
-        false,
-        30000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "web.http_request",
+            "web",
+            "Perform an HTTP request (GET, POST, PUT, DELETE) with custom headers, body, and authentication",
+            vec!["web.http"],
+            "medium",
+            true,
+            false,
+            30000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "url": { "type": "string" },
+                "method": { "type": "string", "default": "GET" },
+                "headers": { "type": "object", "additionalProperties": { "type": "string" } },
+                "body": { "type": "string" },
+                "auth_bearer": { "type": "string", "description": "Bearer token" },
+                "timeout_secs": { "type": "integer", "minimum": 1, "default": 15 },
+                "follow_redirects": { "type": "boolean", "default": true }
+            },
+            "required": ["url"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "web.scrape",
-        "web",
-        "Fetch a web page and extract text content or specific elements using CSS selectors",
-        vec!["web.read"],
-        "low",
-        true,
-        false,
-        30000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "web.scrape",
+            "web",
+            "Fetch a web page and extract text content or specific elements using CSS selectors",
+            vec!["web.read"],
+            "low",
+            true,
+            false,
+            30000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "url": { "type": "string" },
+                "selector": {
+                    "type": "string",
+                    "description": "CSS selector of the elements to extract"
+                },
+                "max_length": { "type": "integer", "minimum": 1, "default": 50000 }
+            },
+            "required": ["url"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "web.webhook",
-        "web",
-        "Send a webhook notification to an external URL with a JSON payload",
-        vec!["web.write"],
-        "medium",
-        false,
-        false,
-        15000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "web.webhook",
+            "web",
+            "Send a webhook notification to an external URL with a JSON payload",
+            vec!["web.write"],
+            "medium",
+            false,
+            false,
+            15000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "url": { "type": "string" },
+                "payload": { "description": "JSON body" },
+                "headers": { "type": "object", "additionalProperties": { "type": "string" } },
+                "secret": { "type": "string", "description": "Sent in the X-Webhook-Secret header" }
+            },
+            "required": ["url"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "web.download",
-        "web",
-        "Download a file from a URL and save it to a local path",
-        vec!["web.http", "fs.write"],
-        "medium",
-        false,
-        true,
-        120000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "web.download",
+            "web",
+            "Download a file from a URL and save it to a local path; an interrupted download resumes where it stopped",
+            vec!["web.http", "fs.write"],
+            "medium",
+            false,
+            true,
+            120000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "url": { "type": "string" },
+                "destination": { "type": "string" },
+                "create_dirs": { "type": "boolean", "default": true },
+                "timeout_secs": { "type": "integer", "minimum": 1, "default": 120 }
+            },
+            "required": ["url", "destination"]
+        }),
     ));
 
-    reg.register_tool(make_tool(
-        "web.api_call",
-        "web",
-        "Call an external REST API with structured request and parse JSON response",
-        vec!["web.http"],
-        "medium",
-        true,
-        false,
-        30000,
+    reg.register_tool(with_input_schema(
+        make_tool(
+            "web.api_call",
+            "web",
+            "Call an external REST API with structured request and parse JSON response",
+            vec!["web.http"],
+            "medium",
+            true,
+            false,
+            30000,
+        ),
+        json!({
+            "type": "object",
+            "properties": {
+                "url": { "type": "string" },
+                "method": { "type": "string", "default": "GET" },
+                "headers": { "type": "object", "additionalProperties": { "type": "string" } },
+                "body": { "description": "JSON body" },
+                "query_params": { "type": "object", "additionalProperties": { "type": "string" } },
+                "auth_bearer": { "type": "string", "description": "Bearer token" },
+                "timeout_secs": { "type": "integer", "minimum": 1, "default": 30 }
+            },
+            "required": ["url"]
+        }),
     ));
 }
@@ -2,7 +2,8 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+
+use crate::sandbox::command;
 
 #[derive(Deserialize)]
 struct Input {
@@ -32,7 +33,7 @@ pub fn execute(input: &[u8]) -> Result<Vec<u8>> {
     let input: Input = serde_json::from_slice(input).context("Invalid JSON input")?;
 
     // Fetch the page with curl
-    let output = Command::new("curl")
+    let output = command("curl")
         .args(["-s", "-S", "-L", "--max-time", "15", &input.url])
         .output()
         .with_context(|| format!("Failed to fetch URL: {}", input.url))?;